@@ -20,6 +20,7 @@ import (
 	"net/http"
 	"os"
 	"os/signal"
+	"strconv"
 	"sync"
 	"syscall"
 	"time"
@@ -35,6 +36,9 @@ type Config struct {
 	TimestampTolerance int64 // milliseconds
 	HeartbeatInterval  time.Duration
 	ConnectionTimeout  time.Duration
+	// TrustUpstream accepts clients without the HMAC handshake, for a proxy that is only
+	// reachable through an authenticating reverse proxy
+	TrustUpstream      bool
 }
 
 // Connection represents a WebSocket connection
@@ -69,10 +73,14 @@ type Server struct {
 	subscriptions    map[string]map[int]bool // symbol -> client IDs
 	symbolData       map[string]Message
 	connectionIDCtr  int
+	pendingHTTP      map[int]chan Message // request_id -> HTTP klines request waiting on MT5
 	mu               sync.RWMutex
 	upgrader         websocket.Upgrader
 }
 
+// httpKlinesTimeout bounds how long an HTTP klines request waits for MT5
+const httpKlinesTimeout = 30 * time.Second
+
 var (
 	config Config
 	server *Server
@@ -84,6 +92,7 @@ func main() {
 	flag.StringVar(&config.APIKey, "key", getEnvOrDefault("API_KEY", "your_api_key"), "API key")
 	flag.StringVar(&config.APISecret, "secret", getEnvOrDefault("API_SECRET", "your_secret"), "API secret")
 	flag.Int64Var(&config.TimestampTolerance, "tolerance", 30000, "Timestamp tolerance (ms)")
+	flag.BoolVar(&config.TrustUpstream, "trust-upstream", false, "Treat clients as authenticated on connect (only behind an authenticating reverse proxy)")
 	flag.Parse()
 
 	config.HeartbeatInterval = 30 * time.Second
@@ -96,6 +105,7 @@ func main() {
 		clientConns:    make(map[int]*Connection),
 		subscriptions:  make(map[string]map[int]bool),
 		symbolData:     make(map[string]Message),
+		pendingHTTP:    make(map[int]chan Message),
 		upgrader: websocket.Upgrader{
 			CheckOrigin: func(r *http.Request) bool { return true },
 		},
@@ -104,6 +114,9 @@ func main() {
 	// HTTP handlers
 	http.HandleFunc("/mt5", server.handleMT5)
 	http.HandleFunc("/client", server.handleClient)
+	// Read-only fallback for networks that block WebSocket upgrades
+	http.HandleFunc("/api/symbols", server.handleHTTPSymbols)
+	http.HandleFunc("/api/klines", server.handleHTTPKlines)
 	http.HandleFunc("/", func(w http.ResponseWriter, r *http.Request) {
 		fmt.Fprintf(w, "MT5 Proxy Server Running\n")
 	})
@@ -128,6 +141,7 @@ func main() {
 	log.Printf("Port: %d\n", config.Port)
 	log.Printf("MT5 Endpoint: ws://localhost:%d/mt5\n", config.Port)
 	log.Printf("Client Endpoint: ws://localhost:%d/client\n", config.Port)
+	log.Printf("HTTP Fallback: http://localhost:%d/api/symbols, /api/klines\n", config.Port)
 	log.Printf("API Key: %s****\n", config.APIKey[:min(4, len(config.APIKey))])
 	log.Println("========================================")
 
@@ -215,7 +229,7 @@ func (s *Server) handleClient(w http.ResponseWriter, r *http.Request) {
 		ID:            connID,
 		WS:            ws,
 		IP:            r.RemoteAddr,
-		Authenticated: false,
+		Authenticated: s.config.TrustUpstream,
 		LastActivity:  time.Now(),
 		Subscriptions: make(map[string]bool),
 	}
@@ -267,7 +281,7 @@ func (s *Server) handleMT5Message(conn *Connection, rawMsg []byte) {
 	switch msgType {
 	case "auth":
 		s.handleMT5Auth(conn, msg)
-	case "trade", "depth", "kline":
+	case "trade", "depth", "kline", "session":
 		if conn.Authenticated {
 			s.forwardMarketData(msg)
 		}
@@ -320,11 +334,22 @@ func (s *Server) handleClientMessage(conn *Connection, rawMsg []byte) {
 		if conn.Authenticated {
 			s.handleGetKlines(conn, msg)
 		}
+	case "set_chart_symbol":
+		if conn.Authenticated {
+			s.handleSetChartSymbol(conn, msg)
+		}
 	case "ping":
 		s.sendTo(conn.WS, Message{"type": "pong", "time": time.Now().UnixMilli()})
 	default:
 		if !conn.Authenticated {
 			s.sendTo(conn.WS, Message{"type": "error", "message": "Not authenticated"})
+		} else {
+			// Lets clients tell optional requests (e.g. get_depth_history) apart from failures
+			s.sendTo(conn.WS, Message{
+				"type":    "error",
+				"code":    "unsupported_request",
+				"message": fmt.Sprintf("Unsupported request: %s", msgType),
+			})
 		}
 	}
 }
@@ -367,9 +392,10 @@ func (s *Server) handleClientAuth(conn *Connection, msg Message) {
 		conn.Authenticated = true
 		log.Printf("[Client] Authenticated (id: %d)\n", conn.ID)
 		s.sendTo(conn.WS, Message{
-			"type":        "auth_response",
-			"success":     true,
-			"server_time": time.Now().UnixMilli(),
+			"type":         "auth_response",
+			"success":      true,
+			"server_time":  time.Now().UnixMilli(),
+			"capabilities": []string{"subscribe_ack"},
 		})
 	} else {
 		log.Printf("[Client] Auth failed (id: %d)\n", conn.ID)
@@ -420,13 +446,33 @@ func (s *Server) computeSignature(apiKey string, timestamp int64) string {
 // handleSubscribe handles subscription request
 func (s *Server) handleSubscribe(conn *Connection, msg Message) {
 	symbols, _ := msg["symbols"].([]interface{})
+	channels, _ := msg["channels"].([]interface{})
+
+	accepted := []Message{}
+	rejected := []Message{}
 
 	s.mu.Lock()
+	known := s.knownSymbolsLocked()
 	for _, sym := range symbols {
 		symbol, _ := sym.(string)
 		if symbol == "" {
 			continue
 		}
+		if known != nil && !known[symbol] {
+			s.sendTo(conn.WS, Message{
+				"type":    "error",
+				"code":    "symbol_not_found",
+				"symbol":  symbol,
+				"message": "Symbol not found: " + symbol,
+			})
+			rejected = append(rejected, Message{
+				"symbol": symbol,
+				"code":   "symbol_not_found",
+				"reason": "Symbol not found: " + symbol,
+			})
+			continue
+		}
+		accepted = append(accepted, Message{"symbol": symbol, "channels": channels})
 		conn.Subscriptions[symbol] = true
 		if s.subscriptions[symbol] == nil {
 			s.subscriptions[symbol] = make(map[int]bool)
@@ -441,6 +487,30 @@ func (s *Server) handleSubscribe(conn *Connection, msg Message) {
 		"type":    "subscribed",
 		"symbols": symbols,
 	})
+	// Tells the client which symbols it will get data for, and why the others won't
+	s.sendTo(conn.WS, Message{
+		"type":     "subscribe_response",
+		"accepted": accepted,
+		"rejected": rejected,
+	})
+}
+
+// knownSymbolsLocked returns the symbols reported by authenticated MT5 terminals,
+// or nil if none have reported yet. Caller must hold s.mu.
+func (s *Server) knownSymbolsLocked() map[string]bool {
+	var known map[string]bool
+	for _, mt5Conn := range s.mt5Connections {
+		if !mt5Conn.Authenticated || len(mt5Conn.Symbols) == 0 {
+			continue
+		}
+		if known == nil {
+			known = make(map[string]bool)
+		}
+		for _, sym := range mt5Conn.Symbols {
+			known[sym.Symbol] = true
+		}
+	}
+	return known
 }
 
 // handleUnsubscribe handles unsubscription request
@@ -517,6 +587,162 @@ func (s *Server) handleGetKlines(conn *Connection, msg Message) {
 	s.sendTo(mt5Conn.WS, msg)
 }
 
+// handleSetChartSymbol forwards a chart switch to the terminal listing the symbol, or to the
+// first one when none lists it. Nothing is sent back, the client doesn't wait for an answer.
+func (s *Server) handleSetChartSymbol(conn *Connection, msg Message) {
+	symbol, _ := msg["symbol"].(string)
+	if symbol == "" {
+		return
+	}
+
+	s.mu.RLock()
+	var mt5Conn *Connection
+	for _, c := range s.mt5Connections {
+		if !c.Authenticated {
+			continue
+		}
+		if mt5Conn == nil {
+			mt5Conn = c
+		}
+		if hasSymbol(c.Symbols, symbol) {
+			mt5Conn = c
+			break
+		}
+	}
+	s.mu.RUnlock()
+
+	if mt5Conn == nil {
+		log.Printf("[Client] Chart switch to %s dropped: No MT5 connection available\n", symbol)
+		return
+	}
+
+	forward := Message{"type": "set_chart_symbol", "symbol": symbol}
+	// Left out, the chart keeps its period
+	if timeframe, ok := msg["timeframe"].(string); ok {
+		forward["timeframe"] = timeframe
+	}
+
+	log.Printf("[Client] Forwarding chart switch to %s, client_id=%d\n", symbol, conn.ID)
+	s.sendTo(mt5Conn.WS, forward)
+}
+
+func hasSymbol(symbols []SymbolInfo, symbol string) bool {
+	for _, sym := range symbols {
+		if sym.Symbol == symbol {
+			return true
+		}
+	}
+	return false
+}
+
+// authorizeHTTP checks the HMAC handshake carried in X-API-Key, X-Timestamp and X-Signature
+func (s *Server) authorizeHTTP(w http.ResponseWriter, r *http.Request) bool {
+	if r.Method != http.MethodGet {
+		http.Error(w, "method not allowed", http.StatusMethodNotAllowed)
+		return false
+	}
+	if s.config.TrustUpstream {
+		return true
+	}
+
+	timestamp, _ := strconv.ParseInt(r.Header.Get("X-Timestamp"), 10, 64)
+	if !s.validateAuth(r.Header.Get("X-API-Key"), timestamp, r.Header.Get("X-Signature")) {
+		log.Printf("[HTTP] Authentication failed from %s\n", r.RemoteAddr)
+		http.Error(w, "authentication failed", http.StatusUnauthorized)
+		return false
+	}
+	return true
+}
+
+func writeJSON(w http.ResponseWriter, status int, msg Message) {
+	w.Header().Set("Content-Type", "application/json")
+	w.WriteHeader(status)
+	json.NewEncoder(w).Encode(msg)
+}
+
+// handleHTTPSymbols serves the same body as a get_symbols socket request
+func (s *Server) handleHTTPSymbols(w http.ResponseWriter, r *http.Request) {
+	if !s.authorizeHTTP(w, r) {
+		return
+	}
+
+	var allSymbols []SymbolInfo
+
+	s.mu.RLock()
+	for _, mt5Conn := range s.mt5Connections {
+		if mt5Conn.Authenticated {
+			allSymbols = append(allSymbols, mt5Conn.Symbols...)
+		}
+	}
+	s.mu.RUnlock()
+
+	writeJSON(w, http.StatusOK, Message{"type": "symbols", "data": allSymbols})
+}
+
+// handleHTTPKlines forwards a get_klines request built from the query to MT5 and waits for it
+func (s *Server) handleHTTPKlines(w http.ResponseWriter, r *http.Request) {
+	if !s.authorizeHTTP(w, r) {
+		return
+	}
+
+	query := r.URL.Query()
+	msg := Message{
+		"type":      "get_klines",
+		"symbol":    query.Get("symbol"),
+		"timeframe": query.Get("timeframe"),
+	}
+	for _, key := range []string{"limit", "start", "end"} {
+		if value, err := strconv.ParseInt(query.Get(key), 10, 64); err == nil {
+			msg[key] = float64(value)
+		}
+	}
+	log.Printf("[HTTP] Klines request: symbol=%s, timeframe=%s\n", msg["symbol"], msg["timeframe"])
+
+	s.mu.Lock()
+	var mt5Conn *Connection
+	for _, c := range s.mt5Connections {
+		if c.Authenticated {
+			mt5Conn = c
+			break
+		}
+	}
+	if mt5Conn == nil {
+		s.mu.Unlock()
+		writeJSON(w, http.StatusServiceUnavailable, Message{
+			"type":    "error",
+			"message": "No MT5 connection available",
+		})
+		return
+	}
+	// Shares the counter with connections, so the id can't collide with a socket client's
+	s.connectionIDCtr++
+	requestID := s.connectionIDCtr
+	response := make(chan Message, 1)
+	s.pendingHTTP[requestID] = response
+	s.mu.Unlock()
+
+	defer func() {
+		s.mu.Lock()
+		delete(s.pendingHTTP, requestID)
+		s.mu.Unlock()
+	}()
+
+	msg["request_id"] = float64(requestID)
+	s.sendTo(mt5Conn.WS, msg)
+
+	select {
+	case klines := <-response:
+		writeJSON(w, http.StatusOK, klines)
+	case <-time.After(httpKlinesTimeout):
+		log.Printf("[HTTP] Klines request %d timed out\n", requestID)
+		writeJSON(w, http.StatusGatewayTimeout, Message{
+			"type":    "error",
+			"message": "MT5 did not answer in time",
+		})
+	case <-r.Context().Done():
+	}
+}
+
 // forwardMarketData forwards market data to subscribed clients
 func (s *Server) forwardMarketData(msg Message) {
 	symbol, _ := msg["symbol"].(string)
@@ -555,9 +781,20 @@ func (s *Server) forwardKlinesResponse(msg Message) {
 	log.Printf("[MT5] Klines response received, request_id=%d\n", int(requestID))
 
 	s.mu.RLock()
+	pending, isHTTP := s.pendingHTTP[int(requestID)]
 	client, ok := s.clientConns[int(requestID)]
 	s.mu.RUnlock()
 
+	if isHTTP {
+		delete(msg, "request_id")
+		// Buffered for one answer, a duplicate must not stall MT5's read loop
+		select {
+		case pending <- msg:
+		default:
+		}
+		return
+	}
+
 	if !ok {
 		log.Printf("[MT5] Client not found for request_id=%d (client may have disconnected)\n", int(requestID))
 		return
@@ -623,7 +860,13 @@ func (s *Server) shutdown() {
 	for _, conn := range s.mt5Connections {
 		conn.WS.Close()
 	}
+	// Tell clients the proxy is going away on purpose, so they reconnect with their usual backoff
+	// instead of treating it as a network failure
+	notice := Message{"type": "shutdown", "reason": "maintenance"}
+	closeFrame := websocket.FormatCloseMessage(websocket.CloseServiceRestart, "maintenance")
 	for _, conn := range s.clientConns {
+		s.sendTo(conn.WS, notice)
+		conn.WS.WriteControl(websocket.CloseMessage, closeFrame, time.Now().Add(time.Second))
 		conn.WS.Close()
 	}
 }
@@ -1,3 +1,4 @@
+pub mod sweep;
 pub mod ticks;
 pub mod time;
 
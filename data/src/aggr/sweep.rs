@@ -0,0 +1,202 @@
+//! Rapid same side prints folded into sweeps.
+//!
+//! At a news spike one aggressive order walking the book prints as hundreds of small trades. A
+//! print joins the open sweep while it's on the same side, arrives within the window of the
+//! previous print and doesn't step back against the sweep's direction. Raw trades are never held
+//! back, a sweep is only reported once the next print or the clock shows it's over, so it lags
+//! its last print by at most the window.
+
+use exchange::Trade;
+use exchange::util::Price;
+
+/// Gap between two prints of one sweep
+pub const DEFAULT_WINDOW_MS: u16 = 50;
+
+pub const WINDOW_RANGE_MS: std::ops::RangeInclusive<u16> = 10..=500;
+
+/// Consecutive prints of one aggressor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweepEvent {
+    pub is_sell: bool,
+    /// Price of the first print
+    pub price_from: Price,
+    /// Price of the last print, where the sweep got to
+    pub price_to: Price,
+    pub total_qty: f32,
+    pub count: usize,
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+impl SweepEvent {
+    fn new(trade: &Trade) -> Self {
+        Self {
+            is_sell: trade.is_sell,
+            price_from: trade.price,
+            price_to: trade.price,
+            total_qty: trade.qty,
+            count: 1,
+            start_ms: trade.time,
+            end_ms: trade.time,
+        }
+    }
+
+    /// Whether `trade` continues this sweep
+    pub fn extends(&self, trade: &Trade, window_ms: u16) -> bool {
+        let moves_along = if self.is_sell {
+            trade.price <= self.price_to
+        } else {
+            trade.price >= self.price_to
+        };
+
+        trade.is_sell == self.is_sell
+            && moves_along
+            && trade.time >= self.end_ms
+            && trade.time - self.end_ms <= u64::from(window_ms)
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        self.price_to = trade.price;
+        self.total_qty += trade.qty;
+        self.count += 1;
+        self.end_ms = trade.time;
+    }
+
+    /// Whether it's more than a single print
+    pub fn is_sweep(&self) -> bool {
+        self.count > 1
+    }
+}
+
+/// Folds a trade stream into sweeps one print at a time
+#[derive(Debug, Clone)]
+pub struct SweepAggregator {
+    window_ms: u16,
+    open: Option<SweepEvent>,
+}
+
+impl SweepAggregator {
+    pub fn new(window_ms: u16) -> Self {
+        Self {
+            window_ms,
+            open: None,
+        }
+    }
+
+    pub fn window_ms(&self) -> u16 {
+        self.window_ms
+    }
+
+    /// Adds a print, returning the sweep it ended
+    pub fn push(&mut self, trade: &Trade) -> Option<SweepEvent> {
+        match &mut self.open {
+            Some(open) if open.extends(trade, self.window_ms) => {
+                open.add(trade);
+                None
+            }
+            open => open.replace(SweepEvent::new(trade)),
+        }
+    }
+
+    /// Ends the open sweep once `now_ms` is past its window
+    pub fn flush(&mut self, now_ms: u64) -> Option<SweepEvent> {
+        self.open
+            .take_if(|open| now_ms.saturating_sub(open.end_ms) > u64::from(self.window_ms))
+    }
+
+    /// The sweep still taking prints
+    pub fn open(&self) -> Option<&SweepEvent> {
+        self.open.as_ref()
+    }
+
+    /// Ends the open sweep regardless of the clock
+    pub fn finish(&mut self) -> Option<SweepEvent> {
+        self.open.take()
+    }
+}
+
+/// Sweeps of a batch of trades, the last one ended by the end of the batch
+pub fn aggregate(trades: &[Trade], window_ms: u16) -> Vec<SweepEvent> {
+    let mut aggregator = SweepAggregator::new(window_ms);
+    let mut sweeps: Vec<SweepEvent> = trades
+        .iter()
+        .filter_map(|trade| aggregator.push(trade))
+        .collect();
+
+    sweeps.extend(aggregator.finish());
+    sweeps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(time: u64, price: f32, qty: f32, is_sell: bool) -> Trade {
+        Trade {
+            time,
+            is_sell,
+            price: Price::from_f32(price),
+            qty,
+        }
+    }
+
+    #[test]
+    fn window_boundary_splits_sweeps() {
+        let trades = [
+            trade(0, 100.0, 1.0, false),
+            trade(30, 100.5, 2.0, false),
+            // Exactly one window after the previous print still belongs to it
+            trade(80, 101.0, 3.0, false),
+            // One past the window starts over
+            trade(131, 101.0, 4.0, false),
+        ];
+
+        let sweeps = aggregate(&trades, DEFAULT_WINDOW_MS);
+        assert_eq!(sweeps.len(), 2);
+
+        let first = sweeps[0];
+        assert_eq!(first.count, 3);
+        assert_eq!(first.total_qty, 6.0);
+        assert_eq!(first.price_from, Price::from_f32(100.0));
+        assert_eq!(first.price_to, Price::from_f32(101.0));
+        assert_eq!((first.start_ms, first.end_ms), (0, 80));
+
+        assert_eq!(sweeps[1].count, 1);
+        assert!(!sweeps[1].is_sweep());
+    }
+
+    #[test]
+    fn mixed_sides_interleave_into_separate_sweeps() {
+        let trades = [
+            trade(0, 100.0, 1.0, false),
+            trade(5, 99.9, 1.0, true),
+            trade(10, 100.1, 1.0, false),
+            trade(15, 100.2, 1.0, false),
+            trade(20, 99.8, 1.0, true),
+            trade(25, 99.7, 1.0, true),
+            // Buying back below the sweep's last price is someone else
+            trade(26, 99.8, 1.0, true),
+        ];
+
+        let sweeps = aggregate(&trades, DEFAULT_WINDOW_MS);
+        let shape: Vec<(bool, usize)> = sweeps.iter().map(|s| (s.is_sell, s.count)).collect();
+        assert_eq!(
+            shape,
+            [(false, 1), (true, 1), (false, 2), (true, 2), (true, 1)]
+        );
+    }
+
+    #[test]
+    fn open_sweep_ends_once_the_window_passes() {
+        let mut aggregator = SweepAggregator::new(DEFAULT_WINDOW_MS);
+        assert_eq!(aggregator.push(&trade(1_000, 10.0, 1.0, true)), None);
+        assert_eq!(aggregator.push(&trade(1_020, 9.9, 1.0, true)), None);
+
+        assert_eq!(aggregator.flush(1_070), None);
+        assert_eq!(aggregator.open().map(|s| s.count), Some(2));
+
+        let ended = aggregator.flush(1_071).unwrap();
+        assert_eq!((ended.count, ended.total_qty), (2, 2.0));
+        assert!(aggregator.open().is_none());
+    }
+}
@@ -160,6 +160,16 @@ impl TickAggr {
         self.update_poc_status();
     }
 
+    /// Drops the oldest bars beyond `max_bars`, never the one `keep_from` bars back from the
+    /// latest or any newer. Returns how many were dropped.
+    pub fn prune(&mut self, max_bars: usize, keep_from: Option<usize>) -> usize {
+        let keep = keep_from.map_or(max_bars, |back| max_bars.max(back.saturating_add(1)));
+        let excess = self.datapoints.len().saturating_sub(keep);
+
+        self.datapoints.drain(..excess);
+        excess
+    }
+
     pub fn update_poc_status(&mut self) {
         let updates = self
             .datapoints
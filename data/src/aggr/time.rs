@@ -3,6 +3,8 @@ use std::collections::BTreeMap;
 use crate::chart::Basis;
 use crate::chart::heatmap::HeatmapDataPoint;
 use crate::chart::kline::{ClusterKind, KlineDataPoint, KlineTrades, NPoc};
+use crate::chart::revision::{Revision, RevisionSettings};
+use crate::chart::session::SessionBreak;
 
 use exchange::util::{Price, PriceStep};
 use exchange::{Kline, Timeframe, Trade};
@@ -29,6 +31,77 @@ pub struct TimeSeries<D: DataPoint> {
     pub datapoints: BTreeMap<u64, D>,
     pub interval: Timeframe,
     pub tick_size: PriceStep,
+    /// Session calendar the bars are checked against, kline charts only
+    pub session_break: Option<SessionBreak>,
+    /// Bars opened early by ticks after a session break, keyed by their open time
+    opened_early: BTreeMap<u64, EarlyOpen>,
+}
+
+/// The ticks a bar got before its own open time, and the bar the server sent for the rest of it
+#[derive(Debug, Clone, Copy)]
+struct EarlyOpen {
+    ticks: Kline,
+    server: Option<Kline>,
+    /// Time of the last tick folded in
+    through: u64,
+}
+
+impl EarlyOpen {
+    fn new(time: u64, trade: &Trade, server: Option<Kline>) -> Self {
+        let mut ticks = Kline {
+            time,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: (0.0, 0.0),
+        };
+        add_volume(&mut ticks, trade);
+
+        Self {
+            ticks,
+            server,
+            through: trade.time,
+        }
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        // Trades already loaded get fed again with older history, ticks are only ever appended
+        if trade.time < self.through {
+            return;
+        }
+        self.through = trade.time;
+
+        self.ticks.high = self.ticks.high.max(trade.price);
+        self.ticks.low = self.ticks.low.min(trade.price);
+        self.ticks.close = trade.price;
+        add_volume(&mut self.ticks, trade);
+    }
+
+    fn kline(&self) -> Kline {
+        match self.server {
+            Some(server) => Kline {
+                time: server.time,
+                open: self.ticks.open,
+                high: self.ticks.high.max(server.high),
+                low: self.ticks.low.min(server.low),
+                close: server.close,
+                volume: (
+                    self.ticks.volume.0 + server.volume.0,
+                    self.ticks.volume.1 + server.volume.1,
+                ),
+            },
+            None => self.ticks,
+        }
+    }
+}
+
+fn add_volume(kline: &mut Kline, trade: &Trade) {
+    if trade.is_sell {
+        kline.volume.1 += trade.qty;
+    } else {
+        kline.volume.0 += trade.qty;
+    }
 }
 
 impl<D: DataPoint> TimeSeries<D> {
@@ -166,6 +239,8 @@ impl TimeSeries<KlineDataPoint> {
             datapoints: BTreeMap::new(),
             interval,
             tick_size,
+            session_break: None,
+            opened_early: BTreeMap::new(),
         };
 
         timeseries.insert_klines(klines);
@@ -177,6 +252,8 @@ impl TimeSeries<KlineDataPoint> {
             datapoints: self.datapoints.clone(),
             interval: self.interval,
             tick_size: self.tick_size,
+            session_break: self.session_break,
+            opened_early: self.opened_early.clone(),
         };
 
         new_series.insert_trades_or_create_bucket(trades);
@@ -185,33 +262,143 @@ impl TimeSeries<KlineDataPoint> {
 
     pub fn insert_klines(&mut self, klines: &[Kline]) {
         for kline in klines {
+            // The server's bar runs past the break, what's kept of it ends there
+            if self.is_split(kline.time) && self.datapoints.contains_key(&kline.time) {
+                continue;
+            }
+
+            let kline = match self.opened_early.get_mut(&kline.time) {
+                Some(early) => {
+                    early.server = Some(*kline);
+                    early.kline()
+                }
+                None => *kline,
+            };
+
             let entry = self
                 .datapoints
                 .entry(kline.time)
                 .or_insert_with(|| KlineDataPoint {
-                    kline: *kline,
+                    kline,
                     footprint: KlineTrades::new(),
                 });
 
-            entry.kline = *kline;
+            entry.kline = kline;
         }
 
         self.update_poc_status();
     }
 
+    /// Fetched `klines` that revise bars already held. The latest bar is still forming and bars
+    /// split at a session break differ on purpose, neither is compared.
+    pub fn revisions(&self, klines: &[Kline], settings: &RevisionSettings) -> Vec<Revision> {
+        let forming = self.latest_timestamp();
+
+        klines
+            .iter()
+            .filter(|kline| Some(kline.time) != forming && !self.is_split(kline.time))
+            .filter_map(|fetched| {
+                let held = self.datapoints.get(&fetched.time)?;
+                Revision::of(&held.kline, fetched, settings, self.tick_size)
+            })
+            .collect()
+    }
+
+    /// Drops the oldest bars beyond `max_bars`, never one at or after `keep_from`. Returns how
+    /// many were dropped.
+    pub fn prune(&mut self, max_bars: usize, keep_from: Option<u64>) -> usize {
+        let excess = self.datapoints.len().saturating_sub(max_bars);
+        let Some(&cut) = self.datapoints.keys().nth(excess) else {
+            return 0;
+        };
+        let cut = keep_from.map_or(cut, |keep_from| cut.min(keep_from));
+
+        let before = self.datapoints.len();
+        self.datapoints = self.datapoints.split_off(&cut);
+        self.opened_early = self.opened_early.split_off(&cut);
+
+        before - self.datapoints.len()
+    }
+
+    /// Open time of the bar a tick at `time` goes to. Bars line up with the latest one, as MT5
+    /// brokers open daily bars at their server's midnight rather than UTC's.
+    pub fn bar_time(&self, time: u64) -> u64 {
+        let interval = self.interval.to_milliseconds();
+        let phase = self
+            .latest_timestamp()
+            .map_or(0, |latest| latest % interval);
+        let aligned = time.saturating_sub((time + interval - phase) % interval);
+
+        match self.session_break {
+            Some(session_break) => session_break.bar_time_from(aligned, time, interval),
+            None => aligned,
+        }
+    }
+
+    /// Whether the ticks after a session break inside this bar opened the next one instead
+    fn is_split(&self, time: u64) -> bool {
+        self.opened_early
+            .contains_key(&(time + self.interval.to_milliseconds()))
+    }
+
+    /// Whether the bar holds ticks from both sides of a session break. Anchored charts still
+    /// have those where the ticks after the break weren't seen, like fetched history.
+    pub fn spans_session_break(&self, time: u64) -> bool {
+        let interval = self.interval.to_milliseconds();
+
+        self.session_break
+            .and_then(|session_break| session_break.within(time, interval))
+            .is_some()
+            && !self.is_split(time)
+    }
+
+    /// How long the bar opened at `time` runs, a split one ends at the break
+    pub fn bar_span(&self, time: u64) -> u64 {
+        let interval = self.interval.to_milliseconds();
+
+        match self.session_break {
+            Some(session_break) if self.is_split(time) => session_break
+                .within(time, interval)
+                .map_or(interval, |start| start - time),
+            _ => interval,
+        }
+    }
+
+    /// Folds a tick that came after the break inside the bar before `time` into the bar at
+    /// `time`, opening it if the server hasn't yet
+    fn open_early(&mut self, time: u64, trade: &Trade) {
+        let server = self.datapoints.get(&time).map(|dp| dp.kline);
+        let kline = self
+            .opened_early
+            .entry(time)
+            .and_modify(|early| early.add(trade))
+            .or_insert_with(|| EarlyOpen::new(time, trade, server))
+            .kline();
+
+        self.datapoints
+            .entry(time)
+            .or_insert_with(|| KlineDataPoint {
+                kline,
+                footprint: KlineTrades::new(),
+            })
+            .kline = kline;
+    }
+
     pub fn insert_trades_or_create_bucket(&mut self, buffer: &[Trade]) {
         if buffer.is_empty() {
             return;
         }
-        let aggr_time = self.interval.to_milliseconds();
         let mut updated_times = Vec::new();
 
         buffer.iter().for_each(|trade| {
-            let rounded_time = (trade.time / aggr_time) * aggr_time;
+            let rounded_time = self.bar_time(trade.time);
 
             if !updated_times.contains(&rounded_time) {
                 updated_times.push(rounded_time);
             }
+            if trade.time < rounded_time {
+                self.open_early(rounded_time, trade);
+            }
 
             let entry = self
                 .datapoints
@@ -242,11 +429,13 @@ impl TimeSeries<KlineDataPoint> {
         if buffer.is_empty() {
             return;
         }
-        let aggr_time = self.interval.to_milliseconds();
         let mut updated_times: Vec<u64> = Vec::new();
 
         for trade in buffer {
-            let rounded_time = (trade.time / aggr_time) * aggr_time;
+            let rounded_time = self.bar_time(trade.time);
+            if trade.time < rounded_time {
+                self.open_early(rounded_time, trade);
+            }
 
             if let Some(entry) = self.datapoints.get_mut(&rounded_time) {
                 if !updated_times.contains(&rounded_time) {
@@ -389,6 +578,8 @@ impl TimeSeries<HeatmapDataPoint> {
             datapoints: BTreeMap::new(),
             interval: timeframe,
             tick_size,
+            session_break: None,
+            opened_early: BTreeMap::new(),
         }
     }
 
@@ -428,3 +619,183 @@ impl From<&TimeSeries<KlineDataPoint>> for BTreeMap<u64, (f32, f32)> {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::timezone::UserTimezone;
+
+    const HOUR: u64 = 3_600_000;
+    const BAR: u64 = 20 * HOUR;
+
+    fn kline(time: u64, open: f32, high: f32, low: f32, close: f32) -> Kline {
+        Kline {
+            time,
+            open: Price::from_f32(open),
+            high: Price::from_f32(high),
+            low: Price::from_f32(low),
+            close: Price::from_f32(close),
+            volume: (1.0, 1.0),
+        }
+    }
+
+    fn trade(time: u64, price: f32) -> Trade {
+        Trade {
+            time,
+            is_sell: false,
+            price: Price::from_f32(price),
+            qty: 1.0,
+        }
+    }
+
+    fn series(anchored: bool) -> TimeSeries<KlineDataPoint> {
+        let mut series = TimeSeries::<KlineDataPoint>::new(
+            Timeframe::H4,
+            PriceStep::from_f32(1.0),
+            &[kline(BAR, 100.0, 101.0, 99.0, 100.0)],
+        );
+        series.session_break = Some(SessionBreak {
+            timezone: UserTimezone::Utc,
+            start_hour: 23,
+            anchored,
+        });
+        series
+    }
+
+    #[test]
+    fn trades_join_bars_opened_off_utc_midnight() {
+        const DAY: u64 = 24 * HOUR;
+        // Daily bars of a UTC+2 broker open at 22:00 UTC
+        let open = 10 * DAY - 2 * HOUR;
+        let mut series = TimeSeries::<KlineDataPoint>::new(
+            Timeframe::D1,
+            PriceStep::from_f32(1.0),
+            &[kline(open, 100.0, 101.0, 99.0, 100.0)],
+        );
+
+        assert_eq!(series.bar_time(10 * DAY + HOUR), open);
+        assert_eq!(series.bar_time(open + DAY), open + DAY);
+
+        series.insert_trades_or_create_bucket(&[trade(10 * DAY + HOUR, 100.0)]);
+        assert_eq!(
+            series.datapoints.keys().copied().collect::<Vec<_>>(),
+            [open]
+        );
+    }
+
+    fn held_bars() -> TimeSeries<KlineDataPoint> {
+        TimeSeries::<KlineDataPoint>::new(
+            Timeframe::H4,
+            PriceStep::from_f32(1.0),
+            &[
+                kline(BAR, 100.0, 104.0, 98.0, 102.0),
+                kline(BAR + 4 * HOUR, 102.0, 103.0, 101.0, 101.0),
+                kline(BAR + 8 * HOUR, 101.0, 101.0, 100.0, 100.0),
+            ],
+        )
+    }
+
+    #[test]
+    fn revised_high_and_low_are_reported() {
+        let series = held_bars();
+        let fetched = [
+            kline(BAR, 100.0, 106.0, 97.0, 102.0),
+            kline(BAR + 4 * HOUR, 102.0, 103.0, 101.0, 101.0),
+            // Still forming, not compared
+            kline(BAR + 8 * HOUR, 101.0, 102.0, 99.0, 99.0),
+        ];
+
+        let revisions = series.revisions(&fetched, &RevisionSettings::default());
+        assert_eq!(revisions.len(), 1);
+        assert_eq!(revisions[0].held.high, Price::from_f32(104.0));
+        assert_eq!(revisions[0].fetched.high, Price::from_f32(106.0));
+        assert_eq!(revisions[0].fetched.low, Price::from_f32(97.0));
+
+        let tolerant = RevisionSettings {
+            price_ticks: 2,
+            ..RevisionSettings::default()
+        };
+        assert!(series.revisions(&fetched, &tolerant).is_empty());
+    }
+
+    #[test]
+    fn identical_overlap_reports_nothing() {
+        let series = held_bars();
+        let fetched: Vec<Kline> = series.datapoints.values().map(|dp| dp.kline).collect();
+
+        let settings = RevisionSettings {
+            volume: Some(0.0),
+            ..RevisionSettings::default()
+        };
+        assert!(series.revisions(&fetched, &settings).is_empty());
+        // Bars before what's held don't overlap anything
+        assert!(
+            series
+                .revisions(&[kline(HOUR, 1.0, 2.0, 0.5, 1.5)], &settings)
+                .is_empty()
+        );
+    }
+
+    fn traded(series: &TimeSeries<KlineDataPoint>) -> f32 {
+        series
+            .datapoints
+            .values()
+            .flat_map(|dp| dp.footprint.trades.values())
+            .map(|group| group.buy_qty + group.sell_qty)
+            .sum()
+    }
+
+    #[test]
+    fn anchored_bars_end_at_the_session_break() {
+        let mut series = series(true);
+        let gap = [
+            trade(BAR + 3 * HOUR, 120.0),
+            trade(BAR + 3 * HOUR + 5, 122.0),
+        ];
+
+        series.insert_trades_existing_buckets(&[trade(BAR + HOUR, 100.0)]);
+        series.insert_trades_existing_buckets(&gap);
+
+        // The server's bar still runs across the break, the part before it is kept
+        series.insert_klines(&[kline(BAR, 100.0, 122.0, 99.0, 122.0)]);
+        assert_eq!(series.datapoints[&BAR].kline.high, Price::from_f32(101.0));
+        assert!(!series.spans_session_break(BAR));
+        assert_eq!(series.bar_span(BAR), 3 * HOUR);
+
+        let next = BAR + 4 * HOUR;
+        let opened = series.datapoints[&next].kline;
+        assert_eq!(opened.open, Price::from_f32(120.0));
+        assert_eq!(opened.close, Price::from_f32(122.0));
+
+        // The server's next bar is merged with the ticks that opened it early
+        series.insert_klines(&[kline(next, 123.0, 125.0, 121.0, 124.0)]);
+        let merged = series.datapoints[&next].kline;
+        assert_eq!(merged.open, Price::from_f32(120.0));
+        assert_eq!(merged.low, Price::from_f32(120.0));
+        assert_eq!(merged.high, Price::from_f32(125.0));
+        assert_eq!(merged.close, Price::from_f32(124.0));
+        assert_eq!(merged.volume, (3.0, 1.0));
+
+        // Trades fed again after older history loaded aren't counted twice
+        series.insert_trades_existing_buckets(&gap[..1]);
+        assert_eq!(series.datapoints[&next].kline.volume, (3.0, 1.0));
+    }
+
+    #[test]
+    fn bars_across_the_break_keep_every_tick() {
+        let trades = [trade(BAR + HOUR, 100.0), trade(BAR + 3 * HOUR, 120.0)];
+
+        let mut spanning = series(false);
+        spanning.insert_trades_existing_buckets(&trades);
+
+        assert_eq!(spanning.datapoints.len(), 1);
+        assert_eq!(traded(&spanning), 2.0);
+        assert!(spanning.spans_session_break(BAR));
+        assert_eq!(spanning.bar_span(BAR), 4 * HOUR);
+
+        let mut anchored = series(true);
+        anchored.insert_trades_existing_buckets(&trades);
+        assert_eq!(anchored.datapoints.len(), 2);
+        assert_eq!(traded(&anchored), 2.0);
+    }
+}
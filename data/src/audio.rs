@@ -23,6 +23,10 @@ impl std::fmt::Display for Threshold {
 pub struct StreamCfg {
     pub enabled: bool,
     pub threshold: Threshold,
+    /// Count sweeps of prints this many ms apart instead of single prints, see
+    /// [`crate::aggr::sweep`]
+    #[serde(default)]
+    pub sweep_window_ms: Option<u16>,
 }
 
 impl Default for StreamCfg {
@@ -30,6 +34,7 @@ impl Default for StreamCfg {
         StreamCfg {
             enabled: true,
             threshold: Threshold::Count(10),
+            sweep_window_ms: None,
         }
     }
 }
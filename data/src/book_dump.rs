@@ -0,0 +1,123 @@
+//! Order book dumps for bug reports, written to the data folder on demand.
+//!
+//! A dump holds the book as last delivered by the stream, the raw messages before it when
+//! capture is on for the venue, see [`exchange::capture`], and a few stream metrics.
+
+use exchange::adapter::{self, Exchange, ExchangeInclusive};
+use exchange::capture::{self, RawMessage};
+use exchange::{Ticker, depth::Depth};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+pub const DUMPS_DIR: &str = "dumps";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookDump {
+    /// Unix timestamp in milliseconds of when the dump was taken
+    pub taken_at: u64,
+    pub exchange: Exchange,
+    pub symbol: String,
+    /// Exchange time of the last depth update
+    pub depth_time: u64,
+    /// `(price, qty)`, best first
+    pub bids: Vec<(f32, f32)>,
+    pub asks: Vec<(f32, f32)>,
+    /// Oldest first, empty unless raw capture was on
+    pub raw_messages: Vec<RawMessage>,
+    pub metrics: StreamMetrics,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamMetrics {
+    pub bid_levels: usize,
+    pub ask_levels: usize,
+    pub raw_capture_enabled: bool,
+    /// Depth events dropped for slow consumers across MT5 and custom WS streams
+    pub dropped_depth_events: u64,
+}
+
+impl BookDump {
+    pub fn new(ticker: Ticker, depth_time: u64, depth: &Depth, taken_at: u64) -> Self {
+        let (symbol, _) = ticker.to_full_symbol_and_type();
+
+        Self {
+            taken_at,
+            exchange: ticker.exchange,
+            symbol,
+            depth_time,
+            bids: depth
+                .bids
+                .iter()
+                .rev()
+                .map(|(price, qty)| (price.to_f32(), *qty))
+                .collect(),
+            asks: depth
+                .asks
+                .iter()
+                .map(|(price, qty)| (price.to_f32(), *qty))
+                .collect(),
+            raw_messages: capture::recent(&ticker),
+            metrics: StreamMetrics {
+                bid_levels: depth.bids.len(),
+                ask_levels: depth.asks.len(),
+                raw_capture_enabled: capture::is_enabled(ExchangeInclusive::of(ticker.exchange)),
+                dropped_depth_events: adapter::dropped_depth_count(),
+            },
+        }
+    }
+
+    /// Relative to the data folder, e.g. `dumps/book-BinanceLinear-BTCUSDT-20250101-120000.000.json`
+    pub fn file_name(&self) -> String {
+        let time = chrono::DateTime::from_timestamp_millis(self.taken_at as i64)
+            .map(|t| t.format("%Y%m%d-%H%M%S%.3f").to_string())
+            .unwrap_or_else(|| self.taken_at.to_string());
+        let symbol: String = self
+            .symbol
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+
+        format!("{DUMPS_DIR}/book-{:?}-{symbol}-{time}.json", self.exchange)
+    }
+
+    /// Writes the dump, returns the full path of the file
+    pub fn write(&self) -> std::io::Result<PathBuf> {
+        let json = serde_json::to_string_pretty(self)?;
+        let file_name = self.file_name();
+
+        crate::write_json_to_file(&json, &file_name)?;
+        Ok(crate::data_path(Some(&file_name)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::util::Price;
+
+    #[test]
+    fn dump_round_trips_through_serde() {
+        let ticker = Ticker::new("EURUSD", Exchange::MetaTrader5);
+        let mut depth = Depth::default();
+        depth.bids.insert(Price::from_f32(1.0850), 2.0);
+        depth.bids.insert(Price::from_f32(1.0851), 1.0);
+        depth.asks.insert(Price::from_f32(1.0853), 3.0);
+
+        let mut dump = BookDump::new(ticker, 1_704_665_100_000, &depth, 1_704_665_100_250);
+        dump.raw_messages.push(RawMessage {
+            received_at: 1_704_665_100_200,
+            text: r#"{"type":"depth","symbol":"EURUSD"}"#.to_string(),
+        });
+
+        assert_eq!(dump.bids[0].0, Price::from_f32(1.0851).to_f32());
+        assert_eq!(dump.metrics.bid_levels, 2);
+        assert_eq!(
+            dump.file_name(),
+            "dumps/book-MetaTrader5-EURUSD-20240107-220500.250.json"
+        );
+
+        let json = serde_json::to_string_pretty(&dump).unwrap();
+        let loaded: BookDump = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, dump);
+    }
+}
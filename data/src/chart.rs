@@ -1,9 +1,31 @@
+pub mod audit;
+pub mod bar_close;
+pub mod calendar;
 pub mod comparison;
+pub mod data_window;
+pub mod drawing;
 pub mod heatmap;
+pub mod imbalance;
 pub mod indicator;
 pub mod kline;
+pub mod levels;
+pub mod panels;
+pub mod pattern;
+pub mod position_size;
+pub mod price_alert;
+pub mod replay;
+pub mod resolution;
+pub mod revision;
+pub mod session;
+pub mod spread;
+pub mod spread_alert;
+pub mod stop_run;
+pub mod strip;
+pub mod trade_size;
+pub mod volume_curve;
 
 use exchange::Timeframe;
+use exchange::util::Price;
 use serde::{Deserialize, Serialize};
 
 use super::aggr::{
@@ -44,12 +66,144 @@ impl<D: DataPoint> PlotData<D> {
             }
         }
     }
+
+    /// Close of the oldest bar within the range, what percent scales are anchored at
+    pub fn first_close_in_range(&self, start_interval: u64, end_interval: u64) -> Option<Price> {
+        match self {
+            PlotData::TimeBased(timeseries) => timeseries
+                .datapoints
+                .range(start_interval..=end_interval)
+                .find_map(|(_, dp)| dp.kline().map(|kline| kline.close)),
+            PlotData::TickBased(tick_aggr) => {
+                // Indices count back from the latest bar, the oldest visible is the highest one
+                let oldest = (tick_aggr.datapoints.len() as u64).checked_sub(1)?;
+                let index = end_interval.min(oldest);
+                (index >= start_interval)
+                    .then(|| tick_aggr.datapoints.get((oldest - index) as usize))
+                    .flatten()
+                    .map(|dp| dp.kline.close)
+            }
+        }
+    }
+
+    /// Oldest loaded interval, for tick based data the index of the oldest bar counting back
+    /// from the latest
+    pub fn oldest_interval(&self) -> Option<u64> {
+        match self {
+            PlotData::TimeBased(timeseries) => timeseries.datapoints.keys().next().copied(),
+            PlotData::TickBased(tick_aggr) => (tick_aggr.datapoints.len() as u64).checked_sub(1),
+        }
+    }
+}
+
+impl PlotData<kline::KlineDataPoint> {
+    /// Drops the oldest bars beyond `max_bars`, never the one at `keep_from` or any newer, an
+    /// interval as [`Self::oldest_interval`] counts them. Returns how many were dropped.
+    pub fn prune(&mut self, max_bars: usize, keep_from: Option<u64>) -> usize {
+        match self {
+            PlotData::TimeBased(timeseries) => timeseries.prune(max_bars, keep_from),
+            PlotData::TickBased(tick_aggr) => tick_aggr.prune(
+                max_bars,
+                keep_from.map(|back| usize::try_from(back).unwrap_or(usize::MAX)),
+            ),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 pub struct ViewConfig {
     pub splits: Vec<f32>,
     pub autoscale: Option<Autoscale>,
+    /// Framing the chart was left at, `None` opens it at the chart's default
+    #[serde(default)]
+    pub viewport: Option<Viewport>,
+    #[serde(default)]
+    pub price_scale: PriceScale,
+    /// `None` in layouts saved before panel heights were kept, see [`panels`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub panel_heights: Option<panels::PanelHeights>,
+}
+
+impl ViewConfig {
+    /// Autoscale to open the chart with. The saved one only applies together with the framing
+    /// it was saved with, a locked scale also needs the price range it was locked to.
+    pub fn restored_autoscale(&self, default: Autoscale) -> Option<Autoscale> {
+        match (&self.viewport, self.autoscale) {
+            (Some(viewport), None) if viewport.price_range.is_some() => None,
+            (Some(_), Some(autoscale)) => Some(autoscale),
+            _ => Some(default),
+        }
+    }
+}
+
+/// Zoom and position of a chart, saved with the layout
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+pub struct Viewport {
+    pub cell_width: f32,
+    pub cell_height: f32,
+    /// Interval at the right edge, `None` while the latest one was in view so the restored
+    /// chart keeps following it
+    pub right_edge: Option<u64>,
+    /// Highest and lowest visible price while the vertical scale was locked
+    pub price_range: Option<(f32, f32)>,
+}
+
+impl Viewport {
+    /// Right edge to scroll a time based chart to, with data loaded from `earliest` on
+    pub fn right_edge_within(&self, earliest: u64, latest: u64) -> Option<u64> {
+        self.right_edge
+            .filter(|edge| *edge < latest)
+            .map(|edge| edge.max(earliest))
+    }
+
+    /// Whether the locked price range still shows `price`
+    pub fn price_range_holds(&self, price: f32) -> bool {
+        self.price_range
+            .is_none_or(|(highest, lowest)| (lowest..=highest).contains(&price))
+    }
+}
+
+/// How prices map to the y axis of a kline chart
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq, Eq)]
+pub enum PriceScale {
+    #[default]
+    Linear,
+    /// Equal distances are equal ratios
+    Log,
+    /// Linear, labelled as the change from the close of the leftmost visible bar
+    Percent,
+}
+
+impl PriceScale {
+    pub const ALL: [PriceScale; 3] = [PriceScale::Linear, PriceScale::Log, PriceScale::Percent];
+
+    /// Where `price` sits between `lowest` at 0.0 and `highest` at 1.0
+    pub fn ratio(self, price: f32, lowest: f32, highest: f32) -> f32 {
+        match self {
+            PriceScale::Log if lowest > 0.0 && price > 0.0 => {
+                (price / lowest).ln() / (highest / lowest).ln()
+            }
+            _ => (price - lowest) / (highest - lowest),
+        }
+    }
+
+    /// Price at `ratio` between `lowest` and `highest`, the inverse of [`PriceScale::ratio`]
+    pub fn interpolate(self, ratio: f32, lowest: f32, highest: f32) -> f32 {
+        match self {
+            PriceScale::Log if lowest > 0.0 => lowest * (highest / lowest).powf(ratio),
+            _ => lowest + ratio * (highest - lowest),
+        }
+    }
+}
+
+impl std::fmt::Display for PriceScale {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PriceScale::Linear => write!(f, "Linear"),
+            PriceScale::Log => write!(f, "Logarithmic"),
+            PriceScale::Percent => write!(f, "Percent"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize, Default, PartialEq)]
@@ -112,3 +266,75 @@ pub enum Study {
     Heatmap(Vec<heatmap::HeatmapStudy>),
     Footprint(Vec<kline::FootprintStudy>),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restored_viewport_clamps_and_keeps_locked_scale_with_its_range() {
+        let locked = Viewport {
+            cell_width: 6.0,
+            cell_height: 2.0,
+            right_edge: Some(1_000),
+            price_range: Some((110.0, 90.0)),
+        };
+
+        let config = ViewConfig {
+            splits: vec![],
+            autoscale: None,
+            viewport: Some(locked),
+            price_scale: PriceScale::Log,
+            panel_heights: None,
+        };
+        assert_eq!(config.restored_autoscale(Autoscale::FitToVisible), None);
+
+        // A layout saved before viewports were kept opens the default way
+        let config: ViewConfig = serde_json::from_str(r#"{"splits":[],"autoscale":null}"#).unwrap();
+        assert_eq!(
+            config.restored_autoscale(Autoscale::FitToVisible),
+            Some(Autoscale::FitToVisible)
+        );
+
+        // Scrolled further back than what's loaded after the restart
+        assert_eq!(locked.right_edge_within(5_000, 9_000), Some(5_000));
+        assert_eq!(locked.right_edge_within(0, 9_000), Some(1_000));
+        assert_eq!(locked.right_edge_within(0, 900), None);
+
+        assert!(locked.price_range_holds(100.0));
+        assert!(!locked.price_range_holds(120.0));
+    }
+
+    #[test]
+    fn price_scale_persists_and_older_layouts_open_linear() {
+        let config = ViewConfig {
+            price_scale: PriceScale::Percent,
+            ..ViewConfig::default()
+        };
+        let json = serde_json::to_string(&config).unwrap();
+        let restored: ViewConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.price_scale, PriceScale::Percent);
+
+        let older: ViewConfig = serde_json::from_str(r#"{"splits":[],"autoscale":null}"#).unwrap();
+        assert_eq!(older.price_scale, PriceScale::Linear);
+    }
+
+    #[test]
+    fn log_scale_places_equal_ratios_equally_apart() {
+        let (lowest, highest) = (10.0, 1000.0);
+
+        assert!((PriceScale::Log.ratio(100.0, lowest, highest) - 0.5).abs() < 1e-6);
+        assert!((PriceScale::Linear.ratio(505.0, lowest, highest) - 0.5).abs() < 1e-6);
+
+        for scale in PriceScale::ALL {
+            for price in [10.0, 42.0, 250.0, 1000.0] {
+                let ratio = scale.ratio(price, lowest, highest);
+                let back = scale.interpolate(ratio, lowest, highest);
+                assert!(
+                    (back - price).abs() / price < 1e-4,
+                    "{scale}: {price} -> {back}"
+                );
+            }
+        }
+    }
+}
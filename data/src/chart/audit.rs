@@ -0,0 +1,384 @@
+//! Audit of stored klines against a fresh fetch of the same range.
+//!
+//! Both sides are fed in chunks as they are read and fetched, oldest first, and compared bar by
+//! bar as soon as both reach a time, so a long range never has to be held in full. Only the bars
+//! one side is ahead by and the listed findings are kept.
+
+use super::revision::{prices_differ, volume_differs};
+
+use exchange::Kline;
+use exchange::util::{MinTicksize, PriceStep};
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+/// Findings listed in a report, further ones are only counted
+pub const MAX_LISTED: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Prices within this many ticks of each other count as the same
+    pub price_ticks: u32,
+    /// Share of the larger volume two volumes may differ by
+    pub volume: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            price_ticks: 0,
+            volume: 0.01,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Finding {
+    /// A bar the fetch has that isn't stored
+    Missing(Kline),
+    /// A stored bar the fetch no longer has
+    Extra(Kline),
+    /// OHLC prices differ beyond the tolerance, volumes may too
+    Prices { stored: Kline, fetched: Kline },
+    /// Only the volumes differ beyond the tolerance
+    Volume { stored: Kline, fetched: Kline },
+}
+
+impl Finding {
+    pub fn time(&self) -> u64 {
+        match self {
+            Finding::Missing(kline) | Finding::Extra(kline) => kline.time,
+            Finding::Prices { stored, .. } | Finding::Volume { stored, .. } => stored.time,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Finding::Missing(_) => "missing",
+            Finding::Extra(_) => "extra",
+            Finding::Prices { .. } => "prices",
+            Finding::Volume { .. } => "volume",
+        }
+    }
+
+    fn stored(&self) -> Option<&Kline> {
+        match self {
+            Finding::Missing(_) => None,
+            Finding::Extra(stored)
+            | Finding::Prices { stored, .. }
+            | Finding::Volume { stored, .. } => Some(stored),
+        }
+    }
+
+    fn fetched(&self) -> Option<&Kline> {
+        match self {
+            Finding::Extra(_) => None,
+            Finding::Missing(fetched)
+            | Finding::Prices { fetched, .. }
+            | Finding::Volume { fetched, .. } => Some(fetched),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    /// Bars found on both sides
+    pub compared: usize,
+    pub missing: usize,
+    pub extra: usize,
+    pub price_mismatches: usize,
+    pub volume_mismatches: usize,
+    /// The first [`MAX_LISTED`] findings, oldest first
+    pub findings: Vec<Finding>,
+}
+
+impl Report {
+    pub fn is_clean(&self) -> bool {
+        self.missing + self.extra + self.price_mismatches + self.volume_mismatches == 0
+    }
+
+    /// Findings beyond [`MAX_LISTED`] that were only counted
+    pub fn unlisted(&self) -> usize {
+        self.missing + self.extra + self.price_mismatches + self.volume_mismatches
+            - self.findings.len()
+    }
+
+    /// One line per finding with the stored and fetched bar, empty where a side has none
+    pub fn to_csv(&self, precision: MinTicksize) -> String {
+        let mut csv = String::from(
+            "time,finding,stored_open,stored_high,stored_low,stored_close,stored_volume,\
+             fetched_open,fetched_high,fetched_low,fetched_close,fetched_volume\n",
+        );
+
+        let columns = |kline: Option<&Kline>| match kline {
+            Some(kline) => format!(
+                "{},{},{},{},{}",
+                kline.open.to_string(precision),
+                kline.high.to_string(precision),
+                kline.low.to_string(precision),
+                kline.close.to_string(precision),
+                kline.volume.0 + kline.volume.1
+            ),
+            None => ",,,,".to_string(),
+        };
+
+        for finding in &self.findings {
+            let _ = writeln!(
+                csv,
+                "{},{},{},{}",
+                finding.time(),
+                finding.label(),
+                columns(finding.stored()),
+                columns(finding.fetched()),
+            );
+        }
+        csv
+    }
+
+    fn record(&mut self, finding: Finding) {
+        match finding {
+            Finding::Missing(_) => self.missing += 1,
+            Finding::Extra(_) => self.extra += 1,
+            Finding::Prices { .. } => self.price_mismatches += 1,
+            Finding::Volume { .. } => self.volume_mismatches += 1,
+        }
+        if self.findings.len() < MAX_LISTED {
+            self.findings.push(finding);
+        }
+    }
+}
+
+/// A running comparison, fed chunks of both sides in any interleaving
+#[derive(Debug)]
+pub struct Audit {
+    tolerance: Tolerance,
+    step: PriceStep,
+    stored: VecDeque<Kline>,
+    fetched: VecDeque<Kline>,
+    report: Report,
+}
+
+impl Audit {
+    pub fn new(tolerance: Tolerance, step: PriceStep) -> Self {
+        Self {
+            tolerance,
+            step,
+            stored: VecDeque::new(),
+            fetched: VecDeque::new(),
+            report: Report::default(),
+        }
+    }
+
+    /// Next stored bars, later than those pushed before
+    pub fn push_stored(&mut self, klines: &[Kline]) {
+        self.stored.extend(klines);
+        self.compare();
+    }
+
+    /// Next fetched bars, later than those pushed before
+    pub fn push_fetched(&mut self, klines: &[Kline]) {
+        self.fetched.extend(klines);
+        self.compare();
+    }
+
+    /// Findings so far, bars one side is still ahead by aren't in yet
+    pub fn report(&self) -> &Report {
+        &self.report
+    }
+
+    /// Ends the audit once both sides are read, bars only one side reached are findings
+    pub fn finish(mut self) -> Report {
+        while let Some(stored) = self.stored.pop_front() {
+            self.report.record(Finding::Extra(stored));
+        }
+        while let Some(fetched) = self.fetched.pop_front() {
+            self.report.record(Finding::Missing(fetched));
+        }
+        self.report
+    }
+
+    /// Settles every bar the other side has moved past
+    fn compare(&mut self) {
+        while let (Some(stored), Some(fetched)) = (self.stored.front(), self.fetched.front()) {
+            let finding = if stored.time < fetched.time {
+                self.stored.pop_front().map(Finding::Extra)
+            } else if fetched.time < stored.time {
+                self.fetched.pop_front().map(Finding::Missing)
+            } else {
+                let (stored, fetched) = (*stored, *fetched);
+                self.stored.pop_front();
+                self.fetched.pop_front();
+                self.report.compared += 1;
+                self.check(stored, fetched)
+            };
+
+            if let Some(finding) = finding {
+                self.report.record(finding);
+            }
+        }
+    }
+
+    fn check(&self, stored: Kline, fetched: Kline) -> Option<Finding> {
+        if prices_differ(&stored, &fetched, self.tolerance.price_ticks, self.step) {
+            Some(Finding::Prices { stored, fetched })
+        } else if volume_differs(&stored, &fetched, self.tolerance.volume) {
+            Some(Finding::Volume { stored, fetched })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::util::Price;
+
+    const BAR: u64 = 60_000;
+
+    fn kline(index: u64, close: f32, volume: f32) -> Kline {
+        Kline {
+            time: index * BAR,
+            open: Price::from_f32(100.0),
+            high: Price::from_f32(close.max(100.0) + 1.0),
+            low: Price::from_f32(close.min(100.0) - 1.0),
+            close: Price::from_f32(close),
+            volume: (volume / 2.0, volume / 2.0),
+        }
+    }
+
+    fn series(len: u64) -> Vec<Kline> {
+        (0..len).map(|i| kline(i, 100.0 + i as f32, 10.0)).collect()
+    }
+
+    fn audit(stored: &[Kline], fetched: &[Kline], tolerance: Tolerance) -> Report {
+        let mut audit = Audit::new(tolerance, PriceStep::from_f32(0.5));
+        audit.push_stored(stored);
+        audit.push_fetched(fetched);
+        audit.finish()
+    }
+
+    #[test]
+    fn identical_series_are_clean() {
+        let report = audit(&series(50), &series(50), Tolerance::default());
+
+        assert!(report.is_clean());
+        assert_eq!(report.compared, 50);
+        assert!(report.findings.is_empty());
+    }
+
+    #[test]
+    fn divergent_series_report_every_kind() {
+        let mut stored = series(10);
+        let mut fetched = series(10);
+
+        // Stored lacks bar 3, the fetch lacks bar 7
+        stored.remove(3);
+        fetched.remove(7);
+        fetched[1] = kline(1, 150.0, 10.0);
+        fetched[5] = kline(5, 105.0, 20.0);
+
+        let report = audit(&stored, &fetched, Tolerance::default());
+
+        assert_eq!(
+            (
+                report.missing,
+                report.extra,
+                report.price_mismatches,
+                report.volume_mismatches
+            ),
+            (1, 1, 1, 1)
+        );
+        assert_eq!(report.compared, 8);
+
+        let times: Vec<_> = report.findings.iter().map(|f| f.time() / BAR).collect();
+        assert_eq!(times, vec![1, 3, 5, 7]);
+        assert!(matches!(report.findings[1], Finding::Missing(_)));
+        assert!(matches!(report.findings[3], Finding::Extra(_)));
+    }
+
+    #[test]
+    fn differences_within_tolerance_pass() {
+        let stored = vec![kline(0, 100.0, 10.0)];
+        let fetched = vec![kline(0, 100.5, 10.05)];
+
+        let strict = Tolerance {
+            price_ticks: 0,
+            volume: 0.0,
+        };
+        assert_eq!(audit(&stored, &fetched, strict).price_mismatches, 1);
+
+        let loose = Tolerance {
+            price_ticks: 1,
+            volume: 0.01,
+        };
+        assert!(audit(&stored, &fetched, loose).is_clean());
+    }
+
+    #[test]
+    fn chunked_feeding_matches_a_single_pass() {
+        let stored = series(200);
+        let mut fetched = series(205);
+        fetched[120] = kline(120, 90.0, 10.0);
+        fetched.remove(40);
+
+        let whole = audit(&stored, &fetched, Tolerance::default());
+
+        let mut chunked = Audit::new(Tolerance::default(), PriceStep::from_f32(0.5));
+        let mut stored_chunks = stored.chunks(7);
+        let mut fetched_chunks = fetched.chunks(31);
+        loop {
+            let (s, f) = (stored_chunks.next(), fetched_chunks.next());
+            if s.is_none() && f.is_none() {
+                break;
+            }
+            chunked.push_stored(s.unwrap_or_default());
+            chunked.push_fetched(f.unwrap_or_default());
+            // Only the side that's ahead is held
+            assert!(chunked.stored.is_empty() || chunked.fetched.is_empty());
+        }
+
+        let chunked = chunked.finish();
+        let summary = |report: &Report| {
+            let times: Vec<_> = report.findings.iter().map(Finding::time).collect();
+            (
+                report.compared,
+                report.to_csv(MinTicksize::from(0.5)),
+                times,
+            )
+        };
+        assert_eq!(summary(&chunked), summary(&whole));
+        assert_eq!(
+            (whole.extra, whole.price_mismatches, whole.missing),
+            (1, 1, 5)
+        );
+    }
+
+    #[test]
+    fn listing_is_capped_but_counting_is_not() {
+        let fetched = series(MAX_LISTED as u64 + 5);
+        let report = audit(&[], &fetched, Tolerance::default());
+
+        assert_eq!(report.missing, MAX_LISTED + 5);
+        assert_eq!(report.findings.len(), MAX_LISTED);
+        assert_eq!(report.unlisted(), 5);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_finding() {
+        let report = audit(
+            &[kline(0, 100.0, 10.0)],
+            &[kline(0, 101.0, 10.0), kline(1, 102.0, 10.0)],
+            Tolerance::default(),
+        );
+        let csv = report.to_csv(MinTicksize::from(0.1));
+        let rows: Vec<_> = csv.lines().collect();
+
+        assert_eq!(rows.len(), 3);
+        assert_eq!(
+            rows[1],
+            "0,prices,100.0,101.0,99.0,100.0,10,100.0,102.0,99.0,101.0,10"
+        );
+        assert_eq!(rows[2], "60000,missing,,,,,,100.0,103.0,99.0,102.0,10");
+    }
+}
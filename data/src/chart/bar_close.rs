@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+/// A close older than this is treated as missed (e.g. the machine was asleep) and skipped
+const FIRE_GRACE_MS: u64 = 5_000;
+
+/// Per-pane "notify on bar close" options
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct BarCloseAlert {
+    pub play_sound: bool,
+    pub show_toast: bool,
+}
+
+impl Default for BarCloseAlert {
+    fn default() -> Self {
+        Self {
+            play_sound: true,
+            show_toast: false,
+        }
+    }
+}
+
+/// Detects timeframe boundaries from a server-time clock polled at a regular interval.
+///
+/// Fires at most once per boundary and never for boundaries that passed while it wasn't polled,
+/// so resuming from sleep resyncs silently instead of replaying every missed close.
+#[derive(Debug, Clone, Default)]
+pub struct BarCloseClock {
+    interval_ms: u64,
+    last_boundary: Option<u64>,
+}
+
+impl BarCloseClock {
+    /// Returns the open time of the bar that just started, if a bar closed since the last poll
+    pub fn poll(&mut self, interval_ms: u64, server_now_ms: u64) -> Option<u64> {
+        if interval_ms == 0 {
+            return None;
+        }
+        if interval_ms != self.interval_ms {
+            self.interval_ms = interval_ms;
+            self.last_boundary = None;
+        }
+
+        let boundary = server_now_ms - (server_now_ms % interval_ms);
+        let previous = self.last_boundary.replace(boundary);
+
+        match previous {
+            Some(prev) if boundary > prev => {
+                (server_now_ms - boundary <= FIRE_GRACE_MS).then_some(boundary)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.last_boundary = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const M1: u64 = 60_000;
+
+    #[test]
+    fn fires_once_per_boundary() {
+        let mut clock = BarCloseClock::default();
+
+        // First poll only syncs, even right on a boundary
+        assert_eq!(clock.poll(M1, 120_000), None);
+        assert_eq!(clock.poll(M1, 150_000), None);
+
+        assert_eq!(clock.poll(M1, 180_050), Some(180_000));
+        assert_eq!(clock.poll(M1, 180_150), None);
+    }
+
+    #[test]
+    fn resumes_from_sleep_without_burst() {
+        let mut clock = BarCloseClock::default();
+        clock.poll(M1, 100_000);
+
+        // Ten bars pass while suspended, the latest closed well outside the grace window
+        assert_eq!(clock.poll(M1, 10 * M1 + 30_000), None);
+        assert_eq!(clock.poll(M1, 11 * M1 + 200), Some(11 * M1));
+    }
+
+    #[test]
+    fn timeframe_change_resyncs() {
+        let mut clock = BarCloseClock::default();
+        clock.poll(M1, 119_000);
+
+        assert_eq!(clock.poll(5 * M1, 300_100), None);
+        assert_eq!(clock.poll(5 * M1, 600_100), Some(600_000));
+    }
+}
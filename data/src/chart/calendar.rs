@@ -0,0 +1,123 @@
+//! Economic calendar markers along the time axis of kline charts.
+//!
+//! Events are fetched for a window around the current day and kept until the day is over, so
+//! charts of the same currencies share one request. Actual values released later in the day
+//! show from the next day's fetch.
+
+use exchange::calendar::{CalendarEvent, Importance};
+use serde::{Deserialize, Serialize};
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+const DAY_MS: u64 = 86_400_000;
+/// Days before the current one whose events are fetched
+const LOOKBACK_DAYS: u64 = 7;
+/// Days after the current one, for upcoming releases
+const LOOKAHEAD_DAYS: u64 = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CalendarConfig {
+    /// Less important events are hidden
+    pub min_importance: Importance,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        Self {
+            min_importance: Importance::Medium,
+        }
+    }
+}
+
+impl CalendarConfig {
+    pub fn shows(&self, event: &CalendarEvent) -> bool {
+        event.importance >= self.min_importance
+    }
+}
+
+/// UTC day of `now_ms`, what the cache and the fetch window go by
+pub fn day_of(now_ms: u64) -> u64 {
+    now_ms / DAY_MS
+}
+
+/// Events to fetch on `day`
+pub fn window(day: u64) -> (u64, u64) {
+    (
+        day.saturating_sub(LOOKBACK_DAYS) * DAY_MS,
+        (day + 1 + LOOKAHEAD_DAYS) * DAY_MS,
+    )
+}
+
+/// Events with the day they were fetched on, by currencies
+type Cache = HashMap<Vec<String>, (u64, Vec<CalendarEvent>)>;
+
+static CACHE: Mutex<Option<Cache>> = Mutex::new(None);
+
+/// Events of `currencies` fetched earlier on `day`
+pub fn cached(currencies: &[String], day: u64) -> Option<Vec<CalendarEvent>> {
+    let cache = CACHE.lock().ok()?;
+
+    cache
+        .as_ref()?
+        .get(currencies)
+        .filter(|(fetched_on, _)| *fetched_on == day)
+        .map(|(_, events)| events.clone())
+}
+
+/// Keeps `events` for the rest of `day`, dropping those fetched on earlier days
+pub fn store(currencies: &[String], day: u64, events: Vec<CalendarEvent>) {
+    if let Ok(mut cache) = CACHE.lock() {
+        let cache = cache.get_or_insert_with(HashMap::new);
+        cache.retain(|_, (fetched_on, _)| *fetched_on == day);
+        cache.insert(currencies.to_vec(), (day, events));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(importance: Importance) -> CalendarEvent {
+        CalendarEvent {
+            time: 0,
+            currency: "USD".to_string(),
+            importance,
+            title: "CPI m/m".to_string(),
+            actual: None,
+            forecast: None,
+            previous: None,
+        }
+    }
+
+    #[test]
+    fn window_spans_the_days_around_today() {
+        let day = day_of(1_704_459_600_000);
+        let (from, to) = window(day);
+
+        assert_eq!(from, (day - 7) * DAY_MS);
+        assert_eq!(to - from, 15 * DAY_MS);
+        assert!((from..to).contains(&1_704_459_600_000));
+    }
+
+    #[test]
+    fn cache_holds_for_the_day_only() {
+        let currencies = vec!["NZD".to_string(), "CHF".to_string()];
+        store(&currencies, 100, vec![event(Importance::High)]);
+
+        assert_eq!(cached(&currencies, 100).map(|events| events.len()), Some(1));
+        assert_eq!(cached(&currencies, 101), None);
+        assert_eq!(cached(&["CHF".to_string()], 100), None);
+    }
+
+    #[test]
+    fn minimum_importance_filters() {
+        let config = CalendarConfig {
+            min_importance: Importance::Medium,
+        };
+
+        assert!(!config.shows(&event(Importance::Low)));
+        assert!(config.shows(&event(Importance::Medium)));
+        assert!(config.shows(&event(Importance::High)));
+    }
+}
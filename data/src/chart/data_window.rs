@@ -0,0 +1,156 @@
+//! Values at the hovered bar, listed in a corner of the pane.
+//!
+//! Everything shown is looked up by the hovered bar's key in series the pane already holds, a
+//! tree or binary search, so hovering costs the same however much history is loaded and nothing
+//! is collected to find it.
+
+use super::heatmap::OrderRun;
+use crate::indicators;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Shown and where, kept per pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DataWindow {
+    pub visible: bool,
+    pub corner: Corner,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum Corner {
+    #[default]
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::TopLeft,
+        Corner::TopRight,
+        Corner::BottomLeft,
+        Corner::BottomRight,
+    ];
+
+    /// Top left of a `size` box docked in this corner of `bounds`, `margin` from the edges and
+    /// never pushed past the top left one
+    pub fn place(self, size: (f32, f32), bounds: (f32, f32), margin: f32) -> (f32, f32) {
+        let right = (bounds.0 - size.0 - margin).max(margin);
+        let bottom = (bounds.1 - size.1 - margin).max(margin);
+
+        match self {
+            Corner::TopLeft => (margin, margin),
+            Corner::TopRight => (right, margin),
+            Corner::BottomLeft => (margin, bottom),
+            Corner::BottomRight => (right, bottom),
+        }
+    }
+}
+
+impl std::fmt::Display for Corner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Corner::TopLeft => write!(f, "Top left"),
+            Corner::TopRight => write!(f, "Top right"),
+            Corner::BottomLeft => write!(f, "Bottom left"),
+            Corner::BottomRight => write!(f, "Bottom right"),
+        }
+    }
+}
+
+/// The bar keyed `at`, or the latest one when `at` is past it
+pub fn bar_at<T>(series: &BTreeMap<u64, T>, at: u64) -> Option<(u64, &T)> {
+    series
+        .get_key_value(&at)
+        .map(|(key, bar)| (*key, bar))
+        .or_else(|| {
+            series
+                .last_key_value()
+                .filter(|(last, _)| at > **last)
+                .map(|(key, bar)| (*key, bar))
+        })
+}
+
+/// Value of an overlay at the bar opened at `time`
+pub fn point_at(points: &[indicators::Point], time: u64) -> Option<indicators::Value> {
+    points
+        .binary_search_by_key(&time, |point| point.time)
+        .ok()
+        .and_then(|index| points[index].value)
+}
+
+/// The last `count` runs of a price level that started by `time`, oldest first. Runs of a
+/// level are held in the order they started.
+pub fn runs_until(runs: &[OrderRun], time: u64, count: usize) -> &[OrderRun] {
+    let end = runs.partition_point(|run| run.start_time <= time);
+    &runs[end.saturating_sub(count)..end]
+}
+
+/// Buy minus sell volume, `None` for venues that only report the total
+pub fn delta((buy, sell): (f32, f32)) -> Option<f32> {
+    (buy != -1.0).then_some(buy - sell)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn boxes_dock_in_their_corner() {
+        let (size, bounds) = ((100.0, 40.0), (400.0, 300.0));
+        assert_eq!(Corner::TopLeft.place(size, bounds, 8.0), (8.0, 8.0));
+        assert_eq!(Corner::TopRight.place(size, bounds, 8.0), (292.0, 8.0));
+        assert_eq!(Corner::BottomRight.place(size, bounds, 8.0), (292.0, 252.0));
+        // A pane too small for the box keeps it at the top left
+        assert_eq!(
+            Corner::BottomRight.place(size, (80.0, 30.0), 8.0),
+            (8.0, 8.0)
+        );
+
+        let saved: DataWindow = serde_json::from_str(r#"{"visible":true}"#).unwrap();
+        assert_eq!(
+            saved,
+            DataWindow {
+                visible: true,
+                corner: Corner::TopLeft
+            }
+        );
+    }
+
+    #[test]
+    fn lookups_find_the_hovered_bar() {
+        let series: BTreeMap<u64, u32> = [(60, 1), (120, 2), (240, 4)].into_iter().collect();
+        assert_eq!(bar_at(&series, 120), Some((120, &2)));
+        // A gap shows nothing, past the latest bar shows the latest
+        assert_eq!(bar_at(&series, 180), None);
+        assert_eq!(bar_at(&series, 600), Some((240, &4)));
+        assert_eq!(bar_at(&series, 0), None);
+
+        let points: Vec<indicators::Point> = [(60, None), (120, Some(1.5))]
+            .into_iter()
+            .map(|(time, value)| indicators::Point {
+                time,
+                value: value.map(indicators::Value::Line),
+            })
+            .collect();
+        assert_eq!(point_at(&points, 120), Some(indicators::Value::Line(1.5)));
+        assert_eq!(point_at(&points, 60), None);
+        assert_eq!(point_at(&points, 90), None);
+
+        let runs: Vec<OrderRun> = (0..10)
+            .map(|i| OrderRun::new(i * 100, 100, i as f32, true))
+            .collect();
+        let shown = runs_until(&runs, 450, 3);
+        assert_eq!(
+            shown.iter().map(OrderRun::qty).collect::<Vec<_>>(),
+            [2.0, 3.0, 4.0]
+        );
+        assert!(runs_until(&runs[5..], 100, 3).is_empty());
+
+        assert_eq!(delta((3.0, 1.0)), Some(2.0));
+        assert_eq!(delta((-1.0, 4.0)), None);
+    }
+}
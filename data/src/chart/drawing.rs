@@ -0,0 +1,176 @@
+//! User drawings on kline charts, persisted per ticker inside a pane's settings.
+//!
+//! The list is written with an explicit `version` tag, a layout saved by a newer build with an
+//! unknown version loads as empty instead of failing the whole pane.
+
+use exchange::util::Price;
+use exchange::{Kline, Ticker};
+use serde::{Deserialize, Serialize};
+
+/// A point on a time based chart, `time` is the open time of the bar it was placed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Anchor {
+    pub time: u64,
+    pub price: Price,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Drawing {
+    HorizontalLine { price: Price },
+    Rectangle { start: Anchor, end: Anchor },
+}
+
+impl Drawing {
+    /// Rectangles are anchored to bar open times, which only map back to a fixed spot on time
+    /// based charts
+    pub fn needs_time_axis(&self) -> bool {
+        matches!(self, Drawing::Rectangle { .. })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TickerDrawings {
+    pub ticker: Ticker,
+    pub items: Vec<Drawing>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "version")]
+enum Versioned {
+    #[serde(rename = "1")]
+    V1 { tickers: Vec<TickerDrawings> },
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(from = "Versioned", into = "Versioned")]
+pub struct Drawings {
+    tickers: Vec<TickerDrawings>,
+}
+
+impl From<Versioned> for Drawings {
+    fn from(value: Versioned) -> Self {
+        match value {
+            Versioned::V1 { tickers } => Drawings { tickers },
+        }
+    }
+}
+
+impl From<Drawings> for Versioned {
+    fn from(value: Drawings) -> Self {
+        Versioned::V1 {
+            tickers: value.tickers,
+        }
+    }
+}
+
+impl Drawings {
+    pub fn get(&self, ticker: &Ticker) -> &[Drawing] {
+        self.tickers
+            .iter()
+            .find(|entry| entry.ticker == *ticker)
+            .map_or(&[], |entry| entry.items.as_slice())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tickers.is_empty()
+    }
+
+    pub fn set(&mut self, ticker: Ticker, items: Vec<Drawing>) {
+        self.tickers.retain(|entry| entry.ticker != ticker);
+
+        if !items.is_empty() {
+            self.tickers.push(TickerDrawings { ticker, items });
+        }
+    }
+}
+
+/// Snaps `price` to the closest open/high/low/close of `kline` that lies within `tolerance`
+pub fn snap_to_ohlc(price: Price, kline: &Kline, tolerance: Price) -> Price {
+    [kline.open, kline.high, kline.low, kline.close]
+        .into_iter()
+        .map(|level| (level, (level.units - price.units).abs()))
+        .filter(|(_, distance)| *distance <= tolerance.units.abs())
+        .min_by_key(|(_, distance)| *distance)
+        .map_or(price, |(level, _)| level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::adapter::Exchange;
+
+    fn drawings() -> (Ticker, Drawings) {
+        let ticker = Ticker::new("BTCUSDT", Exchange::BinanceLinear);
+
+        let mut drawings = Drawings::default();
+        drawings.set(
+            ticker,
+            vec![
+                Drawing::HorizontalLine {
+                    price: Price::from_f32(100.5),
+                },
+                Drawing::Rectangle {
+                    start: Anchor {
+                        time: 60_000,
+                        price: Price::from_f32(99.0),
+                    },
+                    end: Anchor {
+                        time: 180_000,
+                        price: Price::from_f32(101.0),
+                    },
+                },
+            ],
+        );
+
+        (ticker, drawings)
+    }
+
+    #[test]
+    fn roundtrip_keeps_version_tag() {
+        let (ticker, drawings) = drawings();
+
+        let json = serde_json::to_string(&drawings).unwrap();
+        assert!(json.contains(r#""version":"1""#));
+
+        let restored: Drawings = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, drawings);
+        assert_eq!(restored.get(&ticker).len(), 2);
+    }
+
+    #[test]
+    fn unknown_version_is_rejected() {
+        let json = r#"{"version":"99","tickers":[]}"#;
+        assert!(serde_json::from_str::<Drawings>(json).is_err());
+    }
+
+    #[test]
+    fn clearing_a_ticker_drops_its_entry() {
+        let (ticker, mut drawings) = drawings();
+
+        drawings.set(ticker, vec![]);
+        assert!(drawings.get(&ticker).is_empty());
+        assert_eq!(drawings, Drawings::default());
+    }
+
+    #[test]
+    fn snaps_only_within_tolerance() {
+        let kline = Kline {
+            time: 0,
+            open: Price::from_f32(10.0),
+            high: Price::from_f32(12.0),
+            low: Price::from_f32(9.0),
+            close: Price::from_f32(11.0),
+            volume: (0.0, 0.0),
+        };
+        let tolerance = Price::from_f32(0.25);
+
+        assert_eq!(
+            snap_to_ohlc(Price::from_f32(11.9), &kline, tolerance),
+            Price::from_f32(12.0)
+        );
+        assert_eq!(
+            snap_to_ohlc(Price::from_f32(11.5), &kline, tolerance),
+            Price::from_f32(11.5)
+        );
+    }
+}
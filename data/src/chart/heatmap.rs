@@ -1,5 +1,6 @@
 use super::Basis;
 use super::aggr::time::DataPoint;
+use exchange::tick_rule::{TickRule, TickTiers};
 use exchange::util::{Price, PriceStep};
 use exchange::{adapter::MarketKind, depth::Depth, volume_size_unit};
 
@@ -7,6 +8,12 @@ use rustc_hash::{FxBuildHasher, FxHashMap};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 
+pub mod delta;
+pub mod history;
+pub mod intensity;
+pub mod liquidity;
+pub mod wall;
+
 pub const CLEANUP_THRESHOLD: usize = 4800;
 
 /// Allow up to 500ms delay in order updates before starting a new order run.
@@ -18,7 +25,38 @@ pub struct Config {
     pub trade_size_filter: f32,
     pub order_size_filter: f32,
     pub trade_size_scale: Option<i32>,
+    /// Derive the trade size filter from this percentile of recent trades instead
+    #[serde(default)]
+    pub auto_trade_size: Option<u8>,
     pub coalescing: Option<CoalesceKind>,
+    /// Minutes of depth history kept on disk across restarts, `None` disables it
+    #[serde(default = "default_history_minutes")]
+    pub history_minutes: Option<u16>,
+    /// Liquidity wall detection, `None` disables it
+    #[serde(default)]
+    pub walls: Option<wall::WallConfig>,
+    /// Mark prints this many ms apart as one sweep, see [`crate::aggr::sweep`]
+    #[serde(default)]
+    pub sweep_window_ms: Option<u16>,
+    #[serde(default)]
+    pub depth_color: DepthColor,
+    /// How opaque depth cells are for their size, see [`intensity`]
+    #[serde(default)]
+    pub intensity: intensity::Intensity,
+}
+
+/// What the depth cells of the heatmap are shaded by
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum DepthColor {
+    /// Resting size, bids and asks apart
+    #[default]
+    Size,
+    /// Size added or pulled within each column, see [`liquidity`]
+    LiquidityChange,
+}
+
+fn default_history_minutes() -> Option<u16> {
+    Some(history::DEFAULT_WINDOW_MINUTES)
 }
 
 impl Default for Config {
@@ -27,7 +65,13 @@ impl Default for Config {
             trade_size_filter: 0.0,
             order_size_filter: 0.0,
             trade_size_scale: Some(100),
+            auto_trade_size: None,
             coalescing: Some(CoalesceKind::Average(0.15)),
+            history_minutes: default_history_minutes(),
+            walls: None,
+            sweep_window_ms: None,
+            depth_color: DepthColor::Size,
+            intensity: intensity::Intensity::Linear,
         }
     }
 }
@@ -143,6 +187,8 @@ pub struct HistoricalDepth {
     price_levels: BTreeMap<Price, Vec<OrderRun>>,
     aggr_time: u64,
     tick_size: PriceStep,
+    /// Ticks of a symbol quoted in tiers, levels never group finer than their tier's
+    tiers: Option<TickTiers>,
     min_order_qty: f32,
 }
 
@@ -155,10 +201,15 @@ impl HistoricalDepth {
                 Basis::Tick(_) => unimplemented!(),
             },
             tick_size,
+            tiers: None,
             min_order_qty,
         }
     }
 
+    pub fn tiered(self, tiers: Option<TickTiers>) -> Self {
+        Self { tiers, ..self }
+    }
+
     pub fn insert_latest_depth(&mut self, depth: &Depth, time: u64) {
         self.process_side(&depth.bids, time, true);
         self.process_side(&depth.asks, time, false);
@@ -171,7 +222,10 @@ impl HistoricalDepth {
         let step = self.tick_size;
 
         for (price, qty) in side {
-            let rounded_price = price.round_to_side_step(is_bid, step);
+            let rounded_price = match self.tiers {
+                Some(tiers) => TickRule::Tiered(tiers).group(*price, is_bid, step),
+                None => price.round_to_side_step(is_bid, step),
+            };
             if Some(rounded_price) == current_price {
                 current_qty += qty;
             } else {
@@ -245,6 +299,11 @@ impl HistoricalDepth {
             })
     }
 
+    /// Runs of the level at `price`, in the order they started
+    pub fn runs_at(&self, price: Price) -> &[OrderRun] {
+        self.price_levels.get(&price).map_or(&[], Vec::as_slice)
+    }
+
     pub fn latest_order_runs(
         &self,
         highest: Price,
@@ -260,6 +319,54 @@ impl HistoricalDepth {
             })
     }
 
+    /// Prepends depth recorded before anything held so far, from `(time, depth)` snapshots
+    /// sorted by time.
+    ///
+    /// Everything from the aggregated time of the earliest held run or of `live_start` on is
+    /// already covered, so those snapshots are dropped and older runs end there. That keeps the
+    /// bucket where the live stream took over from being drawn twice.
+    pub fn backfill<'a>(
+        &mut self,
+        snapshots: impl IntoIterator<Item = (u64, &'a Depth)>,
+        live_start: Option<u64>,
+    ) {
+        let aggr_time = self.aggr_time.max(1);
+        let earliest_held = self
+            .price_levels
+            .values()
+            .filter_map(|runs| runs.first().map(|run| run.start_time))
+            .min();
+        let cutoff = live_start
+            .into_iter()
+            .chain(earliest_held)
+            .min()
+            .map(|time| (time / aggr_time) * aggr_time);
+
+        let mut older = HistoricalDepth {
+            price_levels: BTreeMap::new(),
+            ..*self
+        };
+        for (time, depth) in snapshots {
+            let rounded = (time / aggr_time) * aggr_time;
+            if cutoff.is_some_and(|cutoff| rounded >= cutoff) {
+                break;
+            }
+            older.insert_latest_depth(depth, rounded);
+        }
+
+        for (price, mut runs) in older.price_levels {
+            if let Some(cutoff) = cutoff {
+                for run in &mut runs {
+                    run.until_time = run.until_time.min(cutoff);
+                }
+            }
+
+            let held = self.price_levels.entry(price).or_default();
+            runs.append(held);
+            *held = runs;
+        }
+    }
+
     pub fn cleanup_old_price_levels(&mut self, oldest_time: u64) {
         self.price_levels.iter_mut().for_each(|(_, runs)| {
             runs.retain(|run| run.until_time >= oldest_time);
@@ -631,3 +738,35 @@ impl std::fmt::Display for ProfileKind {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::Timeframe;
+
+    fn book(bid: f32, qty: f32) -> Depth {
+        let mut depth = Depth::default();
+        depth.bids.insert(Price::from_f32(bid), qty);
+        depth
+    }
+
+    #[test]
+    fn backfill_stops_where_live_data_starts() {
+        let step = PriceStep::from_f32(0.5);
+        let mut depth = HistoricalDepth::new(0.0, step, Basis::Time(Timeframe::MS500));
+        depth.insert_latest_depth(&book(100.0, 5.0), 3_000);
+
+        let history = [
+            (1_000, book(100.0, 2.0)),
+            (2_000, book(100.0, 2.0)),
+            (3_200, book(100.0, 9.0)),
+        ];
+        depth.backfill(history.iter().map(|(t, d)| (*t, d)), Some(3_000));
+
+        let runs = &depth.price_levels[&Price::from_f32(100.0)];
+        assert_eq!(runs.len(), 2);
+        assert_eq!((runs[0].start_time, runs[0].qty()), (1_000, 2.0));
+        assert!(runs[0].until_time <= runs[1].start_time);
+        assert_eq!((runs[1].start_time, runs[1].qty()), (3_000, 5.0));
+    }
+}
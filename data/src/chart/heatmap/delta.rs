@@ -0,0 +1,228 @@
+//! Traded delta and volume per heatmap column, for the footer below the heatmap.
+//!
+//! Trades are summed into the column their depth update falls in, the same buckets
+//! [`HistoricalDepth`](super::HistoricalDepth) draws its runs in. Bucket times are rounded down
+//! to a multiple of the aggregation interval rather than counted from the first trade, so
+//! columns after a reconnect gap or restored from disk line up with the live ones.
+//!
+//! Prints almost always land in the newest column, which is kept apart from the finished ones
+//! so adding one is a plain sum.
+
+use super::super::Basis;
+
+use std::collections::BTreeMap;
+
+/// Traded size of one column, in the units the stream reports
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BucketDelta {
+    pub buy: f32,
+    pub sell: f32,
+}
+
+impl BucketDelta {
+    /// Positive when more was bought than sold
+    pub fn delta(&self) -> f32 {
+        self.buy - self.sell
+    }
+
+    pub fn volume(&self) -> f32 {
+        self.buy + self.sell
+    }
+
+    fn add(&mut self, qty: f32, is_sell: bool) {
+        if is_sell {
+            self.sell += qty;
+        } else {
+            self.buy += qty;
+        }
+    }
+
+    fn merge(&mut self, other: BucketDelta) {
+        self.buy += other.buy;
+        self.sell += other.sell;
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DeltaHistory {
+    finished: BTreeMap<u64, BucketDelta>,
+    /// The newest column, still taking trades
+    open: Option<(u64, BucketDelta)>,
+    aggr_time: u64,
+}
+
+impl DeltaHistory {
+    pub fn new(basis: Basis) -> Self {
+        Self {
+            finished: BTreeMap::new(),
+            open: None,
+            aggr_time: match basis {
+                Basis::Time(interval) => interval.into(),
+                Basis::Tick(_) => unimplemented!(),
+            },
+        }
+    }
+
+    /// Adds a trade to the column `time` falls in
+    pub fn add_trade(&mut self, time: u64, qty: f32, is_sell: bool) {
+        let bucket = self.bucket_of(time);
+
+        match &mut self.open {
+            Some((open, delta)) if *open == bucket => delta.add(qty, is_sell),
+            // A late print for a column that already moved on
+            Some((open, _)) if bucket < *open => {
+                self.finished.entry(bucket).or_default().add(qty, is_sell);
+            }
+            _ => {
+                let mut delta = BucketDelta::default();
+                delta.add(qty, is_sell);
+
+                if let Some((time, finished)) = self.open.replace((bucket, delta)) {
+                    self.finished.entry(time).or_default().merge(finished);
+                }
+            }
+        }
+    }
+
+    /// Columns from `earliest` to `latest`, oldest first
+    pub fn iter_range(
+        &self,
+        earliest: u64,
+        latest: u64,
+    ) -> impl Iterator<Item = (u64, BucketDelta)> + '_ {
+        let open = self
+            .open
+            .filter(|(time, _)| (earliest..=latest).contains(time));
+
+        self.finished
+            .range(earliest..=latest)
+            .map(|(time, delta)| (*time, *delta))
+            .filter(move |(time, _)| open.is_none_or(|(open, _)| *time < open))
+            .chain(open.map(|(time, delta)| {
+                // Restored columns may overlap the one being filled
+                let restored = self.finished.get(&time).copied().unwrap_or_default();
+                (
+                    time,
+                    BucketDelta {
+                        buy: delta.buy + restored.buy,
+                        sell: delta.sell + restored.sell,
+                    },
+                )
+            }))
+    }
+
+    /// The column `time` falls in, if it saw any trade
+    pub fn at(&self, time: u64) -> Option<BucketDelta> {
+        let bucket = self.bucket_of(time);
+        self.iter_range(bucket, bucket)
+            .next()
+            .map(|(_, delta)| delta)
+    }
+
+    pub fn cleanup(&mut self, oldest_time: u64) {
+        self.finished = self.finished.split_off(&oldest_time);
+    }
+
+    pub fn aggr_time(&self) -> u64 {
+        self.aggr_time
+    }
+
+    /// Every column at or after `oldest_time`, oldest first, the open one included
+    pub(super) fn columns_since(&self, oldest_time: u64) -> Vec<(u64, BucketDelta)> {
+        self.iter_range(oldest_time, u64::MAX).collect()
+    }
+
+    /// Replaces the finished columns, the open one keeps filling
+    pub(super) fn set_finished(&mut self, columns: BTreeMap<u64, BucketDelta>) {
+        self.finished = columns;
+    }
+
+    fn bucket_of(&self, time: u64) -> u64 {
+        if self.aggr_time == 0 {
+            return time;
+        }
+        (time / self.aggr_time) * self.aggr_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::Timeframe;
+
+    fn history() -> DeltaHistory {
+        DeltaHistory::new(Basis::Time(Timeframe::MS100))
+    }
+
+    fn columns(history: &DeltaHistory) -> Vec<(u64, f32, f32)> {
+        history
+            .iter_range(0, u64::MAX)
+            .map(|(time, delta)| (time, delta.delta(), delta.volume()))
+            .collect()
+    }
+
+    #[test]
+    fn trades_sum_per_column() {
+        let mut history = history();
+
+        history.add_trade(1_000, 2.0, false);
+        history.add_trade(1_050, 0.5, true);
+        history.add_trade(1_100, 1.0, true);
+        // Late print for the first column
+        history.add_trade(1_099, 1.0, false);
+
+        assert_eq!(
+            columns(&history),
+            vec![(1_000, 2.5, 3.5), (1_100, -1.0, 1.0)]
+        );
+        assert_eq!(history.at(1_180).map(|d| d.sell), Some(1.0));
+        assert_eq!(history.at(1_200), None);
+    }
+
+    #[test]
+    fn columns_after_a_gap_stay_aligned() {
+        let mut history = history();
+
+        history.add_trade(1_030, 1.0, false);
+        // Reconnected a while later, mid-interval
+        history.add_trade(7_777, 1.0, false);
+        history.add_trade(7_845, 1.0, true);
+
+        let times: Vec<_> = columns(&history).iter().map(|(time, ..)| *time).collect();
+        assert_eq!(times, vec![1_000, 7_700, 7_800]);
+        assert!(times.iter().all(|time| time % history.aggr_time() == 0));
+    }
+
+    #[test]
+    fn restored_columns_merge_with_live_ones() {
+        let mut history = history();
+        history.set_finished(BTreeMap::from([
+            (
+                900,
+                BucketDelta {
+                    buy: 1.0,
+                    sell: 0.0,
+                },
+            ),
+            (
+                1_000,
+                BucketDelta {
+                    buy: 0.0,
+                    sell: 3.0,
+                },
+            ),
+        ]));
+
+        history.add_trade(1_010, 1.0, false);
+        assert_eq!(columns(&history), vec![(900, 1.0, 1.0), (1_000, -2.0, 4.0)]);
+
+        history.add_trade(1_100, 1.0, false);
+        assert_eq!(
+            columns(&history),
+            vec![(900, 1.0, 1.0), (1_000, -2.0, 4.0), (1_100, 1.0, 1.0)]
+        );
+
+        history.cleanup(1_000);
+        assert_eq!(columns(&history).len(), 2);
+    }
+}
@@ -0,0 +1,715 @@
+//! Rolling on-disk copy of a heatmap's depth history and its footer's traded delta, so
+//! reopening a layout doesn't start empty.
+//!
+//! One file per ticker + tick size + aggregation interval, changing any of them simply reads
+//! (and eventually overwrites) a different file. Files are plain little-endian records:
+//!
+//! ```text
+//! magic "FSHM" | version u8 | aggr_time u64 | tick_units i64
+//! per chunk: chunk_len u32 | level_count u32
+//!   per level: price_units i64 | run_count u32
+//!     per run: start_time u64 | until_time u64 | qty f32 | is_bid u8
+//!   column_count u32
+//!     per column: time u64 | buy f32 | sell f32
+//! ```
+//!
+//! Each save appends a chunk with only what changed since the previous one. A run or column
+//! saved again replaces what earlier chunks hold for it, and a chunk cut short by a crash is
+//! ignored. All writes go through the one [`HistoryWriter`] thread, which also compacts each file
+//! every [`COMPACT_INTERVAL`] into a single chunk without what fell out of the window.
+
+use super::delta::{BucketDelta, DeltaHistory};
+use super::{HistoricalDepth, OrderRun};
+use exchange::Ticker;
+use exchange::util::{Price, PriceStep};
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8; 4] = b"FSHM";
+const VERSION: u8 = 1;
+
+const HEADER_LEN: usize = 4 + 1 + 8 + 8;
+const RUN_LEN: usize = 8 + 8 + 4 + 1;
+const COLUMN_LEN: usize = 8 + 4 + 4;
+
+pub const DEFAULT_WINDOW_MINUTES: u16 = 60;
+pub const WINDOW_MINUTES_RANGE: std::ops::RangeInclusive<u16> = 30..=120;
+
+/// How often a live heatmap appends to its history file
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often a file is rewritten without what fell out of the window
+pub const COMPACT_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Identifies which depth history a file holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistoryKey {
+    pub ticker: Ticker,
+    pub tick_size: PriceStep,
+    pub aggr_time: u64,
+}
+
+impl HistoryKey {
+    pub fn path(&self) -> PathBuf {
+        let symbol: String = self
+            .ticker
+            .symbol_and_exchange_string()
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+
+        // Each MT5 connection's book of a symbol is its own
+        let connection = self
+            .ticker
+            .connection()
+            .map_or_else(String::new, |id| format!("-{id}"));
+
+        crate::data_path(None).join("heatmap").join(format!(
+            "{symbol}{connection}-{}-{}.bin",
+            self.tick_size.units, self.aggr_time
+        ))
+    }
+
+    fn holds(&self, contents: &Contents) -> bool {
+        contents.aggr_time == self.aggr_time && contents.tick_units == self.tick_size.units
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum HistoryError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Corrupted heatmap history: {0}")]
+    Corrupted(&'static str),
+    #[error("Heatmap history was written for a different tick size or interval")]
+    KeyMismatch,
+}
+
+fn header(version: u8, aggr_time: u64, tick_units: i64) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(HEADER_LEN);
+    buf.extend_from_slice(MAGIC);
+    buf.push(version);
+    buf.extend_from_slice(&aggr_time.to_le_bytes());
+    buf.extend_from_slice(&tick_units.to_le_bytes());
+    buf
+}
+
+/// Appends a chunk of `levels` and `columns` to `buf`, with its length in front
+fn write_chunk(
+    buf: &mut Vec<u8>,
+    levels: &[(Price, Vec<&OrderRun>)],
+    columns: &[(u64, BucketDelta)],
+) {
+    let len_at = buf.len();
+    buf.extend_from_slice(&0u32.to_le_bytes());
+
+    buf.extend_from_slice(&(levels.len() as u32).to_le_bytes());
+    for (price, runs) in levels {
+        buf.extend_from_slice(&price.units.to_le_bytes());
+        buf.extend_from_slice(&(runs.len() as u32).to_le_bytes());
+
+        for run in runs {
+            buf.extend_from_slice(&run.start_time.to_le_bytes());
+            buf.extend_from_slice(&run.until_time.to_le_bytes());
+            buf.extend_from_slice(&run.qty.to_le_bytes());
+            buf.push(u8::from(run.is_bid));
+        }
+    }
+
+    buf.extend_from_slice(&(columns.len() as u32).to_le_bytes());
+    for (time, delta) in columns {
+        buf.extend_from_slice(&time.to_le_bytes());
+        buf.extend_from_slice(&delta.buy.to_le_bytes());
+        buf.extend_from_slice(&delta.sell.to_le_bytes());
+    }
+
+    let chunk_len = (buf.len() - len_at - 4) as u32;
+    buf[len_at..len_at + 4].copy_from_slice(&chunk_len.to_le_bytes());
+}
+
+/// What a file holds once its chunks are merged
+#[derive(Debug, Default)]
+struct Contents {
+    aggr_time: u64,
+    tick_units: i64,
+    /// Runs by start and side, later chunks replacing earlier ones
+    levels: BTreeMap<Price, BTreeMap<(u64, bool), OrderRun>>,
+    columns: BTreeMap<u64, BucketDelta>,
+}
+
+impl Contents {
+    fn decode(bytes: &[u8]) -> Result<Self, HistoryError> {
+        let mut reader = Reader { bytes, pos: 0 };
+
+        if reader.take(4)? != MAGIC {
+            return Err(HistoryError::Corrupted("bad magic"));
+        }
+        let version = reader.take(1)?[0];
+        if version != VERSION {
+            return Err(HistoryError::Corrupted("unsupported version"));
+        }
+
+        let mut contents = Contents {
+            aggr_time: reader.u64()?,
+            tick_units: reader.i64()?,
+            ..Contents::default()
+        };
+
+        while reader.remaining() >= 4 {
+            let chunk_len = reader.u32()? as usize;
+            // Cut short by a crash while appending, what came before is whole
+            if reader.remaining() < chunk_len {
+                break;
+            }
+
+            let mut chunk = Reader {
+                bytes: reader.take(chunk_len)?,
+                pos: 0,
+            };
+            contents.read_chunk(&mut chunk)?;
+            if chunk.remaining() != 0 {
+                return Err(HistoryError::Corrupted("trailing bytes in chunk"));
+            }
+        }
+
+        Ok(contents)
+    }
+
+    fn read_chunk(&mut self, reader: &mut Reader) -> Result<(), HistoryError> {
+        let level_count = reader.u32()?;
+
+        for _ in 0..level_count {
+            let price = Price::from_units(reader.i64()?);
+            let run_count = reader.u32()? as usize;
+
+            if reader.remaining() < run_count * RUN_LEN {
+                return Err(HistoryError::Corrupted("truncated runs"));
+            }
+
+            let runs = self.levels.entry(price).or_default();
+            for _ in 0..run_count {
+                let run = OrderRun {
+                    start_time: reader.u64()?,
+                    until_time: reader.u64()?,
+                    qty: f32::from_le_bytes(reader.array()?),
+                    is_bid: reader.take(1)?[0] != 0,
+                };
+
+                if run.until_time < run.start_time || !run.qty.is_finite() {
+                    return Err(HistoryError::Corrupted("invalid run"));
+                }
+                runs.insert((run.start_time, run.is_bid), run);
+            }
+        }
+
+        let column_count = reader.u32()? as usize;
+        if reader.remaining() < column_count * COLUMN_LEN {
+            return Err(HistoryError::Corrupted("truncated delta columns"));
+        }
+
+        for _ in 0..column_count {
+            let time = reader.u64()?;
+            let delta = BucketDelta {
+                buy: f32::from_le_bytes(reader.array()?),
+                sell: f32::from_le_bytes(reader.array()?),
+            };
+
+            if !delta.buy.is_finite() || !delta.sell.is_finite() {
+                return Err(HistoryError::Corrupted("invalid delta column"));
+            }
+            self.columns.insert(time, delta);
+        }
+
+        Ok(())
+    }
+
+    /// Drops the runs that ended and the columns that started before `oldest_time`
+    fn prune(&mut self, oldest_time: u64) {
+        for runs in self.levels.values_mut() {
+            runs.retain(|_, run| run.until_time >= oldest_time);
+        }
+        self.levels.retain(|_, runs| !runs.is_empty());
+        self.columns = self.columns.split_off(&oldest_time);
+    }
+
+    /// The whole file again, as a single chunk
+    fn encode(&self) -> Vec<u8> {
+        let levels: Vec<(Price, Vec<&OrderRun>)> = self
+            .levels
+            .iter()
+            .map(|(price, runs)| (*price, runs.values().collect()))
+            .collect();
+        let columns: Vec<(u64, BucketDelta)> = self
+            .columns
+            .iter()
+            .map(|(time, delta)| (*time, *delta))
+            .collect();
+
+        let mut buf = header(VERSION, self.aggr_time, self.tick_units);
+        write_chunk(&mut buf, &levels, &columns);
+        buf
+    }
+}
+
+impl HistoricalDepth {
+    /// A chunk of the runs still going at or after `since` and the delta columns from then on,
+    /// what changed since a save made at `since`
+    pub fn encode_chunk(&self, deltas: &DeltaHistory, since: u64) -> Vec<u8> {
+        let levels: Vec<(Price, Vec<&OrderRun>)> = self
+            .price_levels
+            .iter()
+            .map(|(price, runs)| {
+                (
+                    *price,
+                    runs.iter()
+                        .filter(|run| run.until_time >= since)
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .filter(|(_, runs)| !runs.is_empty())
+            .collect();
+
+        // From the column `since` falls in, it may have filled up since
+        let columns = deltas.columns_since(since - since % deltas.aggr_time().max(1));
+
+        let run_count: usize = levels.iter().map(|(_, runs)| runs.len()).sum();
+        let mut buf = Vec::with_capacity(
+            12 + levels.len() * 12 + run_count * RUN_LEN + columns.len() * COLUMN_LEN,
+        );
+        write_chunk(&mut buf, &levels, &columns);
+        buf
+    }
+
+    /// Restores runs and delta columns from `bytes` into `self` and `deltas`, dropping what
+    /// ended before `oldest_time`.
+    ///
+    /// Both are left untouched when the bytes don't decode or belong to another key.
+    pub fn restore(
+        &mut self,
+        deltas: &mut DeltaHistory,
+        bytes: &[u8],
+        oldest_time: u64,
+    ) -> Result<(), HistoryError> {
+        let mut contents = Contents::decode(bytes)?;
+        if contents.aggr_time != self.aggr_time || contents.tick_units != self.tick_size.units {
+            return Err(HistoryError::KeyMismatch);
+        }
+        contents.prune(oldest_time);
+
+        self.price_levels = contents
+            .levels
+            .into_iter()
+            .map(|(price, runs)| (price, runs.into_values().collect()))
+            .collect();
+        deltas.set_finished(contents.columns);
+        Ok(())
+    }
+
+    /// End time of the most recent run, if any
+    pub fn latest_time(&self) -> Option<u64> {
+        self.price_levels
+            .values()
+            .filter_map(|runs| runs.last().map(|run| run.until_time))
+            .max()
+    }
+}
+
+/// Loads the history for `key` into `depth` and `deltas`, a corrupted file is deleted and
+/// otherwise ignored
+pub fn load(
+    key: &HistoryKey,
+    depth: &mut HistoricalDepth,
+    deltas: &mut DeltaHistory,
+    oldest_time: u64,
+) {
+    let path = key.path();
+
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+        Err(e) => {
+            log::warn!("Failed to read heatmap history {}: {e}", path.display());
+            return;
+        }
+    };
+
+    if let Err(e) = depth.restore(deltas, &bytes, oldest_time) {
+        log::warn!("Discarding heatmap history {}: {e}", path.display());
+
+        if let Err(e) = std::fs::remove_file(&path) {
+            log::error!("Failed to remove {}: {e}", path.display());
+        }
+    }
+}
+
+enum Job {
+    Append {
+        key: HistoryKey,
+        chunk: Vec<u8>,
+        oldest_time: u64,
+    },
+    Flush(mpsc::Sender<()>),
+}
+
+/// The one thread writing heatmap history files, so appends and compactions of a file never
+/// overlap
+struct HistoryWriter {
+    jobs: mpsc::Sender<Job>,
+}
+
+static WRITER: LazyLock<HistoryWriter> = LazyLock::new(HistoryWriter::spawn);
+
+impl HistoryWriter {
+    fn spawn() -> Self {
+        let (jobs, receiver) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("heatmap-history-writer".to_string())
+            .spawn(move || run(&receiver))
+            .expect("Failed to spawn the heatmap history writer");
+
+        Self { jobs }
+    }
+}
+
+/// Queues `chunk` to be appended to the file of `key` and returns right away. `oldest_time` is
+/// where the window starts, for compacting.
+pub fn append(key: HistoryKey, chunk: Vec<u8>, oldest_time: u64) {
+    let job = Job::Append {
+        key,
+        chunk,
+        oldest_time,
+    };
+    if WRITER.jobs.send(job).is_err() {
+        log::error!("The heatmap history writer stopped");
+    }
+}
+
+/// Blocks until every queued append is written
+pub fn flush() {
+    let (done, wait) = mpsc::channel();
+    if WRITER.jobs.send(Job::Flush(done)).is_ok() {
+        wait.recv().ok();
+    }
+}
+
+fn run(jobs: &mpsc::Receiver<Job>) {
+    // Files compacted this session and when, the first append of a session compacts as well
+    let mut compacted: HashMap<PathBuf, Instant> = HashMap::new();
+
+    while let Ok(job) = jobs.recv() {
+        match job {
+            Job::Append {
+                key,
+                chunk,
+                oldest_time,
+            } => {
+                let path = key.path();
+                let due = compacted
+                    .get(&path)
+                    .is_none_or(|at| at.elapsed() >= COMPACT_INTERVAL);
+                if due {
+                    compacted.insert(path.clone(), Instant::now());
+                }
+
+                if let Err(e) = write(&key, &path, &chunk, oldest_time, due) {
+                    log::error!("Failed to save heatmap history {}: {e}", path.display());
+                    // A partly appended chunk is dropped by compacting before the next one
+                    compacted.remove(&path);
+                }
+            }
+            Job::Flush(done) => {
+                done.send(()).ok();
+            }
+        }
+    }
+}
+
+/// Appends `chunk` to `path`, compacting the file first when `compact` or when it's missing or
+/// not a history file
+fn write(
+    key: &HistoryKey,
+    path: &Path,
+    chunk: &[u8],
+    oldest_time: u64,
+    compact: bool,
+) -> Result<(), HistoryError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let current = std::fs::File::open(path).and_then(|mut file| {
+        let mut start = [0u8; 5];
+        std::io::Read::read_exact(&mut file, &mut start)?;
+        Ok(start[..4] == *MAGIC && start[4] == VERSION)
+    });
+    if compact || !current.unwrap_or(false) {
+        compact_file(key, path, oldest_time)?;
+    }
+
+    let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+    file.write_all(chunk)?;
+    Ok(())
+}
+
+/// Rewrites `path` as a single chunk without what ended before `oldest_time`, through a
+/// temp file of its own so a crash mid-write can't leave a torn file. A file that's missing or
+/// doesn't decode starts over empty.
+fn compact_file(key: &HistoryKey, path: &Path, oldest_time: u64) -> Result<(), HistoryError> {
+    static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let contents = match std::fs::read(path) {
+        Ok(bytes) => match Contents::decode(&bytes) {
+            Ok(mut contents) if key.holds(&contents) => {
+                contents.prune(oldest_time);
+                Some(contents)
+            }
+            Ok(_) => None,
+            Err(e) => {
+                log::warn!("Discarding heatmap history {}: {e}", path.display());
+                None
+            }
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e.into()),
+    };
+    let bytes = contents.map_or_else(
+        || header(VERSION, key.aggr_time, key.tick_size.units),
+        |contents| contents.encode(),
+    );
+
+    let tmp = path.with_extension(format!(
+        "bin.{}-{}.tmp",
+        std::process::id(),
+        TMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&tmp, bytes)?;
+    std::fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], HistoryError> {
+        if self.remaining() < len {
+            return Err(HistoryError::Corrupted("unexpected end of file"));
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], HistoryError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn u32(&mut self) -> Result<u32, HistoryError> {
+        self.array().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Result<u64, HistoryError> {
+        self.array().map(u64::from_le_bytes)
+    }
+
+    fn i64(&mut self) -> Result<i64, HistoryError> {
+        self.array().map(i64::from_le_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart::Basis;
+    use exchange::Timeframe;
+    use exchange::adapter::Exchange;
+
+    fn depth_with_runs() -> HistoricalDepth {
+        let mut depth =
+            HistoricalDepth::new(0.0, PriceStep::from_f32(0.5), Basis::Time(Timeframe::MS500));
+
+        let mut older = vec![OrderRun::new(1_000, 500, 2.0, true)];
+        older.push(OrderRun::new(9_000, 500, 3.0, true));
+        depth.price_levels.insert(Price::from_f32(100.0), older);
+        depth.price_levels.insert(
+            Price::from_f32(101.0),
+            vec![OrderRun::new(1_000, 500, 1.0, false)],
+        );
+
+        depth
+    }
+
+    fn empty_depth() -> HistoricalDepth {
+        HistoricalDepth::new(0.0, PriceStep::from_f32(0.5), Basis::Time(Timeframe::MS500))
+    }
+
+    fn no_deltas() -> DeltaHistory {
+        DeltaHistory::new(Basis::Time(Timeframe::MS500))
+    }
+
+    /// A file holding everything of `depth` and `deltas` in one chunk
+    fn file_of(depth: &HistoricalDepth, deltas: &DeltaHistory) -> Vec<u8> {
+        let mut bytes = header(VERSION, depth.aggr_time, depth.tick_size.units);
+        bytes.extend(depth.encode_chunk(deltas, 0));
+        bytes
+    }
+
+    fn scratch_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "flowsurface-heatmap-{name}-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("history.bin")
+    }
+
+    fn key() -> HistoryKey {
+        HistoryKey {
+            ticker: Ticker::new("BTCUSDT", Exchange::BinanceLinear),
+            tick_size: PriceStep::from_f32(0.5),
+            aggr_time: 500,
+        }
+    }
+
+    #[test]
+    fn roundtrip_prunes_old_runs() {
+        let bytes = file_of(&depth_with_runs(), &no_deltas());
+
+        let mut restored = empty_depth();
+        restored.restore(&mut no_deltas(), &bytes, 5_000).unwrap();
+
+        assert_eq!(restored.price_levels.len(), 1);
+        assert_eq!(restored.price_levels[&Price::from_f32(100.0)].len(), 1);
+        assert_eq!(restored.latest_time(), Some(9_500));
+    }
+
+    #[test]
+    fn different_grouping_is_rejected() {
+        let bytes = file_of(&depth_with_runs(), &no_deltas());
+
+        let mut other =
+            HistoricalDepth::new(0.0, PriceStep::from_f32(1.0), Basis::Time(Timeframe::MS500));
+        assert!(matches!(
+            other.restore(&mut no_deltas(), &bytes, 0),
+            Err(HistoryError::KeyMismatch)
+        ));
+    }
+
+    #[test]
+    fn corrupted_bytes_leave_depth_untouched() {
+        let mut bytes = file_of(&depth_with_runs(), &no_deltas());
+        // The first run's until time, before its start
+        let until_at = HEADER_LEN + 4 + 4 + 8 + 4 + 8;
+        bytes[until_at..until_at + 8].copy_from_slice(&0u64.to_le_bytes());
+
+        let mut target = depth_with_runs();
+        let before = target.clone();
+
+        assert!(target.restore(&mut no_deltas(), &bytes, 0).is_err());
+        assert!(target.restore(&mut no_deltas(), b"nope", 0).is_err());
+        assert_eq!(target, before);
+    }
+
+    #[test]
+    fn appended_chunks_replace_what_earlier_ones_saved() {
+        let mut depth = depth_with_runs();
+        let mut bytes = file_of(&depth, &no_deltas());
+
+        // The newest run went on, and a level appeared
+        let saved_at = depth.latest_time().unwrap();
+        depth.price_levels.get_mut(&Price::from_f32(100.0)).unwrap()[1].until_time = 12_000;
+        depth.price_levels.insert(
+            Price::from_f32(99.5),
+            vec![OrderRun::new(11_000, 500, 5.0, true)],
+        );
+        let chunk = depth.encode_chunk(&no_deltas(), saved_at);
+        bytes.extend(&chunk);
+
+        let mut restored = empty_depth();
+        restored.restore(&mut no_deltas(), &bytes, 0).unwrap();
+        assert_eq!(restored.price_levels, depth.price_levels);
+
+        // Only the runs still going were appended
+        let mut appended = Contents::default();
+        appended
+            .read_chunk(&mut Reader {
+                bytes: &chunk,
+                pos: 4,
+            })
+            .unwrap();
+        assert_eq!(
+            appended.levels.values().map(BTreeMap::len).sum::<usize>(),
+            2
+        );
+
+        // A chunk cut short by a crash is dropped, the ones before it are kept
+        bytes.extend(&chunk[..chunk.len() - 5]);
+        let mut restored = empty_depth();
+        restored.restore(&mut no_deltas(), &bytes, 0).unwrap();
+        assert_eq!(restored.price_levels, depth.price_levels);
+    }
+
+    #[test]
+    fn delta_columns_roundtrip() {
+        let mut deltas = no_deltas();
+        deltas.add_trade(1_000, 2.0, false);
+        deltas.add_trade(1_200, 1.0, true);
+        deltas.add_trade(9_000, 4.0, true);
+
+        let bytes = file_of(&depth_with_runs(), &deltas);
+
+        let mut restored_deltas = no_deltas();
+        let mut restored = empty_depth();
+        restored
+            .restore(&mut restored_deltas, &bytes, 5_000)
+            .unwrap();
+
+        let columns: Vec<_> = restored_deltas
+            .iter_range(0, u64::MAX)
+            .map(|(time, delta)| (time, delta.delta()))
+            .collect();
+        assert_eq!(columns, vec![(9_000, -4.0)]);
+    }
+
+    #[test]
+    fn compacting_drops_what_left_the_window() {
+        let path = scratch_file("compact");
+        let chunk = depth_with_runs().encode_chunk(&no_deltas(), 0);
+
+        let run_count = |path: &Path| {
+            let contents = Contents::decode(&std::fs::read(path).unwrap()).unwrap();
+            contents.levels.values().map(BTreeMap::len).sum::<usize>()
+        };
+
+        // Not due, but a missing file gets its header before the first chunk
+        write(&key(), &path, &chunk, 5_000, false).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap()[4], VERSION);
+        assert_eq!(run_count(&path), 3);
+
+        // Appending alone keeps runs that left the window, compacting drops them
+        write(&key(), &path, &[], 5_000, false).unwrap();
+        assert_eq!(run_count(&path), 3);
+        write(&key(), &path, &[], 5_000, true).unwrap();
+        assert_eq!(run_count(&path), 1);
+
+        let leftovers = std::fs::read_dir(path.parent().unwrap()).unwrap().count();
+        assert_eq!(leftovers, 1, "no temp files left behind");
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
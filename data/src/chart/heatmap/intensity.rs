@@ -0,0 +1,323 @@
+//! How opaque depth cells are drawn for their size.
+//!
+//! Worked out per column in view when drawing, so changing it never touches the recorded history.
+//! A run of resting size is drawn as the [`Segment`]s it gets split into, one per stretch of
+//! columns shaded alike, so a run shaded the same all along is still a single rectangle.
+
+use serde::{Deserialize, Serialize};
+
+pub const HALF_LIFE_RANGE: std::ops::RangeInclusive<u16> = 5..=600;
+const DEFAULT_HALF_LIFE_SECS: u16 = 60;
+
+/// Opacity levels segments are rounded to before neighbouring ones are merged
+const LEVELS: f32 = 64.0;
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Intensity {
+    /// Size against the largest in view
+    #[default]
+    Linear,
+    /// As `Linear`, fading by half every `half_life_secs` back from the right edge of the view
+    Decay { half_life_secs: u16 },
+    /// Size against the largest of its own column
+    PerColumn,
+}
+
+impl Intensity {
+    pub fn decay() -> Self {
+        Intensity::Decay {
+            half_life_secs: DEFAULT_HALF_LIFE_SECS,
+        }
+    }
+}
+
+/// Stretch of a run drawn at one opacity
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Segment {
+    pub start: u64,
+    pub until: u64,
+    pub alpha: f32,
+}
+
+/// Opacity per size of each column from `earliest` to `latest`, `interval` ms wide. Columns
+/// are merged when there are more than `max_columns`, as there's no point in shading them
+/// apart more finely than the pixels they're drawn on.
+pub struct Scales {
+    first: u64,
+    interval: u64,
+    /// `None` scales every column alike
+    columns: Option<Vec<f32>>,
+    /// Columns before this one are too faded to draw
+    faded: usize,
+    uniform: f32,
+}
+
+impl Scales {
+    /// `runs` are the `(start, until, qty)` drawn, only read for [`Intensity::PerColumn`]
+    pub fn new(
+        intensity: Intensity,
+        earliest: u64,
+        latest: u64,
+        interval: u64,
+        max_columns: usize,
+        max_qty: f32,
+        runs: impl Iterator<Item = (u64, u64, f32)>,
+    ) -> Self {
+        let interval = interval.max(1);
+        let columns_in_view = latest.saturating_sub(earliest).div_ceil(interval);
+        let interval = interval * columns_in_view.div_ceil(max_columns.max(1) as u64).max(1);
+        let first = earliest - earliest % interval;
+        let count = (latest.saturating_sub(first)).div_ceil(interval).max(1) as usize;
+        let uniform = if max_qty > 0.0 { 1.0 / max_qty } else { 0.0 };
+
+        let mut faded = 0;
+        let columns = match intensity {
+            Intensity::Linear => None,
+            Intensity::Decay { half_life_secs } => {
+                // One power for the newest column and one for the step, the rest multiply
+                let half_life_ms = f32::from(half_life_secs.max(1)) * 1000.0;
+                let newest_age = latest.saturating_sub(first + (count as u64 - 1) * interval);
+                let step = 0.5f32.powf(interval as f32 / half_life_ms);
+
+                let mut scales = vec![0.0; count];
+                let mut weight = 0.5f32.powf(newest_age as f32 / half_life_ms) * uniform;
+                for scale in scales.iter_mut().rev() {
+                    *scale = weight;
+                    weight *= step;
+                }
+                // Even the largest size rounds to nothing there
+                faded = scales.partition_point(|scale| scale * max_qty * LEVELS + 0.5 < 1.0);
+                Some(scales)
+            }
+            Intensity::PerColumn => {
+                let mut max = vec![0.0f32; count];
+                for (start, until, qty) in runs {
+                    let (from, to) = Self::span(first, interval, count, start, until);
+                    for column_max in &mut max[from..to] {
+                        *column_max = column_max.max(qty);
+                    }
+                }
+                Some(
+                    max.into_iter()
+                        .map(|max| if max > 0.0 { 1.0 / max } else { 0.0 })
+                        .collect(),
+                )
+            }
+        };
+
+        Self {
+            first,
+            interval,
+            columns,
+            faded,
+            uniform,
+        }
+    }
+
+    /// Columns `start..until` covers, clamped to the ones in view
+    fn span(first: u64, interval: u64, count: usize, start: u64, until: u64) -> (usize, usize) {
+        let index = |time: u64| (time.saturating_sub(first) / interval) as usize;
+        let from = index(start).min(count);
+        let to = if until > start {
+            (index(until - 1) + 1).min(count)
+        } else {
+            from
+        };
+        (from, to)
+    }
+
+    /// `qty` resting from `start` until `until`, split where its opacity changes
+    pub fn segments(&self, start: u64, until: u64, qty: f32, mut draw: impl FnMut(Segment)) {
+        let Some(columns) = &self.columns else {
+            draw(Segment {
+                start,
+                until,
+                alpha: (qty * self.uniform).min(1.0),
+            });
+            return;
+        };
+
+        let (from, to) = Self::span(self.first, self.interval, columns.len(), start, until);
+        let from = from.max(self.faded).min(to);
+        if from == to {
+            return;
+        }
+
+        let mut flush = |column: usize, end: usize, level: u32| {
+            if level > 0 {
+                draw(Segment {
+                    start: (self.first + column as u64 * self.interval).max(start),
+                    until: (self.first + end as u64 * self.interval).min(until),
+                    alpha: level as f32 / LEVELS,
+                });
+            }
+        };
+        // Rounded by truncating half a level up, as `round` is slow in this loop
+        let level = |scale: f32| ((qty * scale * LEVELS + 0.5) as u32).min(LEVELS as u32);
+
+        let (mut segment_start, mut segment_level) = (from, level(columns[from]));
+        for (column, &scale) in columns.iter().enumerate().take(to).skip(from + 1) {
+            let level = level(scale);
+            if level != segment_level {
+                flush(segment_start, column, segment_level);
+                (segment_start, segment_level) = (column, level);
+            }
+        }
+        flush(segment_start, to, segment_level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: u64 = 1000;
+
+    fn collect(scales: &Scales, start: u64, until: u64, qty: f32) -> Vec<Segment> {
+        let mut segments = vec![];
+        scales.segments(start, until, qty, |segment| segments.push(segment));
+        segments
+    }
+
+    #[test]
+    fn decay_halves_opacity_every_half_life_back_from_the_view_edge() {
+        let scales = Scales::new(
+            Intensity::Decay { half_life_secs: 10 },
+            0,
+            30 * SECOND,
+            10 * SECOND,
+            100,
+            4.0,
+            std::iter::empty(),
+        );
+
+        let segments = collect(&scales, 0, 30 * SECOND, 4.0);
+        let alphas = segments.iter().map(|s| s.alpha).collect::<Vec<_>>();
+        // Columns are aged by their start, the newest one is a half-life old at the edge
+        assert_eq!(alphas, [0.125, 0.25, 0.5]);
+        assert_eq!((segments[0].start, segments[2].until), (0, 30 * SECOND));
+
+        // Linear keeps the run whole
+        let linear = Scales::new(
+            Intensity::Linear,
+            0,
+            30 * SECOND,
+            10 * SECOND,
+            100,
+            4.0,
+            std::iter::empty(),
+        );
+        assert_eq!(
+            collect(&linear, 5 * SECOND, 25 * SECOND, 2.0),
+            [Segment {
+                start: 5 * SECOND,
+                until: 25 * SECOND,
+                alpha: 0.5
+            }]
+        );
+    }
+
+    #[test]
+    fn per_column_scales_each_column_by_its_own_largest() {
+        let runs = [(0, 20 * SECOND, 2.0), (10 * SECOND, 20 * SECOND, 8.0)];
+        let scales = Scales::new(
+            Intensity::PerColumn,
+            0,
+            20 * SECOND,
+            10 * SECOND,
+            100,
+            8.0,
+            runs.into_iter(),
+        );
+
+        let segments = collect(&scales, 0, 20 * SECOND, 2.0);
+        let alphas = segments.iter().map(|s| s.alpha).collect::<Vec<_>>();
+        assert_eq!(alphas, [1.0, 0.25]);
+        assert_eq!(
+            collect(&scales, 10 * SECOND, 20 * SECOND, 8.0)[0].alpha,
+            1.0
+        );
+
+        // With room for a single column both are shaded against the larger run
+        let merged = Scales::new(
+            Intensity::PerColumn,
+            0,
+            20 * SECOND,
+            10 * SECOND,
+            1,
+            8.0,
+            runs.into_iter(),
+        );
+        let segments = collect(&merged, 0, 20 * SECOND, 2.0);
+        assert_eq!((segments.len(), segments[0].alpha), (1, 0.25));
+    }
+
+    #[test]
+    fn columns_shaded_alike_stay_one_segment() {
+        let scales = Scales::new(
+            Intensity::PerColumn,
+            0,
+            50 * SECOND,
+            SECOND,
+            100,
+            1.0,
+            [(0, 50 * SECOND, 1.0)].into_iter(),
+        );
+        assert_eq!(
+            collect(&scales, 2500, 40 * SECOND, 1.0),
+            [Segment {
+                start: 2500,
+                until: 40 * SECOND,
+                alpha: 1.0
+            }]
+        );
+    }
+
+    /// `cargo test --release -p flowsurface-data intensity_timing -- --ignored --nocapture`
+    #[test]
+    #[ignore = "timing, meaningful in release builds only"]
+    fn intensity_timing() {
+        // A full default history window of 100ms columns over 200 levels, each level's size
+        // changing every few seconds, drawn on a pane as wide as a 4K screen
+        let window = u64::from(super::super::history::DEFAULT_WINDOW_MINUTES) * 60 * SECOND;
+        let interval = 100;
+        let runs = (0..200u64)
+            .flat_map(|level| {
+                let every = (2 + level % 7) * SECOND;
+                (0..window / every).map(move |i| (i * every, (i + 1) * every, (level % 13) as f32))
+            })
+            .collect::<Vec<_>>();
+
+        let frame = |intensity| {
+            let started = std::time::Instant::now();
+            let scales = Scales::new(
+                intensity,
+                0,
+                window,
+                interval,
+                3840,
+                12.0,
+                runs.iter().copied(),
+            );
+            let cells: usize = runs
+                .iter()
+                .map(|&(start, until, qty)| {
+                    let mut cells = 0;
+                    scales.segments(start, until, qty, |_| cells += 1);
+                    cells
+                })
+                .sum();
+            (std::hint::black_box(cells), started.elapsed())
+        };
+
+        let (linear_cells, linear) = frame(Intensity::Linear);
+        for intensity in [Intensity::decay(), Intensity::PerColumn] {
+            let (cells, took) = frame(intensity);
+            println!(
+                "{intensity:?} over {} runs: {took:?} and {cells} cells, linear {linear:?} and {linear_cells} cells",
+                runs.len()
+            );
+            assert!(took < std::time::Duration::from_millis(8));
+        }
+    }
+}
@@ -0,0 +1,160 @@
+//! Where liquidity was added to or pulled from the book, per heatmap column.
+//!
+//! Each depth update's level changes are summed into the column its time falls in, the same
+//! buckets [`HistoricalDepth`](super::HistoricalDepth) draws its runs in. Prices are grouped by
+//! the chart's tick size the way the depth runs are, so a change lines up with the level it
+//! changed. Fills show up as pulls, the book can't tell a cancel from a trade.
+
+use super::super::Basis;
+use exchange::depth::DepthChanges;
+use exchange::util::{Price, PriceStep};
+
+use rustc_hash::FxHashMap;
+use std::collections::BTreeMap;
+
+/// Size added and removed at one price within one column
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LevelChange {
+    pub added: f32,
+    pub pulled: f32,
+}
+
+impl LevelChange {
+    /// Positive when more was added than pulled
+    pub fn net(&self) -> f32 {
+        self.added - self.pulled
+    }
+
+    fn add(&mut self, change: f32) {
+        if change > 0.0 {
+            self.added += change;
+        } else {
+            self.pulled -= change;
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LiquidityHistory {
+    columns: BTreeMap<u64, FxHashMap<Price, LevelChange>>,
+    aggr_time: u64,
+    tick_size: PriceStep,
+}
+
+impl LiquidityHistory {
+    pub fn new(tick_size: PriceStep, basis: Basis) -> Self {
+        Self {
+            columns: BTreeMap::new(),
+            aggr_time: match basis {
+                Basis::Time(interval) => interval.into(),
+                Basis::Tick(_) => unimplemented!(),
+            },
+            tick_size,
+        }
+    }
+
+    /// Adds one update's changes to the column starting at `time`
+    pub fn insert(&mut self, changes: &DepthChanges, time: u64) {
+        if changes.is_empty() {
+            return;
+        }
+
+        let column = self.columns.entry(time).or_default();
+        let sides = [(&changes.bids, true), (&changes.asks, false)];
+
+        for (levels, is_bid) in sides {
+            for (price, change) in levels {
+                let price = price.round_to_side_step(is_bid, self.tick_size);
+                column.entry(price).or_default().add(*change);
+            }
+        }
+    }
+
+    /// Changes of the columns from `earliest` to `latest` between `lowest` and `highest`
+    pub fn iter_range(
+        &self,
+        earliest: u64,
+        latest: u64,
+        highest: Price,
+        lowest: Price,
+    ) -> impl Iterator<Item = (u64, Price, LevelChange)> {
+        self.columns
+            .range(earliest..=latest)
+            .flat_map(move |(time, levels)| {
+                levels
+                    .iter()
+                    .filter(move |(price, _)| **price >= lowest && **price <= highest)
+                    .map(move |(price, change)| (*time, *price, *change))
+            })
+    }
+
+    pub fn cleanup(&mut self, oldest_time: u64) {
+        self.columns = self.columns.split_off(&oldest_time);
+    }
+
+    pub fn aggr_time(&self) -> u64 {
+        self.aggr_time
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn px(units: i64) -> Price {
+        Price::from_units(units * PriceStep::from_f32(0.5).units)
+    }
+
+    fn changes(bids: &[(i64, f32)], asks: &[(i64, f32)]) -> DepthChanges {
+        let side = |levels: &[(i64, f32)]| levels.iter().map(|(p, q)| (px(*p), *q)).collect();
+        DepthChanges {
+            bids: side(bids),
+            asks: side(asks),
+        }
+    }
+
+    #[test]
+    fn changes_sum_per_column_and_level() {
+        let mut history = LiquidityHistory::new(
+            PriceStep::from_f32(0.5),
+            Basis::Time(exchange::Timeframe::MS100),
+        );
+
+        history.insert(&changes(&[(10, 4.0)], &[(12, -1.0)]), 1_000);
+        history.insert(&changes(&[(10, -1.5), (10, 2.0)], &[]), 1_000);
+        history.insert(&changes(&[(9, 3.0)], &[]), 1_100);
+
+        let first: Vec<_> = history
+            .iter_range(1_000, 1_000, px(20), px(0))
+            .map(|(_, price, change)| (price, change))
+            .collect::<std::collections::BTreeMap<_, _>>()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            first,
+            [
+                (
+                    px(10),
+                    LevelChange {
+                        added: 6.0,
+                        pulled: 1.5
+                    }
+                ),
+                (
+                    px(12),
+                    LevelChange {
+                        added: 0.0,
+                        pulled: 1.0
+                    }
+                ),
+            ]
+        );
+        assert_eq!(first[0].1.net(), 4.5);
+
+        // Outside the price range
+        assert_eq!(history.iter_range(1_100, 1_100, px(8), px(0)).count(), 0);
+
+        history.cleanup(1_100);
+        assert_eq!(history.iter_range(0, u64::MAX, px(20), px(0)).count(), 1);
+    }
+}
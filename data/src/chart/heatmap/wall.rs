@@ -0,0 +1,447 @@
+//! Detection of unusually large resting orders ("walls") in the order book.
+//!
+//! A level becomes a wall once its size crosses the configured threshold and stays one until it
+//! drops below `RELEASE_RATIO` of it, so a level hovering around the threshold doesn't flicker.
+//! When a wall goes away, trades printed against it shortly before tell apart a wall that got
+//! consumed from one that was pulled.
+
+use exchange::depth::Depth;
+use exchange::util::{Price, PriceStep};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Share of the threshold a wall has to fall under before it stops being one
+pub const RELEASE_RATIO: f32 = 0.75;
+/// Trades this long before a removal are attributed to it
+pub const TRADE_WINDOW_MS: u64 = 2_000;
+/// Share of the lost size that must have traded for a removal to count as consumed
+const CONSUMED_RATIO: f32 = 0.5;
+/// Weight of the latest book in the rolling average level size
+const AVERAGE_WEIGHT: f32 = 0.05;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+pub enum WallThreshold {
+    /// Times the rolling average level size
+    Multiple(f32),
+    /// Level value in the quote currency
+    Notional(f32),
+}
+
+impl WallThreshold {
+    pub const DEFAULT_MULTIPLE: f32 = 10.0;
+    pub const DEFAULT_NOTIONAL: f32 = 1_000_000.0;
+}
+
+impl PartialEq for WallThreshold {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+impl Eq for WallThreshold {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WallConfig {
+    pub threshold: WallThreshold,
+    pub sound: bool,
+}
+
+impl Default for WallConfig {
+    fn default() -> Self {
+        Self {
+            threshold: WallThreshold::Multiple(WallThreshold::DEFAULT_MULTIPLE),
+            sound: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+impl std::fmt::Display for Side {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Side::Bid => write!(f, "Bid"),
+            Side::Ask => write!(f, "Ask"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WallChange {
+    Appeared,
+    /// Removed after being traded into
+    Consumed,
+    /// Removed without matching trades
+    Pulled,
+}
+
+impl std::fmt::Display for WallChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WallChange::Appeared => write!(f, "appeared"),
+            WallChange::Consumed => write!(f, "consumed"),
+            WallChange::Pulled => write!(f, "pulled"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WallEvent {
+    pub side: Side,
+    pub price: Price,
+    /// Size at the time of the change, the last seen size for removals
+    pub qty: f32,
+    pub change: WallChange,
+    pub time: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Wall {
+    pub qty: f32,
+    pub since: u64,
+}
+
+struct RecentTrade {
+    time: u64,
+    price: Price,
+    is_sell: bool,
+    qty: f32,
+}
+
+#[derive(Default)]
+pub struct WallDetector {
+    walls: BTreeMap<(Side, Price), Wall>,
+    average: Option<f32>,
+    trades: VecDeque<RecentTrade>,
+}
+
+impl WallDetector {
+    pub fn walls(&self) -> impl Iterator<Item = (Side, Price, &Wall)> {
+        self.walls
+            .iter()
+            .map(|((side, price), wall)| (*side, *price, wall))
+    }
+
+    pub fn clear(&mut self) {
+        self.walls.clear();
+        self.average = None;
+        self.trades.clear();
+    }
+
+    /// Feeds one order book update along with the trades since the previous one.
+    ///
+    /// Levels are grouped by `step` the same way the heatmap groups them, `to_quote` converts a
+    /// level's quantity into its quote currency value for the threshold comparison.
+    pub fn update(
+        &mut self,
+        config: &WallConfig,
+        depth: &Depth,
+        trades: &[exchange::Trade],
+        time: u64,
+        step: PriceStep,
+        to_quote: impl Fn(f32, Price) -> f32,
+    ) -> Vec<WallEvent> {
+        for trade in trades {
+            self.trades.push_back(RecentTrade {
+                time: trade.time,
+                price: trade.price,
+                is_sell: trade.is_sell,
+                qty: trade.qty,
+            });
+        }
+        let cutoff = time.saturating_sub(TRADE_WINDOW_MS);
+        while self.trades.front().is_some_and(|t| t.time < cutoff) {
+            self.trades.pop_front();
+        }
+
+        let levels: BTreeMap<(Side, Price), f32> = group_side(&depth.bids, Side::Bid, step)
+            .chain(group_side(&depth.asks, Side::Ask, step))
+            .fold(BTreeMap::new(), |mut acc, (key, qty)| {
+                *acc.entry(key).or_default() += qty;
+                acc
+            });
+
+        if levels.is_empty() {
+            return vec![];
+        }
+
+        let mean = levels
+            .iter()
+            .map(|((_, price), qty)| to_quote(*qty, *price))
+            .sum::<f32>()
+            / levels.len() as f32;
+        let average = match self.average {
+            Some(avg) => avg + (mean - avg) * AVERAGE_WEIGHT,
+            None => mean,
+        };
+        self.average = Some(average);
+
+        let enter = match config.threshold {
+            WallThreshold::Multiple(multiple) => average * multiple,
+            WallThreshold::Notional(notional) => notional,
+        };
+        if !enter.is_finite() || enter <= 0.0 {
+            return vec![];
+        }
+        let exit = enter * RELEASE_RATIO;
+
+        let mut events = vec![];
+
+        // Levels beyond what the book snapshot covers are out of view, not removed
+        let bid_floor = levels.keys().find(|(side, _)| *side == Side::Bid);
+        let ask_ceiling = levels.keys().rev().find(|(side, _)| *side == Side::Ask);
+        let in_view = |side: Side, price: Price| match side {
+            Side::Bid => bid_floor.is_some_and(|(_, floor)| price >= *floor),
+            Side::Ask => ask_ceiling.is_some_and(|(_, ceiling)| price <= *ceiling),
+        };
+
+        let mut removed = vec![];
+        for (&(side, price), wall) in &mut self.walls {
+            match levels.get(&(side, price)) {
+                Some(qty) if to_quote(*qty, price) >= exit => wall.qty = *qty,
+                Some(qty) => removed.push((side, price, wall.qty, Some(*qty))),
+                None if in_view(side, price) => removed.push((side, price, wall.qty, Some(0.0))),
+                None => removed.push((side, price, wall.qty, None)),
+            }
+        }
+
+        for (side, price, last_qty, qty) in removed {
+            self.walls.remove(&(side, price));
+            let Some(qty) = qty else {
+                continue;
+            };
+
+            let change = if self.was_traded_into(side, price, last_qty - qty, step) {
+                WallChange::Consumed
+            } else {
+                WallChange::Pulled
+            };
+            events.push(WallEvent {
+                side,
+                price,
+                qty: last_qty,
+                change,
+                time,
+            });
+        }
+
+        for (&(side, price), &qty) in &levels {
+            if self.walls.contains_key(&(side, price)) || to_quote(qty, price) < enter {
+                continue;
+            }
+
+            self.walls.insert((side, price), Wall { qty, since: time });
+            events.push(WallEvent {
+                side,
+                price,
+                qty,
+                change: WallChange::Appeared,
+                time,
+            });
+        }
+
+        events
+    }
+
+    /// Sells hit bids and buys lift asks, a print through the wall's price means it got taken
+    fn was_traded_into(&self, side: Side, price: Price, lost_qty: f32, step: PriceStep) -> bool {
+        let mut traded = 0.0;
+
+        for trade in &self.trades {
+            let hits_side = match side {
+                Side::Bid => trade.is_sell,
+                Side::Ask => !trade.is_sell,
+            };
+            if !hits_side {
+                continue;
+            }
+
+            let trade_price = trade.price.round_to_side_step(side == Side::Bid, step);
+            let through = match side {
+                Side::Bid => trade_price < price,
+                Side::Ask => trade_price > price,
+            };
+            if through {
+                return true;
+            }
+            if trade_price == price {
+                traded += trade.qty;
+            }
+        }
+
+        traded > 0.0 && traded >= lost_qty * CONSUMED_RATIO
+    }
+}
+
+fn group_side(
+    side: &BTreeMap<Price, f32>,
+    wall_side: Side,
+    step: PriceStep,
+) -> impl Iterator<Item = ((Side, Price), f32)> + '_ {
+    side.iter().map(move |(price, qty)| {
+        let rounded = price.round_to_side_step(wall_side == Side::Bid, step);
+        ((wall_side, rounded), *qty)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn px(price: i64) -> Price {
+        Price::from_units(price * 10i64.pow(Price::PRICE_SCALE as u32))
+    }
+
+    fn step() -> PriceStep {
+        PriceStep { units: px(1).units }
+    }
+
+    fn depth(bids: &[(i64, f32)], asks: &[(i64, f32)]) -> Depth {
+        let side = |levels: &[(i64, f32)]| levels.iter().map(|(p, q)| (px(*p), *q)).collect();
+        Depth {
+            bids: side(bids),
+            asks: side(asks),
+            ..Depth::default()
+        }
+    }
+
+    fn trade(time: u64, price: i64, is_sell: bool, qty: f32) -> exchange::Trade {
+        exchange::Trade {
+            time,
+            is_sell,
+            price: px(price),
+            qty,
+        }
+    }
+
+    const NOTIONAL: WallConfig = WallConfig {
+        threshold: WallThreshold::Notional(100.0),
+        sound: false,
+    };
+
+    fn changes(events: &[WallEvent]) -> Vec<(Side, Price, WallChange)> {
+        events.iter().map(|e| (e.side, e.price, e.change)).collect()
+    }
+
+    #[test]
+    fn hysteresis_keeps_wall_near_threshold() {
+        let mut detector = WallDetector::default();
+        let asks = [(101, 1.0), (110, 1.0)];
+
+        let events = detector.update(
+            &NOTIONAL,
+            &depth(&[(100, 120.0), (99, 1.0)], &asks),
+            &[],
+            1_000,
+            step(),
+            |qty, _| qty,
+        );
+        assert_eq!(
+            changes(&events),
+            vec![(Side::Bid, px(100), WallChange::Appeared)]
+        );
+
+        // Dipping below the threshold but above the release level is still a wall
+        for (time, qty) in [(1_100, 90.0), (1_200, 105.0), (1_300, 80.0)] {
+            let events = detector.update(
+                &NOTIONAL,
+                &depth(&[(100, qty), (99, 1.0)], &asks),
+                &[],
+                time,
+                step(),
+                |qty, _| qty,
+            );
+            assert!(events.is_empty());
+        }
+        assert_eq!(detector.walls().count(), 1);
+
+        let events = detector.update(
+            &NOTIONAL,
+            &depth(&[(100, 50.0), (99, 1.0)], &asks),
+            &[],
+            1_400,
+            step(),
+            |qty, _| qty,
+        );
+        assert_eq!(
+            changes(&events),
+            vec![(Side::Bid, px(100), WallChange::Pulled)]
+        );
+    }
+
+    #[test]
+    fn removal_is_classified_from_recent_trades() {
+        let mut detector = WallDetector::default();
+        let bids = [(100, 1.0), (90, 1.0)];
+
+        detector.update(
+            &NOTIONAL,
+            &depth(&bids, &[(105, 150.0), (106, 150.0), (120, 1.0)]),
+            &[],
+            1_000,
+            step(),
+            |qty, _| qty,
+        );
+        assert_eq!(detector.walls().count(), 2);
+
+        // Buys at 105 eat most of that wall, 106 disappears untouched
+        let events = detector.update(
+            &NOTIONAL,
+            &depth(&bids, &[(120, 1.0)]),
+            &[
+                trade(1_100, 105, false, 90.0),
+                trade(1_150, 104, true, 500.0),
+            ],
+            1_200,
+            step(),
+            |qty, _| qty,
+        );
+        assert_eq!(
+            changes(&events),
+            vec![
+                (Side::Ask, px(105), WallChange::Consumed),
+                (Side::Ask, px(106), WallChange::Pulled),
+            ]
+        );
+    }
+
+    #[test]
+    fn multiple_threshold_and_out_of_view_levels() {
+        let mut detector = WallDetector::default();
+        let config = WallConfig {
+            threshold: WallThreshold::Multiple(3.0),
+            sound: false,
+        };
+
+        let events = detector.update(
+            &config,
+            &depth(&[(100, 1.0), (99, 1.0), (98, 20.0)], &[(101, 1.0)]),
+            &[],
+            1_000,
+            step(),
+            |qty, price| qty * price.to_f32(),
+        );
+        assert_eq!(
+            changes(&events),
+            vec![(Side::Bid, px(98), WallChange::Appeared)]
+        );
+
+        // The book moved up and 98 fell off the snapshot, that's not a pull
+        let events = detector.update(
+            &config,
+            &depth(&[(105, 1.0), (104, 1.0)], &[(106, 1.0)]),
+            &[],
+            1_100,
+            step(),
+            |qty, price| qty * price.to_f32(),
+        );
+        assert!(events.is_empty());
+        assert_eq!(detector.walls().count(), 0);
+    }
+}
@@ -0,0 +1,274 @@
+//! Stacked diagonal imbalances of footprint bars.
+//!
+//! Buying at a price is compared with selling one step below it, the way aggressive buyers lift
+//! offers above where sellers hit bids. A price is imbalanced when one side outweighs its diagonal
+//! by the configured ratio, and enough imbalanced prices in a row on the same side form a stack.
+//! A diagonal without any volume doesn't count, thin bars would otherwise stack everywhere.
+
+use super::kline::KlineTrades;
+use exchange::util::{Price, PriceStep};
+use rustc_hash::FxHashMap;
+
+/// Minimum imbalance ratio and stack length, see [`super::kline::FootprintStudy::StackedImbalance`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Params {
+    /// e.g. 3.0 when ask volume must reach 3× the diagonal bid volume
+    pub ratio: f32,
+    pub min_levels: usize,
+}
+
+impl Params {
+    /// `ratio_pct` is the ratio in percent, 300 for 3×
+    pub fn new(ratio_pct: usize, min_levels: usize) -> Self {
+        Self {
+            ratio: ratio_pct as f32 / 100.0,
+            min_levels: min_levels.max(1),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    /// Buying outweighs selling a step below
+    Buy,
+    /// Selling outweighs buying a step above
+    Sell,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stack {
+    pub side: Side,
+    pub low: Price,
+    pub high: Price,
+    pub levels: usize,
+}
+
+fn imbalance_at(
+    footprint: &KlineTrades,
+    price: Price,
+    step: PriceStep,
+    ratio: f32,
+) -> Option<Side> {
+    let group = footprint.trades.get(&price)?;
+
+    let sold_below = footprint
+        .trades
+        .get(&price.add_steps(-1, step))
+        .map_or(0.0, |g| g.sell_qty);
+    if sold_below > 0.0 && group.buy_qty >= sold_below * ratio {
+        return Some(Side::Buy);
+    }
+
+    let bought_above = footprint
+        .trades
+        .get(&price.add_steps(1, step))
+        .map_or(0.0, |g| g.buy_qty);
+    if bought_above > 0.0 && group.sell_qty >= bought_above * ratio {
+        return Some(Side::Sell);
+    }
+
+    None
+}
+
+/// Stacks of at least `params.min_levels` consecutive imbalanced prices in `footprint`, lowest
+/// first. A price with no trades ends a stack.
+pub fn stacked(footprint: &KlineTrades, step: PriceStep, params: Params) -> Vec<Stack> {
+    let mut prices: Vec<Price> = footprint.trades.keys().copied().collect();
+    prices.sort_unstable();
+
+    let mut stacks = vec![];
+    let mut current: Option<Stack> = None;
+
+    let mut close = |stack: Option<Stack>| {
+        if let Some(stack) = stack
+            && stack.levels >= params.min_levels
+        {
+            stacks.push(stack);
+        }
+    };
+
+    for price in prices {
+        let side = imbalance_at(footprint, price, step, params.ratio);
+
+        current = match (current, side) {
+            (Some(mut stack), Some(side))
+                if stack.side == side && stack.high.add_steps(1, step) == price =>
+            {
+                stack.high = price;
+                stack.levels += 1;
+                Some(stack)
+            }
+            (previous, side) => {
+                close(previous);
+                side.map(|side| Stack {
+                    side,
+                    low: price,
+                    high: price,
+                    levels: 1,
+                })
+            }
+        };
+    }
+    close(current);
+
+    stacks
+}
+
+/// Stacks of every bar of a chart, keyed the way the chart keys its bars.
+///
+/// Only bars that received trades are scanned again, closed bars keep what they had.
+#[derive(Debug)]
+pub struct StackCache {
+    params: Params,
+    bars: FxHashMap<u64, Vec<Stack>>,
+}
+
+impl StackCache {
+    pub fn new(params: Params) -> Self {
+        Self {
+            params,
+            bars: FxHashMap::default(),
+        }
+    }
+
+    pub fn params(&self) -> Params {
+        self.params
+    }
+
+    /// Rescans one bar, the others keep their stacks
+    pub fn refresh(&mut self, bar: u64, footprint: &KlineTrades, step: PriceStep) {
+        let stacks = stacked(footprint, step, self.params);
+
+        if stacks.is_empty() {
+            self.bars.remove(&bar);
+        } else {
+            self.bars.insert(bar, stacks);
+        }
+    }
+
+    pub fn get(&self, bar: u64) -> &[Stack] {
+        self.bars.get(&bar).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn clear(&mut self) {
+        self.bars.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::Trade;
+
+    const STEP: f32 = 0.25;
+
+    /// `levels` are (price, bought, sold) cells of one bar
+    fn footprint(levels: &[(f32, f32, f32)]) -> KlineTrades {
+        let step = PriceStep::from_f32(STEP);
+        let mut footprint = KlineTrades::new();
+
+        for &(price, bought, sold) in levels {
+            for (qty, is_sell) in [(bought, false), (sold, true)] {
+                if qty > 0.0 {
+                    let trade = Trade {
+                        time: 0,
+                        is_sell,
+                        price: Price::from_f32(price),
+                        qty,
+                    };
+                    footprint.add_trade_to_nearest_bin(&trade, step);
+                }
+            }
+        }
+        footprint
+    }
+
+    fn prices(stack: &Stack) -> (f32, f32) {
+        (stack.low.to_f32(), stack.high.to_f32())
+    }
+
+    #[test]
+    fn finds_diagonal_stacks_on_both_sides() {
+        let step = PriceStep::from_f32(STEP);
+        let params = Params::new(300, 3);
+
+        // Buyers lift 100.25..=100.75 at 3x+ what sold a step below each
+        let bar = footprint(&[
+            (100.00, 1.0, 10.0),
+            (100.25, 30.0, 5.0),
+            (100.50, 15.0, 4.0),
+            (100.75, 12.0, 2.0),
+            (101.00, 3.0, 1.0),
+        ]);
+        let stacks = stacked(&bar, step, params);
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].side, Side::Buy);
+        assert_eq!(prices(&stacks[0]), (100.25, 100.75));
+        assert_eq!(stacks[0].levels, 3);
+
+        // One level short of a stack
+        assert!(stacked(&bar, step, Params::new(300, 4)).is_empty());
+
+        // Sellers hit 99.00..=99.50 at 3x+ what bought a step above each
+        let bar = footprint(&[
+            (99.00, 1.0, 9.0),
+            (99.25, 3.0, 12.0),
+            (99.50, 2.0, 9.0),
+            (99.75, 3.0, 1.0),
+        ]);
+        let stacks = stacked(&bar, step, params);
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].side, Side::Sell);
+        assert_eq!(prices(&stacks[0]), (99.00, 99.50));
+    }
+
+    #[test]
+    fn gaps_and_empty_diagonals_end_stacks() {
+        let step = PriceStep::from_f32(STEP);
+        let params = Params::new(300, 2);
+
+        // 100.50 traded nothing, so 100.25 stays a level of its own
+        let bar = footprint(&[
+            (100.00, 0.0, 2.0),
+            (100.25, 9.0, 0.0),
+            (100.75, 1.0, 3.0),
+            (101.00, 9.0, 1.0),
+            (101.25, 9.0, 0.0),
+        ]);
+        let stacks = stacked(&bar, step, params);
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(prices(&stacks[0]), (101.00, 101.25));
+
+        // Nobody sold at all, so no diagonal to outweigh
+        let bar = footprint(&[(100.00, 5.0, 0.0), (100.25, 8.0, 0.0), (100.50, 9.0, 0.0)]);
+        assert!(stacked(&bar, step, params).is_empty());
+    }
+
+    #[test]
+    fn refreshing_the_forming_bar_leaves_closed_bars_alone() {
+        let step = PriceStep::from_f32(STEP);
+        let mut cache = StackCache::new(Params::new(300, 3));
+
+        let closed = [
+            (99.00, 1.0, 2.0),
+            (99.25, 7.0, 1.0),
+            (99.50, 4.0, 1.0),
+            (99.75, 5.0, 1.0),
+        ];
+        cache.refresh(0, &footprint(&closed), step);
+        assert_eq!(cache.get(0).len(), 1);
+
+        // The forming bar completes its stack once a third level prints
+        let mut forming = vec![(100.00, 1.0, 2.0), (100.25, 7.0, 1.0), (100.50, 4.0, 1.0)];
+        cache.refresh(60_000, &footprint(&forming), step);
+        assert!(cache.get(60_000).is_empty());
+
+        forming.push((100.75, 5.0, 0.0));
+        cache.refresh(60_000, &footprint(&forming), step);
+        assert_eq!(cache.get(60_000)[0].levels, 3);
+        assert_eq!(cache.get(0).len(), 1);
+
+        cache.clear();
+        assert!(cache.get(0).is_empty());
+    }
+}
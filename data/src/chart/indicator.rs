@@ -1,19 +1,26 @@
 use std::fmt::{self, Debug, Display};
 
 use enum_map::Enum;
-use exchange::adapter::MarketKind;
+use exchange::adapter::{Capabilities, MarketKind};
 use serde::{Deserialize, Serialize};
 
 pub trait Indicator: PartialEq + Display + 'static {
     fn for_market(market: MarketKind) -> &'static [Self]
     where
         Self: Sized;
+
+    /// Whether the ticker's adapter can provide the data this indicator needs
+    fn is_supported(&self, _capabilities: Capabilities) -> bool {
+        true
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, Eq, Enum)]
 pub enum KlineIndicator {
     Volume,
     OpenInterest,
+    /// Bid/ask spread history from the depth stream
+    Spread,
 }
 
 impl Indicator for KlineIndicator {
@@ -23,15 +30,27 @@ impl Indicator for KlineIndicator {
             MarketKind::LinearPerps | MarketKind::InversePerps => &Self::FOR_PERPS,
         }
     }
+
+    fn is_supported(&self, capabilities: Capabilities) -> bool {
+        match self {
+            KlineIndicator::Volume => true,
+            KlineIndicator::OpenInterest => capabilities.open_interest,
+            KlineIndicator::Spread => true,
+        }
+    }
 }
 
 impl KlineIndicator {
     // Indicator togglers on UI menus depend on these arrays.
     // Every variant needs to be in either SPOT, PERPS or both.
     /// Indicators that can be used with spot market tickers
-    const FOR_SPOT: [KlineIndicator; 1] = [KlineIndicator::Volume];
+    const FOR_SPOT: [KlineIndicator; 2] = [KlineIndicator::Volume, KlineIndicator::Spread];
     /// Indicators that can be used with perpetual swap market tickers
-    const FOR_PERPS: [KlineIndicator; 2] = [KlineIndicator::Volume, KlineIndicator::OpenInterest];
+    const FOR_PERPS: [KlineIndicator; 3] = [
+        KlineIndicator::Volume,
+        KlineIndicator::OpenInterest,
+        KlineIndicator::Spread,
+    ];
 }
 
 impl Display for KlineIndicator {
@@ -39,6 +58,7 @@ impl Display for KlineIndicator {
         match self {
             KlineIndicator::Volume => write!(f, "Volume"),
             KlineIndicator::OpenInterest => write!(f, "Open Interest"),
+            KlineIndicator::Spread => write!(f, "Spread"),
         }
     }
 }
@@ -46,6 +66,8 @@ impl Display for KlineIndicator {
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, Eq, Enum)]
 pub enum HeatmapIndicator {
     Volume,
+    /// Footer row of traded delta and volume per column
+    Delta,
 }
 
 impl Indicator for HeatmapIndicator {
@@ -61,15 +83,16 @@ impl HeatmapIndicator {
     // Indicator togglers on UI menus depend on these arrays.
     // Every variant needs to be in either SPOT, PERPS or both.
     /// Indicators that can be used with spot market tickers
-    const FOR_SPOT: [HeatmapIndicator; 1] = [HeatmapIndicator::Volume];
+    const FOR_SPOT: [HeatmapIndicator; 2] = [HeatmapIndicator::Volume, HeatmapIndicator::Delta];
     /// Indicators that can be used with perpetual swap market tickers
-    const FOR_PERPS: [HeatmapIndicator; 1] = [HeatmapIndicator::Volume];
+    const FOR_PERPS: [HeatmapIndicator; 2] = [HeatmapIndicator::Volume, HeatmapIndicator::Delta];
 }
 
 impl Display for HeatmapIndicator {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             HeatmapIndicator::Volume => write!(f, "Volume"),
+            HeatmapIndicator::Delta => write!(f, "Delta"),
         }
     }
 }
@@ -364,6 +364,13 @@ pub enum FootprintStudy {
         color_scale: Option<usize>,
         ignore_zeros: bool,
     },
+    /// Runs of diagonal imbalances, see [`super::imbalance`]
+    StackedImbalance {
+        /// Volume one side needs over its diagonal, in percent
+        ratio: usize,
+        /// Consecutive imbalanced prices that make a stack
+        levels: usize,
+    },
 }
 
 impl FootprintStudy {
@@ -375,18 +382,26 @@ impl FootprintStudy {
                     FootprintStudy::Imbalance { .. },
                     FootprintStudy::Imbalance { .. }
                 )
+                | (
+                    FootprintStudy::StackedImbalance { .. },
+                    FootprintStudy::StackedImbalance { .. }
+                )
         )
     }
 }
 
 impl FootprintStudy {
-    pub const ALL: [FootprintStudy; 2] = [
+    pub const ALL: [FootprintStudy; 3] = [
         FootprintStudy::NPoC { lookback: 80 },
         FootprintStudy::Imbalance {
             threshold: 200,
             color_scale: Some(400),
             ignore_zeros: true,
         },
+        FootprintStudy::StackedImbalance {
+            ratio: 300,
+            levels: 3,
+        },
     ];
 }
 
@@ -395,6 +410,7 @@ impl std::fmt::Display for FootprintStudy {
         match self {
             FootprintStudy::NPoC { .. } => write!(f, "Naked Point of Control"),
             FootprintStudy::Imbalance { .. } => write!(f, "Imbalance"),
+            FootprintStudy::StackedImbalance { .. } => write!(f, "Stacked Imbalance"),
         }
     }
 }
@@ -0,0 +1,140 @@
+//! Auto levels derived from daily klines and the current session, per chart ticker.
+
+use super::session::SessionLevels;
+use exchange::Kline;
+use exchange::util::Price;
+
+use std::fmt;
+
+const DAY_MS: u64 = 86_400_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LevelKind {
+    SessionOpen,
+    SessionHigh,
+    SessionLow,
+    PrevDayHigh,
+    PrevDayLow,
+    PrevDayClose,
+}
+
+impl LevelKind {
+    pub fn is_session(self) -> bool {
+        matches!(
+            self,
+            LevelKind::SessionOpen | LevelKind::SessionHigh | LevelKind::SessionLow
+        )
+    }
+}
+
+impl fmt::Display for LevelKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            LevelKind::SessionOpen => "SO",
+            LevelKind::SessionHigh => "SH",
+            LevelKind::SessionLow => "SL",
+            LevelKind::PrevDayHigh => "PDH",
+            LevelKind::PrevDayLow => "PDL",
+            LevelKind::PrevDayClose => "PDC",
+        };
+        write!(f, "{label}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamedLevel {
+    pub kind: LevelKind,
+    pub price: Price,
+}
+
+/// The last completed daily bar at `now_ms`.
+///
+/// Daily bars only exist for trading days, so taking the previous bar rather than the previous
+/// calendar date skips weekends and holidays, which matters for MT5 symbols whose sessions
+/// follow the broker's calendar.
+pub fn previous_day(daily: &[Kline], now_ms: u64) -> Option<&Kline> {
+    daily
+        .iter()
+        .rev()
+        .find(|kline| kline.time + DAY_MS <= now_ms)
+}
+
+pub fn named_levels(prev_day: Option<&Kline>, session: Option<SessionLevels>) -> Vec<NamedLevel> {
+    let mut levels = Vec::with_capacity(6);
+
+    if let Some(kline) = prev_day {
+        levels.extend([
+            NamedLevel {
+                kind: LevelKind::PrevDayHigh,
+                price: kline.high,
+            },
+            NamedLevel {
+                kind: LevelKind::PrevDayLow,
+                price: kline.low,
+            },
+            NamedLevel {
+                kind: LevelKind::PrevDayClose,
+                price: kline.close,
+            },
+        ]);
+    }
+
+    if let Some(session) = session {
+        levels.extend([
+            NamedLevel {
+                kind: LevelKind::SessionOpen,
+                price: session.open,
+            },
+            NamedLevel {
+                kind: LevelKind::SessionHigh,
+                price: session.high,
+            },
+            NamedLevel {
+                kind: LevelKind::SessionLow,
+                price: session.low,
+            },
+        ]);
+    }
+
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daily(day: u64, close: f32) -> Kline {
+        Kline {
+            time: day * DAY_MS,
+            open: Price::from_f32(close),
+            high: Price::from_f32(close + 1.0),
+            low: Price::from_f32(close - 1.0),
+            close: Price::from_f32(close),
+            volume: (0.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn previous_day_skips_missing_calendar_days() {
+        // Friday, then Monday is still forming, no weekend bars
+        let bars = [daily(10, 100.0), daily(11, 101.0), daily(14, 102.0)];
+
+        let monday_noon = 14 * DAY_MS + DAY_MS / 2;
+        assert_eq!(previous_day(&bars, monday_noon).unwrap().time, 11 * DAY_MS);
+
+        let saturday = 12 * DAY_MS + 1_000;
+        assert_eq!(previous_day(&bars, saturday).unwrap().time, 11 * DAY_MS);
+
+        assert!(previous_day(&bars[..1], 10 * DAY_MS + 5).is_none());
+    }
+
+    #[test]
+    fn levels_are_named() {
+        let bar = daily(1, 50.0);
+        let levels = named_levels(Some(&bar), None);
+
+        assert_eq!(levels.len(), 3);
+        assert_eq!(levels[0].kind.to_string(), "PDH");
+        assert_eq!(levels[0].price, Price::from_f32(51.0));
+    }
+}
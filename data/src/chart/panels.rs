@@ -0,0 +1,171 @@
+//! Heights of the panels under a chart, e.g. volume or open interest.
+//!
+//! They're kept as shares of the pane by panel kind rather than as split positions, so a panel
+//! keeps its height when the panels are reordered, when it's turned off and on again and across
+//! restarts. Panels turned on without a saved height get the average height of the others, and
+//! everything shown is scaled to fill the pane without changing how tall panels are relative to
+//! each other.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Version of [`PanelHeights`] written by this build
+pub const VERSION: u32 = 1;
+
+/// Share a panel gets when there's no other panel to take the height of
+const DEFAULT_SHARE: f32 = 0.2;
+/// Smallest share any panel is laid out at
+const MIN_SHARE: f32 = 0.05;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct PanelHeights {
+    pub version: u32,
+    /// Share of the pane the chart itself takes
+    pub main: f32,
+    /// Share of each panel kind, also of those turned off
+    pub panels: BTreeMap<String, f32>,
+}
+
+impl PanelHeights {
+    /// Reads the heights `splits` give the chart and `panels`, how layouts saved before heights
+    /// were kept are carried over. Splits that don't match the panels only give the chart's.
+    pub fn from_splits<K: Serialize>(splits: &[f32], panels: &[K]) -> Self {
+        let mut heights = Self {
+            version: VERSION,
+            main: 1.0 - DEFAULT_SHARE,
+            panels: BTreeMap::new(),
+        };
+        heights.record(splits, panels);
+        heights
+    }
+
+    /// Saved heights, the ones `splits` give if there are none or they're from a newer build
+    pub fn restore<K: Serialize>(saved: Option<&Self>, splits: &[f32], panels: &[K]) -> Self {
+        match saved {
+            Some(saved) if saved.version <= VERSION => Self {
+                version: VERSION,
+                ..saved.clone()
+            },
+            _ => Self::from_splits(splits, panels),
+        }
+    }
+
+    /// Takes in the heights `splits` give the chart and `panels` after a resize
+    pub fn record<K: Serialize>(&mut self, splits: &[f32], panels: &[K]) {
+        if panels.is_empty() || splits.len() != panels.len() {
+            if let Some(&main) = splits.first().filter(|_| !panels.is_empty()) {
+                self.main = main;
+            }
+            return;
+        }
+
+        self.main = splits[0];
+        for (index, panel) in panels.iter().enumerate() {
+            let top = splits[index];
+            let bottom = splits.get(index + 1).copied().unwrap_or(1.0);
+            self.panels.insert(key(panel), (bottom - top).max(0.0));
+        }
+    }
+
+    /// Split positions for the chart then `panels` top to bottom, `[main]` without panels
+    pub fn splits<K: Serialize>(&self, panels: &[K]) -> Vec<f32> {
+        if panels.is_empty() {
+            return vec![self.main];
+        }
+
+        let keys = panels.iter().map(key).collect::<Vec<_>>();
+        let known = keys
+            .iter()
+            .filter_map(|key| self.panels.get(key))
+            .collect::<Vec<_>>();
+        let fallback = if known.is_empty() {
+            DEFAULT_SHARE
+        } else {
+            known.iter().copied().sum::<f32>() / known.len() as f32
+        };
+
+        let shares = keys
+            .iter()
+            .map(|key| self.panels.get(key).copied().unwrap_or(fallback))
+            .map(|share| share.max(MIN_SHARE))
+            .collect::<Vec<_>>();
+        let main = self.main.max(MIN_SHARE);
+        let total = main + shares.iter().sum::<f32>();
+
+        let mut splits = vec![main / total];
+        for share in &shares[..shares.len() - 1] {
+            let last = splits[splits.len() - 1];
+            splits.push(last + share / total);
+        }
+        splits
+    }
+}
+
+/// A panel kind as it's written in layouts
+fn key<K: Serialize>(panel: &K) -> String {
+    match serde_json::to_value(panel) {
+        Ok(serde_json::Value::String(name)) => name,
+        Ok(value) => value.to_string(),
+        Err(_) => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart::indicator::KlineIndicator::{OpenInterest, Spread, Volume};
+
+    fn assert_splits(actual: Vec<f32>, expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len(), "{actual:?}");
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-4, "{actual:?} != {expected:?}");
+        }
+    }
+
+    #[test]
+    fn heights_follow_the_panel_through_reorder_and_toggle() {
+        let mut heights = PanelHeights::from_splits(&[0.6, 0.9], &[Volume, OpenInterest]);
+        assert_splits(heights.splits(&[OpenInterest, Volume]), &[0.6, 0.7]);
+
+        // Turned off the chart takes the room, turned on again it gets its height back
+        assert_splits(heights.splits(&[Volume]), &[0.6 / 0.9]);
+        assert_splits(heights.splits(&[Volume, OpenInterest]), &[0.6, 0.9]);
+
+        heights.record(&[0.5, 0.7], &[OpenInterest, Volume]);
+        assert_splits(heights.splits(&[Volume, OpenInterest]), &[0.5, 0.8]);
+    }
+
+    #[test]
+    fn a_new_panel_takes_room_in_proportion() {
+        let heights = PanelHeights::from_splits(&[0.6, 0.9], &[Volume, OpenInterest]);
+
+        // The spread panel gets the average, 0.2, of the others, all keep their proportions
+        let splits = heights.splits(&[Volume, OpenInterest, Spread]);
+        assert_splits(splits, &[0.5, 0.75, 0.833_33]);
+    }
+
+    #[test]
+    fn layouts_before_heights_were_kept_migrate_from_their_splits() {
+        let saved: crate::chart::ViewConfig =
+            serde_json::from_str(r#"{"splits":[0.7,0.85],"autoscale":null}"#).unwrap();
+        assert!(saved.panel_heights.is_none());
+
+        let heights = PanelHeights::restore(
+            saved.panel_heights.as_ref(),
+            &saved.splits,
+            &[Volume, Spread],
+        );
+        assert_splits(heights.splits(&[Volume, Spread]), &[0.7, 0.85]);
+
+        let json = serde_json::to_string(&heights).unwrap();
+        assert!(json.contains(r#""version":1"#) && json.contains(r#""Spread":"#));
+
+        // Heights from a newer build are read from the splits again
+        let newer = PanelHeights {
+            version: VERSION + 1,
+            ..heights.clone()
+        };
+        let restored = PanelHeights::restore(Some(&newer), &[0.8], &[Volume]);
+        assert_splits(restored.splits(&[Volume]), &[0.8]);
+    }
+}
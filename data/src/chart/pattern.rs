@@ -0,0 +1,396 @@
+//! Classic candle patterns marked on kline charts: engulfing bars, pin bars and inside bars.
+//!
+//! Each bar is judged once, when it closes, against the bar before it. The checks are pure so
+//! they're tested on crafted bars, [`PatternTracker`] only keeps track of which bars were judged
+//! so a chart never rescans the history it already went through.
+
+use exchange::Kline;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum PatternKind {
+    /// Up bar whose body covers the whole body of the down bar before it
+    BullishEngulfing,
+    BearishEngulfing,
+    /// Long lower wick, small body near the high
+    BullishPinBar,
+    BearishPinBar,
+    /// Range within the range of the bar before it
+    InsideBar,
+}
+
+impl PatternKind {
+    /// `Some(true)` for patterns that point up, `None` for those that point nowhere
+    pub fn is_bullish(self) -> Option<bool> {
+        match self {
+            PatternKind::BullishEngulfing | PatternKind::BullishPinBar => Some(true),
+            PatternKind::BearishEngulfing | PatternKind::BearishPinBar => Some(false),
+            PatternKind::InsideBar => None,
+        }
+    }
+
+    /// Letter drawn next to the bar
+    pub fn glyph(self) -> &'static str {
+        match self {
+            PatternKind::BullishEngulfing | PatternKind::BearishEngulfing => "E",
+            PatternKind::BullishPinBar | PatternKind::BearishPinBar => "P",
+            PatternKind::InsideBar => "I",
+        }
+    }
+}
+
+impl std::fmt::Display for PatternKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PatternKind::BullishEngulfing => write!(f, "Bullish engulfing"),
+            PatternKind::BearishEngulfing => write!(f, "Bearish engulfing"),
+            PatternKind::BullishPinBar => write!(f, "Bullish pin bar"),
+            PatternKind::BearishPinBar => write!(f, "Bearish pin bar"),
+            PatternKind::InsideBar => write!(f, "Inside bar"),
+        }
+    }
+}
+
+/// Whether a pattern is marked, and whether it's also announced when a bar closes on it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PatternToggle {
+    pub shown: bool,
+    pub alert: bool,
+}
+
+impl PatternToggle {
+    const SHOWN: PatternToggle = PatternToggle {
+        shown: true,
+        alert: false,
+    };
+}
+
+/// Per-pane candle pattern markers
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PatternConfig {
+    pub engulfing: PatternToggle,
+    pub pin_bar: PatternToggle,
+    pub inside_bar: PatternToggle,
+    /// How many times the body the long wick of a pin bar has to be
+    pub pin_wick_ratio: f32,
+}
+
+impl Default for PatternConfig {
+    fn default() -> Self {
+        Self {
+            engulfing: PatternToggle::SHOWN,
+            pin_bar: PatternToggle::SHOWN,
+            inside_bar: PatternToggle::default(),
+            pin_wick_ratio: 2.0,
+        }
+    }
+}
+
+impl PatternConfig {
+    pub const WICK_RATIO_RANGE: std::ops::RangeInclusive<f32> = 1.5..=5.0;
+
+    fn toggle(&self, kind: PatternKind) -> PatternToggle {
+        match kind {
+            PatternKind::BullishEngulfing | PatternKind::BearishEngulfing => self.engulfing,
+            PatternKind::BullishPinBar | PatternKind::BearishPinBar => self.pin_bar,
+            PatternKind::InsideBar => self.inside_bar,
+        }
+    }
+
+    /// Shown patterns `bar` forms after `prev`
+    pub fn detect(&self, prev: &Kline, bar: &Kline) -> Vec<PatternKind> {
+        [
+            self.engulfing.shown.then(|| engulfing(prev, bar)).flatten(),
+            self.pin_bar
+                .shown
+                .then(|| pin_bar(bar, self.pin_wick_ratio))
+                .flatten(),
+            (self.inside_bar.shown && inside_bar(prev, bar)).then_some(PatternKind::InsideBar),
+        ]
+        .into_iter()
+        .flatten()
+        .collect()
+    }
+
+    pub fn alerts(&self, kind: PatternKind) -> bool {
+        self.toggle(kind).alert
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatternMark {
+    /// Open time of the bar that formed it
+    pub time: u64,
+    pub kind: PatternKind,
+}
+
+/// Up bar opening at or below the prior down bar's close and closing at or above its open, the
+/// mirror for bearish. Bodies must differ so two identical bars don't engulf each other.
+pub fn engulfing(prev: &Kline, bar: &Kline) -> Option<PatternKind> {
+    let body = |kline: &Kline| (kline.close.to_f32() - kline.open.to_f32()).abs();
+    if body(bar) <= body(prev) {
+        return None;
+    }
+
+    if prev.close < prev.open && bar.close > bar.open {
+        (bar.open <= prev.close && bar.close >= prev.open).then_some(PatternKind::BullishEngulfing)
+    } else if prev.close > prev.open && bar.close < bar.open {
+        (bar.open >= prev.close && bar.close <= prev.open).then_some(PatternKind::BearishEngulfing)
+    } else {
+        None
+    }
+}
+
+/// One wick at least `wick_ratio` times the body and twice the other wick, with the body in the
+/// outer third of the range
+pub fn pin_bar(bar: &Kline, wick_ratio: f32) -> Option<PatternKind> {
+    let (open, close) = (bar.open.to_f32(), bar.close.to_f32());
+    let (high, low) = (bar.high.to_f32(), bar.low.to_f32());
+    let range = high - low;
+    if range <= 0.0 {
+        return None;
+    }
+
+    // A doji still needs some body to measure the wick against
+    let body = (close - open).abs().max(range * 0.05);
+    let upper = high - open.max(close);
+    let lower = open.min(close) - low;
+
+    if lower >= body * wick_ratio
+        && lower >= upper * 2.0
+        && open.min(close) >= low + range * 2.0 / 3.0
+    {
+        Some(PatternKind::BullishPinBar)
+    } else if upper >= body * wick_ratio
+        && upper >= lower * 2.0
+        && open.max(close) <= high - range * 2.0 / 3.0
+    {
+        Some(PatternKind::BearishPinBar)
+    } else {
+        None
+    }
+}
+
+/// High and low both strictly within the prior bar's
+pub fn inside_bar(prev: &Kline, bar: &Kline) -> bool {
+    bar.high < prev.high && bar.low > prev.low
+}
+
+/// Patterns of the closed bars a chart has gone through. Bars are judged as they close, or as
+/// older history is loaded in front of them, and never again.
+#[derive(Debug, Clone, Default)]
+pub struct PatternTracker {
+    config: Option<PatternConfig>,
+    marks: Vec<PatternMark>,
+    /// Open times of the first and last closed bar judged
+    judged: Option<(u64, u64)>,
+}
+
+impl PatternTracker {
+    /// Forgets everything once `config` differs from what the marks were made with
+    pub fn configure(&mut self, config: Option<PatternConfig>) -> bool {
+        if self.config == config {
+            return false;
+        }
+        self.config = config;
+        self.reset();
+        true
+    }
+
+    pub fn reset(&mut self) {
+        self.marks.clear();
+        self.judged = None;
+    }
+
+    /// First and last closed bar judged
+    pub fn judged(&self) -> Option<(u64, u64)> {
+        self.judged
+    }
+
+    /// Judges the bars of `klines` not judged yet, a contiguous run of closed bars in time order.
+    /// Returns the patterns to announce, those formed by `latest_closed`.
+    pub fn update(&mut self, klines: &[Kline], latest_closed: Option<u64>) -> Vec<PatternMark> {
+        let Some(config) = self.config else {
+            return vec![];
+        };
+        let (Some(first), Some(last)) = (klines.first(), klines.last()) else {
+            return vec![];
+        };
+
+        let is_new = |time: u64| match self.judged {
+            Some((earliest, latest)) => time <= earliest || time > latest,
+            None => true,
+        };
+
+        let mut fresh = vec![];
+        for pair in klines.windows(2) {
+            let (prev, bar) = (&pair[0], &pair[1]);
+            if !is_new(bar.time) {
+                continue;
+            }
+
+            for kind in config.detect(prev, bar) {
+                let mark = PatternMark {
+                    time: bar.time,
+                    kind,
+                };
+                if Some(bar.time) == latest_closed && config.alerts(kind) {
+                    fresh.push(mark);
+                }
+                self.marks.push(mark);
+            }
+        }
+
+        self.judged = Some(match self.judged {
+            Some((earliest, latest)) => (earliest.min(first.time), latest.max(last.time)),
+            None => (first.time, last.time),
+        });
+        self.marks.sort_by_key(|mark| mark.time);
+
+        fresh
+    }
+
+    pub fn marks(&self) -> &[PatternMark] {
+        &self.marks
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::util::Price;
+
+    const M1: u64 = 60_000;
+
+    fn kline(index: u64, open: f32, high: f32, low: f32, close: f32) -> Kline {
+        Kline {
+            time: index * M1,
+            open: Price::from_f32(open),
+            high: Price::from_f32(high),
+            low: Price::from_f32(low),
+            close: Price::from_f32(close),
+            volume: (0.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn engulfing_needs_the_opposite_body_covered() {
+        let down = kline(0, 101.0, 101.5, 99.5, 100.0);
+
+        let bullish = kline(1, 99.8, 102.0, 99.6, 101.4);
+        assert_eq!(
+            engulfing(&down, &bullish),
+            Some(PatternKind::BullishEngulfing)
+        );
+
+        // Closes under the prior open, so its body doesn't cover
+        let short = kline(1, 99.8, 101.0, 99.6, 100.8);
+        assert_eq!(engulfing(&down, &short), None);
+
+        let up = kline(0, 100.0, 101.5, 99.5, 101.0);
+        let bearish = kline(1, 101.2, 101.4, 99.0, 99.6);
+        assert_eq!(
+            engulfing(&up, &bearish),
+            Some(PatternKind::BearishEngulfing)
+        );
+
+        // Same direction twice is a continuation
+        assert_eq!(engulfing(&up, &bullish), None);
+    }
+
+    #[test]
+    fn pin_bar_follows_the_wick_ratio() {
+        // Body of 0.2 with a lower wick of 1.8
+        let hammer = kline(0, 101.8, 102.1, 100.0, 102.0);
+        assert_eq!(pin_bar(&hammer, 2.0), Some(PatternKind::BullishPinBar));
+        assert_eq!(pin_bar(&hammer, 10.0), None);
+
+        let shooting_star = kline(0, 100.2, 102.0, 99.9, 100.0);
+        assert_eq!(
+            pin_bar(&shooting_star, 2.0),
+            Some(PatternKind::BearishPinBar)
+        );
+
+        // Wicks on both sides make a spinning top
+        let spinning = kline(0, 100.9, 102.0, 100.0, 101.1);
+        assert_eq!(pin_bar(&spinning, 2.0), None);
+
+        let flat = kline(0, 100.0, 100.0, 100.0, 100.0);
+        assert_eq!(pin_bar(&flat, 2.0), None);
+    }
+
+    #[test]
+    fn inside_bar_stays_strictly_within() {
+        let mother = kline(0, 100.0, 102.0, 98.0, 101.0);
+
+        assert!(inside_bar(&mother, &kline(1, 100.5, 101.5, 99.0, 100.0)));
+        assert!(!inside_bar(&mother, &kline(1, 100.5, 102.0, 99.0, 100.0)));
+        assert!(!inside_bar(&mother, &kline(1, 100.5, 102.5, 97.0, 100.0)));
+    }
+
+    #[test]
+    fn config_picks_the_patterns_shown() {
+        let prev = kline(0, 101.0, 103.0, 97.0, 100.0);
+        // Engulfs the prior body and stays inside its range
+        let bar = kline(1, 99.8, 102.0, 98.0, 101.4);
+
+        let config = PatternConfig::default();
+        assert_eq!(config.detect(&prev, &bar), [PatternKind::BullishEngulfing]);
+
+        let config = PatternConfig {
+            engulfing: PatternToggle::default(),
+            inside_bar: PatternToggle::SHOWN,
+            ..PatternConfig::default()
+        };
+        assert_eq!(config.detect(&prev, &bar), [PatternKind::InsideBar]);
+    }
+
+    #[test]
+    fn tracker_judges_each_bar_once() {
+        let mut tracker = PatternTracker::default();
+        tracker.configure(Some(PatternConfig {
+            engulfing: PatternToggle {
+                shown: true,
+                alert: true,
+            },
+            ..PatternConfig::default()
+        }));
+
+        let klines = [
+            kline(10, 100.5, 100.6, 99.9, 100.0),
+            kline(11, 101.0, 101.5, 99.5, 100.0),
+            kline(12, 99.8, 102.0, 99.6, 101.4),
+            kline(13, 101.4, 101.6, 101.0, 101.5),
+        ];
+
+        // History is marked but not announced
+        assert!(tracker.update(&klines[..3], Some(10 * M1)).is_empty());
+        assert_eq!(tracker.marks().len(), 1);
+        assert_eq!(tracker.judged(), Some((10 * M1, 12 * M1)));
+
+        // Passing judged bars again adds nothing
+        assert!(tracker.update(&klines[1..3], Some(12 * M1)).is_empty());
+        assert_eq!(tracker.marks().len(), 1);
+
+        let closing = [klines[3], kline(14, 101.6, 101.7, 100.0, 100.2)];
+        assert_eq!(
+            tracker.update(&closing, Some(14 * M1)),
+            [PatternMark {
+                time: 14 * M1,
+                kind: PatternKind::BearishEngulfing,
+            }]
+        );
+
+        // Older history loaded in front gets the first bar judged against it
+        let older = [kline(9, 100.0, 100.25, 99.95, 100.2), klines[0]];
+        assert!(tracker.update(&older, Some(14 * M1)).is_empty());
+        assert_eq!(tracker.marks()[0].time, 10 * M1);
+        assert_eq!(tracker.marks().len(), 3);
+        assert_eq!(tracker.judged(), Some((9 * M1, 14 * M1)));
+
+        assert!(tracker.configure(None));
+        assert!(tracker.marks().is_empty());
+    }
+}
@@ -0,0 +1,246 @@
+//! "What-if" position sizing from an entry, a stop and the amount the user is willing to lose.
+//!
+//! Money per tick is taken from the symbol's tick value when the source provides one, otherwise
+//! it is derived as `tick_size * contract_size` in the quote currency and converted with a user
+//! supplied quote to account currency rate. That covers FX pairs such as USDJPY, where a tick is
+//! worth yen, as well as index CFDs with a contract size of 1 or 10.
+
+use exchange::TickerInfo;
+
+/// R-multiples shown as targets
+pub const TARGET_MULTIPLES: [f64; 3] = [1.0, 2.0, 3.0];
+
+/// Contract metadata the calculation needs, in plain floats
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SymbolSpec {
+    pub tick_size: f64,
+    pub contract_size: f64,
+    pub min_lot: f64,
+    /// Account currency value of one tick for one lot, when the source reports it
+    pub tick_value: Option<f64>,
+}
+
+impl SymbolSpec {
+    /// `None` when the ticker lacks a contract size, sizing in lots is meaningless without it
+    pub fn from_ticker_info(info: &TickerInfo) -> Option<Self> {
+        let contract_size = f64::from(f32::from(info.contract_size?));
+        let tick_size = f64::from(f32::from(info.min_ticksize));
+        let min_lot = f64::from(f32::from(info.min_qty));
+
+        if contract_size <= 0.0 || tick_size <= 0.0 || min_lot <= 0.0 {
+            return None;
+        }
+
+        Some(Self {
+            tick_size,
+            contract_size,
+            min_lot,
+            tick_value: None,
+        })
+    }
+
+    /// Account currency value of one tick for one lot
+    pub fn tick_value(&self, quote_to_account: f64) -> f64 {
+        self.tick_value
+            .unwrap_or(self.tick_size * self.contract_size * quote_to_account)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Risk {
+    /// Fixed amount in the account currency
+    Amount(f64),
+    /// Percent of `equity`
+    Percent { percent: f64, equity: f64 },
+}
+
+impl Risk {
+    pub fn amount(self) -> f64 {
+        match self {
+            Risk::Amount(amount) => amount,
+            Risk::Percent { percent, equity } => equity * percent / 100.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bracket {
+    pub entry: f64,
+    pub stop: f64,
+    pub risk: Risk,
+    /// Multiplied into the derived tick value, 1.0 when the quote is the account currency
+    pub quote_to_account: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionSize {
+    pub lots: f64,
+    pub stop_ticks: f64,
+    /// Account currency value of one tick at `lots`
+    pub risk_per_tick: f64,
+    /// Money lost at the stop with `lots`, at most the requested risk
+    pub risk_at_stop: f64,
+    pub targets: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizingError {
+    InsufficientSymbolInfo,
+    StopAtEntry,
+    InvalidRisk,
+    /// Even the minimum lot risks more than allowed
+    BelowMinLot,
+}
+
+impl std::fmt::Display for SizingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SizingError::InsufficientSymbolInfo => write!(f, "Insufficient symbol info"),
+            SizingError::StopAtEntry => write!(f, "Stop must differ from entry"),
+            SizingError::InvalidRisk => write!(f, "Risk must be positive"),
+            SizingError::BelowMinLot => write!(f, "Risk is below the minimum lot"),
+        }
+    }
+}
+
+pub fn calculate(spec: Option<SymbolSpec>, bracket: Bracket) -> Result<PositionSize, SizingError> {
+    let spec = spec.ok_or(SizingError::InsufficientSymbolInfo)?;
+
+    let distance = (bracket.entry - bracket.stop).abs();
+    let stop_ticks = (distance / spec.tick_size).round();
+    if stop_ticks < 1.0 {
+        return Err(SizingError::StopAtEntry);
+    }
+
+    let risk = bracket.risk.amount();
+    let tick_value = spec.tick_value(bracket.quote_to_account);
+    if !risk.is_finite() || risk <= 0.0 || !tick_value.is_finite() || tick_value <= 0.0 {
+        return Err(SizingError::InvalidRisk);
+    }
+
+    let raw_lots = risk / (stop_ticks * tick_value);
+    // The small epsilon keeps exact multiples from flooring one step down
+    let lots = ((raw_lots / spec.min_lot) + 1e-9).floor() * spec.min_lot;
+    if lots < spec.min_lot {
+        return Err(SizingError::BelowMinLot);
+    }
+
+    let direction = if bracket.entry >= bracket.stop {
+        1.0
+    } else {
+        -1.0
+    };
+    let targets = TARGET_MULTIPLES
+        .iter()
+        .map(|r| (*r, bracket.entry + direction * distance * r))
+        .collect();
+
+    let risk_per_tick = tick_value * lots;
+
+    Ok(PositionSize {
+        lots,
+        stop_ticks,
+        risk_per_tick,
+        risk_at_stop: risk_per_tick * stop_ticks,
+        targets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn jpy_pair_converts_tick_value_to_account_currency() {
+        // USDJPY, 100k contract, 0.001 tick, account in USD at 150 yen per dollar
+        let spec = SymbolSpec {
+            tick_size: 0.001,
+            contract_size: 100_000.0,
+            min_lot: 0.01,
+            tick_value: None,
+        };
+        let bracket = Bracket {
+            entry: 150.000,
+            stop: 149.800,
+            risk: Risk::Percent {
+                percent: 1.0,
+                equity: 10_000.0,
+            },
+            quote_to_account: 1.0 / 150.0,
+        };
+
+        let size = calculate(Some(spec), bracket).unwrap();
+        assert!(approx(size.stop_ticks, 200.0));
+        // 100 yen per tick per lot, 200 ticks, $100 risk
+        assert!(approx(size.lots, 0.75));
+        assert!(size.risk_at_stop <= 100.0 + 1e-6);
+        assert!(approx(size.targets[1].1, 150.400));
+    }
+
+    #[test]
+    fn index_cfd_short_uses_reported_tick_value() {
+        let spec = SymbolSpec {
+            tick_size: 0.1,
+            contract_size: 1.0,
+            min_lot: 0.1,
+            tick_value: Some(0.1),
+        };
+        let bracket = Bracket {
+            entry: 39_000.0,
+            stop: 39_050.0,
+            risk: Risk::Amount(333.0),
+            quote_to_account: 1.0,
+        };
+
+        let size = calculate(Some(spec), bracket).unwrap();
+        assert!(approx(size.stop_ticks, 500.0));
+        // $50 per lot at the stop, floored to the 0.1 lot step
+        assert!(approx(size.lots, 6.6));
+        assert!(approx(size.targets[0].1, 38_950.0));
+    }
+
+    #[test]
+    fn reports_unusable_inputs() {
+        let spec = SymbolSpec {
+            tick_size: 0.01,
+            contract_size: 1.0,
+            min_lot: 1.0,
+            tick_value: None,
+        };
+        let bracket = Bracket {
+            entry: 100.0,
+            stop: 99.0,
+            risk: Risk::Amount(10.0),
+            quote_to_account: 1.0,
+        };
+
+        assert_eq!(
+            calculate(None, bracket),
+            Err(SizingError::InsufficientSymbolInfo)
+        );
+        assert_eq!(
+            calculate(
+                Some(spec),
+                Bracket {
+                    stop: 100.0,
+                    ..bracket
+                }
+            ),
+            Err(SizingError::StopAtEntry)
+        );
+        assert_eq!(
+            calculate(
+                Some(spec),
+                Bracket {
+                    risk: Risk::Amount(0.5),
+                    ..bracket
+                }
+            ),
+            Err(SizingError::BelowMinLot)
+        );
+    }
+}
@@ -0,0 +1,41 @@
+use exchange::util::Price;
+use serde::{Deserialize, Serialize};
+
+/// One-shot alert raised when the last traded price crosses `price`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PriceAlert {
+    pub price: Price,
+    /// Where the alert came from, e.g. the level it was created on
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+impl PriceAlert {
+    /// Whether moving from `prev` to `now` touched or crossed the alert price
+    pub fn crossed(&self, prev: Price, now: Price) -> bool {
+        let (low, high) = if prev <= now {
+            (prev, now)
+        } else {
+            (now, prev)
+        };
+        prev != now && low <= self.price && self.price <= high
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_on_cross_in_either_direction() {
+        let alert = PriceAlert {
+            price: Price::from_f32(100.0),
+            label: None,
+        };
+
+        assert!(alert.crossed(Price::from_f32(99.5), Price::from_f32(100.5)));
+        assert!(alert.crossed(Price::from_f32(101.0), Price::from_f32(100.0)));
+        assert!(!alert.crossed(Price::from_f32(100.0), Price::from_f32(100.0)));
+        assert!(!alert.crossed(Price::from_f32(98.0), Price::from_f32(99.0)));
+    }
+}
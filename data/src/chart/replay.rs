@@ -0,0 +1,197 @@
+//! Bar replay over already fetched klines: everything after a cursor stays hidden and is revealed
+//! one bar at a time, either stepped by hand or played back at a fixed pace.
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplaySpeed {
+    #[default]
+    X1,
+    X2,
+    X5,
+    X10,
+    X25,
+}
+
+impl ReplaySpeed {
+    pub const ALL: [ReplaySpeed; 5] = [
+        ReplaySpeed::X1,
+        ReplaySpeed::X2,
+        ReplaySpeed::X5,
+        ReplaySpeed::X10,
+        ReplaySpeed::X25,
+    ];
+
+    /// Time between two revealed bars, 1x reveals one bar per second
+    pub fn bar_interval(self) -> Duration {
+        let bars_per_sec = match self {
+            ReplaySpeed::X1 => 1,
+            ReplaySpeed::X2 => 2,
+            ReplaySpeed::X5 => 5,
+            ReplaySpeed::X10 => 10,
+            ReplaySpeed::X25 => 25,
+        };
+        Duration::from_millis(1000 / bars_per_sec)
+    }
+}
+
+impl std::fmt::Display for ReplaySpeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplaySpeed::X1 => write!(f, "1x"),
+            ReplaySpeed::X2 => write!(f, "2x"),
+            ReplaySpeed::X5 => write!(f, "5x"),
+            ReplaySpeed::X10 => write!(f, "10x"),
+            ReplaySpeed::X25 => write!(f, "25x"),
+        }
+    }
+}
+
+/// How many of `len` bars are revealed, and whether playback is running
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ReplayCursor {
+    len: usize,
+    revealed: usize,
+    speed: ReplaySpeed,
+    /// When the last bar was revealed by playback, `None` while paused
+    playing_since: Option<Instant>,
+}
+
+impl ReplayCursor {
+    /// Starts with `revealed` bars shown, at least one and at most `len`
+    pub fn new(len: usize, revealed: usize) -> Self {
+        Self {
+            len,
+            revealed: revealed.clamp(1.min(len), len),
+            speed: ReplaySpeed::default(),
+            playing_since: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn revealed(&self) -> usize {
+        self.revealed
+    }
+
+    pub fn is_at_end(&self) -> bool {
+        self.revealed >= self.len
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing_since.is_some()
+    }
+
+    pub fn speed(&self) -> ReplaySpeed {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: ReplaySpeed) {
+        self.speed = speed;
+    }
+
+    /// More bars became available, e.g. live updates or a history fetch while replaying
+    pub fn set_len(&mut self, len: usize, inserted_before: usize) {
+        self.len = len;
+        self.revealed = (self.revealed + inserted_before).min(len);
+    }
+
+    /// Moves the cursor by `delta` bars, returns the new count of revealed bars if it changed
+    pub fn step(&mut self, delta: isize) -> Option<usize> {
+        let target = self
+            .revealed
+            .saturating_add_signed(delta)
+            .clamp(1.min(self.len), self.len);
+
+        if target == self.revealed {
+            return None;
+        }
+        self.revealed = target;
+        Some(target)
+    }
+
+    pub fn toggle_play(&mut self, now: Instant) {
+        self.playing_since = match self.playing_since {
+            Some(_) => None,
+            None if self.is_at_end() => None,
+            None => Some(now),
+        };
+    }
+
+    pub fn pause(&mut self) {
+        self.playing_since = None;
+    }
+
+    /// Bars playback should reveal by `now`, already applied to the cursor.
+    /// Playback stops on its own once the last bar is shown.
+    pub fn advance(&mut self, now: Instant) -> usize {
+        let Some(since) = self.playing_since else {
+            return 0;
+        };
+
+        let interval = self.speed.bar_interval();
+        let elapsed = now.saturating_duration_since(since);
+        let due = (elapsed.as_millis() / interval.as_millis().max(1)) as usize;
+        if due == 0 {
+            return 0;
+        }
+
+        let steps = due.min(self.len - self.revealed);
+        self.revealed += steps;
+        self.playing_since = if self.is_at_end() {
+            None
+        } else {
+            Some(since + interval * due as u32)
+        };
+        steps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stepping_is_clamped_to_the_history() {
+        let mut cursor = ReplayCursor::new(5, 0);
+        assert_eq!(cursor.revealed(), 1);
+
+        assert_eq!(cursor.step(-1), None);
+        assert_eq!(cursor.step(10), Some(5));
+        assert!(cursor.is_at_end());
+        assert_eq!(cursor.step(-2), Some(3));
+
+        cursor.set_len(8, 2);
+        assert_eq!(cursor.revealed(), 5);
+        assert_eq!(cursor.len(), 8);
+    }
+
+    #[test]
+    fn playback_reveals_bars_at_speed_and_stops_at_end() {
+        let start = Instant::now();
+        let mut cursor = ReplayCursor::new(10, 4);
+        cursor.set_speed(ReplaySpeed::X5);
+
+        assert_eq!(cursor.advance(start + Duration::from_secs(1)), 0);
+
+        cursor.toggle_play(start);
+        assert_eq!(cursor.advance(start + Duration::from_millis(100)), 0);
+        assert_eq!(cursor.advance(start + Duration::from_millis(450)), 2);
+        // The leftover 50ms carries into the next bar
+        assert_eq!(cursor.advance(start + Duration::from_millis(600)), 1);
+        assert_eq!(cursor.revealed(), 7);
+
+        assert_eq!(cursor.advance(start + Duration::from_secs(10)), 3);
+        assert!(cursor.is_at_end());
+        assert!(!cursor.is_playing());
+
+        cursor.toggle_play(start);
+        assert!(!cursor.is_playing());
+    }
+}
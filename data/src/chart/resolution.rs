@@ -0,0 +1,269 @@
+//! Coarser bars for time based charts zoomed out past what their own timeframe can show.
+//!
+//! Once the visible span holds more than [`BAR_BUDGET`] bars, or they'd be narrower than the
+//! chart draws them, the chart switches to the next timeframe of its [`ladder`] that fits.
+//! Bars of that timeframe are fetched for the older part of the span and stitched with ones
+//! built from the chart's own bars near the right edge, so the latest bar keeps following the
+//! live feed. Zooming back in returns to the chart's own bars.
+
+use exchange::{Kline, Timeframe};
+
+use std::collections::BTreeMap;
+
+/// Most bars a chart shows at once before moving to a coarser timeframe
+pub const BAR_BUDGET: usize = 2_000;
+
+/// Headroom a finer timeframe needs before the chart returns to it, so a zoom resting near a
+/// threshold doesn't flip back and forth
+const REFINE_HEADROOM: f32 = 4.0 / 3.0;
+
+const STEPS: [Timeframe; 6] = [
+    Timeframe::M1,
+    Timeframe::M5,
+    Timeframe::M15,
+    Timeframe::H1,
+    Timeframe::H4,
+    Timeframe::D1,
+];
+
+/// Timeframes a chart on `base` can show, finest first. Each is a multiple of `base`, so its
+/// bars are whole runs of the chart's own.
+pub fn ladder(base: Timeframe) -> Vec<Timeframe> {
+    let base_ms = base.to_milliseconds();
+
+    std::iter::once(base)
+        .chain(
+            STEPS
+                .into_iter()
+                .filter(|step| *step > base && step.to_milliseconds().is_multiple_of(base_ms)),
+        )
+        .collect()
+}
+
+/// Timeframe to show `span_ms` across `width_px` at, the finest of `base`'s ladder whose bars
+/// fit: at most [`BAR_BUDGET`] of them, each at least `min_bar_px` wide
+pub fn pick(
+    base: Timeframe,
+    shown: Timeframe,
+    span_ms: u64,
+    width_px: f32,
+    min_bar_px: f32,
+) -> Timeframe {
+    let ladder = ladder(base);
+
+    let fits = |timeframe: Timeframe| {
+        let headroom = if timeframe < shown {
+            REFINE_HEADROOM
+        } else {
+            1.0
+        };
+        let bars = (span_ms as f32 / timeframe.to_milliseconds() as f32).max(1.0);
+
+        bars * headroom <= BAR_BUDGET as f32 && width_px / bars >= min_bar_px * headroom
+    };
+
+    ladder
+        .iter()
+        .copied()
+        .find(|timeframe| fits(*timeframe))
+        .or(ladder.last().copied())
+        .unwrap_or(base)
+}
+
+/// Open time of the `timeframe` bar holding `time`, bars open at `phase` past each multiple
+/// of the timeframe
+pub fn bar_start(time: u64, timeframe: Timeframe, phase: u64) -> u64 {
+    let interval = timeframe.to_milliseconds();
+    time.saturating_sub((time + interval - phase % interval) % interval)
+}
+
+/// Phase the fetched bars of a timeframe open at, MT5 brokers open daily bars at their
+/// server's midnight
+pub fn phase(fetched: &BTreeMap<u64, Kline>, timeframe: Timeframe) -> u64 {
+    fetched
+        .keys()
+        .next_back()
+        .map_or(0, |time| time % timeframe.to_milliseconds())
+}
+
+/// One bar opened at `time` out of the finer `bars` it spans, in order
+pub fn merge<'a>(time: u64, bars: impl IntoIterator<Item = &'a Kline>) -> Option<Kline> {
+    bars.into_iter().fold(None, |merged: Option<Kline>, bar| {
+        Some(match merged {
+            None => Kline { time, ..*bar },
+            Some(merged) => Kline {
+                time,
+                open: merged.open,
+                high: merged.high.max(bar.high),
+                low: merged.low.min(bar.low),
+                close: bar.close,
+                volume: (
+                    merged.volume.0 + bar.volume.0,
+                    merged.volume.1 + bar.volume.1,
+                ),
+            },
+        })
+    })
+}
+
+/// Bars of `timeframe` over both layers. Those from the first one `fine` covers whole on are
+/// built from it, older ones are the `fetched` bars.
+pub fn stitch(timeframe: Timeframe, fetched: &BTreeMap<u64, Kline>, fine: &[Kline]) -> Vec<Kline> {
+    let phase = phase(fetched, timeframe);
+    let interval = timeframe.to_milliseconds();
+
+    let fine_from = fine.first().map(|first| {
+        let start = bar_start(first.time, timeframe, phase);
+        if start == first.time {
+            start
+        } else {
+            start + interval
+        }
+    });
+
+    let mut stitched: Vec<Kline> = fetched
+        .range(..fine_from.unwrap_or(u64::MAX))
+        .map(|(_, kline)| *kline)
+        .collect();
+
+    if let Some(fine_from) = fine_from {
+        let covered = &fine[fine.partition_point(|bar| bar.time < fine_from)..];
+
+        let mut rest = covered;
+        while let Some(first) = rest.first() {
+            let start = bar_start(first.time, timeframe, phase);
+            let len = rest.partition_point(|bar| bar.time < start + interval);
+
+            stitched.extend(merge(start, &rest[..len]));
+            rest = &rest[len..];
+        }
+    }
+
+    stitched
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::util::Price;
+
+    const MINUTE: u64 = 60_000;
+    const HOUR: u64 = 60 * MINUTE;
+
+    fn kline(time: u64, price: f32) -> Kline {
+        let price = Price::from_f32(price);
+        Kline {
+            time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: (1.0, 2.0),
+        }
+    }
+
+    #[test]
+    fn steps_up_past_the_budget_and_back_with_headroom() {
+        assert_eq!(
+            ladder(Timeframe::M1),
+            [
+                Timeframe::M1,
+                Timeframe::M5,
+                Timeframe::M15,
+                Timeframe::H1,
+                Timeframe::H4,
+                Timeframe::D1
+            ]
+        );
+        // Coarser steps that don't split into whole 3 minute bars are left out
+        assert_eq!(
+            ladder(Timeframe::M3),
+            [
+                Timeframe::M3,
+                Timeframe::M15,
+                Timeframe::H1,
+                Timeframe::H4,
+                Timeframe::D1
+            ]
+        );
+
+        let day = 24 * HOUR;
+        assert_eq!(
+            pick(Timeframe::M1, Timeframe::M1, day / 2, 2_000.0, 1.0),
+            Timeframe::M1
+        );
+        // Three days of minutes are past the budget, their 5 minute bars aren't
+        assert_eq!(
+            pick(Timeframe::M1, Timeframe::M1, 3 * day, 2_000.0, 1.0),
+            Timeframe::M5
+        );
+        // Too narrow on a small pane even within the budget
+        assert_eq!(
+            pick(Timeframe::M1, Timeframe::M1, day / 2, 400.0, 1.0),
+            Timeframe::M5
+        );
+
+        // Just within the budget again, the chart stays coarser until there's room to spare
+        let span = BAR_BUDGET as u64 * MINUTE - MINUTE;
+        assert_eq!(
+            pick(Timeframe::M1, Timeframe::M5, span, 4_000.0, 1.0),
+            Timeframe::M5
+        );
+        assert_eq!(
+            pick(Timeframe::M1, Timeframe::M5, span / 2, 4_000.0, 1.0),
+            Timeframe::M1
+        );
+
+        // Past every step the coarsest one stays
+        assert_eq!(
+            pick(Timeframe::H4, Timeframe::H4, 10_000 * day, 2_000.0, 1.0),
+            Timeframe::D1
+        );
+    }
+
+    #[test]
+    fn fine_bars_take_over_from_the_first_whole_bar() {
+        // Hourly bars opening at half past, fetched up to 10:30
+        let fetched: BTreeMap<u64, Kline> = (0..11)
+            .map(|hour| {
+                let time = hour * HOUR + 30 * MINUTE;
+                (time, kline(time, 100.0))
+            })
+            .collect();
+        assert_eq!(phase(&fetched, Timeframe::H1), 30 * MINUTE);
+
+        // Minute bars from 8:45, the 8:30 bar isn't whole in them
+        let fine: Vec<Kline> = (0..200)
+            .map(|i| {
+                let time = 8 * HOUR + 45 * MINUTE + i * MINUTE;
+                kline(time, 200.0 + i as f32)
+            })
+            .collect();
+
+        let stitched = stitch(Timeframe::H1, &fetched, &fine);
+        let times: Vec<u64> = stitched.iter().map(|k| k.time / MINUTE).collect();
+        assert_eq!(
+            times,
+            [30, 90, 150, 210, 270, 330, 390, 450, 510, 570, 630, 690]
+        );
+
+        // 8:30 is fetched, 9:30 onwards built from the minutes
+        assert_eq!(stitched[8].close, Price::from_f32(100.0));
+        let built = stitched[9];
+        assert_eq!(built.open, Price::from_f32(245.0));
+        assert_eq!(built.close, Price::from_f32(304.0));
+        assert_eq!(built.high, Price::from_f32(304.0));
+        assert_eq!(built.volume, (60.0, 120.0));
+
+        // The live bar is still forming
+        let latest = stitched.last().unwrap();
+        assert_eq!(latest.time, 11 * HOUR + 30 * MINUTE);
+        assert_eq!(latest.close, Price::from_f32(399.0));
+        assert_eq!(latest.volume, (35.0, 70.0));
+
+        assert_eq!(
+            bar_start(12 * HOUR, Timeframe::H1, 30 * MINUTE),
+            11 * HOUR + 30 * MINUTE
+        );
+    }
+}
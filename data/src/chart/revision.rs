@@ -0,0 +1,79 @@
+//! Bars a broker revised after the chart got them, from late ticks or corrections.
+//!
+//! A backfill overlapping bars the chart already holds is compared with them bar by bar, those
+//! that differ beyond the tolerance are revisions. Whether the fetched bar then replaces the
+//! chart's copy is up to [`RevisionSettings::auto_apply`].
+
+use exchange::Kline;
+use exchange::util::PriceStep;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RevisionSettings {
+    /// Prices within this many ticks of each other count as the same
+    pub price_ticks: u32,
+    /// Share of the larger volume two volumes may differ by, `None` ignores volume. MT5 history
+    /// reports tick volume while bars built live sum trade sizes, so it's off by default.
+    pub volume: Option<f32>,
+    /// Replace the chart's bar with the revised one, or keep it and only report the difference
+    pub auto_apply: bool,
+}
+
+impl Default for RevisionSettings {
+    fn default() -> Self {
+        Self {
+            price_ticks: 0,
+            volume: None,
+            auto_apply: true,
+        }
+    }
+}
+
+/// A bar as the chart held it and as the broker now sends it
+#[derive(Debug, Clone, Copy)]
+pub struct Revision {
+    pub held: Kline,
+    pub fetched: Kline,
+}
+
+impl Revision {
+    /// `fetched` as a revision of `held`, `None` if they agree within `settings`
+    pub fn of(
+        held: &Kline,
+        fetched: &Kline,
+        settings: &RevisionSettings,
+        step: PriceStep,
+    ) -> Option<Self> {
+        let differs = prices_differ(held, fetched, settings.price_ticks, step)
+            || settings
+                .volume
+                .is_some_and(|share| volume_differs(held, fetched, share));
+
+        differs.then_some(Self {
+            held: *held,
+            fetched: *fetched,
+        })
+    }
+}
+
+/// Whether any of the OHLC prices are more than `ticks` apart
+pub(crate) fn prices_differ(a: &Kline, b: &Kline, ticks: u32, step: PriceStep) -> bool {
+    let tolerance = i64::from(ticks) * step.units;
+
+    [
+        (a.open, b.open),
+        (a.high, b.high),
+        (a.low, b.low),
+        (a.close, b.close),
+    ]
+    .into_iter()
+    .any(|(a, b)| (a.units - b.units).abs() > tolerance)
+}
+
+/// Whether the total volumes differ by more than `share` of the larger one
+pub(crate) fn volume_differs(a: &Kline, b: &Kline, share: f32) -> bool {
+    let total = |kline: &Kline| kline.volume.0 + kline.volume.1;
+    let (a, b) = (total(a), total(b));
+    (a - b).abs() > share * a.max(b)
+}
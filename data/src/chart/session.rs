@@ -0,0 +1,293 @@
+use crate::config::timezone::UserTimezone;
+use exchange::Kline;
+use exchange::util::Price;
+
+use serde::{Deserialize, Serialize};
+
+const HOUR_MS: u64 = 3_600_000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+
+/// Per-pane price reference lines drawn over kline charts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReferenceLines {
+    pub last_price: bool,
+    pub session_levels: bool,
+    pub prev_day_levels: bool,
+    /// Hour of day, in the user's timezone, at which a new session starts
+    pub session_start_hour: u8,
+    /// Bars end at the session start, the rest of a bar running across it opens the next one
+    pub anchor_sessions: bool,
+}
+
+impl Default for ReferenceLines {
+    fn default() -> Self {
+        Self {
+            last_price: true,
+            session_levels: false,
+            prev_day_levels: false,
+            session_start_hour: 0,
+            anchor_sessions: false,
+        }
+    }
+}
+
+impl ReferenceLines {
+    pub fn session_break(&self, timezone: UserTimezone) -> SessionBreak {
+        SessionBreak {
+            timezone,
+            start_hour: self.session_start_hour,
+            anchored: self.anchor_sessions,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionLevels {
+    pub open: Price,
+    pub high: Price,
+    pub low: Price,
+}
+
+impl SessionLevels {
+    fn new(open: Price, high: Price, low: Price) -> Self {
+        Self { open, high, low }
+    }
+
+    fn extend(&mut self, high: Price, low: Price) {
+        self.high = self.high.max(high);
+        self.low = self.low.min(low);
+    }
+}
+
+/// Returns the `[start, end)` range in UTC millis of the session containing `ts_ms`
+pub fn session_bounds(timezone: UserTimezone, start_hour: u8, ts_ms: u64) -> (u64, u64) {
+    let shift = u64::from(start_hour.min(23)) * HOUR_MS;
+    let to_local = |ts: u64| ts as i64 + i64::from(timezone.utc_offset_secs(ts as i64)) * 1000;
+
+    let local = to_local(ts_ms) - shift as i64;
+    let local_start = local - local.rem_euclid(DAY_MS as i64) + shift as i64;
+
+    let start = (local_start - (to_local(ts_ms) - ts_ms as i64)).max(0) as u64;
+    // Offsets may differ across the session on DST switches
+    let end_local = local_start + DAY_MS as i64;
+    let end_guess = (end_local - (to_local(start) - start as i64)).max(0) as u64;
+    let end = (end_local - (to_local(end_guess) - end_guess as i64)).max(0) as u64;
+
+    (start, end.max(start + HOUR_MS))
+}
+
+/// The daily session start as seen by the bars of a time based chart.
+///
+/// Index and commodity CFDs gap at the break, a bar running across it mixes two sessions. Bars
+/// are aligned to the timeframe, so the break can fall inside one whenever the session doesn't
+/// start on a multiple of it, e.g. an H4 or D1 bar on a 23:00 session.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SessionBreak {
+    pub timezone: UserTimezone,
+    pub start_hour: u8,
+    /// Whether ticks after a break inside a bar go to the next bar instead
+    pub anchored: bool,
+}
+
+impl SessionBreak {
+    /// The session start inside the bar opened at `bar_time`, if one is
+    pub fn within(&self, bar_time: u64, interval: u64) -> Option<u64> {
+        let (_, end) = session_bounds(self.timezone, self.start_hour, bar_time);
+        (end < bar_time + interval).then_some(end)
+    }
+
+    /// Open time of the bar a tick at `time` goes to
+    pub fn bar_time(&self, time: u64, interval: u64) -> u64 {
+        self.bar_time_from((time / interval) * interval, time, interval)
+    }
+
+    /// Same as [`Self::bar_time`] for bars that don't open on multiples of the interval,
+    /// `aligned` is the open of the bar `time` falls in
+    pub fn bar_time_from(&self, aligned: u64, time: u64, interval: u64) -> u64 {
+        match self.within(aligned, interval) {
+            Some(start) if self.anchored && time >= start => aligned + interval,
+            _ => aligned,
+        }
+    }
+}
+
+/// Tracks the open, high and low of the current session from klines and live trades.
+///
+/// The owner polls it with server time, a `true` result means the session rolled over (or its
+/// definition changed) and the levels should be rebuilt from whatever data is loaded.
+#[derive(Debug, Clone, Default)]
+pub struct SessionTracker {
+    config: Option<(UserTimezone, u8)>,
+    bounds: Option<(u64, u64)>,
+    levels: Option<SessionLevels>,
+}
+
+impl SessionTracker {
+    pub fn levels(&self) -> Option<SessionLevels> {
+        self.levels
+    }
+
+    pub fn bounds(&self) -> Option<(u64, u64)> {
+        self.bounds
+    }
+
+    /// Forces a rebuild on the next poll, e.g. after older data was loaded
+    pub fn reset(&mut self) {
+        self.config = None;
+        self.levels = None;
+    }
+
+    pub fn poll(&mut self, timezone: UserTimezone, start_hour: u8, server_now_ms: u64) -> bool {
+        let config = Some((timezone, start_hour));
+
+        let still_valid = self.config == config
+            && self
+                .bounds
+                .is_some_and(|(start, end)| (start..end).contains(&server_now_ms));
+
+        if still_valid {
+            return false;
+        }
+
+        self.config = config;
+        self.bounds = Some(session_bounds(timezone, start_hour, server_now_ms));
+        self.levels = None;
+        true
+    }
+
+    /// Folds in a bar opened at `kline.time` and spanning `span_ms`
+    pub fn on_kline(&mut self, kline: &Kline, span_ms: u64) {
+        let Some((start, end)) = self.bounds else {
+            return;
+        };
+        if kline.time + span_ms.max(1) <= start {
+            return;
+        }
+        if kline.time >= end {
+            self.roll_over(kline.time);
+        }
+
+        match &mut self.levels {
+            Some(levels) => levels.extend(kline.high, kline.low),
+            None => self.levels = Some(SessionLevels::new(kline.open, kline.high, kline.low)),
+        }
+    }
+
+    pub fn on_trade(&mut self, time: u64, price: Price) {
+        let Some((start, end)) = self.bounds else {
+            return;
+        };
+        if time < start {
+            return;
+        }
+        if time >= end {
+            self.roll_over(time);
+        }
+
+        match &mut self.levels {
+            Some(levels) => levels.extend(price, price),
+            None => self.levels = Some(SessionLevels::new(price, price, price)),
+        }
+    }
+
+    fn roll_over(&mut self, ts_ms: u64) {
+        if let Some((timezone, start_hour)) = self.config {
+            self.bounds = Some(session_bounds(timezone, start_hour, ts_ms));
+        }
+        self.levels = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kline(time: u64, open: f32, high: f32, low: f32) -> Kline {
+        Kline {
+            time,
+            open: Price::from_f32(open),
+            high: Price::from_f32(high),
+            low: Price::from_f32(low),
+            close: Price::from_f32(open),
+            volume: (0.0, 0.0),
+        }
+    }
+
+    #[test]
+    fn bounds_respect_start_hour() {
+        let ts = 10 * DAY_MS + 3 * HOUR_MS;
+
+        assert_eq!(
+            session_bounds(UserTimezone::Utc, 0, ts),
+            (10 * DAY_MS, 11 * DAY_MS)
+        );
+        assert_eq!(
+            session_bounds(UserTimezone::Utc, 8, ts),
+            (9 * DAY_MS + 8 * HOUR_MS, 10 * DAY_MS + 8 * HOUR_MS)
+        );
+    }
+
+    #[test]
+    fn tracks_levels_and_rolls_over_on_trades() {
+        let mut tracker = SessionTracker::default();
+        assert!(tracker.poll(UserTimezone::Utc, 0, DAY_MS + 1_000));
+        assert!(!tracker.poll(UserTimezone::Utc, 0, DAY_MS + 2_000));
+
+        // The bar opened before the session is ignored
+        tracker.on_kline(&kline(DAY_MS - 60_000, 1.0, 50.0, 0.5), 60_000);
+        tracker.on_kline(&kline(DAY_MS, 10.0, 12.0, 9.0), 60_000);
+        tracker.on_trade(DAY_MS + 70_000, Price::from_f32(8.0));
+
+        let levels = tracker.levels().unwrap();
+        assert_eq!(levels.open, Price::from_f32(10.0));
+        assert_eq!(levels.high, Price::from_f32(12.0));
+        assert_eq!(levels.low, Price::from_f32(8.0));
+
+        tracker.on_trade(2 * DAY_MS + 5, Price::from_f32(20.0));
+        let levels = tracker.levels().unwrap();
+        assert_eq!(levels.open, Price::from_f32(20.0));
+        assert_eq!(levels.low, Price::from_f32(20.0));
+        assert!(!tracker.poll(UserTimezone::Utc, 0, 2 * DAY_MS + 10));
+    }
+
+    #[test]
+    fn anchored_ticks_after_the_break_open_the_next_bar() {
+        let h4 = 4 * HOUR_MS;
+        let bar = 10 * DAY_MS + 20 * HOUR_MS;
+        let split = SessionBreak {
+            timezone: UserTimezone::Utc,
+            start_hour: 23,
+            anchored: true,
+        };
+
+        assert_eq!(split.within(bar, h4), Some(bar + 3 * HOUR_MS));
+        assert_eq!(split.within(bar + h4, h4), None);
+        // On the hour the break never falls inside an H1 bar
+        assert_eq!(split.within(bar + 3 * HOUR_MS, HOUR_MS), None);
+
+        assert_eq!(split.bar_time(bar + 3 * HOUR_MS - 1, h4), bar);
+        assert_eq!(split.bar_time(bar + 3 * HOUR_MS, h4), bar + h4);
+        assert_eq!(split.bar_time(bar + h4 + 1, h4), bar + h4);
+
+        // A daily bar holds exactly one session
+        assert_eq!(split.bar_time(bar + 2 * HOUR_MS, DAY_MS), 10 * DAY_MS);
+        assert_eq!(split.bar_time(bar + 3 * HOUR_MS, DAY_MS), 11 * DAY_MS);
+
+        let spanning = SessionBreak {
+            anchored: false,
+            ..split
+        };
+        assert_eq!(spanning.bar_time(bar + 3 * HOUR_MS, h4), bar);
+    }
+
+    #[test]
+    fn config_change_requests_rebuild() {
+        let mut tracker = SessionTracker::default();
+        tracker.poll(UserTimezone::Utc, 0, DAY_MS + 1_000);
+        tracker.on_trade(DAY_MS + 1_000, Price::from_f32(1.0));
+
+        assert!(tracker.poll(UserTimezone::Utc, 9, DAY_MS + 1_000));
+        assert!(tracker.levels().is_none());
+    }
+}
@@ -0,0 +1,250 @@
+use exchange::depth::Depth;
+use exchange::util::MinTicksize;
+
+use std::collections::{BTreeMap, VecDeque};
+
+/// Buckets kept per series, the oldest are dropped past it
+const MAX_BUCKETS: usize = 5_000;
+
+/// Quotes the rolling median spread is taken over
+pub const MEDIAN_WINDOW: usize = 600;
+
+/// Median of the last spreads, kept sorted so a quote costs a search and a shift
+#[derive(Debug, Clone)]
+pub struct RollingMedian {
+    window: usize,
+    recent: VecDeque<f32>,
+    sorted: Vec<f32>,
+}
+
+impl Default for RollingMedian {
+    fn default() -> Self {
+        Self::new(MEDIAN_WINDOW)
+    }
+}
+
+impl RollingMedian {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            recent: VecDeque::with_capacity(window),
+            sorted: Vec::with_capacity(window),
+        }
+    }
+
+    pub fn push(&mut self, spread: f32) {
+        if !spread.is_finite() {
+            return;
+        }
+
+        if self.recent.len() == self.window
+            && let Some(oldest) = self.recent.pop_front()
+        {
+            let index = self.sorted.partition_point(|s| *s < oldest);
+            self.sorted.remove(index);
+        }
+
+        self.recent.push_back(spread);
+        let index = self.sorted.partition_point(|s| *s < spread);
+        self.sorted.insert(index, spread);
+    }
+
+    pub fn median(&self) -> Option<f32> {
+        let len = self.sorted.len();
+        match len {
+            0 => None,
+            _ if len % 2 == 1 => Some(self.sorted[len / 2]),
+            _ => Some((self.sorted[len / 2 - 1] + self.sorted[len / 2]) / 2.0),
+        }
+    }
+
+    /// Quotes the median is taken over so far
+    pub fn len(&self) -> usize {
+        self.recent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recent.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.recent.clear();
+        self.sorted.clear();
+    }
+}
+
+/// Spread samples that landed in one bar
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpreadBucket {
+    pub min: f32,
+    pub max: f32,
+    sum: f64,
+    count: u32,
+}
+
+impl SpreadBucket {
+    fn new(spread: f32) -> Self {
+        Self {
+            min: spread,
+            max: spread,
+            sum: f64::from(spread),
+            count: 1,
+        }
+    }
+
+    fn add(&mut self, spread: f32) {
+        self.min = self.min.min(spread);
+        self.max = self.max.max(spread);
+        self.sum += f64::from(spread);
+        self.count += 1;
+    }
+
+    pub fn avg(&self) -> f32 {
+        (self.sum / f64::from(self.count.max(1))) as f32
+    }
+}
+
+/// Bid/ask spread history in points, bucketed by bar open time.
+///
+/// Only bars that saw a quote get a bucket, so stretches without quotes (e.g. the market was
+/// closed) stay empty instead of being bridged.
+#[derive(Debug, Clone, Default)]
+pub struct SpreadSeries {
+    interval_ms: u64,
+    buckets: BTreeMap<u64, SpreadBucket>,
+    latest: Option<f32>,
+    median: RollingMedian,
+}
+
+impl SpreadSeries {
+    /// Folds in the spread of a quote at `time`, a different `interval_ms` starts over
+    pub fn insert(&mut self, time: u64, spread: f32, interval_ms: u64) {
+        if interval_ms == 0 || !spread.is_finite() {
+            return;
+        }
+        if interval_ms != self.interval_ms {
+            self.interval_ms = interval_ms;
+            self.buckets.clear();
+        }
+
+        let bar_time = time - (time % interval_ms);
+        self.buckets
+            .entry(bar_time)
+            .and_modify(|bucket| bucket.add(spread))
+            .or_insert_with(|| SpreadBucket::new(spread));
+        self.latest = Some(spread);
+        self.median.push(spread);
+
+        while self.buckets.len() > MAX_BUCKETS {
+            self.buckets.pop_first();
+        }
+    }
+
+    pub fn buckets(&self) -> &BTreeMap<u64, SpreadBucket> {
+        &self.buckets
+    }
+
+    /// Spread of the last quote
+    pub fn latest(&self) -> Option<f32> {
+        self.latest
+    }
+
+    /// Median spread of the last [`MEDIAN_WINDOW`] quotes
+    pub fn median(&self) -> Option<f32> {
+        self.median.median()
+    }
+
+    pub fn interval_ms(&self) -> u64 {
+        self.interval_ms
+    }
+
+    pub fn clear(&mut self) {
+        self.buckets.clear();
+        self.latest = None;
+        self.median.clear();
+    }
+}
+
+/// Distance between the best ask and bid in whole multiples of `point`, `None` for a one-sided
+/// book
+pub fn spread_points(depth: &Depth, point: MinTicksize) -> Option<f32> {
+    let (best_bid, _) = depth.bids.last_key_value()?;
+    let (best_ask, _) = depth.asks.first_key_value()?;
+
+    let spread = best_ask.round_to_min_tick(point) - best_bid.round_to_min_tick(point);
+    let points = (spread.to_f32() / point.as_f32()).round();
+    (points >= 0.0).then_some(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::util::Price;
+
+    const MINUTE_MS: u64 = 60_000;
+
+    #[test]
+    fn buckets_track_min_avg_max_per_bar() {
+        let mut series = SpreadSeries::default();
+        series.insert(1_000, 2.0, MINUTE_MS);
+        series.insert(30_000, 6.0, MINUTE_MS);
+        series.insert(59_999, 1.0, MINUTE_MS);
+        series.insert(MINUTE_MS, 3.0, MINUTE_MS);
+
+        let bucket = series.buckets()[&0];
+        assert_eq!((bucket.min, bucket.max), (1.0, 6.0));
+        assert_eq!(bucket.avg(), 3.0);
+        assert_eq!(series.buckets()[&MINUTE_MS].avg(), 3.0);
+        assert_eq!(series.latest(), Some(3.0));
+    }
+
+    #[test]
+    fn quiet_bars_stay_empty_and_interval_change_starts_over() {
+        let mut series = SpreadSeries::default();
+        series.insert(0, 2.0, MINUTE_MS);
+        // Closed over the weekend, the next quote is hours later
+        series.insert(5 * 60 * MINUTE_MS, 40.0, MINUTE_MS);
+
+        let times: Vec<_> = series.buckets().keys().copied().collect();
+        assert_eq!(times, vec![0, 5 * 60 * MINUTE_MS]);
+
+        series.insert(5 * 60 * MINUTE_MS, 4.0, 5 * MINUTE_MS);
+        assert_eq!(series.buckets().len(), 1);
+    }
+
+    #[test]
+    fn median_rolls_over_the_last_quotes() {
+        let mut median = RollingMedian::new(4);
+        assert_eq!(median.median(), None);
+
+        for spread in [5.0, 1.0, 3.0] {
+            median.push(spread);
+        }
+        assert_eq!(median.median(), Some(3.0));
+        median.push(9.0);
+        assert_eq!(median.median(), Some(4.0));
+
+        // The 5 drops out of the window
+        median.push(2.0);
+        assert_eq!(median.len(), 4);
+        assert_eq!(median.median(), Some(2.5));
+
+        // A blowout counts as one quote above the rest, however wide
+        median.push(80.0);
+        assert_eq!(median.median(), Some(6.0));
+    }
+
+    #[test]
+    fn spread_is_counted_in_points() {
+        let mut depth = Depth::default();
+        depth.bids.insert(Price::from_f32(1.08510), 1.0);
+        depth.bids.insert(Price::from_f32(1.08500), 1.0);
+        depth.asks.insert(Price::from_f32(1.08523), 1.0);
+
+        let spread = spread_points(&depth, MinTicksize::new(-5)).unwrap();
+        assert_eq!(spread, 13.0);
+
+        depth.asks.clear();
+        assert_eq!(spread_points(&depth, MinTicksize::new(-5)), None);
+    }
+}
@@ -0,0 +1,170 @@
+//! Alerts when the bid/ask spread of an MT5 pair widens past a limit, as it does around news.
+//!
+//! The limit is either a number of points or a multiple of the rolling median spread, the same
+//! [`RollingMedian`] the spread panel shows. An alert fires once when the spread crosses the
+//! limit and rearms only after it narrows back under [`REARM_RATIO`] of it, so a spread
+//! hovering around the limit doesn't fire on every quote.
+
+use super::spread::RollingMedian;
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Share of the limit the spread has to narrow under before another widening can fire
+pub const REARM_RATIO: f32 = 0.8;
+
+/// Quotes a median limit waits for, the first few aren't a baseline yet
+pub const MIN_MEDIAN_QUOTES: usize = 30;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub enum SpreadLimit {
+    Points(f32),
+    /// Multiple of the rolling median spread
+    MedianMultiple(f32),
+}
+
+impl SpreadLimit {
+    /// The limit in points, `None` while the median has too few quotes behind it
+    pub fn points(self, median: &RollingMedian) -> Option<f32> {
+        match self {
+            SpreadLimit::Points(points) => Some(points),
+            SpreadLimit::MedianMultiple(multiple) => (median.len() >= MIN_MEDIAN_QUOTES)
+                .then(|| median.median())
+                .flatten()
+                .map(|median| median * multiple),
+        }
+    }
+}
+
+impl std::fmt::Display for SpreadLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpreadLimit::Points(points) => write!(f, "{points} points"),
+            SpreadLimit::MedianMultiple(multiple) => write!(f, "{multiple}× the median"),
+        }
+    }
+}
+
+/// Per-pane spread alert options
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct SpreadAlert {
+    pub enabled: bool,
+    pub limit: SpreadLimit,
+    pub play_sound: bool,
+}
+
+impl Default for SpreadAlert {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            limit: SpreadLimit::MedianMultiple(3.0),
+            play_sound: true,
+        }
+    }
+}
+
+/// A widening that just started
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Widening {
+    pub spread: f32,
+    pub limit: f32,
+}
+
+/// Follows a pane's spreads, telling when a widening episode starts
+#[derive(Debug, Clone, Default)]
+pub struct SpreadWatch {
+    median: RollingMedian,
+    widened: bool,
+}
+
+impl SpreadWatch {
+    /// Feeds the spread of a quote, returns the widening it starts
+    pub fn update(&mut self, spread: f32, limit: SpreadLimit) -> Option<Widening> {
+        let limit = limit.points(&self.median);
+        self.median.push(spread);
+        let limit = limit?;
+
+        if self.widened {
+            self.widened = spread > limit * REARM_RATIO;
+            return None;
+        }
+
+        self.widened = spread > limit;
+        self.widened.then_some(Widening { spread, limit })
+    }
+
+    pub fn reset(&mut self) {
+        self.median.clear();
+        self.widened = false;
+    }
+}
+
+/// Alerts of panes that set none of their own, by MT5 connection name
+static DEFAULTS: LazyLock<RwLock<HashMap<String, SpreadAlert>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Replaces every connection's default alert with `defaults`
+pub fn set_defaults(defaults: impl IntoIterator<Item = (String, SpreadAlert)>) {
+    if let Ok(mut current) = DEFAULTS.write() {
+        *current = defaults.into_iter().collect();
+    }
+}
+
+/// Default alert of the MT5 connection named `connection`
+pub fn default_for(connection: &str) -> Option<SpreadAlert> {
+    DEFAULTS.read().ok()?.get(connection).copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fires_once_per_widening() {
+        let mut watch = SpreadWatch::default();
+        let limit = SpreadLimit::Points(20.0);
+
+        assert_eq!(watch.update(12.0, limit), None);
+        assert_eq!(
+            watch.update(25.0, limit),
+            Some(Widening {
+                spread: 25.0,
+                limit: 20.0
+            })
+        );
+        // Still wide, or narrowing but not under the rearm level
+        assert_eq!(watch.update(40.0, limit), None);
+        assert_eq!(watch.update(18.0, limit), None);
+        assert_eq!(watch.update(22.0, limit), None);
+
+        // Back to normal, the next widening fires again
+        assert_eq!(watch.update(15.0, limit), None);
+        assert!(watch.update(21.0, limit).is_some());
+    }
+
+    #[test]
+    fn median_limit_waits_for_a_baseline() {
+        let mut watch = SpreadWatch::default();
+        let limit = SpreadLimit::MedianMultiple(3.0);
+
+        // Opening straight into a wide spread has nothing to compare it with
+        assert_eq!(watch.update(50.0, limit), None);
+        for _ in 1..MIN_MEDIAN_QUOTES {
+            assert_eq!(watch.update(10.0, limit), None);
+        }
+
+        assert_eq!(watch.update(29.0, limit), None);
+        assert_eq!(
+            watch.update(31.0, limit),
+            Some(Widening {
+                spread: 31.0,
+                limit: 30.0
+            })
+        );
+
+        let alert: SpreadAlert =
+            serde_json::from_str(&serde_json::to_string(&SpreadAlert::default()).unwrap()).unwrap();
+        assert_eq!(alert, SpreadAlert::default());
+    }
+}
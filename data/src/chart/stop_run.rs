@@ -0,0 +1,468 @@
+//! Stop runs: price pokes just past a recent swing high or low, where stops tend to rest, and
+//! snaps back inside on a burst of opposite side volume.
+//!
+//! A swing high is a bar whose high beats that of `swing_bars` bars on each side, a swing low the
+//! same with lows. The first later bar to trade past it is the sweep. It counts as a run when it
+//! overshoots by no more than a fraction of the average bar range, closes back inside, and the
+//! prints within a short window from the first one past the level are dominated by the opposite
+//! side. Each swing is judged once, at its first sweep, whatever the outcome.
+//!
+//! [`detect`] is pure over klines and trades so recorded sessions can be rerun with other
+//! parameters.
+
+use exchange::util::Price;
+use exchange::{Kline, TickerInfo, Trade};
+use serde::{Deserialize, Serialize};
+
+/// Bars averaged for the typical bar range an overshoot is measured in
+const RANGE_BARS: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum Sensitivity {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl Sensitivity {
+    pub const ALL: [Sensitivity; 3] = [Sensitivity::Low, Sensitivity::Medium, Sensitivity::High];
+
+    pub fn params(self) -> Params {
+        match self {
+            Sensitivity::Low => Params {
+                swing_bars: 5,
+                max_overshoot: 0.5,
+                burst_ratio: 2.5,
+                window_ms: 5_000,
+            },
+            Sensitivity::Medium => Params {
+                swing_bars: 3,
+                max_overshoot: 0.75,
+                burst_ratio: 1.8,
+                window_ms: 5_000,
+            },
+            Sensitivity::High => Params {
+                swing_bars: 2,
+                max_overshoot: 1.0,
+                burst_ratio: 1.3,
+                window_ms: 5_000,
+            },
+        }
+    }
+}
+
+impl std::fmt::Display for Sensitivity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Sensitivity::Low => write!(f, "Low"),
+            Sensitivity::Medium => write!(f, "Medium"),
+            Sensitivity::High => write!(f, "High"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Params {
+    /// Bars on each side a swing has to beat
+    pub swing_bars: usize,
+    /// Furthest a sweep may reach past the swing, in average bar ranges
+    pub max_overshoot: f32,
+    /// Opposite side volume needed within the window, as a multiple of the sweeping side's
+    pub burst_ratio: f32,
+    /// Span from the first print past the swing over which the sides are compared
+    pub window_ms: u64,
+}
+
+/// Per-pane stop run markers and how they're announced
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StopRunConfig {
+    pub sensitivity: Sensitivity,
+    pub play_sound: bool,
+    pub show_toast: bool,
+    /// URL each run is posted to as JSON
+    pub webhook: Option<String>,
+}
+
+impl Default for StopRunConfig {
+    fn default() -> Self {
+        Self {
+            sensitivity: Sensitivity::default(),
+            play_sound: true,
+            show_toast: true,
+            webhook: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StopRun {
+    /// Open time of the bar that swept the swing
+    pub time: u64,
+    /// Open time of the swing bar
+    pub swing_time: u64,
+    /// Whether a swing high was run, so the reversal is down
+    pub swept_high: bool,
+    pub level: Price,
+    /// Furthest the sweep got past the level
+    pub extreme: Price,
+    /// Volume of the reversing side within the window
+    pub reversal_qty: f32,
+    /// Volume of the sweeping side within the window
+    pub sweep_qty: f32,
+}
+
+impl StopRun {
+    /// What a webhook is posted for the run
+    pub fn webhook_payload(&self, ticker_info: &TickerInfo) -> serde_json::Value {
+        serde_json::json!({
+            "event": "stop_run",
+            "exchange": ticker_info.exchange().to_string(),
+            "ticker": ticker_info.ticker.to_string(),
+            "side": if self.swept_high { "high" } else { "low" },
+            "level": self.level.to_f32(),
+            "extreme": self.extreme.to_f32(),
+            "time": self.time,
+            "reversal_qty": self.reversal_qty,
+            "sweep_qty": self.sweep_qty,
+        })
+    }
+}
+
+/// Stop runs within `klines`, oldest first.
+///
+/// `klines` and `trades` are in time order and the last kline is taken as still forming. A run is
+/// only reported once its volume window has passed `now_ms`.
+pub fn detect(klines: &[Kline], trades: &[Trade], params: &Params, now_ms: u64) -> Vec<StopRun> {
+    let span = params.swing_bars.max(1);
+    let closed = klines.len().saturating_sub(1);
+
+    let mut runs = vec![];
+
+    for swing in span..closed.saturating_sub(span) {
+        for swept_high in [true, false] {
+            let level = extreme_of(&klines[swing], swept_high);
+            let beats = |kline: &Kline| beyond(extreme_of(kline, swept_high), level, swept_high);
+
+            let is_swing = klines[swing - span..swing]
+                .iter()
+                .chain(&klines[swing + 1..=swing + span])
+                .all(|kline| !beats(kline) && extreme_of(kline, swept_high) != level);
+            if !is_swing {
+                continue;
+            }
+
+            let Some(sweep) = (swing + span + 1..closed).find(|&i| beats(&klines[i])) else {
+                continue;
+            };
+
+            if let Some(run) = judge_sweep(klines, trades, params, now_ms, swing, sweep, swept_high)
+            {
+                runs.push(run);
+            }
+        }
+    }
+
+    runs.sort_by_key(|run| (run.time, run.swing_time));
+    runs
+}
+
+fn judge_sweep(
+    klines: &[Kline],
+    trades: &[Trade],
+    params: &Params,
+    now_ms: u64,
+    swing: usize,
+    sweep: usize,
+    swept_high: bool,
+) -> Option<StopRun> {
+    let bar = &klines[sweep];
+    let level = extreme_of(&klines[swing], swept_high);
+    let extreme = extreme_of(bar, swept_high);
+
+    if beyond(bar.close, level, swept_high) || bar.close == level {
+        return None;
+    }
+
+    let range = average_range(&klines[sweep.saturating_sub(RANGE_BARS)..sweep])?;
+    let overshoot = (extreme.to_f32() - level.to_f32()).abs();
+    if overshoot > params.max_overshoot * range {
+        return None;
+    }
+
+    let bar_end = klines[sweep + 1].time;
+    let from = trades.partition_point(|trade| trade.time < bar.time);
+    let first = trades[from..]
+        .iter()
+        .take_while(|trade| trade.time < bar_end)
+        .find(|trade| beyond(trade.price, level, swept_high))?;
+
+    let window_end = first.time + params.window_ms;
+    if window_end > now_ms {
+        return None;
+    }
+
+    let (mut reversal_qty, mut sweep_qty) = (0.0, 0.0);
+    let start = trades.partition_point(|trade| trade.time < first.time);
+    for trade in trades[start..]
+        .iter()
+        .take_while(|trade| trade.time <= window_end)
+    {
+        // Sellers reverse a run on highs, buyers one on lows
+        if trade.is_sell == swept_high {
+            reversal_qty += trade.qty;
+        } else {
+            sweep_qty += trade.qty;
+        }
+    }
+
+    (reversal_qty > 0.0 && reversal_qty >= params.burst_ratio * sweep_qty).then_some(StopRun {
+        time: bar.time,
+        swing_time: klines[swing].time,
+        swept_high,
+        level,
+        extreme,
+        reversal_qty,
+        sweep_qty,
+    })
+}
+
+fn extreme_of(kline: &Kline, high: bool) -> Price {
+    if high { kline.high } else { kline.low }
+}
+
+/// Whether `price` is strictly past `level`, above it for highs
+fn beyond(price: Price, level: Price, high: bool) -> bool {
+    if high { price > level } else { price < level }
+}
+
+fn average_range(klines: &[Kline]) -> Option<f32> {
+    if klines.is_empty() {
+        return None;
+    }
+
+    let total: f32 = klines
+        .iter()
+        .map(|kline| kline.high.to_f32() - kline.low.to_f32())
+        .sum();
+    let average = total / klines.len() as f32;
+
+    (average > 0.0).then_some(average)
+}
+
+/// The runs of the last scan, and which of them were already announced
+#[derive(Debug, Clone, Default)]
+pub struct StopRunTracker {
+    runs: Vec<StopRun>,
+    announced: Vec<StopRun>,
+}
+
+impl StopRunTracker {
+    /// Replaces the runs with those of a fresh scan, returns the ones to announce: runs swept by
+    /// the latest closed bar, opened at `latest_closed`, that weren't announced yet. Older ones
+    /// come from history and are only marked.
+    pub fn update(&mut self, runs: Vec<StopRun>, latest_closed: Option<u64>) -> Vec<StopRun> {
+        let fresh: Vec<StopRun> = match latest_closed {
+            Some(latest_closed) => {
+                self.announced.retain(|run| run.time >= latest_closed);
+
+                runs.iter()
+                    .filter(|run| run.time >= latest_closed)
+                    .filter(|run| !self.announced.iter().any(|seen| same_run(seen, run)))
+                    .copied()
+                    .collect()
+            }
+            None => vec![],
+        };
+
+        self.announced.extend_from_slice(&fresh);
+        self.runs = runs;
+
+        fresh
+    }
+
+    pub fn runs(&self) -> &[StopRun] {
+        &self.runs
+    }
+
+    pub fn clear(&mut self) {
+        self.runs.clear();
+        self.announced.clear();
+    }
+}
+
+fn same_run(a: &StopRun, b: &StopRun) -> bool {
+    a.time == b.time && a.swing_time == b.swing_time && a.swept_high == b.swept_high
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const M1: u64 = 60_000;
+    const NOW: u64 = 10 * M1;
+
+    fn kline(index: u64, high: f32, low: f32, close: f32) -> Kline {
+        Kline {
+            time: index * M1,
+            open: Price::from_f32(close),
+            high: Price::from_f32(high),
+            low: Price::from_f32(low),
+            close: Price::from_f32(close),
+            volume: (0.0, 0.0),
+        }
+    }
+
+    fn trade(time: u64, price: f32, qty: f32, is_sell: bool) -> Trade {
+        Trade {
+            time,
+            is_sell,
+            price: Price::from_f32(price),
+            qty,
+        }
+    }
+
+    /// A swing high of 102 at bar 3, swept to 102.4 by bar 7 which closes back under it
+    fn swept_high(sweep_high: f32, sweep_close: f32) -> Vec<Kline> {
+        vec![
+            kline(0, 100.0, 99.0, 99.5),
+            kline(1, 100.5, 99.5, 100.0),
+            kline(2, 101.0, 100.0, 100.5),
+            kline(3, 102.0, 101.0, 101.5),
+            kline(4, 101.5, 100.5, 101.0),
+            kline(5, 101.0, 100.0, 100.5),
+            kline(6, 100.8, 99.8, 100.2),
+            kline(7, sweep_high, 101.0, sweep_close),
+            kline(8, 101.2, 100.5, 100.8),
+        ]
+    }
+
+    fn reversal_prints() -> Vec<Trade> {
+        vec![
+            trade(7 * M1 + 500, 101.5, 1.0, false),
+            trade(7 * M1 + 1_000, 102.2, 2.0, false),
+            trade(7 * M1 + 1_500, 102.3, 3.0, true),
+            trade(7 * M1 + 2_000, 101.8, 5.0, true),
+            // Past the window
+            trade(7 * M1 + 6_500, 101.5, 10.0, false),
+        ]
+    }
+
+    /// The same market upside down, prices mirrored around 200 and sides swapped
+    fn mirrored(klines: &[Kline], trades: &[Trade]) -> (Vec<Kline>, Vec<Trade>) {
+        let flip = |price: Price| Price::from_f32(200.0 - price.to_f32());
+        let klines = klines
+            .iter()
+            .map(|k| Kline {
+                open: flip(k.open),
+                high: flip(k.low),
+                low: flip(k.high),
+                close: flip(k.close),
+                ..*k
+            })
+            .collect();
+        let trades = trades
+            .iter()
+            .map(|t| Trade {
+                price: flip(t.price),
+                is_sell: !t.is_sell,
+                ..*t
+            })
+            .collect();
+        (klines, trades)
+    }
+
+    #[test]
+    fn sweep_closing_back_on_opposite_volume_is_a_run() {
+        let params = Sensitivity::Medium.params();
+        let runs = detect(&swept_high(102.4, 101.2), &reversal_prints(), &params, NOW);
+
+        assert_eq!(
+            runs,
+            vec![StopRun {
+                time: 7 * M1,
+                swing_time: 3 * M1,
+                swept_high: true,
+                level: Price::from_f32(102.0),
+                extreme: Price::from_f32(102.4),
+                reversal_qty: 8.0,
+                sweep_qty: 2.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn runs_on_lows_mirror_runs_on_highs() {
+        let params = Sensitivity::Medium.params();
+        let (klines, trades) = mirrored(&swept_high(102.4, 101.2), &reversal_prints());
+        let runs = detect(&klines, &trades, &params, NOW);
+
+        assert_eq!(runs.len(), 1);
+        assert!(!runs[0].swept_high);
+        assert_eq!(runs[0].level, Price::from_f32(98.0));
+        assert_eq!(runs[0].swing_time, 3 * M1);
+    }
+
+    #[test]
+    fn breakouts_and_absorbed_sweeps_are_not_runs() {
+        let params = Sensitivity::Medium.params();
+
+        // Closes past the level
+        assert!(detect(&swept_high(102.4, 102.2), &reversal_prints(), &params, NOW).is_empty());
+        // Reaches too far past it
+        assert!(detect(&swept_high(103.5, 101.2), &reversal_prints(), &params, NOW).is_empty());
+
+        // Buyers keep lifting after the sweep
+        let mut trades = reversal_prints();
+        trades.insert(2, trade(7 * M1 + 1_200, 102.3, 20.0, false));
+        assert!(detect(&swept_high(102.4, 101.2), &trades, &params, NOW).is_empty());
+        // No prints, nothing to judge the reversal by
+        assert!(detect(&swept_high(102.4, 101.2), &[], &params, NOW).is_empty());
+    }
+
+    #[test]
+    fn run_waits_for_its_window_to_pass() {
+        let params = Sensitivity::Medium.params();
+        let klines = swept_high(102.4, 101.2);
+        let first_print = 7 * M1 + 1_000;
+
+        assert!(detect(&klines, &reversal_prints(), &params, first_print + 4_000).is_empty());
+        assert_eq!(
+            detect(&klines, &reversal_prints(), &params, first_print + 5_000).len(),
+            1
+        );
+    }
+
+    #[test]
+    fn sensitivity_changes_what_qualifies() {
+        // Reversal outweighs the sweep 8 to 6
+        let mut trades = reversal_prints();
+        trades.insert(2, trade(7 * M1 + 1_200, 102.3, 4.0, false));
+        let klines = swept_high(102.4, 101.2);
+
+        assert_eq!(
+            detect(&klines, &trades, &Sensitivity::High.params(), NOW).len(),
+            1
+        );
+        assert!(detect(&klines, &trades, &Sensitivity::Medium.params(), NOW).is_empty());
+        // Three bars left of the swing aren't enough for five
+        assert!(detect(&klines, &reversal_prints(), &Sensitivity::Low.params(), NOW).is_empty());
+    }
+
+    #[test]
+    fn tracker_announces_only_fresh_runs_once() {
+        let params = Sensitivity::Medium.params();
+        let runs = detect(&swept_high(102.4, 101.2), &reversal_prints(), &params, NOW);
+        let mut tracker = StopRunTracker::default();
+
+        // Scrolled back into history, the run is marked but not announced
+        assert!(tracker.update(runs.clone(), Some(8 * M1)).is_empty());
+        assert_eq!(tracker.runs().len(), 1);
+
+        let mut tracker = StopRunTracker::default();
+        assert_eq!(tracker.update(runs.clone(), Some(7 * M1)), runs);
+        assert!(tracker.update(runs.clone(), Some(7 * M1)).is_empty());
+        assert!(tracker.update(runs, Some(8 * M1)).is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+use exchange::Timeframe;
+use serde::{Deserialize, Serialize};
+
+pub const MAX_STRIPS: usize = 6;
+pub const MIN_BARS: u16 = 20;
+pub const MAX_BARS: u16 = 300;
+
+/// Timeframes shown by a multi-timeframe strip pane, kept in ascending order
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub timeframes: Vec<Timeframe>,
+    /// Bars drawn per strip, also the length of the initial fetch
+    pub bars: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            timeframes: vec![Timeframe::M1, Timeframe::M15, Timeframe::H1, Timeframe::D1],
+            bars: 60,
+        }
+    }
+}
+
+impl Config {
+    /// Adds or removes `timeframe`, the last remaining one can't be removed
+    pub fn toggle(&mut self, timeframe: Timeframe) {
+        if let Some(pos) = self.timeframes.iter().position(|tf| *tf == timeframe) {
+            if self.timeframes.len() > 1 {
+                self.timeframes.remove(pos);
+            }
+        } else if self.timeframes.len() < MAX_STRIPS {
+            self.timeframes.push(timeframe);
+            self.timeframes.sort();
+        }
+    }
+
+    /// Drops what a hand edited or older layout may contain that the pane can't show
+    pub fn sanitized(mut self) -> Self {
+        self.timeframes.retain(|tf| Timeframe::KLINE.contains(tf));
+        self.timeframes.sort();
+        self.timeframes.dedup();
+        self.timeframes.truncate(MAX_STRIPS);
+
+        if self.timeframes.is_empty() {
+            self.timeframes = Config::default().timeframes;
+        }
+        self.bars = self.bars.clamp(MIN_BARS, MAX_BARS);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_keeps_order_and_limits() {
+        let mut cfg = Config {
+            timeframes: vec![Timeframe::H1],
+            bars: 60,
+        };
+
+        cfg.toggle(Timeframe::M5);
+        assert_eq!(cfg.timeframes, vec![Timeframe::M5, Timeframe::H1]);
+
+        cfg.toggle(Timeframe::M5);
+        cfg.toggle(Timeframe::H1);
+        assert_eq!(cfg.timeframes, vec![Timeframe::H1]);
+
+        let cfg = Config {
+            timeframes: vec![Timeframe::D1, Timeframe::MS100, Timeframe::D1],
+            bars: 5,
+        }
+        .sanitized();
+        assert_eq!(cfg.timeframes, vec![Timeframe::D1]);
+        assert_eq!(cfg.bars, MIN_BARS);
+    }
+}
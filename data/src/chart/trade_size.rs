@@ -0,0 +1,177 @@
+//! Large trade thresholds, fixed or derived from the sizes a stream has been printing.
+//!
+//! A lot means very different things across MT5 symbols, so one fixed threshold rarely fits
+//! every chart. In auto mode the threshold is a percentile of the recent trade values instead.
+
+use exchange::TickerInfo;
+use exchange::adapter::{Exchange, MarketKind};
+use exchange::conversion::LotConverter;
+use exchange::util::Price;
+use std::collections::VecDeque;
+
+pub const DEFAULT_PERCENTILE: u8 = 95;
+
+/// Trade values the auto threshold is computed over
+pub const WINDOW: usize = 2_000;
+
+/// Trades to observe before the auto threshold replaces the fixed one
+pub const MIN_SAMPLES: usize = 50;
+
+/// Percentiles of the last `capacity` values pushed
+#[derive(Debug, Clone)]
+pub struct RollingPercentile {
+    window: VecDeque<f32>,
+    sorted: Vec<f32>,
+    capacity: usize,
+}
+
+impl Default for RollingPercentile {
+    fn default() -> Self {
+        Self::new(WINDOW)
+    }
+}
+
+impl RollingPercentile {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            window: VecDeque::with_capacity(capacity),
+            sorted: Vec::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn push(&mut self, value: f32) {
+        if !value.is_finite() {
+            return;
+        }
+
+        if self.window.len() == self.capacity
+            && let Some(oldest) = self.window.pop_front()
+        {
+            let index = self.sorted.partition_point(|v| *v < oldest);
+            self.sorted.remove(index);
+        }
+
+        self.window.push_back(value);
+        let index = self.sorted.partition_point(|v| *v < value);
+        self.sorted.insert(index, value);
+    }
+
+    pub fn len(&self) -> usize {
+        self.window.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.window.is_empty()
+    }
+
+    /// Nearest-rank `percentile` (0 to 100) of the window, `None` while it's empty
+    pub fn percentile(&self, percentile: u8) -> Option<f32> {
+        let len = self.sorted.len();
+        if len == 0 {
+            return None;
+        }
+
+        let rank = (f32::from(percentile.min(100)) / 100.0 * len as f32).ceil() as usize;
+        Some(self.sorted[rank.clamp(1, len) - 1])
+    }
+}
+
+/// Trades above this value are drawn as large, `auto` is the percentile to derive it from.
+/// Until enough trades were seen, or with `auto` off, `fixed` applies.
+pub fn threshold(auto: Option<u8>, fixed: f32, sizes: &RollingPercentile) -> f32 {
+    auto.filter(|_| sizes.len() >= MIN_SAMPLES)
+        .and_then(|percentile| sizes.percentile(percentile))
+        .unwrap_or(fixed)
+}
+
+/// What a trade is worth for size filtering: the contract value for MT5 symbols quoted in
+/// lots, the quote currency value otherwise
+#[derive(Debug, Clone, Copy)]
+pub struct TradeValuer {
+    market: MarketKind,
+    size_in_quote_ccy: bool,
+    lots: Option<LotConverter>,
+}
+
+impl TradeValuer {
+    pub fn new(ticker_info: &TickerInfo, size_in_quote_ccy: bool) -> Self {
+        // Quote sized MT5 quantities arrive already converted by the adapter
+        let lots = (ticker_info.exchange() == Exchange::MetaTrader5
+            && !size_in_quote_ccy
+            && ticker_info.contract_size.is_some())
+        .then(|| LotConverter::for_ticker(ticker_info, None));
+
+        Self {
+            market: ticker_info.market_type(),
+            size_in_quote_ccy,
+            lots,
+        }
+    }
+
+    pub fn value(&self, qty: f32, price: Price) -> f32 {
+        match self.lots {
+            Some(converter) => converter.convert(qty, price.to_f32()),
+            None => self
+                .market
+                .qty_in_quote_value(qty, price, self.size_in_quote_ccy),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_percentile_tracks_the_window() {
+        let mut sizes = RollingPercentile::new(100);
+        for value in 1..=100 {
+            sizes.push(value as f32);
+        }
+        assert_eq!(sizes.percentile(95), Some(95.0));
+        assert_eq!(sizes.percentile(0), Some(1.0));
+        assert_eq!(sizes.percentile(100), Some(100.0));
+
+        // The 100 oldest values roll out
+        for value in 1001..=1100 {
+            sizes.push(value as f32);
+        }
+        assert_eq!(sizes.len(), 100);
+        assert_eq!(sizes.percentile(50), Some(1050.0));
+
+        sizes.push(f32::NAN);
+        assert_eq!(sizes.len(), 100);
+    }
+
+    #[test]
+    fn auto_threshold_waits_for_enough_samples() {
+        let mut sizes = RollingPercentile::default();
+        for _ in 0..MIN_SAMPLES - 1 {
+            sizes.push(10.0);
+        }
+        assert_eq!(threshold(Some(90), 500.0, &sizes), 500.0);
+
+        sizes.push(10.0);
+        assert_eq!(threshold(Some(90), 500.0, &sizes), 10.0);
+        assert_eq!(threshold(None, 500.0, &sizes), 500.0);
+    }
+
+    #[test]
+    fn mt5_lots_are_valued_by_contract_size() {
+        let eurusd = TickerInfo::new(
+            exchange::Ticker::new("EURUSD", Exchange::MetaTrader5),
+            0.00001,
+            0.01,
+            Some(100_000.0),
+        );
+        let price = Price::from_f32(1.1);
+
+        let valuer = TradeValuer::new(&eurusd, false);
+        assert!((valuer.value(1.0, price) - 110_000.0).abs() < 1.0);
+
+        // Already converted by the adapter
+        let valuer = TradeValuer::new(&eurusd, true);
+        assert_eq!(valuer.value(2_500.0, price), 2_500.0);
+    }
+}
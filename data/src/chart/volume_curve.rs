@@ -0,0 +1,333 @@
+//! Typical volume by time of day, averaged over the sessions before the current one.
+//!
+//! Sessions follow the chart's session start hour. Days the market was closed have no bars and
+//! don't count towards the average, nor do bar slots a session didn't trade in, so weekends,
+//! holidays and daily breaks don't drag the curve down.
+
+use super::session::session_bounds;
+use crate::config::timezone::UserTimezone;
+use exchange::{Kline, Ticker};
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{LazyLock, Mutex};
+
+pub const VOLUME_CURVE_PATH: &str = "volume-curves.json";
+
+const DAY_MS: u64 = 86_400_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct VolumeCurveConfig {
+    /// Previous sessions averaged
+    pub sessions: u8,
+    /// Bars above this percentage of the typical volume are highlighted
+    pub threshold_pct: u16,
+}
+
+impl Default for VolumeCurveConfig {
+    fn default() -> Self {
+        Self {
+            sessions: 10,
+            threshold_pct: 150,
+        }
+    }
+}
+
+impl VolumeCurveConfig {
+    pub const SESSIONS: [u8; 3] = [5, 10, 20];
+    pub const THRESHOLDS: [u16; 3] = [125, 150, 200];
+
+    pub fn threshold(&self) -> f32 {
+        f32::from(self.threshold_pct) / 100.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct VolumeCurve {
+    pub timezone: UserTimezone,
+    pub start_hour: u8,
+    pub interval_ms: u64,
+    /// Start of the session the curve was built for, it's stale once the next one starts
+    pub session_start: u64,
+    /// Sessions that traded, at most as many as asked for
+    pub sessions: usize,
+    /// Average volume of each bar from the session start, `None` where no session traded
+    slots: Vec<Option<f32>>,
+}
+
+impl VolumeCurve {
+    /// Averages the `sessions` sessions of `klines` that traded before the one at `now_ms`
+    pub fn build(
+        klines: &[Kline],
+        interval_ms: u64,
+        timezone: UserTimezone,
+        start_hour: u8,
+        sessions: usize,
+        now_ms: u64,
+    ) -> Self {
+        let (session_start, _) = session_bounds(timezone, start_hour, now_ms);
+
+        let mut by_session: BTreeMap<u64, Vec<(usize, f32)>> = BTreeMap::new();
+        for kline in klines.iter().filter(|kline| kline.time < session_start) {
+            let volume = total_volume(kline);
+            if volume <= 0.0 {
+                continue;
+            }
+            let (start, _) = session_bounds(timezone, start_hour, kline.time);
+            let slot = ((kline.time - start) / interval_ms.max(1)) as usize;
+            by_session.entry(start).or_default().push((slot, volume));
+        }
+
+        let mut sums: Vec<(f32, u32)> = vec![];
+        let used = by_session.values().rev().take(sessions);
+        let count = used.len();
+        for bars in used {
+            for &(slot, volume) in bars {
+                if sums.len() <= slot {
+                    sums.resize(slot + 1, (0.0, 0));
+                }
+                sums[slot].0 += volume;
+                sums[slot].1 += 1;
+            }
+        }
+
+        Self {
+            timezone,
+            start_hour,
+            interval_ms,
+            session_start,
+            sessions: count,
+            slots: sums
+                .into_iter()
+                .map(|(sum, n)| (n > 0).then(|| sum / n as f32))
+                .collect(),
+        }
+    }
+
+    /// Typical volume of the bar opening at `bar_time`
+    pub fn typical_at(&self, bar_time: u64) -> Option<f32> {
+        let (start, _) = session_bounds(self.timezone, self.start_hour, bar_time);
+        let slot = (bar_time.checked_sub(start)? / self.interval_ms.max(1)) as usize;
+
+        self.slots.get(slot).copied().flatten()
+    }
+
+    /// Whether `volume` at `bar_time` exceeds `threshold` times the typical volume
+    pub fn is_elevated(&self, bar_time: u64, volume: f32, threshold: f32) -> bool {
+        self.typical_at(bar_time)
+            .is_some_and(|typical| typical > 0.0 && volume > typical * threshold)
+    }
+
+    /// Whether the curve still covers the session at `now_ms`, for these parameters
+    pub fn is_current(
+        &self,
+        timezone: UserTimezone,
+        start_hour: u8,
+        interval_ms: u64,
+        now_ms: u64,
+    ) -> bool {
+        self.timezone == timezone
+            && self.start_hour == start_hour
+            && self.interval_ms == interval_ms
+            && session_bounds(timezone, start_hour, now_ms).0 == self.session_start
+    }
+}
+
+fn total_volume(kline: &Kline) -> f32 {
+    let (buy, sell) = kline.volume;
+    // Sources without a buy/sell split report the total as sell volume
+    if buy < 0.0 { sell } else { buy + sell }
+}
+
+/// Klines to fetch for a curve over `sessions` sessions before the one at `now_ms`, with room
+/// for weekends and holidays
+pub fn history_range(
+    timezone: UserTimezone,
+    start_hour: u8,
+    sessions: u8,
+    now_ms: u64,
+) -> (u64, u64) {
+    let (start, _) = session_bounds(timezone, start_hour, now_ms);
+    let days = u64::from(sessions) * 7 / 5 + 3;
+
+    (start.saturating_sub(days * DAY_MS), start)
+}
+
+/// Curves built this session and on earlier runs, by ticker, timeframe and session count
+static CACHE: LazyLock<Mutex<Option<HashMap<String, VolumeCurve>>>> =
+    LazyLock::new(|| Mutex::new(None));
+
+fn cache_key(ticker: &Ticker, interval_ms: u64, sessions: u8) -> String {
+    format!("{}:{ticker}:{interval_ms}:{sessions}", ticker.exchange)
+}
+
+fn with_cache<R>(f: impl FnOnce(&mut HashMap<String, VolumeCurve>) -> R) -> Option<R> {
+    let mut cache = CACHE.lock().ok()?;
+    let cache = cache.get_or_insert_with(load);
+
+    Some(f(cache))
+}
+
+/// A curve saved for the session at `now_ms`, sparing a refetch of its history
+pub fn cached(
+    ticker: &Ticker,
+    config: VolumeCurveConfig,
+    timezone: UserTimezone,
+    start_hour: u8,
+    interval_ms: u64,
+    now_ms: u64,
+) -> Option<VolumeCurve> {
+    with_cache(|cache| {
+        cache
+            .get(&cache_key(ticker, interval_ms, config.sessions))
+            .filter(|curve| curve.is_current(timezone, start_hour, interval_ms, now_ms))
+            .cloned()
+    })
+    .flatten()
+}
+
+/// Saves `curve`, dropping the curves of sessions that have ended
+pub fn store(ticker: &Ticker, config: VolumeCurveConfig, curve: VolumeCurve, now_ms: u64) {
+    let json = with_cache(|cache| {
+        cache.retain(|_, saved| {
+            saved.is_current(saved.timezone, saved.start_hour, saved.interval_ms, now_ms)
+        });
+        cache.insert(cache_key(ticker, curve.interval_ms, config.sessions), curve);
+
+        serde_json::to_string(cache)
+    });
+
+    match json {
+        Some(Ok(json)) => {
+            if let Err(e) = crate::write_json_to_file(&json, VOLUME_CURVE_PATH) {
+                log::error!("Failed to write volume curves: {e}");
+            }
+        }
+        Some(Err(e)) => log::error!("Failed to serialize volume curves: {e}"),
+        None => {}
+    }
+}
+
+fn load() -> HashMap<String, VolumeCurve> {
+    let path = crate::data_path(Some(VOLUME_CURVE_PATH));
+
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+            log::warn!(
+                "Discarding unreadable volume curves {}: {e}",
+                path.display()
+            );
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::util::Price;
+
+    const HOUR_MS: u64 = 3_600_000;
+    // Monday 2024-01-08 00:00 UTC
+    const MONDAY: u64 = 1_704_672_000_000;
+
+    fn kline(time: u64, volume: f32) -> Kline {
+        let price = Price::from_f32(1.0);
+        Kline {
+            time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: (volume / 2.0, volume / 2.0),
+        }
+    }
+
+    /// Hourly bars of `day` from `hours`, each with `volume`
+    fn session(day: u64, hours: std::ops::Range<u64>, volume: f32) -> Vec<Kline> {
+        hours
+            .map(|hour| kline(MONDAY + day * DAY_MS + hour * HOUR_MS, volume))
+            .collect()
+    }
+
+    #[test]
+    fn averages_each_time_of_day_across_sessions() {
+        let mut klines = session(0, 0..24, 100.0);
+        klines.extend(session(1, 0..24, 300.0));
+        let now = MONDAY + 2 * DAY_MS + 5 * HOUR_MS;
+
+        let curve = VolumeCurve::build(&klines, HOUR_MS, UserTimezone::Utc, 0, 10, now);
+
+        assert_eq!(curve.sessions, 2);
+        assert_eq!(curve.session_start, MONDAY + 2 * DAY_MS);
+        assert_eq!(
+            curve.typical_at(MONDAY + 2 * DAY_MS + 9 * HOUR_MS),
+            Some(200.0)
+        );
+        assert!(curve.is_elevated(MONDAY + 2 * DAY_MS, 301.0, 1.5));
+        assert!(!curve.is_elevated(MONDAY + 2 * DAY_MS, 299.0, 1.5));
+    }
+
+    #[test]
+    fn closed_days_and_hours_are_left_out() {
+        // Friday trades, the weekend doesn't, Monday only trades its first half
+        let mut klines = session(4, 0..24, 100.0);
+        klines.extend(session(7, 0..12, 300.0));
+        let now = MONDAY + 8 * DAY_MS;
+
+        let curve = VolumeCurve::build(&klines, HOUR_MS, UserTimezone::Utc, 0, 10, now);
+
+        assert_eq!(curve.sessions, 2);
+        assert_eq!(curve.typical_at(now + 2 * HOUR_MS), Some(200.0));
+        assert_eq!(curve.typical_at(now + 20 * HOUR_MS), Some(100.0));
+    }
+
+    #[test]
+    fn only_the_latest_sessions_are_averaged() {
+        let mut klines = session(0, 0..24, 1_000.0);
+        klines.extend(session(1, 0..24, 100.0));
+        klines.extend(session(2, 0..24, 300.0));
+        // The running session never counts
+        klines.extend(session(3, 0..2, 5_000.0));
+        let now = MONDAY + 3 * DAY_MS + 2 * HOUR_MS;
+
+        let curve = VolumeCurve::build(&klines, HOUR_MS, UserTimezone::Utc, 0, 2, now);
+
+        assert_eq!(curve.sessions, 2);
+        assert_eq!(curve.typical_at(MONDAY + 3 * DAY_MS), Some(200.0));
+    }
+
+    #[test]
+    fn slots_start_at_the_session_start_hour() {
+        // Sessions open at 22:00, the 22:00 bar of one day is the first of the next session
+        let klines = vec![
+            kline(MONDAY + 22 * HOUR_MS, 500.0),
+            kline(MONDAY + DAY_MS + 10 * HOUR_MS, 50.0),
+        ];
+        let now = MONDAY + DAY_MS + 23 * HOUR_MS;
+
+        let curve = VolumeCurve::build(&klines, HOUR_MS, UserTimezone::Utc, 22, 10, now);
+
+        assert_eq!(curve.session_start, MONDAY + DAY_MS + 22 * HOUR_MS);
+        assert_eq!(curve.typical_at(now - HOUR_MS), Some(500.0));
+        assert_eq!(curve.typical_at(now + 11 * HOUR_MS), Some(50.0));
+    }
+
+    #[test]
+    fn stale_once_the_next_session_starts() {
+        let klines = session(0, 0..24, 100.0);
+        let now = MONDAY + DAY_MS + HOUR_MS;
+        let curve = VolumeCurve::build(&klines, HOUR_MS, UserTimezone::Utc, 0, 10, now);
+
+        assert!(curve.is_current(UserTimezone::Utc, 0, HOUR_MS, now + 20 * HOUR_MS));
+        assert!(!curve.is_current(UserTimezone::Utc, 0, HOUR_MS, now + DAY_MS));
+        assert!(!curve.is_current(UserTimezone::Utc, 1, HOUR_MS, now));
+        assert!(!curve.is_current(UserTimezone::Utc, 0, 60_000, now));
+
+        let (from, to) = history_range(UserTimezone::Utc, 0, 10, now);
+        assert_eq!(to, MONDAY + DAY_MS);
+        assert_eq!(from, to - 17 * DAY_MS);
+    }
+}
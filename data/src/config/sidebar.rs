@@ -10,6 +10,9 @@ pub struct Sidebar {
     pub active_menu: Option<Menu>,
     #[serde(default)]
     pub tickers_table: Option<tickers_table::Settings>,
+    /// Whether the strip listing the active streams shows under the dashboard
+    #[serde(default)]
+    pub connection_bar: bool,
 }
 
 impl Sidebar {
@@ -36,6 +39,7 @@ impl Default for Sidebar {
             position: Position::Left,
             active_menu: None,
             tickers_table: None,
+            connection_bar: false,
         }
     }
 }
@@ -57,8 +61,8 @@ pub enum Position {
 impl std::fmt::Display for Position {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Position::Left => write!(f, "Left"),
-            Position::Right => write!(f, "Right"),
+            Position::Left => f.write_str(crate::t!("sidebar.left")),
+            Position::Right => f.write_str(crate::t!("sidebar.right")),
         }
     }
 }
@@ -70,4 +74,7 @@ pub enum Menu {
     Audio,
     ThemeEditor,
     Mt5Config,
+    CustomWsConfig,
+    Notifications,
+    Logs,
 }
@@ -1,6 +1,7 @@
 use super::ScaleFactor;
 use super::sidebar::Sidebar;
 use super::timezone::UserTimezone;
+use crate::i18n::Locale;
 use crate::layout::WindowSpec;
 use crate::{AudioStream, Layout, Theme};
 
@@ -19,12 +20,37 @@ pub struct Mt5Connection {
     pub name: String,
     /// Server address (host:port)
     pub server_addr: String,
-    /// API key for authentication
+    /// API key for authentication, or the `env:NAME` variable it's read from
     pub api_key: String,
+    /// Saved only as an `env:NAME` reference, never as the secret itself
+    #[serde(
+        default,
+        skip_serializing_if = "exchange::adapter::metatrader5::credentials::is_literal"
+    )]
+    pub api_secret: String,
     /// Whether to use TLS
     pub use_tls: bool,
     /// Auto reconnect on disconnect
     pub auto_reconnect: bool,
+    /// Pause depth and trades of symbols whose market is closed
+    #[serde(default = "default_true")]
+    pub pause_when_closed: bool,
+    /// Versions the proxy reported last time it was connected
+    #[serde(default)]
+    pub server_info: exchange::adapter::metatrader5::ServerInfo,
+    /// Spread alert of this connection's panes that set none of their own
+    #[serde(default)]
+    pub spread_alert: Option<crate::chart::spread_alert::SpreadAlert>,
+    /// Terminal picked on a proxy bridging several, `None` for the proxy's only one
+    #[serde(default)]
+    pub terminal_id: Option<String>,
+    /// Point the terminal's chart at the symbol of the focused pane
+    #[serde(default)]
+    pub sync_terminal_chart: bool,
+}
+
+fn default_true() -> bool {
+    true
 }
 
 /// MT5 settings - stores all MT5 connections
@@ -34,6 +60,15 @@ pub struct Mt5Settings {
     pub connections: Vec<Mt5Connection>,
     /// Active connection name (if any)
     pub active_connection: Option<String>,
+    /// How history overlapping bars already on a chart is reconciled
+    #[serde(default)]
+    pub revisions: crate::chart::revision::RevisionSettings,
+    /// Tick sizes replacing what the broker lists, by symbol
+    #[serde(default)]
+    pub tick_overrides: std::collections::HashMap<
+        exchange::Ticker,
+        exchange::adapter::metatrader5::tick_override::TickOverride,
+    >,
 }
 
 #[derive(Default, Clone, Deserialize, Serialize)]
@@ -44,6 +79,7 @@ pub struct State {
     pub custom_theme: Option<Theme>,
     pub main_window: Option<WindowSpec>,
     pub timezone: UserTimezone,
+    pub locale: Locale,
     pub sidebar: Sidebar,
     pub scale_factor: ScaleFactor,
     pub audio_cfg: AudioStream,
@@ -51,6 +87,16 @@ pub struct State {
     pub size_in_quote_ccy: exchange::SizeUnit,
     /// MT5 connection settings
     pub mt5_settings: Mt5Settings,
+    /// Synthetic instrument definitions, e.g. spreads between two tickers
+    pub synthetics: Vec<exchange::synthetic::SyntheticSpec>,
+    /// Generic WebSocket JSON feeds, see [`exchange::adapter::custom_ws`]
+    pub custom_ws: Vec<exchange::adapter::custom_ws::CustomWsConfig>,
+    /// Local price endpoint for scripts, see [`exchange::ipc`]
+    pub ipc: exchange::ipc::IpcConfig,
+    /// How much history panes keep, see [`crate::retention`]
+    pub retention: crate::retention::Retention,
+    /// Streams and fetches run at once, see [`exchange::limits`]
+    pub limits: exchange::limits::Limits,
 }
 
 impl State {
@@ -65,6 +111,8 @@ impl State {
         audio_cfg: AudioStream,
         volume_size_unit: exchange::SizeUnit,
         mt5_settings: Mt5Settings,
+        synthetics: Vec<exchange::synthetic::SyntheticSpec>,
+        custom_ws: Vec<exchange::adapter::custom_ws::CustomWsConfig>,
     ) -> Self {
         State {
             layout_manager,
@@ -72,12 +120,18 @@ impl State {
             custom_theme: custom_theme.map(|t| Theme(t.0)),
             main_window,
             timezone,
+            locale: crate::i18n::locale(),
             sidebar,
             scale_factor,
             audio_cfg,
             trade_fetch_enabled: exchange::fetcher::is_trade_fetch_enabled(),
             size_in_quote_ccy: volume_size_unit,
             mt5_settings,
+            synthetics,
+            custom_ws,
+            ipc: exchange::ipc::config(),
+            retention: crate::retention::config(),
+            limits: exchange::limits::config(),
         }
     }
 }
@@ -11,6 +11,21 @@ pub enum UserTimezone {
 }
 
 impl UserTimezone {
+    /// Offset from UTC in seconds at the given UTC timestamp, DST aware for `Local`
+    pub fn utc_offset_secs(&self, timestamp_millis: i64) -> i32 {
+        match self {
+            UserTimezone::Utc => 0,
+            UserTimezone::Local => {
+                DateTime::from_timestamp_millis(timestamp_millis).map_or(0, |datetime| {
+                    datetime
+                        .with_timezone(&chrono::Local)
+                        .offset()
+                        .local_minus_utc()
+                })
+            }
+        }
+    }
+
     /// Converts UTC timestamp to the appropriate timezone and formats it according to timeframe
     pub fn format_timestamp(&self, timestamp: i64, timeframe: exchange::Timeframe) -> String {
         if let Some(datetime) = DateTime::from_timestamp(timestamp, 0) {
@@ -79,7 +94,8 @@ impl fmt::Display for UserTimezone {
                 let local_offset = chrono::Local::now().offset().local_minus_utc();
                 let hours = local_offset / 3600;
                 let minutes = (local_offset % 3600) / 60;
-                write!(f, "Local (UTC {hours:+03}:{minutes:02})")
+                let offset = format!("{hours:+03}:{minutes:02}");
+                f.write_str(&crate::t!("timezone.local", offset = offset))
             }
         }
     }
@@ -0,0 +1,480 @@
+//! Where every file Flowsurface writes lives.
+//!
+//! Command line flags picking a workspace or a state file win over everything, see
+//! [`use_instance`]. Then `FLOWSURFACE_DATA_PATH`, then portable mode, where a
+//! [`PORTABLE_MARKER`] file next to the executable keeps the data in a folder beside it. Otherwise
+//! it's the location picked in the settings, remembered by a pointer file in the platform data
+//! dir, or that platform dir itself.
+//!
+//! Paths kept in the saved state go through [`to_stored`] and [`from_stored`], so they stay valid
+//! after the data dir moved.
+
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, RwLock};
+use std::{fs, io};
+
+pub const PORTABLE_MARKER: &str = "flowsurface-portable";
+
+/// Folder next to the executable holding the data in portable mode
+const PORTABLE_DIR: &str = "data";
+
+/// Names the relocated data dir, the one file that stays in the platform data dir after a move
+const POINTER_FILE: &str = "data-location.txt";
+
+/// Folder in the data dir holding one data dir per named workspace
+const WORKSPACES_DIR: &str = "workspaces";
+
+/// Left behind by a move, the running app keeps writing to it until it restarts
+const KEPT_FILES: [&str; 1] = [crate::log::LOG_FILE];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Origin {
+    CommandLine,
+    Env,
+    Portable,
+    Custom,
+    Default,
+}
+
+impl Origin {
+    /// Whether the settings can move the data dir, the other origins are picked outside the app
+    pub fn is_relocatable(self) -> bool {
+        matches!(self, Origin::Custom | Origin::Default)
+    }
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::CommandLine => write!(f, "set on the command line"),
+            Origin::Env => write!(f, "set by FLOWSURFACE_DATA_PATH"),
+            Origin::Portable => write!(f, "portable mode"),
+            Origin::Custom => write!(f, "custom location"),
+            Origin::Default => write!(f, "default location"),
+        }
+    }
+}
+
+static ROOT: LazyLock<RwLock<(PathBuf, Origin)>> = LazyLock::new(|| RwLock::new(resolve()));
+/// Saved state file picked on the command line, `None` keeps it in the data dir
+static STATE_FILE: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+fn default_dir() -> PathBuf {
+    dirs_next::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("flowsurface")
+}
+
+fn portable_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let dir = exe.parent()?;
+
+    dir.join(PORTABLE_MARKER)
+        .exists()
+        .then(|| dir.join(PORTABLE_DIR))
+}
+
+fn resolve() -> (PathBuf, Origin) {
+    if let Ok(path) = std::env::var("FLOWSURFACE_DATA_PATH") {
+        return (PathBuf::from(path), Origin::Env);
+    }
+    if let Some(dir) = portable_dir() {
+        return (dir, Origin::Portable);
+    }
+
+    let default = default_dir();
+    match fs::read_to_string(default.join(POINTER_FILE)) {
+        Ok(pointer) if !pointer.trim().is_empty() => {
+            (PathBuf::from(pointer.trim()), Origin::Custom)
+        }
+        _ => (default, Origin::Default),
+    }
+}
+
+pub fn root() -> PathBuf {
+    ROOT.read()
+        .map(|root| root.0.clone())
+        .unwrap_or_else(|e| e.into_inner().0.clone())
+}
+
+pub fn origin() -> Origin {
+    ROOT.read().map_or_else(|e| e.into_inner().1, |root| root.1)
+}
+
+/// Where the saved state is read from and written to
+pub fn state_file() -> PathBuf {
+    STATE_FILE
+        .read()
+        .ok()
+        .and_then(|file| file.clone())
+        .unwrap_or_else(|| root().join(crate::SAVED_STATE_PATH))
+}
+
+/// Keeps this instance's files apart from other instances', to be called before anything is
+/// read from the data dir.
+///
+/// A `workspace` gets its own data dir under the usual one. A `state_file` alone keeps every
+/// other file in a folder named after it, next to it, so instances on different state files
+/// never share caches.
+pub fn use_instance(state_file: Option<&Path>, workspace: Option<&str>) -> Result<(), String> {
+    let state_file = state_file
+        .map(std::path::absolute)
+        .transpose()
+        .map_err(|e| format!("Invalid state file: {e}"))?;
+
+    let Some(instance_root) = instance_root(&root(), state_file.as_deref(), workspace)? else {
+        return Ok(());
+    };
+
+    log::info!("Data folder for this instance: {}", instance_root.display());
+    if let Ok(mut root) = ROOT.write() {
+        *root = (instance_root, Origin::CommandLine);
+    }
+    if let Ok(mut file) = STATE_FILE.write() {
+        *file = state_file;
+    }
+    Ok(())
+}
+
+fn instance_root(
+    base: &Path,
+    state_file: Option<&Path>,
+    workspace: Option<&str>,
+) -> Result<Option<PathBuf>, String> {
+    if let Some(name) = workspace {
+        let is_plain_name = !name.trim().is_empty()
+            && name != "."
+            && name != ".."
+            && !name.contains(['/', '\\', ':']);
+        if !is_plain_name {
+            return Err(format!("Invalid workspace name: {name:?}"));
+        }
+        return Ok(Some(base.join(WORKSPACES_DIR).join(name)));
+    }
+
+    let Some(state_file) = state_file else {
+        return Ok(None);
+    };
+    let (Some(dir), Some(stem)) = (state_file.parent(), state_file.file_stem()) else {
+        return Err(format!("Invalid state file: {}", state_file.display()));
+    };
+
+    let mut folder = stem.to_os_string();
+    folder.push("-data");
+    Ok(Some(dir.join(folder)))
+}
+
+/// `path` relative to the data dir when it's inside, for keeping in the saved state
+pub fn to_stored(path: &Path) -> PathBuf {
+    relative_to(&root(), path)
+}
+
+/// Resolves a path kept by [`to_stored`]
+pub fn from_stored(path: &Path) -> PathBuf {
+    if path.is_relative() {
+        root().join(path)
+    } else {
+        path.to_path_buf()
+    }
+}
+
+fn relative_to(root: &Path, path: &Path) -> PathBuf {
+    path.strip_prefix(root)
+        .map_or_else(|_| path.to_path_buf(), Path::to_path_buf)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Progress {
+    pub copied_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl Progress {
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes == 0 {
+            1.0
+        } else {
+            self.copied_bytes as f32 / self.total_bytes as f32
+        }
+    }
+}
+
+/// Makes `target` the data dir. With `move_files`, everything in the current one is copied over
+/// first and removed once all of it arrived. A failed copy removes what it copied and keeps the
+/// current location. Blocks until done.
+pub fn relocate(
+    target: &Path,
+    move_files: bool,
+    on_progress: impl FnMut(Progress),
+) -> Result<PathBuf, String> {
+    if !origin().is_relocatable() {
+        return Err(format!("The data folder is {}", origin()));
+    }
+
+    let target = std::path::absolute(target).map_err(|e| format!("Invalid folder: {e}"))?;
+    let current = root();
+
+    if target == current {
+        return Ok(target);
+    }
+    if target.starts_with(&current) || current.starts_with(&target) {
+        return Err("The new folder can't be inside the current one, or contain it".to_string());
+    }
+    if fs::read_dir(&target).is_ok_and(|mut entries| entries.next().is_some()) {
+        return Err(format!("{} is not empty", target.display()));
+    }
+
+    let files = if move_files && current.exists() {
+        let skipped = [POINTER_FILE].into_iter().chain(KEPT_FILES);
+        let files = list_files(&current, &skipped.collect::<Vec<_>>())
+            .map_err(|e| format!("Failed to list {}: {e}", current.display()))?;
+
+        copy_files(&current, &target, &files, copy_file, on_progress)?;
+        files
+    } else {
+        fs::create_dir_all(&target)
+            .map_err(|e| format!("Failed to create {}: {e}", target.display()))?;
+        vec![]
+    };
+
+    if let Err(e) = write_pointer(&target) {
+        remove_copies(&target, &files);
+        return Err(format!("Failed to remember the new folder: {e}"));
+    }
+
+    if let Ok(mut root) = ROOT.write() {
+        let origin = if target == default_dir() {
+            Origin::Default
+        } else {
+            Origin::Custom
+        };
+        *root = (target.clone(), origin);
+    }
+
+    let leftovers = remove_originals(&current, &files);
+    if leftovers > 0 {
+        log::warn!(
+            "{leftovers} files couldn't be removed from {} after moving them",
+            current.display()
+        );
+    }
+
+    log::info!(
+        "Data folder moved from {} to {}",
+        current.display(),
+        target.display()
+    );
+    Ok(target)
+}
+
+fn write_pointer(target: &Path) -> io::Result<()> {
+    let default = default_dir();
+    let pointer = default.join(POINTER_FILE);
+
+    if target == default {
+        match fs::remove_file(pointer) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    } else {
+        fs::create_dir_all(&default)?;
+        fs::write(pointer, target.to_string_lossy().as_bytes())
+    }
+}
+
+/// Every file under `root`, relative to it, except the top level `skipped` names
+fn list_files(root: &Path, skipped: &[&str]) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    let mut dirs = vec![PathBuf::new()];
+
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(root.join(&dir))? {
+            let entry = entry?;
+            let relative = dir.join(entry.file_name());
+
+            if dir.as_os_str().is_empty() && skipped.iter().any(|name| entry.file_name() == *name) {
+                continue;
+            }
+
+            if entry.file_type()?.is_dir() {
+                dirs.push(relative);
+            } else {
+                files.push(relative);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn copy_file(from: &Path, to: &Path) -> io::Result<u64> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::copy(from, to)
+}
+
+fn copy_files(
+    from: &Path,
+    to: &Path,
+    files: &[PathBuf],
+    mut copy: impl FnMut(&Path, &Path) -> io::Result<u64>,
+    mut on_progress: impl FnMut(Progress),
+) -> Result<(), String> {
+    let mut progress = Progress {
+        copied_bytes: 0,
+        total_bytes: files
+            .iter()
+            .filter_map(|file| fs::metadata(from.join(file)).ok())
+            .map(|metadata| metadata.len())
+            .sum(),
+    };
+    on_progress(progress);
+
+    fs::create_dir_all(to).map_err(|e| format!("Failed to create {}: {e}", to.display()))?;
+
+    for (copied, file) in files.iter().enumerate() {
+        match copy(&from.join(file), &to.join(file)) {
+            Ok(bytes) => {
+                progress.copied_bytes += bytes;
+                on_progress(progress);
+            }
+            Err(e) => {
+                remove_copies(to, &files[..=copied]);
+                return Err(format!("Failed to copy {}: {e}", file.display()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Undoes a copy into `to`, which was empty before it
+fn remove_copies(to: &Path, files: &[PathBuf]) {
+    for file in files {
+        fs::remove_file(to.join(file)).ok();
+    }
+    remove_empty_dirs(to);
+}
+
+/// Returns how many of `files` are still there
+fn remove_originals(from: &Path, files: &[PathBuf]) -> usize {
+    let leftovers = files
+        .iter()
+        .filter(|file| fs::remove_file(from.join(file)).is_err())
+        .count();
+    remove_empty_dirs(from);
+    leftovers
+}
+
+/// Removes the empty folders under `dir`, and `dir` itself if nothing else is left
+fn remove_empty_dirs(dir: &Path) {
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(Result::ok) {
+            if entry.file_type().is_ok_and(|kind| kind.is_dir()) {
+                remove_empty_dirs(&entry.path());
+            }
+        }
+    }
+    // Fails on folders that still hold something, those stay
+    fs::remove_dir(dir).ok();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "flowsurface-{name}-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn stored_paths_are_relative_inside_the_data_dir() {
+        let root = Path::new("/data/flowsurface");
+
+        assert_eq!(
+            relative_to(root, &root.join("heatmap/BTCUSDT.bin")),
+            Path::new("heatmap/BTCUSDT.bin")
+        );
+        assert_eq!(
+            relative_to(root, Path::new("/home/me/alert.wav")),
+            Path::new("/home/me/alert.wav")
+        );
+    }
+
+    #[test]
+    fn instances_get_their_own_data_dir() {
+        let base = Path::new("/data/flowsurface");
+        let state = Path::new("/brokers/icmarkets.json");
+
+        assert_eq!(instance_root(base, None, None), Ok(None));
+        assert_eq!(
+            instance_root(base, Some(state), None),
+            Ok(Some(PathBuf::from("/brokers/icmarkets-data")))
+        );
+        // A workspace decides the data dir, the state file can still live elsewhere
+        assert_eq!(
+            instance_root(base, Some(state), Some("pepperstone")),
+            Ok(Some(base.join("workspaces/pepperstone")))
+        );
+
+        for name in ["", " ", "..", "a/b", "a\\b"] {
+            assert!(instance_root(base, None, Some(name)).is_err(), "{name:?}");
+        }
+    }
+
+    #[test]
+    fn failed_copy_rolls_back_and_keeps_the_source() {
+        let from = scratch_dir("from");
+        let to = scratch_dir("to").join("moved");
+
+        fs::write(from.join("saved-state.json"), "{}").unwrap();
+        fs::write(from.join(POINTER_FILE), "elsewhere").unwrap();
+        fs::create_dir_all(from.join("heatmap")).unwrap();
+        fs::write(from.join("heatmap/BTCUSDT.bin"), [0u8; 64]).unwrap();
+
+        let mut files = list_files(&from, &[POINTER_FILE]).unwrap();
+        files.sort();
+        assert_eq!(
+            files,
+            [
+                PathBuf::from("heatmap/BTCUSDT.bin"),
+                PathBuf::from("saved-state.json")
+            ]
+        );
+
+        let mut calls = 0;
+        let failing_second = |from: &Path, to: &Path| {
+            calls += 1;
+            if calls == 2 {
+                Err(io::Error::other("disk full"))
+            } else {
+                copy_file(from, to)
+            }
+        };
+        let result = copy_files(&from, &to, &files, failing_second, |_| {});
+        assert!(result.unwrap_err().contains("disk full"));
+        assert!(!to.exists());
+        assert!(from.join("heatmap/BTCUSDT.bin").exists());
+
+        let mut last = Progress::default();
+        copy_files(&from, &to, &files, copy_file, |p| last = p).unwrap();
+        assert_eq!(last.copied_bytes, 66);
+        assert_eq!(last.fraction(), 1.0);
+
+        assert_eq!(remove_originals(&from, &files), 0);
+        assert!(from.join(POINTER_FILE).exists());
+        assert!(!from.join("heatmap").exists());
+        assert_eq!(
+            fs::read_to_string(to.join("saved-state.json")).unwrap(),
+            "{}"
+        );
+
+        fs::remove_dir_all(&from).ok();
+        fs::remove_dir_all(to.parent().unwrap()).ok();
+    }
+}
@@ -0,0 +1,262 @@
+//! Translations of user facing text.
+//!
+//! Each locale is a TOML file embedded in the binary, its nested tables become dotted keys such as
+//! `settings.theme`. A key the active locale lacks falls back to English and is logged once, one
+//! English lacks too shows as the key itself. Dynamic text names its parts as `{name}`
+//! placeholders, filled in with [`t!`](crate::t).
+
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+
+const ENGLISH: &str = include_str!("../locales/en.toml");
+const CHINESE_SIMPLIFIED: &str = include_str!("../locales/zh-CN.toml");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en")]
+    English,
+    #[serde(rename = "zh-CN")]
+    ChineseSimplified,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::ChineseSimplified];
+
+    /// BCP 47 tag, also the name of the locale's file
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::ChineseSimplified => "zh-CN",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Locale::English => ENGLISH,
+            Locale::ChineseSimplified => CHINESE_SIMPLIFIED,
+        }
+    }
+
+    fn catalog(self) -> &'static Catalog {
+        static ENGLISH: LazyLock<Catalog> = LazyLock::new(|| Catalog::load(Locale::English));
+        static CHINESE_SIMPLIFIED: LazyLock<Catalog> =
+            LazyLock::new(|| Catalog::load(Locale::ChineseSimplified));
+
+        match self {
+            Locale::English => &ENGLISH,
+            Locale::ChineseSimplified => &CHINESE_SIMPLIFIED,
+        }
+    }
+}
+
+/// Each locale is listed under its own name, so it can be found whatever the active one is
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Locale::English => write!(f, "English"),
+            Locale::ChineseSimplified => write!(f, "简体中文"),
+        }
+    }
+}
+
+static ACTIVE: AtomicU8 = AtomicU8::new(0);
+
+/// Keys already reported missing, per locale
+static MISSING: LazyLock<Mutex<FxHashSet<(Locale, &'static str)>>> =
+    LazyLock::new(|| Mutex::new(FxHashSet::default()));
+
+pub fn set_locale(locale: Locale) {
+    ACTIVE.store(locale as u8, Ordering::Relaxed);
+}
+
+pub fn locale() -> Locale {
+    match ACTIVE.load(Ordering::Relaxed) {
+        1 => Locale::ChineseSimplified,
+        _ => Locale::English,
+    }
+}
+
+/// Text of one locale by dotted key
+#[derive(Debug, Default)]
+struct Catalog(FxHashMap<String, String>);
+
+impl Catalog {
+    fn load(locale: Locale) -> Self {
+        Self::parse(locale.source()).unwrap_or_else(|e| {
+            log::error!("Failed to parse the {} translation: {e}", locale.code());
+            Self::default()
+        })
+    }
+
+    fn parse(source: &str) -> Result<Self, toml::de::Error> {
+        let table: toml::Table = toml::from_str(source)?;
+
+        let mut catalog = Self::default();
+        catalog.flatten("", &table);
+        Ok(catalog)
+    }
+
+    fn flatten(&mut self, prefix: &str, table: &toml::Table) {
+        for (name, value) in table {
+            let key = if prefix.is_empty() {
+                name.clone()
+            } else {
+                format!("{prefix}.{name}")
+            };
+
+            match value {
+                toml::Value::Table(table) => self.flatten(&key, table),
+                toml::Value::String(text) => {
+                    self.0.insert(key, text.clone());
+                }
+                _ => log::warn!("Translation {key} isn't a string"),
+            }
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+}
+
+/// Text of `key` in the active locale
+pub fn text(key: &'static str) -> &'static str {
+    lookup(locale(), key)
+}
+
+/// Text of `key` with each `{name}` placeholder replaced by the argument of that name
+pub fn format(key: &'static str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    interpolate(text(key), args)
+}
+
+fn lookup(locale: Locale, key: &'static str) -> &'static str {
+    let catalog = locale.catalog();
+    if catalog.get(key).is_none() {
+        report_missing(locale, key);
+    }
+    translate(catalog, Locale::English.catalog(), key)
+}
+
+fn translate<'a>(catalog: &'a Catalog, english: &'a Catalog, key: &'a str) -> &'a str {
+    catalog.get(key).or_else(|| english.get(key)).unwrap_or(key)
+}
+
+fn report_missing(locale: Locale, key: &'static str) {
+    let Ok(mut missing) = MISSING.lock() else {
+        return;
+    };
+    if missing.insert((locale, key)) {
+        log::warn!("No {} translation for \"{key}\"", locale.code());
+    }
+}
+
+/// Placeholders without an argument are left in as they are, so a mistake shows up in the UI
+fn interpolate(template: &str, args: &[(&str, &dyn fmt::Display)]) -> String {
+    use std::fmt::Write;
+
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else {
+            break;
+        };
+        let name = &rest[1..end];
+
+        match args.iter().find(|(arg, _)| *arg == name) {
+            Some((_, value)) => {
+                let _ = write!(out, "{value}");
+            }
+            None => out.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+/// Translated text of a key, `t!("key")` gives a `&'static str` and
+/// `t!("key", name = value, ...)` a `String` with the `{name}` placeholders filled in
+#[macro_export]
+macro_rules! t {
+    ($key:literal) => {
+        $crate::i18n::text($key)
+    };
+    ($key:literal, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::format(
+            $key,
+            &[$((stringify!($name), &$value as &dyn ::std::fmt::Display)),+],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placeholders(text: &str) -> Vec<&str> {
+        let mut names: Vec<&str> = text
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn every_locale_translates_every_key() {
+        let english = Catalog::parse(ENGLISH).unwrap();
+        assert!(!english.0.is_empty());
+
+        for locale in Locale::ALL {
+            let catalog = Catalog::parse(locale.source()).unwrap();
+
+            for (key, text) in &english.0 {
+                let translated = catalog
+                    .get(key)
+                    .unwrap_or_else(|| panic!("{} lacks {key}", locale.code()));
+                assert_eq!(
+                    placeholders(translated),
+                    placeholders(text),
+                    "{} {key} has other placeholders",
+                    locale.code()
+                );
+            }
+            for key in catalog.0.keys() {
+                assert!(english.get(key).is_some(), "{key} isn't an English key");
+            }
+        }
+    }
+
+    #[test]
+    fn missing_keys_fall_back_to_english_then_the_key() {
+        let english = Catalog::parse("[menu]\nsave = \"Save\"\nopen = \"Open\"").unwrap();
+        let chinese = Catalog::parse("menu.save = \"保存\"").unwrap();
+
+        assert_eq!(translate(&chinese, &english, "menu.save"), "保存");
+        assert_eq!(translate(&chinese, &english, "menu.open"), "Open");
+        assert_eq!(translate(&chinese, &english, "menu.close"), "menu.close");
+    }
+
+    #[test]
+    fn placeholders_are_filled_by_name() {
+        let filled = interpolate(
+            "{count} new from {source}",
+            &[("source", &"MT5"), ("count", &3)],
+        );
+        assert_eq!(filled, "3 new from MT5");
+
+        assert_eq!(interpolate("{unknown} stays", &[]), "{unknown} stays");
+        assert_eq!(interpolate("unclosed {brace", &[]), "unclosed {brace");
+    }
+}
@@ -0,0 +1,527 @@
+//! Overlay indicators computed from klines and drawn over the price pane.
+//!
+//! Every indicator keeps one point per bar, oldest first. A bar newer than the last one appends
+//! a point, an update of the forming bar only recomputes the last point, anything else (e.g.
+//! backfilled history landing before the first bar) needs a full recompute.
+
+pub mod script;
+pub mod signal;
+
+use crate::chart::session::session_bounds;
+use crate::config::timezone::UserTimezone;
+use exchange::Kline;
+
+use serde::{Deserialize, Serialize};
+
+/// An indicator and its parameters, saved with the pane settings
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub enum Overlay {
+    Sma {
+        period: usize,
+    },
+    Ema {
+        period: usize,
+    },
+    /// Volume weighted average of the typical price, reset at every session start
+    Vwap {
+        /// Hour of day, in the user's timezone, at which a new session starts
+        session_start_hour: u8,
+    },
+    Bollinger {
+        period: usize,
+        /// Band width in standard deviations from the middle line
+        std_dev: f32,
+    },
+    /// A user script from the scripts folder, see [`script`]
+    Script {
+        name: String,
+        /// Values of the parameters the script declares, by name
+        params: Vec<(String, f32)>,
+    },
+}
+
+impl Overlay {
+    /// Defaults offered when adding an overlay to a pane
+    pub const ALL: [Overlay; 4] = [
+        Overlay::Sma { period: 20 },
+        Overlay::Ema { period: 20 },
+        Overlay::Vwap {
+            session_start_hour: 0,
+        },
+        Overlay::Bollinger {
+            period: 20,
+            std_dev: 2.0,
+        },
+    ];
+
+    pub fn name(&self) -> &str {
+        match self {
+            Overlay::Sma { .. } => "SMA",
+            Overlay::Ema { .. } => "EMA",
+            Overlay::Vwap { .. } => "VWAP",
+            Overlay::Bollinger { .. } => "Bollinger Bands",
+            Overlay::Script { name, .. } => name,
+        }
+    }
+
+    /// An empty indicator for these parameters, sessions are judged in `timezone`
+    pub fn build(&self, timezone: UserTimezone) -> Box<dyn Indicator> {
+        match *self {
+            Overlay::Sma { period } => Box::new(Series::new(Sma {
+                period: period.max(1),
+            })),
+            Overlay::Ema { period } => Box::new(Series::new(Ema {
+                period: period.max(1),
+            })),
+            Overlay::Vwap { session_start_hour } => Box::new(Series::new(Vwap {
+                timezone,
+                start_hour: session_start_hour,
+            })),
+            Overlay::Bollinger { period, std_dev } => Box::new(Series::new(Bollinger {
+                period: period.max(1),
+                std_dev,
+            })),
+            Overlay::Script {
+                ref name,
+                ref params,
+            } => Box::new(script::ScriptIndicator::new(
+                name,
+                script::get(name),
+                params,
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Overlay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Overlay::Sma { period } => write!(f, "SMA {period}"),
+            Overlay::Ema { period } => write!(f, "EMA {period}"),
+            Overlay::Vwap { .. } => write!(f, "VWAP"),
+            Overlay::Bollinger { period, std_dev } => write!(f, "BB {period}, {std_dev:.1}"),
+            Overlay::Script { name, params } => {
+                write!(f, "{name}")?;
+                for (i, (_, value)) in params.iter().enumerate() {
+                    write!(f, "{}{value}", if i == 0 { " " } else { ", " })?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Line(f32),
+    Band { middle: f32, upper: f32, lower: f32 },
+}
+
+impl Value {
+    pub fn middle(&self) -> f32 {
+        match *self {
+            Value::Line(value) => value,
+            Value::Band { middle, .. } => middle,
+        }
+    }
+
+    /// Upper and lower edges of a band
+    pub fn band(&self) -> Option<(f32, f32)> {
+        match *self {
+            Value::Line(_) => None,
+            Value::Band { upper, lower, .. } => Some((upper, lower)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Point {
+    /// Open time of the bar
+    pub time: u64,
+    /// `None` until enough bars were folded in
+    pub value: Option<Value>,
+}
+
+pub trait Indicator {
+    /// Parameters it was built with
+    fn params(&self) -> Overlay;
+
+    /// One point per bar folded in so far, oldest first
+    fn points(&self) -> &[Point];
+
+    /// Appends the point of a bar newer than every one so far
+    fn push(&mut self, kline: &Kline);
+
+    /// The forming bar changed, recomputes only its point
+    fn amend_last(&mut self, kline: &Kline);
+
+    fn clear(&mut self);
+
+    /// Recomputes every point from `klines`, oldest first
+    fn recompute(&mut self, klines: &[Kline]) {
+        self.clear();
+        klines.iter().for_each(|kline| self.push(kline));
+    }
+
+    /// Folds in a bar keyed by its open time. Returns `false` for a bar older than the last one,
+    /// which only a [`Indicator::recompute`] can place.
+    fn update(&mut self, kline: &Kline) -> bool {
+        match self.points().last() {
+            Some(last) if kline.time == last.time => self.amend_last(kline),
+            Some(last) if kline.time < last.time => return false,
+            _ => self.push(kline),
+        }
+        true
+    }
+
+    /// Lines drawn besides the points, each with one value per point
+    fn extra_lines(&self) -> &[Vec<Option<f32>>] {
+        &[]
+    }
+
+    /// An error to surface to the user, each distinct one is returned once
+    fn take_error(&mut self) -> Option<String> {
+        None
+    }
+}
+
+/// Computes the value at a bar from the bars up to it and the points before it
+trait Formula {
+    fn params(&self) -> Overlay;
+
+    /// Value at the last of `klines`, `prev` holds the points of every bar before it
+    fn value(&self, klines: &[Kline], prev: &[Point]) -> Option<Value>;
+}
+
+struct Series<F> {
+    formula: F,
+    klines: Vec<Kline>,
+    points: Vec<Point>,
+}
+
+impl<F: Formula> Series<F> {
+    fn new(formula: F) -> Self {
+        Self {
+            formula,
+            klines: vec![],
+            points: vec![],
+        }
+    }
+
+    fn push_point(&mut self) {
+        let Some(time) = self.klines.last().map(|kline| kline.time) else {
+            return;
+        };
+        let value = self.formula.value(&self.klines, &self.points);
+        self.points.push(Point { time, value });
+    }
+}
+
+impl<F: Formula> Indicator for Series<F> {
+    fn params(&self) -> Overlay {
+        self.formula.params()
+    }
+
+    fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    fn push(&mut self, kline: &Kline) {
+        self.klines.push(*kline);
+        self.push_point();
+    }
+
+    fn amend_last(&mut self, kline: &Kline) {
+        match self.klines.last_mut() {
+            Some(last) => *last = *kline,
+            None => self.klines.push(*kline),
+        }
+        self.points.pop();
+        self.push_point();
+    }
+
+    fn clear(&mut self) {
+        self.klines.clear();
+        self.points.clear();
+    }
+}
+
+fn closes(klines: &[Kline], period: usize) -> Option<impl Iterator<Item = f64> + '_> {
+    let start = klines.len().checked_sub(period)?;
+    Some(
+        klines[start..]
+            .iter()
+            .map(|kline| f64::from(kline.close.to_f32())),
+    )
+}
+
+fn mean(klines: &[Kline], period: usize) -> Option<f64> {
+    closes(klines, period).map(|closes| closes.sum::<f64>() / period as f64)
+}
+
+struct Sma {
+    period: usize,
+}
+
+impl Formula for Sma {
+    fn params(&self) -> Overlay {
+        Overlay::Sma {
+            period: self.period,
+        }
+    }
+
+    fn value(&self, klines: &[Kline], _prev: &[Point]) -> Option<Value> {
+        mean(klines, self.period).map(|mean| Value::Line(mean as f32))
+    }
+}
+
+/// Seeded with the simple average of the first `period` closes
+struct Ema {
+    period: usize,
+}
+
+impl Formula for Ema {
+    fn params(&self) -> Overlay {
+        Overlay::Ema {
+            period: self.period,
+        }
+    }
+
+    fn value(&self, klines: &[Kline], prev: &[Point]) -> Option<Value> {
+        let close = f64::from(klines.last()?.close.to_f32());
+        let alpha = 2.0 / (self.period as f64 + 1.0);
+
+        let ema = match prev.last().and_then(|point| point.value) {
+            Some(prev) => alpha * close + (1.0 - alpha) * f64::from(prev.middle()),
+            None => mean(klines, self.period)?,
+        };
+        Some(Value::Line(ema as f32))
+    }
+}
+
+struct Vwap {
+    timezone: UserTimezone,
+    start_hour: u8,
+}
+
+impl Formula for Vwap {
+    fn params(&self) -> Overlay {
+        Overlay::Vwap {
+            session_start_hour: self.start_hour,
+        }
+    }
+
+    fn value(&self, klines: &[Kline], _prev: &[Point]) -> Option<Value> {
+        let last = klines.last()?;
+        let (session_start, _) = session_bounds(self.timezone, self.start_hour, last.time);
+        let first = klines.partition_point(|kline| kline.time < session_start);
+
+        let (mut price_volume, mut volume) = (0.0, 0.0);
+        for kline in &klines[first..] {
+            let typical = (f64::from(kline.high.to_f32())
+                + f64::from(kline.low.to_f32())
+                + f64::from(kline.close.to_f32()))
+                / 3.0;
+            // Some venues only report the total, in the sell side with the buy side at -1
+            let (buy, sell) = kline.volume;
+            let qty = f64::from(if buy < 0.0 { sell } else { buy + sell });
+
+            price_volume += typical * qty;
+            volume += qty;
+        }
+
+        if volume > 0.0 {
+            Some(Value::Line((price_volume / volume) as f32))
+        } else {
+            // No volume yet this session, fall back to the bar's own typical price
+            let typical = (last.high.to_f32() + last.low.to_f32() + last.close.to_f32()) / 3.0;
+            Some(Value::Line(typical))
+        }
+    }
+}
+
+/// Simple average with bands at `std_dev` population standard deviations
+struct Bollinger {
+    period: usize,
+    std_dev: f32,
+}
+
+impl Formula for Bollinger {
+    fn params(&self) -> Overlay {
+        Overlay::Bollinger {
+            period: self.period,
+            std_dev: self.std_dev,
+        }
+    }
+
+    fn value(&self, klines: &[Kline], _prev: &[Point]) -> Option<Value> {
+        let middle = mean(klines, self.period)?;
+        let variance = closes(klines, self.period)?
+            .map(|close| (close - middle).powi(2))
+            .sum::<f64>()
+            / self.period as f64;
+        let width = variance.sqrt() * f64::from(self.std_dev);
+
+        Some(Value::Band {
+            middle: middle as f32,
+            upper: (middle + width) as f32,
+            lower: (middle - width) as f32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::util::Price;
+
+    const HOUR_MS: u64 = 3_600_000;
+    const DAY_MS: u64 = 24 * HOUR_MS;
+
+    fn kline(time: u64, high: f32, low: f32, close: f32, volume: f32) -> Kline {
+        Kline {
+            time,
+            open: Price::from_f32(close),
+            high: Price::from_f32(high),
+            low: Price::from_f32(low),
+            close: Price::from_f32(close),
+            volume: (volume / 2.0, volume / 2.0),
+        }
+    }
+
+    fn closes(closes: &[f32]) -> Vec<Kline> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| kline(i as u64 * 60_000, close, close, close, 1.0))
+            .collect()
+    }
+
+    fn lines(indicator: &dyn Indicator) -> Vec<Option<f32>> {
+        indicator
+            .points()
+            .iter()
+            .map(|point| point.value.map(|value| value.middle()))
+            .collect()
+    }
+
+    fn assert_close(actual: f32, expected: f32, tolerance: f32) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{actual} is not within {tolerance} of {expected}"
+        );
+    }
+
+    #[test]
+    fn sma_fills_after_period() {
+        let mut sma = Overlay::Sma { period: 3 }.build(UserTimezone::Utc);
+        sma.recompute(&closes(&[1.0, 2.0, 3.0, 4.0, 5.0]));
+
+        assert_eq!(
+            lines(&*sma),
+            vec![None, None, Some(2.0), Some(3.0), Some(4.0)]
+        );
+    }
+
+    #[test]
+    fn ema_matches_reference_values() {
+        // 10 period EMA seeded with the 10 period SMA, from the StockCharts worked example
+        let input = [
+            22.27, 22.19, 22.08, 22.17, 22.18, 22.13, 22.23, 22.43, 22.24, 22.29, 22.15, 22.39,
+            22.38, 22.61, 23.36, 24.05, 23.75, 23.83, 23.95, 23.63,
+        ];
+        let expected = [
+            22.22, 22.21, 22.24, 22.27, 22.33, 22.52, 22.80, 22.97, 23.13, 23.28, 23.34,
+        ];
+
+        let mut ema = Overlay::Ema { period: 10 }.build(UserTimezone::Utc);
+        ema.recompute(&closes(&input));
+
+        let values = lines(&*ema);
+        assert!(values[..9].iter().all(Option::is_none));
+        for (value, expected) in values[9..].iter().zip(expected) {
+            assert_close(value.unwrap(), expected, 0.006);
+        }
+    }
+
+    #[test]
+    fn bollinger_bands_use_population_deviation() {
+        let mut bb = Overlay::Bollinger {
+            period: 5,
+            std_dev: 2.0,
+        }
+        .build(UserTimezone::Utc);
+        bb.recompute(&closes(&[1.0, 2.0, 3.0, 4.0, 5.0]));
+
+        let Some(Value::Band {
+            middle,
+            upper,
+            lower,
+        }) = bb.points()[4].value
+        else {
+            panic!("expected a band at the fifth bar");
+        };
+        assert_close(middle, 3.0, 1e-5);
+        assert_close(upper, 5.828_427, 1e-4);
+        assert_close(lower, 0.171_573, 1e-4);
+    }
+
+    #[test]
+    fn vwap_weights_typical_price_and_resets_each_session() {
+        let mut vwap = Overlay::Vwap {
+            session_start_hour: 0,
+        }
+        .build(UserTimezone::Utc);
+
+        let klines = [
+            // typical price 10, then 20 at three times the volume
+            kline(DAY_MS - HOUR_MS, 11.0, 9.0, 10.0, 100.0),
+            kline(DAY_MS, 12.0, 8.0, 10.0, 100.0),
+            kline(DAY_MS + HOUR_MS, 21.0, 19.0, 20.0, 300.0),
+        ];
+        vwap.recompute(&klines);
+
+        let values = lines(&*vwap);
+        assert_close(values[0].unwrap(), 10.0, 1e-4);
+        // The bar before midnight belongs to the previous session
+        assert_close(values[1].unwrap(), 10.0, 1e-4);
+        assert_close(values[2].unwrap(), 17.5, 1e-4);
+    }
+
+    #[test]
+    fn forming_bar_and_backfill_match_full_recompute() {
+        let input = closes(&[5.0, 6.0, 7.0, 8.0, 9.0, 10.0]);
+
+        for params in Overlay::ALL {
+            let params = match params {
+                Overlay::Sma { .. } => Overlay::Sma { period: 3 },
+                Overlay::Ema { .. } => Overlay::Ema { period: 3 },
+                Overlay::Bollinger { std_dev, .. } => Overlay::Bollinger { period: 3, std_dev },
+                other => other,
+            };
+
+            let mut full = params.build(UserTimezone::Utc);
+            full.recompute(&input);
+
+            // Live updates: the last bar forms at 12 before closing at 10
+            let mut live = params.build(UserTimezone::Utc);
+            input[..5].iter().for_each(|k| assert!(live.update(k)));
+            let mut forming = input[5];
+            forming.close = Price::from_f32(12.0);
+            assert!(live.update(&forming));
+            assert!(live.update(&input[5]));
+            assert_eq!(live.points(), full.points(), "{params}");
+
+            // Backfill lands before the first bar
+            let mut backfilled = params.build(UserTimezone::Utc);
+            input[3..]
+                .iter()
+                .for_each(|k| assert!(backfilled.update(k)));
+            assert!(!backfilled.update(&input[0]));
+            backfilled.recompute(&input);
+            assert_eq!(backfilled.points(), full.points(), "{params}");
+
+            assert_eq!(live.params(), params);
+        }
+    }
+}
@@ -0,0 +1,676 @@
+//! User indicators written in Rhai, loaded from the [`SCRIPTS_DIR`] folder in the data dir.
+//!
+//! A script defines `compute(bars, params)`, where `bars` maps `time`, `open`, `high`, `low`,
+//! `close` and `volume` to arrays with one number per bar, oldest first, and `delta` to buy
+//! minus sell volume, `()` for venues that only report the total. It returns an array with one
+//! value per bar for a single line, or a map of such arrays for several lines drawn in key
+//! order. A `()` or any other non-number leaves a gap. Scripts defining
+//! `signal(bars, book, params)` instead are [`signal`](super::signal) scripts.
+//!
+//! An optional `params()` returns a map of parameter defaults, either a number or
+//! `[default, min, max]` with an optional step, e.g. `#{ period: [14, 2, 100] }`.
+//!
+//! Scripts run sandboxed: no modules or file access, bounded operations, call depth and sizes,
+//! and at most [`TIME_LIMIT`] per run.
+
+use super::{Indicator, Overlay, Point, Value, signal};
+use exchange::Kline;
+
+use rhai::{AST, Array, Dynamic, Engine, Map, Scope};
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+pub const SCRIPTS_DIR: &str = "scripts";
+const EXTENSION: &str = "rhai";
+
+/// Longest a single run may take before it's aborted
+pub const TIME_LIMIT: Duration = Duration::from_millis(100);
+const MAX_OPERATIONS: u64 = 50_000_000;
+/// Only the newest bars are handed to a script
+pub const MAX_BARS: usize = 5_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub default: f32,
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptKind {
+    /// Draws lines over the chart from `compute(bars, params)`
+    Indicator,
+    /// Marks bars and posts notices from `signal(bars, book, params)`
+    Signal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Script {
+    /// File name without the extension
+    pub name: String,
+    pub kind: ScriptKind,
+    pub source: String,
+    pub params: Vec<Param>,
+    modified: Option<SystemTime>,
+}
+
+impl Script {
+    /// Compiles `source` and reads its parameter declarations
+    pub fn parse(name: impl Into<String>, source: impl Into<String>) -> Result<Self, String> {
+        let (name, source) = (name.into(), source.into());
+
+        let engine = sandboxed_engine(None);
+        let ast = engine.compile(&source).map_err(|e| e.to_string())?;
+
+        let defines = |name: &str, arity: usize| {
+            ast.iter_functions()
+                .any(|f| f.name == name && f.params.len() == arity)
+        };
+        let kind = if defines("compute", 2) {
+            ScriptKind::Indicator
+        } else if defines("signal", 3) {
+            ScriptKind::Signal
+        } else {
+            return Err(
+                "missing `fn compute(bars, params)` or `fn signal(bars, book, params)`".to_string(),
+            );
+        };
+
+        let params = if ast
+            .iter_functions()
+            .any(|f| f.name == "params" && f.params.is_empty())
+        {
+            let declared = engine
+                .call_fn::<Map>(&mut Scope::new(), &ast, "params", ())
+                .map_err(|e| format!("params(): {e}"))?;
+            declared
+                .into_iter()
+                .map(|(name, value)| parse_param(name.to_string(), value))
+                .collect::<Result<_, _>>()?
+        } else {
+            vec![]
+        };
+
+        Ok(Self {
+            name,
+            kind,
+            source,
+            params,
+            modified: None,
+        })
+    }
+
+    /// The overlay for this script with every parameter at its default
+    pub fn overlay(&self) -> Overlay {
+        Overlay::Script {
+            name: self.name.clone(),
+            params: self
+                .params
+                .iter()
+                .map(|param| (param.name.clone(), param.default))
+                .collect(),
+        }
+    }
+}
+
+fn parse_param(name: String, value: Dynamic) -> Result<Param, String> {
+    let number = |value: &Dynamic| {
+        value
+            .as_float()
+            .ok()
+            .or_else(|| value.as_int().ok().map(|int| int as f64))
+            .map(|value| value as f32)
+    };
+    let invalid = || format!("params(): `{name}` must be a number or [default, min, max, step]");
+
+    if let Some(default) = number(&value) {
+        let is_whole = default.fract() == 0.0;
+        return Ok(Param {
+            default,
+            min: 0.0,
+            max: (default.abs() * 4.0).max(1.0),
+            step: if is_whole { 1.0 } else { 0.1 },
+            name,
+        });
+    }
+
+    let Some(array) = value.try_cast::<Array>() else {
+        return Err(invalid());
+    };
+    let numbers: Vec<f32> = array
+        .iter()
+        .map(number)
+        .collect::<Option<_>>()
+        .ok_or_else(invalid)?;
+
+    match numbers[..] {
+        [default, min, max] | [default, min, max, _] if min <= max => Ok(Param {
+            name,
+            default: default.clamp(min, max),
+            min,
+            max,
+            step: numbers
+                .get(3)
+                .copied()
+                .filter(|step| *step > 0.0)
+                .unwrap_or(if [default, min, max].iter().all(|n| n.fract() == 0.0) {
+                    1.0
+                } else {
+                    0.1
+                }),
+        }),
+        _ => Err(invalid()),
+    }
+}
+
+/// Engine without modules and with bounded resources, runs are aborted past the deadline, which
+/// is `limit` after the run started
+pub(super) fn sandboxed_engine(deadline: Option<(Rc<Cell<Instant>>, Duration)>) -> Engine {
+    let mut engine = Engine::new();
+
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .set_max_string_size(10_000)
+        .set_max_array_size(MAX_BARS * 4)
+        .set_max_map_size(64)
+        .on_print(|text| log::info!("script: {text}"))
+        .on_debug(|text, _, pos| log::debug!("script {pos}: {text}"));
+
+    if let Some((deadline, limit)) = deadline {
+        engine.on_progress(move |_| {
+            (Instant::now() > deadline.get())
+                .then(|| format!("took longer than {}ms", limit.as_millis()).into())
+        });
+    }
+
+    engine
+}
+
+/// Scripts found by the last [`reload`]
+static REGISTRY: RwLock<Vec<Script>> = RwLock::new(Vec::new());
+/// Bumped whenever a reload changed any script, so charts rebuild the ones they run
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+pub fn generation() -> u64 {
+    GENERATION.load(Ordering::Relaxed)
+}
+
+pub fn get(name: &str) -> Option<Script> {
+    REGISTRY
+        .read()
+        .ok()?
+        .iter()
+        .find(|script| script.name == name)
+        .cloned()
+}
+
+/// Every loaded script, by name
+pub fn available() -> Vec<Script> {
+    REGISTRY
+        .read()
+        .map(|scripts| scripts.clone())
+        .unwrap_or_default()
+}
+
+/// Loaded scripts of `kind`, by name
+pub fn available_of(kind: ScriptKind) -> Vec<Script> {
+    let mut scripts = available();
+    scripts.retain(|script| script.kind == kind);
+    scripts
+}
+
+pub fn dir() -> PathBuf {
+    crate::data_path(Some(SCRIPTS_DIR))
+}
+
+#[derive(Debug, Default)]
+pub struct Reload {
+    pub loaded: usize,
+    /// Scripts that were added, edited or removed
+    pub changed: usize,
+    /// File name and why it couldn't be loaded
+    pub errors: Vec<(String, String)>,
+}
+
+/// Re-reads the scripts folder, creating it with the [`signal::EXAMPLES`] if it's missing.
+/// Unchanged files are kept as they are.
+pub fn reload() -> Reload {
+    let dir = dir();
+    let mut report = Reload::default();
+
+    let first_run = !dir.exists();
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        report
+            .errors
+            .push((dir.display().to_string(), e.to_string()));
+        return report;
+    }
+    if first_run {
+        for (file, source) in signal::EXAMPLES {
+            if let Err(e) = std::fs::write(dir.join(file), source) {
+                report.errors.push((file.to_string(), e.to_string()));
+            }
+        }
+    }
+
+    let previous = available();
+    let mut scripts = vec![];
+
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report
+                .errors
+                .push((dir.display().to_string(), e.to_string()));
+            return report;
+        }
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == EXTENSION))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        match load(&path, &previous) {
+            Ok(script) => scripts.push(script),
+            Err(e) => {
+                let file = path.file_name().map_or_else(
+                    || path.display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                );
+                report.errors.push((file, e));
+            }
+        }
+    }
+
+    report.loaded = scripts.len();
+    report.changed = scripts
+        .iter()
+        .filter(|script| !previous.contains(script))
+        .count()
+        + previous
+            .iter()
+            .filter(|old| !scripts.iter().any(|script| script.name == old.name))
+            .count();
+
+    if report.changed > 0 {
+        GENERATION.fetch_add(1, Ordering::Relaxed);
+    }
+    if let Ok(mut registry) = REGISTRY.write() {
+        *registry = scripts;
+    }
+
+    report
+}
+
+fn load(path: &Path, previous: &[Script]) -> Result<Script, String> {
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .ok_or("no file name")?;
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+
+    if let Some(unchanged) = previous
+        .iter()
+        .find(|script| script.name == name && modified.is_some() && script.modified == modified)
+    {
+        return Ok(unchanged.clone());
+    }
+
+    let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut script = Script::parse(name, source)?;
+    script.modified = modified;
+    Ok(script)
+}
+
+/// Runs a script over every bar on each update. Its first line becomes the point values, the
+/// rest are [`Indicator::extra_lines`].
+pub struct ScriptIndicator {
+    name: String,
+    params: Vec<(String, f32)>,
+    compiled: Result<(Engine, AST), String>,
+    deadline: Rc<Cell<Instant>>,
+    klines: Vec<Kline>,
+    points: Vec<Point>,
+    extra_lines: Vec<Vec<Option<f32>>>,
+    error: Option<String>,
+    error_reported: bool,
+}
+
+impl ScriptIndicator {
+    /// `params` override the script's defaults by name, `None` reports the script as missing
+    pub fn new(name: &str, script: Option<Script>, params: &[(String, f32)]) -> Self {
+        let deadline = Rc::new(Cell::new(Instant::now()));
+
+        let compiled = match &script {
+            Some(script) => {
+                let engine = sandboxed_engine(Some((deadline.clone(), TIME_LIMIT)));
+                engine
+                    .compile(&script.source)
+                    .map(|ast| (engine, ast))
+                    .map_err(|e| e.to_string())
+            }
+            None => Err(format!("no script named {name:?} in the scripts folder")),
+        };
+        let params = resolve_params(script.as_ref(), params);
+
+        let error = compiled.as_ref().err().cloned();
+
+        Self {
+            name: name.to_string(),
+            params,
+            compiled,
+            deadline,
+            klines: vec![],
+            points: vec![],
+            extra_lines: vec![],
+            error,
+            error_reported: false,
+        }
+    }
+
+    fn run(&mut self) {
+        let result = self.evaluate();
+
+        let lines = match result {
+            Ok(lines) => {
+                self.set_error(None);
+                lines
+            }
+            Err(e) => {
+                self.set_error(Some(e));
+                vec![]
+            }
+        };
+
+        let mut lines = lines.into_iter();
+        let first = lines.next().unwrap_or_default();
+
+        self.points = self
+            .klines
+            .iter()
+            .enumerate()
+            .map(|(i, kline)| Point {
+                time: kline.time,
+                value: first.get(i).copied().flatten().map(Value::Line),
+            })
+            .collect();
+        self.extra_lines = lines.collect();
+    }
+
+    /// Lines aligned with `self.klines`, shorter results are padded with gaps
+    fn evaluate(&self) -> Result<Vec<Vec<Option<f32>>>, String> {
+        let (engine, ast) = self.compiled.as_ref().map_err(Clone::clone)?;
+        if self.klines.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let skipped = self.klines.len().saturating_sub(MAX_BARS);
+        let bars = &self.klines[skipped..];
+
+        self.deadline.set(Instant::now() + TIME_LIMIT);
+        let output = engine
+            .call_fn::<Dynamic>(
+                &mut Scope::new(),
+                ast,
+                "compute",
+                (bars_input(bars), params_input(&self.params)),
+            )
+            .map_err(|e| e.to_string())?;
+
+        let to_line = |value: Dynamic| -> Result<Vec<Option<f32>>, String> {
+            let values = value
+                .try_cast::<Array>()
+                .ok_or("compute() must return an array or a map of arrays")?;
+
+            let mut line = vec![None; skipped];
+            line.extend(values.into_iter().take(bars.len()).map(|v| {
+                v.as_float()
+                    .ok()
+                    .or_else(|| v.as_int().ok().map(|int| int as f64))
+                    .map(|v| v as f32)
+                    .filter(|v| v.is_finite())
+            }));
+            line.resize(self.klines.len(), None);
+            Ok(line)
+        };
+
+        if output.is_map() {
+            let map = output.cast::<Map>();
+            map.into_values().map(to_line).collect()
+        } else {
+            Ok(vec![to_line(output)?])
+        }
+    }
+
+    fn set_error(&mut self, error: Option<String>) {
+        if self.error != error {
+            self.error = error;
+            self.error_reported = false;
+        }
+    }
+}
+
+/// Parameter values for `script`, saved values win and ones it no longer declares are dropped
+pub(super) fn resolve_params(
+    script: Option<&Script>,
+    saved: &[(String, f32)],
+) -> Vec<(String, f32)> {
+    match script {
+        Some(script) => script
+            .params
+            .iter()
+            .map(|param| {
+                let saved = saved.iter().find(|(name, _)| *name == param.name);
+                let value = saved.map_or(param.default, |(_, v)| v.clamp(param.min, param.max));
+                (param.name.clone(), value)
+            })
+            .collect(),
+        None => saved.to_vec(),
+    }
+}
+
+/// The `bars` map scripts get, a column per field
+pub(super) fn bars_input(bars: &[Kline]) -> Map {
+    let column = |f: &dyn Fn(&Kline) -> Dynamic| -> Dynamic {
+        Dynamic::from_array(bars.iter().map(f).collect())
+    };
+    let float = |value: f32| Dynamic::from_float(f64::from(value));
+
+    let mut input = Map::new();
+    input.insert(
+        "time".into(),
+        column(&|k| Dynamic::from_float(k.time as f64)),
+    );
+    input.insert("open".into(), column(&|k| float(k.open.to_f32())));
+    input.insert("high".into(), column(&|k| float(k.high.to_f32())));
+    input.insert("low".into(), column(&|k| float(k.low.to_f32())));
+    input.insert("close".into(), column(&|k| float(k.close.to_f32())));
+    input.insert(
+        "volume".into(),
+        column(&|k| {
+            let (buy, sell) = k.volume;
+            float(if buy < 0.0 { sell } else { buy + sell })
+        }),
+    );
+    input.insert(
+        "delta".into(),
+        column(&|k| {
+            let (buy, sell) = k.volume;
+            if buy < 0.0 {
+                Dynamic::UNIT
+            } else {
+                float(buy - sell)
+            }
+        }),
+    );
+    input
+}
+
+pub(super) fn params_input(params: &[(String, f32)]) -> Map {
+    params
+        .iter()
+        .map(|(name, value)| (name.into(), Dynamic::from_float(f64::from(*value))))
+        .collect()
+}
+
+impl Indicator for ScriptIndicator {
+    fn params(&self) -> Overlay {
+        Overlay::Script {
+            name: self.name.clone(),
+            params: self.params.clone(),
+        }
+    }
+
+    fn points(&self) -> &[Point] {
+        &self.points
+    }
+
+    fn push(&mut self, kline: &Kline) {
+        self.klines.push(*kline);
+        self.run();
+    }
+
+    fn amend_last(&mut self, kline: &Kline) {
+        match self.klines.last_mut() {
+            Some(last) => *last = *kline,
+            None => self.klines.push(*kline),
+        }
+        self.run();
+    }
+
+    fn clear(&mut self) {
+        self.klines.clear();
+        self.points.clear();
+        self.extra_lines.clear();
+    }
+
+    /// Scripts see the whole series at once, so a recompute is a single run
+    fn recompute(&mut self, klines: &[Kline]) {
+        self.klines = klines.to_vec();
+        self.run();
+    }
+
+    fn extra_lines(&self) -> &[Vec<Option<f32>>] {
+        &self.extra_lines
+    }
+
+    fn take_error(&mut self) -> Option<String> {
+        if self.error_reported {
+            return None;
+        }
+        self.error_reported = true;
+        self.error.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::util::Price;
+
+    fn klines(closes: &[f32]) -> Vec<Kline> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Kline {
+                time: i as u64 * 60_000,
+                open: Price::from_f32(close),
+                high: Price::from_f32(close + 1.0),
+                low: Price::from_f32(close - 1.0),
+                close: Price::from_f32(close),
+                volume: (1.0, 1.0),
+            })
+            .collect()
+    }
+
+    const MOMENTUM: &str = r#"
+        fn params() { #{ lookback: [2, 1, 10] } }
+
+        fn compute(bars, params) {
+            let n = params.lookback.to_int();
+            let out = [];
+            for i in 0..bars.close.len() {
+                out.push(if i >= n { bars.close[i] - bars.close[i - n] } else { () });
+            }
+            out
+        }
+    "#;
+
+    #[test]
+    fn parses_params_and_runs_over_bars() {
+        let script = Script::parse("momentum", MOMENTUM).unwrap();
+        assert_eq!(
+            script.params,
+            vec![Param {
+                name: "lookback".to_string(),
+                default: 2.0,
+                min: 1.0,
+                max: 10.0,
+                step: 1.0,
+            }]
+        );
+
+        let mut indicator = ScriptIndicator::new("momentum", Some(script), &[]);
+        indicator.recompute(&klines(&[1.0, 2.0, 4.0, 7.0]));
+
+        let values: Vec<_> = indicator
+            .points()
+            .iter()
+            .map(|p| p.value.map(|v| v.middle()))
+            .collect();
+        assert_eq!(values, vec![None, None, Some(3.0), Some(5.0)]);
+        assert_eq!(indicator.take_error(), None);
+
+        // A saved value overrides the default, the forming bar reruns the script
+        let script = Script::parse("momentum", MOMENTUM).unwrap();
+        let mut indicator =
+            ScriptIndicator::new("momentum", Some(script), &[("lookback".to_string(), 1.0)]);
+        indicator.recompute(&klines(&[1.0, 2.0, 4.0]));
+        indicator.amend_last(&klines(&[1.0, 2.0, 10.0])[2]);
+        assert_eq!(indicator.points()[2].value, Some(Value::Line(8.0)));
+    }
+
+    #[test]
+    fn map_output_draws_several_lines() {
+        let script = Script::parse(
+            "range",
+            "fn compute(bars, params) { #{ a_high: bars.high, b_low: bars.low } }",
+        )
+        .unwrap();
+
+        let mut indicator = ScriptIndicator::new("range", Some(script), &[]);
+        indicator.recompute(&klines(&[5.0, 6.0]));
+
+        assert_eq!(indicator.points()[1].value, Some(Value::Line(7.0)));
+        assert_eq!(indicator.extra_lines(), &[vec![Some(4.0), Some(5.0)]]);
+    }
+
+    #[test]
+    fn runaway_and_broken_scripts_report_once() {
+        let script = Script::parse("spin", "fn compute(bars, params) { loop {} }").unwrap();
+        let mut indicator = ScriptIndicator::new("spin", Some(script), &[]);
+        indicator.recompute(&klines(&[1.0]));
+
+        assert!(indicator.take_error().is_some());
+        assert_eq!(indicator.take_error(), None);
+        assert_eq!(indicator.points()[0].value, None);
+
+        assert!(Script::parse("broken", "fn compute(bars, params) {").is_err());
+        assert!(Script::parse("no_compute", "fn other() { 1 }").is_err());
+
+        let mut missing = ScriptIndicator::new("gone", None, &[]);
+        assert!(missing.take_error().unwrap().contains("gone"));
+    }
+}
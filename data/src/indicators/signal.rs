@@ -0,0 +1,529 @@
+//! Signal scripts, Rhai scripts in the [`script`] folder that watch a pane's bars and order book
+//! and mark or announce what they find.
+//!
+//! A signal script defines `signal(bars, book, params)`. `bars` and `params` are what indicator
+//! scripts get, `book` holds the pane's order book as of at most [`BOOK_INTERVAL`] ago: its
+//! `time`, and `bids` and `asks` as arrays of `[price, qty]`, best first and at most
+//! [`BOOK_LEVELS`] each. Both sides are empty while the pane streams no book.
+//!
+//! Reading those is all a script can do besides calling `mark(bar, text)` or
+//! `mark(bar, price, text)`, which marks the bar at index `bar`, and `notify(text)`. A notice
+//! the previous run posted too isn't posted again, markers stay until the pane rebuilds its
+//! scripts.
+//!
+//! Each run gets [`TIME_LIMIT`]. A script that runs past it [`MAX_OVERRUNS`] times in a row is
+//! disabled until it's rearmed or the scripts reload.
+
+use super::script::{self, Script, ScriptKind};
+use exchange::Kline;
+use exchange::depth::Depth;
+
+use rhai::{AST, Array, Dynamic, Engine, EvalAltResult, FLOAT, INT, ImmutableString, Map, Scope};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Longest a single run may take, signals run far more often than indicators
+pub const TIME_LIMIT: Duration = Duration::from_millis(20);
+/// Overruns in a row that disable a script
+pub const MAX_OVERRUNS: u32 = 3;
+pub const RUN_INTERVAL: Duration = Duration::from_secs(1);
+/// Oldest the book handed to a script may be
+pub const BOOK_INTERVAL: Duration = Duration::from_millis(250);
+/// Levels per side of the book handed to a script
+pub const BOOK_LEVELS: usize = 20;
+/// Markers and notices a single run may emit, the rest are dropped
+const MAX_EMITS: usize = 50;
+/// Markers kept per script, the oldest go first
+const MAX_MARKS: usize = 500;
+const MAX_MARK_TEXT: usize = 24;
+
+/// Written to the scripts folder when it's created
+pub const EXAMPLES: [(&str, &str); 2] = [
+    (
+        "delta_divergence_bid_wall.rhai",
+        include_str!("../../scripts/delta_divergence_bid_wall.rhai"),
+    ),
+    (
+        "book_imbalance.rhai",
+        include_str!("../../scripts/book_imbalance.rhai"),
+    ),
+];
+
+/// A signal script a pane runs, saved with the pane settings
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SignalSetup {
+    pub name: String,
+    /// Values of the parameters the script declares, by name
+    pub params: Vec<(String, f32)>,
+}
+
+impl SignalSetup {
+    /// `script` with every parameter at its default
+    pub fn new(script: &Script) -> Self {
+        Self {
+            name: script.name.clone(),
+            params: script
+                .params
+                .iter()
+                .map(|param| (param.name.clone(), param.default))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for SignalSetup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// The best levels of a book
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BookSnapshot {
+    pub time: u64,
+    pub bids: Vec<(f32, f32)>,
+    pub asks: Vec<(f32, f32)>,
+}
+
+impl BookSnapshot {
+    pub fn new(time: u64, depth: &Depth) -> Self {
+        let level = |(price, qty): (&exchange::util::Price, &f32)| (price.to_f32(), *qty);
+
+        Self {
+            time,
+            bids: depth
+                .bids
+                .iter()
+                .rev()
+                .take(BOOK_LEVELS)
+                .map(level)
+                .collect(),
+            asks: depth.asks.iter().take(BOOK_LEVELS).map(level).collect(),
+        }
+    }
+
+    fn input(&self) -> Map {
+        let side = |levels: &[(f32, f32)]| -> Dynamic {
+            Dynamic::from_array(
+                levels
+                    .iter()
+                    .map(|(price, qty)| {
+                        let level: Array = vec![
+                            Dynamic::from_float(f64::from(*price)),
+                            Dynamic::from_float(f64::from(*qty)),
+                        ];
+                        Dynamic::from_array(level)
+                    })
+                    .collect(),
+            )
+        };
+
+        let mut input = Map::new();
+        input.insert("time".into(), Dynamic::from_float(self.time as f64));
+        input.insert("bids".into(), side(&self.bids));
+        input.insert("asks".into(), side(&self.asks));
+        input
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignalMark {
+    pub time: u64,
+    /// Drawn above the bar while `None`
+    pub price: Option<f32>,
+    pub text: String,
+}
+
+/// What a run has to tell the user
+#[derive(Debug, Clone, PartialEq)]
+pub enum Output {
+    Notice(String),
+    /// Each distinct error is reported once
+    Error(String),
+    /// The script ran past its time limit too often
+    Disabled,
+}
+
+#[derive(Default)]
+struct Emitted {
+    marks: Vec<(INT, Option<FLOAT>, String)>,
+    notices: Vec<String>,
+}
+
+enum Failure {
+    Overrun,
+    Error(String),
+}
+
+/// Runs a signal script over a pane's bars and book
+pub struct SignalRunner {
+    /// As the pane saved it
+    setup: SignalSetup,
+    /// Parameter values the script runs with
+    params: Vec<(String, f32)>,
+    compiled: Result<(Engine, AST), String>,
+    deadline: Rc<Cell<Instant>>,
+    emitted: Rc<RefCell<Emitted>>,
+    marks: Vec<SignalMark>,
+    /// Posted by the last run
+    notices: Vec<String>,
+    last_run: Option<Instant>,
+    overruns: u32,
+    disabled: bool,
+    error: Option<String>,
+}
+
+impl SignalRunner {
+    /// `None` reports the script as missing
+    pub fn new(setup: &SignalSetup, script: Option<Script>) -> Self {
+        let deadline = Rc::new(Cell::new(Instant::now()));
+        let emitted = Rc::new(RefCell::new(Emitted::default()));
+
+        let compiled = match &script {
+            Some(script) if script.kind == ScriptKind::Signal => {
+                let engine = signal_engine(deadline.clone(), emitted.clone());
+                engine
+                    .compile(&script.source)
+                    .map(|ast| (engine, ast))
+                    .map_err(|e| e.to_string())
+            }
+            Some(_) => Err("not a signal script, it has no `fn signal(bars, book, params)`".into()),
+            None => Err(format!(
+                "no script named {:?} in the scripts folder",
+                setup.name
+            )),
+        };
+
+        Self {
+            setup: setup.clone(),
+            params: script::resolve_params(script.as_ref(), &setup.params),
+            compiled,
+            deadline,
+            emitted,
+            marks: vec![],
+            notices: vec![],
+            last_run: None,
+            overruns: 0,
+            disabled: false,
+            error: None,
+        }
+    }
+
+    pub fn setup(&self) -> &SignalSetup {
+        &self.setup
+    }
+
+    pub fn marks(&self) -> &[SignalMark] {
+        &self.marks
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.disabled
+    }
+
+    /// Whether the next [`run`](Self::run) at `now` would run the script
+    pub fn is_due(&self, now: Instant) -> bool {
+        !self.disabled
+            && self
+                .last_run
+                .is_none_or(|last| now.duration_since(last) >= RUN_INTERVAL)
+    }
+
+    /// Lets a disabled script run again
+    pub fn rearm(&mut self) {
+        self.disabled = false;
+        self.overruns = 0;
+        self.last_run = None;
+    }
+
+    /// Runs the script over `klines` and `book` unless it ran less than [`RUN_INTERVAL`] ago
+    pub fn run(&mut self, klines: &[Kline], book: &BookSnapshot, now: Instant) -> Vec<Output> {
+        if !self.is_due(now) || klines.is_empty() {
+            return vec![];
+        }
+        self.last_run = Some(now);
+
+        let bars = &klines[klines.len().saturating_sub(script::MAX_BARS)..];
+        let result = self.evaluate(bars, book);
+        let emitted = std::mem::take(&mut *self.emitted.borrow_mut());
+
+        match result {
+            Ok(()) => {
+                self.overruns = 0;
+                self.error = None;
+            }
+            Err(Failure::Overrun) => {
+                self.overruns += 1;
+                if self.overruns >= MAX_OVERRUNS {
+                    self.disabled = true;
+                    return vec![Output::Disabled];
+                }
+                return vec![];
+            }
+            Err(Failure::Error(error)) => {
+                if self.error.as_ref() == Some(&error) {
+                    return vec![];
+                }
+                self.error = Some(error.clone());
+                return vec![Output::Error(error)];
+            }
+        }
+
+        for (bar, price, text) in emitted.marks {
+            let Some(kline) = usize::try_from(bar).ok().and_then(|bar| bars.get(bar)) else {
+                continue;
+            };
+            let mark = SignalMark {
+                time: kline.time,
+                price: price.map(|price| price as f32).filter(|p| p.is_finite()),
+                text,
+            };
+            if !self
+                .marks
+                .iter()
+                .any(|old| old.time == mark.time && old.text == mark.text)
+            {
+                self.marks.push(mark);
+            }
+        }
+        let excess = self.marks.len().saturating_sub(MAX_MARKS);
+        self.marks.drain(..excess);
+
+        let mut outputs = vec![];
+        for notice in &emitted.notices {
+            if !self.notices.contains(notice) && !outputs.contains(&Output::Notice(notice.clone()))
+            {
+                outputs.push(Output::Notice(notice.clone()));
+            }
+        }
+        self.notices = emitted.notices;
+
+        outputs
+    }
+
+    fn evaluate(&self, bars: &[Kline], book: &BookSnapshot) -> Result<(), Failure> {
+        let (engine, ast) = self
+            .compiled
+            .as_ref()
+            .map_err(|e| Failure::Error(e.clone()))?;
+
+        self.deadline.set(Instant::now() + TIME_LIMIT);
+        engine
+            .call_fn::<Dynamic>(
+                &mut Scope::new(),
+                ast,
+                "signal",
+                (
+                    script::bars_input(bars),
+                    book.input(),
+                    script::params_input(&self.params),
+                ),
+            )
+            .map(|_| ())
+            .map_err(|e| {
+                if is_overrun(&e) {
+                    Failure::Overrun
+                } else {
+                    Failure::Error(e.to_string())
+                }
+            })
+    }
+}
+
+/// The sandboxed engine with `mark` and `notify` added, both only record into `emitted`
+fn signal_engine(deadline: Rc<Cell<Instant>>, emitted: Rc<RefCell<Emitted>>) -> Engine {
+    let mut engine = script::sandboxed_engine(Some((deadline, TIME_LIMIT)));
+
+    let record_mark = {
+        let emitted = emitted.clone();
+        move |bar: INT, price: Option<FLOAT>, text: ImmutableString| {
+            let mut emitted = emitted.borrow_mut();
+            if emitted.marks.len() < MAX_EMITS {
+                let text = text.chars().take(MAX_MARK_TEXT).collect();
+                emitted.marks.push((bar, price, text));
+            }
+        }
+    };
+    let mark_at = record_mark.clone();
+
+    engine
+        .register_fn("mark", move |bar: INT, text: ImmutableString| {
+            record_mark(bar, None, text);
+        })
+        .register_fn(
+            "mark",
+            move |bar: INT, price: FLOAT, text: ImmutableString| {
+                mark_at(bar, Some(price), text);
+            },
+        )
+        .register_fn("notify", move |text: ImmutableString| {
+            let mut emitted = emitted.borrow_mut();
+            if emitted.notices.len() < MAX_EMITS {
+                emitted.notices.push(text.to_string());
+            }
+        });
+
+    engine
+}
+
+fn is_overrun(error: &EvalAltResult) -> bool {
+    match error {
+        EvalAltResult::ErrorTerminated(..) | EvalAltResult::ErrorTooManyOperations(..) => true,
+        EvalAltResult::ErrorInFunctionCall(_, _, inner, _) => is_overrun(inner),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::util::Price;
+
+    fn klines(lows: &[f32], deltas: &[f32]) -> Vec<Kline> {
+        lows.iter()
+            .zip(deltas)
+            .enumerate()
+            .map(|(i, (&low, &delta))| Kline {
+                time: i as u64 * 60_000,
+                open: Price::from_f32(low + 2.0),
+                high: Price::from_f32(low + 3.0),
+                low: Price::from_f32(low),
+                close: Price::from_f32(low + 1.0),
+                volume: (10.0 + delta.max(0.0), 10.0 - delta.min(0.0)),
+            })
+            .collect()
+    }
+
+    fn load(name: &str, source: &str) -> SignalRunner {
+        let script = Script::parse(name, source).unwrap();
+        assert_eq!(script.kind, ScriptKind::Signal);
+        SignalRunner::new(&SignalSetup::new(&script), Some(script))
+    }
+
+    #[test]
+    fn marks_and_notices_once_per_episode() {
+        let mut runner = load(
+            "last",
+            r#"fn signal(bars, book, params) {
+                let last = bars.close.len() - 1;
+                mark(last, "seen");
+                if book.bids.len() > 0 { notify(`bid ${book.bids[0][0]}`); }
+            }"#,
+        );
+        let book = BookSnapshot {
+            time: 0,
+            bids: vec![(99.0, 5.0)],
+            asks: vec![],
+        };
+        let start = Instant::now();
+
+        let outputs = runner.run(&klines(&[100.0], &[0.0]), &book, start);
+        assert_eq!(outputs, vec![Output::Notice("bid 99.0".to_string())]);
+        assert_eq!(runner.marks().len(), 1);
+
+        // Throttled, then the same notice and marker again aren't repeated
+        assert!(!runner.is_due(start + RUN_INTERVAL / 2));
+        let later = start + RUN_INTERVAL;
+        assert!(
+            runner
+                .run(&klines(&[100.0], &[0.0]), &book, later)
+                .is_empty()
+        );
+        assert_eq!(runner.marks().len(), 1);
+
+        // A new bar is marked too
+        let outputs = runner.run(
+            &klines(&[100.0, 101.0], &[0.0, 0.0]),
+            &BookSnapshot::default(),
+            later + RUN_INTERVAL,
+        );
+        assert!(outputs.is_empty());
+        assert_eq!(
+            runner.marks().iter().map(|m| m.time).collect::<Vec<_>>(),
+            [0, 60_000]
+        );
+    }
+
+    #[test]
+    fn slow_scripts_are_disabled() {
+        let mut runner = load("spin", "fn signal(bars, book, params) { loop {} }");
+        let bars = klines(&[1.0], &[0.0]);
+        let mut now = Instant::now();
+
+        for _ in 1..MAX_OVERRUNS {
+            assert!(runner.run(&bars, &BookSnapshot::default(), now).is_empty());
+            now += RUN_INTERVAL;
+        }
+        assert_eq!(
+            runner.run(&bars, &BookSnapshot::default(), now),
+            vec![Output::Disabled]
+        );
+        assert!(runner.is_disabled());
+        assert!(!runner.is_due(now + RUN_INTERVAL * 10));
+
+        runner.rearm();
+        assert!(runner.is_due(now));
+
+        // Errors are reported once, scripts can't reach anything but their inputs
+        let mut broken = load(
+            "broken",
+            r#"fn signal(bars, book, params) { bars.close[0] = 1.0; open_file("x") }"#,
+        );
+        let error = broken.run(&bars, &BookSnapshot::default(), now);
+        assert!(matches!(&error[..], [Output::Error(e)] if e.contains("open_file")));
+        assert!(
+            broken
+                .run(&bars, &BookSnapshot::default(), now + RUN_INTERVAL)
+                .is_empty()
+        );
+
+        let indicator = Script::parse("line", "fn compute(bars, params) { bars.close }").unwrap();
+        let mut wrong_kind = SignalRunner::new(&SignalSetup::new(&indicator), Some(indicator));
+        assert!(matches!(
+            &wrong_kind.run(&bars, &BookSnapshot::default(), now)[..],
+            [Output::Error(_)]
+        ));
+    }
+
+    #[test]
+    fn examples_fire_on_their_setups() {
+        let [(_, divergence), (_, imbalance)] = EXAMPLES;
+
+        // A lower low on less selling, with a large bid below
+        let mut lows = vec![105.0; 20];
+        lows[5] = 100.0;
+        lows.push(99.0);
+        let mut deltas = vec![0.0; 21];
+        deltas[5] = -8.0;
+        deltas[20] = -2.0;
+
+        let mut bids: Vec<(f32, f32)> = (0..10).map(|i| (99.0 - i as f32, 1.0)).collect();
+        bids[6].1 = 25.0;
+        let book = BookSnapshot {
+            time: 0,
+            bids,
+            asks: (0..10).map(|i| (101.0 + i as f32, 1.0)).collect(),
+        };
+
+        let mut runner = load("divergence", divergence);
+        let outputs = runner.run(&klines(&lows, &deltas), &book, Instant::now());
+        assert_eq!(outputs.len(), 1, "{outputs:?}");
+        assert_eq!(
+            runner.marks(),
+            &[SignalMark {
+                time: 20 * 60_000,
+                price: Some(93.0),
+                text: "div + bid wall".to_string(),
+            }]
+        );
+
+        let mut runner = load("imbalance", imbalance);
+        let outputs = runner.run(&klines(&lows, &deltas), &book, Instant::now());
+        assert_eq!(
+            outputs,
+            vec![Output::Notice("bids outweigh asks".to_string())]
+        );
+    }
+}
@@ -0,0 +1,612 @@
+//! Bars shared by every pane charting the same stream.
+//!
+//! Panes showing one ticker at one timeframe, of the same connection and price basis, used to
+//! each fetch and hold their own history. They now ask the store instead: a fetch already in
+//! flight for the same span is joined rather than issued again, history the store already holds
+//! is served from it, and a finished fetch is delivered to everyone who asked. Live bars are
+//! applied here once per update, before the subscribed panes get them.
+//!
+//! Subscriptions are counted per pane, an entry goes once no pane subscribes to it and nothing is
+//! fetched for it. Held bars are capped by the same [`Retention`] limits as the panes'.
+//!
+//! Each entry also keeps what was asked for next to what came back, so a chart can tell a gap
+//! where nothing traded from one where data is missing, see [`Coverage`]. Without a calendar of
+//! past sessions, stretches without bars in a span the venue answered count as closed.
+
+use crate::chart::Basis;
+use crate::retention::Retention;
+use exchange::{BarStatus, Kline, TickerInfo, Timeframe};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use uuid::Uuid;
+
+/// What bars are stored by, the ticker carries its connection and price basis
+pub type Key = (TickerInfo, Timeframe);
+
+/// Span of a fetch, `None` for the latest bars
+pub type Span = Option<(u64, u64)>;
+
+/// A pane waiting on a fetch, and the request it answers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Waiter {
+    pub pane: Uuid,
+    pub req_id: Option<Uuid>,
+}
+
+/// How a pane's request for bars is answered
+#[derive(Debug, Clone)]
+pub enum Request {
+    /// Nobody fetches the span yet, the caller should
+    Fetch,
+    /// The span is already being fetched, the pane gets it with everyone else
+    Join,
+    /// The store holds the span already
+    Serve(Vec<Kline>),
+}
+
+/// What is known of a stretch of a stream's history
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage {
+    /// Bars were received
+    Received,
+    /// The venue answered with no bars, the market was closed or nothing traded
+    Closed,
+    /// Asked for but never received, the fetch failed or the stream dropped out
+    Missing,
+}
+
+/// `coverage` from `from` until `to`, in unix ms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stretch {
+    pub from: u64,
+    pub to: u64,
+    pub coverage: Coverage,
+}
+
+/// A finished fetch, for everyone who waited on it
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    pub waiters: Vec<Waiter>,
+    pub klines: Vec<Kline>,
+    /// Open times of the first and last bar the fetch brought
+    pub changed: Option<(u64, u64)>,
+}
+
+#[derive(Debug, Default)]
+struct Entry {
+    subscribers: HashSet<Uuid>,
+    bars: BTreeMap<u64, Kline>,
+    /// Spans of fetched history the bars hold without gaps, in order
+    covered: Vec<(u64, u64)>,
+    /// Spans asked for from `from` until `to`, whether they came or not, in order
+    requested: Vec<(u64, u64)>,
+    /// Spans the venue answered, with bars or without, in order
+    answered: Vec<(u64, u64)>,
+    pending: HashMap<Span, Vec<Waiter>>,
+    /// Open time of the latest bar the stream closed
+    closed_through: Option<u64>,
+    /// Coverage changed since it was last taken
+    coverage_changed: bool,
+}
+
+impl Entry {
+    fn covers(&self, from: u64, to: u64) -> bool {
+        self.covered
+            .iter()
+            .any(|(start, end)| *start <= from && to <= *end)
+    }
+
+    fn cover(&mut self, from: u64, to: u64, interval: u64) {
+        merge_into(&mut self.covered, (from, to), interval);
+    }
+
+    fn ask(&mut self, from: u64, to: u64) {
+        if from < to {
+            merge_into(&mut self.requested, (from, to), 0);
+            self.coverage_changed = true;
+        }
+    }
+
+    fn answer(&mut self, from: u64, to: u64) {
+        if from < to {
+            merge_into(&mut self.answered, (from, to), 0);
+            self.coverage_changed = true;
+        }
+    }
+
+    fn coverage(&self, interval: u64) -> Vec<Stretch> {
+        let mut stretches = vec![];
+
+        for &(start, end) in &self.answered {
+            let mut cursor = start;
+            for &time in self.bars.range(start..end).map(|(time, _)| time) {
+                stretches.push((cursor, time, Coverage::Closed));
+                stretches.push((time, time + interval, Coverage::Received));
+                cursor = time + interval;
+            }
+            stretches.push((cursor, end, Coverage::Closed));
+        }
+
+        // What of each request no answer covers
+        for &(start, end) in &self.requested {
+            let mut cursor = start;
+            for &(from, to) in &self.answered {
+                if to > cursor && from < end {
+                    stretches.push((cursor, from.min(end), Coverage::Missing));
+                    cursor = to;
+                }
+            }
+            stretches.push((cursor, end, Coverage::Missing));
+        }
+
+        stretches.sort_unstable_by_key(|(from, ..)| *from);
+
+        let mut merged: Vec<Stretch> = vec![];
+        for (from, to, coverage) in stretches {
+            if from >= to {
+                continue;
+            }
+            match merged.last_mut() {
+                Some(last) if last.coverage == coverage && last.to >= from => {
+                    last.to = last.to.max(to);
+                }
+                _ => merged.push(Stretch { from, to, coverage }),
+            }
+        }
+        merged
+    }
+}
+
+/// Adds `span` to the ordered `spans`, joining those no more than `gap` apart
+fn merge_into(spans: &mut Vec<(u64, u64)>, span: (u64, u64), gap: u64) {
+    spans.push(span);
+    spans.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(spans.len());
+    for (start, end) in spans.drain(..) {
+        match merged.last_mut() {
+            Some(last) if start <= last.1.saturating_add(gap) => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    *spans = merged;
+}
+
+#[derive(Debug, Default)]
+pub struct KlineStore {
+    entries: HashMap<Key, Entry>,
+}
+
+impl KlineStore {
+    /// Panes subscribed to `key`
+    pub fn subscribers(&self, key: &Key) -> usize {
+        self.entries
+            .get(key)
+            .map_or(0, |entry| entry.subscribers.len())
+    }
+
+    /// Replaces every subscription with `subscriptions`, dropping entries nobody subscribes to
+    /// or waits on anymore
+    pub fn sync(&mut self, subscriptions: impl IntoIterator<Item = (Key, Uuid)>) {
+        self.entries
+            .values_mut()
+            .for_each(|entry| entry.subscribers.clear());
+
+        for (key, pane) in subscriptions {
+            self.entries
+                .entry(key)
+                .or_default()
+                .subscribers
+                .insert(pane);
+        }
+
+        self.entries
+            .retain(|_, entry| !entry.subscribers.is_empty() || !entry.pending.is_empty());
+    }
+
+    /// Answers `waiter`'s request for `span` of `key`. The latest bars are served only while
+    /// another pane subscribes, its live feed is what kept them current.
+    pub fn request(&mut self, key: Key, span: Span, waiter: Waiter) -> Request {
+        let entry = self.entries.entry(key).or_default();
+
+        if let Some(waiting) = entry.pending.get_mut(&span) {
+            waiting.push(waiter);
+            return Request::Join;
+        }
+
+        let held = match span {
+            Some((from, to)) => entry.covers(from, to).then(|| {
+                entry
+                    .bars
+                    .range(from..=to)
+                    .map(|(_, kline)| *kline)
+                    .collect()
+            }),
+            None => (!entry.bars.is_empty()
+                && entry.subscribers.iter().any(|pane| *pane != waiter.pane))
+            .then(|| entry.bars.values().copied().collect()),
+        };
+
+        if let Some(klines) = held {
+            return Request::Serve(klines);
+        }
+
+        entry.pending.insert(span, vec![waiter]);
+        Request::Fetch
+    }
+
+    /// Stores the bars fetched for `span` of `key`, `None` when nobody waited on it
+    pub fn complete(&mut self, key: &Key, span: Span, klines: Vec<Kline>) -> Option<Delivery> {
+        let entry = self.entries.get_mut(key)?;
+        let waiters = entry.pending.remove(&span)?;
+
+        let interval = key.1.to_milliseconds();
+        let changed = klines
+            .first()
+            .zip(klines.last())
+            .map(|(a, b)| (a.time, b.time));
+        // The venue answered for all of a span, also where it had no bars
+        if let Some((from, to)) = span {
+            entry.ask(from, to);
+            entry.answer(from, to);
+        }
+        if let Some((from, to)) = changed {
+            entry.cover(from, to, interval);
+            entry.ask(from, to + interval);
+            entry.answer(from, to + interval);
+        }
+        entry
+            .bars
+            .extend(klines.iter().map(|kline| (kline.time, *kline)));
+
+        Some(Delivery {
+            waiters,
+            klines,
+            changed,
+        })
+    }
+
+    /// Gives up on the fetch of `span` of `key`, returning who waited on it
+    pub fn fail(&mut self, key: &Key, span: Span) -> Vec<Waiter> {
+        let Some(entry) = self.entries.get_mut(key) else {
+            return vec![];
+        };
+        if let Some((from, to)) = span {
+            entry.ask(from, to);
+        }
+        entry.pending.remove(&span).unwrap_or_default()
+    }
+
+    /// Applies a live bar, returning the span it changed. A forming bar replaces the one held
+    /// at its open time, a late update of a bar the stream already closed is dropped. Streams
+    /// whose history isn't held are left alone.
+    pub fn apply_live(
+        &mut self,
+        key: &Key,
+        kline: &Kline,
+        status: BarStatus,
+    ) -> Option<(u64, u64)> {
+        let entry = self.entries.get_mut(key)?;
+        if entry.bars.is_empty() {
+            return None;
+        }
+
+        match status {
+            BarStatus::Forming if entry.closed_through >= Some(kline.time) => return None,
+            BarStatus::Forming => {}
+            BarStatus::Closed => {
+                entry.closed_through = entry.closed_through.max(Some(kline.time));
+            }
+        }
+
+        // The stream asked for everything since the last bar held, a gap before this one is
+        // missing
+        let interval = key.1.to_milliseconds();
+        if let Some((&last, _)) = entry.bars.last_key_value()
+            && kline.time > last
+        {
+            entry.ask(last, kline.time + interval);
+            entry.answer(kline.time, kline.time + interval);
+        }
+
+        entry.bars.insert(kline.time, *kline);
+        Some((kline.time, kline.time + interval))
+    }
+
+    /// Coverage of every stream whose coverage changed since this was last called
+    pub fn coverage_changes(&mut self) -> Vec<(Key, Vec<Stretch>)> {
+        self.entries
+            .iter_mut()
+            .filter(|(_, entry)| entry.coverage_changed)
+            .map(|(key, entry)| {
+                entry.coverage_changed = false;
+                (*key, entry.coverage(key.1.to_milliseconds()))
+            })
+            .collect()
+    }
+
+    /// Coverage of `key`'s history held, oldest first
+    pub fn coverage(&self, key: &Key) -> Vec<Stretch> {
+        self.entries
+            .get(key)
+            .map(|entry| entry.coverage(key.1.to_milliseconds()))
+            .unwrap_or_default()
+    }
+
+    /// Drops the oldest bars past `retention`'s limits, spans they leave uncovered are fetched
+    /// again when asked for
+    pub fn prune(&mut self, retention: &Retention) {
+        for ((_, timeframe), entry) in &mut self.entries {
+            let max_bars = retention.max_bars(Basis::Time(*timeframe));
+            let excess = entry.bars.len().saturating_sub(max_bars);
+            let Some(&cut) = entry.bars.keys().nth(excess) else {
+                continue;
+            };
+
+            entry.bars = entry.bars.split_off(&cut);
+            entry.covered.retain_mut(|(start, end)| {
+                *start = (*start).max(cut);
+                *end >= cut
+            });
+            for spans in [&mut entry.requested, &mut entry.answered] {
+                spans.retain_mut(|(start, end)| {
+                    *start = (*start).max(cut);
+                    *end > cut
+                });
+            }
+            entry.coverage_changed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::adapter::Exchange;
+    use exchange::util::Price;
+
+    const MINUTE: u64 = 60_000;
+
+    fn key() -> Key {
+        let ticker_info = TickerInfo::new(
+            exchange::Ticker::new("EURUSD", Exchange::MetaTrader5),
+            0.00001,
+            1.0,
+            None,
+        );
+        (ticker_info, Timeframe::M5)
+    }
+
+    fn kline(time: u64, price: f32) -> Kline {
+        let price = Price::from_f32(price);
+        Kline {
+            time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: (1.0, 1.0),
+        }
+    }
+
+    fn bars(from: u64, count: u64) -> Vec<Kline> {
+        (from..from + count)
+            .map(|i| kline(i * 5 * MINUTE, 1.1 + i as f32 / 1_000.0))
+            .collect()
+    }
+
+    /// Open time and close of every bar, what two deliveries are compared by
+    fn seen(klines: &[Kline]) -> Vec<(u64, Price)> {
+        klines.iter().map(|k| (k.time, k.close)).collect()
+    }
+
+    fn waiter(pane: Uuid) -> Waiter {
+        Waiter { pane, req_id: None }
+    }
+
+    #[test]
+    fn two_subscribers_share_one_fetch() {
+        let (candles, indicators) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut store = KlineStore::default();
+        store.sync([(key(), candles), (key(), indicators)]);
+        assert_eq!(store.subscribers(&key()), 2);
+
+        // Both panes open at once, only the first request fetches
+        let mut fetches = 0;
+        for pane in [candles, indicators] {
+            match store.request(key(), None, waiter(pane)) {
+                Request::Fetch => fetches += 1,
+                Request::Join => {}
+                Request::Serve(_) => panic!("nothing is held yet"),
+            }
+        }
+        assert_eq!(fetches, 1);
+
+        let fetched = bars(0, 100);
+        let delivery = store.complete(&key(), None, fetched.clone()).unwrap();
+        assert_eq!(delivery.waiters, [waiter(candles), waiter(indicators)]);
+        assert_eq!(seen(&delivery.klines), seen(&fetched));
+        assert_eq!(delivery.changed, Some((0, 99 * 5 * MINUTE)));
+        assert!(store.complete(&key(), None, fetched.clone()).is_none());
+
+        // A live bar is applied once, a pane reopening afterwards is served it with the history
+        let live = kline(100 * 5 * MINUTE, 1.2);
+        assert_eq!(
+            store.apply_live(&key(), &live, BarStatus::Forming),
+            Some((live.time, live.time + 5 * MINUTE))
+        );
+        let Request::Serve(served) = store.request(key(), None, waiter(indicators)) else {
+            panic!("the history is held");
+        };
+        assert_eq!(seen(&served[..100]), seen(&fetched));
+        assert_eq!(seen(&served[100..]), seen(&[live]));
+    }
+
+    #[test]
+    fn held_spans_are_served_and_others_fetched() {
+        let pane = Uuid::new_v4();
+        let mut store = KlineStore::default();
+        store.sync([(key(), pane)]);
+
+        // Alone on the stream, the pane's latest bars are fetched again
+        assert!(matches!(
+            store.request(key(), None, waiter(pane)),
+            Request::Fetch
+        ));
+        store.complete(&key(), None, bars(100, 100));
+        assert!(matches!(
+            store.request(key(), None, waiter(pane)),
+            Request::Fetch
+        ));
+        store.fail(&key(), None);
+
+        // Older history joins up with what is held
+        let older = Some((50 * 5 * MINUTE, 99 * 5 * MINUTE));
+        assert!(matches!(
+            store.request(key(), older, waiter(pane)),
+            Request::Fetch
+        ));
+        store.complete(&key(), older, bars(50, 50));
+
+        let span = Some((60 * 5 * MINUTE, 150 * 5 * MINUTE));
+        let Request::Serve(served) = store.request(key(), span, waiter(pane)) else {
+            panic!("the span is held");
+        };
+        assert_eq!(seen(&served), seen(&bars(60, 91)));
+
+        let beyond = Some((0, 60 * 5 * MINUTE));
+        assert!(matches!(
+            store.request(key(), beyond, waiter(pane)),
+            Request::Fetch
+        ));
+        assert_eq!(store.fail(&key(), beyond), [waiter(pane)]);
+    }
+
+    #[test]
+    fn retention_and_unsubscribing_free_the_bars() {
+        let pane = Uuid::new_v4();
+        let mut store = KlineStore::default();
+        store.sync([(key(), pane)]);
+        store.request(key(), None, waiter(pane));
+        store.complete(&key(), None, bars(0, 6_000));
+
+        let retention = Retention::default();
+        store.prune(&retention);
+        let kept = retention.max_bars(Basis::Time(Timeframe::M5)) as u64;
+        let first = (6_000 - kept) * 5 * MINUTE;
+
+        // Pruned history is fetched again
+        let pruned = Some((0, first));
+        assert!(matches!(
+            store.request(key(), pruned, waiter(pane)),
+            Request::Fetch
+        ));
+        let Request::Serve(served) = store.request(key(), Some((first, first)), waiter(pane))
+        else {
+            panic!("the newest bars are kept");
+        };
+        assert_eq!(served.len(), 1);
+
+        // An entry stays while a fetch for it is in flight
+        store.sync([]);
+        assert_eq!(store.subscribers(&key()), 0);
+        assert!(store.complete(&key(), pruned, bars(0, 10)).is_some());
+        store.sync([]);
+        assert!(
+            store
+                .apply_live(&key(), &kline(0, 1.0), BarStatus::Closed)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn forming_updates_replace_the_bar_until_it_closes() {
+        let (pane, other) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut store = KlineStore::default();
+        store.sync([(key(), pane), (key(), other)]);
+        store.request(key(), None, waiter(pane));
+        store.complete(&key(), None, bars(0, 10));
+
+        let time = 10 * 5 * MINUTE;
+        let with_volume = |buy, sell| Kline {
+            volume: (buy, sell),
+            ..kline(time, 1.2)
+        };
+        for (update, status) in [
+            (with_volume(1.0, 0.5), BarStatus::Forming),
+            (with_volume(2.0, 1.5), BarStatus::Forming),
+            (with_volume(3.0, 2.0), BarStatus::Closed),
+        ] {
+            assert!(store.apply_live(&key(), &update, status).is_some());
+        }
+        // A forming update arriving after the close is stale
+        assert!(
+            store
+                .apply_live(&key(), &with_volume(2.5, 1.5), BarStatus::Forming)
+                .is_none()
+        );
+        store.apply_live(&key(), &kline(time + 5 * MINUTE, 1.3), BarStatus::Forming);
+
+        let Request::Serve(served) = store.request(key(), None, waiter(other)) else {
+            panic!("the history is held");
+        };
+        assert_eq!(served.len(), 12);
+        assert_eq!(served[10].volume, (3.0, 2.0));
+    }
+
+    #[test]
+    fn coverage_tells_closed_from_missing_across_fetches_and_live_bars() {
+        let pane = Uuid::new_v4();
+        let mut store = KlineStore::default();
+        store.sync([(key(), pane)]);
+        let bar = 5 * MINUTE;
+
+        // The latest bars, with two missing where nothing traded
+        store.request(key(), None, waiter(pane));
+        let mut latest = bars(100, 10);
+        latest.retain(|kline| ![104 * bar, 105 * bar].contains(&kline.time));
+        store.complete(&key(), None, latest);
+
+        // Older history the venue has bars for only part of, and a fetch that failed
+        let older = Some((90 * bar, 100 * bar));
+        store.request(key(), older, waiter(pane));
+        store.complete(&key(), older, bars(95, 5));
+        let failed = Some((80 * bar, 90 * bar));
+        store.request(key(), failed, waiter(pane));
+        store.fail(&key(), failed);
+
+        // A live bar right after the history, then one after the stream dropped out
+        store.apply_live(&key(), &kline(110 * bar, 1.2), BarStatus::Closed);
+        store.apply_live(&key(), &kline(113 * bar, 1.3), BarStatus::Forming);
+
+        let stretch = |from: u64, to: u64, coverage| Stretch {
+            from: from * bar,
+            to: to * bar,
+            coverage,
+        };
+        let changes = store.coverage_changes();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].1,
+            [
+                stretch(80, 90, Coverage::Missing),
+                stretch(90, 95, Coverage::Closed),
+                stretch(95, 104, Coverage::Received),
+                stretch(104, 106, Coverage::Closed),
+                stretch(106, 111, Coverage::Received),
+                stretch(111, 113, Coverage::Missing),
+                stretch(113, 114, Coverage::Received),
+            ]
+        );
+        assert!(store.coverage_changes().is_empty());
+
+        // Backfilling the gap fills it in
+        let gap = Some((111 * bar, 113 * bar));
+        store.request(key(), gap, waiter(pane));
+        store.complete(&key(), gap, bars(111, 2));
+        assert_eq!(
+            store.coverage(&key())[4..],
+            [stretch(106, 114, Coverage::Received)]
+        );
+    }
+}
@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 
 pub mod dashboard;
 pub mod pane;
+pub mod template;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Layout {
@@ -20,6 +21,32 @@ impl Default for Layout {
     }
 }
 
+impl Layout {
+    /// Relative to the data folder, e.g. `exports/layout-Scalping-20250101-120000.json`
+    pub fn export_file_name(&self, exported_at: u64) -> String {
+        let time = chrono::DateTime::from_timestamp_millis(exported_at as i64)
+            .map(|t| t.format("%Y%m%d-%H%M%S").to_string())
+            .unwrap_or_else(|| exported_at.to_string());
+        let name: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '_' })
+            .collect();
+
+        format!("exports/layout-{name}-{time}.json")
+    }
+
+    /// Writes the layout as it would be saved, returns the full path of the file. Broken panes
+    /// are written as they were read, to be fixed by hand.
+    pub fn export(&self, exported_at: u64) -> std::io::Result<std::path::PathBuf> {
+        let json = serde_json::to_string_pretty(self)?;
+        let file_name = self.export_file_name(exported_at);
+
+        crate::write_json_to_file(&json, &file_name)?;
+        Ok(crate::data_path(Some(&file_name)))
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
 pub struct Window<T = f32> {
     pub width: T,
@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
-use super::{WindowSpec, pane::Pane};
+use super::{
+    WindowSpec,
+    pane::{LinkGroup, Pane},
+};
 use crate::util::ok_or_default;
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -9,4 +13,153 @@ pub struct Dashboard {
     pub pane: Pane,
     #[serde(deserialize_with = "ok_or_default", default)]
     pub popout: Vec<(Pane, WindowSpec)>,
+    /// Link groups that also share their timeframe, not just the ticker
+    #[serde(deserialize_with = "ok_or_default", default)]
+    pub linked_timeframes: Vec<LinkGroup>,
+}
+
+impl Dashboard {
+    /// Gives panes saved without an id one derived from `seed`, the layout's name, and where
+    /// they sit. Popout windows are told apart by their order.
+    pub fn assign_pane_ids(&mut self, seed: &str) {
+        let mut taken = HashSet::new();
+
+        self.pane.assign_ids(&format!("{seed}/main"), &mut taken);
+        for (i, (pane, _)) in self.popout.iter_mut().enumerate() {
+            pane.assign_ids(&format!("{seed}/popout{i}"), &mut taken);
+        }
+    }
+
+    /// Fresh pane ids, for a copy of the layout
+    pub fn renew_pane_ids(&mut self) {
+        self.pane.renew_ids();
+        for (pane, _) in &mut self.popout {
+            pane.renew_ids();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    /// Saved before panes had ids
+    const OLD_LAYOUT: &str = r#"{
+        "pane": {
+            "Split": {
+                "axis": "Vertical",
+                "ratio": 0.3,
+                "a": { "Ladder": { "stream_type": [], "settings": { "tick_multiply": 5 } } },
+                "b": { "TimeAndSales": { "stream_type": [], "settings": { "tick_multiply": 10 } } }
+            }
+        },
+        "popout": [
+            [
+                { "Ladder": { "stream_type": [], "settings": { "tick_multiply": 25 } } },
+                { "width": 400.0, "height": 300.0, "pos_x": 0.0, "pos_y": 0.0 }
+            ]
+        ]
+    }"#;
+
+    fn load(json: &str, seed: &str) -> Dashboard {
+        let mut dashboard: Dashboard = serde_json::from_str(json).unwrap();
+        dashboard.assign_pane_ids(seed);
+        dashboard
+    }
+
+    /// Tick multiplier of every leaf by its id
+    fn settings_by_id(dashboard: &Dashboard) -> Vec<(Uuid, Option<u16>)> {
+        fn walk(pane: &Pane, out: &mut Vec<(Uuid, Option<u16>)>) {
+            match pane {
+                Pane::Split { a, b, .. } => {
+                    walk(a, out);
+                    walk(b, out);
+                }
+                leaf => {
+                    let multiplier = leaf
+                        .clone()
+                        .settings_mut()
+                        .and_then(|settings| settings.tick_multiply)
+                        .map(|m| m.0);
+                    out.push((leaf.id().unwrap(), multiplier));
+                }
+            }
+        }
+
+        let mut out = vec![];
+        walk(&dashboard.pane, &mut out);
+        for (pane, _) in &dashboard.popout {
+            walk(pane, &mut out);
+        }
+        out.sort();
+        out
+    }
+
+    #[test]
+    fn old_layouts_get_the_same_ids_on_every_load() {
+        let first = settings_by_id(&load(OLD_LAYOUT, "Scalping"));
+        assert_eq!(first.len(), 3);
+        assert!(first.iter().all(|(id, _)| !id.is_nil()));
+        assert!(first.windows(2).all(|pair| pair[0].0 != pair[1].0));
+
+        assert_eq!(settings_by_id(&load(OLD_LAYOUT, "Scalping")), first);
+
+        // Another layout of the same shape doesn't share them
+        let other = settings_by_id(&load(OLD_LAYOUT, "Swing"));
+        assert!(
+            other
+                .iter()
+                .all(|(id, _)| first.iter().all(|(f, _)| f != id))
+        );
+    }
+
+    #[test]
+    fn settings_stay_with_their_pane_when_the_tree_changes() {
+        let dashboard = load(OLD_LAYOUT, "Scalping");
+        let before = settings_by_id(&dashboard);
+
+        // Swap the two sides and move the popout into the main window, then save and load again
+        let mut restructured = dashboard.clone();
+        let Pane::Split { a, b, .. } = &mut restructured.pane else {
+            panic!("expected a split");
+        };
+        std::mem::swap(a, b);
+        let (popout, _) = restructured.popout.remove(0);
+        restructured.pane = Pane::Split {
+            axis: super::super::pane::Axis::Horizontal,
+            ratio: 0.5,
+            a: Box::new(popout),
+            b: Box::new(restructured.pane),
+        };
+
+        let json = serde_json::to_string(&restructured).unwrap();
+        assert_eq!(settings_by_id(&load(&json, "Scalping")), before);
+    }
+
+    #[test]
+    fn duplicated_ids_are_replaced_and_copies_renewed() {
+        let mut dashboard = load(OLD_LAYOUT, "Scalping");
+        let Pane::Split { a, b, .. } = &mut dashboard.pane else {
+            panic!("expected a split");
+        };
+        **b = (**a).clone();
+        let copied = a.id();
+
+        let json = serde_json::to_string(&dashboard).unwrap();
+        let reloaded = load(&json, "Scalping");
+        let ids = settings_by_id(&reloaded);
+        assert_eq!(ids.len(), 3);
+        assert!(ids.windows(2).all(|pair| pair[0].0 != pair[1].0));
+        assert!(ids.iter().any(|(id, _)| Some(*id) == copied));
+
+        let mut copy = reloaded.clone();
+        copy.renew_pane_ids();
+        let renewed = settings_by_id(&copy);
+        assert!(
+            renewed
+                .iter()
+                .all(|(id, _)| ids.iter().all(|(i, _)| i != id))
+        );
+    }
 }
@@ -1,16 +1,30 @@
-use exchange::adapter::PersistStreamKind;
-use exchange::{TickMultiplier, TickerInfo, Timeframe};
-use serde::{Deserialize, Serialize};
+use exchange::adapter::metatrader5::price_basis::PriceBasis;
+use exchange::adapter::{Exchange, PersistStreamKind};
+use exchange::{SourceId, TickMultiplier, TickerInfo, Timeframe};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashSet;
+use uuid::Uuid;
 
-use crate::chart::{comparison, heatmap, kline};
+use crate::chart::{comparison, heatmap, kline, strip};
+use crate::indicators::{Overlay, signal::SignalSetup};
 use crate::panel::{ladder, timeandsales};
 use crate::util::ok_or_default;
 
 use crate::chart::{
     Basis, ViewConfig,
+    bar_close::BarCloseAlert,
+    calendar::CalendarConfig,
+    data_window::DataWindow,
+    drawing::Drawings,
     heatmap::HeatmapStudy,
     indicator::{HeatmapIndicator, KlineIndicator},
     kline::KlineChartKind,
+    pattern::PatternConfig,
+    price_alert::PriceAlert,
+    session::ReferenceLines,
+    spread_alert::SpreadAlert,
+    stop_run::StopRunConfig,
+    volume_curve::VolumeCurveConfig,
 };
 
 #[derive(Debug, Clone, Copy, Deserialize, Serialize)]
@@ -19,7 +33,13 @@ pub enum Axis {
     Vertical,
 }
 
+/// A saved pane or split. One that fails to load turns into [`Pane::Quarantined`] instead of
+/// failing the whole layout.
+///
+/// Every other leaf has an `id` that stays with it wherever it's moved in the tree, layouts
+/// saved before ids existed load with nil ones until [`Pane::assign_ids`] fills them in.
 #[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(remote = "Self")]
 pub enum Pane {
     Split {
         axis: Axis,
@@ -28,11 +48,15 @@ pub enum Pane {
         b: Box<Pane>,
     },
     Starter {
+        #[serde(default = "Uuid::nil")]
+        id: Uuid,
         #[serde(deserialize_with = "ok_or_default", default)]
         link_group: Option<LinkGroup>,
     },
     HeatmapChart {
         layout: ViewConfig,
+        #[serde(default = "Uuid::nil")]
+        id: Uuid,
         #[serde(deserialize_with = "ok_or_default", default)]
         studies: Vec<HeatmapStudy>,
         #[serde(deserialize_with = "ok_or_default", default)]
@@ -46,6 +70,8 @@ pub enum Pane {
     },
     KlineChart {
         layout: ViewConfig,
+        #[serde(default = "Uuid::nil")]
+        id: Uuid,
         kind: KlineChartKind,
         #[serde(deserialize_with = "ok_or_default", default)]
         stream_type: Vec<PersistStreamKind>,
@@ -57,6 +83,8 @@ pub enum Pane {
         link_group: Option<LinkGroup>,
     },
     ComparisonChart {
+        #[serde(default = "Uuid::nil")]
+        id: Uuid,
         stream_type: Vec<PersistStreamKind>,
         #[serde(deserialize_with = "ok_or_default")]
         settings: Settings,
@@ -64,23 +92,172 @@ pub enum Pane {
         link_group: Option<LinkGroup>,
     },
     TimeAndSales {
+        #[serde(default = "Uuid::nil")]
+        id: Uuid,
         stream_type: Vec<PersistStreamKind>,
         settings: Settings,
         #[serde(deserialize_with = "ok_or_default", default)]
         link_group: Option<LinkGroup>,
     },
     Ladder {
+        #[serde(default = "Uuid::nil")]
+        id: Uuid,
         stream_type: Vec<PersistStreamKind>,
         settings: Settings,
         #[serde(deserialize_with = "ok_or_default", default)]
         link_group: Option<LinkGroup>,
     },
+    TimeframeStrip {
+        #[serde(default = "Uuid::nil")]
+        id: Uuid,
+        #[serde(deserialize_with = "ok_or_default", default)]
+        stream_type: Vec<PersistStreamKind>,
+        #[serde(deserialize_with = "ok_or_default")]
+        settings: Settings,
+        #[serde(deserialize_with = "ok_or_default", default)]
+        link_group: Option<LinkGroup>,
+    },
+    /// A pane that failed to load, kept as it was saved so fixing it by hand stays possible
+    #[serde(skip)]
+    Quarantined {
+        error: String,
+        raw: serde_json::Value,
+    },
+}
+
+impl<'de> Deserialize<'de> for Pane {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = serde_json::Value::deserialize(deserializer)?;
+
+        match Pane::deserialize(&raw) {
+            Ok(pane) => Ok(pane),
+            Err(e) => {
+                log::error!("Quarantined a pane that failed to load: {e}");
+                Ok(Pane::Quarantined {
+                    error: e.to_string(),
+                    raw,
+                })
+            }
+        }
+    }
+}
+
+impl Serialize for Pane {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Pane::Quarantined { raw, .. } => raw.serialize(serializer),
+            pane => Pane::serialize(pane, serializer),
+        }
+    }
 }
 
 impl Default for Pane {
     fn default() -> Self {
-        Pane::Starter { link_group: None }
+        Pane::Starter {
+            id: Uuid::new_v4(),
+            link_group: None,
+        }
+    }
+}
+
+impl Pane {
+    /// Id of a leaf, `None` for splits and quarantined panes
+    pub fn id(&self) -> Option<Uuid> {
+        match self {
+            Pane::Split { .. } | Pane::Quarantined { .. } => None,
+            Pane::Starter { id, .. }
+            | Pane::HeatmapChart { id, .. }
+            | Pane::KlineChart { id, .. }
+            | Pane::ComparisonChart { id, .. }
+            | Pane::TimeAndSales { id, .. }
+            | Pane::Ladder { id, .. }
+            | Pane::TimeframeStrip { id, .. } => Some(*id),
+        }
+    }
+
+    fn id_mut(&mut self) -> Option<&mut Uuid> {
+        match self {
+            Pane::Split { .. } | Pane::Quarantined { .. } => None,
+            Pane::Starter { id, .. }
+            | Pane::HeatmapChart { id, .. }
+            | Pane::KlineChart { id, .. }
+            | Pane::ComparisonChart { id, .. }
+            | Pane::TimeAndSales { id, .. }
+            | Pane::Ladder { id, .. }
+            | Pane::TimeframeStrip { id, .. } => Some(id),
+        }
     }
+
+    /// Gives each leaf without an id, or with one an earlier leaf already has, an id derived
+    /// from `seed` and its place in the tree. The same layout gets the same ids on every load
+    /// until it's saved with them.
+    pub fn assign_ids(&mut self, seed: &str, taken: &mut HashSet<Uuid>) {
+        self.assign_ids_at(seed, &mut String::new(), taken);
+    }
+
+    fn assign_ids_at(&mut self, seed: &str, place: &mut String, taken: &mut HashSet<Uuid>) {
+        if let Pane::Split { a, b, .. } = self {
+            for (side, pane) in [('a', a), ('b', b)] {
+                place.push(side);
+                pane.assign_ids_at(seed, place, taken);
+                place.pop();
+            }
+            return;
+        }
+
+        if let Some(id) = self.id_mut()
+            && (id.is_nil() || !taken.insert(*id))
+        {
+            let mut attempt = 0u32;
+            *id = loop {
+                let derived = derived_id(&format!("{seed}/{place}/{attempt}"));
+                if taken.insert(derived) {
+                    break derived;
+                }
+                attempt += 1;
+            };
+        }
+    }
+
+    /// Fresh ids for every leaf, for a copy living next to the original
+    pub fn renew_ids(&mut self) {
+        match self {
+            Pane::Split { a, b, .. } => {
+                a.renew_ids();
+                b.renew_ids();
+            }
+            pane => {
+                if let Some(id) = pane.id_mut() {
+                    *id = Uuid::new_v4();
+                }
+            }
+        }
+    }
+
+    /// Settings of a content pane, `None` for splits and starters
+    pub fn settings_mut(&mut self) -> Option<&mut Settings> {
+        match self {
+            Pane::Split { .. } | Pane::Starter { .. } | Pane::Quarantined { .. } => None,
+            Pane::HeatmapChart { settings, .. }
+            | Pane::KlineChart { settings, .. }
+            | Pane::ComparisonChart { settings, .. }
+            | Pane::TimeAndSales { settings, .. }
+            | Pane::Ladder { settings, .. }
+            | Pane::TimeframeStrip { settings, .. } => Some(settings),
+        }
+    }
+}
+
+/// 128-bit FNV-1a of `name` as a version 8 UUID, stable across runs and builds
+fn derived_id(name: &str) -> Uuid {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013B;
+
+    let hash = name.bytes().fold(OFFSET, |hash, byte| {
+        (hash ^ u128::from(byte)).wrapping_mul(PRIME)
+    });
+
+    uuid::Builder::from_custom_bytes(hash.to_be_bytes()).into_uuid()
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -89,6 +266,71 @@ pub struct Settings {
     pub tick_multiply: Option<exchange::TickMultiplier>,
     pub visual_config: Option<VisualConfig>,
     pub selected_basis: Option<Basis>,
+    pub bar_close_alert: Option<BarCloseAlert>,
+    /// Stop run markers on kline charts, `None` while off
+    pub stop_runs: Option<StopRunConfig>,
+    /// Candle pattern markers on kline charts, `None` while off
+    pub patterns: Option<PatternConfig>,
+    /// Typical volume by time of day in the volume panel, `None` while off
+    pub volume_curve: Option<VolumeCurveConfig>,
+    /// Economic calendar markers on the time axis, `None` while off
+    pub calendar: Option<CalendarConfig>,
+    /// Unit sizes are shown in, the app wide one while `None`
+    pub size_unit_override: Option<exchange::SizeUnit>,
+    pub reference_lines: ReferenceLines,
+    #[serde(deserialize_with = "ok_or_default")]
+    pub drawings: Drawings,
+    pub price_alerts: Vec<PriceAlert>,
+    /// Indicators drawn over the price pane of kline charts
+    #[serde(deserialize_with = "ok_or_default")]
+    pub overlays: Vec<Overlay>,
+    /// Signal scripts run over kline charts
+    #[serde(deserialize_with = "ok_or_default")]
+    pub signals: Vec<SignalSetup>,
+    /// Connection the pane streams from instead of the active one
+    pub source: Option<DataSource>,
+    /// Price MT5 bars are drawn from, whatever the feed sends while `None`
+    pub price_basis: Option<PriceBasis>,
+    /// Values at the hovered bar, listed in a corner of charts
+    pub data_window: DataWindow,
+    /// Alert on MT5 spread widening, the connection's default while `None`
+    pub spread_alert: Option<SpreadAlert>,
+}
+
+impl Settings {
+    /// `ticker_info` streamed through the pane's pinned connection, at its price basis. Only
+    /// MT5 tickers have a price to choose.
+    pub fn bind(&self, ticker_info: TickerInfo) -> TickerInfo {
+        let ticker_info = DataSource::pin(self.source.as_ref(), ticker_info);
+        let price_basis = self
+            .price_basis
+            .filter(|_| ticker_info.exchange() == Exchange::MetaTrader5);
+
+        ticker_info.priced(price_basis)
+    }
+}
+
+/// A saved connection of `exchange`, by the name it was saved under
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct DataSource {
+    pub exchange: Exchange,
+    pub connection: String,
+}
+
+impl DataSource {
+    pub fn id(&self) -> SourceId {
+        SourceId::of(&self.connection)
+    }
+
+    /// `ticker_info` pinned to `source`, tickers of other exchanges and those of unpinned panes
+    /// go through their exchange's connection
+    pub fn pin(source: Option<&DataSource>, ticker_info: TickerInfo) -> TickerInfo {
+        let source = source
+            .filter(|source| source.exchange == ticker_info.exchange())
+            .map(DataSource::id);
+
+        ticker_info.pinned(source)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
@@ -143,6 +385,7 @@ pub enum VisualConfig {
     Kline(kline::Config),
     Ladder(ladder::Config),
     Comparison(comparison::Config),
+    TimeframeStrip(strip::Config),
 }
 
 impl VisualConfig {
@@ -180,6 +423,13 @@ impl VisualConfig {
             _ => None,
         }
     }
+
+    pub fn strip(&self) -> Option<strip::Config> {
+        match self {
+            Self::TimeframeStrip(cfg) => Some(cfg.clone()),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -191,10 +441,11 @@ pub enum ContentKind {
     ComparisonChart,
     TimeAndSales,
     Ladder,
+    TimeframeStrip,
 }
 
 impl ContentKind {
-    pub const ALL: [ContentKind; 7] = [
+    pub const ALL: [ContentKind; 8] = [
         ContentKind::Starter,
         ContentKind::HeatmapChart,
         ContentKind::FootprintChart,
@@ -202,6 +453,7 @@ impl ContentKind {
         ContentKind::ComparisonChart,
         ContentKind::TimeAndSales,
         ContentKind::Ladder,
+        ContentKind::TimeframeStrip,
     ];
 }
 
@@ -215,6 +467,7 @@ impl std::fmt::Display for ContentKind {
             ContentKind::ComparisonChart => "Comparison Chart",
             ContentKind::TimeAndSales => "Time&Sales",
             ContentKind::Ladder => "DOM/Ladder",
+            ContentKind::TimeframeStrip => "Timeframe Strip",
         };
         write!(f, "{s}")
     }
@@ -262,7 +515,7 @@ impl PaneSetup {
             ContentKind::CandlestickChart | ContentKind::ComparisonChart => {
                 Some(current_basis.unwrap_or(Basis::Time(Timeframe::M15)))
             }
-            ContentKind::Starter | ContentKind::TimeAndSales => None,
+            ContentKind::Starter | ContentKind::TimeAndSales | ContentKind::TimeframeStrip => None,
         };
 
         let tick_multiplier = match content_kind {
@@ -284,6 +537,7 @@ impl PaneSetup {
             ContentKind::CandlestickChart
             | ContentKind::ComparisonChart
             | ContentKind::TimeAndSales
+            | ContentKind::TimeframeStrip
             | ContentKind::Starter => current_tick_multiplier,
         };
 
@@ -314,3 +568,195 @@ impl PaneSetup {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::Ticker;
+
+    fn ticker_info(exchange: Exchange) -> TickerInfo {
+        TickerInfo::new(Ticker::new("EURUSD", exchange), 0.00001, 0.01, None)
+    }
+
+    #[test]
+    fn sources_pin_only_tickers_of_their_exchange() {
+        let source = DataSource {
+            exchange: Exchange::MetaTrader5,
+            connection: "MT5 broker-b:9876".to_string(),
+        };
+
+        let mt5 = DataSource::pin(Some(&source), ticker_info(Exchange::MetaTrader5));
+        assert_eq!(mt5.source, Some(source.id()));
+
+        let spot = DataSource::pin(Some(&source), ticker_info(Exchange::BinanceSpot));
+        assert_eq!(spot.source, None);
+
+        // A ticker handed over from a pinned pane loses the pin in an unpinned one
+        assert_eq!(DataSource::pin(None, mt5).source, None);
+    }
+
+    #[test]
+    fn price_basis_only_applies_to_mt5() {
+        let settings = Settings {
+            price_basis: Some(PriceBasis::Mid),
+            ..Settings::default()
+        };
+
+        let mt5 = settings.bind(ticker_info(Exchange::MetaTrader5));
+        assert_eq!(mt5.price_basis, Some(PriceBasis::Mid));
+        assert_eq!(
+            settings
+                .bind(ticker_info(Exchange::BinanceSpot))
+                .price_basis,
+            None
+        );
+        assert_eq!(Settings::default().bind(mt5).price_basis, None);
+
+        // Saved with the pane, older panes have none
+        let json = serde_json::to_string(&settings).unwrap();
+        let restored: Settings = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.price_basis, Some(PriceBasis::Mid));
+        let old: Settings = serde_json::from_str("{}").unwrap();
+        assert_eq!(old.price_basis, None);
+    }
+
+    #[test]
+    fn a_broken_pane_is_quarantined_not_the_layout() {
+        let json = r#"{
+            "Split": {
+                "axis": "Vertical",
+                "ratio": 0.5,
+                "a": { "Starter": {} },
+                "b": { "Ladder": { "stream_type": "not a list", "settings": {} } }
+            }
+        }"#;
+
+        let pane: Pane = serde_json::from_str(json).unwrap();
+        let Pane::Split { a, b, .. } = &pane else {
+            panic!("expected the split to load");
+        };
+        assert!(matches!(**a, Pane::Starter { .. }));
+        let Pane::Quarantined { error, raw } = &**b else {
+            panic!("expected the ladder to be quarantined");
+        };
+        assert!(!error.is_empty());
+
+        // Saved back as it was found
+        let saved = serde_json::to_value(&pane).unwrap();
+        assert_eq!(&saved["Split"]["b"], raw);
+        assert_eq!(
+            saved["Split"]["a"],
+            serde_json::json!({ "Starter": { "id": a.id(), "link_group": null } })
+        );
+    }
+
+    #[test]
+    fn every_content_pane_hands_out_its_settings() {
+        let ticker = Ticker::new("BTCUSDT", Exchange::BinanceLinear);
+        let mut settings = Settings::default();
+        settings.drawings.set(
+            ticker,
+            vec![crate::chart::drawing::Drawing::HorizontalLine {
+                price: exchange::util::Price::from_f32(100.5),
+            }],
+        );
+
+        let mut panes = vec![
+            Pane::HeatmapChart {
+                layout: ViewConfig::default(),
+                id: Uuid::new_v4(),
+                studies: vec![],
+                stream_type: vec![],
+                settings: settings.clone(),
+                indicators: vec![],
+                link_group: None,
+            },
+            Pane::KlineChart {
+                layout: ViewConfig::default(),
+                id: Uuid::new_v4(),
+                kind: KlineChartKind::Candles,
+                stream_type: vec![],
+                settings: settings.clone(),
+                indicators: vec![],
+                link_group: None,
+            },
+            Pane::ComparisonChart {
+                id: Uuid::new_v4(),
+                stream_type: vec![],
+                settings: settings.clone(),
+                link_group: None,
+            },
+            Pane::TimeAndSales {
+                id: Uuid::new_v4(),
+                stream_type: vec![],
+                settings: settings.clone(),
+                link_group: None,
+            },
+            Pane::Ladder {
+                id: Uuid::new_v4(),
+                stream_type: vec![],
+                settings: settings.clone(),
+                link_group: None,
+            },
+            Pane::TimeframeStrip {
+                id: Uuid::new_v4(),
+                stream_type: vec![],
+                settings,
+                link_group: None,
+            },
+        ];
+
+        for pane in &mut panes {
+            let settings = pane.settings_mut().expect("content panes have settings");
+            assert_eq!(settings.drawings.get(&ticker).len(), 1);
+            settings.drawings = Drawings::default();
+        }
+        for pane in &mut panes {
+            assert!(pane.settings_mut().unwrap().drawings.is_empty());
+        }
+
+        let mut starter = Pane::Starter {
+            id: Uuid::new_v4(),
+            link_group: None,
+        };
+        assert!(starter.settings_mut().is_none());
+        let mut quarantined = Pane::Quarantined {
+            error: "invalid type".to_string(),
+            raw: serde_json::json!({ "Ladder": {} }),
+        };
+        assert!(quarantined.settings_mut().is_none());
+
+        let mut split = Pane::Split {
+            axis: Axis::Vertical,
+            ratio: 0.5,
+            a: Box::new(starter),
+            b: Box::new(panes.remove(0)),
+        };
+        assert!(split.settings_mut().is_none());
+    }
+
+    #[test]
+    fn renewing_ids_gives_every_leaf_a_fresh_one() {
+        let leaf = || Pane::Starter {
+            id: Uuid::new_v4(),
+            link_group: Some(LinkGroup::A),
+        };
+        let mut pane = Pane::Split {
+            axis: Axis::Horizontal,
+            ratio: 0.5,
+            a: Box::new(leaf()),
+            b: Box::new(leaf()),
+        };
+        let leaf_ids = |pane: &Pane| match pane {
+            Pane::Split { a, b, .. } => [a.id().unwrap(), b.id().unwrap()],
+            _ => unreachable!(),
+        };
+
+        let before = leaf_ids(&pane);
+        pane.renew_ids();
+        let after = leaf_ids(&pane);
+
+        assert_ne!(after[0], after[1]);
+        assert!(after.iter().all(|id| !before.contains(id)));
+    }
+}
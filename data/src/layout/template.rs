@@ -0,0 +1,356 @@
+//! Starter layouts offered on first run, before any ticker is picked, and grids of charts opened
+//! for several tickers at once.
+//!
+//! Every pane of a template shares one link group, so picking a ticker in any of them loads it in
+//! all the others. Grid panes each keep their own ticker and aren't linked.
+
+use exchange::adapter::{Exchange, PersistKline, PersistStreamKind};
+use exchange::{Ticker, Timeframe};
+use uuid::Uuid;
+
+use super::pane::{Axis, LinkGroup, Pane, Settings};
+use crate::chart::indicator::{HeatmapIndicator, KlineIndicator};
+use crate::chart::{Autoscale, KlineChartKind, PriceScale, ViewConfig};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutTemplate {
+    Candlestick,
+    HeatmapLadder,
+    MultiTimeframe,
+}
+
+impl LayoutTemplate {
+    pub const ALL: [LayoutTemplate; 3] = [
+        LayoutTemplate::Candlestick,
+        LayoutTemplate::HeatmapLadder,
+        LayoutTemplate::MultiTimeframe,
+    ];
+
+    /// Timeframes of the multi-timeframe panes, top left to bottom right
+    pub const TIMEFRAMES: [Timeframe; 4] =
+        [Timeframe::M5, Timeframe::M15, Timeframe::H1, Timeframe::H4];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LayoutTemplate::Candlestick => "Candlestick",
+            LayoutTemplate::HeatmapLadder => "Heatmap + DOM",
+            LayoutTemplate::MultiTimeframe => "Multi-timeframe",
+        }
+    }
+
+    pub fn pane(self) -> Pane {
+        match self {
+            LayoutTemplate::Candlestick => candles(None),
+            LayoutTemplate::HeatmapLadder => split(
+                Axis::Vertical,
+                0.75,
+                Pane::HeatmapChart {
+                    id: Uuid::new_v4(),
+                    layout: ViewConfig {
+                        splits: vec![],
+                        autoscale: Some(Autoscale::CenterLatest),
+                        viewport: None,
+                        price_scale: PriceScale::Linear,
+                        panel_heights: None,
+                    },
+                    studies: vec![],
+                    stream_type: vec![],
+                    settings: Settings::default(),
+                    indicators: vec![HeatmapIndicator::Volume],
+                    link_group: Some(LINK_GROUP),
+                },
+                Pane::Ladder {
+                    id: Uuid::new_v4(),
+                    stream_type: vec![],
+                    settings: Settings::default(),
+                    link_group: Some(LINK_GROUP),
+                },
+            ),
+            LayoutTemplate::MultiTimeframe => {
+                let [a, b, c, d] = Self::TIMEFRAMES.map(|timeframe| candles(Some(timeframe)));
+                split(
+                    Axis::Vertical,
+                    0.5,
+                    split(Axis::Horizontal, 0.5, a, b),
+                    split(Axis::Horizontal, 0.5, c, d),
+                )
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for LayoutTemplate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+const LINK_GROUP: LinkGroup = LinkGroup::A;
+
+fn split(axis: Axis, ratio: f32, a: Pane, b: Pane) -> Pane {
+    Pane::Split {
+        axis,
+        ratio,
+        a: Box::new(a),
+        b: Box::new(b),
+    }
+}
+
+/// A candlestick chart waiting for its ticker, on `timeframe` or the default one
+fn candles(timeframe: Option<Timeframe>) -> Pane {
+    kline_pane(timeframe, vec![], Some(LINK_GROUP))
+}
+
+fn kline_pane(
+    timeframe: Option<Timeframe>,
+    stream_type: Vec<PersistStreamKind>,
+    link_group: Option<LinkGroup>,
+) -> Pane {
+    Pane::KlineChart {
+        id: Uuid::new_v4(),
+        layout: ViewConfig {
+            splits: vec![],
+            autoscale: Some(Autoscale::FitToVisible),
+            viewport: None,
+            price_scale: PriceScale::Linear,
+            panel_heights: None,
+        },
+        kind: KlineChartKind::Candles,
+        stream_type,
+        settings: Settings {
+            selected_basis: timeframe.map(Into::into),
+            ..Settings::default()
+        },
+        indicators: vec![KlineIndicator::Volume],
+        link_group,
+    }
+}
+
+/// Panes a generated grid holds at most, a 4×4
+pub const MAX_GRID_PANES: usize = 16;
+
+/// Why a picked ticker was left out of a grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GridSkip {
+    /// Its source isn't connected, there's nothing to open it with yet
+    Unavailable,
+    /// The grid already holds [`MAX_GRID_PANES`]
+    PaneLimit,
+    /// Its connection carries this many kline streams at most, all taken by earlier picks
+    StreamCap(usize),
+}
+
+/// Splits `picked` into the tickers a grid opens, in their order, and the ones it leaves out.
+/// `stream_cap` is how many kline streams one connection of an exchange carries at most.
+pub fn plan_grid(
+    picked: &[Ticker],
+    is_available: impl Fn(&Ticker) -> bool,
+    stream_cap: impl Fn(Exchange) -> Option<usize>,
+) -> (Vec<Ticker>, Vec<(Ticker, GridSkip)>) {
+    let mut opened: Vec<Ticker> = vec![];
+    let mut skipped = vec![];
+
+    for ticker in picked {
+        if opened.contains(ticker) {
+            continue;
+        }
+
+        let on_connection = opened
+            .iter()
+            .filter(|t| t.exchange == ticker.exchange)
+            .count();
+
+        if !is_available(ticker) {
+            skipped.push((*ticker, GridSkip::Unavailable));
+        } else if opened.len() >= MAX_GRID_PANES {
+            skipped.push((*ticker, GridSkip::PaneLimit));
+        } else if let Some(cap) = stream_cap(ticker.exchange)
+            && on_connection >= cap
+        {
+            skipped.push((*ticker, GridSkip::StreamCap(cap)));
+        } else {
+            opened.push(*ticker);
+        }
+    }
+
+    (opened, skipped)
+}
+
+/// Candlestick charts of `tickers` on `timeframe`, laid out row by row in a grid about as wide
+/// as it is tall
+pub fn grid(tickers: &[Ticker], timeframe: Timeframe) -> Option<Pane> {
+    if tickers.is_empty() {
+        return None;
+    }
+
+    let columns = (1..).find(|n| n * n >= tickers.len()).unwrap_or(1);
+
+    let rows = tickers
+        .chunks(columns)
+        .map(|row| {
+            let cells = row
+                .iter()
+                .map(|ticker| {
+                    let stream = PersistStreamKind::Kline(PersistKline {
+                        ticker: *ticker,
+                        timeframe,
+                    });
+                    kline_pane(Some(timeframe), vec![stream], None)
+                })
+                .collect();
+            even(Axis::Vertical, cells)
+        })
+        .collect();
+
+    Some(even(Axis::Horizontal, rows))
+}
+
+/// Splits `panes` along `axis` into equal shares, `panes` must not be empty
+fn even(axis: Axis, mut panes: Vec<Pane>) -> Pane {
+    if panes.len() == 1 {
+        return panes.remove(0);
+    }
+
+    let total = panes.len();
+    let rest = panes.split_off(total / 2);
+    let ratio = (total / 2) as f32 / total as f32;
+
+    split(axis, ratio, even(axis, panes), even(axis, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart::Basis;
+
+    fn leaves(pane: &Pane) -> Vec<&Pane> {
+        match pane {
+            Pane::Split { a, b, .. } => [leaves(a), leaves(b)].concat(),
+            leaf => vec![leaf],
+        }
+    }
+
+    #[test]
+    fn templates_are_linked_and_multi_timeframe_covers_each_timeframe() {
+        for template in LayoutTemplate::ALL {
+            let pane = template.pane();
+            for leaf in leaves(&pane) {
+                let link_group = match leaf {
+                    Pane::KlineChart { link_group, .. }
+                    | Pane::HeatmapChart { link_group, .. }
+                    | Pane::Ladder { link_group, .. } => *link_group,
+                    other => panic!("{template} has an unexpected pane {other:?}"),
+                };
+                assert_eq!(link_group, Some(LINK_GROUP), "{template}");
+            }
+        }
+
+        let pane = LayoutTemplate::MultiTimeframe.pane();
+        let bases: Vec<_> = leaves(&pane)
+            .into_iter()
+            .map(|leaf| match leaf {
+                Pane::KlineChart { settings, .. } => settings.selected_basis,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            bases,
+            LayoutTemplate::TIMEFRAMES.map(|timeframe| Some(Basis::Time(timeframe)))
+        );
+    }
+
+    fn grid_tickers(pane: &Pane) -> Vec<Ticker> {
+        leaves(pane)
+            .into_iter()
+            .map(|leaf| match leaf {
+                Pane::KlineChart {
+                    stream_type,
+                    settings,
+                    link_group,
+                    ..
+                } => {
+                    assert_eq!(settings.selected_basis, Some(Basis::Time(Timeframe::H1)));
+                    assert_eq!(*link_group, None);
+                    match stream_type.as_slice() {
+                        [PersistStreamKind::Kline(kline)] => kline.ticker,
+                        other => panic!("unexpected streams {other:?}"),
+                    }
+                }
+                other => panic!("unexpected pane {other:?}"),
+            })
+            .collect()
+    }
+
+    fn rows(pane: &Pane) -> Vec<usize> {
+        match pane {
+            Pane::Split {
+                axis: Axis::Horizontal,
+                a,
+                b,
+                ..
+            } => [rows(a), rows(b)].concat(),
+            row => vec![leaves(row).len()],
+        }
+    }
+
+    fn tickers(exchange: Exchange, symbols: &[&str]) -> Vec<Ticker> {
+        symbols
+            .iter()
+            .map(|symbol| Ticker::new(symbol, exchange))
+            .collect()
+    }
+
+    #[test]
+    fn grids_are_balanced_and_keep_the_picked_order() {
+        for (count, expected) in [
+            (1, vec![1]),
+            (2, vec![2]),
+            (4, vec![2, 2]),
+            (5, vec![3, 2]),
+            (9, vec![3, 3, 3]),
+            (10, vec![4, 4, 2]),
+        ] {
+            let symbols: Vec<String> = (0..count).map(|i| format!("T{i}USDT")).collect();
+            let symbols: Vec<&str> = symbols.iter().map(String::as_str).collect();
+            let picked = tickers(Exchange::BinanceLinear, &symbols);
+
+            let pane = grid(&picked, Timeframe::H1).unwrap();
+            assert_eq!(rows(&pane), expected, "{count} tickers");
+            assert_eq!(grid_tickers(&pane), picked);
+        }
+
+        let four = tickers(Exchange::BinanceLinear, &["A", "B", "C", "D"]);
+        match grid(&four, Timeframe::H1).unwrap() {
+            Pane::Split { ratio, .. } => assert_eq!(ratio, 0.5),
+            other => panic!("unexpected pane {other:?}"),
+        }
+        assert!(grid(&[], Timeframe::H1).is_none());
+    }
+
+    #[test]
+    fn grid_plan_reports_what_it_leaves_out() {
+        let mut picked = tickers(Exchange::BybitSpot, &["A", "B", "C", "A"]);
+        picked.extend(tickers(Exchange::BinanceLinear, &["OFFLINE"]));
+        let symbols: Vec<String> = (0..MAX_GRID_PANES).map(|i| format!("T{i}")).collect();
+        let symbols: Vec<&str> = symbols.iter().map(String::as_str).collect();
+        picked.extend(tickers(Exchange::BinanceLinear, &symbols));
+
+        let (opened, skipped) = plan_grid(
+            &picked,
+            |ticker| ticker != &picked[4],
+            |exchange| (exchange == Exchange::BybitSpot).then_some(2),
+        );
+
+        assert_eq!(opened.len(), MAX_GRID_PANES);
+        assert_eq!(opened[..2], picked[..2]);
+        assert_eq!(
+            skipped,
+            vec![
+                (picked[2], GridSkip::StreamCap(2)),
+                (picked[4], GridSkip::Unavailable),
+                (picked[picked.len() - 2], GridSkip::PaneLimit),
+                (picked[picked.len() - 1], GridSkip::PaneLimit),
+            ]
+        );
+    }
+}
@@ -1,16 +1,33 @@
 pub mod aggr;
 pub mod audio;
+pub mod book_dump;
 pub mod chart;
 pub mod config;
+pub mod data_dir;
+pub mod i18n;
+pub mod indicators;
+pub mod kline_store;
 pub mod layout;
 pub mod log;
+pub mod notifications;
 pub mod panel;
+pub mod retention;
+pub mod session;
+pub mod snapshot;
+pub mod state_store;
+pub mod stream_pause;
+pub mod stream_slots;
+pub mod stream_stats;
+pub mod support_bundle;
+pub mod symbol_cache;
+pub mod symbol_search;
+pub mod terminal_chart;
 pub mod tickers_table;
 pub mod util;
 
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub use audio::AudioStream;
 pub use config::ScaleFactor;
@@ -20,7 +37,10 @@ pub use config::theme::Theme;
 pub use config::timezone::UserTimezone;
 
 use ::log::{error, info, warn};
+pub use i18n::Locale;
 pub use layout::{Dashboard, Layout, Pane};
+pub use notifications::NotificationLog;
+pub use symbol_cache::SymbolCache;
 
 pub const SAVED_STATE_PATH: &str = "saved-state.json";
 
@@ -33,8 +53,10 @@ pub enum InternalError {
 }
 
 pub fn write_json_to_file(json: &str, file_name: &str) -> std::io::Result<()> {
-    let path = data_path(Some(file_name));
+    write_json_to_path(json, &data_path(Some(file_name)))
+}
 
+pub fn write_json_to_path(json: &str, path: &Path) -> std::io::Result<()> {
     let parent = path.parent().ok_or_else(|| {
         std::io::Error::new(std::io::ErrorKind::InvalidInput, "Invalid state file path")
     })?;
@@ -48,10 +70,10 @@ pub fn write_json_to_file(json: &str, file_name: &str) -> std::io::Result<()> {
     Ok(())
 }
 
-pub fn read_from_file(file_name: &str) -> Result<State, Box<dyn std::error::Error>> {
-    let path = data_path(Some(file_name));
-
-    let file_open_result = File::open(&path);
+/// Reads the saved state at `path`, usually [`data_dir::state_file`], either a manifest of
+/// [`state_store`] sections or a single file from before those
+pub fn read_from_file(path: &Path) -> Result<State, Box<dyn std::error::Error>> {
+    let file_open_result = File::open(path);
     let mut file = match file_open_result {
         Ok(file) => file,
         Err(e) => return Err(Box::new(e)),
@@ -62,22 +84,31 @@ pub fn read_from_file(file_name: &str) -> Result<State, Box<dyn std::error::Erro
         return Err(Box::new(e));
     }
 
-    match serde_json::from_str(&contents) {
+    let parsed = match state_store::read_manifest(path, &contents) {
+        Some(state) => state,
+        None => serde_json::from_str(&contents),
+    };
+
+    match parsed {
         Ok(state) => Ok(state),
         Err(e) => {
             // If parsing fails, backup the file
             drop(file); // Close the file before renaming
 
             // Create backup file with different name to prevent overwriting it
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
             let backup_file_name = if let Some(pos) = file_name.rfind('.') {
                 format!("{}_old{}", &file_name[..pos], &file_name[pos..])
             } else {
                 format!("{}_old", file_name)
             };
 
-            let backup_path = data_path(Some(&backup_file_name));
+            let backup_path = path.with_file_name(backup_file_name);
 
-            if let Err(rename_err) = std::fs::rename(&path, &backup_path) {
+            if let Err(rename_err) = std::fs::rename(path, &backup_path) {
                 warn!(
                     "Failed to backup corrupted state file '{}' to '{}': {}",
                     path.display(),
@@ -117,16 +148,13 @@ pub fn open_data_folder() -> Result<(), InternalError> {
     }
 }
 
+/// `path_name` inside the data dir, see [`data_dir`] for where that is
 pub fn data_path(path_name: Option<&str>) -> PathBuf {
-    if let Ok(path) = std::env::var("FLOWSURFACE_DATA_PATH") {
-        PathBuf::from(path)
+    let data_dir = data_dir::root();
+    if let Some(path_name) = path_name {
+        data_dir.join(path_name)
     } else {
-        let data_dir = dirs_next::data_dir().unwrap_or_else(|| PathBuf::from("."));
-        if let Some(path_name) = path_name {
-            data_dir.join("flowsurface").join(path_name)
-        } else {
-            data_dir.join("flowsurface")
-        }
+        data_dir
     }
 }
 
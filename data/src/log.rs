@@ -1,9 +1,22 @@
+use std::collections::VecDeque;
 use std::path::PathBuf;
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::sync::{Mutex, OnceLock};
 use std::{fs, io};
 
 use crate::data_path;
 
-const LOG_FILE: &str = "flowsurface-current.log";
+pub(crate) const LOG_FILE: &str = "flowsurface-current.log";
+
+/// Lines kept for the log viewer
+pub const VIEWER_CAPACITY: usize = 2_000;
+
+/// Lines waiting for the UI to collect them. Past this they are dropped rather than making the
+/// logging thread wait.
+const PENDING_CAPACITY: usize = 10_000;
+
+/// Key-value tag carrying the MT5 server address a record is about
+pub const MT5_CONNECTION_KEY: &str = "mt5";
 
 pub fn file() -> Result<fs::File, Error> {
     let path = path()?;
@@ -30,6 +43,174 @@ pub fn path() -> Result<PathBuf, Error> {
     Ok(full_path)
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    /// Position in the stream of captured lines, keeps counting past evicted ones
+    pub seq: u64,
+    /// Unix timestamp in milliseconds
+    pub time: u64,
+    pub level: log::Level,
+    /// Adapter or connection it came from, e.g. "Binance" or "MT5 localhost:9876"
+    pub source: String,
+    pub message: String,
+}
+
+impl Line {
+    /// The line as plain text, for pasting into a bug report
+    pub fn details(&self) -> String {
+        let time = chrono::DateTime::from_timestamp_millis(self.time as i64)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+            .unwrap_or_else(|| self.time.to_string());
+
+        format!("{time} {} [{}] {}", self.level, self.source, self.message)
+    }
+}
+
+struct Pending {
+    sender: SyncSender<Line>,
+    receiver: Mutex<Receiver<Line>>,
+}
+
+fn pending() -> &'static Pending {
+    static PENDING: OnceLock<Pending> = OnceLock::new();
+
+    PENDING.get_or_init(|| {
+        let (sender, receiver) = sync_channel(PENDING_CAPACITY);
+        Pending {
+            sender,
+            receiver: Mutex::new(receiver),
+        }
+    })
+}
+
+/// Queues `record` for the log viewer. Never blocks, the stream loops log from hot paths.
+pub fn capture(record: &log::Record) {
+    let source = match record
+        .key_values()
+        .get(log::kv::Key::from_str(MT5_CONNECTION_KEY))
+    {
+        Some(server_addr) => format!("MT5 {server_addr}"),
+        None => source_of_target(record.target()).to_string(),
+    };
+
+    let line = Line {
+        seq: 0,
+        time: chrono::Utc::now().timestamp_millis() as u64,
+        level: record.level(),
+        source,
+        message: record.args().to_string(),
+    };
+
+    let _ = pending().sender.try_send(line);
+}
+
+/// Lines captured since the last call
+pub fn take_captured() -> Vec<Line> {
+    pending()
+        .receiver
+        .lock()
+        .map(|receiver| receiver.try_iter().collect())
+        .unwrap_or_default()
+}
+
+/// Which adapter a log target belongs to, by module path
+pub fn source_of_target(target: &str) -> &'static str {
+    let adapter = target
+        .strip_prefix("exchange::adapter::")
+        .and_then(|rest| rest.split("::").next());
+
+    match adapter {
+        Some("metatrader5") => "MT5",
+        Some("binance") => "Binance",
+        Some("bybit") => "Bybit",
+        Some("okex") => "OKX",
+        Some("hyperliquid") => "Hyperliquid",
+        Some("custom_ws") => "Custom feed",
+        _ => match target.split("::").next() {
+            Some("exchange") => "Exchange",
+            Some("data") => "Data",
+            _ => "App",
+        },
+    }
+}
+
+/// What the log viewer shows
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub max_level: log::LevelFilter,
+    pub source: Option<String>,
+    /// Case-insensitive substring of the message
+    pub query: String,
+    /// Hides lines captured after this one, set while the viewer is paused
+    pub until: Option<u64>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            max_level: log::LevelFilter::Trace,
+            source: None,
+            query: String::new(),
+            until: None,
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, line: &Line, query: &str) -> bool {
+        line.level <= self.max_level
+            && self.until.is_none_or(|until| line.seq <= until)
+            && self.source.as_ref().is_none_or(|s| *s == line.source)
+            && (query.is_empty() || line.message.to_lowercase().contains(query))
+    }
+}
+
+/// The newest [`VIEWER_CAPACITY`] captured lines
+#[derive(Debug, Default)]
+pub struct LogBuffer {
+    lines: VecDeque<Line>,
+    next_seq: u64,
+}
+
+impl LogBuffer {
+    pub fn extend(&mut self, lines: impl IntoIterator<Item = Line>) {
+        for mut line in lines {
+            line.seq = self.next_seq;
+            self.next_seq += 1;
+
+            self.lines.push_back(line);
+        }
+        while self.lines.len() > VIEWER_CAPACITY {
+            self.lines.pop_front();
+        }
+    }
+
+    /// Sequence number of the newest line, `None` before anything was captured
+    pub fn last_seq(&self) -> Option<u64> {
+        self.next_seq.checked_sub(1)
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    /// Every source with at least one line, sorted
+    pub fn sources(&self) -> Vec<String> {
+        let mut sources: Vec<String> = self.lines.iter().map(|l| l.source.clone()).collect();
+        sources.sort();
+        sources.dedup();
+        sources
+    }
+
+    /// Lines passing `filter`, oldest first
+    pub fn filtered<'a>(&'a self, filter: &'a Filter) -> impl Iterator<Item = &'a Line> {
+        let query = filter.query.to_lowercase();
+        self.lines
+            .iter()
+            .filter(move |line| filter.matches(line, &query))
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -39,3 +220,68 @@ pub enum Error {
     #[error(transparent)]
     ParseLevel(#[from] log::ParseLevelError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(level: log::Level, source: &str, message: &str) -> Line {
+        Line {
+            seq: 0,
+            time: 0,
+            level,
+            source: source.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn buffer_is_bounded_and_filters_by_level_source_text_and_pause() {
+        let mut buffer = LogBuffer::default();
+        buffer.extend(
+            (0..VIEWER_CAPACITY + 5)
+                .map(|i| line(log::Level::Debug, "Binance", &format!("depth update {i}"))),
+        );
+        assert_eq!(buffer.filtered(&Filter::default()).count(), VIEWER_CAPACITY);
+        assert_eq!(buffer.last_seq(), Some(VIEWER_CAPACITY as u64 + 4));
+
+        buffer.clear();
+        buffer.extend([
+            line(
+                log::Level::Info,
+                "MT5 localhost:9876",
+                "Subscribed to EURUSD",
+            ),
+            line(log::Level::Error, "MT5 localhost:9876", "Connection reset"),
+            line(log::Level::Warn, "Bybit", "Connection lagging"),
+        ]);
+        let paused_at = buffer.last_seq();
+        buffer.extend([line(log::Level::Error, "Bybit", "Connection reset")]);
+
+        let warnings = Filter {
+            max_level: log::LevelFilter::Warn,
+            ..Filter::default()
+        };
+        assert_eq!(buffer.filtered(&warnings).count(), 3);
+
+        let mt5_resets = Filter {
+            source: Some("MT5 localhost:9876".to_string()),
+            query: "RESET".to_string(),
+            ..Filter::default()
+        };
+        assert_eq!(buffer.filtered(&mt5_resets).count(), 1);
+
+        let paused = Filter {
+            until: paused_at,
+            ..Filter::default()
+        };
+        assert_eq!(buffer.filtered(&paused).count(), 3);
+
+        assert_eq!(buffer.sources(), ["Bybit", "MT5 localhost:9876"]);
+        assert_eq!(
+            source_of_target("exchange::adapter::metatrader5::multiplex"),
+            "MT5"
+        );
+        assert_eq!(source_of_target("flowsurface::screen::dashboard"), "App");
+    }
+}
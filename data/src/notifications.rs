@@ -0,0 +1,175 @@
+//! History of connection events, alerts and errors shown in the notifications center.
+//!
+//! Only the newest [`MAX_RECORDS`] are kept, in memory and on disk.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+pub const NOTIFICATIONS_PATH: &str = "notifications.json";
+
+pub const MAX_RECORDS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Info => f.write_str(crate::t!("severity.info")),
+            Severity::Warning => f.write_str(crate::t!("severity.warning")),
+            Severity::Error => f.write_str(crate::t!("severity.error")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct Record {
+    /// Unix timestamp in milliseconds
+    pub time: u64,
+    pub severity: Severity,
+    /// Where it came from, e.g. "MT5 localhost:9876" or "Price alert"
+    pub source: String,
+    pub message: String,
+}
+
+impl Record {
+    /// The record as one line of plain text, for pasting into a bug report
+    pub fn details(&self) -> String {
+        let time = chrono::DateTime::from_timestamp_millis(self.time as i64)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f UTC").to_string())
+            .unwrap_or_else(|| self.time.to_string());
+
+        format!(
+            "{time} [{}] {}: {}",
+            self.severity, self.source, self.message
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotificationLog {
+    records: VecDeque<Record>,
+    /// Newest records not seen in the notifications center yet
+    unread: usize,
+    /// Play a sound for every error that gets recorded
+    pub sound_on_error: bool,
+}
+
+impl NotificationLog {
+    pub fn push(&mut self, record: Record) {
+        self.records.push_back(record);
+        while self.records.len() > MAX_RECORDS {
+            self.records.pop_front();
+        }
+        self.unread = (self.unread + 1).min(self.records.len());
+    }
+
+    pub fn unread(&self) -> usize {
+        self.unread
+    }
+
+    pub fn mark_read(&mut self) {
+        self.unread = 0;
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+        self.unread = 0;
+    }
+
+    /// Whether the record at `index` is one of the `count` newest
+    pub fn is_among_newest(&self, index: usize, count: usize) -> bool {
+        index >= self.records.len().saturating_sub(count)
+    }
+
+    /// Records at or above `min_severity` with their index, newest first
+    pub fn filtered(
+        &self,
+        min_severity: Option<Severity>,
+    ) -> impl Iterator<Item = (usize, &Record)> {
+        self.records
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(move |(_, r)| min_severity.is_none_or(|min| r.severity >= min))
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Record> {
+        self.records.get(index)
+    }
+
+    pub fn load() -> Self {
+        let path = crate::data_path(Some(NOTIFICATIONS_PATH));
+
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                log::warn!(
+                    "Discarding unreadable notifications {}: {e}",
+                    path.display()
+                );
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                if let Err(e) = crate::write_json_to_file(&json, NOTIFICATIONS_PATH) {
+                    log::error!("Failed to write notifications: {e}");
+                }
+            }
+            Err(e) => log::error!("Failed to serialize notifications: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(time: u64, severity: Severity) -> Record {
+        Record {
+            time,
+            severity,
+            source: "MT5 localhost:9876".to_string(),
+            message: format!("event {time}"),
+        }
+    }
+
+    #[test]
+    fn history_is_bounded_and_tracks_unread() {
+        let mut log = NotificationLog::default();
+        for time in 0..(MAX_RECORDS as u64 + 10) {
+            let severity = if time % 2 == 0 {
+                Severity::Info
+            } else {
+                Severity::Error
+            };
+            log.push(record(time, severity));
+        }
+
+        assert_eq!(log.filtered(None).count(), MAX_RECORDS);
+        assert_eq!(log.unread(), MAX_RECORDS);
+        assert_eq!(log.filtered(None).next().unwrap().1.time, 209);
+
+        let errors = log.filtered(Some(Severity::Error));
+        assert!(
+            errors
+                .map(|(_, r)| r.severity)
+                .all(|s| s == Severity::Error)
+        );
+
+        log.mark_read();
+        log.push(record(300, Severity::Warning));
+        assert_eq!(log.unread(), 1);
+        assert!(log.is_among_newest(MAX_RECORDS - 1, log.unread()));
+        assert!(!log.is_among_newest(0, log.unread()));
+    }
+}
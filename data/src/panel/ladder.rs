@@ -6,10 +6,7 @@ use exchange::{
 };
 
 use serde::{Deserialize, Serialize};
-use std::{
-    collections::{BTreeMap, VecDeque},
-    time::Duration,
-};
+use std::{collections::VecDeque, time::Duration};
 
 const TRADE_RETENTION_MS: u64 = 8 * 60_000;
 const CHASE_MIN_VISIBLE_OPACITY: f32 = 0.15;
@@ -20,14 +17,27 @@ pub struct Config {
     #[serde(deserialize_with = "ok_or_default", default)]
     pub show_chase_tracker: bool,
     pub trade_retention: Duration,
+    /// Price levels drawn on each side of the spread, `0` fills the pane
+    #[serde(default)]
+    pub levels_per_side: u16,
+    #[serde(default = "default_true")]
+    pub show_trades: bool,
+}
+
+fn default_true() -> bool {
+    true
 }
 
+pub const MAX_LEVELS_PER_SIDE: u16 = 100;
+
 impl Default for Config {
     fn default() -> Self {
         Self {
             show_spread: false,
             show_chase_tracker: true,
             trade_retention: Duration::from_millis(TRADE_RETENTION_MS),
+            levels_per_side: 0,
+            show_trades: true,
         }
     }
 }
@@ -57,36 +67,6 @@ impl Side {
     }
 }
 
-#[derive(Default)]
-pub struct GroupedDepth {
-    pub orders: BTreeMap<Price, f32>,
-    pub chase: ChaseTracker,
-}
-
-impl GroupedDepth {
-    pub fn new() -> Self {
-        Self {
-            orders: BTreeMap::new(),
-            chase: ChaseTracker::default(),
-        }
-    }
-
-    pub fn regroup_from_raw(&mut self, levels: &BTreeMap<Price, f32>, side: Side, step: PriceStep) {
-        self.orders.clear();
-        for (price, qty) in levels.iter() {
-            let grouped_price = price.round_to_side_step(side.is_bid(), step);
-            *self.orders.entry(grouped_price).or_insert(0.0) += *qty;
-        }
-    }
-
-    pub fn best_price(&self, side: Side) -> Option<Price> {
-        match side {
-            Side::Bid => self.orders.last_key_value().map(|(p, _)| *p),
-            Side::Ask => self.orders.first_key_value().map(|(p, _)| *p),
-        }
-    }
-}
-
 #[derive(Debug)]
 pub struct TradeStore {
     pub raw: VecDeque<Trade>,
@@ -413,3 +393,32 @@ impl ChaseTracker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layouts_saved_before_the_level_cap_keep_the_full_ladder() {
+        let json = r#"{
+            "show_spread": true,
+            "show_chase_tracker": false,
+            "trade_retention": { "secs": 120, "nanos": 0 }
+        }"#;
+
+        let config: Config = serde_json::from_str(json).unwrap();
+        assert_eq!(config.levels_per_side, 0);
+        assert!(config.show_trades);
+        assert!(config.show_spread);
+        assert_eq!(config.trade_retention, Duration::from_secs(120));
+
+        let capped = Config {
+            levels_per_side: 12,
+            show_trades: false,
+            ..config
+        };
+        let restored: Config =
+            serde_json::from_str(&serde_json::to_string(&capped).unwrap()).unwrap();
+        assert_eq!(restored, capped);
+    }
+}
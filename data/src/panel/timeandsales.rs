@@ -14,6 +14,9 @@ pub struct Config {
     pub trade_retention: Duration,
     #[serde(deserialize_with = "ok_or_default", default)]
     pub stacked_bar: Option<StackedBar>,
+    /// Collapse prints this many ms apart into sweeps, see [`crate::aggr::sweep`]
+    #[serde(default)]
+    pub sweep_window_ms: Option<u16>,
 }
 
 impl Default for Config {
@@ -22,6 +25,7 @@ impl Default for Config {
             trade_size_filter: 0.0,
             trade_retention: Duration::from_millis(TRADE_RETENTION_MS),
             stacked_bar: StackedBar::Compact(StackedBarRatio::default()).into(),
+            sweep_window_ms: None,
         }
     }
 }
@@ -42,6 +46,8 @@ pub struct TradeDisplay {
 pub struct TradeEntry {
     pub ts_ms: u64,
     pub display: TradeDisplay,
+    /// Sweep the print belongs to, consecutive entries of one sweep share it
+    pub sweep: u64,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Copy)]
@@ -0,0 +1,261 @@
+//! How much history panes keep in memory, so a session left running for days doesn't grow
+//! without bound.
+//!
+//! Every [`PRUNE_INTERVAL`] charts drop their oldest bars, raw trades and heatmap columns past
+//! these limits. Whatever is on screen is kept however old it is, and a saved framing still
+//! waiting to be applied holds pruning off altogether. Kline charts fetch dropped bars again once
+//! they're scrolled back to.
+
+use crate::chart::Basis;
+use exchange::{Timeframe, Trade};
+
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, RwLock};
+use std::time::Duration;
+
+/// How often charts prune their history
+pub const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest timeframe that counts as intraday
+const INTRADAY: Timeframe = Timeframe::M5;
+
+pub const BAR_CHOICES: [Bars; 6] = [
+    Bars(1_000),
+    Bars(2_000),
+    Bars(5_000),
+    Bars(10_000),
+    Bars(20_000),
+    Bars(50_000),
+];
+
+pub const WINDOW_CHOICES: [Window; 6] = [
+    Window(30),
+    Window(60),
+    Window(120),
+    Window(240),
+    Window(480),
+    Window(1_440),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Retention {
+    /// Bars of timeframes up to 5 minutes, and of tick based charts
+    pub intraday_bars: Bars,
+    /// Bars of longer timeframes
+    pub bars: Bars,
+    pub trades: Window,
+    pub heatmap: Window,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            intraday_bars: Bars(5_000),
+            bars: Bars(20_000),
+            trades: Window(240),
+            heatmap: Window(120),
+        }
+    }
+}
+
+impl Retention {
+    pub fn max_bars(&self, basis: Basis) -> usize {
+        match basis {
+            Basis::Time(timeframe) if timeframe > INTRADAY => self.bars.0,
+            Basis::Time(_) | Basis::Tick(_) => self.intraday_bars.0,
+        }
+    }
+}
+
+/// A number of bars
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Bars(pub usize);
+
+impl std::fmt::Display for Bars {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0 >= 1_000 && self.0.is_multiple_of(1_000) {
+            write!(f, "{}k", self.0 / 1_000)
+        } else {
+            write!(f, "{}", self.0)
+        }
+    }
+}
+
+/// A span of time in minutes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Window(pub u32);
+
+impl Window {
+    pub fn as_millis(self) -> u64 {
+        u64::from(self.0) * 60_000
+    }
+}
+
+impl std::fmt::Display for Window {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0 >= 60 && self.0.is_multiple_of(60) {
+            write!(f, "{}h", self.0 / 60)
+        } else {
+            write!(f, "{}m", self.0)
+        }
+    }
+}
+
+static RETENTION: LazyLock<RwLock<Retention>> = LazyLock::new(|| RwLock::new(Retention::default()));
+
+pub fn config() -> Retention {
+    RETENTION
+        .read()
+        .map(|retention| *retention)
+        .unwrap_or_default()
+}
+
+pub fn set_config(retention: Retention) {
+    if let Ok(mut current) = RETENTION.write() {
+        *current = retention;
+    }
+}
+
+/// Drops trades more than `window_ms` older than the latest one, never one at or after
+/// `keep_from`. Fetched history may land after live trades, so `trades` needn't be in order.
+/// Returns how many were dropped.
+pub fn prune_trades(trades: &mut Vec<Trade>, window_ms: u64, keep_from: Option<u64>) -> usize {
+    let Some(latest) = trades.iter().map(|trade| trade.time).max() else {
+        return 0;
+    };
+    let cut = latest.saturating_sub(window_ms);
+    let cut = keep_from.map_or(cut, |keep_from| cut.min(keep_from));
+
+    let before = trades.len();
+    trades.retain(|trade| trade.time >= cut);
+    before - trades.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggr::{ticks::TickAggr, time::TimeSeries};
+    use crate::chart::kline::KlineDataPoint;
+    use exchange::Kline;
+    use exchange::util::{Price, PriceStep};
+
+    const MINUTE: u64 = 60_000;
+    const DAY: u64 = 1_440 * MINUTE;
+
+    fn kline(time: u64) -> Kline {
+        let price = Price::from_f32(100.0);
+        Kline {
+            time,
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: (1.0, 1.0),
+        }
+    }
+
+    fn trade(time: u64) -> Trade {
+        Trade {
+            time,
+            is_sell: time.is_multiple_of(2),
+            price: Price::from_f32(100.0),
+            qty: 1.0,
+        }
+    }
+
+    /// Five days of a live M1 feed, pruned the way a chart does it
+    #[test]
+    fn a_multi_day_feed_stays_at_the_cap() {
+        let retention = Retention::default();
+        let max_bars = retention.max_bars(Basis::Time(Timeframe::M1));
+        let window = retention.trades.as_millis();
+
+        let mut series = TimeSeries::<KlineDataPoint>::new(
+            Timeframe::M1,
+            PriceStep::from_f32(0.01),
+            &[kline(0)],
+        );
+        let mut trades = vec![];
+        let prune_every = PRUNE_INTERVAL.as_millis() as u64;
+
+        let mut time = 0;
+        while time < 5 * DAY {
+            time += 10_000;
+            trades.push(trade(time));
+            if time.is_multiple_of(MINUTE) {
+                series.insert_klines(&[kline(time)]);
+            }
+
+            if time.is_multiple_of(prune_every) {
+                series.prune(max_bars, None);
+                prune_trades(&mut trades, window, None);
+
+                assert!(series.datapoints.len() <= max_bars);
+                assert!(trades.len() as u64 <= window / 10_000 + 1);
+            }
+        }
+
+        assert_eq!(series.datapoints.len(), max_bars);
+        assert_eq!(
+            series.datapoints.keys().next().copied(),
+            Some(5 * DAY - (max_bars as u64 - 1) * MINUTE)
+        );
+        assert_eq!(trades.first().map(|t| t.time), Some(5 * DAY - window));
+    }
+
+    #[test]
+    fn what_is_on_screen_is_never_pruned() {
+        let klines: Vec<Kline> = (0..100).map(|i| kline(i * MINUTE)).collect();
+        let mut series =
+            TimeSeries::<KlineDataPoint>::new(Timeframe::M1, PriceStep::from_f32(0.01), &klines);
+
+        // Scrolled back to bar 20, only the 20 bars before it may go
+        assert_eq!(series.prune(10, Some(20 * MINUTE)), 20);
+        assert_eq!(series.datapoints.keys().next(), Some(&(20 * MINUTE)));
+
+        // Back at the latest bars the cap applies again
+        assert_eq!(series.prune(10, Some(95 * MINUTE)), 70);
+        assert_eq!(series.datapoints.len(), 10);
+
+        let mut trades: Vec<Trade> = (0..100).map(|i| trade(i * MINUTE)).collect();
+        assert_eq!(
+            prune_trades(&mut trades, 10 * MINUTE, Some(50 * MINUTE)),
+            50
+        );
+        assert_eq!(prune_trades(&mut trades, 10 * MINUTE, None), 39);
+        assert_eq!(trades.len(), 11);
+    }
+
+    #[test]
+    fn tick_bars_keep_the_visible_ones() {
+        let trades: Vec<Trade> = (0..1_000).map(trade).collect();
+        let mut aggr = TickAggr::new(
+            crate::aggr::TickCount(10),
+            PriceStep::from_f32(0.01),
+            &trades,
+        );
+        assert_eq!(aggr.datapoints.len(), 100);
+
+        // The oldest bar on screen is 59 back from the latest
+        assert_eq!(aggr.prune(20, Some(59)), 40);
+        assert_eq!(aggr.prune(20, None), 40);
+        assert_eq!(aggr.datapoints.len(), 20);
+        assert_eq!(aggr.datapoints[0].kline.time, 800);
+    }
+
+    #[test]
+    fn limits_by_timeframe_and_labels() {
+        let retention = Retention::default();
+        assert_eq!(retention.max_bars(Basis::Time(Timeframe::M5)), 5_000);
+        assert_eq!(retention.max_bars(Basis::Time(Timeframe::M15)), 20_000);
+        assert_eq!(Bars(5_000).to_string(), "5k");
+        assert_eq!(Window(240).to_string(), "4h");
+        assert_eq!(Window(30).to_string(), "30m");
+
+        // Older saved states without the setting keep the defaults
+        let parsed: Retention = serde_json::from_str(r#"{"bars":50000}"#).unwrap();
+        assert_eq!(parsed.bars, Bars(50_000));
+        assert_eq!(parsed.trades, retention.trades);
+    }
+}
@@ -0,0 +1,71 @@
+//! Tells a launch whether the previous session ended cleanly.
+//!
+//! A marker next to the saved state is written at startup and removed on a clean exit or
+//! restart. A crash leaves it behind, most often a layout that panics while it renders, so the
+//! next launch finds it and can start without opening any pane.
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Marker of the session using the current state file, instances on other state files keep
+/// their own
+pub fn marker_path() -> PathBuf {
+    crate::data_dir::state_file().with_extension("running")
+}
+
+/// Marks a session as running at `marker`, returns whether the previous one never cleared its
+/// marker
+pub fn begin(marker: &Path) -> bool {
+    let crashed = marker.exists();
+
+    let written = marker
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| std::fs::write(marker, std::process::id().to_string()));
+    if let Err(e) = written {
+        log::warn!("Failed to mark the session at {}: {e}", marker.display());
+    }
+
+    crashed
+}
+
+/// Clears the marker of a session ending cleanly
+pub fn end(marker: &Path) {
+    match std::fs::remove_file(marker) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => log::warn!(
+            "Failed to clear the session marker {}: {e}",
+            marker.display()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_session_that_never_ended_is_reported() {
+        let dir = std::env::temp_dir().join(format!(
+            "flowsurface-session-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        let marker = dir.join("saved-state.running");
+
+        assert!(!begin(&marker));
+        end(&marker);
+        assert!(!marker.exists());
+
+        // Started again and crashed without ending
+        assert!(!begin(&marker));
+        assert!(begin(&marker));
+
+        end(&marker);
+        end(&marker);
+        assert!(!marker.exists());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
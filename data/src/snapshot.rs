@@ -0,0 +1,272 @@
+//! Exported images of a pane or the whole layout, written to the data folder as PNG.
+//!
+//! The pixels are what the window showed, cropped to what's exported. Anything the renderer left
+//! transparent is laid over the theme's background, and a watermark naming the chart and the
+//! time goes in the bottom right corner so a shared image says what it shows.
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+use std::io::Write;
+use std::path::PathBuf;
+
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// Height of the watermark's text in logical pixels
+const WATERMARK_SIZE: f32 = 11.0;
+const WATERMARK_MARGIN: f32 = 6.0;
+
+/// RGBA8 pixels, row by row from the top left
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+}
+
+impl Snapshot {
+    pub fn new(width: u32, height: u32, rgba: Vec<u8>) -> Self {
+        debug_assert_eq!(rgba.len(), width as usize * height as usize * 4);
+        Self {
+            width,
+            height,
+            rgba,
+        }
+    }
+
+    /// Lays every pixel over an opaque `background`
+    pub fn flatten(&mut self, background: [u8; 3]) {
+        for pixel in self.rgba.chunks_exact_mut(4) {
+            let alpha = pixel[3];
+            for (channel, under) in pixel[..3].iter_mut().zip(background) {
+                *channel = blend(under, *channel, alpha);
+            }
+            pixel[3] = u8::MAX;
+        }
+    }
+
+    /// Writes `text` into the bottom right corner, `scale` being the window's scale factor
+    pub fn watermark(
+        &mut self,
+        font: &[u8],
+        text: &str,
+        color: [u8; 4],
+        scale: f32,
+    ) -> Result<(), String> {
+        let font = FontRef::try_from_slice(font).map_err(|e| e.to_string())?;
+        let font = font.as_scaled(PxScale::from(WATERMARK_SIZE * scale));
+        let margin = WATERMARK_MARGIN * scale;
+
+        let glyphs: Vec<_> = text.chars().map(|c| font.glyph_id(c)).collect();
+        let width: f32 = glyphs
+            .iter()
+            .zip(glyphs.iter().skip(1).map(Some).chain([None]))
+            .map(|(id, next)| font.h_advance(*id) + next.map_or(0.0, |next| font.kern(*id, *next)))
+            .sum();
+
+        let mut x = self.width as f32 - margin - width;
+        let baseline = self.height as f32 - margin + font.descent();
+        if x < 0.0 || baseline - font.ascent() < 0.0 {
+            // Too small to fit, the image still shows what it should
+            return Ok(());
+        }
+
+        for (index, id) in glyphs.iter().enumerate() {
+            let glyph = id.with_scale_and_position(font.scale(), point(x, baseline));
+            x += font.h_advance(*id);
+            if let Some(next) = glyphs.get(index + 1) {
+                x += font.kern(*id, *next);
+            }
+
+            let Some(outlined) = font.outline_glyph(glyph) else {
+                continue;
+            };
+            let bounds = outlined.px_bounds();
+
+            outlined.draw(|gx, gy, coverage| {
+                let px = bounds.min.x as i64 + i64::from(gx);
+                let py = bounds.min.y as i64 + i64::from(gy);
+                self.blend_pixel(px, py, color, coverage);
+            });
+        }
+
+        Ok(())
+    }
+
+    fn blend_pixel(&mut self, x: i64, y: i64, color: [u8; 4], coverage: f32) {
+        if x < 0 || y < 0 || x >= i64::from(self.width) || y >= i64::from(self.height) {
+            return;
+        }
+
+        let alpha = (f32::from(color[3]) * coverage.clamp(0.0, 1.0)).round() as u8;
+        let offset = (y as usize * self.width as usize + x as usize) * 4;
+        let pixel = &mut self.rgba[offset..offset + 4];
+
+        for (channel, over) in pixel[..3].iter_mut().zip(color) {
+            *channel = blend(*channel, over, alpha);
+        }
+    }
+
+    /// Non-interlaced 8 bit RGBA PNG
+    pub fn encode_png(&self) -> std::io::Result<Vec<u8>> {
+        let row_len = self.width as usize * 4;
+        let mut scanlines = Vec::with_capacity((row_len + 1) * self.height as usize);
+        if row_len > 0 {
+            for row in self.rgba.chunks_exact(row_len) {
+                // Filter type None, charts are mostly flat areas the deflate handles well anyway
+                scanlines.push(0);
+                scanlines.extend_from_slice(row);
+            }
+        }
+
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+        encoder.write_all(&scanlines)?;
+        let compressed = encoder.finish()?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlace
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        write_chunk(&mut png, b"IHDR", &header);
+        write_chunk(&mut png, b"IDAT", &compressed);
+        write_chunk(&mut png, b"IEND", &[]);
+        Ok(png)
+    }
+
+    /// Relative to the data folder, e.g. `snapshots/BTCUSDT_Heatmap-20250101-120000.png`
+    pub fn file_name(label: &str, taken_at: u64) -> String {
+        let time = chrono::DateTime::from_timestamp_millis(taken_at as i64)
+            .map(|t| t.format("%Y%m%d-%H%M%S%.3f").to_string())
+            .unwrap_or_else(|| taken_at.to_string());
+        let label: String = label
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+
+        format!("{SNAPSHOTS_DIR}/{label}-{time}.png")
+    }
+
+    /// Encodes and writes the image, returns the full path of the file
+    pub fn write(&self, label: &str, taken_at: u64) -> std::io::Result<PathBuf> {
+        let path = crate::data_path(Some(&Self::file_name(label, taken_at)));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(&path, self.encode_png()?)?;
+        Ok(path)
+    }
+}
+
+/// `over` laid over `under` with `alpha` opacity
+fn blend(under: u8, over: u8, alpha: u8) -> u8 {
+    let alpha = u16::from(alpha);
+    ((u16::from(over) * alpha + u16::from(under) * (255 - alpha) + 127) / 255) as u8
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    let mut crc = flate2::Crc::new();
+    crc.update(kind);
+    crc.update(data);
+
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    png.extend_from_slice(&crc.sum().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    const FONT: &[u8] = include_bytes!("../../assets/fonts/AzeretMono-Regular.ttf");
+
+    fn chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+        let mut chunks = vec![];
+        let mut rest = &png[8..];
+        while !rest.is_empty() {
+            let len = u32::from_be_bytes(rest[..4].try_into().unwrap()) as usize;
+            let kind = String::from_utf8(rest[4..8].to_vec()).unwrap();
+            let data = rest[8..8 + len].to_vec();
+
+            let mut crc = flate2::Crc::new();
+            crc.update(&rest[4..8 + len]);
+            let stored = u32::from_be_bytes(rest[8 + len..12 + len].try_into().unwrap());
+            assert_eq!(crc.sum(), stored, "{kind} checksum");
+
+            chunks.push((kind, data));
+            rest = &rest[12 + len..];
+        }
+        chunks
+    }
+
+    #[test]
+    fn png_holds_the_pixels_row_by_row() {
+        let rgba = vec![
+            255, 0, 0, 255, 0, 255, 0, 255, // red, green
+            0, 0, 255, 255, 10, 20, 30, 40, // blue, translucent
+        ];
+        let png = Snapshot::new(2, 2, rgba.clone()).encode_png().unwrap();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let chunks = chunks(&png);
+        let kinds: Vec<&str> = chunks.iter().map(|(kind, _)| kind.as_str()).collect();
+        assert_eq!(kinds, ["IHDR", "IDAT", "IEND"]);
+        assert_eq!(chunks[0].1, [0, 0, 0, 2, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+
+        let mut scanlines = vec![];
+        flate2::read::ZlibDecoder::new(chunks[1].1.as_slice())
+            .read_to_end(&mut scanlines)
+            .unwrap();
+        assert_eq!(scanlines[0], 0);
+        assert_eq!(&scanlines[1..9], &rgba[..8]);
+        assert_eq!(scanlines[9], 0);
+        assert_eq!(&scanlines[10..], &rgba[8..]);
+    }
+
+    #[test]
+    fn flatten_lays_pixels_over_the_background() {
+        let mut snapshot = Snapshot::new(2, 1, vec![200, 100, 0, 255, 200, 100, 0, 0]);
+        snapshot.flatten([20, 30, 40]);
+
+        assert_eq!(snapshot.rgba, [200, 100, 0, 255, 20, 30, 40, 255]);
+    }
+
+    #[test]
+    fn watermark_lands_in_the_bottom_right_corner() {
+        let (width, height) = (200, 60);
+        let blank = vec![0; width * height * 4];
+        let mut snapshot = Snapshot::new(width as u32, height as u32, blank.clone());
+
+        snapshot
+            .watermark(FONT, "BTCUSDT 5m", [255, 255, 255, 255], 1.0)
+            .unwrap();
+
+        let inked: Vec<(usize, usize)> = snapshot
+            .rgba
+            .chunks_exact(4)
+            .enumerate()
+            .filter(|(_, pixel)| pixel[0] > 0)
+            .map(|(i, _)| (i % width, i / width))
+            .collect();
+
+        assert!(!inked.is_empty());
+        assert!(inked.iter().all(|(x, y)| *x > width / 2 && *y > height / 2));
+
+        // Nothing is drawn where it wouldn't fit
+        let mut tiny = Snapshot::new(8, 8, vec![0; 8 * 8 * 4]);
+        tiny.watermark(FONT, "BTCUSDT 5m", [255, 255, 255, 255], 1.0)
+            .unwrap();
+        assert!(tiny.rgba.iter().all(|c| *c == 0));
+    }
+
+    #[test]
+    fn file_names_are_safe_for_any_label() {
+        assert_eq!(
+            Snapshot::file_name("BTCUSDT · Heatmap/1m", 1_704_665_100_250),
+            "snapshots/BTCUSDT___Heatmap_1m-20240107-220500.250.png"
+        );
+    }
+}
@@ -0,0 +1,509 @@
+//! The saved state split into section files, so a save only rewrites the sections that changed.
+//!
+//! The state file itself holds a small manifest naming the section files, which sit next to it as
+//! `<stem>.<section>.json`. A state file from before the split is still read whole, the first save
+//! after that writes the sections and keeps the old file as `<stem>.single.json`.
+//!
+//! A section that can't be loaded is moved aside as `<stem>.<section>_old.json` before the state
+//! falls back to its defaults, so the next save doesn't overwrite what it held.
+//!
+//! Price alerts are pane settings, so they're saved with the layouts.
+
+use crate::State;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+
+const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    /// Every setting not in another section
+    Core,
+    Layouts,
+    Mt5,
+    Sidebar,
+}
+
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::Core,
+        Section::Layouts,
+        Section::Mt5,
+        Section::Sidebar,
+    ];
+
+    /// The [`State`] field this section holds, core holds all the others
+    fn field(self) -> Option<&'static str> {
+        match self {
+            Section::Core => None,
+            Section::Layouts => Some("layout_manager"),
+            Section::Mt5 => Some("mt5_settings"),
+            Section::Sidebar => Some("sidebar"),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Section::Core => "core",
+            Section::Layouts => "layouts",
+            Section::Mt5 => "mt5",
+            Section::Sidebar => "sidebar",
+        }
+    }
+
+    fn file_name(self, stem: &str) -> String {
+        format!("{stem}.{}.json", self.name())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    /// File names, relative to the manifest
+    sections: BTreeMap<Section, String>,
+}
+
+impl Manifest {
+    fn new(stem: &str) -> Self {
+        Self {
+            version: MANIFEST_VERSION,
+            sections: Section::ALL
+                .into_iter()
+                .map(|section| (section, section.file_name(stem)))
+                .collect(),
+        }
+    }
+}
+
+/// `state` serialized section by section
+pub fn split(state: &State) -> serde_json::Result<Vec<(Section, String)>> {
+    let mut core = match serde_json::to_value(state)? {
+        Value::Object(fields) => fields,
+        _ => return Err(serde::ser::Error::custom("state isn't a JSON object")),
+    };
+
+    let mut sections = vec![];
+    for section in Section::ALL {
+        if let Some(field) = section.field() {
+            let value = core.remove(field).unwrap_or(Value::Null);
+            sections.push((section, serde_json::to_string(&value)?));
+        }
+    }
+    sections.push((Section::Core, serde_json::to_string(&core)?));
+
+    Ok(sections)
+}
+
+/// The state behind the manifest in `contents`, `None` if it isn't one
+pub(crate) fn read_manifest(
+    path: &Path,
+    contents: &str,
+) -> Option<Result<State, serde_json::Error>> {
+    let manifest = serde_json::from_str::<Manifest>(contents).ok()?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut fields = Map::new();
+    let mut files = vec![];
+    for (section, file_name) in manifest.sections {
+        let file = dir.join(&file_name);
+
+        // A missing or broken section falls back to defaults, the others are still loaded
+        let value = match std::fs::read_to_string(&file) {
+            Ok(contents) => match serde_json::from_str::<Value>(&contents) {
+                Ok(value) => value,
+                Err(e) => {
+                    log::warn!("Skipped corrupted state section {}: {e}", file.display());
+                    set_aside(&file);
+                    continue;
+                }
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                log::warn!("Skipped state section {}: {e}", file.display());
+                continue;
+            }
+            Err(e) => {
+                log::warn!("Skipped state section {}: {e}", file.display());
+                set_aside(&file);
+                continue;
+            }
+        };
+
+        match (section.field(), value) {
+            (None, Value::Object(core)) => fields.extend(core),
+            (None, _) => {
+                log::warn!("Skipped state section {}: not an object", file.display());
+                set_aside(&file);
+                continue;
+            }
+            (Some(field), value) => {
+                fields.insert(field.to_string(), value);
+            }
+        }
+        files.push(file);
+    }
+
+    let state = serde_json::from_value(Value::Object(fields));
+    // None of it loads, every section would be saved back as defaults
+    if state.is_err() {
+        files.iter().for_each(|file| set_aside(file));
+    }
+
+    Some(state)
+}
+
+/// Where a section that couldn't be loaded is kept, `<stem>.<section>_old.json`
+fn backup_path(file: &Path) -> PathBuf {
+    let stem = file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    file.with_file_name(format!("{stem}_old.json"))
+}
+
+/// Moves a section that couldn't be loaded to its backup, where it can be restored by hand
+fn set_aside(file: &Path) {
+    let backup = backup_path(file);
+
+    match std::fs::rename(file, &backup) {
+        Ok(()) => log::info!(
+            "Backed up state section {} to {}, it can be restored manually",
+            file.display(),
+            backup.display()
+        ),
+        Err(e) => log::warn!(
+            "Failed to back up state section {} to {}: {e}",
+            file.display(),
+            backup.display()
+        ),
+    }
+}
+
+/// Writes sections to disk, skipping those unchanged since the last write
+#[derive(Debug, Default)]
+pub struct SectionStore {
+    path: Option<PathBuf>,
+    /// Hash of each section as last written, or found on disk
+    written: HashMap<Section, u64>,
+    manifest_written: bool,
+}
+
+impl SectionStore {
+    /// Writes the manifest to `path` and the sections that changed next to it, returning those
+    pub fn save(
+        &mut self,
+        path: &Path,
+        sections: &[(Section, String)],
+    ) -> io::Result<Vec<Section>> {
+        if self.path.as_deref() != Some(path) {
+            *self = Self {
+                path: Some(path.to_path_buf()),
+                ..Self::default()
+            };
+        }
+
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "Invalid state file path")
+            })?;
+        let dir = path.parent().unwrap_or(Path::new(""));
+        if !dir.as_os_str().is_empty() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        let mut rewritten = vec![];
+        for (section, contents) in sections {
+            let hash = hash(contents);
+            if self.written.get(section) == Some(&hash) {
+                continue;
+            }
+
+            let file = dir.join(section.file_name(&stem));
+            // First save of this run, the file may already hold the same, or something that
+            // couldn't be loaded and wasn't moved aside
+            let existing = if self.written.contains_key(section) {
+                None
+            } else {
+                std::fs::read_to_string(&file).ok()
+            };
+            if let Some(existing) = &existing
+                && serde_json::from_str::<Value>(existing).is_err()
+            {
+                let backup = backup_path(&file);
+                if !std::fs::read_to_string(&backup).is_ok_and(|kept| kept == *existing) {
+                    write_atomic(&backup, existing)?;
+                    log::info!("Kept unreadable state section as {}", backup.display());
+                }
+            }
+
+            if existing.as_deref() != Some(contents.as_str()) {
+                write_atomic(&file, contents)?;
+                rewritten.push(*section);
+            }
+            self.written.insert(*section, hash);
+        }
+
+        // Last, so a manifest never names sections that weren't written yet
+        if !self.manifest_written {
+            let manifest = serde_json::to_string_pretty(&Manifest::new(&stem))?;
+            let existing = std::fs::read_to_string(path).ok();
+
+            if existing.as_deref() != Some(manifest.as_str()) {
+                if let Some(existing) = existing
+                    && serde_json::from_str::<Manifest>(&existing).is_err()
+                {
+                    let backup = dir.join(format!("{stem}.single.json"));
+                    write_atomic(&backup, &existing)?;
+                    log::info!(
+                        "Moved the saved state to section files, the single file is kept as {}",
+                        backup.display()
+                    );
+                }
+                write_atomic(path, &manifest)?;
+            }
+            self.manifest_written = true;
+        }
+
+        Ok(rewritten)
+    }
+}
+
+fn hash(contents: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Writes to a temporary file first, so a crash mid-write leaves the previous contents
+fn write_atomic(path: &Path, contents: &str) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
+
+enum Job {
+    Save(PathBuf, Vec<(Section, String)>),
+    Flush(mpsc::Sender<()>),
+}
+
+/// The one thread writing the saved state, autosaves and the save on exit alike. Saves queued
+/// while one is written are coalesced into the latest.
+pub struct StateWriter {
+    jobs: mpsc::Sender<Job>,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl StateWriter {
+    pub fn spawn() -> Self {
+        let (jobs, receiver) = mpsc::channel();
+        let error = Arc::new(Mutex::new(None));
+
+        let thread_error = error.clone();
+        std::thread::Builder::new()
+            .name("state-writer".to_string())
+            .spawn(move || run(&receiver, &thread_error))
+            .expect("Failed to spawn the state writer");
+
+        Self { jobs, error }
+    }
+
+    /// Queues a save and returns right away
+    pub fn save(&self, path: PathBuf, sections: Vec<(Section, String)>) {
+        if self.jobs.send(Job::Save(path, sections)).is_err() {
+            self.set_error("The state writer stopped".to_string());
+        }
+    }
+
+    /// Blocks until every queued save is written, with the error of the last failed one
+    pub fn flush(&self) -> Result<(), String> {
+        let (done, wait) = mpsc::channel();
+        if self.jobs.send(Job::Flush(done)).is_ok() {
+            wait.recv().ok();
+        }
+        self.take_error().map_or(Ok(()), Err)
+    }
+
+    /// The error of a save that failed since the last call
+    pub fn take_error(&self) -> Option<String> {
+        self.error.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    fn set_error(&self, error: String) {
+        *self.error.lock().unwrap_or_else(|e| e.into_inner()) = Some(error);
+    }
+}
+
+fn run(jobs: &mpsc::Receiver<Job>, error: &Mutex<Option<String>>) {
+    let mut store = SectionStore::default();
+
+    while let Ok(job) = jobs.recv() {
+        let mut latest = None;
+        let mut flushes = vec![];
+        for job in std::iter::once(job).chain(jobs.try_iter()) {
+            match job {
+                Job::Save(path, sections) => latest = Some((path, sections)),
+                Job::Flush(done) => flushes.push(done),
+            }
+        }
+
+        if let Some((path, sections)) = latest {
+            match store.save(&path, &sections) {
+                Ok(rewritten) if rewritten.is_empty() => {}
+                Ok(rewritten) => log::info!("Persisted {rewritten:?} to {}", path.display()),
+                Err(e) => {
+                    log::error!("Failed to write state to {}: {e}", path.display());
+                    *error.lock().unwrap_or_else(|e| e.into_inner()) = Some(e.to_string());
+                }
+            }
+        }
+
+        for done in flushes {
+            done.send(()).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sidebar::Position;
+
+    fn scratch_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "flowsurface-{name}-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("saved-state.json")
+    }
+
+    fn serialized(state: &State) -> String {
+        serde_json::to_string(state).unwrap()
+    }
+
+    #[test]
+    fn sidebar_change_rewrites_only_the_sidebar_file() {
+        let path = scratch_file("sections");
+        let mut store = SectionStore::default();
+        let mut state = State::default();
+
+        let rewritten = store.save(&path, &split(&state).unwrap()).unwrap();
+        assert_eq!(rewritten.len(), Section::ALL.len());
+        assert!(
+            store
+                .save(&path, &split(&state).unwrap())
+                .unwrap()
+                .is_empty()
+        );
+
+        state.sidebar.position = match state.sidebar.position {
+            Position::Left => Position::Right,
+            Position::Right => Position::Left,
+        };
+        state.sidebar.connection_bar = true;
+        let rewritten = store.save(&path, &split(&state).unwrap()).unwrap();
+        assert_eq!(rewritten, [Section::Sidebar]);
+
+        let loaded = crate::read_from_file(&path).unwrap();
+        assert_eq!(serialized(&loaded), serialized(&state));
+
+        // A new run finds the same sections on disk and leaves them be
+        let mut restarted = SectionStore::default();
+        assert!(
+            restarted
+                .save(&path, &split(&state).unwrap())
+                .unwrap()
+                .is_empty()
+        );
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn single_file_state_migrates_on_first_save() {
+        let path = scratch_file("legacy");
+        let mut state = State::default();
+        state.sidebar.connection_bar = true;
+        std::fs::write(&path, serialized(&state)).unwrap();
+
+        let loaded = crate::read_from_file(&path).unwrap();
+        assert_eq!(serialized(&loaded), serialized(&state));
+
+        let writer = StateWriter::spawn();
+        writer.save(path.clone(), split(&loaded).unwrap());
+        writer.flush().unwrap();
+
+        let manifest = std::fs::read_to_string(&path).unwrap();
+        assert!(serde_json::from_str::<Manifest>(&manifest).is_ok());
+        assert_eq!(
+            std::fs::read_to_string(path.with_file_name("saved-state.single.json")).unwrap(),
+            serialized(&state)
+        );
+        assert_eq!(
+            serialized(&crate::read_from_file(&path).unwrap()),
+            serialized(&state)
+        );
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn a_broken_section_is_kept_aside_not_overwritten() {
+        let path = scratch_file("broken");
+        let layouts = path.with_file_name("saved-state.layouts.json");
+        let backup = path.with_file_name("saved-state.layouts_old.json");
+        let mut state = State::default();
+        state.sidebar.connection_bar = true;
+
+        SectionStore::default()
+            .save(&path, &split(&state).unwrap())
+            .unwrap();
+        let saved = std::fs::read_to_string(&layouts).unwrap();
+        let truncated = &saved[..saved.len() / 2];
+        std::fs::write(&layouts, truncated).unwrap();
+
+        // The rest still loads, the layouts fall back to defaults
+        let loaded = crate::read_from_file(&path).unwrap();
+        assert!(loaded.sidebar.connection_bar);
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), truncated);
+
+        let writer = StateWriter::spawn();
+        writer.save(path.clone(), split(&loaded).unwrap());
+        writer.flush().unwrap();
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), truncated);
+        assert!(serde_json::from_str::<Value>(&std::fs::read_to_string(&layouts).unwrap()).is_ok());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+
+    #[test]
+    fn saving_over_an_unreadable_section_backs_it_up_first() {
+        let path = scratch_file("unreadable");
+        let layouts = path.with_file_name("saved-state.layouts.json");
+        std::fs::write(&layouts, "{\"layouts\": [").unwrap();
+
+        let mut store = SectionStore::default();
+        store
+            .save(&path, &split(&State::default()).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(path.with_file_name("saved-state.layouts_old.json")).unwrap(),
+            "{\"layouts\": ["
+        );
+
+        std::fs::remove_dir_all(path.parent().unwrap()).ok();
+    }
+}
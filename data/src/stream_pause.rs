@@ -0,0 +1,198 @@
+//! Stream data held back from a paused pane, applied in order once it resumes.
+//!
+//! The stream itself stays subscribed, only what reaches the pane waits. Depth updates are full
+//! books, so when too many pile up the oldest are dropped and the chart just skips them. Trades
+//! are all kept, those of a dropped update go with the next one, until [`MAX_TRADES`] is reached
+//! and the pane has to resume to not lose any.
+
+use exchange::adapter::StreamKind;
+use exchange::depth::Depth;
+use exchange::{BarStatus, Kline, Trade};
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Depth updates kept, about a minute of a 100ms stream
+pub const MAX_DEPTH_UPDATES: usize = 600;
+pub const MAX_TRADES: usize = 200_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Held {
+    Buffered,
+    /// The trade cap is reached, nothing more can be held without dropping trades
+    Full,
+}
+
+/// A depth update, the trades of `trades` up to `trades_end` arrived with it or with dropped
+/// updates before it
+#[derive(Debug, Clone)]
+struct DepthUpdate {
+    stream: StreamKind,
+    time: u64,
+    depth: Arc<Depth>,
+    trades_end: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct PausedStream {
+    paused_at: Instant,
+    updates: VecDeque<DepthUpdate>,
+    trades: Vec<Trade>,
+    klines: Vec<(StreamKind, Kline, BarStatus)>,
+    dropped_updates: usize,
+}
+
+impl PausedStream {
+    pub fn new(paused_at: Instant) -> Self {
+        Self {
+            paused_at,
+            updates: VecDeque::new(),
+            trades: vec![],
+            klines: vec![],
+            dropped_updates: 0,
+        }
+    }
+
+    pub fn push_depth(
+        &mut self,
+        stream: StreamKind,
+        time: u64,
+        depth: Arc<Depth>,
+        trades: &[Trade],
+    ) -> Held {
+        self.trades.extend_from_slice(trades);
+        self.updates.push_back(DepthUpdate {
+            stream,
+            time,
+            depth,
+            trades_end: self.trades.len(),
+        });
+
+        if self.updates.len() > MAX_DEPTH_UPDATES {
+            self.updates.pop_front();
+            self.dropped_updates += 1;
+        }
+
+        if self.trades.len() >= MAX_TRADES {
+            Held::Full
+        } else {
+            Held::Buffered
+        }
+    }
+
+    /// Klines are updates of a bar, only the last one of each bar is kept
+    pub fn push_kline(&mut self, stream: StreamKind, kline: Kline, status: BarStatus) {
+        match self.klines.last_mut() {
+            Some((last_stream, last, last_status))
+                if *last_stream == stream && last.time == kline.time =>
+            {
+                *last = kline;
+                *last_status = status;
+            }
+            _ => self.klines.push((stream, kline, status)),
+        }
+    }
+
+    /// How long the pane has been paused
+    pub fn held_for(&self, now: Instant) -> Duration {
+        now.saturating_duration_since(self.paused_at)
+    }
+
+    pub fn trade_count(&self) -> usize {
+        self.trades.len()
+    }
+
+    pub fn dropped_updates(&self) -> usize {
+        self.dropped_updates
+    }
+
+    /// Depth updates oldest first, each with the trades to apply along with it
+    pub fn depth_updates(&self) -> impl Iterator<Item = (&StreamKind, u64, &Depth, &[Trade])> {
+        let mut start = 0;
+        self.updates.iter().map(move |update| {
+            let trades = &self.trades[start..update.trades_end];
+            start = update.trades_end;
+            (&update.stream, update.time, update.depth.as_ref(), trades)
+        })
+    }
+
+    pub fn klines(&self) -> &[(StreamKind, Kline, BarStatus)] {
+        &self.klines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::adapter::{Exchange, StreamTicksize};
+    use exchange::util::Price;
+    use exchange::{Ticker, TickerInfo};
+
+    fn stream() -> StreamKind {
+        let ticker_info = TickerInfo::new(
+            Ticker::new("BTCUSDT", Exchange::BinanceLinear),
+            0.1,
+            0.001,
+            None,
+        );
+        StreamKind::DepthAndTrades {
+            ticker_info,
+            depth_aggr: StreamTicksize::Client,
+            push_freq: exchange::PushFrequency::ServerDefault,
+        }
+    }
+
+    fn trade(time: u64) -> Trade {
+        Trade {
+            time,
+            is_sell: false,
+            price: Price::from_f32(100.0),
+            qty: 1.0,
+        }
+    }
+
+    #[test]
+    fn dropped_updates_hand_their_trades_to_the_next() {
+        let mut paused = PausedStream::new(Instant::now());
+        let depth = Arc::new(Depth::default());
+
+        for time in 0..MAX_DEPTH_UPDATES as u64 + 2 {
+            let held = paused.push_depth(stream(), time, depth.clone(), &[trade(time)]);
+            assert_eq!(held, Held::Buffered);
+        }
+        assert_eq!(paused.dropped_updates(), 2);
+
+        let updates: Vec<(u64, Vec<u64>)> = paused
+            .depth_updates()
+            .map(|(_, time, _, trades)| (time, trades.iter().map(|t| t.time).collect()))
+            .collect();
+
+        assert_eq!(updates.len(), MAX_DEPTH_UPDATES);
+        assert_eq!(updates[0], (2, vec![0, 1, 2]));
+        assert_eq!(updates[1], (3, vec![3]));
+        assert_eq!(
+            updates
+                .iter()
+                .map(|(_, trades)| trades.len())
+                .sum::<usize>(),
+            paused.trade_count()
+        );
+    }
+
+    #[test]
+    fn full_once_the_trade_cap_is_reached() {
+        let mut paused = PausedStream::new(Instant::now());
+        let depth = Arc::new(Depth::default());
+
+        let batch = vec![trade(0); MAX_TRADES - 1];
+        assert_eq!(
+            paused.push_depth(stream(), 0, depth.clone(), &batch),
+            Held::Buffered
+        );
+        assert_eq!(
+            paused.push_depth(stream(), 1, depth, &[trade(1)]),
+            Held::Full
+        );
+    }
+}
@@ -0,0 +1,190 @@
+//! Which panes get their streams when the layout asks for more than [`exchange::limits`] allow.
+//!
+//! A pane gets all of its streams or none, streams it shares with a pane already holding a slot
+//! cost nothing more. The focused pane goes first, then visible ones, then the ones hidden behind
+//! a maximized pane. Among the same priority panes holding a slot keep it, and the rest get theirs
+//! in the order they started waiting. So a hidden pane gives its slot up when a visible one needs
+//! it, and slots freed by a closed pane go to the next one waiting.
+
+use exchange::SourceId;
+use exchange::adapter::{Exchange, StreamKind};
+use exchange::limits::{Cap, Limits};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Hidden,
+    Visible,
+    Focused,
+}
+
+/// Where a pane stands, with the order it got there in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Slot {
+    Held(u64),
+    Waiting(u64),
+}
+
+impl Slot {
+    pub fn is_held(self) -> bool {
+        matches!(self, Slot::Held(_))
+    }
+
+    fn order(self) -> (bool, u64) {
+        match self {
+            Slot::Held(since) => (false, since),
+            Slot::Waiting(since) => (true, since),
+        }
+    }
+}
+
+/// The limit a stream counts against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pool {
+    /// An MT5 connection, the active one or one a pane is pinned to
+    Mt5(Option<SourceId>),
+    Exchanges,
+}
+
+impl Pool {
+    pub fn of(stream: &StreamKind) -> Self {
+        let ticker_info = stream.ticker_info();
+        match ticker_info.exchange() {
+            Exchange::MetaTrader5 => Pool::Mt5(ticker_info.source),
+            _ => Pool::Exchanges,
+        }
+    }
+
+    fn cap(self, limits: &Limits) -> Cap {
+        match self {
+            Pool::Mt5(_) => limits.mt5_streams,
+            Pool::Exchanges => limits.exchange_streams,
+        }
+    }
+}
+
+pub struct Claim {
+    pub priority: Priority,
+    pub slot: Slot,
+    pub streams: Vec<StreamKind>,
+}
+
+/// Whether each claim gets its streams
+pub fn allocate(claims: &[Claim], limits: &Limits) -> Vec<bool> {
+    let mut order = (0..claims.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&index| {
+        let claim = &claims[index];
+        (std::cmp::Reverse(claim.priority), claim.slot.order())
+    });
+
+    let mut granted = vec![false; claims.len()];
+    let mut streaming: Vec<StreamKind> = vec![];
+
+    for index in order {
+        let mut added: Vec<StreamKind> = vec![];
+        for stream in &claims[index].streams {
+            if !streaming.contains(stream) && !added.contains(stream) {
+                added.push(*stream);
+            }
+        }
+
+        let fits = added.iter().all(|stream| {
+            let pool = Pool::of(stream);
+            let count = |streams: &[StreamKind]| {
+                streams
+                    .iter()
+                    .filter(|stream| Pool::of(stream) == pool)
+                    .count()
+            };
+            pool.cap(limits).admits(count(&streaming) + count(&added))
+        });
+
+        if fits {
+            streaming.extend(added);
+            granted[index] = true;
+        }
+    }
+
+    granted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::{TickerInfo, Timeframe};
+
+    fn stream(symbol: &str, exchange: Exchange) -> StreamKind {
+        StreamKind::Kline {
+            ticker_info: TickerInfo::new(exchange::Ticker::new(symbol, exchange), 0.01, 1.0, None),
+            timeframe: Timeframe::M1,
+        }
+    }
+
+    fn claim(priority: Priority, slot: Slot, symbols: &[&str]) -> Claim {
+        Claim {
+            priority,
+            slot,
+            streams: symbols
+                .iter()
+                .map(|symbol| stream(symbol, Exchange::MetaTrader5))
+                .collect(),
+        }
+    }
+
+    fn limits(mt5_streams: usize) -> Limits {
+        Limits {
+            mt5_streams: Cap::Max(mt5_streams),
+            ..Limits::default()
+        }
+    }
+
+    #[test]
+    fn panes_past_the_limit_wait_in_order() {
+        let claims = [
+            claim(Priority::Visible, Slot::Held(0), &["EURUSD", "GBPUSD"]),
+            claim(Priority::Visible, Slot::Waiting(2), &["USDJPY"]),
+            claim(Priority::Visible, Slot::Waiting(1), &["XAUUSD"]),
+            // Shares the held pane's stream
+            claim(Priority::Visible, Slot::Waiting(3), &["EURUSD"]),
+        ];
+
+        assert_eq!(allocate(&claims, &limits(3)), [true, false, true, true]);
+        assert_eq!(allocate(&claims, &limits(4)), [true, true, true, true]);
+    }
+
+    #[test]
+    fn a_visible_pane_takes_the_slot_of_a_hidden_one() {
+        let claims = [
+            claim(Priority::Hidden, Slot::Held(0), &["EURUSD"]),
+            claim(Priority::Visible, Slot::Waiting(1), &["GBPUSD"]),
+            claim(Priority::Focused, Slot::Waiting(2), &["USDJPY"]),
+        ];
+
+        assert_eq!(allocate(&claims, &limits(2)), [false, true, true]);
+    }
+
+    #[test]
+    fn a_closed_pane_frees_its_slots() {
+        let mut claims = vec![
+            claim(Priority::Visible, Slot::Held(0), &["EURUSD", "GBPUSD"]),
+            claim(Priority::Visible, Slot::Waiting(1), &["USDJPY", "XAUUSD"]),
+        ];
+        assert_eq!(allocate(&claims, &limits(2)), [true, false]);
+
+        claims.remove(0);
+        assert_eq!(allocate(&claims, &limits(2)), [true]);
+    }
+
+    #[test]
+    fn each_pool_has_its_own_limit() {
+        let claims = [
+            claim(Priority::Visible, Slot::Held(0), &["EURUSD"]),
+            Claim {
+                priority: Priority::Visible,
+                slot: Slot::Waiting(1),
+                streams: vec![stream("BTCUSDT", Exchange::BinanceLinear)],
+            },
+        ];
+
+        assert_eq!(allocate(&claims, &limits(1)), [true, true]);
+    }
+}
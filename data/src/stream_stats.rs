@@ -0,0 +1,210 @@
+//! Message rates and health of the market streams in use, for the connection bar.
+//!
+//! An event only bumps its stream's counter. The counters are rolled into a sample once per
+//! [`SAMPLE_EVERY`] and the bar only ever reads the samples, so it costs next to nothing however
+//! busy the streams are.
+
+use exchange::adapter::{Event, Exchange, StreamKind};
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+pub const SAMPLE_EVERY: Duration = Duration::from_secs(1);
+/// Samples kept per stream, a minute of history
+pub const HISTORY: usize = 60;
+/// A connected stream without a message for this long shows as idle
+pub const IDLE_AFTER: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    Live,
+    /// Connected, but nothing arrived lately, e.g. a closed market
+    Idle,
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Rate {
+    pending: u32,
+    /// Messages per second, oldest first
+    samples: VecDeque<f32>,
+    last_message: Option<Instant>,
+}
+
+impl Rate {
+    pub fn samples(&self) -> impl ExactSizeIterator<Item = f32> + '_ {
+        self.samples.iter().copied()
+    }
+
+    /// Messages per second over the last sample
+    pub fn per_sec(&self) -> f32 {
+        self.samples.back().copied().unwrap_or(0.0)
+    }
+
+    pub fn peak(&self) -> f32 {
+        self.samples.iter().copied().fold(0.0, f32::max)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct StreamStats {
+    streams: HashMap<StreamKind, Rate>,
+    disconnected: HashSet<Exchange>,
+    last_sample: Option<Instant>,
+}
+
+impl StreamStats {
+    pub fn record(&mut self, event: &Event, now: Instant) {
+        let stream = match event {
+            Event::Connected(exchange) => {
+                self.disconnected.remove(exchange);
+                return;
+            }
+            Event::Disconnected(exchange, _) => {
+                self.disconnected.insert(*exchange);
+                return;
+            }
+            Event::DepthReceived(stream, ..)
+            | Event::KlineReceived(stream, ..)
+            | Event::MarketStateChanged(stream, _) => stream,
+        };
+
+        // Streams of a layout no longer active aren't tracked
+        if let Some(rate) = self.streams.get_mut(stream) {
+            rate.pending += 1;
+            rate.last_message = Some(now);
+        }
+    }
+
+    /// Rolls the counters into a sample once [`SAMPLE_EVERY`] has passed, tracking exactly the
+    /// `active` streams from then on. Returns whether it did.
+    pub fn sample<'a>(
+        &mut self,
+        now: Instant,
+        active: impl Iterator<Item = &'a StreamKind>,
+    ) -> bool {
+        let elapsed = match self.last_sample {
+            Some(last) => now.saturating_duration_since(last),
+            None => SAMPLE_EVERY,
+        };
+        if elapsed < SAMPLE_EVERY {
+            return false;
+        }
+        self.last_sample = Some(now);
+
+        let active: HashSet<&StreamKind> = active.collect();
+        self.streams.retain(|stream, _| active.contains(stream));
+
+        for stream in active {
+            let rate = self.streams.entry(*stream).or_default();
+
+            rate.samples
+                .push_back(rate.pending as f32 / elapsed.as_secs_f32());
+            rate.pending = 0;
+            if rate.samples.len() > HISTORY {
+                rate.samples.pop_front();
+            }
+        }
+
+        true
+    }
+
+    pub fn rate(&self, stream: &StreamKind) -> Option<&Rate> {
+        self.streams.get(stream)
+    }
+
+    pub fn health(&self, stream: &StreamKind, now: Instant) -> Health {
+        if self.disconnected.contains(&stream.ticker_info().exchange()) {
+            return Health::Disconnected;
+        }
+
+        let last_message = self.rate(stream).and_then(|rate| rate.last_message);
+        match last_message {
+            Some(last) if now.saturating_duration_since(last) < IDLE_AFTER => Health::Live,
+            _ => Health::Idle,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::adapter::StreamTicksize;
+    use exchange::depth::Depth;
+    use exchange::{Ticker, TickerInfo, Timeframe};
+    use std::sync::Arc;
+
+    fn ticker_info() -> TickerInfo {
+        TickerInfo::new(
+            Ticker::new("BTCUSDT", Exchange::BinanceLinear),
+            0.1,
+            0.001,
+            None,
+        )
+    }
+
+    fn depth_stream() -> StreamKind {
+        StreamKind::DepthAndTrades {
+            ticker_info: ticker_info(),
+            depth_aggr: StreamTicksize::Client,
+            push_freq: exchange::PushFrequency::ServerDefault,
+        }
+    }
+
+    fn depth_event() -> Event {
+        Event::DepthReceived(depth_stream(), 0, Arc::new(Depth::default()), Box::new([]))
+    }
+
+    #[test]
+    fn rates_are_sampled_not_counted_per_message() {
+        let mut stats = StreamStats::default();
+        let start = Instant::now();
+        let streams = [depth_stream()];
+
+        assert!(stats.sample(start, streams.iter()));
+        for _ in 0..30 {
+            stats.record(&depth_event(), start);
+        }
+        // Nothing shows until the next sample
+        assert_eq!(stats.rate(&depth_stream()).unwrap().per_sec(), 0.0);
+        assert!(!stats.sample(start + Duration::from_millis(500), streams.iter()));
+
+        assert!(stats.sample(start + Duration::from_secs(2), streams.iter()));
+        let rate = stats.rate(&depth_stream()).unwrap();
+        assert_eq!(rate.per_sec(), 15.0);
+        assert_eq!(rate.samples().len(), 2);
+
+        // Streams no panes use anymore are dropped
+        assert!(stats.sample(start + Duration::from_secs(3), [].into_iter()));
+        assert!(stats.rate(&depth_stream()).is_none());
+    }
+
+    #[test]
+    fn health_follows_messages_and_connection_events() {
+        let mut stats = StreamStats::default();
+        let start = Instant::now();
+        let kline = StreamKind::Kline {
+            ticker_info: ticker_info(),
+            timeframe: Timeframe::M1,
+        };
+        stats.sample(start, [depth_stream(), kline].iter());
+
+        stats.record(&depth_event(), start);
+        assert_eq!(stats.health(&depth_stream(), start), Health::Live);
+        assert_eq!(stats.health(&kline, start), Health::Idle);
+        assert_eq!(
+            stats.health(&depth_stream(), start + IDLE_AFTER),
+            Health::Idle
+        );
+
+        stats.record(
+            &Event::Disconnected(Exchange::BinanceLinear, "timeout".into()),
+            start,
+        );
+        assert_eq!(stats.health(&depth_stream(), start), Health::Disconnected);
+        assert_eq!(stats.health(&kline, start), Health::Disconnected);
+
+        stats.record(&Event::Connected(Exchange::BinanceLinear), start);
+        assert_eq!(stats.health(&depth_stream(), start), Health::Live);
+    }
+}
@@ -0,0 +1,370 @@
+//! On-disk cache of MT5 symbol specifications, keyed by connection name.
+//!
+//! Symbol specs rarely change, so a fetched list is served from here until its TTL expires.
+//! Expired entries are still returned (flagged as stale) so callers can show them immediately
+//! while a refresh runs in the background.
+//!
+//! Symbols a connection stops listing are retired rather than dropped: they keep their specs for
+//! the panes and history using them, and are restored if the broker lists them again.
+
+use exchange::{Ticker, TickerInfo, adapter::metatrader5};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, hash_map};
+use std::time::Duration;
+
+pub const SYMBOL_CACHE_PATH: &str = "mt5-symbols.json";
+
+pub const DEFAULT_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+fn default_ttl_secs() -> u64 {
+    DEFAULT_TTL.as_secs()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedSymbols {
+    /// Unix timestamp in milliseconds of the fetch that produced this entry
+    pub fetched_at: u64,
+    pub tickers: HashMap<Ticker, Option<TickerInfo>>,
+    /// Tickers the connection no longer lists, still held in `tickers`
+    #[serde(default)]
+    pub retired: HashSet<Ticker>,
+}
+
+/// Tickers a fetch retired and restored
+#[derive(Debug, Default, PartialEq)]
+pub struct Revalidation {
+    pub retired: Vec<Ticker>,
+    pub restored: Vec<Ticker>,
+}
+
+/// Result of a cache lookup
+pub struct Lookup<'a> {
+    pub tickers: &'a HashMap<Ticker, Option<TickerInfo>>,
+    pub retired: &'a HashSet<Ticker>,
+    /// `false` once the entry is older than the TTL and should be revalidated
+    pub is_fresh: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SymbolCache {
+    #[serde(default = "default_ttl_secs")]
+    ttl_secs: u64,
+    #[serde(default)]
+    entries: HashMap<String, CachedSymbols>,
+}
+
+impl Default for SymbolCache {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_ttl_secs(),
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl SymbolCache {
+    pub fn ttl(&self) -> Duration {
+        Duration::from_secs(self.ttl_secs)
+    }
+
+    pub fn get(&self, connection: &str, now_ms: u64) -> Option<Lookup<'_>> {
+        self.entries.get(connection).map(|entry| {
+            let age = Duration::from_millis(now_ms.saturating_sub(entry.fetched_at));
+
+            Lookup {
+                tickers: &entry.tickers,
+                retired: &entry.retired,
+                is_fresh: age < self.ttl(),
+            }
+        })
+    }
+
+    /// Every cached connection with its symbols, stale or not
+    pub fn connections(
+        &self,
+    ) -> impl Iterator<Item = (&str, &HashMap<Ticker, Option<TickerInfo>>)> {
+        self.entries
+            .iter()
+            .map(|(name, entry)| (name.as_str(), &entry.tickers))
+    }
+
+    pub fn insert(
+        &mut self,
+        connection: &str,
+        tickers: HashMap<Ticker, Option<TickerInfo>>,
+        now_ms: u64,
+    ) {
+        self.entries.insert(
+            connection.to_string(),
+            CachedSymbols {
+                fetched_at: now_ms,
+                tickers,
+                retired: HashSet::new(),
+            },
+        );
+    }
+
+    /// Stores the symbols `connection` lists now, retiring the ones it listed before but no
+    /// longer does and restoring retired ones listed again
+    pub fn revalidate(
+        &mut self,
+        connection: &str,
+        listed: HashMap<Ticker, Option<TickerInfo>>,
+        now_ms: u64,
+    ) -> Revalidation {
+        let Some(entry) = self.entries.get_mut(connection) else {
+            self.insert(connection, listed, now_ms);
+            return Revalidation::default();
+        };
+
+        let mut changes = Revalidation::default();
+        let mut tickers = listed;
+
+        for (ticker, info) in entry.tickers.drain() {
+            match tickers.entry(ticker) {
+                hash_map::Entry::Occupied(_) => {
+                    if entry.retired.remove(&ticker) {
+                        changes.restored.push(ticker);
+                    }
+                }
+                hash_map::Entry::Vacant(vacant) => {
+                    if entry.retired.insert(ticker) {
+                        changes.retired.push(ticker);
+                    }
+                    vacant.insert(info);
+                }
+            }
+        }
+
+        changes.retired.sort_by_cached_key(Ticker::to_string);
+        changes.restored.sort_by_cached_key(Ticker::to_string);
+        entry.tickers = tickers;
+        entry.fetched_at = now_ms;
+        changes
+    }
+
+    /// Retired tickers of every connection
+    pub fn retired(&self) -> impl Iterator<Item = Ticker> + '_ {
+        self.entries
+            .values()
+            .flat_map(|entry| entry.retired.iter().copied())
+    }
+
+    /// Buckets `ticker` by `tick_size` in every entry listing it, keeping when they were fetched
+    pub fn set_tick_size(&mut self, ticker: &Ticker, tick_size: f32) {
+        for entry in self.entries.values_mut() {
+            if let Some(Some(info)) = entry.tickers.get_mut(ticker) {
+                *info = info.with_min_ticksize(tick_size);
+            }
+        }
+    }
+
+    /// Drops the entry for `connection`, returns whether there was one
+    pub fn invalidate(&mut self, connection: &str) -> bool {
+        self.entries.remove(connection).is_some()
+    }
+
+    /// Expires the entry for `connection` if a stream ended because a cached symbol is gone.
+    /// The entry is kept so the refetch can tell which symbols went away.
+    pub fn invalidate_on_disconnect(&mut self, connection: &str, reason: &str) -> bool {
+        if !metatrader5::is_symbol_not_found(reason) {
+            return false;
+        }
+
+        self.entries.get_mut(connection).is_some_and(|entry| {
+            entry.fetched_at = 0;
+            true
+        })
+    }
+
+    pub fn load() -> Self {
+        let path = crate::data_path(Some(SYMBOL_CACHE_PATH));
+
+        let mut cache = match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_else(|e| {
+                log::warn!("Discarding unreadable symbol cache {}: {e}", path.display());
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        cache.tag_connections();
+        cache
+    }
+
+    /// Tags the tickers of every entry with the connection that listed them, entries written
+    /// before tickers told their connections apart carry none
+    fn tag_connections(&mut self) {
+        for (name, entry) in &mut self.entries {
+            let source = metatrader5::name_connection(name);
+            if entry
+                .tickers
+                .keys()
+                .all(|ticker| ticker.connection().is_some())
+            {
+                continue;
+            }
+
+            entry.tickers = std::mem::take(&mut entry.tickers)
+                .into_iter()
+                .map(|(ticker, info)| {
+                    let ticker = ticker.on_connection(ticker.connection().or(Some(source)));
+                    let info = info.map(|info| TickerInfo { ticker, ..info });
+                    (ticker, info)
+                })
+                .collect();
+            entry.retired = std::mem::take(&mut entry.retired)
+                .into_iter()
+                .map(|ticker| ticker.on_connection(ticker.connection().or(Some(source))))
+                .collect();
+        }
+    }
+
+    pub fn save(&self) {
+        match serde_json::to_string(self) {
+            Ok(json) => {
+                if let Err(e) = crate::write_json_to_file(&json, SYMBOL_CACHE_PATH) {
+                    log::error!("Failed to write symbol cache: {e}");
+                }
+            }
+            Err(e) => log::error!("Failed to serialize symbol cache: {e}"),
+        }
+    }
+}
+
+pub fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis().max(0) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::adapter::Exchange;
+
+    fn sample() -> HashMap<Ticker, Option<TickerInfo>> {
+        let ticker = Ticker::new("EURUSD", Exchange::MetaTrader5);
+        let info = TickerInfo::new(ticker, 0.00001, 0.01, Some(100_000.0));
+        HashMap::from([(ticker, Some(info))])
+    }
+
+    #[test]
+    fn entry_goes_stale_after_ttl() {
+        let mut cache = SymbolCache::default();
+        let fetched_at = 1_000;
+        cache.insert("MT5 localhost:9876", sample(), fetched_at);
+
+        let ttl_ms = DEFAULT_TTL.as_millis() as u64;
+
+        let lookup = cache
+            .get("MT5 localhost:9876", fetched_at + ttl_ms - 1)
+            .expect("entry present");
+        assert!(lookup.is_fresh);
+        assert_eq!(lookup.tickers.len(), 1);
+
+        // Stale entries are still served so the UI can show them while revalidating
+        let lookup = cache
+            .get("MT5 localhost:9876", fetched_at + ttl_ms)
+            .expect("stale entry still served");
+        assert!(!lookup.is_fresh);
+        assert_eq!(lookup.tickers.len(), 1);
+    }
+
+    #[test]
+    fn invalidate_drops_only_that_connection() {
+        let mut cache = SymbolCache::default();
+        cache.insert("a", sample(), 0);
+        cache.insert("b", sample(), 0);
+
+        assert!(cache.invalidate("a"));
+        assert!(!cache.invalidate("a"));
+        assert!(cache.get("a", 0).is_none());
+        assert!(cache.get("b", 0).is_some());
+    }
+
+    #[test]
+    fn symbol_not_found_disconnect_invalidates() {
+        let now = now_ms();
+        let mut cache = SymbolCache::default();
+        cache.insert("a", sample(), now);
+
+        assert!(!cache.invalidate_on_disconnect("a", "Stream: Connection reset"));
+        assert!(cache.get("a", now).unwrap().is_fresh);
+
+        let reason = exchange::adapter::AdapterError::InvalidRequest(format!(
+            "{}: EURUSD",
+            metatrader5::SYMBOL_NOT_FOUND
+        ))
+        .to_string();
+        assert!(cache.invalidate_on_disconnect("a", &reason));
+        assert!(!cache.get("a", now).unwrap().is_fresh);
+    }
+
+    #[test]
+    fn delisted_symbols_retire_and_come_back() {
+        let eurusd = Ticker::new("EURUSD", Exchange::MetaTrader5);
+        let delisted = Ticker::new("GER30", Exchange::MetaTrader5);
+        let listing = |tickers: &[Ticker]| -> HashMap<Ticker, Option<TickerInfo>> {
+            tickers
+                .iter()
+                .map(|ticker| (*ticker, Some(TickerInfo::new(*ticker, 0.01, 1.0, None))))
+                .collect()
+        };
+
+        let mut cache = SymbolCache::default();
+        assert_eq!(
+            cache.revalidate("a", listing(&[eurusd, delisted]), 0),
+            Revalidation::default()
+        );
+
+        // Gone from the broker, kept with its specs but flagged
+        let changes = cache.revalidate("a", listing(&[eurusd]), 10);
+        assert_eq!(changes.retired, [delisted]);
+        let lookup = cache.get("a", 10).unwrap();
+        assert!(lookup.tickers[&delisted].is_some());
+        assert!(lookup.retired.contains(&delisted));
+        assert_eq!(cache.retired().collect::<Vec<_>>(), [delisted]);
+
+        // Still missing, nothing changes
+        assert_eq!(
+            cache.revalidate("a", listing(&[eurusd]), 20),
+            Revalidation::default()
+        );
+
+        // The retirement survives a restart
+        let json = serde_json::to_string(&cache).unwrap();
+        let mut cache: SymbolCache = serde_json::from_str(&json).unwrap();
+        assert!(cache.get("a", 20).unwrap().retired.contains(&delisted));
+
+        // Listed again, it is restored
+        let changes = cache.revalidate("a", listing(&[eurusd, delisted]), 30);
+        assert_eq!(changes.restored, [delisted]);
+        assert!(cache.get("a", 30).unwrap().retired.is_empty());
+        assert_eq!(cache.retired().count(), 0);
+    }
+
+    #[test]
+    fn set_tick_size_keeps_the_fetch_time() {
+        let mut cache = SymbolCache::default();
+        cache.insert("a", sample(), 42);
+
+        let ticker = Ticker::new("EURUSD", Exchange::MetaTrader5);
+        cache.set_tick_size(&ticker, 0.0001);
+
+        let lookup = cache.get("a", 42).unwrap();
+        assert!(lookup.is_fresh);
+        let info = lookup.tickers[&ticker].unwrap();
+        assert!((info.min_ticksize.as_f32() - 0.0001).abs() < 1e-9);
+    }
+
+    #[test]
+    fn roundtrips_through_json() {
+        let mut cache = SymbolCache::default();
+        cache.insert("a", sample(), 42);
+
+        let json = serde_json::to_string(&cache).unwrap();
+        let restored: SymbolCache = serde_json::from_str(&json).unwrap();
+
+        let lookup = restored.get("a", 42).unwrap();
+        assert!(lookup.is_fresh);
+        assert_eq!(lookup.tickers, &sample());
+    }
+}
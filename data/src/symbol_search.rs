@@ -0,0 +1,223 @@
+//! Fuzzy search over the tickers of every source, crypto venues and each MT5 connection alike.
+//!
+//! Entries are grouped by source, so a reloaded symbol list only replaces its own group. Building
+//! a group's entries is the costly part and is meant to run off the UI thread.
+
+use exchange::{Ticker, adapter::Exchange};
+use std::collections::HashMap;
+
+/// Where a ticker is listed
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Source {
+    Exchange(Exchange),
+    /// Symbols cached for a saved MT5 connection, by connection name
+    Mt5Connection(String),
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Exchange(exchange) => write!(f, "{exchange}"),
+            Source::Mt5Connection(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub ticker: Ticker,
+    pub source: Source,
+    pub display: String,
+    /// Lowercase symbol without separators, what queries are matched against
+    key: String,
+}
+
+/// Index entries for `tickers` of one source
+pub fn entries(source: &Source, tickers: impl IntoIterator<Item = Ticker>) -> Vec<Entry> {
+    tickers
+        .into_iter()
+        .map(|ticker| {
+            let (display, _) = ticker.display_symbol_and_type();
+
+            Entry {
+                ticker,
+                source: source.clone(),
+                key: normalize(&display),
+                display,
+            }
+        })
+        .collect()
+}
+
+fn normalize(symbol: &str) -> String {
+    symbol
+        .chars()
+        .filter(char::is_ascii_alphanumeric)
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// How well `key` matches `query`, higher is better, `None` if it doesn't match at all
+fn match_score(key: &str, query: &str) -> Option<i32> {
+    if key == query {
+        return Some(1_000);
+    }
+    if key.starts_with(query) {
+        return Some(800 - (key.len() - query.len()) as i32);
+    }
+    if let Some(position) = key.find(query) {
+        return Some(600 - position as i32 * 4 - (key.len() - query.len()) as i32);
+    }
+
+    // Every query char in order, each gap between matched chars costs a little
+    let mut gaps = 0;
+    let mut last = None;
+    let mut chars = key.char_indices();
+    for q in query.chars() {
+        let (index, _) = chars.by_ref().find(|(_, c)| *c == q)?;
+        if last.is_some_and(|last| index != last + 1) {
+            gaps += 1;
+        }
+        last = Some(index);
+    }
+    Some(300 - gaps * 20 - key.len() as i32)
+}
+
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    groups: HashMap<Source, Vec<Entry>>,
+}
+
+impl SearchIndex {
+    /// Swaps in the freshly built entries of one source, leaving the others untouched
+    pub fn replace(&mut self, source: Source, entries: Vec<Entry>) {
+        if entries.is_empty() {
+            self.groups.remove(&source);
+        } else {
+            self.groups.insert(source, entries);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.groups.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Best `limit` matches for `query`, favorites and recently opened tickers first among
+    /// similar matches. A ticker listed by several sources shows up once.
+    pub fn search(
+        &self,
+        query: &str,
+        is_favorite: impl Fn(&Ticker) -> bool,
+        recents: &[Ticker],
+        limit: usize,
+    ) -> Vec<&Entry> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let mut best: HashMap<Ticker, (i32, &Entry)> = HashMap::new();
+
+        for entry in self.groups.values().flatten() {
+            let Some(mut score) = match_score(&entry.key, &query) else {
+                continue;
+            };
+            if is_favorite(&entry.ticker) {
+                score += 250;
+            }
+            if let Some(rank) = recents.iter().position(|t| *t == entry.ticker) {
+                score += 100 - (rank as i32 * 5).min(90);
+            }
+
+            // The live exchange listing wins over a cached connection's copy
+            let is_better = |current: &(i32, &Entry)| {
+                score > current.0
+                    || (score == current.0 && matches!(entry.source, Source::Exchange(_)))
+            };
+            if best.get(&entry.ticker).is_none_or(is_better) {
+                best.insert(entry.ticker, (score, entry));
+            }
+        }
+
+        let mut matches: Vec<(i32, &Entry)> = best.into_values().collect();
+        matches.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.display.cmp(&b.1.display)));
+        matches.truncate(limit);
+
+        matches.into_iter().map(|(_, entry)| entry).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> SearchIndex {
+        let mut index = SearchIndex::default();
+
+        let binance = Source::Exchange(Exchange::BinanceLinear);
+        index.replace(
+            binance.clone(),
+            entries(
+                &binance,
+                ["BTCUSDT", "ETHBTC", "WBTCUSDT"]
+                    .map(|symbol| Ticker::new(symbol, Exchange::BinanceLinear)),
+            ),
+        );
+
+        let mt5 = Source::Mt5Connection("MT5 localhost:9876".to_string());
+        index.replace(
+            mt5.clone(),
+            entries(
+                &mt5,
+                ["EURUSD.a", "EURGBP", "USDJPY"]
+                    .map(|symbol| Ticker::new(symbol, Exchange::MetaTrader5)),
+            ),
+        );
+
+        index
+    }
+
+    fn symbols(results: &[&Entry]) -> Vec<String> {
+        results.iter().map(|e| e.ticker.to_string()).collect()
+    }
+
+    #[test]
+    fn ranks_prefix_over_substring_and_fuzzy() {
+        let index = index();
+        assert_eq!(index.len(), 6);
+
+        let results = index.search("btc", |_| false, &[], 10);
+        assert_eq!(symbols(&results), ["BTCUSDT", "WBTCUSDT", "ETHBTC"]);
+
+        // Separators and case don't matter, a subsequence still matches
+        let results = index.search("EUR/USD", |_| false, &[], 10);
+        assert_eq!(symbols(&results), ["EURUSD.a"]);
+        assert_eq!(results[0].source.to_string(), "MT5 localhost:9876");
+        assert_eq!(
+            symbols(&index.search("eusd", |_| false, &[], 10)),
+            ["EURUSD.a"]
+        );
+    }
+
+    #[test]
+    fn favorites_and_recents_rise_and_groups_refresh_alone() {
+        let mut index = index();
+        let eurgbp = Ticker::new("EURGBP", Exchange::MetaTrader5);
+        let wbtc = Ticker::new("WBTCUSDT", Exchange::BinanceLinear);
+
+        let results = index.search("eur", |_| false, &[eurgbp], 10);
+        assert_eq!(symbols(&results), ["EURGBP", "EURUSD.a"]);
+
+        let results = index.search("btc", |t| *t == wbtc, &[], 10);
+        assert_eq!(symbols(&results)[0], "WBTCUSDT");
+
+        let mt5 = Source::Mt5Connection("MT5 localhost:9876".to_string());
+        index.replace(mt5, vec![]);
+        assert_eq!(index.len(), 3);
+        assert!(index.search("eur", |_| false, &[], 10).is_empty());
+    }
+}
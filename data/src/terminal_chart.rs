@@ -0,0 +1,90 @@
+//! Which symbol an MT5 terminal's chart is pointed at, for connections syncing it with the
+//! focused pane.
+//!
+//! A pane has to stay focused on the same symbol and timeframe for [`SETTLE`] before it's sent,
+//! so flicking through panes or tickers ends in a single request for the last one.
+
+use exchange::{TickerInfo, Timeframe};
+
+use std::time::{Duration, Instant};
+
+/// How long the focused pane has to keep showing a symbol before the terminal follows
+pub const SETTLE: Duration = Duration::from_millis(750);
+
+/// A symbol and, for time based charts, the timeframe shown with it
+pub type Target = (TickerInfo, Option<Timeframe>);
+
+#[derive(Debug, Default)]
+pub struct ChartSync {
+    /// What the focused pane shows, since when
+    wanted: Option<(Target, Instant)>,
+    /// What the terminal was last pointed at
+    sent: Option<Target>,
+}
+
+impl ChartSync {
+    /// Takes what the focused pane shows, `None` when it's nothing to sync. Returns the target
+    /// to send once it's been shown for [`SETTLE`], and only if the terminal isn't showing it.
+    pub fn poll(&mut self, focused: Option<Target>, now: Instant) -> Option<Target> {
+        let Some(target) = focused else {
+            self.wanted = None;
+            return None;
+        };
+
+        let since = match self.wanted {
+            Some((wanted, since)) if wanted == target => since,
+            _ => {
+                self.wanted = Some((target, now));
+                now
+            }
+        };
+
+        if now.duration_since(since) < SETTLE || self.sent == Some(target) {
+            return None;
+        }
+        self.sent = Some(target);
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::Ticker;
+    use exchange::adapter::Exchange;
+
+    fn target(symbol: &str, timeframe: Timeframe) -> Target {
+        let ticker = Ticker::new(symbol, Exchange::MetaTrader5);
+        (
+            TickerInfo::new(ticker, 0.00001, 0.01, Some(100_000.0)),
+            Some(timeframe),
+        )
+    }
+
+    #[test]
+    fn rapid_switching_sends_only_the_symbol_settled_on() {
+        let mut sync = ChartSync::default();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        let (eurusd, gbpusd) = (
+            target("EURUSD", Timeframe::M5),
+            target("GBPUSD", Timeframe::M5),
+        );
+        for (ms, focused) in [(0, eurusd), (200, gbpusd), (400, eurusd), (600, gbpusd)] {
+            assert_eq!(sync.poll(Some(focused), at(ms)), None);
+        }
+        assert_eq!(sync.poll(Some(gbpusd), at(1_000)), None);
+        assert_eq!(sync.poll(Some(gbpusd), at(1_400)), Some(gbpusd));
+
+        // Already shown by the terminal, also after focusing something else in between
+        assert_eq!(sync.poll(Some(gbpusd), at(3_000)), None);
+        assert_eq!(sync.poll(None, at(3_100)), None);
+        assert_eq!(sync.poll(Some(gbpusd), at(5_000)), None);
+
+        // A timeframe change alone is sent as well
+        let gbpusd_h1 = target("GBPUSD", Timeframe::H1);
+        assert_eq!(sync.poll(Some(gbpusd_h1), at(6_000)), None);
+        assert_eq!(sync.poll(Some(gbpusd_h1), at(6_750)), Some(gbpusd_h1));
+    }
+}
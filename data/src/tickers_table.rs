@@ -4,6 +4,9 @@ use exchange::{
 };
 use serde::{Deserialize, Serialize};
 
+/// How many recently opened tickers are remembered for the symbol search
+pub const MAX_RECENT_TICKERS: usize = 20;
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Settings {
     pub favorited_tickers: Vec<Ticker>,
@@ -11,6 +14,15 @@ pub struct Settings {
     pub selected_sort_option: SortOptions,
     pub selected_exchanges: Vec<ExchangeInclusive>,
     pub selected_markets: Vec<MarketKind>,
+    #[serde(default = "default_show_synthetic")]
+    pub show_synthetic: bool,
+    /// Most recently opened first
+    #[serde(default)]
+    pub recent_tickers: Vec<Ticker>,
+}
+
+fn default_show_synthetic() -> bool {
+    true
 }
 
 impl Default for Settings {
@@ -21,6 +33,8 @@ impl Default for Settings {
             selected_sort_option: SortOptions::VolumeDesc,
             selected_exchanges: ExchangeInclusive::ALL.to_vec(),
             selected_markets: MarketKind::ALL.into_iter().collect(),
+            show_synthetic: true,
+            recent_tickers: vec![],
         }
     }
 }
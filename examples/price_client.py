@@ -0,0 +1,66 @@
+#!/usr/bin/env python3
+"""Reads prices from a running Flowsurface through its local price endpoint.
+
+Turn the endpoint on under Settings > Experimental, then copy the token from there:
+
+    python3 examples/price_client.py --token <token> EURUSD XAUUSD
+
+Lists the streamed tickers, prints the price and quote of those given, then prints the updates
+pushed for them until interrupted. Only needs the standard library.
+"""
+
+import argparse
+import json
+import socket
+
+
+def request(stream, message):
+    stream.write(json.dumps(message) + "\n")
+    stream.flush()
+    return read(stream)
+
+
+def read(stream):
+    line = stream.readline()
+    if not line:
+        raise SystemExit("Flowsurface closed the connection")
+    return json.loads(line)
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+    parser.add_argument("tickers", nargs="*", help="symbols like EURUSD or BinanceLinear:BTCUSDT")
+    parser.add_argument("--token", required=True)
+    parser.add_argument("--port", type=int, default=47390)
+    parser.add_argument("--interval", type=int, default=500, help="ms between pushed updates")
+    args = parser.parse_args()
+
+    with socket.create_connection(("127.0.0.1", args.port)) as sock:
+        stream = sock.makefile("rw", encoding="utf-8")
+
+        auth = request(stream, {"type": "auth", "token": args.token})
+        if not auth.get("success"):
+            raise SystemExit(auth.get("error", "Authentication failed"))
+
+        tickers = request(stream, {"type": "list_tickers"})["tickers"]
+        print("Streaming:", ", ".join(tickers) or "nothing yet")
+
+        for ticker in args.tickers:
+            print(request(stream, {"type": "get_price", "ticker": ticker}))
+            print(request(stream, {"type": "get_quote", "ticker": ticker}))
+
+        if not args.tickers:
+            return
+
+        request(stream, {"type": "subscribe", "tickers": args.tickers, "interval_ms": args.interval})
+        try:
+            while True:
+                for quote in read(stream)["quotes"]:
+                    print(f"{quote['ticker']:<28} last {quote['last_price']} "
+                          f"bid {quote['bid']} ask {quote['ask']}")
+        except KeyboardInterrupt:
+            pass
+
+
+if __name__ == "__main__":
+    main()
@@ -1,20 +1,32 @@
 use super::{Ticker, Timeframe};
 use crate::{
-    Kline, OpenInterest, Price, PushFrequency, TickMultiplier, TickerInfo, TickerStats, Trade,
-    depth::Depth,
+    BarStatus, Kline, OpenInterest, Price, PushFrequency, SourceId, TickMultiplier, TickerInfo,
+    TickerStats, Trade,
+    calendar::CalendarEvent,
+    depth::{Depth, DepthPayload},
+    market_state::MarketState,
 };
 
 use enum_map::{Enum, EnumMap};
+use futures_util::{StreamExt as _, future::BoxFuture, stream::BoxStream};
 use rustc_hash::{FxHashMap, FxHashSet};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, LazyLock, RwLock},
+};
 
 pub mod binance;
 pub mod bybit;
+pub mod custom_ws;
+mod emit;
 pub mod hyperliquid;
 pub mod metatrader5;
 pub mod okex;
 
+pub use emit::dropped_depth_count;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ResolvedStream {
     /// Streams that are persisted but needs to be resolved for use
@@ -127,6 +139,9 @@ pub enum AdapterError {
     WebsocketError(String),
     #[error("Invalid request: {0}")]
     InvalidRequest(String),
+    /// The venue or proxy doesn't implement the request, callers may skip it silently
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
 }
 
 impl AdapterError {
@@ -148,6 +163,10 @@ impl AdapterError {
                 log::error!("Adapter websocket error: {err}");
                 "Realtime connection error. Trying to reconnect..."
             }
+            AdapterError::Unsupported(err) => {
+                log::warn!("Adapter unsupported request: {err}");
+                "Not supported by this data source."
+            }
         }
     }
 }
@@ -317,6 +336,15 @@ impl UniqueStreams {
         self.streams(exchange_filter, |_, stream| stream.as_kline_stream())
     }
 
+    /// Every ticker with at least one stream
+    pub fn tickers(&self) -> Vec<TickerInfo> {
+        Exchange::ALL
+            .into_iter()
+            .filter_map(|exchange| self.streams[exchange].as_ref())
+            .flat_map(|ticker_map| ticker_map.keys().copied())
+            .collect()
+    }
+
     pub fn combined_used(&self) -> impl Iterator<Item = (Exchange, &StreamSpecs)> {
         self.specs
             .iter()
@@ -373,6 +401,13 @@ impl From<StreamKind> for PersistStreamKind {
 }
 
 impl PersistStreamKind {
+    pub fn ticker(&self) -> Ticker {
+        match self {
+            PersistStreamKind::Kline(k) => k.ticker,
+            PersistStreamKind::DepthAndTrades(d) => d.ticker,
+        }
+    }
+
     /// Try to convert into runtime StreamKind. `resolver` should return Some(TickerInfo) for a ticker string,
     /// otherwise the conversion fails (so caller can trigger a refresh / fetch).
     pub fn into_stream_kind<F>(self, mut resolver: F) -> Result<StreamKind, String>
@@ -425,15 +460,17 @@ pub enum ExchangeInclusive {
     Hyperliquid,
     Okex,
     MetaTrader5,
+    CustomWs,
 }
 
 impl ExchangeInclusive {
-    pub const ALL: [ExchangeInclusive; 5] = [
+    pub const ALL: [ExchangeInclusive; 6] = [
         ExchangeInclusive::Bybit,
         ExchangeInclusive::Binance,
         ExchangeInclusive::Hyperliquid,
         ExchangeInclusive::Okex,
         ExchangeInclusive::MetaTrader5,
+        ExchangeInclusive::CustomWs,
     ];
 
     pub fn of(ex: Exchange) -> Self {
@@ -445,6 +482,7 @@ impl ExchangeInclusive {
             Exchange::HyperliquidLinear | Exchange::HyperliquidSpot => Self::Hyperliquid,
             Exchange::OkexLinear | Exchange::OkexInverse | Exchange::OkexSpot => Self::Okex,
             Exchange::MetaTrader5 => Self::MetaTrader5,
+            Exchange::CustomWs => Self::CustomWs,
         }
     }
 }
@@ -463,6 +501,7 @@ pub enum Exchange {
     OkexInverse,
     OkexSpot,
     MetaTrader5,
+    CustomWs,
 }
 
 impl std::fmt::Display for Exchange {
@@ -483,6 +522,7 @@ impl std::fmt::Display for Exchange {
                 Exchange::OkexInverse => "Okex Inverse",
                 Exchange::OkexSpot => "Okex Spot",
                 Exchange::MetaTrader5 => "MetaTrader 5",
+                Exchange::CustomWs => "Custom WS",
             }
         )
     }
@@ -505,13 +545,14 @@ impl FromStr for Exchange {
             "Okex Inverse" => Ok(Exchange::OkexInverse),
             "Okex Spot" => Ok(Exchange::OkexSpot),
             "MetaTrader 5" => Ok(Exchange::MetaTrader5),
+            "Custom WS" => Ok(Exchange::CustomWs),
             _ => Err(format!("Invalid exchange: {}", s)),
         }
     }
 }
 
 impl Exchange {
-    pub const ALL: [Exchange; 12] = [
+    pub const ALL: [Exchange; 13] = [
         Exchange::BinanceLinear,
         Exchange::BinanceInverse,
         Exchange::BinanceSpot,
@@ -524,6 +565,7 @@ impl Exchange {
         Exchange::OkexInverse,
         Exchange::OkexSpot,
         Exchange::MetaTrader5,
+        Exchange::CustomWs,
     ];
 
     pub fn market_type(&self) -> MarketKind {
@@ -541,21 +583,21 @@ impl Exchange {
             | Exchange::OkexSpot => MarketKind::Spot,
             // MT5 uses Spot market type as it handles CFDs/Forex
             Exchange::MetaTrader5 => MarketKind::Spot,
+            Exchange::CustomWs => MarketKind::Spot,
         }
     }
 
+    /// Features the registered adapter declares, nothing while none is registered
+    pub fn capabilities(&self) -> Capabilities {
+        adapter(*self).map_or(Capabilities::NONE, |adapter| adapter.capabilities(*self))
+    }
+
     pub fn is_depth_client_aggr(&self) -> bool {
-        !matches!(
-            self,
-            Exchange::HyperliquidLinear | Exchange::HyperliquidSpot
-        )
+        !self.capabilities().server_aggregation
     }
 
     pub fn is_custom_push_freq(&self) -> bool {
-        matches!(
-            self,
-            Exchange::BybitLinear | Exchange::BybitInverse | Exchange::BybitSpot
-        )
+        self.capabilities().custom_push_freq
     }
 
     pub fn allowed_push_freqs(&self) -> &[PushFrequency] {
@@ -609,12 +651,243 @@ impl Exchange {
     }
 }
 
+/// What an adapter can provide, the UI hides or disables controls for anything missing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities {
+    /// Trades can be backfilled for a past range, needed to fill footprints
+    pub historical_trades: bool,
+    /// Depth arrives as incremental diffs on top of a snapshot, rather than full snapshots
+    pub native_depth_diffs: bool,
+    pub open_interest: bool,
+    /// Depth is aggregated by the exchange, custom client side tick sizes aren't possible
+    pub server_aggregation: bool,
+    /// Depth push interval can be picked, see [`Exchange::allowed_push_freqs`]
+    pub custom_push_freq: bool,
+    /// 24h price and volume stats can be polled, otherwise ticker rows start as placeholders
+    pub ticker_stats: bool,
+    /// Depth snapshots of a past range may be fetched to backfill heatmaps, the source can
+    /// still answer that it doesn't record them
+    pub depth_history: bool,
+    /// Trades carry the exchange's aggressor side, rather than one inferred from price changes
+    pub aggressor_side: bool,
+    /// Economic calendar events may be fetched, the source can still answer that it has none
+    pub economic_calendar: bool,
+}
+
+impl Capabilities {
+    pub const NONE: Capabilities = Capabilities {
+        historical_trades: false,
+        native_depth_diffs: false,
+        open_interest: false,
+        server_aggregation: false,
+        custom_push_freq: false,
+        ticker_stats: false,
+        depth_history: false,
+        aggressor_side: false,
+        economic_calendar: false,
+    };
+}
+
+/// How an adapter delivers live klines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KlineFeed {
+    /// One connection carries every subscription of a market
+    Batched,
+    /// One connection per subscription, opened separately so adding a chart doesn't restart
+    /// the others
+    PerSubscription,
+    /// Klines arrive through the market stream, there is nothing to subscribe to
+    MarketStream,
+}
+
+pub type AdapterFuture<T> = BoxFuture<'static, Result<T, AdapterError>>;
+
+/// Entry points of one venue, the rest of the app reaches exchanges only through these.
+///
+/// Adapters are looked up by [`Exchange`] in the registry, see [`register_adapter`].
+pub trait ExchangeAdapter: Send + Sync {
+    /// Markets served by this adapter, it's registered under each of them
+    fn exchanges(&self) -> &'static [Exchange];
+
+    fn capabilities(&self, exchange: Exchange) -> Capabilities;
+
+    fn fetch_ticksize(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, Option<TickerInfo>>>;
+
+    fn fetch_ticker_prices(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, TickerStats>>;
+
+    fn fetch_klines(
+        &self,
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<Kline>>;
+
+    fn fetch_open_interest(
+        &self,
+        _ticker: Ticker,
+        _timeframe: Timeframe,
+        _range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<OpenInterest>> {
+        Box::pin(async { Err(AdapterError::InvalidRequest("Invalid exchange".to_string())) })
+    }
+
+    /// Recorded depth snapshots in `range`, oldest first and at most one per `interval_ms`
+    fn fetch_depth_history(
+        &self,
+        _ticker_info: TickerInfo,
+        _range: (u64, u64),
+        _interval_ms: u64,
+    ) -> AdapterFuture<Vec<DepthPayload>> {
+        Box::pin(async { Err(AdapterError::Unsupported("Depth history".to_string())) })
+    }
+
+    /// Economic calendar events of `currencies` in `range`, oldest first
+    fn fetch_calendar(
+        &self,
+        _range: (u64, u64),
+        _currencies: Vec<String>,
+    ) -> AdapterFuture<Vec<CalendarEvent>> {
+        Box::pin(async { Err(AdapterError::Unsupported("Economic calendar".to_string())) })
+    }
+
+    /// Depth and trades of one ticker
+    fn market_stream(
+        &self,
+        ticker_info: TickerInfo,
+        tick_mltp: Option<TickMultiplier>,
+        push_freq: PushFrequency,
+    ) -> BoxStream<'static, Event>;
+
+    /// Live klines of all `subs`, see [`ExchangeAdapter::kline_feed`]
+    fn kline_stream(
+        &self,
+        subs: Vec<(TickerInfo, Timeframe)>,
+        market: MarketKind,
+    ) -> BoxStream<'static, Event>;
+
+    fn kline_feed(&self) -> KlineFeed {
+        KlineFeed::Batched
+    }
+
+    /// Kline streams one connection to `exchange` carries at most, `None` when the venue sets
+    /// no limit
+    fn max_kline_streams(&self, _exchange: Exchange) -> Option<usize> {
+        None
+    }
+
+    /// Venue clock minus local clock in ms
+    fn server_time_offset_ms(&self) -> i64 {
+        0
+    }
+}
+
+/// Adapters by the market they serve
+pub struct AdapterRegistry {
+    adapters: HashMap<Exchange, Arc<dyn ExchangeAdapter>>,
+}
+
+impl AdapterRegistry {
+    /// Adapters that need no configuration, MetaTrader 5 joins once a connection is set up
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            adapters: HashMap::new(),
+        };
+        registry.register(Arc::new(binance::BinanceAdapter));
+        registry.register(Arc::new(bybit::BybitAdapter));
+        registry.register(Arc::new(hyperliquid::HyperliquidAdapter));
+        registry.register(Arc::new(okex::OkexAdapter));
+        registry.register(Arc::new(custom_ws::CustomWsAdapter));
+        registry
+    }
+
+    /// Replaces whatever served the adapter's markets before
+    pub fn register(&mut self, adapter: Arc<dyn ExchangeAdapter>) {
+        for exchange in adapter.exchanges() {
+            self.adapters.insert(*exchange, Arc::clone(&adapter));
+        }
+    }
+
+    pub fn unregister(&mut self, exchange: Exchange) {
+        self.adapters.remove(&exchange);
+    }
+
+    pub fn get(&self, exchange: Exchange) -> Option<Arc<dyn ExchangeAdapter>> {
+        self.adapters.get(&exchange).cloned()
+    }
+}
+
+static ADAPTERS: LazyLock<RwLock<AdapterRegistry>> =
+    LazyLock::new(|| RwLock::new(AdapterRegistry::builtin()));
+
+/// The adapter serving `exchange`, if one is registered
+pub fn adapter(exchange: Exchange) -> Option<Arc<dyn ExchangeAdapter>> {
+    ADAPTERS
+        .read()
+        .ok()
+        .and_then(|registry| registry.get(exchange))
+}
+
+pub fn register_adapter(adapter: Arc<dyn ExchangeAdapter>) {
+    if let Ok(mut registry) = ADAPTERS.write() {
+        registry.register(adapter);
+    }
+}
+
+pub fn unregister_adapter(exchange: Exchange) {
+    if let Ok(mut registry) = ADAPTERS.write() {
+        registry.unregister(exchange);
+    }
+}
+
+/// The adapter serving `ticker_info`: the one of its pinned connection if it has one, else the
+/// one of the MT5 connection that listed it
+pub fn adapter_for(ticker_info: &TickerInfo) -> Option<Arc<dyn ExchangeAdapter>> {
+    match own_connection(ticker_info) {
+        // Only MT5 has more than one connection
+        Some(source) => metatrader5::pinned_adapter(source),
+        None => adapter(ticker_info.exchange()),
+    }
+}
+
+/// The connection `ticker_info` goes through instead of its exchange's
+fn own_connection(ticker_info: &TickerInfo) -> Option<SourceId> {
+    ticker_info
+        .source
+        .or_else(|| metatrader5::listed_elsewhere(&ticker_info.ticker))
+}
+
+fn unavailable(ticker_info: &TickerInfo) -> AdapterError {
+    if let Some(source) = own_connection(ticker_info) {
+        let name = metatrader5::connection_name(source)
+            .unwrap_or_else(|| ticker_info.exchange().to_string());
+        AdapterError::InvalidRequest(format!(
+            "The {name} connection this pane streams from is not available"
+        ))
+    } else {
+        unregistered(ticker_info.exchange())
+    }
+}
+
+fn unregistered(exchange: Exchange) -> AdapterError {
+    AdapterError::InvalidRequest(format!(
+        "{exchange} is not available, configure its connection first"
+    ))
+}
+
 #[derive(Debug, Clone)]
 pub enum Event {
     Connected(Exchange),
     Disconnected(Exchange, String),
     DepthReceived(StreamKind, u64, Arc<Depth>, Box<[Trade]>),
-    KlineReceived(StreamKind, Kline),
+    KlineReceived(StreamKind, Kline, BarStatus),
+    /// The venue opened, closed or restricted trading of the stream's symbol
+    MarketStateChanged(StreamKind, MarketState),
 }
 
 #[derive(Debug, Clone, Hash)]
@@ -642,37 +915,63 @@ impl<I> StreamConfig<I> {
     }
 }
 
+/// Depth-and-trades stream for any exchange's ticker, for combinators that don't know the
+/// concrete adapter up front
+pub fn connect_depth_stream(
+    ticker_info: TickerInfo,
+    tick_mltp: Option<TickMultiplier>,
+    push_freq: PushFrequency,
+) -> BoxStream<'static, Event> {
+    match adapter_for(&ticker_info) {
+        Some(adapter) => adapter.market_stream(ticker_info, tick_mltp, push_freq),
+        None => {
+            log::warn!(
+                "{} depth stream requested but no adapter is registered",
+                ticker_info.exchange()
+            );
+            futures_util::stream::empty().boxed()
+        }
+    }
+}
+
+/// Live klines of `subs`, which all have to be on the same exchange
+pub fn connect_kline_stream(subs: Vec<(TickerInfo, Timeframe)>) -> BoxStream<'static, Event> {
+    let Some(exchange) = subs.first().map(|(ticker_info, _)| ticker_info.exchange()) else {
+        return futures_util::stream::empty().boxed();
+    };
+
+    match adapter(exchange) {
+        Some(adapter) => adapter.kline_stream(subs, exchange.market_type()),
+        None => futures_util::stream::empty().boxed(),
+    }
+}
+
+/// Ends the streams that hold connections of their own before the app exits, waiting up to
+/// `timeout`. Returns how many were dropped without ending cleanly.
+///
+/// MT5 proxy sockets are shared by panes and outlive them, every other venue's stream belongs to
+/// its subscription and ends with the app.
+pub async fn shutdown(timeout: std::time::Duration) -> usize {
+    metatrader5::shutdown(timeout).await
+}
+
+/// Current time on the exchange's clock in unix ms; timeframe boundaries follow this clock
+pub fn server_now_ms(exchange: Exchange) -> u64 {
+    let local = chrono::Utc::now().timestamp_millis();
+    let offset = adapter(exchange).map_or(0, |adapter| adapter.server_time_offset_ms());
+
+    (local + offset).max(0) as u64
+}
+
 pub async fn fetch_ticker_info(
     exchange: Exchange,
 ) -> Result<HashMap<Ticker, Option<TickerInfo>>, AdapterError> {
-    let market_type = exchange.market_type();
-
-    match exchange {
-        Exchange::BinanceLinear | Exchange::BinanceInverse | Exchange::BinanceSpot => {
-            binance::fetch_ticksize(market_type).await
-        }
-        Exchange::BybitLinear | Exchange::BybitInverse | Exchange::BybitSpot => {
-            bybit::fetch_ticksize(market_type).await
-        }
-        Exchange::HyperliquidLinear | Exchange::HyperliquidSpot => {
-            hyperliquid::fetch_ticksize(market_type).await
-        }
-        Exchange::OkexLinear | Exchange::OkexInverse | Exchange::OkexSpot => {
-            okex::fetch_ticksize(market_type).await
-        }
-        Exchange::MetaTrader5 => {
-            // Try to get global MT5 config
-            if let Some(config) = metatrader5::get_global_config() {
-                log::info!(
-                    "MT5 fetch_ticker_info using global config: {}",
-                    config.server_addr
-                );
-                metatrader5::fetch_ticksize(&config).await
-            } else {
-                log::warn!("MT5 ticker info requested but no MT5 config is set");
-                // Return empty map instead of error - MT5 will be configured later
-                Ok(HashMap::new())
-            }
+    match adapter(exchange) {
+        Some(adapter) => adapter.fetch_ticksize(exchange.market_type()).await,
+        None => {
+            // Venues that need a connection set up first, e.g. MT5, list nothing until then
+            log::warn!("{exchange} ticker info requested but no adapter is registered");
+            Ok(HashMap::new())
         }
     }
 }
@@ -680,26 +979,9 @@ pub async fn fetch_ticker_info(
 pub async fn fetch_ticker_prices(
     exchange: Exchange,
 ) -> Result<HashMap<Ticker, TickerStats>, AdapterError> {
-    let market_type = exchange.market_type();
-
-    match exchange {
-        Exchange::BinanceLinear | Exchange::BinanceInverse | Exchange::BinanceSpot => {
-            binance::fetch_ticker_prices(market_type).await
-        }
-        Exchange::BybitLinear | Exchange::BybitInverse | Exchange::BybitSpot => {
-            bybit::fetch_ticker_prices(market_type).await
-        }
-        Exchange::HyperliquidLinear | Exchange::HyperliquidSpot => {
-            hyperliquid::fetch_ticker_prices(market_type).await
-        }
-        Exchange::OkexLinear | Exchange::OkexInverse | Exchange::OkexSpot => {
-            okex::fetch_ticker_prices(market_type).await
-        }
-        Exchange::MetaTrader5 => {
-            // MT5 prices come from real-time stream, not REST API
-            // Return empty map - prices will be updated via subscription
-            Ok(HashMap::new())
-        }
+    match adapter(exchange) {
+        Some(adapter) => adapter.fetch_ticker_prices(exchange.market_type()).await,
+        None => Ok(HashMap::new()),
     }
 }
 
@@ -708,48 +990,50 @@ pub async fn fetch_klines(
     timeframe: Timeframe,
     range: Option<(u64, u64)>,
 ) -> Result<Vec<Kline>, AdapterError> {
-    match ticker_info.ticker.exchange {
-        Exchange::BinanceLinear | Exchange::BinanceInverse | Exchange::BinanceSpot => {
-            binance::fetch_klines(ticker_info, timeframe, range).await
-        }
-        Exchange::BybitLinear | Exchange::BybitInverse | Exchange::BybitSpot => {
-            bybit::fetch_klines(ticker_info, timeframe, range).await
-        }
-        Exchange::HyperliquidLinear | Exchange::HyperliquidSpot => {
-            hyperliquid::fetch_klines(ticker_info, timeframe, range).await
-        }
-        Exchange::OkexLinear | Exchange::OkexInverse | Exchange::OkexSpot => {
-            okex::fetch_klines(ticker_info, timeframe, range).await
-        }
-        Exchange::MetaTrader5 => {
-            log::info!(
-                "MT5 fetch_klines called for {:?} timeframe {:?}",
-                ticker_info.ticker,
-                timeframe
-            );
-            // Try to get global MT5 config
-            if let Some(config) = metatrader5::get_global_config() {
-                log::info!(
-                    "MT5 config found, fetching klines from {}",
-                    config.server_addr
-                );
-                match metatrader5::fetch_klines(&config, ticker_info, timeframe, range).await {
-                    Ok(klines) => {
-                        log::info!("MT5 fetch_klines success: {} klines", klines.len());
-                        Ok(klines)
-                    }
-                    Err(e) => {
-                        log::error!("MT5 fetch_klines error: {:?}", e);
-                        Err(e)
-                    }
-                }
-            } else {
-                log::warn!("MT5 klines requested but no MT5 config is set");
-                Err(AdapterError::InvalidRequest(
-                    "MT5 not configured. Please configure MT5 connection first.".to_string(),
-                ))
-            }
+    if crate::synthetic::is_synthetic(&ticker_info.ticker) {
+        return crate::synthetic::fetch_klines(ticker_info, timeframe, range).await;
+    }
+
+    match adapter_for(&ticker_info) {
+        Some(adapter) => adapter.fetch_klines(ticker_info, timeframe, range).await,
+        None => Err(unavailable(&ticker_info)),
+    }
+}
+
+pub async fn fetch_depth_history(
+    ticker_info: TickerInfo,
+    range: (u64, u64),
+    interval_ms: u64,
+) -> Result<Vec<DepthPayload>, AdapterError> {
+    match adapter_for(&ticker_info) {
+        Some(adapter) => {
+            adapter
+                .fetch_depth_history(ticker_info, range, interval_ms)
+                .await
         }
+        None => Err(AdapterError::Unsupported(format!(
+            "No adapter registered for {}",
+            ticker_info.exchange()
+        ))),
+    }
+}
+
+/// Economic calendar events concerning `ticker_info`, see [`crate::calendar::currencies`]
+pub async fn fetch_calendar(
+    ticker_info: TickerInfo,
+    range: (u64, u64),
+) -> Result<Vec<CalendarEvent>, AdapterError> {
+    let currencies = crate::calendar::currencies(&ticker_info.ticker);
+    if currencies.is_empty() {
+        return Ok(vec![]);
+    }
+
+    match adapter_for(&ticker_info) {
+        Some(adapter) => adapter.fetch_calendar(range, currencies).await,
+        None => Err(AdapterError::Unsupported(format!(
+            "No adapter registered for {}",
+            ticker_info.exchange()
+        ))),
     }
 }
 
@@ -758,16 +1042,136 @@ pub async fn fetch_open_interest(
     timeframe: Timeframe,
     range: Option<(u64, u64)>,
 ) -> Result<Vec<OpenInterest>, AdapterError> {
-    match ticker.exchange {
-        Exchange::BinanceLinear | Exchange::BinanceInverse => {
-            binance::fetch_historical_oi(ticker, range, timeframe).await
+    match adapter(ticker.exchange) {
+        Some(adapter) => adapter.fetch_open_interest(ticker, timeframe, range).await,
+        None => Err(unregistered(ticker.exchange)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SourceId, Ticker};
+
+    /// What each venue is known to provide, changes to an adapter's capabilities show up here
+    fn expected_capabilities(exchange: Exchange) -> Capabilities {
+        let perps = exchange.is_perps();
+
+        match exchange {
+            Exchange::BinanceLinear | Exchange::BinanceInverse | Exchange::BinanceSpot => {
+                Capabilities {
+                    historical_trades: true,
+                    native_depth_diffs: true,
+                    open_interest: perps,
+                    ticker_stats: true,
+                    aggressor_side: true,
+                    ..Capabilities::NONE
+                }
+            }
+            Exchange::BybitLinear | Exchange::BybitInverse | Exchange::BybitSpot => Capabilities {
+                native_depth_diffs: true,
+                open_interest: perps,
+                custom_push_freq: true,
+                ticker_stats: true,
+                aggressor_side: true,
+                ..Capabilities::NONE
+            },
+            Exchange::HyperliquidLinear | Exchange::HyperliquidSpot => Capabilities {
+                server_aggregation: true,
+                ticker_stats: true,
+                aggressor_side: true,
+                ..Capabilities::NONE
+            },
+            Exchange::OkexLinear | Exchange::OkexInverse | Exchange::OkexSpot => Capabilities {
+                native_depth_diffs: true,
+                open_interest: perps,
+                ticker_stats: true,
+                aggressor_side: true,
+                ..Capabilities::NONE
+            },
+            Exchange::MetaTrader5 => Capabilities {
+                depth_history: true,
+                economic_calendar: true,
+                ..Capabilities::NONE
+            },
+            Exchange::CustomWs => Capabilities {
+                aggressor_side: true,
+                ..Capabilities::NONE
+            },
         }
-        Exchange::BybitLinear | Exchange::BybitInverse => {
-            bybit::fetch_historical_oi(ticker, range, timeframe).await
+    }
+
+    #[test]
+    fn every_exchange_declares_capabilities() {
+        assert_eq!(Exchange::ALL.len(), <Exchange as Enum>::LENGTH);
+
+        let mut registry = AdapterRegistry::builtin();
+        registry.register(Arc::new(metatrader5::Mt5Adapter::new(
+            metatrader5::Mt5Config::default(),
+        )));
+
+        for exchange in Exchange::ALL {
+            let caps = registry
+                .get(exchange)
+                .unwrap_or_else(|| panic!("{exchange} has an adapter"))
+                .capabilities(exchange);
+
+            assert_eq!(caps, expected_capabilities(exchange), "{exchange}");
+            assert!(
+                !caps.open_interest || exchange.is_perps(),
+                "{exchange} declares open interest without being a perps market"
+            );
+            assert!(
+                !caps.custom_push_freq || exchange.allowed_push_freqs().len() > 1,
+                "{exchange} declares custom push frequency without any to pick from"
+            );
         }
-        Exchange::OkexLinear | Exchange::OkexInverse => {
-            okex::fetch_historical_oi(ticker, range, timeframe).await
+    }
+
+    #[test]
+    fn registry_serves_each_market_by_one_adapter() {
+        let mut registry = AdapterRegistry::builtin();
+
+        for exchange in Exchange::ALL {
+            let registered = registry.get(exchange).is_some();
+            assert_eq!(registered, exchange != Exchange::MetaTrader5, "{exchange}");
         }
-        _ => Err(AdapterError::InvalidRequest("Invalid exchange".to_string())),
+
+        registry.register(Arc::new(metatrader5::Mt5Adapter::new(
+            metatrader5::Mt5Config::default(),
+        )));
+        let mt5 = registry.get(Exchange::MetaTrader5).expect("MT5 registered");
+        assert_eq!(mt5.kline_feed(), KlineFeed::MarketStream);
+
+        registry.unregister(Exchange::MetaTrader5);
+        assert!(registry.get(Exchange::MetaTrader5).is_none());
+    }
+
+    #[test]
+    fn pinned_tickers_go_through_their_connection_only() {
+        let ticker_info = TickerInfo::new(
+            Ticker::new("EURUSD", Exchange::MetaTrader5),
+            0.00001,
+            0.01,
+            Some(100_000.0),
+        );
+        let source = SourceId::of("MT5 broker-b:9876");
+        let pinned = ticker_info.pinned(Some(source));
+        assert_ne!(pinned, ticker_info);
+
+        // Not set up this session, no falling back to the active connection
+        assert!(adapter_for(&pinned).is_none());
+        assert!(!metatrader5::has_connection(source));
+
+        metatrader5::register_connection(
+            "MT5 broker-b:9876",
+            metatrader5::Mt5Config {
+                server_addr: "broker-b:9876".to_string(),
+                ..metatrader5::Mt5Config::default()
+            },
+        );
+        assert!(metatrader5::has_connection(source));
+        assert!(adapter_for(&pinned).is_some());
+        assert!(metatrader5::connection_names().contains(&"MT5 broker-b:9876".to_string()));
     }
 }
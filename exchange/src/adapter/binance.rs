@@ -1,7 +1,7 @@
 use super::{
     super::{
-        Exchange, Kline, MarketKind, OpenInterest, Price, PushFrequency, SizeUnit, StreamKind,
-        Ticker, TickerInfo, TickerStats, Timeframe, Trade,
+        BarStatus, Exchange, Kline, MarketKind, OpenInterest, Price, PushFrequency, SizeUnit,
+        StreamKind, TickMultiplier, Ticker, TickerInfo, TickerStats, Timeframe, Trade,
         adapter::StreamTicksize,
         connect::{State, connect_ws},
         de_string_to_f32,
@@ -10,9 +10,11 @@ use super::{
         limiter::{self, RateLimiter},
         str_f32_parse, volume_size_unit,
     },
-    AdapterError, Event,
+    AdapterError, AdapterFuture, Capabilities, Event, ExchangeAdapter,
 };
 
+use futures_util::{FutureExt as _, StreamExt as _, stream::BoxStream};
+
 use csv::ReaderBuilder;
 use fastwebsockets::OpCode;
 use iced_futures::{
@@ -142,6 +144,8 @@ struct SonicKline {
     taker_buy_base_asset_volume: f32,
     #[serde(rename = "i")]
     interval: String,
+    #[serde(rename = "x", default)]
+    is_closed: bool,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -408,6 +412,7 @@ pub fn connect_market_stream(
                     match ws.read_frame().await {
                         Ok(msg) => match msg.opcode {
                             OpCode::Text => {
+                                crate::capture::record(ticker, &msg.payload[..]);
                                 if let Ok(data) = feed_de(&msg.payload[..], market) {
                                     match data {
                                         StreamData::Trade(de_trade) => {
@@ -696,6 +701,7 @@ pub fn connect_kline_stream(
                                                     timeframe,
                                                 },
                                                 kline,
+                                                BarStatus::from_closed(de_kline.is_closed),
                                             ))
                                             .await;
                                     } else {
@@ -1494,3 +1500,102 @@ pub async fn get_hist_trades(
         ))),
     }
 }
+
+/// Registered for the Binance spot, linear and inverse markets
+pub struct BinanceAdapter;
+
+impl ExchangeAdapter for BinanceAdapter {
+    fn exchanges(&self) -> &'static [Exchange] {
+        &[
+            Exchange::BinanceLinear,
+            Exchange::BinanceInverse,
+            Exchange::BinanceSpot,
+        ]
+    }
+
+    fn capabilities(&self, exchange: Exchange) -> Capabilities {
+        Capabilities {
+            historical_trades: true,
+            native_depth_diffs: true,
+            open_interest: exchange.is_perps(),
+            ticker_stats: true,
+            aggressor_side: true,
+            ..Capabilities::NONE
+        }
+    }
+
+    fn fetch_ticksize(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, Option<TickerInfo>>> {
+        fetch_ticksize(market).boxed()
+    }
+
+    fn fetch_ticker_prices(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, TickerStats>> {
+        fetch_ticker_prices(market).boxed()
+    }
+
+    fn fetch_klines(
+        &self,
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<Kline>> {
+        fetch_klines(ticker_info, timeframe, range).boxed()
+    }
+
+    fn fetch_open_interest(
+        &self,
+        ticker: Ticker,
+        timeframe: Timeframe,
+        range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<OpenInterest>> {
+        fetch_historical_oi(ticker, range, timeframe).boxed()
+    }
+
+    fn market_stream(
+        &self,
+        ticker_info: TickerInfo,
+        _tick_mltp: Option<TickMultiplier>,
+        push_freq: PushFrequency,
+    ) -> BoxStream<'static, Event> {
+        connect_market_stream(ticker_info, push_freq).boxed()
+    }
+
+    fn kline_stream(
+        &self,
+        subs: Vec<(TickerInfo, Timeframe)>,
+        market: MarketKind,
+    ) -> BoxStream<'static, Event> {
+        connect_kline_stream(subs, market).boxed()
+    }
+
+    /// A combined stream connection takes 1024 streams
+    fn max_kline_streams(&self, _exchange: Exchange) -> Option<usize> {
+        Some(1024)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stream_times_are_kept_as_sent() {
+        // Binance stamps in UTC, unlike MT5 servers nothing is converted
+        let trade = br#"{"stream":"btcusdt@aggTrade","data":{"e":"aggTrade","s":"BTCUSDT","p":"42000.1","q":"0.5","T":1704355200123,"m":true}}"#;
+        match feed_de(trade, MarketKind::LinearPerps) {
+            Ok(StreamData::Trade(trade)) => assert_eq!(trade.time, 1704355200123),
+            _ => panic!("expected a trade"),
+        }
+
+        let kline = br#"{"stream":"btcusdt@kline_1m","data":{"s":"BTCUSDT","k":{"t":1704355200000,"o":"1","h":"2","l":"0.5","c":"1.5","v":"10","V":"4","i":"1m"}}}"#;
+        match feed_de(kline, MarketKind::Spot) {
+            Ok(StreamData::Kline(_, kline)) => assert_eq!(kline.time, 1704355200000),
+            _ => panic!("expected a kline"),
+        }
+    }
+}
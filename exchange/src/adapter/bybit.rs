@@ -1,7 +1,7 @@
 use super::{
     super::{
-        Exchange, Kline, MarketKind, OpenInterest, Price, PushFrequency, SizeUnit, StreamKind,
-        Ticker, TickerInfo, TickerStats, Timeframe, Trade,
+        BarStatus, Exchange, Kline, MarketKind, OpenInterest, Price, PushFrequency, SizeUnit,
+        StreamKind, TickMultiplier, Ticker, TickerInfo, TickerStats, Timeframe, Trade,
         adapter::StreamTicksize,
         connect::{State, connect_ws},
         de_string_to_f32, de_string_to_u64,
@@ -10,9 +10,11 @@ use super::{
         limiter::{self, http_request_with_limiter},
         volume_size_unit,
     },
-    AdapterError, Event,
+    AdapterError, AdapterFuture, Capabilities, Event, ExchangeAdapter,
 };
 
+use futures_util::{FutureExt as _, StreamExt as _, stream::BoxStream};
+
 use fastwebsockets::{Frame, OpCode};
 use iced_futures::{
     futures::{SinkExt, Stream, channel::mpsc},
@@ -108,6 +110,8 @@ pub struct SonicKline {
     pub volume: f32,
     #[serde(rename = "interval")]
     pub interval: String,
+    #[serde(default)]
+    pub confirm: bool,
 }
 
 enum StreamData {
@@ -351,6 +355,7 @@ pub fn connect_market_stream(
                 State::Connected(websocket) => match websocket.read_frame().await {
                     Ok(msg) => match msg.opcode {
                         OpCode::Text => {
+                            crate::capture::record(ticker, &msg.payload[..]);
                             if let Ok(data) = feed_de(&msg.payload[..], Some(ticker), market_type) {
                                 match data {
                                     StreamData::Trade(de_trade_vec) => {
@@ -540,6 +545,7 @@ pub fn connect_kline_stream(
                                                         timeframe,
                                                     },
                                                     kline,
+                                                    BarStatus::from_closed(de_kline.confirm),
                                                 ))
                                                 .await;
                                         } else {
@@ -944,3 +950,81 @@ pub async fn fetch_ticker_prices(
 
     Ok(ticker_prices_map)
 }
+
+/// Registered for the Bybit spot, linear and inverse markets
+pub struct BybitAdapter;
+
+impl ExchangeAdapter for BybitAdapter {
+    fn exchanges(&self) -> &'static [Exchange] {
+        &[
+            Exchange::BybitLinear,
+            Exchange::BybitInverse,
+            Exchange::BybitSpot,
+        ]
+    }
+
+    fn capabilities(&self, exchange: Exchange) -> Capabilities {
+        Capabilities {
+            native_depth_diffs: true,
+            open_interest: exchange.is_perps(),
+            custom_push_freq: true,
+            ticker_stats: true,
+            aggressor_side: true,
+            ..Capabilities::NONE
+        }
+    }
+
+    fn fetch_ticksize(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, Option<TickerInfo>>> {
+        fetch_ticksize(market).boxed()
+    }
+
+    fn fetch_ticker_prices(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, TickerStats>> {
+        fetch_ticker_prices(market).boxed()
+    }
+
+    fn fetch_klines(
+        &self,
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<Kline>> {
+        fetch_klines(ticker_info, timeframe, range).boxed()
+    }
+
+    fn fetch_open_interest(
+        &self,
+        ticker: Ticker,
+        timeframe: Timeframe,
+        range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<OpenInterest>> {
+        fetch_historical_oi(ticker, range, timeframe).boxed()
+    }
+
+    fn market_stream(
+        &self,
+        ticker_info: TickerInfo,
+        _tick_mltp: Option<TickMultiplier>,
+        push_freq: PushFrequency,
+    ) -> BoxStream<'static, Event> {
+        connect_market_stream(ticker_info, push_freq).boxed()
+    }
+
+    fn kline_stream(
+        &self,
+        subs: Vec<(TickerInfo, Timeframe)>,
+        market: MarketKind,
+    ) -> BoxStream<'static, Event> {
+        connect_kline_stream(subs, market).boxed()
+    }
+
+    /// Every kline is asked for in one subscribe, which spot markets take 10 args in
+    fn max_kline_streams(&self, exchange: Exchange) -> Option<usize> {
+        (exchange == Exchange::BybitSpot).then_some(10)
+    }
+}
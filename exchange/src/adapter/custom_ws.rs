@@ -0,0 +1,1228 @@
+//! Generic WebSocket JSON adapter, configured entirely through field mappings
+//!
+//! Any public feed that pushes trades and order book levels as JSON can be charted without
+//! writing a dedicated adapter: the config names the URL, an optional subscribe message and
+//! where each field lives in a message, using a small JSONPath subset (`$.data[0].price`).
+//!
+//! Depth goes through the same `LocalDepthCache` as the built-in adapters. Snapshots replace
+//! the book, diffs (when the feed sends them) are applied on top once a snapshot has arrived.
+
+use super::emit::{DepthEmitter, PENDING_FLUSH_INTERVAL};
+use super::{
+    AdapterError, AdapterFuture, Capabilities, Event, Exchange, ExchangeAdapter, KlineFeed,
+    MarketKind, StreamKind, StreamTicksize,
+};
+use crate::{
+    Kline, Price, PushFrequency, TickMultiplier, Ticker, TickerInfo, TickerStats, Timeframe, Trade,
+    bars::BarBuilder,
+    depth::{DeOrder, DepthPayload, DepthUpdate, LocalDepthCache},
+};
+
+use futures_util::{StreamExt as _, stream::BoxStream};
+use iced_futures::{
+    futures::{SinkExt, Stream, channel::mpsc},
+    stream,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Replaced by the ticker's symbol in the subscribe template
+pub const SYMBOL_PLACEHOLDER: &str = "{symbol}";
+
+fn default_true() -> bool {
+    true
+}
+
+/// Used when a symbol doesn't specify its min quantity
+pub const DEFAULT_MIN_QTY: f32 = 0.0001;
+
+fn default_min_qty() -> f32 {
+    DEFAULT_MIN_QTY
+}
+
+fn default_sell_value() -> String {
+    "sell".to_string()
+}
+
+fn default_bid_value() -> String {
+    "buy".to_string()
+}
+
+// ============================================================================
+// Configuration Types
+// ============================================================================
+
+/// A symbol the feed serves, the feed itself has no way to tell us its tick size
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CustomSymbol {
+    pub symbol: String,
+    pub tick_size: f32,
+    #[serde(default = "default_min_qty")]
+    pub min_qty: f32,
+}
+
+/// How numeric timestamps are scaled, non-numeric strings are always parsed as RFC 3339
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum TimeUnit {
+    #[default]
+    Millis,
+    Seconds,
+    Micros,
+    Nanos,
+}
+
+impl TimeUnit {
+    pub const ALL: [TimeUnit; 4] = [
+        TimeUnit::Millis,
+        TimeUnit::Seconds,
+        TimeUnit::Micros,
+        TimeUnit::Nanos,
+    ];
+
+    fn to_millis(self, value: f64) -> u64 {
+        let ms = match self {
+            TimeUnit::Millis => value,
+            TimeUnit::Seconds => value * 1_000.0,
+            TimeUnit::Micros => value / 1_000.0,
+            TimeUnit::Nanos => value / 1_000_000.0,
+        };
+        ms.max(0.0) as u64
+    }
+}
+
+impl std::fmt::Display for TimeUnit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TimeUnit::Millis => write!(f, "Milliseconds"),
+            TimeUnit::Seconds => write!(f, "Seconds"),
+            TimeUnit::Micros => write!(f, "Microseconds"),
+            TimeUnit::Nanos => write!(f, "Nanoseconds"),
+        }
+    }
+}
+
+/// Selects a message kind by the value found at `path`, e.g. `$.type` == `"match"`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct MessageMatch {
+    pub path: String,
+    pub equals: String,
+}
+
+impl MessageMatch {
+    fn matches(&self, msg: &Value) -> bool {
+        select(msg, &self.path)
+            .and_then(value_str)
+            .is_some_and(|v| v == self.equals)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TradeMapping {
+    pub matcher: MessageMatch,
+    /// Path to an array of trades when a message batches them, the fields below are then
+    /// resolved against each element
+    #[serde(default)]
+    pub items: Option<String>,
+    /// When missing, the time the message was received is used
+    #[serde(default)]
+    pub time: Option<String>,
+    pub price: String,
+    pub qty: String,
+    pub side: String,
+    /// Value of `side` that marks a sell (taker) trade, compared case-insensitively
+    #[serde(default = "default_sell_value")]
+    pub sell_value: String,
+}
+
+/// Paths of price and quantity within a single book level
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LevelMapping {
+    pub price: String,
+    pub qty: String,
+}
+
+impl Default for LevelMapping {
+    fn default() -> Self {
+        Self {
+            price: "$[0]".to_string(),
+            qty: "$[1]".to_string(),
+        }
+    }
+}
+
+/// Separate bid and ask arrays, used for snapshots and for diffs shaped like them
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct BookMapping {
+    pub matcher: MessageMatch,
+    pub bids: String,
+    pub asks: String,
+    #[serde(default)]
+    pub level: LevelMapping,
+    #[serde(default)]
+    pub time: Option<String>,
+}
+
+/// One array of changes with the side on each entry, e.g. `["buy", "100.5", "0.2"]`
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ChangesMapping {
+    pub matcher: MessageMatch,
+    pub changes: String,
+    pub side: String,
+    pub price: String,
+    pub qty: String,
+    /// Value of `side` that marks a bid level, compared case-insensitively
+    #[serde(default = "default_bid_value")]
+    pub bid_value: String,
+    #[serde(default)]
+    pub time: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "kind")]
+pub enum DiffMapping {
+    Levels(BookMapping),
+    Changes(ChangesMapping),
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Deserialize, Serialize)]
+pub struct Mappings {
+    #[serde(default)]
+    pub trade: Option<TradeMapping>,
+    #[serde(default)]
+    pub snapshot: Option<BookMapping>,
+    /// Incremental book updates, a zero quantity removes the level
+    #[serde(default)]
+    pub diff: Option<DiffMapping>,
+    /// Path to the symbol a message is for, messages for other symbols are ignored
+    #[serde(default)]
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub time_unit: TimeUnit,
+}
+
+impl Mappings {
+    fn paths(&self) -> Vec<&str> {
+        let mut paths = vec![];
+
+        if let Some(symbol) = &self.symbol {
+            paths.push(symbol.as_str());
+        }
+        if let Some(t) = &self.trade {
+            paths.extend([t.matcher.path.as_str(), &t.price, &t.qty, &t.side]);
+            paths.extend(t.items.as_deref());
+            paths.extend(t.time.as_deref());
+        }
+
+        if let Some(b) = &self.snapshot {
+            paths.extend(b.paths());
+        }
+        match &self.diff {
+            Some(DiffMapping::Levels(b)) => paths.extend(b.paths()),
+            Some(DiffMapping::Changes(c)) => {
+                paths.extend([
+                    c.matcher.path.as_str(),
+                    &c.changes,
+                    &c.side,
+                    &c.price,
+                    &c.qty,
+                ]);
+                paths.extend(c.time.as_deref());
+            }
+            None => {}
+        }
+
+        paths
+    }
+}
+
+impl BookMapping {
+    fn paths(&self) -> Vec<&str> {
+        let mut paths = vec![
+            self.matcher.path.as_str(),
+            &self.bids,
+            &self.asks,
+            &self.level.price,
+            &self.level.qty,
+        ];
+        paths.extend(self.time.as_deref());
+        paths
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CustomWsConfig {
+    /// Display name, also identifies the config when saving
+    pub name: String,
+    /// `ws://` or `wss://` endpoint
+    pub url: String,
+    /// Sent as the `Authorization` header of the upgrade request, e.g. `Bearer <token>`
+    #[serde(default)]
+    pub auth_header: Option<String>,
+    /// Text frame sent after connecting, `{symbol}` is replaced; empty sends nothing
+    #[serde(default)]
+    pub subscribe_template: String,
+    pub symbols: Vec<CustomSymbol>,
+    pub mappings: Mappings,
+    #[serde(default = "default_true")]
+    pub auto_reconnect: bool,
+}
+
+impl Default for CustomWsConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            url: String::new(),
+            auth_header: None,
+            subscribe_template: String::new(),
+            symbols: vec![],
+            mappings: Mappings::default(),
+            auto_reconnect: true,
+        }
+    }
+}
+
+impl CustomWsConfig {
+    /// Coinbase Exchange public feed: `match` trades and `level2_batch` snapshots + changes
+    pub fn coinbase_example() -> Self {
+        let matcher = |equals: &str| MessageMatch {
+            path: "$.type".to_string(),
+            equals: equals.to_string(),
+        };
+
+        Self {
+            name: "Coinbase".to_string(),
+            url: "wss://ws-feed.exchange.coinbase.com".to_string(),
+            auth_header: None,
+            subscribe_template: r#"{"type":"subscribe","product_ids":["{symbol}"],"channels":["matches","level2_batch"]}"#
+                .to_string(),
+            symbols: vec![CustomSymbol {
+                symbol: "BTC-USD".to_string(),
+                tick_size: 0.01,
+                min_qty: 0.00000001,
+            }],
+            mappings: Mappings {
+                trade: Some(TradeMapping {
+                    matcher: matcher("match"),
+                    items: None,
+                    time: Some("$.time".to_string()),
+                    price: "$.price".to_string(),
+                    qty: "$.size".to_string(),
+                    side: "$.side".to_string(),
+                    // `side` is the maker's, so a resting buy filled means the taker sold
+                    sell_value: "buy".to_string(),
+                }),
+                snapshot: Some(BookMapping {
+                    matcher: matcher("snapshot"),
+                    bids: "$.bids".to_string(),
+                    asks: "$.asks".to_string(),
+                    level: LevelMapping::default(),
+                    time: None,
+                }),
+                diff: Some(DiffMapping::Changes(ChangesMapping {
+                    matcher: matcher("l2update"),
+                    changes: "$.changes".to_string(),
+                    side: "$[0]".to_string(),
+                    price: "$[1]".to_string(),
+                    qty: "$[2]".to_string(),
+                    bid_value: "buy".to_string(),
+                    time: Some("$.time".to_string()),
+                })),
+                symbol: Some("$.product_id".to_string()),
+                time_unit: TimeUnit::Millis,
+            },
+            auto_reconnect: true,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Name is required".to_string());
+        }
+        if !(self.url.starts_with("ws://") || self.url.starts_with("wss://")) {
+            return Err("URL must start with ws:// or wss://".to_string());
+        }
+        if self.symbols.is_empty() {
+            return Err("At least one symbol is required".to_string());
+        }
+        for s in &self.symbols {
+            if s.symbol.is_empty()
+                || s.symbol.len() > 28
+                || !s.symbol.is_ascii()
+                || s.symbol.contains('|')
+                || s.symbol.contains(':')
+            {
+                return Err(format!(
+                    "Symbol must be 1-28 ASCII chars without '|' or ':': {:?}",
+                    s.symbol
+                ));
+            }
+            if !s.tick_size.is_finite() || s.tick_size <= 0.0 || s.min_qty <= 0.0 {
+                return Err(format!(
+                    "{}: tick size and min qty must be positive",
+                    s.symbol
+                ));
+            }
+        }
+        if self.mappings.trade.is_none() && self.mappings.snapshot.is_none() {
+            return Err("Map at least trades or depth snapshots".to_string());
+        }
+        for path in self.mappings.paths() {
+            parse_path(path).map_err(|e| format!("{path:?}: {e}"))?;
+        }
+        Ok(())
+    }
+
+    pub fn ticker_info(symbol: &CustomSymbol) -> TickerInfo {
+        TickerInfo::new(
+            Ticker::new(&symbol.symbol, Exchange::CustomWs),
+            symbol.tick_size,
+            symbol.min_qty,
+            None,
+        )
+    }
+
+    pub fn ticker_infos(&self) -> HashMap<Ticker, Option<TickerInfo>> {
+        self.symbols
+            .iter()
+            .map(|s| {
+                let info = Self::ticker_info(s);
+                (info.ticker, Some(info))
+            })
+            .collect()
+    }
+
+    pub fn subscribe_message(&self, symbol: &str) -> Option<String> {
+        let template = self.subscribe_template.trim();
+        (!template.is_empty()).then(|| template.replace(SYMBOL_PLACEHOLDER, symbol))
+    }
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+/// Configs loaded from the saved state, looked up by ticker when subscribing
+static REGISTRY: RwLock<Vec<CustomWsConfig>> = RwLock::new(Vec::new());
+
+/// Replace the registered configs, dropping invalid ones and symbols already served by an
+/// earlier config since tickers alone must identify the feed
+pub fn register(configs: Vec<CustomWsConfig>) {
+    let valid = deduplicated(configs);
+
+    if let Ok(mut registry) = REGISTRY.write() {
+        *registry = valid;
+    }
+}
+
+/// `configs` without the invalid ones, each symbol left to the first config serving it
+fn deduplicated(configs: Vec<CustomWsConfig>) -> Vec<CustomWsConfig> {
+    let mut seen = std::collections::HashSet::new();
+
+    configs
+        .into_iter()
+        .filter(|config| match config.validate() {
+            Ok(()) => true,
+            Err(e) => {
+                log::warn!("Ignoring custom WS config {:?}: {e}", config.name);
+                false
+            }
+        })
+        .map(|mut config| {
+            config.symbols.retain(|s| {
+                let fresh = seen.insert(s.symbol.clone());
+                if !fresh {
+                    log::warn!(
+                        "Custom WS config {:?}: {} is already served by another config",
+                        config.name,
+                        s.symbol
+                    );
+                }
+                fresh
+            });
+            config
+        })
+        .collect()
+}
+
+pub fn all() -> Vec<CustomWsConfig> {
+    REGISTRY.read().map(|r| r.clone()).unwrap_or_default()
+}
+
+pub fn lookup(ticker: &Ticker) -> Option<CustomWsConfig> {
+    if ticker.exchange != Exchange::CustomWs {
+        return None;
+    }
+    let (symbol, _) = ticker.to_full_symbol_and_type();
+
+    REGISTRY.read().ok().and_then(|registry| {
+        registry
+            .iter()
+            .find(|config| config.symbols.iter().any(|s| s.symbol == symbol))
+            .cloned()
+    })
+}
+
+/// Symbols of every registered config
+pub fn ticker_infos() -> HashMap<Ticker, Option<TickerInfo>> {
+    all()
+        .iter()
+        .flat_map(CustomWsConfig::ticker_infos)
+        .collect()
+}
+
+// ============================================================================
+// JSONPath-lite
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+/// Parses `$`, `$.a.b`, `$.a[0].b`, `$[1]` or the same without the leading `$`
+fn parse_path(path: &str) -> Result<Vec<Segment<'_>>, String> {
+    let mut rest = path.trim();
+    rest = rest.strip_prefix('$').unwrap_or(rest);
+
+    let mut segments = vec![];
+
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix('[') {
+            let end = after.find(']').ok_or("unclosed '['")?;
+            let index = after[..end]
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid index {:?}", &after[..end]))?;
+            segments.push(Segment::Index(index));
+            rest = &after[end + 1..];
+        } else {
+            let after = rest.strip_prefix('.').unwrap_or(rest);
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            if end == 0 {
+                return Err("empty key".to_string());
+            }
+            segments.push(Segment::Key(&after[..end]));
+            rest = &after[end..];
+        }
+    }
+
+    Ok(segments)
+}
+
+/// Value at `path` within `value`, `None` for missing keys or malformed paths
+pub fn select<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    parse_path(path)
+        .ok()?
+        .into_iter()
+        .try_fold(value, |current, segment| match segment {
+            Segment::Key(key) => current.get(key),
+            Segment::Index(i) => current.get(i),
+        })
+}
+
+fn value_str(value: &Value) -> Option<Cow<'_, str>> {
+    match value {
+        Value::String(s) => Some(Cow::Borrowed(s)),
+        Value::Number(n) => Some(Cow::Owned(n.to_string())),
+        Value::Bool(b) => Some(Cow::Borrowed(if *b { "true" } else { "false" })),
+        _ => None,
+    }
+}
+
+fn require<'a>(value: &'a Value, path: &str) -> Result<&'a Value, AdapterError> {
+    select(value, path).ok_or_else(|| AdapterError::ParseError(format!("{path} not found")))
+}
+
+fn number_at(value: &Value, path: &str) -> Result<f64, AdapterError> {
+    let found = require(value, path)?;
+
+    match found {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse::<f64>().ok(),
+        _ => None,
+    }
+    .ok_or_else(|| AdapterError::ParseError(format!("{path} is not a number: {found}")))
+}
+
+fn text_at<'a>(value: &'a Value, path: &str) -> Result<Cow<'a, str>, AdapterError> {
+    let found = require(value, path)?;
+    value_str(found)
+        .ok_or_else(|| AdapterError::ParseError(format!("{path} is not a scalar: {found}")))
+}
+
+fn array_at<'a>(value: &'a Value, path: &str) -> Result<&'a [Value], AdapterError> {
+    require(value, path)?
+        .as_array()
+        .map(Vec::as_slice)
+        .ok_or_else(|| AdapterError::ParseError(format!("{path} is not an array")))
+}
+
+fn time_at(
+    value: &Value,
+    path: Option<&str>,
+    unit: TimeUnit,
+    received_at: u64,
+) -> Result<u64, AdapterError> {
+    let Some(path) = path else {
+        return Ok(received_at);
+    };
+
+    match require(value, path)? {
+        Value::Number(n) => n
+            .as_f64()
+            .map(|v| unit.to_millis(v))
+            .ok_or_else(|| AdapterError::ParseError(format!("{path} is not a timestamp"))),
+        Value::String(s) => match s.trim().parse::<f64>() {
+            Ok(v) => Ok(unit.to_millis(v)),
+            Err(_) => chrono::DateTime::parse_from_rfc3339(s.trim())
+                .map(|dt| dt.timestamp_millis().max(0) as u64)
+                .map_err(|e| AdapterError::ParseError(format!("{path}: {e}"))),
+        },
+        other => Err(AdapterError::ParseError(format!(
+            "{path} is not a timestamp: {other}"
+        ))),
+    }
+}
+
+// ============================================================================
+// Message Parsing
+// ============================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParsedTrade {
+    pub time: u64,
+    pub price: f32,
+    pub qty: f32,
+    pub is_sell: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedBook {
+    pub time: u64,
+    /// `(price, qty)` pairs as sent, not yet rounded to the tick size
+    pub bids: Vec<(f32, f32)>,
+    pub asks: Vec<(f32, f32)>,
+}
+
+impl ParsedBook {
+    fn into_payload(self) -> DepthPayload {
+        let orders = |levels: Vec<(f32, f32)>| {
+            levels
+                .into_iter()
+                .map(|(price, qty)| DeOrder { price, qty })
+                .collect()
+        };
+
+        DepthPayload {
+            last_update_id: self.time,
+            time: self.time,
+            bids: orders(self.bids),
+            asks: orders(self.asks),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Parsed {
+    Trades(Vec<ParsedTrade>),
+    Snapshot(ParsedBook),
+    Diff(ParsedBook),
+}
+
+/// Parses one text frame with `mappings`.
+///
+/// `Ok(None)` means the message doesn't match any mapping (or is for another symbol than
+/// `symbol`), errors mean it matched but a mapped field is missing or malformed.
+pub fn parse_message(
+    mappings: &Mappings,
+    text: &str,
+    symbol: Option<&str>,
+    received_at: u64,
+) -> Result<Option<Parsed>, AdapterError> {
+    let msg: Value =
+        serde_json::from_str(text).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+
+    if let (Some(symbol), Some(path)) = (symbol, &mappings.symbol)
+        && let Some(found) = select(&msg, path).and_then(value_str)
+        && found != symbol
+    {
+        return Ok(None);
+    }
+
+    let unit = mappings.time_unit;
+
+    if let Some(trade) = &mappings.trade
+        && trade.matcher.matches(&msg)
+    {
+        let items = match &trade.items {
+            Some(path) => array_at(&msg, path)?,
+            None => std::slice::from_ref(&msg),
+        };
+
+        let trades = items
+            .iter()
+            .map(|item| {
+                Ok(ParsedTrade {
+                    time: time_at(item, trade.time.as_deref(), unit, received_at)?,
+                    price: number_at(item, &trade.price)? as f32,
+                    qty: number_at(item, &trade.qty)? as f32,
+                    is_sell: text_at(item, &trade.side)?.eq_ignore_ascii_case(&trade.sell_value),
+                })
+            })
+            .collect::<Result<_, AdapterError>>()?;
+
+        return Ok(Some(Parsed::Trades(trades)));
+    }
+
+    if let Some(book) = &mappings.snapshot
+        && book.matcher.matches(&msg)
+    {
+        return parse_book(&msg, book, unit, received_at).map(|b| Some(Parsed::Snapshot(b)));
+    }
+
+    match &mappings.diff {
+        Some(DiffMapping::Levels(book)) if book.matcher.matches(&msg) => {
+            parse_book(&msg, book, unit, received_at).map(|b| Some(Parsed::Diff(b)))
+        }
+        Some(DiffMapping::Changes(changes)) if changes.matcher.matches(&msg) => {
+            parse_changes(&msg, changes, unit, received_at).map(|b| Some(Parsed::Diff(b)))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn parse_book(
+    msg: &Value,
+    book: &BookMapping,
+    unit: TimeUnit,
+    received_at: u64,
+) -> Result<ParsedBook, AdapterError> {
+    let levels = |path: &str| -> Result<Vec<(f32, f32)>, AdapterError> {
+        array_at(msg, path)?
+            .iter()
+            .map(|level| {
+                Ok((
+                    number_at(level, &book.level.price)? as f32,
+                    number_at(level, &book.level.qty)? as f32,
+                ))
+            })
+            .collect()
+    };
+
+    Ok(ParsedBook {
+        time: time_at(msg, book.time.as_deref(), unit, received_at)?,
+        bids: levels(&book.bids)?,
+        asks: levels(&book.asks)?,
+    })
+}
+
+fn parse_changes(
+    msg: &Value,
+    changes: &ChangesMapping,
+    unit: TimeUnit,
+    received_at: u64,
+) -> Result<ParsedBook, AdapterError> {
+    let mut book = ParsedBook {
+        time: time_at(msg, changes.time.as_deref(), unit, received_at)?,
+        bids: vec![],
+        asks: vec![],
+    };
+
+    for change in array_at(msg, &changes.changes)? {
+        let level = (
+            number_at(change, &changes.price)? as f32,
+            number_at(change, &changes.qty)? as f32,
+        );
+
+        if text_at(change, &changes.side)?.eq_ignore_ascii_case(&changes.bid_value) {
+            book.bids.push(level);
+        } else {
+            book.asks.push(level);
+        }
+    }
+
+    Ok(book)
+}
+
+fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis().max(0) as u64
+}
+
+// ============================================================================
+// Streams
+// ============================================================================
+
+/// Depth-and-trades stream for a ticker of a registered config
+pub fn connect_market_stream(
+    ticker_info: TickerInfo,
+    _push_freq: PushFrequency,
+) -> impl Stream<Item = Event> {
+    stream::channel(100, async move |mut output| {
+        let exchange = Exchange::CustomWs;
+
+        let Some(config) = lookup(&ticker_info.ticker) else {
+            let _ = output
+                .send(Event::Disconnected(
+                    exchange,
+                    format!("No custom WS config serves {}", ticker_info.ticker),
+                ))
+                .await;
+            return;
+        };
+
+        let mut trades_buffer: Vec<Trade> = Vec::new();
+        let mut emitter = DepthEmitter::default();
+        let mut reconnect_delay = Duration::from_secs(1);
+
+        loop {
+            log::info!("Connecting to custom WS {}: {}", config.name, config.url);
+
+            let result = connect_and_stream(
+                &config,
+                ticker_info,
+                &mut trades_buffer,
+                &mut emitter,
+                &mut output,
+            )
+            .await;
+
+            emitter.flush(&mut output).await;
+
+            let reason = match result {
+                Ok(()) => "Connection closed".to_string(),
+                Err(e) => {
+                    log::error!("Custom WS {} error: {e}", config.name);
+                    e.to_string()
+                }
+            };
+            let _ = output.send(Event::Disconnected(exchange, reason)).await;
+
+            if !config.auto_reconnect {
+                break;
+            }
+
+            tokio::time::sleep(reconnect_delay).await;
+            reconnect_delay = std::cmp::min(reconnect_delay * 2, Duration::from_secs(60));
+        }
+    })
+}
+
+async fn connect_and_stream(
+    config: &CustomWsConfig,
+    ticker_info: TickerInfo,
+    trades_buffer: &mut Vec<Trade>,
+    emitter: &mut DepthEmitter,
+    output: &mut mpsc::Sender<Event>,
+) -> Result<(), AdapterError> {
+    use futures_util::{SinkExt as _, StreamExt as _};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let (symbol, _) = ticker_info.ticker.to_full_symbol_and_type();
+    let mut ws = connect(config, &symbol).await?;
+
+    let _ = output.send(Event::Connected(Exchange::CustomWs)).await;
+
+    let stream_kind = StreamKind::DepthAndTrades {
+        ticker_info,
+        depth_aggr: StreamTicksize::Client,
+        push_freq: PushFrequency::ServerDefault,
+    };
+
+    let mappings = &config.mappings;
+    let mut orderbook = LocalDepthCache::default();
+    // Diffs are meaningless until a snapshot gave them a base, unless the feed has none
+    let mut synced = mappings.snapshot.is_none();
+
+    loop {
+        let next = if emitter.has_pending() {
+            tokio::select! {
+                next = ws.next() => next,
+                () = tokio::time::sleep(PENDING_FLUSH_INTERVAL) => {
+                    emitter.try_flush(output)?;
+                    continue;
+                }
+            }
+        } else {
+            ws.next().await
+        };
+
+        let Some(msg_result) = next else {
+            break;
+        };
+        let msg = msg_result.map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Binary(bytes) => match String::from_utf8(bytes) {
+                Ok(text) => text,
+                Err(_) => continue,
+            },
+            Message::Ping(data) => {
+                ws.send(Message::Pong(data)).await.ok();
+                continue;
+            }
+            Message::Close(_) => break,
+            _ => continue,
+        };
+
+        crate::capture::record(ticker_info.ticker, text.as_bytes());
+
+        let parsed = match parse_message(mappings, &text, Some(&symbol), now_ms()) {
+            Ok(Some(parsed)) => parsed,
+            Ok(None) => continue,
+            Err(e) => {
+                log::debug!("Custom WS {}: skipping message: {e}", config.name);
+                continue;
+            }
+        };
+
+        let update = match parsed {
+            Parsed::Trades(trades) => {
+                trades_buffer.extend(trades.into_iter().map(|t| Trade {
+                    time: t.time,
+                    is_sell: t.is_sell,
+                    price: Price::from_f32(t.price).round_to_min_tick(ticker_info.min_ticksize),
+                    qty: t.qty,
+                }));
+
+                // Trade-only feeds have no book message to piggyback on
+                if mappings.snapshot.is_some() || mappings.diff.is_some() {
+                    continue;
+                }
+                None
+            }
+            Parsed::Snapshot(book) => {
+                synced = true;
+                Some(DepthUpdate::Snapshot(book.into_payload()))
+            }
+            Parsed::Diff(book) if synced => Some(DepthUpdate::Diff(book.into_payload())),
+            Parsed::Diff(_) => continue,
+        };
+
+        let time = match update {
+            Some(update) => {
+                orderbook.update(update, ticker_info.min_ticksize);
+                orderbook.time
+            }
+            None => trades_buffer.last().map_or_else(now_ms, |t| t.time),
+        };
+
+        emitter.push(
+            stream_kind,
+            time,
+            Arc::clone(&orderbook.depth),
+            std::mem::take(trades_buffer),
+            output,
+        )?;
+    }
+
+    Ok(())
+}
+
+type WsStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Opens the socket with the configured auth header and sends the subscribe message
+async fn connect(config: &CustomWsConfig, symbol: &str) -> Result<WsStream, AdapterError> {
+    use futures_util::SinkExt as _;
+    use tokio_tungstenite::tungstenite::{
+        Message, client::IntoClientRequest, http::HeaderValue, http::header::AUTHORIZATION,
+    };
+
+    let mut request = config
+        .url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| AdapterError::InvalidRequest(e.to_string()))?;
+
+    if let Some(auth) = config.auth_header.as_deref().filter(|h| !h.is_empty()) {
+        let value = HeaderValue::from_str(auth)
+            .map_err(|e| AdapterError::InvalidRequest(format!("Auth header: {e}")))?;
+        request.headers_mut().insert(AUTHORIZATION, value);
+    }
+
+    let (mut ws, _) = tokio_tungstenite::connect_async(request)
+        .await
+        .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+
+    if let Some(subscribe) = config.subscribe_message(symbol) {
+        ws.send(Message::Text(subscribe))
+            .await
+            .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+    }
+
+    Ok(ws)
+}
+
+/// Connects for the first symbol and returns up to `count` text frames received within
+/// `timeout`, so mappings can be checked against what the feed really sends
+pub async fn capture_messages(
+    config: &CustomWsConfig,
+    count: usize,
+    timeout: Duration,
+) -> Result<Vec<String>, AdapterError> {
+    use futures_util::StreamExt as _;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let symbol = config
+        .symbols
+        .first()
+        .ok_or_else(|| AdapterError::InvalidRequest("No symbols configured".to_string()))?;
+    let mut ws = connect(config, &symbol.symbol).await?;
+
+    let mut messages = vec![];
+
+    let _ = tokio::time::timeout(timeout, async {
+        while messages.len() < count
+            && let Some(msg) = ws.next().await
+        {
+            match msg {
+                Ok(Message::Text(text)) => messages.push(text),
+                Ok(Message::Close(_)) | Err(_) => break,
+                Ok(_) => {}
+            }
+        }
+    })
+    .await;
+
+    let _ = ws.close(None).await;
+
+    if messages.is_empty() {
+        return Err(AdapterError::WebsocketError(format!(
+            "No messages received within {}s",
+            timeout.as_secs()
+        )));
+    }
+    Ok(messages)
+}
+
+/// Live klines built from the feed's trades, custom feeds have no kline history to fetch
+pub fn connect_kline_stream(
+    ticker_info: TickerInfo,
+    timeframe: Timeframe,
+) -> impl Stream<Item = Event> {
+    use futures_util::StreamExt as _;
+
+    let stream_kind = StreamKind::Kline {
+        ticker_info,
+        timeframe,
+    };
+    let mut bar = BarBuilder::new(ticker_info, timeframe);
+
+    connect_market_stream(ticker_info, PushFrequency::ServerDefault).flat_map(move |event| {
+        let events = match event {
+            Event::DepthReceived(_, _, _, trades) => {
+                for trade in &trades {
+                    let volume = if trade.is_sell {
+                        (0.0, trade.qty)
+                    } else {
+                        (trade.qty, 0.0)
+                    };
+                    bar.update(trade.time, trade.price.to_f32(), volume);
+                }
+                bar.drain()
+                    .into_iter()
+                    .map(|(kline, status)| Event::KlineReceived(stream_kind, kline, status))
+                    .collect()
+            }
+            other @ (Event::Connected(_) | Event::Disconnected(..)) => vec![other],
+            _ => vec![],
+        };
+        futures_util::stream::iter(events)
+    })
+}
+
+// ============================================================================
+// Adapter
+// ============================================================================
+
+/// Serves every ticker of the registered configs, nothing can be fetched so all data is live
+pub struct CustomWsAdapter;
+
+impl ExchangeAdapter for CustomWsAdapter {
+    fn exchanges(&self) -> &'static [Exchange] {
+        &[Exchange::CustomWs]
+    }
+
+    // Whatever the mapped feed streams, nothing can be fetched
+    // Trade mappings name the taker's side
+    fn capabilities(&self, _exchange: Exchange) -> Capabilities {
+        Capabilities {
+            aggressor_side: true,
+            ..Capabilities::NONE
+        }
+    }
+
+    fn fetch_ticksize(
+        &self,
+        _market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, Option<TickerInfo>>> {
+        let tickers = ticker_infos();
+        Box::pin(async move { Ok(tickers) })
+    }
+
+    fn fetch_ticker_prices(
+        &self,
+        _market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, TickerStats>> {
+        Box::pin(async { Ok(HashMap::new()) })
+    }
+
+    // Bars are only built live from the feed's trades
+    fn fetch_klines(
+        &self,
+        _ticker_info: TickerInfo,
+        _timeframe: Timeframe,
+        _range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<Kline>> {
+        Box::pin(async { Ok(vec![]) })
+    }
+
+    fn market_stream(
+        &self,
+        ticker_info: TickerInfo,
+        _tick_mltp: Option<TickMultiplier>,
+        push_freq: PushFrequency,
+    ) -> BoxStream<'static, Event> {
+        connect_market_stream(ticker_info, push_freq).boxed()
+    }
+
+    fn kline_stream(
+        &self,
+        subs: Vec<(TickerInfo, Timeframe)>,
+        _market: MarketKind,
+    ) -> BoxStream<'static, Event> {
+        let streams = subs
+            .into_iter()
+            .map(|(ticker_info, timeframe)| connect_kline_stream(ticker_info, timeframe).boxed());
+        futures_util::stream::select_all(streams).boxed()
+    }
+
+    // One feed connection per chart, bars are built from its trades
+    fn kline_feed(&self) -> KlineFeed {
+        KlineFeed::PerSubscription
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MATCH: &str = r#"{"type":"match","trade_id":10,"side":"buy","size":"0.5","price":"100.25","product_id":"BTC-USD","time":"2024-01-01T00:00:01.500Z"}"#;
+    const SNAPSHOT: &str = r#"{"type":"snapshot","product_id":"BTC-USD","bids":[["100.00","1.5"],["99.99","2"]],"asks":[["100.01","0.3"]]}"#;
+    const L2UPDATE: &str = r#"{"type":"l2update","product_id":"BTC-USD","time":"2024-01-01T00:00:02Z","changes":[["buy","100.00","0"],["sell","100.02","4.0"]]}"#;
+
+    #[test]
+    fn select_walks_keys_and_indices() {
+        let value: Value = serde_json::from_str(r#"{"a":{"b":[1,{"c":"x"}]}}"#).unwrap();
+
+        assert_eq!(select(&value, "$.a.b[0]"), Some(&Value::from(1)));
+        assert_eq!(select(&value, "a.b[1].c"), Some(&Value::from("x")));
+        assert_eq!(select(&value, "$"), Some(&value));
+        assert_eq!(select(&value, "$.a.missing"), None);
+        assert!(parse_path("$.a[").is_err());
+        assert!(parse_path("$.a..b").is_err());
+    }
+
+    #[test]
+    fn coinbase_feed_parses_through_example_config() {
+        let config = CustomWsConfig::coinbase_example();
+        config.validate().unwrap();
+        let mappings = &config.mappings;
+
+        let Some(Parsed::Trades(trades)) =
+            parse_message(mappings, MATCH, Some("BTC-USD"), 0).unwrap()
+        else {
+            panic!("match should parse as a trade");
+        };
+        assert_eq!(
+            trades,
+            vec![ParsedTrade {
+                time: 1_704_067_201_500,
+                price: 100.25,
+                qty: 0.5,
+                is_sell: true,
+            }]
+        );
+
+        let Some(Parsed::Snapshot(book)) =
+            parse_message(mappings, SNAPSHOT, Some("BTC-USD"), 7).unwrap()
+        else {
+            panic!("snapshot should parse as a book");
+        };
+        assert_eq!(book.time, 7);
+        assert_eq!(book.bids, vec![(100.0, 1.5), (99.99, 2.0)]);
+        assert_eq!(book.asks, vec![(100.01, 0.3)]);
+
+        let Some(Parsed::Diff(diff)) =
+            parse_message(mappings, L2UPDATE, Some("BTC-USD"), 0).unwrap()
+        else {
+            panic!("l2update should parse as a diff");
+        };
+        assert_eq!(diff.bids, vec![(100.0, 0.0)]);
+        assert_eq!(diff.asks, vec![(100.02, 4.0)]);
+    }
+
+    #[test]
+    fn other_symbols_and_unknown_messages_are_ignored() {
+        let mappings = CustomWsConfig::coinbase_example().mappings;
+
+        assert!(
+            parse_message(&mappings, MATCH, Some("ETH-USD"), 0)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            parse_message(&mappings, r#"{"type":"heartbeat"}"#, None, 0)
+                .unwrap()
+                .is_none()
+        );
+        assert!(parse_message(&mappings, r#"{"type":"match","price":"1"}"#, None, 0).is_err());
+    }
+
+    #[test]
+    fn batched_trades_with_numeric_times() {
+        let mappings = Mappings {
+            trade: Some(TradeMapping {
+                matcher: MessageMatch {
+                    path: "$.e".to_string(),
+                    equals: "trades".to_string(),
+                },
+                items: Some("$.data".to_string()),
+                time: Some("$.T".to_string()),
+                price: "$.p".to_string(),
+                qty: "$.q".to_string(),
+                side: "$.m".to_string(),
+                sell_value: "true".to_string(),
+            }),
+            time_unit: TimeUnit::Seconds,
+            ..Mappings::default()
+        };
+        let msg = r#"{"e":"trades","data":[{"T":1.5,"p":2,"q":"3","m":true},{"T":2,"p":4,"q":1,"m":false}]}"#;
+
+        let Some(Parsed::Trades(trades)) = parse_message(&mappings, msg, None, 0).unwrap() else {
+            panic!("expected trades");
+        };
+        assert_eq!(trades.len(), 2);
+        assert_eq!((trades[0].time, trades[0].is_sell), (1_500, true));
+        assert_eq!((trades[1].time, trades[1].is_sell), (2_000, false));
+    }
+
+    #[test]
+    fn symbols_served_twice_are_left_to_the_first_config() {
+        let first = CustomWsConfig::coinbase_example();
+        let mut second = first.clone();
+        second.name = "Mirror".to_string();
+
+        let configs = deduplicated(vec![first, second]);
+
+        assert_eq!(configs.len(), 2);
+        assert_eq!(configs[0].symbols.len(), 1);
+        assert!(configs[1].symbols.is_empty());
+    }
+}
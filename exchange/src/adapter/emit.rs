@@ -0,0 +1,186 @@
+//! Delivery of depth events from socket readers to the UI, shared by the adapters that read
+//! their feeds themselves instead of through a per-exchange connector.
+
+use super::{AdapterError, Event, StreamKind};
+use crate::{Trade, depth::Depth};
+
+use iced_futures::futures::{SinkExt, channel::mpsc};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Depth events superseded by a newer snapshot before the UI could receive them.
+static DROPPED_DEPTH_EVENTS: AtomicU64 = AtomicU64::new(0);
+
+/// Total number of depth events dropped across all MT5 and custom WS streams because the
+/// consumer was slow
+pub fn dropped_depth_count() -> u64 {
+    DROPPED_DEPTH_EVENTS.load(Ordering::Relaxed)
+}
+
+/// How often an undelivered depth event is retried while the socket is quiet
+pub(super) const PENDING_FLUSH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Non-blocking delivery of depth events to the subscription channel.
+///
+/// When the channel is full only the newest book state is kept: a superseded snapshot is
+/// dropped, but its trades and level changes are carried over into the next event so neither is
+/// lost.
+#[derive(Default)]
+pub(super) struct DepthEmitter {
+    pending: Option<(StreamKind, u64, Arc<Depth>, Vec<Trade>)>,
+    dropped: u64,
+}
+
+impl DepthEmitter {
+    pub(super) fn has_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Events this emitter dropped for a slow consumer
+    pub(super) fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub(super) fn push(
+        &mut self,
+        stream: StreamKind,
+        time: u64,
+        mut depth: Arc<Depth>,
+        trades: Vec<Trade>,
+        output: &mut mpsc::Sender<Event>,
+    ) -> Result<(), AdapterError> {
+        let trades = match self.pending.take() {
+            Some((_, _, superseded, mut carried)) => {
+                if !superseded.changes.is_empty() {
+                    Arc::make_mut(&mut depth).changes.merge(&superseded.changes);
+                }
+                self.dropped += 1;
+                DROPPED_DEPTH_EVENTS.fetch_add(1, Ordering::Relaxed);
+
+                carried.extend(trades);
+                carried
+            }
+            None => trades,
+        };
+
+        self.pending = Some((stream, time, depth, trades));
+        self.try_flush(output)
+    }
+
+    pub(super) fn try_flush(
+        &mut self,
+        output: &mut mpsc::Sender<Event>,
+    ) -> Result<(), AdapterError> {
+        let Some((stream, time, depth, trades)) = self.pending.take() else {
+            return Ok(());
+        };
+
+        let event = Event::DepthReceived(stream, time, depth, trades.into_boxed_slice());
+
+        match output.try_send(event) {
+            Ok(()) => Ok(()),
+            Err(err) if err.is_full() => {
+                if let Event::DepthReceived(stream, time, depth, trades) = err.into_inner() {
+                    self.pending = Some((stream, time, depth, trades.into_vec()));
+                }
+                Ok(())
+            }
+            Err(_) => Err(AdapterError::WebsocketError(
+                "Event channel closed".to_string(),
+            )),
+        }
+    }
+
+    /// Wait for capacity and deliver the pending event, used once the socket is gone
+    pub(super) async fn flush(&mut self, output: &mut mpsc::Sender<Event>) {
+        if let Some((stream, time, depth, trades)) = self.pending.take() {
+            let _ = output
+                .send(Event::DepthReceived(
+                    stream,
+                    time,
+                    depth,
+                    trades.into_boxed_slice(),
+                ))
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::{Exchange, StreamTicksize};
+    use crate::{Price, PushFrequency, Ticker, TickerInfo};
+
+    #[tokio::test]
+    async fn test_slow_consumer_never_stalls_reader() {
+        use iced_futures::futures::StreamExt as _;
+
+        const EVENTS: u64 = 500;
+        const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(30);
+
+        let ticker_info = TickerInfo::new(
+            Ticker::new("EURUSD", Exchange::MetaTrader5),
+            0.00001,
+            0.01,
+            Some(100_000.0),
+        );
+        let stream_kind = StreamKind::DepthAndTrades {
+            ticker_info,
+            depth_aggr: StreamTicksize::Client,
+            push_freq: PushFrequency::ServerDefault,
+        };
+
+        let (mut output, mut rx) = mpsc::channel::<Event>(1);
+
+        let consumer = tokio::spawn(async move {
+            let mut received = vec![];
+            while let Some(event) = rx.next().await {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+                received.push(event);
+            }
+            received
+        });
+
+        let mut emitter = DepthEmitter::default();
+        let started = std::time::Instant::now();
+
+        for time in 0..EVENTS {
+            let trades = vec![
+                Trade {
+                    time,
+                    is_sell: time % 2 == 0,
+                    price: Price::from_f32(1.0),
+                    qty: 1.0,
+                };
+                2
+            ];
+            emitter
+                .push(stream_kind, time, Arc::default(), trades, &mut output)
+                .unwrap();
+            tokio::task::yield_now().await;
+        }
+
+        assert!(started.elapsed() < HEARTBEAT_TIMEOUT);
+
+        emitter.flush(&mut output).await;
+        drop(output);
+
+        let received = consumer.await.unwrap();
+        let mut trade_count = 0;
+        let mut last_time = 0;
+
+        for event in &received {
+            if let Event::DepthReceived(_, time, _, trades) = event {
+                trade_count += trades.len();
+                last_time = *time;
+            }
+        }
+
+        assert!(emitter.dropped > 0);
+        assert_eq!(received.len() as u64 + emitter.dropped, EVENTS);
+        assert_eq!(trade_count as u64, EVENTS * 2);
+        assert_eq!(last_time, EVENTS - 1);
+    }
+}
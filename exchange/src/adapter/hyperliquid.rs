@@ -1,16 +1,18 @@
 use super::{
     super::{
-        Exchange, Kline, MarketKind, Price, PushFrequency, SizeUnit, StreamKind, TickMultiplier,
-        Ticker, TickerInfo, TickerStats, Timeframe, Trade,
+        BarStatus, Exchange, Kline, MarketKind, Price, PushFrequency, SizeUnit, StreamKind,
+        TickMultiplier, Ticker, TickerInfo, TickerStats, Timeframe, Trade,
         connect::{State, connect_ws},
         de_string_to_f32,
         depth::{DeOrder, DepthPayload, DepthUpdate, LocalDepthCache},
         limiter::{self, RateLimiter},
         volume_size_unit,
     },
-    AdapterError, Event,
+    AdapterError, AdapterFuture, Capabilities, Event, ExchangeAdapter,
 };
 
+use futures_util::{FutureExt as _, StreamExt as _, stream::BoxStream};
+
 use fastwebsockets::{FragmentCollector, Frame, OpCode};
 use hyper::upgrade::Upgraded;
 use hyper_util::rt::TokioIo;
@@ -905,6 +907,7 @@ pub fn connect_market_stream(
                     match websocket.read_frame().await {
                         Ok(msg) => match msg.opcode {
                             OpCode::Text => {
+                                crate::capture::record(ticker, &msg.payload);
                                 if let Ok(stream_data) = parse_websocket_message(&msg.payload) {
                                     match stream_data {
                                         StreamData::Trade(trades) => {
@@ -1104,7 +1107,14 @@ pub fn connect_kline_stream(
                                     ticker_info: *ticker_info,
                                     timeframe: *timeframe,
                                 };
-                                let _ = output.send(Event::KlineReceived(stream_kind, kline)).await;
+                                // Pushes carry no close flag, each one is the bar still open
+                                let _ = output
+                                    .send(Event::KlineReceived(
+                                        stream_kind,
+                                        kline,
+                                        BarStatus::Forming,
+                                    ))
+                                    .await;
                             }
                         }
                         OpCode::Close => {
@@ -1211,6 +1221,70 @@ async fn fetch_orderbook(
     })
 }
 
+/// Registered for the Hyperliquid spot and linear markets
+pub struct HyperliquidAdapter;
+
+impl ExchangeAdapter for HyperliquidAdapter {
+    fn exchanges(&self) -> &'static [Exchange] {
+        &[Exchange::HyperliquidLinear, Exchange::HyperliquidSpot]
+    }
+
+    // Snapshots only, aggregated on the server with `nSigFigs`
+    fn capabilities(&self, _exchange: Exchange) -> Capabilities {
+        Capabilities {
+            server_aggregation: true,
+            ticker_stats: true,
+            aggressor_side: true,
+            ..Capabilities::NONE
+        }
+    }
+
+    fn fetch_ticksize(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, Option<TickerInfo>>> {
+        fetch_ticksize(market).boxed()
+    }
+
+    fn fetch_ticker_prices(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, TickerStats>> {
+        fetch_ticker_prices(market).boxed()
+    }
+
+    fn fetch_klines(
+        &self,
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<Kline>> {
+        fetch_klines(ticker_info, timeframe, range).boxed()
+    }
+
+    fn market_stream(
+        &self,
+        ticker_info: TickerInfo,
+        tick_mltp: Option<TickMultiplier>,
+        push_freq: PushFrequency,
+    ) -> BoxStream<'static, Event> {
+        connect_market_stream(ticker_info, tick_mltp, push_freq).boxed()
+    }
+
+    fn kline_stream(
+        &self,
+        subs: Vec<(TickerInfo, Timeframe)>,
+        market: MarketKind,
+    ) -> BoxStream<'static, Event> {
+        connect_kline_stream(subs, market).boxed()
+    }
+
+    /// Subscriptions are capped at 1000 per IP, all of them on this connection
+    fn max_kline_streams(&self, _exchange: Exchange) -> Option<usize> {
+        Some(1000)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
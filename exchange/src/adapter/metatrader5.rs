@@ -22,19 +22,59 @@
 //! - Optional TLS encryption
 //! - Timestamp-based replay attack prevention
 
-use super::{AdapterError, Event, StreamKind, StreamTicksize};
+mod ack;
+pub mod credentials;
+mod diagnosis;
+#[cfg(feature = "fault-injection")]
+pub mod faults;
+mod multiplex;
+mod outbound;
+pub mod price_basis;
+pub mod rates;
+mod reconnect;
+mod rest;
+pub mod retired;
+pub mod suffix;
+pub mod terminals;
+pub mod tick_override;
+mod timezone;
+mod version;
+
+use super::emit::{DepthEmitter, PENDING_FLUSH_INTERVAL};
+use diagnosis::Symptom;
+use multiplex::Feed;
+use price_basis::PriceBasis;
+pub use reconnect::{Cause as ReconnectCause, Retry};
+pub use terminals::Terminal;
+pub use timezone::{Dst, ServerTimezone};
+pub use version::{MIN_PROXY_VERSION, ServerInfo, Version};
+
+use super::{
+    AdapterError, AdapterFuture, Capabilities, Event, Exchange, ExchangeAdapter, KlineFeed,
+    MarketKind, StreamKind, StreamTicksize,
+};
 use crate::{
-    Kline, Price, PushFrequency, Ticker, TickerInfo, TickerStats, Timeframe, Trade,
+    Kline, Price, PushFrequency, SizeUnit, SourceId, TickMultiplier, Ticker, TickerInfo,
+    TickerStats, Timeframe, Trade,
+    calendar::CalendarEvent,
+    conversion::{self, LotConverter},
     depth::{DepthPayload, DepthUpdate, LocalDepthCache},
+    governor::Governor,
+    market_state::MarketState,
+    tick_rule::TickTiers,
+    volume_size_unit,
 };
 
+use futures_util::{StreamExt as _, stream::BoxStream};
 use iced_futures::{
     futures::{SinkExt, Stream, channel::mpsc},
     stream,
 };
+use rustc_hash::FxHashSet;
 use serde::{Deserialize, Serialize};
-use std::sync::RwLock;
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::{borrow::Cow, collections::HashMap, sync::Arc, time::Duration};
 
 // ============================================================================
 // Global MT5 Configuration Storage
@@ -44,10 +84,12 @@ use std::{collections::HashMap, sync::Arc, time::Duration};
 /// This allows adapter::fetch_klines and other global functions to access the MT5 config.
 static GLOBAL_MT5_CONFIG: RwLock<Option<Mt5Config>> = RwLock::new(None);
 
-/// Set the global MT5 configuration (called when user saves config)
+/// Set the global MT5 configuration (called when user saves config), MT5 tickers are served
+/// through this connection from then on
 pub fn set_global_config(config: Mt5Config) {
     if let Ok(mut global) = GLOBAL_MT5_CONFIG.write() {
-        log::info!("MT5 global config set for server: {}", config.server_addr);
+        log::info!(mt5 = config.server_addr.as_str(); "MT5 global config set");
+        super::register_adapter(Arc::new(Mt5Adapter::new(config.clone())));
         *global = Some(config);
     }
 }
@@ -57,26 +99,369 @@ pub fn get_global_config() -> Option<Mt5Config> {
     GLOBAL_MT5_CONFIG.read().ok().and_then(|g| g.clone())
 }
 
+/// Configs of the saved connections set up this session by [`SourceId`], for panes pinned to
+/// one other than the active connection
+static NAMED_CONFIGS: LazyLock<RwLock<HashMap<SourceId, (String, Mt5Config)>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Names of the connections that listed symbols this session
+static CONNECTION_NAMES: LazyLock<RwLock<HashMap<SourceId, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Makes the connection `name` available to panes pinned to it
+pub fn register_connection(name: &str, config: Mt5Config) {
+    let source = name_connection(name);
+    if let Ok(mut configs) = NAMED_CONFIGS.write() {
+        configs.insert(source, (name.to_string(), config));
+    }
+}
+
+/// Remembers the name of a connection symbols are listed by, returns the id its tickers carry
+pub fn name_connection(name: &str) -> SourceId {
+    let source = SourceId::of(name);
+    if let Ok(mut names) = CONNECTION_NAMES.write() {
+        names.entry(source).or_insert_with(|| name.to_string());
+    }
+    source
+}
+
+/// Name of the connection `source` stands for, once it listed symbols or was set up
+pub fn connection_name(source: SourceId) -> Option<String> {
+    CONNECTION_NAMES.read().ok()?.get(&source).cloned()
+}
+
+/// The connection a ticker listed by one other than the active connection streams through
+pub(super) fn listed_elsewhere(ticker: &Ticker) -> Option<SourceId> {
+    let listed_by = ticker.connection()?;
+    let active = get_global_config().map(|config| config.source());
+
+    (active != Some(listed_by)).then_some(listed_by)
+}
+
+/// Whether panes pinned to `source` have a connection to stream from
+pub fn has_connection(source: SourceId) -> bool {
+    NAMED_CONFIGS
+        .read()
+        .is_ok_and(|configs| configs.contains_key(&source))
+}
+
+/// Names of the connections panes can be pinned to, sorted
+pub fn connection_names() -> Vec<String> {
+    let mut names: Vec<String> = NAMED_CONFIGS
+        .read()
+        .map(|configs| configs.values().map(|(name, _)| name.clone()).collect())
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+/// Credentials of every connection set up this session, including ones never serialized, for
+/// keeping them out of what leaves the app
+pub fn session_secrets() -> Vec<String> {
+    let named = NAMED_CONFIGS
+        .read()
+        .map(|configs| configs.values().map(|(_, c)| c.clone()).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    get_global_config()
+        .into_iter()
+        .chain(named)
+        .flat_map(|config| {
+            let mut secrets = vec![config.api_key.clone(), config.api_secret.clone()];
+            // What references resolve to is as secret as the values themselves
+            if let Ok((key, secret)) = config.credentials() {
+                secrets.extend([key, secret]);
+            }
+            match config.auth_mode {
+                AuthMode::Hmac => {}
+                AuthMode::BearerToken(token) => secrets.push(token),
+                AuthMode::Headers(headers) => {
+                    secrets.extend(headers.into_iter().map(|(_, value)| value));
+                }
+            }
+            secrets
+        })
+        .filter(|secret| !secret.is_empty())
+        .collect()
+}
+
+/// Config of the connection `ticker_info` streams through, the one it's pinned to or listed by
+/// and else the active one. `None` for other exchanges' tickers.
+pub fn config_for(ticker_info: &TickerInfo) -> Option<Mt5Config> {
+    if ticker_info.exchange() != Exchange::MetaTrader5 {
+        return None;
+    }
+
+    match ticker_info
+        .source
+        .or_else(|| listed_elsewhere(&ticker_info.ticker))
+    {
+        Some(source) => NAMED_CONFIGS
+            .read()
+            .ok()?
+            .get(&source)
+            .map(|(_, config)| config.clone()),
+        None => get_global_config(),
+    }
+}
+
+pub(super) fn pinned_adapter(source: SourceId) -> Option<Arc<dyn ExchangeAdapter>> {
+    let configs = NAMED_CONFIGS.read().ok()?;
+    let (_, config) = configs.get(&source)?;
+
+    Some(Arc::new(Mt5Adapter::new(config.clone())))
+}
+
+/// The soonest reconnect a dropped proxy connection is waiting for, `None` while all are up
+pub fn pending_reconnect() -> Option<Retry> {
+    multiplex::pending_retry()
+}
+
+/// Retries every dropped proxy connection right away, with the backoff started over, also one
+/// that was stopped
+pub fn reconnect_now() {
+    multiplex::retry_now();
+}
+
+/// Stops dropped proxy connections from retrying until [`reconnect_now`]
+pub fn stop_reconnecting() {
+    multiplex::stop_retrying();
+}
+
+/// Sends every proxy connection's close frame, returns how many were dropped after `timeout`
+/// without confirming
+pub async fn shutdown(timeout: Duration) -> usize {
+    multiplex::shutdown(timeout).await
+}
+
+/// Panes streaming through the sockets of `config`
+pub fn live_streams(config: &Mt5Config) -> usize {
+    multiplex::live_streams(config)
+}
+
+/// Makes `config` the active connection. Open sockets of the one it replaces are handed over
+/// when the edit doesn't change how they connect. Returns whether panes streaming through them
+/// have to restart to pick the edit up instead.
+pub fn apply_config(config: Mt5Config) -> bool {
+    let restart = match get_global_config() {
+        Some(previous) if previous.material_changes(&config).is_empty() => {
+            multiplex::reconfigure(&previous, &config);
+            false
+        }
+        Some(previous) => multiplex::live_streams(&previous) > 0,
+        None => false,
+    };
+
+    set_global_config(config);
+    restart
+}
+
 /// Clear the global MT5 configuration
 pub fn clear_global_config() {
     if let Ok(mut global) = GLOBAL_MT5_CONFIG.write() {
+        super::unregister_adapter(super::Exchange::MetaTrader5);
         *global = None;
     }
 }
 
+/// Error `code` the proxy sends when a subscribed symbol doesn't exist on the terminal
+const SYMBOL_NOT_FOUND_CODE: &str = "symbol_not_found";
+
+/// Prefix of the disconnect reason when a stream ends because its symbol is gone
+pub const SYMBOL_NOT_FOUND: &str = "Symbol not found";
+
+/// Whether a stream disconnect reason means the cached symbol list is out of date
+pub fn is_symbol_not_found(reason: &str) -> bool {
+    reason.contains(SYMBOL_NOT_FOUND)
+}
+
+/// Error `code` of a rejected subscription whose symbol isn't in the terminal's Market Watch
+const NOT_IN_MARKET_WATCH_CODE: &str = "not_in_market_watch";
+
+/// Prefix of the disconnect reason when the proxy refused some of a symbol's channels
+pub const SUBSCRIPTION_REJECTED: &str = "Subscription rejected";
+
+/// Prefix of the disconnect reason when a proxy that answers subscriptions didn't answer one
+pub const SUBSCRIPTION_UNCONFIRMED: &str = "Subscription unconfirmed";
+
+/// Prefix of the disconnect reason when the proxy revoked the session's credentials
+pub const SESSION_REVOKED: &str = "Session revoked by the proxy";
+
+/// Whether a stream disconnect reason means the credentials need checking before reconnecting
+pub fn is_session_revoked(reason: &str) -> bool {
+    reason.starts_with(SESSION_REVOKED)
+}
+
+/// Prefix of connection errors where something answered the upgrade request with plain HTTP,
+/// typically a network that blocks WebSockets
+const UPGRADE_REFUSED: &str = "WebSocket upgrade refused";
+
+fn upgrade_error(error: tokio_tungstenite::tungstenite::Error, server_addr: &str) -> AdapterError {
+    use tokio_tungstenite::tungstenite::Error;
+
+    match (&error, Symptom::of_upgrade(&error)) {
+        (_, Some(symptom)) => AdapterError::WebsocketError(format!(
+            "{UPGRADE_REFUSED}: {}",
+            diagnosis::diagnose(server_addr, &symptom)
+        )),
+        (Error::Http(_) | Error::Protocol(_), None) => {
+            AdapterError::WebsocketError(format!("{UPGRADE_REFUSED}: {error}"))
+        }
+        _ => AdapterError::WebsocketError(error.to_string()),
+    }
+}
+
+/// Whether a one-off request should retry over the HTTP fallback
+fn is_upgrade_refused(error: &AdapterError) -> bool {
+    matches!(error, AdapterError::WebsocketError(reason) if reason.starts_with(UPGRADE_REFUSED))
+}
+
+// ============================================================================
+// Server Clock
+// ============================================================================
+
+/// Proxy clock minus local clock in ms, measured at the last successful auth handshake
+static SERVER_TIME_OFFSET_MS: AtomicI64 = AtomicI64::new(0);
+
+fn record_server_time(server_time: Option<u64>) {
+    if let Some(server_time) = server_time {
+        let offset = server_time as i64 - chrono::Utc::now().timestamp_millis();
+        SERVER_TIME_OFFSET_MS.store(offset, Ordering::Relaxed);
+    }
+}
+
+/// Offset to add to the local unix time in ms to get the MT5 proxy's time
+pub fn server_time_offset_ms() -> i64 {
+    SERVER_TIME_OFFSET_MS.load(Ordering::Relaxed)
+}
+
+// ============================================================================
+// Server Versions
+// ============================================================================
+
+/// What each proxy reported at its latest handshake, by address. Entries restored from the last
+/// session are marked `false` until a handshake confirms them.
+static SERVER_INFO: LazyLock<Mutex<HashMap<String, (ServerInfo, bool)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Reports that differ from what was known, waiting for the app to pick them up
+static SERVER_INFO_UPDATES: Mutex<Vec<(String, ServerInfo)>> = Mutex::new(Vec::new());
+
+/// Timezone each proxy's trade server stamps its data in, by address, read for every frame
+static SERVER_TIMEZONES: LazyLock<RwLock<HashMap<String, ServerTimezone>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+fn set_server_timezone(server_addr: &str, timezone: Option<ServerTimezone>, replace: bool) {
+    let mut timezones = SERVER_TIMEZONES.write().unwrap_or_else(|e| e.into_inner());
+    match timezone {
+        Some(timezone) if replace || !timezones.contains_key(server_addr) => {
+            timezones.insert(server_addr.to_string(), timezone);
+        }
+        None if replace => {
+            timezones.remove(server_addr);
+        }
+        _ => {}
+    }
+}
+
+/// The timezone the proxy at `server_addr` reported for its trade server, UTC if none
+pub fn server_timezone(server_addr: &str) -> ServerTimezone {
+    SERVER_TIMEZONES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(server_addr)
+        .copied()
+        .unwrap_or(ServerTimezone::UTC)
+}
+
+fn record_server_info(server_addr: &str, info: ServerInfo) {
+    set_server_timezone(server_addr, info.server_timezone, true);
+
+    let mut known = SERVER_INFO.lock().unwrap_or_else(|e| e.into_inner());
+    if known.get(server_addr) == Some(&(info.clone(), true)) {
+        return;
+    }
+
+    log::info!(
+        mt5 = server_addr;
+        "MT5 proxy version {}, EA version {}",
+        info.proxy_version.as_deref().unwrap_or("unknown"),
+        info.ea_version.as_deref().unwrap_or("unknown"),
+    );
+    known.insert(server_addr.to_string(), (info.clone(), true));
+    SERVER_INFO_UPDATES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((server_addr.to_string(), info));
+}
+
+/// Seeds what a proxy reported in an earlier session, shown until it reports again
+pub fn restore_server_info(server_addr: &str, info: ServerInfo) {
+    set_server_timezone(server_addr, info.server_timezone, false);
+    SERVER_INFO
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(server_addr.to_string())
+        .or_insert((info, false));
+}
+
+/// Versions the proxy at `server_addr` reported, or last reported in an earlier session
+pub fn server_info(server_addr: &str) -> Option<ServerInfo> {
+    SERVER_INFO
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(server_addr)
+        .map(|(info, _)| info.clone())
+}
+
+/// Handshakes since the last call that reported other versions than known, by proxy address
+pub fn take_server_info_updates() -> Vec<(String, ServerInfo)> {
+    std::mem::take(
+        &mut *SERVER_INFO_UPDATES
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()),
+    )
+}
+
 // ============================================================================
 // Configuration Types
 // ============================================================================
 
+/// How the client proves itself to the proxy, or to a reverse proxy in front of it
+#[derive(Clone, PartialEq, Eq, Default, Deserialize, Serialize)]
+pub enum AuthMode {
+    /// In-band handshake signed with the API key and secret
+    #[default]
+    Hmac,
+    /// `Authorization: Bearer` header on the WebSocket upgrade
+    BearerToken(String),
+    /// Custom headers on the WebSocket upgrade, e.g. Cloudflare Access service tokens
+    Headers(Vec<(String, String)>),
+}
+
+/// Header values are credentials, only names are printed
+impl std::fmt::Debug for AuthMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthMode::Hmac => write!(f, "Hmac"),
+            AuthMode::BearerToken(_) => write!(f, "BearerToken(<redacted>)"),
+            AuthMode::Headers(headers) => f
+                .debug_tuple("Headers")
+                .field(&headers.iter().map(|(name, _)| name).collect::<Vec<_>>())
+                .finish(),
+        }
+    }
+}
+
 /// MT5 server connection configuration
 #[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
 pub struct Mt5Config {
     /// Server address (e.g., "192.168.1.100:9876" or "localhost:9876")
     pub server_addr: String,
-    /// API Key for authentication
+    /// API Key for authentication, or `env:NAME` to read it from the environment
     pub api_key: String,
-    /// API Secret for HMAC signature (not serialized for security)
-    #[serde(skip_serializing, default)]
+    /// API Secret for HMAC signature, only serialized when it's an `env:NAME` reference
+    #[serde(skip_serializing_if = "credentials::is_literal", default)]
     pub api_secret: String,
     /// Whether to use TLS (wss://)
     #[serde(default)]
@@ -87,6 +472,88 @@ pub struct Mt5Config {
     /// Auto-reconnect on disconnect
     #[serde(default = "default_true")]
     pub auto_reconnect: bool,
+    /// Currency of the trading account, e.g. "USD". Sizes shown in quote currency are converted
+    /// into it once a reference rate is streamed, empty keeps each symbol's quote currency
+    #[serde(default)]
+    pub account_currency: String,
+    /// Holds credentials, so like `api_secret` it is not serialized
+    #[serde(skip_serializing, default)]
+    pub auth_mode: AuthMode,
+    /// Send the HMAC handshake as well when authenticating with headers
+    #[serde(default)]
+    pub hmac_with_headers: bool,
+    /// Drop a symbol's depth and trades to quotes while its market is closed, until shortly
+    /// before it reopens
+    #[serde(default = "default_true")]
+    pub pause_when_closed: bool,
+    /// Terminal the connection streams from, for proxies bridging several. `None` for proxies
+    /// with a single unnamed one, requests then leave the field out.
+    #[serde(default)]
+    pub terminal_id: Option<String>,
+    /// Point the terminal's chart at the symbol of the focused pane streaming through this
+    /// connection, off unless asked for
+    #[serde(default)]
+    pub sync_terminal_chart: bool,
+}
+
+/// An edit of a connection that open streams only pick up by restarting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MaterialChange {
+    ServerAddress {
+        from: String,
+        to: String,
+    },
+    Tls {
+        enabled: bool,
+    },
+    Timeout {
+        secs: u64,
+    },
+    AccountCurrency {
+        from: String,
+        to: String,
+    },
+    Terminal {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// Key, secret or how they are sent, never shown
+    Credentials,
+}
+
+impl std::fmt::Display for MaterialChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let or_none = |currency: &str| {
+            if currency.is_empty() {
+                "none".to_string()
+            } else {
+                currency.to_string()
+            }
+        };
+
+        match self {
+            MaterialChange::ServerAddress { from, to } => {
+                write!(f, "Server address: {from} \u{2192} {to}")
+            }
+            MaterialChange::Tls { enabled } => {
+                write!(f, "TLS: {}", if *enabled { "on" } else { "off" })
+            }
+            MaterialChange::Timeout { secs } => write!(f, "Connection timeout: {secs}s"),
+            MaterialChange::AccountCurrency { from, to } => write!(
+                f,
+                "Account currency: {} \u{2192} {}",
+                or_none(from),
+                or_none(to)
+            ),
+            MaterialChange::Terminal { from, to } => write!(
+                f,
+                "Terminal: {} \u{2192} {}",
+                from.as_deref().unwrap_or("default"),
+                to.as_deref().unwrap_or("default")
+            ),
+            MaterialChange::Credentials => write!(f, "Credentials"),
+        }
+    }
 }
 
 fn default_timeout() -> u64 {
@@ -106,96 +573,252 @@ impl Default for Mt5Config {
             use_tls: false,
             timeout_secs: 30,
             auto_reconnect: true,
+            account_currency: String::new(),
+            auth_mode: AuthMode::Hmac,
+            hmac_with_headers: false,
+            pause_when_closed: true,
+            terminal_id: None,
+            sync_terminal_chart: false,
         }
     }
 }
 
 impl Mt5Config {
+    /// Name the connection is saved under, symbols of each terminal are listed apart
+    pub fn connection_name(&self) -> String {
+        match &self.terminal_id {
+            Some(terminal) => format!("MT5 {} · {terminal}", self.server_addr),
+            None => format!("MT5 {}", self.server_addr),
+        }
+    }
+
+    /// Id the tickers this connection lists carry
+    pub fn source(&self) -> SourceId {
+        SourceId::of(&self.connection_name())
+    }
+
+    /// What `edited` changes that streams opened with this config only pick up by restarting.
+    /// Reconnecting and pausing closed markets are read as they go and aren't listed.
+    pub fn material_changes(&self, edited: &Mt5Config) -> Vec<MaterialChange> {
+        let mut changes = vec![];
+
+        if self.server_addr != edited.server_addr {
+            changes.push(MaterialChange::ServerAddress {
+                from: self.server_addr.clone(),
+                to: edited.server_addr.clone(),
+            });
+        }
+        if self.use_tls != edited.use_tls {
+            changes.push(MaterialChange::Tls {
+                enabled: edited.use_tls,
+            });
+        }
+        if self.timeout_secs != edited.timeout_secs {
+            changes.push(MaterialChange::Timeout {
+                secs: edited.timeout_secs,
+            });
+        }
+        if self.account_currency != edited.account_currency {
+            changes.push(MaterialChange::AccountCurrency {
+                from: self.account_currency.clone(),
+                to: edited.account_currency.clone(),
+            });
+        }
+        if self.terminal_id != edited.terminal_id {
+            changes.push(MaterialChange::Terminal {
+                from: self.terminal_id.clone(),
+                to: edited.terminal_id.clone(),
+            });
+        }
+        if self.api_key != edited.api_key
+            || self.api_secret != edited.api_secret
+            || self.auth_mode != edited.auth_mode
+            || self.hmac_with_headers != edited.hmac_with_headers
+        {
+            changes.push(MaterialChange::Credentials);
+        }
+
+        changes
+    }
+
+    /// Lot conversion for `ticker_info` while sizes are shown in quote currency
+    fn lot_converter(&self, ticker_info: &TickerInfo) -> Option<LotConverter> {
+        (volume_size_unit() == SizeUnit::Quote)
+            .then(|| LotConverter::for_ticker(ticker_info, Some(&self.account_currency)))
+    }
+
     /// Create WebSocket URL from config (for client endpoint)
     pub fn ws_url(&self) -> String {
         let protocol = if self.use_tls { "wss" } else { "ws" };
         format!("{}://{}/client", protocol, self.server_addr)
     }
 
+    /// What sockets, symbol lists and history requests are shared by, the client endpoint and
+    /// the terminal picked on it
+    pub fn endpoint(&self) -> String {
+        match &self.terminal_id {
+            Some(terminal) => format!("{}#{terminal}", self.ws_url()),
+            None => self.ws_url(),
+        }
+    }
+
+    /// `request` addressed to the picked terminal, unchanged when none is
+    fn scoped(&self, mut request: serde_json::Value) -> serde_json::Value {
+        if let Some(terminal) = &self.terminal_id {
+            request["terminal_id"] = serde_json::json!(terminal);
+        }
+        request
+    }
+
+    /// `text` with this connection's credentials and any HMAC signature masked. Anything that
+    /// may echo a request or a proxy response goes through this before it's logged.
+    pub fn redact(&self, text: &str) -> String {
+        let resolved = self.credentials().ok();
+        let mut secrets = vec![self.api_key.as_str(), self.api_secret.as_str()];
+        if let Some((key, secret)) = &resolved {
+            secrets.extend([key.as_str(), secret.as_str()]);
+        }
+        match &self.auth_mode {
+            AuthMode::Hmac => {}
+            AuthMode::BearerToken(token) => secrets.push(token),
+            AuthMode::Headers(headers) => secrets.extend(headers.iter().map(|(_, v)| v.as_str())),
+        }
+
+        let mut redacted = text.to_string();
+        for secret in secrets.into_iter().filter(|s| !s.is_empty()) {
+            redacted = redacted.replace(secret, REDACTED);
+        }
+        redact_signatures(&redacted)
+    }
+
+    /// API key and secret with `env:` references resolved, failing on an unset variable
+    pub fn credentials(&self) -> Result<(String, String), String> {
+        Ok((
+            credentials::resolve(&self.api_key, "API key")?,
+            credentials::resolve(&self.api_secret, "API secret")?,
+        ))
+    }
+
+    /// Whether the in-band HMAC handshake follows the WebSocket upgrade
+    pub fn sends_hmac(&self) -> bool {
+        self.auth_mode == AuthMode::Hmac || self.hmac_with_headers
+    }
+
+    /// Upgrade request for the client endpoint, carrying the headers of the auth mode
+    fn client_request(&self) -> Result<ClientRequest, AdapterError> {
+        use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+        use tokio_tungstenite::tungstenite::http::{HeaderName, HeaderValue, header};
+
+        let mut request = self
+            .ws_url()
+            .into_client_request()
+            .map_err(|e| AdapterError::InvalidRequest(e.to_string()))?;
+
+        // Errors name the header at most, never its value
+        let value = |name: &str, value: &str| {
+            HeaderValue::from_str(value)
+                .map_err(|_| AdapterError::InvalidRequest(format!("Invalid value for {name}")))
+        };
+
+        match &self.auth_mode {
+            AuthMode::Hmac => {}
+            AuthMode::BearerToken(token) => {
+                let bearer = value("Authorization", &format!("Bearer {}", token.trim()))?;
+                request.headers_mut().insert(header::AUTHORIZATION, bearer);
+            }
+            AuthMode::Headers(headers) => {
+                for (name, header_value) in headers {
+                    let name = name.trim();
+                    let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|_| {
+                        AdapterError::InvalidRequest(format!("Invalid header name {name}"))
+                    })?;
+                    request
+                        .headers_mut()
+                        .append(header_name, value(name, header_value.trim())?);
+                }
+            }
+        }
+
+        Ok(request)
+    }
+
     /// Validate configuration
     pub fn validate(&self) -> Result<(), String> {
         if self.server_addr.is_empty() {
             return Err("Server address is required".to_string());
         }
-        if self.api_key.is_empty() {
-            return Err("API key is required".to_string());
+
+        match &self.auth_mode {
+            AuthMode::Hmac => {}
+            AuthMode::BearerToken(token) => {
+                if token.trim().is_empty() {
+                    return Err("Bearer token is required".to_string());
+                }
+            }
+            AuthMode::Headers(headers) => {
+                if headers.is_empty() {
+                    return Err("At least one header is required".to_string());
+                }
+                if headers
+                    .iter()
+                    .any(|(name, value)| name.trim().is_empty() || value.trim().is_empty())
+                {
+                    return Err("Every header needs a name and a value".to_string());
+                }
+            }
         }
-        if self.api_secret.is_empty() {
-            return Err("API secret is required".to_string());
+
+        if self.sends_hmac() {
+            if self.api_key.is_empty() {
+                return Err("API key is required".to_string());
+            }
+            if self.api_secret.is_empty() {
+                return Err("API secret is required".to_string());
+            }
+            self.credentials()?;
         }
-        Ok(())
-    }
 
-    /// Test connection to proxy server
-    pub async fn test_connection(&self) -> Result<(), String> {
-        use tokio_tungstenite::tungstenite::Message;
+        self.client_request().map(|_| ()).map_err(|e| e.to_string())
+    }
 
+    /// Test connection to proxy server, returning the versions it reported
+    pub async fn test_connection(&self) -> Result<ServerInfo, String> {
         // Validate config first
         self.validate()?;
 
         let url = self.ws_url();
-        log::info!("Testing connection to {}", url);
+        log::info!(mt5 = self.server_addr.as_str(); "Testing connection to {}", url);
+
+        let request = self.client_request().map_err(|e| e.to_string())?;
 
         // Try to connect
         let connect_result = tokio::time::timeout(
             Duration::from_secs(self.timeout_secs),
-            tokio_tungstenite::connect_async(&url),
+            tokio_tungstenite::connect_async(request),
         )
         .await
         .map_err(|_| "Connection timeout".to_string())?
-        .map_err(|e| format!("WebSocket error: {}", e))?;
+        .map_err(|e| match Symptom::of_upgrade(&e) {
+            Some(symptom) => diagnosis::diagnose(&self.server_addr, &symptom),
+            None => format!("WebSocket error: {}", e),
+        })?;
 
         let (mut ws, _response) = connect_result;
 
-        // Send auth message
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-
-        let signature = compute_hmac_signature(&self.api_key, timestamp, &self.api_secret);
-
-        let auth_msg = serde_json::json!({
-            "type": "auth",
-            "api_key": self.api_key,
-            "timestamp": timestamp,
-            "signature": signature
-        });
-
-        use futures_util::SinkExt as _;
-        ws.send(Message::Text(auth_msg.to_string()))
-            .await
-            .map_err(|e| format!("Failed to send auth: {}", e))?;
-
-        // Wait for auth response
-        use futures_util::StreamExt as _;
-        let response = tokio::time::timeout(Duration::from_secs(10), ws.next())
-            .await
-            .map_err(|_| "Auth response timeout".to_string())?
-            .ok_or("Connection closed")?
-            .map_err(|e| format!("Read error: {}", e))?;
-
-        if let Message::Text(text) = response {
-            let resp: serde_json::Value =
-                serde_json::from_str(&text).map_err(|e| format!("Invalid JSON: {}", e))?;
-
-            if resp["type"] == "auth_response" {
-                if resp["success"] == true {
-                    log::info!("Connection test successful");
-                    return Ok(());
-                } else {
-                    let error = resp["error"].as_str().unwrap_or("Unknown error");
-                    return Err(format!("Auth failed: {}", error));
-                }
-            }
+        // Past the upgrade the headers were accepted, there is no handshake to wait for
+        if !self.sends_hmac() {
+            log::info!("Connection test successful");
+            return Ok(ServerInfo::default());
         }
 
-        Err("Invalid auth response".to_string())
+        let info = authenticate(&mut ws, self).await.map_err(|e| match e {
+            AdapterError::WebsocketError(reason) => reason,
+            e => e.to_string(),
+        })?;
+        ws.close(None).await.ok();
+
+        log::info!(mt5 = self.server_addr.as_str(); "Connection test successful");
+        Ok(info)
     }
 }
 
@@ -215,31 +838,179 @@ struct AuthMessage {
 
 /// Outgoing subscribe message
 #[derive(Debug, Serialize)]
-struct SubscribeMessage {
+struct SubscribeMessage<'a> {
     #[serde(rename = "type")]
     msg_type: &'static str,
     symbols: Vec<String>,
     channels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    terminal_id: Option<&'a str>,
+}
+
+/// Outgoing request to add a symbol to the terminal's Market Watch
+#[derive(Debug, Serialize)]
+struct AddSymbolMessage<'a> {
+    #[serde(rename = "type")]
+    msg_type: &'static str,
+    symbol: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    terminal_id: Option<&'a str>,
+}
+
+/// Outgoing request for the terminal's chart to show a symbol, forwarded to the EA
+#[derive(Debug, Serialize)]
+struct SetChartSymbolMessage<'a> {
+    #[serde(rename = "type")]
+    msg_type: &'static str,
+    symbol: &'a str,
+    /// Period to switch the chart to, kept as it is when left out
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timeframe: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    terminal_id: Option<&'a str>,
+}
+
+/// Incoming answer to a subscribe request, from proxies listing [`SUBSCRIBE_ACK_CAPABILITY`]
+#[derive(Debug, Default, Deserialize)]
+struct SubscribeResponse {
+    #[serde(default)]
+    accepted: Vec<AcceptedSubscription>,
+    #[serde(default)]
+    rejected: Vec<RejectedSubscription>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AcceptedSubscription {
+    symbol: String,
+    #[serde(default)]
+    channels: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RejectedSubscription {
+    symbol: String,
+    /// Channels refused, all of the request's when empty
+    #[serde(default)]
+    channels: Vec<String>,
+    /// Machine readable reason, e.g. `not_in_market_watch`
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    reason: String,
 }
 
 /// Incoming server message (generic)
+///
+/// String fields borrow from the frame whenever they contain no escape sequences.
 #[derive(Debug, Deserialize)]
-struct ServerMessage {
-    #[serde(rename = "type")]
-    msg_type: String,
+struct ServerMessage<'a> {
+    #[serde(rename = "type", borrow)]
+    msg_type: Cow<'a, str>,
     #[serde(default)]
     success: Option<bool>,
+    /// Proxy wall clock in unix ms, sent with `auth_response`
     #[serde(default)]
-    error: Option<String>,
+    server_time: Option<u64>,
+    #[serde(default, borrow)]
+    error: Option<Cow<'a, str>>,
+    /// Human readable detail on `error` frames
+    #[serde(default, borrow)]
+    message: Option<Cow<'a, str>>,
+    /// Machine readable reason on `error` frames, e.g. `symbol_not_found`
+    #[serde(default, borrow)]
+    code: Option<Cow<'a, str>>,
+    /// Symbol a market data or `symbol_not_found` frame is about
+    #[serde(default, borrow)]
+    symbol: Option<Cow<'a, str>>,
+    /// Why the proxy ended the session, on `shutdown` and `kicked` frames
+    #[serde(default, borrow)]
+    reason: Option<Cow<'a, str>>,
+    /// Sent with `auth_response` by proxies that report their version
+    #[serde(default, deserialize_with = "version::lenient")]
+    proxy_version: Option<String>,
+    /// Version of the EA feeding the proxy, if it's attached
+    #[serde(default, deserialize_with = "version::lenient")]
+    ea_version: Option<String>,
+    /// Timezone of the trade server, e.g. `{"utc_offset":7200,"dst":"us"}`
+    #[serde(default, deserialize_with = "timezone::lenient")]
+    server_timezone: Option<ServerTimezone>,
+    /// Optional requests the proxy answers, sent with `auth_response`
+    #[serde(default, deserialize_with = "version::lenient_list")]
+    capabilities: Vec<String>,
+}
+
+impl ServerMessage<'_> {
+    fn server_info(&self) -> ServerInfo {
+        ServerInfo {
+            proxy_version: self.proxy_version.clone(),
+            ea_version: self.ea_version.clone(),
+            server_timezone: self.server_timezone,
+            capabilities: self.capabilities.clone(),
+        }
+    }
 }
 
+/// `MqlTick.flags` bits of a last-trade tick that tell its aggressor
+const TICK_FLAG_BUY: u32 = 32;
+const TICK_FLAG_SELL: u32 = 64;
+
 /// Incoming trade data
 #[derive(Debug, Deserialize)]
-struct Mt5Trade {
+struct Mt5Trade<'a> {
     time: u64,
     price: f64,
     volume: f64,
-    side: String,
+    /// Inferred by the EA from the last price moving up or down
+    #[serde(borrow)]
+    side: Cow<'a, str>,
+    /// `MqlTick.flags`, exchange-traded symbols set the aggressor's bit
+    #[serde(default)]
+    flags: Option<u32>,
+    /// Unrounded last-trade volume
+    #[serde(default)]
+    volume_real: Option<f64>,
+}
+
+impl Mt5Trade<'_> {
+    /// Whether the exchange reported a sell aggressor, `None` without exactly one side flag
+    fn aggressor_is_sell(&self) -> Option<bool> {
+        let flags = self.flags?;
+        match (flags & TICK_FLAG_BUY != 0, flags & TICK_FLAG_SELL != 0) {
+            (true, false) => Some(false),
+            (false, true) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+/// Exchange-traded symbols whose trades came with the aggressor's side
+static AGGRESSOR_SYMBOLS: LazyLock<RwLock<FxHashSet<Ticker>>> =
+    LazyLock::new(|| RwLock::new(FxHashSet::default()));
+
+/// Whether `ticker`'s trades carry the exchange's aggressor side rather than one inferred from
+/// price changes, known once its first flagged trade arrived
+pub fn has_aggressor_side(ticker: &Ticker) -> bool {
+    AGGRESSOR_SYMBOLS
+        .read()
+        .is_ok_and(|symbols| symbols.contains(ticker))
+}
+
+fn mark_aggressor_side(ticker: Ticker) {
+    if !has_aggressor_side(&ticker)
+        && let Ok(mut symbols) = AGGRESSOR_SYMBOLS.write()
+    {
+        symbols.insert(ticker);
+    }
+}
+
+/// Incoming trading session state
+#[derive(Debug, Deserialize)]
+struct Mt5Session<'a> {
+    #[serde(borrow)]
+    state: Cow<'a, str>,
+    /// Next session start in unix ms, sent while closed
+    #[serde(default)]
+    next_open: Option<u64>,
 }
 
 /// Incoming depth data
@@ -250,6 +1021,29 @@ struct Mt5Depth {
     asks: Vec<[f64; 2]>,
 }
 
+impl Mt5Depth {
+    /// MT5 always sends the full DOM, so every payload is a snapshot. Times are on `clock`.
+    fn into_payload(self, clock: &ServerTimezone) -> DepthPayload {
+        let to_orders = |levels: Vec<[f64; 2]>| {
+            levels
+                .into_iter()
+                .map(|[price, qty]| crate::depth::DeOrder {
+                    price: price as f32,
+                    qty: qty as f32,
+                })
+                .collect()
+        };
+
+        let time = clock.to_utc(self.time);
+        DepthPayload {
+            last_update_id: time,
+            time,
+            bids: to_orders(self.bids),
+            asks: to_orders(self.asks),
+        }
+    }
+}
+
 /// Incoming kline data
 #[derive(Debug, Deserialize)]
 struct Mt5Kline {
@@ -268,99 +1062,381 @@ struct Mt5SymbolInfo {
     tick_size: f64,
     min_lot: f64,
     contract_size: f64,
-    #[allow(dead_code)]
     digits: i32,
+    /// Account currency value of a tick for one lot, from proxies that report it
+    #[serde(default)]
+    tick_value: Option<f64>,
+    /// `[threshold, tick]` pairs of symbols quoted in tiers, `tick_size` is then the finest
+    #[serde(default)]
+    tick_table: Option<Vec<(f64, f64)>>,
 }
 
 /// Historical klines response
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize)]
 struct KlinesResponse {
     data: Vec<Mt5Kline>,
+    /// Price the bars were built from, proxies that don't take `price_basis` leave it out
+    #[serde(default)]
+    price_basis: Option<String>,
 }
 
-/// Symbols list response
+/// Symbols list response, entries are read one by one so a malformed one only drops itself
 #[derive(Debug, Deserialize)]
 struct SymbolsResponse {
-    data: Vec<Mt5SymbolInfo>,
+    data: Vec<serde_json::Value>,
+}
+
+/// Recorded DOM snapshots response
+#[derive(Debug, Deserialize)]
+struct DepthHistoryResponse {
+    data: Vec<Mt5Depth>,
 }
 
+/// Economic calendar response
+#[derive(Debug, Deserialize)]
+struct CalendarResponse {
+    data: Vec<CalendarEvent>,
+}
+
+/// Listed in `auth_response` by proxies that answer `get_calendar`
+const CALENDAR_CAPABILITY: &str = "calendar";
+
+/// Listed in `auth_response` by proxies that answer every subscribe with `subscribe_response`
+const SUBSCRIBE_ACK_CAPABILITY: &str = "subscribe_ack";
+
+/// Error `code` a proxy sends for request types it doesn't implement
+const UNSUPPORTED_CODE: &str = "unsupported_request";
+
 // ============================================================================
 // Public API
 // ============================================================================
 
-/// Fetch available symbols from MT5 server via proxy
-pub async fn fetch_ticksize(
-    config: &Mt5Config,
-) -> Result<HashMap<Ticker, Option<TickerInfo>>, AdapterError> {
-    use futures_util::{SinkExt as _, StreamExt as _};
-    use tokio_tungstenite::tungstenite::Message;
+type ProxySocket =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
 
-    log::info!("Fetching MT5 symbols from {}", config.server_addr);
+type ClientRequest = tokio_tungstenite::tungstenite::handshake::client::Request;
 
-    // Connect to proxy
-    let url = config.ws_url();
-    let (mut ws, _) = tokio_tungstenite::connect_async(&url)
+/// Opens a client connection to the proxy and completes the auth handshake, for one-off
+/// request/response exchanges
+async fn connect_authenticated(config: &Mt5Config) -> Result<ProxySocket, AdapterError> {
+    let (mut ws, _) = tokio_tungstenite::connect_async(config.client_request()?)
         .await
-        .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+        .map_err(|e| upgrade_error(e, &config.server_addr))?;
+
+    if config.sends_hmac() {
+        authenticate(&mut ws, config).await?;
+    }
+    Ok(ws)
+}
+
+/// Sends the HMAC auth message and waits for the proxy to accept it, returning the versions it
+/// reported. A server that doesn't answer like a proxy is diagnosed as one on the wrong port.
+async fn authenticate(
+    ws: &mut ProxySocket,
+    config: &Mt5Config,
+) -> Result<ServerInfo, AdapterError> {
+    use futures_util::{SinkExt as _, StreamExt as _};
+    use tokio_tungstenite::tungstenite::Message;
 
-    // Authenticate
     let timestamp = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap()
         .as_millis() as u64;
 
-    let signature = compute_hmac_signature(&config.api_key, timestamp, &config.api_secret);
+    let (api_key, api_secret) = config.credentials().map_err(AdapterError::InvalidRequest)?;
+    let signature = compute_hmac_signature(&api_key, timestamp, &api_secret);
 
-    let auth_msg = serde_json::json!({
-        "type": "auth",
-        "api_key": config.api_key,
-        "timestamp": timestamp,
-        "signature": signature
-    });
+    let auth_msg = AuthMessage {
+        msg_type: "auth",
+        api_key,
+        timestamp,
+        signature,
+    };
+
+    let auth_json =
+        serde_json::to_string(&auth_msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
 
-    ws.send(Message::Text(auth_msg.to_string()))
+    ws.send(Message::Text(auth_json))
         .await
         .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
 
-    // Get auth response
-    if let Some(Ok(Message::Text(text))) = ws.next().await {
-        let resp: ServerMessage =
-            serde_json::from_str(&text).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+    log::debug!(mt5 = config.server_addr.as_str(); "Sent auth message");
 
-        if resp.msg_type == "auth_response" && resp.success != Some(true) {
-            return Err(AdapterError::WebsocketError(
-                resp.error.unwrap_or_else(|| "Auth failed".to_string()),
-            ));
+    let not_a_proxy =
+        |symptom| AdapterError::WebsocketError(diagnosis::diagnose(&config.server_addr, &symptom));
+    let timeout = Duration::from_secs(config.timeout_secs);
+
+    let answered = tokio::time::timeout(timeout, async {
+        while let Some(msg_result) = ws.next().await {
+            let msg = msg_result.map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+
+            if let Some(symptom) = Symptom::of_frame(&msg) {
+                return Err(not_a_proxy(symptom));
+            }
+            let Message::Text(text) = msg else {
+                continue;
+            };
+
+            let server_msg: ServerMessage =
+                serde_json::from_str(&text).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+
+            if server_msg.msg_type == "auth_response" {
+                record_server_time(server_msg.server_time);
+
+                if server_msg.success == Some(true) {
+                    log::info!(mt5 = config.server_addr.as_str(); "MT5 authenticated successfully");
+                    let info = server_msg.server_info();
+                    record_server_info(&config.server_addr, info.clone());
+                    return Ok(info);
+                }
+                return Err(AdapterError::WebsocketError(
+                    server_msg
+                        .error
+                        .map_or_else(|| "Auth failed".to_string(), Cow::into_owned),
+                ));
+            }
         }
-    }
 
-    // Request symbols
-    let symbols_req = serde_json::json!({ "type": "get_symbols" });
-    ws.send(Message::Text(symbols_req.to_string()))
+        Err(AdapterError::WebsocketError(
+            "No auth response received".to_string(),
+        ))
+    })
+    .await;
+
+    answered.unwrap_or_else(|_| Err(not_a_proxy(Symptom::Silent(timeout))))
+}
+
+/// Sends a one-off request over a fresh socket and returns the first text frame answering it
+async fn request_once(
+    config: &Mt5Config,
+    request: &serde_json::Value,
+) -> Result<Option<String>, AdapterError> {
+    use futures_util::{SinkExt as _, StreamExt as _};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let mut ws = connect_authenticated(config).await?;
+
+    ws.send(Message::Text(request.to_string()))
         .await
         .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
 
-    // Get symbols response
-    let mut result = HashMap::new();
+    let response = match ws.next().await {
+        Some(Ok(Message::Text(text))) => Some(text),
+        _ => None,
+    };
+
+    ws.close(None).await.ok();
+    Ok(response)
+}
 
-    if let Some(Ok(Message::Text(text))) = ws.next().await
-        && let Ok(resp) = serde_json::from_str::<SymbolsResponse>(&text)
-    {
-        for sym_info in resp.data {
-            let ticker = Ticker::new(&sym_info.symbol, super::Exchange::MetaTrader5);
-            let info = TickerInfo::new(
-                ticker,
-                sym_info.tick_size as f32,
-                sym_info.min_lot as f32,
-                Some(sym_info.contract_size as f32),
+/// Points the terminal's chart at `ticker`, and at `timeframe` when given. Proxies forward it to
+/// the EA without answering, so this returns once it's sent.
+pub async fn set_chart_symbol(
+    config: &Mt5Config,
+    ticker: Ticker,
+    timeframe: Option<Timeframe>,
+) -> Result<(), AdapterError> {
+    use futures_util::SinkExt as _;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let symbol = ticker.to_string();
+    let msg = SetChartSymbolMessage {
+        msg_type: "set_chart_symbol",
+        symbol: &symbol,
+        timeframe: timeframe.map(timeframe_to_mt5_string),
+        terminal_id: config.terminal_id.as_deref(),
+    };
+    let json = serde_json::to_string(&msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+
+    let mut ws = connect_authenticated(config).await?;
+    let sent = ws
+        .send(Message::Text(json))
+        .await
+        .map_err(|e| AdapterError::WebsocketError(e.to_string()));
+    ws.close(None).await.ok();
+    sent
+}
+
+type SymbolMap = HashMap<Ticker, Option<TickerInfo>>;
+
+/// Symbols a proxy listed, and the entries among them that couldn't be read
+#[derive(Debug, Clone, Default)]
+pub struct SymbolFetchOutcome {
+    pub infos: SymbolMap,
+    /// Symbol, or the entry's position when even that is unreadable, with what was wrong
+    pub failures: Vec<(String, String)>,
+}
+
+/// Pause between two symbol list requests to the same proxy, refreshes asked for sooner are
+/// queued behind the last one and shared by everyone asking meanwhile
+const SYMBOL_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Symbol list requests in flight or queued, by client endpoint
+static SYMBOL_REFRESH: LazyLock<Governor<String, Arc<SymbolFetchOutcome>>> =
+    LazyLock::new(|| Governor::new(SYMBOL_REFRESH_INTERVAL));
+
+/// Identical kline requests in flight, by client endpoint, ticker, price basis, timeframe and range
+static KLINE_REQUESTS: LazyLock<Governor<KlineRequestKey, Arc<Vec<Kline>>>> =
+    LazyLock::new(|| Governor::new(Duration::ZERO));
+
+type KlineRequestKey = (
+    String,
+    Ticker,
+    Option<PriceBasis>,
+    Timeframe,
+    Option<(u64, u64)>,
+);
+
+/// Whether the symbol list of this proxy is being fetched, or queued to be
+pub fn refresh_in_progress(config: &Mt5Config) -> bool {
+    SYMBOL_REFRESH.is_busy(&config.endpoint())
+}
+
+/// Fetch available symbols from MT5 server via proxy, with the user's tick size overrides.
+///
+/// Entries that can't be read are left out and listed in the outcome, a list without a single
+/// readable symbol is an error. Callers asking while a fetch for the same proxy is running or
+/// queued share its result.
+pub async fn fetch_ticksize(config: &Mt5Config) -> Result<SymbolFetchOutcome, AdapterError> {
+    let config = config.clone();
+    name_connection(&config.connection_name());
+
+    SYMBOL_REFRESH
+        .run(config.endpoint(), || async move {
+            request_symbols(&config).await.map(Arc::new)
+        })
+        .await
+        .map(|outcome| {
+            let mut outcome = Arc::unwrap_or_clone(outcome);
+            tick_override::apply(&mut outcome.infos);
+            outcome
+        })
+}
+
+async fn request_symbols(config: &Mt5Config) -> Result<SymbolFetchOutcome, AdapterError> {
+    log::info!(mt5 = config.server_addr.as_str(); "Fetching MT5 symbols");
+
+    let request = config.scoped(serde_json::json!({ "type": "get_symbols" }));
+    let response = match request_once(config, &request).await {
+        Err(e) if is_upgrade_refused(&e) => {
+            log::warn!(
+                mt5 = config.server_addr.as_str();
+                "{}, fetching MT5 symbols over HTTP",
+                config.redact(&e.to_string())
             );
-            result.insert(ticker, Some(info));
+            Some(rest::get_symbols(config).await?)
         }
+        response => response?,
+    };
+
+    let text = response.ok_or_else(|| {
+        AdapterError::WebsocketError("Proxy closed without listing symbols".to_string())
+    })?;
+    let outcome = parse_symbols(&text, config.source())?;
+
+    for (symbol, error) in &outcome.failures {
+        log::warn!(
+            mt5 = config.server_addr.as_str();
+            "Skipped MT5 symbol {symbol}: {error}"
+        );
     }
 
+    Ok(outcome)
+}
+
+/// Longest wait for the terminal list, proxies with a single terminal never answer it
+const TERMINALS_TIMEOUT_SECS: u64 = 5;
+
+/// Terminals the proxy bridges, empty for proxies with a single unnamed one
+pub async fn fetch_terminals(config: &Mt5Config) -> Result<Vec<Terminal>, AdapterError> {
+    let config = Mt5Config {
+        timeout_secs: config.timeout_secs.min(TERMINALS_TIMEOUT_SECS),
+        ..config.clone()
+    };
+    let mut ws = connect_authenticated(&config).await?;
+
+    let request = serde_json::json!({ "type": "get_terminals" });
+    let response = optional_request(&mut ws, &config, &request, "terminals").await;
     ws.close(None).await.ok();
 
-    Ok(result)
+    match response {
+        // Ignored or refused as an unknown request
+        Err(AdapterError::Unsupported(_) | AdapterError::InvalidRequest(_)) => Ok(vec![]),
+        response => terminals::parse(&response?),
+    }
+}
+
+/// Reads a symbols response of the connection `source` entry by entry, recording the specs of
+/// those that parse
+fn parse_symbols(text: &str, source: SourceId) -> Result<SymbolFetchOutcome, AdapterError> {
+    let resp = serde_json::from_str::<SymbolsResponse>(text)
+        .map_err(|e| AdapterError::ParseError(format!("MT5 symbols response: {e}")))?;
+
+    let mut outcome = SymbolFetchOutcome::default();
+    let mut specs = vec![];
+
+    for (index, entry) in resp.data.into_iter().enumerate() {
+        let name = entry
+            .get("symbol")
+            .and_then(|symbol| symbol.as_str())
+            .map_or_else(|| format!("#{}", index + 1), str::to_string);
+
+        let sym_info = match serde_json::from_value::<Mt5SymbolInfo>(entry) {
+            Ok(sym_info) if sym_info.tick_size.is_finite() && sym_info.tick_size > 0.0 => sym_info,
+            Ok(sym_info) => {
+                let error = format!("tick size {} is not positive", sym_info.tick_size);
+                outcome.failures.push((name, error));
+                continue;
+            }
+            Err(e) => {
+                outcome.failures.push((name, e.to_string()));
+                continue;
+            }
+        };
+
+        let tiers = match sym_info.tick_table.as_deref().map(TickTiers::new) {
+            Some(Err(error)) => {
+                outcome.failures.push((name, error));
+                continue;
+            }
+            Some(Ok(tiers)) => Some(tiers),
+            None => None,
+        };
+
+        let ticker =
+            Ticker::new(&sym_info.symbol, super::Exchange::MetaTrader5).on_connection(Some(source));
+        let info = TickerInfo::new(
+            ticker,
+            sym_info.tick_size as f32,
+            sym_info.min_lot as f32,
+            Some(sym_info.contract_size as f32),
+        )
+        .tiered(tiers);
+        outcome.infos.insert(ticker, Some(info));
+
+        specs.push(rates::SymbolSpec {
+            name: sym_info.symbol,
+            tick_size: sym_info.tick_size,
+            contract_size: sym_info.contract_size,
+            digits: sym_info.digits,
+            tick_value: sym_info.tick_value,
+        });
+    }
+
+    if outcome.infos.is_empty() {
+        let reason = match outcome.failures.first() {
+            Some((symbol, error)) => format!(
+                "none of the {} symbols could be read, {symbol}: {error}",
+                outcome.failures.len()
+            ),
+            None => "the proxy listed no symbols".to_string(),
+        };
+        return Err(AdapterError::ParseError(format!("MT5 symbols: {reason}")));
+    }
+
+    rates::record_symbols(source, specs);
+
+    Ok(outcome)
 }
 
 /// Fetch ticker prices/stats from MT5 server
@@ -371,129 +1447,312 @@ pub async fn fetch_ticker_prices(
     Ok(HashMap::new())
 }
 
-/// Fetch historical klines from MT5 server
+/// Fetch historical klines from MT5 server, sharing the result of an identical request in flight
 pub async fn fetch_klines(
     config: &Mt5Config,
     ticker_info: TickerInfo,
     timeframe: Timeframe,
     range: Option<(u64, u64)>,
 ) -> Result<Vec<Kline>, AdapterError> {
-    use futures_util::{SinkExt as _, StreamExt as _};
-    use tokio_tungstenite::tungstenite::Message;
+    let config = config.clone();
+    let key = (
+        config.endpoint(),
+        ticker_info.ticker,
+        ticker_info.price_basis,
+        timeframe,
+        range,
+    );
+
+    KLINE_REQUESTS
+        .run(key, || async move {
+            request_klines(&config, ticker_info, timeframe, range)
+                .await
+                .map(Arc::new)
+        })
+        .await
+        .map(Arc::unwrap_or_clone)
+}
 
+async fn request_klines(
+    config: &Mt5Config,
+    ticker_info: TickerInfo,
+    timeframe: Timeframe,
+    range: Option<(u64, u64)>,
+) -> Result<Vec<Kline>, AdapterError> {
     log::info!(
+        mt5 = config.server_addr.as_str();
         "Fetching MT5 klines for {} {:?}",
         ticker_info.ticker,
         timeframe
     );
 
-    // Connect to proxy
-    let url = config.ws_url();
-    let (mut ws, _) = tokio_tungstenite::connect_async(&url)
-        .await
-        .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+    let basis = ticker_info.price_basis;
+    let mut bars = request_bars(config, ticker_info, basis, timeframe, range).await?;
 
-    // Authenticate
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64;
+    if let Some(basis @ PriceBasis::Mid) = basis
+        && !price_basis::is_honored(basis, bars.price_basis.as_deref())
+    {
+        log::info!(
+            mt5 = config.server_addr.as_str();
+            "MT5 proxy sent no mid bars for {}, averaging bid and ask",
+            ticker_info.ticker
+        );
+        let bid = request_bars(config, ticker_info, Some(PriceBasis::Bid), timeframe, range);
+        let ask = request_bars(config, ticker_info, Some(PriceBasis::Ask), timeframe, range);
+        let (bid, ask) = futures_util::future::try_join(bid, ask).await?;
+        bars.data = price_basis::mid_bars(&bid.data, &ask.data);
+    }
 
-    let signature = compute_hmac_signature(&config.api_key, timestamp, &config.api_secret);
+    let clock = server_timezone(&config.server_addr);
+    let converter = config.lot_converter(&ticker_info);
+    let klines: Vec<Kline> = bars
+        .data
+        .into_iter()
+        .map(|k| {
+            let volume = converter.map_or(k.volume as f32, |c| {
+                c.convert(k.volume as f32, k.close as f32)
+            });
+            let buy_volume = volume / 2.0;
+            let sell_volume = volume / 2.0;
+
+            Kline::new(
+                clock.to_utc(k.time),
+                k.open as f32,
+                k.high as f32,
+                k.low as f32,
+                k.close as f32,
+                (buy_volume, sell_volume),
+                ticker_info.tick_rule(),
+            )
+        })
+        .collect();
 
-    let auth_msg = serde_json::json!({
-        "type": "auth",
-        "api_key": config.api_key,
-        "timestamp": timestamp,
-        "signature": signature
-    });
+    log::info!(
+        mt5 = config.server_addr.as_str();
+        "MT5 fetch_klines completed with {} klines",
+        klines.len()
+    );
+    Ok(klines)
+}
 
-    ws.send(Message::Text(auth_msg.to_string()))
-        .await
-        .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+/// One `get_klines` request, at `basis` when given. An unreadable answer yields no bars.
+async fn request_bars(
+    config: &Mt5Config,
+    ticker_info: TickerInfo,
+    basis: Option<PriceBasis>,
+    timeframe: Timeframe,
+    range: Option<(u64, u64)>,
+) -> Result<KlinesResponse, AdapterError> {
+    let mut klines_req = config.scoped(serde_json::json!({
+        "type": "get_klines",
+        "symbol": ticker_info.ticker.to_string(),
+        "timeframe": timeframe_to_mt5_string(timeframe),
+        "limit": 500
+    }));
+
+    let clock = server_timezone(&config.server_addr);
+    if let Some((start, end)) = range {
+        klines_req["start"] = serde_json::json!(clock.from_utc(start));
+        klines_req["end"] = serde_json::json!(clock.from_utc(end));
+    }
+    if let Some(basis) = basis {
+        klines_req["price_basis"] = serde_json::json!(basis.as_param());
+    }
+
+    let response = match request_once(config, &klines_req).await {
+        Err(e) if is_upgrade_refused(&e) => {
+            log::warn!(
+                mt5 = config.server_addr.as_str();
+                "{}, fetching MT5 klines over HTTP",
+                config.redact(&e.to_string())
+            );
+            Some(rest::get_klines(config, &klines_req).await?)
+        }
+        response => response?,
+    };
 
-    // Wait for auth
-    if let Some(Ok(Message::Text(text))) = ws.next().await {
-        let resp: ServerMessage =
-            serde_json::from_str(&text).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+    let Some(text) = response else {
+        return Ok(KlinesResponse::default());
+    };
+    log::debug!(
+        mt5 = config.server_addr.as_str();
+        "MT5 klines response: {}",
+        config.redact(&text[..text.len().min(500)])
+    );
 
-        if resp.msg_type == "auth_response" && resp.success != Some(true) {
-            return Err(AdapterError::WebsocketError(
-                resp.error.unwrap_or_else(|| "Auth failed".to_string()),
-            ));
+    match serde_json::from_str::<KlinesResponse>(&text) {
+        Ok(resp) => {
+            log::info!(
+                mt5 = config.server_addr.as_str();
+                "MT5 received {} klines for {}",
+                resp.data.len(),
+                ticker_info.ticker
+            );
+            Ok(resp)
+        }
+        Err(e) => {
+            log::error!(
+                mt5 = config.server_addr.as_str();
+                "MT5 klines parse error: {} - response: {}",
+                e,
+                config.redact(&text[..text.len().min(200)])
+            );
+            Ok(KlinesResponse::default())
         }
     }
+}
 
-    // Request klines
-    let mut klines_req = serde_json::json!({
-        "type": "get_klines",
+/// Fetch DOM snapshots the proxy recorded for `range`, at most one per `interval_ms`.
+///
+/// Proxies that don't record depth answer with [`AdapterError::Unsupported`].
+pub async fn fetch_depth_history(
+    config: &Mt5Config,
+    ticker_info: TickerInfo,
+    range: (u64, u64),
+    interval_ms: u64,
+) -> Result<Vec<DepthPayload>, AdapterError> {
+    let mut ws = connect_authenticated(config).await?;
+
+    let clock = server_timezone(&config.server_addr);
+    let request = config.scoped(serde_json::json!({
+        "type": "get_depth_history",
         "symbol": ticker_info.ticker.to_string(),
-        "timeframe": timeframe_to_mt5_string(timeframe),
-        "limit": 500
-    });
+        "start": clock.from_utc(range.0),
+        "end": clock.from_utc(range.1),
+        "interval_ms": interval_ms,
+    }));
 
-    if let Some((start, end)) = range {
-        klines_req["start"] = serde_json::json!(start);
-        klines_req["end"] = serde_json::json!(end);
+    let response = optional_request(&mut ws, config, &request, "depth_history").await;
+    ws.close(None).await.ok();
+
+    let mut snapshots: Vec<DepthPayload> = serde_json::from_str::<DepthHistoryResponse>(&response?)
+        .map_err(|e| AdapterError::ParseError(e.to_string()))?
+        .data
+        .into_iter()
+        .map(|depth| depth.into_payload(&clock))
+        .filter(|depth| depth.time >= range.0 && depth.time < range.1)
+        .collect();
+    snapshots.sort_by_key(|depth| depth.time);
+
+    if let Some(converter) = config.lot_converter(&ticker_info) {
+        for snapshot in &mut snapshots {
+            convert_depth_lots(snapshot, &converter);
+        }
     }
 
-    ws.send(Message::Text(klines_req.to_string()))
+    log::info!(
+        mt5 = config.server_addr.as_str();
+        "MT5 received {} depth snapshots for {}",
+        snapshots.len(),
+        ticker_info.ticker
+    );
+    Ok(snapshots)
+}
+
+/// Sends a request not every proxy implements and returns the frame of type `answer` to it.
+///
+/// Older proxies ignore unknown requests instead of answering, waiting on them ends with
+/// [`AdapterError::Unsupported`] after the configured timeout.
+async fn optional_request(
+    ws: &mut ProxySocket,
+    config: &Mt5Config,
+    request: &serde_json::Value,
+    answer: &str,
+) -> Result<String, AdapterError> {
+    use futures_util::{SinkExt as _, StreamExt as _};
+    use tokio_tungstenite::tungstenite::Message;
+
+    ws.send(Message::Text(request.to_string()))
         .await
         .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
 
-    // Get klines response
-    let mut klines = Vec::new();
-
-    if let Some(Ok(Message::Text(text))) = ws.next().await {
-        log::debug!("MT5 klines response: {}", &text[..text.len().min(500)]);
-        match serde_json::from_str::<KlinesResponse>(&text) {
-            Ok(resp) => {
-                log::info!(
-                    "MT5 received {} klines for {}",
-                    resp.data.len(),
-                    ticker_info.ticker
-                );
-                for k in resp.data {
-                    let buy_volume = (k.volume / 2.0) as f32;
-                    let sell_volume = (k.volume / 2.0) as f32;
-
-                    klines.push(Kline::new(
-                        k.time,
-                        k.open as f32,
-                        k.high as f32,
-                        k.low as f32,
-                        k.close as f32,
-                        (buy_volume, sell_volume),
-                        ticker_info.min_ticksize,
-                    ));
+    tokio::time::timeout(Duration::from_secs(config.timeout_secs), async {
+        while let Some(frame) = ws.next().await {
+            let Ok(Message::Text(text)) = frame else {
+                continue;
+            };
+            let msg: ServerMessage =
+                serde_json::from_str(&text).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+
+            match msg.msg_type.as_ref() {
+                msg_type if msg_type == answer => return Ok(text.to_string()),
+                "error" => {
+                    let detail = msg.message.or(msg.error).unwrap_or_default().into_owned();
+                    return Err(if msg.code.as_deref() == Some(UNSUPPORTED_CODE) {
+                        AdapterError::Unsupported(detail)
+                    } else {
+                        AdapterError::InvalidRequest(detail)
+                    });
                 }
-            }
-            Err(e) => {
-                log::error!(
-                    "MT5 klines parse error: {} - response: {}",
-                    e,
-                    &text[..text.len().min(200)]
-                );
+                _ => {}
             }
         }
+        Err(AdapterError::WebsocketError(format!(
+            "Connection closed before {answer} arrived"
+        )))
+    })
+    .await
+    .map_err(|_| AdapterError::Unsupported(format!("No answer to {}", request["type"])))?
+}
+
+/// Economic calendar events of `currencies` in `range`, oldest first.
+///
+/// Proxies whose handshake doesn't list the calendar among their capabilities aren't asked,
+/// they and the ones that can't reach it answer with [`AdapterError::Unsupported`].
+pub async fn fetch_calendar(
+    config: &Mt5Config,
+    range: (u64, u64),
+    currencies: Vec<String>,
+) -> Result<Vec<CalendarEvent>, AdapterError> {
+    let mut ws = connect_authenticated(config).await?;
+
+    // Header authenticated sessions have no handshake to list capabilities in, ask anyway
+    if let Some(info) = server_info(&config.server_addr)
+        && config.sends_hmac()
+        && !info.supports(CALENDAR_CAPABILITY)
+    {
+        ws.close(None).await.ok();
+        return Err(AdapterError::Unsupported(
+            "Proxy doesn't forward the economic calendar".to_string(),
+        ));
     }
 
+    let clock = server_timezone(&config.server_addr);
+    let request = config.scoped(serde_json::json!({
+        "type": "get_calendar",
+        "from": clock.from_utc(range.0),
+        "to": clock.from_utc(range.1),
+        "currencies": currencies,
+    }));
+
+    let response = optional_request(&mut ws, config, &request, "calendar").await;
     ws.close(None).await.ok();
 
-    log::info!("MT5 fetch_klines completed with {} klines", klines.len());
-    Ok(klines)
+    let mut events = parse_calendar(&response?, &clock)?;
+    events.retain(|event| {
+        (range.0..range.1).contains(&event.time) && currencies.contains(&event.currency)
+    });
+
+    log::info!(
+        mt5 = config.server_addr.as_str();
+        "MT5 received {} calendar events for {}",
+        events.len(),
+        currencies.join("/")
+    );
+    Ok(events)
 }
 
-/// Connect to MT5 market data stream using global config (for Subscription compatibility)
-/// This function doesn't take config as parameter, instead fetches from global storage.
-/// IMPORTANT: Caller must ensure global config exists before calling (use get_global_config().is_some())
-pub fn connect_market_stream_global(
-    ticker_info: TickerInfo,
-    push_freq: PushFrequency,
-) -> impl Stream<Item = Event> {
-    // Caller guarantees config exists, so unwrap is safe here
-    let config = get_global_config().expect("MT5 config must be set before calling this function");
-    connect_market_stream_inner(config, ticker_info, push_freq)
+/// Events of a `calendar` frame with their times moved from the server's clock to UTC
+fn parse_calendar(text: &str, clock: &ServerTimezone) -> Result<Vec<CalendarEvent>, AdapterError> {
+    let mut events = serde_json::from_str::<CalendarResponse>(text)
+        .map_err(|e| AdapterError::ParseError(e.to_string()))?
+        .data;
+
+    for event in &mut events {
+        event.time = clock.to_utc(event.time);
+    }
+    events.sort_by_key(|event| event.time);
+    Ok(events)
 }
 
 /// Connect to MT5 market data stream
@@ -505,7 +1764,8 @@ pub fn connect_market_stream(
     connect_market_stream_inner(config, ticker_info, push_freq)
 }
 
-/// Internal implementation for MT5 market data stream
+/// Internal implementation for MT5 market data stream, attached to the socket shared by every
+/// pane of the same proxy
 fn connect_market_stream_inner(
     config: Mt5Config,
     ticker_info: TickerInfo,
@@ -516,222 +1776,253 @@ fn connect_market_stream_inner(
 
         async move {
             let exchange = super::Exchange::MetaTrader5;
+            let symbol = ticker_info.ticker.to_string();
+            let attachment = multiplex::attach(&config, &symbol);
+            // Keeps the quotes converting this symbol into the account currency coming
+            let _watches = rates::watch(&config, &ticker_info);
+            #[cfg(feature = "fault-injection")]
+            let attachment = faults::Faulty::new(attachment);
+            let mut attachment = attachment;
+
             let mut orderbook = LocalDepthCache::default();
             let mut trades_buffer: Vec<Trade> = Vec::new();
-            let mut reconnect_delay = Duration::from_secs(1);
+            let mut emitter = DepthEmitter::default();
+            let mut market_state = MarketState::Open;
+
+            let stream_kind = StreamKind::DepthAndTrades {
+                ticker_info,
+                depth_aggr: StreamTicksize::Client,
+                push_freq: PushFrequency::ServerDefault,
+            };
 
             loop {
-                log::info!("Connecting to MT5 proxy: {}", config.ws_url());
-
-                match connect_and_stream(
-                    &config,
-                    ticker_info,
-                    &mut orderbook,
-                    &mut trades_buffer,
-                    &mut output,
-                )
-                .await
-                {
-                    Ok(()) => {
-                        let _ = output
-                            .send(Event::Disconnected(
-                                exchange,
-                                "Connection closed".to_string(),
-                            ))
-                            .await;
-                    }
-                    Err(e) => {
-                        log::error!("MT5 connection error: {}", e);
-                        let _ = output
-                            .send(Event::Disconnected(exchange, e.to_string()))
-                            .await;
+                let next = if emitter.has_pending() {
+                    tokio::select! {
+                        next = attachment.recv() => next,
+                        () = tokio::time::sleep(PENDING_FLUSH_INTERVAL) => {
+                            if emitter.try_flush(&mut output).is_err() {
+                                break;
+                            }
+                            continue;
+                        }
                     }
-                }
+                } else {
+                    attachment.recv().await
+                };
 
-                if !config.auto_reconnect {
+                let Some(feed) = next else {
                     break;
-                }
+                };
+
+                match feed {
+                    Feed::Connected => {
+                        let _ = output.send(Event::Connected(exchange)).await;
+                    }
+                    Feed::Disconnected(reason) => {
+                        // Deliver whatever the UI hasn't received yet, trades must not be lost
+                        emitter.flush(&mut output).await;
+                        if emitter.dropped() > 0 {
+                            log::info!(
+                                mt5 = config.server_addr.as_str();
+                                "MT5 {} stream dropped {} stale depth events for a slow consumer",
+                                ticker_info.ticker,
+                                emitter.dropped()
+                            );
+                        }
+
+                        let _ = output.send(Event::Disconnected(exchange, reason)).await;
+                    }
+                    Feed::SymbolNotFound => {
+                        emitter.flush(&mut output).await;
+
+                        // Reconnecting won't make the symbol reappear
+                        let reason = format!("{SYMBOL_NOT_FOUND}: {}", ticker_info.ticker);
+                        let _ = output.send(Event::Disconnected(exchange, reason)).await;
+                        break;
+                    }
+                    Feed::Rejected { channels, reason } => {
+                        emitter.flush(&mut output).await;
+
+                        let reason = format!(
+                            "{SUBSCRIPTION_REJECTED}: {} {}: {reason}",
+                            ticker_info.ticker,
+                            channels.join("/")
+                        );
+                        let _ = output.send(Event::Disconnected(exchange, reason)).await;
+
+                        // Trades reach the chart with depth updates, without depth nothing moves
+                        if channels.iter().any(|channel| channel == "depth") {
+                            break;
+                        }
+                    }
+                    Feed::Unconfirmed => {
+                        // Data may still arrive, the pane keeps listening
+                        let reason = format!("{SUBSCRIPTION_UNCONFIRMED}: {}", ticker_info.ticker);
+                        let _ = output.send(Event::Disconnected(exchange, reason)).await;
+                    }
+                    Feed::Revoked(reason) => {
+                        emitter.flush(&mut output).await;
 
-                // Exponential backoff for reconnect
-                tokio::time::sleep(reconnect_delay).await;
-                reconnect_delay = std::cmp::min(reconnect_delay * 2, Duration::from_secs(60));
+                        let reason = format!("{SESSION_REVOKED}: {reason}");
+                        let _ = output.send(Event::Disconnected(exchange, reason)).await;
+                        break;
+                    }
+                    Feed::Paused { reopens_at } => {
+                        emitter.flush(&mut output).await;
+
+                        let state = MarketState::Closed {
+                            reopens_at: Some(reopens_at),
+                            paused: true,
+                        };
+                        if market_state.transition(state) {
+                            let _ = output
+                                .send(Event::MarketStateChanged(stream_kind, state))
+                                .await;
+                        }
+                    }
+                    Feed::Resumed => {
+                        // Still closed until the session frame says otherwise
+                        if let MarketState::Closed {
+                            reopens_at,
+                            paused: true,
+                        } = market_state
+                        {
+                            let state = MarketState::Closed {
+                                reopens_at,
+                                paused: false,
+                            };
+                            market_state = state;
+                            let _ = output
+                                .send(Event::MarketStateChanged(stream_kind, state))
+                                .await;
+                        }
+                    }
+                    Feed::Frame(frame) => {
+                        crate::capture::record(ticker_info.ticker, frame.as_bytes());
+
+                        let result = handle_market_frame(
+                            &config,
+                            ticker_info,
+                            stream_kind,
+                            frame.as_bytes(),
+                            &mut orderbook,
+                            &mut trades_buffer,
+                            &mut emitter,
+                            &mut output,
+                        );
+                        match result {
+                            Ok(Some(state)) if market_state.transition(state) => {
+                                log::info!(
+                                    mt5 = config.server_addr.as_str();
+                                    "MT5 {} market state: {state:?}",
+                                    ticker_info.ticker
+                                );
+                                let _ = output
+                                    .send(Event::MarketStateChanged(stream_kind, state))
+                                    .await;
+                            }
+                            Ok(_) => {}
+                            Err(_) => break,
+                        }
+                    }
+                }
             }
         }
     })
 }
 
-/// Internal connection and streaming logic
-async fn connect_and_stream(
+/// Applies one `trade` or `depth` frame of the pane's symbol, a `session` frame is returned
+/// as the market state it reports
+#[allow(clippy::too_many_arguments)]
+fn handle_market_frame(
     config: &Mt5Config,
     ticker_info: TickerInfo,
+    stream_kind: StreamKind,
+    frame: &[u8],
     orderbook: &mut LocalDepthCache,
     trades_buffer: &mut Vec<Trade>,
+    emitter: &mut DepthEmitter,
     output: &mut mpsc::Sender<Event>,
-) -> Result<(), AdapterError> {
-    use futures_util::{SinkExt as _, StreamExt as _};
-    use tokio_tungstenite::tungstenite::Message;
+) -> Result<Option<MarketState>, AdapterError> {
+    let Ok(server_msg) = serde_json::from_slice::<ServerMessage>(frame) else {
+        return Ok(None);
+    };
+    let clock = server_timezone(&config.server_addr);
 
-    let exchange = super::Exchange::MetaTrader5;
+    match server_msg.msg_type.as_ref() {
+        "trade" => {
+            if let Ok(mut trade) = parse_trade(frame, ticker_info, &clock) {
+                let price = trade.price.to_f32();
+                conversion::record_reference_price(&ticker_info.ticker.to_string(), price);
 
-    // Connect using tokio-tungstenite
-    let url = config.ws_url();
-    let (mut ws, _) = tokio_tungstenite::connect_async(&url)
-        .await
-        .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+                if let Some(converter) = config.lot_converter(&ticker_info) {
+                    trade.qty = converter.convert(trade.qty, price);
+                }
+                trades_buffer.push(trade);
+            }
+        }
+        "depth" => {
+            if let Ok(mut depth_payload) = parse_depth(frame, ticker_info, &clock) {
+                if let Some(converter) = config.lot_converter(&ticker_info) {
+                    convert_depth_lots(&mut depth_payload, &converter);
+                }
 
-    // Send connected event
-    let _ = output.send(Event::Connected(exchange)).await;
+                // Update local depth cache
+                orderbook.update(
+                    DepthUpdate::Snapshot(depth_payload),
+                    ticker_info.tick_rule(),
+                );
 
-    // Authenticate
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64;
-
-    let signature = compute_hmac_signature(&config.api_key, timestamp, &config.api_secret);
-
-    let auth_msg = AuthMessage {
-        msg_type: "auth",
-        api_key: config.api_key.clone(),
-        timestamp,
-        signature,
-    };
-
-    let auth_json =
-        serde_json::to_string(&auth_msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
-
-    ws.send(Message::Text(auth_json))
-        .await
-        .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
-
-    log::debug!("Sent auth message");
-
-    // Wait for auth response
-    let mut authenticated = false;
-    while let Some(msg_result) = ws.next().await {
-        let msg = msg_result.map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
-
-        if let Message::Text(text) = msg {
-            let server_msg: ServerMessage =
-                serde_json::from_str(&text).map_err(|e| AdapterError::ParseError(e.to_string()))?;
-
-            if server_msg.msg_type == "auth_response" {
-                if server_msg.success == Some(true) {
-                    authenticated = true;
-                    log::info!("MT5 authenticated successfully");
-                    break;
-                } else {
-                    return Err(AdapterError::WebsocketError(
-                        server_msg
-                            .error
-                            .unwrap_or_else(|| "Auth failed".to_string()),
-                    ));
+                if let Some(basis) = ticker_info.price_basis {
+                    price_basis::reprice(
+                        trades_buffer,
+                        basis,
+                        &orderbook.depth,
+                        ticker_info.tick_rule(),
+                    );
                 }
+
+                // Emit depth received event without waiting on the consumer
+                emitter.push(
+                    stream_kind,
+                    orderbook.time,
+                    Arc::clone(&orderbook.depth),
+                    std::mem::take(trades_buffer),
+                    output,
+                )?;
             }
         }
+        "session" => match parse_session(frame, &clock) {
+            Ok(state) => return Ok(Some(state)),
+            Err(e) => log::warn!(mt5 = config.server_addr.as_str(); "MT5 session state: {e}"),
+        },
+        _ => {}
     }
 
-    if !authenticated {
-        return Err(AdapterError::WebsocketError(
-            "No auth response received".to_string(),
-        ));
-    }
-
-    // Subscribe to symbol
-    let sub_msg = SubscribeMessage {
-        msg_type: "subscribe",
-        symbols: vec![ticker_info.ticker.to_string()],
-        channels: vec!["trade".to_string(), "depth".to_string()],
-    };
-
-    let sub_json =
-        serde_json::to_string(&sub_msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
-
-    ws.send(Message::Text(sub_json))
-        .await
-        .map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+    Ok(None)
+}
 
-    log::debug!("Subscribed to {}", ticker_info.ticker);
+/// Compute HMAC-SHA256 signature for authentication
+const REDACTED: &str = "<redacted>";
 
-    // Create StreamKind for events
-    let stream_kind = StreamKind::DepthAndTrades {
-        ticker_info,
-        depth_aggr: StreamTicksize::Client,
-        push_freq: PushFrequency::ServerDefault,
-    };
+/// Masks the value of every `"signature"` field in a JSON text
+fn redact_signatures(text: &str) -> String {
+    const FIELD: &str = "\"signature\":\"";
 
-    // Main message loop
-    while let Some(msg_result) = ws.next().await {
-        let msg = msg_result.map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
-
-        match msg {
-            Message::Text(text) => {
-                // Parse message type
-                if let Ok(server_msg) = serde_json::from_str::<ServerMessage>(&text) {
-                    match server_msg.msg_type.as_str() {
-                        "trade" => {
-                            if let Ok(trade) = parse_trade(&text, ticker_info) {
-                                trades_buffer.push(trade);
-                            }
-                        }
-                        "depth" => {
-                            if let Ok(depth_payload) = parse_depth(&text, ticker_info) {
-                                // Update local depth cache
-                                orderbook.update(
-                                    DepthUpdate::Snapshot(depth_payload),
-                                    ticker_info.min_ticksize,
-                                );
-
-                                // Emit depth received event
-                                let trades: Box<[Trade]> =
-                                    std::mem::take(trades_buffer).into_boxed_slice();
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(FIELD) {
+        let value_start = start + FIELD.len();
+        redacted.push_str(&rest[..value_start]);
+        redacted.push_str(REDACTED);
 
-                                let _ = output
-                                    .send(Event::DepthReceived(
-                                        stream_kind,
-                                        orderbook.time,
-                                        Arc::clone(&orderbook.depth),
-                                        trades,
-                                    ))
-                                    .await;
-                            }
-                        }
-                        "heartbeat" => {
-                            // Send pong response
-                            let pong = serde_json::json!({
-                                "type": "ping",
-                                "time": std::time::SystemTime::now()
-                                    .duration_since(std::time::UNIX_EPOCH)
-                                    .unwrap()
-                                    .as_millis() as u64
-                            });
-                            ws.send(Message::Text(pong.to_string())).await.ok();
-                        }
-                        "error" => {
-                            log::error!(
-                                "MT5 server error: {}",
-                                server_msg.error.unwrap_or_default()
-                            );
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            Message::Ping(data) => {
-                ws.send(Message::Pong(data)).await.ok();
-            }
-            Message::Close(_) => {
-                log::info!("MT5 server sent close frame");
-                break;
-            }
-            _ => {}
-        }
+        rest = &rest[value_start..];
+        rest = rest.find('"').map_or("", |end| &rest[end..]);
     }
-
-    Ok(())
+    redacted.push_str(rest);
+    redacted
 }
 
-/// Compute HMAC-SHA256 signature for authentication
 fn compute_hmac_signature(api_key: &str, timestamp: u64, secret: &str) -> String {
     use hmac::{Hmac, Mac};
     use sha2::Sha256;
@@ -748,51 +2039,70 @@ fn compute_hmac_signature(api_key: &str, timestamp: u64, secret: &str) -> String
     hex::encode(result.into_bytes())
 }
 
-/// Parse incoming trade message
-fn parse_trade(msg: &str, ticker_info: TickerInfo) -> Result<Trade, AdapterError> {
+/// Parse incoming trade message, stamped on `clock`
+fn parse_trade(
+    msg: &[u8],
+    ticker_info: TickerInfo,
+    clock: &ServerTimezone,
+) -> Result<Trade, AdapterError> {
     let mt5_trade: Mt5Trade =
-        serde_json::from_str(msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+        serde_json::from_slice(msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
 
-    let is_sell = mt5_trade.side == "sell";
-    let price = Price::from_f32(mt5_trade.price as f32).round_to_min_tick(ticker_info.min_ticksize);
+    let is_sell = match mt5_trade.aggressor_is_sell() {
+        Some(is_sell) => {
+            mark_aggressor_side(ticker_info.ticker);
+            is_sell
+        }
+        None => mt5_trade.side == "sell",
+    };
+    let price = ticker_info
+        .tick_rule()
+        .round(Price::from_f32(mt5_trade.price as f32));
 
     Ok(Trade {
-        time: mt5_trade.time,
+        time: clock.to_utc(mt5_trade.time),
         is_sell,
         price,
-        qty: mt5_trade.volume as f32,
+        qty: mt5_trade.volume_real.unwrap_or(mt5_trade.volume) as f32,
     })
 }
 
-/// Parse incoming depth message
-fn parse_depth(msg: &str, _ticker_info: TickerInfo) -> Result<DepthPayload, AdapterError> {
+/// Parse incoming depth message, stamped on `clock`
+fn parse_depth(
+    msg: &[u8],
+    _ticker_info: TickerInfo,
+    clock: &ServerTimezone,
+) -> Result<DepthPayload, AdapterError> {
     let mt5_depth: Mt5Depth =
-        serde_json::from_str(msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
-
-    let bids = mt5_depth
-        .bids
-        .iter()
-        .map(|[price, qty]| crate::depth::DeOrder {
-            price: *price as f32,
-            qty: *qty as f32,
-        })
-        .collect();
+        serde_json::from_slice(msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
 
-    let asks = mt5_depth
-        .asks
-        .iter()
-        .map(|[price, qty]| crate::depth::DeOrder {
-            price: *price as f32,
-            qty: *qty as f32,
-        })
-        .collect();
+    Ok(mt5_depth.into_payload(clock))
+}
 
-    Ok(DepthPayload {
-        last_update_id: mt5_depth.time,
-        time: mt5_depth.time,
-        bids,
-        asks,
-    })
+/// Parse incoming session state message, with the next open on `clock`
+fn parse_session(msg: &[u8], clock: &ServerTimezone) -> Result<MarketState, AdapterError> {
+    let session: Mt5Session =
+        serde_json::from_slice(msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+
+    match session.state.as_ref() {
+        "open" => Ok(MarketState::Open),
+        "closed" => Ok(MarketState::Closed {
+            reopens_at: session.next_open.map(|time| clock.to_utc(time)),
+            paused: false,
+        }),
+        "trade_disabled" => Ok(MarketState::TradeDisabled),
+        "close_only" => Ok(MarketState::CloseOnly),
+        other => Err(AdapterError::ParseError(format!(
+            "Unknown session state: {other}"
+        ))),
+    }
+}
+
+/// Level sizes of a DOM snapshot from lots into currency value
+fn convert_depth_lots(payload: &mut DepthPayload, converter: &LotConverter) {
+    for order in payload.bids.iter_mut().chain(payload.asks.iter_mut()) {
+        order.qty = converter.convert(order.qty, order.price);
+    }
 }
 
 /// Convert timeframe to MT5 string format
@@ -816,10 +2126,158 @@ fn timeframe_to_mt5_string(tf: Timeframe) -> &'static str {
 // Tests
 // ============================================================================
 
+// ============================================================================
+// Adapter
+// ============================================================================
+
+/// Serves MT5 tickers through one proxy connection, registered once a config is set
+pub struct Mt5Adapter {
+    config: Mt5Config,
+}
+
+impl Mt5Adapter {
+    pub fn new(config: Mt5Config) -> Self {
+        Self { config }
+    }
+}
+
+impl ExchangeAdapter for Mt5Adapter {
+    fn exchanges(&self) -> &'static [Exchange] {
+        &[Exchange::MetaTrader5]
+    }
+
+    // Proxy pushes full DOM snapshots, no trade history or OI yet
+    fn capabilities(&self, _exchange: Exchange) -> Capabilities {
+        Capabilities {
+            depth_history: true,
+            economic_calendar: true,
+            ..Capabilities::NONE
+        }
+    }
+
+    fn fetch_ticksize(
+        &self,
+        _market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, Option<TickerInfo>>> {
+        let config = self.config.clone();
+        Box::pin(async move { fetch_ticksize(&config).await.map(|outcome| outcome.infos) })
+    }
+
+    fn fetch_ticker_prices(
+        &self,
+        _market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, TickerStats>> {
+        let config = self.config.clone();
+        Box::pin(async move { fetch_ticker_prices(&config).await })
+    }
+
+    fn fetch_klines(
+        &self,
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<Kline>> {
+        let config = self.config.clone();
+        Box::pin(async move {
+            let result = fetch_klines(&config, ticker_info, timeframe, range).await;
+            if let Err(e) = &result {
+                log::error!(
+                    mt5 = config.server_addr.as_str();
+                    "MT5 fetch_klines error: {}",
+                    config.redact(&format!("{e:?}"))
+                );
+            }
+            result
+        })
+    }
+
+    fn fetch_depth_history(
+        &self,
+        ticker_info: TickerInfo,
+        range: (u64, u64),
+        interval_ms: u64,
+    ) -> AdapterFuture<Vec<DepthPayload>> {
+        let config = self.config.clone();
+        Box::pin(async move { fetch_depth_history(&config, ticker_info, range, interval_ms).await })
+    }
+
+    fn fetch_calendar(
+        &self,
+        range: (u64, u64),
+        currencies: Vec<String>,
+    ) -> AdapterFuture<Vec<CalendarEvent>> {
+        let config = self.config.clone();
+        Box::pin(async move { fetch_calendar(&config, range, currencies).await })
+    }
+
+    fn market_stream(
+        &self,
+        ticker_info: TickerInfo,
+        _tick_mltp: Option<TickMultiplier>,
+        push_freq: PushFrequency,
+    ) -> BoxStream<'static, Event> {
+        connect_market_stream(self.config.clone(), ticker_info, push_freq).boxed()
+    }
+
+    fn kline_stream(
+        &self,
+        _subs: Vec<(TickerInfo, Timeframe)>,
+        _market: MarketKind,
+    ) -> BoxStream<'static, Event> {
+        futures_util::stream::empty().boxed()
+    }
+
+    // Klines come through the market stream, a second connection would duplicate them
+    fn kline_feed(&self) -> KlineFeed {
+        KlineFeed::MarketStream
+    }
+
+    fn server_time_offset_ms(&self) -> i64 {
+        server_time_offset_ms()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn only_edits_to_how_streams_connect_are_material() {
+        let active = Mt5Config {
+            api_key: "key".to_string(),
+            api_secret: "secret".to_string(),
+            ..Mt5Config::default()
+        };
+
+        let cosmetic = Mt5Config {
+            auto_reconnect: false,
+            pause_when_closed: false,
+            ..active.clone()
+        };
+        assert!(active.material_changes(&cosmetic).is_empty());
+
+        let moved = Mt5Config {
+            server_addr: "10.0.0.2:9876".to_string(),
+            use_tls: true,
+            api_secret: "rotated".to_string(),
+            ..active.clone()
+        };
+        let changes = active.material_changes(&moved);
+        assert_eq!(
+            changes,
+            vec![
+                MaterialChange::ServerAddress {
+                    from: "localhost:9876".to_string(),
+                    to: "10.0.0.2:9876".to_string(),
+                },
+                MaterialChange::Tls { enabled: true },
+                MaterialChange::Credentials,
+            ]
+        );
+        // Secrets never make it into the summary
+        assert!(changes.iter().all(|c| !c.to_string().contains("rotated")));
+    }
+
     #[test]
     fn test_config_validation() {
         let mut config = Mt5Config::default();
@@ -832,6 +2290,37 @@ mod tests {
         assert!(config.validate().is_ok());
     }
 
+    #[test]
+    fn test_header_auth_modes() {
+        let mut config = Mt5Config {
+            auth_mode: AuthMode::BearerToken("  ".to_string()),
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+
+        config.auth_mode = AuthMode::BearerToken("abc".to_string());
+        assert!(config.validate().is_ok());
+        assert!(!config.sends_hmac());
+        let request = config.client_request().unwrap();
+        assert_eq!(request.headers()["authorization"], "Bearer abc");
+
+        config.auth_mode = AuthMode::Headers(vec![
+            ("CF-Access-Client-Id".to_string(), "id".to_string()),
+            ("CF-Access-Client-Secret".to_string(), "hunter2".to_string()),
+        ]);
+        let request = config.client_request().unwrap();
+        assert_eq!(request.headers()["cf-access-client-secret"], "hunter2");
+        assert!(!format!("{config:?}").contains("hunter2"));
+
+        // The additional handshake needs the HMAC credentials again
+        config.hmac_with_headers = true;
+        assert!(config.validate().is_err());
+
+        config.auth_mode = AuthMode::Headers(vec![("bad header".to_string(), "x".to_string())]);
+        config.hmac_with_headers = false;
+        assert!(config.validate().is_err());
+    }
+
     #[test]
     fn test_ws_url() {
         let config = Mt5Config {
@@ -849,6 +2338,339 @@ mod tests {
         assert_eq!(config_tls.ws_url(), "wss://example.com:9876/client");
     }
 
+    #[test]
+    fn a_picked_terminal_scopes_requests_and_symbols() {
+        let single = Mt5Config::default();
+        let request = serde_json::json!({ "type": "get_symbols" });
+        assert_eq!(single.scoped(request.clone()), request);
+        assert_eq!(single.endpoint(), single.ws_url());
+        let subscribe = SubscribeMessage {
+            msg_type: "subscribe",
+            symbols: vec!["EURUSD".to_string()],
+            channels: vec!["trade".to_string()],
+            terminal_id: single.terminal_id.as_deref(),
+        };
+        assert!(
+            !serde_json::to_string(&subscribe)
+                .unwrap()
+                .contains("terminal_id")
+        );
+
+        let picked = Mt5Config {
+            terminal_id: Some("ic-live".to_string()),
+            ..single.clone()
+        };
+        assert_eq!(picked.scoped(request)["terminal_id"], "ic-live");
+        assert_ne!(picked.endpoint(), single.endpoint());
+        assert_ne!(picked.source(), single.source());
+        assert_eq!(
+            single.material_changes(&picked),
+            vec![MaterialChange::Terminal {
+                from: None,
+                to: Some("ic-live".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn set_chart_symbol_serializes_with_the_mt5_timeframe() {
+        let msg = SetChartSymbolMessage {
+            msg_type: "set_chart_symbol",
+            symbol: "XAUUSD",
+            timeframe: Some(timeframe_to_mt5_string(Timeframe::H4)),
+            terminal_id: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&msg).unwrap(),
+            r#"{"type":"set_chart_symbol","symbol":"XAUUSD","timeframe":"H4"}"#
+        );
+
+        // Panes without a timeframe leave the chart's own, the terminal is named when picked
+        let scoped = SetChartSymbolMessage {
+            timeframe: None,
+            terminal_id: Some("ic-live"),
+            ..msg
+        };
+        assert_eq!(
+            serde_json::to_string(&scoped).unwrap(),
+            r#"{"type":"set_chart_symbol","symbol":"XAUUSD","terminal_id":"ic-live"}"#
+        );
+
+        // Older saved connections never sync
+        let saved = serde_json::to_string(&Mt5Config::default()).unwrap();
+        let saved = saved.replace(r#","sync_terminal_chart":false"#, "");
+        assert!(
+            !serde_json::from_str::<Mt5Config>(&saved)
+                .unwrap()
+                .sync_terminal_chart
+        );
+    }
+
+    const TRADE_FIXTURE: &str = r#"{"type":"trade","symbol":"EURUSD","time":1704355200123,"price":1.09514,"volume":2.50,"side":"sell"}"#;
+    const DEPTH_HISTORY_FIXTURE: &str = r#"{"type":"depth_history","symbol":"EURUSD","data":[{"time":1704355200000,"bids":[[1.09510,3.00]],"asks":[[1.09520,2.00],[1.09530,1.00]]}]}"#;
+    const DEPTH_FIXTURE: &str = r#"{"type":"depth","symbol":"EURUSD","time":1704355200456,"bids":[[1.09510,3.00],[1.09500,1.50]],"asks":[[1.09520,2.00]]}"#;
+
+    fn fixture_ticker_info() -> TickerInfo {
+        TickerInfo::new(
+            Ticker::new("EURUSD", super::super::Exchange::MetaTrader5),
+            0.00001,
+            0.01,
+            Some(100_000.0),
+        )
+    }
+
+    #[test]
+    fn auth_response_versions_are_optional() {
+        let legacy = br#"{"type":"auth_response","success":true,"server_time":1700000000000}"#;
+        let msg: ServerMessage = serde_json::from_slice(legacy).unwrap();
+        assert_eq!(msg.server_info(), ServerInfo::default());
+
+        let current =
+            br#"{"type":"auth_response","success":true,"proxy_version":"1.5.0","ea_version":2.1}"#;
+        let info = serde_json::from_slice::<ServerMessage>(current)
+            .unwrap()
+            .server_info();
+        assert_eq!(info.proxy(), Some(Version::new(1, 5, 0)));
+        assert_eq!(info.ea_version.as_deref(), Some("2.1"));
+
+        // A malformed version is dropped, not a failed handshake
+        let odd = br#"{"type":"auth_response","success":true,"proxy_version":{"major":1}}"#;
+        let msg: ServerMessage = serde_json::from_slice(odd).unwrap();
+        assert_eq!(msg.success, Some(true));
+        assert_eq!(msg.proxy_version, None);
+    }
+
+    #[test]
+    fn test_server_message_borrows_frame() {
+        let msg: ServerMessage = serde_json::from_slice(TRADE_FIXTURE.as_bytes()).unwrap();
+        assert_eq!(msg.msg_type, "trade");
+        assert!(matches!(msg.msg_type, Cow::Borrowed(_)));
+
+        let auth = br#"{"type":"auth_response","success":false,"error":"Authentication failed"}"#;
+        let msg: ServerMessage = serde_json::from_slice(auth).unwrap();
+        assert_eq!(msg.success, Some(false));
+        assert_eq!(msg.error.as_deref(), Some("Authentication failed"));
+
+        // Escaped strings can't be borrowed, but must still parse
+        let escaped = br#"{"type":"error","error":"bad \"symbol\""}"#;
+        let msg: ServerMessage = serde_json::from_slice(escaped).unwrap();
+        assert_eq!(msg.error.as_deref(), Some(r#"bad "symbol""#));
+
+        let missing =
+            br#"{"type":"error","code":"symbol_not_found","message":"Symbol not found: XAUUSD"}"#;
+        let msg: ServerMessage = serde_json::from_slice(missing).unwrap();
+        assert_eq!(msg.code.as_deref(), Some(SYMBOL_NOT_FOUND_CODE));
+        assert_eq!(msg.message.as_deref(), Some("Symbol not found: XAUUSD"));
+    }
+
+    #[test]
+    fn test_parse_session_transitions() {
+        let frames: [&[u8]; 4] = [
+            br#"{"type":"session","symbol":"EURUSD","state":"open"}"#,
+            br#"{"type":"session","symbol":"EURUSD","state":"closed","next_open":1704665100000}"#,
+            br#"{"type":"session","symbol":"EURUSD","state":"closed","next_open":1704665100000}"#,
+            br#"{"type":"session","symbol":"EURUSD","state":"open"}"#,
+        ];
+
+        let mut state = MarketState::Open;
+        let changes: Vec<MarketState> = frames
+            .iter()
+            .map(|frame| parse_session(frame, &ServerTimezone::UTC).unwrap())
+            .filter(|next| state.transition(*next))
+            .collect();
+
+        let closed = MarketState::Closed {
+            reopens_at: Some(1704665100000),
+            paused: false,
+        };
+        assert_eq!(changes, vec![closed, MarketState::Open]);
+        assert_eq!(
+            closed.banner().as_deref(),
+            Some("Market closed — reopens Sun 22:05 UTC")
+        );
+        let paused = MarketState::Closed {
+            reopens_at: Some(1704665100000),
+            paused: true,
+        };
+        assert_eq!(
+            paused.banner().as_deref(),
+            Some("Market closed — reopens Sun 22:05 UTC, stream paused")
+        );
+
+        let disabled = br#"{"type":"session","symbol":"US30","state":"trade_disabled"}"#;
+        assert_eq!(
+            parse_session(disabled, &ServerTimezone::UTC).unwrap(),
+            MarketState::TradeDisabled
+        );
+        assert!(
+            parse_session(
+                br#"{"type":"session","state":"halted"}"#,
+                &ServerTimezone::UTC
+            )
+            .is_err()
+        );
+
+        let msg: ServerMessage = serde_json::from_slice(frames[1]).unwrap();
+        assert_eq!(msg.symbol.as_deref(), Some("EURUSD"));
+    }
+
+    #[test]
+    fn test_redact_credentials_and_signatures() {
+        let config = Mt5Config {
+            api_key: "key-123".to_string(),
+            api_secret: "s3cret".to_string(),
+            auth_mode: AuthMode::BearerToken("tok-456".to_string()),
+            ..Mt5Config::default()
+        };
+
+        let echoed =
+            r#"{"type":"auth","api_key":"key-123","signature":"abcdef","token":"tok-456"}"#;
+        assert_eq!(
+            config.redact(echoed),
+            r#"{"type":"auth","api_key":"<redacted>","signature":"<redacted>","token":"<redacted>"}"#
+        );
+        assert_eq!(config.redact("bad secret s3cret"), "bad secret <redacted>");
+    }
+
+    #[test]
+    fn test_parse_trade_fixture() {
+        let ticker_info = fixture_ticker_info();
+        let trade =
+            parse_trade(TRADE_FIXTURE.as_bytes(), ticker_info, &ServerTimezone::UTC).unwrap();
+
+        assert_eq!(trade.time, 1704355200123);
+        assert!(trade.is_sell);
+        assert_eq!(
+            trade.price,
+            Price::from_f32(1.09514).round_to_min_tick(ticker_info.min_ticksize)
+        );
+        assert_eq!(trade.qty, 2.5);
+
+        let buy = TRADE_FIXTURE.replace("sell", "buy");
+        assert!(
+            !parse_trade(buy.as_bytes(), ticker_info, &ServerTimezone::UTC)
+                .unwrap()
+                .is_sell
+        );
+
+        assert!(parse_trade(b"{\"type\":\"trade\"}", ticker_info, &ServerTimezone::UTC).is_err());
+
+        // Unflagged FX ticks keep the inferred side
+        assert!(!has_aggressor_side(&ticker_info.ticker));
+    }
+
+    #[test]
+    fn test_parse_flagged_exchange_trade() {
+        let ticker_info = TickerInfo::new(
+            Ticker::new("ES-DEC", Exchange::MetaTrader5),
+            0.25,
+            1.0,
+            Some(50.0),
+        );
+
+        // The price ticked up, but the exchange says a seller hit the bid
+        let flagged = r#"{"type":"trade","symbol":"ES-DEC","time":1704355200123,"price":4780.25,"volume":3.00,"side":"buy","flags":88,"volume_real":3.0}"#;
+        let trade = parse_trade(flagged.as_bytes(), ticker_info, &ServerTimezone::UTC).unwrap();
+        assert!(trade.is_sell);
+        assert_eq!(trade.qty, 3.0);
+        assert!(has_aggressor_side(&ticker_info.ticker));
+
+        let fractional = flagged
+            .replace(r#""flags":88"#, r#""flags":56"#)
+            .replace(r#""volume_real":3.0"#, r#""volume_real":0.125"#);
+        let trade = parse_trade(fractional.as_bytes(), ticker_info, &ServerTimezone::UTC).unwrap();
+        assert!(!trade.is_sell);
+        assert_eq!(trade.qty, 0.125);
+
+        // Flags without a side bit, e.g. a bid/ask change, leave the heuristic in charge
+        let bid_ask = flagged.replace(r#""flags":88"#, r#""flags":6"#);
+        assert!(
+            !parse_trade(bid_ask.as_bytes(), ticker_info, &ServerTimezone::UTC)
+                .unwrap()
+                .is_sell
+        );
+    }
+
+    #[test]
+    fn test_parse_depth_fixture() {
+        let depth = parse_depth(
+            DEPTH_FIXTURE.as_bytes(),
+            fixture_ticker_info(),
+            &ServerTimezone::UTC,
+        )
+        .unwrap();
+
+        assert_eq!(depth.time, 1704355200456);
+        assert_eq!(depth.last_update_id, 1704355200456);
+
+        let bids: Vec<(f32, f32)> = depth.bids.iter().map(|o| (o.price, o.qty)).collect();
+        let asks: Vec<(f32, f32)> = depth.asks.iter().map(|o| (o.price, o.qty)).collect();
+        assert_eq!(bids, vec![(1.0951, 3.0), (1.095, 1.5)]);
+        assert_eq!(asks, vec![(1.0952, 2.0)]);
+    }
+
+    #[test]
+    fn server_stamped_times_are_converted_to_utc() {
+        let auth = br#"{"type":"auth_response","success":true,"server_timezone":{"utc_offset":7200,"dst":"eu"}}"#;
+        let clock = serde_json::from_slice::<ServerMessage>(auth)
+            .unwrap()
+            .server_info()
+            .server_timezone
+            .unwrap();
+        assert_eq!(
+            clock,
+            ServerTimezone {
+                utc_offset: 7200,
+                dst: Dst::Eu,
+            }
+        );
+
+        // 2024-01-04 08:00:00.123 on an EET server is 06:00:00.123 UTC
+        let trade = parse_trade(TRADE_FIXTURE.as_bytes(), fixture_ticker_info(), &clock).unwrap();
+        assert_eq!(trade.time, 1704355200123 - 2 * 3_600_000);
+        let depth = parse_depth(DEPTH_FIXTURE.as_bytes(), fixture_ticker_info(), &clock).unwrap();
+        assert_eq!(depth.time, 1704355200456 - 2 * 3_600_000);
+        assert_eq!(depth.last_update_id, depth.time);
+
+        let closed = br#"{"type":"session","state":"closed","next_open":1704665100000}"#;
+        assert_eq!(
+            parse_session(closed, &clock).unwrap(),
+            MarketState::Closed {
+                reopens_at: Some(1704665100000 - 2 * 3_600_000),
+                paused: false,
+            }
+        );
+
+        // An unreadable timezone leaves the times as sent, the handshake still succeeds
+        let odd = br#"{"type":"auth_response","success":true,"server_timezone":"EET"}"#;
+        let msg: ServerMessage = serde_json::from_slice(odd).unwrap();
+        assert_eq!(msg.success, Some(true));
+        assert_eq!(msg.server_timezone, None);
+    }
+
+    #[test]
+    fn calendar_is_read_on_the_server_clock_once_advertised() {
+        let auth = br#"{"type":"auth_response","success":true,"capabilities":["calendar"],"server_timezone":{"utc_offset":7200}}"#;
+        let info = serde_json::from_slice::<ServerMessage>(auth)
+            .unwrap()
+            .server_info();
+        assert!(info.supports(CALENDAR_CAPABILITY));
+
+        let legacy = br#"{"type":"auth_response","success":true,"capabilities":"calendar"}"#;
+        let msg: ServerMessage = serde_json::from_slice(legacy).unwrap();
+        assert!(!msg.server_info().supports(CALENDAR_CAPABILITY));
+
+        let frame = r#"{"type":"calendar","data":[{"time":1704465000000,"currency":"USD","importance":"high","title":"Nonfarm Payrolls","actual":"216K"},{"time":1704445200000,"currency":"EUR","importance":2,"title":"CPI y/y","forecast":"3.0%"}]}"#;
+        let events = parse_calendar(frame, &info.server_timezone.unwrap()).unwrap();
+
+        assert_eq!(events.len(), 2);
+        // Oldest first, 11:00 and 16:30 on a UTC+2 server
+        assert_eq!(events[0].currency, "EUR");
+        assert_eq!(events[0].time, 1704445200000 - 2 * 3_600_000);
+        assert_eq!(events[1].time, 1704465000000 - 2 * 3_600_000);
+        assert_eq!(events[1].importance, crate::calendar::Importance::High);
+    }
+
     #[test]
     fn test_hmac_signature() {
         // Test with same values as Go test
@@ -862,4 +2684,191 @@ mod tests {
             "9ee72b2e88059adefc665b4fff96b888f5d71dfbdcbd8c289e5eb518dc0000bf"
         );
     }
+
+    #[test]
+    fn test_depth_history_fixture() {
+        let resp: DepthHistoryResponse = serde_json::from_str(DEPTH_HISTORY_FIXTURE).unwrap();
+        let payloads: Vec<DepthPayload> = resp
+            .data
+            .into_iter()
+            .map(|depth| depth.into_payload(&ServerTimezone::UTC))
+            .collect();
+
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].time, 1704355200000);
+        assert_eq!(payloads[0].bids.len(), 1);
+        assert_eq!(payloads[0].asks.len(), 2);
+    }
+
+    #[test]
+    fn test_malformed_symbols_are_reported_not_dropped() {
+        let text = r#"{"type":"symbols","data":[
+            {"symbol":"EURUSD","tick_size":0.00001,"min_lot":0.01,"contract_size":100000,"digits":5},
+            {"symbol":"BROKEN","tick_size":"n/a","min_lot":0.01,"contract_size":1,"digits":2},
+            {"symbol":"ZERO","tick_size":0,"min_lot":0.01,"contract_size":1,"digits":2},
+            {"tick_size":0.01},
+            {"symbol":"XAUUSD","tick_size":0.01,"min_lot":0.01,"contract_size":100,"digits":2,"tick_value":1.0}
+        ]}"#;
+
+        let source = SourceId::of("MT5 malformed:443");
+        let outcome = parse_symbols(text, source).unwrap();
+
+        assert_eq!(outcome.infos.len(), 2);
+        assert!(outcome.infos.contains_key(
+            &Ticker::new("XAUUSD", Exchange::MetaTrader5).on_connection(Some(source))
+        ));
+
+        let failed: Vec<&str> = outcome.failures.iter().map(|(s, _)| s.as_str()).collect();
+        assert_eq!(failed, ["BROKEN", "ZERO", "#4"]);
+        assert!(outcome.failures[1].1.contains("not positive"));
+    }
+
+    #[test]
+    fn test_tick_tables_are_read_with_the_symbols() {
+        let text = r#"{"type":"symbols","data":[
+            {"symbol":"FDAX","tick_size":0.25,"min_lot":1,"contract_size":1,"digits":2,"tick_table":[[0,0.25],[1000,0.5]]},
+            {"symbol":"BACKWARDS","tick_size":0.25,"min_lot":1,"contract_size":1,"digits":2,"tick_table":[[1000,0.5],[0,0.25]]}
+        ]}"#;
+
+        let source = SourceId::of("MT5 tiered:443");
+        let outcome = parse_symbols(text, source).unwrap();
+        let ticker = Ticker::new("FDAX", Exchange::MetaTrader5).on_connection(Some(source));
+        let info = outcome.infos[&ticker].unwrap();
+
+        // Quarters are written with two decimals rather than rounded to tenths
+        assert_eq!(info.min_ticksize, crate::util::MinTicksize::new(-2));
+        assert_eq!(
+            info.tick_rule().round(Price::from_f32(1000.3)),
+            Price::from_units(100_050_000_000)
+        );
+        assert_eq!(outcome.failures.len(), 1);
+        assert!(outcome.failures[0].1.contains("ascending"));
+    }
+
+    #[test]
+    fn test_symbols_without_a_readable_entry_are_an_error() {
+        let malformed = r#"{"data":[{"symbol":"BROKEN","tick_size":"n/a"}]}"#;
+        let source = SourceId::of("MT5 unreadable:443");
+        let err = parse_symbols(malformed, source).unwrap_err().to_string();
+        assert!(err.contains("none of the 1 symbols"), "{err}");
+        assert!(err.contains("BROKEN"), "{err}");
+
+        assert!(parse_symbols(r#"{"data":[]}"#, source).is_err());
+        assert!(parse_symbols("not json", source).is_err());
+    }
+
+    #[test]
+    fn same_symbol_of_two_connections_keeps_both_tick_sizes() {
+        let listing = |tick_size: f32| {
+            format!(
+                r#"{{"data":[{{"symbol":"EURUSD","tick_size":{tick_size},"min_lot":0.01,"contract_size":100000,"digits":5}}]}}"#
+            )
+        };
+        let ftmo = name_connection("MT5 ftmo:443");
+        let icm = name_connection("MT5 icm:443");
+
+        let mut listed = parse_symbols(&listing(0.00001), ftmo).unwrap().infos;
+        listed.extend(parse_symbols(&listing(0.0001), icm).unwrap().infos);
+        assert_eq!(listed.len(), 2);
+
+        let tick_of = |source| {
+            let ticker = Ticker::new("EURUSD", Exchange::MetaTrader5).on_connection(Some(source));
+            listed[&ticker].map(|info| info.min_ticksize.as_f32())
+        };
+        assert_eq!(tick_of(ftmo), Some(0.00001));
+        assert_eq!(tick_of(icm), Some(0.0001));
+        assert_eq!(connection_name(icm).as_deref(), Some("MT5 icm:443"));
+
+        // Saved and read back, the tickers still tell the connections apart
+        for ticker in listed.keys() {
+            let json = serde_json::to_string(ticker).unwrap();
+            assert_eq!(serde_json::from_str::<Ticker>(&json).unwrap(), *ticker);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_symbol_refreshes_send_one_request() {
+        use futures_util::{SinkExt as _, StreamExt as _};
+        use std::sync::atomic::AtomicUsize;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Mt5Config {
+            server_addr: listener.local_addr().unwrap().to_string(),
+            auth_mode: AuthMode::BearerToken("tok".to_string()),
+            ..Mt5Config::default()
+        };
+
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&requests);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let counted = Arc::clone(&counted);
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    if let Some(Ok(Message::Text(text))) = ws.next().await
+                        && text.contains("get_symbols")
+                    {
+                        counted.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        let body = r#"{"type":"symbols","data":[{"symbol":"EURUSD","tick_size":0.00001,"min_lot":0.01,"contract_size":100000,"digits":5}]}"#;
+                        ws.send(Message::Text(body.to_string())).await.ok();
+                    }
+                });
+            }
+        });
+
+        let refreshes = (0..10).map(|_| fetch_ticksize(&config));
+        let results = futures_util::future::join_all(refreshes).await;
+
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+        assert!(!refresh_in_progress(&config));
+        for outcome in results {
+            let symbols = outcome.unwrap().infos;
+            assert_eq!(symbols.len(), 1);
+            assert!(symbols.contains_key(
+                &Ticker::new("EURUSD", Exchange::MetaTrader5).on_connection(Some(config.source()))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn set_chart_symbol_reaches_the_proxy() {
+        use futures_util::StreamExt as _;
+        use tokio_tungstenite::tungstenite::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Mt5Config {
+            server_addr: listener.local_addr().unwrap().to_string(),
+            auth_mode: AuthMode::BearerToken("tok".to_string()),
+            terminal_id: Some("ic-live".to_string()),
+            sync_terminal_chart: true,
+            ..Mt5Config::default()
+        };
+
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            if let Some(Ok(Message::Text(text))) = ws.next().await {
+                tx.send(text.to_string()).ok();
+            }
+        });
+
+        let ticker = Ticker::new("EURUSD", Exchange::MetaTrader5);
+        set_chart_symbol(&config, ticker, Some(Timeframe::M15))
+            .await
+            .unwrap();
+
+        let received: serde_json::Value = serde_json::from_str(&rx.await.unwrap()).unwrap();
+        assert_eq!(
+            received,
+            serde_json::json!({
+                "type": "set_chart_symbol",
+                "symbol": "EURUSD",
+                "timeframe": "M15",
+                "terminal_id": "ic-live",
+            })
+        );
+    }
 }
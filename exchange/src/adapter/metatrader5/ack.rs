@@ -0,0 +1,252 @@
+//! Answers the proxy gives to subscribe requests.
+//!
+//! Proxies listing [`SUBSCRIBE_ACK_CAPABILITY`](super::SUBSCRIBE_ACK_CAPABILITY) answer each
+//! subscribe with a `subscribe_response` naming the symbols and channels they accepted and the
+//! ones they refused, with why. A symbol the terminal has but that isn't in its Market Watch is
+//! added and subscribed once more before the refusal reaches its panes.
+//!
+//! Older proxies don't answer at all, so a subscription left unanswered for
+//! [`ACK_TIMEOUT`] is only reported when the proxy is known to answer: it said so in its
+//! handshake or already answered one on this connection.
+
+use super::{NOT_IN_MARKET_WATCH_CODE, SubscribeResponse};
+
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// How long a subscription waits for the proxy to answer it
+pub(super) const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What to do about a symbol the proxy answered for
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Verdict {
+    /// Add the symbol to the Market Watch and subscribe it again
+    Retry(String),
+    /// The proxy refused `channels` of the symbol, all of them when it refused the whole symbol
+    Rejected {
+        symbol: String,
+        channels: Vec<String>,
+        reason: String,
+    },
+}
+
+/// Subscriptions of one connection waiting for their answer
+#[derive(Debug, Default)]
+pub(super) struct Acks {
+    /// When each pending symbol's answer is due
+    pending: HashMap<String, Instant>,
+    /// Symbols already added to the Market Watch once
+    retried: HashSet<String>,
+    /// The proxy answered a subscription on this connection
+    answered: bool,
+}
+
+impl Acks {
+    /// Waits for the answer to a subscription of `symbols`
+    pub(super) fn expect(&mut self, symbols: &[String], now: Instant) {
+        for symbol in symbols {
+            self.pending.insert(symbol.clone(), now + ACK_TIMEOUT);
+        }
+    }
+
+    /// No answer is needed anymore, the symbol's last pane went
+    pub(super) fn forget(&mut self, symbol: &str) {
+        self.pending.remove(symbol);
+        self.retried.remove(symbol);
+    }
+
+    /// Whether an overdue answer means something went wrong rather than an older proxy
+    pub(super) fn is_expected(&self, advertised: bool) -> bool {
+        advertised || self.answered
+    }
+
+    /// Settles the pending symbols `response` answers for. `channels` are the ones every
+    /// subscription asks for, a refusal naming none refused them all.
+    pub(super) fn settle(
+        &mut self,
+        response: SubscribeResponse,
+        channels: &[&str],
+        now: Instant,
+    ) -> Vec<Verdict> {
+        self.answered = true;
+
+        // A symbol may be refused channel by channel
+        let mut refused: Vec<(String, Vec<String>, String, bool)> = vec![];
+        for rejection in response.rejected {
+            if !self.pending.contains_key(&rejection.symbol) {
+                log::warn!(
+                    "MT5 proxy refused {}: {}",
+                    rejection.symbol,
+                    rejection.reason
+                );
+                continue;
+            }
+
+            let not_in_market_watch = rejection.code.as_deref() == Some(NOT_IN_MARKET_WATCH_CODE);
+            let rejected_channels = if rejection.channels.is_empty() {
+                channels.iter().map(|c| c.to_string()).collect()
+            } else {
+                rejection.channels
+            };
+
+            match refused.iter_mut().find(|(s, ..)| *s == rejection.symbol) {
+                Some((_, known, reason, missing)) => {
+                    for channel in rejected_channels {
+                        if !known.contains(&channel) {
+                            known.push(channel);
+                        }
+                    }
+                    if reason.is_empty() {
+                        *reason = rejection.reason;
+                    }
+                    *missing |= not_in_market_watch;
+                }
+                None => refused.push((
+                    rejection.symbol,
+                    rejected_channels,
+                    rejection.reason,
+                    not_in_market_watch,
+                )),
+            }
+        }
+
+        // After the refusals, a symbol accepted on some channels may be refused the others
+        for accepted in &response.accepted {
+            if self.pending.remove(&accepted.symbol).is_some() {
+                log::debug!(
+                    "MT5 proxy accepted {} {}",
+                    accepted.symbol,
+                    accepted.channels.join("/")
+                );
+            }
+        }
+
+        refused
+            .into_iter()
+            .map(|(symbol, channels, reason, not_in_market_watch)| {
+                if not_in_market_watch && self.retried.insert(symbol.clone()) {
+                    self.pending.insert(symbol.clone(), now + ACK_TIMEOUT);
+                    Verdict::Retry(symbol)
+                } else {
+                    self.pending.remove(&symbol);
+                    Verdict::Rejected {
+                        symbol,
+                        channels,
+                        reason,
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// When the soonest pending answer is due
+    pub(super) fn deadline(&self) -> Option<Instant> {
+        self.pending.values().min().copied()
+    }
+
+    /// Symbols whose answer is overdue, no longer waited for
+    pub(super) fn take_overdue(&mut self, now: Instant) -> Vec<String> {
+        let overdue: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, due)| **due <= now)
+            .map(|(symbol, _)| symbol.clone())
+            .collect();
+        for symbol in &overdue {
+            self.pending.remove(symbol);
+        }
+        overdue
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANNELS: [&str; 2] = ["trade", "depth"];
+
+    fn response(json: &str) -> SubscribeResponse {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn refusals_are_merged_per_symbol() {
+        let now = Instant::now();
+        let mut acks = Acks::default();
+        acks.expect(&["EURUSD".to_string(), "XAUUSD".to_string()], now);
+        assert!(!acks.is_expected(false));
+
+        let verdicts = acks.settle(
+            response(
+                r#"{"accepted":[{"symbol":"EURUSD","channels":["trade","depth"]}],
+                    "rejected":[
+                        {"symbol":"XAUUSD","channels":["depth"],"reason":"DOM not available"},
+                        {"symbol":"XAUUSD","channels":["trade"],"reason":""},
+                        {"symbol":"USDJPY","reason":"not subscribed here"}
+                    ]}"#,
+            ),
+            &CHANNELS,
+            now,
+        );
+
+        assert_eq!(
+            verdicts,
+            vec![Verdict::Rejected {
+                symbol: "XAUUSD".to_string(),
+                channels: vec!["depth".to_string(), "trade".to_string()],
+                reason: "DOM not available".to_string(),
+            }]
+        );
+        assert_eq!(acks.deadline(), None);
+        assert!(acks.is_expected(false));
+    }
+
+    #[test]
+    fn missing_from_market_watch_is_retried_once() {
+        let now = Instant::now();
+        let mut acks = Acks::default();
+        acks.expect(&["GBPJPY".to_string()], now);
+
+        let missing = r#"{"rejected":[{"symbol":"GBPJPY","code":"not_in_market_watch","reason":"Not in Market Watch"}]}"#;
+
+        assert_eq!(
+            acks.settle(response(missing), &CHANNELS, now),
+            vec![Verdict::Retry("GBPJPY".to_string())]
+        );
+        assert_eq!(acks.deadline(), Some(now + ACK_TIMEOUT));
+
+        assert_eq!(
+            acks.settle(response(missing), &CHANNELS, now),
+            vec![Verdict::Rejected {
+                symbol: "GBPJPY".to_string(),
+                channels: vec!["trade".to_string(), "depth".to_string()],
+                reason: "Not in Market Watch".to_string(),
+            }]
+        );
+
+        // Subscribed afresh after its panes left, it gets another try
+        acks.forget("GBPJPY");
+        acks.expect(&["GBPJPY".to_string()], now);
+        assert_eq!(
+            acks.settle(response(missing), &CHANNELS, now),
+            vec![Verdict::Retry("GBPJPY".to_string())]
+        );
+    }
+
+    #[test]
+    fn unanswered_subscriptions_come_due() {
+        let now = Instant::now();
+        let mut acks = Acks::default();
+        acks.expect(&["EURUSD".to_string()], now);
+        acks.expect(&["XAUUSD".to_string()], now + Duration::from_secs(1));
+
+        assert!(acks.take_overdue(now).is_empty());
+        assert_eq!(acks.take_overdue(now + ACK_TIMEOUT), ["EURUSD"]);
+        assert_eq!(
+            acks.deadline(),
+            Some(now + Duration::from_secs(1) + ACK_TIMEOUT)
+        );
+        assert!(acks.is_expected(true));
+    }
+}
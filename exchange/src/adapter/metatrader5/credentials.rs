@@ -0,0 +1,151 @@
+//! API key and secret given as `env:NAME`, read from the environment when connecting.
+//!
+//! The reference is what's kept in the config and what gets saved, so a key kept out of the state
+//! file stays out of it. Variables from a `.env` file loaded with [`load_env_file`] are looked up
+//! after the process environment, which takes precedence like it does for other dotenv loaders.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{LazyLock, RwLock};
+
+pub const ENV_PREFIX: &str = "env:";
+
+static ENV_FILE: LazyLock<RwLock<HashMap<String, String>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Name of the variable `value` refers to, `None` for a value given as is
+pub fn env_reference(value: &str) -> Option<&str> {
+    value
+        .trim()
+        .strip_prefix(ENV_PREFIX)
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+}
+
+/// Whether `value` is given as is, for skipping it when serializing
+pub fn is_literal(value: &str) -> bool {
+    env_reference(value).is_none()
+}
+
+/// `value`, or the variable it refers to. `what` names the field in the error.
+pub fn resolve(value: &str, what: &str) -> Result<String, String> {
+    let Some(name) = env_reference(value) else {
+        return Ok(value.to_string());
+    };
+
+    lookup(name)
+        .filter(|resolved| !resolved.is_empty())
+        .ok_or_else(|| format!("{what} refers to environment variable {name}, which is not set"))
+}
+
+fn lookup(name: &str) -> Option<String> {
+    std::env::var(name).ok().or_else(|| {
+        ENV_FILE
+            .read()
+            .ok()
+            .and_then(|vars| vars.get(name).cloned())
+    })
+}
+
+/// Loads the variables of a `.env` file for credentials to refer to, returns how many it set
+pub fn load_env_file(path: &Path) -> Result<usize, String> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| format!("Couldn't read {}: {e}", path.display()))?;
+    let vars = parse_env_file(&text);
+    let count = vars.len();
+
+    if let Ok(mut loaded) = ENV_FILE.write() {
+        loaded.extend(vars);
+    }
+    Ok(count)
+}
+
+/// `NAME=value` lines, skipping blank ones and `#` comments. An `export ` prefix and quotes
+/// around the value are dropped.
+pub fn parse_env_file(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (name, value) = line.split_once('=')?;
+            let name = name.trim();
+            if name.is_empty() {
+                return None;
+            }
+
+            let value = value.trim();
+            let value = [('"', '"'), ('\'', '\'')]
+                .iter()
+                .find_map(|&(open, close)| {
+                    value
+                        .strip_prefix(open)
+                        .and_then(|rest| rest.strip_suffix(close))
+                })
+                .unwrap_or(value);
+            Some((name.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::metatrader5::Mt5Config;
+
+    #[test]
+    fn references_resolve_from_the_env_file_and_name_missing_variables() {
+        let vars = parse_env_file(
+            "# proxy credentials\nexport FS_TEST_MT5_KEY=\"key-123\"\n\nFS_TEST_MT5_SECRET='s3cret'\nnot a line\n",
+        );
+        assert_eq!(
+            vars,
+            [
+                ("FS_TEST_MT5_KEY".to_string(), "key-123".to_string()),
+                ("FS_TEST_MT5_SECRET".to_string(), "s3cret".to_string()),
+            ]
+        );
+        ENV_FILE.write().unwrap().extend(vars);
+
+        assert_eq!(
+            resolve("env:FS_TEST_MT5_KEY", "API key").unwrap(),
+            "key-123"
+        );
+        assert_eq!(resolve("plain-key", "API key").unwrap(), "plain-key");
+        assert_eq!(
+            resolve("env:FS_TEST_MT5_UNSET", "API secret").unwrap_err(),
+            "API secret refers to environment variable FS_TEST_MT5_UNSET, which is not set"
+        );
+        assert_eq!(env_reference("env:"), None);
+    }
+
+    #[test]
+    fn references_are_saved_never_what_they_resolve_to() {
+        ENV_FILE.write().unwrap().insert(
+            "FS_TEST_MT5_SAVED".to_string(),
+            "resolved-s3cret".to_string(),
+        );
+
+        let config = Mt5Config {
+            api_key: "env:FS_TEST_MT5_SAVED".to_string(),
+            api_secret: "env:FS_TEST_MT5_SAVED".to_string(),
+            ..Mt5Config::default()
+        };
+        assert_eq!(config.credentials().unwrap().1, "resolved-s3cret");
+
+        let json = serde_json::to_string(&config).unwrap();
+        assert!(!json.contains("resolved-s3cret"));
+        assert!(json.contains(r#""api_secret":"env:FS_TEST_MT5_SAVED""#));
+
+        // A secret given as is stays unsaved
+        let literal = Mt5Config {
+            api_secret: "s3cret".to_string(),
+            ..config
+        };
+        assert!(
+            !serde_json::to_string(&literal)
+                .unwrap()
+                .contains("api_secret")
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Telling an address that points at something other than the proxy apart from a proxy that's
+//! down.
+//!
+//! Pointed at the EA's own port or some web server, the connection otherwise fails with a
+//! timeout or a JSON error. What answered is judged by how the handshake went: a plain HTTP
+//! answer to the WebSocket upgrade, a WebSocket that never answers `auth`, or a first frame
+//! that isn't a proxy message, a JSON object with a `type`.
+
+use crate::adapter::AdapterError;
+
+use std::time::Duration;
+use tokio_tungstenite::tungstenite;
+
+/// Part of every diagnosis, how errors are recognized as one
+const NOT_A_PROXY: &str = "does not appear to be an mt5-proxy";
+
+/// How much of a foreign frame a diagnosis quotes
+const EXCERPT_CHARS: usize = 40;
+
+/// What gave away that the server isn't a proxy
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Symptom {
+    /// Answered the upgrade with an HTTP status other than switching protocols
+    Http(u16),
+    /// Answered the upgrade with something that isn't HTTP, or HTTP that doesn't upgrade
+    NotHttp,
+    /// Upgraded but never answered the auth handshake
+    Silent(Duration),
+    /// Sent a frame that isn't a proxy message, quoted
+    Foreign(String),
+}
+
+impl Symptom {
+    /// The symptom a failed upgrade shows, `None` for ones that say nothing about what answered,
+    /// like a refused connection, or a proxy refusing the credentials
+    pub(super) fn of_upgrade(error: &tungstenite::Error) -> Option<Symptom> {
+        match error {
+            tungstenite::Error::Http(response) => {
+                let status = response.status().as_u16();
+                (!matches!(status, 401 | 403)).then_some(Symptom::Http(status))
+            }
+            tungstenite::Error::Protocol(_) | tungstenite::Error::HttpFormat(_) => {
+                Some(Symptom::NotHttp)
+            }
+            _ => None,
+        }
+    }
+
+    /// A frame arriving where the proxy's first message belongs, `None` when it is one
+    pub(super) fn of_frame(frame: &tungstenite::Message) -> Option<Symptom> {
+        match frame {
+            tungstenite::Message::Text(text) if is_proxy_message(text) => None,
+            tungstenite::Message::Text(text) => Some(Symptom::Foreign(excerpt(text))),
+            tungstenite::Message::Binary(_) => Some(Symptom::Foreign("a binary frame".to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// What to tell the user about the server at `server_addr`
+pub(super) fn diagnose(server_addr: &str, symptom: &Symptom) -> String {
+    let detail = match symptom {
+        Symptom::Http(status) => {
+            format!("it answered with HTTP {status} instead of opening a WebSocket")
+        }
+        Symptom::NotHttp => "it didn't answer the WebSocket upgrade".to_string(),
+        Symptom::Silent(after) => format!(
+            "it opened a WebSocket but didn't answer the handshake within {}s",
+            after.as_secs()
+        ),
+        Symptom::Foreign(excerpt) => format!("it sent {excerpt}"),
+    };
+
+    format!("The server at {server_addr} answered but {NOT_A_PROXY} — check the port ({detail})")
+}
+
+/// Whether an error is a diagnosis of the server not being a proxy
+pub(super) fn is_not_a_proxy(error: &AdapterError) -> bool {
+    matches!(error, AdapterError::WebsocketError(reason) if reason.contains(NOT_A_PROXY))
+}
+
+/// Whether a frame is a JSON object with a string `type`, as every proxy message is
+fn is_proxy_message(text: &str) -> bool {
+    serde_json::from_str::<serde_json::Value>(text)
+        .is_ok_and(|value| value.get("type").is_some_and(serde_json::Value::is_string))
+}
+
+fn excerpt(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() > EXCERPT_CHARS {
+        let cut: String = text.chars().take(EXCERPT_CHARS).collect();
+        format!("\"{cut}…\"")
+    } else {
+        format!("\"{text}\"")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{AuthMode, Mt5Config};
+    use super::*;
+
+    use futures_util::{SinkExt as _, StreamExt as _};
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+    use tokio_tungstenite::tungstenite::Message;
+
+    /// Config for a local server, signing in with the HMAC handshake
+    fn config(server_addr: String) -> Mt5Config {
+        Mt5Config {
+            server_addr,
+            api_key: "key".to_string(),
+            api_secret: "secret".to_string(),
+            auth_mode: AuthMode::Hmac,
+            timeout_secs: 1,
+            ..Mt5Config::default()
+        }
+    }
+
+    /// A server answering every connection with `answer`
+    async fn mock_server<F, Fut>(answer: F) -> Mt5Config
+    where
+        F: Fn(tokio::net::TcpStream) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = config(listener.local_addr().unwrap().to_string());
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(answer(stream));
+            }
+        });
+        config
+    }
+
+    /// Reads the upgrade request up to its blank line
+    async fn read_request(stream: &mut tokio::net::TcpStream) {
+        let (mut request, mut buf) = (vec![], [0; 1024]);
+        while let Ok(read @ 1..) = stream.read(&mut buf).await {
+            request.extend_from_slice(&buf[..read]);
+            if request.windows(4).any(|end| end == b"\r\n\r\n") {
+                break;
+            }
+        }
+    }
+
+    /// A WebSocket server sending `frame` once the client says something
+    async fn mock_socket(frame: Option<Message>) -> Mt5Config {
+        mock_server(move |stream| {
+            let frame = frame.clone();
+            async move {
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                ws.next().await;
+                if let Some(frame) = frame {
+                    ws.send(frame).await.ok();
+                }
+                // Held open, so only the handshake timeout ends the wait
+                tokio::time::sleep(Duration::from_secs(5)).await;
+            }
+        })
+        .await
+    }
+
+    async fn diagnosed(config: &Mt5Config) -> (String, String) {
+        let tested = config.test_connection().await.unwrap_err();
+        let streamed = super::super::multiplex::connect(config)
+            .await
+            .map(|_| ())
+            .unwrap_err();
+        assert_eq!(streamed.0, super::super::reconnect::Cause::NotAProxy);
+
+        (tested, streamed.1.to_string())
+    }
+
+    #[tokio::test]
+    async fn a_web_server_is_told_apart() {
+        let config = mock_server(|mut stream| async move {
+            read_request(&mut stream).await;
+            let page = "HTTP/1.1 404 Not Found\r\nContent-Length: 9\r\n\r\nNot Found";
+            stream.write_all(page.as_bytes()).await.ok();
+        })
+        .await;
+
+        let (tested, streamed) = diagnosed(&config).await;
+        for error in [tested, streamed] {
+            assert!(error.contains(NOT_A_PROXY), "{error}");
+            assert!(error.contains(&config.server_addr));
+            assert!(error.contains("HTTP 404"));
+        }
+    }
+
+    #[tokio::test]
+    async fn a_raw_socket_is_told_apart() {
+        let config = mock_server(|mut stream| async move {
+            read_request(&mut stream).await;
+            stream.write_all(b"EA bridge v2 ready\n").await.ok();
+        })
+        .await;
+
+        let (tested, streamed) = diagnosed(&config).await;
+        for error in [tested, streamed] {
+            assert!(
+                error.contains("didn't answer the WebSocket upgrade"),
+                "{error}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn a_silent_socket_is_told_apart() {
+        let config = mock_socket(None).await;
+
+        let (tested, streamed) = diagnosed(&config).await;
+        for error in [tested, streamed] {
+            assert!(
+                error.contains("didn't answer the handshake within 1s"),
+                "{error}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn foreign_frames_are_told_apart() {
+        let config = mock_socket(Some(Message::Text(
+            "Welcome to the echo server".to_string(),
+        )))
+        .await;
+        let (tested, streamed) = diagnosed(&config).await;
+        for error in [tested, streamed] {
+            assert!(
+                error.contains("it sent \"Welcome to the echo server\""),
+                "{error}"
+            );
+        }
+
+        // JSON, but not a proxy message
+        let config = mock_socket(Some(Message::Text(r#"{"event":"hello"}"#.to_string()))).await;
+        let (tested, _) = diagnosed(&config).await;
+        assert!(tested.contains(NOT_A_PROXY), "{tested}");
+    }
+
+    #[tokio::test]
+    async fn a_proxy_refusing_credentials_is_not_misdiagnosed() {
+        let config = mock_socket(Some(Message::Text(
+            r#"{"type":"auth_response","success":false,"error":"Authentication failed"}"#
+                .to_string(),
+        )))
+        .await;
+
+        let tested = config.test_connection().await.unwrap_err();
+        assert!(!tested.contains(NOT_A_PROXY), "{tested}");
+        assert!(tested.contains("Authentication failed"));
+
+        let excerpted = excerpt(&"x".repeat(100));
+        assert_eq!(excerpted.chars().count(), EXCERPT_CHARS + 3);
+    }
+}
@@ -0,0 +1,320 @@
+//! Simulated network faults on the MT5 market stream, for reproducing reports like a chart
+//! lagging behind the terminal. Only built with the `fault-injection` feature.
+//!
+//! Faults apply to the raw frames a pane receives from the shared socket, before anything is
+//! parsed, so the adapter handles them the way it would handle a bad network. Frames are delayed
+//! in arrival order, a jittered frame holds back the ones behind it like TCP would. Connection
+//! events go through the same queue, a disconnect doesn't overtake the frames before it.
+
+use super::ServerMessage;
+use super::multiplex::{Attachment, Feed};
+
+use std::collections::VecDeque;
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A partly filled reorder window is let through after this long without new frames
+const WINDOW_IDLE_FLUSH: Duration = Duration::from_millis(100);
+
+static FAULTS: RwLock<Faults> = RwLock::new(Faults::NONE);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Faults {
+    /// Added to every frame
+    pub delay: Duration,
+    /// Up to this much more, picked per frame
+    pub jitter: Duration,
+    /// Share of depth frames dropped, from 0 to 1
+    pub drop_depth: f32,
+    /// Frames are shuffled in batches of this many, below 2 keeps the order
+    pub reorder_window: usize,
+    /// Share of trade frames delivered twice, from 0 to 1
+    pub duplicate_trades: f32,
+}
+
+impl Faults {
+    pub const NONE: Faults = Faults {
+        delay: Duration::ZERO,
+        jitter: Duration::ZERO,
+        drop_depth: 0.0,
+        reorder_window: 0,
+        duplicate_trades: 0.0,
+    };
+}
+
+impl Default for Faults {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// Applies to every MT5 stream from its next frame on
+pub fn set(faults: Faults) {
+    *FAULTS.write().unwrap_or_else(|e| e.into_inner()) = faults;
+}
+
+pub fn current() -> Faults {
+    *FAULTS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A pane's attachment with the current faults applied to what it receives
+pub(super) struct Faulty {
+    inner: Attachment,
+    injector: Injector,
+    closed: bool,
+}
+
+impl Faulty {
+    pub(super) fn new(inner: Attachment) -> Self {
+        Self {
+            inner,
+            injector: Injector::new(fastrand::Rng::new()),
+            closed: false,
+        }
+    }
+
+    /// Same as [`Attachment::recv`], cancel safe as that is
+    pub(super) async fn recv(&mut self) -> Option<Feed> {
+        loop {
+            if let Some(feed) = self.injector.ready.pop_front() {
+                return Some(feed);
+            }
+            if self.closed {
+                return None;
+            }
+
+            let faults = current();
+            let due = self.injector.next_release();
+            let idle = !self.injector.window.is_empty();
+
+            tokio::select! {
+                feed = self.inner.recv() => match feed {
+                    Some(feed) => self.injector.admit(feed, &faults, Instant::now()),
+                    None => {
+                        self.injector.drain();
+                        self.closed = true;
+                    }
+                },
+                () = tokio::time::sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => {
+                    self.injector.release_due(&faults, Instant::now());
+                }
+                () = tokio::time::sleep(WINDOW_IDLE_FLUSH), if idle => {
+                    self.injector.flush_window();
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FrameKind {
+    Depth,
+    Trade,
+    Other,
+}
+
+fn frame_kind(frame: &str) -> FrameKind {
+    match serde_json::from_str::<ServerMessage>(frame) {
+        Ok(message) if message.msg_type == "depth" => FrameKind::Depth,
+        Ok(message) if message.msg_type == "trade" => FrameKind::Trade,
+        _ => FrameKind::Other,
+    }
+}
+
+/// Frames pass through three stages: faults on the frame itself, the delay queue, then the
+/// reorder window, after which they're ready to be delivered
+struct Injector {
+    rng: fastrand::Rng,
+    delayed: VecDeque<(Instant, Feed)>,
+    window: Vec<Feed>,
+    ready: VecDeque<Feed>,
+}
+
+impl Injector {
+    fn new(rng: fastrand::Rng) -> Self {
+        Self {
+            rng,
+            delayed: VecDeque::new(),
+            window: vec![],
+            ready: VecDeque::new(),
+        }
+    }
+
+    fn admit(&mut self, feed: Feed, faults: &Faults, now: Instant) {
+        let copies = match &feed {
+            Feed::Frame(frame) => match frame_kind(frame) {
+                FrameKind::Depth if self.rng.f32() < faults.drop_depth => 0,
+                FrameKind::Trade if self.rng.f32() < faults.duplicate_trades => 2,
+                _ => 1,
+            },
+            _ => 1,
+        };
+
+        for _ in 0..copies {
+            self.delay(feed.clone(), faults, now);
+        }
+    }
+
+    fn delay(&mut self, feed: Feed, faults: &Faults, now: Instant) {
+        let jitter = if faults.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            let max = faults.jitter.as_millis() as u64;
+            Duration::from_millis(self.rng.u64(0..=max))
+        };
+
+        let release = now + faults.delay + jitter;
+        // Never before the frame ahead of it
+        let release = self
+            .delayed
+            .back()
+            .map_or(release, |(last, _)| release.max(*last));
+
+        self.delayed.push_back((release, feed));
+        self.release_due(faults, now);
+    }
+
+    fn next_release(&self) -> Option<Instant> {
+        self.delayed.front().map(|(release, _)| *release)
+    }
+
+    fn release_due(&mut self, faults: &Faults, now: Instant) {
+        while let Some((release, _)) = self.delayed.front()
+            && *release <= now
+        {
+            if let Some((_, feed)) = self.delayed.pop_front() {
+                self.reorder(feed, faults);
+            }
+        }
+    }
+
+    fn reorder(&mut self, feed: Feed, faults: &Faults) {
+        if faults.reorder_window < 2 || !matches!(feed, Feed::Frame(_)) {
+            self.flush_window();
+            self.ready.push_back(feed);
+            return;
+        }
+
+        self.window.push(feed);
+        if self.window.len() >= faults.reorder_window {
+            self.rng.shuffle(&mut self.window);
+            self.flush_window();
+        }
+    }
+
+    fn flush_window(&mut self) {
+        self.ready.extend(self.window.drain(..));
+    }
+
+    /// Everything held back is let through at once, for a socket that's gone
+    fn drain(&mut self) {
+        self.flush_window();
+        self.ready
+            .extend(self.delayed.drain(..).map(|(_, feed)| feed));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(n: u32) -> Feed {
+        Feed::Frame(format!(r#"{{"type":"depth","n":{n}}}"#).into())
+    }
+
+    fn trade(n: u32) -> Feed {
+        Feed::Frame(format!(r#"{{"type":"trade","n":{n}}}"#).into())
+    }
+
+    fn frames(feeds: impl IntoIterator<Item = Feed>) -> Vec<String> {
+        feeds
+            .into_iter()
+            .map(|feed| match feed {
+                Feed::Frame(frame) => frame.to_string(),
+                other => format!("{other:?}"),
+            })
+            .collect()
+    }
+
+    fn text(feed: Feed) -> String {
+        frames([feed]).remove(0)
+    }
+
+    fn injector() -> Injector {
+        Injector::new(fastrand::Rng::with_seed(7))
+    }
+
+    #[test]
+    fn depth_is_dropped_and_trades_duplicated_before_parsing() {
+        let faults = Faults {
+            drop_depth: 1.0,
+            duplicate_trades: 1.0,
+            ..Faults::NONE
+        };
+        let mut injector = injector();
+        let now = Instant::now();
+
+        for feed in [Feed::Connected, depth(1), trade(2), depth(3)] {
+            injector.admit(feed, &faults, now);
+        }
+
+        assert_eq!(
+            frames(injector.ready.drain(..)),
+            ["Connected".to_string(), text(trade(2)), text(trade(2))]
+        );
+    }
+
+    #[test]
+    fn jittered_frames_keep_their_order() {
+        let faults = Faults {
+            delay: Duration::from_millis(500),
+            jitter: Duration::from_millis(300),
+            ..Faults::NONE
+        };
+        let mut injector = injector();
+        let start = Instant::now();
+
+        for n in 0..20 {
+            injector.admit(
+                depth(n),
+                &faults,
+                start + Duration::from_millis(u64::from(n)),
+            );
+        }
+        injector.release_due(&faults, start + Duration::from_millis(499));
+        assert!(injector.ready.is_empty());
+
+        injector.release_due(&faults, start + Duration::from_secs(1));
+        assert!(injector.delayed.is_empty());
+        assert_eq!(
+            frames(injector.ready.drain(..)),
+            (0..20).map(|n| text(depth(n))).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn reordering_stays_within_the_window() {
+        let faults = Faults {
+            reorder_window: 4,
+            ..Faults::NONE
+        };
+        let mut injector = injector();
+        let now = Instant::now();
+
+        for n in 0..10 {
+            injector.admit(depth(n), &faults, now);
+        }
+        // Two full windows went out, the last two frames wait for more or the idle flush
+        assert_eq!(injector.window.len(), 2);
+
+        let mut first = frames(injector.ready.drain(..4));
+        first.sort();
+        assert_eq!(first, (0..4).map(|n| text(depth(n))).collect::<Vec<_>>());
+
+        // A connection event lets the window through ahead of it
+        injector.admit(Feed::Connected, &faults, now);
+        assert_eq!(injector.ready.len(), 4 + 2 + 1);
+        assert!(matches!(injector.ready.back(), Some(Feed::Connected)));
+    }
+}
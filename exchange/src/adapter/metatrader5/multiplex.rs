@@ -0,0 +1,1719 @@
+//! One proxy WebSocket shared by every pane streaming from it.
+//!
+//! Panes attach to a symbol and receive that symbol's frames through a broadcast channel. The
+//! symbol is subscribed when its first pane attaches and unsubscribed when its last pane detaches.
+//! The socket reconnects on its own, paced by [`super::reconnect`], telling every attached pane,
+//! and is closed once no pane has been attached for [`IDLE_GRACE_PERIOD`].
+//!
+//! The proxy subscribes whole symbols, so each subscription is for [`CHANNELS`]. Symbols only
+//! needed for their price, like those converting into the account currency, are watched on the
+//! lighter [`QUOTE_CHANNEL`] instead, unless a pane subscribes them anyway.
+//!
+//! A symbol whose market closes is dropped to quotes too, unless the connection opts out, and
+//! subscribed again [`RESUME_LEAD`] before the session calendar says it reopens, so the first
+//! ticks of the session aren't missed.
+//!
+//! Frames to the proxy are queued in [`super::outbound`], which keeps them in order and retries
+//! or gives up on the connection when a write fails.
+//!
+//! Subscriptions the proxy answers are settled by [`super::ack`], refused channels are told to
+//! the panes of their symbol.
+//!
+//! On exit, [`shutdown`] has every socket send its close frame and waits for them, up to a
+//! timeout, so the proxy sees an orderly goodbye rather than a dropped connection.
+
+use super::ack::{ACK_TIMEOUT, Acks, Verdict};
+use super::diagnosis::{self, Symptom};
+use super::outbound::Outbox;
+use super::reconnect::{self, Backoff, Cause, Retry, Termination, Wake};
+use super::{
+    AdapterError, AddSymbolMessage, Mt5Config, ProxySocket, SUBSCRIBE_ACK_CAPABILITY,
+    SYMBOL_NOT_FOUND_CODE, ServerMessage, SubscribeMessage, SubscribeResponse, authenticate,
+    is_upgrade_refused, rest, upgrade_error,
+};
+
+use crate::market_state::MarketState;
+
+use futures_util::StreamExt as _;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite::Message;
+
+/// How long a socket without attached panes is kept open, so switching a pane's ticker or
+/// reopening a layout doesn't reconnect
+pub(super) const IDLE_GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// Market data channels every subscription asks for
+pub(super) const CHANNELS: [&str; 2] = ["trade", "depth"];
+
+/// Market watch channel of bid/ask updates, for symbols no pane streams
+pub(super) const QUOTE_CHANNEL: [&str; 1] = ["quote"];
+
+/// How long before a closed market reopens its depth and trades are subscribed again. Leaves
+/// room for a slow subscription and a server clock slightly ahead.
+const RESUME_LEAD: Duration = Duration::from_secs(120);
+
+/// Proxy heartbeats every 30s by default. Any frame counts as a sign of life, so a closed
+/// market going quiet isn't mistaken for a dead socket while heartbeats keep arriving.
+const SILENCE_TIMEOUT: Duration = Duration::from_secs(75);
+
+/// Frames buffered per symbol for a pane that falls behind
+const FEED_CAPACITY: usize = 1024;
+
+/// Open sockets by proxy URL and terminal, see [`Mt5Config::endpoint`]
+static CONNECTIONS: LazyLock<Mutex<HashMap<String, Arc<Mutex<Shared>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// What an attached pane receives
+#[derive(Debug, Clone)]
+pub(super) enum Feed {
+    Connected,
+    Disconnected(String),
+    /// A `trade`, `depth` or `session` frame of the attached symbol
+    Frame(Arc<str>),
+    /// The symbol's market closed, its depth and trades stay off until shortly before
+    /// `reopens_at`
+    Paused {
+        reopens_at: u64,
+    },
+    /// Depth and trades are subscribed again after a pause
+    Resumed,
+    /// The proxy rejected the subscription, reconnecting won't help
+    SymbolNotFound,
+    /// The proxy refused these channels of the symbol
+    Rejected {
+        channels: Vec<String>,
+        reason: String,
+    },
+    /// A proxy that answers subscriptions didn't answer the symbol's in time
+    Unconfirmed,
+    /// The proxy revoked the session's credentials, the socket won't reconnect
+    Revoked(String),
+}
+
+enum Command {
+    Subscribe(String),
+    Unsubscribe(String),
+    Watch(String),
+    Unwatch(String),
+    /// The symbol's market is closed until the unix ms given
+    Pause(String, u64),
+    /// Closes the socket for good, answering once the close frame is sent
+    Shutdown(oneshot::Sender<()>),
+    /// Cuts a reconnect attempt or its wait short and tries again with the backoff started over
+    RetryNow,
+    /// Stops reconnecting until `RetryNow`
+    StopRetrying,
+}
+
+/// Reference counted subscriptions of one socket
+#[derive(Default)]
+struct Subscriptions {
+    symbols: HashMap<String, (usize, broadcast::Sender<Feed>)>,
+}
+
+impl Subscriptions {
+    /// Feed of `symbol`, and whether it's the symbol's first pane
+    fn attach(&mut self, symbol: &str) -> (broadcast::Receiver<Feed>, bool) {
+        match self.symbols.get_mut(symbol) {
+            Some((panes, sender)) => {
+                *panes += 1;
+                (sender.subscribe(), false)
+            }
+            None => {
+                let (sender, receiver) = broadcast::channel(FEED_CAPACITY);
+                self.symbols.insert(symbol.to_string(), (1, sender));
+                (receiver, true)
+            }
+        }
+    }
+
+    /// Whether that was the symbol's last pane
+    fn detach(&mut self, symbol: &str) -> bool {
+        let Some((panes, _)) = self.symbols.get_mut(symbol) else {
+            return false;
+        };
+
+        *panes -= 1;
+        if *panes == 0 {
+            self.symbols.remove(symbol);
+            return true;
+        }
+        false
+    }
+
+    fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    fn symbols(&self) -> Vec<String> {
+        self.symbols.keys().cloned().collect()
+    }
+
+    fn dispatch(&self, symbol: &str, feed: Feed) {
+        if let Some((_, sender)) = self.symbols.get(symbol) {
+            let _ = sender.send(feed);
+        }
+    }
+
+    fn broadcast(&self, feed: &Feed) {
+        for (_, sender) in self.symbols.values() {
+            let _ = sender.send(feed.clone());
+        }
+    }
+}
+
+struct Shared {
+    config: Mt5Config,
+    subscriptions: Subscriptions,
+    /// Holders of each watched symbol's quotes
+    watched: HashMap<String, usize>,
+    /// Subscribed symbols down to quotes while their market is closed, with when it reopens
+    paused: HashMap<String, u64>,
+    connected: bool,
+    /// Set while disconnected, until the socket is up again
+    retry: Option<Retry>,
+    /// The app is exiting, the socket must not reconnect
+    shutting_down: bool,
+    commands: mpsc::UnboundedSender<Command>,
+}
+
+/// A pane's hold on a symbol of a shared socket, detaches when dropped
+pub(super) struct Attachment {
+    shared: Arc<Mutex<Shared>>,
+    symbol: String,
+    feed: broadcast::Receiver<Feed>,
+    /// `Connected` for a pane joining a socket that's already up, and `Paused` if the symbol is
+    greeting: VecDeque<Feed>,
+}
+
+impl Attachment {
+    /// Next event for the pane, `None` once the socket is gone for good
+    pub(super) async fn recv(&mut self) -> Option<Feed> {
+        if let Some(feed) = self.greeting.pop_front() {
+            return Some(feed);
+        }
+
+        loop {
+            match self.feed.recv().await {
+                Ok(feed) => return Some(feed),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::warn!("MT5 {} pane lagged, skipped {skipped} frames", self.symbol);
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+impl Drop for Attachment {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock()
+            && shared.subscriptions.detach(&self.symbol)
+        {
+            let _ = shared
+                .commands
+                .send(Command::Unsubscribe(self.symbol.clone()));
+        }
+    }
+}
+
+/// Attaches a pane to `symbol` on the socket for `config`, opening the socket if needed
+pub(super) fn attach(config: &Mt5Config, symbol: &str) -> Attachment {
+    let mut connections = CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+    let url = config.endpoint();
+
+    // A changed config gets its own socket, panes on the old one keep it until they detach
+    let shared = match connections.get(&url) {
+        Some(shared) if shared.lock().is_ok_and(|s| s.config == *config) => Arc::clone(shared),
+        _ => {
+            let (commands, receiver) = mpsc::unbounded_channel();
+            let shared = Arc::new(Mutex::new(Shared {
+                config: config.clone(),
+                subscriptions: Subscriptions::default(),
+                watched: HashMap::new(),
+                paused: HashMap::new(),
+                connected: false,
+                retry: None,
+                shutting_down: false,
+                commands,
+            }));
+
+            tokio::spawn(run(config.clone(), Arc::clone(&shared), receiver));
+            connections.insert(url, Arc::clone(&shared));
+            shared
+        }
+    };
+
+    let (feed, greeting) = {
+        let mut state = shared.lock().unwrap_or_else(|e| e.into_inner());
+        let (feed, is_first) = state.subscriptions.attach(symbol);
+        if is_first {
+            let _ = state.commands.send(Command::Subscribe(symbol.to_string()));
+        }
+        let mut greeting = VecDeque::new();
+        if state.connected {
+            greeting.push_back(Feed::Connected);
+        }
+        if let Some(&reopens_at) = state.paused.get(symbol) {
+            greeting.push_back(Feed::Paused { reopens_at });
+        }
+        (feed, greeting)
+    };
+
+    Attachment {
+        shared,
+        symbol: symbol.to_string(),
+        feed,
+        greeting,
+    }
+}
+
+/// Panes attached to the sockets opened for `config`
+pub(super) fn live_streams(config: &Mt5Config) -> usize {
+    let connections = CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+
+    connections
+        .values()
+        .filter_map(|shared| shared.lock().ok())
+        .filter(|s| s.config == *config)
+        .map(|s| {
+            s.subscriptions
+                .symbols
+                .values()
+                .map(|(panes, _)| panes)
+                .sum::<usize>()
+        })
+        .sum()
+}
+
+/// Hands the sockets opened for `previous` over to `config`, which has to connect the same way.
+/// Settings read while streaming apply from then on and new panes share the sockets. Returns
+/// how many sockets were taken over.
+pub(super) fn reconfigure(previous: &Mt5Config, config: &Mt5Config) -> usize {
+    let connections = CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+
+    let mut taken_over = 0;
+    for shared in connections.values() {
+        if let Ok(mut state) = shared.lock()
+            && state.config == *previous
+        {
+            state.config = config.clone();
+            taken_over += 1;
+        }
+    }
+    taken_over
+}
+
+/// A hold on the quotes of a symbol, unwatches when dropped
+pub(super) struct Watch {
+    shared: Arc<Mutex<Shared>>,
+    symbol: String,
+}
+
+impl Drop for Watch {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock()
+            && let Some(holders) = shared.watched.get_mut(&self.symbol)
+        {
+            *holders -= 1;
+            if *holders == 0 {
+                shared.watched.remove(&self.symbol);
+                let _ = shared.commands.send(Command::Unwatch(self.symbol.clone()));
+            }
+        }
+    }
+}
+
+/// Watches the quotes of `symbol` on the open socket for `config`, `None` without one
+pub(super) fn watch(config: &Mt5Config, symbol: &str) -> Option<Watch> {
+    let connections = CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+    let shared = connections
+        .get(&config.endpoint())
+        .filter(|shared| shared.lock().is_ok_and(|s| s.config == *config))
+        .map(Arc::clone)?;
+
+    {
+        let mut state = shared.lock().unwrap_or_else(|e| e.into_inner());
+        let holders = state.watched.entry(symbol.to_string()).or_default();
+        *holders += 1;
+        if *holders == 1 {
+            let _ = state.commands.send(Command::Watch(symbol.to_string()));
+        }
+    }
+
+    Some(Watch {
+        shared,
+        symbol: symbol.to_string(),
+    })
+}
+
+/// The soonest reconnect any proxy socket is waiting for
+pub(super) fn pending_retry() -> Option<Retry> {
+    let connections = CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+
+    connections
+        .values()
+        .filter_map(|shared| shared.lock().ok().and_then(|s| s.retry))
+        .min_by_key(|retry| retry.at)
+}
+
+/// Closes every open socket, returns how many didn't confirm within `timeout`
+pub(super) async fn shutdown(timeout: Duration) -> usize {
+    let sockets: Vec<_> = CONNECTIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .values()
+        .cloned()
+        .collect();
+
+    shutdown_sockets(sockets, timeout).await
+}
+
+async fn shutdown_sockets(sockets: Vec<Arc<Mutex<Shared>>>, timeout: Duration) -> usize {
+    let mut closing = vec![];
+    for shared in sockets {
+        let mut state = shared.lock().unwrap_or_else(|e| e.into_inner());
+        state.shutting_down = true;
+
+        // One waiting to reconnect has nothing to close, it stops at its next attempt
+        let (ack, closed) = oneshot::channel();
+        if state.connected && state.commands.send(Command::Shutdown(ack)).is_ok() {
+            closing.push((state.config.endpoint(), closed));
+        }
+    }
+
+    let deadline = Instant::now() + timeout;
+    let mut stuck = 0;
+    for (url, closed) in closing {
+        // A dropped answer means the socket ended on its own meanwhile
+        if tokio::time::timeout_at(deadline, closed).await.is_err() {
+            log::warn!(
+                "MT5 connection to {url} didn't close within {}s, dropping it",
+                timeout.as_secs()
+            );
+            stuck += 1;
+        }
+    }
+    stuck
+}
+
+/// Why a connection ended without an error
+enum Served {
+    Closed,
+    Idle,
+    ShutDown,
+    /// The proxy said why it ended the session
+    Terminated(Termination),
+}
+
+/// Owns the socket of one proxy for as long as panes are attached to it
+async fn run(
+    config: Mt5Config,
+    shared: Arc<Mutex<Shared>>,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+) {
+    let mut backoff = Backoff::default();
+
+    loop {
+        if shared.lock().is_ok_and(|s| s.shutting_down) {
+            break;
+        }
+
+        if backoff.is_noteworthy() {
+            log::info!(mt5 = config.server_addr.as_str(); "Connecting to MT5 proxy: {}", config.ws_url());
+        }
+
+        let connecting = tokio::select! {
+            connected = connect(&config) => connected,
+            interrupt = interrupted(&mut commands) => {
+                if interrupt == Interrupt::Stop && !stopped(&config, &shared, &mut commands).await {
+                    break;
+                }
+                log::info!(mt5 = config.server_addr.as_str(); "Retrying MT5 connection on request");
+                backoff.reset();
+                set_retry_at(&shared, std::time::Instant::now());
+                continue;
+            }
+        };
+
+        let outcome = match connecting {
+            Ok(ws) => {
+                backoff.reset();
+                serve(ws, &config, &shared, &mut commands)
+                    .await
+                    .map_err(|e| (Cause::of_failure(&e, Cause::Dropped), e))
+            }
+            Err(failure) => Err(failure),
+        };
+
+        let (cause, reason) = match outcome {
+            Ok(Served::Idle | Served::ShutDown) => break,
+            Ok(Served::Closed) => (Cause::Dropped, "Connection closed".to_string()),
+            Ok(Served::Terminated(termination)) if termination.is_final => {
+                log::error!(
+                    mt5 = config.server_addr.as_str();
+                    "MT5 proxy revoked the session, not reconnecting: {}",
+                    config.redact(&termination.reason)
+                );
+                if let Ok(mut state) = shared.lock() {
+                    state.connected = false;
+                    state
+                        .subscriptions
+                        .broadcast(&Feed::Revoked(termination.reason));
+                }
+                break;
+            }
+            // A restarting proxy is waited for like any other dropped connection
+            Ok(Served::Terminated(termination)) => (
+                Cause::Dropped,
+                format!("Proxy ended the session: {}", termination.reason),
+            ),
+            // Charts show history fine, so say why they still don't move
+            Err((cause, e))
+                if is_upgrade_refused(&e) && rest::served_history(&config.server_addr) =>
+            {
+                let note = "history was loaded over HTTP, live data needs a WebSocket connection";
+                (cause, format!("{e} ({note})"))
+            }
+            Err((cause, e)) => (cause, e.to_string()),
+        };
+
+        let delay = backoff.fail(cause);
+        let is_noteworthy = backoff.is_noteworthy();
+        if is_noteworthy {
+            log::error!(
+                mt5 = config.server_addr.as_str();
+                "MT5 connection error ({cause}, attempt {}): {}",
+                backoff.failures(),
+                config.redact(&reason)
+            );
+        }
+
+        if let Ok(mut state) = shared.lock() {
+            state.connected = false;
+            // Panes already know about an ongoing outage
+            if is_noteworthy {
+                state.subscriptions.broadcast(&Feed::Disconnected(reason));
+            }
+        }
+
+        if !shared.lock().is_ok_and(|s| s.config.auto_reconnect)
+            || retire(&config, &shared)
+            || shared.lock().is_ok_and(|s| s.shutting_down)
+        {
+            break;
+        }
+
+        if let Ok(mut state) = shared.lock() {
+            state.retry = Some(Retry {
+                at: std::time::Instant::now() + delay,
+                cause,
+                failures: backoff.failures(),
+                stopped: false,
+            });
+        }
+
+        let wake = tokio::select! {
+            wake = reconnect::wait(&config.server_addr, cause, delay) => wake,
+            interrupt = interrupted(&mut commands) => {
+                if interrupt == Interrupt::Stop && !stopped(&config, &shared, &mut commands).await {
+                    break;
+                }
+                backoff.reset();
+                set_retry_at(&shared, std::time::Instant::now());
+                Wake::Requested
+            }
+        };
+
+        match wake {
+            Wake::Elapsed => {}
+            wake => log::info!(
+                mt5 = config.server_addr.as_str();
+                "Retrying MT5 connection early: {wake:?}"
+            ),
+        }
+    }
+
+    // Ends the feeds, every attached pane's stream finishes after what's buffered
+    let mut connections = CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+    if connections
+        .get(&config.endpoint())
+        .is_some_and(|current| Arc::ptr_eq(current, &shared))
+    {
+        connections.remove(&config.endpoint());
+    }
+    if let Ok(mut state) = shared.lock() {
+        state.subscriptions = Subscriptions::default();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Interrupt {
+    RetryNow,
+    Stop,
+}
+
+/// Waits for a reconnect control, other commands are covered by subscribing the current set
+/// once connected
+async fn interrupted(commands: &mut mpsc::UnboundedReceiver<Command>) -> Interrupt {
+    loop {
+        match commands.recv().await {
+            Some(Command::RetryNow) => return Interrupt::RetryNow,
+            Some(Command::StopRetrying) => return Interrupt::Stop,
+            Some(_) => {}
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Waits while retrying is stopped, `true` once asked to reconnect and `false` when the last
+/// pane detached meanwhile
+async fn stopped(
+    config: &Mt5Config,
+    shared: &Arc<Mutex<Shared>>,
+    commands: &mut mpsc::UnboundedReceiver<Command>,
+) -> bool {
+    log::info!(mt5 = config.server_addr.as_str(); "Stopped reconnecting to the MT5 proxy");
+    if let Ok(mut state) = shared.lock()
+        && let Some(retry) = &mut state.retry
+    {
+        retry.stopped = true;
+    }
+
+    loop {
+        match commands.recv().await {
+            Some(Command::RetryNow) => break,
+            Some(Command::Unsubscribe(_)) if retire(config, shared) => return false,
+            Some(_) => {}
+            None => return false,
+        }
+    }
+
+    if let Ok(mut state) = shared.lock()
+        && let Some(retry) = &mut state.retry
+    {
+        retry.stopped = false;
+    }
+    true
+}
+
+/// Moves the pending retry's countdown to `at`
+fn set_retry_at(shared: &Arc<Mutex<Shared>>, at: std::time::Instant) {
+    if let Ok(mut state) = shared.lock()
+        && let Some(retry) = &mut state.retry
+    {
+        retry.at = at;
+    }
+}
+
+/// Sends `command` to every socket waiting to reconnect
+fn control_reconnects(command: impl Fn() -> Command) {
+    let connections = CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+
+    for shared in connections.values() {
+        if let Ok(state) = shared.lock()
+            && state.retry.is_some()
+        {
+            let _ = state.commands.send(command());
+        }
+    }
+}
+
+/// Retries every socket waiting to reconnect right away
+pub(super) fn retry_now() {
+    control_reconnects(|| Command::RetryNow);
+}
+
+/// Stops every socket waiting to reconnect from retrying
+pub(super) fn stop_retrying() {
+    control_reconnects(|| Command::StopRetrying);
+}
+
+/// Drops the socket from the registry if no pane is attached, checked under the registry lock
+/// so a pane can't attach to a socket that's shutting down
+fn retire(config: &Mt5Config, shared: &Arc<Mutex<Shared>>) -> bool {
+    let mut connections = CONNECTIONS.lock().unwrap_or_else(|e| e.into_inner());
+    let is_idle = shared.lock().is_ok_and(|s| s.subscriptions.is_empty());
+
+    if is_idle
+        && connections
+            .get(&config.endpoint())
+            .is_some_and(|current| Arc::ptr_eq(current, shared))
+    {
+        connections.remove(&config.endpoint());
+    }
+    is_idle
+}
+
+/// Opens and authenticates the socket, telling why it failed
+pub(super) async fn connect(config: &Mt5Config) -> Result<ProxySocket, (Cause, AdapterError)> {
+    let request = config.client_request().map_err(|e| (Cause::Rejected, e))?;
+
+    let (mut ws, _) = match tokio::time::timeout(
+        reconnect::CONNECT_TIMEOUT,
+        tokio_tungstenite::connect_async(request),
+    )
+    .await
+    {
+        Ok(Ok(connected)) => connected,
+        Ok(Err(e)) => {
+            return Err((Cause::of_connect(&e), upgrade_error(e, &config.server_addr)));
+        }
+        Err(_) => {
+            return Err((
+                Cause::NetworkDown,
+                AdapterError::WebsocketError(format!(
+                    "Connecting timed out after {}s",
+                    reconnect::CONNECT_TIMEOUT.as_secs()
+                )),
+            ));
+        }
+    };
+
+    if config.sends_hmac() {
+        authenticate(&mut ws, config)
+            .await
+            .map_err(|e| (Cause::of_failure(&e, Cause::Rejected), e))?;
+    }
+
+    Ok(ws)
+}
+
+async fn serve(
+    mut ws: ProxySocket,
+    config: &Mt5Config,
+    shared: &Arc<Mutex<Shared>>,
+    commands: &mut mpsc::UnboundedReceiver<Command>,
+) -> Result<Served, AdapterError> {
+    // Whatever was queued while disconnected is covered by subscribing the current set
+    while commands.try_recv().is_ok() {}
+
+    // Connected just as the app began exiting
+    if shared.lock().is_ok_and(|s| s.shutting_down) {
+        ws.close(None).await.ok();
+        return Ok(Served::ShutDown);
+    }
+
+    let (symbols, watched) = {
+        let mut state = shared.lock().unwrap_or_else(|e| e.into_inner());
+        state.connected = true;
+        state.retry = None;
+        state.subscriptions.broadcast(&Feed::Connected);
+        // Everything is subscribed afresh, a still closed market pauses again on its session frame
+        for symbol in std::mem::take(&mut state.paused).into_keys() {
+            state.subscriptions.dispatch(&symbol, Feed::Resumed);
+        }
+        (state.subscriptions.symbols(), quotes_only(&state))
+    };
+
+    // Header authenticated sessions have no handshake to list capabilities in
+    let acks_advertised = config.sends_hmac()
+        && super::server_info(&config.server_addr)
+            .is_some_and(|info| info.supports(SUBSCRIBE_ACK_CAPABILITY));
+
+    // Everything sent from here on goes through the outbox, in order
+    let mut outbox = Outbox::default();
+    let terminal = config.terminal_id.as_deref();
+    let mut acks = Acks::default();
+    let mut idle_since = None;
+    let mut last_frame = Instant::now();
+    // Sessions without the handshake first hear from the server once subscribed
+    let mut heard = config.sends_hmac();
+    if symbols.is_empty() {
+        idle_since = Some(Instant::now());
+    } else {
+        subscribe_symbols(&mut outbox, &mut acks, terminal, symbols)?;
+    }
+    if !watched.is_empty() {
+        queue_subscription(&mut outbox, terminal, "subscribe", watched, &QUOTE_CHANNEL)?;
+    }
+
+    loop {
+        outbox.flush(&mut ws).await?;
+
+        let idle_deadline = idle_since.map(|since| since + IDLE_GRACE_PERIOD);
+        let resume_deadline = next_resume(shared);
+        let ack_deadline = acks.deadline();
+
+        tokio::select! {
+            next = ws.next() => {
+                let Some(msg) = next else {
+                    return Ok(Served::Closed);
+                };
+                let msg = msg.map_err(|e| AdapterError::WebsocketError(e.to_string()))?;
+                last_frame = Instant::now();
+
+                if !heard && matches!(msg, Message::Text(_) | Message::Binary(_)) {
+                    heard = true;
+                    if let Some(symptom) = Symptom::of_frame(&msg) {
+                        let diagnosis = diagnosis::diagnose(&config.server_addr, &symptom);
+                        return Err(AdapterError::WebsocketError(diagnosis));
+                    }
+                }
+
+                match msg {
+                    Message::Text(text) => {
+                        if let Some(termination) = route(&mut outbox, &mut acks, config, shared, text)? {
+                            ws.close(None).await.ok();
+                            return Ok(Served::Terminated(termination));
+                        }
+                    }
+                    Message::Ping(data) => outbox.push_pong(Message::Pong(data)),
+                    Message::Close(frame) => {
+                        log::info!(mt5 = config.server_addr.as_str(); "MT5 server sent close frame: {frame:?}");
+                        let termination = frame.and_then(|frame| {
+                            Termination::from_close(u16::from(frame.code), &frame.reason)
+                        });
+                        return Ok(termination.map_or(Served::Closed, Served::Terminated));
+                    }
+                    _ => {}
+                }
+            }
+            Some(command) = commands.recv() => match command {
+                Command::Subscribe(symbol) => {
+                    idle_since = None;
+                    subscribe_symbols(&mut outbox, &mut acks, terminal, vec![symbol.clone()])?;
+                    log::debug!(mt5 = config.server_addr.as_str(); "Subscribed to {symbol}");
+                }
+                Command::Unsubscribe(symbol) => {
+                    acks.forget(&symbol);
+                    let (was_paused, is_watched) = shared.lock().map_or((false, false), |mut s| {
+                        (s.paused.remove(&symbol).is_some(), s.watched.contains_key(&symbol))
+                    });
+
+                    // A paused symbol is only on quotes already
+                    if !was_paused {
+                        queue_subscription(&mut outbox, terminal, "unsubscribe", vec![symbol.clone()], &CHANNELS)?;
+                    }
+                    log::debug!(mt5 = config.server_addr.as_str(); "Unsubscribed from {symbol}");
+
+                    // Its last pane went, but its price is still needed
+                    match (was_paused, is_watched) {
+                        (false, true) => {
+                            queue_subscription(&mut outbox, terminal, "subscribe", vec![symbol], &QUOTE_CHANNEL)?;
+                        }
+                        (true, false) => {
+                            queue_subscription(&mut outbox, terminal, "unsubscribe", vec![symbol], &QUOTE_CHANNEL)?;
+                        }
+                        _ => {}
+                    }
+
+                    if shared.lock().is_ok_and(|s| s.subscriptions.is_empty()) {
+                        idle_since = Some(Instant::now());
+                    }
+                }
+                // A symbol a pane streams already records its price from trades
+                Command::Watch(symbol) => {
+                    if !is_subscribed(shared, &symbol) {
+                        queue_subscription(&mut outbox, terminal, "subscribe", vec![symbol.clone()], &QUOTE_CHANNEL)?;
+                        log::debug!(mt5 = config.server_addr.as_str(); "Watching {symbol} quotes");
+                    }
+                }
+                Command::Unwatch(symbol) => {
+                    if !is_subscribed(shared, &symbol) {
+                        queue_subscription(&mut outbox, terminal, "unsubscribe", vec![symbol.clone()], &QUOTE_CHANNEL)?;
+                        log::debug!(mt5 = config.server_addr.as_str(); "Stopped watching {symbol} quotes");
+                    }
+                }
+                Command::Pause(symbol, reopens_at) => {
+                    if should_pause(shared, &symbol, reopens_at) {
+                        queue_subscription(&mut outbox, terminal, "unsubscribe", vec![symbol.clone()], &CHANNELS)?;
+                        if !shared.lock().is_ok_and(|s| s.watched.contains_key(&symbol)) {
+                            queue_subscription(&mut outbox, terminal, "subscribe", vec![symbol.clone()], &QUOTE_CHANNEL)?;
+                        }
+
+                        if let Ok(mut state) = shared.lock() {
+                            state.paused.insert(symbol.clone(), reopens_at);
+                            state.subscriptions.dispatch(&symbol, Feed::Paused { reopens_at });
+                        }
+                        log::info!(mt5 = config.server_addr.as_str(); "Market of {symbol} closed, paused its stream");
+                    }
+                }
+                Command::Shutdown(ack) => {
+                    log::info!(mt5 = config.server_addr.as_str(); "Closing MT5 connection to {}", config.ws_url());
+                    ws.close(None).await.ok();
+                    let _ = ack.send(());
+                    return Ok(Served::ShutDown);
+                }
+                // Sent just before the socket came up
+                Command::RetryNow | Command::StopRetrying => {}
+            },
+            () = tokio::time::sleep_until(last_frame + SILENCE_TIMEOUT) => {
+                return Err(AdapterError::WebsocketError(format!(
+                    "No heartbeat from the proxy for {}s",
+                    SILENCE_TIMEOUT.as_secs()
+                )));
+            }
+            () = sleep_until(resume_deadline) => {
+                let due = take_due(shared);
+                if !due.is_empty() {
+                    subscribe_symbols(&mut outbox, &mut acks, terminal, due.clone())?;
+
+                    let quotes_unneeded: Vec<String> = shared.lock().map_or_else(
+                        |_| vec![],
+                        |s| {
+                            due.iter()
+                                .filter(|symbol| !s.watched.contains_key(*symbol))
+                                .cloned()
+                                .collect()
+                        },
+                    );
+                    if !quotes_unneeded.is_empty() {
+                        queue_subscription(&mut outbox, terminal, "unsubscribe", quotes_unneeded, &QUOTE_CHANNEL)?;
+                    }
+
+                    if let Ok(state) = shared.lock() {
+                        for symbol in &due {
+                            state.subscriptions.dispatch(symbol, Feed::Resumed);
+                        }
+                    }
+                    log::info!(mt5 = config.server_addr.as_str(); "Markets about to reopen, resumed {}", due.join(", "));
+                }
+            }
+            () = sleep_until(ack_deadline) => {
+                let overdue = acks.take_overdue(Instant::now());
+                if overdue.is_empty() {
+                    continue;
+                }
+
+                if acks.is_expected(acks_advertised) {
+                    log::warn!(
+                        mt5 = config.server_addr.as_str();
+                        "MT5 proxy didn't answer the subscription of {} within {}s",
+                        overdue.join(", "),
+                        ACK_TIMEOUT.as_secs()
+                    );
+                    if let Ok(state) = shared.lock() {
+                        for symbol in &overdue {
+                            state.subscriptions.dispatch(symbol, Feed::Unconfirmed);
+                        }
+                    }
+                } else {
+                    log::debug!(
+                        mt5 = config.server_addr.as_str();
+                        "MT5 proxy doesn't answer subscriptions, assuming {} subscribed",
+                        overdue.join(", ")
+                    );
+                }
+            }
+            () = sleep_until(idle_deadline) => {
+                if retire(config, shared) {
+                    log::info!(
+                        mt5 = config.server_addr.as_str();
+                        "Closing idle MT5 connection to {}",
+                        config.ws_url()
+                    );
+                    ws.close(None).await.ok();
+                    return Ok(Served::Idle);
+                }
+                idle_since = None;
+            }
+        }
+    }
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+/// When depth and trades of a market reopening at `reopens_at` are subscribed again
+fn resumes_at(reopens_at: u64) -> u64 {
+    reopens_at.saturating_sub(RESUME_LEAD.as_millis() as u64)
+}
+
+/// Whether `symbol` is still streamed and its market stays closed long enough to pause
+fn should_pause(shared: &Arc<Mutex<Shared>>, symbol: &str, reopens_at: u64) -> bool {
+    resumes_at(reopens_at) > now_ms()
+        && shared.lock().is_ok_and(|s| {
+            s.config.pause_when_closed
+                && s.subscriptions.symbols.contains_key(symbol)
+                && !s.paused.contains_key(symbol)
+        })
+}
+
+/// The soonest a paused symbol is due to resume
+fn next_resume(shared: &Arc<Mutex<Shared>>) -> Option<Instant> {
+    let resumes_at = shared
+        .lock()
+        .ok()?
+        .paused
+        .values()
+        .map(|&reopens_at| resumes_at(reopens_at))
+        .min()?;
+
+    Some(Instant::now() + Duration::from_millis(resumes_at.saturating_sub(now_ms())))
+}
+
+/// Paused symbols due to resume, no longer paused
+fn take_due(shared: &Arc<Mutex<Shared>>) -> Vec<String> {
+    let Ok(mut state) = shared.lock() else {
+        return vec![];
+    };
+
+    let now = now_ms();
+    let due: Vec<String> = state
+        .paused
+        .iter()
+        .filter(|(_, reopens_at)| resumes_at(**reopens_at) <= now)
+        .map(|(symbol, _)| symbol.clone())
+        .collect();
+    for symbol in &due {
+        state.paused.remove(symbol);
+    }
+    due
+}
+
+/// Watched symbols no pane subscribes
+fn quotes_only(state: &Shared) -> Vec<String> {
+    state
+        .watched
+        .keys()
+        .filter(|symbol| !state.subscriptions.symbols.contains_key(*symbol))
+        .cloned()
+        .collect()
+}
+
+fn is_subscribed(shared: &Arc<Mutex<Shared>>, symbol: &str) -> bool {
+    shared
+        .lock()
+        .is_ok_and(|s| s.subscriptions.symbols.contains_key(symbol))
+}
+
+fn queue_subscription(
+    outbox: &mut Outbox,
+    terminal: Option<&str>,
+    msg_type: &'static str,
+    symbols: Vec<String>,
+    channels: &[&str],
+) -> Result<(), AdapterError> {
+    let msg = SubscribeMessage {
+        msg_type,
+        symbols,
+        channels: channels.iter().map(|c| c.to_string()).collect(),
+        terminal_id: terminal,
+    };
+    let json = serde_json::to_string(&msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+
+    outbox.push(Message::Text(json));
+    Ok(())
+}
+
+/// Subscribes `symbols` to every market data channel, their answer is waited for
+fn subscribe_symbols(
+    outbox: &mut Outbox,
+    acks: &mut Acks,
+    terminal: Option<&str>,
+    symbols: Vec<String>,
+) -> Result<(), AdapterError> {
+    acks.expect(&symbols, Instant::now());
+    queue_subscription(outbox, terminal, "subscribe", symbols, &CHANNELS)
+}
+
+fn queue_add_symbol(
+    outbox: &mut Outbox,
+    terminal: Option<&str>,
+    symbol: &str,
+) -> Result<(), AdapterError> {
+    let msg = AddSymbolMessage {
+        msg_type: "add_symbol",
+        symbol,
+        terminal_id: terminal,
+    };
+    let json = serde_json::to_string(&msg).map_err(|e| AdapterError::ParseError(e.to_string()))?;
+
+    outbox.push(Message::Text(json));
+    Ok(())
+}
+
+/// Acts on the proxy's answer to subscriptions: symbols missing from the Market Watch are added
+/// and subscribed again, refusals reach the panes of their symbol
+fn settle_subscriptions(
+    outbox: &mut Outbox,
+    acks: &mut Acks,
+    config: &Mt5Config,
+    shared: &Arc<Mutex<Shared>>,
+    text: &str,
+) -> Result<(), AdapterError> {
+    let response: SubscribeResponse = match serde_json::from_str(text) {
+        Ok(response) => response,
+        Err(e) => {
+            log::warn!(mt5 = config.server_addr.as_str(); "Unreadable subscribe_response: {e}");
+            return Ok(());
+        }
+    };
+
+    for verdict in acks.settle(response, &CHANNELS, Instant::now()) {
+        match verdict {
+            Verdict::Retry(symbol) => {
+                log::info!(
+                    mt5 = config.server_addr.as_str();
+                    "{symbol} isn't in the Market Watch, adding it and subscribing again"
+                );
+                let terminal = config.terminal_id.as_deref();
+                queue_add_symbol(outbox, terminal, &symbol)?;
+                queue_subscription(outbox, terminal, "subscribe", vec![symbol], &CHANNELS)?;
+            }
+            Verdict::Rejected {
+                symbol,
+                channels,
+                reason,
+            } => {
+                log::warn!(
+                    mt5 = config.server_addr.as_str();
+                    "MT5 proxy refused {symbol} {}: {reason}",
+                    channels.join("/")
+                );
+                if let Ok(state) = shared.lock() {
+                    state
+                        .subscriptions
+                        .dispatch(&symbol, Feed::Rejected { channels, reason });
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Hands a text frame to the panes of its symbol, answering heartbeats and subscription
+/// answers on the way. Returns why the proxy ended the session if the frame says so.
+fn route(
+    outbox: &mut Outbox,
+    acks: &mut Acks,
+    config: &Mt5Config,
+    shared: &Arc<Mutex<Shared>>,
+    text: String,
+) -> Result<Option<Termination>, AdapterError> {
+    let Ok(server_msg) = serde_json::from_str::<ServerMessage>(&text) else {
+        return Ok(None);
+    };
+
+    match server_msg.msg_type.as_ref() {
+        "trade" | "depth" | "session" => {
+            if let Some(symbol) = server_msg.symbol.as_deref()
+                && let Ok(state) = shared.lock()
+            {
+                state
+                    .subscriptions
+                    .dispatch(symbol, Feed::Frame(Arc::from(text.as_str())));
+
+                if server_msg.msg_type == "session"
+                    && state.config.pause_when_closed
+                    && let Some(reopens_at) = reopens_at(config, &text)
+                {
+                    let _ = state
+                        .commands
+                        .send(Command::Pause(symbol.to_string(), reopens_at));
+                }
+            }
+        }
+        "quote" => super::rates::record_quote(&text),
+        "subscribe_response" => settle_subscriptions(outbox, acks, config, shared, &text)?,
+        "heartbeat" => {
+            let pong = serde_json::json!({
+                "type": "ping",
+                "time": std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis() as u64
+            });
+            outbox.push_pong(Message::Text(pong.to_string()));
+        }
+        "error" => {
+            if server_msg.code.as_deref() == Some(SYMBOL_NOT_FOUND_CODE)
+                && let Some(symbol) = server_msg.symbol.as_deref()
+                && let Ok(state) = shared.lock()
+            {
+                state.subscriptions.dispatch(symbol, Feed::SymbolNotFound);
+                return Ok(None);
+            }
+
+            let detail = server_msg.message.or(server_msg.error).unwrap_or_default();
+            log::error!(
+                mt5 = config.server_addr.as_str();
+                "MT5 server error: {}",
+                config.redact(&detail)
+            );
+        }
+        "shutdown" | "kicked" => {
+            let reason = server_msg.reason.or(server_msg.message).unwrap_or_default();
+            return Ok(Some(Termination::from_reason(&reason)));
+        }
+        _ => {}
+    }
+    Ok(None)
+}
+
+/// When a `session` frame says the market reopens, if it's closed
+fn reopens_at(config: &Mt5Config, text: &str) -> Option<u64> {
+    let clock = super::server_timezone(&config.server_addr);
+
+    match super::parse_session(text.as_bytes(), &clock) {
+        Ok(MarketState::Closed { reopens_at, .. }) => reopens_at,
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::SinkExt as _;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn subscriptions_are_counted_per_symbol() {
+        let mut subs = Subscriptions::default();
+
+        let (mut first, is_first) = subs.attach("EURUSD");
+        assert!(is_first);
+        let (mut second, is_first) = subs.attach("EURUSD");
+        assert!(!is_first);
+        let (mut other, _) = subs.attach("XAUUSD");
+
+        subs.dispatch("EURUSD", Feed::Frame(Arc::from("eurusd")));
+        assert!(matches!(first.try_recv(), Ok(Feed::Frame(f)) if &*f == "eurusd"));
+        assert!(matches!(second.try_recv(), Ok(Feed::Frame(_))));
+        assert!(other.try_recv().is_err());
+
+        subs.broadcast(&Feed::Connected);
+        assert!(matches!(other.try_recv(), Ok(Feed::Connected)));
+
+        assert!(!subs.detach("EURUSD"));
+        assert!(subs.detach("EURUSD"));
+        assert!(!subs.detach("EURUSD"));
+        assert!(!subs.is_empty());
+
+        assert!(subs.detach("XAUUSD"));
+        assert!(subs.is_empty());
+    }
+
+    /// Proxy that sends `farewell` to every client once it subscribed, returns its address and
+    /// the number of connections it accepted
+    async fn mock_proxy(farewell: &'static str) -> (Mt5Config, Arc<AtomicUsize>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Mt5Config {
+            server_addr: listener.local_addr().unwrap().to_string(),
+            auth_mode: super::super::AuthMode::BearerToken("tok".to_string()),
+            ..Mt5Config::default()
+        };
+
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                counted.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    while let Some(Ok(msg)) = ws.next().await {
+                        if let Message::Text(text) = msg
+                            && text.contains("subscribe")
+                        {
+                            ws.send(Message::Text(farewell.to_string())).await.ok();
+                        }
+                    }
+                });
+            }
+        });
+
+        (config, connections)
+    }
+
+    async fn next_feed(attachment: &mut Attachment) -> Option<Feed> {
+        tokio::time::timeout(Duration::from_secs(10), attachment.recv())
+            .await
+            .expect("no feed from the socket")
+    }
+
+    #[tokio::test]
+    async fn revoked_session_stops_reconnecting() {
+        let (config, connections) =
+            mock_proxy(r#"{"type":"kicked","reason":"api key revoked"}"#).await;
+        let mut attachment = attach(&config, "EURUSD");
+
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Connected)
+        ));
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Revoked(reason)) if reason == "api key revoked"
+        ));
+        // The feed ends instead of reporting a reconnect
+        assert!(next_feed(&mut attachment).await.is_none());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn maintenance_shutdown_reconnects() {
+        let (config, connections) =
+            mock_proxy(r#"{"type":"shutdown","reason":"maintenance"}"#).await;
+        let mut attachment = attach(&config, "EURUSD");
+
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Connected)
+        ));
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Disconnected(reason)) if reason.contains("maintenance")
+        ));
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Connected)
+        ));
+        assert!(connections.load(Ordering::SeqCst) >= 2);
+    }
+
+    #[tokio::test]
+    async fn edits_that_connect_the_same_way_keep_the_socket() {
+        let (config, connections) = mock_proxy(r#"{"type":"heartbeat"}"#).await;
+        let mut first = attach(&config, "EURUSD");
+        assert!(matches!(next_feed(&mut first).await, Some(Feed::Connected)));
+
+        let edited = Mt5Config {
+            auto_reconnect: !config.auto_reconnect,
+            pause_when_closed: !config.pause_when_closed,
+            ..config.clone()
+        };
+        assert!(config.material_changes(&edited).is_empty());
+        assert_eq!(live_streams(&config), 1);
+
+        assert_eq!(reconfigure(&config, &edited), 1);
+        assert_eq!(live_streams(&config), 0);
+        assert_eq!(live_streams(&edited), 1);
+
+        // Panes opened with the edited config join the running socket
+        let mut second = attach(&edited, "XAUUSD");
+        assert!(matches!(
+            next_feed(&mut second).await,
+            Some(Feed::Connected)
+        ));
+        assert!(Arc::ptr_eq(&first.shared, &second.shared));
+        assert_eq!(live_streams(&edited), 2);
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    async fn eventually(check: impl Fn() -> bool) {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while !check() {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("condition never held");
+    }
+
+    #[tokio::test]
+    async fn retrying_stops_and_resumes_mid_attempt() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Mt5Config {
+            server_addr: listener.local_addr().unwrap().to_string(),
+            auth_mode: super::super::AuthMode::BearerToken("tok".to_string()),
+            ..Mt5Config::default()
+        };
+
+        // Ends the first session, then never answers a handshake so attempts stay in flight
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&connections);
+        tokio::spawn(async move {
+            let mut hanging = vec![];
+            while let Ok((stream, _)) = listener.accept().await {
+                if counted.fetch_add(1, Ordering::SeqCst) > 0 {
+                    hanging.push(stream);
+                    continue;
+                }
+                tokio::spawn(async move {
+                    let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                    while let Some(Ok(msg)) = ws.next().await {
+                        if let Message::Text(text) = msg
+                            && text.contains("subscribe")
+                        {
+                            let farewell = r#"{"type":"shutdown","reason":"restart"}"#;
+                            ws.send(Message::Text(farewell.into())).await.ok();
+                        }
+                    }
+                });
+            }
+        });
+
+        let mut attachment = attach(&config, "EURUSD");
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Connected)
+        ));
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Disconnected(_))
+        ));
+
+        let socket = CONNECTIONS
+            .lock()
+            .unwrap()
+            .get(&config.endpoint())
+            .cloned()
+            .expect("an open socket");
+        let send = |command| socket.lock().unwrap().commands.send(command).unwrap();
+        let is_stopped = || {
+            socket
+                .lock()
+                .unwrap()
+                .retry
+                .is_some_and(|retry| retry.stopped)
+        };
+
+        eventually(|| connections.load(Ordering::SeqCst) == 2).await;
+        send(Command::StopRetrying);
+        eventually(is_stopped).await;
+
+        send(Command::RetryNow);
+        eventually(|| connections.load(Ordering::SeqCst) == 3).await;
+        assert!(!is_stopped());
+    }
+
+    #[tokio::test]
+    async fn shutdown_sends_the_close_frame_and_stays_down() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Mt5Config {
+            server_addr: listener.local_addr().unwrap().to_string(),
+            auth_mode: super::super::AuthMode::BearerToken("tok".to_string()),
+            ..Mt5Config::default()
+        };
+
+        let (closed, close_frame) = oneshot::channel();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counted = Arc::clone(&connections);
+        tokio::spawn(async move {
+            let mut closed = Some(closed);
+            while let Ok((stream, _)) = listener.accept().await {
+                counted.fetch_add(1, Ordering::SeqCst);
+                let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+                while let Some(Ok(msg)) = ws.next().await {
+                    if let Message::Close(_) = msg
+                        && let Some(closed) = closed.take()
+                    {
+                        let _ = closed.send(());
+                    }
+                }
+            }
+        });
+
+        let mut attachment = attach(&config, "EURUSD");
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Connected)
+        ));
+
+        let socket = CONNECTIONS
+            .lock()
+            .unwrap()
+            .get(&config.endpoint())
+            .cloned()
+            .expect("an open socket");
+        assert_eq!(
+            shutdown_sockets(vec![socket], Duration::from_secs(5)).await,
+            0
+        );
+
+        tokio::time::timeout(Duration::from_secs(5), close_frame)
+            .await
+            .expect("no close frame")
+            .unwrap();
+        // The feed ends instead of reporting a reconnect
+        assert!(next_feed(&mut attachment).await.is_none());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn closed_market_pauses_until_shortly_before_it_reopens() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Mt5Config {
+            server_addr: listener.local_addr().unwrap().to_string(),
+            auth_mode: super::super::AuthMode::BearerToken("tok".to_string()),
+            ..Mt5Config::default()
+        };
+
+        // Closed on the first subscription, open again on the next
+        let reopens_at = now_ms() + RESUME_LEAD.as_millis() as u64 + 500;
+        let (received, mut requests) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            let mut subscriptions = 0;
+            while let Some(Ok(msg)) = ws.next().await {
+                let Message::Text(text) = msg else {
+                    continue;
+                };
+                let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                let channels = request["channels"].as_array().unwrap().len();
+                let _ = received.send(format!("{} {channels}", request["type"].as_str().unwrap()));
+
+                if request["type"] == "subscribe" && channels == CHANNELS.len() {
+                    subscriptions += 1;
+                    let session = if subscriptions == 1 {
+                        format!(
+                            r#"{{"type":"session","symbol":"EURUSD","state":"closed","next_open":{reopens_at}}}"#
+                        )
+                    } else {
+                        r#"{"type":"session","symbol":"EURUSD","state":"open"}"#.to_string()
+                    };
+                    ws.send(Message::Text(session)).await.ok();
+                }
+            }
+        });
+
+        let mut attachment = attach(&config, "EURUSD");
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Connected)
+        ));
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Frame(frame)) if frame.contains("closed")
+        ));
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Paused { reopens_at: at }) if at == reopens_at
+        ));
+
+        // A pane joining meanwhile learns about the pause too
+        let mut late = attach(&config, "EURUSD");
+        assert!(matches!(next_feed(&mut late).await, Some(Feed::Connected)));
+        assert!(matches!(
+            next_feed(&mut late).await,
+            Some(Feed::Paused { .. })
+        ));
+
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Resumed)
+        ));
+        assert!(now_ms() < reopens_at);
+        assert!(matches!(
+            next_feed(&mut attachment).await,
+            Some(Feed::Frame(frame)) if frame.contains("open")
+        ));
+
+        let mut sent = vec![];
+        while sent.len() < 5 {
+            let request = tokio::time::timeout(Duration::from_secs(5), requests.recv())
+                .await
+                .expect("no request at the proxy");
+            sent.push(request.expect("proxy went away"));
+        }
+        assert_eq!(
+            sent,
+            [
+                "subscribe 2",
+                "unsubscribe 2",
+                "subscribe 1",
+                "subscribe 2",
+                "unsubscribe 1"
+            ]
+        );
+    }
+
+    /// Proxy answering each request with the frames `script` returns for it. Every request is
+    /// reported as its type and symbols, e.g. `subscribe EURUSD,XAUUSD`.
+    async fn scripted_proxy(
+        mut script: impl FnMut(&str, &str) -> Vec<String> + Send + 'static,
+    ) -> (Mt5Config, mpsc::UnboundedReceiver<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = Mt5Config {
+            server_addr: listener.local_addr().unwrap().to_string(),
+            auth_mode: super::super::AuthMode::BearerToken("tok".to_string()),
+            ..Mt5Config::default()
+        };
+
+        let (received, requests) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(msg)) = ws.next().await {
+                let Message::Text(text) = msg else {
+                    continue;
+                };
+                let request: serde_json::Value = serde_json::from_str(&text).unwrap();
+                let msg_type = request["type"].as_str().unwrap_or_default();
+                let mut symbols: Vec<&str> = match request["symbols"].as_array() {
+                    Some(symbols) => symbols.iter().filter_map(|s| s.as_str()).collect(),
+                    None => request["symbol"].as_str().into_iter().collect(),
+                };
+                symbols.sort_unstable();
+                let symbols = symbols.join(",");
+
+                let _ = received.send(format!("{msg_type} {symbols}"));
+                for frame in script(msg_type, &symbols) {
+                    ws.send(Message::Text(frame)).await.ok();
+                }
+            }
+        });
+
+        (config, requests)
+    }
+
+    async fn next_requests(
+        requests: &mut mpsc::UnboundedReceiver<String>,
+        n: usize,
+    ) -> Vec<String> {
+        let mut sent = vec![];
+        while sent.len() < n {
+            let request = tokio::time::timeout(Duration::from_secs(5), requests.recv())
+                .await
+                .expect("no request at the proxy");
+            sent.push(request.expect("proxy went away"));
+        }
+        sent
+    }
+
+    #[tokio::test]
+    async fn refused_channels_reach_only_their_symbol() {
+        let (config, mut requests) = scripted_proxy(|msg_type, _| {
+            if msg_type != "subscribe" {
+                return vec![];
+            }
+            vec![
+                r#"{"type":"subscribe_response",
+                    "accepted":[
+                        {"symbol":"XAUUSD","channels":["trade","depth"]},
+                        {"symbol":"EURUSD","channels":["trade"]}
+                    ],
+                    "rejected":[
+                        {"symbol":"EURUSD","channels":["depth"],"code":"dom_unavailable","reason":"DOM not available"}
+                    ]}"#
+                .to_string(),
+                r#"{"type":"trade","symbol":"EURUSD"}"#.to_string(),
+                r#"{"type":"trade","symbol":"XAUUSD"}"#.to_string(),
+            ]
+        })
+        .await;
+
+        // Both attach before the socket is up, so they're subscribed together
+        let mut eurusd = attach(&config, "EURUSD");
+        let mut xauusd = attach(&config, "XAUUSD");
+
+        assert!(matches!(
+            next_feed(&mut eurusd).await,
+            Some(Feed::Connected)
+        ));
+        assert!(matches!(
+            next_feed(&mut eurusd).await,
+            Some(Feed::Rejected { channels, reason })
+                if channels == ["depth"] && reason == "DOM not available"
+        ));
+        assert!(matches!(
+            next_feed(&mut eurusd).await,
+            Some(Feed::Frame(frame)) if frame.contains("EURUSD")
+        ));
+
+        assert!(matches!(
+            next_feed(&mut xauusd).await,
+            Some(Feed::Connected)
+        ));
+        assert!(matches!(
+            next_feed(&mut xauusd).await,
+            Some(Feed::Frame(frame)) if frame.contains("XAUUSD")
+        ));
+
+        assert_eq!(
+            next_requests(&mut requests, 1).await,
+            ["subscribe EURUSD,XAUUSD"]
+        );
+    }
+
+    #[tokio::test]
+    async fn symbols_missing_from_market_watch_are_added_once() {
+        // Only GBPJPY can be added, NZDCHF stays missing
+        let mut added = false;
+        let (config, mut requests) = scripted_proxy(move |msg_type, symbol| match msg_type {
+            "add_symbol" => {
+                added |= symbol == "GBPJPY";
+                vec![]
+            }
+            "subscribe" if symbol == "GBPJPY" && added => vec![
+                r#"{"type":"subscribe_response","accepted":[{"symbol":"GBPJPY","channels":["trade","depth"]}]}"#
+                    .to_string(),
+                r#"{"type":"trade","symbol":"GBPJPY"}"#.to_string(),
+            ],
+            "subscribe" => vec![format!(
+                r#"{{"type":"subscribe_response","rejected":[{{"symbol":"{symbol}","code":"not_in_market_watch","reason":"Not in Market Watch"}}]}}"#
+            )],
+            _ => vec![],
+        })
+        .await;
+
+        let mut gbpjpy = attach(&config, "GBPJPY");
+        assert!(matches!(
+            next_feed(&mut gbpjpy).await,
+            Some(Feed::Connected)
+        ));
+        // The refusal that was retried never reaches the pane
+        assert!(matches!(
+            next_feed(&mut gbpjpy).await,
+            Some(Feed::Frame(frame)) if frame.contains("GBPJPY")
+        ));
+
+        let mut nzdchf = attach(&config, "NZDCHF");
+        assert!(matches!(
+            next_feed(&mut nzdchf).await,
+            Some(Feed::Connected)
+        ));
+        assert!(matches!(
+            next_feed(&mut nzdchf).await,
+            Some(Feed::Rejected { channels, reason })
+                if channels == CHANNELS && reason == "Not in Market Watch"
+        ));
+
+        assert_eq!(
+            next_requests(&mut requests, 6).await,
+            [
+                "subscribe GBPJPY",
+                "add_symbol GBPJPY",
+                "subscribe GBPJPY",
+                "subscribe NZDCHF",
+                "add_symbol NZDCHF",
+                "subscribe NZDCHF",
+            ]
+        );
+        // Retried once only
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), requests.recv())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn shutdown_gives_up_on_a_stuck_socket() {
+        let (commands, _stuck) = mpsc::unbounded_channel();
+        let socket = Arc::new(Mutex::new(Shared {
+            config: Mt5Config::default(),
+            subscriptions: Subscriptions::default(),
+            watched: HashMap::new(),
+            paused: HashMap::new(),
+            connected: true,
+            retry: None,
+            shutting_down: false,
+            commands,
+        }));
+
+        let started = Instant::now();
+        assert_eq!(
+            shutdown_sockets(vec![Arc::clone(&socket)], Duration::from_millis(100)).await,
+            1
+        );
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(socket.lock().unwrap().shutting_down);
+    }
+}
@@ -0,0 +1,278 @@
+//! Frames the shared socket sends to the proxy.
+//!
+//! Everything sent after authentication goes through the connection's [`Outbox`] in the order
+//! it was queued, so a symbol's subscribe and unsubscribe can't overtake each other. A write
+//! that fails on a full buffer or an interrupted call is retried a few times, any other failure
+//! ends the connection and the current subscriptions are sent afresh once it's back.
+//!
+//! Pongs only keep the proxy's heartbeat happy, so they are neither retried nor queued behind
+//! a backlog.
+
+use super::AdapterError;
+
+use futures_util::{Sink, SinkExt as _};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio_tungstenite::tungstenite::{self, Message};
+
+/// Tries at one frame before the connection is given up
+const MAX_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry, doubled for each further one
+const RETRY_DELAY: Duration = Duration::from_millis(50);
+
+/// Frames waiting to be sent beyond which pongs are dropped
+const PONG_BACKLOG: usize = 8;
+
+struct Outbound {
+    frame: Message,
+    is_pong: bool,
+}
+
+#[derive(Default)]
+pub(super) struct Outbox {
+    queue: VecDeque<Outbound>,
+}
+
+impl Outbox {
+    pub(super) fn push(&mut self, frame: Message) {
+        self.queue.push_back(Outbound {
+            frame,
+            is_pong: false,
+        });
+    }
+
+    /// Queues the answer to a heartbeat, unless frames are already backing up
+    pub(super) fn push_pong(&mut self, frame: Message) {
+        if self.queue.len() >= PONG_BACKLOG {
+            log::debug!("Dropped a pong with {} frames waiting", self.queue.len());
+            return;
+        }
+        self.queue.push_back(Outbound {
+            frame,
+            is_pong: true,
+        });
+    }
+
+    /// Sends the queued frames in order. An error means the connection can't be relied on
+    /// anymore and should be dropped.
+    pub(super) async fn flush<S>(&mut self, ws: &mut S) -> Result<(), AdapterError>
+    where
+        S: Sink<Message, Error = tungstenite::Error> + Unpin,
+    {
+        while let Some(outbound) = self.queue.front() {
+            match deliver(ws, outbound).await {
+                Ok(()) => {}
+                Err(e) if outbound.is_pong && is_transient(&e) => {
+                    log::debug!("Dropped a pong the socket didn't take: {e}");
+                }
+                Err(e) => {
+                    self.queue.clear();
+                    return Err(AdapterError::WebsocketError(format!(
+                        "Sending to the proxy failed: {e}"
+                    )));
+                }
+            }
+            self.queue.pop_front();
+        }
+        Ok(())
+    }
+}
+
+/// Writes one frame, retrying transient failures of a frame that isn't a pong. A frame the
+/// socket took is only flushed again, never written twice.
+async fn deliver<S>(ws: &mut S, outbound: &Outbound) -> Result<(), tungstenite::Error>
+where
+    S: Sink<Message, Error = tungstenite::Error> + Unpin,
+{
+    let mut taken = false;
+    let mut attempt = 1;
+
+    loop {
+        let result = if taken {
+            ws.flush().await
+        } else {
+            match ws.feed(outbound.frame.clone()).await {
+                Ok(()) => {
+                    taken = true;
+                    ws.flush().await
+                }
+                Err(e) => Err(e),
+            }
+        };
+
+        match result {
+            Ok(()) => return Ok(()),
+            Err(e) if is_transient(&e) && !outbound.is_pong && attempt < MAX_ATTEMPTS => {
+                log::debug!("Retrying a frame to the proxy after: {e}");
+                tokio::time::sleep(RETRY_DELAY * 2u32.pow(attempt - 1)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn is_transient(error: &tungstenite::Error) -> bool {
+    use std::io::ErrorKind;
+
+    match error {
+        tungstenite::Error::WriteBufferFull(_) => true,
+        tungstenite::Error::Io(e) => matches!(
+            e.kind(),
+            ErrorKind::WouldBlock | ErrorKind::Interrupted | ErrorKind::TimedOut
+        ),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures_util::StreamExt as _;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    use tokio::sync::mpsc;
+
+    /// A socket whose next `failures` writes fail with `error` before the frame reaches it
+    struct Flaky<S> {
+        inner: S,
+        failures: usize,
+        error: fn() -> tungstenite::Error,
+    }
+
+    impl<S: Sink<Message, Error = tungstenite::Error> + Unpin> Sink<Message> for Flaky<S> {
+        type Error = tungstenite::Error;
+
+        fn poll_ready(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Poll::Ready(Err((self.error)()));
+            }
+            Pin::new(&mut self.inner).poll_ready(cx)
+        }
+
+        fn start_send(mut self: Pin<&mut Self>, item: Message) -> Result<(), Self::Error> {
+            Pin::new(&mut self.inner).start_send(item)
+        }
+
+        fn poll_flush(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Pin::new(&mut self.inner).poll_flush(cx)
+        }
+
+        fn poll_close(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+        ) -> Poll<Result<(), Self::Error>> {
+            Pin::new(&mut self.inner).poll_close(cx)
+        }
+    }
+
+    fn interrupted() -> tungstenite::Error {
+        tungstenite::Error::Io(std::io::ErrorKind::Interrupted.into())
+    }
+
+    /// Socket to a mock proxy that reports every text frame it receives
+    async fn mock_proxy(
+        failures: usize,
+        error: fn() -> tungstenite::Error,
+    ) -> (
+        Flaky<super::super::ProxySocket>,
+        mpsc::UnboundedReceiver<String>,
+    ) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let (received, frames) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            while let Some(Ok(msg)) = ws.next().await {
+                if let Message::Text(text) = msg {
+                    let _ = received.send(text);
+                }
+            }
+        });
+
+        let (ws, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        let flaky = Flaky {
+            inner: ws,
+            failures,
+            error,
+        };
+        (flaky, frames)
+    }
+
+    async fn next_frame(frames: &mut mpsc::UnboundedReceiver<String>) -> String {
+        tokio::time::timeout(Duration::from_secs(5), frames.recv())
+            .await
+            .expect("nothing reached the proxy")
+            .expect("proxy went away")
+    }
+
+    #[tokio::test]
+    async fn subscribe_goes_through_after_a_failed_write() {
+        let (mut ws, mut frames) = mock_proxy(1, interrupted).await;
+
+        let mut outbox = Outbox::default();
+        outbox.push(Message::Text("subscribe EURUSD".to_string()));
+        outbox.push(Message::Text("unsubscribe EURUSD".to_string()));
+        outbox.flush(&mut ws).await.unwrap();
+
+        assert!(outbox.queue.is_empty());
+        assert_eq!(next_frame(&mut frames).await, "subscribe EURUSD");
+        assert_eq!(next_frame(&mut frames).await, "unsubscribe EURUSD");
+    }
+
+    #[tokio::test]
+    async fn persistent_failures_end_the_connection() {
+        let (mut ws, _frames) = mock_proxy(MAX_ATTEMPTS as usize, interrupted).await;
+
+        let mut outbox = Outbox::default();
+        outbox.push(Message::Text("subscribe EURUSD".to_string()));
+        assert!(outbox.flush(&mut ws).await.is_err());
+        assert!(outbox.queue.is_empty());
+
+        let (mut ws, _frames) = mock_proxy(1, || tungstenite::Error::AlreadyClosed).await;
+        outbox.push(Message::Text("subscribe EURUSD".to_string()));
+        assert!(outbox.flush(&mut ws).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn pongs_give_way_but_keep_their_place() {
+        let (mut ws, mut frames) = mock_proxy(1, interrupted).await;
+
+        // A pong the socket refuses is dropped, the subscribe behind it still goes out
+        let mut outbox = Outbox::default();
+        outbox.push_pong(Message::Text("pong 1".to_string()));
+        outbox.push(Message::Text("subscribe EURUSD".to_string()));
+        outbox.flush(&mut ws).await.unwrap();
+        assert_eq!(next_frame(&mut frames).await, "subscribe EURUSD");
+
+        for i in 0..PONG_BACKLOG {
+            outbox.push(Message::Text(format!("subscribe {i}")));
+        }
+        outbox.push_pong(Message::Text("pong 2".to_string()));
+        outbox.push(Message::Text("unsubscribe EURUSD".to_string()));
+        outbox.flush(&mut ws).await.unwrap();
+
+        // Once the backlog cleared pongs are queued again
+        outbox.push_pong(Message::Text("pong 3".to_string()));
+        outbox.flush(&mut ws).await.unwrap();
+
+        let mut sent = vec![];
+        for _ in 0..PONG_BACKLOG + 2 {
+            sent.push(next_frame(&mut frames).await);
+        }
+        assert!(!sent.contains(&"pong 2".to_string()));
+        assert_eq!(sent[PONG_BACKLOG], "unsubscribe EURUSD");
+        assert_eq!(sent[PONG_BACKLOG + 1], "pong 3");
+    }
+}
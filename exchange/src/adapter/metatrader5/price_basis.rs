@@ -0,0 +1,184 @@
+//! Which price MT5 charts are drawn from.
+//!
+//! Bars the proxy builds come from whatever the terminal charts, bid for most FX and CFD symbols,
+//! and a prop firm's markup shows up only on the ask. A pane can chart bid, ask, mid or last
+//! explicitly instead: its bars are fetched with a `price_basis` parameter, and a proxy that
+//! answers a mid request without saying it built mid bars is asked for bid and ask bars, which
+//! are averaged here.
+//!
+//! Streamed symbols are subscribed to trades and depth, not quotes, so live bars follow the best
+//! bid and ask of the pane's book.
+
+use super::Mt5Kline;
+use crate::{Trade, depth::Depth, tick_rule::TickRule};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum PriceBasis {
+    Bid,
+    Ask,
+    Mid,
+    Last,
+}
+
+impl PriceBasis {
+    pub const ALL: [PriceBasis; 4] = [
+        PriceBasis::Bid,
+        PriceBasis::Ask,
+        PriceBasis::Mid,
+        PriceBasis::Last,
+    ];
+
+    /// Value of the `price_basis` parameter of `get_klines`
+    pub(super) fn as_param(self) -> &'static str {
+        match self {
+            PriceBasis::Bid => "bid",
+            PriceBasis::Ask => "ask",
+            PriceBasis::Mid => "mid",
+            PriceBasis::Last => "last",
+        }
+    }
+}
+
+impl std::fmt::Display for PriceBasis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            PriceBasis::Bid => "Bid",
+            PriceBasis::Ask => "Ask",
+            PriceBasis::Mid => "Mid",
+            PriceBasis::Last => "Last",
+        })
+    }
+}
+
+/// Bars halfway between `bid` and `ask` bars of the same times, bars only one side has are left
+/// out. Highs and lows are averaged too, both sides rarely peak on the same tick anyway.
+pub(super) fn mid_bars(bid: &[Mt5Kline], ask: &[Mt5Kline]) -> Vec<Mt5Kline> {
+    let asks: HashMap<u64, &Mt5Kline> = ask.iter().map(|bar| (bar.time, bar)).collect();
+    let mid = |a: f64, b: f64| (a + b) / 2.0;
+
+    bid.iter()
+        .filter_map(|bid| {
+            let ask = asks.get(&bid.time)?;
+            Some(Mt5Kline {
+                time: bid.time,
+                open: mid(bid.open, ask.open),
+                high: mid(bid.high, ask.high),
+                low: mid(bid.low, ask.low),
+                close: mid(bid.close, ask.close),
+                volume: bid.volume,
+            })
+        })
+        .collect()
+}
+
+/// Moves `trades` to the `basis` price of `depth`'s best bid and ask. Trades keep their price
+/// for last, and while the book lacks the side they'd need.
+pub(super) fn reprice(trades: &mut [Trade], basis: PriceBasis, depth: &Depth, tick_rule: TickRule) {
+    let bid = depth.bids.last_key_value().map(|(price, _)| *price);
+    let ask = depth.asks.first_key_value().map(|(price, _)| *price);
+    let quoted = match basis {
+        PriceBasis::Bid => bid,
+        PriceBasis::Ask => ask,
+        PriceBasis::Mid => bid
+            .zip(ask)
+            .map(|(bid, ask)| tick_rule.round((bid + ask) / 2)),
+        PriceBasis::Last => None,
+    };
+
+    if let Some(price) = quoted {
+        for trade in trades {
+            trade.price = price;
+        }
+    }
+}
+
+/// Whether the bars of a `get_klines` answer are at `basis`, proxies that don't know the
+/// parameter don't name one
+pub(super) fn is_honored(basis: PriceBasis, answered: Option<&str>) -> bool {
+    answered.is_some_and(|answered| answered.eq_ignore_ascii_case(basis.as_param()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Price;
+    use crate::util::MinTicksize;
+
+    fn bar(time: u64, open: f64, high: f64, low: f64, close: f64) -> Mt5Kline {
+        Mt5Kline {
+            time,
+            open,
+            high,
+            low,
+            close,
+            volume: 10.0,
+        }
+    }
+
+    #[test]
+    fn mid_bars_fall_back_to_bid_and_ask() {
+        let bid = [
+            bar(0, 1.1000, 1.1010, 1.0990, 1.1005),
+            bar(60, 1.1005, 1.1020, 1.1000, 1.1015),
+            bar(120, 1.1015, 1.1015, 1.1010, 1.1012),
+        ];
+        // The ask side missed the first minute
+        let ask = [
+            bar(60, 1.1007, 1.1022, 1.1002, 1.1017),
+            bar(120, 1.1017, 1.1017, 1.1012, 1.1014),
+        ];
+
+        let mid = mid_bars(&bid, &ask);
+        assert_eq!(mid.len(), 2);
+        assert_eq!(mid[0].time, 60);
+        assert!((mid[0].open - 1.1006).abs() < 1e-9);
+        assert!((mid[0].high - 1.1021).abs() < 1e-9);
+        assert!((mid[1].close - 1.1013).abs() < 1e-9);
+
+        assert!(is_honored(PriceBasis::Mid, Some("mid")));
+        assert!(!is_honored(PriceBasis::Mid, None));
+        assert!(!is_honored(PriceBasis::Mid, Some("bid")));
+    }
+
+    #[test]
+    fn live_trades_take_the_quoted_side() {
+        use crate::depth::{DepthPayload, DepthUpdate, LocalDepthCache};
+
+        let min_ticksize = MinTicksize::from(0.00001);
+        let mut book = LocalDepthCache::default();
+        book.update(
+            DepthUpdate::Snapshot(DepthPayload {
+                last_update_id: 1,
+                time: 1,
+                bids: vec![crate::depth::DeOrder {
+                    price: 1.10000,
+                    qty: 1.0,
+                }],
+                asks: vec![crate::depth::DeOrder {
+                    price: 1.10004,
+                    qty: 1.0,
+                }],
+            }),
+            min_ticksize,
+        );
+        let traded = Trade {
+            time: 1,
+            is_sell: false,
+            price: Price::from_f32(1.10001),
+            qty: 1.0,
+        };
+
+        let priced = |basis| {
+            let mut trades = [traded];
+            reprice(&mut trades, basis, &book.depth, min_ticksize.into());
+            trades[0].price
+        };
+        let tick = |price| Price::from_f32(price).round_to_min_tick(min_ticksize);
+        assert_eq!(priced(PriceBasis::Ask), tick(1.10004));
+        assert_eq!(priced(PriceBasis::Mid), tick(1.10002));
+        assert_eq!(priced(PriceBasis::Last), traded.price);
+    }
+}
@@ -0,0 +1,293 @@
+//! Live tick and pip values of MT5 symbols in the account currency.
+//!
+//! What a pip of EURGBP is worth on a USD account moves with GBPUSD. The symbol list tells which
+//! pairs the broker has, so the conversion [`Route`] is resolved from those, and each streamed
+//! symbol watches the quotes of its route's symbols on the proxy socket it already uses. Until
+//! those quotes arrive, or when the broker has no symbol to convert through, the tick value the
+//! broker reported with the symbol list stands in, flagged as stale.
+
+use super::{multiplex, suffix};
+use crate::conversion::{self, Route};
+use crate::{SourceId, Ticker, TickerInfo};
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Metadata of listed symbols by the connection listing them, then by normalized name
+static SYMBOLS: LazyLock<RwLock<HashMap<SourceId, HashMap<String, SymbolSpec>>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct SymbolSpec {
+    /// The broker's own name, e.g. `GBPUSD.a`
+    pub name: String,
+    pub tick_size: f64,
+    pub contract_size: f64,
+    pub digits: i32,
+    /// Account currency value of a tick for one lot, as of when the list was fetched
+    pub tick_value: Option<f64>,
+}
+
+impl SymbolSpec {
+    /// FX quotes with a fractional last digit have pips ten ticks wide
+    fn pip_size(&self) -> f64 {
+        if matches!(self.digits, 3 | 5) {
+            self.tick_size * 10.0
+        } else {
+            self.tick_size
+        }
+    }
+}
+
+/// An account currency value, and whether it's the broker's snapshot rather than live
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccountValue {
+    pub value: f64,
+    pub stale: bool,
+}
+
+pub(super) fn record_symbols(source: SourceId, specs: impl IntoIterator<Item = SymbolSpec>) {
+    if let Ok(mut symbols) = SYMBOLS.write() {
+        let listed = symbols.entry(source).or_default();
+        for spec in specs {
+            listed.insert(suffix::normalize(&spec.name), spec);
+        }
+    }
+}
+
+/// Connection whose listing describes `ticker`, the active one for tickers of no connection
+fn listed_by(ticker: &Ticker) -> Option<SourceId> {
+    ticker
+        .connection()
+        .or_else(|| super::get_global_config().map(|config| config.source()))
+}
+
+fn spec_of(ticker: &Ticker) -> Option<SymbolSpec> {
+    SYMBOLS
+        .read()
+        .ok()?
+        .get(&listed_by(ticker)?)?
+        .get(&suffix::normalize(&ticker.to_string()))
+        .cloned()
+}
+
+/// The tick `ticker` was listed with, before any override or rounding
+pub fn listed_tick_size(ticker: &Ticker) -> Option<f32> {
+    spec_of(ticker).map(|spec| spec.tick_size as f32)
+}
+
+fn route(symbol: &str, account: &str, symbols: &HashMap<String, SymbolSpec>) -> Option<Route> {
+    let quote = conversion::quote_currency(symbol)?;
+    Route::resolve(&quote, account, |name| symbols.contains_key(name))
+}
+
+/// Account currency value of one tick for one lot of `spec`, live when every price on the route
+/// is known
+fn tick_value_of(
+    spec: &SymbolSpec,
+    route: Option<&Route>,
+    price_of: impl Fn(&str) -> Option<f64>,
+) -> Option<AccountValue> {
+    let live = route
+        .and_then(|route| route.rate(price_of))
+        .map(|rate| AccountValue {
+            value: spec.tick_size * spec.contract_size * rate,
+            stale: false,
+        });
+
+    live.or_else(|| {
+        spec.tick_value
+            .filter(|value| *value > 0.0)
+            .map(|value| AccountValue { value, stale: true })
+    })
+}
+
+fn account_currency() -> Option<String> {
+    super::get_global_config()
+        .map(|config| config.account_currency.trim().to_uppercase())
+        .filter(|currency| !currency.is_empty())
+}
+
+fn live_price(symbol: &str) -> Option<f64> {
+    conversion::reference_price(symbol).map(f64::from)
+}
+
+/// Account currency value of one tick for one lot of `ticker`
+pub fn tick_value(ticker: &Ticker) -> Option<AccountValue> {
+    let spec = spec_of(ticker)?;
+    let route = account_currency().and_then(|account| {
+        let symbols = SYMBOLS.read().ok()?;
+        route(&spec.name, &account, symbols.get(&listed_by(ticker)?)?)
+    });
+
+    tick_value_of(&spec, route.as_ref(), live_price)
+}
+
+/// Account currency value of one pip at `lots` of `ticker`, see [`tick_value`] for whether it's
+/// live
+pub fn pip_value(ticker: &Ticker, lots: f64) -> Option<f64> {
+    let spec = spec_of(ticker)?;
+    let per_tick = tick_value(ticker)?.value;
+
+    Some(per_tick * spec.pip_size() / spec.tick_size * lots)
+}
+
+/// Watches the quotes converting `ticker_info` into the account currency, for as long as the
+/// returned watches are held
+pub(super) fn watch(config: &super::Mt5Config, ticker_info: &TickerInfo) -> Vec<multiplex::Watch> {
+    let account = config.account_currency.trim();
+    let Ok(listings) = SYMBOLS.read() else {
+        return vec![];
+    };
+    // Conversion symbols are watched on the socket the pane streams through
+    let Some(symbols) = listings.get(&config.source()) else {
+        return vec![];
+    };
+    let Some(route) = route(&ticker_info.ticker.to_string(), account, symbols) else {
+        return vec![];
+    };
+
+    route
+        .legs
+        .iter()
+        .filter_map(|leg| symbols.get(&leg.symbol))
+        .filter(|spec| spec.name != ticker_info.ticker.to_string())
+        .filter_map(|spec| multiplex::watch(config, &spec.name))
+        .collect()
+}
+
+#[derive(Deserialize)]
+struct QuoteFrame<'a> {
+    #[serde(borrow)]
+    symbol: std::borrow::Cow<'a, str>,
+    bid: f64,
+    ask: f64,
+}
+
+/// Records the mid price of a `quote` frame
+pub(super) fn record_quote(frame: &str) {
+    if let Ok(quote) = serde_json::from_str::<QuoteFrame>(frame)
+        && quote.bid > 0.0
+        && quote.ask > 0.0
+    {
+        conversion::record_reference_price(&quote.symbol, ((quote.bid + quote.ask) / 2.0) as f32);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, tick_size: f64, contract_size: f64, digits: i32) -> SymbolSpec {
+        SymbolSpec {
+            name: name.to_string(),
+            tick_size,
+            contract_size,
+            digits,
+            tick_value: None,
+        }
+    }
+
+    fn listed(specs: &[SymbolSpec]) -> HashMap<String, SymbolSpec> {
+        specs
+            .iter()
+            .map(|spec| (suffix::normalize(&spec.name), spec.clone()))
+            .collect()
+    }
+
+    fn prices(table: &'static [(&'static str, f64)]) -> impl Fn(&str) -> Option<f64> {
+        |symbol| {
+            table
+                .iter()
+                .find(|(name, _)| *name == symbol)
+                .map(|(_, price)| *price)
+        }
+    }
+
+    fn assert_close(value: Option<AccountValue>, expected: f64, stale: bool) {
+        let value = value.expect("a tick value");
+        assert!((value.value - expected).abs() < 1e-9, "{value:?}");
+        assert_eq!(value.stale, stale);
+    }
+
+    #[test]
+    fn direct_rate_follows_the_quote_pair() {
+        let eurgbp = spec("EURGBP.a", 0.00001, 100_000.0, 5);
+        let symbols = listed(&[eurgbp.clone(), spec("GBPUSD.a", 0.00001, 100_000.0, 5)]);
+        let route = route(&eurgbp.name, "USD", &symbols);
+
+        // A tick is 1 GBP a lot, worth GBPUSD dollars
+        assert_close(
+            tick_value_of(&eurgbp, route.as_ref(), prices(&[("GBPUSD", 1.25)])),
+            1.25,
+            false,
+        );
+        assert_close(
+            tick_value_of(&eurgbp, route.as_ref(), prices(&[("GBPUSD", 1.30)])),
+            1.30,
+            false,
+        );
+        assert!((eurgbp.pip_size() - 0.0001).abs() < 1e-12);
+    }
+
+    #[test]
+    fn inverse_rate_divides_by_the_account_pair() {
+        let eurjpy = spec("EURJPY", 0.001, 100_000.0, 3);
+        let symbols = listed(&[eurjpy.clone(), spec("USDJPY", 0.001, 100_000.0, 3)]);
+        let route = route(&eurjpy.name, "USD", &symbols);
+
+        // A tick is 100 JPY a lot
+        assert_close(
+            tick_value_of(&eurjpy, route.as_ref(), prices(&[("USDJPY", 160.0)])),
+            0.625,
+            false,
+        );
+    }
+
+    #[test]
+    fn cross_rate_goes_through_usd() {
+        let eurgbp = spec("EURGBP", 0.00001, 100_000.0, 5);
+        let symbols = listed(&[
+            eurgbp.clone(),
+            spec("GBPUSD", 0.00001, 100_000.0, 5),
+            spec("USDJPY", 0.001, 100_000.0, 3),
+        ]);
+        let route = route(&eurgbp.name, "JPY", &symbols);
+        assert_eq!(route.as_ref().map(|route| route.legs.len()), Some(2));
+
+        assert_close(
+            tick_value_of(
+                &eurgbp,
+                route.as_ref(),
+                prices(&[("GBPUSD", 1.25), ("USDJPY", 150.0)]),
+            ),
+            187.5,
+            false,
+        );
+    }
+
+    #[test]
+    fn missing_conversion_symbol_falls_back_to_the_reported_tick_value() {
+        let eurgbp = SymbolSpec {
+            tick_value: Some(1.27),
+            ..spec("EURGBP", 0.00001, 100_000.0, 5)
+        };
+        let symbols = listed(std::slice::from_ref(&eurgbp));
+        let route = route(&eurgbp.name, "USD", &symbols);
+        assert_eq!(route, None);
+
+        assert_close(tick_value_of(&eurgbp, None, prices(&[])), 1.27, true);
+        // Listed, but no quote arrived yet
+        let symbols = listed(&[eurgbp.clone(), spec("GBPUSD", 0.00001, 100_000.0, 5)]);
+        let route = super::route(&eurgbp.name, "USD", &symbols);
+        assert_close(
+            tick_value_of(&eurgbp, route.as_ref(), prices(&[])),
+            1.27,
+            true,
+        );
+
+        let unreported = spec("EURGBP", 0.00001, 100_000.0, 5);
+        assert_eq!(tick_value_of(&unreported, None, prices(&[])), None);
+    }
+}
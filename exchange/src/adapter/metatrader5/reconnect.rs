@@ -0,0 +1,362 @@
+//! Pacing of the shared proxy socket's reconnects.
+//!
+//! The backoff grows while connecting keeps failing the same way and starts over once the cause
+//! changes, a proxy that comes back after the network did shouldn't inherit the network's delay.
+//! Waits are cut short when the proxy accepts TCP connections again or the machine just woke up,
+//! so resuming a laptop doesn't leave panes waiting out a minute long backoff.
+
+use super::diagnosis::{self, Symptom};
+use crate::adapter::AdapterError;
+
+use std::io;
+use std::time::{Duration, SystemTime};
+use tokio::time::Instant;
+use tokio_tungstenite::tungstenite;
+
+pub(super) const INITIAL_DELAY: Duration = Duration::from_secs(1);
+pub(super) const MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Connecting to a host the network can't reach otherwise hangs for the OS TCP timeout
+pub(super) const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often a wait checks whether retrying early is worth it
+const PROBE_INTERVAL: Duration = Duration::from_secs(2);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The wall clock running this much ahead of the monotonic one means the machine was asleep
+const RESUME_GAP: Duration = Duration::from_secs(5);
+
+/// Failures of one outage that are all logged, after that only every power of two is
+const LOGGED_FAILURES: u32 = 3;
+
+/// Why connecting to the proxy failed, or the connection ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cause {
+    /// DNS failed or the proxy's host can't be reached
+    NetworkDown,
+    /// The host is reachable but nothing accepts connections on the proxy's port
+    ServerDown,
+    /// The proxy refused the credentials
+    Rejected,
+    /// Something answered that isn't a proxy, the address likely points at the wrong port
+    NotAProxy,
+    /// An established connection was closed or broke
+    Dropped,
+}
+
+impl Cause {
+    pub(super) fn of_connect(error: &tungstenite::Error) -> Cause {
+        match error {
+            tungstenite::Error::Io(error) => Cause::of_io(error),
+            tungstenite::Error::Http(response)
+                if matches!(response.status().as_u16(), 401 | 403) =>
+            {
+                Cause::Rejected
+            }
+            error if Symptom::of_upgrade(error).is_some() => Cause::NotAProxy,
+            _ => Cause::ServerDown,
+        }
+    }
+
+    /// `otherwise`, unless the failure showed the server isn't a proxy
+    pub(super) fn of_failure(error: &AdapterError, otherwise: Cause) -> Cause {
+        if diagnosis::is_not_a_proxy(error) {
+            Cause::NotAProxy
+        } else {
+            otherwise
+        }
+    }
+
+    fn of_io(error: &io::Error) -> Cause {
+        match error.kind() {
+            io::ErrorKind::ConnectionRefused
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted => Cause::ServerDown,
+            _ => Cause::NetworkDown,
+        }
+    }
+
+    /// Whether the proxy's port accepting connections again is a reason to retry right away
+    fn is_probed(self) -> bool {
+        matches!(self, Cause::NetworkDown | Cause::ServerDown)
+    }
+}
+
+impl std::fmt::Display for Cause {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cause::NetworkDown => write!(f, "network unreachable"),
+            Cause::ServerDown => write!(f, "proxy not accepting connections"),
+            Cause::Rejected => write!(f, "credentials rejected"),
+            Cause::NotAProxy => write!(f, "not an mt5-proxy"),
+            Cause::Dropped => write!(f, "connection lost"),
+        }
+    }
+}
+
+/// A reconnect the socket is waiting for
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+    pub at: std::time::Instant,
+    pub cause: Cause,
+    /// Failed attempts in a row, the first failure counts as one
+    pub failures: u32,
+    /// Retrying was stopped, the socket waits to be told to reconnect
+    pub stopped: bool,
+}
+
+impl Retry {
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(std::time::Instant::now())
+    }
+}
+
+/// Close codes the proxy uses when it won't take these credentials anymore
+const REVOKED_CLOSE_CODES: [u16; 3] = [1008, 4001, 4003];
+
+/// Close codes of a proxy that's going away for a while
+const RESTART_CLOSE_CODES: [u16; 3] = [1001, 1012, 1013];
+
+/// Words in a termination reason that mean the credentials were revoked or banned
+const REVOKED_REASONS: [&str; 7] = [
+    "revoked",
+    "ban",
+    "unauthorized",
+    "forbidden",
+    "invalid_key",
+    "invalid key",
+    "expired",
+];
+
+/// The proxy ending a session on purpose, with a `shutdown` or `kicked` message or a close frame
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Termination {
+    pub reason: String,
+    /// The credentials were revoked or banned, reconnecting would just be refused again
+    pub is_final: bool,
+}
+
+impl Termination {
+    /// Reads the `reason` of a `shutdown` or `kicked` message
+    pub(super) fn from_reason(reason: &str) -> Self {
+        let lowercase = reason.to_ascii_lowercase();
+        let is_final = REVOKED_REASONS.iter().any(|word| lowercase.contains(word));
+
+        Self {
+            reason: if reason.is_empty() {
+                "no reason given"
+            } else {
+                reason
+            }
+            .to_string(),
+            is_final,
+        }
+    }
+
+    /// Reads a close frame, `None` for codes that don't end the session on purpose
+    pub(super) fn from_close(code: u16, reason: &str) -> Option<Self> {
+        if REVOKED_CLOSE_CODES.contains(&code) {
+            let mut termination = Self::from_reason(reason);
+            termination.is_final = true;
+            return Some(termination);
+        }
+        if RESTART_CLOSE_CODES.contains(&code) || !reason.is_empty() {
+            return Some(Self::from_reason(reason));
+        }
+        None
+    }
+}
+
+#[derive(Debug)]
+pub(super) struct Backoff {
+    delay: Duration,
+    cause: Option<Cause>,
+    failures: u32,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            delay: INITIAL_DELAY,
+            cause: None,
+            failures: 0,
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before the next attempt after a failure of `cause`
+    pub(super) fn fail(&mut self, cause: Cause) -> Duration {
+        if self.cause == Some(cause) {
+            self.delay = (self.delay * 2).min(MAX_DELAY);
+        } else {
+            self.delay = INITIAL_DELAY;
+            self.failures = 0;
+        }
+        self.cause = Some(cause);
+        self.failures += 1;
+        self.delay
+    }
+
+    /// Starts over after a connection was established
+    pub(super) fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub(super) fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Whether the latest failure is worth a log line, a long outage logs ever more rarely
+    pub(super) fn is_noteworthy(&self) -> bool {
+        self.failures <= LOGGED_FAILURES || self.failures.is_power_of_two()
+    }
+}
+
+/// Why a wait ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum Wake {
+    Elapsed,
+    /// The proxy's port accepts connections again
+    Reachable,
+    /// The machine woke up from sleep, the network may well be back
+    Resumed,
+    /// Retrying now was asked for
+    Requested,
+}
+
+/// Sleeps for `delay`, waking early once retrying looks promising
+pub(super) async fn wait(server_addr: &str, cause: Cause, delay: Duration) -> Wake {
+    let deadline = Instant::now() + delay;
+
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return Wake::Elapsed;
+        }
+
+        let wall = SystemTime::now();
+        tokio::time::sleep(PROBE_INTERVAL.min(deadline - now)).await;
+
+        // The monotonic clock stands still while the machine sleeps, the wall clock doesn't
+        let wall_elapsed = wall.elapsed().unwrap_or_default();
+        if wall_elapsed > now.elapsed() + RESUME_GAP {
+            return Wake::Resumed;
+        }
+
+        if cause.is_probed() && is_reachable(server_addr).await {
+            return Wake::Reachable;
+        }
+    }
+}
+
+/// A bare TCP connect to the proxy, far cheaper than a WebSocket handshake
+async fn is_reachable(server_addr: &str) -> bool {
+    matches!(
+        tokio::time::timeout(PROBE_TIMEOUT, tokio::net::TcpStream::connect(server_addr)).await,
+        Ok(Ok(_))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_starts_over_when_the_cause_changes() {
+        let mut backoff = Backoff::default();
+
+        assert_eq!(backoff.fail(Cause::NetworkDown), INITIAL_DELAY);
+        assert_eq!(backoff.fail(Cause::NetworkDown), INITIAL_DELAY * 2);
+        for _ in 0..10 {
+            backoff.fail(Cause::NetworkDown);
+        }
+        assert_eq!(backoff.fail(Cause::NetworkDown), MAX_DELAY);
+
+        // The network is back but the proxy isn't up yet
+        assert_eq!(backoff.fail(Cause::ServerDown), INITIAL_DELAY);
+        assert_eq!(backoff.failures(), 1);
+
+        backoff.reset();
+        assert_eq!(backoff.fail(Cause::ServerDown), INITIAL_DELAY);
+    }
+
+    #[test]
+    fn long_outages_log_rarely() {
+        let mut backoff = Backoff::default();
+        let logged: Vec<u32> = (1..=40)
+            .filter(|_| {
+                backoff.fail(Cause::NetworkDown);
+                backoff.is_noteworthy()
+            })
+            .collect();
+
+        assert_eq!(logged, [1, 2, 3, 4, 8, 16, 32]);
+    }
+
+    #[test]
+    fn revoked_sessions_are_final_and_restarts_are_not() {
+        assert!(Termination::from_reason("api key revoked").is_final);
+        assert!(Termination::from_reason("Banned: too many logins").is_final);
+        assert!(!Termination::from_reason("maintenance").is_final);
+        assert_eq!(Termination::from_reason("").reason, "no reason given");
+
+        // Policy violation ends it whatever the wording
+        assert!(Termination::from_close(1008, "").unwrap().is_final);
+        assert!(Termination::from_close(4003, "bye").unwrap().is_final);
+        assert!(
+            !Termination::from_close(1012, "restarting")
+                .unwrap()
+                .is_final
+        );
+        assert!(
+            Termination::from_close(1000, "key expired")
+                .unwrap()
+                .is_final
+        );
+        assert_eq!(Termination::from_close(1000, ""), None);
+    }
+
+    #[test]
+    fn io_errors_tell_network_from_server() {
+        let refused = io::Error::from(io::ErrorKind::ConnectionRefused);
+        assert_eq!(Cause::of_io(&refused), Cause::ServerDown);
+
+        let unreachable = io::Error::from(io::ErrorKind::NetworkUnreachable);
+        assert_eq!(Cause::of_io(&unreachable), Cause::NetworkDown);
+
+        let dns = io::Error::other("failed to lookup address information");
+        assert_eq!(
+            Cause::of_connect(&tungstenite::Error::Io(dns)),
+            Cause::NetworkDown
+        );
+    }
+
+    #[tokio::test]
+    async fn wait_ends_once_the_proxy_is_back() {
+        // Reserve a port, then free it so the proxy looks down
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        drop(listener);
+
+        assert!(!is_reachable(&addr).await);
+
+        let proxy = {
+            let addr = addr.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                std::net::TcpListener::bind(addr).unwrap()
+            })
+        };
+
+        let started = Instant::now();
+        let wake = wait(&addr, Cause::ServerDown, Duration::from_secs(30)).await;
+        assert_eq!(wake, Wake::Reachable);
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        // Credentials don't get fixed by the port opening, those waits run their course
+        let _listener = proxy.await.unwrap();
+        let wake = wait(&addr, Cause::Rejected, Duration::from_millis(300)).await;
+        assert_eq!(wake, Wake::Elapsed);
+    }
+}
@@ -0,0 +1,220 @@
+//! Read-only HTTP fallback for networks that block WebSocket upgrades.
+//!
+//! The proxy serves the symbol list and klines at `/api/symbols` and `/api/klines` with the
+//! same JSON bodies as the `symbols` and `klines` socket responses. The HMAC handshake moves into
+//! `X-API-Key`, `X-Timestamp` and `X-Signature` headers, next to whatever the auth mode adds.
+//! Live streams have no such fallback.
+
+use super::{AdapterError, AuthMode, Mt5Config, compute_hmac_signature};
+use crate::limiter::HTTP_CLIENT;
+
+use reqwest::RequestBuilder;
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+/// Proxies, by address, that served history over HTTP since the app started
+static SERVED_OVER_HTTP: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Whether history from the proxy at `server_addr` was loaded through this fallback
+pub(super) fn served_history(server_addr: &str) -> bool {
+    SERVED_OVER_HTTP
+        .lock()
+        .is_ok_and(|served| served.contains(server_addr))
+}
+
+fn base_url(config: &Mt5Config) -> String {
+    let protocol = if config.use_tls { "https" } else { "http" };
+    format!("{protocol}://{}/api", config.server_addr)
+}
+
+fn authorized(
+    config: &Mt5Config,
+    mut request: RequestBuilder,
+) -> Result<RequestBuilder, AdapterError> {
+    if config.sends_hmac() {
+        let (api_key, api_secret) = config.credentials().map_err(AdapterError::InvalidRequest)?;
+        let timestamp = chrono::Utc::now().timestamp_millis() as u64;
+        request = request
+            .header("X-API-Key", &api_key)
+            .header("X-Timestamp", timestamp.to_string())
+            .header(
+                "X-Signature",
+                compute_hmac_signature(&api_key, timestamp, &api_secret),
+            );
+    }
+
+    Ok(match &config.auth_mode {
+        AuthMode::Hmac => request,
+        AuthMode::BearerToken(token) => request.bearer_auth(token.trim()),
+        AuthMode::Headers(headers) => headers.iter().fold(request, |request, (name, value)| {
+            request.header(name.trim(), value.trim())
+        }),
+    })
+}
+
+/// GETs `endpoint` and returns the body, which parses like the matching socket response
+async fn get(
+    config: &Mt5Config,
+    endpoint: &str,
+    query: &[(&str, String)],
+) -> Result<String, AdapterError> {
+    let url = format!("{}/{endpoint}", base_url(config));
+
+    let request = HTTP_CLIENT
+        .get(&url)
+        .query(query)
+        .timeout(Duration::from_secs(config.timeout_secs));
+    let response = authorized(config, request)?.send().await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(AdapterError::InvalidRequest(format!(
+            "HTTP fallback {endpoint}: {status}"
+        )));
+    }
+    let body = response.text().await?;
+
+    if let Ok(mut served) = SERVED_OVER_HTTP.lock() {
+        served.insert(config.server_addr.clone());
+    }
+    Ok(body)
+}
+
+pub(super) async fn get_symbols(config: &Mt5Config) -> Result<String, AdapterError> {
+    let query: Vec<(&str, String)> = config
+        .terminal_id
+        .iter()
+        .map(|terminal| ("terminal_id", terminal.clone()))
+        .collect();
+    get(config, "symbols", &query).await
+}
+
+/// `request` is the `get_klines` socket request, its fields become the query
+pub(super) async fn get_klines(
+    config: &Mt5Config,
+    request: &serde_json::Value,
+) -> Result<String, AdapterError> {
+    let query: Vec<(&str, String)> = request
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| *key != "type")
+        .map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            (key.as_str(), value)
+        })
+        .collect();
+
+    get(config, "klines", &query).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Ticker, adapter::Exchange};
+    use std::io::{BufRead as _, BufReader, Write as _};
+    use std::net::TcpListener;
+
+    /// Answers one request with `body` and hands back the request line and headers it got
+    fn mock_proxy(body: &'static str) -> (String, std::thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+
+        let handle = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+
+            let mut head = vec![];
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                head.push(line.trim().to_string());
+            }
+
+            let mut stream = reader.into_inner();
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            head
+        });
+
+        (addr, handle)
+    }
+
+    fn config(server_addr: String) -> Mt5Config {
+        Mt5Config {
+            server_addr,
+            api_key: "key-123".to_string(),
+            api_secret: "s3cret".to_string(),
+            ..Mt5Config::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn symbols_come_with_hmac_headers() {
+        let body = r#"{"type":"symbols","data":[{"symbol":"EURUSD","tick_size":0.00001,"min_lot":0.01,"contract_size":100000,"digits":5}]}"#;
+        let (addr, proxy) = mock_proxy(body);
+        let config = config(addr);
+
+        assert!(!served_history(&config.server_addr));
+        let response = get_symbols(&config).await.unwrap();
+        let parsed = super::super::parse_symbols(&response, config.source()).unwrap();
+        assert!(parsed.infos.contains_key(
+            &Ticker::new("EURUSD", Exchange::MetaTrader5).on_connection(Some(config.source()))
+        ));
+        assert!(served_history(&config.server_addr));
+
+        let head = proxy.join().unwrap();
+        assert!(head[0].starts_with("GET /api/symbols "));
+        let has = |name: &str| {
+            head.iter()
+                .any(|line| line.to_ascii_lowercase().starts_with(name))
+        };
+        assert!(has("x-api-key: key-123"));
+        assert!(has("x-timestamp: "));
+        assert!(has("x-signature: "));
+    }
+
+    #[tokio::test]
+    async fn klines_request_becomes_the_query() {
+        let body = r#"{"type":"klines","data":[{"time":1704355200000,"open":1.1,"high":1.2,"low":1.0,"close":1.15,"volume":42.0}]}"#;
+        let (addr, proxy) = mock_proxy(body);
+        let config = Mt5Config {
+            auth_mode: AuthMode::BearerToken("tok-456".to_string()),
+            ..config(addr)
+        };
+
+        let request = serde_json::json!({
+            "type": "get_klines",
+            "symbol": "EURUSD",
+            "timeframe": "M1",
+            "limit": 500,
+        });
+        let response = get_klines(&config, &request).await.unwrap();
+        let parsed: super::super::KlinesResponse = serde_json::from_str(&response).unwrap();
+        assert_eq!(parsed.data.len(), 1);
+
+        let head = proxy.join().unwrap();
+        let request_line = &head[0];
+        assert!(request_line.starts_with("GET /api/klines?"));
+        for param in ["symbol=EURUSD", "timeframe=M1", "limit=500"] {
+            assert!(request_line.contains(param), "{request_line}");
+        }
+        assert!(!request_line.contains("type="));
+        assert!(
+            head.iter()
+                .any(|line| line.eq_ignore_ascii_case("authorization: Bearer tok-456"))
+        );
+    }
+}
@@ -0,0 +1,23 @@
+//! Symbols a broker stopped listing, such as delisted CFDs.
+//!
+//! Retired symbols keep their specs so panes and saved history still resolve them, but nothing
+//! subscribes to them anymore. The list is owned by the symbol cache and mirrored here, where
+//! streams and panes can look it up.
+
+use crate::Ticker;
+
+use std::collections::HashSet;
+use std::sync::{LazyLock, RwLock};
+
+static RETIRED: LazyLock<RwLock<HashSet<Ticker>>> = LazyLock::new(|| RwLock::new(HashSet::new()));
+
+/// Replaces every retired symbol with `tickers`
+pub fn set_all(tickers: impl IntoIterator<Item = Ticker>) {
+    if let Ok(mut retired) = RETIRED.write() {
+        *retired = tickers.into_iter().collect();
+    }
+}
+
+pub fn is_retired(ticker: &Ticker) -> bool {
+    RETIRED.read().is_ok_and(|retired| retired.contains(ticker))
+}
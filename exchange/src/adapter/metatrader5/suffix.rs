@@ -0,0 +1,130 @@
+//! Broker symbol suffixes, so a layout made on one MT5 broker can find its symbols on another.
+//!
+//! Brokers tag the same instrument per account type, `EURUSD.a`, `EURUSDm` and `EURUSD_i` are
+//! all `EURUSD`. Names are compared on their normalized form and only a single candidate is
+//! taken as a match.
+
+/// Characters a broker puts between the instrument and its account tag
+const SEPARATORS: [char; 3] = ['.', '_', '-'];
+/// Marker characters some brokers append or prepend, e.g. `BTCUSD#` or `#AAPL`
+const MARKERS: [char; 3] = ['#', '!', '+'];
+/// Lowercase tags glued straight onto the instrument, single letters are accepted on their own
+const GLUED_TAGS: [&str; 6] = ["pro", "ecn", "raw", "mini", "micro", "cent"];
+
+const MAX_TAG_LEN: usize = 5;
+/// Shortest instrument name left after stripping, keeps `DE.30` style names intact
+const MIN_BASE_LEN: usize = 3;
+
+/// Broker neutral name of `symbol`, uppercased with the account tag removed
+pub fn normalize(symbol: &str) -> String {
+    let mut base = symbol.trim().trim_matches(MARKERS);
+
+    if let Some(pos) = base.rfind(SEPARATORS)
+        && pos >= MIN_BASE_LEN
+        && base.len() - pos - 1 <= MAX_TAG_LEN
+    {
+        base = &base[..pos];
+    }
+
+    let body = base.trim_end_matches(|c: char| c.is_ascii_lowercase());
+    let tag = &base[body.len()..];
+    let is_known_tag = tag.len() == 1 || GLUED_TAGS.contains(&tag);
+    if !tag.is_empty()
+        && is_known_tag
+        && body.len() >= MIN_BASE_LEN
+        && body
+            .chars()
+            .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+    {
+        base = body;
+    }
+
+    base.to_ascii_uppercase()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SymbolMatch<'a> {
+    /// Listed under the very same name
+    Exact,
+    /// The only listed symbol with the same normalized name
+    Unique(&'a str),
+    /// Several listed symbols share the normalized name, sorted by name
+    Ambiguous(Vec<&'a str>),
+    NotFound,
+}
+
+/// Looks `symbol` up among the names a connection lists
+pub fn find_match<'a>(
+    symbol: &str,
+    available: impl IntoIterator<Item = &'a str>,
+) -> SymbolMatch<'a> {
+    let wanted = normalize(symbol);
+    let mut candidates = vec![];
+
+    for name in available {
+        if name == symbol {
+            return SymbolMatch::Exact;
+        }
+        if normalize(name) == wanted {
+            candidates.push(name);
+        }
+    }
+
+    candidates.sort_unstable();
+    candidates.dedup();
+
+    match candidates.as_slice() {
+        [] => SymbolMatch::NotFound,
+        [only] => SymbolMatch::Unique(only),
+        _ => SymbolMatch::Ambiguous(candidates),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_real_world_suffixes() {
+        let table = [
+            ("EURUSD.a", "EURUSD"),
+            ("EURUSD.r", "EURUSD"),
+            ("EURUSDm", "EURUSD"),
+            ("USDJPYc", "USDJPY"),
+            ("XAUUSD_i", "XAUUSD"),
+            ("GBPJPY.pro", "GBPJPY"),
+            ("EURUSD.ecn", "EURUSD"),
+            ("EURUSDmicro", "EURUSD"),
+            ("AUDCAD-ECN", "AUDCAD"),
+            ("EURUSD+", "EURUSD"),
+            ("BTCUSD#", "BTCUSD"),
+            ("#AAPL", "AAPL"),
+            ("US30.cash", "US30"),
+            ("eurusd", "EURUSD"),
+            ("GER40", "GER40"),
+            // Not an account tag, only uppercased
+            ("GER30Cash", "GER30CASH"),
+            ("DE.30", "DE.30"),
+        ];
+
+        for (symbol, expected) in table {
+            assert_eq!(normalize(symbol), expected, "{symbol}");
+        }
+    }
+
+    #[test]
+    fn only_a_single_candidate_is_a_match() {
+        let listed = ["EURUSD", "GBPUSD.r", "GBPUSD.x", "XAUUSD"];
+
+        assert_eq!(find_match("XAUUSD", listed), SymbolMatch::Exact);
+        assert_eq!(
+            find_match("EURUSD.a", listed),
+            SymbolMatch::Unique("EURUSD")
+        );
+        assert_eq!(
+            find_match("GBPUSDm", listed),
+            SymbolMatch::Ambiguous(vec!["GBPUSD.r", "GBPUSD.x"])
+        );
+        assert_eq!(find_match("USDJPY.a", listed), SymbolMatch::NotFound);
+    }
+}
@@ -0,0 +1,77 @@
+//! Terminals a proxy bridges.
+//!
+//! One proxy can front several MT5 terminals at once, of different brokers or accounts. It
+//! lists them in answer to `get_terminals`, and routes subscriptions and fetches naming a
+//! `terminal_id` to that terminal. Proxies with a single unnamed terminal don't answer the
+//! request and take everything without the field, a connection that picked no terminal never
+//! sends it.
+
+use super::AdapterError;
+use super::version::lenient;
+
+use serde::Deserialize;
+
+/// A terminal behind the proxy
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Terminal {
+    pub id: String,
+    #[serde(default, deserialize_with = "lenient")]
+    pub broker: Option<String>,
+    /// Account login, sent as a number by some proxies
+    #[serde(default, deserialize_with = "lenient")]
+    pub account: Option<String>,
+}
+
+/// e.g. `IC Markets · 5012345`, the id when the proxy labels it with neither
+impl std::fmt::Display for Terminal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.broker, &self.account) {
+            (Some(broker), Some(account)) => write!(f, "{broker} · {account}"),
+            (Some(label), None) | (None, Some(label)) => write!(f, "{label} ({})", self.id),
+            (None, None) => write!(f, "{}", self.id),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TerminalsResponse {
+    #[serde(default)]
+    data: Vec<Terminal>,
+}
+
+/// Reads a `terminals` answer, entries without an id are left out
+pub(super) fn parse(text: &str) -> Result<Vec<Terminal>, AdapterError> {
+    let resp = serde_json::from_str::<TerminalsResponse>(text)
+        .map_err(|e| AdapterError::ParseError(e.to_string()))?;
+
+    Ok(resp
+        .data
+        .into_iter()
+        .filter(|terminal| !terminal.id.trim().is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn terminals_parse_with_or_without_labels() {
+        let text = r#"{"type":"terminals","data":[
+            {"id":"ic-live","broker":"IC Markets","account":5012345},
+            {"id":"demo","account":"88001"},
+            {"id":"bare"},
+            {"id":" "}
+        ]}"#;
+        let terminals = parse(text).unwrap();
+
+        assert_eq!(
+            terminals
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            ["IC Markets · 5012345", "88001 (demo)", "bare"]
+        );
+        assert!(parse(r#"{"type":"terminals"}"#).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+//! Tick sizes set by the user for MT5 symbols the broker lists with a finer tick than they trade.
+//!
+//! A broker reporting 0.00001 for a symbol quoted in 0.0001 steps makes heatmap rows ten times
+//! too thin. Overrides replace the listed tick whenever the symbol list is fetched, and remember
+//! the broker's tick so that clearing one can put it back.
+
+use crate::{Ticker, TickerInfo};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Overrides of the saved settings, by symbol
+static OVERRIDES: LazyLock<RwLock<HashMap<Ticker, TickOverride>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TickOverride {
+    pub tick_size: f32,
+    /// What the broker listed when the override was set
+    pub broker_tick: f32,
+}
+
+/// Replaces every override with `overrides`
+pub fn set_all(overrides: &HashMap<Ticker, TickOverride>) {
+    if let Ok(mut current) = OVERRIDES.write() {
+        current.clone_from(overrides);
+    }
+}
+
+pub fn get(ticker: &Ticker) -> Option<TickOverride> {
+    of(&*OVERRIDES.read().ok()?, ticker)
+}
+
+/// The override of `ticker`, or one set before tickers told their connections apart
+fn of(overrides: &HashMap<Ticker, TickOverride>, ticker: &Ticker) -> Option<TickOverride> {
+    overrides
+        .get(ticker)
+        .or_else(|| overrides.get(&ticker.on_connection(None)))
+        .copied()
+}
+
+/// Swaps the listed tick of every overridden symbol in `symbols`
+pub(super) fn apply(symbols: &mut HashMap<Ticker, Option<TickerInfo>>) {
+    let Ok(overrides) = OVERRIDES.read() else {
+        return;
+    };
+
+    for (ticker, info) in symbols.iter_mut() {
+        if let Some(info) = info
+            && let Some(tick) = of(&overrides, ticker)
+        {
+            *info = info.with_min_ticksize(tick.tick_size);
+        }
+    }
+}
+
+/// Why a tick size can't be used as is
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Issue {
+    NotPositive,
+    /// Ticks are powers of ten, `nearest` is the one closest to what was asked
+    NotPowerOfTen {
+        nearest: f32,
+    },
+    /// Prices the broker sends would fall between rows, allowed once confirmed
+    NotMultiple {
+        broker_tick: f32,
+    },
+}
+
+impl Issue {
+    /// Whether the tick size can still be used if the user insists
+    pub fn is_confirmable(&self) -> bool {
+        matches!(self, Issue::NotMultiple { .. })
+    }
+}
+
+/// Checks `tick_size` against the tick the broker lists, `broker_tick`
+pub fn validate(tick_size: f32, broker_tick: f32) -> Result<(), Issue> {
+    if !tick_size.is_finite() || tick_size <= 0.0 {
+        return Err(Issue::NotPositive);
+    }
+
+    let power = tick_size.log10().round().clamp(-8.0, 2.0);
+    let nearest = 10f32.powf(power);
+    if ((nearest - tick_size) / tick_size).abs() > 1e-4 {
+        return Err(Issue::NotPowerOfTen { nearest });
+    }
+
+    if broker_tick > 0.0 {
+        let ratio = tick_size / broker_tick;
+        if ratio.round() < 1.0 || ((ratio - ratio.round()) / ratio).abs() > 1e-4 {
+            return Err(Issue::NotMultiple { broker_tick });
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Exchange;
+
+    #[test]
+    fn coarser_power_of_ten_is_accepted() {
+        assert_eq!(validate(0.0001, 0.00001), Ok(()));
+        assert_eq!(validate(0.01, 0.01), Ok(()));
+        // Futures listed in quarter points still take a whole point
+        assert_eq!(validate(1.0, 0.25), Ok(()));
+    }
+
+    #[test]
+    fn finer_or_misaligned_tick_needs_confirming() {
+        let finer = validate(0.00001, 0.0001).unwrap_err();
+        assert_eq!(
+            finer,
+            Issue::NotMultiple {
+                broker_tick: 0.0001
+            }
+        );
+        assert!(finer.is_confirmable());
+
+        assert!(validate(0.1, 0.25).unwrap_err().is_confirmable());
+    }
+
+    #[test]
+    fn unusable_values_are_rejected() {
+        assert_eq!(validate(0.0, 0.01), Err(Issue::NotPositive));
+        assert_eq!(validate(-0.1, 0.01), Err(Issue::NotPositive));
+        assert_eq!(validate(f32::NAN, 0.01), Err(Issue::NotPositive));
+
+        let issue = validate(0.0005, 0.00001).unwrap_err();
+        assert!(
+            matches!(issue, Issue::NotPowerOfTen { nearest } if (nearest - 0.001).abs() < 1e-9)
+        );
+        assert!(!issue.is_confirmable());
+    }
+
+    #[test]
+    fn apply_swaps_only_overridden_symbols() {
+        let overridden = Ticker::new("OVERRIDDEN", Exchange::MetaTrader5);
+        let listed = Ticker::new("LISTED", Exchange::MetaTrader5);
+        let mut symbols = HashMap::from([
+            (
+                overridden,
+                Some(TickerInfo::new(overridden, 0.00001, 0.01, None)),
+            ),
+            (listed, Some(TickerInfo::new(listed, 0.00001, 0.01, None))),
+        ]);
+
+        set_all(&HashMap::from([(
+            overridden,
+            TickOverride {
+                tick_size: 0.0001,
+                broker_tick: 0.00001,
+            },
+        )]));
+        apply(&mut symbols);
+        set_all(&HashMap::new());
+
+        let tick_of = |ticker| symbols[&ticker].unwrap().min_ticksize.as_f32();
+        assert!((tick_of(overridden) - 0.0001).abs() < 1e-9);
+        assert!((tick_of(listed) - 0.00001).abs() < 1e-10);
+    }
+}
@@ -0,0 +1,205 @@
+//! The timezone of the MT5 trade server, which brokers stamp ticks, bars and DOM snapshots in.
+//!
+//! Most run on UTC+2 with DST, so the New York close falls on their midnight. Proxies report it
+//! with `auth_response` and the adapter converts every time to UTC before it leaves the adapter,
+//! so the rest of the app only ever sees UTC. Proxies that don't report one are taken to send
+//! UTC already, as before.
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Weekday};
+use serde::{Deserialize, Deserializer, Serialize};
+
+const HOUR_MS: i64 = 3_600_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ServerTimezone {
+    /// Offset from UTC in seconds, outside of DST
+    pub utc_offset: i32,
+    #[serde(default)]
+    pub dst: Dst,
+}
+
+/// When the server moves its clock an hour forward
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Dst {
+    #[default]
+    None,
+    /// Last Sunday of March to the last Sunday of October, at 01:00 UTC
+    Eu,
+    /// Second Sunday of March to the first Sunday of November, at 02:00 in New York
+    Us,
+}
+
+impl Dst {
+    /// Start and end of DST in `year`, in unix ms
+    fn period(self, year: i32) -> Option<(i64, i64)> {
+        let at = |date: NaiveDate, hour: u32| {
+            date.and_hms_opt(hour, 0, 0)
+                .map(|time| time.and_utc().timestamp_millis())
+        };
+
+        match self {
+            Dst::None => None,
+            Dst::Eu => Some((
+                at(last_sunday(year, 3)?, 1)?,
+                at(last_sunday(year, 10)?, 1)?,
+            )),
+            // New York is on EST at the start and EDT at the end
+            Dst::Us => Some((
+                at(
+                    NaiveDate::from_weekday_of_month_opt(year, 3, Weekday::Sun, 2)?,
+                    7,
+                )?,
+                at(
+                    NaiveDate::from_weekday_of_month_opt(year, 11, Weekday::Sun, 1)?,
+                    6,
+                )?,
+            )),
+        }
+    }
+
+    fn is_active(self, utc_ms: i64) -> bool {
+        DateTime::from_timestamp_millis(utc_ms)
+            .and_then(|time| self.period(time.year()))
+            .is_some_and(|(start, end)| (start..end).contains(&utc_ms))
+    }
+}
+
+fn last_sunday(year: i32, month: u32) -> Option<NaiveDate> {
+    let last_day = NaiveDate::from_ymd_opt(year, month + 1, 1)?.checked_sub_days(Days::new(1))?;
+    last_day.checked_sub_days(Days::new(u64::from(
+        last_day.weekday().num_days_from_sunday(),
+    )))
+}
+
+impl ServerTimezone {
+    pub const UTC: ServerTimezone = ServerTimezone {
+        utc_offset: 0,
+        dst: Dst::None,
+    };
+
+    /// Offset from UTC in ms at the UTC time `utc_ms`
+    fn offset_ms(&self, utc_ms: i64) -> i64 {
+        let dst = if self.dst.is_active(utc_ms) {
+            HOUR_MS
+        } else {
+            0
+        };
+        i64::from(self.utc_offset) * 1000 + dst
+    }
+
+    /// Server time `server_ms` in UTC. The hour repeated when DST ends is taken as its first
+    /// occurrence.
+    pub fn to_utc(&self, server_ms: u64) -> u64 {
+        if *self == Self::UTC {
+            return server_ms;
+        }
+
+        let standard = server_ms as i64 - i64::from(self.utc_offset) * 1000;
+        let summer = standard - HOUR_MS;
+        let utc = if self.dst.is_active(summer) {
+            summer
+        } else {
+            standard
+        };
+
+        utc.max(0) as u64
+    }
+
+    /// UTC time `utc_ms` on the server's clock, for the times sent with requests
+    pub fn from_utc(&self, utc_ms: u64) -> u64 {
+        if *self == Self::UTC {
+            return utc_ms;
+        }
+
+        let utc = utc_ms as i64;
+        (utc + self.offset_ms(utc)).max(0) as u64
+    }
+}
+
+/// A timezone the adapter can't read counts as not reported, rather than failing the handshake
+pub(super) fn lenient<'de, D>(deserializer: D) -> Result<Option<ServerTimezone>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(serde_json::from_value(serde_json::Value::deserialize(deserializer)?).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EET: ServerTimezone = ServerTimezone {
+        utc_offset: 7200,
+        dst: Dst::Eu,
+    };
+    const NY_CLOSE: ServerTimezone = ServerTimezone {
+        utc_offset: 7200,
+        dst: Dst::Us,
+    };
+
+    fn utc(text: &str) -> u64 {
+        DateTime::parse_from_rfc3339(text)
+            .unwrap()
+            .timestamp_millis() as u64
+    }
+
+    #[test]
+    fn eu_dst_moves_the_offset_at_the_transition() {
+        // 2024-03-31 01:00 UTC the server jumps from 03:00 to 04:00
+        let before = utc("2024-03-31T00:59:00Z");
+        let after = utc("2024-03-31T01:00:00Z");
+        assert_eq!(EET.from_utc(before), utc("2024-03-31T02:59:00Z"));
+        assert_eq!(EET.from_utc(after), utc("2024-03-31T04:00:00Z"));
+
+        assert_eq!(EET.to_utc(utc("2024-03-31T02:59:00Z")), before);
+        assert_eq!(EET.to_utc(utc("2024-03-31T04:00:00Z")), after);
+
+        // 2024-10-27 01:00 UTC the server goes back from 04:00 to 03:00
+        let summer = utc("2024-10-27T00:30:00Z");
+        let winter = utc("2024-10-27T01:30:00Z");
+        assert_eq!(EET.from_utc(summer), utc("2024-10-27T03:30:00Z"));
+        assert_eq!(EET.from_utc(winter), utc("2024-10-27T03:30:00Z"));
+        // The repeated hour reads as its first occurrence
+        assert_eq!(EET.to_utc(utc("2024-10-27T03:30:00Z")), summer);
+        assert_eq!(
+            EET.to_utc(utc("2024-10-27T04:30:00Z")),
+            utc("2024-10-27T02:30:00Z")
+        );
+    }
+
+    #[test]
+    fn us_dst_follows_new_york() {
+        // 2024-03-10 02:00 in New York, two weeks before the EU change
+        assert_eq!(
+            NY_CLOSE.to_utc(utc("2024-03-10T08:59:00Z")),
+            utc("2024-03-10T06:59:00Z")
+        );
+        assert_eq!(
+            NY_CLOSE.to_utc(utc("2024-03-10T10:00:00Z")),
+            utc("2024-03-10T07:00:00Z")
+        );
+        assert_eq!(
+            EET.to_utc(utc("2024-03-10T10:00:00Z")),
+            utc("2024-03-10T08:00:00Z")
+        );
+
+        // The New York close at 17:00 EDT is the server's midnight all summer
+        let close = utc("2024-07-15T21:00:00Z");
+        assert_eq!(NY_CLOSE.from_utc(close), utc("2024-07-16T00:00:00Z"));
+        // and at 17:00 EST all winter, after 2024-11-03
+        let close = utc("2024-11-04T22:00:00Z");
+        assert_eq!(NY_CLOSE.from_utc(close), utc("2024-11-05T00:00:00Z"));
+    }
+
+    #[test]
+    fn conversions_round_trip_outside_the_repeated_hour() {
+        let start = utc("2024-01-01T00:00:00Z");
+        for tz in [ServerTimezone::UTC, EET, NY_CLOSE] {
+            for hour in (0..366 * 24).step_by(7) {
+                let time = start + hour * HOUR_MS as u64;
+                assert_eq!(tz.to_utc(tz.from_utc(time)), time, "{tz:?} at {time}");
+            }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+//! Versions of the proxy and the EA behind it, as reported with `auth_response`.
+//!
+//! Mismatched releases mostly break in subtle ways, a field the desktop expects is simply never
+//! sent. Proxies from before versions were reported leave the fields out, and a connection that
+//! authenticates with headers alone has no handshake to report them in, both show as unknown
+//! rather than failing. Only a proxy that reports a version older than [`MIN_PROXY_VERSION`] is
+//! flagged, and only with a warning, most of the adapter still works against it.
+
+use super::timezone::ServerTimezone;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+/// Oldest proxy sending everything the adapter relies on: the server time with `auth_response`,
+/// `shutdown` frames and recorded depth history
+pub const MIN_PROXY_VERSION: Version = Version::new(1, 4, 0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Reads `1.4`, `v1.4.2` or `1.4.2-rc1 (build 77)`, missing parts count as zero and anything
+    /// after the numbers is ignored
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text
+            .strip_prefix(['v', 'V'])
+            .unwrap_or(text)
+            .split(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .next()?;
+
+        let mut parts = text.split('.').map(str::parse::<u32>);
+        let major = parts.next()?.ok()?;
+        let minor = parts.next().and_then(Result::ok).unwrap_or(0);
+        let patch = parts.next().and_then(Result::ok).unwrap_or(0);
+
+        Some(Self::new(major, minor, patch))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// What a proxy reported about itself, versions as sent since builds append their own suffixes
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerInfo {
+    #[serde(default)]
+    pub proxy_version: Option<String>,
+    #[serde(default)]
+    pub ea_version: Option<String>,
+    /// Timezone of the trade server, times are taken as UTC without it
+    #[serde(default)]
+    pub server_timezone: Option<ServerTimezone>,
+    /// Optional requests the proxy answers, e.g. `calendar`, empty for proxies predating the list
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+}
+
+impl ServerInfo {
+    pub fn proxy(&self) -> Option<Version> {
+        self.proxy_version.as_deref().and_then(Version::parse)
+    }
+
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|name| name == capability)
+    }
+
+    /// Whether the proxy reports a version older than the adapter needs
+    pub fn is_outdated(&self) -> bool {
+        self.proxy()
+            .is_some_and(|version| version < MIN_PROXY_VERSION)
+    }
+}
+
+/// A version field sent as a string or a bare number, anything else counts as absent
+pub(super) fn lenient<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+        serde_json::Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    })
+}
+
+/// A list of names, anything else counts as empty
+pub(super) fn lenient_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::Array(values) => values
+            .into_iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect(),
+        _ => vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_parse_leniently() {
+        assert_eq!(Version::parse("1.4.2"), Some(Version::new(1, 4, 2)));
+        assert_eq!(Version::parse("v2.1"), Some(Version::new(2, 1, 0)));
+        assert_eq!(
+            Version::parse(" 1.3.9-rc1 (build 77)"),
+            Some(Version::new(1, 3, 9))
+        );
+        assert_eq!(Version::parse("dev"), None);
+        assert_eq!(Version::parse(""), None);
+
+        assert!(Version::new(1, 3, 9) < MIN_PROXY_VERSION);
+        assert!(Version::new(1, 10, 0) > MIN_PROXY_VERSION);
+    }
+
+    #[test]
+    fn only_a_reported_old_proxy_is_outdated() {
+        let info = |proxy: Option<&str>| ServerInfo {
+            proxy_version: proxy.map(str::to_string),
+            ea_version: None,
+            server_timezone: None,
+            capabilities: vec![],
+        };
+
+        assert!(info(Some("1.2.0")).is_outdated());
+        assert!(!info(Some("1.4.0")).is_outdated());
+        // Legacy proxies and unreadable versions are unknown, not outdated
+        assert!(!info(None).is_outdated());
+        assert!(!info(Some("nightly")).is_outdated());
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    OpenInterest, Price, PushFrequency, SizeUnit,
+    OpenInterest, Price, PushFrequency, SizeUnit, TickMultiplier,
     adapter::{StreamKind, StreamTicksize},
     limiter::{self, RateLimiter},
     volume_size_unit,
@@ -7,14 +7,16 @@ use crate::{
 
 use super::{
     super::{
-        Exchange, Kline, MarketKind, Ticker, TickerInfo, TickerStats, Timeframe, Trade,
+        BarStatus, Exchange, Kline, MarketKind, Ticker, TickerInfo, TickerStats, Timeframe, Trade,
         connect::{State, connect_ws},
         de_string_to_f32, de_string_to_u64, is_symbol_supported,
         limiter::HTTP_CLIENT,
     },
-    AdapterError, Event,
+    AdapterError, AdapterFuture, Capabilities, Event, ExchangeAdapter,
 };
 
+use futures_util::{FutureExt as _, StreamExt as _, stream::BoxStream};
+
 use super::super::depth::{DeOrder, DepthPayload, DepthUpdate, LocalDepthCache};
 
 use fastwebsockets::{Frame, OpCode};
@@ -233,6 +235,7 @@ pub fn connect_market_stream(
                 State::Connected(ws) => match ws.read_frame().await {
                     Ok(msg) => match msg.opcode {
                         OpCode::Text => {
+                            crate::capture::record(ticker, &msg.payload[..]);
                             if let Ok(data) = feed_de(&msg.payload[..], ticker) {
                                 match data {
                                     StreamData::Trade(de_trade_vec) => {
@@ -431,6 +434,9 @@ pub fn connect_kline_stream(
                                             .get(4)
                                             .and_then(|x| x.as_str())
                                             .and_then(|s| s.parse::<f32>().ok());
+                                        // "1" once the candle is final
+                                        let confirmed =
+                                            row.get(8).and_then(|x| x.as_str()) == Some("1");
                                         let volume = row
                                             .get(5)
                                             .and_then(|x| x.as_str())
@@ -476,6 +482,7 @@ pub fn connect_kline_stream(
                                                     timeframe,
                                                 },
                                                 kline,
+                                                BarStatus::from_closed(confirmed),
                                             ))
                                             .await;
                                     }
@@ -856,3 +863,75 @@ pub async fn fetch_historical_oi(
 
     Ok(open_interest)
 }
+
+/// Registered for the OKX spot, linear and inverse markets
+pub struct OkexAdapter;
+
+impl ExchangeAdapter for OkexAdapter {
+    fn exchanges(&self) -> &'static [Exchange] {
+        &[
+            Exchange::OkexLinear,
+            Exchange::OkexInverse,
+            Exchange::OkexSpot,
+        ]
+    }
+
+    fn capabilities(&self, exchange: Exchange) -> Capabilities {
+        Capabilities {
+            native_depth_diffs: true,
+            open_interest: exchange.is_perps(),
+            ticker_stats: true,
+            aggressor_side: true,
+            ..Capabilities::NONE
+        }
+    }
+
+    fn fetch_ticksize(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, Option<TickerInfo>>> {
+        fetch_ticksize(market).boxed()
+    }
+
+    fn fetch_ticker_prices(
+        &self,
+        market: MarketKind,
+    ) -> AdapterFuture<HashMap<Ticker, TickerStats>> {
+        fetch_ticker_prices(market).boxed()
+    }
+
+    fn fetch_klines(
+        &self,
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<Kline>> {
+        fetch_klines(ticker_info, timeframe, range).boxed()
+    }
+
+    fn fetch_open_interest(
+        &self,
+        ticker: Ticker,
+        timeframe: Timeframe,
+        range: Option<(u64, u64)>,
+    ) -> AdapterFuture<Vec<OpenInterest>> {
+        fetch_historical_oi(ticker, range, timeframe).boxed()
+    }
+
+    fn market_stream(
+        &self,
+        ticker_info: TickerInfo,
+        _tick_mltp: Option<TickMultiplier>,
+        push_freq: PushFrequency,
+    ) -> BoxStream<'static, Event> {
+        connect_market_stream(ticker_info, push_freq).boxed()
+    }
+
+    fn kline_stream(
+        &self,
+        subs: Vec<(TickerInfo, Timeframe)>,
+        market: MarketKind,
+    ) -> BoxStream<'static, Event> {
+        connect_kline_stream(subs, market).boxed()
+    }
+}
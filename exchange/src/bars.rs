@@ -0,0 +1,115 @@
+//! Bars built locally from a stream of prices, for feeds that don't stream their own klines
+
+use crate::{BarStatus, Kline, Price, TickerInfo, Timeframe};
+
+/// Builds the bars of a timeframe from a stream of prices
+pub(crate) struct BarBuilder {
+    timeframe: Timeframe,
+    ticker_info: TickerInfo,
+    current: Option<Kline>,
+    /// Bars closed since the last drain
+    closed: Vec<Kline>,
+    changed: bool,
+}
+
+impl BarBuilder {
+    pub(crate) fn new(ticker_info: TickerInfo, timeframe: Timeframe) -> Self {
+        Self {
+            timeframe,
+            ticker_info,
+            current: None,
+            closed: vec![],
+            changed: false,
+        }
+    }
+
+    /// Folds a price in, one past the forming bar closes it. Prices of a bar already closed are
+    /// dropped, a closed bar is final.
+    pub(crate) fn update(&mut self, time: u64, value: f32, volume: (f32, f32)) {
+        let interval = self.timeframe.to_milliseconds();
+        let open_time = time - (time % interval);
+        let price = Price::from_f32(value).round_to_min_tick(self.ticker_info.min_ticksize);
+
+        match &mut self.current {
+            Some(bar) if bar.time == open_time => {
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                bar.volume.0 += volume.0;
+                bar.volume.1 += volume.1;
+            }
+            Some(bar) if bar.time > open_time => return,
+            _ => {
+                let opened = Kline {
+                    time: open_time,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume,
+                };
+                if let Some(closed) = self.current.replace(opened) {
+                    self.closed.push(closed);
+                }
+            }
+        }
+
+        self.changed = true;
+    }
+
+    /// Bars closed since the last drain, then the forming one if it changed since
+    pub(crate) fn drain(&mut self) -> Vec<(Kline, BarStatus)> {
+        let mut bars: Vec<_> = self
+            .closed
+            .drain(..)
+            .map(|kline| (kline, BarStatus::Closed))
+            .collect();
+
+        if std::mem::take(&mut self.changed)
+            && let Some(forming) = self.current
+        {
+            bars.push((forming, BarStatus::Forming));
+        }
+        bars
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ticker;
+    use crate::adapter::Exchange;
+
+    #[test]
+    fn bars_close_once_with_their_volume() {
+        let mut bar = BarBuilder::new(
+            TickerInfo::new(
+                Ticker::new("EURUSD", Exchange::MetaTrader5),
+                0.00001,
+                0.01,
+                None,
+            ),
+            Timeframe::M1,
+        );
+        bar.update(1_000, 1.0, (2.0, 0.0));
+        bar.update(30_000, 1.2, (0.0, 1.0));
+
+        let forming = bar.drain();
+        assert_eq!(forming.len(), 1);
+        assert_eq!(forming[0].1, BarStatus::Forming);
+        assert_eq!(forming[0].0.volume, (2.0, 1.0));
+        assert!(bar.drain().is_empty(), "nothing changed since");
+
+        // One batch runs past the bar, it closes with every trade counted once
+        bar.update(50_000, 1.1, (1.0, 0.0));
+        bar.update(61_000, 1.3, (0.5, 0.0));
+        // Late for a bar already closed
+        bar.update(59_000, 0.9, (4.0, 0.0));
+
+        let bars = bar.drain();
+        let statuses: Vec<_> = bars.iter().map(|(_, status)| *status).collect();
+        assert_eq!(statuses, [BarStatus::Closed, BarStatus::Forming]);
+        assert_eq!((bars[0].0.time, bars[0].0.volume), (0, (3.0, 1.0)));
+        assert_eq!((bars[1].0.time, bars[1].0.volume), (60_000, (0.5, 0.0)));
+    }
+}
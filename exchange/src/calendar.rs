@@ -0,0 +1,130 @@
+//! Economic calendar events, as forwarded by MT5 terminals from their built-in calendar.
+//!
+//! Events belong to a currency rather than a symbol, a symbol shows those of the currencies
+//! spelled out by its name, so EURUSD gets both the ECB and the FOMC. Times are unix ms in UTC
+//! once they leave the adapter.
+
+use crate::Ticker;
+use crate::adapter::metatrader5::suffix;
+
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize)]
+pub enum Importance {
+    #[default]
+    Low,
+    Medium,
+    High,
+}
+
+impl Importance {
+    pub const ALL: [Importance; 3] = [Importance::Low, Importance::Medium, Importance::High];
+}
+
+impl fmt::Display for Importance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Importance::Low => write!(f, "Low"),
+            Importance::Medium => write!(f, "Medium"),
+            Importance::High => write!(f, "High"),
+        }
+    }
+}
+
+/// MT5's `CALENDAR_IMPORTANCE_*` as a number from 0 to 3, or its name in either case. Events
+/// of no importance are taken as low.
+impl<'de> Deserialize<'de> for Importance {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::Number(number) => match number.as_u64() {
+                Some(3) => Importance::High,
+                Some(2) => Importance::Medium,
+                _ => Importance::Low,
+            },
+            serde_json::Value::String(name) => match name.trim().to_ascii_lowercase().as_str() {
+                "high" => Importance::High,
+                "medium" | "moderate" => Importance::Medium,
+                _ => Importance::Low,
+            },
+            _ => Importance::Low,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    /// Unix ms in UTC
+    pub time: u64,
+    /// ISO code, e.g. `USD`
+    pub currency: String,
+    #[serde(default)]
+    pub importance: Importance,
+    pub title: String,
+    /// Values as the calendar formats them, e.g. `3.2%` or `215K`, `None` until released
+    #[serde(default, deserialize_with = "lenient_value")]
+    pub actual: Option<String>,
+    #[serde(default, deserialize_with = "lenient_value")]
+    pub forecast: Option<String>,
+    #[serde(default, deserialize_with = "lenient_value")]
+    pub previous: Option<String>,
+}
+
+/// A value sent as text or a bare number, anything else counts as not released
+fn lenient_value<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(text) if !text.trim().is_empty() => Some(text.trim().to_string()),
+        serde_json::Value::Number(number) => Some(number.to_string()),
+        _ => None,
+    })
+}
+
+/// Currencies whose events concern `ticker`, the base and quote of FX and metals names like
+/// `EURJPY.a` or `XAUUSD`, none for names such as index CFDs that don't carry them
+pub fn currencies(ticker: &Ticker) -> Vec<String> {
+    let name = suffix::normalize(&ticker.to_string());
+    if name.len() == 6 && name.chars().all(|c| c.is_ascii_alphabetic()) {
+        vec![name[..3].to_string(), name[3..].to_string()]
+    } else {
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Exchange;
+
+    #[test]
+    fn fx_and_metals_names_give_both_currencies() {
+        let currencies_of = |name| currencies(&Ticker::new(name, Exchange::MetaTrader5));
+
+        assert_eq!(currencies_of("EURUSD"), ["EUR", "USD"]);
+        assert_eq!(currencies_of("GBPJPY.a"), ["GBP", "JPY"]);
+        assert_eq!(currencies_of("XAUUSD"), ["XAU", "USD"]);
+        assert!(currencies_of("US30").is_empty());
+    }
+
+    #[test]
+    fn events_parse_leniently() {
+        let event: CalendarEvent = serde_json::from_str(
+            r#"{"time":1704459600000,"currency":"USD","importance":3,"title":"Nonfarm Payrolls","actual":"216K","forecast":170,"previous":null}"#,
+        )
+        .unwrap();
+
+        assert_eq!(event.importance, Importance::High);
+        assert_eq!(event.actual.as_deref(), Some("216K"));
+        assert_eq!(event.forecast.as_deref(), Some("170"));
+        assert_eq!(event.previous, None);
+
+        let named: CalendarEvent = serde_json::from_str(
+            r#"{"time":0,"currency":"EUR","importance":"Moderate","title":"ZEW","actual":""}"#,
+        )
+        .unwrap();
+        assert_eq!(named.importance, Importance::Medium);
+        assert_eq!(named.actual, None);
+    }
+}
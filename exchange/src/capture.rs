@@ -0,0 +1,103 @@
+//! Opt-in ring buffers of the raw messages each market stream received, for book dumps.
+//!
+//! Capture is off by default and switched per venue, keeping the last [`CAPTURE_CAPACITY`]
+//! messages of every stream costs memory. While it's off, recording is one atomic load.
+
+use crate::Ticker;
+use crate::adapter::ExchangeInclusive;
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{LazyLock, Mutex};
+
+/// Raw messages kept per stream while capture is on
+pub const CAPTURE_CAPACITY: usize = 256;
+
+/// One bit per venue, see [`venue_bit`]
+static ENABLED: AtomicU32 = AtomicU32::new(0);
+
+static BUFFERS: LazyLock<Mutex<FxHashMap<Ticker, VecDeque<RawMessage>>>> =
+    LazyLock::new(|| Mutex::new(FxHashMap::default()));
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RawMessage {
+    /// Local unix time in milliseconds
+    pub received_at: u64,
+    pub text: String,
+}
+
+fn venue_bit(venue: ExchangeInclusive) -> u32 {
+    1 << venue as u32
+}
+
+pub fn is_enabled(venue: ExchangeInclusive) -> bool {
+    ENABLED.load(Ordering::Relaxed) & venue_bit(venue) != 0
+}
+
+/// Turns capture on or off for every stream of `venue`, turning it off frees what was kept
+pub fn set_enabled(venue: ExchangeInclusive, enabled: bool) {
+    if enabled {
+        ENABLED.fetch_or(venue_bit(venue), Ordering::Relaxed);
+    } else {
+        ENABLED.fetch_and(!venue_bit(venue), Ordering::Relaxed);
+
+        if let Ok(mut buffers) = BUFFERS.lock() {
+            buffers.retain(|ticker, _| ExchangeInclusive::of(ticker.exchange) != venue);
+        }
+    }
+}
+
+/// Keeps `payload` as the newest raw message of `ticker`'s stream if its venue captures
+pub fn record(ticker: Ticker, payload: &[u8]) {
+    if !is_enabled(ExchangeInclusive::of(ticker.exchange)) {
+        return;
+    }
+
+    let message = RawMessage {
+        received_at: chrono::Utc::now().timestamp_millis() as u64,
+        text: String::from_utf8_lossy(payload).into_owned(),
+    };
+
+    if let Ok(mut buffers) = BUFFERS.lock() {
+        let buffer = buffers.entry(ticker).or_default();
+        if buffer.len() == CAPTURE_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(message);
+    }
+}
+
+/// Captured messages of `ticker`'s stream, oldest first
+pub fn recent(ticker: &Ticker) -> Vec<RawMessage> {
+    BUFFERS
+        .lock()
+        .ok()
+        .and_then(|buffers| buffers.get(ticker).map(|b| b.iter().cloned().collect()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Exchange;
+
+    #[test]
+    fn records_only_while_enabled_and_keeps_the_newest() {
+        let ticker = Ticker::new("BTCUSDT", Exchange::OkexLinear);
+        record(ticker, b"dropped");
+        assert!(recent(&ticker).is_empty());
+
+        set_enabled(ExchangeInclusive::Okex, true);
+        for i in 0..CAPTURE_CAPACITY + 3 {
+            record(ticker, format!("message {i}").as_bytes());
+        }
+        let kept = recent(&ticker);
+        assert_eq!(kept.len(), CAPTURE_CAPACITY);
+        assert_eq!(kept[0].text, "message 3");
+
+        set_enabled(ExchangeInclusive::Okex, false);
+        assert!(recent(&ticker).is_empty());
+    }
+}
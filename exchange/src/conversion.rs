@@ -0,0 +1,418 @@
+//! Lot quantities of CFD venues shown as currency value.
+//!
+//! A lot is `contract_size` units of the instrument, so its quote currency value is
+//! `lots * contract_size * price`. Reaching the account currency from a cross takes one more
+//! rate, read from the last price of a subscribed reference symbol, e.g. USDJPY for a JPY quoted
+//! pair on a USD account, or two through USD when the broker lists no pair of the two
+//! currencies. Without a contract size quantities stay in raw lots.
+//!
+//! Streams deliver sizes in the app wide [`SizeUnit`], [`SizeDisplay`] shows them in the unit a
+//! pane picked instead.
+
+use crate::adapter::metatrader5::{self, suffix};
+use crate::adapter::{Exchange, MarketKind};
+use crate::{SizeUnit, TickerInfo};
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Last traded price per normalized symbol name, fed by the live streams
+static REFERENCE_PRICES: LazyLock<RwLock<HashMap<String, f32>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Records the latest price of a streamed symbol so crosses can convert through it
+pub fn record_reference_price(symbol: &str, price: f32) {
+    if price > 0.0
+        && let Ok(mut prices) = REFERENCE_PRICES.write()
+    {
+        prices.insert(suffix::normalize(symbol), price);
+    }
+}
+
+pub(crate) fn reference_price(symbol: &str) -> Option<f32> {
+    REFERENCE_PRICES.read().ok()?.get(symbol).copied()
+}
+
+/// Quote currency spelled out by FX and metals names like `EURJPY.a` or `XAUUSD`,
+/// `None` for names such as index CFDs that don't carry one
+pub fn quote_currency(symbol: &str) -> Option<String> {
+    let name = suffix::normalize(symbol);
+    (name.len() == 6 && name.chars().all(|c| c.is_ascii_alphabetic())).then(|| name[3..].into())
+}
+
+/// Rate converting `quote` into `account` currency, from the direct or the inverted pair, or
+/// through USD
+pub fn cross_rate(
+    quote: &str,
+    account: &str,
+    price_of: impl Fn(&str) -> Option<f32>,
+) -> Option<f32> {
+    Route::resolve(quote, account, |symbol| price_of(symbol).is_some())?
+        .rate(|symbol| price_of(symbol).map(f64::from))
+        .map(|rate| rate as f32)
+}
+
+/// Currency crosses are taken through when no pair links two currencies directly
+const PIVOT: &str = "USD";
+
+/// One step of a [`Route`], the price of `symbol` or its inverse
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leg {
+    /// Normalized symbol name, e.g. `GBPUSD`
+    pub symbol: String,
+    pub inverse: bool,
+}
+
+impl Leg {
+    fn between(from: &str, to: &str, listed: &impl Fn(&str) -> bool) -> Option<Self> {
+        let (direct, inverse) = (format!("{from}{to}"), format!("{to}{from}"));
+        if listed(&direct) {
+            Some(Self {
+                symbol: direct,
+                inverse: false,
+            })
+        } else if listed(&inverse) {
+            Some(Self {
+                symbol: inverse,
+                inverse: true,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// The symbols whose prices convert one currency into another, empty for the same currency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub legs: Vec<Leg>,
+}
+
+impl Route {
+    /// Route from `quote` to `account` over the symbols `listed` says exist, the direct or
+    /// inverted pair first, then through USD
+    pub fn resolve(quote: &str, account: &str, listed: impl Fn(&str) -> bool) -> Option<Self> {
+        let (quote, account) = (quote.trim().to_uppercase(), account.trim().to_uppercase());
+        if quote.is_empty() || account.is_empty() {
+            return None;
+        }
+        if quote == account {
+            return Some(Self { legs: vec![] });
+        }
+
+        if let Some(leg) = Leg::between(&quote, &account, &listed) {
+            return Some(Self { legs: vec![leg] });
+        }
+
+        if quote == PIVOT || account == PIVOT {
+            return None;
+        }
+        Some(Self {
+            legs: vec![
+                Leg::between(&quote, PIVOT, &listed)?,
+                Leg::between(PIVOT, &account, &listed)?,
+            ],
+        })
+    }
+
+    /// Product of the legs' rates, `None` while a price is missing
+    pub fn rate(&self, price_of: impl Fn(&str) -> Option<f64>) -> Option<f64> {
+        self.legs
+            .iter()
+            .try_fold(1.0, |rate, leg| {
+                let price = price_of(&leg.symbol)?;
+                Some(if leg.inverse {
+                    rate / price
+                } else {
+                    rate * price
+                })
+            })
+            .filter(|rate| rate.is_finite() && *rate > 0.0)
+    }
+}
+
+/// Converts lot quantities of one symbol for display
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LotConverter {
+    contract_size: Option<f32>,
+    /// Quote to account currency, `None` keeps values in the quote currency
+    account_rate: Option<f32>,
+}
+
+impl LotConverter {
+    pub fn new(contract_size: Option<f32>, account_rate: Option<f32>) -> Self {
+        Self {
+            contract_size: contract_size.filter(|size| *size > 0.0),
+            account_rate,
+        }
+    }
+
+    /// Converter for `ticker_info` into `account_currency` with the recorded reference prices,
+    /// staying in the quote currency when no rate is known or no account currency is set
+    pub fn for_ticker(ticker_info: &TickerInfo, account_currency: Option<&str>) -> Self {
+        let account_rate = account_currency
+            .filter(|ccy| !ccy.trim().is_empty())
+            .zip(quote_currency(&ticker_info.ticker.to_string()))
+            .and_then(|(account, quote)| cross_rate(&quote, account.trim(), reference_price));
+
+        Self::new(ticker_info.contract_size.map(f32::from), account_rate)
+    }
+
+    pub fn quote_value(&self, lots: f32, price: f32) -> Option<f32> {
+        self.contract_size.map(|size| lots * size * price)
+    }
+
+    pub fn account_value(&self, lots: f32, price: f32) -> Option<f32> {
+        Some(self.quote_value(lots, price)? * self.account_rate?)
+    }
+
+    /// Account currency value when a rate is known, quote currency value otherwise,
+    /// `lots` unchanged when the contract size is unknown
+    pub fn convert(&self, lots: f32, price: f32) -> f32 {
+        self.account_value(lots, price)
+            .or_else(|| self.quote_value(lots, price))
+            .unwrap_or(lots)
+    }
+
+    /// Lots worth `value`, the inverse of [`Self::convert`]
+    pub fn lots(&self, value: f32, price: f32) -> f32 {
+        let per_lot = self.convert(1.0, price);
+        if self.contract_size.is_some() && per_lot > 0.0 {
+            value / per_lot
+        } else {
+            value
+        }
+    }
+}
+
+/// Sizes of one ticker as streamed, in the app wide unit, converted into the unit a pane shows.
+/// A lot is the base unit and the contract of MT5 symbols, crypto contracts are counted with
+/// the listed contract size. Inverse perpetuals are always shown as streamed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeDisplay {
+    streamed: SizeUnit,
+    shown: SizeUnit,
+    market: MarketKind,
+    contract_size: Option<f32>,
+    /// Lot values of MT5 symbols, in the account currency like the adapter converts them
+    lots: Option<LotConverter>,
+}
+
+impl SizeDisplay {
+    /// Shows sizes of `ticker_info` in `unit_override`, or in `app_unit` they're streamed in
+    pub fn new(
+        ticker_info: &TickerInfo,
+        unit_override: Option<SizeUnit>,
+        app_unit: SizeUnit,
+    ) -> Self {
+        let lots = (ticker_info.exchange() == Exchange::MetaTrader5).then(|| {
+            let account = metatrader5::get_global_config().map(|config| config.account_currency);
+            LotConverter::for_ticker(ticker_info, account.as_deref())
+        });
+
+        Self {
+            streamed: app_unit,
+            shown: unit_override.unwrap_or(app_unit),
+            market: ticker_info.market_type(),
+            contract_size: ticker_info
+                .contract_size
+                .map(f32::from)
+                .filter(|size| *size > 0.0),
+            lots,
+        }
+    }
+
+    pub fn unit(&self) -> SizeUnit {
+        self.shown
+    }
+
+    /// Whether sizes show as streamed
+    pub fn is_identity(&self) -> bool {
+        self.streamed == self.shown || self.market == MarketKind::InversePerps
+    }
+
+    /// `qty` as streamed, traded at `price`, in the shown unit
+    pub fn qty(&self, qty: f32, price: f32) -> f32 {
+        if self.is_identity() {
+            return qty;
+        }
+
+        let base = match (self.streamed, self.lots) {
+            (SizeUnit::Quote, Some(lots)) => lots.lots(qty, price),
+            (SizeUnit::Quote, None) if price > 0.0 => qty / price,
+            _ => qty,
+        };
+
+        match (self.shown, self.lots) {
+            (SizeUnit::Base, _) | (SizeUnit::Contracts, Some(_)) => base,
+            (SizeUnit::Quote, Some(lots)) => lots.convert(base, price),
+            (SizeUnit::Quote, None) => base * price,
+            (SizeUnit::Contracts, None) => self.contract_size.map_or(base, |size| base / size),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prices(table: &[(&str, f32)]) -> impl Fn(&str) -> Option<f32> {
+        let table: HashMap<String, f32> = table.iter().map(|(s, p)| (s.to_string(), *p)).collect();
+        move |symbol| table.get(symbol).copied()
+    }
+
+    #[test]
+    fn jpy_pair_converts_through_the_inverted_reference() {
+        assert_eq!(quote_currency("USDJPY.a").as_deref(), Some("JPY"));
+
+        let rate = cross_rate("JPY", "USD", prices(&[("USDJPY", 150.0)]));
+        let converter = LotConverter::new(Some(100_000.0), rate);
+
+        assert_eq!(converter.quote_value(1.0, 150.0), Some(15_000_000.0));
+        assert!((converter.convert(1.0, 150.0) - 100_000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn index_cfd_stays_in_quote_currency() {
+        assert_eq!(quote_currency("US30"), None);
+        assert_eq!(quote_currency("GER40.cash"), None);
+
+        let converter = LotConverter::new(Some(1.0), None);
+        assert_eq!(converter.account_value(0.5, 38_000.0), None);
+        assert_eq!(converter.convert(0.5, 38_000.0), 19_000.0);
+    }
+
+    #[test]
+    fn metals_convert_into_the_account_currency() {
+        assert_eq!(quote_currency("XAUUSDm").as_deref(), Some("USD"));
+
+        let rate = cross_rate("USD", "EUR", prices(&[("EURUSD", 1.25)]));
+        let converter = LotConverter::new(Some(100.0), rate);
+
+        assert_eq!(converter.quote_value(0.1, 2_000.0), Some(20_000.0));
+        assert_eq!(converter.convert(0.1, 2_000.0), 16_000.0);
+
+        let same = cross_rate("usd", "USD", prices(&[]));
+        assert_eq!(same, Some(1.0));
+    }
+
+    #[test]
+    fn routes_go_direct_inverse_or_through_usd() {
+        let listed = |symbol: &str| ["GBPUSD", "USDJPY", "EURGBP"].contains(&symbol);
+
+        let direct = Route::resolve("GBP", "USD", listed).unwrap();
+        assert_eq!(direct.legs.len(), 1);
+        assert!(!direct.legs[0].inverse);
+
+        let inverse = Route::resolve("JPY", "USD", listed).unwrap();
+        assert_eq!(inverse.legs[0].symbol, "USDJPY");
+        assert!(inverse.legs[0].inverse);
+
+        // GBP to JPY without GBPJPY takes GBPUSD then USDJPY
+        let cross = Route::resolve("gbp", "jpy", listed).unwrap();
+        assert_eq!(
+            cross
+                .legs
+                .iter()
+                .map(|leg| leg.symbol.as_str())
+                .collect::<Vec<_>>(),
+            ["GBPUSD", "USDJPY"]
+        );
+        let rate = cross.rate(|symbol| match symbol {
+            "GBPUSD" => Some(1.25),
+            "USDJPY" => Some(150.0),
+            _ => None,
+        });
+        assert_eq!(rate, Some(187.5));
+        assert_eq!(cross.rate(|_| None), None);
+
+        assert_eq!(Route::resolve("CHF", "USD", listed), None);
+        assert_eq!(Route::resolve("CHF", "CHF", listed).unwrap().legs, []);
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() <= b.abs() * 1e-4
+    }
+
+    #[test]
+    fn mt5_cfd_sizes_in_every_unit() {
+        use crate::Ticker;
+
+        // A lot of XAUUSD is 100 ounces, quoted in USD
+        let xau = TickerInfo::new(
+            Ticker::new("XAUUSD", Exchange::MetaTrader5),
+            0.01,
+            0.01,
+            Some(100.0),
+        );
+        let price = 2_000.0;
+
+        let from_lots = |unit| SizeDisplay::new(&xau, unit, SizeUnit::Base).qty(0.5, price);
+        assert_eq!(from_lots(None), 0.5);
+        assert_eq!(from_lots(Some(SizeUnit::Base)), 0.5);
+        assert_eq!(from_lots(Some(SizeUnit::Contracts)), 0.5);
+        assert!(close(from_lots(Some(SizeUnit::Quote)), 100_000.0));
+
+        let from_value = |unit| SizeDisplay::new(&xau, unit, SizeUnit::Quote).qty(100_000.0, price);
+        assert_eq!(from_value(None), 100_000.0);
+        assert!(close(from_value(Some(SizeUnit::Base)), 0.5));
+        assert!(close(from_value(Some(SizeUnit::Contracts)), 0.5));
+    }
+
+    #[test]
+    fn crypto_sizes_in_every_unit() {
+        use crate::Ticker;
+
+        // OKX lists BTC perpetuals in contracts of 0.01 BTC, streamed sizes are already in BTC
+        let btc = TickerInfo::new(
+            Ticker::new("BTC-USDT-SWAP", Exchange::OkexLinear),
+            0.1,
+            0.01,
+            Some(0.01),
+        );
+        let price = 50_000.0;
+
+        let from_coins = |unit| SizeDisplay::new(&btc, unit, SizeUnit::Base).qty(2.0, price);
+        assert_eq!(from_coins(None), 2.0);
+        assert_eq!(from_coins(Some(SizeUnit::Quote)), 100_000.0);
+        assert!(close(from_coins(Some(SizeUnit::Contracts)), 200.0));
+
+        let from_value = |unit| SizeDisplay::new(&btc, unit, SizeUnit::Quote).qty(100_000.0, price);
+        assert_eq!(from_value(Some(SizeUnit::Base)), 2.0);
+        assert!(close(from_value(Some(SizeUnit::Contracts)), 200.0));
+        assert_eq!(from_value(Some(SizeUnit::Quote)), 100_000.0);
+
+        // Without a contract size a coin counts as one contract
+        let spot = TickerInfo::new(
+            Ticker::new("BTCUSDT", Exchange::BinanceSpot),
+            0.01,
+            0.0001,
+            None,
+        );
+        let display = SizeDisplay::new(&spot, Some(SizeUnit::Contracts), SizeUnit::Base);
+        assert_eq!(display.qty(2.0, price), 2.0);
+    }
+
+    #[test]
+    fn inverse_perps_show_as_streamed() {
+        use crate::Ticker;
+
+        let inverse = TickerInfo::new(
+            Ticker::new("BTCUSD_PERP", Exchange::BinanceInverse),
+            0.1,
+            1.0,
+            Some(100.0),
+        );
+        let display = SizeDisplay::new(&inverse, Some(SizeUnit::Base), SizeUnit::Quote);
+        assert!(display.is_identity());
+        assert_eq!(display.qty(500.0, 50_000.0), 500.0);
+    }
+
+    #[test]
+    fn unknown_contract_size_falls_back_to_lots() {
+        let converter = LotConverter::new(None, Some(2.0));
+        assert_eq!(converter.quote_value(3.0, 1.1), None);
+        assert_eq!(converter.convert(3.0, 1.1), 3.0);
+        assert_eq!(converter.lots(3.0, 1.1), 3.0);
+    }
+}
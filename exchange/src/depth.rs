@@ -1,12 +1,20 @@
-use crate::{MinTicksize, Price};
+use crate::Price;
+use crate::tick_rule::{TickRule, TickTiers};
+use crate::util::PriceStep;
 
 use serde::Deserializer;
 use serde::de::Error as SerdeError;
 use serde_json::Value;
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy)]
 pub struct DeOrder {
     pub price: f32,
     pub qty: f32,
@@ -51,6 +59,7 @@ struct Order {
     qty: f32,
 }
 
+#[derive(Debug, Clone)]
 pub struct DepthPayload {
     pub last_update_id: u64,
     pub time: u64,
@@ -67,6 +76,58 @@ pub enum DepthUpdate {
 pub struct Depth {
     pub bids: BTreeMap<Price, f32>,
     pub asks: BTreeMap<Price, f32>,
+    /// What the latest update did to the levels above
+    pub changes: DepthChanges,
+    /// Changes with every update of a [`LocalDepthCache`], unique across books. Zero for a book
+    /// built some other way, which is never assumed unchanged.
+    pub version: u64,
+}
+
+/// Versions handed out to depth updates, zero is left for unversioned books
+static NEXT_VERSION: AtomicU64 = AtomicU64::new(1);
+
+/// Signed size change of each level an update touched, positive where liquidity was added and
+/// negative where it was pulled or filled
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DepthChanges {
+    pub bids: Vec<(Price, f32)>,
+    pub asks: Vec<(Price, f32)>,
+}
+
+impl DepthChanges {
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    /// Folds in the changes of an earlier update that's delivered along with these
+    pub fn merge(&mut self, earlier: &DepthChanges) {
+        self.bids.extend_from_slice(&earlier.bids);
+        self.asks.extend_from_slice(&earlier.asks);
+    }
+
+    fn clear(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+    }
+
+    /// Changes between two states of one side
+    fn compare(
+        old: &BTreeMap<Price, f32>,
+        new: &BTreeMap<Price, f32>,
+        out: &mut Vec<(Price, f32)>,
+    ) {
+        for (price, qty) in new {
+            let change = qty - old.get(price).copied().unwrap_or(0.0);
+            if change != 0.0 {
+                out.push((*price, change));
+            }
+        }
+        for (price, qty) in old {
+            if !new.contains_key(price) {
+                out.push((*price, -qty));
+            }
+        }
+    }
 }
 
 impl std::fmt::Debug for Depth {
@@ -74,56 +135,88 @@ impl std::fmt::Debug for Depth {
         f.debug_struct("Depth")
             .field("bids", &self.bids.len())
             .field("asks", &self.asks.len())
+            .field(
+                "changes",
+                &(self.changes.bids.len() + self.changes.asks.len()),
+            )
+            .field("version", &self.version)
             .finish()
     }
 }
 
 impl Depth {
-    fn update(&mut self, diff: &DepthPayload, min_ticksize: MinTicksize) {
-        Self::diff_price_levels(&mut self.bids, &diff.bids, min_ticksize);
-        Self::diff_price_levels(&mut self.asks, &diff.asks, min_ticksize);
+    fn update(&mut self, diff: &DepthPayload, tick_rule: TickRule) {
+        self.changes.clear();
+        Self::diff_price_levels(
+            &mut self.bids,
+            &diff.bids,
+            tick_rule,
+            &mut self.changes.bids,
+        );
+        Self::diff_price_levels(
+            &mut self.asks,
+            &diff.asks,
+            tick_rule,
+            &mut self.changes.asks,
+        );
     }
 
     fn diff_price_levels(
         price_map: &mut BTreeMap<Price, f32>,
         orders: &[DeOrder],
-        min_ticksize: MinTicksize,
+        tick_rule: TickRule,
+        changes: &mut Vec<(Price, f32)>,
     ) {
         orders.iter().for_each(|order| {
             let order = Order {
-                price: Price::from_f32(order.price).round_to_min_tick(min_ticksize),
+                price: tick_rule.round(Price::from_f32(order.price)),
                 qty: order.qty,
             };
 
-            if order.qty == 0.0 {
-                price_map.remove(&order.price);
+            let previous = if order.qty == 0.0 {
+                price_map.remove(&order.price)
             } else {
-                price_map.insert(order.price, order.qty);
+                price_map.insert(order.price, order.qty)
+            };
+
+            let change = order.qty - previous.unwrap_or(0.0);
+            if change != 0.0 {
+                changes.push((order.price, change));
             }
         });
     }
 
-    fn replace_all(&mut self, snapshot: &DepthPayload, min_ticksize: MinTicksize) {
-        self.bids = snapshot
+    /// A snapshot over an empty book is where the stream starts rather than liquidity being
+    /// added, so it leaves no changes
+    fn replace_all(&mut self, snapshot: &DepthPayload, tick_rule: TickRule) {
+        let bids = snapshot
             .bids
             .iter()
             .map(|de_order| {
                 (
-                    Price::from_f32(de_order.price).round_to_min_tick(min_ticksize),
+                    tick_rule.round(Price::from_f32(de_order.price)),
                     de_order.qty,
                 )
             })
             .collect::<BTreeMap<Price, f32>>();
-        self.asks = snapshot
+        let asks = snapshot
             .asks
             .iter()
             .map(|de_order| {
                 (
-                    Price::from_f32(de_order.price).round_to_min_tick(min_ticksize),
+                    tick_rule.round(Price::from_f32(de_order.price)),
                     de_order.qty,
                 )
             })
             .collect::<BTreeMap<Price, f32>>();
+
+        self.changes.clear();
+        if !self.bids.is_empty() || !self.asks.is_empty() {
+            DepthChanges::compare(&self.bids, &bids, &mut self.changes.bids);
+            DepthChanges::compare(&self.asks, &asks, &mut self.changes.asks);
+        }
+        self.bids = bids;
+        self.asks = asks;
     }
 
     pub fn mid_price(&self) -> Option<Price> {
@@ -142,22 +235,290 @@ pub struct LocalDepthCache {
 }
 
 impl LocalDepthCache {
-    pub fn update(&mut self, new_depth: DepthUpdate, min_ticksize: MinTicksize) {
+    pub fn update(&mut self, new_depth: DepthUpdate, tick_rule: impl Into<TickRule>) {
+        let tick_rule = tick_rule.into();
         match new_depth {
             DepthUpdate::Snapshot(snapshot) => {
                 self.last_update_id = snapshot.last_update_id;
                 self.time = snapshot.time;
 
                 let depth = Arc::make_mut(&mut self.depth);
-                depth.replace_all(&snapshot, min_ticksize);
+                depth.replace_all(&snapshot, tick_rule);
+                depth.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
             }
             DepthUpdate::Diff(diff) => {
                 self.last_update_id = diff.last_update_id;
                 self.time = diff.time;
 
                 let depth = Arc::make_mut(&mut self.depth);
-                depth.update(&diff, min_ticksize);
+                depth.update(&diff, tick_rule);
+                depth.version = NEXT_VERSION.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+/// A book re-bucketed into a pane's own price step. Panes of one symbol share the stream's book
+/// and each keeps a view of it at its grouping, regrouped only when the book has changed since.
+#[derive(Debug, Clone, Default)]
+pub struct GroupedDepthView {
+    step: PriceStep,
+    /// Ticks of a symbol quoted in tiers, levels never group finer than their tier's
+    tiers: Option<TickTiers>,
+    /// Version of the book the levels were grouped from
+    grouped: Option<u64>,
+    bids: BTreeMap<Price, f32>,
+    asks: BTreeMap<Price, f32>,
+}
+
+impl GroupedDepthView {
+    pub fn new(step: PriceStep) -> Self {
+        Self {
+            step,
+            ..Self::default()
+        }
+    }
+
+    pub fn step(&self) -> PriceStep {
+        self.step
+    }
+
+    pub fn set_step(&mut self, step: PriceStep) {
+        if self.step != step {
+            self.step = step;
+            self.grouped = None;
+        }
+    }
+
+    pub fn set_tiers(&mut self, tiers: Option<TickTiers>) {
+        if self.tiers != tiers {
+            self.tiers = tiers;
+            self.grouped = None;
+        }
+    }
+
+    /// Regroups `depth` unless it's the version already grouped, returns whether it did
+    pub fn update(&mut self, depth: &Depth) -> bool {
+        if depth.version != 0 && self.grouped == Some(depth.version) {
+            return false;
+        }
+
+        Self::regroup(&depth.bids, true, self.step, self.tiers, &mut self.bids);
+        Self::regroup(&depth.asks, false, self.step, self.tiers, &mut self.asks);
+        self.grouped = Some(depth.version);
+        true
+    }
+
+    /// Bids round down and asks up, so a grouped level never crosses the spread
+    fn regroup(
+        levels: &BTreeMap<Price, f32>,
+        is_bid: bool,
+        step: PriceStep,
+        tiers: Option<TickTiers>,
+        out: &mut BTreeMap<Price, f32>,
+    ) {
+        out.clear();
+
+        // Rounding keeps the order, so each bucket is one run of consecutive levels
+        let mut run: Option<(Price, f32)> = None;
+        for (price, qty) in levels {
+            let grouped = match tiers {
+                Some(tiers) => TickRule::Tiered(tiers).group(*price, is_bid, step),
+                None => price.round_to_side_step(is_bid, step),
+            };
+            match &mut run {
+                Some((price, total)) if *price == grouped => *total += qty,
+                _ => {
+                    if let Some((price, total)) = run.replace((grouped, *qty)) {
+                        out.insert(price, total);
+                    }
+                }
             }
         }
+        if let Some((price, total)) = run {
+            out.insert(price, total);
+        }
+    }
+
+    pub fn bids(&self) -> &BTreeMap<Price, f32> {
+        &self.bids
+    }
+
+    pub fn asks(&self) -> &BTreeMap<Price, f32> {
+        &self.asks
+    }
+
+    pub fn best_bid(&self) -> Option<Price> {
+        self.bids.last_key_value().map(|(price, _)| *price)
+    }
+
+    pub fn best_ask(&self) -> Option<Price> {
+        self.asks.first_key_value().map(|(price, _)| *price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MinTicksize;
+
+    fn payload(bids: &[(f32, f32)], asks: &[(f32, f32)]) -> DepthPayload {
+        let orders = |levels: &[(f32, f32)]| {
+            levels
+                .iter()
+                .map(|&(price, qty)| DeOrder { price, qty })
+                .collect()
+        };
+        DepthPayload {
+            last_update_id: 0,
+            time: 0,
+            bids: orders(bids),
+            asks: orders(asks),
+        }
+    }
+
+    const TICK: f32 = 0.1;
+
+    fn sorted(mut changes: Vec<(Price, f32)>) -> Vec<(Price, f32)> {
+        changes.sort_by_key(|(price, _)| *price);
+        changes
+    }
+
+    fn level(price: f32, qty: f32) -> (Price, f32) {
+        (
+            Price::from_f32(price).round_to_min_tick(MinTicksize::from(TICK)),
+            qty,
+        )
+    }
+
+    #[test]
+    fn views_regroup_once_per_book_version() {
+        let tick = MinTicksize::from(TICK);
+        let mut cache = LocalDepthCache::default();
+        cache.update(
+            DepthUpdate::Snapshot(payload(
+                &[(99.9, 1.0), (99.6, 2.0), (99.4, 3.0)],
+                &[(100.1, 4.0), (100.4, 5.0), (100.6, 6.0)],
+            )),
+            tick,
+        );
+
+        let mut coarse = GroupedDepthView::new(PriceStep::from_f32(0.5));
+        let mut fine = GroupedDepthView::new(PriceStep::from_f32(0.1));
+        assert!(coarse.update(&cache.depth));
+        assert!(fine.update(&cache.depth));
+
+        assert_eq!(
+            coarse.bids().clone().into_iter().collect::<Vec<_>>(),
+            [level(99.0, 3.0), level(99.5, 3.0)]
+        );
+        assert_eq!(
+            coarse.asks().clone().into_iter().collect::<Vec<_>>(),
+            [level(100.5, 9.0), level(101.0, 6.0)]
+        );
+        assert_eq!(fine.bids().len(), 3);
+        assert_eq!(coarse.best_bid(), Some(level(99.5, 0.0).0));
+
+        // Same book, nothing to do
+        assert!(!coarse.update(&cache.depth));
+
+        // Regrouping one view leaves the other as it was
+        coarse.set_step(PriceStep::from_f32(1.0));
+        assert!(coarse.update(&cache.depth));
+        assert_eq!(coarse.bids().len(), 1);
+        assert!(!fine.update(&cache.depth));
+
+        cache.update(DepthUpdate::Diff(payload(&[(99.9, 0.0)], &[])), tick);
+        assert!(fine.update(&cache.depth));
+        assert_eq!(fine.best_bid(), Some(level(99.6, 0.0).0));
+    }
+
+    #[test]
+    fn tiered_books_group_evenly_across_the_threshold() {
+        // f32 can't hold four digit prices to eight decimals
+        let price = |value: f64| Price::from_units((value * 1e8).round() as i64);
+
+        let tiers = TickTiers::new(&[(0.0, 0.25), (1000.0, 0.5)]).unwrap();
+        let mut cache = LocalDepthCache::default();
+        cache.update(
+            DepthUpdate::Snapshot(payload(
+                &[(999.25, 1.0), (999.5, 1.0), (999.75, 1.0)],
+                &[(1000.5, 1.0), (1001.0, 1.0), (1001.5, 1.0)],
+            )),
+            TickRule::Tiered(tiers),
+        );
+        // Quarters survive rather than being rounded to tenths
+        assert!(cache.depth.bids.contains_key(&price(999.25)));
+
+        // Three quarters below the threshold, whole points above it
+        let mut view = GroupedDepthView::new(PriceStep::from_f32(0.75));
+        view.set_tiers(Some(tiers));
+        view.update(&cache.depth);
+        let prices = |side: &BTreeMap<Price, f32>| side.keys().copied().collect::<Vec<_>>();
+        assert_eq!(prices(view.bids()), [price(999.0), price(999.75)]);
+        assert_eq!(prices(view.asks()), [price(1001.0), price(1002.0)]);
+        assert_eq!(view.asks()[&price(1001.0)], 2.0);
+    }
+
+    /// `cargo test --release -p flowsurface-exchange regroup_timing -- --ignored --nocapture`
+    #[test]
+    #[ignore = "timing, meaningful in release builds only"]
+    fn regroup_timing() {
+        let tick = MinTicksize::from(0.01);
+        let levels = |start: f32, dir: f32| -> Vec<(f32, f32)> {
+            (0..50)
+                .map(|i| (start + dir * i as f32 * 0.01, 1.0 + i as f32))
+                .collect()
+        };
+        let mut cache = LocalDepthCache::default();
+        cache.update(
+            DepthUpdate::Snapshot(payload(&levels(2650.0, -1.0), &levels(2650.01, 1.0))),
+            tick,
+        );
+
+        let mut view = GroupedDepthView::new(PriceStep::from_f32(0.5));
+        let rounds = 100_000;
+        let started = std::time::Instant::now();
+        for _ in 0..rounds {
+            view.grouped = None;
+            view.update(std::hint::black_box(&cache.depth));
+        }
+        let per_pass = started.elapsed() / rounds;
+
+        println!("regrouping a 50 level book: {per_pass:?} per pass");
+        assert!(per_pass < std::time::Duration::from_micros(5));
+    }
+
+    #[test]
+    fn updates_record_signed_level_changes() {
+        let tick = MinTicksize::from(TICK);
+        let mut cache = LocalDepthCache::default();
+
+        cache.update(
+            DepthUpdate::Snapshot(payload(&[(99.5, 4.0), (99.0, 2.0)], &[(100.0, 3.0)])),
+            tick,
+        );
+        assert!(cache.depth.changes.is_empty());
+
+        cache.update(
+            DepthUpdate::Diff(payload(&[(99.5, 1.0), (98.5, 5.0)], &[(100.0, 0.0)])),
+            tick,
+        );
+        assert_eq!(
+            sorted(cache.depth.changes.bids.clone()),
+            [level(98.5, 5.0), level(99.5, -3.0)]
+        );
+        assert_eq!(cache.depth.changes.asks, [level(100.0, -3.0)]);
+
+        // A resync snapshot is compared against the book it replaces
+        cache.update(
+            DepthUpdate::Snapshot(payload(&[(99.5, 1.0), (99.0, 6.0)], &[(100.5, 2.0)])),
+            tick,
+        );
+        assert_eq!(
+            sorted(cache.depth.changes.bids.clone()),
+            [level(98.5, -5.0), level(99.0, 4.0)]
+        );
+        assert_eq!(cache.depth.changes.asks, [level(100.5, 2.0)]);
     }
 }
@@ -1,4 +1,6 @@
 use crate::adapter::StreamKind;
+use crate::calendar::CalendarEvent;
+use crate::depth::DepthPayload;
 use crate::{Kline, OpenInterest, Trade};
 
 use smallvec::SmallVec;
@@ -30,6 +32,16 @@ pub enum FetchedData {
         data: Vec<OpenInterest>,
         req_id: Option<uuid::Uuid>,
     },
+    /// Recorded depth snapshots, empty when the source doesn't keep any
+    Depth {
+        data: Vec<DepthPayload>,
+        req_id: uuid::Uuid,
+    },
+    /// Economic calendar events, empty when the source doesn't forward any
+    Calendar {
+        data: Vec<CalendarEvent>,
+        req_id: uuid::Uuid,
+    },
 }
 
 #[derive(thiserror::Error, Debug, Clone)]
@@ -90,6 +102,23 @@ impl RequestHandler {
         Ok(Some(id))
     }
 
+    /// Requests `fetch` again, even if it failed or completed moments ago, unless it's pending
+    pub fn retry(&mut self, fetch: FetchRange) -> Option<Uuid> {
+        let request = FetchRequest::new(fetch);
+        let mut pending = false;
+        self.requests.retain(|_, existing| {
+            pending |= existing.same_with(&request) && existing.status == RequestStatus::Pending;
+            pending || !existing.same_with(&request)
+        });
+        if pending {
+            return None;
+        }
+
+        let id = Uuid::new_v4();
+        self.requests.insert(id, request);
+        Some(id)
+    }
+
     pub fn mark_completed(&mut self, id: Uuid) {
         if let Some(request) = self.requests.get_mut(&id) {
             let timestamp = chrono::Utc::now().timestamp_millis() as u64;
@@ -119,6 +148,8 @@ pub enum FetchRange {
     Kline(u64, u64),
     OpenInterest(u64, u64),
     Trades(u64, u64),
+    Depth(u64, u64),
+    Calendar(u64, u64),
 }
 
 #[derive(PartialEq, Debug)]
@@ -138,9 +169,9 @@ impl FetchRequest {
     fn same_with(&self, other: &FetchRequest) -> bool {
         match (&self.fetch_type, &other.fetch_type) {
             (FetchRange::Kline(s1, e1), FetchRange::Kline(s2, e2)) => e1 == e2 && s1 == s2,
-            (FetchRange::OpenInterest(s1, e1), FetchRange::OpenInterest(s2, e2)) => {
-                e1 == e2 && s1 == s2
-            }
+            (FetchRange::OpenInterest(s1, e1), FetchRange::OpenInterest(s2, e2))
+            | (FetchRange::Depth(s1, e1), FetchRange::Depth(s2, e2))
+            | (FetchRange::Calendar(s1, e1), FetchRange::Calendar(s2, e2)) => e1 == e2 && s1 == s2,
             _ => false,
         }
     }
@@ -189,4 +220,5 @@ pub enum InfoKind {
     FetchingKlines,
     FetchingTrades(usize),
     FetchingOI,
+    FetchingDepth,
 }
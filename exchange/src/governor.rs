@@ -0,0 +1,249 @@
+//! Coalescing and pacing of one-off requests, per key.
+//!
+//! A caller asking for a key that's already being fetched waits for that fetch and gets a copy of
+//! its result. A fetch starting sooner than the minimum interval after the previous one waits out
+//! the rest of the interval first, and callers arriving meanwhile join it, so at most one fetch
+//! per key is ever queued.
+//!
+//! Keys that have nothing running and no longer hold back the next fetch are forgotten, so keys
+//! like kline ranges that are seldom asked for twice don't pile up over a long session.
+
+use crate::adapter::AdapterError;
+
+use futures_util::FutureExt as _;
+use futures_util::future::{BoxFuture, Shared};
+use rustc_hash::FxHashMap;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+type SharedFetch<V> = Shared<BoxFuture<'static, Result<V, Arc<AdapterError>>>>;
+
+struct Slot<V> {
+    /// The running or queued fetch, with the id that lets it clear only itself
+    fetch: Option<(u64, SharedFetch<V>)>,
+    last_started: Option<Instant>,
+}
+
+impl<V> Default for Slot<V> {
+    fn default() -> Self {
+        Self {
+            fetch: None,
+            last_started: None,
+        }
+    }
+}
+
+impl<V> Slot<V> {
+    /// Nothing running and the next fetch wouldn't wait
+    fn is_idle(&self, now: Instant, min_interval: Duration) -> bool {
+        self.fetch.is_none()
+            && self
+                .last_started
+                .is_none_or(|last| last + min_interval <= now)
+    }
+}
+
+pub(crate) struct Governor<K, V> {
+    min_interval: Duration,
+    slots: Arc<Mutex<FxHashMap<K, Slot<V>>>>,
+    next_id: AtomicU64,
+}
+
+impl<K, V> Governor<K, V>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    /// With a zero `min_interval` identical requests are only coalesced, never delayed
+    pub(crate) fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            slots: Arc::new(Mutex::new(FxHashMap::default())),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Keys still remembered, idle ones are dropped on the next fetch
+    #[cfg(test)]
+    fn remembered(&self) -> usize {
+        self.slots.lock().map_or(0, |slots| slots.len())
+    }
+
+    /// Whether a fetch of `key` is running or queued, joining it beats asking again
+    pub(crate) fn is_busy(&self, key: &K) -> bool {
+        self.slots
+            .lock()
+            .is_ok_and(|slots| slots.get(key).is_some_and(|slot| slot.fetch.is_some()))
+    }
+
+    /// Result of `fetch` for `key`, shared with every caller that asked while it was on its way.
+    /// `fetch` is only called when no fetch of `key` is running or queued.
+    pub(crate) async fn run<F, Fut>(&self, key: K, fetch: F) -> Result<V, AdapterError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V, AdapterError>> + Send + 'static,
+    {
+        let shared = {
+            let mut slots = self.slots.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            slots.retain(|_, slot| !slot.is_idle(now, self.min_interval));
+            let slot = slots.entry(key.clone()).or_default();
+
+            match &slot.fetch {
+                Some((_, running)) => running.clone(),
+                None => {
+                    let delay = slot.last_started.map_or(Duration::ZERO, |last| {
+                        (last + self.min_interval).saturating_duration_since(now)
+                    });
+                    slot.last_started = Some(now + delay);
+
+                    let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                    let request = fetch();
+                    let slots = Arc::clone(&self.slots);
+
+                    let shared = async move {
+                        if !delay.is_zero() {
+                            tokio::time::sleep(delay).await;
+                        }
+                        let result = request.await.map_err(Arc::new);
+
+                        if let Ok(mut slots) = slots.lock()
+                            && let Some(slot) = slots.get_mut(&key)
+                            && slot
+                                .fetch
+                                .as_ref()
+                                .is_some_and(|(current, _)| *current == id)
+                        {
+                            slot.fetch = None;
+                        }
+                        result
+                    }
+                    .boxed()
+                    .shared();
+
+                    slot.fetch = Some((id, shared.clone()));
+                    shared
+                }
+            }
+        };
+
+        shared
+            .await
+            .map_err(|error| Arc::try_unwrap(error).unwrap_or_else(|error| copy_error(&error)))
+    }
+}
+
+/// A caller's own copy of a shared error, reqwest errors can't be cloned so they keep their text
+fn copy_error(error: &AdapterError) -> AdapterError {
+    match error {
+        AdapterError::FetchError(e) => AdapterError::InvalidRequest(e.to_string()),
+        AdapterError::ParseError(e) => AdapterError::ParseError(e.clone()),
+        AdapterError::WebsocketError(e) => AdapterError::WebsocketError(e.clone()),
+        AdapterError::InvalidRequest(e) => AdapterError::InvalidRequest(e.clone()),
+        AdapterError::Unsupported(e) => AdapterError::Unsupported(e.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    fn counted(
+        calls: &Arc<AtomicUsize>,
+        result: Result<u32, AdapterError>,
+    ) -> impl Future<Output = Result<u32, AdapterError>> + Send + 'static {
+        calls.fetch_add(1, Ordering::SeqCst);
+        async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            result
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrent_callers_share_one_fetch() {
+        let governor = Governor::<&str, u32>::new(Duration::ZERO);
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let callers = (0..10).map(|_| governor.run("symbols", || counted(&calls, Ok(7))));
+        let results = futures_util::future::join_all(callers).await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(results.iter().all(|r| matches!(r, Ok(7))));
+        assert!(!governor.is_busy(&"symbols"));
+
+        // Errors are shared the same way
+        let callers = (0..3).map(|_| {
+            governor.run("symbols", || {
+                counted(&calls, Err(AdapterError::WebsocketError("gone".into())))
+            })
+        });
+        let results = futures_util::future::join_all(callers).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(
+            results
+                .iter()
+                .all(|r| matches!(r, Err(AdapterError::WebsocketError(e)) if e == "gone"))
+        );
+    }
+
+    #[tokio::test]
+    async fn refreshes_too_soon_wait_and_queue_once() {
+        let min_interval = Duration::from_millis(300);
+        let governor = Governor::<&str, u32>::new(min_interval);
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let started = Instant::now();
+        governor
+            .run("symbols", || counted(&calls, Ok(1)))
+            .await
+            .unwrap();
+
+        // Both ask again right away, one refresh is queued and they share it
+        let mut first = std::pin::pin!(governor.run("symbols", || counted(&calls, Ok(2))));
+        assert!(futures_util::poll!(first.as_mut()).is_pending());
+        assert!(governor.is_busy(&"symbols"));
+        let second = governor.run("symbols", || counted(&calls, Ok(3)));
+        let (first, second) = futures_util::join!(first, second);
+
+        assert_eq!((first.unwrap(), second.unwrap()), (2, 2));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(started.elapsed() >= min_interval);
+
+        // Other keys aren't held back
+        let other = Instant::now();
+        governor
+            .run("klines", || counted(&calls, Ok(4)))
+            .await
+            .unwrap();
+        assert!(other.elapsed() < min_interval);
+    }
+
+    #[tokio::test]
+    async fn idle_keys_are_forgotten() {
+        let governor = Governor::<u64, u32>::new(Duration::ZERO);
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        // One-off ranges, each asked for once
+        for range in 0..20 {
+            governor
+                .run(range, || counted(&calls, Ok(0)))
+                .await
+                .unwrap();
+            assert!(governor.remembered() <= 1);
+        }
+
+        // Paced keys are kept while they'd still delay the next fetch
+        let paced = Governor::<&str, u32>::new(Duration::from_millis(200));
+        paced.run("a", || counted(&calls, Ok(0))).await.unwrap();
+        paced.run("b", || counted(&calls, Ok(0))).await.unwrap();
+        assert_eq!(paced.remembered(), 2);
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        paced.run("c", || counted(&calls, Ok(0))).await.unwrap();
+        assert_eq!(paced.remembered(), 1);
+    }
+}
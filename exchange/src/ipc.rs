@@ -0,0 +1,560 @@
+//! Local endpoint for scripts to read the prices the app already streams, without opening another
+//! connection to the venue.
+//!
+//! Off by default. When on it listens on 127.0.0.1 only and speaks newline delimited JSON. The
+//! first line of a connection must be `{"type":"auth","token":"..."}`, after that each line is a
+//! request answered by one line, `subscribe` then also pushes the quotes that moved once per
+//! interval. Requests carrying an `id` get it back in their answer.
+//!
+//! Answers only read the latest [`Snapshot`] the app published to the [`Feed`], so market data
+//! streams never wait on a client. See `examples/price_client.py` for a client.
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+use std::sync::{Arc, LazyLock, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+pub const DEFAULT_PORT: u16 = 47_390;
+/// Clients connected at once, further ones are turned away
+const MAX_CLIENTS: usize = 16;
+/// How long a client has to send its token
+const AUTH_TIMEOUT: Duration = Duration::from_secs(5);
+const MIN_PUSH_INTERVAL_MS: u64 = 100;
+const DEFAULT_PUSH_INTERVAL_MS: u64 = 500;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IpcConfig {
+    pub enabled: bool,
+    pub port: u16,
+    /// Clients send this first, generated the first time the endpoint is turned on
+    pub token: String,
+}
+
+impl Default for IpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+            token: String::new(),
+        }
+    }
+}
+
+impl IpcConfig {
+    /// Turned on with a fresh token if none was generated yet
+    pub fn enable(self) -> Self {
+        let token = if self.token.is_empty() {
+            uuid::Uuid::new_v4().simple().to_string()
+        } else {
+            self.token
+        };
+
+        Self {
+            enabled: true,
+            token,
+            ..self
+        }
+    }
+}
+
+static CONFIG: LazyLock<RwLock<IpcConfig>> = LazyLock::new(|| RwLock::new(IpcConfig::default()));
+
+pub fn config() -> IpcConfig {
+    CONFIG
+        .read()
+        .map(|config| config.clone())
+        .unwrap_or_default()
+}
+
+pub fn set_config(config: IpcConfig) {
+    if let Ok(mut current) = CONFIG.write() {
+        *current = config;
+    }
+}
+
+/// What the app knows about one streamed ticker
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Quote {
+    /// Exchange and symbol, e.g. `BinanceLinear:BTCUSDT`
+    pub ticker: String,
+    pub symbol: String,
+    pub last_price: Option<f32>,
+    pub bid: Option<f32>,
+    pub ask: Option<f32>,
+    /// Change over the last 24h in percent, for venues that report daily stats
+    pub change_pct: Option<f32>,
+    pub daily_volume: Option<f32>,
+    /// Unix ms of the latest trade or book update
+    pub time: u64,
+}
+
+impl Quote {
+    pub fn new(ticker: &crate::Ticker) -> Self {
+        let id = serde_json::to_value(ticker)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_else(|| ticker.to_string());
+
+        Self {
+            ticker: id,
+            symbol: ticker.to_string(),
+            last_price: None,
+            bid: None,
+            ask: None,
+            change_pct: None,
+            daily_volume: None,
+            time: 0,
+        }
+    }
+
+    /// Whether `name` is this quote's full ticker or bare symbol, ignoring case
+    fn is(&self, name: &str) -> bool {
+        self.ticker.eq_ignore_ascii_case(name) || self.symbol.eq_ignore_ascii_case(name)
+    }
+
+    fn spread(&self) -> Option<f32> {
+        Some(self.ask? - self.bid?)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Snapshot {
+    pub quotes: Vec<Quote>,
+}
+
+impl Snapshot {
+    fn find(&self, name: &str) -> Option<&Quote> {
+        self.quotes.iter().find(|quote| quote.is(name))
+    }
+}
+
+/// Latest snapshot, replaced whole by the app and only cloned out by clients
+#[derive(Debug, Default)]
+pub struct Feed(RwLock<Arc<Snapshot>>);
+
+impl Feed {
+    pub fn publish(&self, snapshot: Snapshot) {
+        if let Ok(mut current) = self.0.write() {
+            *current = Arc::new(snapshot);
+        }
+    }
+
+    fn latest(&self) -> Arc<Snapshot> {
+        self.0
+            .read()
+            .map(|snapshot| Arc::clone(&snapshot))
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    id: Option<Value>,
+    #[serde(flatten)]
+    request: Request,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Request {
+    Auth {
+        token: String,
+    },
+    ListTickers,
+    GetPrice {
+        ticker: String,
+    },
+    GetQuote {
+        ticker: String,
+    },
+    Subscribe {
+        tickers: Vec<String>,
+        #[serde(default)]
+        interval_ms: Option<u64>,
+    },
+    Unsubscribe,
+}
+
+/// Quotes pushed to a client, and what it was last sent of each
+struct Subscription {
+    tickers: Vec<String>,
+    interval: tokio::time::Interval,
+    sent: HashMap<String, Quote>,
+}
+
+impl Subscription {
+    fn new(tickers: Vec<String>, interval_ms: u64) -> Self {
+        let mut interval = tokio::time::interval(Duration::from_millis(interval_ms));
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        Self {
+            tickers,
+            interval,
+            sent: HashMap::new(),
+        }
+    }
+
+    /// Quotes of the subscribed tickers that changed since the last push
+    fn changed(&mut self, snapshot: &Snapshot) -> Vec<Quote> {
+        let mut changed = vec![];
+        for name in &self.tickers {
+            let Some(quote) = snapshot.find(name) else {
+                continue;
+            };
+            if self.sent.get(&quote.ticker) != Some(quote) {
+                self.sent.insert(quote.ticker.clone(), quote.clone());
+                changed.push(quote.clone());
+            }
+        }
+        changed
+    }
+}
+
+/// Listens on the loopback interface only
+pub async fn bind(port: u16) -> std::io::Result<TcpListener> {
+    TcpListener::bind((Ipv4Addr::LOCALHOST, port)).await
+}
+
+/// Serves clients until dropped, which also drops their connections
+pub async fn serve(listener: TcpListener, token: String, feed: Arc<Feed>) {
+    let token: Arc<str> = token.into();
+    let mut clients = tokio::task::JoinSet::new();
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let Ok((stream, addr)) = accepted else {
+                    continue;
+                };
+                if clients.len() >= MAX_CLIENTS {
+                    log::warn!("Refused price endpoint client {addr}, {MAX_CLIENTS} are connected");
+                    continue;
+                }
+
+                log::info!("Price endpoint client connected from {addr}");
+                clients.spawn(handle(stream, Arc::clone(&token), Arc::clone(&feed)));
+            }
+            Some(_) = clients.join_next(), if !clients.is_empty() => {}
+        }
+    }
+}
+
+async fn handle(stream: TcpStream, token: Arc<str>, feed: Arc<Feed>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    let authenticated = match tokio::time::timeout(AUTH_TIMEOUT, lines.next_line()).await {
+        Ok(Ok(Some(line))) => matches!(
+            serde_json::from_str::<Envelope>(&line),
+            Ok(Envelope { request: Request::Auth { token: sent }, .. }) if same_token(&sent, &token)
+        ),
+        _ => false,
+    };
+    if !authenticated {
+        let answer = json!({ "type": "auth_response", "success": false, "error": "Invalid token" });
+        send(&mut writer, &answer).await.ok();
+        return;
+    }
+    if send(
+        &mut writer,
+        &json!({ "type": "auth_response", "success": true }),
+    )
+    .await
+    .is_err()
+    {
+        return;
+    }
+
+    let mut subscription: Option<Subscription> = None;
+
+    loop {
+        let answer = tokio::select! {
+            line = lines.next_line() => match line {
+                Ok(Some(line)) => answer(&line, &feed.latest(), &mut subscription),
+                _ => break,
+            },
+            () = next_push(&mut subscription) => {
+                let Some(subscription) = subscription.as_mut() else {
+                    continue;
+                };
+                let quotes = subscription.changed(&feed.latest());
+                if quotes.is_empty() {
+                    continue;
+                }
+                json!({ "type": "price_update", "quotes": quotes })
+            }
+        };
+
+        if send(&mut writer, &answer).await.is_err() {
+            break;
+        }
+    }
+}
+
+async fn next_push(subscription: &mut Option<Subscription>) {
+    match subscription {
+        Some(subscription) => {
+            subscription.interval.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
+async fn send(writer: &mut tokio::net::tcp::OwnedWriteHalf, answer: &Value) -> std::io::Result<()> {
+    let mut line = answer.to_string();
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await
+}
+
+/// Compares every byte, so the time taken doesn't tell how much of a guess was right
+fn same_token(sent: &str, token: &str) -> bool {
+    sent.len() == token.len()
+        && sent
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn answer(line: &str, snapshot: &Snapshot, subscription: &mut Option<Subscription>) -> Value {
+    let Envelope { id, request } = match serde_json::from_str::<Envelope>(line) {
+        Ok(envelope) => envelope,
+        Err(e) => return json!({ "type": "error", "message": format!("Invalid request: {e}") }),
+    };
+
+    let unknown =
+        |ticker: &str| json!({ "type": "error", "message": format!("Unknown ticker: {ticker}") });
+
+    let mut answer = match request {
+        Request::Auth { .. } => json!({ "type": "auth_response", "success": true }),
+        Request::ListTickers => json!({
+            "type": "tickers",
+            "tickers": snapshot.quotes.iter().map(|quote| &quote.ticker).collect::<Vec<_>>(),
+        }),
+        Request::GetPrice { ticker } => match snapshot.find(&ticker) {
+            Some(quote) => json!({
+                "type": "price",
+                "ticker": quote.ticker,
+                "last_price": quote.last_price,
+                "change_pct": quote.change_pct,
+                "daily_volume": quote.daily_volume,
+                "time": quote.time,
+            }),
+            None => unknown(&ticker),
+        },
+        Request::GetQuote { ticker } => match snapshot.find(&ticker) {
+            Some(quote) => json!({
+                "type": "quote",
+                "ticker": quote.ticker,
+                "bid": quote.bid,
+                "ask": quote.ask,
+                "spread": quote.spread(),
+                "time": quote.time,
+            }),
+            None => unknown(&ticker),
+        },
+        Request::Subscribe {
+            tickers,
+            interval_ms,
+        } => {
+            let interval_ms = interval_ms
+                .unwrap_or(DEFAULT_PUSH_INTERVAL_MS)
+                .max(MIN_PUSH_INTERVAL_MS);
+            let answer = json!({
+                "type": "subscribed",
+                "tickers": tickers,
+                "interval_ms": interval_ms,
+            });
+            *subscription = Some(Subscription::new(tickers, interval_ms));
+            answer
+        }
+        Request::Unsubscribe => {
+            *subscription = None;
+            json!({ "type": "unsubscribed" })
+        }
+    };
+
+    if let (Some(id), Value::Object(fields)) = (id, &mut answer) {
+        fields.insert("id".to_string(), id);
+    }
+    answer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Ticker;
+    use crate::adapter::Exchange;
+
+    const TOKEN: &str = "secret-token";
+
+    fn quote(symbol: &str, last_price: f32, bid: f32, ask: f32) -> Quote {
+        Quote {
+            last_price: Some(last_price),
+            bid: Some(bid),
+            ask: Some(ask),
+            time: 1_704_355_200_000,
+            ..Quote::new(&Ticker::new(symbol, Exchange::MetaTrader5))
+        }
+    }
+
+    struct Client {
+        lines: tokio::io::Lines<BufReader<tokio::net::tcp::OwnedReadHalf>>,
+        writer: tokio::net::tcp::OwnedWriteHalf,
+    }
+
+    impl Client {
+        async fn connect(feed: Arc<Feed>) -> Self {
+            let listener = bind(0).await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            assert!(addr.ip().is_loopback());
+            tokio::spawn(serve(listener, TOKEN.to_string(), feed));
+
+            let (reader, writer) = TcpStream::connect(addr).await.unwrap().into_split();
+            Self {
+                lines: BufReader::new(reader).lines(),
+                writer,
+            }
+        }
+
+        async fn request(&mut self, request: Value) -> Value {
+            self.writer
+                .write_all(format!("{request}\n").as_bytes())
+                .await
+                .unwrap();
+            self.next().await
+        }
+
+        async fn next(&mut self) -> Value {
+            let line = tokio::time::timeout(Duration::from_secs(5), self.lines.next_line())
+                .await
+                .unwrap()
+                .unwrap()
+                .unwrap();
+            serde_json::from_str(&line).unwrap()
+        }
+
+        async fn authenticated(feed: Arc<Feed>) -> Self {
+            let mut client = Self::connect(feed).await;
+            let answer = client
+                .request(json!({ "type": "auth", "token": TOKEN }))
+                .await;
+            assert_eq!(answer["success"], true);
+            client
+        }
+    }
+
+    fn feed() -> Arc<Feed> {
+        let feed = Arc::new(Feed::default());
+        feed.publish(Snapshot {
+            quotes: vec![
+                quote("EURUSD", 1.0952, 1.0951, 1.0953),
+                quote("XAUUSD", 2050.5, 2050.3, 2050.7),
+            ],
+        });
+        feed
+    }
+
+    #[tokio::test]
+    async fn requests_need_the_token_first() {
+        let mut client = Client::connect(feed()).await;
+        let answer = client
+            .request(json!({ "type": "auth", "token": "guess" }))
+            .await;
+        assert_eq!(answer["success"], false);
+        // The connection is closed after a wrong token
+        assert!(client.lines.next_line().await.unwrap().is_none());
+
+        let mut client = Client::connect(feed()).await;
+        let answer = client.request(json!({ "type": "list_tickers" })).await;
+        assert_eq!(answer["success"], false);
+    }
+
+    #[tokio::test]
+    async fn list_tickers_names_every_streamed_ticker() {
+        let mut client = Client::authenticated(feed()).await;
+        let answer = client
+            .request(json!({ "type": "list_tickers", "id": 7 }))
+            .await;
+
+        assert_eq!(answer["type"], "tickers");
+        assert_eq!(answer["id"], 7);
+        assert_eq!(
+            answer["tickers"],
+            json!(["MetaTrader5:EURUSD", "MetaTrader5:XAUUSD"])
+        );
+    }
+
+    #[tokio::test]
+    async fn get_price_by_full_ticker_or_symbol() {
+        let mut client = Client::authenticated(feed()).await;
+
+        let answer = client
+            .request(json!({ "type": "get_price", "ticker": "MetaTrader5:XAUUSD" }))
+            .await;
+        assert_eq!(answer["type"], "price");
+        assert_eq!(answer["last_price"], 2050.5);
+
+        let answer = client
+            .request(json!({ "type": "get_price", "ticker": "eurusd" }))
+            .await;
+        assert_eq!(answer["ticker"], "MetaTrader5:EURUSD");
+
+        let answer = client
+            .request(json!({ "type": "get_price", "ticker": "GBPUSD" }))
+            .await;
+        assert_eq!(answer["type"], "error");
+    }
+
+    #[tokio::test]
+    async fn get_quote_has_the_best_bid_and_ask() {
+        let mut client = Client::authenticated(feed()).await;
+        let answer = client
+            .request(json!({ "type": "get_quote", "ticker": "XAUUSD" }))
+            .await;
+
+        assert_eq!(answer["type"], "quote");
+        assert_eq!(answer["bid"], json!(2050.3_f32));
+        assert_eq!(answer["ask"], json!(2050.7_f32));
+        let spread = answer["spread"].as_f64().unwrap();
+        assert!((spread - 0.4).abs() < 1e-3);
+    }
+
+    #[tokio::test]
+    async fn subscribe_pushes_only_what_moved() {
+        let feed = feed();
+        let mut client = Client::authenticated(Arc::clone(&feed)).await;
+
+        let answer = client
+            .request(
+                json!({ "type": "subscribe", "tickers": ["EURUSD", "XAUUSD"], "interval_ms": 1 }),
+            )
+            .await;
+        assert_eq!(answer["type"], "subscribed");
+        assert_eq!(answer["interval_ms"], MIN_PUSH_INTERVAL_MS);
+
+        let first = client.next().await;
+        assert_eq!(first["type"], "price_update");
+        assert_eq!(first["quotes"].as_array().unwrap().len(), 2);
+
+        feed.publish(Snapshot {
+            quotes: vec![
+                quote("EURUSD", 1.0955, 1.0954, 1.0956),
+                quote("XAUUSD", 2050.5, 2050.3, 2050.7),
+            ],
+        });
+        let update = client.next().await;
+        let quotes = update["quotes"].as_array().unwrap();
+        assert_eq!(quotes.len(), 1);
+        assert_eq!(quotes[0]["symbol"], "EURUSD");
+
+        let answer = client.request(json!({ "type": "unsubscribe" })).await;
+        assert_eq!(answer["type"], "unsubscribed");
+    }
+}
@@ -1,12 +1,25 @@
 pub mod adapter;
+mod bars;
+pub mod calendar;
+pub mod capture;
 pub mod connect;
+pub mod conversion;
 pub mod depth;
 pub mod fetcher;
+mod governor;
+pub mod ipc;
 mod limiter;
+pub mod limits;
+pub mod market_state;
+pub mod synthetic;
+pub mod tick_rule;
 pub mod util;
+pub mod webhook;
 
+use crate::tick_rule::{TickRule, TickTiers};
 use crate::util::{ContractSize, MinQtySize, MinTicksize, Price};
 pub use adapter::Event;
+use adapter::metatrader5::price_basis::PriceBasis;
 use adapter::{Exchange, MarketKind, StreamKind};
 
 use serde::{Deserialize, Deserializer, Serialize};
@@ -17,8 +30,10 @@ use std::{fmt, hash::Hash};
 
 /// Unit for displaying volume/quantity size values.
 ///
-/// - `Base`: Display in base asset units (e.g., BTC for BTCUSDT)
+/// - `Base`: Display in base asset units (e.g., BTC for BTCUSDT, lots for MT5 symbols)
 /// - `Quote`: Display in quote currency value (e.g., USD/USDT equivalent)
+/// - `Contracts`: Display in contract counts, only a pane can choose it, streams deliver sizes
+///   in the app wide `Base` or `Quote`, see [`conversion::SizeDisplay`]
 ///
 /// Note: Only applies to linear perpetuals and spot markets.
 /// Inverse perpetuals always display in USD regardless of this setting.
@@ -28,6 +43,21 @@ pub enum SizeUnit {
     Base = 0,
     #[default]
     Quote = 1,
+    Contracts = 2,
+}
+
+impl SizeUnit {
+    pub const ALL: [SizeUnit; 3] = [SizeUnit::Base, SizeUnit::Quote, SizeUnit::Contracts];
+}
+
+impl fmt::Display for SizeUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SizeUnit::Base => write!(f, "Base units"),
+            SizeUnit::Quote => write!(f, "Quote currency"),
+            SizeUnit::Contracts => write!(f, "Contracts"),
+        }
+    }
 }
 
 static SIZE_CALC_UNIT: AtomicU8 = AtomicU8::new(SizeUnit::Base as u8);
@@ -223,6 +253,7 @@ impl SerTicker {
             Exchange::OkexInverse => "OkexInverse",
             Exchange::OkexSpot => "OkexSpot",
             Exchange::MetaTrader5 => "MetaTrader5",
+            Exchange::CustomWs => "CustomWs",
         }
     }
 
@@ -240,6 +271,7 @@ impl SerTicker {
             "OkexInverse" => Ok(Exchange::OkexInverse),
             "OkexSpot" => Ok(Exchange::OkexSpot),
             "MetaTrader5" => Ok(Exchange::MetaTrader5),
+            "CustomWs" => Ok(Exchange::CustomWs),
             _ => Err(format!("Unknown exchange: {}", s)),
         }
     }
@@ -252,7 +284,12 @@ impl Serialize for SerTicker {
     {
         let (ticker_str, _) = self.ticker.to_full_symbol_and_type();
         let exchange_str = Self::exchange_to_string(self.exchange);
-        let combined = format!("{}:{}", exchange_str, ticker_str);
+        let combined = format!(
+            "{}:{}{}",
+            exchange_str,
+            ticker_str,
+            connection_suffix(self.ticker.connection)
+        );
         serializer.serialize_str(&combined)
     }
 }
@@ -275,8 +312,8 @@ impl<'de> Deserialize<'de> for SerTicker {
         let exchange_str = parts[0];
         let exchange = Self::string_to_exchange(exchange_str).map_err(serde::de::Error::custom)?;
 
-        let ticker_str = parts[1];
-        let ticker = Ticker::new(ticker_str, exchange);
+        let (ticker_str, connection) = split_connection(parts[1]);
+        let ticker = Ticker::new(ticker_str, exchange).on_connection(connection);
 
         Ok(SerTicker { exchange, ticker })
     }
@@ -286,7 +323,13 @@ impl fmt::Display for SerTicker {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let (ticker_str, _) = self.ticker.to_full_symbol_and_type();
         let exchange_str = Self::exchange_to_string(self.exchange);
-        write!(f, "{}:{}", exchange_str, ticker_str)
+        write!(
+            f,
+            "{}:{}{}",
+            exchange_str,
+            ticker_str,
+            connection_suffix(self.ticker.connection)
+        )
     }
 }
 
@@ -298,6 +341,8 @@ pub struct Ticker {
     // to show "HYPEUSDC" instead of "@107"
     display_bytes: [u8; Ticker::MAX_LEN as usize],
     has_display_symbol: bool,
+    /// MT5 connection the symbol was listed by, brokers list the same names with their own specs
+    connection: Option<SourceId>,
 }
 
 impl Ticker {
@@ -339,9 +384,19 @@ impl Ticker {
             exchange,
             display_bytes,
             has_display_symbol,
+            connection: None,
         }
     }
 
+    /// The same symbol as listed by `connection`, or by no connection in particular for `None`
+    pub fn on_connection(self, connection: Option<SourceId>) -> Self {
+        Self { connection, ..self }
+    }
+
+    pub fn connection(&self) -> Option<SourceId> {
+        self.connection
+    }
+
     #[inline]
     fn as_str(&self) -> &str {
         let end = self
@@ -427,18 +482,20 @@ impl fmt::Debug for Ticker {
         if self.has_display_symbol && internal_sym != sym {
             write!(
                 f,
-                "Ticker({}:{}[{}], {:?})",
+                "Ticker({}:{}[{}]{}, {:?})",
                 SerTicker::exchange_to_string(self.exchange),
                 sym,
                 internal_sym,
+                connection_suffix(self.connection),
                 kind
             )
         } else {
             write!(
                 f,
-                "Ticker({}:{}, {:?})",
+                "Ticker({}:{}{}, {:?})",
                 SerTicker::exchange_to_string(self.exchange),
                 sym,
+                connection_suffix(self.connection),
                 kind
             )
         }
@@ -452,11 +509,12 @@ impl Serialize for Ticker {
     {
         let internal = self.as_str();
         let exchange = SerTicker::exchange_to_string(self.exchange);
+        let connection = connection_suffix(self.connection);
         let s = if self.has_display_symbol {
             let display = self.display_as_str();
-            format!("{exchange}:{internal}|{display}")
+            format!("{exchange}:{internal}|{display}{connection}")
         } else {
-            format!("{exchange}:{internal}")
+            format!("{exchange}:{internal}{connection}")
         };
         serializer.serialize_str(&s)
     }
@@ -488,12 +546,13 @@ impl<'de> Deserialize<'de> for Ticker {
                 let exchange = SerTicker::string_to_exchange(exchange_str)
                     .map_err(serde::de::Error::custom)?;
 
+                let (rest, connection) = split_connection(rest);
                 let (symbol, display) = if let Some((sym, disp)) = rest.split_once('|') {
                     (sym, Some(disp))
                 } else {
                     (rest, None)
                 };
-                Ok(Ticker::new_with_display(symbol, exchange, display))
+                Ok(Ticker::new_with_display(symbol, exchange, display).on_connection(connection))
             }
             TickerDe::Old {
                 data,
@@ -532,11 +591,53 @@ impl<'de> Deserialize<'de> for Ticker {
     }
 }
 
+/// Marks the connection a serialized ticker was listed by, e.g. `MetaTrader5:EURUSD~1a2b...`
+pub(crate) const CONNECTION_MARK: char = '~';
+
+fn connection_suffix(connection: Option<SourceId>) -> String {
+    connection.map_or_else(String::new, |id| format!("{CONNECTION_MARK}{id}"))
+}
+
+/// Splits the connection off a serialized ticker, symbols may contain the mark themselves
+fn split_connection(s: &str) -> (&str, Option<SourceId>) {
+    s.rsplit_once(CONNECTION_MARK)
+        .filter(|(_, id)| id.len() == 16)
+        .and_then(|(rest, id)| {
+            u64::from_str_radix(id, 16)
+                .ok()
+                .map(|id| (rest, Some(SourceId(id))))
+        })
+        .unwrap_or((s, None))
+}
+
 pub enum StreamPairKind {
     SingleSource(TickerInfo),
     MultiSource(Vec<TickerInfo>),
 }
 
+/// A saved connection a ticker is pinned to, instead of the one its exchange is served through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceId(u64);
+
+impl SourceId {
+    /// Id of the connection named `connection`, the same across runs
+    pub fn of(connection: &str) -> Self {
+        // FNV-1a
+        let hash = connection
+            .bytes()
+            .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+                (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+            });
+        Self(hash)
+    }
+}
+
+impl fmt::Display for SourceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, Hash, Eq)]
 pub struct TickerInfo {
     pub ticker: Ticker,
@@ -544,6 +645,17 @@ pub struct TickerInfo {
     pub min_ticksize: MinTicksize,
     pub min_qty: MinQtySize,
     pub contract_size: Option<ContractSize>,
+    /// Set on the tickers of panes pinned to a connection, streams and fetches go through it.
+    /// Pins are kept with the pane's settings, not with the ticker.
+    #[serde(skip)]
+    pub source: Option<SourceId>,
+    /// Set on the MT5 tickers of panes charting a chosen price, kept with the pane's settings
+    #[serde(skip)]
+    pub price_basis: Option<PriceBasis>,
+    /// Price dependent ticks of symbols quoted in tiers, `min_ticksize` is then the precision
+    /// they're written with
+    #[serde(default, rename = "tickTiers", skip_serializing_if = "Option::is_none")]
+    pub tick_tiers: Option<TickTiers>,
 }
 
 impl TickerInfo {
@@ -558,6 +670,45 @@ impl TickerInfo {
             min_ticksize: MinTicksize::from(min_ticksize),
             min_qty: MinQtySize::from(min_qty),
             contract_size: contract_size.map(ContractSize::from),
+            source: None,
+            price_basis: None,
+            tick_tiers: None,
+        }
+    }
+
+    /// The same ticker quoted in `tiers`, or at its single tick for `None`
+    pub fn tiered(self, tiers: Option<TickTiers>) -> Self {
+        Self {
+            min_ticksize: tiers.map_or(self.min_ticksize, |tiers| tiers.precision()),
+            tick_tiers: tiers,
+            ..self
+        }
+    }
+
+    /// How the ticker's prices are rounded
+    pub fn tick_rule(&self) -> TickRule {
+        self.tick_tiers
+            .map_or(TickRule::Fixed(self.min_ticksize), TickRule::Tiered)
+    }
+
+    /// The same ticker served through `source`, or through its exchange's connection for `None`
+    pub fn pinned(self, source: Option<SourceId>) -> Self {
+        Self { source, ..self }
+    }
+
+    /// The same ticker charted at `price_basis`, or at whatever the feed sends for `None`
+    pub fn priced(self, price_basis: Option<PriceBasis>) -> Self {
+        Self {
+            price_basis,
+            ..self
+        }
+    }
+
+    /// The same ticker bucketed by `min_ticksize` instead of its listed tick
+    pub fn with_min_ticksize(self, min_ticksize: f32) -> Self {
+        Self {
+            min_ticksize: MinTicksize::from(min_ticksize),
+            ..self
         }
     }
 
@@ -573,6 +724,21 @@ impl TickerInfo {
     pub fn exchange(&self) -> Exchange {
         self.ticker.exchange
     }
+
+    /// Synthetic tickers are assembled from live legs, so none of the adapter features apply
+    pub fn capabilities(&self) -> adapter::Capabilities {
+        if synthetic::is_synthetic(&self.ticker) {
+            adapter::Capabilities::default()
+        } else if self.exchange() == Exchange::MetaTrader5 {
+            // Only exchange-traded symbols report who the aggressor was
+            adapter::Capabilities {
+                aggressor_side: adapter::metatrader5::has_aggressor_side(&self.ticker),
+                ..self.exchange().capabilities()
+            }
+        } else {
+            self.exchange().capabilities()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Deserialize)]
@@ -602,19 +768,43 @@ impl Kline {
         low: f32,
         close: f32,
         volume: (f32, f32),
-        min_ticksize: MinTicksize,
+        tick_rule: impl Into<TickRule>,
     ) -> Self {
+        let tick_rule = tick_rule.into();
         Self {
             time,
-            open: Price::from_f32(open).round_to_min_tick(MinTicksize::from(min_ticksize)),
-            high: Price::from_f32(high).round_to_min_tick(MinTicksize::from(min_ticksize)),
-            low: Price::from_f32(low).round_to_min_tick(MinTicksize::from(min_ticksize)),
-            close: Price::from_f32(close).round_to_min_tick(MinTicksize::from(min_ticksize)),
+            open: tick_rule.round(Price::from_f32(open)),
+            high: tick_rule.round(Price::from_f32(high)),
+            low: tick_rule.round(Price::from_f32(low)),
+            close: tick_rule.round(Price::from_f32(close)),
             volume,
         }
     }
 }
 
+/// Whether a live kline is its bar's final state or an update of the bar still forming. Updates
+/// of a forming bar replace it in place, klines fetched as history are closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BarStatus {
+    Forming,
+    #[default]
+    Closed,
+}
+
+impl BarStatus {
+    pub fn from_closed(closed: bool) -> Self {
+        if closed {
+            BarStatus::Closed
+        } else {
+            BarStatus::Forming
+        }
+    }
+
+    pub fn is_closed(self) -> bool {
+        self == BarStatus::Closed
+    }
+}
+
 #[derive(Debug, Clone, Copy, Deserialize)]
 pub struct TickerStats {
     pub mark_price: f32,
@@ -717,6 +907,15 @@ impl TickMultiplier {
         let power = ticker_info.min_ticksize.power as i32;
         let multiply = self.0 as f32;
 
+        // Tiered symbols step by their finest tick, written at the table's precision
+        if let Some(tiers) = ticker_info.tick_tiers {
+            let decimal_places = (-power).max(0) as u32;
+            return round_to_decimal_places(
+                multiply * tiers.finest().to_f32_lossy(),
+                decimal_places,
+            );
+        }
+
         let decimal_places: u32 = if power < 0 { (-power) as u32 } else { 0 };
 
         let raw = if power >= 0 {
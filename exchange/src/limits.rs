@@ -0,0 +1,175 @@
+//! How many streams and history fetches the app runs at once, so a large layout doesn't exceed
+//! what a broker or proxy accepts or run the machine out of sockets.
+//!
+//! Stream limits are enforced by the dashboard, which hands its slots to panes. Backfills wait for
+//! a [`backfill_slot`] here before fetching. Changed limits apply right away, a raised backfill
+//! limit lets waiting fetches go at once and a lowered one holds new fetches until enough running
+//! ones finish.
+
+use serde::{Deserialize, Serialize};
+use std::sync::{LazyLock, Mutex, RwLock};
+use tokio::sync::Notify;
+
+pub const STREAM_CHOICES: [Cap; 6] = [
+    Cap::Max(4),
+    Cap::Max(8),
+    Cap::Max(16),
+    Cap::Max(32),
+    Cap::Max(64),
+    Cap::Unlimited,
+];
+
+pub const BACKFILL_CHOICES: [Cap; 5] = [
+    Cap::Max(1),
+    Cap::Max(2),
+    Cap::Max(4),
+    Cap::Max(8),
+    Cap::Unlimited,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Limits {
+    /// Streams over one MT5 connection
+    pub mt5_streams: Cap,
+    /// Streams of the other exchanges together, each one a websocket
+    pub exchange_streams: Cap,
+    /// History fetches running at once
+    pub backfills: Cap,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            mt5_streams: Cap::Max(32),
+            exchange_streams: Cap::Max(64),
+            backfills: Cap::Max(4),
+        }
+    }
+}
+
+/// Most of something allowed at once
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Cap {
+    Max(usize),
+    Unlimited,
+}
+
+impl Cap {
+    /// Whether `count` of something fit
+    pub fn admits(self, count: usize) -> bool {
+        match self {
+            Cap::Max(max) => count <= max,
+            Cap::Unlimited => true,
+        }
+    }
+}
+
+impl std::fmt::Display for Cap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Cap::Max(max) => write!(f, "{max}"),
+            Cap::Unlimited => write!(f, "∞"),
+        }
+    }
+}
+
+static LIMITS: LazyLock<RwLock<Limits>> = LazyLock::new(|| RwLock::new(Limits::default()));
+
+static BACKFILLS: LazyLock<Gate> = LazyLock::new(Gate::default);
+
+pub fn config() -> Limits {
+    LIMITS.read().map(|limits| *limits).unwrap_or_default()
+}
+
+pub fn set_config(limits: Limits) {
+    if let Ok(mut current) = LIMITS.write() {
+        *current = limits;
+    }
+    BACKFILLS.freed.notify_waiters();
+}
+
+/// Waits until fewer backfills than the limit are running, the fetch holds the slot until it's
+/// dropped
+pub async fn backfill_slot() -> Permit<'static> {
+    BACKFILLS.acquire(|| config().backfills).await
+}
+
+/// Runs `fetch` once a backfill slot is free
+pub async fn backfill<F: Future>(fetch: F) -> F::Output {
+    let _slot = backfill_slot().await;
+    fetch.await
+}
+
+#[derive(Default)]
+struct Gate {
+    running: Mutex<usize>,
+    freed: Notify,
+}
+
+impl Gate {
+    /// `cap` is read again every time a slot frees up, so it may change while waiting
+    async fn acquire(&self, cap: impl Fn() -> Cap) -> Permit<'_> {
+        loop {
+            let freed = self.freed.notified();
+            tokio::pin!(freed);
+            freed.as_mut().enable();
+
+            if let Ok(mut running) = self.running.lock()
+                && cap().admits(*running + 1)
+            {
+                *running += 1;
+                return Permit { gate: self };
+            }
+
+            freed.await;
+        }
+    }
+}
+
+pub struct Permit<'a> {
+    gate: &'a Gate,
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.gate.running.lock() {
+            *running = running.saturating_sub(1);
+        }
+        self.gate.freed.notify_waiters();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::FutureExt as _;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn fetches_wait_for_a_slot_and_follow_a_changed_limit() {
+        let gate = Gate::default();
+        let max = AtomicUsize::new(1);
+        let cap = || Cap::Max(max.load(Ordering::Relaxed));
+
+        let first = gate.acquire(cap).now_or_never().expect("a slot is free");
+
+        let second = gate.acquire(cap);
+        tokio::pin!(second);
+        assert!(second.as_mut().now_or_never().is_none());
+
+        drop(first);
+        let second = second.await;
+
+        let third = gate.acquire(cap);
+        tokio::pin!(third);
+        assert!(third.as_mut().now_or_never().is_none());
+
+        max.store(2, Ordering::Relaxed);
+        gate.freed.notify_waiters();
+        let _third = third.await;
+        drop(second);
+
+        assert_eq!(*gate.running.lock().unwrap(), 1);
+    }
+}
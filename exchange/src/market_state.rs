@@ -0,0 +1,57 @@
+//! Whether a venue is trading a symbol right now.
+//!
+//! CFD and FX venues stop streaming outside their sessions, and a pane that is merely waiting for
+//! the market to reopen shouldn't look frozen.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MarketState {
+    #[default]
+    Open,
+    /// Outside the trading sessions, `reopens_at` in unix ms when the session calendar is known.
+    /// `paused` while the stream is down to quotes until shortly before the reopen.
+    Closed {
+        reopens_at: Option<u64>,
+        paused: bool,
+    },
+    /// The broker doesn't accept orders, quotes may still stream
+    TradeDisabled,
+    /// Only existing positions can be closed
+    CloseOnly,
+}
+
+impl MarketState {
+    pub fn is_open(self) -> bool {
+        self == MarketState::Open
+    }
+
+    /// Replaces the state, returns whether it changed
+    pub fn transition(&mut self, next: MarketState) -> bool {
+        let changed = *self != next;
+        *self = next;
+        changed
+    }
+
+    /// Banner text for a pane, `None` while trading normally
+    pub fn banner(self) -> Option<String> {
+        match self {
+            MarketState::Open => None,
+            MarketState::Closed { reopens_at, paused } => {
+                let closed = match reopens_at
+                    .and_then(|ms| chrono::DateTime::from_timestamp_millis(ms as i64))
+                {
+                    Some(time) => {
+                        format!("Market closed — reopens {}", time.format("%a %H:%M UTC"))
+                    }
+                    None => "Market closed".to_string(),
+                };
+                Some(if paused {
+                    format!("{closed}, stream paused")
+                } else {
+                    closed
+                })
+            }
+            MarketState::TradeDisabled => Some("Trading disabled for this symbol".to_string()),
+            MarketState::CloseOnly => Some("Close-only: new positions disabled".to_string()),
+        }
+    }
+}
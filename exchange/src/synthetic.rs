@@ -0,0 +1,633 @@
+//! Synthetic instruments built from two underlying tickers
+//!
+//! A synthetic ticker (e.g. `EURUSD-GBPUSD`) is defined as a formula over two legs, possibly
+//! on different exchanges. Its streams subscribe to both legs, align their quotes by time and
+//! emit regular `Event`s for the synthetic `TickerInfo`, so panes treat it like any other ticker.
+//!
+//! When one leg goes quiet for longer than the tolerance window, output pauses instead of
+//! combining a fresh price with a stale one.
+
+use crate::adapter::{self, AdapterError, Event, StreamKind, StreamTicksize};
+use crate::bars::BarBuilder;
+use crate::depth::Depth;
+use crate::{Kline, Price, PushFrequency, Ticker, TickerInfo, Timeframe, Trade};
+
+use futures_util::StreamExt as _;
+use iced_futures::{
+    futures::{SinkExt, Stream},
+    stream,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Tick size used for ratio instruments, which have no natural tick
+const RATIO_TICKSIZE: f32 = 0.00001;
+
+/// Synthetic tickers are this followed by their symbol. No exchange lists a symbol starting with
+/// it, so a synthetic can't take over the streams of a real ticker named the same.
+pub const SYMBOL_PREFIX: char = '=';
+
+fn default_tolerance_ms() -> u64 {
+    1_000
+}
+
+fn default_multiplier() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Formula {
+    /// `a * ma - b * mb`
+    Difference,
+    /// `(a * ma) / (b * mb)`
+    Ratio,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct Leg {
+    pub ticker_info: TickerInfo,
+    #[serde(default = "default_multiplier")]
+    pub multiplier: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SyntheticSpec {
+    pub symbol: String,
+    pub formula: Formula,
+    pub legs: [Leg; 2],
+    /// Max age difference between the two legs' last quotes for a value to be emitted
+    #[serde(default = "default_tolerance_ms")]
+    pub tolerance_ms: u64,
+}
+
+impl SyntheticSpec {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.symbol.is_empty() || self.symbol.len() > 27 || !self.symbol.is_ascii() {
+            return Err(format!(
+                "Synthetic symbol must be 1-27 ASCII chars: {:?}",
+                self.symbol
+            ));
+        }
+        if self.symbol.contains('|') || self.symbol.contains(':') {
+            return Err(format!(
+                "Synthetic symbol cannot contain '|' or ':': {:?}",
+                self.symbol
+            ));
+        }
+        if self.symbol.starts_with(SYMBOL_PREFIX) {
+            return Err(format!(
+                "Synthetic symbol cannot start with '{SYMBOL_PREFIX}': {:?}",
+                self.symbol
+            ));
+        }
+        // Saved tickers end with the connection they're pinned to after this mark
+        if self.symbol.contains(crate::CONNECTION_MARK) {
+            return Err(format!(
+                "Synthetic symbol cannot contain '{}': {:?}",
+                crate::CONNECTION_MARK,
+                self.symbol
+            ));
+        }
+        if self.legs.iter().any(|leg| leg.multiplier == 0.0) {
+            return Err(format!("{}: leg multipliers must be non-zero", self.symbol));
+        }
+        if self.legs[0].ticker_info == self.legs[1].ticker_info {
+            return Err(format!("{}: legs must be different tickers", self.symbol));
+        }
+        Ok(())
+    }
+
+    /// The synthetic ticker lives on the first leg's exchange so existing routing applies, under
+    /// [`SYMBOL_PREFIX`] so it never shadows one of its tickers
+    pub fn ticker_info(&self) -> TickerInfo {
+        let [a, b] = self.legs;
+        let ticker = Ticker::new(
+            &format!("{SYMBOL_PREFIX}{}", self.symbol),
+            a.ticker_info.exchange(),
+        );
+
+        let min_ticksize = match self.formula {
+            Formula::Difference => {
+                let tick = |leg: Leg| leg.ticker_info.min_ticksize.as_f32() * leg.multiplier.abs();
+                tick(a).min(tick(b))
+            }
+            Formula::Ratio => RATIO_TICKSIZE,
+        };
+
+        TickerInfo::new(ticker, min_ticksize, a.ticker_info.min_qty.as_f32(), None)
+    }
+
+    pub fn value(&self, a: f32, b: f32) -> Option<f32> {
+        let a = a * self.legs[0].multiplier;
+        let b = b * self.legs[1].multiplier;
+
+        let value = match self.formula {
+            Formula::Difference => a - b,
+            Formula::Ratio if b == 0.0 => return None,
+            Formula::Ratio => a / b,
+        };
+
+        value.is_finite().then_some(value)
+    }
+
+    /// Whether a rising price on `leg` moves the synthetic up
+    fn moves_with(&self, leg: usize) -> bool {
+        let sign = match self.formula {
+            Formula::Difference => self.legs[leg].multiplier,
+            Formula::Ratio => self.legs[0].multiplier * self.legs[1].multiplier,
+        };
+
+        (sign > 0.0) == (leg == 0)
+    }
+
+    fn stream_kind(&self, push_freq: PushFrequency) -> StreamKind {
+        StreamKind::DepthAndTrades {
+            ticker_info: self.ticker_info(),
+            depth_aggr: StreamTicksize::Client,
+            push_freq,
+        }
+    }
+}
+
+// ============================================================================
+// Registry
+// ============================================================================
+
+/// Synthetic definitions loaded from the saved state, looked up by ticker when subscribing
+static REGISTRY: RwLock<Vec<SyntheticSpec>> = RwLock::new(Vec::new());
+
+/// Replace the registered synthetic definitions, dropping invalid ones
+pub fn register(specs: Vec<SyntheticSpec>) {
+    let valid = valid_specs(specs);
+
+    if let Ok(mut registry) = REGISTRY.write() {
+        *registry = valid;
+    }
+}
+
+/// `specs` without the invalid ones and the ones whose ticker an earlier one already took
+fn valid_specs(specs: Vec<SyntheticSpec>) -> Vec<SyntheticSpec> {
+    let mut valid: Vec<SyntheticSpec> = vec![];
+
+    for spec in specs {
+        let taken = valid
+            .iter()
+            .any(|other| other.ticker_info().ticker == spec.ticker_info().ticker);
+        match spec.validate() {
+            Ok(()) if taken => {
+                log::warn!(
+                    "Ignoring synthetic definition: {} is defined twice",
+                    spec.symbol
+                );
+            }
+            Ok(()) => valid.push(spec),
+            Err(e) => log::warn!("Ignoring synthetic definition: {e}"),
+        }
+    }
+    valid
+}
+
+pub fn all() -> Vec<SyntheticSpec> {
+    REGISTRY.read().map(|r| r.clone()).unwrap_or_default()
+}
+
+pub fn lookup(ticker: &Ticker) -> Option<SyntheticSpec> {
+    if !ticker.as_str().starts_with(SYMBOL_PREFIX) {
+        return None;
+    }
+
+    REGISTRY.read().ok().and_then(|registry| {
+        registry
+            .iter()
+            .find(|spec| spec.ticker_info().ticker == *ticker)
+            .cloned()
+    })
+}
+
+pub fn is_synthetic(ticker: &Ticker) -> bool {
+    lookup(ticker).is_some()
+}
+
+// ============================================================================
+// Alignment
+// ============================================================================
+
+#[derive(Debug, Clone, Copy)]
+struct Quote {
+    time: u64,
+    price: f32,
+}
+
+/// Tracks the latest quote of each leg and combines them when they're close enough in time
+pub struct Aligner {
+    spec: SyntheticSpec,
+    quotes: [Option<Quote>; 2],
+}
+
+impl Aligner {
+    pub fn new(spec: SyntheticSpec) -> Self {
+        Self {
+            spec,
+            quotes: [None, None],
+        }
+    }
+
+    /// Records a quote for `leg` and returns the synthetic value, if both legs are fresh
+    pub fn update(&mut self, leg: usize, time: u64, price: f32) -> Option<f32> {
+        self.quotes[leg] = Some(Quote { time, price });
+
+        let (a, b) = (self.quotes[0]?, self.quotes[1]?);
+        if a.time.abs_diff(b.time) > self.spec.tolerance_ms {
+            return None;
+        }
+
+        self.spec.value(a.price, b.price)
+    }
+
+    /// Forget a leg's last quote, e.g. after its stream disconnected
+    pub fn reset(&mut self, leg: usize) {
+        self.quotes[leg] = None;
+    }
+
+    fn trade(&mut self, leg: usize, trade: &Trade, ticker_info: TickerInfo) -> Option<Trade> {
+        let value = self.update(leg, trade.time, trade.price.to_f32())?;
+
+        Some(Trade {
+            time: trade.time,
+            is_sell: if self.spec.moves_with(leg) {
+                trade.is_sell
+            } else {
+                !trade.is_sell
+            },
+            price: Price::from_f32(value).round_to_min_tick(ticker_info.min_ticksize),
+            qty: trade.qty,
+        })
+    }
+}
+
+// ============================================================================
+// Historical data
+// ============================================================================
+
+/// Combines two legs' klines bar by bar, skipping bars missing on either side.
+///
+/// High/low are approximated from same-side extremes, since the legs' intrabar paths are unknown.
+pub fn combine_klines(spec: &SyntheticSpec, a: &[Kline], b: &[Kline]) -> Vec<Kline> {
+    let ticker_info = spec.ticker_info();
+    let by_time: HashMap<u64, &Kline> = b.iter().map(|k| (k.time, k)).collect();
+
+    a.iter()
+        .filter_map(|ka| {
+            let kb = by_time.get(&ka.time)?;
+            let value = |pa: Price, pb: Price| spec.value(pa.to_f32(), pb.to_f32());
+
+            let open = value(ka.open, kb.open)?;
+            let close = value(ka.close, kb.close)?;
+            let hh = value(ka.high, kb.high)?;
+            let ll = value(ka.low, kb.low)?;
+
+            Some(Kline::new(
+                ka.time,
+                open,
+                open.max(close).max(hh).max(ll),
+                open.min(close).min(hh).min(ll),
+                close,
+                ka.volume,
+                ticker_info.min_ticksize,
+            ))
+        })
+        .collect()
+}
+
+pub async fn fetch_klines(
+    ticker_info: TickerInfo,
+    timeframe: Timeframe,
+    range: Option<(u64, u64)>,
+) -> Result<Vec<Kline>, AdapterError> {
+    let spec = lookup(&ticker_info.ticker).ok_or_else(|| {
+        AdapterError::InvalidRequest(format!("Unknown synthetic ticker: {}", ticker_info.ticker))
+    })?;
+
+    let [a, b] = spec.legs;
+    let (klines_a, klines_b) = futures_util::future::try_join(
+        Box::pin(adapter::fetch_klines(a.ticker_info, timeframe, range)),
+        Box::pin(adapter::fetch_klines(b.ticker_info, timeframe, range)),
+    )
+    .await?;
+
+    Ok(combine_klines(&spec, &klines_a, &klines_b))
+}
+
+// ============================================================================
+// Streams
+// ============================================================================
+
+/// Both legs' depth streams, tagged with the leg index
+fn leg_events(
+    spec: &SyntheticSpec,
+    push_freq: PushFrequency,
+) -> impl Stream<Item = (usize, Event)> {
+    let [a, b] = spec.legs;
+
+    futures_util::stream::select(
+        adapter::connect_depth_stream(a.ticker_info, None, push_freq).map(|e| (0, e)),
+        adapter::connect_depth_stream(b.ticker_info, None, push_freq).map(|e| (1, e)),
+    )
+}
+
+/// Feeds one leg event into the aligner, returning the synthetic trades and latest value
+fn on_leg_event(
+    aligner: &mut Aligner,
+    ticker_info: TickerInfo,
+    leg: usize,
+    event: &Event,
+) -> (Vec<Trade>, Option<(u64, f32)>) {
+    match event {
+        Event::DepthReceived(_, time, depth, trades) => {
+            let synthetic_trades = trades
+                .iter()
+                .filter_map(|trade| aligner.trade(leg, trade, ticker_info))
+                .collect();
+
+            let value = depth
+                .mid_price()
+                .and_then(|mid| aligner.update(leg, *time, mid.to_f32()))
+                .map(|value| (*time, value));
+
+            (synthetic_trades, value)
+        }
+        Event::Disconnected(..) => {
+            aligner.reset(leg);
+            (Vec::new(), None)
+        }
+        Event::Connected(_) | Event::KlineReceived(..) | Event::MarketStateChanged(..) => {
+            (Vec::new(), None)
+        }
+    }
+}
+
+/// Depth-and-trades stream for a registered synthetic ticker.
+///
+/// The synthetic has no order book of its own, so depth events carry an empty book.
+pub fn connect_market_stream(
+    ticker_info: TickerInfo,
+    push_freq: PushFrequency,
+) -> impl Stream<Item = Event> {
+    stream::channel(100, async move |mut output| {
+        let Some(spec) = lookup(&ticker_info.ticker) else {
+            log::warn!("No synthetic definition for {}", ticker_info.ticker);
+            return;
+        };
+
+        let exchange = ticker_info.exchange();
+        let stream_kind = spec.stream_kind(push_freq);
+        let empty_depth = Arc::new(Depth::default());
+
+        let mut aligner = Aligner::new(spec.clone());
+        let mut legs = std::pin::pin!(leg_events(&spec, push_freq));
+
+        let _ = output.send(Event::Connected(exchange)).await;
+
+        while let Some((leg, event)) = legs.next().await {
+            if let Event::Disconnected(_, reason) = &event {
+                log::info!("{} leg {leg} disconnected: {reason}", spec.symbol);
+            }
+
+            let (trades, _) = on_leg_event(&mut aligner, ticker_info, leg, &event);
+
+            if let Some(last) = trades.last() {
+                let event = Event::DepthReceived(
+                    stream_kind,
+                    last.time,
+                    Arc::clone(&empty_depth),
+                    trades.into_boxed_slice(),
+                );
+                if output.send(event).await.is_err() {
+                    break;
+                }
+            }
+        }
+
+        let _ = output
+            .send(Event::Disconnected(
+                exchange,
+                format!("{} legs closed", spec.symbol),
+            ))
+            .await;
+    })
+}
+
+/// Live kline stream for a registered synthetic ticker, built from aligned leg quotes
+pub fn connect_kline_stream(
+    ticker_info: TickerInfo,
+    timeframe: Timeframe,
+) -> impl Stream<Item = Event> {
+    stream::channel(100, async move |mut output| {
+        let Some(spec) = lookup(&ticker_info.ticker) else {
+            log::warn!("No synthetic definition for {}", ticker_info.ticker);
+            return;
+        };
+
+        let exchange = ticker_info.exchange();
+        let stream_kind = StreamKind::Kline {
+            ticker_info,
+            timeframe,
+        };
+
+        let mut aligner = Aligner::new(spec.clone());
+        let mut bar = BarBuilder::new(ticker_info, timeframe);
+        let mut legs = std::pin::pin!(leg_events(&spec, PushFrequency::ServerDefault));
+
+        'legs: while let Some((leg, event)) = legs.next().await {
+            let (trades, value) = on_leg_event(&mut aligner, ticker_info, leg, &event);
+
+            for trade in &trades {
+                let volume = if trade.is_sell {
+                    (0.0, trade.qty)
+                } else {
+                    (trade.qty, 0.0)
+                };
+                bar.update(trade.time, trade.price.to_f32(), volume);
+            }
+            if let Some((time, value)) = value {
+                bar.update(time, value, (0.0, 0.0));
+            }
+
+            for (kline, status) in bar.drain() {
+                if output
+                    .send(Event::KlineReceived(stream_kind, kline, status))
+                    .await
+                    .is_err()
+                {
+                    break 'legs;
+                }
+            }
+        }
+
+        let _ = output
+            .send(Event::Disconnected(
+                exchange,
+                format!("{} legs closed", spec.symbol),
+            ))
+            .await;
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::Exchange;
+
+    fn leg(symbol: &str, tick: f32) -> Leg {
+        Leg {
+            ticker_info: TickerInfo::new(
+                Ticker::new(symbol, Exchange::MetaTrader5),
+                tick,
+                0.01,
+                Some(100_000.0),
+            ),
+            multiplier: 1.0,
+        }
+    }
+
+    fn spread() -> SyntheticSpec {
+        SyntheticSpec {
+            symbol: "EURUSD-GBPUSD".to_string(),
+            formula: Formula::Difference,
+            legs: [leg("EURUSD", 0.00001), leg("GBPUSD", 0.00001)],
+            tolerance_ms: 500,
+        }
+    }
+
+    #[test]
+    fn aligner_pauses_on_stale_leg() {
+        let mut aligner = Aligner::new(spread());
+
+        assert_eq!(aligner.update(0, 1_000, 1.10), None, "second leg missing");
+
+        let value = aligner.update(1, 1_200, 1.25).expect("both legs fresh");
+        assert!((value - -0.15).abs() < 1e-6);
+
+        // Leg A moves on, leg B's last quote is now older than the tolerance
+        assert_eq!(aligner.update(0, 1_800, 1.11), None);
+
+        // Once leg B quotes again the output resumes
+        assert!(aligner.update(1, 1_900, 1.26).is_some());
+
+        aligner.reset(1);
+        assert_eq!(aligner.update(0, 1_950, 1.12), None);
+    }
+
+    #[test]
+    fn trade_side_follows_formula_sign() {
+        let mut aligner = Aligner::new(spread());
+        let ticker_info = spread().ticker_info();
+        aligner.update(0, 0, 1.10);
+
+        let buy_b = Trade {
+            time: 10,
+            is_sell: false,
+            price: Price::from_f32(1.25),
+            qty: 1.0,
+        };
+        let trade = aligner.trade(1, &buy_b, ticker_info).unwrap();
+
+        // Buying the subtracted leg pushes the spread down
+        assert!(trade.is_sell);
+        assert_eq!(
+            trade.price,
+            Price::from_f32(-0.15).round_to_min_tick(ticker_info.min_ticksize)
+        );
+    }
+
+    #[test]
+    fn combine_klines_skips_unaligned_bars() {
+        let spec = SyntheticSpec {
+            formula: Formula::Ratio,
+            ..spread()
+        };
+        let tick = spec.legs[0].ticker_info.min_ticksize;
+        let bar = |time, c: f32| Kline::new(time, c, c, c, c, (1.0, 1.0), tick);
+
+        let a = [bar(0, 1.0), bar(60_000, 1.2), bar(120_000, 1.3)];
+        let b = [bar(0, 2.0), bar(120_000, 2.6)];
+
+        let combined = combine_klines(&spec, &a, &b);
+        assert_eq!(combined.len(), 2);
+        assert_eq!(combined[0].time, 0);
+        assert_eq!(combined[1].time, 120_000);
+        assert_eq!(combined[1].close, Price::from_f32(0.5));
+    }
+
+    #[test]
+    fn validate_rejects_bad_specs() {
+        assert!(spread().validate().is_ok());
+
+        let same_legs = SyntheticSpec {
+            legs: [leg("EURUSD", 0.00001), leg("EURUSD", 0.00001)],
+            ..spread()
+        };
+        assert!(same_legs.validate().is_err());
+
+        let bad_symbol = SyntheticSpec {
+            symbol: "A|B".to_string(),
+            ..spread()
+        };
+        assert!(bad_symbol.validate().is_err());
+
+        let prefixed = SyntheticSpec {
+            symbol: format!("{SYMBOL_PREFIX}EURGBP"),
+            ..spread()
+        };
+        assert!(prefixed.validate().is_err());
+    }
+
+    #[test]
+    fn synthetics_never_take_over_a_listed_ticker() {
+        // Named like its first leg, which is listed on the same exchange
+        let shadowing = SyntheticSpec {
+            symbol: "EURUSD".to_string(),
+            ..spread()
+        };
+        let listed = shadowing.legs[0].ticker_info.ticker;
+
+        assert!(shadowing.validate().is_ok());
+        assert_ne!(shadowing.ticker_info().ticker, listed);
+        assert!(!is_synthetic(&listed));
+        assert_eq!(
+            shadowing.ticker_info().ticker.to_full_symbol_and_type().0,
+            "=EURUSD"
+        );
+
+        // A second definition of the same ticker is dropped, not left to shadow the first
+        let valid = valid_specs(vec![shadowing.clone(), shadowing.clone(), spread()]);
+        assert_eq!(valid, [shadowing, spread()]);
+    }
+
+    #[test]
+    fn synthetic_tickers_survive_a_save() {
+        // Looks like the connection id a saved ticker may end with
+        for symbol in ["0123456789ABCDEF", "0123456789abcdef", "EUR=USD"] {
+            let spec = SyntheticSpec {
+                symbol: symbol.to_string(),
+                ..spread()
+            };
+            assert!(spec.validate().is_ok(), "{symbol}");
+
+            let ticker = spec.ticker_info().ticker;
+            let json = serde_json::to_string(&ticker).unwrap();
+            let restored: Ticker = serde_json::from_str(&json).unwrap();
+            assert_eq!(restored, ticker, "{json}");
+            assert_eq!(restored.connection(), None);
+        }
+
+        for symbol in ["A~0123456789abcdef", "~EURUSD", "EURUSD~"] {
+            let spec = SyntheticSpec {
+                symbol: symbol.to_string(),
+                ..spread()
+            };
+            assert!(spec.validate().is_err(), "{symbol}");
+        }
+    }
+}
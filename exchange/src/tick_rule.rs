@@ -0,0 +1,255 @@
+//! Tick sizes that depend on the price, for instruments quoted in tiers such as 0.25 below 1000
+//! and 0.5 above.
+//!
+//! A tiered symbol's prices are rounded to the tick of the tier they fall in, and grouped levels
+//! never get finer than that tick, so a book straddling a threshold buckets evenly on both sides.
+//! Tables are interned, so [`TickerInfo`](crate::TickerInfo) stays small and `Copy` and every
+//! listing of a symbol shares one.
+
+use crate::util::{MinTicksize, Price, PriceStep};
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{LazyLock, Mutex};
+
+/// Most tiers a table holds, longer tables are refused
+pub const MAX_TIERS: usize = 16;
+
+type Table = [(Price, PriceStep)];
+
+static INTERNED: LazyLock<Mutex<HashSet<&'static Table>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// Ticks by price, each applying from its threshold up to the next one's
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(try_from = "Vec<(f64, f64)>", into = "Vec<(f64, f64)>")]
+pub struct TickTiers {
+    tiers: &'static Table,
+}
+
+impl TickTiers {
+    /// A table of `(threshold, tick)` pairs in ascending thresholds, prices below the first
+    /// threshold take its tick
+    pub fn new(table: &[(f64, f64)]) -> Result<Self, String> {
+        if table.is_empty() {
+            return Err("the tick table is empty".to_string());
+        }
+        if table.len() > MAX_TIERS {
+            return Err(format!(
+                "the tick table has {} tiers, at most {MAX_TIERS} are supported",
+                table.len()
+            ));
+        }
+
+        let scale = 10f64.powi(Price::PRICE_SCALE);
+        let mut tiers: Vec<(Price, PriceStep)> = Vec::with_capacity(table.len());
+
+        for &(threshold, tick) in table {
+            if !threshold.is_finite() || !tick.is_finite() || (tick * scale).round() < 1.0 {
+                return Err(format!("tier {threshold} has an invalid tick {tick}"));
+            }
+
+            // In f64, f32 can't hold four digit prices to eight decimals
+            let threshold = Price::from_units((threshold * scale).round() as i64);
+            if tiers.last().is_some_and(|(last, _)| threshold <= *last) {
+                return Err("the tick table's thresholds are not ascending".to_string());
+            }
+            let tick = PriceStep {
+                units: (tick * scale).round() as i64,
+            };
+            tiers.push((threshold, tick));
+        }
+
+        let mut interned = INTERNED.lock().unwrap_or_else(|e| e.into_inner());
+        let tiers = match interned.get(tiers.as_slice()) {
+            Some(known) => *known,
+            None => {
+                let leaked: &'static Table = Box::leak(tiers.into_boxed_slice());
+                interned.insert(leaked);
+                leaked
+            }
+        };
+
+        Ok(Self { tiers })
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Price, PriceStep)> + '_ {
+        self.tiers.iter().copied()
+    }
+
+    /// Tick of the tier `price` falls in
+    pub fn tick_at(&self, price: Price) -> PriceStep {
+        self.iter()
+            .take_while(|(threshold, _)| *threshold <= price)
+            .last()
+            .unwrap_or(self.tiers[0])
+            .1
+    }
+
+    pub fn finest(&self) -> PriceStep {
+        self.iter()
+            .map(|(_, tick)| tick)
+            .min_by_key(|tick| tick.units)
+            .unwrap_or(self.tiers[0].1)
+    }
+
+    /// Decimals every tier's prices are written with
+    pub fn precision(&self) -> MinTicksize {
+        let power = self
+            .iter()
+            .map(|(_, tick)| {
+                let (mut units, mut power) = (tick.units, -Price::PRICE_SCALE);
+                while units % 10 == 0 && power < 0 {
+                    units /= 10;
+                    power += 1;
+                }
+                power
+            })
+            .min()
+            .unwrap_or(0);
+
+        MinTicksize::new(power as i8)
+    }
+
+    /// `step` widened to a whole number of the ticks at `price`
+    fn bucket_at(&self, price: Price, step: PriceStep) -> PriceStep {
+        let tick = self.tick_at(price).units;
+        let ticks = (step.units.max(1) + tick - 1) / tick;
+        PriceStep {
+            units: ticks * tick,
+        }
+    }
+}
+
+impl TryFrom<Vec<(f64, f64)>> for TickTiers {
+    type Error = String;
+
+    fn try_from(table: Vec<(f64, f64)>) -> Result<Self, Self::Error> {
+        Self::new(&table)
+    }
+}
+
+impl From<TickTiers> for Vec<(f64, f64)> {
+    fn from(tiers: TickTiers) -> Self {
+        let scale = 10f64.powi(Price::PRICE_SCALE);
+        tiers
+            .iter()
+            .map(|(threshold, tick)| (threshold.units as f64 / scale, tick.units as f64 / scale))
+            .collect()
+    }
+}
+
+/// How a symbol's prices are rounded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TickRule {
+    Fixed(MinTicksize),
+    Tiered(TickTiers),
+}
+
+impl TickRule {
+    /// The nearest price the symbol can quote
+    pub fn round(self, price: Price) -> Price {
+        match self {
+            TickRule::Fixed(min_ticksize) => price.round_to_min_tick(min_ticksize),
+            TickRule::Tiered(tiers) => price.round_to_step(tiers.tick_at(price)),
+        }
+    }
+
+    /// The level `price` is grouped into at `step`, bids round down and asks up. Tiered prices
+    /// group by whole ticks of their tier, at least one.
+    pub fn group(self, price: Price, is_bid: bool, step: PriceStep) -> Price {
+        match self {
+            TickRule::Fixed(_) => price.round_to_side_step(is_bid, step),
+            TickRule::Tiered(tiers) => {
+                price.round_to_side_step(is_bid, tiers.bucket_at(price, step))
+            }
+        }
+    }
+
+    pub fn precision(self) -> MinTicksize {
+        match self {
+            TickRule::Fixed(min_ticksize) => min_ticksize,
+            TickRule::Tiered(tiers) => tiers.precision(),
+        }
+    }
+}
+
+impl From<MinTicksize> for TickRule {
+    fn from(min_ticksize: MinTicksize) -> Self {
+        TickRule::Fixed(min_ticksize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quarters_then_halves() -> TickTiers {
+        TickTiers::new(&[(0.0, 0.25), (1000.0, 0.5)]).unwrap()
+    }
+
+    fn price(value: f64) -> Price {
+        Price::from_units((value * 1e8).round() as i64)
+    }
+
+    fn ticks(value: f64) -> PriceStep {
+        PriceStep {
+            units: (value * 1e8).round() as i64,
+        }
+    }
+
+    #[test]
+    fn rounding_switches_ticks_at_the_boundary() {
+        let rule = TickRule::Tiered(quarters_then_halves());
+
+        assert_eq!(rule.round(price(999.8)), price(999.75));
+        assert_eq!(rule.round(price(999.9)), price(1000.0));
+        assert_eq!(rule.round(price(1000.0)), price(1000.0));
+        // Past the threshold quarters aren't quoted anymore
+        assert_eq!(rule.round(price(1000.2)), price(1000.0));
+        assert_eq!(rule.round(price(1000.3)), price(1000.5));
+        assert_eq!(rule.round(price(1000.74)), price(1000.5));
+        assert_eq!(rule.round(price(1001.0)), price(1001.0));
+
+        // A power of ten tick would have rounded quarters to tenths
+        let fixed = TickRule::from(MinTicksize::from(0.25));
+        assert_eq!(fixed.round(price(999.25)), price(999.3));
+        assert_eq!(rule.round(price(999.25)), price(999.25));
+    }
+
+    #[test]
+    fn levels_group_by_their_tier() {
+        let rule = TickRule::Tiered(quarters_then_halves());
+        let step = ticks(0.25);
+
+        // At the finest step each tier keeps its own ticks
+        assert_eq!(rule.group(price(999.75), true, step), price(999.75));
+        assert_eq!(rule.group(price(1000.5), true, step), price(1000.5));
+
+        // Three quarters is a whole number of quarters, above the threshold it widens to a point
+        let step = ticks(0.75);
+        assert_eq!(rule.group(price(999.5), true, step), price(999.0));
+        assert_eq!(rule.group(price(1001.5), true, step), price(1001.0));
+        assert_eq!(rule.group(price(1001.5), false, step), price(1002.0));
+    }
+
+    #[test]
+    fn tables_are_checked_and_round_trip() {
+        let tiers = quarters_then_halves();
+        assert_eq!(tiers.tick_at(price(5.0)), ticks(0.25));
+        assert_eq!(tiers.tick_at(price(5000.0)), ticks(0.5));
+        assert_eq!(tiers.finest(), ticks(0.25));
+        assert_eq!(tiers.precision(), MinTicksize::new(-2));
+        // Listings of the same table share it
+        assert!(std::ptr::eq(quarters_then_halves().tiers, tiers.tiers));
+
+        assert!(TickTiers::new(&[]).is_err());
+        assert!(TickTiers::new(&[(1000.0, 0.5), (0.0, 0.25)]).is_err());
+        assert!(TickTiers::new(&[(0.0, 0.0)]).is_err());
+
+        let json = serde_json::to_string(&tiers).unwrap();
+        assert_eq!(json, "[[0.0,0.25],[1000.0,0.5]]");
+        assert_eq!(serde_json::from_str::<TickTiers>(&json).unwrap(), tiers);
+        assert!(serde_json::from_str::<TickTiers>("[[1,0.5],[0,0.25]]").is_err());
+    }
+}
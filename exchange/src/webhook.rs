@@ -0,0 +1,18 @@
+//! Posting alerts to webhooks the user set up, e.g. a chat bot or an automation service.
+
+use crate::adapter::AdapterError;
+use crate::limiter::HTTP_CLIENT;
+
+use serde_json::Value;
+
+/// Posts `body` as JSON to `url`, a non-success status is an error
+pub async fn post(url: String, body: Value) -> Result<(), AdapterError> {
+    HTTP_CLIENT
+        .post(&url)
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -5,20 +5,30 @@ pub const BUY_SOUND_DATA: &[u8] = include_bytes!("../assets/sounds/hard-typewrit
 pub const HARD_BUY_SOUND_DATA: &[u8] = include_bytes!("../assets/sounds/dry-pop-up.wav");
 pub const SELL_SOUND_DATA: &[u8] = include_bytes!("../assets/sounds/hard-typewriter-hit.wav");
 pub const HARD_SELL_SOUND_DATA: &[u8] = include_bytes!("../assets/sounds/fall-on-foam-splash.wav");
+pub const BAR_CLOSE_SOUND_DATA: &[u8] = include_bytes!("../assets/sounds/soft-chime.wav");
+pub const ALERT_SOUND_DATA: &[u8] = include_bytes!("../assets/sounds/alert-bell.wav");
 
 pub const BUY_SOUND: &str = "hard-typewriter-click.wav";
 pub const HARD_BUY_SOUND: &str = "dry-pop-up.wav";
 pub const SELL_SOUND: &str = "hard-typewriter-hit.wav";
 pub const HARD_SELL_SOUND: &str = "fall-on-foam-splash.wav";
+pub const BAR_CLOSE_SOUND: &str = "soft-chime.wav";
+pub const ALERT_SOUND: &str = "alert-bell.wav";
 
 const OVERLAP_THRESHOLD: Duration = Duration::from_millis(10);
 
+const SOUND_COUNT: usize = 6;
+
 #[derive(Clone, Copy)]
 pub enum SoundType {
     Buy = 0,
     HardBuy = 1,
     Sell = 2,
     HardSell = 3,
+    /// A bar closing on a chart with a bar close alert
+    BarClose = 4,
+    /// A price or spread alert going off
+    Alert = 5,
 }
 
 impl std::fmt::Display for SoundType {
@@ -31,6 +41,8 @@ impl std::fmt::Display for SoundType {
                 Self::HardBuy => HARD_BUY_SOUND,
                 Self::Sell => SELL_SOUND,
                 Self::HardSell => HARD_SELL_SOUND,
+                Self::BarClose => BAR_CLOSE_SOUND,
+                Self::Alert => ALERT_SOUND,
             }
         )
     }
@@ -46,8 +58,8 @@ pub struct SoundCache {
     _stream: OutputStream,
     stream_handle: OutputStreamHandle,
     volume: Option<f32>,
-    sample_buffers: [Option<rodio::buffer::SamplesBuffer<i16>>; 4],
-    last_played: [(Option<Instant>, usize); 4],
+    sample_buffers: [Option<rodio::buffer::SamplesBuffer<i16>>; SOUND_COUNT],
+    last_played: [(Option<Instant>, usize); SOUND_COUNT],
 }
 
 impl SoundCache {
@@ -63,8 +75,8 @@ impl SoundCache {
             _stream: stream,
             stream_handle,
             volume,
-            sample_buffers: [None, None, None, None],
-            last_played: [(None, 0), (None, 0), (None, 0), (None, 0)],
+            sample_buffers: std::array::from_fn(|_| None),
+            last_played: [(None, 0); SOUND_COUNT],
         })
     }
 
@@ -76,6 +88,8 @@ impl SoundCache {
             SoundType::HardBuy,
             SoundType::Sell,
             SoundType::HardSell,
+            SoundType::BarClose,
+            SoundType::Alert,
         ];
 
         for sound_type in &sound_types {
@@ -84,6 +98,8 @@ impl SoundCache {
                 SoundType::HardBuy => (HARD_BUY_SOUND, HARD_BUY_SOUND_DATA),
                 SoundType::Sell => (SELL_SOUND, SELL_SOUND_DATA),
                 SoundType::HardSell => (HARD_SELL_SOUND, HARD_SELL_SOUND_DATA),
+                SoundType::BarClose => (BAR_CLOSE_SOUND, BAR_CLOSE_SOUND_DATA),
+                SoundType::Alert => (ALERT_SOUND, ALERT_SOUND_DATA),
             };
 
             if let Err(e) = cache.load_sound_from_memory(*sound_type, data) {
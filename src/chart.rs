@@ -1,13 +1,21 @@
 pub mod comparison;
+mod coverage;
 pub mod heatmap;
 pub mod indicator;
 pub mod kline;
 mod scale;
+pub mod strip;
 
 use crate::style;
 use crate::widget::multi_split::{DRAG_SIZE, MultiSplit};
 use crate::widget::tooltip;
-use data::chart::{Autoscale, Basis, PlotData, ViewConfig, indicator::Indicator};
+use data::chart::{
+    Autoscale, Basis, PlotData, PriceScale, ViewConfig, Viewport,
+    data_window::Corner,
+    drawing::{self, Anchor, Drawing},
+    indicator::Indicator,
+    levels::{LevelKind, NamedLevel},
+};
 use exchange::TickerInfo;
 use exchange::fetcher::{FetchRange, FetchRequests, FetchSpec, RequestHandler};
 use exchange::util::{Price, PriceStep};
@@ -18,7 +26,7 @@ use iced::theme::palette::Extended;
 use iced::widget::canvas::{self, Cache, Canvas, Event, Frame, LineDash, Path, Stroke};
 use iced::{
     Alignment, Element, Length, Point, Rectangle, Size, Theme, Vector, keyboard, mouse, padding,
-    widget::{button, center, column, container, mouse_area, row, rule, text},
+    widget::{button, center, column, container, mouse_area, opaque, row, rule, stack, text},
 };
 
 const ZOOM_SENSITIVITY: f32 = 30.0;
@@ -38,6 +46,29 @@ pub enum Interaction {
     Ruler {
         start: Option<Point>,
     },
+    Drawing {
+        tool: DrawingTool,
+        start: Option<Anchor>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawingTool {
+    HorizontalLine,
+    Rectangle,
+    /// Entry then stop, hands both prices to the position size calculator
+    PositionSize,
+}
+
+/// How the canvas routes input while a bar replay is set up or running
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplayInput {
+    #[default]
+    Off,
+    /// The next left click picks the bar the replay starts from
+    PickingStart,
+    /// Arrow keys step bars, space plays or pauses
+    Stepping,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -57,6 +88,24 @@ pub enum Message {
     BoundsChanged(Rectangle),
     SplitDragged(usize, f32),
     DoubleClick(AxisScaleClicked),
+    DrawingAdded(Drawing),
+    DrawingRemoved(usize),
+    LevelAlertRequested(NamedLevel),
+    PriceAlertRemoved(usize),
+    BracketPlaced {
+        entry: Price,
+        stop: Price,
+    },
+    ReplayStartPicked(u64),
+    ReplayStepped(isize),
+    ReplayPlayToggled,
+    /// Right click on the price axis, at this height of the chart canvas
+    PriceMenuOpened(f32),
+    PriceMenuDismissed,
+    AlertAt(Price),
+    PriceCopied(Price),
+    /// A missing stretch of history clicked on the coverage strip, from and until in unix ms
+    CoverageBackfill(u64, u64),
 }
 
 pub trait Chart: PlotConstants + canvas::Program<Message> {
@@ -81,6 +130,17 @@ pub trait Chart: PlotConstants + canvas::Program<Message> {
     fn supports_fit_autoscaling(&self) -> bool;
 
     fn is_empty(&self) -> bool;
+
+    fn supports_drawings(&self) -> bool;
+
+    /// The bar at `interval`, drawings snap to its OHLC values
+    fn kline_at(&self, interval: u64) -> Option<exchange::Kline>;
+
+    /// Whether the pane keeps price alerts for this chart
+    fn supports_price_alerts(&self) -> bool;
+
+    /// What of the history was received, shown under the time axis when not empty
+    fn coverage(&self) -> &[data::kline_store::Stretch];
 }
 
 fn canvas_interaction<T: Chart>(
@@ -120,6 +180,17 @@ fn canvas_interaction<T: Chart>(
                 mouse::Event::ButtonPressed(button) => {
                     let cursor_in_bounds = cursor_position?;
 
+                    if let mouse::Button::Left = button
+                        && state.replay_input == ReplayInput::PickingStart
+                        && matches!(interaction, Interaction::None)
+                    {
+                        let anchor = anchor_at(chart, cursor_in_bounds, bounds.size());
+                        return Some(
+                            canvas::Action::publish(Message::ReplayStartPicked(anchor.time))
+                                .and_capture(),
+                        );
+                    }
+
                     if let mouse::Button::Left = button {
                         match interaction {
                             Interaction::None
@@ -138,7 +209,51 @@ fn canvas_interaction<T: Chart>(
                             Interaction::Ruler { .. } => {
                                 *interaction = Interaction::None;
                             }
+                            Interaction::Drawing { tool, start } => {
+                                let anchor = anchor_at(chart, cursor_in_bounds, bounds.size());
+
+                                let drawing = match (*tool, *start) {
+                                    (DrawingTool::PositionSize, None) => {
+                                        *start = Some(anchor);
+                                        None
+                                    }
+                                    (DrawingTool::PositionSize, Some(start)) => {
+                                        *interaction = Interaction::None;
+                                        return Some(
+                                            canvas::Action::publish(Message::BracketPlaced {
+                                                entry: start.price,
+                                                stop: anchor.price,
+                                            })
+                                            .and_capture(),
+                                        );
+                                    }
+                                    (DrawingTool::HorizontalLine, _) => {
+                                        Some(Drawing::HorizontalLine {
+                                            price: anchor.price,
+                                        })
+                                    }
+                                    (DrawingTool::Rectangle, None) => {
+                                        *start = Some(anchor);
+                                        None
+                                    }
+                                    (DrawingTool::Rectangle, Some(start)) => {
+                                        Some(Drawing::Rectangle { start, end: anchor })
+                                    }
+                                };
+
+                                if let Some(drawing) = drawing {
+                                    *interaction = Interaction::None;
+                                    return Some(
+                                        canvas::Action::publish(Message::DrawingAdded(drawing))
+                                            .and_capture(),
+                                    );
+                                }
+                            }
                         }
+                    } else if let mouse::Button::Right = button
+                        && let Some(message) = right_click_target(state, cursor_in_bounds, bounds)
+                    {
+                        return Some(canvas::Action::publish(message).and_capture());
                     }
                     Some(canvas::Action::request_redraw().and_capture())
                 }
@@ -150,7 +265,7 @@ fn canvas_interaction<T: Chart>(
                         );
                         Some(canvas::Action::publish(msg).and_capture())
                     }
-                    Interaction::None | Interaction::Ruler { .. } => {
+                    Interaction::None | Interaction::Ruler { .. } | Interaction::Drawing { .. } => {
                         Some(canvas::Action::publish(Message::CrosshairMoved))
                     }
                     _ => None,
@@ -252,6 +367,12 @@ fn canvas_interaction<T: Chart>(
                 _ => None,
             }
         }
+        Event::Keyboard(keyboard::Event::KeyPressed {
+            key: keyboard::Key::Named(keyboard::key::Named::Escape),
+            ..
+        }) if chart.state().price_menu.is_some() => {
+            Some(canvas::Action::publish(Message::PriceMenuDismissed).and_capture())
+        }
         Event::Keyboard(keyboard_event) => {
             cursor_position?;
             match keyboard_event {
@@ -264,6 +385,30 @@ fn canvas_interaction<T: Chart>(
                         *interaction = Interaction::None;
                         Some(canvas::Action::request_redraw().and_capture())
                     }
+                    keyboard::Key::Named(named)
+                        if chart.state().replay_input == ReplayInput::Stepping =>
+                    {
+                        let message = match named {
+                            keyboard::key::Named::ArrowLeft => Message::ReplayStepped(-1),
+                            keyboard::key::Named::ArrowRight => Message::ReplayStepped(1),
+                            keyboard::key::Named::Space => Message::ReplayPlayToggled,
+                            _ => return None,
+                        };
+                        Some(canvas::Action::publish(message).and_capture())
+                    }
+                    keyboard::Key::Character(c) if chart.supports_drawings() => {
+                        let tool = match c {
+                            "h" => DrawingTool::HorizontalLine,
+                            "p" => DrawingTool::PositionSize,
+                            "r" if matches!(chart.state().basis, Basis::Time(_)) => {
+                                DrawingTool::Rectangle
+                            }
+                            _ => return None,
+                        };
+
+                        *interaction = Interaction::Drawing { tool, start: None };
+                        Some(canvas::Action::request_redraw().and_capture())
+                    }
                     _ => None,
                 },
                 _ => None,
@@ -273,9 +418,157 @@ fn canvas_interaction<T: Chart>(
     }
 }
 
+/// Chart position under the cursor, rounded to the ticker's min tick and snapped to the OHLC of
+/// the bar below it when close enough
+fn anchor_at<T: Chart>(chart: &T, cursor: Point, bounds: Size) -> Anchor {
+    const SNAP_DISTANCE: f32 = 6.0;
+
+    let state = chart.state();
+    let point = state.canvas_to_chart(cursor, bounds);
+
+    let time = match state.basis {
+        Basis::Time(timeframe) => {
+            let interval = timeframe.to_milliseconds();
+            (state.x_to_interval(point.x) + interval / 2) / interval * interval
+        }
+        Basis::Tick(_) => state.x_to_interval(point.x),
+    };
+
+    let price = state
+        .y_to_price(point.y)
+        .round_to_min_tick(state.ticker_info.min_ticksize);
+    let tolerance = Price::from_units(
+        (state
+            .y_to_price(point.y - SNAP_DISTANCE / state.scaling)
+            .units
+            - state.y_to_price(point.y).units)
+            .abs(),
+    );
+
+    let price = match chart.kline_at(time) {
+        Some(kline) => drawing::snap_to_ohlc(price, &kline, tolerance),
+        None => price,
+    };
+
+    Anchor { time, price }
+}
+
+/// Right clicking removes a drawing or a price alert, or arms an alert on an auto level
+fn right_click_target(state: &ViewState, cursor: Point, bounds: Rectangle) -> Option<Message> {
+    const HIT_DISTANCE: f32 = 5.0;
+
+    if let Some(index) = drawing_at(state, cursor, bounds.size()) {
+        return Some(Message::DrawingRemoved(index));
+    }
+
+    let point = state.canvas_to_chart(cursor, bounds.size());
+    let near =
+        |price: Price| (state.price_to_y(price) - point.y).abs() * state.scaling <= HIT_DISTANCE;
+
+    if let Some(index) = state.price_alerts.iter().position(|price| near(*price)) {
+        return Some(Message::PriceAlertRemoved(index));
+    }
+
+    state
+        .levels
+        .iter()
+        .find(|level| near(level.price))
+        .map(|level| Message::LevelAlertRequested(*level))
+}
+
+/// Index of the topmost drawing under the cursor
+fn drawing_at(state: &ViewState, cursor: Point, bounds: Size) -> Option<usize> {
+    const HIT_DISTANCE: f32 = 5.0;
+
+    let point = state.canvas_to_chart(cursor, bounds);
+    let tolerance = HIT_DISTANCE / state.scaling;
+
+    state.drawings.iter().rposition(|drawing| match drawing {
+        Drawing::HorizontalLine { price } => {
+            (state.price_to_y(*price) - point.y).abs() <= tolerance
+        }
+        Drawing::Rectangle { .. } if !matches!(state.basis, Basis::Time(_)) => false,
+        Drawing::Rectangle { start, end } => {
+            let rect = state.anchors_to_rect(*start, *end);
+            rect.expand(tolerance).contains(point)
+        }
+    })
+}
+
+pub fn level_color(kind: LevelKind, palette: &Extended) -> iced::Color {
+    match kind {
+        LevelKind::SessionOpen => palette.secondary.strong.color,
+        LevelKind::SessionHigh => palette.success.base.color,
+        LevelKind::SessionLow => palette.danger.base.color,
+        LevelKind::PrevDayHigh | LevelKind::PrevDayLow | LevelKind::PrevDayClose => {
+            palette.primary.base.color
+        }
+    }
+}
+
 pub enum Action {
     ErrorOccurred(data::InternalError),
     RequestFetch(FetchRequests),
+    /// Something the user should know about the chart, shown as a warning toast
+    Notice(String),
+}
+
+/// Applies the framing saved with the layout once the chart has bounds and data, `oldest` being
+/// the oldest loaded interval. Returns a notice if the locked price range had to be given up.
+pub fn restore_viewport<T: Chart>(chart: &mut T, oldest: Option<u64>) -> Option<String> {
+    let state = chart.state();
+    let viewport = state.pending_viewport?;
+    if state.bounds.width <= 0.0 || chart.is_empty() {
+        return None;
+    }
+
+    let (min_width, max_width) = (T::min_cell_width(chart), T::max_cell_width(chart));
+    let (min_height, max_height) = (T::min_cell_height(chart), T::max_cell_height(chart));
+    let supports_fit_autoscaling = chart.supports_fit_autoscaling();
+
+    let state = chart.mut_state();
+    state.pending_viewport = None;
+    state.cell_width = viewport.cell_width.clamp(min_width, max_width);
+    state.cell_height = viewport.cell_height.clamp(min_height, max_height);
+
+    let right_edge = match state.basis {
+        Basis::Time(_) => {
+            oldest.and_then(|oldest| viewport.right_edge_within(oldest, state.latest_x))
+        }
+        Basis::Tick(_) => viewport
+            .right_edge
+            .map(|edge| edge.min(oldest.unwrap_or_default()))
+            .filter(|edge| *edge > 0),
+    };
+    if let Some(edge) = right_edge
+        && state.layout.autoscale != Some(Autoscale::CenterLatest)
+    {
+        let width = state.bounds.width / state.scaling;
+        state.translation.x = width / 2.0 - state.interval_to_x(edge);
+    }
+
+    let (Some((highest, lowest)), None) = (viewport.price_range, state.layout.autoscale) else {
+        return None;
+    };
+
+    let last_price = state.last_price.map(|label| label.price().to_f32_lossy());
+    if last_price.is_some_and(|price| !viewport.price_range_holds(price)) {
+        state.layout.autoscale = Some(if supports_fit_autoscaling {
+            Autoscale::FitToVisible
+        } else {
+            Autoscale::CenterLatest
+        });
+        return Some("Price left the saved price range, switched back to autoscale".to_string());
+    }
+
+    let height = state.bounds.height / state.scaling;
+    if let Some(cell_height) = state.cell_height_to_fit(highest, lowest, height) {
+        state.cell_height = cell_height.clamp(min_height, max_height);
+    }
+    let middle = state.layout.price_scale.interpolate(0.5, lowest, highest);
+    state.translation.y = -state.price_to_y(Price::from_f32(middle));
+
+    None
 }
 
 pub fn update<T: Chart>(chart: &mut T, message: &Message) {
@@ -486,6 +779,39 @@ pub fn update<T: Chart>(chart: &mut T, message: &Message) {
             }
         }
         Message::CrosshairMoved => return chart.invalidate_crosshair(),
+        Message::DrawingAdded(drawing) => {
+            chart.mut_state().drawings.push(*drawing);
+        }
+        Message::DrawingRemoved(index) => {
+            let drawings = &mut chart.mut_state().drawings;
+            if *index < drawings.len() {
+                drawings.remove(*index);
+            }
+        }
+        Message::PriceMenuOpened(y) => {
+            let state = chart.mut_state();
+            state.price_menu = Some(state.price_at_canvas_y(*y));
+        }
+        Message::PriceMenuDismissed => {
+            chart.mut_state().price_menu = None;
+            return;
+        }
+        // The pane acts on the picked price
+        Message::AlertAt(_) | Message::PriceCopied(_) => {
+            chart.mut_state().price_menu = None;
+            return;
+        }
+        // Alerts live in the pane settings, the pane applies these
+        Message::LevelAlertRequested(_)
+        | Message::PriceAlertRemoved(_)
+        | Message::BracketPlaced { .. } => {}
+        // Replay and backfills are specific to kline charts, the pane forwards these
+        Message::ReplayStartPicked(_)
+        | Message::ReplayStepped(_)
+        | Message::ReplayPlayToggled
+        | Message::CoverageBackfill(..) => {
+            return;
+        }
     }
     chart.invalidate_all();
 }
@@ -553,12 +879,17 @@ pub fn view<'a, T: Chart>(
             translation_y: state.translation.y,
             scaling: state.scaling,
             decimals: state.decimals,
+            tick_tiers: state.ticker_info.tick_tiers,
             min: state.base_price_y.to_f32_lossy(),
-            last_price: state.last_price,
+            last_price: state.last_price.filter(|_| state.show_last_price),
+            levels: &state.levels,
+            price_alerts: &state.price_alerts,
             tick_size: state.tick_size.to_f32_lossy(),
             cell_height: state.cell_height,
             basis: state.basis,
             chart_bounds: state.bounds,
+            price_scale: state.layout.price_scale,
+            percent_anchor: state.percent_anchor.map(Price::to_f32_lossy),
         })
         .width(Length::Fill)
         .height(Length::Fill);
@@ -577,6 +908,11 @@ pub fn view<'a, T: Chart>(
         ]
         .into();
 
+        let main_chart = match state.price_menu {
+            Some(price) => price_menu(chart, main_chart, price),
+            None => main_chart,
+        };
+
         let indicators = chart.view_indicators(indicators);
 
         if indicators.is_empty() {
@@ -593,6 +929,23 @@ pub fn view<'a, T: Chart>(
         }
     };
 
+    let coverage = (!chart.coverage().is_empty()).then(|| {
+        let strip = Canvas::new(coverage::CoverageStrip {
+            state,
+            stretches: chart.coverage(),
+        })
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+        row![
+            container(strip)
+                .padding(padding::right(1))
+                .width(Length::FillPortion(10))
+                .height(Length::Fixed(coverage::HEIGHT)),
+            iced::widget::space::horizontal().width(y_labels_width),
+        ]
+    });
+
     column![
         content,
         rule::horizontal(1).style(style::split_ruler),
@@ -607,10 +960,61 @@ pub fn view<'a, T: Chart>(
             buttons.width(y_labels_width).height(Length::Fixed(26.0))
         ]
     ]
+    .push(coverage)
     .padding(padding::left(1).right(1).bottom(1))
     .into()
 }
 
+/// Quick actions for a price picked on the axis, opened next to it over the chart. Clicking
+/// anywhere else closes it.
+fn price_menu<'a, T: Chart>(
+    chart: &'a T,
+    base: Element<'a, Message>,
+    price: Price,
+) -> Element<'a, Message> {
+    const MENU_HEIGHT: f32 = 84.0;
+
+    let state = chart.state();
+    let y = state
+        .chart_to_canvas(
+            Point::new(0.0, state.price_to_y(price)),
+            state.bounds.size(),
+        )
+        .y
+        .clamp(0.0, (state.bounds.height - MENU_HEIGHT).max(0.0));
+
+    let item = |label: &'static str, message: Message| {
+        button(text(label).size(12))
+            .width(Length::Fill)
+            .on_press(message)
+            .style(|theme, status| style::button::menu_body(theme, status, false))
+    };
+
+    let menu = column![
+        text(price.to_string(state.ticker_info.tick_rule().precision())).size(12),
+        chart
+            .supports_price_alerts()
+            .then(|| item("Alert here", Message::AlertAt(price))),
+        item("Copy price", Message::PriceCopied(price)),
+    ]
+    .spacing(2)
+    .width(120);
+
+    stack![
+        base,
+        mouse_area(
+            container(opaque(container(menu).padding(6).style(style::chart_modal)))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding(padding::top(y))
+                .align_x(Alignment::End)
+        )
+        .on_press(Message::PriceMenuDismissed)
+        .on_right_press(Message::PriceMenuDismissed)
+    ]
+    .into()
+}
+
 pub trait PlotConstants {
     fn min_scaling(&self) -> f32;
     fn max_scaling(&self) -> f32;
@@ -653,12 +1057,24 @@ pub struct ViewState {
     cell_height: f32,
     basis: Basis,
     last_price: Option<PriceInfoLabel>,
+    show_last_price: bool,
+    levels: Vec<NamedLevel>,
+    price_alerts: Vec<Price>,
+    drawings: Vec<Drawing>,
+    bracket: Option<(Price, Price)>,
+    replay_input: ReplayInput,
     base_price_y: Price,
     latest_x: u64,
     tick_size: PriceStep,
     decimals: usize,
     ticker_info: TickerInfo,
     layout: ViewConfig,
+    /// Framing from the saved layout, applied once the chart has bounds and data
+    pending_viewport: Option<Viewport>,
+    /// Close of the leftmost visible bar, what a percent scale reads relative to
+    percent_anchor: Option<Price>,
+    /// Price picked on the axis while its menu is open
+    price_menu: Option<Price>,
 }
 
 impl ViewState {
@@ -667,10 +1083,12 @@ impl ViewState {
         tick_size: PriceStep,
         decimals: usize,
         ticker_info: TickerInfo,
-        layout: ViewConfig,
+        mut layout: ViewConfig,
         cell_width: f32,
         cell_height: f32,
     ) -> Self {
+        let pending_viewport = layout.viewport.take();
+
         ViewState {
             cache: Caches::default(),
             bounds: Rectangle::default(),
@@ -680,12 +1098,21 @@ impl ViewState {
             cell_height,
             basis,
             last_price: None,
+            show_last_price: true,
+            levels: vec![],
+            price_alerts: vec![],
+            drawings: vec![],
+            bracket: None,
+            replay_input: ReplayInput::Off,
             base_price_y: Price::from_f32_lossy(0.0),
             latest_x: 0,
             tick_size,
             decimals,
             ticker_info,
             layout,
+            pending_viewport,
+            percent_anchor: None,
+            price_menu: None,
         }
     }
 
@@ -706,6 +1133,63 @@ impl ViewState {
         }
     }
 
+    fn canvas_to_chart(&self, point: Point, bounds: Size) -> Point {
+        let region = self.visible_region(bounds);
+
+        Point::new(
+            region.x + point.x / self.scaling,
+            region.y + point.y / self.scaling,
+        )
+    }
+
+    /// Price at a height of the chart canvas, on a level the symbol can quote
+    fn price_at_canvas_y(&self, y: f32) -> Price {
+        let point = self.canvas_to_chart(Point::new(0.0, y), self.bounds.size());
+        self.ticker_info.tick_rule().round(self.y_to_price(point.y))
+    }
+
+    fn chart_to_canvas(&self, point: Point, bounds: Size) -> Point {
+        let region = self.visible_region(bounds);
+
+        Point::new(
+            (point.x - region.x) * self.scaling,
+            (point.y - region.y) * self.scaling,
+        )
+    }
+
+    fn anchors_to_rect(&self, start: Anchor, end: Anchor) -> Rectangle {
+        let (x1, x2) = (self.interval_to_x(start.time), self.interval_to_x(end.time));
+        let (y1, y2) = (self.price_to_y(start.price), self.price_to_y(end.price));
+
+        Rectangle {
+            x: x1.min(x2),
+            y: y1.min(y2),
+            width: (x1 - x2).abs(),
+            height: (y1 - y2).abs(),
+        }
+    }
+
+    pub fn drawings(&self) -> &[Drawing] {
+        &self.drawings
+    }
+
+    pub fn set_drawings(&mut self, drawings: Vec<Drawing>) {
+        self.drawings = drawings;
+        self.cache.clear_all();
+    }
+
+    /// Entry and stop of the position size calculator, drawn while it is open
+    pub fn replay_input(&self) -> ReplayInput {
+        self.replay_input
+    }
+
+    pub fn set_bracket(&mut self, bracket: Option<(Price, Price)>) {
+        if self.bracket != bracket {
+            self.bracket = bracket;
+            self.cache.clear_all();
+        }
+    }
+
     fn is_interval_x_visible(&self, interval_x: f32) -> bool {
         let region = self.visible_region(self.bounds.size());
 
@@ -769,7 +1253,58 @@ impl ViewState {
         }
     }
 
+    fn to_f64(price: Price) -> f64 {
+        price.units as f64 / Self::price_unit() as f64
+    }
+
+    fn log_factor(&self) -> f64 {
+        let tick = if self.tick_size.units == 0 {
+            1.0
+        } else {
+            self.tick_size.units as f64 / Self::price_unit() as f64
+        };
+        scale::log::factor(Self::to_f64(self.base_price_y), tick, self.cell_height)
+    }
+
+    /// Cell height that spreads `lowest` to `highest` over `height`
+    fn cell_height_to_fit(&self, highest: f32, lowest: f32, height: f32) -> Option<f32> {
+        let tick = self.tick_size.to_f32_lossy();
+        let cell_height = match self.layout.price_scale {
+            PriceScale::Log => {
+                height * tick / (self.base_price_y.to_f32_lossy() * (highest / lowest).ln())
+            }
+            PriceScale::Linear | PriceScale::Percent => height * tick / (highest - lowest),
+        };
+        (cell_height.is_finite() && cell_height > 0.0).then_some(cell_height)
+    }
+
+    /// Switches the price scale, keeping the visible prices in view
+    fn rescale_prices(&mut self, price_scale: PriceScale, min_height: f32, max_height: f32) {
+        let region = self.visible_region(self.bounds.size());
+        let (highest, lowest) = self.price_range(&region);
+        let (highest, lowest) = (highest.to_f32_lossy(), lowest.to_f32_lossy());
+
+        self.layout.price_scale = price_scale;
+        if price_scale != PriceScale::Percent {
+            self.percent_anchor = None;
+        }
+
+        if region.height <= 0.0 {
+            return;
+        }
+        if let Some(cell_height) = self.cell_height_to_fit(highest, lowest, region.height) {
+            self.cell_height = cell_height.clamp(min_height, max_height);
+            let middle = price_scale.interpolate(0.5, lowest, highest);
+            self.translation.y = -self.price_to_y(Price::from_f32(middle));
+        }
+    }
+
     fn price_to_y(&self, price: Price) -> f32 {
+        if self.layout.price_scale == PriceScale::Log {
+            let base = Self::to_f64(self.base_price_y);
+            return scale::log::price_to_y(base, Self::to_f64(price), self.log_factor());
+        }
+
         if self.tick_size.units == 0 {
             let one = Self::price_unit() as f32;
             let delta_units = (self.base_price_y.units - price.units) as f32;
@@ -782,6 +1317,12 @@ impl ViewState {
     }
 
     fn y_to_price(&self, y: f32) -> Price {
+        if self.layout.price_scale == PriceScale::Log {
+            let base = Self::to_f64(self.base_price_y);
+            let price = scale::log::y_to_price(base, y, self.log_factor());
+            return Price::from_units((price * Self::price_unit() as f64).round() as i64);
+        }
+
         if self.tick_size.units == 0 {
             let one = Self::price_unit() as f32;
             let delta_units = ((y / self.cell_height) * one).round() as i64;
@@ -816,8 +1357,8 @@ impl ViewState {
             let p2 = cursor_position;
 
             let snap_y = |y: f32| {
-                let ratio = y / bounds.height;
-                let price = highest + ratio * (lowest - highest);
+                let ratio = 1.0 - y / bounds.height;
+                let price = self.layout.price_scale.interpolate(ratio, lowest, highest);
 
                 let rounded_price_p = if self.tick_size.units == 0 {
                     Price::from_f32_lossy((price / tick_size).round() * tick_size)
@@ -828,8 +1369,11 @@ impl ViewState {
                     Price::from_units(tick_index * tick_units)
                 };
                 let rounded_price = rounded_price_p.to_f32_lossy();
-                let snap_ratio = (rounded_price - highest) / (lowest - highest);
-                snap_ratio * bounds.height
+                let snap_ratio = self
+                    .layout
+                    .price_scale
+                    .ratio(rounded_price, lowest, highest);
+                (1.0 - snap_ratio) * bounds.height
             };
 
             let snap_x = |x: f32| {
@@ -850,7 +1394,9 @@ impl ViewState {
             } else {
                 ((price2.to_f32_lossy() - price1.to_f32_lossy()) / price1.to_f32_lossy()) * 100.0
             };
-            let pct_text = format!("{:.2}%", pct);
+            let decimals = self.decimals;
+            let delta = price2.to_f32_lossy() - price1.to_f32_lossy();
+            let pct_text = format!("{delta:+.decimals$} ({pct:+.2}%)");
 
             let interval_diff: String = match self.basis {
                 Basis::Time(_) => {
@@ -983,12 +1529,60 @@ impl ViewState {
             });
         }
 
+        if let Interaction::Drawing {
+            tool: DrawingTool::Rectangle,
+            start: Some(start),
+        } = interaction
+        {
+            let start = self.chart_to_canvas(
+                Point::new(self.interval_to_x(start.time), self.price_to_y(start.price)),
+                bounds,
+            );
+            let top_left = Point::new(
+                start.x.min(cursor_position.x),
+                start.y.min(cursor_position.y),
+            );
+            let size = Size::new(
+                (start.x - cursor_position.x).abs(),
+                (start.y - cursor_position.y).abs(),
+            );
+
+            let palette = theme.extended_palette();
+            frame.fill_rectangle(top_left, size, palette.primary.base.color.scale_alpha(0.08));
+            frame.stroke_rectangle(top_left, size, dashed_line);
+        }
+
+        if let Interaction::Drawing {
+            tool: DrawingTool::PositionSize,
+            start: Some(start),
+        } = interaction
+        {
+            let entry_y = self
+                .chart_to_canvas(Point::new(0.0, self.price_to_y(start.price)), bounds)
+                .y;
+
+            frame.stroke(
+                &Path::line(Point::new(0.0, entry_y), Point::new(bounds.width, entry_y)),
+                Stroke::with_color(
+                    Stroke::default(),
+                    theme.extended_palette().primary.base.color,
+                ),
+            );
+        }
+
         // Horizontal price line
-        let crosshair_ratio = cursor_position.y / bounds.height;
-        let crosshair_price = highest + crosshair_ratio * (lowest - highest);
+        let crosshair_ratio = 1.0 - cursor_position.y / bounds.height;
+        let crosshair_price = self
+            .layout
+            .price_scale
+            .interpolate(crosshair_ratio, lowest, highest);
 
         let rounded_price = (crosshair_price / tick_size).round() * tick_size;
-        let snap_ratio = (rounded_price - highest) / (lowest - highest);
+        let snap_ratio = 1.0
+            - self
+                .layout
+                .price_scale
+                .ratio(rounded_price, lowest, highest);
 
         frame.stroke(
             &Path::line(
@@ -1042,7 +1636,9 @@ impl ViewState {
         palette: &Extended,
         region: Rectangle,
     ) {
-        if let Some(price) = &self.last_price {
+        if let Some(price) = &self.last_price
+            && self.show_last_price
+        {
             let (last_price, line_color) = price.get_with_color(palette);
             let y_pos = self.price_to_y(last_price);
 
@@ -1068,14 +1664,194 @@ impl ViewState {
         }
     }
 
+    fn draw_drawings(&self, frame: &mut canvas::Frame, palette: &Extended, region: Rectangle) {
+        let color = palette.primary.base.color;
+        let stroke = Stroke::with_color(
+            Stroke {
+                width: 1.0,
+                ..Default::default()
+            },
+            color.scale_alpha(0.8),
+        );
+
+        for drawing in &self.drawings {
+            match drawing {
+                Drawing::HorizontalLine { price } => {
+                    let y_pos = self.price_to_y(*price);
+
+                    frame.stroke(
+                        &Path::line(
+                            Point::new(region.x, y_pos),
+                            Point::new(region.x + region.width, y_pos),
+                        ),
+                        stroke,
+                    );
+                }
+                Drawing::Rectangle { start, end } => {
+                    if !matches!(self.basis, Basis::Time(_)) {
+                        continue;
+                    }
+                    let rect = self.anchors_to_rect(*start, *end);
+
+                    frame.fill_rectangle(rect.position(), rect.size(), color.scale_alpha(0.08));
+                    frame.stroke_rectangle(rect.position(), rect.size(), stroke);
+                }
+            }
+        }
+    }
+
+    fn draw_levels(&self, frame: &mut canvas::Frame, palette: &Extended, region: Rectangle) {
+        for level in &self.levels {
+            let y_pos = self.price_to_y(level.price);
+            let color = level_color(level.kind, palette);
+
+            let segments: &[f32] = if level.kind.is_session() {
+                &[6.0, 4.0]
+            } else {
+                &[2.0, 3.0]
+            };
+
+            frame.stroke(
+                &Path::line(
+                    Point::new(region.x, y_pos),
+                    Point::new(region.x + region.width, y_pos),
+                ),
+                Stroke::with_color(
+                    Stroke {
+                        width: 1.0,
+                        line_dash: LineDash {
+                            segments,
+                            offset: 0,
+                        },
+                        ..Default::default()
+                    },
+                    color.scale_alpha(0.5),
+                ),
+            );
+
+            frame.fill_text(canvas::Text {
+                content: level.kind.to_string(),
+                position: Point::new(region.x + 4.0 / self.scaling, y_pos - 2.0 / self.scaling),
+                color: color.scale_alpha(0.8),
+                size: iced::Pixels(10.0 / self.scaling),
+                align_y: Alignment::End.into(),
+                font: style::AZERET_MONO,
+                ..Default::default()
+            });
+        }
+
+        for price in &self.price_alerts {
+            let y_pos = self.price_to_y(*price);
+
+            frame.stroke(
+                &Path::line(
+                    Point::new(region.x, y_pos),
+                    Point::new(region.x + region.width, y_pos),
+                ),
+                Stroke::with_color(
+                    Stroke {
+                        width: 1.0,
+                        line_dash: LineDash {
+                            segments: &[1.0, 3.0],
+                            offset: 0,
+                        },
+                        ..Default::default()
+                    },
+                    palette.warning.base.color,
+                ),
+            );
+        }
+
+        if let Some((entry, stop)) = self.bracket {
+            let line =
+                |frame: &mut canvas::Frame, price: Price, color: iced::Color, label: &str| {
+                    let y_pos = self.price_to_y(price);
+
+                    frame.stroke(
+                        &Path::line(
+                            Point::new(region.x, y_pos),
+                            Point::new(region.x + region.width, y_pos),
+                        ),
+                        Stroke::with_color(
+                            Stroke {
+                                width: 1.0,
+                                ..Default::default()
+                            },
+                            color,
+                        ),
+                    );
+                    frame.fill_text(canvas::Text {
+                        content: label.to_string(),
+                        position: Point::new(
+                            region.x + region.width - 4.0 / self.scaling,
+                            y_pos - 2.0 / self.scaling,
+                        ),
+                        color,
+                        size: iced::Pixels(10.0 / self.scaling),
+                        align_x: Alignment::End.into(),
+                        align_y: Alignment::End.into(),
+                        font: style::AZERET_MONO,
+                        ..Default::default()
+                    });
+                };
+
+            line(frame, entry, palette.primary.base.color, "Entry");
+            line(frame, stop, palette.danger.base.color, "Stop");
+
+            let distance = entry.units - stop.units;
+            for (r, label) in [(1, "1R"), (2, "2R"), (3, "3R")] {
+                let target = Price::from_units(entry.units + distance * r);
+                line(
+                    frame,
+                    target,
+                    palette.success.base.color.scale_alpha(0.6),
+                    label,
+                );
+            }
+        }
+    }
+
     fn layout(&self) -> ViewConfig {
         let layout = &self.layout;
         ViewConfig {
             splits: layout.splits.clone(),
             autoscale: layout.autoscale,
+            viewport: self.viewport(),
+            price_scale: layout.price_scale,
+            panel_heights: layout.panel_heights.clone(),
         }
     }
 
+    /// Current framing, or the restored one if the chart never got to apply it
+    fn viewport(&self) -> Option<Viewport> {
+        if self.pending_viewport.is_some() {
+            return self.pending_viewport;
+        }
+        if self.bounds.width <= 0.0 {
+            return None;
+        }
+
+        let region = self.visible_region(self.bounds.size());
+        let latest = match self.basis {
+            Basis::Time(_) => self.latest_x,
+            Basis::Tick(_) => 0,
+        };
+
+        let right_edge = (!self.is_interval_x_visible(self.interval_to_x(latest)))
+            .then(|| self.x_to_interval(region.x + region.width));
+        let price_range = self.layout.autoscale.is_none().then(|| {
+            let (highest, lowest) = self.price_range(&region);
+            (highest.to_f32_lossy(), lowest.to_f32_lossy())
+        });
+
+        Some(Viewport {
+            cell_width: self.cell_width,
+            cell_height: self.cell_height,
+            right_edge,
+            price_range,
+        })
+    }
+
     fn y_labels_width(&self) -> Length {
         let precision = self.ticker_info.min_ticksize;
 
@@ -1147,6 +1923,90 @@ fn request_fetch(handler: &mut RequestHandler, range: FetchRange) -> Option<Acti
     }
 }
 
+/// A line of a data window, its value drawn in `color` or the text color
+pub struct DataRow {
+    pub label: String,
+    pub value: String,
+    pub color: Option<iced::Color>,
+}
+
+impl DataRow {
+    fn new(label: impl Into<String>, value: String) -> Self {
+        Self {
+            label: label.into(),
+            value,
+            color: None,
+        }
+    }
+
+    fn colored(self, color: iced::Color) -> Self {
+        Self {
+            color: Some(color),
+            ..self
+        }
+    }
+}
+
+/// Lists `rows` in a box docked at `corner` of a chart `bounds` in size
+fn draw_data_window(
+    frame: &mut Frame,
+    palette: &Extended,
+    bounds: Size,
+    corner: Corner,
+    rows: &[DataRow],
+) {
+    const LINE_HEIGHT: f32 = 16.0;
+    const PADDING: f32 = 6.0;
+    const MARGIN: f32 = 8.0;
+
+    if rows.is_empty() {
+        return;
+    }
+
+    let char_width = TEXT_SIZE * 0.62;
+    let widest = |chars: fn(&DataRow) -> usize| rows.iter().map(chars).max().unwrap_or(0);
+    let label_width = widest(|row| row.label.chars().count()) as f32 * char_width;
+    let value_width = widest(|row| row.value.chars().count()) as f32 * char_width;
+
+    let size = Size::new(
+        label_width + value_width + char_width * 2.0 + PADDING * 2.0,
+        rows.len() as f32 * LINE_HEIGHT + PADDING * 2.0,
+    );
+    let (x, y) = corner.place(
+        (size.width, size.height),
+        (bounds.width, bounds.height),
+        MARGIN,
+    );
+
+    frame.fill_rectangle(
+        Point::new(x, y),
+        size,
+        palette.background.weakest.color.scale_alpha(0.9),
+    );
+
+    for (i, row) in rows.iter().enumerate() {
+        let line_y = y + PADDING + i as f32 * LINE_HEIGHT;
+
+        frame.fill_text(canvas::Text {
+            content: row.label.clone(),
+            position: Point::new(x + PADDING, line_y),
+            size: iced::Pixels(TEXT_SIZE),
+            color: palette.background.base.text.scale_alpha(0.7),
+            font: style::AZERET_MONO,
+            ..canvas::Text::default()
+        });
+        frame.fill_text(canvas::Text {
+            content: row.value.clone(),
+            position: Point::new(x + size.width - PADDING, line_y),
+            size: iced::Pixels(TEXT_SIZE),
+            color: row.color.unwrap_or(palette.background.base.text),
+            font: style::AZERET_MONO,
+            align_x: Alignment::End.into(),
+            ..canvas::Text::default()
+        });
+    }
+}
+
 fn draw_volume_bar(
     frame: &mut canvas::Frame,
     start_x: f32,
@@ -1215,3 +2075,110 @@ fn draw_volume_bar(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::adapter::Exchange;
+    use exchange::adapter::metatrader5::price_basis::PriceBasis;
+    use exchange::{Ticker, Timeframe};
+
+    const CELL_HEIGHT: f32 = 4.0;
+
+    /// A 100 high canvas centered on 100.0, one 0.1 row every [`CELL_HEIGHT`], quoted in 0.01
+    fn view(price_scale: PriceScale, ticker_info: TickerInfo) -> ViewState {
+        let mut state = ViewState::new(
+            Basis::Time(Timeframe::M5),
+            PriceStep::from_f32(0.1),
+            2,
+            ticker_info,
+            ViewConfig {
+                price_scale,
+                ..ViewConfig::default()
+            },
+            4.0,
+            CELL_HEIGHT,
+        );
+        state.bounds = Rectangle::new(Point::ORIGIN, Size::new(200.0, 100.0));
+        state.base_price_y = price(100.0);
+        state
+    }
+
+    fn price(value: f64) -> Price {
+        Price::from_units((value * ViewState::price_unit() as f64).round() as i64)
+    }
+
+    fn ticker_info() -> TickerInfo {
+        TickerInfo::new(
+            Ticker::new("EURUSD", Exchange::MetaTrader5),
+            0.01,
+            0.01,
+            None,
+        )
+    }
+
+    #[test]
+    fn linear_heights_map_to_quotable_prices() {
+        let state = view(PriceScale::Linear, ticker_info());
+
+        assert_eq!(state.price_at_canvas_y(50.0), price(100.0));
+        assert_eq!(
+            state.price_at_canvas_y(50.0 + 3.0 * CELL_HEIGHT),
+            price(99.7)
+        );
+        assert_eq!(
+            state.price_at_canvas_y(50.0 - 2.0 * CELL_HEIGHT),
+            price(100.2)
+        );
+
+        // Between rows, 99.963 isn't quoted and goes to the nearest 0.01
+        assert_eq!(
+            state.price_at_canvas_y(50.0 + 0.37 * CELL_HEIGHT),
+            price(99.96)
+        );
+    }
+
+    #[test]
+    fn percent_scale_reads_heights_as_linear_does() {
+        let linear = view(PriceScale::Linear, ticker_info());
+        let mut percent = view(PriceScale::Percent, ticker_info());
+        percent.percent_anchor = Some(price(95.0));
+
+        for y in [0.0, 21.0, 50.0, 77.7, 100.0] {
+            assert_eq!(percent.price_at_canvas_y(y), linear.price_at_canvas_y(y));
+        }
+    }
+
+    #[test]
+    fn log_heights_map_back_to_the_prices_drawn_there() {
+        let state = view(PriceScale::Log, ticker_info());
+        let canvas_y = |value: f64| {
+            state
+                .chart_to_canvas(
+                    Point::new(0.0, state.price_to_y(price(value))),
+                    state.bounds.size(),
+                )
+                .y
+        };
+
+        for value in [80.0, 99.95, 100.0, 120.0] {
+            assert_eq!(state.price_at_canvas_y(canvas_y(value)), price(value));
+        }
+
+        // Equal ratios are equally far apart, so 80 is further below than 120 is above
+        assert!(canvas_y(80.0) - canvas_y(100.0) > canvas_y(100.0) - canvas_y(120.0));
+    }
+
+    #[test]
+    fn price_basis_keeps_the_symbol_tick_rule() {
+        let listed = view(PriceScale::Linear, ticker_info());
+
+        for basis in PriceBasis::ALL {
+            let priced = view(PriceScale::Linear, ticker_info().priced(Some(basis)));
+
+            for y in [10.0, 50.0 + 0.37 * CELL_HEIGHT, 93.0] {
+                assert_eq!(priced.price_at_canvas_y(y), listed.price_at_canvas_y(y));
+            }
+        }
+    }
+}
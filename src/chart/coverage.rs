@@ -0,0 +1,102 @@
+//! Thin strip under the time axis of kline charts, showing which of the history was received,
+//! where the market was closed and what is missing. Clicking a missing stretch fetches it again.
+
+use super::{Message, ViewState};
+use data::kline_store::{Coverage, Stretch};
+
+use iced::widget::canvas::{self, Event, Geometry};
+use iced::{Color, Point, Rectangle, Renderer, Size, Theme, mouse};
+
+pub const HEIGHT: f32 = 4.0;
+
+pub struct CoverageStrip<'a> {
+    pub state: &'a ViewState,
+    pub stretches: &'a [Stretch],
+}
+
+impl CoverageStrip<'_> {
+    /// Stretches in view with their left and right edge on the strip
+    fn visible(&self, width: f32) -> impl Iterator<Item = (&Stretch, f32, f32)> {
+        let chart_size = self.state.bounds.size();
+        let x = move |time: u64| {
+            self.state
+                .chart_to_canvas(Point::new(self.state.interval_to_x(time), 0.0), chart_size)
+                .x
+        };
+
+        self.stretches.iter().filter_map(move |stretch| {
+            let (left, right) = (x(stretch.from).max(0.0), x(stretch.to).min(width));
+            (right > left).then_some((stretch, left, right))
+        })
+    }
+
+    fn missing_at(&self, bounds: Rectangle, cursor: mouse::Cursor) -> Option<&Stretch> {
+        let position = cursor.position_in(bounds)?;
+        self.visible(bounds.width)
+            .find(|(stretch, left, right)| {
+                stretch.coverage == Coverage::Missing && (*left..=*right).contains(&position.x)
+            })
+            .map(|(stretch, ..)| stretch)
+    }
+}
+
+impl canvas::Program<Message> for CoverageStrip<'_> {
+    type State = ();
+
+    fn update(
+        &self,
+        _state: &mut Self::State,
+        event: &Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Option<canvas::Action<Message>> {
+        if let Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) = event {
+            let stretch = self.missing_at(bounds, cursor)?;
+            return Some(
+                canvas::Action::publish(Message::CoverageBackfill(stretch.from, stretch.to))
+                    .and_capture(),
+            );
+        }
+        None
+    }
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let palette = theme.extended_palette();
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+
+        for (stretch, left, right) in self.visible(bounds.width) {
+            let color = match stretch.coverage {
+                Coverage::Received => palette.success.base.color,
+                Coverage::Closed => palette.background.strong.color,
+                Coverage::Missing => palette.danger.base.color,
+            };
+            frame.fill_rectangle(
+                Point::new(left, 0.0),
+                Size::new(right - left, bounds.height),
+                Color { a: 0.8, ..color },
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        _state: &Self::State,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> mouse::Interaction {
+        if self.missing_at(bounds, cursor).is_some() {
+            mouse::Interaction::Pointer
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
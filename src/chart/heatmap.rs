@@ -1,31 +1,45 @@
 use super::{
-    Chart, Interaction, Message, PlotConstants, TEXT_SIZE, ViewState, scale::linear::PriceInfoLabel,
+    Chart, DataRow, Interaction, Message, PlotConstants, TEXT_SIZE, ViewState, draw_data_window,
+    scale::linear::PriceInfoLabel,
 };
 use crate::{
     modal::pane::settings::study::{self, Study},
     style,
 };
 use data::chart::{
-    Basis, ViewConfig,
+    Basis, PriceScale, ViewConfig,
+    data_window::{self, DataWindow},
     heatmap::{
-        CLEANUP_THRESHOLD, Config, HeatmapDataPoint, HeatmapStudy, HistoricalDepth, ProfileKind,
-        QtyScale,
+        CLEANUP_THRESHOLD, Config, DepthColor, HeatmapDataPoint, HeatmapStudy, HistoricalDepth,
+        OrderRun, ProfileKind, QtyScale,
+        delta::DeltaHistory,
+        history::{self, HistoryKey},
+        intensity,
+        liquidity::LiquidityHistory,
+        wall::{Side, WallChange, WallDetector, WallEvent},
     },
     indicator::HeatmapIndicator,
+    trade_size,
 };
-use data::util::{abbr_large_numbers, count_decimals};
+use data::config::timezone::UserTimezone;
+use data::util::{abbr_large_numbers, count_decimals, format_with_commas};
 use data::{
-    aggr::time::{DataPoint, TimeSeries},
+    aggr::{
+        sweep::{SweepAggregator, SweepEvent},
+        time::{DataPoint, TimeSeries},
+    },
     chart::Autoscale,
 };
 use exchange::{
-    TickerInfo, Trade,
-    depth::Depth,
+    SizeUnit, TickerInfo, Trade,
+    conversion::SizeDisplay,
+    depth::{Depth, DepthPayload, DepthUpdate, LocalDepthCache},
+    fetcher::{FetchRange, FetchRequests, FetchSpec},
     util::{Price, PriceStep},
     volume_size_unit,
 };
 
-use iced::widget::canvas::{self, Event, Geometry, Path};
+use iced::widget::canvas::{self, Event, Geometry, Path, Stroke};
 use iced::{
     Alignment, Color, Element, Point, Rectangle, Renderer, Size, Theme, Vector, mouse,
     theme::palette::Extended,
@@ -33,6 +47,7 @@ use iced::{
 
 use enum_map::EnumMap;
 use rustc_hash::FxHashMap;
+use std::collections::VecDeque;
 use std::time::Instant;
 
 const MIN_SCALING: f32 = 0.6;
@@ -52,6 +67,26 @@ const TOOLTIP_PADDING: f32 = 12.0;
 
 const MAX_CIRCLE_RADIUS: f32 = 16.0;
 
+/// Height of each of the delta footer's two rows, in screen pixels
+const FOOTER_ROW_HEIGHT: f32 = 7.0;
+
+/// Removed walls kept around as markers
+const MAX_WALL_MARKS: usize = 256;
+
+/// Sweeps kept around as markers
+const MAX_SWEEP_MARKS: usize = 1024;
+
+/// Depth history requested on open when the pane keeps no history window of its own
+const DEFAULT_BACKFILL_MS: u64 = 30 * 60_000;
+
+/// Recorded depth fetched once per basis, so a fresh heatmap isn't blank until it fills live
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DepthBackfill {
+    Idle,
+    Requested(uuid::Uuid),
+    Done,
+}
+
 impl Chart for HeatmapChart {
     type IndicatorKind = HeatmapIndicator;
 
@@ -108,6 +143,22 @@ impl Chart for HeatmapChart {
     fn is_empty(&self) -> bool {
         self.trades.datapoints.is_empty()
     }
+
+    fn supports_drawings(&self) -> bool {
+        false
+    }
+
+    fn kline_at(&self, _interval: u64) -> Option<exchange::Kline> {
+        None
+    }
+
+    fn supports_price_alerts(&self) -> bool {
+        false
+    }
+
+    fn coverage(&self) -> &[data::kline_store::Stretch] {
+        &[]
+    }
 }
 
 impl PlotConstants for HeatmapChart {
@@ -144,6 +195,7 @@ impl PlotConstants for HeatmapChart {
 enum IndicatorData {
     #[default]
     Volume,
+    Delta,
 }
 
 pub struct HeatmapChart {
@@ -152,9 +204,34 @@ pub struct HeatmapChart {
     indicators: EnumMap<HeatmapIndicator, Option<IndicatorData>>,
     pause_buffer: Vec<(u64, Box<[Trade]>, Depth)>,
     heatmap: HistoricalDepth,
+    /// Size added and pulled per column, drawn with [`DepthColor::LiquidityChange`]
+    liquidity: LiquidityHistory,
+    /// Traded delta per column, for [`HeatmapIndicator::Delta`]
+    deltas: DeltaHistory,
     visual_config: Config,
     study_configurator: study::Configurator<HeatmapStudy>,
     last_tick: Instant,
+    last_history_save: Instant,
+    /// Latest time already in the history file, later changes are appended on the next save
+    history_saved_until: Option<u64>,
+    /// When columns past [`data::retention`]'s heatmap window were last dropped
+    last_prune: Instant,
+    walls: WallDetector,
+    /// Wall changes not yet picked up by the pane
+    wall_events: Vec<WallEvent>,
+    /// Removed walls with the aggregated time they are drawn at
+    wall_marks: VecDeque<(u64, WallEvent)>,
+    /// Groups prints into sweeps while `visual_config.sweep_window_ms` is set
+    sweeps: Option<SweepAggregator>,
+    /// Ended sweeps of more than one print with the aggregated time they are drawn at
+    sweep_marks: VecDeque<(u64, SweepEvent)>,
+    backfill: DepthBackfill,
+    /// Values of recent trades, for the auto trade size filter
+    trade_sizes: trade_size::RollingPercentile,
+    sizes: SizeDisplay,
+    data_window: DataWindow,
+    /// Timezone the data window's times are written in
+    timezone: UserTimezone,
     pub studies: Vec<HeatmapStudy>,
 }
 
@@ -174,10 +251,13 @@ impl HeatmapChart {
         for &indicator in enabled_indicators {
             indicators[indicator] = Some(match indicator {
                 HeatmapIndicator::Volume => IndicatorData::Volume,
+                HeatmapIndicator::Delta => IndicatorData::Delta,
             });
         }
 
-        let heatmap = HistoricalDepth::new(ticker_info.min_qty.into(), step, basis);
+        let heatmap = HistoricalDepth::new(ticker_info.min_qty.into(), step, basis)
+            .tiered(ticker_info.tick_tiers);
+        let sizes = SizeDisplay::new(&ticker_info, None, volume_size_unit());
 
         let view_state = ViewState::new(
             basis,
@@ -185,26 +265,168 @@ impl HeatmapChart {
             count_decimals(tick_size),
             ticker_info,
             ViewConfig {
+                autoscale: layout.restored_autoscale(Autoscale::CenterLatest),
                 splits: layout.splits,
-                autoscale: Some(Autoscale::CenterLatest),
+                viewport: layout.viewport,
+                price_scale: PriceScale::Linear,
+                panel_heights: layout.panel_heights,
             },
             DEFAULT_CELL_WIDTH,
             4.0,
         );
 
-        HeatmapChart {
+        let visual_config = config.unwrap_or_default();
+
+        let mut chart = HeatmapChart {
             chart: view_state,
             indicators,
             pause_buffer: vec![],
             heatmap,
+            liquidity: LiquidityHistory::new(step, basis),
+            deltas: DeltaHistory::new(basis),
             trades: TimeSeries::<HeatmapDataPoint>::new(basis, step),
-            visual_config: config.unwrap_or_default(),
+            sweeps: visual_config.sweep_window_ms.map(SweepAggregator::new),
+            visual_config,
             study_configurator: study::Configurator::new(),
             studies,
             last_tick: Instant::now(),
+            last_history_save: Instant::now(),
+            history_saved_until: None,
+            last_prune: Instant::now(),
+            walls: WallDetector::default(),
+            wall_events: vec![],
+            wall_marks: VecDeque::new(),
+            sweep_marks: VecDeque::new(),
+            backfill: DepthBackfill::Idle,
+            trade_sizes: trade_size::RollingPercentile::default(),
+            sizes,
+            data_window: DataWindow::default(),
+            timezone: UserTimezone::default(),
+        };
+        chart.load_history();
+        chart
+    }
+
+    /// Trades worth more than this are drawn, see [`trade_size::threshold`]
+    pub fn trade_size_filter(&self) -> f32 {
+        trade_size::threshold(
+            self.visual_config.auto_trade_size,
+            self.visual_config.trade_size_filter,
+            &self.trade_sizes,
+        )
+    }
+
+    /// Shows sizes in `unit`, the app wide one while `None`
+    pub fn set_size_unit(&mut self, unit: Option<SizeUnit>) {
+        let sizes = SizeDisplay::new(&self.chart.ticker_info, unit, volume_size_unit());
+        if sizes != self.sizes {
+            self.sizes = sizes;
+            self.invalidate(None);
+        }
+    }
+
+    pub fn set_data_window(&mut self, config: DataWindow, timezone: UserTimezone) {
+        if self.data_window != config || self.timezone != timezone {
+            self.data_window = config;
+            self.timezone = timezone;
+            self.chart.cache.clear_crosshair();
+        }
+    }
+
+    /// Rows of the data window for the level at `price`, its latest runs up to `time` newest
+    /// first
+    fn data_window_rows(
+        &self,
+        price: Price,
+        time: u64,
+        aggr_time: u64,
+        palette: &Extended,
+    ) -> Vec<DataRow> {
+        const RUNS_SHOWN: usize = 5;
+
+        let precision = self.chart.ticker_info.tick_rule().precision();
+        let mut rows = vec![
+            DataRow::new("Price", price.to_string(precision)),
+            DataRow::new(
+                "Time",
+                self.timezone
+                    .format_crosshair_timestamp(time as i64, aggr_time),
+            ),
+        ];
+
+        let runs = data_window::runs_until(self.heatmap.runs_at(price), time, RUNS_SHOWN);
+        let resting = runs
+            .last()
+            .filter(|run| run.until_time > time)
+            .map_or(0.0, |run| self.sizes.qty(run.qty(), price.to_f32()));
+        rows.push(DataRow::new("Resting", abbr_large_numbers(resting)));
+
+        rows.extend(runs.iter().rev().map(|run| {
+            let color = if run.is_bid {
+                palette.success.base.color
+            } else {
+                palette.danger.base.color
+            };
+            DataRow::new(
+                self.timezone
+                    .format_crosshair_timestamp(run.start_time as i64, aggr_time),
+                abbr_large_numbers(self.sizes.qty(run.qty(), price.to_f32())),
+            )
+            .colored(color)
+        }));
+
+        rows
+    }
+
+    fn history_key(&self) -> Option<HistoryKey> {
+        Some(HistoryKey {
+            ticker: self.chart.ticker_info.ticker,
+            tick_size: self.chart.tick_size,
+            aggr_time: self.basis_interval()?,
+        })
+    }
+
+    fn history_window_ms(&self) -> Option<u64> {
+        self.visual_config
+            .history_minutes
+            .map(|minutes| u64::from(minutes) * 60_000)
+    }
+
+    fn load_history(&mut self) {
+        self.history_saved_until = None;
+
+        if let (Some(key), Some(window)) = (self.history_key(), self.history_window_ms()) {
+            let now = chrono::Utc::now().timestamp_millis() as u64;
+            history::load(
+                &key,
+                &mut self.heatmap,
+                &mut self.deltas,
+                now.saturating_sub(window),
+            );
+            self.history_saved_until = self.heatmap.latest_time();
         }
     }
 
+    /// Hands what changed since the last save to the history writer, which appends it off the
+    /// UI thread
+    pub fn save_history(&mut self) {
+        self.last_history_save = Instant::now();
+
+        let (Some(key), Some(window)) = (self.history_key(), self.history_window_ms()) else {
+            return;
+        };
+        let Some(latest) = self.heatmap.latest_time() else {
+            return;
+        };
+        let oldest = latest.saturating_sub(window);
+        let since = self
+            .history_saved_until
+            .map_or(oldest, |saved| saved.max(oldest));
+
+        history::append(key, self.heatmap.encode_chunk(&self.deltas, since), oldest);
+        self.history_saved_until = Some(latest);
+    }
+
     pub fn insert_datapoint(
         &mut self,
         trades_buffer: &[Trade],
@@ -238,25 +460,50 @@ impl HeatmapChart {
         }
 
         self.process_datapoint(trades_buffer, depth_update_t, depth);
+
+        if self.last_history_save.elapsed() >= history::SAVE_INTERVAL {
+            self.save_history();
+        }
     }
 
+    /// Drops the oldest columns past [`CLEANUP_THRESHOLD`], and every [`PRUNE_INTERVAL`] the
+    /// ones older than [`data::retention`]'s heatmap window. Columns on screen stay.
+    ///
+    /// [`PRUNE_INTERVAL`]: data::retention::PRUNE_INTERVAL
     fn cleanup_old_data(&mut self) {
-        if self.trades.datapoints.len() > CLEANUP_THRESHOLD {
-            let keys_to_remove = self
-                .trades
-                .datapoints
-                .keys()
-                .take(CLEANUP_THRESHOLD / 10)
-                .copied()
-                .collect::<Vec<u64>>();
+        let datapoints = &self.trades.datapoints;
+        let Some(latest) = datapoints.keys().next_back().copied() else {
+            return;
+        };
 
-            for key in keys_to_remove {
-                self.trades.datapoints.remove(&key);
-            }
+        let by_count = (datapoints.len() > CLEANUP_THRESHOLD)
+            .then(|| datapoints.keys().nth(CLEANUP_THRESHOLD / 10).copied())
+            .flatten();
+        let by_age = (self.last_prune.elapsed() >= data::retention::PRUNE_INTERVAL)
+            .then(|| {
+                self.last_prune = Instant::now();
+                latest.checked_sub(data::retention::config().heatmap.as_millis())
+            })
+            .flatten();
+
+        let Some(cut) = by_count.max(by_age) else {
+            return;
+        };
+        let cut = self
+            .visible_timerange()
+            .map_or(cut, |(earliest, _)| cut.min(earliest));
+        if datapoints.keys().next().is_none_or(|oldest| *oldest >= cut) {
+            return;
+        }
 
-            if let Some(oldest_time) = self.trades.datapoints.keys().next().copied() {
-                self.heatmap.cleanup_old_price_levels(oldest_time);
-            }
+        self.trades.datapoints = self.trades.datapoints.split_off(&cut);
+
+        if let Some(oldest_time) = self.trades.datapoints.keys().next().copied() {
+            self.heatmap.cleanup_old_price_levels(oldest_time);
+            self.liquidity.cleanup(oldest_time);
+            self.deltas.cleanup(oldest_time);
+            self.wall_marks.retain(|(time, _)| *time >= oldest_time);
+            self.sweep_marks.retain(|(time, _)| *time >= oldest_time);
         }
     }
 
@@ -280,13 +527,60 @@ impl HeatmapChart {
                     buy_sell: (0.0, 0.0),
                 });
 
+            let size_in_quote_ccy = volume_size_unit() == exchange::SizeUnit::Quote;
+            let valuer = trade_size::TradeValuer::new(&chart.ticker_info, size_in_quote_ccy);
+
             for trade in trades_buffer {
                 entry.add_trade(trade, chart.tick_size);
+                self.deltas
+                    .add_trade(rounded_depth_update, trade.qty, trade.is_sell);
+                self.trade_sizes.push(valuer.value(trade.qty, trade.price));
+            }
+        }
+
+        if let Some(sweeps) = &mut self.sweeps {
+            let mut ended: Vec<SweepEvent> = trades_buffer
+                .iter()
+                .filter_map(|trade| sweeps.push(trade))
+                .collect();
+            ended.extend(sweeps.flush(depth_update));
+
+            for sweep in ended.into_iter().filter(SweepEvent::is_sweep) {
+                if self.sweep_marks.len() >= MAX_SWEEP_MARKS {
+                    self.sweep_marks.pop_front();
+                }
+                let time = (sweep.end_ms / aggregate_time) * aggregate_time;
+                self.sweep_marks.push_back((time, sweep));
             }
         }
 
         self.heatmap
             .insert_latest_depth(depth, rounded_depth_update);
+        self.liquidity.insert(&depth.changes, rounded_depth_update);
+
+        if let Some(wall_config) = self.visual_config.walls {
+            let market_type = chart.ticker_info.market_type();
+            let size_in_quote_ccy = volume_size_unit() == exchange::SizeUnit::Quote;
+
+            let events = self.walls.update(
+                &wall_config,
+                depth,
+                trades_buffer,
+                depth_update,
+                chart.tick_size,
+                |qty, price| market_type.qty_in_quote_value(qty, price, size_in_quote_ccy),
+            );
+
+            for event in &events {
+                if event.change != WallChange::Appeared {
+                    if self.wall_marks.len() >= MAX_WALL_MARKS {
+                        self.wall_marks.pop_front();
+                    }
+                    self.wall_marks.push_back((rounded_depth_update, *event));
+                }
+            }
+            self.wall_events.extend(events);
+        }
 
         {
             let mid_price = depth.mid_price().unwrap_or(chart.base_price_y);
@@ -301,19 +595,34 @@ impl HeatmapChart {
     }
 
     pub fn set_visual_config(&mut self, visual_config: Config) {
+        if visual_config.walls != self.visual_config.walls {
+            self.reset_walls();
+        }
+        if visual_config.sweep_window_ms != self.visual_config.sweep_window_ms {
+            self.sweeps = visual_config.sweep_window_ms.map(SweepAggregator::new);
+            self.sweep_marks.clear();
+        }
         self.visual_config = visual_config;
         self.invalidate(Some(Instant::now()));
     }
 
     pub fn set_basis(&mut self, basis: Basis) {
+        self.save_history();
         self.chart.basis = basis;
 
         self.trades.datapoints.clear();
+        self.sweep_marks.clear();
+        self.reset_walls();
+        self.backfill = DepthBackfill::Idle;
         self.heatmap = HistoricalDepth::new(
             self.chart.ticker_info.min_qty.into(),
             self.chart.tick_size,
             basis,
-        );
+        )
+        .tiered(self.chart.ticker_info.tick_tiers);
+        self.liquidity = LiquidityHistory::new(self.chart.tick_size, basis);
+        self.deltas = DeltaHistory::new(basis);
+        self.load_history();
 
         let chart = &mut self.chart;
         chart.translation = Vector::new(
@@ -324,6 +633,17 @@ impl HeatmapChart {
         self.invalidate(None);
     }
 
+    /// Drains the wall changes detected since the last call
+    pub fn take_wall_events(&mut self) -> Vec<WallEvent> {
+        std::mem::take(&mut self.wall_events)
+    }
+
+    fn reset_walls(&mut self) {
+        self.walls.clear();
+        self.wall_events.clear();
+        self.wall_marks.clear();
+    }
+
     pub fn study_configurator(&self) -> &study::Configurator<HeatmapStudy> {
         &self.study_configurator
     }
@@ -365,6 +685,7 @@ impl HeatmapChart {
     }
 
     pub fn change_tick_size(&mut self, new_tick_size: f32) {
+        self.save_history();
         let chart_state = self.mut_state();
 
         let basis = chart_state.basis;
@@ -375,7 +696,14 @@ impl HeatmapChart {
         chart_state.decimals = count_decimals(new_tick_size);
 
         self.trades.datapoints.clear();
-        self.heatmap = HistoricalDepth::new(self.chart.ticker_info.min_qty.into(), step, basis);
+        self.sweep_marks.clear();
+        self.reset_walls();
+        self.backfill = DepthBackfill::Idle;
+        self.heatmap = HistoricalDepth::new(self.chart.ticker_info.min_qty.into(), step, basis)
+            .tiered(self.chart.ticker_info.tick_tiers);
+        self.liquidity = LiquidityHistory::new(step, basis);
+        self.deltas = DeltaHistory::new(basis);
+        self.load_history();
     }
 
     pub fn tick_size(&self) -> f32 {
@@ -388,12 +716,20 @@ impl HeatmapChart {
         } else {
             let data = match indicator {
                 HeatmapIndicator::Volume => IndicatorData::Volume,
+                HeatmapIndicator::Delta => IndicatorData::Delta,
             };
             self.indicators[indicator] = Some(data);
         }
     }
 
     pub fn invalidate(&mut self, now: Option<Instant>) -> Option<super::Action> {
+        // Restored on a tick once backfilled depth is in, so the saved range clamps to it
+        let notice = if now.is_some() && !matches!(self.backfill, DepthBackfill::Requested(_)) {
+            let oldest = self.trades.datapoints.keys().next().copied();
+            super::restore_viewport(self, oldest)
+        } else {
+            None
+        };
         let chart = &mut self.chart;
 
         if chart.layout.autoscale.is_some() {
@@ -409,13 +745,395 @@ impl HeatmapChart {
             self.last_tick = t;
         }
 
-        None
+        if let Some(notice) = notice {
+            return Some(super::Action::Notice(notice));
+        }
+        self.request_backfill()
+    }
+
+    fn request_backfill(&mut self) -> Option<super::Action> {
+        if self.backfill != DepthBackfill::Idle {
+            return None;
+        }
+
+        let ticker_info = self.chart.ticker_info;
+        if !ticker_info.capabilities().depth_history {
+            self.backfill = DepthBackfill::Done;
+            return None;
+        }
+
+        let end = exchange::adapter::server_now_ms(ticker_info.exchange());
+        let window = self.history_window_ms().unwrap_or(DEFAULT_BACKFILL_MS);
+
+        let req_id = uuid::Uuid::new_v4();
+        self.backfill = DepthBackfill::Requested(req_id);
+
+        Some(super::Action::RequestFetch(FetchRequests::from([
+            FetchSpec {
+                req_id,
+                fetch: FetchRange::Depth(end.saturating_sub(window), end),
+                stream: None,
+            },
+        ])))
+    }
+
+    /// Fills the heatmap with recorded depth from before the live stream started
+    pub fn insert_depth_history(&mut self, req_id: uuid::Uuid, snapshots: Vec<DepthPayload>) {
+        if self.backfill != DepthBackfill::Requested(req_id) {
+            log::warn!(
+                "Ignoring stale depth history for {}",
+                self.chart.ticker_info.ticker
+            );
+            return;
+        }
+        self.backfill = DepthBackfill::Done;
+
+        if snapshots.is_empty() {
+            return;
+        }
+
+        let min_ticksize = self.chart.ticker_info.min_ticksize;
+        let mut cache = LocalDepthCache::default();
+        let books = snapshots
+            .into_iter()
+            .map(|snapshot| {
+                let time = snapshot.time;
+                cache.update(DepthUpdate::Snapshot(snapshot), min_ticksize);
+                (time, (*cache.depth).clone())
+            })
+            .collect::<Vec<_>>();
+
+        let live_start = self.trades.datapoints.keys().next().copied();
+        self.heatmap
+            .backfill(books.iter().map(|(time, depth)| (*time, depth)), live_start);
+
+        // Nothing live yet, center on the recorded book until the stream takes over
+        if live_start.is_none()
+            && let Some((time, depth)) = books.last()
+        {
+            let aggr_time = self.basis_interval();
+            let chart = &mut self.chart;
+            if let Some(mid_price) = depth.mid_price() {
+                chart.base_price_y = mid_price.round_to_step(chart.tick_size);
+                chart.last_price = Some(PriceInfoLabel::Neutral(mid_price));
+            }
+            if let Some(aggr_time) = aggr_time {
+                chart.latest_x = (time / aggr_time) * aggr_time;
+            }
+        }
+
+        self.invalidate(None);
     }
 
     pub fn last_update(&self) -> Instant {
         self.last_tick
     }
 
+    /// Outlines active walls up to the latest column and marks where removed ones went away,
+    /// a circle for consumed walls and a cross for pulled ones
+    fn draw_walls(
+        &self,
+        frame: &mut canvas::Frame,
+        palette: &Extended,
+        earliest: u64,
+        latest: u64,
+        highest: Price,
+        lowest: Price,
+    ) {
+        let chart = self.state();
+        let Some(aggr_time) = self.basis_interval() else {
+            return;
+        };
+
+        let cell_height = chart.cell_height;
+        let stroke_width = 1.0 / chart.scaling;
+        let in_range = |price: Price| price >= lowest && price <= highest;
+
+        for (side, price, wall) in self.walls.walls() {
+            if !in_range(price) {
+                continue;
+            }
+            let since = ((wall.since / aggr_time) * aggr_time).max(earliest);
+            let start_x = chart.interval_to_x(since);
+            let y = chart.price_to_y(price);
+
+            frame.stroke(
+                &Path::rectangle(
+                    Point::new(start_x, y - cell_height / 2.0),
+                    Size::new((-start_x).max(chart.cell_width), cell_height),
+                ),
+                Stroke::with_color(
+                    Stroke::default().with_width(stroke_width),
+                    depth_color(palette, side == Side::Bid, 1.0),
+                ),
+            );
+        }
+
+        let radius = (cell_height / 2.0).max(3.0 / chart.scaling);
+
+        for (time, event) in &self.wall_marks {
+            if !(earliest..=latest).contains(time) || !in_range(event.price) {
+                continue;
+            }
+            let center = Point::new(chart.interval_to_x(*time), chart.price_to_y(event.price));
+            let stroke = Stroke::with_color(
+                Stroke::default().with_width(stroke_width * 1.5),
+                depth_color(palette, event.side == Side::Bid, 1.0),
+            );
+
+            if event.change == WallChange::Consumed {
+                frame.stroke(&Path::circle(center, radius), stroke);
+            } else {
+                let path = Path::new(|builder| {
+                    builder.move_to(Point::new(center.x - radius, center.y - radius));
+                    builder.line_to(Point::new(center.x + radius, center.y + radius));
+                    builder.move_to(Point::new(center.x - radius, center.y + radius));
+                    builder.line_to(Point::new(center.x + radius, center.y - radius));
+                });
+                frame.stroke(&path, stroke);
+            }
+        }
+    }
+
+    /// Sweeps worth more than the trade size filter, as a line over the prices they took with a
+    /// ring where they stopped
+    fn draw_sweeps(
+        &self,
+        frame: &mut canvas::Frame,
+        palette: &Extended,
+        range: std::ops::RangeInclusive<u64>,
+        trade_valuer: &trade_size::TradeValuer,
+        trade_size_filter: f32,
+        max_trade_qty: f32,
+    ) {
+        let chart = self.state();
+        let stroke_width = 1.0 / chart.scaling;
+
+        for (time, sweep) in &self.sweep_marks {
+            if !range.contains(time)
+                || trade_valuer.value(sweep.total_qty, sweep.price_to) <= trade_size_filter
+            {
+                continue;
+            }
+
+            let color = if sweep.is_sell {
+                palette.danger.strong.color
+            } else {
+                palette.success.strong.color
+            };
+            let x = chart.interval_to_x(*time);
+            let (from, to) = (
+                Point::new(x, chart.price_to_y(sweep.price_from)),
+                Point::new(x, chart.price_to_y(sweep.price_to)),
+            );
+
+            let radius = if max_trade_qty > 0.0 {
+                1.0 + (sweep.total_qty / max_trade_qty).min(1.0) * (MAX_CIRCLE_RADIUS - 1.0)
+            } else {
+                chart.cell_height / 2.0
+            };
+            let stroke =
+                Stroke::with_color(Stroke::default().with_width(stroke_width * 2.0), color);
+
+            frame.stroke(&Path::line(from, to), stroke);
+            frame.stroke(&Path::circle(to, radius), stroke);
+        }
+    }
+
+    /// Shades each level by the net size added to it within a column, additions and pulls in
+    /// their own hue
+    fn draw_liquidity_changes(
+        &self,
+        frame: &mut canvas::Frame,
+        palette: &Extended,
+        earliest: u64,
+        latest: u64,
+        highest: Price,
+        lowest: Price,
+    ) {
+        let chart = self.state();
+        let market_type = chart.ticker_info.market_type();
+        let size_in_quote_ccy = volume_size_unit() == exchange::SizeUnit::Quote;
+        let aggr_time = self.liquidity.aggr_time();
+
+        let changes = self
+            .liquidity
+            .iter_range(earliest, latest, highest, lowest)
+            .map(|(time, price, change)| (time, price, change.net()))
+            .filter(|(_, price, net)| {
+                let size = market_type.qty_in_quote_value(net.abs(), *price, size_in_quote_ccy);
+                size > self.visual_config.order_size_filter
+            })
+            .collect::<Vec<_>>();
+
+        let max_change = changes
+            .iter()
+            .map(|(_, _, net)| net.abs())
+            .fold(0.0, f32::max);
+        if max_change <= 0.0 {
+            return;
+        }
+
+        let cell_height = chart.cell_height;
+        for (time, price, net) in changes {
+            let start_x = chart.interval_to_x(time);
+            let end_x = chart.interval_to_x(time + aggr_time).min(0.0);
+            let width = end_x - start_x;
+
+            if width > 0.001 {
+                let y_position = chart.price_to_y(price);
+                let alpha = (net.abs() / max_change).min(1.0);
+
+                frame.fill_rectangle(
+                    Point::new(start_x, y_position - (cell_height / 2.0)),
+                    Size::new(width, cell_height),
+                    liquidity_change_color(palette, net > 0.0, alpha),
+                );
+            }
+        }
+    }
+
+    /// Screen height of the delta footer, zero while it's off
+    fn footer_height(&self) -> f32 {
+        if self.indicators[HeatmapIndicator::Delta].is_some() {
+            FOOTER_ROW_HEIGHT * 2.0
+        } else {
+            0.0
+        }
+    }
+
+    /// Footer rows along the bottom, each column's traded delta shaded by its sign above its
+    /// total volume
+    fn draw_delta_footer(
+        &self,
+        frame: &mut canvas::Frame,
+        palette: &Extended,
+        region: &Rectangle,
+        earliest: u64,
+        latest: u64,
+    ) {
+        let chart = &self.chart;
+
+        let row_height = FOOTER_ROW_HEIGHT / chart.scaling;
+        let delta_y = (region.y + region.height) - row_height * 2.0;
+        let volume_y = delta_y + row_height;
+
+        frame.fill_rectangle(
+            Point::new(region.x, delta_y),
+            Size::new(region.width, row_height * 2.0),
+            palette.background.weakest.color.scale_alpha(0.8),
+        );
+
+        let (max_delta, max_volume) = self.deltas.iter_range(earliest, latest).fold(
+            (0.0f32, 0.0f32),
+            |(delta, volume), (_, column)| {
+                (delta.max(column.delta().abs()), volume.max(column.volume()))
+            },
+        );
+        if max_volume <= 0.0 {
+            return;
+        }
+
+        let width = chart.cell_width * 0.9;
+
+        for (time, column) in self.deltas.iter_range(earliest, latest) {
+            let x = chart.interval_to_x(time) - width / 2.0;
+            let delta = column.delta();
+
+            if max_delta > 0.0 && delta != 0.0 {
+                let color = if delta > 0.0 {
+                    palette.success.base.color
+                } else {
+                    palette.danger.base.color
+                };
+                frame.fill_rectangle(
+                    Point::new(x, delta_y),
+                    Size::new(width, row_height),
+                    color.scale_alpha(0.2 + 0.8 * (delta.abs() / max_delta)),
+                );
+            }
+
+            frame.fill_rectangle(
+                Point::new(x, volume_y),
+                Size::new(width, row_height),
+                palette
+                    .background
+                    .base
+                    .text
+                    .scale_alpha(0.1 + 0.6 * (column.volume() / max_volume)),
+            );
+        }
+    }
+
+    /// Exact delta and volume of the footer column under the cursor
+    fn draw_delta_tooltip(
+        &self,
+        frame: &mut canvas::Frame,
+        theme: &Theme,
+        bounds: Size,
+        cursor_position: Point,
+        time: u64,
+    ) {
+        let Some(column) = self.deltas.at(time) else {
+            return;
+        };
+
+        let palette = theme.extended_palette();
+        let price = self.chart.base_price_y.to_f32();
+        let size = |qty: f32| format_with_commas(self.sizes.qty(qty, price));
+
+        let delta = column.delta();
+        let lines = [
+            (
+                format!(
+                    "Delta {}{}",
+                    if delta > 0.0 { "+" } else { "" },
+                    format_with_commas(self.sizes.qty(delta.abs(), price).copysign(delta))
+                ),
+                if delta >= 0.0 {
+                    palette.success.strong.color
+                } else {
+                    palette.danger.strong.color
+                },
+            ),
+            (
+                format!("Volume {}", size(column.volume())),
+                palette.background.base.text,
+            ),
+            (
+                format!("Buy {} / Sell {}", size(column.buy), size(column.sell)),
+                palette.background.base.text,
+            ),
+        ];
+
+        let line_height = TEXT_SIZE + 4.0;
+        let height = line_height * lines.len() as f32 + 8.0;
+
+        let x = if cursor_position.x > bounds.width - (TOOLTIP_WIDTH + TOOLTIP_PADDING) {
+            cursor_position.x - TOOLTIP_WIDTH - TOOLTIP_PADDING
+        } else {
+            cursor_position.x + TOOLTIP_PADDING
+        };
+        let y = cursor_position.y - height - TOOLTIP_PADDING;
+
+        frame.fill(
+            &Path::rectangle(Point::new(x, y), Size::new(TOOLTIP_WIDTH, height)),
+            palette.background.weakest.color.scale_alpha(0.9),
+        );
+
+        for (i, (content, color)) in lines.into_iter().enumerate() {
+            frame.fill_text(canvas::Text {
+                content,
+                position: Point::new(x + 8.0, y + 4.0 + line_height * (i as f32 + 0.5)),
+                size: iced::Pixels(TEXT_SIZE - 1.0),
+                color,
+                font: style::AZERET_MONO,
+                align_y: Alignment::Center.into(),
+                ..canvas::Text::default()
+            });
+        }
+    }
+
     fn calc_qty_scales(
         &self,
         earliest: u64,
@@ -445,6 +1163,12 @@ impl HeatmapChart {
     }
 }
 
+impl Drop for HeatmapChart {
+    fn drop(&mut self) {
+        self.save_history();
+    }
+}
+
 impl canvas::Program<Message> for HeatmapChart {
     type State = Interaction;
 
@@ -501,76 +1225,96 @@ impl canvas::Program<Message> for HeatmapChart {
                 (qty_scales.max_aggr_volume, qty_scales.max_trade_qty);
 
             let size_in_quote_ccy = volume_size_unit() == exchange::SizeUnit::Quote;
+            let trade_valuer = trade_size::TradeValuer::new(&chart.ticker_info, size_in_quote_ccy);
+            let trade_size_filter = self.trade_size_filter();
 
             let volume_indicator = self.indicators[HeatmapIndicator::Volume].is_some();
+            // The volume bars sit on top of the delta footer
+            let footer_height = self.footer_height() / chart.scaling;
 
-            if let Some(merge_strat) = self.visual_config().coalescing {
-                let coalesced_visual_runs = self.heatmap.coalesced_runs(
+            if self.visual_config.depth_color == DepthColor::LiquidityChange {
+                self.draw_liquidity_changes(frame, palette, earliest, latest, highest, lowest);
+            } else {
+                let order_size_filter = self.visual_config.order_size_filter;
+                let coalesced_runs = self.visual_config().coalescing.map(|merge_strat| {
+                    self.heatmap.coalesced_runs(
+                        earliest,
+                        latest,
+                        highest,
+                        lowest,
+                        market_type,
+                        order_size_filter,
+                        merge_strat,
+                    )
+                });
+                let visible_runs = || -> Box<dyn Iterator<Item = (Price, &OrderRun)> + '_> {
+                    match &coalesced_runs {
+                        Some(runs) => Box::new(runs.iter().map(|(price, run)| (*price, run))),
+                        None => Box::new(
+                            self.heatmap
+                                .iter_time_filtered(earliest, latest, highest, lowest)
+                                .flat_map(move |(price, runs)| {
+                                    runs.iter()
+                                        .filter(move |run| {
+                                            let order_size = market_type.qty_in_quote_value(
+                                                run.qty(),
+                                                *price,
+                                                size_in_quote_ccy,
+                                            );
+                                            order_size > order_size_filter
+                                        })
+                                        .map(move |run| (*price, run))
+                                }),
+                        ),
+                    }
+                };
+                let clipped = |run: &OrderRun| {
+                    (
+                        run.start_time.max(earliest),
+                        run.until_time.min(latest),
+                        run.qty(),
+                    )
+                };
+
+                let scales = intensity::Scales::new(
+                    self.visual_config.intensity,
                     earliest,
                     latest,
-                    highest,
-                    lowest,
-                    market_type,
-                    self.visual_config.order_size_filter,
-                    merge_strat,
+                    self.basis_interval().unwrap_or(latest - earliest),
+                    bounds.width as usize,
+                    max_depth_qty,
+                    visible_runs().map(|(_, run)| clipped(run)),
                 );
 
-                for (price_of_run, visual_run) in coalesced_visual_runs {
-                    let y_position = chart.price_to_y(price_of_run);
-
-                    let run_start_time_clipped = visual_run.start_time.max(earliest);
-                    let run_until_time_clipped = visual_run.until_time.min(latest);
+                for (price, run) in visible_runs() {
+                    let y_position = chart.price_to_y(price);
+                    let (start, until, qty) = clipped(run);
 
-                    if run_start_time_clipped >= run_until_time_clipped {
+                    // Merged runs fully outside the view are skipped, as are slivers
+                    if coalesced_runs.is_some() && start >= until {
                         continue;
                     }
 
-                    let start_x = chart.interval_to_x(run_start_time_clipped);
-                    let end_x = chart.interval_to_x(run_until_time_clipped).min(0.0);
+                    scales.segments(start, until, qty, |segment| {
+                        let start_x = chart.interval_to_x(segment.start);
+                        let end_x = chart.interval_to_x(segment.until).min(0.0);
+                        let width = end_x - start_x;
 
-                    let width = end_x - start_x;
-
-                    if width > 0.001 {
-                        let color_alpha = (visual_run.qty() / max_depth_qty).min(1.0);
+                        if coalesced_runs.is_some() && width <= 0.001 {
+                            return;
+                        }
 
                         frame.fill_rectangle(
                             Point::new(start_x, y_position - (cell_height / 2.0)),
                             Size::new(width, cell_height),
-                            depth_color(palette, visual_run.is_bid, color_alpha),
+                            depth_color(palette, run.is_bid, segment.alpha),
                         );
-                    }
-                }
-            } else {
-                self.heatmap
-                    .iter_time_filtered(earliest, latest, highest, lowest)
-                    .for_each(|(price, runs)| {
-                        let y_position = chart.price_to_y(*price);
-
-                        runs.iter()
-                            .filter(|run| {
-                                let order_size = market_type.qty_in_quote_value(
-                                    run.qty(),
-                                    *price,
-                                    size_in_quote_ccy,
-                                );
-                                order_size > self.visual_config.order_size_filter
-                            })
-                            .for_each(|run| {
-                                let start_x = chart.interval_to_x(run.start_time.max(earliest));
-                                let end_x =
-                                    chart.interval_to_x(run.until_time.min(latest)).min(0.0);
-
-                                let width = end_x - start_x;
-
-                                let color_alpha = (run.qty() / max_depth_qty).min(1.0);
-
-                                frame.fill_rectangle(
-                                    Point::new(start_x, y_position - (cell_height / 2.0)),
-                                    Size::new(width, cell_height),
-                                    depth_color(palette, run.is_bid, color_alpha),
-                                );
-                            });
                     });
+                }
+            }
+
+            if self.visual_config.walls.is_some() {
+                self.draw_walls(frame, palette, earliest, latest, highest, lowest);
             }
 
             if let Some(latest_timestamp) = self.trades.latest_timestamp() {
@@ -599,7 +1343,8 @@ impl canvas::Program<Message> for HeatmapChart {
 
                     // max bid/ask quantity text
                     let text_size = 9.0 / chart.scaling;
-                    let text_content = abbr_large_numbers(max_qty);
+                    let text_content =
+                        abbr_large_numbers(self.sizes.qty(max_qty, chart.base_price_y.to_f32()));
                     let text_position = Point::new(50.0, region.y);
 
                     frame.fill_text(canvas::Text {
@@ -622,13 +1367,9 @@ impl canvas::Program<Message> for HeatmapChart {
                     dp.grouped_trades.iter().for_each(|trade| {
                         let y_position = chart.price_to_y(trade.price);
 
-                        let trade_size = market_type.qty_in_quote_value(
-                            trade.qty,
-                            trade.price,
-                            size_in_quote_ccy,
-                        );
+                        let trade_size = trade_valuer.value(trade.qty, trade.price);
 
-                        if trade_size > self.visual_config.trade_size_filter {
+                        if trade_size > trade_size_filter {
                             let color = if trade.is_sell {
                                 palette.danger.base.color
                             } else {
@@ -663,7 +1404,7 @@ impl canvas::Program<Message> for HeatmapChart {
                         super::draw_volume_bar(
                             frame,
                             x_position,
-                            (region.y + region.height) - area_height,
+                            (region.y + region.height) - footer_height - area_height,
                             buy_volume,
                             sell_volume,
                             max_aggr_volume,
@@ -677,14 +1418,32 @@ impl canvas::Program<Message> for HeatmapChart {
                     }
                 });
 
+            self.draw_sweeps(
+                frame,
+                palette,
+                earliest..=latest,
+                &trade_valuer,
+                trade_size_filter,
+                max_trade_qty,
+            );
+
+            if footer_height > 0.0 {
+                self.draw_delta_footer(frame, palette, &region, earliest, latest);
+            }
+
             if volume_indicator && max_aggr_volume > 0.0 {
                 let text_size = 9.0 / chart.scaling;
-                let text_content = abbr_large_numbers(max_aggr_volume);
+                let text_content = abbr_large_numbers(
+                    self.sizes.qty(max_aggr_volume, chart.base_price_y.to_f32()),
+                );
                 let text_width = (text_content.len() as f32 * text_size) / 1.5;
 
                 let text_position = Point::new(
                     (region.x + region.width) - text_width,
-                    (region.y + region.height) - (bounds.height / chart.scaling) * 0.1 - text_size,
+                    (region.y + region.height)
+                        - footer_height
+                        - (bounds.height / chart.scaling) * 0.1
+                        - text_size,
                 );
 
                 frame.fill_text(canvas::Text {
@@ -731,6 +1490,7 @@ impl canvas::Program<Message> for HeatmapChart {
                     palette,
                     chart,
                     &self.trades,
+                    &self.sizes,
                     area_width,
                 );
             }
@@ -781,6 +1541,17 @@ impl canvas::Program<Message> for HeatmapChart {
                         return;
                     }
 
+                    if cursor_position.y >= bounds.height - self.footer_height() {
+                        self.draw_delta_tooltip(
+                            frame,
+                            theme,
+                            bounds_size,
+                            cursor_position,
+                            cursor_at_time,
+                        );
+                        return;
+                    }
+
                     let aggr_time: u64 = match chart.basis {
                         Basis::Time(interval) => interval.into(),
                         Basis::Tick(_) => return,
@@ -793,6 +1564,23 @@ impl canvas::Program<Message> for HeatmapChart {
                         .to_f32();
                     let base_data_time = (cursor_at_time / aggr_time) * aggr_time;
 
+                    if self.data_window.visible {
+                        let rows = self.data_window_rows(
+                            Price::from_f32(cursor_at_price).round_to_step(step),
+                            base_data_time,
+                            aggr_time,
+                            palette,
+                        );
+                        draw_data_window(
+                            frame,
+                            palette,
+                            bounds_size,
+                            self.data_window.corner,
+                            &rows,
+                        );
+                        return;
+                    }
+
                     let price_tick_offsets = [1i64, 0, -1];
                     let time_interval_offsets = [-1i64, 0, 1, 2];
 
@@ -860,7 +1648,8 @@ impl canvas::Program<Message> for HeatmapChart {
                             if let Some((qty, is_bid)) =
                                 display_grid_qtys.get(&(data_time_val, data_price_key))
                             {
-                                let text_content = abbr_large_numbers(*qty);
+                                let text_content =
+                                    abbr_large_numbers(self.sizes.qty(*qty, data_price_val));
                                 let color = if *is_bid {
                                     palette.success.strong.color
                                 } else {
@@ -905,7 +1694,7 @@ impl canvas::Program<Message> for HeatmapChart {
         match interaction {
             Interaction::Panning { .. } => mouse::Interaction::Grabbing,
             Interaction::Zoomin { .. } => mouse::Interaction::ZoomIn,
-            Interaction::None | Interaction::Ruler { .. } => {
+            Interaction::None | Interaction::Ruler { .. } | Interaction::Drawing { .. } => {
                 if cursor.is_over(bounds) {
                     return mouse::Interaction::Crosshair;
                 }
@@ -923,6 +1712,14 @@ fn depth_color(palette: &Extended, is_bid: bool, alpha: f32) -> Color {
     }
 }
 
+fn liquidity_change_color(palette: &Extended, is_added: bool, alpha: f32) -> Color {
+    if is_added {
+        palette.primary.strong.color.scale_alpha(alpha)
+    } else {
+        palette.warning.strong.color.scale_alpha(alpha)
+    }
+}
+
 fn draw_volume_profile(
     frame: &mut canvas::Frame,
     region: &Rectangle,
@@ -930,6 +1727,7 @@ fn draw_volume_profile(
     palette: &Extended,
     chart: &ViewState,
     timeseries: &TimeSeries<HeatmapDataPoint>,
+    sizes: &SizeDisplay,
     area_width: f32,
 ) {
     let (highest, lowest) = chart.price_range(region);
@@ -986,10 +1784,11 @@ fn draw_volume_profile(
                 let index = ((grouped_price.units - first_tick.units) / step.units) as usize;
 
                 if let Some(entry) = profile.get_mut(index) {
+                    let qty = sizes.qty(trade.qty, trade.price.to_f32());
                     if trade.is_sell {
-                        entry.1 += trade.qty;
+                        entry.1 += qty;
                     } else {
-                        entry.0 += trade.qty;
+                        entry.0 += qty;
                     }
                     max_aggr_volume = max_aggr_volume.max(entry.0 + entry.1);
                 }
@@ -110,6 +110,7 @@ impl canvas::Program<Message> for IndicatorLabel<'_> {
                 TEXT_SIZE,
                 palette.background.base.text,
                 None,
+                None,
             );
 
             let common_bounds = Rectangle {
@@ -3,10 +3,13 @@ use crate::chart::{Message, ViewState};
 use data::chart::PlotData;
 use data::chart::indicator::KlineIndicator;
 use data::chart::kline::KlineDataPoint;
+use data::chart::volume_curve::VolumeCurve;
+use exchange::conversion::SizeDisplay;
 use exchange::fetcher::FetchRange;
 use exchange::{Kline, Timeframe, Trade};
 
 pub mod open_interest;
+pub mod spread;
 pub mod volume;
 
 pub trait KlineIndicatorImpl {
@@ -23,6 +26,11 @@ pub trait KlineIndicatorImpl {
         visible_range: std::ops::RangeInclusive<u64>,
     ) -> iced::Element<'a, Message>;
 
+    /// Label and value at the bar keyed `at`, for the data window
+    fn value_at(&self, _at: u64) -> Option<(&'static str, String)> {
+        None
+    }
+
     /// If the indicator needs data fetching, return the required range
     fn fetch_range(&mut self, _ctx: &FetchCtx) -> Option<FetchRange> {
         None
@@ -47,6 +55,15 @@ pub trait KlineIndicatorImpl {
     fn on_basis_change(&mut self, _source: &PlotData<KlineDataPoint>) {}
 
     fn on_open_interest(&mut self, _pairs: &[exchange::OpenInterest]) {}
+
+    /// Bid/ask spread of a quote in points, bucketed into `timeframe` bars
+    fn on_spread(&mut self, _time: u64, _spread: f32, _timeframe: Timeframe) {}
+
+    /// Typical volume by time of day, and the multiple of it worth highlighting
+    fn on_volume_curve(&mut self, _curve: Option<&VolumeCurve>, _threshold: f32) {}
+
+    /// Sizes are shown in another unit than they're streamed in
+    fn on_size_display(&mut self, _sizes: SizeDisplay, _source: &PlotData<KlineDataPoint>) {}
 }
 
 pub struct FetchCtx<'a> {
@@ -63,5 +80,6 @@ pub fn make_empty(which: KlineIndicator) -> Box<dyn KlineIndicatorImpl> {
         KlineIndicator::OpenInterest => {
             Box::new(super::kline::open_interest::OpenInterestIndicator::new())
         }
+        KlineIndicator::Spread => Box::new(super::kline::spread::SpreadIndicator::new()),
     }
 }
@@ -97,7 +97,7 @@ impl OpenInterestIndicator {
     }
 
     pub fn is_supported_exchange(exchange: Exchange) -> bool {
-        exchange.is_perps() && exchange != Exchange::HyperliquidLinear
+        exchange.capabilities().open_interest
     }
 
     pub fn is_supported_timeframe(timeframe: Timeframe) -> bool {
@@ -110,6 +110,11 @@ impl KlineIndicatorImpl for OpenInterestIndicator {
         self.cache.clear_all();
     }
 
+    fn value_at(&self, at: u64) -> Option<(&'static str, String)> {
+        let value = self.data.get(&at)?;
+        Some(("Open interest", format_with_commas(*value)))
+    }
+
     fn clear_crosshair_caches(&mut self) {
         self.cache.clear_crosshair();
     }
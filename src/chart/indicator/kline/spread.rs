@@ -0,0 +1,122 @@
+use crate::chart::{
+    Basis, Caches, Message, ViewState,
+    indicator::{
+        indicator_row,
+        kline::KlineIndicatorImpl,
+        plot::{
+            PlotTooltip,
+            band::{BandPlot, BandValues},
+        },
+    },
+};
+
+use data::chart::spread::{SpreadBucket, SpreadSeries};
+use data::chart::{PlotData, kline::KlineDataPoint};
+use exchange::Timeframe;
+
+use iced::widget::{center, row, text};
+use std::ops::RangeInclusive;
+
+/// Min, average and max spread per bar, collected from the live depth stream
+pub struct SpreadIndicator {
+    cache: Caches,
+    series: SpreadSeries,
+}
+
+impl SpreadIndicator {
+    pub fn new() -> Self {
+        Self {
+            cache: Caches::default(),
+            series: SpreadSeries::default(),
+        }
+    }
+
+    fn indicator_elem<'a>(
+        &'a self,
+        main_chart: &'a ViewState,
+        visible_range: RangeInclusive<u64>,
+    ) -> iced::Element<'a, Message> {
+        if let Basis::Tick(_) = main_chart.basis {
+            return center(text("Spread is not available for tick charts")).into();
+        }
+
+        let (earliest, latest) = visible_range.clone().into_inner();
+        if latest < earliest {
+            return row![].into();
+        }
+        if self.series.buckets().is_empty() {
+            return center(text("Waiting for quotes...")).into();
+        }
+
+        let median = self.series.median();
+        let tooltip = move |bucket: &SpreadBucket, _next: Option<&SpreadBucket>| {
+            let mut text = format!(
+                "Spread max: {}\nSpread avg: {:.1}\nSpread min: {}",
+                bucket.max,
+                bucket.avg(),
+                bucket.min
+            );
+            if let Some(median) = median {
+                text.push_str(&format!("\nRolling median: {median:.1}"));
+            }
+            PlotTooltip::new(text)
+        };
+
+        let plot = BandPlot::new(|bucket: &SpreadBucket| BandValues {
+            low: bucket.min,
+            mid: bucket.avg(),
+            high: bucket.max,
+        })
+        .with_tooltip(tooltip);
+
+        indicator_row(
+            main_chart,
+            &self.cache,
+            plot,
+            self.series.buckets(),
+            visible_range,
+        )
+    }
+}
+
+impl KlineIndicatorImpl for SpreadIndicator {
+    fn clear_all_caches(&mut self) {
+        self.cache.clear_all();
+    }
+
+    fn value_at(&self, at: u64) -> Option<(&'static str, String)> {
+        let bucket = self.series.buckets().get(&at)?;
+        Some((
+            "Spread",
+            format!("{} / {:.1} / {}", bucket.min, bucket.avg(), bucket.max),
+        ))
+    }
+
+    fn clear_crosshair_caches(&mut self) {
+        self.cache.clear_crosshair();
+    }
+
+    fn element<'a>(
+        &'a self,
+        chart: &'a ViewState,
+        visible_range: RangeInclusive<u64>,
+    ) -> iced::Element<'a, Message> {
+        self.indicator_elem(chart, visible_range)
+    }
+
+    fn rebuild_from_source(&mut self, _source: &PlotData<KlineDataPoint>) {
+        // Spreads only come from the live stream, there is nothing to rebuild them from
+        self.clear_all_caches();
+    }
+
+    fn on_basis_change(&mut self, _source: &PlotData<KlineDataPoint>) {
+        self.series.clear();
+        self.clear_all_caches();
+    }
+
+    fn on_spread(&mut self, time: u64, spread: f32, timeframe: Timeframe) {
+        self.series
+            .insert(time, spread, timeframe.to_milliseconds());
+        self.clear_all_caches();
+    }
+}
@@ -1,25 +1,55 @@
 use crate::chart::{
-    Caches, Message, ViewState,
+    Basis, Caches, Message, ViewState,
     indicator::{
         indicator_row,
         kline::KlineIndicatorImpl,
         plot::{
-            PlotTooltip,
+            AnySeries, Plot, PlotTooltip, Series, TooltipFn, YScale,
             bar::{BarClass, BarPlot},
         },
     },
 };
 
-use data::chart::{PlotData, kline::KlineDataPoint};
+use data::chart::{PlotData, kline::KlineDataPoint, volume_curve::VolumeCurve};
 use data::util::format_with_commas;
+use exchange::conversion::SizeDisplay;
 use exchange::{Kline, Trade};
 
+use iced::widget::canvas::{self, Path, Stroke};
+use iced::{Point, Size, Theme};
+
 use std::collections::BTreeMap;
 use std::ops::RangeInclusive;
 
 pub struct VolumeIndicator {
     cache: Caches,
     data: BTreeMap<u64, (f32, f32)>,
+    /// Typical volume by time of day, with the multiple of it that highlights a bar
+    typical: Option<(VolumeCurve, f32)>,
+    /// Unit the bars are shown in, `None` while it's the one they're streamed in
+    sizes: Option<SizeDisplay>,
+    /// Converts the typical volume like the bars, at the latest close
+    typical_scale: f32,
+}
+
+fn total(&(buy, sell): &(f32, f32)) -> f32 {
+    if buy == -1.0 { sell } else { buy + sell }
+}
+
+/// Volume of a bar closing at `close` in the shown unit
+fn convert(sizes: Option<&SizeDisplay>, (buy, sell): (f32, f32), close: f32) -> (f32, f32) {
+    match sizes {
+        Some(sizes) => {
+            // Single bars mark the buy side with -1
+            let buy = if buy == -1.0 {
+                buy
+            } else {
+                sizes.qty(buy, close)
+            };
+            (buy, sizes.qty(sell, close))
+        }
+        None => (buy, sell),
+    }
 }
 
 impl VolumeIndicator {
@@ -27,6 +57,9 @@ impl VolumeIndicator {
         Self {
             cache: Caches::default(),
             data: BTreeMap::new(),
+            typical: None,
+            sizes: None,
+            typical_scale: 1.0,
         }
     }
 
@@ -55,15 +88,137 @@ impl VolumeIndicator {
             }
         };
 
-        let value_fn = |&(buy, sell): &(f32, f32)| {
-            if buy == -1.0 { sell } else { buy + sell }
-        };
-
-        let plot = BarPlot::new(value_fn, bar_kind)
+        let plot = BarPlot::new(total, bar_kind)
             .bar_width_factor(0.9)
             .with_tooltip(tooltip);
 
-        indicator_row(main_chart, &self.cache, plot, &self.data, visible_range)
+        match &self.typical {
+            // A zoomed out chart's coarser bars have no curve of their own
+            Some((curve, threshold))
+                if matches!(main_chart.basis, Basis::Time(timeframe)
+                    if timeframe.to_milliseconds() == curve.interval_ms) =>
+            {
+                let plot = TypicalVolumePlot {
+                    bars: plot,
+                    curve,
+                    scale: self.typical_scale,
+                    threshold: *threshold,
+                };
+                indicator_row(main_chart, &self.cache, plot, &self.data, visible_range)
+            }
+            _ => indicator_row(main_chart, &self.cache, plot, &self.data, visible_range),
+        }
+    }
+}
+
+/// Volume bars over a faint curve of the typical volume at their time of day, bars above
+/// `threshold` times the typical volume are outlined
+struct TypicalVolumePlot<'a, P> {
+    bars: P,
+    curve: &'a VolumeCurve,
+    /// Into the unit of the bars
+    scale: f32,
+    threshold: f32,
+}
+
+impl<P> TypicalVolumePlot<'_, P> {
+    fn typical_in(&self, range: RangeInclusive<u64>) -> impl Iterator<Item = (u64, f32)> + '_ {
+        let step = self.curve.interval_ms.max(1);
+        let start = *range.start() - *range.start() % step;
+
+        (start..=*range.end())
+            .step_by(step as usize)
+            .filter_map(|time| {
+                self.curve
+                    .typical_at(time)
+                    .map(|typical| (time, typical * self.scale))
+            })
+    }
+}
+
+impl<'s, P> Plot<AnySeries<'s, (f32, f32)>> for TypicalVolumePlot<'_, P>
+where
+    P: Plot<AnySeries<'s, (f32, f32)>>,
+{
+    fn y_extents(
+        &self,
+        s: &AnySeries<'s, (f32, f32)>,
+        range: RangeInclusive<u64>,
+    ) -> Option<(f32, f32)> {
+        let bars = self.bars.y_extents(s, range.clone());
+        let typical = self
+            .typical_in(range)
+            .map(|(_, typical)| typical)
+            .reduce(f32::max);
+
+        match (bars, typical) {
+            (Some((min, max)), Some(typical)) => Some((min, max.max(typical))),
+            (None, Some(typical)) if typical > 0.0 => Some((0.0, typical)),
+            (bars, _) => bars,
+        }
+    }
+
+    fn draw(
+        &self,
+        frame: &mut canvas::Frame,
+        ctx: &ViewState,
+        theme: &Theme,
+        s: &AnySeries<'s, (f32, f32)>,
+        range: RangeInclusive<u64>,
+        scale: &YScale,
+    ) {
+        let palette = theme.extended_palette();
+
+        let curve = Stroke::with_color(
+            Stroke {
+                width: 1.0,
+                ..Stroke::default()
+            },
+            palette.background.base.text.scale_alpha(0.35),
+        );
+        let step = self.curve.interval_ms.max(1);
+        let mut prev: Option<(u64, Point)> = None;
+        for (time, typical) in self.typical_in(range.clone()) {
+            let point = Point::new(ctx.interval_to_x(time), scale.to_y(typical));
+            // Slots no session traded in leave a gap
+            if let Some((prev_time, prev_point)) = prev
+                && time - prev_time == step
+            {
+                frame.stroke(&Path::line(prev_point, point), curve);
+            }
+            prev = Some((time, point));
+        }
+
+        self.bars.draw(frame, ctx, theme, s, range.clone(), scale);
+
+        let bar_width = ctx.cell_width * 0.9;
+        let outline = Stroke::with_color(
+            Stroke {
+                width: 1.5,
+                ..Stroke::default()
+            },
+            palette.warning.base.color,
+        );
+        s.for_each_in(range, |time, volume| {
+            let volume = total(volume);
+            if self
+                .curve
+                .is_elevated(time, volume / self.scale, self.threshold)
+            {
+                let top = scale.to_y(volume);
+                frame.stroke(
+                    &Path::rectangle(
+                        Point::new(ctx.interval_to_x(time) - bar_width / 2.0, top),
+                        Size::new(bar_width, (scale.to_y(0.0) - top).max(0.0)),
+                    ),
+                    outline,
+                );
+            }
+        });
+    }
+
+    fn tooltip_fn(&self) -> Option<&TooltipFn<(f32, f32)>> {
+        self.bars.tooltip_fn()
     }
 }
 
@@ -84,22 +239,55 @@ impl KlineIndicatorImpl for VolumeIndicator {
         self.indicator_elem(chart, visible_range)
     }
 
+    fn value_at(&self, at: u64) -> Option<(&'static str, String)> {
+        let volume = self.data.get(&at)?;
+        Some(("Volume", format_with_commas(total(volume))))
+    }
+
     fn rebuild_from_source(&mut self, source: &PlotData<KlineDataPoint>) {
-        match source {
+        let sizes = self.sizes.as_ref();
+        let latest_close = match source {
             PlotData::TimeBased(timeseries) => {
                 self.data = timeseries.volume_data();
+                if sizes.is_some() {
+                    for (time, volume) in &mut self.data {
+                        if let Some(dp) = timeseries.datapoints.get(time) {
+                            *volume = convert(sizes, *volume, dp.kline.close.to_f32());
+                        }
+                    }
+                }
+                timeseries
+                    .datapoints
+                    .values()
+                    .last()
+                    .map(|dp| dp.kline.close)
             }
             PlotData::TickBased(tickseries) => {
                 self.data = tickseries.volume_data();
+                if sizes.is_some() {
+                    for (index, volume) in &mut self.data {
+                        if let Some(dp) = tickseries.datapoints.get(*index as usize) {
+                            *volume = convert(sizes, *volume, dp.kline.close.to_f32());
+                        }
+                    }
+                }
+                tickseries.datapoints.last().map(|dp| dp.kline.close)
             }
-        }
+        };
+
+        self.typical_scale = match (sizes, latest_close) {
+            (Some(sizes), Some(close)) => sizes.qty(1.0, close.to_f32()),
+            _ => 1.0,
+        };
         self.clear_all_caches();
     }
 
     fn on_insert_klines(&mut self, klines: &[Kline]) {
         for kline in klines {
-            self.data
-                .insert(kline.time, (kline.volume.0, kline.volume.1));
+            self.data.insert(
+                kline.time,
+                convert(self.sizes.as_ref(), kline.volume, kline.close.to_f32()),
+            );
         }
         self.clear_all_caches();
     }
@@ -115,8 +303,14 @@ impl KlineIndicatorImpl for VolumeIndicator {
             PlotData::TickBased(tickseries) => {
                 let start_idx = old_dp_len.saturating_sub(1);
                 for (idx, dp) in tickseries.datapoints.iter().enumerate().skip(start_idx) {
-                    self.data
-                        .insert(idx as u64, (dp.kline.volume.0, dp.kline.volume.1));
+                    self.data.insert(
+                        idx as u64,
+                        convert(
+                            self.sizes.as_ref(),
+                            dp.kline.volume,
+                            dp.kline.close.to_f32(),
+                        ),
+                    );
                 }
             }
         }
@@ -130,4 +324,14 @@ impl KlineIndicatorImpl for VolumeIndicator {
     fn on_basis_change(&mut self, source: &PlotData<KlineDataPoint>) {
         self.rebuild_from_source(source);
     }
+
+    fn on_volume_curve(&mut self, curve: Option<&VolumeCurve>, threshold: f32) {
+        self.typical = curve.map(|curve| (curve.clone(), threshold));
+        self.clear_all_caches();
+    }
+
+    fn on_size_display(&mut self, sizes: SizeDisplay, source: &PlotData<KlineDataPoint>) {
+        self.sizes = (!sizes.is_identity()).then_some(sizes);
+        self.rebuild_from_source(source);
+    }
 }
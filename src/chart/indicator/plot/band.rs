@@ -0,0 +1,129 @@
+use std::ops::RangeInclusive;
+
+use iced::{
+    Point, Size, Theme,
+    widget::canvas::{self, Path, Stroke},
+};
+
+use crate::chart::{
+    Basis, ViewState,
+    indicator::plot::{Plot, PlotTooltip, Series, TooltipFn, YScale},
+};
+
+/// Low, middle and high value of a datapoint
+pub struct BandValues {
+    pub low: f32,
+    pub mid: f32,
+    pub high: f32,
+}
+
+/// A low to high range per datapoint with its middle value connected across neighbours.
+///
+/// The middle line only joins datapoints one interval apart, missing datapoints leave a gap.
+pub struct BandPlot<V, T> {
+    pub value: V,
+    pub tooltip: Option<TooltipFn<T>>,
+    // padding in percentage of the value range, applies both top and bottom
+    pub padding: f32,
+    pub bar_width_factor: f32,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<V, T> BandPlot<V, T> {
+    pub fn new(value: V) -> Self {
+        Self {
+            value,
+            tooltip: None,
+            padding: 0.08,
+            bar_width_factor: 0.6,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    pub fn with_tooltip<F>(mut self, tooltip: F) -> Self
+    where
+        F: Fn(&T, Option<&T>) -> PlotTooltip + 'static,
+    {
+        self.tooltip = Some(Box::new(tooltip));
+        self
+    }
+}
+
+impl<S, V> Plot<S> for BandPlot<V, S::Y>
+where
+    S: Series,
+    V: Fn(&S::Y) -> BandValues,
+{
+    fn y_extents(&self, datapoints: &S, range: RangeInclusive<u64>) -> Option<(f32, f32)> {
+        let mut min_v = f32::MAX;
+        let mut max_v = f32::MIN;
+
+        datapoints.for_each_in(range, |_, y| {
+            let band = (self.value)(y);
+            min_v = min_v.min(band.low);
+            max_v = max_v.max(band.high);
+        });
+
+        (min_v != f32::MAX).then_some((min_v, max_v))
+    }
+
+    fn adjust_extents(&self, min: f32, max: f32) -> (f32, f32) {
+        if self.padding > 0.0 && max > min {
+            let pad = (max - min) * self.padding;
+            (min - pad, max + pad)
+        } else {
+            (min, max)
+        }
+    }
+
+    fn draw(
+        &self,
+        frame: &mut canvas::Frame,
+        ctx: &ViewState,
+        theme: &Theme,
+        datapoints: &S,
+        range: RangeInclusive<u64>,
+        scale: &YScale,
+    ) {
+        let palette = theme.extended_palette();
+        let color = palette.secondary.strong.color;
+        let bar_width = ctx.cell_width * self.bar_width_factor;
+
+        let step = match ctx.basis {
+            Basis::Time(timeframe) => timeframe.to_milliseconds(),
+            Basis::Tick(_) => 1,
+        };
+        let stroke = Stroke::with_color(
+            Stroke {
+                width: 1.0,
+                ..Stroke::default()
+            },
+            color,
+        );
+
+        let mut prev: Option<(u64, Point)> = None;
+        datapoints.for_each_in(range, |x, y| {
+            let band = (self.value)(y);
+            let center_x = ctx.interval_to_x(x);
+
+            let (top, bottom) = (scale.to_y(band.high), scale.to_y(band.low));
+            frame.fill_rectangle(
+                Point::new(center_x - bar_width / 2.0, top),
+                Size::new(bar_width, (bottom - top).max(1.0)),
+                color.scale_alpha(0.3),
+            );
+
+            let mid = Point::new(center_x, scale.to_y(band.mid));
+            if let Some((prev_x, prev_mid)) = prev
+                && x.abs_diff(prev_x) == step
+            {
+                frame.stroke(&Path::line(prev_mid, mid), stroke);
+            }
+            prev = Some((x, mid));
+        });
+    }
+
+    fn tooltip_fn(&self) -> Option<&TooltipFn<S::Y>> {
+        self.tooltip.as_ref()
+    }
+}
@@ -1,23 +1,45 @@
 use super::{
-    Action, Basis, Chart, Interaction, Message, PlotConstants, PlotData, TEXT_SIZE, ViewState,
-    indicator, request_fetch, scale::linear::PriceInfoLabel,
+    Action, Basis, Chart, DataRow, Interaction, Message, PlotConstants, PlotData, ReplayInput,
+    TEXT_SIZE, ViewState, draw_data_window, indicator, request_fetch,
+    scale::linear::PriceInfoLabel,
 };
 use crate::chart::indicator::kline::KlineIndicatorImpl;
 use crate::{modal::pane::settings::study, style};
 use data::aggr::ticks::TickAggr;
 use data::aggr::time::TimeSeries;
 use data::chart::Autoscale;
+use data::chart::calendar::{self, CalendarConfig};
+use data::chart::data_window::{self, DataWindow};
+use data::chart::imbalance::{self, StackCache};
 use data::chart::kline::ClusterScaling;
+use data::chart::levels;
+use data::chart::panels::PanelHeights;
+use data::chart::pattern::{PatternConfig, PatternMark, PatternTracker};
+use data::chart::replay::{ReplayCursor, ReplaySpeed};
+use data::chart::resolution;
+use data::chart::revision::{Revision, RevisionSettings};
+use data::chart::session::{ReferenceLines, SessionTracker, session_bounds};
+use data::chart::spread;
+use data::chart::stop_run::{self, StopRun, StopRunTracker};
+use data::chart::volume_curve::{self, VolumeCurve, VolumeCurveConfig};
 use data::chart::{
-    KlineChartKind, ViewConfig,
+    KlineChartKind, PriceScale, ViewConfig,
     indicator::{Indicator, KlineIndicator},
     kline::{ClusterKind, FootprintStudy, KlineDataPoint, KlineTrades, NPoc, PointOfControl},
 };
-use data::util::{abbr_large_numbers, count_decimals};
+use data::config::timezone::UserTimezone;
+use data::indicators::signal::{self, BookSnapshot, SignalMark, SignalRunner, SignalSetup};
+use data::indicators::{self, Overlay};
+use data::kline_store::Stretch;
+use data::util::{abbr_large_numbers, count_decimals, format_with_commas};
+use exchange::calendar::{CalendarEvent, Importance};
+use exchange::conversion::SizeDisplay;
 use exchange::util::{Price, PriceStep};
 use exchange::{
-    Kline, OpenInterest as OIData, TickerInfo, Trade,
-    fetcher::{FetchRange, RequestHandler},
+    BarStatus, Kline, OpenInterest as OIData, SizeUnit, TickerInfo, Timeframe, Trade,
+    adapter::StreamKind,
+    depth::Depth,
+    fetcher::{FetchRange, FetchRequests, FetchSpec, RequestHandler},
 };
 
 use iced::task::Handle;
@@ -26,6 +48,7 @@ use iced::widget::canvas::{self, Event, Geometry, Path, Stroke};
 use iced::{Alignment, Element, Point, Rectangle, Renderer, Size, Theme, Vector, mouse};
 
 use enum_map::EnumMap;
+use std::collections::{BTreeMap, HashMap};
 use std::time::Instant;
 
 impl Chart for KlineChart {
@@ -129,6 +152,36 @@ impl Chart for KlineChart {
         true
     }
 
+    fn supports_drawings(&self) -> bool {
+        true
+    }
+
+    fn kline_at(&self, interval: u64) -> Option<Kline> {
+        match &self.data_source {
+            PlotData::TimeBased(timeseries) => {
+                timeseries.datapoints.get(&interval).map(|dp| dp.kline)
+            }
+            PlotData::TickBased(tick_aggr) => {
+                let index = tick_aggr
+                    .datapoints
+                    .len()
+                    .checked_sub(1 + interval as usize)?;
+                tick_aggr.datapoints.get(index).map(|dp| dp.kline)
+            }
+        }
+    }
+
+    fn supports_price_alerts(&self) -> bool {
+        true
+    }
+
+    fn coverage(&self) -> &[Stretch] {
+        match self.chart.basis {
+            Basis::Time(_) => &self.coverage,
+            Basis::Tick(_) => &[],
+        }
+    }
+
     fn is_empty(&self) -> bool {
         match &self.data_source {
             PlotData::TimeBased(timeseries) => timeseries.datapoints.is_empty(),
@@ -151,7 +204,12 @@ impl PlotConstants for KlineChart {
     }
 
     fn min_cell_width(&self) -> f32 {
-        self.kind.min_cell_width()
+        // Zooming out past the kind's limit moves to a coarser timeframe instead
+        if self.can_coarsen() {
+            self.kind.min_cell_width() / 10.0
+        } else {
+            self.kind.min_cell_width()
+        }
     }
 
     fn max_cell_height(&self) -> f32 {
@@ -177,6 +235,162 @@ pub struct KlineChart {
     request_handler: RequestHandler,
     study_configurator: study::Configurator<FootprintStudy>,
     last_tick: Instant,
+    /// When history past [`data::retention`]'s limits was last dropped
+    last_prune: Instant,
+    reference_lines: ReferenceLines,
+    session: SessionTracker,
+    daily: Box<DailyLevels>,
+    server_now: u64,
+    replay: Option<Box<Replay>>,
+    /// Stacked imbalances per bar while the study is on
+    stacks: Option<StackCache>,
+    overlays: Vec<Box<dyn indicators::Indicator>>,
+    data_window: DataWindow,
+    /// Timezone the overlays' sessions were built for
+    overlay_timezone: UserTimezone,
+    /// [`indicators::script::generation`] the overlays were built at
+    overlay_scripts_generation: u64,
+    /// Spread of the last quote in points, while the spread panel is on
+    latest_spread: Option<f32>,
+    stop_runs: StopRunTracker,
+    /// When and with what parameters stop runs were last scanned for, `None` while they're off
+    stop_run_scan: Option<(Instant, stop_run::Params)>,
+    patterns: PatternTracker,
+    signals: Box<SignalState>,
+    volume_curve: Box<VolumeCurveState>,
+    calendar: CalendarState,
+    resolution: Box<Resolution>,
+    /// Unit the pane shows sizes in, `None` for the app wide one
+    size_unit: Option<SizeUnit>,
+    /// Open time of the latest live bar the stream closed
+    closed_through: Option<u64>,
+    /// Panels shown under the chart, top to bottom
+    panels: Vec<KlineIndicator>,
+    /// What of the stream's history the dashboard's store received
+    coverage: Vec<Stretch>,
+}
+
+const DAY_MS: u64 = 86_400_000;
+/// Bars from the latest back that are scanned for stop runs
+const STOP_RUN_SCAN_BARS: usize = 500;
+const STOP_RUN_SCAN_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+const DAILY_LOOKBACK_DAYS: u64 = 10;
+/// Kline requests a volume curve's history may take, adapters cap how many bars each returns
+const VOLUME_CURVE_MAX_PAGES: usize = 60;
+
+/// Full history while replaying, the chart's data source only holds the revealed bars
+struct Replay {
+    klines: Vec<Kline>,
+    cursor: ReplayCursor,
+}
+
+impl Replay {
+    fn revealed(&self) -> &[Kline] {
+        &self.klines[..self.cursor.revealed()]
+    }
+
+    /// Folds in klines that arrived while replaying, older ones land before the cursor
+    fn merge(&mut self, klines: &[Kline]) {
+        let cursor_time = self.revealed().last().map_or(0, |k| k.time);
+
+        let mut merged: std::collections::BTreeMap<u64, Kline> =
+            self.klines.iter().map(|k| (k.time, *k)).collect();
+        for kline in klines {
+            merged.insert(kline.time, *kline);
+        }
+
+        let revealed_before = self.cursor.revealed();
+        self.klines = merged.into_values().collect();
+
+        let revealed_after = self.klines.partition_point(|k| k.time <= cursor_time);
+        self.cursor.set_len(
+            self.klines.len(),
+            revealed_after.saturating_sub(revealed_before),
+        );
+    }
+}
+
+#[derive(Default)]
+struct DailyLevels {
+    req_id: Option<uuid::Uuid>,
+    requested_day: Option<u64>,
+    klines: Vec<Kline>,
+}
+
+/// Signal scripts the pane runs, with the copy of the book they read
+#[derive(Default)]
+struct SignalState {
+    runners: Vec<SignalRunner>,
+    /// [`indicators::script::generation`] the runners were built at
+    generation: u64,
+    book: BookSnapshot,
+}
+
+/// Typical volume by time of day for the volume panel, its history fetched once per session
+/// unless a saved curve already covers it
+#[derive(Default)]
+struct VolumeCurveState {
+    config: Option<VolumeCurveConfig>,
+    timezone: UserTimezone,
+    curve: Option<VolumeCurve>,
+    /// Start of the session the history is fetched for
+    session: Option<u64>,
+    range: (u64, u64),
+    req_id: Option<uuid::Uuid>,
+    next_page: Option<(u64, u64)>,
+    pages: usize,
+    /// Whether the last page asked for bars after those already fetched, or before them
+    forward: bool,
+    /// Ends of the range that returned nothing more, as `[before, after]`
+    exhausted: [bool; 2],
+    klines: Vec<Kline>,
+}
+
+/// Economic calendar events of the chart's currencies, fetched once per UTC day
+#[derive(Default)]
+struct CalendarState {
+    config: Option<CalendarConfig>,
+    /// Day the events were fetched or looked up for
+    day: Option<u64>,
+    req_id: Option<uuid::Uuid>,
+    events: Vec<CalendarEvent>,
+}
+
+/// Coarser bars shown in place of the chart's own while it's zoomed out too far for them, see
+/// [`data::chart::resolution`]
+#[derive(Default)]
+struct Resolution {
+    /// The chart's own timeframe and bars, set aside while a coarser timeframe is shown
+    base: Option<(Timeframe, TimeSeries<KlineDataPoint>)>,
+    /// Bars fetched for each coarser timeframe, kept for when it's shown again
+    layers: HashMap<Timeframe, BTreeMap<u64, Kline>>,
+    /// Where each coarser timeframe's history ran out, the earliest bar held when a fetch
+    /// before it came back with nothing older
+    exhausted: HashMap<Timeframe, u64>,
+    /// Fetch in flight, with the timeframe and the time it asked for bars before
+    req_id: Option<(uuid::Uuid, Timeframe, u64)>,
+}
+
+impl Resolution {
+    /// Adds a live bar of the chart's own timeframe to the set aside ones, returns the bar of
+    /// the `shown` timeframe it falls in rebuilt from them
+    fn fold_live(&mut self, kline: &Kline, shown: Timeframe) -> Option<Kline> {
+        let (_, base) = self.base.as_mut()?;
+        base.insert_klines(&[*kline]);
+
+        let phase = self
+            .layers
+            .get(&shown)
+            .map_or(0, |fetched| resolution::phase(fetched, shown));
+        let start = resolution::bar_start(kline.time, shown, phase);
+
+        resolution::merge(
+            start,
+            base.datapoints
+                .range(start..start + shown.to_milliseconds())
+                .map(|(_, dp)| &dp.kline),
+        )
+    }
 }
 
 impl KlineChart {
@@ -229,8 +443,11 @@ impl KlineChart {
                     count_decimals(tick_size),
                     ticker_info,
                     ViewConfig {
+                        autoscale: layout.restored_autoscale(Autoscale::FitToVisible),
                         splits: layout.splits,
-                        autoscale: Some(Autoscale::FitToVisible),
+                        viewport: layout.viewport,
+                        price_scale: layout.price_scale,
+                        panel_heights: layout.panel_heights,
                     },
                     cell_width,
                     cell_height,
@@ -259,7 +476,7 @@ impl KlineChart {
                     indicators[i] = Some(indi);
                 }
 
-                KlineChart {
+                let mut kline_chart = KlineChart {
                     chart,
                     data_source,
                     raw_trades,
@@ -269,7 +486,33 @@ impl KlineChart {
                     kind: kind.clone(),
                     study_configurator: study::Configurator::new(),
                     last_tick: Instant::now(),
-                }
+                    last_prune: Instant::now(),
+                    reference_lines: ReferenceLines::default(),
+                    session: SessionTracker::default(),
+                    daily: Box::default(),
+                    server_now: 0,
+                    replay: None,
+                    stacks: None,
+                    overlays: vec![],
+                    data_window: DataWindow::default(),
+                    overlay_timezone: UserTimezone::default(),
+                    overlay_scripts_generation: 0,
+                    latest_spread: None,
+                    stop_runs: StopRunTracker::default(),
+                    stop_run_scan: None,
+                    patterns: PatternTracker::default(),
+                    signals: Box::default(),
+                    volume_curve: Box::default(),
+                    calendar: CalendarState::default(),
+                    resolution: Box::default(),
+                    size_unit: None,
+                    closed_through: None,
+                    panels: vec![],
+                    coverage: vec![],
+                };
+                kline_chart.arrange_panels(enabled_indicators);
+                kline_chart.rebuild_stacks();
+                kline_chart
             }
             Basis::Tick(interval) => {
                 let step = PriceStep::from_f32(tick_size);
@@ -289,8 +532,11 @@ impl KlineChart {
                     count_decimals(tick_size),
                     ticker_info,
                     ViewConfig {
+                        autoscale: layout.restored_autoscale(Autoscale::FitToVisible),
                         splits: layout.splits,
-                        autoscale: Some(Autoscale::FitToVisible),
+                        viewport: layout.viewport,
+                        price_scale: layout.price_scale,
+                        panel_heights: layout.panel_heights,
                     },
                     cell_width,
                     cell_height,
@@ -317,7 +563,7 @@ impl KlineChart {
                     indicators[i] = Some(indi);
                 }
 
-                KlineChart {
+                let mut kline_chart = KlineChart {
                     chart,
                     data_source,
                     raw_trades,
@@ -327,21 +573,85 @@ impl KlineChart {
                     kind: kind.clone(),
                     study_configurator: study::Configurator::new(),
                     last_tick: Instant::now(),
-                }
+                    last_prune: Instant::now(),
+                    reference_lines: ReferenceLines::default(),
+                    session: SessionTracker::default(),
+                    daily: Box::default(),
+                    server_now: 0,
+                    replay: None,
+                    stacks: None,
+                    overlays: vec![],
+                    data_window: DataWindow::default(),
+                    overlay_timezone: UserTimezone::default(),
+                    overlay_scripts_generation: 0,
+                    latest_spread: None,
+                    stop_runs: StopRunTracker::default(),
+                    stop_run_scan: None,
+                    patterns: PatternTracker::default(),
+                    signals: Box::default(),
+                    volume_curve: Box::default(),
+                    calendar: CalendarState::default(),
+                    resolution: Box::default(),
+                    size_unit: None,
+                    closed_through: None,
+                    panels: vec![],
+                    coverage: vec![],
+                };
+                kline_chart.arrange_panels(enabled_indicators);
+                kline_chart.rebuild_stacks();
+                kline_chart
             }
         }
     }
 
-    pub fn update_latest_kline(&mut self, kline: &Kline) {
+    /// Applies a live bar, a forming one replaces the bar at its open time. A late update of a
+    /// bar the stream already closed is dropped.
+    pub fn update_latest_kline(&mut self, kline: &Kline, status: BarStatus) {
+        match status {
+            BarStatus::Forming if self.closed_through >= Some(kline.time) => return,
+            BarStatus::Forming => {}
+            BarStatus::Closed => self.closed_through = self.closed_through.max(Some(kline.time)),
+        }
+
+        if let Some(replay) = &mut self.replay {
+            replay.merge(&[*kline]);
+            return;
+        }
+
+        let folded;
+        let kline = match self.shown_resolution() {
+            Some(shown) => match self.resolution.fold_live(kline, shown) {
+                Some(coarse) => {
+                    folded = coarse;
+                    &folded
+                }
+                None => return,
+            },
+            None => kline,
+        };
+
         match self.data_source {
             PlotData::TimeBased(ref mut timeseries) => {
                 timeseries.insert_klines(&[*kline]);
+                // Anchored to the session, the bar may end early or have opened early
+                let kline = &timeseries
+                    .datapoints
+                    .get(&kline.time)
+                    .map_or(*kline, |dp| dp.kline);
+
+                if self.reference_lines.session_levels {
+                    self.session
+                        .on_kline(kline, timeseries.bar_span(kline.time));
+                    self.sync_levels();
+                }
 
                 self.indicators
                     .values_mut()
                     .filter_map(Option::as_mut)
                     .for_each(|indi| indi.on_insert_klines(&[*kline]));
 
+                self.update_overlays(&[*kline]);
+
                 let chart = self.mut_state();
 
                 if (kline.time) > chart.latest_x {
@@ -354,11 +664,505 @@ impl KlineChart {
         }
     }
 
+    pub fn reference_lines(&self) -> ReferenceLines {
+        self.reference_lines
+    }
+
+    pub fn set_reference_lines(&mut self, reference_lines: ReferenceLines) {
+        self.reference_lines = reference_lines;
+        self.chart.show_last_price = reference_lines.last_price;
+
+        self.session.reset();
+        self.sync_levels();
+
+        self.invalidate(None);
+    }
+
+    /// Rolls the session levels over at the session boundary, judged by the exchange's server
+    /// time, and rebuilds them from loaded data whenever the session definition changes
+    pub fn poll_session(&mut self, timezone: UserTimezone, server_now_ms: u64) {
+        self.server_now = server_now_ms;
+
+        if let PlotData::TimeBased(timeseries) = &mut self.data_source {
+            timeseries.session_break = Some(self.reference_lines.session_break(timezone));
+        }
+
+        let start_hour = self.reference_lines.session_start_hour;
+        if self.reference_lines.session_levels
+            && self.session.poll(timezone, start_hour, server_now_ms)
+            && let Some((start, _)) = self.session.bounds()
+        {
+            match &self.data_source {
+                PlotData::TimeBased(timeseries) => {
+                    let interval = timeseries.interval.to_milliseconds();
+                    timeseries
+                        .datapoints
+                        .range(start.saturating_sub(interval)..)
+                        .for_each(|(time, dp)| {
+                            self.session.on_kline(&dp.kline, timeseries.bar_span(*time));
+                        });
+                }
+                PlotData::TickBased(tick_aggr) => {
+                    tick_aggr
+                        .datapoints
+                        .iter()
+                        .for_each(|dp| self.session.on_kline(&dp.kline, 0));
+                }
+            }
+        }
+
+        self.sync_levels();
+    }
+
+    fn sync_levels(&mut self) {
+        let session = self
+            .reference_lines
+            .session_levels
+            .then(|| self.session.levels())
+            .flatten();
+
+        let prev_day = if self.reference_lines.prev_day_levels {
+            match &self.data_source {
+                PlotData::TimeBased(timeseries) if timeseries.interval == Timeframe::D1 => {
+                    let mut recent = timeseries
+                        .datapoints
+                        .values()
+                        .rev()
+                        .take(8)
+                        .map(|dp| dp.kline)
+                        .collect::<Vec<_>>();
+                    recent.reverse();
+                    levels::previous_day(&recent, self.server_now).copied()
+                }
+                _ => levels::previous_day(&self.daily.klines, self.server_now).copied(),
+            }
+        } else {
+            None
+        };
+
+        self.chart.levels = levels::named_levels(prev_day.as_ref(), session);
+    }
+
+    /// Daily klines backing the previous day levels, refetched once per server day. Charts
+    /// already on the daily timeframe read them from their own series instead.
+    fn daily_levels_task(&mut self) -> Option<Action> {
+        if !self.reference_lines.prev_day_levels
+            || self.basis() == Basis::Time(Timeframe::D1)
+            || self.server_now == 0
+        {
+            return None;
+        }
+
+        let day = self.server_now / DAY_MS;
+        if self.daily.requested_day == Some(day) {
+            return None;
+        }
+
+        let req_id = uuid::Uuid::new_v4();
+        self.daily.requested_day = Some(day);
+        self.daily.req_id = Some(req_id);
+
+        let fetch = FetchSpec {
+            req_id,
+            fetch: FetchRange::Kline(
+                self.server_now.saturating_sub(DAILY_LOOKBACK_DAYS * DAY_MS),
+                self.server_now,
+            ),
+            stream: Some(StreamKind::Kline {
+                ticker_info: self.chart.ticker_info,
+                timeframe: Timeframe::D1,
+            }),
+        };
+        Some(Action::RequestFetch(FetchRequests::from([fetch])))
+    }
+
+    pub fn is_daily_levels_request(&self, req_id: uuid::Uuid) -> bool {
+        self.daily.req_id == Some(req_id)
+    }
+
+    pub fn insert_daily_klines(&mut self, klines: &[Kline]) {
+        self.daily.req_id = None;
+
+        let mut klines = klines.to_vec();
+        klines.sort_by_key(|kline| kline.time);
+        self.daily.klines = klines;
+
+        self.sync_levels();
+        self.invalidate(None);
+    }
+
+    /// Turns the typical volume curve of the volume panel on or off, dropping a curve built for
+    /// another timezone or session count
+    pub fn set_volume_curve(&mut self, config: Option<VolumeCurveConfig>, timezone: UserTimezone) {
+        if self.volume_curve.config == config && self.volume_curve.timezone == timezone {
+            return;
+        }
+
+        let same_sessions =
+            self.volume_curve.config.map(|c| c.sessions) == config.map(|c| c.sessions);
+        if same_sessions && config.is_some() && self.volume_curve.timezone == timezone {
+            // Only the highlight threshold moved, the curve itself still holds
+            self.volume_curve.config = config;
+            self.push_volume_curve();
+            return;
+        }
+
+        *self.volume_curve = VolumeCurveState {
+            config,
+            timezone,
+            ..VolumeCurveState::default()
+        };
+        self.push_volume_curve();
+    }
+
+    fn push_volume_curve(&mut self) {
+        let threshold = self
+            .volume_curve
+            .config
+            .map_or(1.0, |config| config.threshold());
+
+        if let Some(indi) = self.indicators[KlineIndicator::Volume].as_mut() {
+            indi.on_volume_curve(self.volume_curve.curve.as_ref(), threshold);
+        }
+    }
+
+    /// History of the sessions a volume curve averages, requested a page at a time once per
+    /// session. Only intraday time based charts have a time of day to compare by.
+    fn volume_curve_task(&mut self) -> Option<Action> {
+        let config = self.volume_curve.config?;
+        let Basis::Time(timeframe) = self.basis() else {
+            return None;
+        };
+        let interval = timeframe.to_milliseconds();
+        if interval >= DAY_MS || self.server_now == 0 || self.volume_curve.req_id.is_some() {
+            return None;
+        }
+
+        let (timezone, now) = (self.volume_curve.timezone, self.server_now);
+        let start_hour = self.reference_lines.session_start_hour;
+        if self
+            .volume_curve
+            .curve
+            .as_ref()
+            .is_some_and(|curve| curve.is_current(timezone, start_hour, interval, now))
+        {
+            return None;
+        }
+
+        let (session_start, _) = session_bounds(timezone, start_hour, now);
+        if self.volume_curve.session != Some(session_start) {
+            let ticker = self.chart.ticker_info.ticker;
+            if let Some(curve) =
+                volume_curve::cached(&ticker, config, timezone, start_hour, interval, now)
+            {
+                self.volume_curve.curve = Some(curve);
+                self.push_volume_curve();
+                return None;
+            }
+
+            let range = volume_curve::history_range(timezone, start_hour, config.sessions, now);
+            *self.volume_curve = VolumeCurveState {
+                config: Some(config),
+                timezone,
+                curve: self.volume_curve.curve.take(),
+                session: Some(session_start),
+                range,
+                next_page: Some(range),
+                ..VolumeCurveState::default()
+            };
+        }
+
+        let (from, to) = self.volume_curve.next_page.take()?;
+        let req_id = uuid::Uuid::new_v4();
+        self.volume_curve.req_id = Some(req_id);
+        self.volume_curve.pages += 1;
+
+        let fetch = FetchSpec {
+            req_id,
+            fetch: FetchRange::Kline(from, to),
+            stream: Some(StreamKind::Kline {
+                ticker_info: self.chart.ticker_info,
+                timeframe,
+            }),
+        };
+        Some(Action::RequestFetch(FetchRequests::from([fetch])))
+    }
+
+    pub fn is_volume_curve_request(&self, req_id: uuid::Uuid) -> bool {
+        self.volume_curve.req_id == Some(req_id)
+    }
+
+    /// Adds a page of the volume curve's history, and builds the curve once nothing is missing
+    pub fn insert_volume_curve_klines(&mut self, klines: &[Kline]) {
+        let Basis::Time(timeframe) = self.basis() else {
+            return;
+        };
+        let interval = timeframe.to_milliseconds();
+        let state = &mut *self.volume_curve;
+        state.req_id = None;
+
+        let (from, to) = state.range;
+        let before = state.klines.len();
+        state.klines.extend(
+            klines
+                .iter()
+                .filter(|kline| (from..to).contains(&kline.time)),
+        );
+        state.klines.sort_by_key(|kline| kline.time);
+        state.klines.dedup_by_key(|kline| kline.time);
+
+        if state.klines.len() == before {
+            state.exhausted[usize::from(state.forward)] = true;
+        }
+
+        if let (Some(first), Some(last)) = (state.klines.first(), state.klines.last())
+            && state.pages < VOLUME_CURVE_MAX_PAGES
+        {
+            if !state.exhausted[1] && last.time + interval < to {
+                state.forward = true;
+                state.next_page = Some((last.time + interval, to));
+            } else if !state.exhausted[0] && first.time > from + interval {
+                state.forward = false;
+                state.next_page = Some((from, first.time));
+            }
+        }
+
+        if state.next_page.is_some() {
+            return;
+        }
+
+        let (Some(config), Some(_)) = (state.config, state.session) else {
+            return;
+        };
+        let curve = VolumeCurve::build(
+            &state.klines,
+            interval,
+            state.timezone,
+            self.reference_lines.session_start_hour,
+            usize::from(config.sessions),
+            self.server_now,
+        );
+        state.klines = vec![];
+
+        volume_curve::store(
+            &self.chart.ticker_info.ticker,
+            config,
+            curve.clone(),
+            self.server_now,
+        );
+        state.curve = Some(curve);
+        self.push_volume_curve();
+    }
+
+    /// Shows sizes in `unit`, `None` for the app wide unit they're streamed in
+    pub fn set_size_unit(&mut self, unit: Option<SizeUnit>) {
+        if self.size_unit != unit {
+            self.size_unit = unit;
+
+            let sizes = self.size_display();
+            for indi in self.indicators.values_mut().flatten() {
+                indi.on_size_display(sizes, &self.data_source);
+            }
+            self.invalidate(None);
+        }
+    }
+
+    fn size_display(&self) -> SizeDisplay {
+        SizeDisplay::new(
+            &self.chart.ticker_info,
+            self.size_unit,
+            exchange::volume_size_unit(),
+        )
+    }
+
+    pub fn set_data_window(&mut self, config: DataWindow) {
+        if self.data_window != config {
+            self.data_window = config;
+            self.chart.cache.clear_crosshair();
+        }
+    }
+
+    /// Rows of the data window for the bar at `at_interval`
+    fn data_window_rows(&self, at_interval: u64, palette: &Extended) -> Vec<DataRow> {
+        let Some((key, kline)) = hovered_bar(&self.data_source, at_interval) else {
+            return vec![];
+        };
+
+        let precision = self.chart.ticker_info.tick_rule().precision();
+        let price = |value: f32| Price::from_f32_lossy(value).to_string(precision);
+        let interval = match self.chart.basis {
+            Basis::Time(timeframe) => timeframe.to_milliseconds(),
+            Basis::Tick(_) => u64::MAX,
+        };
+
+        let change_pct = ((kline.close - kline.open).to_f32() / kline.open.to_f32()) * 100.0;
+        let change_color = if change_pct >= 0.0 {
+            palette.success.base.color
+        } else {
+            palette.danger.base.color
+        };
+
+        let mut rows = vec![
+            DataRow::new(
+                "Time",
+                self.overlay_timezone
+                    .format_crosshair_timestamp(kline.time as i64, interval),
+            ),
+            DataRow::new("Open", kline.open.to_string(precision)),
+            DataRow::new("High", kline.high.to_string(precision)),
+            DataRow::new("Low", kline.low.to_string(precision)),
+            DataRow::new("Close", kline.close.to_string(precision)).colored(change_color),
+            DataRow::new("Change", format!("{change_pct:+.2}%")).colored(change_color),
+        ];
+
+        let sizes = self.size_display();
+        let close = kline.close.to_f32();
+        let (buy, sell) = kline.volume;
+        match data_window::delta(kline.volume) {
+            Some(delta) => {
+                let delta = sizes.qty(delta, close);
+                rows.extend([
+                    DataRow::new("Buy", format_with_commas(sizes.qty(buy, close)))
+                        .colored(palette.success.base.color),
+                    DataRow::new("Sell", format_with_commas(sizes.qty(sell, close)))
+                        .colored(palette.danger.base.color),
+                    DataRow::new("Delta", format_with_commas(delta)).colored(if delta >= 0.0 {
+                        palette.success.base.color
+                    } else {
+                        palette.danger.base.color
+                    }),
+                ]);
+            }
+            None => rows.push(DataRow::new(
+                "Volume",
+                format_with_commas(sizes.qty(sell, close)),
+            )),
+        }
+
+        rows.extend(
+            self.indicators
+                .values()
+                .flatten()
+                .filter_map(|indi| indi.value_at(key))
+                .map(|(label, value)| DataRow::new(label, value)),
+        );
+
+        rows.extend(self.overlays.iter().filter_map(|overlay| {
+            let value = match data_window::point_at(overlay.points(), kline.time)? {
+                indicators::Value::Line(value) => price(value),
+                indicators::Value::Band {
+                    middle,
+                    upper,
+                    lower,
+                } => format!("{} / {} / {}", price(upper), price(middle), price(lower)),
+            };
+            Some(DataRow::new(overlay.params().to_string(), value))
+        }));
+
+        rows
+    }
+
+    /// Shows or hides the economic calendar markers, `None` hides them
+    pub fn set_calendar(&mut self, config: Option<CalendarConfig>) {
+        if self.calendar.config != config {
+            self.calendar.config = config;
+            self.invalidate(None);
+        }
+    }
+
+    /// Calendar events around the current day, looked up in the shared cache before asking the
+    /// venue. Only time based charts have an axis to mark them on.
+    fn calendar_task(&mut self) -> Option<Action> {
+        let Basis::Time(timeframe) = self.chart.basis else {
+            return None;
+        };
+        if self.calendar.config.is_none()
+            || self.server_now == 0
+            || !self.chart.ticker_info.capabilities().economic_calendar
+        {
+            return None;
+        }
+
+        let day = calendar::day_of(self.server_now);
+        if self.calendar.day == Some(day) {
+            return None;
+        }
+        self.calendar.day = Some(day);
+
+        let currencies = exchange::calendar::currencies(&self.chart.ticker_info.ticker);
+        if currencies.is_empty() {
+            return None;
+        }
+        if let Some(events) = calendar::cached(&currencies, day) {
+            self.calendar.events = events;
+            self.invalidate(None);
+            return None;
+        }
+
+        let req_id = uuid::Uuid::new_v4();
+        self.calendar.req_id = Some(req_id);
+
+        let (from, to) = calendar::window(day);
+        let fetch = FetchSpec {
+            req_id,
+            fetch: FetchRange::Calendar(from, to),
+            stream: Some(StreamKind::Kline {
+                ticker_info: self.chart.ticker_info,
+                timeframe,
+            }),
+        };
+        Some(Action::RequestFetch(FetchRequests::from([fetch])))
+    }
+
+    pub fn is_calendar_request(&self, req_id: uuid::Uuid) -> bool {
+        self.calendar.req_id == Some(req_id)
+    }
+
+    /// Takes the fetched calendar events. Sources that failed or don't forward a calendar send
+    /// none, those aren't cached so another chart may try again.
+    pub fn insert_calendar_events(&mut self, events: Vec<CalendarEvent>) {
+        self.calendar.req_id = None;
+
+        if let Some(day) = self.calendar.day
+            && !events.is_empty()
+        {
+            let currencies = exchange::calendar::currencies(&self.chart.ticker_info.ticker);
+            calendar::store(&currencies, day, events.clone());
+        }
+        self.calendar.events = events;
+        self.invalidate(None);
+    }
+
+    pub fn last_traded_price(&self) -> Option<Price> {
+        self.chart.last_price.map(PriceInfoLabel::price)
+    }
+
+    pub fn set_price_alerts(&mut self, alerts: Vec<Price>) {
+        if self.chart.price_alerts != alerts {
+            self.chart.price_alerts = alerts;
+            self.chart.cache.clear_all();
+        }
+    }
+
     pub fn kind(&self) -> &KlineChartKind {
         &self.kind
     }
 
     fn missing_data_task(&mut self) -> Option<Action> {
+        if let Some(action) = self.daily_levels_task() {
+            return Some(action);
+        }
+        if let Some(action) = self.volume_curve_task() {
+            return Some(action);
+        }
+        if let Some(action) = self.calendar_task() {
+            return Some(action);
+        }
+        // The chart's own bars wait until it's zoomed back in to them
+        if self.resolution.base.is_some() {
+            return self.resolution_task();
+        }
+
         match &self.data_source {
             PlotData::TimeBased(timeseries) => {
                 let timeframe_ms = timeseries.interval.to_milliseconds();
@@ -389,6 +1193,7 @@ impl KlineChart {
                 // priority 2, trades fetch
                 if !self.fetching_trades.0
                     && exchange::fetcher::is_trade_fetch_enabled()
+                    && self.chart.ticker_info.capabilities().historical_trades
                     && let Some((fetch_from, fetch_to)) =
                         timeseries.suggest_trade_fetch_range(visible_earliest, visible_latest)
                 {
@@ -486,11 +1291,22 @@ impl KlineChart {
             None => {}
         }
 
+        self.sync_stack_study();
         self.invalidate(None);
     }
 
     pub fn chart_layout(&self) -> ViewConfig {
-        self.chart.layout()
+        let mut layout = self.chart.layout();
+
+        // Saved as wide as the chart's own bars are drawn at the same zoom
+        if let (Some(shown), Basis::Time(base), Some(viewport)) = (
+            self.shown_resolution(),
+            self.basis(),
+            layout.viewport.as_mut(),
+        ) {
+            viewport.cell_width *= base.to_milliseconds() as f32 / shown.to_milliseconds() as f32;
+        }
+        layout
     }
 
     pub fn set_cluster_kind(&mut self, new_kind: ClusterKind) {
@@ -515,25 +1331,230 @@ impl KlineChart {
         self.invalidate(None);
     }
 
+    /// The chart's own basis, not the coarser timeframe it may be showing while zoomed out
     pub fn basis(&self) -> Basis {
-        self.chart.basis
+        match &self.resolution.base {
+            Some((timeframe, _)) => Basis::Time(*timeframe),
+            None => self.chart.basis,
+        }
     }
 
-    pub fn change_tick_size(&mut self, new_tick_size: f32) {
-        let chart = self.mut_state();
+    /// Coarser timeframe shown in place of the chart's own while it's zoomed out
+    pub fn shown_resolution(&self) -> Option<Timeframe> {
+        self.resolution.base.as_ref()?;
+        match self.chart.basis {
+            Basis::Time(timeframe) => Some(timeframe),
+            Basis::Tick(_) => None,
+        }
+    }
 
-        let step = PriceStep::from_f32(new_tick_size);
+    /// Only candles have nothing finer than the bar to lose when shown coarser
+    fn adapts_resolution(&self) -> bool {
+        self.kind == KlineChartKind::Candles && self.replay.is_none() && self.chart.basis.is_time()
+    }
 
-        chart.cell_height *= new_tick_size / chart.tick_size.to_f32_lossy();
-        chart.tick_size = step;
+    /// Whether zooming out further can still move to a coarser timeframe
+    fn can_coarsen(&self) -> bool {
+        let Basis::Time(shown) = self.chart.basis else {
+            return false;
+        };
+        self.adapts_resolution()
+            && resolution::ladder(self.basis_timeframe().unwrap_or(shown)).last() != Some(&shown)
+    }
 
-        match self.data_source {
-            PlotData::TickBased(ref mut tick_aggr) => {
-                tick_aggr.change_tick_size(new_tick_size, &self.raw_trades);
-            }
-            PlotData::TimeBased(ref mut timeseries) => {
-                timeseries.change_tick_size(new_tick_size, &self.raw_trades);
-            }
+    fn basis_timeframe(&self) -> Option<Timeframe> {
+        match self.basis() {
+            Basis::Time(timeframe) => Some(timeframe),
+            Basis::Tick(_) => None,
+        }
+    }
+
+    /// Moves to the timeframe the visible span fits at
+    fn adapt_resolution(&mut self) {
+        if !self.adapts_resolution() {
+            self.show_base();
+            return;
+        }
+        let (Basis::Time(shown), Some(base)) = (self.chart.basis, self.basis_timeframe()) else {
+            return;
+        };
+        let Some((earliest, latest)) = self.visible_timerange() else {
+            return;
+        };
+
+        let target = resolution::pick(
+            base,
+            shown,
+            latest.saturating_sub(earliest),
+            self.chart.bounds.width,
+            self.kind.min_cell_width(),
+        );
+        if target != shown {
+            self.show_resolution(target);
+        }
+    }
+
+    /// Back to the chart's own bars, before anything that works on them
+    fn show_base(&mut self) {
+        if let Some(base) = self.basis_timeframe()
+            && self.resolution.base.is_some()
+        {
+            self.show_resolution(base);
+        }
+    }
+
+    /// Shows `target` bars, the chart's own or coarser ones stitched from the fetched and its
+    /// own. What's at the center stays there, drawn as wide per hour as before.
+    fn show_resolution(&mut self, target: Timeframe) {
+        let Basis::Time(shown) = self.chart.basis else {
+            return;
+        };
+        let center = self.chart.x_to_interval(-self.chart.translation.x);
+        let step = self.chart.tick_size;
+
+        let PlotData::TimeBased(series) = &mut self.data_source else {
+            return;
+        };
+        let current =
+            std::mem::replace(series, TimeSeries::<KlineDataPoint>::new(target, step, &[]));
+        let (base, own) = self.resolution.base.take().unwrap_or((shown, current));
+
+        if target == base {
+            *series = own;
+        } else {
+            let empty = BTreeMap::new();
+            let fetched = self.resolution.layers.get(&target).unwrap_or(&empty);
+            let fine: Vec<Kline> = own.datapoints.values().map(|dp| dp.kline).collect();
+
+            *series = TimeSeries::<KlineDataPoint>::new(
+                target,
+                step,
+                &resolution::stitch(target, fetched, &fine),
+            );
+            self.resolution.base = Some((base, own));
+        }
+
+        if let Some(latest) = series.latest_timestamp() {
+            self.chart.latest_x = latest;
+        }
+        self.chart.basis = Basis::Time(target);
+
+        let ratio = target.to_milliseconds() as f32 / shown.to_milliseconds() as f32;
+        let (min_width, max_width) = (self.min_cell_width(), self.max_cell_width());
+        let chart = &mut self.chart;
+        chart.cell_width = (chart.cell_width * ratio).clamp(min_width, max_width);
+        chart.translation.x = -chart.interval_to_x(center);
+
+        self.indicators
+            .values_mut()
+            .filter_map(Option::as_mut)
+            .for_each(|indi| indi.rebuild_from_source(&self.data_source));
+        self.session.reset();
+        self.rebuild_stacks();
+        self.rebuild_overlays();
+    }
+
+    /// Bars of the coarser timeframe shown, before the earliest it has
+    fn resolution_task(&mut self) -> Option<Action> {
+        let shown = self.shown_resolution()?;
+        // One for a timeframe no longer shown is left to come back unused
+        if self
+            .resolution
+            .req_id
+            .is_some_and(|(_, timeframe, _)| timeframe == shown)
+        {
+            return None;
+        }
+        let PlotData::TimeBased(series) = &self.data_source else {
+            return None;
+        };
+
+        let (visible_earliest, visible_latest) = self.visible_timerange()?;
+        let held_from = match series.datapoints.first_key_value() {
+            Some((time, _)) => *time,
+            None => self.server_now,
+        };
+        if visible_earliest >= held_from
+            || self.resolution.exhausted.get(&shown) == Some(&held_from)
+        {
+            return None;
+        }
+
+        let from = visible_earliest.saturating_sub(visible_latest - visible_earliest);
+        let req_id = uuid::Uuid::new_v4();
+        self.resolution.req_id = Some((req_id, shown, held_from));
+
+        let fetch = FetchSpec {
+            req_id,
+            fetch: FetchRange::Kline(from, held_from),
+            stream: Some(StreamKind::Kline {
+                ticker_info: self.chart.ticker_info,
+                timeframe: shown,
+            }),
+        };
+        Some(Action::RequestFetch(FetchRequests::from([fetch])))
+    }
+
+    pub fn set_coverage(&mut self, coverage: Vec<Stretch>) {
+        self.coverage = coverage;
+    }
+
+    /// Fetches the bars from `from` until `to` again, e.g. a stretch the coverage strip shows
+    /// as missing
+    pub fn backfill(&mut self, from: u64, to: u64) -> Option<Action> {
+        let fetch = FetchRange::Kline(from, to);
+        let req_id = self.request_handler.retry(fetch)?;
+
+        Some(Action::RequestFetch(FetchRequests::from([FetchSpec {
+            req_id,
+            fetch,
+            stream: None,
+        }])))
+    }
+
+    pub fn is_resolution_request(&self, req_id: uuid::Uuid) -> bool {
+        self.resolution
+            .req_id
+            .is_some_and(|(pending, ..)| pending == req_id)
+    }
+
+    /// Keeps fetched bars of a coarser timeframe, and stitches them in while it's shown
+    pub fn insert_resolution_klines(&mut self, klines: &[Kline]) {
+        let Some((_, timeframe, before)) = self.resolution.req_id.take() else {
+            return;
+        };
+        if !klines.iter().any(|kline| kline.time < before) {
+            self.resolution.exhausted.insert(timeframe, before);
+        }
+        self.resolution
+            .layers
+            .entry(timeframe)
+            .or_default()
+            .extend(klines.iter().map(|kline| (kline.time, *kline)));
+
+        if self.shown_resolution() == Some(timeframe) {
+            self.show_resolution(timeframe);
+            self.invalidate(None);
+        }
+    }
+
+    pub fn change_tick_size(&mut self, new_tick_size: f32) {
+        self.exit_replay();
+        self.show_base();
+        let chart = self.mut_state();
+
+        let step = PriceStep::from_f32(new_tick_size);
+
+        chart.cell_height *= new_tick_size / chart.tick_size.to_f32_lossy();
+        chart.tick_size = step;
+
+        match self.data_source {
+            PlotData::TickBased(ref mut tick_aggr) => {
+                tick_aggr.change_tick_size(new_tick_size, &self.raw_trades);
+            }
+            PlotData::TimeBased(ref mut timeseries) => {
+                timeseries.change_tick_size(new_tick_size, &self.raw_trades);
+            }
         }
 
         self.indicators
@@ -541,11 +1562,17 @@ impl KlineChart {
             .filter_map(Option::as_mut)
             .for_each(|indi| indi.on_ticksize_change(&self.data_source));
 
+        self.rebuild_stacks();
+        self.rebuild_overlays();
         self.invalidate(None);
     }
 
     pub fn set_basis(&mut self, new_basis: Basis) -> Option<Action> {
+        self.replay = None;
+        self.chart.replay_input = ReplayInput::Off;
         self.chart.last_price = None;
+        self.session.reset();
+        *self.resolution = Resolution::default();
         self.chart.basis = new_basis;
 
         match new_basis {
@@ -566,6 +1593,8 @@ impl KlineChart {
             .filter_map(Option::as_mut)
             .for_each(|indi| indi.on_basis_change(&self.data_source));
 
+        self.rebuild_stacks();
+        self.rebuild_overlays();
         self.reset_request_handler();
         self.invalidate(Some(Instant::now()))
     }
@@ -585,16 +1614,30 @@ impl KlineChart {
             *studies = new_studies;
         }
 
+        self.sync_stack_study();
         self.invalidate(None);
     }
 
     pub fn insert_trades_buffer(&mut self, trades_buffer: &[Trade]) {
         self.raw_trades.extend_from_slice(trades_buffer);
 
+        // Kept for when the replay ends, bars past the cursor stay hidden until then
+        if self.replay.is_some() {
+            return;
+        }
+
+        if self.reference_lines.session_levels {
+            trades_buffer
+                .iter()
+                .for_each(|trade| self.session.on_trade(trade.time, trade.price));
+            self.sync_levels();
+        }
+
         match self.data_source {
             PlotData::TickBased(ref mut tick_aggr) => {
                 let old_dp_len = tick_aggr.datapoints.len();
                 tick_aggr.insert_trades(trades_buffer);
+                let new_dp_len = tick_aggr.datapoints.len() as u64;
 
                 if let Some(last_dp) = tick_aggr.datapoints.last() {
                     self.chart.last_price =
@@ -610,15 +1653,40 @@ impl KlineChart {
                         indi.on_insert_trades(trades_buffer, old_dp_len, &self.data_source)
                     });
 
+                self.refresh_stacks(old_dp_len.saturating_sub(1) as u64..new_dp_len);
+                self.refresh_tick_overlays(old_dp_len);
+
                 self.invalidate(None);
             }
             PlotData::TimeBased(ref mut timeseries) => {
                 timeseries.insert_trades_existing_buckets(trades_buffer);
+                if let Some((_, base)) = &mut self.resolution.base {
+                    base.insert_trades_existing_buckets(trades_buffer);
+                }
+
+                // Ticks after a session break may have opened the next bar ahead of the server
+                if let Some(latest) = timeseries.latest_timestamp()
+                    && latest > self.chart.latest_x
+                {
+                    self.chart.latest_x = latest;
+                }
+
+                if self.stacks.is_some() {
+                    let mut bars: Vec<u64> = vec![];
+                    for trade in trades_buffer {
+                        let bar = timeseries.bar_time(trade.time);
+                        if !bars.contains(&bar) {
+                            bars.push(bar);
+                        }
+                    }
+                    self.refresh_stacks(bars);
+                }
             }
         }
     }
 
     pub fn insert_raw_trades(&mut self, raw_trades: Vec<Trade>, is_batches_done: bool) {
+        self.show_base();
         match self.data_source {
             PlotData::TickBased(ref mut tick_aggr) => {
                 tick_aggr.insert_trades(&raw_trades);
@@ -629,17 +1697,60 @@ impl KlineChart {
         }
 
         self.raw_trades.extend(raw_trades);
+        self.rebuild_stacks();
+        if matches!(self.data_source, PlotData::TickBased(_)) {
+            self.rebuild_overlays();
+        }
 
         if is_batches_done {
             self.fetching_trades = (false, None);
         }
     }
 
-    pub fn insert_hist_klines(&mut self, req_id: uuid::Uuid, klines_raw: &[Kline]) {
+    /// With `revisions`, fetched bars overlapping held ones are checked for revisions first.
+    /// Returns those found, applied or not as the settings say.
+    pub fn insert_hist_klines(
+        &mut self,
+        req_id: uuid::Uuid,
+        klines_raw: &[Kline],
+        revisions: Option<&RevisionSettings>,
+    ) -> Vec<Revision> {
+        if let Some(replay) = &mut self.replay {
+            replay.merge(klines_raw);
+            if klines_raw.is_empty() {
+                self.request_handler
+                    .mark_failed(req_id, "No data received".to_string());
+            } else {
+                self.request_handler.mark_completed(req_id);
+            }
+            self.reveal_replay();
+            return vec![];
+        }
+        self.show_base();
+
         match self.data_source {
             PlotData::TimeBased(ref mut timeseries) => {
+                let revised = revisions.map_or_else(Vec::new, |settings| {
+                    timeseries.revisions(klines_raw, settings)
+                });
+                let kept: Vec<Kline>;
+                let klines_raw = if revised.is_empty() || revisions.is_some_and(|s| s.auto_apply) {
+                    klines_raw
+                } else {
+                    // The chart's own copy of a revised bar stays
+                    kept = klines_raw
+                        .iter()
+                        .filter(|kline| !revised.iter().any(|r| r.fetched.time == kline.time))
+                        .copied()
+                        .collect();
+                    &kept
+                };
+
                 timeseries.insert_klines(klines_raw);
                 timeseries.insert_trades_existing_buckets(&self.raw_trades);
+                self.session.reset();
+                self.rebuild_stacks();
+                self.rebuild_overlays();
 
                 self.indicators
                     .values_mut()
@@ -653,8 +1764,9 @@ impl KlineChart {
                     self.request_handler.mark_completed(req_id);
                 }
                 self.invalidate(None);
+                revised
             }
-            PlotData::TickBased(_) => {}
+            PlotData::TickBased(_) => vec![],
         }
     }
 
@@ -673,6 +1785,32 @@ impl KlineChart {
         }
     }
 
+    /// Feeds the spread panel from the best bid and ask of a depth update
+    pub fn insert_depth(&mut self, time: u64, depth: &Depth) {
+        let signals = &mut self.signals;
+        if !signals.runners.is_empty()
+            && time.abs_diff(signals.book.time) >= signal::BOOK_INTERVAL.as_millis() as u64
+        {
+            signals.book = BookSnapshot::new(time, depth);
+        }
+
+        let Some(indi) = self.indicators[KlineIndicator::Spread].as_mut() else {
+            return;
+        };
+        let Some(spread) = spread::spread_points(depth, self.chart.ticker_info.min_ticksize) else {
+            return;
+        };
+
+        self.latest_spread = Some(spread);
+        if let Basis::Time(timeframe) = self.chart.basis {
+            indi.on_spread(time, spread, timeframe);
+        }
+    }
+
+    pub fn latest_spread(&self) -> Option<f32> {
+        self.latest_spread
+    }
+
     fn calc_qty_scales(
         &self,
         earliest: u64,
@@ -713,7 +1851,28 @@ impl KlineChart {
         self.last_tick
     }
 
+    pub fn price_scale(&self) -> PriceScale {
+        self.chart.layout.price_scale
+    }
+
+    pub fn set_price_scale(&mut self, price_scale: PriceScale) {
+        if self.chart.layout.price_scale == price_scale {
+            return;
+        }
+        let (min_height, max_height) = (self.kind.min_cell_height(), self.kind.max_cell_height());
+        self.chart
+            .rescale_prices(price_scale, min_height, max_height);
+        self.invalidate(None);
+    }
+
     pub fn invalidate(&mut self, now: Option<Instant>) -> Option<Action> {
+        if let Some(now) = now {
+            self.prune_history(now);
+        }
+        // Restored on a tick, where a notice reaches the pane
+        let notice =
+            now.and_then(|_| super::restore_viewport(self, self.data_source.oldest_interval()));
+        self.adapt_resolution();
         let chart = &mut self.chart;
 
         if let Some(autoscale) = chart.layout.autoscale {
@@ -767,17 +1926,30 @@ impl KlineChart {
                         .data_source
                         .visible_price_range(start_interval, end_interval)
                     {
-                        let padding = (highest - lowest) * 0.05;
-                        let price_span = (highest - lowest) + (2.0 * padding);
+                        let (padded_highest, padded_lowest) = match chart.layout.price_scale {
+                            PriceScale::Log if lowest > 0.0 => {
+                                let padding = ((highest / lowest).ln() * 0.05).exp();
+                                (highest * padding, lowest / padding)
+                            }
+                            _ => {
+                                let padding = (highest - lowest) * 0.05;
+                                (highest + padding, lowest - padding)
+                            }
+                        };
 
-                        if price_span > 0.0 && chart.bounds.height > f32::EPSILON {
-                            let padded_highest = highest + padding;
+                        if padded_highest > padded_lowest
+                            && chart.bounds.height > f32::EPSILON
+                            && chart.tick_size.to_f32_lossy() > 0.0
+                        {
                             let chart_height = chart.bounds.height;
-                            let tick_size = chart.tick_size.to_f32_lossy();
-
-                            if tick_size > 0.0 {
-                                chart.cell_height = (chart_height * tick_size) / price_span;
-                                chart.base_price_y = Price::from_f32(padded_highest);
+                            chart.base_price_y = Price::from_f32(padded_highest);
+
+                            if let Some(cell_height) = chart.cell_height_to_fit(
+                                padded_highest,
+                                padded_lowest,
+                                chart_height,
+                            ) {
+                                chart.cell_height = cell_height;
                                 chart.translation.y = -chart_height / 2.0;
                             }
                         }
@@ -786,6 +1958,14 @@ impl KlineChart {
             }
         }
 
+        if chart.layout.price_scale == PriceScale::Percent {
+            let region = chart.visible_region(chart.bounds.size());
+            let (start_interval, end_interval) = chart.interval_range(&region);
+            chart.percent_anchor = self
+                .data_source
+                .first_close_in_range(start_interval, end_interval);
+        }
+
         chart.cache.clear_all();
         for indi in self.indicators.values_mut().filter_map(Option::as_mut) {
             indi.clear_all_caches();
@@ -793,30 +1973,670 @@ impl KlineChart {
 
         if let Some(t) = now {
             self.last_tick = t;
+            if let Some(notice) = notice {
+                return Some(Action::Notice(notice));
+            }
+            // Replay works on what is already loaded
+            if self.replay.is_some() {
+                return None;
+            }
             self.missing_data_task()
         } else {
             None
         }
     }
 
+    /// Drops bars and raw trades past [`data::retention`]'s limits, oldest first. What's on
+    /// screen stays, and nothing goes while a saved framing, a replay or a trade fetch still
+    /// relies on the loaded history.
+    fn prune_history(&mut self, now: Instant) {
+        if now.duration_since(self.last_prune) < data::retention::PRUNE_INTERVAL
+            || self.chart.pending_viewport.is_some()
+            || self.replay.is_some()
+            || self.fetching_trades.0
+        {
+            return;
+        }
+        self.last_prune = now;
+
+        let retention = data::retention::config();
+        let region = self.chart.visible_region(self.chart.bounds.size());
+        let visible = (region.width > 0.0).then(|| self.chart.interval_range(&region));
+
+        // Tick based intervals count back from the latest bar, the oldest visible is the highest
+        let (keep_bars_from, keep_trades_from) = match (&self.data_source, visible) {
+            (_, None) => (None, None),
+            (PlotData::TimeBased(_), Some((earliest, _))) => (Some(earliest), Some(earliest)),
+            (PlotData::TickBased(tick_aggr), Some((_, oldest))) => {
+                let index = tick_aggr
+                    .datapoints
+                    .len()
+                    .saturating_sub(1)
+                    .saturating_sub(usize::try_from(oldest).unwrap_or(usize::MAX));
+                let time = tick_aggr.datapoints.get(index).map(|dp| dp.kline.time);
+                (Some(oldest), time)
+            }
+        };
+
+        let bars = self
+            .data_source
+            .prune(retention.max_bars(self.chart.basis), keep_bars_from);
+        // Scrolled back to, the chart's own bars are fetched again
+        if let Some((timeframe, base)) = &mut self.resolution.base {
+            base.prune(retention.max_bars(Basis::Time(*timeframe)), None);
+        }
+        let trades = data::retention::prune_trades(
+            &mut self.raw_trades,
+            retention.trades.as_millis(),
+            keep_trades_from,
+        );
+
+        if bars > 0 {
+            self.indicators
+                .values_mut()
+                .filter_map(Option::as_mut)
+                .for_each(|indi| indi.rebuild_from_source(&self.data_source));
+            self.rebuild_stacks();
+            self.rebuild_overlays();
+        }
+        if bars > 0 || trades > 0 {
+            log::debug!(
+                "Pruned {bars} bars and {trades} trades of {}",
+                self.chart.ticker_info.ticker
+            );
+        }
+    }
+
+    pub fn replay(&self) -> Option<&ReplayCursor> {
+        self.replay.as_ref().map(|replay| &replay.cursor)
+    }
+
+    /// Open time of the last revealed bar
+    pub fn replay_time(&self) -> Option<u64> {
+        self.replay.as_ref()?.revealed().last().map(|k| k.time)
+    }
+
+    pub fn supports_replay(&self) -> bool {
+        matches!(self.data_source, PlotData::TimeBased(_)) && !self.is_empty()
+    }
+
+    /// Waits for a click on the chart to pick where the replay starts
+    pub fn arm_replay(&mut self) {
+        if self.supports_replay() {
+            self.chart.replay_input = ReplayInput::PickingStart;
+        }
+    }
+
+    /// Snapshots the loaded klines and hides every bar after the one at `time`
+    pub fn start_replay(&mut self, time: u64) {
+        self.show_base();
+        let PlotData::TimeBased(timeseries) = &self.data_source else {
+            return;
+        };
+
+        let klines: Vec<Kline> = timeseries.datapoints.values().map(|dp| dp.kline).collect();
+        if klines.is_empty() {
+            return;
+        }
+        let revealed = klines.partition_point(|k| k.time <= time);
+
+        self.replay = Some(Box::new(Replay {
+            cursor: ReplayCursor::new(klines.len(), revealed),
+            klines,
+        }));
+        self.chart.replay_input = ReplayInput::Stepping;
+        self.reveal_replay();
+    }
+
+    /// Puts the full history back, including whatever streamed in while replaying
+    pub fn exit_replay(&mut self) {
+        self.chart.replay_input = ReplayInput::Off;
+
+        let Some(replay) = self.replay.take() else {
+            return;
+        };
+        self.rebuild_time_series(&replay.klines);
+    }
+
+    pub fn replay_step(&mut self, delta: isize) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        replay.cursor.pause();
+        let before = replay.cursor.revealed();
+
+        if replay.cursor.step(delta).is_some() {
+            self.on_replay_moved(before);
+        }
+    }
+
+    pub fn replay_toggle_play(&mut self, now: Instant) {
+        if let Some(replay) = &mut self.replay {
+            replay.cursor.toggle_play(now);
+        }
+    }
+
+    pub fn set_replay_speed(&mut self, speed: ReplaySpeed) {
+        if let Some(replay) = &mut self.replay {
+            replay.cursor.set_speed(speed);
+        }
+    }
+
+    /// Reveals the bars playback has due by `now`
+    pub fn poll_replay(&mut self, now: Instant) {
+        let Some(replay) = &mut self.replay else {
+            return;
+        };
+        let before = replay.cursor.revealed();
+
+        if replay.cursor.advance(now) > 0 {
+            self.on_replay_moved(before);
+        }
+    }
+
+    /// Going forward appends the new bars, going back rebuilds from the snapshot
+    fn on_replay_moved(&mut self, revealed_before: usize) {
+        let Some(replay) = &self.replay else {
+            return;
+        };
+        let revealed = replay.cursor.revealed();
+
+        if revealed < revealed_before {
+            self.reveal_replay();
+            return;
+        }
+
+        let PlotData::TimeBased(timeseries) = &mut self.data_source else {
+            return;
+        };
+        let new_klines = replay.klines[revealed_before..revealed].to_vec();
+        let (Some(first), Some(last)) = (new_klines.first().copied(), new_klines.last().copied())
+        else {
+            return;
+        };
+
+        let until = last.time + timeseries.interval.to_milliseconds();
+        let trades: Vec<Trade> = self
+            .raw_trades
+            .iter()
+            .filter(|t| t.time >= first.time && t.time < until)
+            .copied()
+            .collect();
+
+        timeseries.insert_klines(&new_klines);
+        timeseries.insert_trades_existing_buckets(&trades);
+
+        self.indicators
+            .values_mut()
+            .filter_map(Option::as_mut)
+            .for_each(|indi| indi.on_insert_klines(&new_klines));
+        self.update_overlays(&new_klines);
+
+        let new_bars: Vec<u64> = new_klines.iter().map(|k| k.time).collect();
+
+        self.chart.latest_x = last.time;
+        self.chart.last_price = Some(PriceInfoLabel::new(last.close, last.open));
+        self.refresh_stacks(new_bars);
+        self.invalidate(None);
+    }
+
+    /// Rebuilds the data source and indicators from the revealed bars only
+    fn reveal_replay(&mut self) {
+        let Some(replay) = self.replay.take() else {
+            return;
+        };
+        self.rebuild_time_series(replay.revealed());
+        self.replay = Some(replay);
+    }
+
+    fn rebuild_time_series(&mut self, klines: &[Kline]) {
+        let PlotData::TimeBased(timeseries) = &self.data_source else {
+            return;
+        };
+        let interval = timeseries.interval;
+
+        let until = klines
+            .last()
+            .map_or(0, |k| k.time + interval.to_milliseconds());
+        let trades: Vec<Trade> = self
+            .raw_trades
+            .iter()
+            .filter(|t| t.time < until)
+            .copied()
+            .collect();
+
+        self.data_source = PlotData::TimeBased(
+            TimeSeries::<KlineDataPoint>::new(interval, self.chart.tick_size, klines)
+                .with_trades(&trades),
+        );
+
+        self.indicators
+            .values_mut()
+            .filter_map(Option::as_mut)
+            .for_each(|indi| indi.rebuild_from_source(&self.data_source));
+        self.rebuild_stacks();
+        self.rebuild_overlays();
+
+        if let Some(last) = klines.last() {
+            self.chart.latest_x = last.time;
+            self.chart.last_price = Some(PriceInfoLabel::new(last.close, last.open));
+        }
+        self.session.reset();
+        self.invalidate(None);
+    }
+
+    fn stack_params(&self) -> Option<imbalance::Params> {
+        let KlineChartKind::Footprint { studies, .. } = &self.kind else {
+            return None;
+        };
+
+        studies.iter().find_map(|study| {
+            if let FootprintStudy::StackedImbalance { ratio, levels } = study {
+                Some(imbalance::Params::new(*ratio, *levels))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Rescans every bar if the stacked imbalance study was toggled or configured
+    fn sync_stack_study(&mut self) {
+        if self.stack_params() != self.stacks.as_ref().map(StackCache::params) {
+            self.rebuild_stacks();
+        }
+    }
+
+    /// Rescans every bar, for when the data source was rebuilt or refilled
+    fn rebuild_stacks(&mut self) {
+        self.stacks = self.stack_params().map(StackCache::new);
+        if self.stacks.is_none() {
+            return;
+        }
+
+        let bars: Vec<u64> = match &self.data_source {
+            PlotData::TimeBased(timeseries) => timeseries.datapoints.keys().copied().collect(),
+            PlotData::TickBased(tick_aggr) => (0..tick_aggr.datapoints.len() as u64).collect(),
+        };
+        self.refresh_stacks(bars);
+    }
+
+    /// Rescans only `bars`, tick based bars are keyed by their index from the oldest
+    fn refresh_stacks(&mut self, bars: impl IntoIterator<Item = u64>) {
+        let Some(stacks) = &mut self.stacks else {
+            return;
+        };
+        let step = self.chart.tick_size;
+
+        for bar in bars {
+            let footprint = match &self.data_source {
+                PlotData::TimeBased(timeseries) => {
+                    timeseries.datapoints.get(&bar).map(|dp| &dp.footprint)
+                }
+                PlotData::TickBased(tick_aggr) => tick_aggr
+                    .datapoints
+                    .get(bar as usize)
+                    .map(|dp| &dp.footprint),
+            };
+            if let Some(footprint) = footprint {
+                stacks.refresh(bar, footprint, step);
+            }
+        }
+    }
+
+    pub fn overlays(&self) -> Vec<Overlay> {
+        self.overlays
+            .iter()
+            .map(|overlay| overlay.params())
+            .collect()
+    }
+
+    /// Rebuilds the overlays when their parameters, or the timezone their sessions are judged
+    /// in, changed
+    pub fn sync_overlays(&mut self, overlays: &[Overlay], timezone: UserTimezone) {
+        // Scripts rebuild after a reload changed any of them
+        let scripts_generation = indicators::script::generation();
+        let scripts_reloaded = self.overlay_scripts_generation != scripts_generation
+            && overlays
+                .iter()
+                .any(|overlay| matches!(overlay, Overlay::Script { .. }));
+
+        if self.overlay_timezone == timezone && self.overlays() == overlays && !scripts_reloaded {
+            return;
+        }
+
+        self.overlay_timezone = timezone;
+        self.overlay_scripts_generation = scripts_generation;
+        self.overlays = overlays
+            .iter()
+            .map(|overlay| overlay.build(timezone))
+            .collect();
+        self.rebuild_overlays();
+        self.invalidate(None);
+    }
+
+    /// Judges the bars that closed since the last call for candle patterns, and older ones
+    /// fetched since, returns the patterns to announce. `None` turns the markers off. Nothing is
+    /// announced while replaying.
+    pub fn poll_patterns(&mut self, config: Option<PatternConfig>) -> Vec<PatternMark> {
+        let reconfigured = self.patterns.configure(config);
+        if config.is_none() {
+            if reconfigured {
+                self.invalidate(None);
+            }
+            return vec![];
+        }
+
+        let (first, latest_closed) = match &self.data_source {
+            PlotData::TimeBased(timeseries) => (
+                timeseries.datapoints.keys().next().copied(),
+                timeseries.datapoints.keys().nth_back(1).copied(),
+            ),
+            PlotData::TickBased(tick_aggr) => (
+                tick_aggr.datapoints.first().map(|dp| dp.kline.time),
+                tick_aggr
+                    .datapoints
+                    .iter()
+                    .nth_back(1)
+                    .map(|dp| dp.kline.time),
+            ),
+        };
+        let (Some(first), Some(latest_closed)) = (first, latest_closed) else {
+            return vec![];
+        };
+
+        // Bars were taken away, e.g. by a replay starting
+        if let Some((_, latest)) = self.patterns.judged()
+            && latest_closed < latest
+        {
+            self.patterns.reset();
+        }
+
+        let marked = self.patterns.marks().len();
+        let announced = self.replay.is_none().then_some(latest_closed);
+
+        let fresh = match self.patterns.judged() {
+            Some((earliest, latest)) => {
+                if first < earliest {
+                    let older = klines_between(&self.data_source, first, earliest);
+                    self.patterns.update(&older, None);
+                }
+                if latest_closed > latest {
+                    let closed = klines_between(&self.data_source, latest, latest_closed);
+                    self.patterns.update(&closed, announced)
+                } else {
+                    vec![]
+                }
+            }
+            None => {
+                let history = klines_between(&self.data_source, first, latest_closed);
+                self.patterns.update(&history, None);
+                vec![]
+            }
+        };
+
+        if reconfigured || self.patterns.marks().len() != marked {
+            self.invalidate(None);
+        }
+
+        fresh
+    }
+
+    /// Runs the pane's signal scripts over the latest bars and book, each at most every
+    /// [`signal::RUN_INTERVAL`], and returns what they announced by script name. Changed setups
+    /// or reloaded scripts rebuild them. Nothing runs while replaying.
+    pub fn poll_signals(
+        &mut self,
+        setups: &[SignalSetup],
+        now: Instant,
+    ) -> Vec<(String, signal::Output)> {
+        let generation = indicators::script::generation();
+        let signals = &mut self.signals;
+
+        if signals.generation != generation
+            || !signals
+                .runners
+                .iter()
+                .map(SignalRunner::setup)
+                .eq(setups.iter())
+        {
+            let had_marks = signals
+                .runners
+                .iter()
+                .any(|runner| !runner.marks().is_empty());
+
+            signals.generation = generation;
+            signals.runners = setups
+                .iter()
+                .map(|setup| SignalRunner::new(setup, indicators::script::get(&setup.name)))
+                .collect();
+            if signals.runners.is_empty() {
+                signals.book = BookSnapshot::default();
+            }
+            if had_marks {
+                self.invalidate(None);
+            }
+        }
+
+        if self.replay.is_some() || !self.signals.runners.iter().any(|r| r.is_due(now)) {
+            return vec![];
+        }
+
+        let klines = latest_klines(&self.data_source, indicators::script::MAX_BARS);
+        let signals = &mut self.signals;
+        let mut outputs = vec![];
+        let mut marked = false;
+
+        for runner in &mut signals.runners {
+            let last_mark = runner.marks().last().cloned();
+            let name = runner.setup().name.clone();
+
+            outputs.extend(
+                runner
+                    .run(&klines, &signals.book, now)
+                    .into_iter()
+                    .map(|output| (name.clone(), output)),
+            );
+            marked |= runner.marks().last() != last_mark.as_ref();
+        }
+
+        if marked {
+            self.invalidate(None);
+        }
+        outputs
+    }
+
+    /// Lets the signal scripts disabled for running too long run again
+    pub fn rearm_signals(&mut self) {
+        self.signals
+            .runners
+            .iter_mut()
+            .filter(|runner| runner.is_disabled())
+            .for_each(SignalRunner::rearm);
+    }
+
+    pub fn disabled_signals(&self) -> Vec<String> {
+        self.signals
+            .runners
+            .iter()
+            .filter(|runner| runner.is_disabled())
+            .map(|runner| runner.setup().name.clone())
+            .collect()
+    }
+
+    /// Rescans the latest bars for stop runs, at most once a second unless `params` changed, and
+    /// returns the runs to announce. `None` turns the markers off. Nothing is announced while
+    /// replaying.
+    pub fn poll_stop_runs(
+        &mut self,
+        params: Option<stop_run::Params>,
+        now: Instant,
+    ) -> Vec<StopRun> {
+        let Some(params) = params else {
+            if self.stop_run_scan.take().is_some() {
+                self.stop_runs.clear();
+                self.invalidate(None);
+            }
+            return vec![];
+        };
+
+        match self.stop_run_scan {
+            Some((last, scanned)) if scanned == params => {
+                if now.duration_since(last) < STOP_RUN_SCAN_INTERVAL {
+                    return vec![];
+                }
+            }
+            _ => self.stop_runs.clear(),
+        }
+        self.stop_run_scan = Some((now, params));
+
+        let klines = latest_klines(&self.data_source, STOP_RUN_SCAN_BARS);
+
+        let Some(first) = klines.first() else {
+            return vec![];
+        };
+        // Fetched history is appended after live trades
+        let mut trades: Vec<Trade> = self
+            .raw_trades
+            .iter()
+            .filter(|trade| trade.time >= first.time)
+            .copied()
+            .collect();
+        trades.sort_by_key(|trade| trade.time);
+
+        let runs = stop_run::detect(&klines, &trades, &params, self.server_now);
+        let latest_closed = klines
+            .iter()
+            .rev()
+            .nth(1)
+            .map(|kline| kline.time)
+            .filter(|_| self.replay.is_none());
+
+        let marked = self.stop_runs.runs().to_vec();
+        let fresh = self.stop_runs.update(runs, latest_closed);
+        if self.stop_runs.runs() != marked {
+            self.invalidate(None);
+        }
+
+        fresh
+    }
+
+    /// Errors the overlays ran into since the last call, e.g. a failing script
+    pub fn take_overlay_errors(&mut self) -> Vec<String> {
+        self.overlays
+            .iter_mut()
+            .filter_map(|overlay| {
+                let error = overlay.take_error()?;
+                Some(format!("{}: {error}", overlay.params().name()))
+            })
+            .collect()
+    }
+
+    /// Recomputes every overlay point, for when the data source was rebuilt or backfilled
+    fn rebuild_overlays(&mut self) {
+        if self.overlays.is_empty() {
+            return;
+        }
+
+        let klines: Vec<Kline> = match &self.data_source {
+            PlotData::TimeBased(timeseries) => {
+                timeseries.datapoints.values().map(|dp| dp.kline).collect()
+            }
+            PlotData::TickBased(tick_aggr) => {
+                tick_aggr.datapoints.iter().map(|dp| dp.kline).collect()
+            }
+        };
+        self.overlays
+            .iter_mut()
+            .for_each(|overlay| overlay.recompute(&klines));
+    }
+
+    /// Folds time based bars into the overlays, a forming bar only recomputes its own point
+    fn update_overlays(&mut self, klines: &[Kline]) {
+        let mut in_order = true;
+        for kline in klines {
+            for overlay in &mut self.overlays {
+                in_order &= overlay.update(kline);
+            }
+        }
+
+        if !in_order {
+            self.rebuild_overlays();
+        }
+    }
+
+    /// Folds in tick based bars from the one that was forming at `old_dp_len - 1` on
+    fn refresh_tick_overlays(&mut self, old_dp_len: usize) {
+        let PlotData::TickBased(tick_aggr) = &self.data_source else {
+            return;
+        };
+
+        let mut out_of_sync = false;
+        for overlay in &mut self.overlays {
+            if overlay.points().len() != old_dp_len {
+                out_of_sync = true;
+                continue;
+            }
+
+            let mut bars = tick_aggr.datapoints[old_dp_len.saturating_sub(1)..]
+                .iter()
+                .map(|dp| &dp.kline);
+            if old_dp_len > 0
+                && let Some(forming) = bars.next()
+            {
+                overlay.amend_last(forming);
+            }
+            bars.for_each(|kline| overlay.push(kline));
+        }
+
+        if out_of_sync {
+            self.rebuild_overlays();
+        }
+    }
+
     pub fn toggle_indicator(&mut self, indicator: KlineIndicator) {
-        let prev_indi_count = self.indicators.values().filter(|v| v.is_some()).count();
+        let mut order = self.panels.clone();
+        order.retain(|panel| *panel != indicator);
 
         if self.indicators[indicator].is_some() {
             self.indicators[indicator] = None;
+            if indicator == KlineIndicator::Spread {
+                self.latest_spread = None;
+            }
         } else {
             let mut box_indi = indicator::kline::make_empty(indicator);
             box_indi.rebuild_from_source(&self.data_source);
+            box_indi.on_size_display(self.size_display(), &self.data_source);
             self.indicators[indicator] = Some(box_indi);
+            if indicator == KlineIndicator::Volume {
+                self.push_volume_curve();
+            }
+            order.push(indicator);
         }
 
-        if let Some(main_split) = self.chart.layout.splits.first() {
-            let current_indi_count = self.indicators.values().filter(|v| v.is_some()).count();
-            self.chart.layout.splits = data::util::calc_panel_splits(
-                *main_split,
-                current_indi_count,
-                Some(prev_indi_count),
-            );
+        self.arrange_panels(&order);
+    }
+
+    /// Lays the panels out in `order`, each at the height it was last given
+    pub fn arrange_panels(&mut self, order: &[KlineIndicator]) {
+        let available = KlineIndicator::for_market(self.chart.ticker_info.market_type());
+        let shown = order
+            .iter()
+            .copied()
+            .filter(|panel| available.contains(panel) && self.indicators[*panel].is_some())
+            .collect::<Vec<_>>();
+
+        let layout = &mut self.chart.layout;
+        let heights = PanelHeights::restore(layout.panel_heights.as_ref(), &layout.splits, &shown);
+        layout.splits = heights.splits(&shown);
+        layout.panel_heights = Some(heights);
+        self.panels = shown;
+    }
+
+    /// Keeps the heights the panels were just resized to
+    pub fn record_panel_heights(&mut self) {
+        let layout = &mut self.chart.layout;
+        if let Some(heights) = &mut layout.panel_heights {
+            heights.record(&layout.splits, &self.panels);
         }
     }
 }
@@ -915,6 +2735,7 @@ impl canvas::Program<Message> for KlineChart {
                         };
                         should_show_text(cell_height_unscaled, cell_width_unscaled, min_w)
                     };
+                    let labels = show_text.then(|| self.size_display());
 
                     draw_all_npocs(
                         &self.data_source,
@@ -954,7 +2775,7 @@ impl canvas::Program<Message> for KlineChart {
                                 palette,
                                 text_size,
                                 self.tick_size(),
-                                show_text,
+                                labels,
                                 imbalance,
                                 kline,
                                 trades,
@@ -963,6 +2784,21 @@ impl canvas::Program<Message> for KlineChart {
                             );
                         },
                     );
+
+                    if let Some(stacks) = &self.stacks {
+                        draw_stacked_imbalances(
+                            &self.data_source,
+                            stacks,
+                            frame,
+                            price_to_y,
+                            interval_to_x,
+                            chart.cell_width,
+                            chart.cell_height,
+                            palette,
+                            earliest,
+                            latest,
+                        );
+                    }
                 }
                 KlineChartKind::Candles => {
                     let candle_width = chart.cell_width * 0.8;
@@ -987,6 +2823,67 @@ impl canvas::Program<Message> for KlineChart {
                 }
             }
 
+            draw_patterns(
+                &self.data_source,
+                self.patterns.marks(),
+                frame,
+                price_to_y,
+                interval_to_x,
+                chart.cell_width,
+                palette,
+                earliest,
+                latest,
+            );
+
+            draw_stop_runs(
+                &self.data_source,
+                self.stop_runs.runs(),
+                frame,
+                price_to_y,
+                interval_to_x,
+                chart.cell_width,
+                palette,
+                earliest,
+                latest,
+            );
+
+            draw_signal_marks(
+                &self.data_source,
+                self.signals.runners.iter().flat_map(SignalRunner::marks),
+                frame,
+                price_to_y,
+                interval_to_x,
+                palette,
+                earliest,
+                latest,
+            );
+
+            draw_overlays(
+                &self.overlays,
+                frame,
+                price_to_y,
+                interval_to_x,
+                chart.basis.is_time(),
+                earliest,
+                latest,
+                chart.scaling,
+            );
+
+            if let Some(config) = self.calendar.config
+                && chart.basis.is_time()
+            {
+                draw_calendar_markers(
+                    &calendar_groups(&self.calendar.events, config),
+                    frame,
+                    region,
+                    interval_to_x,
+                    chart.scaling,
+                    palette,
+                );
+            }
+
+            chart.draw_drawings(frame, palette, region);
+            chart.draw_levels(frame, palette, region);
             chart.draw_last_price_line(frame, palette, region);
         });
 
@@ -995,13 +2892,39 @@ impl canvas::Program<Message> for KlineChart {
                 let (_, rounded_aggregation) =
                     chart.draw_crosshair(frame, theme, bounds_size, cursor_position, interaction);
 
-                draw_crosshair_tooltip(
-                    &self.data_source,
-                    &chart.ticker_info,
-                    frame,
-                    palette,
-                    rounded_aggregation,
-                );
+                if self.data_window.visible {
+                    draw_data_window(
+                        frame,
+                        palette,
+                        bounds_size,
+                        self.data_window.corner,
+                        &self.data_window_rows(rounded_aggregation, palette),
+                    );
+                } else {
+                    draw_crosshair_tooltip(
+                        &self.data_source,
+                        &chart.ticker_info,
+                        frame,
+                        palette,
+                        rounded_aggregation,
+                    );
+                }
+
+                if let Some(config) = self.calendar.config
+                    && chart.basis.is_time()
+                {
+                    let region = chart.visible_region(bounds_size);
+                    let to_screen_x = |time: u64| {
+                        (chart.interval_to_x(time) - region.x) / region.width * bounds_size.width
+                    };
+                    draw_calendar_tooltip(
+                        &calendar_groups(&self.calendar.events, config),
+                        frame,
+                        to_screen_x,
+                        cursor_position,
+                        palette,
+                    );
+                }
             }
         });
 
@@ -1017,7 +2940,7 @@ impl canvas::Program<Message> for KlineChart {
         match interaction {
             Interaction::Panning { .. } => mouse::Interaction::Grabbing,
             Interaction::Zoomin { .. } => mouse::Interaction::ZoomIn,
-            Interaction::None | Interaction::Ruler { .. } => {
+            Interaction::None | Interaction::Ruler { .. } | Interaction::Drawing { .. } => {
                 if cursor.is_over(bounds) {
                     mouse::Interaction::Crosshair
                 } else {
@@ -1153,6 +3076,546 @@ fn render_data_source<F>(
     }
 }
 
+/// Closed bars opened from `from` to `to`, both included
+/// The newest `count` bars, oldest first
+fn latest_klines(data_source: &PlotData<KlineDataPoint>, count: usize) -> Vec<Kline> {
+    let mut klines: Vec<Kline> = match data_source {
+        PlotData::TimeBased(timeseries) => timeseries
+            .datapoints
+            .values()
+            .rev()
+            .take(count)
+            .map(|dp| dp.kline)
+            .collect(),
+        PlotData::TickBased(tick_aggr) => tick_aggr
+            .datapoints
+            .iter()
+            .rev()
+            .take(count)
+            .map(|dp| dp.kline)
+            .collect(),
+    };
+    klines.reverse();
+    klines
+}
+
+fn klines_between(data_source: &PlotData<KlineDataPoint>, from: u64, to: u64) -> Vec<Kline> {
+    match data_source {
+        PlotData::TimeBased(timeseries) => timeseries
+            .datapoints
+            .range(from..=to)
+            .map(|(_, dp)| dp.kline)
+            .collect(),
+        PlotData::TickBased(tick_aggr) => {
+            let start = tick_aggr
+                .datapoints
+                .partition_point(|dp| dp.kline.time < from);
+            tick_aggr.datapoints[start..]
+                .iter()
+                .take_while(|dp| dp.kline.time <= to)
+                .map(|dp| dp.kline)
+                .collect()
+        }
+    }
+}
+
+/// The bar opened at `time` and its x, `None` while it's out of view
+fn visible_bar(
+    data_source: &PlotData<KlineDataPoint>,
+    time: u64,
+    interval_to_x: impl Fn(u64) -> f32,
+    earliest: u64,
+    latest: u64,
+) -> Option<(f32, Kline)> {
+    match data_source {
+        PlotData::TimeBased(timeseries) => {
+            if !(earliest..=latest).contains(&time) {
+                return None;
+            }
+            let dp = timeseries.datapoints.get(&time)?;
+            Some((interval_to_x(time), dp.kline))
+        }
+        PlotData::TickBased(tick_aggr) => {
+            let position = tick_aggr
+                .datapoints
+                .iter()
+                .rposition(|dp| dp.kline.time == time)?;
+            // x is counted from the newest bar
+            let index = (tick_aggr.datapoints.len() - 1 - position) as u64;
+            (earliest..=latest)
+                .contains(&index)
+                .then(|| (interval_to_x(index), tick_aggr.datapoints[position].kline))
+        }
+    }
+}
+
+/// A dot with the script's text above it for each signal mark, at the price it gave or over the
+/// bar's high
+fn draw_signal_marks<'a>(
+    data_source: &PlotData<KlineDataPoint>,
+    marks: impl Iterator<Item = &'a SignalMark>,
+    frame: &mut canvas::Frame,
+    price_to_y: impl Fn(Price) -> f32,
+    interval_to_x: impl Fn(u64) -> f32,
+    palette: &Extended,
+    earliest: u64,
+    latest: u64,
+) {
+    let color = palette.primary.strong.color;
+
+    for mark in marks {
+        let Some((x, kline)) =
+            visible_bar(data_source, mark.time, &interval_to_x, earliest, latest)
+        else {
+            continue;
+        };
+        let y = match mark.price {
+            Some(price) => price_to_y(Price::from_f32(price)),
+            None => price_to_y(kline.high) - 6.0,
+        };
+
+        frame.fill(&Path::circle(Point::new(x, y), 2.5), color);
+        frame.fill_text(canvas::Text {
+            content: mark.text.clone(),
+            position: Point::new(x, y - 5.0),
+            size: iced::Pixels(10.0),
+            color: color.scale_alpha(0.9),
+            font: style::AZERET_MONO,
+            align_x: Alignment::Center.into(),
+            align_y: Alignment::End.into(),
+            ..canvas::Text::default()
+        });
+    }
+}
+
+/// Letters past the bar's extreme for each candle pattern it formed, below bullish ones and
+/// above the rest, stacked when a bar formed several
+fn draw_patterns(
+    data_source: &PlotData<KlineDataPoint>,
+    marks: &[PatternMark],
+    frame: &mut canvas::Frame,
+    price_to_y: impl Fn(Price) -> f32,
+    interval_to_x: impl Fn(u64) -> f32,
+    cell_width: f32,
+    palette: &Extended,
+    earliest: u64,
+    latest: u64,
+) {
+    let bar_of = |time: u64| visible_bar(data_source, time, &interval_to_x, earliest, latest);
+
+    let size = (cell_width * 0.8).clamp(8.0, 12.0);
+    // Glyphs drawn so far on the current bar, above and below it
+    let mut stacked = (None, 0.0, 0.0);
+
+    for mark in marks {
+        let Some((x, kline)) = bar_of(mark.time) else {
+            continue;
+        };
+        if stacked.0 != Some(mark.time) {
+            stacked = (Some(mark.time), 0.0, 0.0);
+        }
+
+        let (color, y, align_y) = match mark.kind.is_bullish() {
+            Some(true) => {
+                let y = price_to_y(kline.low) + 3.0 + stacked.2;
+                stacked.2 += size;
+                (palette.success.base.color, y, Alignment::Start)
+            }
+            bullish => {
+                let y = price_to_y(kline.high) - 3.0 - stacked.1;
+                stacked.1 += size;
+                let color = if bullish.is_none() {
+                    palette.background.base.text
+                } else {
+                    palette.danger.base.color
+                };
+                (color, y, Alignment::End)
+            }
+        };
+
+        frame.fill_text(canvas::Text {
+            content: mark.kind.glyph().to_string(),
+            position: Point::new(x, y),
+            size: iced::Pixels(size),
+            color: color.scale_alpha(0.7),
+            font: style::AZERET_MONO,
+            align_x: Alignment::Center.into(),
+            align_y: align_y.into(),
+            ..canvas::Text::default()
+        });
+    }
+}
+
+/// Marks each stop run with a triangle past the bar's extreme pointing the way it reversed,
+/// and a line along the swept level from the swing
+fn draw_stop_runs(
+    data_source: &PlotData<KlineDataPoint>,
+    runs: &[StopRun],
+    frame: &mut canvas::Frame,
+    price_to_y: impl Fn(Price) -> f32,
+    interval_to_x: impl Fn(u64) -> f32,
+    cell_width: f32,
+    palette: &Extended,
+    earliest: u64,
+    latest: u64,
+) {
+    let x_of = |time: u64| -> Option<f32> {
+        match data_source {
+            PlotData::TimeBased(_) => (earliest..=latest)
+                .contains(&time)
+                .then(|| interval_to_x(time)),
+            PlotData::TickBased(tick_aggr) => {
+                let position = tick_aggr
+                    .datapoints
+                    .iter()
+                    .rposition(|dp| dp.kline.time == time)?;
+                // x is counted from the newest bar
+                let index = (tick_aggr.datapoints.len() - 1 - position) as u64;
+                (earliest..=latest)
+                    .contains(&index)
+                    .then(|| interval_to_x(index))
+            }
+        }
+    };
+
+    let half = cell_width * 0.3;
+
+    for run in runs {
+        let Some(x) = x_of(run.time) else {
+            continue;
+        };
+
+        let color = if run.swept_high {
+            palette.danger.base.color
+        } else {
+            palette.success.base.color
+        };
+
+        let level_y = price_to_y(run.level);
+        let swing_x = x_of(run.swing_time).unwrap_or_else(|| interval_to_x(earliest));
+        frame.stroke(
+            &Path::line(Point::new(swing_x, level_y), Point::new(x, level_y)),
+            Stroke::with_color(
+                Stroke {
+                    width: 1.0,
+                    ..Default::default()
+                },
+                color.scale_alpha(0.6),
+            ),
+        );
+
+        // Past the extreme, pointing back inside
+        let extreme_y = price_to_y(run.extreme);
+        let (base_y, tip_y) = if run.swept_high {
+            (extreme_y - 2.5 * half, extreme_y - half)
+        } else {
+            (extreme_y + 2.5 * half, extreme_y + half)
+        };
+        let triangle = Path::new(|builder| {
+            builder.move_to(Point::new(x - half, base_y));
+            builder.line_to(Point::new(x + half, base_y));
+            builder.line_to(Point::new(x, tip_y));
+            builder.close();
+        });
+        frame.fill(&triangle, color);
+    }
+}
+
+/// Radius of a calendar marker in px, they sit this far and a bit above the time axis
+const CALENDAR_MARKER_RADIUS: f32 = 4.0;
+const CALENDAR_MARKER_GAP: f32 = 3.0;
+
+/// Shown calendar events, grouped by the time they're released at
+fn calendar_groups(events: &[CalendarEvent], config: CalendarConfig) -> Vec<Vec<&CalendarEvent>> {
+    let shown = events
+        .iter()
+        .filter(|event| config.shows(event))
+        .collect::<Vec<_>>();
+
+    shown
+        .chunk_by(|a, b| a.time == b.time)
+        .map(<[&CalendarEvent]>::to_vec)
+        .collect()
+}
+
+fn importance_color(importance: Importance, palette: &Extended) -> iced::Color {
+    match importance {
+        Importance::High => palette.danger.base.color,
+        Importance::Medium => palette.warning.base.color,
+        Importance::Low => palette.background.strong.color,
+    }
+}
+
+/// Marks each release time along the bottom edge, colored by its most important event
+fn draw_calendar_markers(
+    groups: &[Vec<&CalendarEvent>],
+    frame: &mut canvas::Frame,
+    region: Rectangle,
+    interval_to_x: impl Fn(u64) -> f32,
+    scaling: f32,
+    palette: &Extended,
+) {
+    let radius = CALENDAR_MARKER_RADIUS / scaling;
+    let y = region.y + region.height - radius - CALENDAR_MARKER_GAP / scaling;
+
+    for group in groups {
+        let Some(importance) = group.iter().map(|event| event.importance).max() else {
+            continue;
+        };
+        let x = interval_to_x(group[0].time);
+        if x < region.x || x > region.x + region.width {
+            continue;
+        }
+
+        let diamond = Path::new(|builder| {
+            builder.move_to(Point::new(x, y - radius));
+            builder.line_to(Point::new(x + radius, y));
+            builder.line_to(Point::new(x, y + radius));
+            builder.line_to(Point::new(x - radius, y));
+            builder.close();
+        });
+        frame.fill(&diamond, importance_color(importance, palette));
+    }
+}
+
+/// Lists the events of the marker under the cursor, above it
+fn draw_calendar_tooltip(
+    groups: &[Vec<&CalendarEvent>],
+    frame: &mut canvas::Frame,
+    to_screen_x: impl Fn(u64) -> f32,
+    cursor: Point,
+    palette: &Extended,
+) {
+    let bottom = frame.height();
+    let marker_y = bottom - CALENDAR_MARKER_RADIUS - CALENDAR_MARKER_GAP;
+    if (cursor.y - marker_y).abs() > CALENDAR_MARKER_RADIUS * 2.0 {
+        return;
+    }
+
+    let Some((x, group)) = groups
+        .iter()
+        .map(|group| (to_screen_x(group[0].time), group))
+        .filter(|(x, _)| (x - cursor.x).abs() <= CALENDAR_MARKER_RADIUS * 2.0)
+        .min_by(|(a, _), (b, _)| (a - cursor.x).abs().total_cmp(&(b - cursor.x).abs()))
+    else {
+        return;
+    };
+
+    let mut lines = vec![];
+    for event in group {
+        lines.push((
+            format!("{} {}", event.currency, event.title),
+            importance_color(event.importance, palette),
+        ));
+
+        let values = [
+            ("A", &event.actual),
+            ("F", &event.forecast),
+            ("P", &event.previous),
+        ]
+        .into_iter()
+        .filter_map(|(label, value)| value.as_ref().map(|value| format!("{label} {value}")))
+        .collect::<Vec<_>>();
+        if !values.is_empty() {
+            lines.push((values.join("  "), palette.background.base.text));
+        }
+    }
+
+    let line_height = 16.0;
+    let width = lines
+        .iter()
+        .map(|(line, _)| line.chars().count() as f32 * 7.0)
+        .fold(0.0, f32::max)
+        + 12.0;
+    let height = lines.len() as f32 * line_height + 8.0;
+
+    let left = (x - width / 2.0).clamp(0.0, (frame.width() - width).max(0.0));
+    let top = (marker_y - CALENDAR_MARKER_RADIUS * 2.0 - height).max(0.0);
+
+    frame.fill_rectangle(
+        Point::new(left, top),
+        Size::new(width, height),
+        palette.background.weakest.color.scale_alpha(0.95),
+    );
+
+    for (i, (line, color)) in lines.into_iter().enumerate() {
+        frame.fill_text(canvas::Text {
+            content: line,
+            position: Point::new(left + 6.0, top + 4.0 + i as f32 * line_height),
+            size: iced::Pixels(12.0),
+            color,
+            font: style::AZERET_MONO,
+            ..canvas::Text::default()
+        });
+    }
+}
+
+/// Tints the cells of every stack and marks it on the edge of the side it favors
+fn draw_stacked_imbalances(
+    data_source: &PlotData<KlineDataPoint>,
+    stacks: &StackCache,
+    frame: &mut canvas::Frame,
+    price_to_y: impl Fn(Price) -> f32,
+    interval_to_x: impl Fn(u64) -> f32,
+    cell_width: f32,
+    cell_height: f32,
+    palette: &Extended,
+    earliest: u64,
+    latest: u64,
+) {
+    let inset = cell_width * 0.05;
+    let marker_width = (cell_width * 0.03).max(1.0);
+
+    let mut draw_bar = |x_position: f32, bar: u64| {
+        for stack in stacks.get(bar) {
+            let color = match stack.side {
+                imbalance::Side::Buy => palette.success.base.color,
+                imbalance::Side::Sell => palette.danger.base.color,
+            };
+
+            let top = price_to_y(stack.high) - (cell_height / 2.0);
+            let height = price_to_y(stack.low) - price_to_y(stack.high) + cell_height;
+            let left = x_position - (cell_width / 2.0) + inset;
+            let width = cell_width - (2.0 * inset);
+
+            frame.fill_rectangle(
+                Point::new(left, top),
+                Size::new(width, height),
+                color.scale_alpha(0.12),
+            );
+
+            let marker_x = match stack.side {
+                imbalance::Side::Buy => left + width - marker_width,
+                imbalance::Side::Sell => left,
+            };
+            frame.fill_rectangle(
+                Point::new(marker_x, top),
+                Size::new(marker_width, height),
+                color,
+            );
+        }
+    };
+
+    match data_source {
+        PlotData::TickBased(tick_aggr) => {
+            let len = tick_aggr.datapoints.len() as u64;
+            if len == 0 {
+                return;
+            }
+            // x is counted from the newest bar, the cache from the oldest
+            for index in earliest..=latest.min(len - 1) {
+                draw_bar(interval_to_x(index), len - 1 - index);
+            }
+        }
+        PlotData::TimeBased(timeseries) => {
+            if latest < earliest {
+                return;
+            }
+            for timestamp in timeseries
+                .datapoints
+                .range(earliest..=latest)
+                .map(|(t, _)| *t)
+            {
+                draw_bar(interval_to_x(timestamp), timestamp);
+            }
+        }
+    }
+}
+
+/// Connects the points of each overlay across the visible bars, leaving gaps where a point has
+/// no value yet. Bands get their edges drawn fainter than the middle line.
+fn draw_overlays(
+    overlays: &[Box<dyn indicators::Indicator>],
+    frame: &mut canvas::Frame,
+    price_to_y: impl Fn(Price) -> f32,
+    interval_to_x: impl Fn(u64) -> f32,
+    is_time_based: bool,
+    earliest: u64,
+    latest: u64,
+    scaling: f32,
+) {
+    for (index, overlay) in overlays.iter().enumerate() {
+        let points = overlay.points();
+        let len = points.len();
+        if len == 0 || latest < earliest {
+            continue;
+        }
+
+        // One bar past each edge so lines run off screen instead of stopping short
+        let (start, end) = if is_time_based {
+            (
+                points
+                    .partition_point(|point| point.time < earliest)
+                    .saturating_sub(1),
+                (points.partition_point(|point| point.time <= latest) + 1).min(len),
+            )
+        } else {
+            // x is counted from the newest bar, the points from the oldest
+            (
+                len.saturating_sub(latest.saturating_add(2).min(len as u64) as usize),
+                (len + 1)
+                    .saturating_sub(earliest.min(len as u64) as usize)
+                    .min(len),
+            )
+        };
+
+        let x_at = |i: usize| {
+            if is_time_based {
+                interval_to_x(points[i].time)
+            } else {
+                interval_to_x((len - 1 - i) as u64)
+            }
+        };
+
+        let mut stroke_line = |value_at: &dyn Fn(usize) -> Option<f32>,
+                               color: iced::Color,
+                               alpha: f32| {
+            let path = Path::new(|builder| {
+                let mut drawing = false;
+                for i in start..end {
+                    let Some(value) = value_at(i) else {
+                        drawing = false;
+                        continue;
+                    };
+
+                    let position = Point::new(x_at(i), price_to_y(Price::from_f32_lossy(value)));
+                    if drawing {
+                        builder.line_to(position);
+                    } else {
+                        builder.move_to(position);
+                    }
+                    drawing = true;
+                }
+            });
+
+            frame.stroke(
+                &path,
+                Stroke::with_color(
+                    Stroke {
+                        width: 1.5 / scaling,
+                        ..Default::default()
+                    },
+                    color.scale_alpha(alpha),
+                ),
+            );
+        };
+
+        let color = style::overlay_color(index);
+        let band_edge = |i: usize| points[i].value.and_then(|value| value.band());
+
+        stroke_line(&|i| points[i].value.map(|value| value.middle()), color, 0.9);
+        if let Overlay::Bollinger { .. } = overlay.params() {
+            stroke_line(&|i| band_edge(i).map(|(upper, _)| upper), color, 0.6);
+            stroke_line(&|i| band_edge(i).map(|(_, lower)| lower), color, 0.6);
+        }
+        // Further lines of a script, each in the next color along
+        for (line_index, line) in overlay.extra_lines().iter().enumerate() {
+            let color = style::overlay_color(index + line_index + 1);
+            stroke_line(&|i| line.get(i).copied().flatten(), color, 0.9);
+        }
+    }
+}
+
 fn draw_all_npocs(
     data_source: &PlotData<KlineDataPoint>,
     frame: &mut canvas::Frame,
@@ -1350,7 +3813,7 @@ fn draw_clusters(
     palette: &Extended,
     text_size: f32,
     tick_size: f32,
-    show_text: bool,
+    labels: Option<SizeDisplay>,
     imbalance: Option<(usize, Option<usize>, bool)>,
     kline: &Kline,
     footprint: &KlineTrades,
@@ -1358,6 +3821,10 @@ fn draw_clusters(
     spacing: ContentGaps,
 ) {
     let text_color = palette.background.weakest.text;
+    let show_text = labels.is_some();
+    let label = |qty: f32, price: Price| {
+        abbr_large_numbers(labels.map_or(qty, |sizes| sizes.qty(qty, price.to_f32())))
+    };
 
     let bar_width_factor: f32 = 0.9;
     let inset = (cell_width * (1.0 - bar_width_factor)) / 2.0;
@@ -1400,7 +3867,7 @@ fn draw_clusters(
                         if show_text {
                             draw_cluster_text(
                                 frame,
-                                &abbr_large_numbers(group.total_qty()),
+                                &label(group.total_qty(), *price),
                                 Point::new(area.bars_left, y),
                                 text_size,
                                 text_color,
@@ -1414,7 +3881,7 @@ fn draw_clusters(
                         if show_text {
                             draw_cluster_text(
                                 frame,
-                                &abbr_large_numbers(delta),
+                                &label(delta, *price),
                                 Point::new(area.bars_left, y),
                                 text_size,
                                 text_color,
@@ -1510,7 +3977,7 @@ fn draw_clusters(
                     if show_text {
                         draw_cluster_text(
                             frame,
-                            &abbr_large_numbers(group.buy_qty),
+                            &label(group.buy_qty, *price),
                             Point::new(area.bid_area_left, y),
                             text_size,
                             text_color,
@@ -1532,7 +3999,7 @@ fn draw_clusters(
                     if show_text {
                         draw_cluster_text(
                             frame,
-                            &abbr_large_numbers(group.sell_qty),
+                            &label(group.sell_qty, *price),
                             Point::new(area.ask_area_right, y),
                             text_size,
                             text_color,
@@ -1705,6 +4172,20 @@ fn draw_cluster_text(
     });
 }
 
+/// The bar under the crosshair at `at_interval` and the key its indicator values are stored by,
+/// the open time or, for tick charts, the bar's index
+fn hovered_bar(data: &PlotData<KlineDataPoint>, at_interval: u64) -> Option<(u64, &Kline)> {
+    match data {
+        PlotData::TimeBased(timeseries) => data_window::bar_at(&timeseries.datapoints, at_interval)
+            .map(|(time, dp)| (time, &dp.kline)),
+        PlotData::TickBased(tick_aggr) => {
+            let from_latest = (at_interval / u64::from(tick_aggr.interval.0)) as usize;
+            let index = tick_aggr.datapoints.len().checked_sub(from_latest + 1)?;
+            Some((index as u64, &tick_aggr.datapoints[index].kline))
+        }
+    }
+}
+
 fn draw_crosshair_tooltip(
     data: &PlotData<KlineDataPoint>,
     ticker_info: &TickerInfo,
@@ -1712,35 +4193,7 @@ fn draw_crosshair_tooltip(
     palette: &Extended,
     at_interval: u64,
 ) {
-    let kline_opt = match data {
-        PlotData::TimeBased(timeseries) => timeseries
-            .datapoints
-            .iter()
-            .find(|(time, _)| **time == at_interval)
-            .map(|(_, dp)| &dp.kline)
-            .or_else(|| {
-                if timeseries.datapoints.is_empty() {
-                    None
-                } else {
-                    let (last_time, dp) = timeseries.datapoints.last_key_value()?;
-                    if at_interval > *last_time {
-                        Some(&dp.kline)
-                    } else {
-                        None
-                    }
-                }
-            }),
-        PlotData::TickBased(tick_aggr) => {
-            let index = (at_interval / u64::from(tick_aggr.interval.0)) as usize;
-            if index < tick_aggr.datapoints.len() {
-                Some(&tick_aggr.datapoints[tick_aggr.datapoints.len() - 1 - index].kline)
-            } else {
-                None
-            }
-        }
-    };
-
-    if let Some(kline) = kline_opt {
+    if let Some((_, kline)) = hovered_bar(data, at_interval) {
         let change_pct = ((kline.close - kline.open).to_f32() / kline.open.to_f32()) * 100.0;
         let change_color = if change_pct >= 0.0 {
             palette.success.base.color
@@ -1,10 +1,15 @@
 pub mod linear;
+pub mod log;
 pub mod timeseries;
 
 use crate::{chart::TEXT_SIZE, style::AZERET_MONO};
 
 use super::{Basis, Interaction, Message};
-use data::{chart::Autoscale, util::round_to_tick};
+use data::{
+    chart::{Autoscale, PriceScale, levels::NamedLevel},
+    util::round_to_tick,
+};
+use exchange::util::Price;
 use iced::{
     Alignment, Color, Event, Point, Rectangle, Renderer, Size, Theme, mouse,
     theme::palette::Extended,
@@ -536,11 +541,18 @@ pub struct AxisLabelsY<'a> {
     pub scaling: f32,
     pub min: f32,
     pub last_price: Option<linear::PriceInfoLabel>,
+    pub levels: &'a [NamedLevel],
+    pub price_alerts: &'a [Price],
     pub tick_size: f32,
     pub decimals: usize,
+    /// Ticks of a symbol quoted in tiers, what labels snap to
+    pub tick_tiers: Option<exchange::tick_rule::TickTiers>,
     pub cell_height: f32,
     pub basis: Basis,
     pub chart_bounds: Rectangle,
+    pub price_scale: PriceScale,
+    /// Price percent labels are relative to
+    pub percent_anchor: Option<f32>,
 }
 
 impl AxisLabelsY<'_> {
@@ -557,8 +569,26 @@ impl AxisLabelsY<'_> {
     }
 
     fn y_to_price(&self, y: f32) -> f32 {
+        if self.price_scale == PriceScale::Log {
+            let (base, tick) = (f64::from(self.min), f64::from(self.tick_size));
+            let factor = log::factor(base, if tick > 0.0 { tick } else { 1.0 }, self.cell_height);
+            return log::y_to_price(base, y, factor) as f32;
+        }
         self.min - (y / self.cell_height) * self.tick_size
     }
+
+    fn percent_anchor(&self) -> Option<f32> {
+        self.percent_anchor
+            .filter(|anchor| self.price_scale == PriceScale::Percent && *anchor > 0.0)
+    }
+
+    /// Price as the axis reads it, a change from the anchor on percent scales
+    fn format_price(&self, price: f32) -> String {
+        match self.percent_anchor() {
+            Some(anchor) => linear::format_percent(linear::percent_change(price, anchor), 2),
+            None => format!("{:.*}", self.decimals, price),
+        }
+    }
 }
 
 impl canvas::Program<Message> for AxisLabelsY<'_> {
@@ -584,6 +614,10 @@ impl canvas::Program<Message> for AxisLabelsY<'_> {
                         last_position: cursor_position,
                     };
                 }
+                mouse::Event::ButtonPressed(mouse::Button::Right) => {
+                    let message = Message::PriceMenuOpened(cursor_position.y);
+                    return Some(canvas::Action::publish(message).and_capture());
+                }
                 mouse::Event::CursorMoved { .. } => {
                     if let Interaction::Zoomin {
                         ref mut last_position,
@@ -643,16 +677,63 @@ impl canvas::Program<Message> for AxisLabelsY<'_> {
             let highest = self.y_to_price(region.y);
             let lowest = self.y_to_price(region.y + region.height);
 
-            let range = highest - lowest;
+            let y_of = |price: f32| {
+                bounds.height - self.price_scale.ratio(price, lowest, highest) * bounds.height
+            };
 
-            let mut all_labels = linear::generate_labels(
-                bounds,
-                lowest,
-                highest,
-                text_size,
-                palette.background.base.text,
-                Some(self.decimals),
-            );
+            let text_color = palette.background.base.text;
+            let mut all_labels = match (self.price_scale, self.percent_anchor()) {
+                (PriceScale::Log, _) => log::generate_labels(
+                    bounds,
+                    lowest,
+                    highest,
+                    text_size,
+                    text_color,
+                    self.decimals,
+                ),
+                (_, Some(anchor)) => linear::generate_percent_labels(
+                    bounds, lowest, highest, anchor, text_size, text_color,
+                ),
+                _ => linear::generate_labels(
+                    bounds,
+                    lowest,
+                    highest,
+                    text_size,
+                    text_color,
+                    Some(self.decimals),
+                    self.tick_tiers,
+                ),
+            };
+
+            // Auto levels and price alerts (priority 1)
+            let levels = self
+                .levels
+                .iter()
+                .map(|level| {
+                    let color = super::level_color(level.kind, palette);
+                    (level.price, color.scale_alpha(0.6))
+                })
+                .chain(
+                    self.price_alerts
+                        .iter()
+                        .map(|price| (*price, palette.warning.weak.color)),
+                );
+
+            for (price, color) in levels {
+                let price = price.to_f32();
+                let y_pos = y_of(price);
+
+                all_labels.push(AxisLabel::Y {
+                    bounds: calc_label_rect(y_pos, 1, text_size, bounds),
+                    value_label: LabelContent {
+                        content: self.format_price(price),
+                        background_color: Some(color),
+                        text_color: palette.background.base.text,
+                        text_size: 11.0,
+                    },
+                    timer_label: None,
+                });
+            }
 
             // Last price (priority 2)
             if let Some(label) = self.last_price {
@@ -697,7 +778,7 @@ impl canvas::Program<Message> for AxisLabelsY<'_> {
                 let price = price.to_f32();
 
                 let price_label = LabelContent {
-                    content: format!("{:.*}", self.decimals, price),
+                    content: self.format_price(price),
                     background_color: Some(color),
                     text_color: {
                         if candle_close_label.is_some() {
@@ -713,7 +794,7 @@ impl canvas::Program<Message> for AxisLabelsY<'_> {
                     text_size: 12.0,
                 };
 
-                let y_pos = bounds.height - ((price - lowest) / range * bounds.height);
+                let y_pos = y_of(price);
                 let content_amt = if candle_close_label.is_some() { 2 } else { 1 };
 
                 all_labels.push(AxisLabel::Y {
@@ -725,14 +806,15 @@ impl canvas::Program<Message> for AxisLabelsY<'_> {
 
             // Crosshair price (priority 3)
             if let Some(crosshair_pos) = cursor.position_in(self.chart_bounds) {
+                let ratio = (bounds.height - crosshair_pos.y) / bounds.height;
                 let rounded_price = round_to_tick(
-                    lowest + (range * (bounds.height - crosshair_pos.y) / bounds.height),
+                    self.price_scale.interpolate(ratio, lowest, highest),
                     self.tick_size,
                 );
-                let y_position = bounds.height - ((rounded_price - lowest) / range * bounds.height);
+                let y_position = y_of(rounded_price);
 
                 let label = LabelContent {
-                    content: format!("{:.*}", self.decimals, rounded_price),
+                    content: self.format_price(rounded_price),
                     background_color: Some(palette.secondary.base.color),
                     text_color: palette.secondary.base.text,
                     text_size: 12.0,
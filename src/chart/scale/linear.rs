@@ -1,5 +1,6 @@
 use super::{AxisLabel, LabelContent, calc_label_rect};
 use data::util::abbr_large_numbers;
+use exchange::tick_rule::{TickRule, TickTiers};
 use exchange::util::Price;
 
 const MAX_ITERATIONS: usize = 1000;
@@ -30,6 +31,7 @@ pub fn generate_labels(
     text_size: f32,
     text_color: iced::Color,
     decimals: Option<usize>,
+    tiers: Option<TickTiers>,
 ) -> Vec<AxisLabel> {
     if !lowest.is_finite() || !highest.is_finite() {
         return Vec::new();
@@ -60,18 +62,20 @@ pub fn generate_labels(
         }];
     }
 
-    let (step, max) = calc_optimal_ticks(highest, lowest, labels_can_fit);
-
-    let mut value = max;
-    while value > highest {
-        value -= step;
+    let mut values = tick_values(highest, lowest, labels_can_fit);
+    // Labels of symbols quoted in tiers sit on prices their tier quotes
+    if let Some(tiers) = tiers {
+        let rule = TickRule::Tiered(tiers);
+        values = values
+            .into_iter()
+            .map(|value| rule.round(Price::from_f32(value)).to_f32())
+            .collect();
+        values.dedup_by(|a, b| (*a - *b).abs() < f32::EPSILON);
     }
 
-    let mut labels = Vec::with_capacity((labels_can_fit + 2) as usize);
-    let mut safety_counter = 0;
-
-    while value >= lowest && safety_counter < MAX_ITERATIONS {
-        if value <= highest + step * 0.5 && value >= lowest - step * 0.5 {
+    values
+        .into_iter()
+        .map(|value| {
             let content = if let Some(decimals) = decimals {
                 format!("{value:.decimals$}")
             } else {
@@ -89,18 +93,104 @@ pub fn generate_labels(
             let label_pos =
                 bounds.height - ((clamped_value - lowest) / (highest - lowest) * bounds.height);
 
-            labels.push(AxisLabel::Y {
+            AxisLabel::Y {
                 bounds: calc_label_rect(label_pos, 1, text_size, bounds),
                 value_label: label,
                 timer_label: None,
-            });
-        }
+            }
+        })
+        .collect()
+}
+
+/// Round values to label from `highest` down to `lowest`
+pub fn tick_values(highest: f32, lowest: f32, labels_can_fit: i32) -> Vec<f32> {
+    let (step, max) = calc_optimal_ticks(highest, lowest, labels_can_fit);
+
+    let mut value = max;
+    while value > highest {
+        value -= step;
+    }
 
+    let mut values = Vec::with_capacity((labels_can_fit.max(0) + 2) as usize);
+    let mut safety_counter = 0;
+
+    while value >= lowest && safety_counter < MAX_ITERATIONS {
+        values.push(value);
         value -= step;
         safety_counter += 1;
     }
 
-    labels
+    values
+}
+
+/// Labels for a linear scale read as the change from `anchor`, at round percentages
+pub fn generate_percent_labels(
+    bounds: iced::Rectangle,
+    lowest: f32,
+    highest: f32,
+    anchor: f32,
+    text_size: f32,
+    text_color: iced::Color,
+) -> Vec<AxisLabel> {
+    if !lowest.is_finite() || !highest.is_finite() || anchor <= 0.0 {
+        return Vec::new();
+    }
+
+    let (lowest_pct, highest_pct) = (
+        percent_change(lowest, anchor),
+        percent_change(highest, anchor),
+    );
+    if (highest_pct - lowest_pct).abs() < f32::EPSILON {
+        return Vec::new();
+    }
+
+    let labels_can_fit = ((bounds.height / (text_size * 3.0)) as i32).max(1);
+    let values = tick_values(highest_pct, lowest_pct, labels_can_fit);
+
+    let step = match values.as_slice() {
+        [first, second, ..] => first - second,
+        _ => highest_pct - lowest_pct,
+    };
+    let decimals = (-step.log10().floor()).clamp(0.0, 4.0) as usize;
+
+    values
+        .into_iter()
+        .map(|value| {
+            // Keeps float noise around the anchor from reading as a change
+            let value = if value.abs() < step * 1e-3 {
+                0.0
+            } else {
+                value
+            };
+
+            let label_pos =
+                bounds.height - ((value - lowest_pct) / (highest_pct - lowest_pct) * bounds.height);
+
+            AxisLabel::Y {
+                bounds: calc_label_rect(label_pos, 1, text_size, bounds),
+                value_label: LabelContent {
+                    content: format_percent(value, decimals),
+                    background_color: None,
+                    text_color,
+                    text_size,
+                },
+                timer_label: None,
+            }
+        })
+        .collect()
+}
+
+/// Change of `price` from `anchor`, in percent
+pub fn percent_change(price: f32, anchor: f32) -> f32 {
+    (price / anchor - 1.0) * 100.0
+}
+
+pub fn format_percent(change: f32, decimals: usize) -> String {
+    if change == 0.0 {
+        format!("{:.decimals$}%", 0.0)
+    } else {
+        format!("{change:+.decimals$}%")
+    }
 }
 
 // other helpers
@@ -120,6 +210,12 @@ impl PriceInfoLabel {
         }
     }
 
+    pub fn price(self) -> Price {
+        match self {
+            PriceInfoLabel::Up(p) | PriceInfoLabel::Down(p) | PriceInfoLabel::Neutral(p) => p,
+        }
+    }
+
     pub fn get_with_color(self, palette: &iced::theme::palette::Extended) -> (Price, iced::Color) {
         match self {
             PriceInfoLabel::Up(p) => (p, palette.success.base.color),
@@ -128,3 +224,62 @@ impl PriceInfoLabel {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(labels: &[AxisLabel]) -> Vec<String> {
+        labels
+            .iter()
+            .map(|label| match label {
+                AxisLabel::Y { value_label, .. } => value_label.content.clone(),
+                AxisLabel::X { .. } => unreachable!(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ticks_are_round_and_within_range() {
+        let values = tick_values(104.3, 99.1, 6);
+
+        assert_eq!(values, vec![104.0, 103.0, 102.0, 101.0, 100.0]);
+    }
+
+    #[test]
+    fn percent_labels_read_as_change_from_anchor() {
+        let bounds = iced::Rectangle::new(iced::Point::ORIGIN, iced::Size::new(80.0, 360.0));
+        let labels = generate_percent_labels(bounds, 97.5, 104.5, 100.0, 12.0, iced::Color::WHITE);
+
+        assert_eq!(
+            contents(&labels),
+            vec!["+4%", "+3%", "+2%", "+1%", "0%", "-1%", "-2%"]
+        );
+
+        // The anchor itself sits where its price is on the linear scale
+        let zero = labels
+            .iter()
+            .find_map(|label| match label {
+                AxisLabel::Y {
+                    bounds,
+                    value_label,
+                    ..
+                } if value_label.content == "0%" => Some(bounds.center_y()),
+                _ => None,
+            })
+            .unwrap();
+        assert!((zero - 360.0 * 4.5 / 7.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn narrow_percent_ranges_get_more_decimals() {
+        let bounds = iced::Rectangle::new(iced::Point::ORIGIN, iced::Size::new(80.0, 360.0));
+        let labels =
+            generate_percent_labels(bounds, 100.025, 100.145, 100.0, 12.0, iced::Color::WHITE);
+
+        assert_eq!(
+            contents(&labels),
+            vec!["+0.14%", "+0.12%", "+0.10%", "+0.08%", "+0.06%", "+0.04%"]
+        );
+    }
+}
@@ -0,0 +1,157 @@
+use super::{AxisLabel, LabelContent, calc_label_rect, linear};
+use data::chart::PriceScale;
+
+/// Keeps zero and negative prices finite on the log scale
+const MIN_PRICE: f64 = 1e-9;
+
+/// Mantissas labelled within each decade, densest first
+const MANTISSAS: [&[f32]; 5] = [
+    &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 9.0],
+    &[1.0, 2.0, 3.0, 5.0, 7.0],
+    &[1.0, 2.0, 5.0],
+    &[1.0, 3.0],
+    &[1.0],
+];
+
+/// Pixels per unit of `ln(price)`, a tick at `base` is `cell_height` tall as on the linear scale
+pub fn factor(base: f64, tick: f64, cell_height: f32) -> f64 {
+    f64::from(cell_height) * base.max(MIN_PRICE) / tick
+}
+
+pub fn price_to_y(base: f64, price: f64, factor: f64) -> f32 {
+    ((base.max(MIN_PRICE).ln() - price.max(MIN_PRICE).ln()) * factor) as f32
+}
+
+pub fn y_to_price(base: f64, y: f32, factor: f64) -> f64 {
+    base.max(MIN_PRICE) * (-f64::from(y) / factor).exp()
+}
+
+/// Round values to label from `highest` down to `lowest`, spread evenly on a log scale
+pub fn tick_values(highest: f32, lowest: f32, labels_can_fit: i32) -> Vec<f32> {
+    // Within half a decade the scale is close to linear, where even steps read better
+    if lowest <= 0.0 || (highest / lowest).log10() < 0.5 {
+        return linear::tick_values(highest, lowest, labels_can_fit);
+    }
+
+    let labels = labels_can_fit.max(1) as usize;
+    let (first, last) = (lowest.log10().floor() as i32, highest.log10().ceil() as i32);
+
+    let in_decades = |mantissas: &[f32], every: i32| {
+        let mut values: Vec<f32> = (first..=last)
+            .filter(|exp| exp.rem_euclid(every) == 0)
+            .flat_map(|exp| mantissas.iter().map(move |m| m * 10f32.powi(exp)))
+            .filter(|value| (lowest..=highest).contains(value))
+            .collect();
+        values.reverse();
+        values
+    };
+
+    for mantissas in MANTISSAS {
+        let values = in_decades(mantissas, 1);
+        if values.len() <= labels {
+            return values;
+        }
+    }
+
+    // More decades than labels, only every few get one
+    let every = (last - first) / labels as i32 + 1;
+    in_decades(&[1.0], every)
+}
+
+pub fn generate_labels(
+    bounds: iced::Rectangle,
+    lowest: f32,
+    highest: f32,
+    text_size: f32,
+    text_color: iced::Color,
+    decimals: usize,
+) -> Vec<AxisLabel> {
+    if !lowest.is_finite() || !highest.is_finite() || lowest <= 0.0 || highest <= lowest {
+        return Vec::new();
+    }
+
+    let labels_can_fit = ((bounds.height / (text_size * 3.0)) as i32).max(1);
+
+    tick_values(highest, lowest, labels_can_fit)
+        .into_iter()
+        .map(|value| {
+            let label_pos =
+                bounds.height - PriceScale::Log.ratio(value, lowest, highest) * bounds.height;
+
+            AxisLabel::Y {
+                bounds: calc_label_rect(label_pos, 1, text_size, bounds),
+                value_label: LabelContent {
+                    content: format!("{value:.decimals$}"),
+                    background_color: None,
+                    text_color,
+                    text_size,
+                },
+                timer_label: None,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_ranges_get_round_values_per_decade() {
+        assert_eq!(tick_values(1000.0, 1.0, 4), vec![1000.0, 100.0, 10.0, 1.0]);
+        assert_eq!(
+            tick_values(1000.0, 1.0, 10),
+            vec![1000.0, 500.0, 200.0, 100.0, 50.0, 20.0, 10.0, 5.0, 2.0, 1.0]
+        );
+
+        // Too many decades for a label each
+        assert_eq!(tick_values(1e8, 1.0, 3), vec![1e6, 1e3, 1.0]);
+    }
+
+    #[test]
+    fn narrow_ranges_fall_back_to_even_steps() {
+        assert_eq!(
+            tick_values(104.3, 99.1, 6),
+            linear::tick_values(104.3, 99.1, 6)
+        );
+    }
+
+    #[test]
+    fn decades_are_equally_far_apart() {
+        let bounds = iced::Rectangle::new(iced::Point::ORIGIN, iced::Size::new(80.0, 300.0));
+        let labels = generate_labels(bounds, 1.0, 1000.0, 12.0, iced::Color::WHITE, 0);
+
+        let positions: Vec<_> = labels
+            .iter()
+            .filter_map(|label| match label {
+                AxisLabel::Y {
+                    bounds,
+                    value_label,
+                    ..
+                } => Some((value_label.content.as_str(), bounds.center_y())),
+                AxisLabel::X { .. } => None,
+            })
+            .collect();
+
+        let contents: Vec<_> = positions.iter().map(|(content, _)| *content).collect();
+        assert_eq!(contents, vec!["1000", "300", "100", "30", "10", "3", "1"]);
+
+        let at = |content| positions.iter().find(|(c, _)| *c == content).unwrap().1;
+        assert!((at("100") - 100.0).abs() < 0.5);
+        assert!((at("10") - 200.0).abs() < 0.5);
+    }
+
+    #[test]
+    fn mapping_round_trips() {
+        let (base, tick) = (50_000.0, 0.5);
+        let k = factor(base, tick, 4.0);
+
+        // A tick right at the base keeps its linear height
+        assert!((price_to_y(base, base - tick, k) - 4.0).abs() < 0.01);
+
+        for price in [100.0, 12_345.6, 50_000.0, 90_000.0] {
+            let y = price_to_y(base, price, k);
+            assert!((y_to_price(base, y, k) - price).abs() / price < 1e-6);
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! Compact candlestick strips of one ticker at several timeframes, stacked vertically.
+//!
+//! Every timeframe has its own kline stream and fetch handler, so a failed or slow fetch only
+//! leaves its own strip empty.
+
+use crate::style;
+
+use data::chart::strip::Config;
+use exchange::adapter::StreamKind;
+use exchange::fetcher::{FetchRange, FetchRequests, FetchSpec, RequestHandler};
+use exchange::{Kline, TickerInfo, Timeframe};
+
+use iced::widget::canvas::{self, Path, Stroke};
+use iced::widget::{center, column, container, row, text};
+use iced::{Alignment, Element, Length, Point, Rectangle, Renderer, Size, Theme, mouse};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// A strip still empty this long after its request is fetched again
+const FETCH_RETRY: Duration = Duration::from_secs(30);
+const LABEL_WIDTH: f32 = 64.0;
+
+enum FetchState {
+    Idle,
+    Requested(Instant),
+    Loaded,
+    NoData,
+}
+
+struct Strip {
+    timeframe: Timeframe,
+    klines: BTreeMap<u64, Kline>,
+    request_handler: RequestHandler,
+    fetch: FetchState,
+    cache: canvas::Cache,
+}
+
+impl Strip {
+    fn new(timeframe: Timeframe) -> Self {
+        Self {
+            timeframe,
+            klines: BTreeMap::new(),
+            request_handler: RequestHandler::new(),
+            fetch: FetchState::Idle,
+            cache: canvas::Cache::default(),
+        }
+    }
+
+    fn trim(&mut self, bars: usize) {
+        while self.klines.len() > bars {
+            self.klines.pop_first();
+        }
+    }
+
+    fn change(&self) -> Option<f32> {
+        let last = self.klines.values().next_back()?;
+        let open = last.open.to_f32();
+        (open > 0.0).then(|| (last.close.to_f32() - open) / open * 100.0)
+    }
+}
+
+pub struct TimeframeStrip {
+    ticker_info: TickerInfo,
+    pub config: Config,
+    strips: Vec<Strip>,
+    last_tick: Instant,
+}
+
+impl TimeframeStrip {
+    pub fn new(ticker_info: TickerInfo, config: Option<Config>) -> Self {
+        let config = config.unwrap_or_default().sanitized();
+
+        Self {
+            ticker_info,
+            strips: config.timeframes.iter().copied().map(Strip::new).collect(),
+            config,
+            last_tick: Instant::now(),
+        }
+    }
+
+    pub fn streams(&self) -> Vec<StreamKind> {
+        self.config
+            .timeframes
+            .iter()
+            .map(|tf| StreamKind::Kline {
+                ticker_info: self.ticker_info,
+                timeframe: *tf,
+            })
+            .collect()
+    }
+
+    /// Applies new settings, strips of timeframes that stay keep their data
+    pub fn set_config(&mut self, config: Config) {
+        let config = config.sanitized();
+        let mut previous = std::mem::take(&mut self.strips);
+
+        self.strips = config
+            .timeframes
+            .iter()
+            .map(
+                |tf| match previous.iter().position(|s| s.timeframe == *tf) {
+                    Some(pos) => previous.swap_remove(pos),
+                    None => Strip::new(*tf),
+                },
+            )
+            .collect();
+
+        let bars = usize::from(config.bars);
+        for strip in &mut self.strips {
+            strip.trim(bars);
+            strip.cache.clear();
+        }
+        self.config = config;
+    }
+
+    pub fn last_update(&self) -> Instant {
+        self.last_tick
+    }
+
+    pub fn invalidate(&mut self, now: Option<Instant>) -> Option<super::Action> {
+        if let Some(now) = now {
+            self.last_tick = now;
+        }
+        for strip in &self.strips {
+            strip.cache.clear();
+        }
+
+        let now = Instant::now();
+        let server_now = exchange::adapter::server_now_ms(self.ticker_info.exchange());
+        let bars = u64::from(self.config.bars);
+
+        let mut specs = vec![];
+        for strip in &mut self.strips {
+            // Live klines may arrive first, the history fetch still has to run once
+            match strip.fetch {
+                FetchState::Requested(at) if now.duration_since(at) < FETCH_RETRY => continue,
+                FetchState::Loaded | FetchState::NoData => continue,
+                FetchState::Requested(_) => strip.request_handler = RequestHandler::new(),
+                FetchState::Idle => {}
+            }
+
+            let dt = strip.timeframe.to_milliseconds().max(1);
+            let end = (server_now / dt) * dt + dt;
+            let range = FetchRange::Kline(end.saturating_sub(bars * dt), end);
+
+            if let Ok(Some(req_id)) = strip.request_handler.add_request(range) {
+                strip.fetch = FetchState::Requested(now);
+                specs.push(FetchSpec {
+                    req_id,
+                    fetch: range,
+                    stream: Some(StreamKind::Kline {
+                        ticker_info: self.ticker_info,
+                        timeframe: strip.timeframe,
+                    }),
+                });
+            }
+        }
+
+        (!specs.is_empty()).then(|| super::Action::RequestFetch(FetchRequests::from(specs)))
+    }
+
+    pub fn insert_history(&mut self, req_id: uuid::Uuid, timeframe: Timeframe, klines: &[Kline]) {
+        let bars = usize::from(self.config.bars);
+
+        let Some(strip) = self.strips.iter_mut().find(|s| s.timeframe == timeframe) else {
+            log::warn!("Ignoring kline fetch for {timeframe}, not shown on the strip anymore");
+            return;
+        };
+
+        if klines.is_empty() {
+            strip
+                .request_handler
+                .mark_failed(req_id, "No data received".to_string());
+            strip.fetch = FetchState::NoData;
+            return;
+        }
+
+        for kline in klines {
+            strip.klines.insert(kline.time, *kline);
+        }
+        strip.trim(bars);
+        strip.request_handler.mark_completed(req_id);
+        strip.fetch = FetchState::Loaded;
+        strip.cache.clear();
+    }
+
+    /// Live klines replace the forming bar in place and push new ones on the right
+    pub fn update_latest_kline(&mut self, timeframe: Timeframe, kline: &Kline) {
+        let bars = usize::from(self.config.bars);
+
+        if let Some(strip) = self.strips.iter_mut().find(|s| s.timeframe == timeframe) {
+            let is_older = strip
+                .klines
+                .first_key_value()
+                .is_some_and(|(first, _)| kline.time < *first && strip.klines.len() >= bars);
+            if is_older {
+                return;
+            }
+
+            strip.klines.insert(kline.time, *kline);
+            strip.trim(bars);
+            strip.cache.clear();
+        }
+    }
+
+    pub fn view<'a, M: 'a>(&'a self) -> Element<'a, M> {
+        let bars = usize::from(self.config.bars);
+        let precision = self.ticker_info.min_ticksize;
+
+        let rows = self.strips.iter().map(|strip| {
+            let last_close = strip
+                .klines
+                .values()
+                .next_back()
+                .map(|k| k.close.to_string(precision));
+
+            let change = strip.change().map(|pct| {
+                text(format!("{pct:+.2}%"))
+                    .size(10)
+                    .style(move |theme: &Theme| {
+                        let palette = theme.extended_palette();
+                        iced::widget::text::Style {
+                            color: Some(if pct >= 0.0 {
+                                palette.success.base.color
+                            } else {
+                                palette.danger.base.color
+                            }),
+                        }
+                    })
+            });
+
+            let mut label = column![text(strip.timeframe.to_string()).size(12)].spacing(2);
+            if let Some(close) = last_close {
+                label = label.push(text(close).size(10).font(style::AZERET_MONO));
+            }
+            if let Some(change) = change {
+                label = label.push(change);
+            }
+
+            let body: Element<'a, M> = if strip.klines.is_empty() {
+                let status = match strip.fetch {
+                    FetchState::NoData => "No data",
+                    FetchState::Idle | FetchState::Requested(_) | FetchState::Loaded => {
+                        "Loading..."
+                    }
+                };
+                center(text(status).size(11)).into()
+            } else {
+                iced::widget::canvas(StripCanvas { strip, bars })
+                    .width(Length::Fill)
+                    .height(Length::Fill)
+                    .into()
+            };
+
+            container(
+                row![
+                    container(label)
+                        .width(Length::Fixed(LABEL_WIDTH))
+                        .padding(4),
+                    body
+                ]
+                .align_y(Alignment::Center),
+            )
+            .height(Length::FillPortion(1))
+            .style(style::chart_modal)
+            .into()
+        });
+
+        column(rows).spacing(2).padding(2).into()
+    }
+}
+
+struct StripCanvas<'a> {
+    strip: &'a Strip,
+    bars: usize,
+}
+
+impl<M> canvas::Program<M> for StripCanvas<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry<Renderer>> {
+        let palette = theme.extended_palette();
+
+        let geometry = self.strip.cache.draw(renderer, bounds.size(), |frame| {
+            let klines = &self.strip.klines;
+
+            let (low, high) = klines
+                .values()
+                .fold((f32::MAX, f32::MIN), |(low, high), k| {
+                    (low.min(k.low.to_f32()), high.max(k.high.to_f32()))
+                });
+            let range = (high - low).max(f32::EPSILON);
+
+            let pad = 4.0;
+            let height = (bounds.height - pad * 2.0).max(1.0);
+            let to_y = |price: f32| pad + (high - price) / range * height;
+
+            let cell = bounds.width / self.bars.max(1) as f32;
+            let body_width = (cell * 0.7).max(1.0);
+            // Right-align so the forming bar sits at the edge while history loads
+            let offset = (self.bars.saturating_sub(klines.len())) as f32 * cell;
+
+            for (i, kline) in klines.values().enumerate() {
+                let x = offset + i as f32 * cell + cell / 2.0;
+                let (open, close) = (kline.open.to_f32(), kline.close.to_f32());
+
+                let color = if close >= open {
+                    palette.success.base.color
+                } else {
+                    palette.danger.base.color
+                };
+
+                frame.stroke(
+                    &Path::line(
+                        Point::new(x, to_y(kline.high.to_f32())),
+                        Point::new(x, to_y(kline.low.to_f32())),
+                    ),
+                    Stroke::with_color(Stroke::default(), color.scale_alpha(0.8)),
+                );
+
+                let top = to_y(open.max(close));
+                let bottom = to_y(open.min(close));
+                frame.fill_rectangle(
+                    Point::new(x - body_width / 2.0, top),
+                    Size::new(body_width, (bottom - top).max(1.0)),
+                    color,
+                );
+            }
+        });
+
+        vec![geometry]
+    }
+}
@@ -0,0 +1,50 @@
+//! Command line flags, mostly for running several instances side by side
+
+use clap::{CommandFactory, Parser};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Saved state file to load and save. Unless a workspace is given, the rest of the data
+    /// (caches, logs, scripts) goes in a folder named after it, next to it
+    #[arg(long, value_name = "FILE")]
+    pub state: Option<PathBuf>,
+    /// Layout to open, by name
+    #[arg(long, value_name = "NAME")]
+    pub layout: Option<String>,
+    /// One of error, warn, info, debug or trace, takes precedence over RUST_LOG
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<log::Level>,
+    /// Keeps every file, the saved state included, in a data folder of its own
+    #[arg(long, value_name = "NAME")]
+    pub workspace: Option<String>,
+    /// `.env` file with variables MT5 credentials given as `env:NAME` can refer to
+    #[arg(long, value_name = "FILE")]
+    pub env_file: Option<PathBuf>,
+}
+
+impl Args {
+    /// Points the data folder at this instance's, exits on an invalid state file or workspace
+    pub fn apply_instance(&self) {
+        if let Err(e) =
+            data::data_dir::use_instance(self.state.as_deref(), self.workspace.as_deref())
+        {
+            Self::command()
+                .error(clap::error::ErrorKind::ValueValidation, e)
+                .exit();
+        }
+    }
+
+    /// Loads the `.env` file if one was given, exits when it can't be read
+    pub fn load_env_file(&self) {
+        let Some(path) = &self.env_file else {
+            return;
+        };
+
+        match exchange::adapter::metatrader5::credentials::load_env_file(path) {
+            Ok(count) => log::info!("Loaded {count} variables from {}", path.display()),
+            Err(e) => Self::command().error(clap::error::ErrorKind::Io, e).exit(),
+        }
+    }
+}
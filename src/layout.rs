@@ -31,6 +31,8 @@ pub struct SavedState {
     pub audio_cfg: data::AudioStream,
     pub volume_size_unit: exchange::SizeUnit,
     pub mt5_settings: data::Mt5Settings,
+    /// Nothing was set up yet, neither layouts nor MT5 connections
+    pub first_run: bool,
 }
 
 impl SavedState {
@@ -60,6 +62,7 @@ impl Default for SavedState {
             audio_cfg: data::AudioStream::default(),
             volume_size_unit: exchange::SizeUnit::Base,
             mt5_settings: data::Mt5Settings::default(),
+            first_run: true,
         }
     }
 }
@@ -103,6 +106,7 @@ impl From<&Dashboard> for data::Dashboard {
                     .map(|(pane, window_spec)| (pane.clone(), *window_spec))
                     .collect()
             },
+            linked_timeframes: dashboard.linked_timeframes.iter().copied().collect(),
         }
     }
 }
@@ -113,8 +117,13 @@ impl From<&pane::State> for data::Pane {
 
         match &pane.content {
             pane::Content::Starter => data::Pane::Starter {
+                id: pane.unique_id(),
                 link_group: pane.link_group,
             },
+            pane::Content::Quarantined { error, raw } => data::Pane::Quarantined {
+                error: error.clone(),
+                raw: raw.clone(),
+            },
             pane::Content::Heatmap {
                 chart,
                 indicators,
@@ -122,6 +131,7 @@ impl From<&pane::State> for data::Pane {
                 layout,
                 ..
             } => data::Pane::HeatmapChart {
+                id: pane.unique_id(),
                 layout: chart.as_ref().map_or(layout.clone(), |c| c.chart_layout()),
                 stream_type: streams,
                 settings: pane.settings.clone(),
@@ -138,6 +148,7 @@ impl From<&pane::State> for data::Pane {
                 layout,
                 ..
             } => data::Pane::KlineChart {
+                id: pane.unique_id(),
                 layout: chart.as_ref().map_or(layout.clone(), |c| c.chart_layout()),
                 kind: kind.clone(),
                 stream_type: streams,
@@ -146,11 +157,13 @@ impl From<&pane::State> for data::Pane {
                 link_group: pane.link_group,
             },
             pane::Content::TimeAndSales(_) => data::Pane::TimeAndSales {
+                id: pane.unique_id(),
                 stream_type: streams,
                 settings: pane.settings.clone(),
                 link_group: pane.link_group,
             },
             pane::Content::Ladder(_) => data::Pane::Ladder {
+                id: pane.unique_id(),
                 stream_type: streams,
                 settings: pane.settings.clone(),
                 link_group: pane.link_group,
@@ -164,11 +177,18 @@ impl From<&pane::State> for data::Pane {
                 };
 
                 data::Pane::ComparisonChart {
+                    id: pane.unique_id(),
                     stream_type: streams,
                     settings,
                     link_group: pane.link_group,
                 }
             }
+            pane::Content::Strip(_) => data::Pane::TimeframeStrip {
+                id: pane.unique_id(),
+                stream_type: streams,
+                settings: pane.settings.clone(),
+                link_group: pane.link_group,
+            },
         }
     }
 }
@@ -184,13 +204,22 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             a: Box::new(configuration(*a)),
             b: Box::new(configuration(*b)),
         },
-        data::Pane::Starter { link_group } => Configuration::Pane(pane::State::from_config(
+        data::Pane::Starter { id, link_group } => Configuration::Pane(pane::State::from_config(
+            id,
             pane::Content::Starter,
             vec![],
             data::layout::pane::Settings::default(),
             link_group,
         )),
+        data::Pane::Quarantined { error, raw } => Configuration::Pane(pane::State::from_config(
+            Uuid::new_v4(),
+            pane::Content::Quarantined { error, raw },
+            vec![],
+            data::layout::pane::Settings::default(),
+            None,
+        )),
         data::Pane::HeatmapChart {
+            id,
             layout,
             studies,
             stream_type,
@@ -206,6 +235,7 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             };
 
             Configuration::Pane(pane::State::from_config(
+                id,
                 content,
                 stream_type,
                 settings,
@@ -213,6 +243,7 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             ))
         }
         data::Pane::KlineChart {
+            id,
             layout,
             kind,
             stream_type,
@@ -228,6 +259,7 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             };
 
             Configuration::Pane(pane::State::from_config(
+                id,
                 content,
                 stream_type,
                 settings,
@@ -235,6 +267,7 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             ))
         }
         data::Pane::ComparisonChart {
+            id,
             stream_type,
             settings,
             link_group,
@@ -242,6 +275,7 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             let content = pane::Content::Comparison(None);
 
             Configuration::Pane(pane::State::from_config(
+                id,
                 content,
                 stream_type,
                 settings,
@@ -249,6 +283,7 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             ))
         }
         data::Pane::TimeAndSales {
+            id,
             stream_type,
             settings,
             link_group,
@@ -256,6 +291,7 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             let content = pane::Content::TimeAndSales(None);
 
             Configuration::Pane(pane::State::from_config(
+                id,
                 content,
                 stream_type,
                 settings,
@@ -263,6 +299,7 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             ))
         }
         data::Pane::Ladder {
+            id,
             stream_type,
             settings,
             link_group,
@@ -270,6 +307,23 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
             let content = pane::Content::Ladder(None);
 
             Configuration::Pane(pane::State::from_config(
+                id,
+                content,
+                stream_type,
+                settings,
+                link_group,
+            ))
+        }
+        data::Pane::TimeframeStrip {
+            id,
+            stream_type,
+            settings,
+            link_group,
+        } => {
+            let content = pane::Content::Strip(None);
+
+            Configuration::Pane(pane::State::from_config(
+                id,
                 content,
                 stream_type,
                 settings,
@@ -279,15 +333,18 @@ pub fn configuration(pane: data::Pane) -> Configuration<pane::State> {
     }
 }
 
-pub fn load_saved_state() -> SavedState {
-    match data::read_from_file(data::SAVED_STATE_PATH) {
+pub fn load_saved_state(path: &std::path::Path) -> SavedState {
+    match data::read_from_file(path) {
         Ok(state) => {
             let mut de_layouts = vec![];
 
             for layout in &state.layout_manager.layouts {
+                let mut saved = layout.dashboard.clone();
+                saved.assign_pane_ids(&layout.name);
+
                 let mut popout_windows = Vec::new();
 
-                for (pane, window_spec) in &layout.dashboard.popout {
+                for (pane, window_spec) in &saved.popout {
                     let configuration = configuration(pane.clone());
                     popout_windows.push((configuration, *window_spec));
                 }
@@ -295,8 +352,9 @@ pub fn load_saved_state() -> SavedState {
                 let layout_id = Uuid::new_v4();
 
                 let dashboard = Dashboard::from_config(
-                    configuration(layout.dashboard.pane.clone()),
+                    configuration(saved.pane),
                     popout_windows,
+                    saved.linked_timeframes.iter().copied().collect(),
                     layout_id,
                 );
 
@@ -326,11 +384,25 @@ pub fn load_saved_state() -> SavedState {
                                 .map(|layout| layout.id.clone())
                         });
 
-                LayoutManager::from_config(layouts, active_layout)
+                if layouts.is_empty() {
+                    // Nothing to open otherwise
+                    LayoutManager::new()
+                } else {
+                    LayoutManager::from_config(layouts, active_layout)
+                }
             };
 
+            let first_run = state.layout_manager.layouts.is_empty()
+                && state.mt5_settings.connections.is_empty();
+
             exchange::fetcher::toggle_trade_fetch(state.trade_fetch_enabled);
+            data::i18n::set_locale(state.locale);
             exchange::set_preferred_currency(state.size_in_quote_ccy);
+            exchange::synthetic::register(state.synthetics);
+            exchange::adapter::custom_ws::register(state.custom_ws);
+            exchange::ipc::set_config(state.ipc);
+            data::retention::set_config(state.retention);
+            exchange::limits::set_config(state.limits);
 
             SavedState {
                 theme: state.selected_theme,
@@ -343,6 +415,7 @@ pub fn load_saved_state() -> SavedState {
                 audio_cfg: state.audio_cfg,
                 volume_size_unit: state.size_in_quote_ccy,
                 mt5_settings: state.mt5_settings,
+                first_run,
             }
         }
         Err(e) => {
@@ -17,18 +17,24 @@ enum LogMessage {
     Shutdown,
 }
 
-pub fn setup(is_debug: bool) -> Result<(), Error> {
+/// `level` from the command line wins over `RUST_LOG`
+pub fn setup(is_debug: bool, level: Option<log::Level>) -> Result<(), Error> {
     let default_level = if is_debug {
         log::Level::Debug
     } else {
         log::Level::Info
     };
 
-    let level_filter = std::env::var("RUST_LOG")
-        .ok()
-        .as_deref()
-        .map(str::parse::<log::Level>)
-        .transpose()?
+    let env_level = match level {
+        Some(_) => None,
+        None => std::env::var("RUST_LOG")
+            .ok()
+            .as_deref()
+            .map(str::parse::<log::Level>)
+            .transpose()?,
+    };
+    let level_filter = level
+        .or(env_level)
         .unwrap_or(default_level)
         .to_level_filter();
 
@@ -60,6 +66,7 @@ pub fn setup(is_debug: bool) -> Result<(), Error> {
         .level_for("exchange", level_filter)
         .level_for("flowsurface", level_filter)
         .chain(io_sink)
+        .chain(fern::Output::call(data::log::capture))
         .apply()?;
 
     Ok(())
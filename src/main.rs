@@ -2,6 +2,7 @@
 
 mod audio;
 mod chart;
+mod cli;
 mod layout;
 mod logger;
 mod modal;
@@ -11,10 +12,20 @@ mod widget;
 mod window;
 
 use data::config::theme::default_theme;
-use data::{layout::WindowSpec, sidebar};
+use data::{
+    chart::heatmap::wall::{Side, WallChange},
+    layout::WindowSpec,
+    notifications::Severity,
+    sidebar, t,
+};
 use layout::{LayoutId, configuration};
-use modal::{LayoutManager, ThemeEditor, audio::AudioStream};
-use modal::{Mt5ConfigModal, dashboard_modal, main_dialog_modal};
+use modal::{
+    CustomWsConfigModal, DataLocation, Mt5ConfigModal, NotificationCenter, Onboarding,
+    dashboard_modal, main_dialog_modal,
+};
+use modal::{
+    LayoutManager, LogViewer, SymbolSearch, ThemeEditor, TickSizeModal, audio::AudioStream,
+};
 use screen::dashboard::{self, Dashboard};
 use widget::{
     confirm_dialog_container,
@@ -31,26 +42,45 @@ use iced::{
 };
 use std::{borrow::Cow, collections::HashMap, vec};
 
+/// How often a chart's kline revisions make it to the notifications, they're all logged
+const REVISION_REPORT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5 * 60);
+
+/// How soon after a check an MT5 reconnect checks the broker's symbols again
+const SYMBOL_RECHECK_ON_RECONNECT: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// How long exiting waits for streams to close, a stuck one is dropped after it
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3);
+
 fn main() {
-    logger::setup(cfg!(debug_assertions)).expect("Failed to initialize logger");
+    let args = <cli::Args as clap::Parser>::parse();
+    // Everything after this reads the data dir, the logger included
+    args.apply_instance();
+
+    logger::setup(cfg!(debug_assertions), args.log_level).expect("Failed to initialize logger");
+    args.load_env_file();
 
     std::thread::spawn(data::cleanup_old_market_data);
 
-    let _ = iced::daemon(Flowsurface::new, Flowsurface::update, Flowsurface::view)
-        .settings(iced::Settings {
-            antialiasing: true,
-            fonts: vec![
-                Cow::Borrowed(style::AZERET_MONO_BYTES),
-                Cow::Borrowed(style::ICONS_BYTES),
-            ],
-            default_text_size: iced::Pixels(12.0),
-            ..Default::default()
-        })
-        .title(Flowsurface::title)
-        .theme(Flowsurface::theme)
-        .scale_factor(Flowsurface::scale_factor)
-        .subscription(Flowsurface::subscription)
-        .run();
+    let layout = args.layout;
+    let _ = iced::daemon(
+        move || Flowsurface::new(layout.as_deref()),
+        Flowsurface::update,
+        Flowsurface::view,
+    )
+    .settings(iced::Settings {
+        antialiasing: true,
+        fonts: vec![
+            Cow::Borrowed(style::AZERET_MONO_BYTES),
+            Cow::Borrowed(style::ICONS_BYTES),
+        ],
+        default_text_size: iced::Pixels(12.0),
+        ..Default::default()
+    })
+    .title(Flowsurface::title)
+    .theme(Flowsurface::theme)
+    .scale_factor(Flowsurface::scale_factor)
+    .subscription(Flowsurface::subscription)
+    .run();
 }
 
 struct Flowsurface {
@@ -61,12 +91,41 @@ struct Flowsurface {
     audio_stream: AudioStream,
     mt5_modal: Mt5ConfigModal,
     mt5_settings: data::Mt5Settings,
+    mt5_symbol_cache: data::SymbolCache,
+    /// When the MT5 symbols were last checked against the broker, or the app started
+    mt5_symbols_checked: std::time::Instant,
+    custom_ws_modal: CustomWsConfigModal,
     confirm_dialog: Option<screen::ConfirmDialog<Message>>,
     volume_size_unit: exchange::SizeUnit,
     ui_scale_factor: data::ScaleFactor,
     timezone: data::UserTimezone,
     theme: data::Theme,
     notifications: Vec<Toast>,
+    notification_log: data::NotificationLog,
+    state_writer: data::state_store::StateWriter,
+    notification_center: NotificationCenter,
+    symbol_index: data::symbol_search::SearchIndex,
+    symbol_search: Option<SymbolSearch>,
+    onboarding: Option<Onboarding>,
+    tick_size_modal: Option<TickSizeModal>,
+    connection_bar: dashboard::connection_bar::ConnectionBar,
+    #[cfg(feature = "fault-injection")]
+    fault_panel: bool,
+    log_buffer: data::log::LogBuffer,
+    log_viewer: LogViewer,
+    data_location: DataLocation,
+    /// Book and update time each depth stream last delivered, for order book dumps
+    latest_depth: HashMap<exchange::Ticker, (u64, std::sync::Arc<exchange::depth::Depth>)>,
+    /// When kline revisions of a chart were last reported, to not flood the notifications
+    revisions_reported: HashMap<(exchange::Ticker, exchange::Timeframe), std::time::Instant>,
+    /// Time and price of each ticker's last trade, for the price endpoint
+    last_trades: HashMap<exchange::Ticker, (u64, exchange::util::Price)>,
+    /// Prices the local endpoint answers with, see [`exchange::ipc`]
+    price_feed: std::sync::Arc<exchange::ipc::Feed>,
+    /// The endpoint while it runs, dropping it stops the server
+    price_endpoint: Option<iced::task::Handle>,
+    /// The last session didn't exit cleanly, the layout stays closed until asked for
+    safe_mode: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -82,13 +141,21 @@ enum Message {
     WindowEvent(window::Event),
     ExitRequested(HashMap<window::Id, WindowSpec>),
     RestartRequested(HashMap<window::Id, WindowSpec>),
+    /// Streams were closed for an exit, or a restart when `true`, the state is saved next
+    StreamsClosed(HashMap<window::Id, WindowSpec>, bool),
     SaveStateOnly(HashMap<window::Id, WindowSpec>),
     GoBack,
     DataFolderRequested,
     ThemeSelected(data::Theme),
     ScaleFactorChanged(data::ScaleFactor),
     SetTimezone(data::UserTimezone),
+    SetLocale(data::Locale),
     ToggleTradeFetch(bool),
+    TogglePriceEndpoint(bool),
+    SetRetention(data::retention::Retention),
+    SetLimits(exchange::limits::Limits),
+    PriceEndpointStopped(Result<(), String>),
+    CopyPriceEndpointToken,
     ApplyVolumeSizeUnit(exchange::SizeUnit),
     RemoveNotification(usize),
     ToggleDialogModal(Option<screen::ConfirmDialog<Message>>),
@@ -96,16 +163,59 @@ enum Message {
     Layouts(modal::layout_manager::Message),
     AudioStream(modal::audio::Message),
     Mt5Config(modal::mt5_config::Message),
-    Mt5ConnectionTestResult(Result<(), String>),
+    Mt5ConnectionTestResult(Result<exchange::adapter::metatrader5::ServerInfo, String>),
+    Mt5TerminalsListed(Vec<exchange::adapter::metatrader5::Terminal>),
+    /// Serve cached symbols if any, hitting the proxy only once the cache is stale
     FetchMt5Symbols(exchange::adapter::metatrader5::Mt5Config),
+    /// Saves an MT5 connection edit the user confirmed restarting live streams for
+    ApplyMt5Config(
+        exchange::adapter::metatrader5::Mt5Config,
+        data::chart::revision::RevisionSettings,
+    ),
+    /// Fetch symbols from the proxy regardless of the cache
+    RefreshMt5Symbols(exchange::adapter::metatrader5::Mt5Config),
     Mt5SymbolsReceived(
-        Result<std::collections::HashMap<exchange::Ticker, Option<exchange::TickerInfo>>, String>,
+        String,
+        Result<exchange::adapter::metatrader5::SymbolFetchOutcome, String>,
     ),
+    CustomWsConfig(modal::custom_ws_config::Message),
+    CustomWsCaptured(Result<Vec<String>, String>),
+    Notifications(modal::notifications::Message),
+    LogViewer(modal::log_viewer::Message),
+    DataLocation(modal::data_location::Message),
+    RelocateDataDir(std::path::PathBuf, bool),
+    DataDirRelocated(Result<std::path::PathBuf, String>),
+    /// Dumps the order book of the focused pane, if it streams depth
+    DumpFocusedBook,
+    BookDumped(Result<std::path::PathBuf, String>),
+    WebhookPosted(Result<(), String>),
+    /// Saves an image of the active layout's panes in the main window
+    ExportLayoutImage,
+    ImageCaptured(window::Capture),
+    ImageExported(Result<std::path::PathBuf, String>),
+    LayoutExported(Result<std::path::PathBuf, String>),
+    SupportBundleCreated(Result<std::path::PathBuf, String>),
+    /// Leaves safe mode and opens the active layout as a normal start would
+    ExitSafeMode,
+    ToggleSymbolSearch,
+    #[cfg(feature = "fault-injection")]
+    ToggleFaultPanel,
+    #[cfg(feature = "fault-injection")]
+    FaultInjection(modal::fault_injection::Message),
+    SymbolSearch(modal::symbol_search::Message),
+    /// Opens the setup wizard, or closes it if open
+    ToggleOnboarding,
+    Onboarding(modal::onboarding::Message),
+    TickSizeModal(modal::tick_size::Message),
+    ConnectionBar(dashboard::connection_bar::Message),
+    SymbolIndexUpdated(data::symbol_search::Source, Vec<data::symbol_search::Entry>),
 }
 
 impl Flowsurface {
-    fn new() -> (Self, Task<Message>) {
-        let saved_state = layout::load_saved_state();
+    /// `layout` names the layout to open instead of the last active one
+    fn new(layout: Option<&str>) -> (Self, Task<Message>) {
+        let safe_mode = data::session::begin(&data::session::marker_path());
+        let saved_state = layout::load_saved_state(&data::data_dir::state_file());
 
         let (main_window_id, open_main_window) = {
             let (position, size) = saved_state.window();
@@ -118,15 +228,58 @@ impl Flowsurface {
             window::open(config)
         };
 
-        let (sidebar, launch_sidebar) = dashboard::Sidebar::new(&saved_state);
+        let (mut sidebar, launch_sidebar) = dashboard::Sidebar::new(&saved_state);
+
+        data::chart::spread_alert::set_defaults(
+            saved_state
+                .mt5_settings
+                .connections
+                .iter()
+                .filter_map(|connection| Some((connection.name.clone(), connection.spread_alert?))),
+        );
+        for connection in &saved_state.mt5_settings.connections {
+            if connection.server_info != Default::default() {
+                exchange::adapter::metatrader5::restore_server_info(
+                    &connection.server_addr,
+                    connection.server_info.clone(),
+                );
+            }
+        }
+
+        exchange::adapter::metatrader5::tick_override::set_all(
+            &saved_state.mt5_settings.tick_overrides,
+        );
+
+        let onboarding = saved_state.first_run.then(|| {
+            Onboarding::new(
+                sidebar.tickers_table.exchange_filters(),
+                saved_state.mt5_settings.revisions,
+            )
+        });
+
+        let mt5_symbol_cache = data::SymbolCache::load();
+        exchange::adapter::metatrader5::retired::set_all(mt5_symbol_cache.retired());
+        if let Some(name) = &saved_state.mt5_settings.active_connection
+            && let Some(cached) = mt5_symbol_cache.get(name, data::symbol_cache::now_ms())
+        {
+            sidebar
+                .tickers_table
+                .update(dashboard::tickers_table::Message::UpdateTickersInfo(
+                    exchange::adapter::Exchange::MetaTrader5,
+                    cached.tickers.clone(),
+                ));
+        }
 
         let mut state = Self {
             main_window: window::Window::new(main_window_id),
             layout_manager: saved_state.layout_manager,
             theme_editor: ThemeEditor::new(saved_state.custom_theme),
             audio_stream: AudioStream::new(saved_state.audio_cfg),
-            mt5_modal: Mt5ConfigModal::new(),
+            mt5_modal: Mt5ConfigModal::new().with_revisions(saved_state.mt5_settings.revisions),
             mt5_settings: saved_state.mt5_settings,
+            mt5_symbol_cache,
+            mt5_symbols_checked: std::time::Instant::now(),
+            custom_ws_modal: CustomWsConfigModal::new(),
             sidebar,
             confirm_dialog: None,
             timezone: saved_state.timezone,
@@ -134,8 +287,61 @@ impl Flowsurface {
             volume_size_unit: saved_state.volume_size_unit,
             theme: saved_state.theme,
             notifications: vec![],
+            notification_log: data::NotificationLog::load(),
+            state_writer: data::state_store::StateWriter::spawn(),
+            notification_center: NotificationCenter::default(),
+            symbol_index: data::symbol_search::SearchIndex::default(),
+            symbol_search: None,
+            onboarding,
+            tick_size_modal: None,
+            connection_bar: dashboard::connection_bar::ConnectionBar::default(),
+            #[cfg(feature = "fault-injection")]
+            fault_panel: false,
+            log_buffer: data::log::LogBuffer::default(),
+            log_viewer: LogViewer::default(),
+            data_location: DataLocation::default(),
+            latest_depth: HashMap::new(),
+            revisions_reported: HashMap::new(),
+            last_trades: HashMap::new(),
+            price_feed: std::sync::Arc::default(),
+            price_endpoint: None,
+            safe_mode,
         };
 
+        let scripts = data::indicators::script::reload();
+        for (file, error) in scripts.errors {
+            state.record_notification(
+                t!("source.indicator_scripts"),
+                Severity::Error,
+                format!("{file}: {error}"),
+            );
+        }
+
+        let index_cached_symbols =
+            Task::batch(state.mt5_symbol_cache.connections().map(|(name, tickers)| {
+                reindex_symbols(
+                    data::symbol_search::Source::Mt5Connection(name.to_string()),
+                    tickers.keys().copied().collect(),
+                )
+            }));
+
+        if let Some(name) = layout {
+            match state
+                .layout_manager
+                .layouts
+                .iter()
+                .find(|entry| entry.id.name == name)
+            {
+                Some(entry) => {
+                    let _ = state.layout_manager.set_active_layout(entry.id.unique);
+                }
+                None => state.notify(
+                    t!("source.command_line"),
+                    Toast::error(t!("notify.unknown_layout", name = name)),
+                ),
+            }
+        }
+
         let active_layout_id = state.layout_manager.active_layout_id().unwrap_or(
             &state
                 .layout_manager
@@ -144,29 +350,99 @@ impl Flowsurface {
                 .expect("No layouts available")
                 .id,
         );
-        let load_layout = state.load_layout(active_layout_id.unique, main_window_id);
+        let load_layout = if safe_mode {
+            log::warn!("The last session didn't exit cleanly, starting in safe mode");
+            state.record_notification(
+                t!("source.safe_mode"),
+                Severity::Warning,
+                t!("safe_mode.notification").to_string(),
+            );
+            state.sidebar.set_menu(Some(sidebar::Menu::Layout));
+            Task::none()
+        } else {
+            state.load_layout(active_layout_id.unique, main_window_id)
+        };
+        let price_endpoint = state.start_price_endpoint();
 
         (
             state,
             open_main_window
                 .discard()
                 .chain(load_layout)
-                .chain(launch_sidebar.map(Message::Sidebar)),
+                .chain(launch_sidebar.map(Message::Sidebar))
+                .chain(index_cached_symbols)
+                .chain(price_endpoint),
         )
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::MarketWsEvent(event) => {
+                if self.sidebar.is_connection_bar_shown() {
+                    self.connection_bar
+                        .stats
+                        .record(&event, std::time::Instant::now());
+                }
+
                 let main_window_id = self.main_window.id;
-                let dashboard = self.active_dashboard_mut();
+                let task = self
+                    .active_dashboard_mut()
+                    .on_market_event(&event, main_window_id)
+                    .map(move |msg| Message::Dashboard {
+                        layout_id: None,
+                        event: msg,
+                    });
 
                 match event {
                     exchange::Event::Connected(exchange) => {
                         log::info!("a stream connected to {exchange} WS");
+
+                        if exchange == exchange::adapter::Exchange::MetaTrader5 {
+                            return task
+                                .chain(self.recheck_mt5_symbols(SYMBOL_RECHECK_ON_RECONNECT));
+                        }
                     }
                     exchange::Event::Disconnected(exchange, reason) => {
                         log::info!("a stream disconnected from {exchange} WS: {reason:?}");
+
+                        let source = match &self.mt5_settings.active_connection {
+                            Some(name) if exchange == exchange::adapter::Exchange::MetaTrader5 => {
+                                name.clone()
+                            }
+                            _ => exchange.to_string(),
+                        };
+                        if exchange::adapter::metatrader5::is_session_revoked(&reason) {
+                            // Reconnecting stopped, nothing moves until the credentials are fixed
+                            let toast = Toast::error(t!(
+                                "notify.session_revoked",
+                                reason = reason,
+                                source = source
+                            ))
+                            .sticky();
+                            self.notify(source, toast);
+                        } else {
+                            self.record_notification(
+                                source,
+                                Severity::Warning,
+                                t!("notify.stream_disconnected", reason = reason),
+                            );
+                        }
+
+                        if exchange == exchange::adapter::Exchange::MetaTrader5
+                            && let Some(name) = &self.mt5_settings.active_connection
+                            && self
+                                .mt5_symbol_cache
+                                .invalidate_on_disconnect(name, &reason)
+                        {
+                            log::info!("Invalidated cached MT5 symbols for {name}");
+                            self.mt5_symbol_cache.save();
+
+                            if let Some(config) =
+                                exchange::adapter::metatrader5::get_global_config()
+                            {
+                                return task.chain(Task::done(Message::RefreshMt5Symbols(config)));
+                            }
+                        }
                     }
                     exchange::Event::DepthReceived(
                         stream,
@@ -174,46 +450,61 @@ impl Flowsurface {
                         depth,
                         trades_buffer,
                     ) => {
-                        let task = dashboard
-                            .update_depth_and_trades(
-                                &stream,
-                                depth_update_t,
-                                &depth,
-                                &trades_buffer,
-                                main_window_id,
-                            )
-                            .map(move |msg| Message::Dashboard {
-                                layout_id: None,
-                                event: msg,
-                            });
-
                         if let Err(err) = self.audio_stream.try_play_sound(&stream, &trades_buffer)
                         {
                             log::error!("Failed to play sound: {err}");
                         }
-
-                        return task;
-                    }
-                    exchange::Event::KlineReceived(stream, kline) => {
-                        return dashboard
-                            .update_latest_klines(&stream, &kline, main_window_id)
-                            .map(move |msg| Message::Dashboard {
-                                layout_id: None,
-                                event: msg,
-                            });
+                        let ticker = stream.ticker_info().ticker;
+                        if let Some(trade) = trades_buffer.last() {
+                            self.last_trades.insert(ticker, (trade.time, trade.price));
+                        }
+                        self.latest_depth.insert(ticker, (depth_update_t, depth));
                     }
+                    exchange::Event::MarketStateChanged(..)
+                    | exchange::Event::KlineReceived(..) => {}
                 }
+
+                return task;
             }
             Message::Tick(now) => {
+                self.log_buffer.extend(data::log::take_captured());
+                self.apply_mt5_server_info();
+
+                if let Some(error) = self.state_writer.take_error() {
+                    self.record_notification(
+                        t!("source.saved_state"),
+                        Severity::Error,
+                        t!("notify.state_write_failed", error = error),
+                    );
+                }
+
                 let main_window_id = self.main_window.id;
+                let timezone = self.timezone;
+
+                if self.price_endpoint.is_some() {
+                    self.publish_prices();
+                }
 
-                return self
+                if self.sidebar.is_connection_bar_shown() {
+                    let entries = self.active_dashboard().connection_entries(main_window_id);
+                    self.connection_bar
+                        .stats
+                        .sample(now, entries.iter().map(|entry| &entry.stream));
+                }
+
+                if self.safe_mode {
+                    return Task::none();
+                }
+
+                let recheck = self.recheck_mt5_symbols(self.mt5_symbol_cache.ttl());
+                let tick = self
                     .active_dashboard_mut()
-                    .tick(now, main_window_id)
+                    .tick(now, main_window_id, timezone)
                     .map(move |msg| Message::Dashboard {
                         layout_id: None,
                         event: msg,
                     });
+                return Task::batch([tick, recheck]);
             }
             Message::WindowEvent(event) => match event {
                 window::Event::CloseRequested(window) => {
@@ -236,12 +527,26 @@ impl Flowsurface {
                 }
             },
             Message::ExitRequested(windows) => {
-                self.save_state_to_disk(&windows);
-                return iced::exit();
+                return close_streams(windows, false);
             }
             Message::RestartRequested(windows) => {
+                return close_streams(windows, true);
+            }
+            Message::StreamsClosed(windows, restart) => {
                 self.save_state_to_disk(&windows);
-                return self.restart();
+                // Written before exiting, the restarted app reads it right back
+                self.flush_saved_state();
+                let main_window = self.main_window.id;
+                self.layout_manager
+                    .iter_dashboards_mut()
+                    .for_each(|dashboard| dashboard.save_heatmap_history(main_window));
+                data::chart::heatmap::history::flush();
+                data::session::end(&data::session::marker_path());
+                return if restart {
+                    self.restart()
+                } else {
+                    iced::exit()
+                };
             }
             Message::SaveStateOnly(windows) => {
                 self.save_state_to_disk(&windows);
@@ -250,7 +555,13 @@ impl Flowsurface {
             Message::GoBack => {
                 let main_window = self.main_window.id;
 
-                if self.confirm_dialog.is_some() {
+                if self.symbol_search.is_some() {
+                    self.symbol_search = None;
+                } else if self.onboarding.is_some() {
+                    self.onboarding = None;
+                } else if self.tick_size_modal.is_some() {
+                    self.tick_size_modal = None;
+                } else if self.confirm_dialog.is_some() {
                     self.confirm_dialog = None;
                 } else if self.sidebar.active_menu().is_some() {
                     self.sidebar.set_menu(None);
@@ -281,6 +592,8 @@ impl Flowsurface {
                 let main_window = self.main_window;
                 let layout_id = id.unwrap_or(active_layout.unique);
 
+                let revisions = self.mt5_settings.revisions;
+
                 if let Some(dashboard) = self.layout_manager.mut_dashboard(layout_id) {
                     let (main_task, event) = dashboard.update(msg, &main_window, &layout_id);
 
@@ -291,15 +604,307 @@ impl Flowsurface {
                             data,
                             stream,
                         }) => dashboard
-                            .distribute_fetched_data(main_window.id, pane_id, data, stream)
+                            .distribute_fetched_data(
+                                main_window.id,
+                                pane_id,
+                                data,
+                                stream,
+                                &revisions,
+                            )
                             .map(move |msg| Message::Dashboard {
                                 layout_id: Some(layout_id),
                                 event: msg,
                             }),
                         Some(dashboard::Event::Notification(toast)) => {
-                            self.notifications.push(toast);
+                            self.notify(t!("source.dashboard"), toast);
                             Task::none()
                         }
+                        Some(dashboard::Event::BarClosed {
+                            ticker_info,
+                            timeframe,
+                            alert,
+                        }) => {
+                            if alert.play_sound
+                                && let Err(err) = self.audio_stream.play(audio::SoundType::BarClose)
+                            {
+                                log::error!("Failed to play bar close sound: {err}");
+                            }
+                            if alert.show_toast {
+                                self.notify(
+                                    t!("source.bar_close_alert"),
+                                    Toast::new(widget::toast::Notification::Info(t!(
+                                        "notify.bar_closed",
+                                        ticker = ticker_info.ticker,
+                                        timeframe = timeframe
+                                    ))),
+                                );
+                            }
+                            Task::none()
+                        }
+                        Some(dashboard::Event::PriceAlertTriggered { ticker_info, alert }) => {
+                            if let Err(err) = self.audio_stream.play(audio::SoundType::Alert) {
+                                log::error!("Failed to play price alert sound: {err}");
+                            }
+
+                            let label = alert.label.map(|l| format!(" ({l})")).unwrap_or_default();
+                            self.notify(
+                                t!("source.price_alert"),
+                                Toast::new(widget::toast::Notification::Warn(t!(
+                                    "notify.price_crossed",
+                                    ticker = ticker_info.ticker,
+                                    price = alert.price.to_string(ticker_info.min_ticksize),
+                                    label = label
+                                ))),
+                            );
+                            Task::none()
+                        }
+                        Some(dashboard::Event::CandlePatterns { ticker_info, marks }) => {
+                            for mark in marks {
+                                log::info!("{} closed on a {}", ticker_info.ticker, mark.kind);
+                                self.notify(
+                                    t!("source.candle_pattern"),
+                                    Toast::new(widget::toast::Notification::Info(t!(
+                                        "notify.candle_pattern",
+                                        ticker = ticker_info.ticker,
+                                        pattern = mark.kind
+                                    ))),
+                                );
+                            }
+                            Task::none()
+                        }
+                        Some(dashboard::Event::StopRuns {
+                            ticker_info,
+                            config,
+                            runs,
+                        }) => {
+                            let mut posts = vec![];
+
+                            for run in runs {
+                                let level = run.level.to_string(ticker_info.min_ticksize);
+                                log::info!(
+                                    "{} stop run on the swing {} at {level}",
+                                    ticker_info.ticker,
+                                    if run.swept_high { "high" } else { "low" },
+                                );
+
+                                if config.play_sound {
+                                    let sound = if run.swept_high {
+                                        audio::SoundType::HardSell
+                                    } else {
+                                        audio::SoundType::HardBuy
+                                    };
+                                    if let Err(err) = self.audio_stream.play(sound) {
+                                        log::error!("Failed to play stop run sound: {err}");
+                                    }
+                                }
+                                if config.show_toast {
+                                    let message = if run.swept_high {
+                                        t!(
+                                            "notify.stop_run_high",
+                                            ticker = ticker_info.ticker,
+                                            level = level
+                                        )
+                                    } else {
+                                        t!(
+                                            "notify.stop_run_low",
+                                            ticker = ticker_info.ticker,
+                                            level = level
+                                        )
+                                    };
+                                    self.notify(
+                                        t!("source.stop_run"),
+                                        Toast::new(widget::toast::Notification::Warn(message)),
+                                    );
+                                }
+                                if let Some(url) = config.webhook.clone() {
+                                    posts.push(Task::perform(
+                                        exchange::webhook::post(
+                                            url,
+                                            run.webhook_payload(&ticker_info),
+                                        ),
+                                        |result| {
+                                            Message::WebhookPosted(
+                                                result.map_err(|e| e.to_string()),
+                                            )
+                                        },
+                                    ));
+                                }
+                            }
+
+                            Task::batch(posts)
+                        }
+                        Some(dashboard::Event::SpreadWidened {
+                            ticker_info,
+                            alert,
+                            widening,
+                        }) => {
+                            let spread = format!("{:.1}", widening.spread);
+                            let limit = format!("{:.1}", widening.limit);
+                            log::info!(
+                                "{} spread widened to {spread} points, over {limit}",
+                                ticker_info.ticker
+                            );
+
+                            if alert.play_sound
+                                && let Err(err) = self.audio_stream.play(audio::SoundType::Alert)
+                            {
+                                log::error!("Failed to play spread alert sound: {err}");
+                            }
+                            self.record_notification(
+                                t!("source.spread_alert"),
+                                Severity::Warning,
+                                t!(
+                                    "notify.spread_widened",
+                                    ticker = ticker_info.ticker,
+                                    spread = spread,
+                                    limit = limit
+                                ),
+                            );
+
+                            Task::none()
+                        }
+                        Some(dashboard::Event::Signals {
+                            ticker_info,
+                            outputs,
+                        }) => {
+                            use data::indicators::signal::{Output, TIME_LIMIT};
+
+                            for (script, output) in outputs {
+                                match output {
+                                    Output::Notice(notice) => {
+                                        log::info!("{} {script}: {notice}", ticker_info.ticker);
+                                        self.notify(
+                                            t!("source.signal_scripts"),
+                                            Toast::new(widget::toast::Notification::Info(t!(
+                                                "notify.signal_notice",
+                                                script = script,
+                                                ticker = ticker_info.ticker,
+                                                notice = notice
+                                            ))),
+                                        );
+                                    }
+                                    Output::Error(error) => {
+                                        log::warn!("Signal script {script} failed: {error}");
+                                        self.record_notification(
+                                            t!("source.signal_scripts"),
+                                            Severity::Error,
+                                            t!(
+                                                "notify.signal_failed",
+                                                script = script,
+                                                error = error
+                                            ),
+                                        );
+                                    }
+                                    Output::Disabled => {
+                                        log::warn!("Signal script {script} disabled for overruns");
+                                        self.notify(
+                                            t!("source.signal_scripts"),
+                                            Toast::new(widget::toast::Notification::Warn(t!(
+                                                "notify.signal_disabled",
+                                                script = script,
+                                                ticker = ticker_info.ticker,
+                                                limit = TIME_LIMIT.as_millis()
+                                            ))),
+                                        );
+                                    }
+                                }
+                            }
+
+                            Task::none()
+                        }
+                        Some(dashboard::Event::SpreadAlertDefault { ticker_info, alert }) => {
+                            use exchange::adapter::metatrader5;
+
+                            let name = ticker_info
+                                .ticker
+                                .connection()
+                                .and_then(metatrader5::connection_name)
+                                .or_else(|| {
+                                    metatrader5::get_global_config()
+                                        .map(|config| config.connection_name())
+                                });
+                            if let Some(connection) = name.and_then(|name| {
+                                self.mt5_settings
+                                    .connections
+                                    .iter_mut()
+                                    .find(|connection| connection.name == name)
+                            }) {
+                                connection.spread_alert = alert;
+                                let name = connection.name.clone();
+
+                                data::chart::spread_alert::set_defaults(
+                                    self.mt5_settings
+                                        .connections
+                                        .iter()
+                                        .filter_map(|connection| {
+                                            Some((
+                                                connection.name.clone(),
+                                                connection.spread_alert?,
+                                            ))
+                                        }),
+                                );
+                                self.notify(
+                                    t!("source.spread_alert"),
+                                    Toast::new(widget::toast::Notification::Info(t!(
+                                        "notify.spread_alert_default",
+                                        connection = name
+                                    ))),
+                                );
+                            }
+
+                            Task::none()
+                        }
+                        Some(dashboard::Event::LiquidityWall {
+                            ticker_info,
+                            wall,
+                            sound,
+                        }) => {
+                            log::info!(
+                                "{} {} wall {} at {}, qty {}",
+                                ticker_info.ticker,
+                                wall.side,
+                                wall.change,
+                                wall.price.to_string(ticker_info.min_ticksize),
+                                wall.qty
+                            );
+
+                            if sound {
+                                let sound = match (wall.side, wall.change) {
+                                    (Side::Bid, WallChange::Appeared) => audio::SoundType::Buy,
+                                    (Side::Ask, WallChange::Appeared) => audio::SoundType::Sell,
+                                    (Side::Bid, _) => audio::SoundType::HardBuy,
+                                    (Side::Ask, _) => audio::SoundType::HardSell,
+                                };
+                                if let Err(err) = self.audio_stream.play(sound) {
+                                    log::error!("Failed to play liquidity wall sound: {err}");
+                                }
+                            }
+                            Task::none()
+                        }
+                        Some(dashboard::Event::KlinesRevised {
+                            ticker_info,
+                            timeframe,
+                            revisions,
+                            applied,
+                        }) => {
+                            self.report_kline_revisions(
+                                ticker_info,
+                                timeframe,
+                                &revisions,
+                                applied,
+                            );
+                            Task::none()
+                        }
+                        Some(dashboard::Event::DumpBook(ticker_info)) => {
+                            self.dump_book(ticker_info.ticker)
+                        }
+                        Some(dashboard::Event::ExportImage {
+                            window,
+                            target,
+                            label,
+                        }) => {
+                            window::capture(window, Some(target), label).map(Message::ImageCaptured)
+                        }
                         Some(dashboard::Event::ResolveStreams { pane_id, streams }) => {
                             let tickers_info = self.sidebar.tickers_info();
 
@@ -359,6 +964,15 @@ impl Flowsurface {
             Message::SetTimezone(tz) => {
                 self.timezone = tz;
             }
+            Message::SetRetention(retention) => {
+                data::retention::set_config(retention);
+            }
+            Message::SetLimits(limits) => {
+                exchange::limits::set_config(limits);
+            }
+            Message::SetLocale(locale) => {
+                data::i18n::set_locale(locale);
+            }
             Message::ScaleFactorChanged(value) => {
                 self.ui_scale_factor = value;
             }
@@ -373,6 +987,32 @@ impl Flowsurface {
                     self.confirm_dialog = None;
                 }
             }
+            Message::TogglePriceEndpoint(enabled) => {
+                let config = exchange::ipc::config();
+                exchange::ipc::set_config(if enabled {
+                    config.enable()
+                } else {
+                    exchange::ipc::IpcConfig {
+                        enabled: false,
+                        ..config
+                    }
+                });
+
+                return self.start_price_endpoint();
+            }
+            Message::PriceEndpointStopped(result) => {
+                self.price_endpoint = None;
+
+                if let Err(err) = result {
+                    self.notify(
+                        t!("source.price_endpoint"),
+                        Toast::error(t!("notify.price_endpoint_failed", error = err)),
+                    );
+                }
+            }
+            Message::CopyPriceEndpointToken => {
+                return iced::clipboard::write(exchange::ipc::config().token);
+            }
             Message::ToggleDialogModal(dialog) => {
                 self.confirm_dialog = dialog;
             }
@@ -380,6 +1020,11 @@ impl Flowsurface {
                 let action = self.layout_manager.update(message);
 
                 match action {
+                    Some(modal::layout_manager::Action::Select(layout)) if self.safe_mode => {
+                        if let Err(err) = self.layout_manager.set_active_layout(layout) {
+                            log::error!("Failed to set active layout: {}", err);
+                        }
+                    }
                     Some(modal::layout_manager::Action::Select(layout)) => {
                         let active_popout_keys = self
                             .active_dashboard()
@@ -413,6 +1058,24 @@ impl Flowsurface {
                         .chain(window_tasks)
                         .chain(self.load_layout(layout, self.main_window.id));
                     }
+                    Some(modal::layout_manager::Action::Export(id)) => {
+                        let Some(layout) = self.layout_manager.get(id) else {
+                            return Task::none();
+                        };
+                        let layout = data::Layout {
+                            name: layout.id.name.clone(),
+                            dashboard: data::Dashboard::from(&layout.dashboard),
+                        };
+
+                        return Task::perform(
+                            async move {
+                                layout
+                                    .export(data::symbol_cache::now_ms())
+                                    .map_err(|e| format!("Failed to export layout: {e}"))
+                            },
+                            Message::LayoutExported,
+                        );
+                    }
                     Some(modal::layout_manager::Action::Clone(id)) => {
                         let manager = &mut self.layout_manager;
 
@@ -424,7 +1087,8 @@ impl Flowsurface {
                             )
                         });
 
-                        if let Some((name, old_id, ser_dashboard)) = source_data {
+                        if let Some((name, old_id, mut ser_dashboard)) = source_data {
+                            ser_dashboard.renew_pane_ids();
                             let new_uid = uuid::Uuid::new_v4();
                             let new_layout = LayoutId {
                                 unique: new_uid,
@@ -441,6 +1105,7 @@ impl Flowsurface {
                             let dashboard = Dashboard::from_config(
                                 configuration(ser_dashboard.pane.clone()),
                                 popout_windows,
+                                ser_dashboard.linked_timeframes.iter().copied().collect(),
                                 old_id,
                             );
 
@@ -458,82 +1123,373 @@ impl Flowsurface {
                     modal::mt5_config::Action::Exit => {
                         self.sidebar.set_menu(None);
                     }
-                    modal::mt5_config::Action::SaveConfig(config) => {
-                        // Convert exchange::Mt5Config to data::Mt5Connection
-                        let connection = data::Mt5Connection {
-                            name: format!("MT5 {}", config.server_addr),
-                            server_addr: config.server_addr.clone(),
-                            api_key: config.api_key.clone(),
-                            use_tls: config.use_tls,
-                            auto_reconnect: config.auto_reconnect,
-                        };
-
-                        // Trigger fetch symbols immediately
-                        let fetch_cmd = Task::done(Message::FetchMt5Symbols(config.clone()));
+                    modal::mt5_config::Action::SaveConfig(config, revisions) => {
+                        self.sidebar.set_menu(None);
 
-                        // Add or update connection in settings
-                        if let Some(existing) = self
-                            .mt5_settings
-                            .connections
-                            .iter_mut()
-                            .find(|c| c.server_addr == connection.server_addr)
-                        {
-                            *existing = connection.clone();
-                        } else {
-                            self.mt5_settings.connections.push(connection.clone());
+                        if let Some(confirm_dialog) = mt5_restart_dialog(&config, revisions) {
+                            self.confirm_dialog = Some(confirm_dialog);
+                            return Task::none();
                         }
-                        self.mt5_settings.active_connection = Some(connection.name.clone());
+                        return self.apply_mt5_config(config, revisions);
+                    }
+                    modal::mt5_config::Action::TestConnection(config) => {
+                        // Spawn async connection test, listing the proxy's terminals alongside
+                        let test = Task::future({
+                            let config = config.clone();
+                            async move {
+                                let result = config.test_connection().await;
+                                Message::Mt5ConnectionTestResult(result)
+                            }
+                        });
+                        let terminals = Task::future(async move {
+                            let terminals =
+                                exchange::adapter::metatrader5::fetch_terminals(&config)
+                                    .await
+                                    .unwrap_or_else(|e| {
+                                        log::debug!("MT5 terminals not listed: {e}");
+                                        vec![]
+                                    });
+                            Message::Mt5TerminalsListed(terminals)
+                        });
+                        return Task::batch([test, terminals]);
+                    }
+                    modal::mt5_config::Action::RefreshSymbols(config) => {
+                        return Task::done(Message::RefreshMt5Symbols(config));
+                    }
+                    modal::mt5_config::Action::None => {}
+                }
+            }
+            Message::CustomWsConfig(message) => match self.custom_ws_modal.update(message) {
+                modal::custom_ws_config::Action::Exit => {
+                    self.sidebar.set_menu(None);
+                }
+                modal::custom_ws_config::Action::SaveConfig(config) => {
+                    use exchange::adapter::custom_ws;
+
+                    let name = config.name.clone();
+                    let mut configs = custom_ws::all();
+                    match configs.iter_mut().find(|c| c.name == name) {
+                        Some(existing) => *existing = config,
+                        None => configs.push(config),
+                    }
+                    custom_ws::register(configs);
+
+                    self.sidebar.tickers_table.update(
+                        dashboard::tickers_table::Message::UpdateTickersInfo(
+                            exchange::adapter::Exchange::CustomWs,
+                            custom_ws::ticker_infos(),
+                        ),
+                    );
+                    self.notifications
+                        .push(Toast::new(widget::toast::Notification::Info(t!(
+                            "notify.custom_feed_saved",
+                            name = name
+                        ))));
+                    self.sidebar.set_menu(None);
+
+                    let mut active_windows = self
+                        .active_dashboard()
+                        .popout
+                        .keys()
+                        .copied()
+                        .collect::<Vec<window::Id>>();
+                    active_windows.push(self.main_window.id);
+                    return window::collect_window_specs(active_windows, Message::SaveStateOnly);
+                }
+                modal::custom_ws_config::Action::DeleteConfig(name) => {
+                    use exchange::adapter::custom_ws;
 
-                        // Set global config so fetch_klines can access it
-                        exchange::adapter::metatrader5::set_global_config(config.clone());
+                    let mut configs = custom_ws::all();
+                    configs.retain(|c| c.name != name);
+                    custom_ws::register(configs);
 
-                        log::info!("MT5 config saved: {}", config.server_addr);
-                        self.sidebar.set_menu(None);
+                    let mut active_windows = self
+                        .active_dashboard()
+                        .popout
+                        .keys()
+                        .copied()
+                        .collect::<Vec<window::Id>>();
+                    active_windows.push(self.main_window.id);
+                    return window::collect_window_specs(active_windows, Message::SaveStateOnly);
+                }
+                modal::custom_ws_config::Action::TestConnection(config) => {
+                    return Task::perform(
+                        modal::custom_ws_config::capture(config),
+                        Message::CustomWsCaptured,
+                    );
+                }
+                modal::custom_ws_config::Action::None => {}
+            },
+            Message::CustomWsCaptured(result) => self.custom_ws_modal.set_captured(result),
+            Message::Notifications(message) => {
+                match self
+                    .notification_center
+                    .update(message, &mut self.notification_log)
+                {
+                    Some(modal::notifications::Action::CopyToClipboard(details)) => {
+                        return iced::clipboard::write(details);
+                    }
+                    None => {}
+                }
+            }
+            Message::LogViewer(message) => {
+                match self.log_viewer.update(message, &mut self.log_buffer) {
+                    Some(modal::log_viewer::Action::CopyToClipboard(lines)) => {
+                        return iced::clipboard::write(lines);
+                    }
+                    Some(modal::log_viewer::Action::CreateSupportBundle(path)) => {
+                        return self.create_support_bundle(path);
+                    }
+                    None => {}
+                }
+            }
+            Message::DataLocation(message) => match self.data_location.update(message) {
+                Some(modal::data_location::Action::Confirm { target, move_files }) => {
+                    let message = if move_files {
+                        format!(
+                            "Move everything in {} to {}?",
+                            data::data_dir::root().display(),
+                            target.display()
+                        )
+                    } else {
+                        format!(
+                            "Keep data in {} from now on? Existing files stay where they are.",
+                            target.display()
+                        )
+                    };
+                    let confirm_dialog = screen::ConfirmDialog::new(
+                        message,
+                        Box::new(Message::RelocateDataDir(target, move_files)),
+                    )
+                    .with_confirm_btn_text(if move_files { "Move" } else { "Change" }.to_string());
+
+                    self.confirm_dialog = Some(confirm_dialog);
+                }
+                None => {}
+            },
+            Message::RelocateDataDir(target, move_files) => {
+                self.data_location.started();
+
+                return Task::sip(
+                    modal::data_location::relocate(target, move_files),
+                    |progress| {
+                        Message::DataLocation(modal::data_location::Message::Progressed(progress))
+                    },
+                    Message::DataDirRelocated,
+                );
+            }
+            Message::DataDirRelocated(result) => {
+                self.data_location.finished(result.is_ok());
+
+                match result {
+                    Ok(path) => {
                         self.notifications
-                            .push(Toast::new(widget::toast::Notification::Info(
-                                "MT5 configuration saved".to_string(),
+                            .push(Toast::new(widget::toast::Notification::Info(t!(
+                                "notify.data_folder_moved",
+                                path = path.display()
+                            ))));
+                    }
+                    Err(err) => {
+                        self.notify(t!("source.data_folder"), Toast::error(err));
+                    }
+                }
+            }
+            Message::DumpFocusedBook => {
+                let main_window = self.main_window.id;
+                let dashboard = self.active_dashboard();
+
+                let ticker_info = dashboard.focus.and_then(|(window, pane)| {
+                    dashboard
+                        .get_pane(main_window, window, pane)
+                        .and_then(dashboard::pane::State::stream_pair)
+                });
+
+                if let Some(ticker_info) = ticker_info {
+                    return self.dump_book(ticker_info.ticker);
+                }
+            }
+            Message::BookDumped(result) => match result {
+                Ok(path) => {
+                    self.notifications
+                        .push(Toast::new(widget::toast::Notification::Info(t!(
+                            "notify.book_dumped",
+                            path = path.display()
+                        ))));
+                }
+                Err(err) => {
+                    self.notify(t!("source.order_book_dump"), Toast::error(err));
+                }
+            },
+            Message::WebhookPosted(result) => {
+                if let Err(err) = result {
+                    self.notify(
+                        t!("source.stop_run"),
+                        Toast::error(t!("notify.webhook_failed", error = err)),
+                    );
+                }
+            }
+            Message::ExportLayoutImage => {
+                let label = self
+                    .layout_manager
+                    .active_layout_id()
+                    .map_or_else(|| "Layout".to_string(), |id| id.name.clone());
+
+                return window::capture(
+                    self.main_window.id,
+                    Some(dashboard::layout_capture_id()),
+                    label,
+                )
+                .map(Message::ImageCaptured);
+            }
+            Message::ImageCaptured(capture) => {
+                return self.export_image(capture);
+            }
+            Message::ImageExported(result) => match result {
+                Ok(path) => {
+                    self.notifications
+                        .push(Toast::new(widget::toast::Notification::Info(t!(
+                            "notify.image_exported",
+                            path = path.display()
+                        ))));
+                }
+                Err(err) => {
+                    self.notify(t!("source.image_export"), Toast::error(err));
+                }
+            },
+            Message::LayoutExported(result) => match result {
+                Ok(path) => {
+                    self.notifications
+                        .push(Toast::new(widget::toast::Notification::Info(t!(
+                            "notify.layout_exported",
+                            path = path.display()
+                        ))));
+                }
+                Err(err) => {
+                    self.notify(t!("source.layout_export"), Toast::error(err));
+                }
+            },
+            Message::SupportBundleCreated(result) => match result {
+                Ok(path) => {
+                    self.notifications
+                        .push(Toast::new(widget::toast::Notification::Info(t!(
+                            "notify.support_bundle_created",
+                            path = path.display()
+                        ))));
+                }
+                Err(err) => {
+                    self.notify(t!("source.support_bundle"), Toast::error(err));
+                }
+            },
+            Message::ExitSafeMode => {
+                self.safe_mode = false;
+                self.sidebar.set_menu(None);
+
+                let Some(layout) = self.layout_manager.active_layout_id().map(|id| id.unique)
+                else {
+                    return Task::none();
+                };
+                return self.load_layout(layout, self.main_window.id);
+            }
+            #[cfg(feature = "fault-injection")]
+            Message::ToggleFaultPanel => {
+                self.fault_panel = !self.fault_panel;
+            }
+            #[cfg(feature = "fault-injection")]
+            Message::FaultInjection(message) => {
+                modal::fault_injection::update(message);
+            }
+            Message::ToggleSymbolSearch => {
+                if self.symbol_search.take().is_none() {
+                    self.symbol_search = Some(SymbolSearch::default());
+                    return iced::widget::operation::focus(modal::symbol_search::SEARCH_BOX_ID);
+                }
+            }
+            Message::SymbolSearch(message) => {
+                let Some(search) = &mut self.symbol_search else {
+                    return Task::none();
+                };
+                let tickers_table = &self.sidebar.tickers_table;
+
+                match search.update(
+                    message,
+                    &self.symbol_index,
+                    &tickers_table.favorited_tickers,
+                    &tickers_table.recent_tickers,
+                ) {
+                    Some(modal::symbol_search::Action::Select(ticker, source)) => {
+                        let Some(ticker_info) =
+                            tickers_table.tickers_info.get(&ticker).copied().flatten()
+                        else {
+                            self.notifications.push(Toast::warn(t!(
+                                "notify.connect_to_open",
+                                source = source,
+                                ticker = ticker
                             )));
+                            return Task::none();
+                        };
+
+                        self.symbol_search = None;
+                        self.sidebar.tickers_table.push_recent(ticker);
 
-                        // Trigger save to disk by collecting window specs
-                        let mut active_windows = self
+                        let main_window_id = self.main_window.id;
+                        return self
                             .active_dashboard_mut()
-                            .popout
-                            .keys()
-                            .copied()
-                            .collect::<Vec<window::Id>>();
-                        active_windows.push(self.main_window.id);
-                        return window::collect_window_specs(
-                            active_windows,
-                            Message::SaveStateOnly,
-                        )
-                        .chain(fetch_cmd);
+                            .switch_tickers_in_group(main_window_id, ticker_info)
+                            .map(move |msg| Message::Dashboard {
+                                layout_id: None,
+                                event: msg,
+                            });
                     }
-                    modal::mt5_config::Action::TestConnection(config) => {
-                        // Spawn async connection test
-                        return Task::future(async move {
-                            let result = config.test_connection().await;
-                            Message::Mt5ConnectionTestResult(result)
-                        });
+                    Some(modal::symbol_search::Action::OpenGrid(tickers, timeframe)) => {
+                        return self.open_grid(&tickers, timeframe);
                     }
-                    modal::mt5_config::Action::None => {}
+                    None => {}
+                }
+            }
+            Message::SymbolIndexUpdated(source, entries) => {
+                self.symbol_index.replace(source, entries);
+
+                if let Some(search) = &mut self.symbol_search {
+                    let tickers_table = &self.sidebar.tickers_table;
+                    search.refresh(
+                        &self.symbol_index,
+                        &tickers_table.favorited_tickers,
+                        &tickers_table.recent_tickers,
+                    );
                 }
             }
             Message::Mt5ConnectionTestResult(result) => match result {
-                Ok(()) => {
+                Ok(info) => {
+                    let message = t!(
+                        "notify.connection_successful_versions",
+                        proxy = modal::mt5_config::version_label(info.proxy_version.as_deref()),
+                        ea = modal::mt5_config::version_label(info.ea_version.as_deref())
+                    );
+                    self.mt5_modal.set_test_result(Ok(message.clone()));
+                    if let Some(onboarding) = &mut self.onboarding {
+                        onboarding.set_test_result(Ok(message.clone()));
+                    }
                     self.notifications
-                        .push(Toast::new(widget::toast::Notification::Info(
-                            "Connection successful!".to_string(),
-                        )));
+                        .push(Toast::new(widget::toast::Notification::Info(message)));
                 }
                 Err(e) => {
-                    self.notifications
-                        .push(Toast::error(format!("Connection failed: {}", e)));
+                    self.mt5_modal.set_test_result(Err(e.clone()));
+                    if let Some(onboarding) = &mut self.onboarding {
+                        onboarding.set_test_result(Err(e.clone()));
+                    }
+                    self.notify(
+                        "MT5",
+                        Toast::error(t!("notify.connection_failed", error = e)),
+                    );
                 }
             },
+            Message::Mt5TerminalsListed(terminals) => self.mt5_modal.set_terminals(terminals),
             Message::DataFolderRequested => {
                 if let Err(err) = data::open_data_folder() {
-                    self.notifications
-                        .push(Toast::error(format!("Failed to open data folder: {err}")));
+                    self.notifications.push(Toast::error(t!(
+                        "notify.data_folder_open_failed",
+                        error = err
+                    )));
                 }
             }
             Message::ThemeEditor(msg) => {
@@ -554,9 +1510,103 @@ impl Flowsurface {
                     None => {}
                 }
             }
+            Message::ToggleOnboarding => {
+                self.onboarding = match self.onboarding.take() {
+                    Some(_) => None,
+                    None => {
+                        self.sidebar.set_menu(None);
+                        Some(Onboarding::new(
+                            self.sidebar.tickers_table.exchange_filters(),
+                            self.mt5_settings.revisions,
+                        ))
+                    }
+                };
+            }
+            Message::Onboarding(message) => {
+                let Some(onboarding) = &mut self.onboarding else {
+                    return Task::none();
+                };
+
+                match onboarding.update(message) {
+                    Some(modal::onboarding::Action::TestConnection(config)) => {
+                        return Task::future(async move {
+                            let result = config.test_connection().await;
+                            Message::Mt5ConnectionTestResult(result)
+                        });
+                    }
+                    Some(modal::onboarding::Action::RefreshSymbols(config)) => {
+                        return Task::done(Message::RefreshMt5Symbols(config));
+                    }
+                    Some(modal::onboarding::Action::Finish(setup)) => {
+                        self.onboarding = None;
+                        return self.finish_onboarding(setup);
+                    }
+                    Some(modal::onboarding::Action::Close) => {
+                        self.onboarding = None;
+                    }
+                    None => {}
+                }
+            }
+            Message::TickSizeModal(message) => {
+                let Some(modal) = &mut self.tick_size_modal else {
+                    return Task::none();
+                };
+
+                match modal.update(message) {
+                    Some(modal::tick_size::Action::Set {
+                        ticker_info,
+                        tick_override,
+                    }) => {
+                        self.tick_size_modal = None;
+                        return self.set_tick_override(ticker_info, tick_override);
+                    }
+                    Some(modal::tick_size::Action::Close) => {
+                        self.tick_size_modal = None;
+                    }
+                    None => {}
+                }
+            }
+            Message::ConnectionBar(message) => {
+                let main_window = self.main_window.id;
+                let action = self.connection_bar.update(message);
+                let dashboard = self.active_dashboard_mut();
+
+                match action {
+                    Some(dashboard::connection_bar::Action::TogglePause(stream)) => {
+                        dashboard.toggle_stream_pause(&stream, main_window);
+                    }
+                    Some(dashboard::connection_bar::Action::Resubscribe(stream)) => {
+                        dashboard.resubscribe(&stream);
+                    }
+                    Some(dashboard::connection_bar::Action::Focus(stream)) => {
+                        return dashboard
+                            .focus_stream(&stream, main_window)
+                            .map(move |msg| Message::Dashboard {
+                                layout_id: None,
+                                event: msg,
+                            });
+                    }
+                    None => {}
+                }
+            }
             Message::Sidebar(message) => {
+                let was_open = self.sidebar.is_menu_active(sidebar::Menu::Notifications);
+                let updated_exchange = match &message {
+                    dashboard::sidebar::Message::TickersTable(
+                        dashboard::tickers_table::Message::UpdateTickersInfo(exchange, _),
+                    ) => Some(*exchange),
+                    _ => None,
+                };
                 let (task, action) = self.sidebar.update(message);
 
+                if let Some(exchange) = updated_exchange {
+                    return Task::batch([task.map(Message::Sidebar), self.reindex(exchange)]);
+                }
+
+                if !was_open && self.sidebar.is_menu_active(sidebar::Menu::Notifications) {
+                    self.notification_center.open(&mut self.notification_log);
+                }
+
                 match action {
                     Some(dashboard::sidebar::Action::TickerSelected(ticker_info, content)) => {
                         let main_window_id = self.main_window.id;
@@ -580,7 +1630,10 @@ impl Flowsurface {
                         });
                     }
                     Some(dashboard::sidebar::Action::ErrorOccurred(err)) => {
-                        self.notifications.push(Toast::error(err.to_string()));
+                        self.notify(t!("source.tickers"), Toast::error(err.to_string()));
+                    }
+                    Some(dashboard::sidebar::Action::OverrideTickSize(ticker_info)) => {
+                        self.tick_size_modal = Some(TickSizeModal::new(ticker_info));
                     }
                     None => {}
                 }
@@ -597,36 +1650,123 @@ impl Flowsurface {
 
                 return window::collect_window_specs(active_windows, Message::RestartRequested);
             }
+            Message::ApplyMt5Config(config, revisions) => {
+                self.confirm_dialog = None;
+                return self.apply_mt5_config(config, revisions);
+            }
             Message::FetchMt5Symbols(config) => {
+                let name = mt5_connection_name(&config);
+
+                let cached = self
+                    .mt5_symbol_cache
+                    .get(&name, data::symbol_cache::now_ms())
+                    .map(|cached| (cached.tickers.clone(), cached.is_fresh));
+
+                if let Some((tickers, is_fresh)) = cached {
+                    self.sidebar.tickers_table.update(
+                        dashboard::tickers_table::Message::UpdateTickersInfo(
+                            exchange::adapter::Exchange::MetaTrader5,
+                            tickers.clone(),
+                        ),
+                    );
+                    let remap = Task::batch([
+                        self.remap_mt5_panes(&tickers),
+                        self.reindex(exchange::adapter::Exchange::MetaTrader5),
+                    ]);
+
+                    if is_fresh {
+                        log::info!("Using {} cached MT5 symbols for {name}", tickers.len());
+                        return remap;
+                    }
+
+                    log::info!("Cached MT5 symbols for {name} are stale, refreshing");
+                    return Task::batch([remap, Task::done(Message::RefreshMt5Symbols(config))]);
+                }
+
+                return Task::done(Message::RefreshMt5Symbols(config));
+            }
+            Message::RefreshMt5Symbols(config) => {
+                // The running refresh is reported once it lands, another waiter would only repeat it
+                if exchange::adapter::metatrader5::refresh_in_progress(&config) {
+                    log::debug!("MT5 symbol refresh already in progress, not asking again");
+                    return Task::none();
+                }
+                self.mt5_symbols_checked = std::time::Instant::now();
+                let name = mt5_connection_name(&config);
+
                 return Task::perform(
                     async move { exchange::adapter::metatrader5::fetch_ticksize(&config).await },
-                    |result| Message::Mt5SymbolsReceived(result.map_err(|e| e.to_string())),
+                    move |result| {
+                        Message::Mt5SymbolsReceived(name.clone(), result.map_err(|e| e.to_string()))
+                    },
                 );
             }
-            Message::Mt5SymbolsReceived(result) => match result {
-                Ok(info) => {
+            Message::Mt5SymbolsReceived(name, result) => match result {
+                Ok(exchange::adapter::metatrader5::SymbolFetchOutcome {
+                    infos: info,
+                    failures,
+                }) => {
+                    let now_ms = data::symbol_cache::now_ms();
+                    let changes = self
+                        .mt5_symbol_cache
+                        .revalidate(&name, info.clone(), now_ms);
+                    self.mt5_symbol_cache.save();
+                    exchange::adapter::metatrader5::retired::set_all(
+                        self.mt5_symbol_cache.retired(),
+                    );
+
+                    // Retired symbols stay listed, flagged
+                    let listed = self
+                        .mt5_symbol_cache
+                        .get(&name, now_ms)
+                        .map_or_else(|| info.clone(), |cached| cached.tickers.clone());
                     self.sidebar.tickers_table.update(
                         dashboard::tickers_table::Message::UpdateTickersInfo(
                             exchange::adapter::Exchange::MetaTrader5,
-                            info.clone(),
+                            listed,
                         ),
                     );
+                    self.report_retired_symbols(&name, &changes);
 
                     let count = info.len();
                     log::info!("Fetched {} MT5 symbols", count);
                     self.notifications
-                        .push(Toast::new(widget::toast::Notification::Info(format!(
-                            "Connected! Found {} symbols",
-                            count
+                        .push(Toast::new(widget::toast::Notification::Info(t!(
+                            "notify.mt5_symbols_found",
+                            count = count
                         ))));
 
-                    // Auto-connect to market data stream for all symbols if needed,
-                    // or just let the user search. For now, we update the table.
+                    if !failures.is_empty() {
+                        let summary =
+                            t!("notify.mt5_symbols_skipped", count = failures.len()).to_string();
+                        let details = failures
+                            .iter()
+                            .map(|(symbol, error)| format!("{symbol}: {error}"))
+                            .collect::<Vec<_>>()
+                            .join("\n");
+
+                        self.record_notification(
+                            name.clone(),
+                            Severity::Warning,
+                            format!("{summary}\n{details}"),
+                        );
+                        self.notifications.push(Toast::warn(t!(
+                            "notify.mt5_symbols_skipped_toast",
+                            count = failures.len()
+                        )));
+                    }
+
+                    return Task::batch([
+                        self.remap_mt5_panes(&info),
+                        self.reindex(exchange::adapter::Exchange::MetaTrader5),
+                    ]);
                 }
                 Err(e) => {
                     log::error!("Failed to fetch MT5 symbols: {}", e);
-                    self.notifications
-                        .push(Toast::error(format!("MT5 Symbol Fetch Failed: {}", e)));
+                    self.notify(
+                        name,
+                        Toast::error(t!("notify.mt5_symbols_failed", error = e)),
+                    );
                 }
             },
         }
@@ -642,15 +1782,19 @@ impl Flowsurface {
         let content = if id == self.main_window.id {
             let sidebar_view = self
                 .sidebar
-                .view(self.audio_stream.volume())
+                .view(self.audio_stream.volume(), self.notification_log.unread())
                 .map(Message::Sidebar);
 
-            let dashboard_view = dashboard
-                .view(&self.main_window, tickers_table, self.timezone)
-                .map(move |msg| Message::Dashboard {
-                    layout_id: None,
-                    event: msg,
-                });
+            let dashboard_view = if self.safe_mode {
+                safe_mode_view()
+            } else {
+                dashboard
+                    .view(&self.main_window, tickers_table, self.timezone)
+                    .map(move |msg| Message::Dashboard {
+                        layout_id: None,
+                        event: msg,
+                    })
+            };
 
             let header_title = {
                 #[cfg(target_os = "macos")]
@@ -674,6 +1818,16 @@ impl Flowsurface {
                 }
             };
 
+            let connection_bar = self.sidebar.is_connection_bar_shown().then(|| {
+                self.connection_bar
+                    .view(
+                        dashboard.connection_entries(self.main_window.id),
+                        self.mt5_settings.active_connection.as_deref(),
+                        std::time::Instant::now(),
+                    )
+                    .map(Message::ConnectionBar)
+            });
+
             let base = column![
                 header_title,
                 match sidebar_pos {
@@ -681,13 +1835,65 @@ impl Flowsurface {
                     sidebar::Position::Right => row![dashboard_view, sidebar_view],
                 }
                 .spacing(4)
-                .padding(8),
-            ];
+                .padding(if connection_bar.is_some() {
+                    padding::all(8).bottom(0)
+                } else {
+                    padding::all(8)
+                }),
+            ]
+            .push(connection_bar);
 
-            if let Some(menu) = self.sidebar.active_menu() {
+            let base = if let Some(menu) = self.sidebar.active_menu() {
                 self.view_with_modal(base.into(), dashboard, menu)
             } else {
                 base.into()
+            };
+
+            #[cfg(feature = "fault-injection")]
+            let base = if self.fault_panel {
+                dashboard_modal(
+                    base,
+                    modal::fault_injection::view().map(Message::FaultInjection),
+                    Message::ToggleFaultPanel,
+                    padding::top(80),
+                    Alignment::Start,
+                    Alignment::Center,
+                )
+            } else {
+                base
+            };
+
+            let base = if let Some(onboarding) = &self.onboarding {
+                main_dialog_modal(
+                    base,
+                    onboarding.view().map(Message::Onboarding),
+                    Message::ToggleOnboarding,
+                )
+            } else {
+                base
+            };
+
+            let base = if let Some(modal) = &self.tick_size_modal {
+                main_dialog_modal(
+                    base,
+                    modal.view().map(Message::TickSizeModal),
+                    Message::TickSizeModal(modal::tick_size::Message::Close),
+                )
+            } else {
+                base
+            };
+
+            if let Some(search) = &self.symbol_search {
+                dashboard_modal(
+                    base,
+                    search.view(&self.symbol_index).map(Message::SymbolSearch),
+                    Message::ToggleSymbolSearch,
+                    padding::top(80),
+                    Alignment::Start,
+                    Alignment::Center,
+                )
+            } else {
+                base
             }
         } else {
             container(
@@ -734,52 +1940,343 @@ impl Flowsurface {
         let window_events = window::events().map(Message::WindowEvent);
         let sidebar = self.sidebar.subscription().map(Message::Sidebar);
 
-        let exchange_streams = self
-            .active_dashboard()
-            .market_subscriptions()
-            .map(Message::MarketWsEvent);
+        let exchange_streams = if self.safe_mode {
+            Subscription::none()
+        } else {
+            self.active_dashboard()
+                .market_subscriptions()
+                .map(Message::MarketWsEvent)
+        };
 
         let tick = iced::time::every(std::time::Duration::from_millis(100)).map(Message::Tick);
 
         let hotkeys = keyboard::listen().filter_map(|event| {
-            let keyboard::Event::KeyPressed { key, .. } = event else {
+            let keyboard::Event::KeyPressed { key, modifiers, .. } = event else {
                 return None;
             };
-            match key {
+            match key.as_ref() {
                 keyboard::Key::Named(keyboard::key::Named::Escape) => Some(Message::GoBack),
+                keyboard::Key::Character("k") if modifiers.command() => {
+                    Some(Message::ToggleSymbolSearch)
+                }
+                keyboard::Key::Character("d" | "D") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::DumpFocusedBook)
+                }
+                keyboard::Key::Character("s" | "S") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::ExportLayoutImage)
+                }
+                #[cfg(feature = "fault-injection")]
+                keyboard::Key::Character("f" | "F") if modifiers.command() && modifiers.shift() => {
+                    Some(Message::ToggleFaultPanel)
+                }
+                keyboard::Key::Named(keyboard::key::Named::ArrowUp) => Some(Message::SymbolSearch(
+                    modal::symbol_search::Message::Navigate(-1),
+                )),
+                keyboard::Key::Named(keyboard::key::Named::ArrowDown) => Some(
+                    Message::SymbolSearch(modal::symbol_search::Message::Navigate(1)),
+                ),
                 _ => None,
             }
         });
 
-        Subscription::batch(vec![
-            exchange_streams,
-            sidebar,
-            window_events,
-            tick,
-            hotkeys,
-        ])
+        Subscription::batch(vec![
+            exchange_streams,
+            sidebar,
+            window_events,
+            tick,
+            hotkeys,
+        ])
+    }
+
+    fn active_dashboard(&self) -> &Dashboard {
+        let active_layout = self
+            .layout_manager
+            .active_layout_id()
+            .expect("No active layout");
+        self.layout_manager
+            .get(active_layout.unique)
+            .map(|layout| &layout.dashboard)
+            .expect("No active dashboard")
+    }
+
+    fn active_dashboard_mut(&mut self) -> &mut Dashboard {
+        let active_layout = self
+            .layout_manager
+            .active_layout_id()
+            .expect("No active layout");
+        self.layout_manager
+            .get_mut(active_layout.unique)
+            .map(|layout| &mut layout.dashboard)
+            .expect("No active dashboard")
+    }
+
+    /// Starts the price endpoint if it's enabled, stopping one already running
+    fn start_price_endpoint(&mut self) -> Task<Message> {
+        self.price_endpoint = None;
+
+        let config = exchange::ipc::config();
+        if !config.enabled {
+            return Task::none();
+        }
+
+        let feed = std::sync::Arc::clone(&self.price_feed);
+        let (task, handle) = Task::perform(
+            async move {
+                let listener = exchange::ipc::bind(config.port)
+                    .await
+                    .map_err(|e| format!("port {}: {e}", config.port))?;
+                log::info!("Price endpoint listening on 127.0.0.1:{}", config.port);
+
+                exchange::ipc::serve(listener, config.token, feed).await;
+                Ok(())
+            },
+            Message::PriceEndpointStopped,
+        )
+        .abortable();
+
+        self.price_endpoint = Some(handle.abort_on_drop());
+        task
+    }
+
+    /// Hands the latest prices of the active layout's streams to the price endpoint
+    fn publish_prices(&self) {
+        let mut quotes = self
+            .active_dashboard()
+            .streams
+            .tickers()
+            .into_iter()
+            .map(|info| {
+                let ticker = info.ticker;
+                let mut quote = exchange::ipc::Quote::new(&ticker);
+
+                if let Some((time, depth)) = self.latest_depth.get(&ticker) {
+                    quote.bid = depth.bids.last_key_value().map(|(price, _)| price.to_f32());
+                    quote.ask = depth
+                        .asks
+                        .first_key_value()
+                        .map(|(price, _)| price.to_f32());
+                    quote.time = *time;
+                }
+                if let Some((time, price)) = self.last_trades.get(&ticker) {
+                    quote.last_price = Some(price.to_f32());
+                    quote.time = quote.time.max(*time);
+                }
+                if let Some(stats) = self.sidebar.tickers_table.stats(&ticker) {
+                    quote.last_price = quote.last_price.or(Some(stats.mark_price));
+                    quote.change_pct = Some(stats.daily_price_chg);
+                    quote.daily_volume = Some(stats.daily_volume);
+                }
+
+                quote
+            })
+            .collect::<Vec<_>>();
+        quotes.sort_by(|a, b| a.ticker.cmp(&b.ticker));
+
+        self.price_feed.publish(exchange::ipc::Snapshot { quotes });
+    }
+
+    /// Writes the last book `ticker`'s depth stream delivered to the dumps folder, off the UI
+    /// thread
+    fn dump_book(&mut self, ticker: exchange::Ticker) -> Task<Message> {
+        let Some((depth_time, depth)) = self.latest_depth.get(&ticker).cloned() else {
+            self.notifications
+                .push(Toast::warn(t!("notify.no_book_yet", ticker = ticker)));
+            return Task::none();
+        };
+
+        Task::perform(
+            async move {
+                data::book_dump::BookDump::new(
+                    ticker,
+                    depth_time,
+                    &depth,
+                    data::symbol_cache::now_ms(),
+                )
+                .write()
+                .map_err(|e| format!("Failed to write order book dump: {e}"))
+            },
+            Message::BookDumped,
+        )
+    }
+
+    /// Crops, watermarks and writes a captured image off the UI thread. The watermark names what
+    /// was exported and when, in the user's timezone.
+    fn export_image(&self, capture: window::Capture) -> Task<Message> {
+        let theme = iced_core::Theme::from(self.theme.clone());
+        let palette = theme.extended_palette();
+        let [r, g, b, _] = palette.background.base.color.into_rgba8();
+        let ink = palette.background.base.text.scale_alpha(0.6).into_rgba8();
+
+        let taken_at = data::symbol_cache::now_ms();
+        let offset = self.timezone.utc_offset_secs(taken_at as i64);
+        let stamp = chrono::DateTime::from_timestamp_millis(taken_at as i64)
+            .zip(chrono::FixedOffset::east_opt(offset))
+            .map(|(time, offset)| {
+                time.with_timezone(&offset)
+                    .format("%Y-%m-%d %H:%M:%S %:z")
+                    .to_string()
+            })
+            .unwrap_or_default();
+
+        Task::perform(
+            async move {
+                let mut snapshot = capture.cropped();
+                snapshot.flatten([r, g, b]);
+
+                let watermark = format!("{} · {stamp}", capture.label);
+                if let Err(e) = snapshot.watermark(
+                    style::AZERET_MONO_BYTES,
+                    &watermark,
+                    ink,
+                    capture.screenshot.scale_factor,
+                ) {
+                    log::warn!("Exporting without a watermark: {e}");
+                }
+
+                snapshot
+                    .write(&capture.label, taken_at)
+                    .map_err(|e| format!("Failed to write image: {e}"))
+            },
+            Message::ImageExported,
+        )
+    }
+
+    /// Rebuilds the symbol search entries of `exchange` from the tickers table. MT5 symbols are
+    /// indexed under the active connection, the only one the table holds.
+    fn reindex(&self, exchange: exchange::adapter::Exchange) -> Task<Message> {
+        let source = match &self.mt5_settings.active_connection {
+            Some(name) if exchange == exchange::adapter::Exchange::MetaTrader5 => {
+                data::symbol_search::Source::Mt5Connection(name.clone())
+            }
+            _ => data::symbol_search::Source::Exchange(exchange),
+        };
+
+        let tickers = self
+            .sidebar
+            .tickers_table
+            .tickers_info
+            .keys()
+            .filter(|ticker| ticker.exchange == exchange)
+            .copied()
+            .collect();
+
+        reindex_symbols(source, tickers)
+    }
+
+    /// Saves the tick size override of `ticker_info`'s ticker and rebuckets what shows it
+    fn set_tick_override(
+        &mut self,
+        ticker_info: exchange::TickerInfo,
+        tick_override: Option<exchange::adapter::metatrader5::tick_override::TickOverride>,
+    ) -> Task<Message> {
+        let ticker = ticker_info.ticker;
+        match tick_override {
+            Some(tick) => {
+                self.mt5_settings.tick_overrides.insert(ticker, tick);
+            }
+            None => {
+                self.mt5_settings.tick_overrides.remove(&ticker);
+            }
+        }
+        exchange::adapter::metatrader5::tick_override::set_all(&self.mt5_settings.tick_overrides);
+
+        self.sidebar
+            .tickers_table
+            .tickers_info
+            .insert(ticker, Some(ticker_info));
+        self.mt5_symbol_cache
+            .set_tick_size(&ticker, ticker_info.min_ticksize.as_f32());
+        self.mt5_symbol_cache.save();
+
+        let main_window = self.main_window.id;
+        self.active_dashboard_mut()
+            .retick(main_window, ticker_info)
+            .map(move |msg| Message::Dashboard {
+                layout_id: None,
+                event: msg,
+            })
+    }
+
+    /// Rebinds panes of the active layout to this connection's symbol names, see
+    /// [`Dashboard::remap_mt5_tickers`], and tells the user what changed
+    /// Checks the active MT5 connection's symbols against the broker once `after` passed since
+    /// the last check
+    fn recheck_mt5_symbols(&self, after: std::time::Duration) -> Task<Message> {
+        if self.mt5_symbols_checked.elapsed() < after {
+            return Task::none();
+        }
+
+        match exchange::adapter::metatrader5::get_global_config() {
+            Some(config) => Task::done(Message::RefreshMt5Symbols(config)),
+            None => Task::none(),
+        }
     }
 
-    fn active_dashboard(&self) -> &Dashboard {
-        let active_layout = self
-            .layout_manager
-            .active_layout_id()
-            .expect("No active layout");
-        self.layout_manager
-            .get(active_layout.unique)
-            .map(|layout| &layout.dashboard)
-            .expect("No active dashboard")
+    fn report_retired_symbols(&mut self, name: &str, changes: &data::symbol_cache::Revalidation) {
+        let list = |tickers: &[exchange::Ticker]| {
+            tickers
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        if !changes.retired.is_empty() {
+            log::info!("Retired MT5 symbols of {name}: {}", list(&changes.retired));
+            self.record_notification(
+                name,
+                Severity::Warning,
+                t!(
+                    "notify.mt5_symbols_retired",
+                    symbols = list(&changes.retired)
+                )
+                .to_string(),
+            );
+        }
+        if !changes.restored.is_empty() {
+            log::info!(
+                "Restored MT5 symbols of {name}: {}",
+                list(&changes.restored)
+            );
+            self.record_notification(
+                name,
+                Severity::Info,
+                t!(
+                    "notify.mt5_symbols_restored",
+                    symbols = list(&changes.restored)
+                )
+                .to_string(),
+            );
+        }
     }
 
-    fn active_dashboard_mut(&mut self) -> &mut Dashboard {
-        let active_layout = self
-            .layout_manager
-            .active_layout_id()
-            .expect("No active layout");
-        self.layout_manager
-            .get_mut(active_layout.unique)
-            .map(|layout| &mut layout.dashboard)
-            .expect("No active dashboard")
+    fn remap_mt5_panes(
+        &mut self,
+        symbols: &HashMap<exchange::Ticker, Option<exchange::TickerInfo>>,
+    ) -> Task<Message> {
+        let main_window = self.main_window.id;
+        let (task, remapped) = self
+            .active_dashboard_mut()
+            .remap_mt5_tickers(main_window, symbols);
+
+        if !remapped.is_empty() {
+            let pairs = remapped
+                .iter()
+                .map(|(from, to)| format!("{from} → {to}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            log::info!("Remapped MT5 symbols: {pairs}");
+            self.notifications
+                .push(Toast::new(widget::toast::Notification::Info(t!(
+                    "notify.mt5_symbols_remapped",
+                    pairs = pairs
+                ))));
+        }
+
+        task.map(move |msg| Message::Dashboard {
+            layout_id: None,
+            event: msg,
+        })
     }
 
     fn load_layout(&mut self, layout_uid: uuid::Uuid, main_window: window::Id) -> Task<Message> {
@@ -826,7 +2323,7 @@ impl Flowsurface {
                         })
                     };
 
-                    let toggle_theme_editor = button(text("Theme editor")).on_press(
+                    let toggle_theme_editor = button(text(t!("settings.theme_editor"))).on_press(
                         Message::Sidebar(dashboard::sidebar::Message::ToggleSidebarMenu(Some(
                             sidebar::Menu::ThemeEditor,
                         ))),
@@ -841,11 +2338,11 @@ impl Flowsurface {
                     let size_in_quote_currency_checkbox = {
                         let is_active = match self.volume_size_unit {
                             exchange::SizeUnit::Quote => true,
-                            exchange::SizeUnit::Base => false,
+                            exchange::SizeUnit::Base | exchange::SizeUnit::Contracts => false,
                         };
 
                         let checkbox = iced::widget::checkbox(is_active)
-                            .label("Size in quote currency")
+                            .label(t!("settings.size_in_quote"))
                             .on_toggle(|checked| {
                                 let on_dialog_confirm = Message::ApplyVolumeSizeUnit(if checked {
                                     exchange::SizeUnit::Quote
@@ -854,24 +2351,27 @@ impl Flowsurface {
                                 });
 
                                 let confirm_dialog = screen::ConfirmDialog::new(
-                                    "Changing size display currency requires application restart"
-                                        .to_string(),
+                                    t!("settings.size_in_quote_restart").to_string(),
                                     Box::new(on_dialog_confirm.clone()),
                                 )
-                                .with_confirm_btn_text("Restart now".to_string());
+                                .with_confirm_btn_text(t!("settings.restart_now").to_string());
 
                                 Message::ToggleDialogModal(Some(confirm_dialog))
                             });
 
                         tooltip(
                             checkbox,
-                            Some(
-                                "Display sizes/volumes in quote currency (USD)\nHas no effect on inverse perps or open interest",
-                            ),
+                            Some(t!("settings.size_in_quote_tooltip")),
                             TooltipPosition::Top,
                         )
                     };
 
+                    let locale_picklist = pick_list(
+                        data::Locale::ALL,
+                        Some(data::i18n::locale()),
+                        Message::SetLocale,
+                    );
+
                     let sidebar_pos = pick_list(
                         [sidebar::Position::Left, sidebar::Position::Right],
                         Some(sidebar_pos),
@@ -914,12 +2414,11 @@ impl Flowsurface {
                         let is_active = exchange::fetcher::is_trade_fetch_enabled();
 
                         let checkbox = iced::widget::checkbox(is_active)
-                            .label("Fetch trades (Binance)")
+                            .label(t!("settings.fetch_trades"))
                             .on_toggle(|checked| {
                                 if checked {
                                     let confirm_dialog = screen::ConfirmDialog::new(
-                                        "This might be unreliable and take some time to complete. Proceed?"
-                                            .to_string(),
+                                        t!("settings.fetch_trades_confirm").to_string(),
                                         Box::new(Message::ToggleTradeFetch(true)),
                                     );
                                     Message::ToggleDialogModal(Some(confirm_dialog))
@@ -930,32 +2429,214 @@ impl Flowsurface {
 
                         tooltip(
                             checkbox,
-                            Some("Try to fetch trades for footprint charts"),
+                            Some(t!("settings.fetch_trades_tooltip")),
                             TooltipPosition::Top,
                         )
                     };
 
+                    let price_endpoint = {
+                        let config = exchange::ipc::config();
+
+                        let checkbox = iced::widget::checkbox(config.enabled)
+                            .label(t!("settings.price_endpoint", port = config.port))
+                            .on_toggle(Message::TogglePriceEndpoint);
+                        let checkbox = tooltip(
+                            checkbox,
+                            Some(t!("settings.price_endpoint_tooltip")),
+                            TooltipPosition::Top,
+                        );
+
+                        let copy_token = button(text(t!("settings.copy_token")).size(12))
+                            .on_press_maybe(
+                                config.enabled.then_some(Message::CopyPriceEndpointToken),
+                            );
+
+                        row![checkbox, copy_token]
+                            .spacing(8)
+                            .align_y(Alignment::Center)
+                    };
+
+                    let retention = {
+                        let current = data::retention::config();
+
+                        let limit = |label, picker: Element<'static, Message>| {
+                            row![
+                                text(label).size(12),
+                                iced::widget::space::horizontal(),
+                                picker
+                            ]
+                            .spacing(8)
+                            .align_y(Alignment::Center)
+                        };
+
+                        let title = tooltip(
+                            text(t!("settings.retention")).size(14),
+                            Some(t!("settings.retention_tooltip")),
+                            TooltipPosition::Top,
+                        );
+
+                        column![
+                            title,
+                            limit(
+                                t!("settings.retention_intraday"),
+                                pick_list(
+                                    data::retention::BAR_CHOICES,
+                                    Some(current.intraday_bars),
+                                    move |intraday_bars| {
+                                        Message::SetRetention(data::retention::Retention {
+                                            intraday_bars,
+                                            ..current
+                                        })
+                                    },
+                                )
+                                .into(),
+                            ),
+                            limit(
+                                t!("settings.retention_bars"),
+                                pick_list(
+                                    data::retention::BAR_CHOICES,
+                                    Some(current.bars),
+                                    move |bars| {
+                                        Message::SetRetention(data::retention::Retention {
+                                            bars,
+                                            ..current
+                                        })
+                                    },
+                                )
+                                .into(),
+                            ),
+                            limit(
+                                t!("settings.retention_trades"),
+                                pick_list(
+                                    data::retention::WINDOW_CHOICES,
+                                    Some(current.trades),
+                                    move |trades| {
+                                        Message::SetRetention(data::retention::Retention {
+                                            trades,
+                                            ..current
+                                        })
+                                    },
+                                )
+                                .into(),
+                            ),
+                            limit(
+                                t!("settings.retention_heatmap"),
+                                pick_list(
+                                    data::retention::WINDOW_CHOICES,
+                                    Some(current.heatmap),
+                                    move |heatmap| {
+                                        Message::SetRetention(data::retention::Retention {
+                                            heatmap,
+                                            ..current
+                                        })
+                                    },
+                                )
+                                .into(),
+                            ),
+                        ]
+                        .spacing(8)
+                    };
+
+                    let limits = {
+                        let current = exchange::limits::config();
+
+                        let limit = |label, picker: Element<'static, Message>| {
+                            row![
+                                text(label).size(12),
+                                iced::widget::space::horizontal(),
+                                picker
+                            ]
+                            .spacing(8)
+                            .align_y(Alignment::Center)
+                        };
+
+                        let title = tooltip(
+                            text(t!("settings.limits")).size(14),
+                            Some(t!("settings.limits_tooltip")),
+                            TooltipPosition::Top,
+                        );
+
+                        column![
+                            title,
+                            limit(
+                                t!("settings.limits_mt5"),
+                                pick_list(
+                                    exchange::limits::STREAM_CHOICES,
+                                    Some(current.mt5_streams),
+                                    move |mt5_streams| {
+                                        Message::SetLimits(exchange::limits::Limits {
+                                            mt5_streams,
+                                            ..current
+                                        })
+                                    },
+                                )
+                                .into(),
+                            ),
+                            limit(
+                                t!("settings.limits_exchanges"),
+                                pick_list(
+                                    exchange::limits::STREAM_CHOICES,
+                                    Some(current.exchange_streams),
+                                    move |exchange_streams| {
+                                        Message::SetLimits(exchange::limits::Limits {
+                                            exchange_streams,
+                                            ..current
+                                        })
+                                    },
+                                )
+                                .into(),
+                            ),
+                            limit(
+                                t!("settings.limits_backfills"),
+                                pick_list(
+                                    exchange::limits::BACKFILL_CHOICES,
+                                    Some(current.backfills),
+                                    move |backfills| {
+                                        Message::SetLimits(exchange::limits::Limits {
+                                            backfills,
+                                            ..current
+                                        })
+                                    },
+                                )
+                                .into(),
+                            ),
+                        ]
+                        .spacing(8)
+                    };
+
                     let open_data_folder = {
-                        let button =
-                            button(text("Open data folder")).on_press(Message::DataFolderRequested);
+                        let button = button(text(t!("settings.open_data_folder")))
+                            .on_press(Message::DataFolderRequested);
 
                         tooltip(
                             button,
-                            Some("Open the folder where the data & config is stored"),
+                            Some(t!("settings.open_data_folder_tooltip")),
                             TooltipPosition::Top,
                         )
                     };
 
+                    let open_logs = button(text(t!("settings.logs"))).on_press(Message::Sidebar(
+                        dashboard::sidebar::Message::ToggleSidebarMenu(Some(sidebar::Menu::Logs)),
+                    ));
+
+                    let open_onboarding = button(text(t!("settings.setup_wizard")))
+                        .on_press(Message::ToggleOnboarding);
+
                     let column_content = split_column![
-                        column![open_data_folder,].spacing(8),
-                        column![text("Sidebar position").size(14), sidebar_pos,].spacing(12),
-                        column![text("Time zone").size(14), timezone_picklist,].spacing(12),
-                        column![text("Market data").size(14), size_in_quote_currency_checkbox,].spacing(12),
-                        column![text("Theme").size(14), theme_picklist,].spacing(12),
-                        column![text("Interface scale").size(14), scale_factor,].spacing(12),
+                        column![open_data_folder, open_logs, open_onboarding].spacing(8),
+                        self.data_location.view().map(Message::DataLocation),
+                        column![text(t!("settings.language")).size(14), locale_picklist,].spacing(12),
+                        column![text(t!("settings.sidebar_position")).size(14), sidebar_pos,].spacing(12),
+                        column![text(t!("settings.time_zone")).size(14), timezone_picklist,].spacing(12),
+                        column![text(t!("settings.market_data")).size(14), size_in_quote_currency_checkbox,].spacing(12),
+                        column![text(t!("settings.theme")).size(14), theme_picklist,].spacing(12),
+                        column![text(t!("settings.interface_scale")).size(14), scale_factor,].spacing(12),
+                        retention,
+                        limits,
                         column![
-                            text("Experimental").size(14),
-                            column![trade_fetch_checkbox, toggle_theme_editor,].spacing(8),
+                            text(t!("settings.experimental")).size(14),
+                            column![trade_fetch_checkbox, price_endpoint, toggle_theme_editor,]
+                                .spacing(8),
                         ]
                         .spacing(12),
                         ; spacing = 16, align_x = Alignment::Start
@@ -1008,12 +2689,12 @@ impl Flowsurface {
                 let manage_pane = if let Some((window_id, pane_id)) = dashboard.focus {
                     let selected_pane_str =
                         if let Some(state) = dashboard.get_pane(main_window, window_id, pane_id) {
-                            let link_group_name: String =
-                                state.link_group.as_ref().map_or_else(String::new, |g| {
-                                    " - Group ".to_string() + &g.to_string()
-                                });
-
-                            state.content.to_string() + &link_group_name
+                            match &state.link_group {
+                                Some(group) => {
+                                    t!("layout.link_group", pane = state.content, group = group)
+                                }
+                                None => state.content.to_string(),
+                            }
                         } else {
                             "".to_string()
                         };
@@ -1021,7 +2702,7 @@ impl Flowsurface {
                     let is_main_window = window_id == main_window;
 
                     let reset_pane_button = {
-                        let btn = button(text("Reset").align_x(Alignment::Center))
+                        let btn = button(text(t!("common.reset")).align_x(Alignment::Center))
                             .width(iced::Length::Fill);
                         if is_main_window {
                             let dashboard_msg = Message::Dashboard {
@@ -1038,7 +2719,7 @@ impl Flowsurface {
                         }
                     };
                     let split_pane_button = {
-                        let btn = button(text("Split").align_x(Alignment::Center))
+                        let btn = button(text(t!("common.split")).align_x(Alignment::Center))
                             .width(iced::Length::Fill);
                         if is_main_window {
                             let dashboard_msg = Message::Dashboard {
@@ -1063,7 +2744,7 @@ impl Flowsurface {
                             tooltip(
                                 reset_pane_button,
                                 if is_main_window {
-                                    Some("Reset selected pane")
+                                    Some(t!("layout.reset_pane"))
                                 } else {
                                     None
                                 },
@@ -1072,7 +2753,7 @@ impl Flowsurface {
                             tooltip(
                                 split_pane_button,
                                 if is_main_window {
-                                    Some("Split selected pane horizontally")
+                                    Some(t!("layout.split_pane"))
                                 } else {
                                     None
                                 },
@@ -1083,7 +2764,7 @@ impl Flowsurface {
                     ]
                     .spacing(8)
                 } else {
-                    column![text("No pane selected"),].spacing(8)
+                    column![text(t!("layout.no_pane_selected")),].spacing(8)
                 };
 
                 let manage_layout_modal = {
@@ -1164,6 +2845,55 @@ impl Flowsurface {
                     align_x,
                 )
             }
+            sidebar::Menu::CustomWsConfig => {
+                let (align_x, padding) = match sidebar_pos {
+                    sidebar::Position::Left => (Alignment::Start, padding::left(44).top(60)),
+                    sidebar::Position::Right => (Alignment::End, padding::right(44).top(60)),
+                };
+
+                dashboard_modal(
+                    base,
+                    self.custom_ws_modal.view().map(Message::CustomWsConfig),
+                    Message::Sidebar(dashboard::sidebar::Message::ToggleSidebarMenu(None)),
+                    padding,
+                    Alignment::Start,
+                    align_x,
+                )
+            }
+            sidebar::Menu::Logs => {
+                let (align_x, padding) = match sidebar_pos {
+                    sidebar::Position::Left => (Alignment::Start, padding::left(44).bottom(4)),
+                    sidebar::Position::Right => (Alignment::End, padding::right(44).bottom(4)),
+                };
+
+                dashboard_modal(
+                    base,
+                    self.log_viewer
+                        .view(&self.log_buffer)
+                        .map(Message::LogViewer),
+                    Message::Sidebar(dashboard::sidebar::Message::ToggleSidebarMenu(None)),
+                    padding,
+                    Alignment::End,
+                    align_x,
+                )
+            }
+            sidebar::Menu::Notifications => {
+                let (align_x, padding) = match sidebar_pos {
+                    sidebar::Position::Left => (Alignment::Start, padding::left(44).bottom(40)),
+                    sidebar::Position::Right => (Alignment::End, padding::right(44).bottom(40)),
+                };
+
+                dashboard_modal(
+                    base,
+                    self.notification_center
+                        .view(&self.notification_log)
+                        .map(Message::Notifications),
+                    Message::Sidebar(dashboard::sidebar::Message::ToggleSidebarMenu(None)),
+                    padding,
+                    Alignment::End,
+                    align_x,
+                )
+            }
         }
     }
 
@@ -1177,6 +2907,33 @@ impl Flowsurface {
                 }
             });
 
+        let main_window_spec = windows
+            .iter()
+            .find(|(id, _)| **id == self.main_window.id)
+            .map(|(_, spec)| *spec);
+        let state = self.collect_state(main_window_spec);
+
+        // Written in the background, only the sections that changed
+        match data::state_store::split(&state) {
+            Ok(sections) => {
+                self.state_writer
+                    .save(data::data_dir::state_file(), sections);
+            }
+            Err(e) => {
+                log::error!("Failed to serialize layout: {}", e);
+                self.record_notification(
+                    t!("source.saved_state"),
+                    Severity::Error,
+                    t!("notify.state_serialize_failed", error = e),
+                );
+            }
+        }
+
+        self.notification_log.save();
+    }
+
+    /// The state as it is saved, with the main window at `main_window_spec`
+    fn collect_state(&mut self, main_window_spec: Option<WindowSpec>) -> data::State {
         self.sidebar.sync_tickers_table_settings();
 
         let mut ser_layouts = vec![];
@@ -1199,14 +2956,9 @@ impl Flowsurface {
                 .clone(),
         };
 
-        let main_window_spec = windows
-            .iter()
-            .find(|(id, _)| **id == self.main_window.id)
-            .map(|(_, spec)| *spec);
-
         let audio_cfg = data::AudioStream::from(&self.audio_stream);
 
-        let state = data::State::from_parts(
+        data::State::from_parts(
             layouts,
             self.theme.clone(),
             self.theme_editor.custom_theme.clone().map(data::Theme),
@@ -1217,19 +2969,449 @@ impl Flowsurface {
             audio_cfg,
             self.volume_size_unit,
             self.mt5_settings.clone(),
+            exchange::synthetic::all(),
+            exchange::adapter::custom_ws::all(),
+        )
+    }
+
+    /// Gathers what a bug report needs and zips it off the UI thread, credentials left out
+    fn create_support_bundle(&mut self, path: Option<std::path::PathBuf>) -> Task<Message> {
+        let state = self.collect_state(None);
+        let main_window = self.main_window.id;
+        let mt5_connection = self.mt5_settings.active_connection.as_deref();
+
+        let dashboard = self.active_dashboard();
+        let layout_name = self
+            .layout_manager
+            .active_layout_id()
+            .map_or_else(String::new, |id| id.name.clone());
+        let layout = std::iter::once(format!("Layout: {layout_name}"))
+            .chain(dashboard.pane_summary(main_window))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let streams = self.connection_bar.report(
+            dashboard.connection_entries(main_window),
+            mt5_connection,
+            std::time::Instant::now(),
         );
 
-        match serde_json::to_string(&state) {
-            Ok(layout_str) => {
-                let file_name = data::SAVED_STATE_PATH;
-                if let Err(e) = data::write_json_to_file(&layout_str, file_name) {
-                    log::error!("Failed to write layout state to file: {}", e);
+        let logs = self
+            .log_buffer
+            .filtered(&data::log::Filter::default())
+            .map(data::log::Line::details)
+            .collect::<Vec<_>>()
+            .join("\n");
+        let system = self.system_report();
+        let secrets = exchange::adapter::metatrader5::session_secrets();
+
+        Task::perform(
+            async move {
+                let mut bundle = data::support_bundle::Bundle::default();
+                bundle.text("README.txt", data::support_bundle::README.to_string());
+                bundle
+                    .json("state.json", &state)
+                    .map_err(|e| format!("Failed to serialize the state: {e}"))?;
+                bundle.text("system.txt", system);
+                bundle.text("layout.txt", layout);
+                bundle.text("streams.txt", streams);
+                bundle.text("logs.txt", logs);
+                secrets.iter().for_each(|secret| bundle.mask(secret));
+
+                bundle
+                    .write(path.as_deref(), data::symbol_cache::now_ms())
+                    .map_err(|e| format!("Failed to write the support bundle: {e}"))
+            },
+            Message::SupportBundleCreated,
+        )
+    }
+
+    /// Build, OS and the versions of the proxies connected to, as plain text
+    fn system_report(&self) -> String {
+        let mut lines = vec![
+            format!(
+                "flowsurface {} ({} build{})",
+                env!("CARGO_PKG_VERSION"),
+                if cfg!(debug_assertions) {
+                    "debug"
+                } else {
+                    "release"
+                },
+                if cfg!(feature = "fault-injection") {
+                    ", fault injection"
+                } else {
+                    ""
+                }
+            ),
+            format!(
+                "OS: {} {} ({})",
+                std::env::consts::OS,
+                std::env::consts::ARCH,
+                std::env::consts::FAMILY
+            ),
+            format!("Locale: {}", data::i18n::locale()),
+            format!("UI scale: {}", f32::from(self.ui_scale_factor)),
+        ];
+
+        for connection in &self.mt5_settings.connections {
+            let info = &connection.server_info;
+            lines.push(format!(
+                "MT5 {}{}: proxy {}, EA {}{}",
+                connection.name,
+                if self.mt5_settings.active_connection.as_ref() == Some(&connection.name) {
+                    " (active)"
+                } else {
+                    ""
+                },
+                modal::mt5_config::version_label(info.proxy_version.as_deref()),
+                modal::mt5_config::version_label(info.ea_version.as_deref()),
+                if info.capabilities.is_empty() {
+                    String::new()
                 } else {
-                    log::info!("Persisted state to {file_name}");
+                    format!(", supports {}", info.capabilities.join(", "))
                 }
+            ));
+        }
+        for feed in exchange::adapter::custom_ws::all() {
+            lines.push(format!("Custom feed {}", feed.name));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Blocks until the queued saves are on disk
+    fn flush_saved_state(&mut self) {
+        if let Err(e) = self.state_writer.flush() {
+            self.record_notification(
+                t!("source.saved_state"),
+                Severity::Error,
+                t!("notify.state_write_failed", error = e),
+            );
+        }
+    }
+
+    /// Shows `toast` and keeps it in the notifications center under `source`
+    fn notify(&mut self, source: impl Into<String>, toast: Toast) {
+        self.record_notification(source, toast.severity(), toast.body().to_string());
+        self.notifications.push(toast);
+    }
+
+    /// Keeps the versions proxies report with their connections, warning about outdated ones
+    fn apply_mt5_server_info(&mut self) {
+        use exchange::adapter::metatrader5;
+
+        for (server_addr, info) in metatrader5::take_server_info_updates() {
+            let mut source = server_addr.clone();
+            if let Some(connection) = self
+                .mt5_settings
+                .connections
+                .iter_mut()
+                .find(|c| c.server_addr == server_addr)
+            {
+                connection.server_info = info.clone();
+                source = connection.name.clone();
+            }
+
+            if let Some(version) = info.proxy().filter(|_| info.is_outdated()) {
+                self.record_notification(
+                    source,
+                    Severity::Warning,
+                    t!(
+                        "notify.mt5_proxy_outdated",
+                        version = version,
+                        required = metatrader5::MIN_PROXY_VERSION
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Logs every revised bar, and notifies at most once per [`REVISION_REPORT_INTERVAL`] a chart
+    fn report_kline_revisions(
+        &mut self,
+        ticker_info: exchange::TickerInfo,
+        timeframe: exchange::Timeframe,
+        revisions: &[data::chart::revision::Revision],
+        applied: bool,
+    ) {
+        let ohlc = |kline: &exchange::Kline| {
+            [kline.open, kline.high, kline.low, kline.close]
+                .map(|price| price.to_string(ticker_info.min_ticksize))
+                .join(" ")
+        };
+        for revision in revisions {
+            log::info!(
+                "{} {timeframe} bar at {} revised: OHLC {} -> {}",
+                ticker_info.ticker,
+                revision.held.time,
+                ohlc(&revision.held),
+                ohlc(&revision.fetched),
+            );
+        }
+
+        let now = std::time::Instant::now();
+        let key = (ticker_info.ticker, timeframe);
+        if self
+            .revisions_reported
+            .get(&key)
+            .is_some_and(|last| now.duration_since(*last) < REVISION_REPORT_INTERVAL)
+        {
+            return;
+        }
+        self.revisions_reported.insert(key, now);
+
+        let count = revisions.len();
+        let (severity, message) = if applied {
+            (
+                Severity::Info,
+                t!(
+                    "notify.klines_revised_applied",
+                    ticker = ticker_info.ticker,
+                    timeframe = timeframe,
+                    count = count
+                ),
+            )
+        } else {
+            (
+                Severity::Warning,
+                t!(
+                    "notify.klines_revised_kept",
+                    ticker = ticker_info.ticker,
+                    timeframe = timeframe,
+                    count = count
+                ),
+            )
+        };
+        self.record_notification(t!("source.kline_revision"), severity, message.to_string());
+    }
+
+    /// Adds `config` to the MT5 connections, or updates the one on its address, and makes it
+    /// the active one. Returns the task fetching its symbols.
+    /// Saves the edited connection and persists the app state with it
+    fn apply_mt5_config(
+        &mut self,
+        config: exchange::adapter::metatrader5::Mt5Config,
+        revisions: data::chart::revision::RevisionSettings,
+    ) -> Task<Message> {
+        let fetch_cmd = self.save_mt5_config(config, revisions);
+
+        // Trigger save to disk by collecting window specs
+        let mut active_windows = self
+            .active_dashboard_mut()
+            .popout
+            .keys()
+            .copied()
+            .collect::<Vec<window::Id>>();
+        active_windows.push(self.main_window.id);
+        window::collect_window_specs(active_windows, Message::SaveStateOnly).chain(fetch_cmd)
+    }
+
+    fn save_mt5_config(
+        &mut self,
+        config: exchange::adapter::metatrader5::Mt5Config,
+        revisions: data::chart::revision::RevisionSettings,
+    ) -> Task<Message> {
+        self.mt5_settings.revisions = revisions;
+        self.mt5_modal.set_revisions(revisions);
+
+        // Convert exchange::Mt5Config to data::Mt5Connection
+        let connection = data::Mt5Connection {
+            name: mt5_connection_name(&config),
+            server_addr: config.server_addr.clone(),
+            api_key: config.api_key.clone(),
+            api_secret: config.api_secret.clone(),
+            use_tls: config.use_tls,
+            auto_reconnect: config.auto_reconnect,
+            pause_when_closed: config.pause_when_closed,
+            server_info: exchange::adapter::metatrader5::server_info(&config.server_addr)
+                .unwrap_or_default(),
+            spread_alert: None,
+            terminal_id: config.terminal_id.clone(),
+            sync_terminal_chart: config.sync_terminal_chart,
+        };
+
+        // Add or update connection in settings
+        if let Some(existing) = self
+            .mt5_settings
+            .connections
+            .iter_mut()
+            .find(|c| c.server_addr == connection.server_addr)
+        {
+            *existing = data::Mt5Connection {
+                spread_alert: existing.spread_alert,
+                ..connection.clone()
+            };
+        } else {
+            self.mt5_settings.connections.push(connection.clone());
+        }
+        self.mt5_settings.active_connection = Some(connection.name.clone());
+
+        // Set global config so fetch_klines can access it, streams that still use the
+        // replaced one start over with it
+        if exchange::adapter::metatrader5::apply_config(config.clone()) {
+            self.layout_manager
+                .iter_dashboards_mut()
+                .for_each(|dashboard| {
+                    dashboard.resubscribe_exchange(exchange::adapter::Exchange::MetaTrader5)
+                });
+        }
+        exchange::adapter::metatrader5::register_connection(&connection.name, config.clone());
+
+        log::info!("MT5 config saved: {}", config.server_addr);
+        self.notifications
+            .push(Toast::new(widget::toast::Notification::Info(
+                t!("notify.mt5_saved").to_string(),
+            )));
+
+        // Trigger fetch symbols immediately
+        Task::done(Message::FetchMt5Symbols(config))
+    }
+
+    /// Applies what the setup wizard ended with, opening the starter layout if one was picked
+    fn finish_onboarding(&mut self, setup: modal::onboarding::Setup) -> Task<Message> {
+        let modal::onboarding::Setup {
+            exchanges,
+            mt5,
+            template,
+        } = setup;
+
+        if let Some(exchanges) = exchanges {
+            self.sidebar.tickers_table.set_exchange_filters(exchanges);
+        }
+
+        let fetch_cmd = match mt5 {
+            Some((config, revisions)) => self.save_mt5_config(config, revisions),
+            None => Task::none(),
+        };
+
+        let open_layout = match template {
+            Some(template) => {
+                let unique = uuid::Uuid::new_v4();
+                let id = LayoutId {
+                    unique,
+                    name: self
+                        .layout_manager
+                        .ensure_unique_name(template.name(), unique),
+                };
+                let dashboard = Dashboard::from_config(
+                    configuration(template.pane()),
+                    vec![],
+                    std::collections::HashSet::new(),
+                    unique,
+                );
+                self.layout_manager.insert_layout(id, dashboard);
+
+                Task::done(Message::Layouts(
+                    modal::layout_manager::Message::SelectActive(unique),
+                ))
             }
-            Err(e) => log::error!("Failed to serialize layout: {}", e),
+            None => Task::none(),
+        };
+
+        // Trigger save to disk by collecting window specs
+        let mut active_windows = self
+            .active_dashboard()
+            .popout
+            .keys()
+            .copied()
+            .collect::<Vec<window::Id>>();
+        active_windows.push(self.main_window.id);
+
+        open_layout
+            .chain(window::collect_window_specs(
+                active_windows,
+                Message::SaveStateOnly,
+            ))
+            .chain(fetch_cmd)
+    }
+
+    /// Opens a new layout with a candlestick chart of each picked ticker, telling which ones
+    /// didn't fit. Charts fetch their history like those of a restored layout, through each
+    /// venue's rate limits.
+    fn open_grid(
+        &mut self,
+        picked: &[exchange::Ticker],
+        timeframe: exchange::Timeframe,
+    ) -> Task<Message> {
+        use data::layout::template::{self, GridSkip, MAX_GRID_PANES};
+
+        let tickers_info = &self.sidebar.tickers_table.tickers_info;
+        let (tickers, skipped) = template::plan_grid(
+            picked,
+            |ticker| tickers_info.get(ticker).copied().flatten().is_some(),
+            |exchange| {
+                exchange::adapter::adapter(exchange)
+                    .and_then(|adapter| adapter.max_kline_streams(exchange))
+            },
+        );
+
+        if !skipped.is_empty() {
+            let tickers = skipped
+                .iter()
+                .map(|(ticker, skip)| match skip {
+                    GridSkip::Unavailable => t!("notify.grid_skip_unavailable", ticker = ticker),
+                    GridSkip::PaneLimit => t!(
+                        "notify.grid_skip_pane_limit",
+                        ticker = ticker,
+                        max = MAX_GRID_PANES
+                    ),
+                    GridSkip::StreamCap(cap) => t!(
+                        "notify.grid_skip_stream_cap",
+                        ticker = ticker,
+                        exchange = ticker.exchange,
+                        cap = cap
+                    ),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            self.notifications
+                .push(Toast::warn(t!("notify.grid_skipped", tickers = tickers)));
+        }
+
+        let Some(pane) = template::grid(&tickers, timeframe) else {
+            return Task::none();
+        };
+        self.symbol_search = None;
+
+        let unique = uuid::Uuid::new_v4();
+        let id = LayoutId {
+            unique,
+            name: self
+                .layout_manager
+                .ensure_unique_name(&format!("Grid {timeframe}"), unique),
+        };
+        let dashboard = Dashboard::from_config(
+            configuration(pane),
+            vec![],
+            std::collections::HashSet::new(),
+            unique,
+        );
+        self.layout_manager.insert_layout(id, dashboard);
+
+        Task::done(Message::Layouts(
+            modal::layout_manager::Message::SelectActive(unique),
+        ))
+    }
+
+    /// Adds a record to the notifications center without showing a toast
+    fn record_notification(
+        &mut self,
+        source: impl Into<String>,
+        severity: Severity,
+        message: String,
+    ) {
+        if severity == Severity::Error
+            && self.notification_log.sound_on_error
+            && let Err(err) = self.audio_stream.play(audio::SoundType::HardSell)
+        {
+            log::error!("Failed to play notification sound: {err}");
         }
+
+        self.notification_log.push(data::notifications::Record {
+            time: data::symbol_cache::now_ms(),
+            severity,
+            source: source.into(),
+            message,
+        });
     }
 
     fn restart(&mut self) -> Task<Message> {
@@ -1244,9 +3426,104 @@ impl Flowsurface {
                 .collect::<Vec<_>>(),
         );
 
-        let (new_state, init_task) = Flowsurface::new();
+        let (new_state, init_task) = Flowsurface::new(None);
         *self = new_state;
 
         close_windows.chain(init_task)
     }
 }
+
+/// Stands in for the dashboard after a crash, no pane of the layout is drawn until asked for
+fn safe_mode_view<'a>() -> Element<'a, Message> {
+    let menu_btn = |label: &'static str, menu: sidebar::Menu| {
+        button(text(label)).on_press(Message::Sidebar(
+            dashboard::sidebar::Message::ToggleSidebarMenu(Some(menu)),
+        ))
+    };
+
+    let content = column![
+        text(t!("safe_mode.title")).size(16),
+        text(t!("safe_mode.hint")).size(13),
+        row![
+            menu_btn(t!("safe_mode.layouts"), sidebar::Menu::Layout),
+            menu_btn(t!("safe_mode.notifications"), sidebar::Menu::Notifications),
+            button(text(t!("safe_mode.normal_start")))
+                .style(|theme, status| style::button::confirm(theme, status, true))
+                .on_press(Message::ExitSafeMode),
+        ]
+        .spacing(8),
+    ]
+    .spacing(12)
+    .max_width(420)
+    .align_x(Alignment::Center);
+
+    container(iced::widget::center(content))
+        .style(style::dashboard_modal)
+        .into()
+}
+
+/// Closes the streams' connections before the state is saved and the app exits or restarts
+fn close_streams(windows: HashMap<window::Id, WindowSpec>, restart: bool) -> Task<Message> {
+    Task::perform(
+        exchange::adapter::shutdown(SHUTDOWN_TIMEOUT),
+        move |stuck| {
+            if stuck > 0 {
+                log::warn!("Exiting with {stuck} stream(s) that didn't close in time");
+            }
+            Message::StreamsClosed(windows, restart)
+        },
+    )
+}
+
+/// Builds a source's search entries in the background, they replace only that source's group
+fn reindex_symbols(
+    source: data::symbol_search::Source,
+    tickers: Vec<exchange::Ticker>,
+) -> Task<Message> {
+    Task::perform(
+        {
+            let source = source.clone();
+            async move { data::symbol_search::entries(&source, tickers) }
+        },
+        move |entries| Message::SymbolIndexUpdated(source.clone(), entries),
+    )
+}
+
+/// Name under which an MT5 connection is persisted and its symbols are cached
+fn mt5_connection_name(config: &exchange::adapter::metatrader5::Mt5Config) -> String {
+    config.connection_name()
+}
+
+/// Confirmation for an edit of the active MT5 connection that restarts the streams using it,
+/// `None` when it can be saved right away
+fn mt5_restart_dialog(
+    config: &exchange::adapter::metatrader5::Mt5Config,
+    revisions: data::chart::revision::RevisionSettings,
+) -> Option<screen::ConfirmDialog<Message>> {
+    use exchange::adapter::metatrader5;
+
+    let active = metatrader5::get_global_config()?;
+    let changes = active.material_changes(config);
+    let streams = metatrader5::live_streams(&active);
+    if changes.is_empty() || streams == 0 {
+        return None;
+    }
+
+    let summary = changes
+        .iter()
+        .map(|change| format!("- {change}"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let message = format!(
+        "This edit changes how the active MT5 connection streams:\n{summary}\n\n{streams} live {} will be restarted with the new settings.",
+        if streams == 1 { "stream" } else { "streams" }
+    );
+
+    Some(
+        screen::ConfirmDialog::new(
+            message,
+            Box::new(Message::ApplyMt5Config(config.clone(), revisions)),
+        )
+        .with_confirm_btn_text("Save and restart".to_string()),
+    )
+}
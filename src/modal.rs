@@ -1,16 +1,32 @@
 pub mod audio;
+pub mod custom_ws_config;
+pub mod data_location;
+#[cfg(feature = "fault-injection")]
+pub mod fault_injection;
 pub mod layout_manager;
+pub mod log_viewer;
 pub mod mt5_config;
+pub mod notifications;
+pub mod onboarding;
 pub mod pane;
+pub mod symbol_search;
 pub mod theme_editor;
+pub mod tick_size;
 
+pub use custom_ws_config::CustomWsConfigModal;
+pub use data_location::DataLocation;
 use iced::widget::{center, container, mouse_area, opaque, stack};
 use iced::{Alignment, Color, Element, Length, padding};
 pub use layout_manager::LayoutManager;
+pub use log_viewer::LogViewer;
 pub use mt5_config::Mt5ConfigModal;
+pub use notifications::NotificationCenter;
+pub use onboarding::Onboarding;
 pub use pane::indicators;
 pub use pane::stream::{self, ModifierKind};
+pub use symbol_search::SymbolSearch;
 pub use theme_editor::ThemeEditor;
+pub use tick_size::TickSizeModal;
 
 pub fn main_dialog_modal<'a, Message>(
     base: impl Into<Element<'a, Message>>,
@@ -2,6 +2,7 @@ use crate::TooltipPosition;
 use crate::audio::{SoundCache, SoundType};
 use crate::style::{self, icon_text};
 use crate::widget::{labeled_slider, tooltip};
+use data::aggr::sweep;
 use data::audio::StreamCfg;
 use exchange::adapter::{Exchange, StreamKind, StreamTicksize};
 
@@ -20,6 +21,7 @@ pub enum Message {
     ToggleStream(bool, (Exchange, exchange::Ticker)),
     ToggleCard(Exchange, exchange::Ticker),
     SetThreshold(Exchange, exchange::Ticker, data::audio::Threshold),
+    SetSweepWindow(Exchange, exchange::Ticker, Option<u16>),
 }
 
 pub struct AudioStream {
@@ -93,6 +95,13 @@ impl AudioStream {
                     cfg.threshold = threshold;
                 }
             }
+            Message::SetSweepWindow(exchange, ticker, sweep_window_ms) => {
+                if let Some(streams) = self.streams.get_mut(&exchange)
+                    && let Some(cfg) = streams.get_mut(&ticker)
+                {
+                    cfg.sweep_window_ms = sweep_window_ms;
+                }
+            }
         }
     }
 
@@ -195,10 +204,26 @@ impl AudioStream {
                                         )
                                     });
 
+                                let counted = if cfg.sweep_window_ms.is_some() {
+                                    "sweep"
+                                } else {
+                                    "trade"
+                                };
+                                let sweeps_checkbox = checkbox(cfg.sweep_window_ms.is_some())
+                                    .label("Count sweeps instead of prints")
+                                    .on_toggle(move |value| {
+                                        Message::SetSweepWindow(
+                                            exchange,
+                                            ticker,
+                                            value.then_some(sweep::DEFAULT_WINDOW_MS),
+                                        )
+                                    });
+
                                 column = column.push(
                                     column![
-                                        text(format!("Buy/sell trade count in buffer ≥ {}", v)),
-                                        threshold_slider
+                                        text(format!("Buy/sell {counted} count in buffer ≥ {v}")),
+                                        threshold_slider,
+                                        sweeps_checkbox,
                                     ]
                                     .padding(8)
                                     .spacing(4),
@@ -278,14 +303,15 @@ impl AudioStream {
 
         match cfg.threshold {
             data::audio::Threshold::Count(v) => {
-                let (buy_count, sell_count) =
-                    trades_buffer.iter().fold((0, 0), |(buy_c, sell_c), trade| {
-                        if trade.is_sell {
-                            (buy_c, sell_c + 1)
-                        } else {
-                            (buy_c + 1, sell_c)
-                        }
-                    });
+                // A sweep of many small prints is one order, it shouldn't sound like a burst
+                let (buy_count, sell_count) = match cfg.sweep_window_ms {
+                    Some(window_ms) => count_sides(
+                        sweep::aggregate(trades_buffer, window_ms)
+                            .iter()
+                            .map(|sweep| sweep.is_sell),
+                    ),
+                    None => count_sides(trades_buffer.iter().map(|trade| trade.is_sell)),
+                };
 
                 if buy_count < v && sell_count < v {
                     return Ok(());
@@ -327,6 +353,17 @@ impl AudioStream {
     }
 }
 
+/// Buy and sell counts of a run of sides
+fn count_sides(is_sell: impl Iterator<Item = bool>) -> (usize, usize) {
+    is_sell.fold((0, 0), |(buy_c, sell_c), is_sell| {
+        if is_sell {
+            (buy_c, sell_c + 1)
+        } else {
+            (buy_c + 1, sell_c)
+        }
+    })
+}
+
 impl From<&AudioStream> for data::AudioStream {
     fn from(audio_stream: &AudioStream) -> Self {
         let mut streams = FxHashMap::default();
@@ -0,0 +1,442 @@
+//! Custom WebSocket Feed Configuration Modal
+//!
+//! Edits a generic JSON feed: connection settings, served symbols and the field mappings,
+//! with a preview that runs sample (or captured) messages through the mappings.
+
+use exchange::adapter::custom_ws::{self, CustomSymbol, CustomWsConfig, Mappings, Parsed};
+use iced::{
+    Alignment, Element, Length,
+    widget::{
+        button, column, container, pick_list, row, scrollable, text, text_editor, text_input,
+        toggler,
+    },
+};
+
+use crate::style;
+
+/// Frames captured by "Test & Preview"
+const CAPTURE_COUNT: usize = 8;
+const CAPTURE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Load a saved config into the form
+    Select(String),
+    /// Start a new config from the Coinbase example
+    LoadExample,
+    NameChanged(String),
+    UrlChanged(String),
+    AuthHeaderChanged(String),
+    SubscribeTemplateChanged(String),
+    SymbolsChanged(String),
+    AutoReconnectChanged(bool),
+    MappingsEdited(text_editor::Action),
+    SampleEdited(text_editor::Action),
+    /// Parse the sample messages with the current mappings
+    Preview,
+    /// Connect and capture live messages into the sample box
+    TestConnection,
+    Delete,
+    Save,
+    Cancel,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    SaveConfig(CustomWsConfig),
+    DeleteConfig(String),
+    TestConnection(CustomWsConfig),
+    Exit,
+    None,
+}
+
+#[derive(Debug, Clone, Default)]
+enum Status {
+    #[default]
+    Idle,
+    Testing,
+    Failed(String),
+}
+
+pub struct CustomWsConfigModal {
+    name: String,
+    url: String,
+    auth_header: String,
+    subscribe_template: String,
+    symbols: String,
+    auto_reconnect: bool,
+    mappings: text_editor::Content,
+    sample: text_editor::Content,
+    preview: Vec<String>,
+    status: Status,
+}
+
+impl CustomWsConfigModal {
+    pub fn new() -> Self {
+        let mut modal = Self {
+            name: String::new(),
+            url: String::new(),
+            auth_header: String::new(),
+            subscribe_template: String::new(),
+            symbols: String::new(),
+            auto_reconnect: true,
+            mappings: text_editor::Content::new(),
+            sample: text_editor::Content::new(),
+            preview: vec![],
+            status: Status::Idle,
+        };
+        modal.load(&CustomWsConfig::coinbase_example());
+        modal
+    }
+
+    fn load(&mut self, config: &CustomWsConfig) {
+        self.name = config.name.clone();
+        self.url = config.url.clone();
+        self.auth_header = config.auth_header.clone().unwrap_or_default();
+        self.subscribe_template = config.subscribe_template.clone();
+        self.symbols = format_symbols(&config.symbols);
+        self.auto_reconnect = config.auto_reconnect;
+        self.mappings = text_editor::Content::with_text(
+            &serde_json::to_string_pretty(&config.mappings).unwrap_or_default(),
+        );
+        self.preview.clear();
+        self.status = Status::Idle;
+    }
+
+    /// Builds the config from the form, without validating it
+    fn config(&self) -> Result<CustomWsConfig, String> {
+        let mappings: Mappings =
+            serde_json::from_str(&self.mappings.text()).map_err(|e| format!("Mappings: {e}"))?;
+
+        Ok(CustomWsConfig {
+            name: self.name.trim().to_string(),
+            url: self.url.trim().to_string(),
+            auth_header: Some(self.auth_header.trim().to_string()).filter(|h| !h.is_empty()),
+            subscribe_template: self.subscribe_template.clone(),
+            symbols: parse_symbols(&self.symbols)?,
+            mappings,
+            auto_reconnect: self.auto_reconnect,
+        })
+    }
+
+    fn valid_config(&self) -> Result<CustomWsConfig, String> {
+        let config = self.config()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Fills the sample box with frames captured from the live feed and previews them
+    pub fn set_captured(&mut self, result: Result<Vec<String>, String>) {
+        match result {
+            Ok(messages) => {
+                self.sample = text_editor::Content::with_text(&messages.join("\n"));
+                self.status = Status::Idle;
+                self.run_preview();
+            }
+            Err(e) => self.status = Status::Failed(e),
+        }
+    }
+
+    fn run_preview(&mut self) {
+        let config = match self.config() {
+            Ok(config) => config,
+            Err(e) => {
+                self.status = Status::Failed(e);
+                return;
+            }
+        };
+        let symbol = config.symbols.first().map(|s| s.symbol.as_str());
+        let now = chrono::Utc::now().timestamp_millis().max(0) as u64;
+
+        self.preview = self
+            .sample
+            .text()
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(
+                |line| match custom_ws::parse_message(&config.mappings, line, symbol, now) {
+                    Ok(Some(parsed)) => describe(&parsed),
+                    Ok(None) => "ignored: no mapping matched".to_string(),
+                    Err(e) => format!("error: {e}"),
+                },
+            )
+            .collect();
+    }
+
+    pub fn update(&mut self, message: Message) -> Action {
+        match message {
+            Message::Select(name) => {
+                if let Some(config) = custom_ws::all().into_iter().find(|c| c.name == name) {
+                    self.load(&config);
+                }
+            }
+            Message::LoadExample => self.load(&CustomWsConfig::coinbase_example()),
+            Message::NameChanged(name) => self.name = name,
+            Message::UrlChanged(url) => self.url = url,
+            Message::AuthHeaderChanged(header) => self.auth_header = header,
+            Message::SubscribeTemplateChanged(template) => self.subscribe_template = template,
+            Message::SymbolsChanged(symbols) => self.symbols = symbols,
+            Message::AutoReconnectChanged(auto_reconnect) => self.auto_reconnect = auto_reconnect,
+            Message::MappingsEdited(action) => self.mappings.perform(action),
+            Message::SampleEdited(action) => self.sample.perform(action),
+            Message::Preview => {
+                self.status = Status::Idle;
+                self.run_preview();
+            }
+            Message::TestConnection => match self.valid_config() {
+                Ok(config) => {
+                    self.status = Status::Testing;
+                    return Action::TestConnection(config);
+                }
+                Err(e) => self.status = Status::Failed(e),
+            },
+            Message::Delete => return Action::DeleteConfig(self.name.trim().to_string()),
+            Message::Save => match self.valid_config() {
+                Ok(config) => return Action::SaveConfig(config),
+                Err(e) => self.status = Status::Failed(e),
+            },
+            Message::Cancel => return Action::Exit,
+        }
+
+        Action::None
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let saved: Vec<String> = custom_ws::all().into_iter().map(|c| c.name).collect();
+        let is_saved = saved.contains(&self.name);
+
+        let header = row![
+            text("Custom WebSocket Feed").size(18),
+            iced::widget::Space::new().width(Length::Fill),
+            pick_list(saved, is_saved.then(|| self.name.clone()), Message::Select)
+                .placeholder("Saved feeds")
+                .text_size(13),
+            button(text("Example").size(13))
+                .on_press(Message::LoadExample)
+                .style(button::secondary),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
+        let connection = column![
+            labeled_input("Name", "e.g., Coinbase", &self.name, Message::NameChanged),
+            labeled_input(
+                "URL",
+                "wss://example.com/ws",
+                &self.url,
+                Message::UrlChanged
+            ),
+            column![
+                text("Authorization Header").size(13),
+                text_input("Optional, e.g., Bearer <token>", &self.auth_header)
+                    .on_input(Message::AuthHeaderChanged)
+                    .secure(true)
+                    .padding(8)
+                    .size(14),
+            ]
+            .spacing(4),
+            labeled_input(
+                "Subscribe Message ({symbol} is replaced)",
+                "Optional",
+                &self.subscribe_template,
+                Message::SubscribeTemplateChanged,
+            ),
+            labeled_input(
+                "Symbols (symbol:tick_size[:min_qty], comma separated)",
+                "BTC-USD:0.01:0.00000001",
+                &self.symbols,
+                Message::SymbolsChanged,
+            ),
+            row![
+                text("Auto Reconnect").width(Length::Fill),
+                toggler(self.auto_reconnect)
+                    .on_toggle(Message::AutoReconnectChanged)
+                    .size(20),
+            ]
+            .align_y(Alignment::Center)
+            .spacing(8),
+        ]
+        .spacing(12)
+        .width(360);
+
+        let editors = column![
+            text("Field Mappings (JSON)").size(13),
+            text_editor(&self.mappings)
+                .on_action(Message::MappingsEdited)
+                .font(style::AZERET_MONO)
+                .size(11)
+                .height(220),
+            text("Sample Messages (one per line)").size(13),
+            text_editor(&self.sample)
+                .on_action(Message::SampleEdited)
+                .placeholder("Paste messages, or capture them with Test & Preview")
+                .font(style::AZERET_MONO)
+                .size(11)
+                .height(120),
+        ]
+        .spacing(6)
+        .width(420);
+
+        let preview = scrollable(
+            column(
+                self.preview
+                    .iter()
+                    .map(|line| text(line).size(11).font(style::AZERET_MONO).into()),
+            )
+            .spacing(2),
+        )
+        .height(96)
+        .width(Length::Fill);
+
+        let status = match &self.status {
+            Status::Idle => text("").size(12),
+            Status::Testing => text("Capturing messages...").size(12),
+            Status::Failed(msg) => text(format!("✗ {msg}"))
+                .size(12)
+                .color(iced::Color::from_rgb(0.9, 0.3, 0.3)),
+        };
+
+        let buttons = row![
+            button(text("Test & Preview").size(13))
+                .on_press(Message::TestConnection)
+                .style(button::secondary),
+            button(text("Preview Sample").size(13))
+                .on_press(Message::Preview)
+                .style(button::secondary),
+            iced::widget::Space::new().width(Length::Fill),
+            button(text("Delete").size(13))
+                .on_press_maybe(is_saved.then_some(Message::Delete))
+                .style(button::danger),
+            button(text("Cancel").size(13))
+                .on_press(Message::Cancel)
+                .style(button::secondary),
+            button(text("Save").size(13))
+                .on_press(Message::Save)
+                .style(button::primary),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
+        let content = column![
+            header,
+            row![connection, editors].spacing(16),
+            text("Preview").size(13),
+            preview,
+            status,
+            buttons,
+        ]
+        .spacing(12)
+        .width(800);
+
+        container(content)
+            .padding(24)
+            .style(style::dashboard_modal)
+            .into()
+    }
+}
+
+impl Default for CustomWsConfigModal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Captures live frames for the preview, see [`custom_ws::capture_messages`]
+pub async fn capture(config: CustomWsConfig) -> Result<Vec<String>, String> {
+    custom_ws::capture_messages(&config, CAPTURE_COUNT, CAPTURE_TIMEOUT)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+fn describe(parsed: &Parsed) -> String {
+    match parsed {
+        Parsed::Trades(trades) => trades
+            .iter()
+            .map(|t| {
+                format!(
+                    "trade {} {} @ {} t={}",
+                    if t.is_sell { "sell" } else { "buy" },
+                    t.qty,
+                    t.price,
+                    t.time
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; "),
+        Parsed::Snapshot(book) | Parsed::Diff(book) => {
+            let kind = if matches!(parsed, Parsed::Snapshot(_)) {
+                "snapshot"
+            } else {
+                "diff"
+            };
+            let best = |levels: &[(f32, f32)]| {
+                levels
+                    .first()
+                    .map_or_else(|| "-".to_string(), |(p, q)| format!("{q} @ {p}"))
+            };
+            format!(
+                "{kind} {} bids (first {}), {} asks (first {}) t={}",
+                book.bids.len(),
+                best(&book.bids),
+                book.asks.len(),
+                best(&book.asks),
+                book.time
+            )
+        }
+    }
+}
+
+fn format_symbols(symbols: &[CustomSymbol]) -> String {
+    symbols
+        .iter()
+        .map(|s| format!("{}:{}:{}", s.symbol, s.tick_size, s.min_qty))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn parse_symbols(input: &str) -> Result<Vec<CustomSymbol>, String> {
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let mut parts = entry.split(':').map(str::trim);
+            let symbol = parts.next().unwrap_or_default().to_string();
+
+            let number = |part: Option<&str>, what: &str| -> Result<Option<f32>, String> {
+                part.map(|p| {
+                    p.parse::<f32>()
+                        .map_err(|_| format!("{symbol}: invalid {what} {p:?}"))
+                })
+                .transpose()
+            };
+
+            let tick_size = number(parts.next(), "tick size")?
+                .ok_or_else(|| format!("{symbol}: missing tick size"))?;
+            let min_qty = number(parts.next(), "min qty")?.unwrap_or(custom_ws::DEFAULT_MIN_QTY);
+
+            Ok(CustomSymbol {
+                symbol,
+                tick_size,
+                min_qty,
+            })
+        })
+        .collect()
+}
+
+fn labeled_input<'a>(
+    label: &'a str,
+    placeholder: &'a str,
+    value: &'a str,
+    on_input: impl Fn(String) -> Message + 'a,
+) -> Element<'a, Message> {
+    column![
+        text(label).size(13),
+        text_input(placeholder, value)
+            .on_input(on_input)
+            .padding(8)
+            .size(14),
+    ]
+    .spacing(4)
+    .into()
+}
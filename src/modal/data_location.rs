@@ -0,0 +1,149 @@
+use crate::style;
+use data::data_dir::{self, Progress};
+
+use iced::futures::StreamExt as _;
+use iced::futures::channel::{mpsc, oneshot};
+use iced::task::{Straw, sipper};
+use iced::widget::{button, checkbox, column, progress_bar, row, text, text_input};
+use iced::{Alignment, Element};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    PathChanged(String),
+    ToggleMoveFiles(bool),
+    ApplyPressed,
+    Progressed(Progress),
+}
+
+pub enum Action {
+    /// Asks before anything is moved
+    Confirm { target: PathBuf, move_files: bool },
+}
+
+pub struct DataLocation {
+    input: String,
+    move_files: bool,
+    moving: Option<Progress>,
+}
+
+impl Default for DataLocation {
+    fn default() -> Self {
+        Self {
+            input: String::new(),
+            move_files: true,
+            moving: None,
+        }
+    }
+}
+
+impl DataLocation {
+    pub fn update(&mut self, message: Message) -> Option<Action> {
+        match message {
+            Message::PathChanged(input) => self.input = input,
+            Message::ToggleMoveFiles(move_files) => self.move_files = move_files,
+            Message::ApplyPressed => {
+                let target = self.input.trim();
+                if !target.is_empty() && self.moving.is_none() {
+                    return Some(Action::Confirm {
+                        target: PathBuf::from(target),
+                        move_files: self.move_files,
+                    });
+                }
+            }
+            Message::Progressed(progress) => self.moving = Some(progress),
+        }
+        None
+    }
+
+    pub fn started(&mut self) {
+        self.moving = Some(Progress::default());
+    }
+
+    pub fn finished(&mut self, succeeded: bool) {
+        self.moving = None;
+        if succeeded {
+            self.input.clear();
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let origin = data_dir::origin();
+
+        let current = column![
+            text("Data folder").size(14),
+            text(data_dir::root().display().to_string()).size(12),
+            text(origin.to_string()).size(11).style(style::muted_text),
+        ]
+        .spacing(4);
+
+        if !origin.is_relocatable() {
+            return current.into();
+        }
+
+        if let Some(progress) = self.moving {
+            return column![
+                current,
+                progress_bar(0.0..=1.0, progress.fraction()),
+                text(format!(
+                    "Moving, {:.0}% of {:.1} MB",
+                    progress.fraction() * 100.0,
+                    progress.total_bytes as f64 / 1_048_576.0
+                ))
+                .size(11),
+            ]
+            .spacing(8)
+            .into();
+        }
+
+        let can_apply = !self.input.trim().is_empty();
+
+        let controls = column![
+            text_input("New folder path", &self.input)
+                .on_input(Message::PathChanged)
+                .on_submit_maybe(can_apply.then_some(Message::ApplyPressed))
+                .size(12),
+            row![
+                checkbox(self.move_files)
+                    .label("Move existing files")
+                    .on_toggle(Message::ToggleMoveFiles)
+                    .text_size(12),
+                button(text("Change").size(12))
+                    .on_press_maybe(can_apply.then_some(Message::ApplyPressed))
+                    .style(move |t, s| style::button::transparent(t, s, false)),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center),
+        ]
+        .spacing(8);
+
+        column![current, controls].spacing(8).into()
+    }
+}
+
+/// Runs [`data_dir::relocate`] off the UI thread, reporting progress about every percent
+pub fn relocate(target: PathBuf, move_files: bool) -> impl Straw<PathBuf, Progress, String> {
+    sipper(async move |mut sender| {
+        let (progress_tx, mut progress_rx) = mpsc::unbounded();
+        let (done_tx, done_rx) = oneshot::channel();
+
+        std::thread::spawn(move || {
+            let mut reported = -1.0;
+            let result = data_dir::relocate(&target, move_files, |progress| {
+                if progress.fraction() - reported >= 0.01 {
+                    reported = progress.fraction();
+                    progress_tx.unbounded_send(progress).ok();
+                }
+            });
+            done_tx.send(result).ok();
+        });
+
+        while let Some(progress) = progress_rx.next().await {
+            sender.send(progress).await;
+        }
+
+        done_rx
+            .await
+            .unwrap_or_else(|_| Err("Moving the data folder was interrupted".to_string()))
+    })
+}
@@ -0,0 +1,101 @@
+//! Developer panel for the simulated MT5 network faults, see [`faults`]. Only built with the
+//! `fault-injection` feature and opened with Ctrl/Cmd+Shift+F.
+
+use crate::{style, widget::labeled_slider};
+use exchange::adapter::metatrader5::faults::{self, Faults};
+
+use iced::widget::{button, column, container, row, text};
+use iced::{Alignment, Element, Length};
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    DelayChanged(f32),
+    JitterChanged(f32),
+    DropDepthChanged(f32),
+    ReorderWindowChanged(f32),
+    DuplicateTradesChanged(f32),
+    Reset,
+}
+
+pub fn update(message: Message) {
+    let mut current = faults::current();
+
+    match message {
+        Message::DelayChanged(ms) => current.delay = Duration::from_millis(ms as u64),
+        Message::JitterChanged(ms) => current.jitter = Duration::from_millis(ms as u64),
+        Message::DropDepthChanged(pct) => current.drop_depth = pct / 100.0,
+        Message::ReorderWindowChanged(frames) => current.reorder_window = frames as usize,
+        Message::DuplicateTradesChanged(pct) => current.duplicate_trades = pct / 100.0,
+        Message::Reset => current = Faults::NONE,
+    }
+
+    log::warn!("MT5 fault injection: {current:?}");
+    faults::set(current);
+}
+
+pub fn view<'a>() -> Element<'a, Message> {
+    let current = faults::current();
+
+    let header = row![
+        text("MT5 fault injection").size(14),
+        iced::widget::space::horizontal(),
+        button(text("Reset").size(12))
+            .on_press_maybe((current != Faults::NONE).then_some(Message::Reset))
+            .style(move |t, s| style::button::transparent(t, s, false)),
+    ]
+    .align_y(Alignment::Center);
+
+    let millis = |value: &f32| format!("{value:.0} ms");
+    let percent = |value: &f32| format!("{value:.0}%");
+
+    let sliders = column![
+        labeled_slider(
+            "Delay",
+            0.0..=5000.0,
+            current.delay.as_millis() as f32,
+            Message::DelayChanged,
+            millis,
+            Some(50.0),
+        ),
+        labeled_slider(
+            "Jitter",
+            0.0..=2000.0,
+            current.jitter.as_millis() as f32,
+            Message::JitterChanged,
+            millis,
+            Some(10.0),
+        ),
+        labeled_slider(
+            "Drop depth",
+            0.0..=100.0,
+            current.drop_depth * 100.0,
+            Message::DropDepthChanged,
+            percent,
+            Some(1.0),
+        ),
+        labeled_slider(
+            "Reorder window",
+            0.0..=16.0,
+            current.reorder_window as f32,
+            Message::ReorderWindowChanged,
+            |value| format!("{value:.0} frames"),
+            Some(1.0),
+        ),
+        labeled_slider(
+            "Duplicate trades",
+            0.0..=100.0,
+            current.duplicate_trades * 100.0,
+            Message::DuplicateTradesChanged,
+            percent,
+            Some(1.0),
+        ),
+    ]
+    .spacing(8);
+
+    container(column![header, sliders].spacing(12))
+        .width(Length::Fixed(360.0))
+        .padding(16)
+        .style(style::dashboard_modal)
+        .into()
+}
@@ -30,12 +30,14 @@ pub enum Message {
     RemoveLayout(Uuid),
     ToggleEditMode(Editing),
     CloneLayout(Uuid),
+    ExportLayout(Uuid),
     Reorder(DragEvent),
 }
 
 pub enum Action {
     Select(Uuid),
     Clone(Uuid),
+    Export(Uuid),
 }
 
 pub struct LayoutManager {
@@ -190,6 +192,9 @@ impl LayoutManager {
             Message::CloneLayout(id) => {
                 return Some(Action::Clone(id));
             }
+            Message::ExportLayout(id) => {
+                return Some(Action::Export(id));
+            }
             Message::Reorder(event) => column_drag::reorder_vec(&mut self.layouts, &event),
         }
 
@@ -265,6 +270,7 @@ impl LayoutManager {
                     layout_row = layout_row
                         .push(create_layout_button(layout_id, None))
                         .push(create_clone_button(layout_id))
+                        .push(create_export_button(layout_id))
                         .push(create_rename_button(layout_id));
 
                     if !is_active {
@@ -376,6 +382,19 @@ fn create_clone_button<'a>(layout: &LayoutId) -> Element<'a, Message> {
     )
 }
 
+fn create_export_button<'a>(layout: &LayoutId) -> Element<'a, Message> {
+    tooltip(
+        create_icon_button(
+            style::Icon::ExternalLink,
+            12,
+            |theme, status| style::button::layout_name(theme, *status),
+            Some(Message::ExportLayout(layout.unique)),
+        ),
+        Some("Export layout to a file"),
+        TooltipPosition::Top,
+    )
+}
+
 fn create_confirm_delete_buttons<'a>(
     layout: &LayoutId,
 ) -> (button::Button<'a, Message>, button::Button<'a, Message>) {
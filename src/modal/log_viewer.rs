@@ -0,0 +1,248 @@
+use crate::style;
+use data::log::{Filter, LogBuffer};
+
+use exchange::adapter::ExchangeInclusive;
+use iced::widget::{
+    button, checkbox, column, container, pick_list, row, scrollable, space, text, text_input,
+};
+use iced::{Alignment, Element, Length, Theme};
+use log::LevelFilter;
+use std::path::PathBuf;
+
+/// Newest lines rendered, the rest stay reachable through "Copy all"
+const MAX_SHOWN: usize = 500;
+
+const LEVELS: [LevelFilter; 5] = [
+    LevelFilter::Error,
+    LevelFilter::Warn,
+    LevelFilter::Info,
+    LevelFilter::Debug,
+    LevelFilter::Trace,
+];
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    LevelSelected(LevelFilter),
+    SourceSelected(SourceFilter),
+    QueryChanged(String),
+    TogglePause,
+    CopyAll,
+    Clear,
+    /// Keeps the raw messages of the venue's streams for order book dumps
+    ToggleRawCapture(ExchangeInclusive, bool),
+    BundlePathChanged(String),
+    CreateBundle,
+}
+
+pub enum Action {
+    CopyToClipboard(String),
+    /// Writes a support bundle to the path, or to the data folder without one
+    CreateSupportBundle(Option<PathBuf>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceFilter {
+    All,
+    Only(String),
+}
+
+impl std::fmt::Display for SourceFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceFilter::All => write!(f, "All sources"),
+            SourceFilter::Only(source) => write!(f, "{source}"),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct LogViewer {
+    filter: Filter,
+    bundle_path: String,
+}
+
+impl LogViewer {
+    pub fn update(&mut self, message: Message, buffer: &mut LogBuffer) -> Option<Action> {
+        match message {
+            Message::LevelSelected(level) => self.filter.max_level = level,
+            Message::SourceSelected(SourceFilter::All) => self.filter.source = None,
+            Message::SourceSelected(SourceFilter::Only(source)) => {
+                self.filter.source = Some(source);
+            }
+            Message::QueryChanged(query) => self.filter.query = query,
+            Message::TogglePause => {
+                self.filter.until = match self.filter.until {
+                    Some(_) => None,
+                    None => Some(buffer.last_seq().unwrap_or_default()),
+                };
+            }
+            Message::CopyAll => {
+                let lines: Vec<String> =
+                    buffer.filtered(&self.filter).map(|l| l.details()).collect();
+                return Some(Action::CopyToClipboard(lines.join("\n")));
+            }
+            Message::Clear => buffer.clear(),
+            Message::ToggleRawCapture(venue, enabled) => {
+                exchange::capture::set_enabled(venue, enabled);
+            }
+            Message::BundlePathChanged(path) => self.bundle_path = path,
+            Message::CreateBundle => {
+                let path = self.bundle_path.trim();
+                return Some(Action::CreateSupportBundle(
+                    (!path.is_empty()).then(|| PathBuf::from(path)),
+                ));
+            }
+        }
+        None
+    }
+
+    pub fn view<'a>(&'a self, buffer: &'a LogBuffer) -> Element<'a, Message> {
+        let is_paused = self.filter.until.is_some();
+
+        let source_filter = {
+            let mut options = vec![SourceFilter::All];
+            options.extend(buffer.sources().into_iter().map(SourceFilter::Only));
+
+            let selected = match &self.filter.source {
+                Some(source) => SourceFilter::Only(source.clone()),
+                None => SourceFilter::All,
+            };
+            pick_list(options, Some(selected), Message::SourceSelected).text_size(12)
+        };
+
+        let header = row![
+            text("Logs").size(14),
+            space::horizontal(),
+            pick_list(LEVELS, Some(self.filter.max_level), Message::LevelSelected).text_size(12),
+            source_filter,
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
+        let controls = row![
+            text_input("Search messages...", &self.filter.query)
+                .on_input(Message::QueryChanged)
+                .size(12),
+            button(text(if is_paused { "Resume" } else { "Pause" }).size(12))
+                .on_press(Message::TogglePause)
+                .style(move |t, s| style::button::transparent(t, s, is_paused)),
+            button(text("Copy all").size(12))
+                .on_press(Message::CopyAll)
+                .style(move |t, s| style::button::transparent(t, s, false)),
+            button(text("Clear").size(12))
+                .on_press(Message::Clear)
+                .style(move |t, s| style::button::transparent(t, s, false)),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
+        let matching: Vec<_> = buffer.filtered(&self.filter).collect();
+        let shown = &matching[matching.len().saturating_sub(MAX_SHOWN)..];
+
+        let list: Element<_> = if shown.is_empty() {
+            text("Nothing to show").size(12).into()
+        } else {
+            let mut lines = column![].spacing(2);
+
+            for line in shown {
+                let level = line.level;
+                let time = chrono::DateTime::from_timestamp_millis(line.time as i64)
+                    .map(|t| {
+                        t.with_timezone(&chrono::Local)
+                            .format("%H:%M:%S%.3f")
+                            .to_string()
+                    })
+                    .unwrap_or_default();
+
+                lines = lines.push(
+                    row![
+                        text(time).size(11).style(style::muted_text),
+                        text(level.to_string())
+                            .size(11)
+                            .style(move |theme: &Theme| level_text(theme, level)),
+                        text(line.source.as_str()).size(11).style(style::muted_text),
+                        text(line.message.as_str()).size(11),
+                    ]
+                    .spacing(6),
+                );
+            }
+
+            scrollable::Scrollable::with_direction(
+                lines,
+                scrollable::Direction::Vertical(
+                    scrollable::Scrollbar::new().width(8).scroller_width(6),
+                ),
+            )
+            .anchor_bottom()
+            .height(Length::Fixed(420.0))
+            .into()
+        };
+
+        let summary = text(format!(
+            "{} of {} lines{}",
+            shown.len(),
+            matching.len(),
+            if is_paused { ", paused" } else { "" }
+        ))
+        .size(11)
+        .style(style::muted_text);
+
+        let raw_capture = ExchangeInclusive::ALL.into_iter().fold(
+            row![text("Capture raw messages").size(12)]
+                .spacing(8)
+                .align_y(Alignment::Center),
+            |row, venue| {
+                row.push(
+                    checkbox(exchange::capture::is_enabled(venue))
+                        .label(venue_label(venue))
+                        .on_toggle(move |enabled| Message::ToggleRawCapture(venue, enabled))
+                        .text_size(12),
+                )
+            },
+        );
+
+        let support_bundle = row![
+            text_input(
+                "Save support bundle to... (data folder if empty)",
+                &self.bundle_path
+            )
+            .on_input(Message::BundlePathChanged)
+            .size(12),
+            button(text("Create support bundle").size(12))
+                .on_press(Message::CreateBundle)
+                .style(move |t, s| style::button::transparent(t, s, false)),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
+        container(column![header, controls, list, summary, raw_capture, support_bundle].spacing(12))
+            .width(Length::Fixed(640.0))
+            .padding(16)
+            .style(style::dashboard_modal)
+            .into()
+    }
+}
+
+fn venue_label(venue: ExchangeInclusive) -> &'static str {
+    match venue {
+        ExchangeInclusive::Bybit => "Bybit",
+        ExchangeInclusive::Binance => "Binance",
+        ExchangeInclusive::Hyperliquid => "Hyperliquid",
+        ExchangeInclusive::Okex => "OKX",
+        ExchangeInclusive::MetaTrader5 => "MT5",
+        ExchangeInclusive::CustomWs => "Custom",
+    }
+}
+
+fn level_text(theme: &Theme, level: log::Level) -> text::Style {
+    let palette = theme.extended_palette();
+
+    text::Style {
+        color: match level {
+            log::Level::Error => Some(palette.danger.base.color),
+            log::Level::Warn => Some(palette.warning.base.color),
+            log::Level::Info => Some(palette.primary.base.color),
+            log::Level::Debug | log::Level::Trace => None,
+        },
+    }
+}
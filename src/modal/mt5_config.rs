@@ -3,13 +3,15 @@
 //! Allows users to configure MetaTrader 5 server connections
 //! including server address, API credentials, and connection options.
 
-use exchange::adapter::metatrader5::Mt5Config;
+use exchange::adapter::metatrader5::{self, AuthMode, Mt5Config, ServerInfo, Terminal};
 use iced::{
     Alignment, Element, Length,
-    widget::{button, column, container, row, text, text_input, toggler},
+    widget::{button, column, container, pick_list, row, text, text_input, toggler},
 };
 
 use crate::style;
+use data::chart::revision::RevisionSettings;
+use data::t;
 
 /// MT5 configuration modal messages
 #[derive(Debug, Clone)]
@@ -24,8 +26,34 @@ pub enum Message {
     UseTlsChanged(bool),
     /// Auto reconnect toggle changed
     AutoReconnectChanged(bool),
+    /// Pause streams of closed markets toggle changed
+    PauseWhenClosedChanged(bool),
+    /// Terminal chart following the focused pane toggle changed
+    SyncTerminalChartChanged(bool),
+    /// Terminal picked among those the proxy bridges
+    TerminalSelected(Terminal),
+    /// Account currency changed
+    AccountCurrencyChanged(String),
+    /// Authentication mode selected
+    AuthKindSelected(AuthKind),
+    /// Bearer token changed
+    BearerTokenChanged(String),
+    /// Name of the custom header at the index changed
+    HeaderNameChanged(usize, String),
+    /// Value of the custom header at the index changed
+    HeaderValueChanged(usize, String),
+    AddHeader,
+    RemoveHeader(usize),
+    /// Send the HMAC handshake in addition to the headers
+    HmacWithHeadersChanged(bool),
+    /// Replace held bars the broker revised, or only report them
+    AutoApplyRevisionsChanged(bool),
+    /// Price tolerance of revised bars, in ticks
+    RevisionTicksChanged(String),
     /// Test connection button pressed
     TestConnection,
+    /// Re-fetch the symbol list, ignoring the cache
+    RefreshSymbols,
     /// Save configuration
     Save,
     /// Cancel/close modal
@@ -35,19 +63,50 @@ pub enum Message {
 /// Actions returned from modal update
 #[derive(Debug, Clone, PartialEq)]
 pub enum Action {
-    /// Save the configuration
-    SaveConfig(Mt5Config),
+    /// Save the configuration, with the revision settings of every connection
+    SaveConfig(Mt5Config, RevisionSettings),
     /// Test connection with current config
     TestConnection(Mt5Config),
+    /// Fetch symbols for the config, bypassing the symbol cache
+    RefreshSymbols(Mt5Config),
     /// Close the modal
     Exit,
     /// No action
     None,
 }
 
+/// Authentication modes offered by the selector, without their credentials
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthKind {
+    Hmac,
+    BearerToken,
+    Headers,
+}
+
+impl AuthKind {
+    const ALL: [AuthKind; 3] = [AuthKind::Hmac, AuthKind::BearerToken, AuthKind::Headers];
+
+    fn of(mode: &AuthMode) -> Self {
+        match mode {
+            AuthMode::Hmac => AuthKind::Hmac,
+            AuthMode::BearerToken(_) => AuthKind::BearerToken,
+            AuthMode::Headers(_) => AuthKind::Headers,
+        }
+    }
+}
+
+impl std::fmt::Display for AuthKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AuthKind::Hmac => f.write_str(t!("mt5.auth_hmac")),
+            AuthKind::BearerToken => f.write_str(t!("mt5.auth_bearer")),
+            AuthKind::Headers => f.write_str(t!("mt5.auth_headers")),
+        }
+    }
+}
+
 /// Connection test status
 #[derive(Debug, Clone, Default)]
-#[allow(dead_code)] // Success variant will be used when connection test is implemented
 pub enum TestStatus {
     #[default]
     Idle,
@@ -64,6 +123,10 @@ pub struct Mt5ConfigModal {
     test_status: TestStatus,
     /// Whether this is a new connection or editing existing
     is_new: bool,
+    /// How bars the broker revised are handled, shared by all connections
+    revisions: RevisionSettings,
+    /// Terminals the proxy listed at the last connection test, empty for a single one
+    terminals: Vec<Terminal>,
 }
 
 impl Mt5ConfigModal {
@@ -73,15 +136,28 @@ impl Mt5ConfigModal {
             config: Mt5Config::default(),
             test_status: TestStatus::Idle,
             is_new: true,
+            revisions: RevisionSettings::default(),
+            terminals: vec![],
         }
     }
 
+    pub fn with_revisions(mut self, revisions: RevisionSettings) -> Self {
+        self.revisions = revisions;
+        self
+    }
+
+    /// Revision settings saved from elsewhere, e.g. the setup wizard
+    pub fn set_revisions(&mut self, revisions: RevisionSettings) {
+        self.revisions = revisions;
+    }
+
     /// Update the modal state
     pub fn update(&mut self, message: Message) -> Action {
         match message {
             Message::ServerAddressChanged(addr) => {
                 self.config.server_addr = addr;
                 self.test_status = TestStatus::Idle;
+                self.terminals.clear();
                 Action::None
             }
             Message::ApiKeyChanged(key) => {
@@ -103,58 +179,287 @@ impl Mt5ConfigModal {
                 self.config.auto_reconnect = auto_reconnect;
                 Action::None
             }
+            Message::PauseWhenClosedChanged(pause) => {
+                self.config.pause_when_closed = pause;
+                Action::None
+            }
+            Message::SyncTerminalChartChanged(sync) => {
+                self.config.sync_terminal_chart = sync;
+                Action::None
+            }
+            Message::TerminalSelected(terminal) => {
+                self.config.terminal_id = Some(terminal.id);
+                self.test_status = TestStatus::Idle;
+                Action::None
+            }
+            Message::AccountCurrencyChanged(currency) => {
+                self.config.account_currency = currency.trim().to_uppercase();
+                Action::None
+            }
+            Message::AuthKindSelected(kind) => {
+                if kind != AuthKind::of(&self.config.auth_mode) {
+                    self.config.auth_mode = match kind {
+                        AuthKind::Hmac => AuthMode::Hmac,
+                        AuthKind::BearerToken => AuthMode::BearerToken(String::new()),
+                        AuthKind::Headers => {
+                            AuthMode::Headers(vec![(String::new(), String::new())])
+                        }
+                    };
+                    self.test_status = TestStatus::Idle;
+                }
+                Action::None
+            }
+            Message::BearerTokenChanged(value) => {
+                if let AuthMode::BearerToken(token) = &mut self.config.auth_mode {
+                    *token = value;
+                }
+                self.test_status = TestStatus::Idle;
+                Action::None
+            }
+            Message::HeaderNameChanged(index, value) => {
+                if let AuthMode::Headers(headers) = &mut self.config.auth_mode
+                    && let Some((name, _)) = headers.get_mut(index)
+                {
+                    *name = value;
+                }
+                self.test_status = TestStatus::Idle;
+                Action::None
+            }
+            Message::HeaderValueChanged(index, value) => {
+                if let AuthMode::Headers(headers) = &mut self.config.auth_mode
+                    && let Some((_, header_value)) = headers.get_mut(index)
+                {
+                    *header_value = value;
+                }
+                self.test_status = TestStatus::Idle;
+                Action::None
+            }
+            Message::AddHeader => {
+                if let AuthMode::Headers(headers) = &mut self.config.auth_mode {
+                    headers.push((String::new(), String::new()));
+                }
+                Action::None
+            }
+            Message::RemoveHeader(index) => {
+                if let AuthMode::Headers(headers) = &mut self.config.auth_mode
+                    && index < headers.len()
+                {
+                    headers.remove(index);
+                }
+                self.test_status = TestStatus::Idle;
+                Action::None
+            }
+            Message::HmacWithHeadersChanged(enabled) => {
+                self.config.hmac_with_headers = enabled;
+                self.test_status = TestStatus::Idle;
+                Action::None
+            }
+            Message::AutoApplyRevisionsChanged(enabled) => {
+                self.revisions.auto_apply = enabled;
+                Action::None
+            }
+            Message::RevisionTicksChanged(value) => {
+                let value = value.trim();
+                if value.is_empty() {
+                    self.revisions.price_ticks = 0;
+                } else if let Ok(ticks) = value.parse() {
+                    self.revisions.price_ticks = ticks;
+                }
+                Action::None
+            }
             Message::TestConnection => {
                 self.test_status = TestStatus::Testing;
                 Action::TestConnection(self.config.clone())
             }
+            Message::RefreshSymbols => {
+                if let Err(e) = self.config.validate() {
+                    self.test_status = TestStatus::Failed(e);
+                    Action::None
+                } else {
+                    Action::RefreshSymbols(self.config.clone())
+                }
+            }
             Message::Save => {
                 if let Err(e) = self.config.validate() {
                     self.test_status = TestStatus::Failed(e);
                     Action::None
                 } else {
-                    Action::SaveConfig(self.config.clone())
+                    Action::SaveConfig(self.config.clone(), self.revisions)
                 }
             }
             Message::Cancel => Action::Exit,
         }
     }
 
+    /// Shows how the connection test ended, unless the config was edited since
+    pub fn set_test_result(&mut self, result: Result<String, String>) {
+        if matches!(self.test_status, TestStatus::Testing) {
+            self.test_status = match result {
+                Ok(message) => TestStatus::Success(message),
+                Err(error) => TestStatus::Failed(error),
+            };
+        }
+    }
+
+    /// Terminals the proxy listed while testing the connection
+    pub fn set_terminals(&mut self, terminals: Vec<Terminal>) {
+        self.terminals = terminals;
+    }
+
     /// Render the modal view
     pub fn view(&self) -> Element<'_, Message> {
         let title = text(if self.is_new {
-            "Add MT5 Connection"
+            t!("mt5.title_new")
         } else {
-            "Edit MT5 Connection"
+            t!("mt5.title_edit")
         })
         .size(18);
 
         // Server address input
         let server_input = labeled_input(
-            "Server Address",
-            "e.g., 192.168.1.100:9876",
+            t!("mt5.server_address"),
+            t!("mt5.server_address_placeholder"),
             &self.config.server_addr,
             Message::ServerAddressChanged,
         );
 
-        // API Key input
-        let api_key_input = labeled_input(
-            "API Key",
-            "Your API key",
-            &self.config.api_key,
-            Message::ApiKeyChanged,
-        );
+        let auth_kind = AuthKind::of(&self.config.auth_mode);
+        let auth_selector = row![
+            text(t!("mt5.authentication")).size(13).width(Length::Fill),
+            pick_list(AuthKind::ALL, Some(auth_kind), Message::AuthKindSelected).text_size(13),
+        ]
+        .align_y(Alignment::Center)
+        .spacing(8);
+
+        let mut credentials = column![].spacing(12);
+
+        match &self.config.auth_mode {
+            AuthMode::Hmac => {}
+            AuthMode::BearerToken(token) => {
+                credentials = credentials.push(labeled_password_input(
+                    t!("mt5.bearer_token"),
+                    t!("mt5.bearer_token_placeholder"),
+                    token,
+                    Message::BearerTokenChanged,
+                ));
+            }
+            AuthMode::Headers(headers) => {
+                let mut rows = column![text(t!("mt5.headers")).size(13)].spacing(4);
+                for (index, (name, value)) in headers.iter().enumerate() {
+                    rows = rows.push(
+                        row![
+                            text_input(t!("mt5.header_name"), name)
+                                .on_input(move |v| Message::HeaderNameChanged(index, v))
+                                .padding(8)
+                                .size(14),
+                            text_input(t!("mt5.header_value"), value)
+                                .on_input(move |v| Message::HeaderValueChanged(index, v))
+                                .secure(true)
+                                .padding(8)
+                                .size(14),
+                            button(text("×").size(13))
+                                .on_press(Message::RemoveHeader(index))
+                                .style(button::secondary),
+                        ]
+                        .spacing(4)
+                        .align_y(Alignment::Center),
+                    );
+                }
+                rows = rows.push(
+                    button(text(t!("mt5.add_header")).size(12))
+                        .on_press(Message::AddHeader)
+                        .style(button::secondary),
+                );
+                credentials = credentials.push(rows);
+            }
+        }
 
-        // API Secret input (password style)
-        let api_secret_input = labeled_password_input(
-            "API Secret",
-            "Your API secret",
-            &self.config.api_secret,
-            Message::ApiSecretChanged,
+        if auth_kind != AuthKind::Hmac {
+            credentials = credentials.push(
+                row![
+                    text(t!("mt5.hmac_with_headers")).width(Length::Fill),
+                    toggler(self.config.hmac_with_headers)
+                        .on_toggle(Message::HmacWithHeadersChanged)
+                        .size(20),
+                ]
+                .align_y(Alignment::Center)
+                .spacing(8),
+            );
+        }
+
+        if self.config.sends_hmac() {
+            // API Key input, read-only when it comes from the environment
+            credentials = credentials.push(
+                match metatrader5::credentials::env_reference(&self.config.api_key) {
+                    Some(name) => {
+                        labeled_env_reference(t!("mt5.api_key"), name, Message::ApiKeyChanged)
+                    }
+                    None => labeled_input(
+                        t!("mt5.api_key"),
+                        t!("mt5.api_key_placeholder"),
+                        &self.config.api_key,
+                        Message::ApiKeyChanged,
+                    ),
+                },
+            );
+
+            // API Secret input (password style)
+            credentials = credentials.push(
+                match metatrader5::credentials::env_reference(&self.config.api_secret) {
+                    Some(name) => {
+                        labeled_env_reference(t!("mt5.api_secret"), name, Message::ApiSecretChanged)
+                    }
+                    None => labeled_password_input(
+                        t!("mt5.api_secret"),
+                        t!("mt5.api_secret_placeholder"),
+                        &self.config.api_secret,
+                        Message::ApiSecretChanged,
+                    ),
+                },
+            );
+        }
+
+        // Proxies bridging several terminals list them once the connection is tested
+        let terminal_picker: Option<Element<'_, Message>> = if self.terminals.is_empty() {
+            self.config
+                .terminal_id
+                .as_deref()
+                .map(|id| text(t!("mt5.terminal_saved", id = id)).size(12).into())
+        } else {
+            let selected = self
+                .terminals
+                .iter()
+                .find(|terminal| self.config.terminal_id.as_ref() == Some(&terminal.id))
+                .cloned();
+
+            Some(
+                row![
+                    text(t!("mt5.terminal")).size(13).width(Length::Fill),
+                    pick_list(
+                        self.terminals.as_slice(),
+                        selected,
+                        Message::TerminalSelected
+                    )
+                    .placeholder(t!("mt5.terminal_placeholder"))
+                    .text_size(13),
+                ]
+                .align_y(Alignment::Center)
+                .spacing(8)
+                .into(),
+            )
+        };
+
+        // Account currency input, sizes in quote currency convert into it
+        let account_currency_input = labeled_input(
+            t!("mt5.account_currency"),
+            t!("mt5.account_currency_placeholder"),
+            &self.config.account_currency,
+            Message::AccountCurrencyChanged,
         );
 
         // TLS toggle
         let tls_toggle = row![
-            text("Use TLS (Recommended)").width(Length::Fill),
+            text(t!("mt5.use_tls")).width(Length::Fill),
             toggler(self.config.use_tls)
                 .on_toggle(Message::UseTlsChanged)
                 .size(20),
@@ -164,7 +469,7 @@ impl Mt5ConfigModal {
 
         // Auto reconnect toggle
         let reconnect_toggle = row![
-            text("Auto Reconnect").width(Length::Fill),
+            text(t!("mt5.auto_reconnect")).width(Length::Fill),
             toggler(self.config.auto_reconnect)
                 .on_toggle(Message::AutoReconnectChanged)
                 .size(20),
@@ -172,10 +477,47 @@ impl Mt5ConfigModal {
         .align_y(Alignment::Center)
         .spacing(8);
 
+        // Closed markets drop to quotes until shortly before they reopen
+        let pause_toggle = row![
+            text(t!("mt5.pause_when_closed")).width(Length::Fill),
+            toggler(self.config.pause_when_closed)
+                .on_toggle(Message::PauseWhenClosedChanged)
+                .size(20),
+        ]
+        .align_y(Alignment::Center)
+        .spacing(8);
+
+        // Opt-in, the terminal's chart is otherwise left to whoever trades on it
+        let sync_chart_toggle = row![
+            text(t!("mt5.sync_terminal_chart")).width(Length::Fill),
+            toggler(self.config.sync_terminal_chart)
+                .on_toggle(Message::SyncTerminalChartChanged)
+                .size(20),
+        ]
+        .align_y(Alignment::Center)
+        .spacing(8);
+
+        // Bars the broker repainted after the chart got them
+        let revisions_toggle = row![
+            text(t!("mt5.apply_revisions")).width(Length::Fill),
+            toggler(self.revisions.auto_apply)
+                .on_toggle(Message::AutoApplyRevisionsChanged)
+                .size(20),
+        ]
+        .align_y(Alignment::Center)
+        .spacing(8);
+
+        let revision_ticks_input = labeled_input(
+            t!("mt5.revision_ticks"),
+            t!("mt5.revision_ticks_placeholder"),
+            &self.revisions.price_ticks.to_string(),
+            Message::RevisionTicksChanged,
+        );
+
         // Test status display
         let test_status = match &self.test_status {
             TestStatus::Idle => text("").size(12),
-            TestStatus::Testing => text("Testing connection...").size(12),
+            TestStatus::Testing => text(t!("mt5.testing")).size(12),
             TestStatus::Success(msg) => text(format!("✓ {}", msg))
                 .size(12)
                 .color(iced::Color::from_rgb(0.2, 0.8, 0.2)),
@@ -184,21 +526,50 @@ impl Mt5ConfigModal {
                 .color(iced::Color::from_rgb(0.9, 0.3, 0.3)),
         };
 
+        // What the proxy reported at its latest handshake, or in an earlier session
+        let versions = metatrader5::server_info(&self.config.server_addr).map(|info| {
+            let mut versions = column![text(versions_text(&info)).size(12)].spacing(2);
+            if info.is_outdated() {
+                versions = versions.push(
+                    text(t!(
+                        "mt5.version_outdated",
+                        required = metatrader5::MIN_PROXY_VERSION
+                    ))
+                    .size(12)
+                    .color(iced::Color::from_rgb(0.9, 0.6, 0.2)),
+                );
+            }
+            versions
+        });
+
         // Buttons
-        let test_btn = button(text("Test Connection").size(13))
+        let test_btn = button(text(t!("mt5.test_connection")).size(13))
             .on_press(Message::TestConnection)
             .style(button::secondary);
 
-        let cancel_btn = button(text("Cancel").size(13))
+        let refreshing = metatrader5::refresh_in_progress(&self.config);
+        let refresh_btn = button(
+            text(if refreshing {
+                t!("mt5.refreshing")
+            } else {
+                t!("mt5.refresh_symbols")
+            })
+            .size(13),
+        )
+        .on_press_maybe((!refreshing).then_some(Message::RefreshSymbols))
+        .style(button::secondary);
+
+        let cancel_btn = button(text(t!("common.cancel")).size(13))
             .on_press(Message::Cancel)
             .style(button::secondary);
 
-        let save_btn = button(text("Save").size(13))
+        let save_btn = button(text(t!("common.save")).size(13))
             .on_press(Message::Save)
             .style(button::primary);
 
         let buttons = row![
             test_btn,
+            refresh_btn,
             iced::widget::Space::new().width(Length::Fill),
             cancel_btn,
             save_btn,
@@ -210,13 +581,20 @@ impl Mt5ConfigModal {
             title,
             iced::widget::Space::new().height(16),
             server_input,
-            api_key_input,
-            api_secret_input,
+            auth_selector,
+            credentials,
+            terminal_picker,
+            account_currency_input,
             iced::widget::Space::new().height(8),
             tls_toggle,
             reconnect_toggle,
+            pause_toggle,
+            sync_chart_toggle,
+            revisions_toggle,
+            revision_ticks_input,
             iced::widget::Space::new().height(8),
             test_status,
+            versions,
             iced::widget::Space::new().height(16),
             buttons,
         ]
@@ -236,11 +614,24 @@ impl Default for Mt5ConfigModal {
     }
 }
 
+/// Proxy and EA versions, the ones a proxy left out shown as unknown
+pub fn versions_text(info: &ServerInfo) -> String {
+    t!(
+        "mt5.versions",
+        proxy = version_label(info.proxy_version.as_deref()),
+        ea = version_label(info.ea_version.as_deref())
+    )
+}
+
+pub fn version_label(version: Option<&str>) -> &str {
+    version.unwrap_or(t!("mt5.version_unknown"))
+}
+
 /// Helper: Create a labeled text input
 fn labeled_input<'a>(
     label: &'a str,
     placeholder: &'a str,
-    value: &'a str,
+    value: &str,
     on_input: impl Fn(String) -> Message + 'a,
 ) -> Element<'a, Message> {
     column![
@@ -254,6 +645,29 @@ fn labeled_input<'a>(
     .into()
 }
 
+/// Helper: A credential read from an environment variable, cleared to enter one by hand
+fn labeled_env_reference<'a>(
+    label: &'a str,
+    name: &str,
+    on_input: impl Fn(String) -> Message + 'a,
+) -> Element<'a, Message> {
+    column![
+        text(label).size(13),
+        row![
+            text(t!("mt5.from_env", name = name))
+                .size(14)
+                .width(Length::Fill),
+            button(text("×").size(13))
+                .on_press(on_input(String::new()))
+                .style(button::secondary),
+        ]
+        .spacing(4)
+        .align_y(Alignment::Center),
+    ]
+    .spacing(4)
+    .into()
+}
+
 /// Helper: Create a labeled password input
 fn labeled_password_input<'a>(
     label: &'a str,
@@ -0,0 +1,198 @@
+use crate::TooltipPosition;
+use crate::style::{self, Icon, icon_text};
+use crate::widget::tooltip;
+use data::NotificationLog;
+use data::notifications::Severity;
+use data::t;
+
+use iced::widget::{button, checkbox, column, container, pick_list, row, scrollable, space, text};
+use iced::{Alignment, Element, Length, Theme};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    FilterSelected(SeverityFilter),
+    CopyDetails(usize),
+    Clear,
+    ToggleSoundOnError(bool),
+}
+
+pub enum Action {
+    CopyToClipboard(String),
+}
+
+/// Lowest severity shown, `All` shows every record
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeverityFilter {
+    #[default]
+    All,
+    AtLeast(Severity),
+}
+
+impl SeverityFilter {
+    const ALL: [SeverityFilter; 3] = [
+        SeverityFilter::All,
+        SeverityFilter::AtLeast(Severity::Warning),
+        SeverityFilter::AtLeast(Severity::Error),
+    ];
+
+    fn min_severity(self) -> Option<Severity> {
+        match self {
+            SeverityFilter::All => None,
+            SeverityFilter::AtLeast(severity) => Some(severity),
+        }
+    }
+}
+
+impl std::fmt::Display for SeverityFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SeverityFilter::All => f.write_str(t!("notifications.filter_all")),
+            SeverityFilter::AtLeast(Severity::Info) => f.write_str(t!("notifications.filter_info")),
+            SeverityFilter::AtLeast(Severity::Warning) => {
+                f.write_str(t!("notifications.filter_warnings"))
+            }
+            SeverityFilter::AtLeast(Severity::Error) => {
+                f.write_str(t!("notifications.filter_errors"))
+            }
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct NotificationCenter {
+    filter: SeverityFilter,
+    /// Records that were unread when the center was opened, highlighted until it's reopened
+    highlighted: usize,
+}
+
+impl NotificationCenter {
+    /// Marks everything read, keeping what was new highlighted while the center is open
+    pub fn open(&mut self, log: &mut NotificationLog) {
+        self.highlighted = log.unread();
+        log.mark_read();
+    }
+
+    pub fn update(&mut self, message: Message, log: &mut NotificationLog) -> Option<Action> {
+        match message {
+            Message::FilterSelected(filter) => self.filter = filter,
+            Message::CopyDetails(index) => {
+                return log
+                    .get(index)
+                    .map(|record| Action::CopyToClipboard(record.details()));
+            }
+            Message::Clear => {
+                log.clear();
+                self.highlighted = 0;
+            }
+            Message::ToggleSoundOnError(enabled) => log.sound_on_error = enabled,
+        }
+        None
+    }
+
+    pub fn view<'a>(&'a self, log: &'a NotificationLog) -> Element<'a, Message> {
+        let header = row![
+            text(t!("notifications.title")).size(14),
+            space::horizontal(),
+            pick_list(
+                SeverityFilter::ALL,
+                Some(self.filter),
+                Message::FilterSelected
+            )
+            .text_size(12),
+            button(text(t!("common.clear")).size(12))
+                .on_press(Message::Clear)
+                .style(move |t, s| style::button::transparent(t, s, false)),
+        ]
+        .spacing(8)
+        .align_y(Alignment::Center);
+
+        let mut records = column![].spacing(4);
+        let mut is_empty = true;
+
+        for (index, record) in log.filtered(self.filter.min_severity()) {
+            is_empty = false;
+
+            let time = chrono::DateTime::from_timestamp_millis(record.time as i64)
+                .map(|t| {
+                    t.with_timezone(&chrono::Local)
+                        .format("%m-%d %H:%M:%S")
+                        .to_string()
+                })
+                .unwrap_or_default();
+
+            let severity = record.severity;
+            let is_unread = log.is_among_newest(index, self.highlighted);
+
+            let copy_btn = tooltip(
+                button(icon_text(Icon::Clone, 11))
+                    .on_press(Message::CopyDetails(index))
+                    .style(move |t, s| style::button::transparent(t, s, false)),
+                Some(t!("notifications.copy_details")),
+                TooltipPosition::Left,
+            );
+
+            let entry = column![
+                row![
+                    text(severity.to_string())
+                        .size(11)
+                        .style(move |theme: &Theme| severity_text(theme, severity)),
+                    text(record.source.as_str()).size(11),
+                    space::horizontal(),
+                    text(time).size(11).style(style::muted_text),
+                    copy_btn,
+                ]
+                .spacing(6)
+                .align_y(Alignment::Center),
+                text(record.message.as_str()).size(12),
+            ]
+            .spacing(2);
+
+            records = records.push(container(entry).padding(6).width(Length::Fill).style(
+                move |theme: &Theme| {
+                    let mut style = style::modal_container(theme);
+                    if is_unread {
+                        style.border.color = theme.extended_palette().primary.base.color;
+                        style.border.width = 1.0;
+                    }
+                    style
+                },
+            ));
+        }
+
+        let list: Element<_> = if is_empty {
+            text(t!("notifications.empty")).size(12).into()
+        } else {
+            scrollable::Scrollable::with_direction(
+                records,
+                scrollable::Direction::Vertical(
+                    scrollable::Scrollbar::new().width(8).scroller_width(6),
+                ),
+            )
+            .height(Length::Fixed(420.0))
+            .into()
+        };
+
+        let sound_checkbox = checkbox(log.sound_on_error)
+            .label(t!("notifications.sound_on_error"))
+            .on_toggle(Message::ToggleSoundOnError)
+            .text_size(12);
+
+        container(column![header, list, sound_checkbox].spacing(12))
+            .width(Length::Fixed(380.0))
+            .padding(16)
+            .style(style::dashboard_modal)
+            .into()
+    }
+}
+
+fn severity_text(theme: &Theme, severity: Severity) -> text::Style {
+    let palette = theme.extended_palette();
+
+    text::Style {
+        color: Some(match severity {
+            Severity::Info => palette.primary.base.color,
+            Severity::Warning => palette.warning.base.color,
+            Severity::Error => palette.danger.base.color,
+        }),
+    }
+}
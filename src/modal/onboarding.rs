@@ -0,0 +1,255 @@
+//! First-run setup: the exchanges to list, an optional MT5 connection and a starter layout.
+//!
+//! Shown when the saved state has neither layouts nor MT5 connections, and from the settings
+//! afterwards. Any step can be skipped, what was skipped keeps its default.
+
+use super::mt5_config::{self, Mt5ConfigModal};
+use crate::screen::dashboard::tickers_table::EXCHANGE_FILTERS;
+use crate::style::{self, Icon, icon_text};
+use data::chart::revision::RevisionSettings;
+use data::layout::template::LayoutTemplate;
+use data::t;
+use exchange::adapter::ExchangeInclusive;
+use exchange::adapter::metatrader5::Mt5Config;
+
+use iced::widget::{button, checkbox, column, container, row, space, text};
+use iced::{Alignment, Element, Length};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Exchanges,
+    Mt5,
+    Layout,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    ToggleExchange(ExchangeInclusive, bool),
+    Mt5(mt5_config::Message),
+    SelectTemplate(LayoutTemplate),
+    Back,
+    Next,
+    /// Moves on without applying the current step
+    SkipStep,
+    /// Closes the wizard, applying nothing
+    SkipAll,
+}
+
+pub enum Action {
+    TestConnection(Mt5Config),
+    RefreshSymbols(Mt5Config),
+    Finish(Setup),
+    Close,
+}
+
+/// What the wizard ended with, `None` for each step that was skipped
+#[derive(Debug, Clone, Default)]
+pub struct Setup {
+    pub exchanges: Option<Vec<ExchangeInclusive>>,
+    pub mt5: Option<(Mt5Config, RevisionSettings)>,
+    pub template: Option<LayoutTemplate>,
+}
+
+pub struct Onboarding {
+    step: Step,
+    exchanges: Vec<ExchangeInclusive>,
+    mt5: Mt5ConfigModal,
+    template: LayoutTemplate,
+    setup: Setup,
+}
+
+impl Onboarding {
+    /// `exchanges` are those listed so far, `revisions` the MT5 settings in use
+    pub fn new(
+        exchanges: impl IntoIterator<Item = ExchangeInclusive>,
+        revisions: RevisionSettings,
+    ) -> Self {
+        let exchanges: Vec<_> = exchanges.into_iter().collect();
+
+        Self {
+            step: Step::Exchanges,
+            exchanges: if exchanges.is_empty() {
+                ExchangeInclusive::ALL.to_vec()
+            } else {
+                exchanges
+            },
+            mt5: Mt5ConfigModal::new().with_revisions(revisions),
+            template: LayoutTemplate::Candlestick,
+            setup: Setup::default(),
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Action> {
+        match message {
+            Message::ToggleExchange(exchange, enabled) => {
+                self.exchanges.retain(|e| *e != exchange);
+                if enabled {
+                    self.exchanges.push(exchange);
+                }
+            }
+            Message::Mt5(message) => match self.mt5.update(message) {
+                mt5_config::Action::SaveConfig(config, revisions) => {
+                    self.setup.mt5 = Some((config, revisions));
+                    self.step = Step::Layout;
+                }
+                mt5_config::Action::Exit => return self.update(Message::SkipStep),
+                mt5_config::Action::TestConnection(config) => {
+                    return Some(Action::TestConnection(config));
+                }
+                mt5_config::Action::RefreshSymbols(config) => {
+                    return Some(Action::RefreshSymbols(config));
+                }
+                mt5_config::Action::None => {}
+            },
+            Message::SelectTemplate(template) => self.template = template,
+            Message::Back => {
+                self.step = match self.step {
+                    Step::Exchanges | Step::Mt5 => Step::Exchanges,
+                    Step::Layout if self.wants_mt5() => Step::Mt5,
+                    Step::Layout => Step::Exchanges,
+                };
+            }
+            Message::Next => match self.step {
+                Step::Exchanges => {
+                    self.setup.exchanges = Some(self.exchanges.clone());
+                    self.step = self.after_exchanges();
+                }
+                // Saving validates the connection first, and moves on if it's valid
+                Step::Mt5 => return self.update(Message::Mt5(mt5_config::Message::Save)),
+                Step::Layout => {
+                    self.setup.template = Some(self.template);
+                    return Some(Action::Finish(std::mem::take(&mut self.setup)));
+                }
+            },
+            Message::SkipStep => match self.step {
+                Step::Exchanges => {
+                    self.setup.exchanges = None;
+                    self.step = self.after_exchanges();
+                }
+                Step::Mt5 => {
+                    self.setup.mt5 = None;
+                    self.step = Step::Layout;
+                }
+                Step::Layout => {
+                    self.setup.template = None;
+                    return Some(Action::Finish(std::mem::take(&mut self.setup)));
+                }
+            },
+            Message::SkipAll => return Some(Action::Close),
+        }
+
+        None
+    }
+
+    /// Shows how the connection test ended, see [`Mt5ConfigModal::set_test_result`]
+    pub fn set_test_result(&mut self, result: Result<String, String>) {
+        self.mt5.set_test_result(result);
+    }
+
+    fn wants_mt5(&self) -> bool {
+        self.exchanges.contains(&ExchangeInclusive::MetaTrader5)
+    }
+
+    fn after_exchanges(&mut self) -> Step {
+        if self.wants_mt5() {
+            Step::Mt5
+        } else {
+            // MT5 was unticked after going back
+            self.setup.mt5 = None;
+            Step::Layout
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let (title, hint) = match self.step {
+            Step::Exchanges => (
+                t!("onboarding.exchanges_title"),
+                t!("onboarding.exchanges_hint"),
+            ),
+            Step::Mt5 => (t!("onboarding.mt5_title"), t!("onboarding.mt5_hint")),
+            Step::Layout => (t!("onboarding.layout_title"), t!("onboarding.layout_hint")),
+        };
+
+        let body: Element<'_, Message> = match self.step {
+            Step::Exchanges => column(EXCHANGE_FILTERS.iter().map(|(exchange, logo, label)| {
+                let exchange = *exchange;
+                row![
+                    icon_text(style::exchange_icon(*logo), 12),
+                    checkbox(self.exchanges.contains(&exchange))
+                        .label(*label)
+                        .on_toggle(move |enabled| Message::ToggleExchange(exchange, enabled)),
+                ]
+                .spacing(8)
+                .align_y(Alignment::Center)
+                .into()
+            }))
+            .spacing(8)
+            .into(),
+            Step::Mt5 => self.mt5.view().map(Message::Mt5),
+            Step::Layout => column(LayoutTemplate::ALL.map(|template| {
+                let selected = template == self.template;
+                let content = if selected {
+                    row![
+                        text(template.name()),
+                        space::horizontal(),
+                        icon_text(Icon::Checkmark, 12)
+                    ]
+                } else {
+                    row![text(template.name())]
+                };
+
+                button(content.width(Length::Fill))
+                    .width(Length::Fill)
+                    .style(move |theme, status| style::button::modifier(theme, status, selected))
+                    .on_press(Message::SelectTemplate(template))
+                    .into()
+            }))
+            .spacing(4)
+            .into(),
+        };
+
+        let back_btn = button(text(t!("onboarding.back")).size(13))
+            .on_press_maybe((self.step != Step::Exchanges).then_some(Message::Back))
+            .style(button::secondary);
+        let skip_all_btn = button(text(t!("onboarding.skip_all")).size(13))
+            .on_press(Message::SkipAll)
+            .style(button::secondary);
+        let skip_btn = button(text(t!("onboarding.skip_step")).size(13))
+            .on_press(Message::SkipStep)
+            .style(button::secondary);
+        let next_btn = button(
+            text(if self.step == Step::Layout {
+                t!("onboarding.finish")
+            } else {
+                t!("onboarding.next")
+            })
+            .size(13),
+        )
+        .on_press_maybe(
+            (self.step != Step::Exchanges || !self.exchanges.is_empty()).then_some(Message::Next),
+        )
+        .style(button::primary);
+
+        let content = column![
+            text(title).size(18),
+            text(hint).size(13),
+            body,
+            row![
+                back_btn,
+                skip_all_btn,
+                space::horizontal(),
+                skip_btn,
+                next_btn
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center),
+        ]
+        .spacing(16)
+        .max_width(460);
+
+        container(content)
+            .padding(24)
+            .style(style::dashboard_modal)
+            .into()
+    }
+}
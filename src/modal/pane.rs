@@ -5,6 +5,7 @@ use iced::{
 
 pub mod indicators;
 pub mod mini_tickers_list;
+pub mod position_size;
 pub mod settings;
 pub mod stream;
 
@@ -14,8 +15,18 @@ pub enum Modal {
     MiniTickersList(mini_tickers_list::MiniPanel),
     Settings,
     Indicators,
-    LinkGroup,
+    /// `sync_timeframe` mirrors the dashboard's setting for the pane's group while open
+    LinkGroup {
+        sync_timeframe: bool,
+    },
     Controls,
+    PositionSize,
+    /// Picks the connection the pane streams from
+    DataSource,
+    /// Picks the price an MT5 chart is drawn from
+    PriceBasis,
+    /// Asks whether a pane with drawings is cloned with them
+    Clone,
 }
 
 pub fn stack_modal<'a, Message>(
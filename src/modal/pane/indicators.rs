@@ -1,25 +1,32 @@
 use crate::screen::dashboard::pane::{self, Message};
 use crate::style::{self, Icon, icon_text};
-use crate::widget::{column_drag, dragger_row};
+use crate::widget::{column_drag, dragger_row, tooltip};
 
 use data::chart::indicator::{Indicator, UiIndicator};
+use exchange::{TickerInfo, adapter::Capabilities};
 use iced::{
     Element, Length, padding,
-    widget::{button, column, container, pane_grid, row, space, text},
+    widget::{button, column, container, pane_grid, row, space, text, tooltip::Position},
 };
 
 pub fn view<'a, I>(
     pane: pane_grid::Pane,
     state: &'a pane::State,
     selected: &[I],
-    market_type: Option<exchange::adapter::MarketKind>,
+    ticker_info: Option<TickerInfo>,
 ) -> Element<'a, Message>
 where
     I: Indicator + Copy + Into<UiIndicator>,
 {
     let content_allows_dragging = matches!(state.content, pane::Content::Kline { .. });
-    let content_row = if let Some(market) = market_type {
-        content_row(pane, selected, market, content_allows_dragging)
+    let content_row = if let Some(ticker_info) = ticker_info {
+        content_row(
+            pane,
+            selected,
+            ticker_info.market_type(),
+            ticker_info.capabilities(),
+            content_allows_dragging,
+        )
     } else {
         column![].spacing(4).into()
     };
@@ -91,15 +98,30 @@ where
     }
 }
 
-fn available_list<'a, I>(pane: pane_grid::Pane, available: &[I]) -> Element<'a, Message>
+fn available_list<'a, I>(
+    pane: pane_grid::Pane,
+    available: &[I],
+    capabilities: Capabilities,
+) -> Element<'a, Message>
 where
     I: Indicator + Copy + Into<UiIndicator>,
 {
     let elements: Vec<Element<_>> = available
         .iter()
         .map(|indicator| {
-            let base = build_indicator_row(pane, indicator, false);
-            dragger_row(base, false)
+            if indicator.is_supported(capabilities) {
+                let base = build_indicator_row(pane, indicator, false);
+                dragger_row(base, false)
+            } else {
+                let base = button(row![text(indicator.to_string())].width(Length::Fill))
+                    .width(Length::Fill)
+                    .style(move |theme, status| style::button::modifier(theme, status, false));
+                tooltip(
+                    dragger_row(base.into(), false),
+                    Some("Not provided by this exchange"),
+                    Position::Left,
+                )
+            }
         })
         .collect();
 
@@ -112,6 +134,7 @@ fn content_row<'a, I>(
     pane: pane_grid::Pane,
     selected: &[I],
     market: exchange::adapter::MarketKind,
+    capabilities: Capabilities,
     allows_drag: bool,
 ) -> Element<'a, Message>
 where
@@ -131,7 +154,7 @@ where
         .cloned()
         .collect();
     let available_list = if !available.is_empty() {
-        Some(available_list(pane, &available))
+        Some(available_list(pane, &available, capabilities))
     } else {
         None
     };
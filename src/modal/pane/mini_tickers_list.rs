@@ -43,6 +43,14 @@ impl MiniPanel {
         }
     }
 
+    /// Opens with the search already filled in, e.g. to pick between candidate symbols
+    pub fn with_query(query: &str) -> Self {
+        Self {
+            search_query: query.to_uppercase(),
+            ..Self::new()
+        }
+    }
+
     pub fn update(&mut self, message: Message) -> Option<Action> {
         match message {
             Message::SearchChanged(q) => self.search_query = q.to_uppercase(),
@@ -0,0 +1,259 @@
+use crate::style;
+
+use data::chart::position_size::{self, Bracket, Risk, SymbolSpec};
+use exchange::TickerInfo;
+use exchange::adapter::Exchange;
+use exchange::adapter::metatrader5::rates::{self, AccountValue};
+use exchange::util::Price;
+use iced::{
+    Alignment, Element, Length, Theme,
+    widget::{column, container, radio, row, rule, text, text_input},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RiskMode {
+    Amount,
+    Percent,
+}
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    EntryChanged(String),
+    StopChanged(String),
+    RiskChanged(String),
+    RiskModeSelected(RiskMode),
+    EquityChanged(String),
+    QuoteRateChanged(String),
+    TickValueChanged(String),
+}
+
+/// Inputs of the position size calculator, kept as typed so partial edits survive
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calculator {
+    entry: String,
+    stop: String,
+    risk: String,
+    risk_mode: RiskMode,
+    equity: String,
+    quote_rate: String,
+    tick_value: String,
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Self {
+            entry: String::new(),
+            stop: String::new(),
+            risk: "1".to_string(),
+            risk_mode: RiskMode::Percent,
+            equity: String::new(),
+            quote_rate: "1".to_string(),
+            tick_value: String::new(),
+        }
+    }
+}
+
+fn parse(value: &str) -> Option<f64> {
+    value.trim().replace(',', "").parse::<f64>().ok()
+}
+
+impl Calculator {
+    pub fn set_bracket(&mut self, entry: Price, stop: Price, ticker_info: Option<TickerInfo>) {
+        let format = |price: Price| match ticker_info {
+            Some(info) => price.to_string(info.min_ticksize),
+            None => price.to_f32().to_string(),
+        };
+
+        self.entry = format(entry);
+        self.stop = format(stop);
+    }
+
+    /// Entry and stop as typed, for drawing on the chart
+    pub fn bracket_prices(&self) -> Option<(Price, Price)> {
+        let entry = parse(&self.entry)?;
+        let stop = parse(&self.stop)?;
+
+        Some((Price::from_f32(entry as f32), Price::from_f32(stop as f32)))
+    }
+
+    pub fn update(&mut self, message: Message) {
+        match message {
+            Message::EntryChanged(value) => self.entry = value,
+            Message::StopChanged(value) => self.stop = value,
+            Message::RiskChanged(value) => self.risk = value,
+            Message::RiskModeSelected(mode) => self.risk_mode = mode,
+            Message::EquityChanged(value) => self.equity = value,
+            Message::QuoteRateChanged(value) => self.quote_rate = value,
+            Message::TickValueChanged(value) => self.tick_value = value,
+        }
+    }
+
+    fn bracket(&self) -> Option<Bracket> {
+        let risk = parse(&self.risk)?;
+        let risk = match self.risk_mode {
+            RiskMode::Amount => Risk::Amount(risk),
+            RiskMode::Percent => Risk::Percent {
+                percent: risk,
+                equity: parse(&self.equity)?,
+            },
+        };
+
+        Some(Bracket {
+            entry: parse(&self.entry)?,
+            stop: parse(&self.stop)?,
+            risk,
+            quote_to_account: parse(&self.quote_rate).unwrap_or(1.0),
+        })
+    }
+
+    pub fn view(&self, ticker_info: Option<TickerInfo>) -> Element<'_, Message> {
+        let input = |label: &'static str, value: &str, on_input: fn(String) -> Message| {
+            column![
+                text(label).size(12),
+                text_input("", value).on_input(on_input).padding(6).size(13),
+            ]
+            .spacing(2)
+        };
+
+        let risk_mode = row![
+            radio(
+                "Amount",
+                RiskMode::Amount,
+                Some(self.risk_mode),
+                Message::RiskModeSelected
+            )
+            .size(12)
+            .text_size(12),
+            radio(
+                "% of equity",
+                RiskMode::Percent,
+                Some(self.risk_mode),
+                Message::RiskModeSelected
+            )
+            .size(12)
+            .text_size(12),
+        ]
+        .spacing(12);
+
+        let mut risk_inputs = row![input("Risk", &self.risk, Message::RiskChanged)].spacing(8);
+        if self.risk_mode == RiskMode::Percent {
+            risk_inputs = risk_inputs.push(input("Equity", &self.equity, Message::EquityChanged));
+        }
+
+        // A typed tick value wins over the one MT5 converts live
+        let typed = parse(&self.tick_value);
+        let converted = ticker_info
+            .filter(|info| typed.is_none() && info.exchange() == Exchange::MetaTrader5)
+            .and_then(|info| rates::tick_value(&info.ticker));
+
+        let spec = ticker_info.as_ref().and_then(SymbolSpec::from_ticker_info);
+        let spec = spec.map(|spec| SymbolSpec {
+            tick_value: typed.or(converted.map(|converted| converted.value)),
+            ..spec
+        });
+
+        let result: Element<_> = match self.bracket() {
+            None if spec.is_none() => {
+                text(position_size::SizingError::InsufficientSymbolInfo.to_string())
+                    .size(12)
+                    .style(warning_text)
+                    .into()
+            }
+            None => text("Enter entry, stop and risk").size(12).into(),
+            Some(bracket) => match position_size::calculate(spec, bracket) {
+                Ok(size) => {
+                    let mut col = column![
+                        text(format!("Size: {} lots", trim(size.lots))).size(14),
+                        text(format!(
+                            "Stop: {} ticks, {} per tick",
+                            size.stop_ticks,
+                            trim(size.risk_per_tick)
+                        ))
+                        .size(12),
+                        text(format!("Risk at stop: {}", trim(size.risk_at_stop))).size(12),
+                    ]
+                    .spacing(4);
+
+                    for (r, price) in size.targets {
+                        col = col.push(text(format!("{r}R: {}", trim(price))).size(12));
+                    }
+                    col.into()
+                }
+                Err(err) => text(err.to_string()).size(12).style(warning_text).into(),
+            },
+        };
+
+        let content = column![
+            text("Position size").size(14),
+            row![
+                input("Entry", &self.entry, Message::EntryChanged),
+                input("Stop", &self.stop, Message::StopChanged),
+            ]
+            .spacing(8),
+            risk_mode,
+            risk_inputs,
+            row![
+                input(
+                    "Quote to account rate",
+                    &self.quote_rate,
+                    Message::QuoteRateChanged
+                ),
+                input(
+                    "Tick value (optional)",
+                    &self.tick_value,
+                    Message::TickValueChanged
+                ),
+            ]
+            .spacing(8),
+            converted_hint(converted),
+            rule::horizontal(1),
+            result,
+            text("Press P on the chart, then click entry and stop")
+                .size(11)
+                .style(style::muted_text),
+        ]
+        .spacing(10)
+        .align_x(Alignment::Start);
+
+        container(content)
+            .width(Length::Fixed(320.0))
+            .padding(16)
+            .style(style::chart_modal)
+            .into()
+    }
+}
+
+/// Where the tick value in use came from, when it wasn't typed
+fn converted_hint<'a>(converted: Option<AccountValue>) -> Option<Element<'a, Message>> {
+    let AccountValue { value, stale } = converted?;
+
+    Some(if stale {
+        text(format!(
+            "Tick value {} as the broker last reported, no live rate",
+            trim(value)
+        ))
+        .size(11)
+        .style(warning_text)
+        .into()
+    } else {
+        text(format!("Tick value {} (live)", trim(value)))
+            .size(11)
+            .style(style::muted_text)
+            .into()
+    })
+}
+
+/// Drops float noise and trailing zeros for display
+fn trim(value: f64) -> String {
+    let formatted = format!("{value:.5}");
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn warning_text(theme: &Theme) -> text::Style {
+    text::Style {
+        color: Some(theme.extended_palette().warning.base.color),
+    }
+}
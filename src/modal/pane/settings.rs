@@ -2,26 +2,50 @@ use crate::chart::comparison::ComparisonChart;
 use crate::screen::dashboard::pane::{Event, Message};
 use crate::screen::dashboard::panel::timeandsales;
 use crate::split_column;
+use crate::style::{Icon, icon_text};
 use crate::widget::{classic_slider_row, labeled_slider};
 use crate::{style, tooltip, widget::scrollable_content};
 
+use data::aggr::sweep;
+use data::chart::PriceScale;
+use data::chart::bar_close::BarCloseAlert;
+use data::chart::calendar::CalendarConfig;
+use data::chart::data_window::{Corner, DataWindow};
 use data::chart::heatmap::HeatmapStudy;
 use data::chart::kline::FootprintStudy;
+use data::chart::pattern::{PatternConfig, PatternToggle};
+use data::chart::session::ReferenceLines;
+use data::chart::spread_alert::{SpreadAlert, SpreadLimit};
+use data::chart::stop_run::{Sensitivity, StopRunConfig};
+use data::chart::strip;
+use data::chart::trade_size;
+use data::chart::volume_curve::VolumeCurveConfig;
 use data::chart::{
     KlineChartKind,
-    heatmap::{self, CoalesceKind},
+    heatmap::{
+        self, CoalesceKind, DepthColor, history,
+        intensity::{self, Intensity},
+        wall::{WallConfig, WallThreshold},
+    },
     kline::ClusterKind,
 };
+use data::indicators::{
+    Overlay, script,
+    signal::{self, SignalSetup},
+};
 use data::layout::pane::VisualConfig;
 use data::panel::ladder;
 use data::panel::timeandsales::{StackedBar, StackedBarRatio};
 use data::util::format_with_commas;
+use exchange::adapter::Capabilities;
+use exchange::calendar::Importance;
+use exchange::{SizeUnit, Timeframe};
 
 use iced::widget::{checkbox, space};
 use iced::{
-    Alignment, Element, Length,
+    Alignment, Element, Length, padding,
     widget::{
-        button, column, container, pane_grid, pick_list, radio, row, slider, text,
+        button, column, container, pane_grid, pick_list, radio, row, slider, text, text_input,
         tooltip::Position as TooltipPosition,
     },
 };
@@ -45,6 +69,9 @@ pub fn heatmap_cfg_view<'a>(
     study_config: &'a study::Configurator<HeatmapStudy>,
     studies: &'a [HeatmapStudy],
     basis: data::chart::Basis,
+    size_unit: Option<SizeUnit>,
+    data_window: DataWindow,
+    spread_alert: Option<Element<'a, Message>>,
 ) -> Element<'a, Message> {
     let trade_size_slider = {
         let filter = cfg.trade_size_filter;
@@ -196,12 +223,135 @@ pub fn heatmap_cfg_view<'a>(
         None
     };
 
+    let auto_trade_size = {
+        let auto_checkbox = checkbox(cfg.auto_trade_size.is_some())
+            .label("Auto trade filter from recent trades")
+            .on_toggle(move |value| {
+                Message::VisualConfigChanged(
+                    pane,
+                    VisualConfig::Heatmap(heatmap::Config {
+                        auto_trade_size: value.then_some(trade_size::DEFAULT_PERCENTILE),
+                        ..cfg
+                    }),
+                    false,
+                )
+            });
+
+        let percentile_slider = cfg.auto_trade_size.map(|percentile| {
+            classic_slider_row(
+                text("Percentile"),
+                slider(50..=99, percentile, move |value| {
+                    Message::VisualConfigChanged(
+                        pane,
+                        VisualConfig::Heatmap(heatmap::Config {
+                            auto_trade_size: Some(value),
+                            ..cfg
+                        }),
+                        false,
+                    )
+                })
+                .into(),
+                Some(text(format!("p{percentile}")).size(13)),
+            )
+        });
+
+        column![auto_checkbox].push(percentile_slider).spacing(8)
+    };
+
     let size_filters_column = column![
         text("Size filters").size(14),
-        column![trade_size_slider, order_size_slider].spacing(8),
+        column![trade_size_slider, auto_trade_size, order_size_slider].spacing(8),
     ]
     .spacing(8);
 
+    let depth_color_column = {
+        let with_color = move |depth_color| {
+            Message::VisualConfigChanged(
+                pane,
+                VisualConfig::Heatmap(heatmap::Config { depth_color, ..cfg }),
+                false,
+            )
+        };
+
+        let size = radio(
+            "Resting size",
+            DepthColor::Size,
+            Some(cfg.depth_color),
+            with_color,
+        )
+        .spacing(4);
+        let change = radio(
+            "Liquidity change",
+            DepthColor::LiquidityChange,
+            Some(cfg.depth_color),
+            with_color,
+        )
+        .spacing(4);
+
+        let mut col = column![
+            text("Depth coloring").size(14),
+            row![size, change].spacing(12)
+        ]
+        .spacing(8);
+
+        if cfg.depth_color == DepthColor::Size {
+            let with_intensity = move |intensity| {
+                Message::VisualConfigChanged(
+                    pane,
+                    VisualConfig::Heatmap(heatmap::Config { intensity, ..cfg }),
+                    false,
+                )
+            };
+            let decay = match cfg.intensity {
+                Intensity::Decay { .. } => cfg.intensity,
+                _ => Intensity::decay(),
+            };
+
+            let intensity_kinds = row![
+                radio(
+                    "Linear",
+                    Intensity::Linear,
+                    Some(cfg.intensity),
+                    with_intensity
+                )
+                .spacing(4),
+                radio("Fade older", decay, Some(cfg.intensity), with_intensity).spacing(4),
+                radio(
+                    "Per column",
+                    Intensity::PerColumn,
+                    Some(cfg.intensity),
+                    with_intensity
+                )
+                .spacing(4),
+            ]
+            .spacing(12);
+
+            let half_life = if let Intensity::Decay { half_life_secs } = cfg.intensity {
+                Some(labeled_slider(
+                    "Half-life",
+                    intensity::HALF_LIFE_RANGE,
+                    half_life_secs,
+                    move |half_life_secs| with_intensity(Intensity::Decay { half_life_secs }),
+                    |secs| match (secs / 60, secs % 60) {
+                        (0, secs) => format!("{secs}s"),
+                        (mins, 0) => format!("{mins}m"),
+                        (mins, secs) => format!("{mins}m {secs}s"),
+                    },
+                    Some(5),
+                ))
+            } else {
+                None
+            };
+
+            col = col.push(
+                container(column![intensity_kinds].push(half_life).spacing(8))
+                    .style(style::modal_container)
+                    .padding(8),
+            );
+        }
+        col
+    };
+
     let noise_filters_column = {
         let merge_checkbox = checkbox(cfg.coalescing.is_some())
             .label("Merge orders if sizes are similar")
@@ -245,6 +395,132 @@ pub fn heatmap_cfg_view<'a>(
         if let Some(slider) = circle_scaling_slider {
             col = col.push(slider);
         }
+        col.push(sweep_window_cfg(
+            cfg.sweep_window_ms,
+            move |sweep_window_ms| {
+                Message::VisualConfigChanged(
+                    pane,
+                    VisualConfig::Heatmap(heatmap::Config {
+                        sweep_window_ms,
+                        ..cfg
+                    }),
+                    false,
+                )
+            },
+        ))
+    };
+
+    let history_column = {
+        let persist_checkbox = checkbox(cfg.history_minutes.is_some())
+            .label("Restore depth history on launch")
+            .on_toggle(move |value| {
+                Message::VisualConfigChanged(
+                    pane,
+                    VisualConfig::Heatmap(heatmap::Config {
+                        history_minutes: value.then_some(history::DEFAULT_WINDOW_MINUTES),
+                        ..cfg
+                    }),
+                    false,
+                )
+            });
+
+        let mut col = column![text("History").size(14), persist_checkbox].spacing(8);
+
+        if let Some(minutes) = cfg.history_minutes {
+            col = col.push(classic_slider_row(
+                text("Keep last"),
+                slider(history::WINDOW_MINUTES_RANGE, minutes, move |value| {
+                    Message::VisualConfigChanged(
+                        pane,
+                        VisualConfig::Heatmap(heatmap::Config {
+                            history_minutes: Some(value),
+                            ..cfg
+                        }),
+                        false,
+                    )
+                })
+                .step(15u16)
+                .into(),
+                Some(text(format!("{minutes} min")).size(13)),
+            ));
+        }
+        col
+    };
+
+    let walls_column = {
+        let with_walls = move |walls: Option<WallConfig>| {
+            Message::VisualConfigChanged(
+                pane,
+                VisualConfig::Heatmap(heatmap::Config { walls, ..cfg }),
+                false,
+            )
+        };
+
+        let detect_checkbox = checkbox(cfg.walls.is_some())
+            .label("Detect liquidity walls")
+            .on_toggle(move |value| with_walls(value.then(WallConfig::default)));
+
+        let mut col = column![text("Liquidity walls").size(14), detect_checkbox].spacing(8);
+
+        if let Some(walls) = cfg.walls {
+            let threshold_kinds = {
+                let multiple = radio(
+                    "x Average level",
+                    WallThreshold::Multiple(WallThreshold::DEFAULT_MULTIPLE),
+                    Some(walls.threshold),
+                    move |threshold| with_walls(Some(WallConfig { threshold, ..walls })),
+                )
+                .spacing(4);
+
+                let notional = radio(
+                    "Notional",
+                    WallThreshold::Notional(WallThreshold::DEFAULT_NOTIONAL),
+                    Some(walls.threshold),
+                    move |threshold| with_walls(Some(WallConfig { threshold, ..walls })),
+                )
+                .spacing(4);
+
+                row![text("Threshold: "), row![multiple, notional].spacing(12)].spacing(12)
+            };
+
+            let threshold_slider = match walls.threshold {
+                WallThreshold::Multiple(multiple) => classic_slider_row(
+                    text("Size"),
+                    slider(2.0..=50.0, multiple, move |value| {
+                        with_walls(Some(WallConfig {
+                            threshold: WallThreshold::Multiple(value),
+                            ..walls
+                        }))
+                    })
+                    .step(1.0)
+                    .into(),
+                    Some(text(format!("{multiple:.0}x")).size(13)),
+                ),
+                WallThreshold::Notional(notional) => labeled_slider(
+                    "Size",
+                    10_000.0..=10_000_000.0,
+                    notional,
+                    move |value| {
+                        with_walls(Some(WallConfig {
+                            threshold: WallThreshold::Notional(value),
+                            ..walls
+                        }))
+                    },
+                    |value| format!(">${}", format_with_commas(*value)),
+                    Some(10_000.0),
+                ),
+            };
+
+            let sound_checkbox = checkbox(walls.sound)
+                .label("Play sound on wall changes")
+                .on_toggle(move |sound| with_walls(Some(WallConfig { sound, ..walls })));
+
+            col = col.push(
+                container(column![threshold_kinds, threshold_slider, sound_checkbox].spacing(8))
+                    .style(style::modal_container)
+                    .padding(8),
+            );
+        }
         col
     };
 
@@ -257,9 +533,15 @@ pub fn heatmap_cfg_view<'a>(
 
     let content = split_column![
         size_filters_column,
+        depth_color_column,
         noise_filters_column,
         trade_viz_column,
+        history_column,
+        walls_column,
         column![text("Studies").size(14), study_cfg].spacing(8),
+        spread_alert.unwrap_or_else(|| column![].into()),
+        data_window_cfg(pane, data_window),
+        size_unit_cfg(pane, size_unit),
         row![
             space::horizontal(),
             sync_all_button(pane, VisualConfig::Heatmap(cfg))
@@ -273,6 +555,7 @@ pub fn heatmap_cfg_view<'a>(
 pub fn timesales_cfg_view<'a>(
     cfg: timeandsales::Config,
     pane: pane_grid::Pane,
+    size_unit: Option<SizeUnit>,
 ) -> Element<'a, Message> {
     let trade_size_column = {
         let filter = cfg.trade_size_filter;
@@ -428,10 +711,27 @@ pub fn timesales_cfg_view<'a>(
             .into()
     };
 
+    let sweeps_column = column![
+        text("Sweeps").size(14),
+        sweep_window_cfg(cfg.sweep_window_ms, move |sweep_window_ms| {
+            Message::VisualConfigChanged(
+                pane,
+                VisualConfig::TimeAndSales(timeandsales::Config {
+                    sweep_window_ms,
+                    ..cfg
+                }),
+                false,
+            )
+        })
+    ]
+    .spacing(8);
+
     let content = split_column![
         trade_size_column,
         history_column,
         stacked_bar,
+        sweeps_column,
+        size_unit_cfg(pane, size_unit),
         row![space::horizontal(), sync_all_button(pane, VisualConfig::TimeAndSales(cfg))],
         ; spacing = 12, align_x = Alignment::Start
     ];
@@ -439,6 +739,33 @@ pub fn timesales_cfg_view<'a>(
     cfg_view_container(320, content)
 }
 
+/// Toggle and window of the grouping of rapid same side prints into sweeps
+fn sweep_window_cfg<'a>(
+    window_ms: Option<u16>,
+    on_change: impl Fn(Option<u16>) -> Message + Clone + 'a,
+) -> Element<'a, Message> {
+    let toggle = checkbox(window_ms.is_some())
+        .label("Group rapid prints into sweeps")
+        .on_toggle({
+            let on_change = on_change.clone();
+            move |value| on_change(value.then_some(sweep::DEFAULT_WINDOW_MS))
+        });
+
+    let mut col = column![toggle].spacing(8);
+    if let Some(window_ms) = window_ms {
+        col = col.push(classic_slider_row(
+            text("Max gap"),
+            slider(sweep::WINDOW_RANGE_MS, window_ms, move |value| {
+                on_change(Some(value))
+            })
+            .step(10u16)
+            .into(),
+            Some(text(format!("{window_ms} ms")).size(13)),
+        ));
+    }
+    col.into()
+}
+
 pub fn comparison_cfg_view<'a>(
     pane: pane_grid::Pane,
     chart: &'a ComparisonChart,
@@ -456,17 +783,60 @@ pub fn comparison_cfg_view<'a>(
     cfg_view_container(320, content)
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn kline_cfg_view<'a>(
     study_config: &'a study::Configurator<FootprintStudy>,
     cfg: data::chart::kline::Config,
     kind: &'a KlineChartKind,
     pane: pane_grid::Pane,
     basis: data::chart::Basis,
+    price_scale: PriceScale,
+    bar_close: Option<BarCloseAlert>,
+    stop_runs: Option<&StopRunConfig>,
+    patterns: Option<PatternConfig>,
+    volume_curve: Option<VolumeCurveConfig>,
+    calendar: Option<CalendarConfig>,
+    data_window: DataWindow,
+    size_unit: Option<SizeUnit>,
+    reference_lines: ReferenceLines,
+    overlays: &[Overlay],
+    capabilities: Capabilities,
+    spread_alert: Option<Element<'a, Message>>,
+    signals: Element<'a, Message>,
 ) -> Element<'a, Message> {
+    let volume_curve =
+        volume_curve_cfg(pane, basis, volume_curve).unwrap_or_else(|| column![].into());
+    let calendar =
+        calendar_cfg(pane, basis, capabilities, calendar).unwrap_or_else(|| column![].into());
+    let spread_alert = spread_alert.unwrap_or_else(|| column![].into());
+
     let content = match kind {
-        KlineChartKind::Candles => column![text(
-            "This chart type doesn't have any configurations, WIP..."
-        )],
+        KlineChartKind::Candles => {
+            let bar_close =
+                bar_close_cfg(pane, basis, bar_close).unwrap_or_else(|| column![].into());
+            // Time based candles stream no trades to judge a reversal by
+            let stop_runs = if basis.is_time() {
+                column![].into()
+            } else {
+                stop_runs_cfg(pane, stop_runs)
+            };
+
+            split_column![
+                price_scale_cfg(pane, price_scale),
+                overlays_cfg(pane, overlays),
+                signals,
+                reference_lines_cfg(pane, reference_lines),
+                volume_curve,
+                calendar,
+                patterns_cfg(pane, patterns),
+                stop_runs,
+                bar_close,
+                spread_alert,
+                data_window_cfg(pane, data_window),
+                size_unit_cfg(pane, size_unit),
+                ; spacing = 12, align_x = Alignment::Start
+            ]
+        }
         KlineChartKind::Footprint {
             clusters,
             scaling,
@@ -515,10 +885,42 @@ pub fn kline_cfg_view<'a>(
                 )
             });
 
+            let bar_close =
+                bar_close_cfg(pane, basis, bar_close).unwrap_or_else(|| column![].into());
+
+            let mut trades_note = column![].spacing(4);
+            if !capabilities.historical_trades {
+                trades_note = trades_note.push(text(
+                    "This exchange has no trade history, clusters only fill from live trades",
+                ));
+            }
+            if !capabilities.aggressor_side {
+                trades_note = trades_note.push(text(
+                    "Trade sides are inferred from price changes, buy/sell and delta are estimates",
+                ));
+            }
+
             split_column![
-                column![text("Cluster type").size(14), cluster_picklist].spacing(8),
+                column![
+                    text("Cluster type").size(14),
+                    cluster_picklist,
+                    trades_note
+                ]
+                .spacing(8),
                 column![text("Cluster scaling").size(14), scaling].spacing(8),
                 column![text("Studies").size(14), study_cfg].spacing(8),
+                price_scale_cfg(pane, price_scale),
+                overlays_cfg(pane, overlays),
+                signals,
+                reference_lines_cfg(pane, reference_lines),
+                volume_curve,
+                calendar,
+                patterns_cfg(pane, patterns),
+                stop_runs_cfg(pane, stop_runs),
+                bar_close,
+                spread_alert,
+                data_window_cfg(pane, data_window),
+                size_unit_cfg(pane, size_unit),
                 row![
                     space::horizontal(),
                     sync_all_button(pane, VisualConfig::Kline(cfg))
@@ -531,7 +933,798 @@ pub fn kline_cfg_view<'a>(
     cfg_view_container(360, content)
 }
 
-pub fn ladder_cfg_view<'a>(cfg: ladder::Config, pane: pane_grid::Pane) -> Element<'a, Message> {
+fn overlays_cfg<'a>(pane: pane_grid::Pane, overlays: &[Overlay]) -> Element<'a, Message> {
+    let on_change = move |overlays| Message::PaneEvent(pane, Event::OverlaysChanged(overlays));
+
+    let period_slider = |period: usize, on_period: Box<dyn Fn(usize) -> Message>| {
+        column![
+            text(format!("Period: {period} bars")),
+            slider(2.0..=200.0, period as f32, move |value| on_period(
+                value as usize
+            ))
+            .step(1.0),
+        ]
+        .spacing(4)
+    };
+
+    let mut col = column![text("Overlays").size(14)].spacing(8);
+
+    for (index, overlay) in overlays.iter().enumerate() {
+        let list = overlays.to_vec();
+        let set = move |overlay: Overlay| {
+            let mut list = list.clone();
+            list[index] = overlay;
+            on_change(list)
+        };
+
+        let mut remaining = overlays.to_vec();
+        remaining.remove(index);
+        let remove_button = button(icon_text(Icon::TrashBin, 12))
+            .style(|theme, status| style::button::transparent(theme, status, false))
+            .on_press(on_change(remaining));
+
+        let header = row![
+            text(overlay.name().to_string()).color(style::overlay_color(index)),
+            space::horizontal(),
+            remove_button,
+        ]
+        .align_y(Alignment::Center);
+
+        let params = match *overlay {
+            Overlay::Sma { period } => {
+                period_slider(period, Box::new(move |period| set(Overlay::Sma { period })))
+            }
+            Overlay::Ema { period } => {
+                period_slider(period, Box::new(move |period| set(Overlay::Ema { period })))
+            }
+            Overlay::Vwap { session_start_hour } => column![
+                text(format!(
+                    "Session starts at {session_start_hour:02}:00 (chart timezone)"
+                )),
+                slider(0.0..=23.0, f32::from(session_start_hour), move |hour| {
+                    set(Overlay::Vwap {
+                        session_start_hour: hour as u8,
+                    })
+                })
+                .step(1.0),
+            ]
+            .spacing(4),
+            Overlay::Bollinger { period, std_dev } => {
+                let set_period = set.clone();
+
+                column![
+                    period_slider(
+                        period,
+                        Box::new(move |period| set_period(Overlay::Bollinger { period, std_dev })),
+                    ),
+                    text(format!("Width: {std_dev:.1} standard deviations")),
+                    slider(0.5..=4.0, std_dev, move |std_dev| {
+                        set(Overlay::Bollinger { period, std_dev })
+                    })
+                    .step(0.1),
+                ]
+                .spacing(4)
+            }
+            Overlay::Script {
+                ref name,
+                ref params,
+            } => {
+                let owned = name.clone();
+                script_params(name, params, move |params| {
+                    set(Overlay::Script {
+                        name: owned.clone(),
+                        params,
+                    })
+                })
+            }
+        };
+
+        col = col.push(
+            column![header, params.padding(padding::left(16))]
+                .spacing(4)
+                .width(Length::Fill),
+        );
+    }
+
+    let choices: Vec<Overlay> = Overlay::ALL
+        .into_iter()
+        .chain(
+            script::available_of(script::ScriptKind::Indicator)
+                .iter()
+                .map(script::Script::overlay),
+        )
+        .collect();
+
+    let list = overlays.to_vec();
+    let add = pick_list(choices, None::<Overlay>, move |overlay| {
+        let mut list = list.clone();
+        list.push(overlay);
+        on_change(list)
+    })
+    .placeholder("Add overlay...");
+
+    let reload =
+        button(text("Reload scripts")).on_press(Message::PaneEvent(pane, Event::ReloadScripts));
+
+    col.push(row![add, reload].spacing(8).align_y(Alignment::Center))
+        .into()
+}
+
+/// Signal scripts the pane runs, each with its parameters
+pub fn signals_cfg<'a>(
+    pane: pane_grid::Pane,
+    signals: &[SignalSetup],
+    disabled: &[String],
+) -> Element<'a, Message> {
+    let on_change = move |signals| Message::PaneEvent(pane, Event::SignalsChanged(signals));
+
+    let mut col = column![text("Signal scripts").size(14)].spacing(8);
+
+    for (index, setup) in signals.iter().enumerate() {
+        let list = signals.to_vec();
+        let set = move |params| {
+            let mut list = list.clone();
+            list[index].params = params;
+            on_change(list)
+        };
+
+        let mut remaining = signals.to_vec();
+        remaining.remove(index);
+        let remove_button = button(icon_text(Icon::TrashBin, 12))
+            .style(|theme, status| style::button::transparent(theme, status, false))
+            .on_press(on_change(remaining));
+
+        let header = row![text(setup.name.clone()), space::horizontal(), remove_button,]
+            .align_y(Alignment::Center);
+
+        let mut params = script_params(&setup.name, &setup.params, set);
+        if disabled.contains(&setup.name) {
+            params = params.push(
+                row![
+                    text(format!(
+                        "Stopped after running past {}ms {} times",
+                        signal::TIME_LIMIT.as_millis(),
+                        signal::MAX_OVERRUNS
+                    )),
+                    button(text("Run again"))
+                        .on_press(Message::PaneEvent(pane, Event::RearmSignals)),
+                ]
+                .spacing(8)
+                .align_y(Alignment::Center),
+            );
+        }
+
+        col = col.push(
+            column![header, params.padding(padding::left(16))]
+                .spacing(4)
+                .width(Length::Fill),
+        );
+    }
+
+    let choices: Vec<SignalSetup> = script::available_of(script::ScriptKind::Signal)
+        .iter()
+        .filter(|script| !signals.iter().any(|setup| setup.name == script.name))
+        .map(SignalSetup::new)
+        .collect();
+
+    let list = signals.to_vec();
+    let add = pick_list(choices, None::<SignalSetup>, move |setup| {
+        let mut list = list.clone();
+        list.push(setup);
+        on_change(list)
+    })
+    .placeholder("Add signal script...");
+
+    col.push(add).into()
+}
+
+/// A slider per parameter the script declares, values it no longer declares are left out
+fn script_params<'a>(
+    name: &str,
+    values: &[(String, f32)],
+    set: impl Fn(Vec<(String, f32)>) -> Message + Clone + 'a,
+) -> iced::widget::Column<'a, Message> {
+    let Some(script) = script::get(name) else {
+        return column![text(
+            "Script not found, reload scripts after adding it back"
+        )];
+    };
+
+    let mut col = column![].spacing(4);
+    for param in &script.params {
+        let value = values
+            .iter()
+            .find(|(name, _)| *name == param.name)
+            .map_or(param.default, |(_, value)| *value);
+
+        let (values, set) = (values.to_vec(), set.clone());
+        let param_name = param.name.clone();
+        let on_slide = move |value: f32| {
+            let mut params = values.clone();
+            match params.iter_mut().find(|(name, _)| *name == param_name) {
+                Some((_, old)) => *old = value,
+                None => params.push((param_name.clone(), value)),
+            }
+            set(params)
+        };
+
+        col = col
+            .push(text(format!("{}: {value}", param.name)))
+            .push(slider(param.min..=param.max, value, on_slide).step(param.step));
+    }
+    col
+}
+
+fn reference_lines_cfg<'a>(pane: pane_grid::Pane, cfg: ReferenceLines) -> Element<'a, Message> {
+    let on_change = move |cfg| Message::PaneEvent(pane, Event::ReferenceLinesChanged(cfg));
+
+    let last_price = checkbox(cfg.last_price)
+        .label("Last price line")
+        .on_toggle(move |value| {
+            on_change(ReferenceLines {
+                last_price: value,
+                ..cfg
+            })
+        });
+
+    let session_levels = checkbox(cfg.session_levels)
+        .label("Session open/high/low")
+        .on_toggle(move |value| {
+            on_change(ReferenceLines {
+                session_levels: value,
+                ..cfg
+            })
+        });
+
+    let prev_day_levels = checkbox(cfg.prev_day_levels)
+        .label("Previous day high/low/close")
+        .on_toggle(move |value| {
+            on_change(ReferenceLines {
+                prev_day_levels: value,
+                ..cfg
+            })
+        });
+
+    let anchor_sessions = checkbox(cfg.anchor_sessions)
+        .label("Anchor sessions to market open")
+        .on_toggle(move |value| {
+            on_change(ReferenceLines {
+                anchor_sessions: value,
+                ..cfg
+            })
+        });
+
+    let mut col = column![
+        text("Reference lines").size(14),
+        last_price,
+        session_levels,
+        prev_day_levels,
+        tooltip(
+            anchor_sessions,
+            Some("New bars start at the session start, on any timeframe"),
+            TooltipPosition::Top,
+        ),
+    ]
+    .spacing(8);
+
+    if cfg.session_levels || cfg.anchor_sessions {
+        let start_hour = slider(0.0..=23.0, f32::from(cfg.session_start_hour), move |hour| {
+            on_change(ReferenceLines {
+                session_start_hour: hour as u8,
+                ..cfg
+            })
+        })
+        .step(1.0);
+
+        col = col.push(
+            column![
+                text(format!(
+                    "Session starts at {:02}:00 (chart timezone)",
+                    cfg.session_start_hour
+                )),
+                start_hour,
+            ]
+            .spacing(4)
+            .padding(padding::left(16)),
+        );
+    }
+
+    col.into()
+}
+
+/// Bar close notification toggles, only offered on time based charts
+fn bar_close_cfg<'a>(
+    pane: pane_grid::Pane,
+    basis: data::chart::Basis,
+    alert: Option<BarCloseAlert>,
+) -> Option<Element<'a, Message>> {
+    let data::chart::Basis::Time(timeframe) = basis else {
+        return None;
+    };
+
+    let enable_checkbox = checkbox(alert.is_some())
+        .label(format!("Notify on {timeframe} bar close"))
+        .on_toggle(move |enabled| {
+            Message::PaneEvent(
+                pane,
+                Event::BarCloseAlertChanged(enabled.then(BarCloseAlert::default)),
+            )
+        });
+
+    let mut col = column![text("Bar close").size(14), enable_checkbox].spacing(8);
+
+    if let Some(alert) = alert {
+        let sound = checkbox(alert.play_sound)
+            .label("Play sound")
+            .on_toggle(move |value| {
+                Message::PaneEvent(
+                    pane,
+                    Event::BarCloseAlertChanged(Some(BarCloseAlert {
+                        play_sound: value,
+                        ..alert
+                    })),
+                )
+            });
+
+        let toast = checkbox(alert.show_toast)
+            .label("Show notification")
+            .on_toggle(move |value| {
+                Message::PaneEvent(
+                    pane,
+                    Event::BarCloseAlertChanged(Some(BarCloseAlert {
+                        show_toast: value,
+                        ..alert
+                    })),
+                )
+            });
+
+        col = col.push(column![sound, toast].spacing(4).padding(padding::left(16)));
+    }
+
+    Some(col.into())
+}
+
+/// Typical volume curve of the volume panel, only intraday time based charts have one
+fn volume_curve_cfg<'a>(
+    pane: pane_grid::Pane,
+    basis: data::chart::Basis,
+    config: Option<VolumeCurveConfig>,
+) -> Option<Element<'a, Message>> {
+    let data::chart::Basis::Time(timeframe) = basis else {
+        return None;
+    };
+    if timeframe.to_milliseconds() >= exchange::Timeframe::D1.to_milliseconds() {
+        return None;
+    }
+
+    let on_change = move |config| Message::PaneEvent(pane, Event::VolumeCurveChanged(config));
+
+    let enable_checkbox = checkbox(config.is_some())
+        .label("Show typical volume curve")
+        .on_toggle(move |enabled| on_change(enabled.then(VolumeCurveConfig::default)));
+
+    let mut col = column![
+        text("Volume by time of day").size(14),
+        enable_checkbox,
+        text("Average volume at each time of day over previous sessions, in the volume panel"),
+    ]
+    .spacing(8);
+
+    if let Some(config) = config {
+        let sessions = pick_list(
+            VolumeCurveConfig::SESSIONS,
+            Some(config.sessions),
+            move |sessions| on_change(Some(VolumeCurveConfig { sessions, ..config })),
+        );
+        let threshold = pick_list(
+            VolumeCurveConfig::THRESHOLDS.map(|pct| format!("{pct}%")),
+            Some(format!("{}%", config.threshold_pct)),
+            move |pct: String| {
+                let threshold_pct = pct.trim_end_matches('%').parse().unwrap_or(150);
+                on_change(Some(VolumeCurveConfig {
+                    threshold_pct,
+                    ..config
+                }))
+            },
+        );
+
+        col = col.push(
+            column![
+                row![text("Sessions averaged"), sessions]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+                row![text("Highlight above"), threshold]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+            ]
+            .spacing(4)
+            .padding(padding::left(16)),
+        );
+    }
+
+    Some(col.into())
+}
+
+/// Economic calendar markers, for time based charts of venues that forward a calendar
+fn calendar_cfg<'a>(
+    pane: pane_grid::Pane,
+    basis: data::chart::Basis,
+    capabilities: Capabilities,
+    config: Option<CalendarConfig>,
+) -> Option<Element<'a, Message>> {
+    if !basis.is_time() || !capabilities.economic_calendar {
+        return None;
+    }
+
+    let on_change = move |config| Message::PaneEvent(pane, Event::CalendarChanged(config));
+
+    let enable_checkbox = checkbox(config.is_some())
+        .label("Show economic calendar")
+        .on_toggle(move |enabled| on_change(enabled.then(CalendarConfig::default)));
+
+    let mut col = column![
+        text("Economic calendar").size(14),
+        enable_checkbox,
+        text("Releases for the symbol's currencies along the time axis, hover one for details"),
+    ]
+    .spacing(8);
+
+    if let Some(config) = config {
+        let importance = pick_list(
+            Importance::ALL,
+            Some(config.min_importance),
+            move |min_importance| on_change(Some(CalendarConfig { min_importance })),
+        );
+
+        col = col.push(
+            row![text("Minimum importance"), importance]
+                .spacing(8)
+                .align_y(Alignment::Center)
+                .padding(padding::left(16)),
+        );
+    }
+
+    Some(col.into())
+}
+
+fn data_window_cfg<'a>(pane: pane_grid::Pane, config: DataWindow) -> Element<'a, Message> {
+    let on_change = move |config| Message::PaneEvent(pane, Event::DataWindowChanged(config));
+
+    let enable_checkbox = checkbox(config.visible)
+        .label("Show data window")
+        .on_toggle(move |visible| on_change(DataWindow { visible, ..config }));
+
+    let mut col = column![
+        text("Data window").size(14),
+        enable_checkbox,
+        text("Values at the hovered bar in place of the crosshair tooltip"),
+    ]
+    .spacing(8);
+
+    if config.visible {
+        let corner = pick_list(Corner::ALL, Some(config.corner), move |corner| {
+            on_change(DataWindow { corner, ..config })
+        });
+
+        col = col.push(
+            row![text("Corner"), corner]
+                .spacing(8)
+                .align_y(Alignment::Center)
+                .padding(padding::left(16)),
+        );
+    }
+
+    col.into()
+}
+
+/// Unit the pane shows sizes in, overriding the app wide one
+fn price_scale_cfg<'a>(pane: pane_grid::Pane, price_scale: PriceScale) -> Element<'a, Message> {
+    let on_change = move |scale| Message::PaneEvent(pane, Event::PriceScaleChanged(scale));
+
+    let mut choices = column![].spacing(4);
+    for choice in PriceScale::ALL {
+        choices = choices.push(radio(
+            choice.to_string(),
+            choice,
+            Some(price_scale),
+            on_change,
+        ));
+    }
+
+    column![
+        text("Price scale").size(14),
+        choices,
+        text("Percent reads as the change from the leftmost visible bar"),
+    ]
+    .spacing(8)
+    .into()
+}
+
+fn size_unit_cfg<'a>(pane: pane_grid::Pane, unit: Option<SizeUnit>) -> Element<'a, Message> {
+    let on_change = move |unit| Message::PaneEvent(pane, Event::SizeUnitChanged(unit));
+
+    let mut choices = column![radio("App setting", None, Some(unit), on_change)].spacing(4);
+    for choice in SizeUnit::ALL {
+        choices = choices.push(radio(
+            choice.to_string(),
+            Some(choice),
+            Some(unit),
+            on_change,
+        ));
+    }
+
+    column![
+        text("Size unit").size(14),
+        choices,
+        text("A contract of MT5 symbols is one lot"),
+    ]
+    .spacing(8)
+    .into()
+}
+
+/// Stop run markers, with how each run is announced
+fn stop_runs_cfg<'a>(
+    pane: pane_grid::Pane,
+    config: Option<&StopRunConfig>,
+) -> Element<'a, Message> {
+    let on_change = move |config| Message::PaneEvent(pane, Event::StopRunsChanged(config));
+
+    let enable_checkbox = checkbox(config.is_some())
+        .label("Mark stop runs")
+        .on_toggle(move |enabled| on_change(enabled.then(StopRunConfig::default)));
+
+    let mut col = column![
+        text("Stop runs").size(14),
+        enable_checkbox,
+        text("Sweeps of a swing high or low that close back inside on opposite side volume"),
+    ]
+    .spacing(8);
+
+    if let Some(config) = config {
+        let sensitivity = {
+            let config = config.clone();
+            pick_list(
+                Sensitivity::ALL,
+                Some(config.sensitivity),
+                move |sensitivity| {
+                    on_change(Some(StopRunConfig {
+                        sensitivity,
+                        ..config.clone()
+                    }))
+                },
+            )
+        };
+
+        let sound = {
+            let config = config.clone();
+            checkbox(config.play_sound)
+                .label("Play sound")
+                .on_toggle(move |play_sound| {
+                    on_change(Some(StopRunConfig {
+                        play_sound,
+                        ..config.clone()
+                    }))
+                })
+        };
+
+        let toast = {
+            let config = config.clone();
+            checkbox(config.show_toast)
+                .label("Show notification")
+                .on_toggle(move |show_toast| {
+                    on_change(Some(StopRunConfig {
+                        show_toast,
+                        ..config.clone()
+                    }))
+                })
+        };
+
+        let webhook = {
+            let config = config.clone();
+            text_input(
+                "Webhook URL (optional)",
+                config.webhook.as_deref().unwrap_or_default(),
+            )
+            .on_input(move |url| {
+                let url = url.trim();
+                on_change(Some(StopRunConfig {
+                    webhook: (!url.is_empty()).then(|| url.to_string()),
+                    ..config.clone()
+                }))
+            })
+        };
+
+        col = col.push(
+            column![
+                row![text("Sensitivity"), sensitivity]
+                    .spacing(8)
+                    .align_y(Alignment::Center),
+                sound,
+                toast,
+                webhook,
+            ]
+            .spacing(4)
+            .padding(padding::left(16)),
+        );
+    }
+
+    col.into()
+}
+
+/// Spread widening alert of an MT5 pane, `own` set in the pane and `default` the connection's
+pub fn spread_alert_cfg<'a>(
+    pane: pane_grid::Pane,
+    own: Option<SpreadAlert>,
+    default: Option<SpreadAlert>,
+) -> Element<'a, Message> {
+    let on_change = move |config| Message::PaneEvent(pane, Event::SpreadAlertChanged(config));
+    let alert = own.or(default).unwrap_or(SpreadAlert {
+        enabled: false,
+        ..SpreadAlert::default()
+    });
+
+    let enable_checkbox = checkbox(alert.enabled)
+        .label("Alert when the spread widens")
+        .on_toggle(move |enabled| on_change(Some(SpreadAlert { enabled, ..alert })));
+
+    let mut col = column![
+        text("Spread alert").size(14),
+        enable_checkbox,
+        text("Flashes the pane and notifies once per widening, again after the spread narrows"),
+    ]
+    .spacing(8);
+
+    if alert.enabled {
+        let kinds = [
+            ("Points", SpreadLimit::Points(30.0)),
+            ("× median", SpreadLimit::MedianMultiple(3.0)),
+        ];
+        let is_points = matches!(alert.limit, SpreadLimit::Points(_));
+        let kind = kinds
+            .into_iter()
+            .fold(row![].spacing(12), |kind_row, (label, limit)| {
+                kind_row.push(radio(
+                    label,
+                    matches!(limit, SpreadLimit::Points(_)),
+                    Some(is_points),
+                    move |_| on_change(Some(SpreadAlert { limit, ..alert })),
+                ))
+            });
+
+        let limit = match alert.limit {
+            SpreadLimit::Points(points) => labeled_slider(
+                "Limit",
+                1.0..=500.0,
+                points,
+                move |points| {
+                    on_change(Some(SpreadAlert {
+                        limit: SpreadLimit::Points(points),
+                        ..alert
+                    }))
+                },
+                |points| format!("{points} points"),
+                Some(1.0),
+            ),
+            SpreadLimit::MedianMultiple(multiple) => labeled_slider(
+                "Limit",
+                1.5..=10.0,
+                multiple,
+                move |multiple| {
+                    on_change(Some(SpreadAlert {
+                        limit: SpreadLimit::MedianMultiple(multiple),
+                        ..alert
+                    }))
+                },
+                |multiple| format!("{multiple}× median"),
+                Some(0.5),
+            ),
+        };
+
+        let sound = checkbox(alert.play_sound)
+            .label("Play sound")
+            .on_toggle(move |play_sound| {
+                on_change(Some(SpreadAlert {
+                    play_sound,
+                    ..alert
+                }))
+            });
+
+        col = col.push(
+            column![kind, limit, sound]
+                .spacing(4)
+                .padding(padding::left(16)),
+        );
+    }
+
+    let make_default = button(text("Use for all panes of this connection"))
+        .on_press(Message::SpreadAlertDefault(pane, Some(alert)));
+    let mut buttons = row![make_default].spacing(8);
+    if own.is_some() {
+        buttons = buttons.push(
+            button(text("Follow the connection"))
+                .on_press(Message::PaneEvent(pane, Event::SpreadAlertChanged(None))),
+        );
+    } else if default.is_some() {
+        col = col.push(text("Following the connection's default"));
+    }
+
+    col.push(buttons).into()
+}
+
+/// Candle pattern markers, which patterns and whether each is announced as a bar closes on it
+fn patterns_cfg<'a>(pane: pane_grid::Pane, config: Option<PatternConfig>) -> Element<'a, Message> {
+    let on_change = move |config| Message::PaneEvent(pane, Event::PatternsChanged(config));
+
+    let enable_checkbox = checkbox(config.is_some())
+        .label("Mark candle patterns")
+        .on_toggle(move |enabled| on_change(enabled.then(PatternConfig::default)));
+
+    let mut col = column![text("Candle patterns").size(14), enable_checkbox].spacing(8);
+
+    if let Some(config) = config {
+        let pattern_row =
+            |label: &'static str,
+             toggle: PatternToggle,
+             with: fn(PatternConfig, PatternToggle) -> PatternConfig| {
+                let shown = checkbox(toggle.shown).label(label).on_toggle(move |shown| {
+                    on_change(Some(with(config, PatternToggle { shown, ..toggle })))
+                });
+                let alert = checkbox(toggle.alert)
+                    .label("Alert on close")
+                    .on_toggle_maybe(toggle.shown.then_some(move |alert| {
+                        on_change(Some(with(config, PatternToggle { alert, ..toggle })))
+                    }));
+
+                row![shown, space::horizontal(), alert].align_y(Alignment::Center)
+            };
+
+        let wick_ratio = labeled_slider(
+            "Pin wick",
+            PatternConfig::WICK_RATIO_RANGE,
+            config.pin_wick_ratio,
+            move |pin_wick_ratio| {
+                on_change(Some(PatternConfig {
+                    pin_wick_ratio,
+                    ..config
+                }))
+            },
+            |ratio| format!("{ratio:.1}x body"),
+            Some(0.1),
+        );
+
+        col = col.push(
+            column![
+                pattern_row("Engulfing (E)", config.engulfing, |config, engulfing| {
+                    PatternConfig {
+                        engulfing,
+                        ..config
+                    }
+                }),
+                pattern_row("Pin bar (P)", config.pin_bar, |config, pin_bar| {
+                    PatternConfig { pin_bar, ..config }
+                }),
+                wick_ratio,
+                pattern_row("Inside bar (I)", config.inside_bar, |config, inside_bar| {
+                    PatternConfig {
+                        inside_bar,
+                        ..config
+                    }
+                }),
+                text("Bullish patterns are marked under the bar, the others above"),
+            ]
+            .spacing(4)
+            .padding(padding::left(16)),
+        );
+    }
+
+    col.into()
+}
+
+pub fn ladder_cfg_view<'a>(
+    cfg: ladder::Config,
+    pane: pane_grid::Pane,
+    size_unit: Option<SizeUnit>,
+) -> Element<'a, Message> {
     let display_options = {
         let spread = checkbox(cfg.show_spread)
             .label("Show Spread")
@@ -559,10 +1752,24 @@ pub fn ladder_cfg_view<'a>(cfg: ladder::Config, pane: pane_grid::Pane) -> Elemen
                 )
             });
 
+        let trades = checkbox(cfg.show_trades)
+            .label("Show traded volume")
+            .on_toggle(move |value| {
+                Message::VisualConfigChanged(
+                    pane,
+                    VisualConfig::Ladder(ladder::Config {
+                        show_trades: value,
+                        ..cfg
+                    }),
+                    false,
+                )
+            });
+
         column![
             text("Display Options").size(14),
             column![
                 spread,
+                trades,
                 row![
                     chase_tracker,
                     tooltip(
@@ -602,11 +1809,41 @@ pub fn ladder_cfg_view<'a>(cfg: ladder::Config, pane: pane_grid::Pane) -> Elemen
         )
     };
 
+    let levels_slider = {
+        let slider_ui = slider(
+            0.0..=f32::from(ladder::MAX_LEVELS_PER_SIDE),
+            f32::from(cfg.levels_per_side),
+            move |value| {
+                Message::VisualConfigChanged(
+                    pane,
+                    VisualConfig::Ladder(ladder::Config {
+                        levels_per_side: value as u16,
+                        ..cfg
+                    }),
+                    false,
+                )
+            },
+        )
+        .step(1.0);
+
+        let value_text = match cfg.levels_per_side {
+            0 => "Fit pane".to_string(),
+            n => n.to_string(),
+        };
+
+        classic_slider_row(
+            text("Levels per side"),
+            slider_ui.into(),
+            Some(text(value_text).size(13)),
+        )
+    };
+
     let history_column = column![text("History").size(14), retention_slider].spacing(8);
 
     let content = split_column![
-        display_options,
+        column![display_options, levels_slider].spacing(8),
         history_column,
+        size_unit_cfg(pane, size_unit),
         row![
             space::horizontal(),
             sync_all_button(pane, VisualConfig::Ladder(cfg))
@@ -617,6 +1854,59 @@ pub fn ladder_cfg_view<'a>(cfg: ladder::Config, pane: pane_grid::Pane) -> Elemen
     cfg_view_container(320, content)
 }
 
+pub fn strip_cfg_view<'a>(cfg: &strip::Config, pane: pane_grid::Pane) -> Element<'a, Message> {
+    let on_change = move |cfg| Message::PaneEvent(pane, Event::StripConfigChanged(cfg));
+
+    let timeframes = Timeframe::KLINE
+        .chunks(5)
+        .fold(column![].spacing(4), |col, chunk| {
+            let row = chunk.iter().fold(row![].spacing(8), |row, tf| {
+                let mut toggled = cfg.clone();
+                toggled.toggle(*tf);
+
+                row.push(
+                    checkbox(cfg.timeframes.contains(tf))
+                        .label(tf.to_string())
+                        .on_toggle(move |_| on_change(toggled.clone())),
+                )
+            });
+            col.push(row)
+        });
+
+    let bars_slider = {
+        let current = cfg.clone();
+        let slider_ui = slider(
+            f32::from(strip::MIN_BARS)..=f32::from(strip::MAX_BARS),
+            f32::from(cfg.bars),
+            move |value| {
+                on_change(strip::Config {
+                    bars: value as u16,
+                    ..current.clone()
+                })
+            },
+        )
+        .step(10.0);
+
+        classic_slider_row(
+            text("Bars per strip"),
+            slider_ui.into(),
+            Some(text(cfg.bars.to_string()).size(13)),
+        )
+    };
+
+    let content = split_column![
+        column![
+            text(format!("Timeframes (up to {})", strip::MAX_STRIPS)).size(14),
+            timeframes
+        ]
+        .spacing(8),
+        bars_slider,
+        ; spacing = 12, align_x = Alignment::Start
+    ];
+
+    cfg_view_container(360, content)
+}
+
 fn sync_all_button<'a>(pane: pane_grid::Pane, config: VisualConfig) -> Element<'a, Message> {
     tooltip(
         button("Sync all").on_press(Message::VisualConfigChanged(pane, config, true)),
@@ -760,6 +2050,38 @@ pub mod study {
                         .padding(4)
                         .into()
                 }
+                FootprintStudy::StackedImbalance { ratio, levels } => {
+                    let ratio_slider = {
+                        let info_text =
+                            text(format!("Diagonal ratio: {:.2}x", ratio as f32 / 100.0));
+
+                        let ratio_slider = slider(150.0..=800.0, ratio as f32, move |new_value| {
+                            on_change(FootprintStudy::StackedImbalance {
+                                ratio: new_value as usize,
+                                levels,
+                            })
+                        })
+                        .step(25.0);
+
+                        column![info_text, ratio_slider].padding(8).spacing(4)
+                    };
+
+                    let levels_slider = {
+                        let info_text = text(format!("Stack of {levels} price levels"));
+
+                        let levels_slider = slider(2.0..=10.0, levels as f32, move |new_value| {
+                            on_change(FootprintStudy::StackedImbalance {
+                                ratio,
+                                levels: new_value as usize,
+                            })
+                        })
+                        .step(1.0);
+
+                        column![info_text, levels_slider].padding(8).spacing(4)
+                    };
+
+                    split_column![ratio_slider, levels_slider].padding(4).into()
+                }
             }
         }
     }
@@ -0,0 +1,199 @@
+use crate::style::{self, icon_text};
+use data::symbol_search::{Entry, SearchIndex, Source};
+use exchange::{Ticker, Timeframe};
+
+use iced::widget::{button, checkbox, column, container, pick_list, row, space, text, text_input};
+use iced::{Alignment, Element, Length};
+use rustc_hash::FxHashSet;
+
+pub const SEARCH_BOX_ID: &str = "global_symbol_search_box";
+
+const MAX_RESULTS: usize = 12;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    QueryChanged(String),
+    /// Moves the highlighted result by this many rows
+    Navigate(isize),
+    Submit,
+    Picked(usize),
+    /// Adds the result to the tickers opened as a grid, or takes it out
+    Marked(usize, bool),
+    ClearMarked,
+    GridTimeframe(Timeframe),
+    OpenGrid,
+}
+
+pub enum Action {
+    Select(Ticker, Source),
+    /// Opens a new layout of candlestick charts, one per ticker in the order they were marked
+    OpenGrid(Vec<Ticker>, Timeframe),
+}
+
+/// Type-ahead search over every indexed source, opened with Ctrl/Cmd+K
+pub struct SymbolSearch {
+    query: String,
+    selected: usize,
+    results: Vec<Entry>,
+    /// Results marked for a grid, kept while the query changes
+    marked: Vec<Entry>,
+    grid_timeframe: Timeframe,
+}
+
+impl Default for SymbolSearch {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            selected: 0,
+            results: vec![],
+            marked: vec![],
+            grid_timeframe: Timeframe::M15,
+        }
+    }
+}
+
+impl SymbolSearch {
+    pub fn update(
+        &mut self,
+        message: Message,
+        index: &SearchIndex,
+        favorites: &FxHashSet<Ticker>,
+        recents: &[Ticker],
+    ) -> Option<Action> {
+        match message {
+            Message::QueryChanged(query) => {
+                self.query = query;
+                self.refresh(index, favorites, recents);
+            }
+            Message::Navigate(delta) => {
+                if !self.results.is_empty() {
+                    let last = self.results.len() - 1;
+                    self.selected = self.selected.saturating_add_signed(delta).min(last);
+                }
+            }
+            Message::Submit => return self.pick(self.selected),
+            Message::Picked(index) => return self.pick(index),
+            Message::Marked(index, is_marked) => {
+                if let Some(entry) = self.results.get(index) {
+                    self.marked.retain(|marked| marked.ticker != entry.ticker);
+                    if is_marked {
+                        self.marked.push(entry.clone());
+                    }
+                }
+            }
+            Message::ClearMarked => self.marked.clear(),
+            Message::GridTimeframe(timeframe) => self.grid_timeframe = timeframe,
+            Message::OpenGrid => {
+                if !self.marked.is_empty() {
+                    let tickers = self.marked.iter().map(|entry| entry.ticker).collect();
+                    return Some(Action::OpenGrid(tickers, self.grid_timeframe));
+                }
+            }
+        }
+        None
+    }
+
+    /// Reruns the query, e.g. after a source's symbols were reindexed
+    pub fn refresh(
+        &mut self,
+        index: &SearchIndex,
+        favorites: &FxHashSet<Ticker>,
+        recents: &[Ticker],
+    ) {
+        self.results = index
+            .search(
+                &self.query,
+                |ticker| favorites.contains(ticker),
+                recents,
+                MAX_RESULTS,
+            )
+            .into_iter()
+            .cloned()
+            .collect();
+        self.selected = self.selected.min(self.results.len().saturating_sub(1));
+    }
+
+    fn pick(&self, index: usize) -> Option<Action> {
+        self.results
+            .get(index)
+            .map(|entry| Action::Select(entry.ticker, entry.source.clone()))
+    }
+
+    pub fn view<'a>(&'a self, index: &SearchIndex) -> Element<'a, Message> {
+        let search_box = text_input("Search symbols...", &self.query)
+            .id(SEARCH_BOX_ID)
+            .on_input(Message::QueryChanged)
+            .on_submit(Message::Submit)
+            .padding(8)
+            .size(14);
+
+        let results: Element<_> = if self.results.is_empty() {
+            let hint = if self.query.is_empty() {
+                format!("{} symbols indexed", index.len())
+            } else {
+                "No matching symbols".to_string()
+            };
+            text(hint).size(12).style(style::muted_text).into()
+        } else {
+            let mut rows = column![].spacing(2);
+
+            for (i, entry) in self.results.iter().enumerate() {
+                let is_selected = i == self.selected;
+                let is_marked = self.marked.iter().any(|m| m.ticker == entry.ticker);
+
+                let content = row![
+                    icon_text(style::exchange_icon(entry.ticker.exchange), 12),
+                    text(entry.display.as_str()).size(13),
+                    space::horizontal(),
+                    text(entry.source.to_string())
+                        .size(11)
+                        .style(style::muted_text),
+                ]
+                .spacing(8)
+                .align_y(Alignment::Center);
+
+                rows = rows.push(
+                    row![
+                        checkbox(is_marked).on_toggle(move |marked| Message::Marked(i, marked)),
+                        button(content)
+                            .width(Length::Fill)
+                            .on_press(Message::Picked(i))
+                            .style(move |t, s| style::button::transparent(t, s, is_selected)),
+                    ]
+                    .spacing(4)
+                    .align_y(Alignment::Center),
+                );
+            }
+            rows.into()
+        };
+
+        let mut content = column![search_box, results].spacing(8);
+
+        if !self.marked.is_empty() {
+            let grid_bar = row![
+                text(format!("{} marked", self.marked.len())).size(12),
+                button(text("Clear").size(12))
+                    .on_press(Message::ClearMarked)
+                    .style(move |t, s| style::button::transparent(t, s, false)),
+                space::horizontal(),
+                pick_list(
+                    Timeframe::KLINE,
+                    Some(self.grid_timeframe),
+                    Message::GridTimeframe
+                )
+                .text_size(12),
+                button(text("Open as grid").size(12)).on_press(Message::OpenGrid),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center);
+
+            content = content.push(grid_bar);
+        }
+
+        container(content)
+            .width(Length::Fixed(420.0))
+            .padding(12)
+            .style(style::dashboard_modal)
+            .into()
+    }
+}
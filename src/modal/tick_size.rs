@@ -0,0 +1,199 @@
+//! Overrides the tick size an MT5 broker lists for a symbol.
+
+use crate::style;
+use data::t;
+use exchange::TickerInfo;
+use exchange::adapter::metatrader5::{
+    rates,
+    tick_override::{self, Issue, TickOverride},
+};
+
+use iced::widget::{button, column, container, row, space, text, text_input};
+use iced::{Alignment, Element};
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    InputChanged(String),
+    Apply,
+    /// Applies a tick size that isn't a multiple of the broker's
+    Confirm,
+    Clear,
+    Close,
+}
+
+pub enum Action {
+    /// `ticker_info` bucketed by its new tick, `tick_override` is `None` once cleared
+    Set {
+        ticker_info: TickerInfo,
+        tick_override: Option<TickOverride>,
+    },
+    Close,
+}
+
+pub struct TickSizeModal {
+    ticker_info: TickerInfo,
+    broker_tick: f32,
+    current: Option<TickOverride>,
+    input: String,
+    issue: Option<Issue>,
+}
+
+impl TickSizeModal {
+    pub fn new(ticker_info: TickerInfo) -> Self {
+        let current = tick_override::get(&ticker_info.ticker);
+        let broker_tick = current
+            .map(|tick| tick.broker_tick)
+            .or_else(|| rates::listed_tick_size(&ticker_info.ticker))
+            .unwrap_or_else(|| ticker_info.min_ticksize.as_f32());
+
+        Self {
+            ticker_info,
+            broker_tick,
+            current,
+            input: current
+                .map(|tick| tick.tick_size.to_string())
+                .unwrap_or_default(),
+            issue: None,
+        }
+    }
+
+    pub fn update(&mut self, message: Message) -> Option<Action> {
+        match message {
+            Message::InputChanged(input) => {
+                self.input = input;
+                self.issue = None;
+            }
+            Message::Apply => {
+                let tick_size = self.input.trim().parse::<f32>().unwrap_or(f32::NAN);
+
+                match tick_override::validate(tick_size, self.broker_tick) {
+                    Ok(()) => return Some(self.set(tick_size)),
+                    Err(issue) => self.issue = Some(issue),
+                }
+            }
+            Message::Confirm => {
+                if let Some(Issue::NotMultiple { .. }) = self.issue
+                    && let Ok(tick_size) = self.input.trim().parse::<f32>()
+                {
+                    return Some(self.set(tick_size));
+                }
+            }
+            Message::Clear => {
+                return Some(Action::Set {
+                    ticker_info: self.ticker_info.with_min_ticksize(self.broker_tick),
+                    tick_override: None,
+                });
+            }
+            Message::Close => return Some(Action::Close),
+        }
+
+        None
+    }
+
+    fn set(&self, tick_size: f32) -> Action {
+        Action::Set {
+            ticker_info: self.ticker_info.with_min_ticksize(tick_size),
+            tick_override: Some(TickOverride {
+                tick_size,
+                broker_tick: self.broker_tick,
+            }),
+        }
+    }
+
+    pub fn view(&self) -> Element<'_, Message> {
+        let symbol = self.ticker_info.ticker.display_symbol_and_type().0;
+
+        let mut content = column![
+            text(t!("tick_size.title", symbol = symbol)).size(18),
+            text(t!("tick_size.broker_tick", tick = self.broker_tick)).size(13),
+        ]
+        .spacing(12)
+        .max_width(400);
+
+        if let Some(tiers) = self.ticker_info.tick_tiers {
+            let precision = tiers.precision();
+            let tiers = tiers
+                .iter()
+                .map(|(threshold, tick)| {
+                    format!(
+                        "{} ≥ {}",
+                        exchange::util::Price::from_units(tick.units).to_string(precision),
+                        threshold.to_string(precision)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            content = content.push(text(t!("tick_size.tiered", tiers = tiers)).size(13));
+        }
+
+        if let Some(current) = self.current {
+            content = content.push(
+                text(t!("tick_size.current_override", tick = current.tick_size))
+                    .size(13)
+                    .style(|theme: &iced::Theme| text::Style {
+                        color: Some(theme.extended_palette().warning.base.color),
+                    }),
+            );
+        }
+
+        content = content.push(text(t!("tick_size.hint")).size(12)).push(
+            text_input(t!("tick_size.placeholder"), &self.input)
+                .on_input(Message::InputChanged)
+                .on_submit(Message::Apply)
+                .padding(8)
+                .size(14),
+        );
+
+        if let Some(issue) = self.issue {
+            let message = match issue {
+                Issue::NotPositive => t!("tick_size.not_positive").to_string(),
+                Issue::NotPowerOfTen { nearest } => {
+                    t!("tick_size.not_power_of_ten", nearest = nearest)
+                }
+                Issue::NotMultiple { broker_tick } => t!(
+                    "tick_size.not_multiple",
+                    tick = self.input.trim(),
+                    broker = broker_tick
+                ),
+            };
+
+            content = content.push(text(message).size(12).style(move |theme: &iced::Theme| {
+                let palette = theme.extended_palette();
+                text::Style {
+                    color: Some(if issue.is_confirmable() {
+                        palette.warning.base.color
+                    } else {
+                        palette.danger.base.color
+                    }),
+                }
+            }));
+        }
+
+        let clear_btn = button(text(t!("tick_size.clear")).size(13))
+            .on_press_maybe(self.current.is_some().then_some(Message::Clear))
+            .style(button::secondary);
+        let cancel_btn = button(text(t!("common.cancel")).size(13))
+            .on_press(Message::Close)
+            .style(button::secondary);
+        let apply_btn = if self.issue.is_some_and(|issue| issue.is_confirmable()) {
+            button(text(t!("tick_size.apply_anyway")).size(13))
+                .on_press(Message::Confirm)
+                .style(button::danger)
+        } else {
+            button(text(t!("tick_size.apply")).size(13))
+                .on_press_maybe((!self.input.trim().is_empty()).then_some(Message::Apply))
+                .style(button::primary)
+        };
+
+        content = content.push(
+            row![clear_btn, space::horizontal(), cancel_btn, apply_btn]
+                .spacing(8)
+                .align_y(Alignment::Center),
+        );
+
+        container(content)
+            .padding(24)
+            .style(style::dashboard_modal)
+            .into()
+    }
+}
@@ -1,8 +1,12 @@
+pub mod connection_bar;
 pub mod pane;
 pub mod panel;
 pub mod sidebar;
 pub mod tickers_table;
 
+#[cfg(test)]
+mod harness;
+
 pub use sidebar::Sidebar;
 
 use super::DashboardError;
@@ -15,16 +19,35 @@ use crate::{
 };
 use data::{
     UserTimezone,
-    layout::{WindowSpec, pane::ContentKind},
+    chart::{
+        bar_close::BarCloseAlert,
+        heatmap::wall::WallEvent,
+        pattern::PatternMark,
+        price_alert::PriceAlert,
+        revision::{Revision, RevisionSettings},
+        spread_alert::{SpreadAlert, Widening},
+        stop_run::{StopRun, StopRunConfig},
+    },
+    indicators::signal,
+    kline_store::{self, KlineStore},
+    layout::{
+        WindowSpec,
+        pane::{ContentKind, LinkGroup},
+    },
+    stream_slots::{self, Claim, Priority, Slot},
+    terminal_chart::ChartSync,
 };
 use exchange::{
-    Kline, PushFrequency, StreamPairKind, TickMultiplier, TickerInfo, Timeframe, Trade,
+    BarStatus, Kline, PushFrequency, StreamPairKind, TickMultiplier, Ticker, TickerInfo, Timeframe,
+    Trade,
     adapter::{
-        self, AdapterError, Exchange, PersistStreamKind, ResolvedStream, StreamConfig, StreamKind,
-        StreamTicksize, UniqueStreams, binance, bybit, hyperliquid, metatrader5, okex,
+        self, AdapterError, Exchange, KlineFeed, PersistStreamKind, ResolvedStream, StreamConfig,
+        StreamKind, StreamTicksize, UniqueStreams, binance,
     },
     depth::Depth,
     fetcher::{FetchRange, FetchedData},
+    market_state::MarketState,
+    synthetic,
 };
 
 use iced::{
@@ -36,7 +59,20 @@ use iced::{
     },
 };
 use iced_futures::futures::TryFutureExt;
-use std::{collections::HashMap, path::PathBuf, time::Instant, vec};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    sync::Arc,
+    time::Instant,
+    vec,
+};
+
+/// Id of the container holding the main window's panes, exported as the whole layout
+const LAYOUT_CAPTURE_ID: &str = "dashboard-layout";
+
+pub fn layout_capture_id() -> iced::widget::Id {
+    iced::widget::Id::new(LAYOUT_CAPTURE_ID)
+}
 
 #[derive(Debug, Clone)]
 pub enum Message {
@@ -52,6 +88,57 @@ pub enum Message {
         data: FetchedData,
     },
     ResolveStreams(uuid::Uuid, Vec<PersistStreamKind>),
+    /// A pane asking for bars of a kline stream, answered through the [`KlineStore`]
+    RequestKlines {
+        pane_id: uuid::Uuid,
+        stream: StreamKind,
+        req_id: Option<uuid::Uuid>,
+        range: Option<(u64, u64)>,
+    },
+    KlinesFetched {
+        stream: StreamKind,
+        range: Option<(u64, u64)>,
+        result: Result<Vec<Kline>, String>,
+    },
+    BarClosed {
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        alert: BarCloseAlert,
+    },
+    PriceAlertTriggered {
+        ticker_info: TickerInfo,
+        alert: PriceAlert,
+    },
+    LiquidityWall {
+        ticker_info: TickerInfo,
+        wall: WallEvent,
+        sound: bool,
+    },
+    StopRuns {
+        ticker_info: TickerInfo,
+        config: StopRunConfig,
+        runs: Vec<StopRun>,
+    },
+    SpreadWidened {
+        ticker_info: TickerInfo,
+        alert: SpreadAlert,
+        widening: Widening,
+    },
+    /// Notices, errors and disables of signal scripts, by script name
+    Signals {
+        ticker_info: TickerInfo,
+        outputs: Vec<(String, signal::Output)>,
+    },
+    CandlePatterns {
+        ticker_info: TickerInfo,
+        marks: Vec<PatternMark>,
+    },
+    KlinesRevised {
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        revisions: Vec<Revision>,
+        applied: bool,
+    },
 }
 
 pub struct Dashboard {
@@ -59,7 +146,17 @@ pub struct Dashboard {
     pub focus: Option<(window::Id, pane_grid::Pane)>,
     pub popout: HashMap<window::Id, (pane_grid::State<pane::State>, WindowSpec)>,
     pub streams: UniqueStreams,
+    pub linked_timeframes: HashSet<LinkGroup>,
     layout_id: uuid::Uuid,
+    /// Bumped to restart a ticker's subscriptions, see [`Dashboard::resubscribe`]
+    resubscriptions: HashMap<Ticker, u32>,
+    /// History of the kline streams panes chart, fetched once however many panes show it
+    kline_store: KlineStore,
+    last_store_prune: Instant,
+    /// Order panes got or started waiting for their stream slots in
+    slot_order: u64,
+    /// Symbol the MT5 terminal's chart was pointed at, for connections syncing it
+    terminal_chart: ChartSync,
 }
 
 impl Default for Dashboard {
@@ -69,7 +166,13 @@ impl Default for Dashboard {
             focus: None,
             streams: UniqueStreams::default(),
             popout: HashMap::new(),
+            linked_timeframes: HashSet::new(),
             layout_id: uuid::Uuid::new_v4(),
+            resubscriptions: HashMap::new(),
+            kline_store: KlineStore::default(),
+            last_store_prune: Instant::now(),
+            slot_order: 0,
+            terminal_chart: ChartSync::default(),
         }
     }
 }
@@ -87,6 +190,58 @@ pub enum Event {
         pane_id: uuid::Uuid,
         streams: Vec<PersistStreamKind>,
     },
+    BarClosed {
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        alert: BarCloseAlert,
+    },
+    PriceAlertTriggered {
+        ticker_info: TickerInfo,
+        alert: PriceAlert,
+    },
+    LiquidityWall {
+        ticker_info: TickerInfo,
+        wall: WallEvent,
+        sound: bool,
+    },
+    StopRuns {
+        ticker_info: TickerInfo,
+        config: StopRunConfig,
+        runs: Vec<StopRun>,
+    },
+    SpreadWidened {
+        ticker_info: TickerInfo,
+        alert: SpreadAlert,
+        widening: Widening,
+    },
+    /// Notices, errors and disables of signal scripts, by script name
+    Signals {
+        ticker_info: TickerInfo,
+        outputs: Vec<(String, signal::Output)>,
+    },
+    /// Makes `alert` the default of the MT5 connection listing the ticker
+    SpreadAlertDefault {
+        ticker_info: TickerInfo,
+        alert: Option<SpreadAlert>,
+    },
+    CandlePatterns {
+        ticker_info: TickerInfo,
+        marks: Vec<PatternMark>,
+    },
+    DumpBook(TickerInfo),
+    /// Fetched history disagreed with bars a pane held, see [`data::chart::revision`]
+    KlinesRevised {
+        ticker_info: TickerInfo,
+        timeframe: Timeframe,
+        revisions: Vec<Revision>,
+        applied: bool,
+    },
+    /// Save an image of the container with the `target` id in `window`
+    ExportImage {
+        window: window::Id,
+        target: iced::widget::Id,
+        label: String,
+    },
 }
 
 impl Dashboard {
@@ -117,6 +272,7 @@ impl Dashboard {
     pub fn from_config(
         panes: Configuration<pane::State>,
         popout_windows: Vec<(Configuration<pane::State>, WindowSpec)>,
+        linked_timeframes: HashSet<LinkGroup>,
         layout_id: uuid::Uuid,
     ) -> Self {
         let panes = pane_grid::State::with_configuration(panes);
@@ -135,7 +291,13 @@ impl Dashboard {
             focus: None,
             streams: UniqueStreams::default(),
             popout,
+            linked_timeframes,
             layout_id,
+            resubscriptions: HashMap::new(),
+            kline_store: KlineStore::default(),
+            last_store_prune: Instant::now(),
+            slot_order: 0,
+            terminal_chart: ChartSync::default(),
         }
     }
 
@@ -225,6 +387,43 @@ impl Dashboard {
                         self.focus = Some((window, focus_pane.unwrap()));
                     }
                 }
+                pane::Message::ClonePane(pane, with_drawings) => {
+                    return (
+                        self.clone_pane(window, pane, with_drawings, main_window),
+                        None,
+                    );
+                }
+                pane::Message::SpreadAlertDefault(pane, alert) => {
+                    let ticker_info = self
+                        .get_pane(main_window.id, window, pane)
+                        .and_then(pane::State::stream_pair);
+
+                    if let Some(ticker_info) = ticker_info {
+                        return (
+                            Task::none(),
+                            Some(Event::SpreadAlertDefault { ticker_info, alert }),
+                        );
+                    }
+                }
+                pane::Message::DumpBook(pane) => {
+                    let ticker_info = self
+                        .get_pane(main_window.id, window, pane)
+                        .and_then(pane::State::stream_pair);
+
+                    if let Some(ticker_info) = ticker_info {
+                        return (Task::none(), Some(Event::DumpBook(ticker_info)));
+                    }
+                }
+                pane::Message::ExportImage(pane) => {
+                    if let Some(state) = self.get_pane(main_window.id, window, pane) {
+                        let event = Event::ExportImage {
+                            window,
+                            target: state.capture_id(),
+                            label: state.snapshot_label(),
+                        };
+                        return (Task::none(), Some(event));
+                    }
+                }
                 pane::Message::ClosePane(pane) => {
                     if let Some((_, sibling)) = self.panes.close(pane) {
                         self.focus = Some((window, sibling));
@@ -303,6 +502,34 @@ impl Dashboard {
                         state.content.change_visual_config(cfg);
                     }
                 }
+                pane::Message::ToggleLinkedTimeframe(pane, group, enabled) => {
+                    if enabled {
+                        self.linked_timeframes.insert(group);
+                    } else {
+                        self.linked_timeframes.remove(&group);
+                    }
+
+                    if let Some(state) = self.get_mut_pane(main_window.id, window, pane) {
+                        if let Some(crate::modal::pane::Modal::LinkGroup { sync_timeframe }) =
+                            &mut state.modal
+                        {
+                            *sync_timeframe = enabled;
+                        }
+
+                        if enabled && let Some(timeframe) = state.timeframe() {
+                            let origin = state.unique_id();
+                            return (
+                                self.sync_timeframe_in_group(
+                                    main_window.id,
+                                    group,
+                                    timeframe,
+                                    origin,
+                                ),
+                                None,
+                            );
+                        }
+                    }
+                }
                 pane::Message::SwitchLinkGroup(pane, group) => {
                     if group.is_none() {
                         if let Some(state) = self.get_mut_pane(main_window.id, window, pane) {
@@ -327,7 +554,7 @@ impl Dashboard {
                         state.modal = None;
 
                         if let Some(ticker_info) = maybe_ticker_info
-                            && state.stream_pair() != Some(ticker_info)
+                            && !state.shows(ticker_info)
                         {
                             let pane_id = state.unique_id();
                             let content_kind = state.content.kind();
@@ -338,10 +565,7 @@ impl Dashboard {
 
                             for stream in &streams {
                                 if let StreamKind::Kline { .. } = stream {
-                                    return (
-                                        kline_fetch_task(*layout_id, pane_id, *stream, None, None),
-                                        None,
-                                    );
+                                    return (kline_fetch_task(pane_id, *stream, None, None), None);
                                 }
                             }
                         }
@@ -354,9 +578,37 @@ impl Dashboard {
                     return (self.merge_pane(main_window), None);
                 }
                 pane::Message::PaneEvent(pane, local) => {
+                    let Some(state) = self.get_mut_pane(main_window.id, window, pane) else {
+                        return (Task::none(), None);
+                    };
+
+                    let prev_timeframe = state.timeframe();
+                    let effect = state.update(local);
+                    let (origin, group, timeframe) =
+                        (state.unique_id(), state.link_group, state.timeframe());
+
+                    let timeframe_linked =
+                        group.is_some_and(|g| self.linked_timeframes.contains(&g));
+
+                    let sync_task = match (group, timeframe) {
+                        (Some(group), Some(timeframe))
+                            if timeframe_linked
+                                && prev_timeframe.is_some()
+                                && prev_timeframe != Some(timeframe) =>
+                        {
+                            Some(self.sync_timeframe_in_group(
+                                main_window.id,
+                                group,
+                                timeframe,
+                                origin,
+                            ))
+                        }
+                        _ => None,
+                    };
+
                     if let Some(state) = self.get_mut_pane(main_window.id, window, pane) {
-                        let Some(effect) = state.update(local) else {
-                            return (Task::none(), None);
+                        let Some(effect) = effect else {
+                            return (sync_task.unwrap_or_else(Task::none), None);
                         };
 
                         let task = match effect {
@@ -370,11 +622,37 @@ impl Dashboard {
                             pane::Effect::SwitchTickersInGroup(ticker_info) => {
                                 self.switch_tickers_in_group(main_window.id, ticker_info)
                             }
+                            pane::Effect::ReloadTicker(ticker_info) => {
+                                let content_kind = state.content.kind();
+                                let task = self.init_pane(
+                                    main_window.id,
+                                    window,
+                                    pane,
+                                    ticker_info,
+                                    content_kind,
+                                );
+                                task.chain(self.refresh_streams(main_window.id))
+                            }
                             pane::Effect::FocusWidget(id) => {
                                 return (iced::widget::operation::focus(id), None);
                             }
+                            pane::Effect::CopyToClipboard(contents) => {
+                                return (iced::clipboard::write(contents), None);
+                            }
+                            pane::Effect::Notify(toast) => {
+                                return (
+                                    sync_task.unwrap_or_else(Task::none),
+                                    Some(Event::Notification(toast)),
+                                );
+                            }
                         };
-                        return (task, None);
+                        return (
+                            match sync_task {
+                                Some(sync) => Task::batch([task, sync]),
+                                None => task,
+                            },
+                            None,
+                        );
                     }
                 }
             },
@@ -399,6 +677,114 @@ impl Dashboard {
                     }),
                 );
             }
+            Message::RequestKlines {
+                pane_id,
+                stream,
+                req_id,
+                range,
+            } => {
+                let StreamKind::Kline {
+                    ticker_info,
+                    timeframe,
+                } = stream
+                else {
+                    return (Task::none(), None);
+                };
+                let waiter = kline_store::Waiter {
+                    pane: pane_id,
+                    req_id,
+                };
+
+                let task = match self
+                    .kline_store
+                    .request((ticker_info, timeframe), range, waiter)
+                {
+                    kline_store::Request::Fetch => Task::perform(
+                        exchange::limits::backfill(adapter::fetch_klines(
+                            ticker_info,
+                            timeframe,
+                            range,
+                        ))
+                        .map_err(|err| err.to_user_message().to_string()),
+                        move |result| Message::KlinesFetched {
+                            stream,
+                            range,
+                            result,
+                        },
+                    ),
+                    kline_store::Request::Join => Task::none(),
+                    kline_store::Request::Serve(klines) => {
+                        let coverage = self.kline_store.coverage(&(ticker_info, timeframe));
+                        if let Some(pane_state) =
+                            self.get_mut_pane_state_by_uuid(main_window.id, pane_id)
+                        {
+                            pane_state.set_coverage(&stream, &coverage);
+                        }
+
+                        Task::done(Message::DistributeFetchedData {
+                            layout_id: *layout_id,
+                            pane_id,
+                            stream,
+                            data: FetchedData::Klines {
+                                data: klines,
+                                req_id,
+                            },
+                        })
+                    }
+                };
+                return (task, None);
+            }
+            Message::KlinesFetched {
+                stream,
+                range,
+                result,
+            } => {
+                let StreamKind::Kline {
+                    ticker_info,
+                    timeframe,
+                } = stream
+                else {
+                    return (Task::none(), None);
+                };
+                let key = (ticker_info, timeframe);
+
+                let tasks: Vec<Task<Message>> = match result {
+                    Ok(klines) => self
+                        .kline_store
+                        .complete(&key, range, klines)
+                        .map(|delivery| {
+                            delivery
+                                .waiters
+                                .into_iter()
+                                .map(|waiter| {
+                                    Task::done(Message::DistributeFetchedData {
+                                        layout_id: *layout_id,
+                                        pane_id: waiter.pane,
+                                        stream,
+                                        data: FetchedData::Klines {
+                                            data: delivery.klines.clone(),
+                                            req_id: waiter.req_id,
+                                        },
+                                    })
+                                })
+                                .collect()
+                        })
+                        .unwrap_or_default(),
+                    Err(err) => self
+                        .kline_store
+                        .fail(&key, range)
+                        .into_iter()
+                        .map(|waiter| {
+                            Task::done(Message::ErrorOccurred(
+                                Some(waiter.pane),
+                                DashboardError::Fetch(err.clone()),
+                            ))
+                        })
+                        .collect(),
+                };
+                self.push_coverage(main_window.id);
+                return (Task::batch(tasks), None);
+            }
             Message::ResolveStreams(pane_id, streams) => {
                 return (
                     Task::none(),
@@ -408,6 +794,102 @@ impl Dashboard {
             Message::Notification(toast) => {
                 return (Task::none(), Some(Event::Notification(toast)));
             }
+            Message::PriceAlertTriggered { ticker_info, alert } => {
+                return (
+                    Task::none(),
+                    Some(Event::PriceAlertTriggered { ticker_info, alert }),
+                );
+            }
+            Message::LiquidityWall {
+                ticker_info,
+                wall,
+                sound,
+            } => {
+                return (
+                    Task::none(),
+                    Some(Event::LiquidityWall {
+                        ticker_info,
+                        wall,
+                        sound,
+                    }),
+                );
+            }
+            Message::StopRuns {
+                ticker_info,
+                config,
+                runs,
+            } => {
+                return (
+                    Task::none(),
+                    Some(Event::StopRuns {
+                        ticker_info,
+                        config,
+                        runs,
+                    }),
+                );
+            }
+            Message::Signals {
+                ticker_info,
+                outputs,
+            } => {
+                return (
+                    Task::none(),
+                    Some(Event::Signals {
+                        ticker_info,
+                        outputs,
+                    }),
+                );
+            }
+            Message::SpreadWidened {
+                ticker_info,
+                alert,
+                widening,
+            } => {
+                return (
+                    Task::none(),
+                    Some(Event::SpreadWidened {
+                        ticker_info,
+                        alert,
+                        widening,
+                    }),
+                );
+            }
+            Message::CandlePatterns { ticker_info, marks } => {
+                return (
+                    Task::none(),
+                    Some(Event::CandlePatterns { ticker_info, marks }),
+                );
+            }
+            Message::BarClosed {
+                ticker_info,
+                timeframe,
+                alert,
+            } => {
+                return (
+                    Task::none(),
+                    Some(Event::BarClosed {
+                        ticker_info,
+                        timeframe,
+                        alert,
+                    }),
+                );
+            }
+            Message::KlinesRevised {
+                ticker_info,
+                timeframe,
+                revisions,
+                applied,
+            } => {
+                return (
+                    Task::none(),
+                    Some(Event::KlinesRevised {
+                        ticker_info,
+                        timeframe,
+                        revisions,
+                        applied,
+                    }),
+                );
+            }
         }
 
         (Task::none(), None)
@@ -469,6 +951,59 @@ impl Dashboard {
         Task::none()
     }
 
+    /// Inserts a copy of the pane's persisted configuration next to it. The copy is a fresh pane
+    /// that resolves and starts its streams like a pane loaded from a saved layout.
+    fn clone_pane(
+        &mut self,
+        window: window::Id,
+        pane: pane_grid::Pane,
+        with_drawings: bool,
+        main_window: &Window,
+    ) -> Task<Message> {
+        let Some(source) = self.get_mut_pane(main_window.id, window, pane) else {
+            return Task::none();
+        };
+        source.modal = None;
+
+        let unavailable = source
+            .streams
+            .clone()
+            .into_waiting()
+            .iter()
+            .map(|stream| stream.ticker().exchange)
+            .find(|exchange| adapter::adapter(*exchange).is_none());
+
+        if let Some(exchange) = unavailable {
+            source.notifications.push(Toast::error(format!(
+                "Can't clone this pane: {exchange} isn't connected, no stream can be started for it"
+            )));
+            return Task::none();
+        }
+
+        let mut config = data::Pane::from(&*source);
+        config.renew_ids();
+        if !with_drawings && let Some(settings) = config.settings_mut() {
+            settings.drawings = Default::default();
+        }
+
+        let pane_grid::Configuration::Pane(clone) = crate::layout::configuration(config) else {
+            return Task::none();
+        };
+
+        let panes = if window == main_window.id {
+            &mut self.panes
+        } else if let Some((panes, _)) = self.popout.get_mut(&window) {
+            panes
+        } else {
+            return Task::none();
+        };
+
+        match panes.split(pane_grid::Axis::Vertical, pane, clone) {
+            Some((new_pane, _)) => self.focus_pane(window, new_pane),
+            None => Task::none(),
+        }
+    }
+
     fn popout_pane(&mut self, main_window: &Window) -> Task<Message> {
         if let Some((_, id)) = self.focus.take()
             && let Some((pane, _)) = self.panes.close(id)
@@ -588,6 +1123,7 @@ impl Dashboard {
                 self.panes.len(),
                 is_focused,
                 maximized,
+                self.timeframe_linked(pane),
                 main_window.id,
                 main_window,
                 timezone,
@@ -602,6 +1138,12 @@ impl Dashboard {
         .style(style::pane_grid)
         .into();
 
+        let pane_grid: Element<_> = container(pane_grid)
+            .id(layout_capture_id())
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into();
+
         pane_grid.map(move |message| Message::Pane(main_window.id, message))
     }
 
@@ -621,6 +1163,7 @@ impl Dashboard {
                         state.len(),
                         is_focused,
                         false,
+                        self.timeframe_linked(pane),
                         window,
                         main_window,
                         timezone,
@@ -690,7 +1233,7 @@ impl Dashboard {
 
             for stream in &streams {
                 if let StreamKind::Kline { .. } = stream {
-                    return kline_fetch_task(self.layout_id, pane_id, *stream, None, None);
+                    return kline_fetch_task(pane_id, *stream, None, None);
                 }
             }
         }
@@ -715,7 +1258,7 @@ impl Dashboard {
             && let Some(state) = self.get_mut_pane(main_window, window, selected_pane)
         {
             let previous_ticker = state.stream_pair();
-            if previous_ticker.is_some() && previous_ticker != Some(ticker_info) {
+            if previous_ticker.is_some() && !state.shows(ticker_info) {
                 state.link_group = None;
             }
 
@@ -726,7 +1269,7 @@ impl Dashboard {
 
             for stream in &streams {
                 if let StreamKind::Kline { .. } = stream {
-                    return kline_fetch_task(self.layout_id, pane_id, *stream, None, None);
+                    return kline_fetch_task(pane_id, *stream, None, None);
                 }
             }
             return Task::none();
@@ -758,7 +1301,7 @@ impl Dashboard {
             let pane_infos: Vec<(window::Id, pane_grid::Pane, ContentKind)> = self
                 .iter_all_panes_mut(main_window)
                 .filter_map(|(window, pane, state)| {
-                    if state.link_group == Some(group) {
+                    if state.link_group == Some(group) && !state.shows(ticker_info) {
                         Some((window, pane, state.content.kind()))
                     } else {
                         None
@@ -790,6 +1333,170 @@ impl Dashboard {
         }
     }
 
+    /// Rebinds MT5 panes whose symbol isn't in `symbols` to the connection's name for the same
+    /// instrument, e.g. `EURUSD.a` to `EURUSDm` after switching brokers. Panes listing the same
+    /// name take the connection's ticker quietly. Returns the remapped pairs; panes with several
+    /// candidates open their symbol search instead of guessing.
+    pub fn remap_mt5_tickers(
+        &mut self,
+        main_window: window::Id,
+        symbols: &HashMap<Ticker, Option<TickerInfo>>,
+    ) -> (Task<Message>, Vec<(Ticker, Ticker)>) {
+        use crate::modal::pane::{Modal, mini_tickers_list::MiniPanel};
+        use exchange::adapter::metatrader5::{
+            self,
+            suffix::{self, SymbolMatch},
+        };
+
+        let listed = symbols
+            .keys()
+            .map(|ticker| (ticker.to_string(), *ticker))
+            .collect::<HashMap<_, _>>();
+        let connection = symbols.keys().find_map(Ticker::connection);
+        // Tickers of another connection still set up are that connection's to keep
+        let remappable = |ticker: &Ticker| {
+            ticker.connection().is_none_or(|listed_by| {
+                Some(listed_by) == connection || !metatrader5::has_connection(listed_by)
+            })
+        };
+
+        let mut rebinds = vec![];
+        for (window, pane, state) in self.iter_all_panes_mut(main_window) {
+            let ticker = match &state.streams {
+                ResolvedStream::Ready(_) => match state.stream_pair_kind() {
+                    Some(StreamPairKind::SingleSource(ticker_info)) => Some(ticker_info.ticker),
+                    _ => None,
+                },
+                ResolvedStream::Waiting(streams) => streams.first().map(PersistStreamKind::ticker),
+            };
+            // Panes pinned to another connection stream its symbols
+            let pinned_elsewhere = state
+                .settings
+                .source
+                .as_ref()
+                .is_some_and(|source| Some(source.id()) != connection);
+            let Some(ticker) = ticker.filter(|t| {
+                t.exchange == Exchange::MetaTrader5
+                    && !symbols.contains_key(t)
+                    && remappable(t)
+                    && !pinned_elsewhere
+            }) else {
+                continue;
+            };
+
+            let symbol = ticker.to_string();
+            let target = match suffix::find_match(&symbol, listed.keys().map(String::as_str)) {
+                SymbolMatch::Exact => listed.get(&symbol).map(|target| (*target, false)),
+                SymbolMatch::Unique(name) => listed.get(name).map(|target| (*target, true)),
+                SymbolMatch::Ambiguous(candidates) => {
+                    state.modal = Some(Modal::MiniTickersList(MiniPanel::with_query(
+                        &suffix::normalize(&symbol),
+                    )));
+                    state.notifications.push(Toast::warn(format!(
+                        "{symbol} isn't listed, pick one of {}",
+                        candidates.join(", ")
+                    )));
+                    None
+                }
+                SymbolMatch::NotFound => None,
+            };
+
+            if let Some((target, renamed)) = target
+                && let Some(Some(ticker_info)) = symbols.get(&target)
+            {
+                let kind = state.content.kind();
+                rebinds.push((window, pane, ticker, *ticker_info, kind, renamed));
+            }
+        }
+
+        let mut remapped = vec![];
+        let tasks = rebinds
+            .into_iter()
+            .map(|(window, pane, from, ticker_info, content_kind, renamed)| {
+                if renamed {
+                    remapped.push((from, ticker_info.ticker));
+                }
+                self.init_pane(main_window, window, pane, ticker_info, content_kind)
+            })
+            .collect::<Vec<_>>();
+
+        (Task::batch(tasks), remapped)
+    }
+
+    /// Rebuilds the panes streaming `ticker_info`'s ticker with its tick size, keeping their pins
+    pub fn retick(&mut self, main_window: window::Id, ticker_info: TickerInfo) -> Task<Message> {
+        let panes = self
+            .iter_all_panes_mut(main_window)
+            .filter_map(|(window, pane, state)| match state.stream_pair_kind() {
+                Some(StreamPairKind::SingleSource(current))
+                    if current.ticker == ticker_info.ticker =>
+                {
+                    Some((window, pane, current.source, state.content.kind()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        if panes.is_empty() {
+            return Task::none();
+        }
+
+        let tasks = panes
+            .into_iter()
+            .map(|(window, pane, source, content_kind)| {
+                self.init_pane(
+                    main_window,
+                    window,
+                    pane,
+                    ticker_info.pinned(source),
+                    content_kind,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        Task::batch(tasks).chain(self.refresh_streams(main_window))
+    }
+
+    /// Whether the pane's link group shares timeframe changes as well
+    fn timeframe_linked(&self, pane: &pane::State) -> bool {
+        pane.link_group
+            .is_some_and(|group| self.linked_timeframes.contains(&group))
+    }
+
+    /// Applies `timeframe` to every other pane of `group`, only panes that actually change refetch
+    fn sync_timeframe_in_group(
+        &mut self,
+        main_window: window::Id,
+        group: LinkGroup,
+        timeframe: Timeframe,
+        origin: uuid::Uuid,
+    ) -> Task<Message> {
+        let layout_id = self.layout_id;
+        let mut tasks = vec![];
+        let mut needs_refresh = false;
+
+        self.iter_all_panes_mut(main_window)
+            .filter(|(_, _, state)| state.link_group == Some(group) && state.unique_id() != origin)
+            .for_each(|(_, _, state)| match state.sync_timeframe(timeframe) {
+                Some(pane::Effect::RequestFetch(reqs)) => {
+                    needs_refresh = true;
+                    tasks.push(request_fetch_many(
+                        state,
+                        layout_id,
+                        reqs.into_iter().map(|r| (r.req_id, r.fetch, r.stream)),
+                    ));
+                }
+                Some(pane::Effect::RefreshStreams) => needs_refresh = true,
+                Some(_) | None => {}
+            });
+
+        if needs_refresh {
+            tasks.push(self.refresh_streams(main_window));
+        }
+
+        Task::batch(tasks)
+    }
+
     pub fn toggle_trade_fetch(&mut self, is_enabled: bool, main_window: &Window) {
         exchange::fetcher::toggle_trade_fetch(is_enabled);
 
@@ -808,12 +1515,14 @@ impl Dashboard {
             });
     }
 
+    /// `revisions` says how MT5 history overlapping bars a pane already holds is reconciled
     pub fn distribute_fetched_data(
         &mut self,
         main_window: window::Id,
         pane_id: uuid::Uuid,
         data: FetchedData,
         stream_type: StreamKind,
+        revisions: &RevisionSettings,
     ) -> Task<Message> {
         match data {
             FetchedData::Trades { batch, until_time } => {
@@ -848,7 +1557,25 @@ impl Dashboard {
                         ticker_info,
                     } = stream_type
                     {
-                        pane_state.insert_hist_klines(req_id, timeframe, ticker_info, &data);
+                        // Brokers repaint recent bars, crypto history is final
+                        let revisions =
+                            (ticker_info.exchange() == Exchange::MetaTrader5).then_some(revisions);
+                        let revised = pane_state.insert_hist_klines(
+                            req_id,
+                            timeframe,
+                            ticker_info,
+                            &data,
+                            revisions,
+                        );
+
+                        if !revised.is_empty() {
+                            return Task::done(Message::KlinesRevised {
+                                ticker_info,
+                                timeframe,
+                                applied: revisions.is_some_and(|s| s.auto_apply),
+                                revisions: revised,
+                            });
+                        }
                     }
                 }
             }
@@ -861,6 +1588,17 @@ impl Dashboard {
                     }
                 }
             }
+            FetchedData::Depth { data, req_id } => {
+                if let Some(pane_state) = self.get_mut_pane_state_by_uuid(main_window, pane_id) {
+                    pane_state.status = pane::Status::Ready;
+                    pane_state.insert_hist_depth(req_id, data);
+                }
+            }
+            FetchedData::Calendar { data, req_id } => {
+                if let Some(pane_state) = self.get_mut_pane_state_by_uuid(main_window, pane_id) {
+                    pane_state.insert_calendar(req_id, data);
+                }
+            }
         }
 
         Task::none()
@@ -913,26 +1651,56 @@ impl Dashboard {
         }
     }
 
-    pub fn update_latest_klines(
+    /// Routes a market stream event to the panes showing its stream. What the app does besides,
+    /// notifying about a disconnect or playing trade sounds, is up to the caller.
+    pub fn on_market_event(
         &mut self,
-        stream: &StreamKind,
-        kline: &Kline,
+        event: &exchange::Event,
         main_window: window::Id,
     ) -> Task<Message> {
-        let mut found_match = false;
-
-        self.iter_all_panes_mut(main_window)
-            .for_each(|(_, _, pane_state)| {
-                if pane_state.matches_stream(stream) {
-                    match &mut pane_state.content {
-                        pane::Content::Kline { chart: Some(c), .. } => {
-                            c.update_latest_kline(kline);
-                        }
-                        pane::Content::Comparison(Some(c)) => {
-                            c.update_latest_kline(&stream.ticker_info(), kline);
-                        }
-                        _ => {}
-                    }
+        match event {
+            exchange::Event::Connected(_) | exchange::Event::Disconnected(..) => Task::none(),
+            exchange::Event::DepthReceived(stream, depth_update_t, depth, trades_buffer) => self
+                .update_depth_and_trades(
+                    stream,
+                    *depth_update_t,
+                    depth,
+                    trades_buffer,
+                    main_window,
+                ),
+            exchange::Event::MarketStateChanged(stream, state) => {
+                self.update_market_state(stream, *state, main_window);
+                Task::none()
+            }
+            exchange::Event::KlineReceived(stream, kline, status) => {
+                self.update_latest_klines(stream, kline, *status, main_window)
+            }
+        }
+    }
+
+    pub fn update_latest_klines(
+        &mut self,
+        stream: &StreamKind,
+        kline: &Kline,
+        status: BarStatus,
+        main_window: window::Id,
+    ) -> Task<Message> {
+        let mut found_match = false;
+
+        if let StreamKind::Kline {
+            ticker_info,
+            timeframe,
+        } = stream
+        {
+            self.kline_store
+                .apply_live(&(*ticker_info, *timeframe), kline, status);
+            self.push_coverage(main_window);
+        }
+
+        self.iter_all_panes_mut(main_window)
+            .for_each(|(_, _, pane_state)| {
+                if pane_state.matches_stream(stream) {
+                    pane_state.insert_kline(stream, kline, status);
                     found_match = true;
                 }
             });
@@ -945,11 +1713,28 @@ impl Dashboard {
         }
     }
 
+    /// Applies a venue's trading state to every pane showing the stream's ticker
+    pub fn update_market_state(
+        &mut self,
+        stream: &StreamKind,
+        state: MarketState,
+        main_window: window::Id,
+    ) {
+        let ticker_info = stream.ticker_info();
+
+        self.iter_all_panes_mut(main_window)
+            .for_each(|(_, _, pane_state)| {
+                if pane_state.stream_pair() == Some(ticker_info) {
+                    pane_state.set_market_state(ticker_info, state);
+                }
+            });
+    }
+
     pub fn update_depth_and_trades(
         &mut self,
         stream: &StreamKind,
         depth_update_t: u64,
-        depth: &Depth,
+        depth: &Arc<Depth>,
         trades_buffer: &[Trade],
         main_window: window::Id,
     ) -> Task<Message> {
@@ -958,31 +1743,12 @@ impl Dashboard {
         self.iter_all_panes_mut(main_window)
             .for_each(|(_, _, pane_state)| {
                 if pane_state.matches_stream(stream) {
-                    match &mut pane_state.content {
-                        pane::Content::Heatmap { chart, .. } => {
-                            if let Some(c) = chart {
-                                c.insert_datapoint(trades_buffer, depth_update_t, depth);
-                            }
-                        }
-                        pane::Content::Kline { chart, .. } => {
-                            if let Some(c) = chart {
-                                c.insert_trades_buffer(trades_buffer);
-                            }
-                        }
-                        pane::Content::TimeAndSales(panel) => {
-                            if let Some(p) = panel {
-                                p.insert_buffer(trades_buffer);
-                            }
-                        }
-                        pane::Content::Ladder(panel) => {
-                            if let Some(panel) = panel {
-                                panel.insert_buffers(depth_update_t, depth, trades_buffer);
-                            }
-                        }
-                        _ => {
-                            log::error!("No chart found for the stream: {stream:?}");
-                        }
-                    }
+                    pane_state.insert_depth_and_trades(
+                        stream,
+                        depth_update_t,
+                        depth,
+                        trades_buffer,
+                    );
                     found_match = true;
                 }
             });
@@ -995,6 +1761,16 @@ impl Dashboard {
         }
     }
 
+    /// Hands every heatmap's unsaved depth history to the history writer
+    pub fn save_heatmap_history(&mut self, main_window: window::Id) {
+        self.iter_all_panes_mut(main_window)
+            .for_each(|(_, _, state)| {
+                if let pane::Content::Heatmap { chart: Some(c), .. } = &mut state.content {
+                    c.save_history();
+                }
+            });
+    }
+
     pub fn invalidate_all_panes(&mut self, main_window: window::Id) {
         self.iter_all_panes_mut(main_window)
             .for_each(|(_, _, state)| {
@@ -1002,10 +1778,88 @@ impl Dashboard {
             });
     }
 
-    pub fn tick(&mut self, now: Instant, main_window: window::Id) -> Task<Message> {
+    pub fn tick(
+        &mut self,
+        now: Instant,
+        main_window: window::Id,
+        timezone: UserTimezone,
+    ) -> Task<Message> {
         let mut tasks = vec![];
         let layout_id = self.layout_id;
 
+        // Focus, maximized panes and the limits themselves change without a stream refresh
+        if self.allocate_stream_slots(main_window) {
+            tasks.push(self.refresh_streams(main_window));
+        }
+
+        if now.duration_since(self.last_store_prune) >= data::retention::PRUNE_INTERVAL {
+            self.last_store_prune = now;
+            self.sync_kline_store(main_window);
+            self.kline_store.prune(&data::retention::config());
+            self.push_coverage(main_window);
+        }
+
+        if let Some(task) = self.sync_terminal_chart(now, main_window) {
+            tasks.push(task);
+        }
+
+        self.iter_all_panes_mut(main_window)
+            .for_each(|(_window_id, _pane, state)| {
+                for (ticker_info, alert) in state.poll_chart_overlays(timezone) {
+                    tasks.push(Task::done(Message::PriceAlertTriggered {
+                        ticker_info,
+                        alert,
+                    }));
+                }
+                for error in state.take_overlay_errors() {
+                    tasks.push(Task::done(Message::Notification(Toast::error(error))));
+                }
+                if let Some((ticker_info, outputs)) = state.poll_signals() {
+                    tasks.push(Task::done(Message::Signals {
+                        ticker_info,
+                        outputs,
+                    }));
+                }
+
+                if let Some((ticker_info, config, events)) = state.poll_wall_events() {
+                    tasks.extend(events.into_iter().map(|wall| {
+                        Task::done(Message::LiquidityWall {
+                            ticker_info,
+                            wall,
+                            sound: config.sound,
+                        })
+                    }));
+                }
+
+                if let Some((ticker_info, config, runs)) = state.poll_stop_runs() {
+                    tasks.push(Task::done(Message::StopRuns {
+                        ticker_info,
+                        config,
+                        runs,
+                    }));
+                }
+
+                if let Some((ticker_info, alert, widening)) = state.poll_spread_widening() {
+                    tasks.push(Task::done(Message::SpreadWidened {
+                        ticker_info,
+                        alert,
+                        widening,
+                    }));
+                }
+
+                if let Some((ticker_info, marks)) = state.poll_patterns() {
+                    tasks.push(Task::done(Message::CandlePatterns { ticker_info, marks }));
+                }
+
+                if let Some((ticker_info, timeframe, alert)) = state.poll_bar_close() {
+                    tasks.push(Task::done(Message::BarClosed {
+                        ticker_info,
+                        timeframe,
+                        alert,
+                    }));
+                }
+            });
+
         self.iter_all_panes_mut(main_window)
             .for_each(|(_window_id, _pane, state)| match state.tick(now) {
                 Some(pane::Action::Chart(action)) => match action {
@@ -1013,6 +1867,9 @@ impl Dashboard {
                         state.status = pane::Status::Ready;
                         state.notifications.push(Toast::error(err.to_string()));
                     }
+                    chart::Action::Notice(notice) => {
+                        state.notifications.push(Toast::warn(notice));
+                    }
                     chart::Action::RequestFetch(reqs) => {
                         tasks.push(request_fetch_many(
                             state,
@@ -1043,6 +1900,43 @@ impl Dashboard {
         Task::batch(tasks)
     }
 
+    /// Points the MT5 terminal's chart at the focused pane's symbol once it settles, when the
+    /// connection the pane streams through syncs it. Failures are only logged.
+    fn sync_terminal_chart(
+        &mut self,
+        now: Instant,
+        main_window: window::Id,
+    ) -> Option<Task<Message>> {
+        use exchange::adapter::metatrader5;
+
+        let syncs = |ticker_info: &TickerInfo| {
+            metatrader5::config_for(ticker_info).is_some_and(|config| config.sync_terminal_chart)
+        };
+        let focused = self
+            .focus
+            .and_then(|(window, pane)| self.get_pane(main_window, window, pane))
+            .and_then(|state| Some((state.stream_pair()?, state.timeframe())))
+            .filter(|(ticker_info, _)| syncs(ticker_info));
+
+        let (ticker_info, timeframe) = self.terminal_chart.poll(focused, now)?;
+        let config = metatrader5::config_for(&ticker_info)?;
+
+        Some(
+            Task::future(async move {
+                if let Err(e) =
+                    metatrader5::set_chart_symbol(&config, ticker_info.ticker, timeframe).await
+                {
+                    log::warn!(
+                        "Couldn't point the MT5 terminal chart at {}: {}",
+                        ticker_info.ticker,
+                        config.redact(&e.to_string())
+                    );
+                }
+            })
+            .discard(),
+        )
+    }
+
     pub fn resolve_streams(
         &mut self,
         main_window: window::Id,
@@ -1050,12 +1944,17 @@ impl Dashboard {
         streams: Vec<StreamKind>,
     ) -> Task<Message> {
         if let Some(state) = self.get_mut_pane_state_by_uuid(main_window, pane_id) {
-            state.streams = ResolvedStream::Ready(streams.clone());
+            state.streams = ResolvedStream::Ready(state.pin_streams(streams));
         }
         self.refresh_streams(main_window)
     }
 
     pub fn market_subscriptions(&self) -> Subscription<exchange::Event> {
+        // Symbols the broker stopped listing aren't subscribed, their panes keep the history
+        let is_listed = |ticker: &TickerInfo| {
+            !exchange::adapter::metatrader5::retired::is_retired(&ticker.ticker)
+        };
+
         let unique_streams = self
             .streams
             .combined_used()
@@ -1066,12 +1965,14 @@ impl Dashboard {
                     let depth_subs = specs
                         .depth
                         .iter()
+                        .filter(|(ticker, ..)| is_listed(ticker))
                         .map(|(ticker, aggr, push_freq)| {
                             let tick_mltp = match aggr {
                                 StreamTicksize::Client => None,
                                 StreamTicksize::ServerSide(tick_mltp) => Some(*tick_mltp),
                             };
-                            depth_subscription(*ticker, tick_mltp, *push_freq)
+                            let epoch = self.resubscription(&ticker.ticker);
+                            with_epoch(depth_subscription(*ticker, tick_mltp, *push_freq), epoch)
                         })
                         .collect::<Vec<_>>();
 
@@ -1080,14 +1981,32 @@ impl Dashboard {
                     }
                 }
 
-                let kline_params = specs
+                let (synthetic_klines, kline_params): (Vec<_>, Vec<_>) = specs
                     .kline
                     .iter()
+                    .filter(|(ticker, _)| is_listed(ticker))
                     .map(|(ticker, timeframe)| (*ticker, *timeframe))
-                    .collect::<Vec<_>>();
+                    .partition(|(ticker_info, _)| synthetic::is_synthetic(&ticker_info.ticker));
+
+                subs.extend(
+                    synthetic_klines
+                        .into_iter()
+                        .map(|(ticker_info, timeframe)| {
+                            let epoch = self.resubscription(&ticker_info.ticker);
+                            with_epoch(synthetic_kline_subscription(ticker_info, timeframe), epoch)
+                        }),
+                );
 
                 if !kline_params.is_empty() {
-                    subs.push(kline_subscription(exchange, kline_params));
+                    // Batched feeds restart as a whole for any of their tickers
+                    let epoch = kline_params
+                        .iter()
+                        .map(|(ticker_info, _)| self.resubscription(&ticker_info.ticker))
+                        .sum();
+                    subs.push(with_epoch(
+                        kline_subscription(exchange, kline_params),
+                        epoch,
+                    ));
                 }
 
                 subs
@@ -1097,14 +2016,243 @@ impl Dashboard {
         Subscription::batch(unique_streams)
     }
 
+    fn resubscription(&self, ticker: &Ticker) -> u32 {
+        self.resubscriptions.get(ticker).copied().unwrap_or(0)
+    }
+
+    /// Drops and reopens the subscriptions carrying the stream's ticker
+    pub fn resubscribe(&mut self, stream: &StreamKind) {
+        let ticker = stream.ticker_info().ticker;
+        log::info!("Resubscribing to {ticker} streams");
+
+        let epoch = self.resubscriptions.entry(ticker).or_default();
+        *epoch = epoch.wrapping_add(1);
+    }
+
+    /// Drops and reopens the subscriptions of every `exchange` ticker served through its active
+    /// connection, pinned panes keep theirs
+    pub fn resubscribe_exchange(&mut self, exchange: Exchange) {
+        for ticker_info in self.streams.tickers() {
+            if ticker_info.exchange() == exchange && ticker_info.source.is_none() {
+                let epoch = self.resubscriptions.entry(ticker_info.ticker).or_default();
+                *epoch = epoch.wrapping_add(1);
+            }
+        }
+    }
+
+    /// The streams the panes use, for the connection bar
+    pub fn connection_entries(&self, main_window: window::Id) -> Vec<connection_bar::Entry> {
+        self.stream_users(main_window)
+            .into_iter()
+            .map(|(stream, panes)| connection_bar::Entry {
+                stream,
+                paused: panes.iter().all(|(window, pane)| {
+                    self.get_pane(main_window, *window, *pane)
+                        .is_some_and(pane::State::is_paused)
+                }),
+                panes: panes.len(),
+            })
+            .collect()
+    }
+
+    /// What each pane shows, one line each, for support bundles
+    pub fn pane_summary(&self, main_window: window::Id) -> Vec<String> {
+        self.iter_all_panes(main_window)
+            .map(|(window, _, state)| {
+                format!(
+                    "{}: {}{}",
+                    if window == main_window {
+                        "main"
+                    } else {
+                        "popout"
+                    },
+                    state.snapshot_label(),
+                    if state.is_paused() { " (paused)" } else { "" }
+                )
+            })
+            .collect()
+    }
+
+    /// Every stream the panes use, with the panes using it
+    fn stream_users(
+        &self,
+        main_window: window::Id,
+    ) -> Vec<(StreamKind, Vec<(window::Id, pane_grid::Pane)>)> {
+        let mut users: Vec<(StreamKind, Vec<(window::Id, pane_grid::Pane)>)> = vec![];
+
+        for (window, pane, state) in self.iter_all_panes(main_window) {
+            for stream in state.streams.ready_iter().into_iter().flatten() {
+                match users.iter_mut().find(|(used, _)| used == stream) {
+                    Some((_, panes)) => panes.push((window, pane)),
+                    None => users.push((*stream, vec![(window, pane)])),
+                }
+            }
+        }
+
+        users
+    }
+
+    /// Pauses every pane using the stream, or resumes them if they all are paused
+    pub fn toggle_stream_pause(&mut self, stream: &StreamKind, main_window: window::Id) {
+        let uses = |state: &pane::State| {
+            state
+                .streams
+                .ready_iter()
+                .is_some_and(|mut streams| streams.any(|used| used == stream))
+        };
+
+        let pause = self
+            .iter_all_panes(main_window)
+            .any(|(_, _, state)| uses(state) && !state.is_paused());
+        let now = Instant::now();
+
+        for (_, _, state) in self.iter_all_panes_mut(main_window) {
+            if uses(state) && state.is_paused() != pause {
+                state.toggle_pause(now);
+            }
+        }
+    }
+
+    /// Focuses the first pane using the stream, bringing its window forward
+    pub fn focus_stream(&mut self, stream: &StreamKind, main_window: window::Id) -> Task<Message> {
+        let Some((window, pane)) = self
+            .stream_users(main_window)
+            .into_iter()
+            .find(|(used, _)| used == stream)
+            .and_then(|(_, panes)| panes.first().copied())
+        else {
+            return Task::none();
+        };
+
+        let task = self.focus_pane(window, pane);
+        if window == main_window {
+            task
+        } else {
+            task.chain(iced::window::gain_focus(window))
+        }
+    }
+
     fn refresh_streams(&mut self, main_window: window::Id) -> Task<Message> {
+        self.allocate_stream_slots(main_window);
+
         let all_pane_streams = self
             .iter_all_panes(main_window)
+            .filter(|(_, _, pane_state)| pane_state.is_streaming())
             .flat_map(|(_, _, pane_state)| pane_state.streams.ready_iter().into_iter().flatten());
         self.streams = UniqueStreams::from(all_pane_streams);
+        self.sync_kline_store(main_window);
 
         Task::none()
     }
+
+    /// Hands the stream slots out under the configured limits, see [`data::stream_slots`].
+    /// Returns whether a pane got or lost its streams.
+    fn allocate_stream_slots(&mut self, main_window: window::Id) -> bool {
+        let maximized = |window: window::Id| {
+            if window == main_window {
+                self.panes.maximized()
+            } else {
+                self.popout
+                    .get(&window)
+                    .and_then(|(panes, _)| panes.maximized())
+            }
+        };
+
+        let mut order = self.slot_order;
+        let mut ids = vec![];
+        let mut claims = vec![];
+
+        for (window, pane, state) in self.iter_all_panes(main_window) {
+            let Some(streams) = state.streams.ready_iter() else {
+                continue;
+            };
+
+            let priority = if self.focus == Some((window, pane)) {
+                Priority::Focused
+            } else if maximized(window).is_none_or(|shown| shown == pane) {
+                Priority::Visible
+            } else {
+                Priority::Hidden
+            };
+            let slot = state.stream_slot().unwrap_or_else(|| {
+                order += 1;
+                Slot::Waiting(order)
+            });
+
+            ids.push(state.unique_id());
+            claims.push(Claim {
+                priority,
+                slot,
+                streams: streams.copied().collect(),
+            });
+        }
+
+        let granted = stream_slots::allocate(&claims, &exchange::limits::config());
+        let slots = ids
+            .into_iter()
+            .zip(claims)
+            .zip(granted)
+            .map(|((id, claim), granted)| {
+                let slot = match (claim.slot, granted) {
+                    (Slot::Held(_), true) | (Slot::Waiting(_), false) => claim.slot,
+                    (Slot::Waiting(_), true) => {
+                        order += 1;
+                        Slot::Held(order)
+                    }
+                    (Slot::Held(_), false) => {
+                        order += 1;
+                        Slot::Waiting(order)
+                    }
+                };
+                (id, slot)
+            })
+            .collect::<HashMap<_, _>>();
+        self.slot_order = order;
+
+        let mut changed = false;
+        for (_, _, state) in self.iter_all_panes_mut(main_window) {
+            let slot = slots.get(&state.unique_id()).copied();
+            changed |= state.is_streaming() != slot.is_some_and(Slot::is_held);
+            state.set_stream_slot(slot);
+        }
+        changed
+    }
+
+    /// Hands the coverage of kline streams that changed to the panes charting them
+    fn push_coverage(&mut self, main_window: window::Id) {
+        for ((ticker_info, timeframe), coverage) in self.kline_store.coverage_changes() {
+            let stream = StreamKind::Kline {
+                ticker_info,
+                timeframe,
+            };
+            self.iter_all_panes_mut(main_window)
+                .for_each(|(_, _, state)| state.set_coverage(&stream, &coverage));
+        }
+    }
+
+    /// Subscribes every pane to the history of the kline streams it charts
+    fn sync_kline_store(&mut self, main_window: window::Id) {
+        let subscriptions = self
+            .iter_all_panes(main_window)
+            .flat_map(|(_, _, pane_state)| {
+                let pane_id = pane_state.unique_id();
+                pane_state
+                    .streams
+                    .ready_iter()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(move |stream| match stream {
+                        StreamKind::Kline {
+                            ticker_info,
+                            timeframe,
+                        } => Some(((*ticker_info, *timeframe), pane_id)),
+                        _ => None,
+                    })
+            })
+            .collect::<Vec<_>>();
+
+        self.kline_store.sync(subscriptions);
+    }
 }
 
 fn request_fetch(
@@ -1133,13 +2281,7 @@ fn request_fetch(
             };
 
             if let Some((stream, pane_uid)) = kline_stream {
-                return kline_fetch_task(
-                    layout_id,
-                    pane_uid,
-                    stream,
-                    Some(req_id),
-                    Some((from, to)),
-                );
+                return kline_fetch_task(pane_uid, stream, Some(req_id), Some((from, to)));
             }
         }
         FetchRange::OpenInterest(from, to) => {
@@ -1170,47 +2312,64 @@ fn request_fetch(
                 }
             });
 
-            if let Some((ticker_info, pane_id, stream)) = trade_info {
-                let is_binance = matches!(
-                    ticker_info.exchange(),
-                    Exchange::BinanceSpot | Exchange::BinanceLinear | Exchange::BinanceInverse
-                );
-
-                if is_binance {
-                    let data_path = data::data_path(Some("market_data/binance/"));
-
-                    let (task, handle) = Task::sip(
-                        fetch_trades_batched(ticker_info, from_time, to_time, data_path),
-                        move |batch| {
-                            let data = FetchedData::Trades {
-                                batch,
-                                until_time: to_time,
-                            };
-                            Message::DistributeFetchedData {
-                                layout_id,
-                                pane_id,
-                                data,
-                                stream,
-                            }
-                        },
-                        move |result| match result {
-                            Ok(()) => Message::ChangePaneStatus(pane_id, pane::Status::Ready),
-                            Err(err) => Message::ErrorOccurred(
-                                Some(pane_id),
-                                DashboardError::Fetch(err.to_string()),
-                            ),
-                        },
-                    )
-                    .abortable();
+            if let Some((ticker_info, pane_id, stream)) = trade_info
+                && ticker_info.capabilities().historical_trades
+            {
+                let data_path = data::data_path(Some("market_data/binance/"));
+
+                let (task, handle) = Task::sip(
+                    fetch_trades_batched(ticker_info, from_time, to_time, data_path),
+                    move |batch| {
+                        let data = FetchedData::Trades {
+                            batch,
+                            until_time: to_time,
+                        };
+                        Message::DistributeFetchedData {
+                            layout_id,
+                            pane_id,
+                            data,
+                            stream,
+                        }
+                    },
+                    move |result| match result {
+                        Ok(()) => Message::ChangePaneStatus(pane_id, pane::Status::Ready),
+                        Err(err) => Message::ErrorOccurred(
+                            Some(pane_id),
+                            DashboardError::Fetch(err.to_string()),
+                        ),
+                    },
+                )
+                .abortable();
 
-                    if let pane::Content::Kline { chart, .. } = &mut state.content
-                        && let Some(c) = chart
-                    {
-                        c.set_handle(handle.abort_on_drop());
-                    }
+                if let pane::Content::Kline { chart, .. } = &mut state.content
+                    && let Some(c) = chart
+                {
+                    c.set_handle(handle.abort_on_drop());
+                }
 
-                    return task;
+                return task;
+            }
+        }
+        FetchRange::Depth(from, to) => {
+            let depth_stream = state.streams.find_ready_map(|stream| {
+                if let StreamKind::DepthAndTrades { .. } = stream {
+                    Some(*stream)
+                } else {
+                    None
                 }
+            });
+            let interval = match &state.content {
+                pane::Content::Heatmap { chart: Some(c), .. } => c.basis_interval(),
+                _ => None,
+            };
+
+            if let (Some(stream), Some(interval)) = (depth_stream, interval) {
+                return depth_fetch_task(layout_id, pane_id, stream, req_id, (from, to), interval);
+            }
+        }
+        FetchRange::Calendar(from, to) => {
+            if let Some(stream) = stream {
+                return calendar_fetch_task(layout_id, pane_id, stream, req_id, (from, to));
             }
         }
     }
@@ -1247,8 +2406,12 @@ fn oi_fetch_task(
             ticker_info,
             timeframe,
         } => Task::perform(
-            adapter::fetch_open_interest(ticker_info.ticker, timeframe, range)
-                .map_err(|err| format!("{err}")),
+            exchange::limits::backfill(adapter::fetch_open_interest(
+                ticker_info.ticker,
+                timeframe,
+                range,
+            ))
+            .map_err(|err| format!("{err}")),
             move |result| match result {
                 Ok(oi) => {
                     let data = FetchedData::OI { data: oi, req_id };
@@ -1268,49 +2431,113 @@ fn oi_fetch_task(
     update_status.chain(fetch_task)
 }
 
-fn kline_fetch_task(
+fn depth_fetch_task(
     layout_id: uuid::Uuid,
     pane_id: uuid::Uuid,
     stream: StreamKind,
-    req_id: Option<uuid::Uuid>,
-    range: Option<(u64, u64)>,
+    req_id: uuid::Uuid,
+    range: (u64, u64),
+    interval_ms: u64,
 ) -> Task<Message> {
     let update_status = Task::done(Message::ChangePaneStatus(
         pane_id,
-        pane::Status::Loading(exchange::fetcher::InfoKind::FetchingKlines),
+        pane::Status::Loading(exchange::fetcher::InfoKind::FetchingDepth),
     ));
 
-    let fetch_task = match stream {
-        StreamKind::Kline {
+    let ticker_info = stream.ticker_info();
+    let fetch_task = Task::perform(
+        exchange::limits::backfill(adapter::fetch_depth_history(
             ticker_info,
-            timeframe,
-        } => Task::perform(
-            adapter::fetch_klines(ticker_info, timeframe, range)
-                .map_err(|err| err.to_user_message()),
-            move |result| match result {
-                Ok(klines) => {
-                    let data = FetchedData::Klines {
-                        data: klines,
-                        req_id,
-                    };
-                    Message::DistributeFetchedData {
-                        layout_id,
-                        pane_id,
-                        data,
-                        stream,
-                    }
+            range,
+            interval_ms,
+        )),
+        move |result| {
+            let data = match result {
+                Ok(snapshots) => snapshots,
+                // Backfill is optional, the heatmap just fills from the live stream
+                Err(AdapterError::Unsupported(reason)) => {
+                    log::info!(
+                        "Skipping depth backfill for {}: {reason}",
+                        ticker_info.ticker
+                    );
+                    vec![]
                 }
                 Err(err) => {
-                    Message::ErrorOccurred(Some(pane_id), DashboardError::Fetch(err.to_string()))
+                    return Message::ErrorOccurred(
+                        Some(pane_id),
+                        DashboardError::Fetch(err.to_user_message().to_string()),
+                    );
                 }
-            },
-        ),
-        _ => Task::none(),
-    };
+            };
+
+            Message::DistributeFetchedData {
+                layout_id,
+                pane_id,
+                data: FetchedData::Depth { data, req_id },
+                stream,
+            }
+        },
+    );
 
     update_status.chain(fetch_task)
 }
 
+/// Fetches calendar events in the background, the pane's status stays as it is since the
+/// markers are optional
+fn calendar_fetch_task(
+    layout_id: uuid::Uuid,
+    pane_id: uuid::Uuid,
+    stream: StreamKind,
+    req_id: uuid::Uuid,
+    range: (u64, u64),
+) -> Task<Message> {
+    let ticker_info = stream.ticker_info();
+
+    Task::perform(adapter::fetch_calendar(ticker_info, range), move |result| {
+        let data = match result {
+            Ok(events) => events,
+            Err(AdapterError::Unsupported(reason)) => {
+                log::debug!("No economic calendar for {}: {reason}", ticker_info.ticker);
+                vec![]
+            }
+            Err(err) => {
+                log::warn!(
+                    "Failed to fetch the economic calendar for {}: {err}",
+                    ticker_info.ticker
+                );
+                vec![]
+            }
+        };
+
+        Message::DistributeFetchedData {
+            layout_id,
+            pane_id,
+            data: FetchedData::Calendar { data, req_id },
+            stream,
+        }
+    })
+}
+
+/// Bars of a kline stream for a pane, fetched through the dashboard's [`KlineStore`]
+fn kline_fetch_task(
+    pane_id: uuid::Uuid,
+    stream: StreamKind,
+    req_id: Option<uuid::Uuid>,
+    range: Option<(u64, u64)>,
+) -> Task<Message> {
+    let update_status = Task::done(Message::ChangePaneStatus(
+        pane_id,
+        pane::Status::Loading(exchange::fetcher::InfoKind::FetchingKlines),
+    ));
+
+    update_status.chain(Task::done(Message::RequestKlines {
+        pane_id,
+        stream,
+        req_id,
+        range,
+    }))
+}
+
 pub fn fetch_trades_batched(
     ticker_info: TickerInfo,
     from_time: u64,
@@ -1318,6 +2545,7 @@ pub fn fetch_trades_batched(
     data_path: PathBuf,
 ) -> impl Straw<(), Vec<Trade>, AdapterError> {
     sipper(async move |mut progress| {
+        let _slot = exchange::limits::backfill_slot().await;
         let mut latest_trade_t = from_time;
 
         while latest_trade_t < to_time {
@@ -1339,6 +2567,14 @@ pub fn fetch_trades_batched(
     })
 }
 
+/// Restarts the subscription whenever `epoch` changes
+fn with_epoch(
+    subscription: Subscription<exchange::Event>,
+    epoch: u32,
+) -> Subscription<exchange::Event> {
+    subscription.with(epoch).map(|(_, event)| event)
+}
+
 pub fn depth_subscription(
     ticker_info: TickerInfo,
     tick_mlpt: Option<TickMultiplier>,
@@ -1348,81 +2584,58 @@ pub fn depth_subscription(
 
     let config = StreamConfig::new(ticker_info, exchange, tick_mlpt, push_freq);
 
-    match exchange {
-        Exchange::BinanceSpot | Exchange::BinanceInverse | Exchange::BinanceLinear => {
-            let builder = |cfg: &StreamConfig<TickerInfo>| {
-                binance::connect_market_stream(cfg.id, cfg.push_freq)
-            };
-            Subscription::run_with(config, builder)
-        }
-        Exchange::BybitSpot | Exchange::BybitLinear | Exchange::BybitInverse => {
-            let builder = |cfg: &StreamConfig<TickerInfo>| {
-                bybit::connect_market_stream(cfg.id, cfg.push_freq)
-            };
-            Subscription::run_with(config, builder)
-        }
-        Exchange::HyperliquidSpot | Exchange::HyperliquidLinear => {
-            let builder = |cfg: &StreamConfig<TickerInfo>| {
-                hyperliquid::connect_market_stream(cfg.id, cfg.tick_mltp, cfg.push_freq)
-            };
-            Subscription::run_with(config, builder)
-        }
-        Exchange::OkexLinear | Exchange::OkexInverse | Exchange::OkexSpot => {
-            let builder =
-                |cfg: &StreamConfig<TickerInfo>| okex::connect_market_stream(cfg.id, cfg.push_freq);
-            Subscription::run_with(config, builder)
-        }
-        Exchange::MetaTrader5 => {
-            // Check if MT5 config exists before creating subscription
-            if metatrader5::get_global_config().is_some() {
-                let builder = |cfg: &StreamConfig<TickerInfo>| {
-                    metatrader5::connect_market_stream_global(cfg.id, cfg.push_freq)
-                };
-                Subscription::run_with(config, builder)
-            } else {
-                log::warn!("MT5 depth subscription requested but no config set");
-                Subscription::none()
-            }
-        }
+    if synthetic::is_synthetic(&ticker_info.ticker) {
+        let builder = |cfg: &StreamConfig<TickerInfo>| {
+            synthetic::connect_market_stream(cfg.id, cfg.push_freq)
+        };
+        return Subscription::run_with(config, builder);
     }
+
+    if adapter::adapter_for(&ticker_info).is_none() {
+        log::warn!("{exchange} depth subscription requested but no adapter is registered");
+        return Subscription::none();
+    }
+
+    let builder = |cfg: &StreamConfig<TickerInfo>| {
+        adapter::connect_depth_stream(cfg.id, cfg.tick_mltp, cfg.push_freq)
+    };
+    Subscription::run_with(config, builder)
+}
+
+fn synthetic_kline_subscription(
+    ticker_info: TickerInfo,
+    timeframe: Timeframe,
+) -> Subscription<exchange::Event> {
+    let config = StreamConfig::new(
+        (ticker_info, timeframe),
+        ticker_info.exchange(),
+        None,
+        PushFrequency::ServerDefault,
+    );
+    let builder = |cfg: &StreamConfig<(TickerInfo, Timeframe)>| {
+        synthetic::connect_kline_stream(cfg.id.0, cfg.id.1)
+    };
+    Subscription::run_with(config, builder)
 }
 
 pub fn kline_subscription(
     exchange: Exchange,
     kline_subs: Vec<(TickerInfo, Timeframe)>,
 ) -> Subscription<exchange::Event> {
+    let Some(adapter) = adapter::adapter(exchange) else {
+        return Subscription::none();
+    };
     let config = StreamConfig::new(kline_subs, exchange, None, PushFrequency::ServerDefault);
-    match exchange {
-        Exchange::BinanceSpot | Exchange::BinanceInverse | Exchange::BinanceLinear => {
-            let builder = |cfg: &StreamConfig<Vec<(TickerInfo, Timeframe)>>| {
-                binance::connect_kline_stream(cfg.id.clone(), cfg.market_type)
-            };
-            Subscription::run_with(config, builder)
-        }
-        Exchange::BybitSpot | Exchange::BybitInverse | Exchange::BybitLinear => {
-            let builder = |cfg: &StreamConfig<Vec<(TickerInfo, Timeframe)>>| {
-                bybit::connect_kline_stream(cfg.id.clone(), cfg.market_type)
-            };
-            Subscription::run_with(config, builder)
-        }
-        Exchange::HyperliquidSpot | Exchange::HyperliquidLinear => {
-            let builder = |cfg: &StreamConfig<Vec<(TickerInfo, Timeframe)>>| {
-                hyperliquid::connect_kline_stream(cfg.id.clone(), cfg.market_type)
-            };
-            Subscription::run_with(config, builder)
-        }
-        Exchange::OkexLinear | Exchange::OkexInverse | Exchange::OkexSpot => {
-            let builder = |cfg: &StreamConfig<Vec<(TickerInfo, Timeframe)>>| {
-                okex::connect_kline_stream(cfg.id.clone(), cfg.market_type)
-            };
+
+    let builder = |cfg: &StreamConfig<Vec<(TickerInfo, Timeframe)>>| {
+        adapter::connect_kline_stream(cfg.id.clone())
+    };
+    match adapter.kline_feed() {
+        KlineFeed::Batched => Subscription::run_with(config, builder),
+        KlineFeed::PerSubscription => Subscription::batch(config.id.into_iter().map(|sub| {
+            let config = StreamConfig::new(vec![sub], exchange, None, PushFrequency::ServerDefault);
             Subscription::run_with(config, builder)
-        }
-        Exchange::MetaTrader5 => {
-            // MT5 uses global config for kline subscription
-            // The connect_market_stream handles all data types including klines
-            // So we return none here to avoid duplicate connections
-            // Kline updates come through the market stream
-            Subscription::none()
-        }
+        })),
+        KlineFeed::MarketStream => Subscription::none(),
     }
 }
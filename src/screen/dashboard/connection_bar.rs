@@ -0,0 +1,276 @@
+//! Strip under the dashboard listing the streams the panes use, with their message rates and
+//! health. Reads the sampled [`StreamStats`] only, never the messages themselves.
+
+use crate::style;
+use data::stream_stats::{self, Health, Rate, StreamStats};
+use exchange::adapter::{Exchange, StreamKind};
+
+use iced::widget::canvas::{self, Path, Stroke};
+use iced::widget::{button, container, row, scrollable, space, text};
+use iced::{Alignment, Element, Point, Rectangle, Renderer, Size, Theme, mouse};
+use std::time::Instant;
+
+const SPARKLINE_WIDTH: f32 = 48.0;
+const HEIGHT: f32 = 22.0;
+
+#[derive(Debug, Clone)]
+pub enum Message {
+    /// Opens the actions of a stream, or closes them if open
+    Select(StreamKind),
+    TogglePause(StreamKind),
+    Resubscribe(StreamKind),
+    Focus(StreamKind),
+}
+
+pub enum Action {
+    TogglePause(StreamKind),
+    Resubscribe(StreamKind),
+    Focus(StreamKind),
+}
+
+/// A stream as listed, with how many panes use it and whether all of those are paused
+pub struct Entry {
+    pub stream: StreamKind,
+    pub panes: usize,
+    pub paused: bool,
+}
+
+#[derive(Default)]
+pub struct ConnectionBar {
+    pub stats: StreamStats,
+    selected: Option<StreamKind>,
+}
+
+impl ConnectionBar {
+    pub fn update(&mut self, message: Message) -> Option<Action> {
+        match message {
+            Message::Select(stream) => {
+                self.selected = (self.selected != Some(stream)).then_some(stream);
+                None
+            }
+            Message::TogglePause(stream) => Some(Action::TogglePause(stream)),
+            Message::Resubscribe(stream) => {
+                self.selected = None;
+                Some(Action::Resubscribe(stream))
+            }
+            Message::Focus(stream) => {
+                self.selected = None;
+                Some(Action::Focus(stream))
+            }
+        }
+    }
+
+    /// `mt5_connection` names the MT5 connection in use, shown instead of the exchange
+    pub fn view<'a>(
+        &'a self,
+        entries: Vec<Entry>,
+        mt5_connection: Option<&str>,
+        now: Instant,
+    ) -> Element<'a, Message> {
+        let is_empty = entries.is_empty();
+        let items = entries.into_iter().map(|entry| {
+            let Entry {
+                stream,
+                panes,
+                paused,
+            } = entry;
+            let health = self.stats.health(&stream, now);
+            let rate = self.stats.rate(&stream);
+
+            let chip = button(
+                row![
+                    health_dot(health),
+                    text(label(&stream, mt5_connection)).size(11),
+                    sparkline(rate),
+                    text(format!(
+                        "{:.0}/s",
+                        rate.map_or(0.0, stream_stats::Rate::per_sec)
+                    ))
+                    .size(11),
+                ]
+                .spacing(6)
+                .align_y(Alignment::Center),
+            )
+            .padding([2, 6])
+            .style(move |theme, status| style::button::transparent(theme, status, paused))
+            .on_press(Message::Select(stream));
+
+            if self.selected != Some(stream) {
+                return chip.into();
+            }
+
+            let action = |label: &'static str, message: Message| {
+                button(text(label).size(11))
+                    .padding([2, 6])
+                    .style(|theme, status| style::button::transparent(theme, status, false))
+                    .on_press(message)
+            };
+            let panes = if panes == 1 {
+                "Go to pane".to_string()
+            } else {
+                format!("Go to pane (1 of {panes})")
+            };
+
+            row![
+                chip,
+                action(
+                    if paused { "Resume" } else { "Pause" },
+                    Message::TogglePause(stream)
+                ),
+                action("Resubscribe", Message::Resubscribe(stream)),
+                button(text(panes).size(11))
+                    .padding([2, 6])
+                    .style(|theme, status| style::button::transparent(theme, status, false))
+                    .on_press(Message::Focus(stream)),
+            ]
+            .spacing(2)
+            .align_y(Alignment::Center)
+            .into()
+        });
+
+        let content: Element<_> = if is_empty {
+            text("No active streams").size(11).into()
+        } else {
+            scrollable(row(items).spacing(8).align_y(Alignment::Center))
+                .direction(scrollable::Direction::Horizontal(
+                    scrollable::Scrollbar::new().width(2).scroller_width(2),
+                ))
+                .into()
+        };
+
+        container(row![content, space::horizontal()].align_y(Alignment::Center))
+            .height(HEIGHT)
+            .padding([0, 8])
+            .align_y(Alignment::Center)
+            .into()
+    }
+
+    /// The streams with their latest rates and health as plain text, for support bundles
+    pub fn report(
+        &self,
+        entries: Vec<Entry>,
+        mt5_connection: Option<&str>,
+        now: Instant,
+    ) -> String {
+        let lines: Vec<String> = entries
+            .into_iter()
+            .map(|entry| {
+                let rate = match self.stats.rate(&entry.stream) {
+                    Some(rate) if rate.samples().len() > 0 => {
+                        format!("{:.1}/s, peak {:.1}/s", rate.per_sec(), rate.peak())
+                    }
+                    _ => "not sampled".to_string(),
+                };
+
+                format!(
+                    "{}: {:?}, {rate}, {} pane{}{}",
+                    label(&entry.stream, mt5_connection),
+                    self.stats.health(&entry.stream, now),
+                    entry.panes,
+                    if entry.panes == 1 { "" } else { "s" },
+                    if entry.paused { ", paused" } else { "" },
+                )
+            })
+            .collect();
+
+        lines.join("\n")
+    }
+}
+
+fn label(stream: &StreamKind, mt5_connection: Option<&str>) -> String {
+    let ticker_info = stream.ticker_info();
+    let exchange = ticker_info.exchange();
+
+    let source = match mt5_connection {
+        Some(name) if exchange == Exchange::MetaTrader5 => name.to_string(),
+        _ => exchange.to_string(),
+    };
+    let channel = match stream {
+        StreamKind::DepthAndTrades { .. } => "depth".to_string(),
+        StreamKind::Kline { timeframe, .. } => timeframe.to_string(),
+    };
+
+    format!("{source} {} {channel}", ticker_info.ticker)
+}
+
+fn health_dot<'a>(health: Health) -> Element<'a, Message> {
+    container(space::horizontal().width(0))
+        .width(7)
+        .height(7)
+        .style(move |theme: &Theme| {
+            let palette = theme.extended_palette();
+            let color = match health {
+                Health::Live => palette.success.base.color,
+                Health::Idle => palette.warning.base.color,
+                Health::Disconnected => palette.danger.base.color,
+            };
+            style::colored_circle_container(theme, color)
+        })
+        .into()
+}
+
+fn sparkline(rate: Option<&Rate>) -> Element<'_, Message> {
+    canvas::Canvas::new(Sparkline { rate })
+        .width(SPARKLINE_WIDTH)
+        .height(12)
+        .into()
+}
+
+/// Messages per second over the last minute, scaled to the busiest second
+struct Sparkline<'a> {
+    rate: Option<&'a Rate>,
+}
+
+impl canvas::Program<Message> for Sparkline<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let palette = theme.extended_palette();
+
+        if let Some(rate) = self.rate
+            && rate.samples().len() > 1
+        {
+            let peak = rate.peak().max(1.0);
+            let step = bounds.width / (stream_stats::HISTORY - 1) as f32;
+            // Newest sample on the right edge
+            let offset = (stream_stats::HISTORY - rate.samples().len()) as f32 * step;
+
+            let path = Path::new(|builder| {
+                for (index, sample) in rate.samples().enumerate() {
+                    let point = Point::new(
+                        offset + index as f32 * step,
+                        bounds.height - sample / peak * (bounds.height - 1.0),
+                    );
+                    if index == 0 {
+                        builder.move_to(point);
+                    } else {
+                        builder.line_to(point);
+                    }
+                }
+            });
+
+            frame.stroke(
+                &path,
+                Stroke::default()
+                    .with_color(palette.primary.base.color)
+                    .with_width(1.0),
+            );
+        } else {
+            frame.fill_rectangle(
+                Point::new(0.0, bounds.height - 1.0),
+                Size::new(bounds.width, 1.0),
+                palette.background.strong.color,
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
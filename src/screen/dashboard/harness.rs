@@ -0,0 +1,625 @@
+//! Drives a dashboard with scripted market events, headless, without a window or audio.
+//!
+//! Events go through [`Dashboard::on_market_event`], the path the stream subscriptions feed, so
+//! a pane ends up as it would had a live stream delivered them.
+
+use super::{Dashboard, Message, pane, panel::timeandsales::TimeAndSales};
+use crate::window::{self, Window};
+use data::layout::pane::ContentKind;
+use exchange::adapter::{Event, Exchange, StreamKind};
+use exchange::depth::Depth;
+use exchange::util::Price;
+use exchange::{Ticker, TickerInfo, Trade};
+
+use iced::widget::pane_grid;
+use std::sync::Arc;
+
+pub struct Harness {
+    pub dashboard: Dashboard,
+    main_window: window::Id,
+}
+
+impl Harness {
+    /// A dashboard of one pane showing `content` of `ticker_info`
+    pub fn with_pane(ticker_info: TickerInfo, content: ContentKind) -> Self {
+        let (panes, _) = pane_grid::State::new(pane::State::new());
+        let mut harness = Self {
+            dashboard: Dashboard {
+                panes,
+                ..Dashboard::default()
+            },
+            main_window: window::Id::unique(),
+        };
+
+        harness.switch_ticker(ticker_info, content);
+        harness
+    }
+
+    /// A dashboard of the given panes side by side, the first one focused
+    pub fn with_panes(states: impl IntoIterator<Item = pane::State>) -> Self {
+        let mut states = states.into_iter();
+        let (mut panes, first) =
+            pane_grid::State::new(states.next().expect("at least one pane is given"));
+
+        let mut last = first;
+        for state in states {
+            (last, _) = panes
+                .split(pane_grid::Axis::Vertical, last, state)
+                .expect("the pane to split exists");
+        }
+
+        let main_window = window::Id::unique();
+        Self {
+            dashboard: Dashboard {
+                panes,
+                focus: Some((main_window, first)),
+                ..Dashboard::default()
+            },
+            main_window,
+        }
+    }
+
+    /// The grid pane of the main window holding the pane with `id`, and its state
+    pub fn find(&self, id: uuid::Uuid) -> (pane_grid::Pane, &pane::State) {
+        self.dashboard
+            .panes
+            .iter()
+            .find(|(_, state)| state.unique_id() == id)
+            .map(|(pane, state)| (*pane, state))
+            .expect("the pane is on the main window")
+    }
+
+    /// Sends `message` as the pane grid of the main window would
+    pub fn update(&mut self, message: pane::Message) {
+        let _ = self.dashboard.update(
+            Message::Pane(self.main_window, message),
+            &Window::new(self.main_window),
+            &uuid::Uuid::new_v4(),
+        );
+    }
+
+    /// Shows `ticker_info` in the pane instead, as picking it from the tickers table does
+    pub fn switch_ticker(&mut self, ticker_info: TickerInfo, content: ContentKind) {
+        let _ = self
+            .dashboard
+            .init_focused_pane(self.main_window, ticker_info, content);
+    }
+
+    /// Feeds the events in order, the tasks they return would only fetch or resubscribe
+    pub fn feed(&mut self, events: impl IntoIterator<Item = Event>) {
+        for event in events {
+            let _ = self.dashboard.on_market_event(&event, self.main_window);
+        }
+    }
+
+    pub fn pane(&self) -> &pane::State {
+        let (window, pane) = self.dashboard.focus.expect("the pane is focused");
+        self.dashboard
+            .get_pane(self.main_window, window, pane)
+            .expect("the focused pane exists")
+    }
+
+    pub fn pane_mut(&mut self) -> &mut pane::State {
+        let (window, pane) = self.dashboard.focus.expect("the pane is focused");
+        self.dashboard
+            .get_mut_pane(self.main_window, window, pane)
+            .expect("the focused pane exists")
+    }
+
+    /// The depth stream the pane subscribed to
+    pub fn depth_stream(&self) -> StreamKind {
+        self.pane()
+            .streams
+            .find_ready_map(|stream| match stream {
+                StreamKind::DepthAndTrades { .. } => Some(*stream),
+                StreamKind::Kline { .. } => None,
+            })
+            .expect("the pane streams depth")
+    }
+
+    pub fn time_and_sales(&self) -> &TimeAndSales {
+        match &self.pane().content {
+            pane::Content::TimeAndSales(Some(panel)) => panel,
+            _ => panic!("the pane isn't a time and sales"),
+        }
+    }
+
+    /// Tickers the dashboard keeps a depth stream open for
+    pub fn subscribed_tickers(&self) -> Vec<TickerInfo> {
+        self.dashboard
+            .streams
+            .depth_streams(None)
+            .into_iter()
+            .map(|(ticker_info, ..)| ticker_info)
+            .collect()
+    }
+}
+
+pub fn ticker(symbol: &str) -> TickerInfo {
+    TickerInfo::new(
+        Ticker::new(symbol, Exchange::BinanceLinear),
+        0.1,
+        0.001,
+        None,
+    )
+}
+
+/// Trades are pruned by wall clock age, so scripts are timed from now
+pub fn now_ms() -> u64 {
+    chrono::Utc::now().timestamp_millis() as u64
+}
+
+pub fn trade(time: u64, price: f32, qty: f32, is_sell: bool) -> Trade {
+    Trade {
+        time,
+        is_sell,
+        price: Price::from_f32(price),
+        qty,
+    }
+}
+
+/// A one level book around `mid` together with `trades`, as a stream delivers them
+pub fn depth_received(stream: StreamKind, time: u64, mid: f32, trades: &[Trade]) -> Event {
+    let depth = Depth {
+        bids: [(Price::from_f32(mid - 0.1), 5.0)].into(),
+        asks: [(Price::from_f32(mid + 0.1), 5.0)].into(),
+        ..Depth::default()
+    };
+
+    Event::DepthReceived(stream, time, Arc::new(depth), trades.into())
+}
+
+mod tests {
+    use super::*;
+    use data::panel::timeandsales::StackedBarRatio;
+    use std::time::Instant;
+
+    fn tape(harness: &Harness) -> Vec<(u64, bool)> {
+        harness
+            .time_and_sales()
+            .trades()
+            .map(|entry| (entry.ts_ms, entry.display.is_sell))
+            .collect()
+    }
+
+    #[test]
+    fn trades_buffered_across_a_reconnect_all_reach_the_tape() {
+        let btc = ticker("BTCUSDT");
+        let mut harness = Harness::with_pane(btc, ContentKind::TimeAndSales);
+        let stream = harness.depth_stream();
+        let t0 = now_ms();
+
+        harness.feed([
+            Event::Connected(Exchange::BinanceLinear),
+            depth_received(
+                stream,
+                t0,
+                100.0,
+                &[
+                    trade(t0, 100.0, 1.0, false),
+                    trade(t0 + 1, 100.1, 2.0, false),
+                ],
+            ),
+            Event::Disconnected(Exchange::BinanceLinear, "connection reset".to_string()),
+            Event::Connected(Exchange::BinanceLinear),
+            // Prints that arrived while the socket was down come as one batch after it's back
+            depth_received(
+                stream,
+                t0 + 500,
+                99.9,
+                &[
+                    trade(t0 + 200, 99.9, 1.5, true),
+                    trade(t0 + 300, 99.8, 0.5, true),
+                    trade(t0 + 500, 99.9, 1.0, false),
+                ],
+            ),
+        ]);
+
+        assert_eq!(
+            tape(&harness),
+            [
+                (t0, false),
+                (t0 + 1, false),
+                (t0 + 200, true),
+                (t0 + 300, true),
+                (t0 + 500, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn switching_ticker_mid_stream_drops_the_old_stream() {
+        let btc = ticker("BTCUSDT");
+        let eth = ticker("ETHUSDT");
+        let mut harness = Harness::with_pane(btc, ContentKind::TimeAndSales);
+        let btc_stream = harness.depth_stream();
+        let t0 = now_ms();
+
+        harness.feed([depth_received(
+            btc_stream,
+            t0,
+            100.0,
+            &[trade(t0, 100.0, 1.0, false)],
+        )]);
+        assert_eq!(tape(&harness).len(), 1);
+
+        harness.switch_ticker(eth, ContentKind::TimeAndSales);
+        let eth_stream = harness.depth_stream();
+        assert_ne!(eth_stream, btc_stream);
+        assert!(harness.time_and_sales().trades().next().is_none());
+
+        // The old socket delivers a last update before it's closed
+        harness.feed([
+            depth_received(
+                btc_stream,
+                t0 + 10,
+                100.0,
+                &[trade(t0 + 10, 100.0, 3.0, true)],
+            ),
+            depth_received(
+                eth_stream,
+                t0 + 20,
+                50.0,
+                &[trade(t0 + 20, 50.0, 2.0, false)],
+            ),
+        ]);
+
+        assert_eq!(tape(&harness), [(t0 + 20, false)]);
+        assert_eq!(harness.subscribed_tickers(), [eth]);
+    }
+
+    #[test]
+    fn a_paused_pane_catches_up_in_order_on_resume() {
+        let btc = ticker("BTCUSDT");
+        let mut harness = Harness::with_pane(btc, ContentKind::TimeAndSales);
+        let stream = harness.depth_stream();
+        let t0 = now_ms();
+
+        harness.feed([depth_received(
+            stream,
+            t0,
+            100.0,
+            &[trade(t0, 100.0, 1.0, false)],
+        )]);
+        harness.pane_mut().toggle_pause(Instant::now());
+        assert!(harness.pane().is_paused());
+
+        harness.feed([
+            depth_received(
+                stream,
+                t0 + 100,
+                100.0,
+                &[trade(t0 + 100, 100.0, 2.0, true)],
+            ),
+            depth_received(
+                stream,
+                t0 + 200,
+                100.1,
+                &[
+                    trade(t0 + 150, 100.1, 1.0, false),
+                    trade(t0 + 200, 100.1, 0.5, true),
+                ],
+            ),
+        ]);
+        assert_eq!(tape(&harness), [(t0, false)]);
+
+        harness.pane_mut().toggle_pause(Instant::now());
+        assert!(!harness.pane().is_paused());
+        assert_eq!(
+            tape(&harness),
+            [
+                (t0, false),
+                (t0 + 100, true),
+                (t0 + 150, false),
+                (t0 + 200, true),
+            ]
+        );
+        // The stream stayed subscribed all along
+        assert_eq!(harness.subscribed_tickers(), [btc]);
+    }
+
+    #[test]
+    fn stats_follow_each_update() {
+        let btc = ticker("BTCUSDT");
+        let mut harness = Harness::with_pane(btc, ContentKind::TimeAndSales);
+        let stream = harness.depth_stream();
+        let t0 = now_ms();
+
+        assert_eq!(
+            harness.time_and_sales().stats(StackedBarRatio::Volume),
+            None
+        );
+
+        harness.feed([depth_received(
+            stream,
+            t0,
+            100.0,
+            &[trade(t0, 100.0, 3.0, false), trade(t0 + 1, 99.9, 1.0, true)],
+        )]);
+        assert_eq!(
+            harness.time_and_sales().stats(StackedBarRatio::Volume),
+            Some((3.0, 1.0, 0.75))
+        );
+
+        harness.feed([depth_received(
+            stream,
+            t0 + 100,
+            99.8,
+            &[trade(t0 + 100, 99.8, 4.0, true)],
+        )]);
+        assert_eq!(
+            harness.time_and_sales().stats(StackedBarRatio::Count),
+            Some((1.0, 2.0, 1.0 / 3.0))
+        );
+        assert_eq!(
+            harness.time_and_sales().stats(StackedBarRatio::Volume),
+            Some((3.0, 5.0, 0.375))
+        );
+    }
+
+    mod link_groups {
+        use super::*;
+        use crate::modal::pane::{
+            Modal,
+            stream::{self, Modifier, ModifierKind},
+        };
+        use data::layout::pane::LinkGroup;
+        use exchange::Timeframe;
+
+        fn kline_pane(symbol: &str, group: Option<LinkGroup>, timeframe: Timeframe) -> pane::State {
+            let mut state = pane::State::new();
+            state.set_content_and_streams(vec![ticker(symbol)], ContentKind::CandlestickChart);
+            let _ = state.sync_timeframe(timeframe);
+            state.link_group = group;
+            state
+        }
+
+        /// The timeframe the pane shows and the one it streams klines of
+        fn timeframes(harness: &Harness, id: uuid::Uuid) -> (Option<Timeframe>, Option<Timeframe>) {
+            let (_, state) = harness.find(id);
+            let streamed = state.streams.find_ready_map(|stream| match stream {
+                StreamKind::Kline { timeframe, .. } => Some(*timeframe),
+                StreamKind::DepthAndTrades { .. } => None,
+            });
+            (state.timeframe(), streamed)
+        }
+
+        /// Picks `timeframe` from the pane's stream modifier, as clicking it does
+        fn select_timeframe(harness: &mut Harness, id: uuid::Uuid, timeframe: Timeframe) {
+            let (pane, state) = harness.find(id);
+            let current = state.timeframe().expect("the pane is time based");
+            harness.dashboard.panes.get_mut(pane).unwrap().modal = Some(Modal::StreamModifier(
+                Modifier::new(ModifierKind::Candlestick(current.into())),
+            ));
+
+            harness.update(pane::Message::PaneEvent(
+                pane,
+                pane::Event::StreamModifierChanged(stream::Message::BasisSelected(
+                    timeframe.into(),
+                )),
+            ));
+        }
+
+        #[test]
+        fn panes_already_on_the_timeframe_are_left_alone() {
+            let mut on_m15 = kline_pane("BTCUSDT", None, Timeframe::M15);
+            assert!(matches!(
+                on_m15.sync_timeframe(Timeframe::M5),
+                Some(pane::Effect::RequestFetch(_))
+            ));
+            assert!(on_m15.sync_timeframe(Timeframe::M5).is_none());
+        }
+
+        #[test]
+        fn timeframe_changes_reach_the_rest_of_the_group_only() {
+            let panes = [
+                kline_pane("BTCUSDT", Some(LinkGroup::A), Timeframe::M15),
+                kline_pane("ETHUSDT", Some(LinkGroup::A), Timeframe::M15),
+                kline_pane("SOLUSDT", Some(LinkGroup::A), Timeframe::M5),
+                kline_pane("XRPUSDT", Some(LinkGroup::B), Timeframe::M15),
+                kline_pane("BNBUSDT", None, Timeframe::M15),
+            ];
+            let [origin, behind, current, other_group, unlinked] =
+                panes.each_ref().map(pane::State::unique_id);
+            let mut harness = Harness::with_panes(panes);
+            harness.dashboard.linked_timeframes.insert(LinkGroup::A);
+
+            select_timeframe(&mut harness, origin, Timeframe::M5);
+
+            let (m5, m15) = (
+                (Some(Timeframe::M5), Some(Timeframe::M5)),
+                (Some(Timeframe::M15), Some(Timeframe::M15)),
+            );
+            for id in [origin, behind, current] {
+                assert_eq!(timeframes(&harness, id), m5);
+            }
+            for id in [other_group, unlinked] {
+                assert_eq!(timeframes(&harness, id), m15);
+            }
+
+            // The pane a change comes from isn't handed it back
+            let main_window = harness.main_window;
+            let _ = harness.dashboard.sync_timeframe_in_group(
+                main_window,
+                LinkGroup::A,
+                Timeframe::H1,
+                behind,
+            );
+            assert_eq!(timeframes(&harness, behind), m5);
+            for id in [origin, current] {
+                assert_eq!(
+                    timeframes(&harness, id),
+                    (Some(Timeframe::H1), Some(Timeframe::H1))
+                );
+            }
+        }
+
+        #[test]
+        fn groups_share_timeframes_only_while_toggled_on() {
+            let panes = [
+                kline_pane("BTCUSDT", Some(LinkGroup::C), Timeframe::M15),
+                kline_pane("ETHUSDT", Some(LinkGroup::C), Timeframe::M15),
+            ];
+            let [origin, linked] = panes.each_ref().map(pane::State::unique_id);
+            let mut harness = Harness::with_panes(panes);
+            let (pane, _) = harness.find(origin);
+
+            select_timeframe(&mut harness, origin, Timeframe::M5);
+            assert_eq!(timeframes(&harness, linked).0, Some(Timeframe::M15));
+
+            // Turning it on brings the group to the pane it was turned on from
+            harness.update(pane::Message::ToggleLinkedTimeframe(
+                pane,
+                LinkGroup::C,
+                true,
+            ));
+            assert_eq!(timeframes(&harness, linked).0, Some(Timeframe::M5));
+
+            select_timeframe(&mut harness, origin, Timeframe::H1);
+            assert_eq!(timeframes(&harness, linked).0, Some(Timeframe::H1));
+
+            harness.update(pane::Message::ToggleLinkedTimeframe(
+                pane,
+                LinkGroup::C,
+                false,
+            ));
+            select_timeframe(&mut harness, origin, Timeframe::M30);
+            assert_eq!(timeframes(&harness, origin).0, Some(Timeframe::M30));
+            assert_eq!(timeframes(&harness, linked).0, Some(Timeframe::H1));
+        }
+    }
+
+    mod price_menu {
+        use super::*;
+        use crate::chart;
+
+        fn pick(harness: &mut Harness, message: chart::Message) -> Option<pane::Effect> {
+            harness
+                .pane_mut()
+                .update(pane::Event::ChartInteraction(message))
+        }
+
+        #[test]
+        fn alerting_at_a_price_twice_keeps_one_alert() {
+            let mut harness = Harness::with_pane(ticker("BTCUSDT"), ContentKind::CandlestickChart);
+            let price = Price::from_f32(64_250.5);
+
+            for _ in 0..2 {
+                let _ = pick(&mut harness, chart::Message::AlertAt(price));
+            }
+            let _ = pick(
+                &mut harness,
+                chart::Message::AlertAt(Price::from_f32(64_300.0)),
+            );
+
+            let alerts = &harness.pane().settings.price_alerts;
+            assert_eq!(
+                alerts.iter().map(|alert| alert.price).collect::<Vec<_>>(),
+                vec![price, Price::from_f32(64_300.0)]
+            );
+            assert!(alerts.iter().all(|alert| alert.label.is_none()));
+            assert_eq!(harness.pane().notifications.len(), 2);
+        }
+
+        #[test]
+        fn copied_prices_keep_the_symbol_precision() {
+            let eurusd = TickerInfo::new(
+                Ticker::new("EURUSD", Exchange::BinanceLinear),
+                0.00001,
+                0.01,
+                None,
+            );
+
+            for content in [ContentKind::CandlestickChart, ContentKind::HeatmapChart] {
+                let mut harness = Harness::with_pane(eurusd, content);
+
+                let effect = pick(
+                    &mut harness,
+                    chart::Message::PriceCopied(Price::from_f32(1.0842)),
+                );
+                assert!(
+                    matches!(effect, Some(pane::Effect::CopyToClipboard(ref copied)) if copied == "1.08420"),
+                    "{content:?}: {effect:?}"
+                );
+            }
+        }
+    }
+
+    mod cloning {
+        use super::*;
+        use data::chart::drawing::{Drawing, Drawings};
+        use data::layout::pane::LinkGroup;
+
+        fn with_drawing(ticker_info: TickerInfo) -> Harness {
+            let mut harness = Harness::with_pane(ticker_info, ContentKind::CandlestickChart);
+            let state = harness.pane_mut();
+            state.settings.drawings.set(
+                ticker_info.ticker,
+                vec![Drawing::HorizontalLine {
+                    price: Price::from_f32(100.5),
+                }],
+            );
+            state.link_group = Some(LinkGroup::B);
+            harness
+        }
+
+        /// The pane as a layout saves it, without its id
+        fn saved(state: &pane::State) -> serde_json::Value {
+            let mut value = serde_json::to_value(data::Pane::from(state)).unwrap();
+            value["KlineChart"]["id"] = serde_json::Value::Null;
+            value
+        }
+
+        fn clone_focused(harness: &mut Harness, with_drawings: bool) {
+            let (_, pane) = harness.dashboard.focus.expect("a pane is focused");
+            harness.update(pane::Message::ClonePane(pane, with_drawings));
+        }
+
+        #[test]
+        fn a_clone_saves_like_its_source_under_a_new_id() {
+            let mut harness = with_drawing(ticker("BTCUSDT"));
+            let (source_id, source) = (harness.pane().unique_id(), saved(harness.pane()));
+
+            clone_focused(&mut harness, true);
+
+            assert_eq!(harness.dashboard.panes.len(), 2);
+            let clone = harness.pane();
+            assert_ne!(clone.unique_id(), source_id);
+            assert_eq!(saved(clone), source);
+        }
+
+        #[test]
+        fn drawings_are_left_behind_unless_asked_for() {
+            let btc = ticker("BTCUSDT");
+            let mut harness = with_drawing(btc);
+            let source_id = harness.pane().unique_id();
+            let mut expected = saved(harness.pane());
+            expected["KlineChart"]["settings"]["drawings"] =
+                serde_json::to_value(Drawings::default()).unwrap();
+
+            clone_focused(&mut harness, false);
+
+            assert_eq!(saved(harness.pane()), expected);
+            assert!(harness.pane().settings.drawings.is_empty());
+            let (_, source) = harness.find(source_id);
+            assert_eq!(source.settings.drawings.get(&btc.ticker).len(), 1);
+        }
+
+        #[test]
+        fn panes_of_a_disconnected_exchange_are_not_cloned() {
+            let eurusd = TickerInfo::new(
+                Ticker::new("EURUSD", Exchange::MetaTrader5),
+                0.00001,
+                0.01,
+                None,
+            );
+            let mut harness = with_drawing(eurusd);
+            assert!(exchange::adapter::adapter(Exchange::MetaTrader5).is_none());
+
+            clone_focused(&mut harness, true);
+
+            assert_eq!(harness.dashboard.panes.len(), 1);
+            assert_eq!(harness.pane().notifications.len(), 1);
+        }
+    }
+}
@@ -1,11 +1,17 @@
 use crate::{
-    chart::{self, comparison::ComparisonChart, heatmap::HeatmapChart, kline::KlineChart},
+    chart::{
+        self, Chart, comparison::ComparisonChart, heatmap::HeatmapChart, kline::KlineChart,
+        strip::TimeframeStrip,
+    },
     modal::{
         self, ModifierKind,
         pane::{
             Modal,
             mini_tickers_list::MiniPanel,
-            settings::{comparison_cfg_view, heatmap_cfg_view, kline_cfg_view},
+            settings::{
+                comparison_cfg_view, heatmap_cfg_view, kline_cfg_view, signals_cfg,
+                spread_alert_cfg,
+            },
             stack_modal,
         },
     },
@@ -14,36 +20,76 @@ use crate::{
         tickers_table::TickersTable,
     },
     style::{self, Icon, icon_text},
-    widget::{self, button_with_tooltip, column_drag, link_group_button, toast::Toast},
+    widget::{
+        self, button_with_tooltip, column_drag, link_group_button,
+        toast::{Notification, Toast},
+    },
     window::{self, Window},
 };
 use data::{
     UserTimezone,
     chart::{
         Basis, ViewConfig,
+        bar_close::{BarCloseAlert, BarCloseClock},
+        calendar::CalendarConfig,
+        data_window::DataWindow,
+        heatmap::wall::{WallConfig, WallEvent},
         indicator::{HeatmapIndicator, Indicator, KlineIndicator, UiIndicator},
+        pattern::{PatternConfig, PatternMark},
+        price_alert::PriceAlert,
+        replay::ReplaySpeed,
+        revision::{Revision, RevisionSettings},
+        session::ReferenceLines,
+        spread,
+        spread_alert::{self, SpreadAlert, SpreadWatch, Widening},
+        stop_run::{StopRun, StopRunConfig},
+        volume_curve::VolumeCurveConfig,
     },
-    layout::pane::{ContentKind, LinkGroup, PaneSetup, Settings, VisualConfig},
+    indicators::{
+        self, Overlay,
+        signal::{self, SignalSetup},
+    },
+    layout::pane::{ContentKind, DataSource, LinkGroup, PaneSetup, Settings, VisualConfig},
+    stream_pause::{self, PausedStream},
+    stream_slots::Slot,
 };
+use exchange::adapter::metatrader5::{Retry, price_basis::PriceBasis};
 use exchange::{
-    Kline, OpenInterest, StreamPairKind, TickMultiplier, TickerInfo, Timeframe,
+    BarStatus, Kline, OpenInterest, StreamPairKind, TickMultiplier, Ticker, TickerInfo, Timeframe,
+    Trade,
     adapter::{MarketKind, PersistStreamKind, ResolvedStream, StreamKind, StreamTicksize},
+    calendar::CalendarEvent,
+    depth::{Depth, DepthPayload},
     fetcher::FetchRequests,
+    market_state::MarketState,
+    util::Price,
 };
 use iced::{
     Alignment, Element, Length, Renderer, Theme,
     alignment::Vertical,
     padding,
-    widget::{button, center, column, container, pane_grid, pick_list, row, text, tooltip},
+    widget::{
+        button, center, checkbox, column, container, pane_grid, pick_list, row, text, tooltip,
+    },
 };
-use std::time::Instant;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// How long a pane's border blinks after its spread widens
+const SPREAD_FLASH: Duration = Duration::from_secs(3);
 
 #[derive(Debug, Clone)]
 pub enum Effect {
     RefreshStreams,
     RequestFetch(FetchRequests),
     SwitchTickersInGroup(TickerInfo),
+    /// Sets the pane up again for `TickerInfo`, e.g. after its source changed
+    ReloadTicker(TickerInfo),
     FocusWidget(iced::widget::Id),
+    Notify(Toast),
+    CopyToClipboard(String),
 }
 
 #[derive(Debug, Default, Clone, PartialEq)]
@@ -68,12 +114,21 @@ pub enum Message {
     PaneDragged(pane_grid::DragEvent),
     ClosePane(pane_grid::Pane),
     SplitPane(pane_grid::Axis, pane_grid::Pane),
+    /// Duplicates the pane next to itself, `true` copies its drawings too
+    ClonePane(pane_grid::Pane, bool),
+    /// Writes the pane's order book to a file in the data folder
+    DumpBook(pane_grid::Pane),
+    /// Makes the alert the default of the pane's MT5 connection
+    SpreadAlertDefault(pane_grid::Pane, Option<SpreadAlert>),
+    /// Saves what the pane shows as an image in the data folder
+    ExportImage(pane_grid::Pane),
     MaximizePane(pane_grid::Pane),
     Restore,
     ReplacePane(pane_grid::Pane),
     Popout,
     Merge,
     SwitchLinkGroup(pane_grid::Pane, Option<LinkGroup>),
+    ToggleLinkedTimeframe(pane_grid::Pane, LinkGroup, bool),
     VisualConfigChanged(pane_grid::Pane, VisualConfig, bool),
     PaneEvent(pane_grid::Pane, Event),
 }
@@ -94,6 +149,42 @@ pub enum Event {
     StreamModifierChanged(modal::stream::Message),
     ComparisonChartInteraction(super::chart::comparison::Message),
     MiniTickersListInteraction(modal::pane::mini_tickers_list::Message),
+    BarCloseAlertChanged(Option<BarCloseAlert>),
+    StopRunsChanged(Option<StopRunConfig>),
+    PatternsChanged(Option<PatternConfig>),
+    VolumeCurveChanged(Option<VolumeCurveConfig>),
+    CalendarChanged(Option<CalendarConfig>),
+    DataWindowChanged(DataWindow),
+    SpreadAlertChanged(Option<SpreadAlert>),
+    SizeUnitChanged(Option<exchange::SizeUnit>),
+    PriceScaleChanged(data::chart::PriceScale),
+    ReferenceLinesChanged(ReferenceLines),
+    OverlaysChanged(Vec<Overlay>),
+    SignalsChanged(Vec<SignalSetup>),
+    /// Lets signal scripts stopped for running too long run again
+    RearmSignals,
+    ReloadScripts,
+    /// Holds back or applies the pane's stream data, the stream stays subscribed either way
+    TogglePause,
+    /// Retries the dropped MT5 connection right away, also one that stopped retrying
+    ReconnectNow,
+    StopReconnecting,
+    PositionSizeChanged(modal::pane::position_size::Message),
+    StripConfigChanged(data::chart::strip::Config),
+    Replay(ReplayControl),
+    /// Pins the pane to a connection, `None` follows the active one
+    SourceSelected(Option<DataSource>),
+    /// Charts the pane's MT5 ticker at a price, `None` takes what the feed sends
+    PriceBasisSelected(Option<PriceBasis>),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ReplayControl {
+    /// Arms the start picker, or leaves replay if it is already on
+    Toggled,
+    Stepped(isize),
+    PlayToggled,
+    SpeedSelected(ReplaySpeed),
 }
 
 pub struct State {
@@ -105,6 +196,21 @@ pub struct State {
     pub streams: ResolvedStream,
     pub status: Status,
     pub link_group: Option<LinkGroup>,
+    bar_close: BarCloseClock,
+    last_alert_price: Option<Price>,
+    position_size: modal::pane::position_size::Calculator,
+    /// Last reported trading state, for the ticker it was reported for
+    market_state: Option<(TickerInfo, MarketState)>,
+    /// Stream data held back while the pane is paused
+    paused: Option<PausedStream>,
+    /// Spreads of the ticker the alert follows
+    spread_watch: Option<(Ticker, SpreadWatch)>,
+    /// Widening not yet picked up by the dashboard
+    spread_widening: Option<(TickerInfo, SpreadAlert, Widening)>,
+    /// When the border started flashing for a widening
+    spread_flash: Option<Instant>,
+    /// Whether the pane got its streams under the stream limits, none until they're resolved
+    stream_slot: Option<Slot>,
 }
 
 impl State {
@@ -113,12 +219,14 @@ impl State {
     }
 
     pub fn from_config(
+        id: uuid::Uuid,
         content: Content,
         streams: Vec<PersistStreamKind>,
         settings: Settings,
         link_group: Option<LinkGroup>,
     ) -> Self {
         Self {
+            id,
             content,
             settings,
             streams: ResolvedStream::Waiting(streams),
@@ -134,6 +242,108 @@ impl State {
         })
     }
 
+    /// Whether the pane streams `ticker_info`, through whichever connection it's pinned to
+    pub fn shows(&self, ticker_info: TickerInfo) -> bool {
+        let bare = |ti: TickerInfo| ti.pinned(None).priced(None);
+        self.stream_pair().map(bare) == Some(bare(ticker_info))
+    }
+
+    /// `streams` going through the pane's pinned connection, at its price basis
+    pub fn pin_streams(&self, streams: Vec<StreamKind>) -> Vec<StreamKind> {
+        let pin = |ticker_info| self.settings.bind(ticker_info);
+
+        streams
+            .into_iter()
+            .map(|stream| match stream {
+                StreamKind::Kline {
+                    ticker_info,
+                    timeframe,
+                } => StreamKind::Kline {
+                    ticker_info: pin(ticker_info),
+                    timeframe,
+                },
+                StreamKind::DepthAndTrades {
+                    ticker_info,
+                    depth_aggr,
+                    push_freq,
+                } => StreamKind::DepthAndTrades {
+                    ticker_info: pin(ticker_info),
+                    depth_aggr,
+                    push_freq,
+                },
+            })
+            .collect()
+    }
+
+    /// The connection the pane is pinned to while it isn't set up, the pane waits for it
+    /// rather than falling back to the active one
+    fn missing_source(&self) -> Option<&DataSource> {
+        let source = self.settings.source.as_ref()?;
+        let ticker = match &self.streams {
+            ResolvedStream::Ready(_) => self.stream_pair().map(|ti| ti.ticker),
+            ResolvedStream::Waiting(streams) => streams.first().map(PersistStreamKind::ticker),
+        }?;
+
+        (ticker.exchange == source.exchange
+            && !exchange::adapter::metatrader5::has_connection(source.id()))
+        .then_some(source)
+    }
+
+    pub fn set_market_state(&mut self, ticker_info: TickerInfo, state: MarketState) {
+        self.market_state = Some((ticker_info, state));
+    }
+
+    /// Whether the pane's stream reported its market outside the trading sessions
+    pub fn market_is_closed(&self) -> bool {
+        matches!(
+            self.market_state,
+            Some((ticker_info, MarketState::Closed { .. })) if self.stream_pair() == Some(ticker_info)
+        )
+    }
+
+    /// Why the pane's market isn't updating, while it shows the ticker the state was reported for
+    fn market_banner(&self) -> Option<String> {
+        let (ticker_info, state) = self.market_state?;
+        (self.stream_pair() == Some(ticker_info))
+            .then(|| state.banner())
+            .flatten()
+    }
+
+    /// Set while the broker no longer lists the pane's MT5 symbol
+    pub fn stream_slot(&self) -> Option<Slot> {
+        self.stream_slot
+    }
+
+    pub fn set_stream_slot(&mut self, slot: Option<Slot>) {
+        self.stream_slot = slot;
+    }
+
+    /// Whether the pane's streams are subscribed
+    pub fn is_streaming(&self) -> bool {
+        self.stream_slot.is_some_and(Slot::is_held)
+    }
+
+    fn slot_banner(&self) -> Option<String> {
+        matches!(self.stream_slot, Some(Slot::Waiting(_)))
+            .then(|| "Waiting for a stream slot, the stream limits are reached".to_string())
+    }
+
+    fn retired_banner(&self) -> Option<String> {
+        let ticker = self.stream_pair()?.ticker;
+        exchange::adapter::metatrader5::retired::is_retired(&ticker)
+            .then(|| format!("The broker no longer lists {ticker}, only its history is shown"))
+    }
+
+    /// Next reconnect of the MT5 proxy the pane streams from
+    fn pending_reconnect(&self) -> Option<Retry> {
+        let ticker_info = self.stream_pair()?;
+        if ticker_info.exchange() != exchange::adapter::Exchange::MetaTrader5 {
+            return None;
+        }
+
+        exchange::adapter::metatrader5::pending_reconnect()
+    }
+
     pub fn stream_pair_kind(&self) -> Option<StreamPairKind> {
         let ready_streams = self.streams.ready_iter()?;
         let mut unique = vec![];
@@ -160,8 +370,13 @@ impl State {
         if !(self.content.kind() == kind) {
             self.settings.selected_basis = None;
             self.settings.tick_multiply = None;
+            self.settings.price_basis = None;
         }
 
+        let tickers: Vec<TickerInfo> = tickers
+            .into_iter()
+            .map(|ti| self.settings.bind(ti))
+            .collect();
         let base_ticker = tickers[0];
         let prev_base_ticker = self.stream_pair();
 
@@ -311,6 +526,17 @@ impl State {
 
                     (content, streams)
                 }
+                ContentKind::TimeframeStrip => {
+                    let config = self
+                        .settings
+                        .visual_config
+                        .clone()
+                        .and_then(|cfg| cfg.strip());
+                    let strip = TimeframeStrip::new(derived_plan.ticker_info, config);
+                    let streams = strip.streams();
+
+                    (Content::Strip(Some(strip)), streams)
+                }
                 ContentKind::Starter => unreachable!(),
             }
         };
@@ -321,6 +547,22 @@ impl State {
         streams
     }
 
+    pub fn insert_hist_depth(&mut self, req_id: uuid::Uuid, snapshots: Vec<DepthPayload>) {
+        match &mut self.content {
+            Content::Heatmap { chart: Some(c), .. } => c.insert_depth_history(req_id, snapshots),
+            _ => log::warn!("Ignoring depth history, pane content isn't a heatmap"),
+        }
+    }
+
+    pub fn insert_calendar(&mut self, req_id: uuid::Uuid, events: Vec<CalendarEvent>) {
+        match &mut self.content {
+            Content::Kline { chart: Some(c), .. } if c.is_calendar_request(req_id) => {
+                c.insert_calendar_events(events);
+            }
+            _ => log::debug!("Ignoring stale calendar events"),
+        }
+    }
+
     pub fn insert_hist_oi(&mut self, req_id: Option<uuid::Uuid>, oi: &[OpenInterest]) {
         match &mut self.content {
             Content::Kline { chart, .. } => {
@@ -335,13 +577,15 @@ impl State {
         }
     }
 
+    /// Returns the bars a kline chart found revised, see [`KlineChart::insert_hist_klines`]
     pub fn insert_hist_klines(
         &mut self,
         req_id: Option<uuid::Uuid>,
         timeframe: Timeframe,
         ticker_info: TickerInfo,
         klines: &[Kline],
-    ) {
+        revisions: Option<&RevisionSettings>,
+    ) -> Vec<Revision> {
         match &mut self.content {
             Content::Kline {
                 chart, indicators, ..
@@ -351,15 +595,27 @@ impl State {
                 };
 
                 if let Some(id) = req_id {
+                    if chart.is_volume_curve_request(id) {
+                        chart.insert_volume_curve_klines(klines);
+                        return vec![];
+                    }
+                    if chart.is_daily_levels_request(id) {
+                        chart.insert_daily_klines(klines);
+                        return vec![];
+                    }
+                    if chart.is_resolution_request(id) {
+                        chart.insert_resolution_klines(klines);
+                        return vec![];
+                    }
                     if chart.basis() != Basis::Time(timeframe) {
                         log::warn!(
                             "Ignoring stale kline fetch for timeframe {:?}; chart basis = {:?}",
                             timeframe,
                             chart.basis()
                         );
-                        return;
+                        return vec![];
                     }
-                    chart.insert_hist_klines(id, klines);
+                    return chart.insert_hist_klines(id, klines, revisions);
                 } else {
                     let (raw_trades, tick_size) = (chart.raw_trades(), chart.tick_size());
                     let layout = chart.chart_layout();
@@ -388,7 +644,7 @@ impl State {
                             timeframe,
                             chart.timeframe
                         );
-                        return;
+                        return vec![];
                     }
                     chart.insert_history(id, ticker_info, klines);
                 } else {
@@ -399,10 +655,17 @@ impl State {
                     );
                 }
             }
+            Content::Strip(Some(strip)) => {
+                if let Some(id) = req_id {
+                    strip.insert_history(id, timeframe, klines);
+                }
+            }
             _ => {
                 log::error!("pane content not candlestick or footprint");
             }
         }
+
+        vec![]
     }
 
     fn has_stream(&self) -> bool {
@@ -418,18 +681,25 @@ impl State {
         panes: usize,
         is_focused: bool,
         maximized: bool,
+        timeframe_linked: bool,
         window: window::Id,
         main_window: &'a Window,
         timezone: UserTimezone,
         tickers_table: &'a TickersTable,
     ) -> pane_grid::Content<'a, Message, Theme, Renderer> {
-        let mut stream_info_element = if Content::Starter == self.content {
-            row![]
-        } else {
-            row![link_group_button(id, self.link_group, |id| {
-                Message::PaneEvent(id, Event::ShowModal(Modal::LinkGroup))
-            })]
-        };
+        let mut stream_info_element =
+            if matches!(self.content, Content::Starter | Content::Quarantined { .. }) {
+                row![]
+            } else {
+                row![link_group_button(id, self.link_group, move |id| {
+                    Message::PaneEvent(
+                        id,
+                        Event::ShowModal(Modal::LinkGroup {
+                            sync_timeframe: timeframe_linked,
+                        }),
+                    )
+                })]
+            };
 
         if let Some(kind) = self.stream_pair_kind() {
             let (base_ti, extra) = match kind {
@@ -468,7 +738,41 @@ impl State {
                 .padding([4, 10]);
 
             stream_info_element = stream_info_element.push(tickers_list_btn);
-        } else if !matches!(self.content, Content::Starter) && !self.has_stream() {
+
+            if base_ti.exchange() == exchange::adapter::Exchange::MetaTrader5
+                && (self.settings.source.is_some()
+                    || exchange::adapter::metatrader5::connection_names().len() > 1)
+            {
+                stream_info_element = stream_info_element.push(source_badge(
+                    id,
+                    self.settings.source.as_ref(),
+                    matches!(self.modal, Some(Modal::DataSource)),
+                ));
+            }
+
+            if base_ti.exchange() == exchange::adapter::Exchange::MetaTrader5
+                && matches!(self.content, Content::Kline { .. })
+            {
+                stream_info_element = stream_info_element.push(price_basis_badge(
+                    id,
+                    self.settings.price_basis,
+                    matches!(self.modal, Some(Modal::PriceBasis)),
+                ));
+            }
+
+            if let Some(tick) = exchange::adapter::metatrader5::tick_override::get(&base_ti.ticker)
+            {
+                stream_info_element = stream_info_element.push(tick_override_badge(tick.tick_size));
+            }
+
+            if let Content::Kline { chart: Some(c), .. } = &self.content
+                && let Some(shown) = c.shown_resolution()
+            {
+                stream_info_element = stream_info_element.push(resolution_badge(shown));
+            }
+        } else if !matches!(self.content, Content::Starter | Content::Quarantined { .. })
+            && !self.has_stream()
+        {
             let content = row![text("Choose a ticker").size(13)]
                 .align_y(Alignment::Center)
                 .spacing(4);
@@ -524,6 +828,37 @@ impl State {
         };
 
         let body = match &self.content {
+            _ if self.missing_source().is_some() => {
+                let connection = self
+                    .missing_source()
+                    .map(|source| source.connection.as_str())
+                    .unwrap_or_default();
+
+                let base = center(
+                    column![
+                        text("Needs connection").size(16),
+                        text(format!(
+                            "This pane is pinned to {connection}, set it up to stream from it"
+                        ))
+                        .size(13),
+                        button(text("Use the active connection").size(13))
+                            .on_press(Message::PaneEvent(id, Event::SourceSelected(None))),
+                    ]
+                    .spacing(8)
+                    .align_x(Alignment::Center),
+                )
+                .into();
+
+                self.compose_stack_view(
+                    base,
+                    id,
+                    None,
+                    compact_controls,
+                    || column![].into(),
+                    None,
+                    tickers_table,
+                )
+            }
             Content::Starter => {
                 let content_picklist =
                     pick_list(ContentKind::ALL, Some(ContentKind::Starter), move |kind| {
@@ -555,6 +890,35 @@ impl State {
                     tickers_table,
                 )
             }
+            Content::Quarantined { error, .. } => {
+                let content_picklist =
+                    pick_list(ContentKind::ALL, None::<ContentKind>, move |kind| {
+                        Message::PaneEvent(id, Event::ContentSelected(kind))
+                    });
+
+                let base: Element<_> = center(
+                    column![
+                        text("This pane failed to load").size(16),
+                        text(error.clone()).size(12),
+                        text("It's kept as saved until replaced").size(13),
+                        content_picklist,
+                    ]
+                    .align_x(Alignment::Center)
+                    .spacing(8),
+                )
+                .padding(12)
+                .into();
+
+                self.compose_stack_view(
+                    base,
+                    id,
+                    None,
+                    compact_controls,
+                    || column![].into(),
+                    None,
+                    tickers_table,
+                )
+            }
             Content::Comparison(chart) => {
                 if let Some(c) = chart {
                     let selected_basis = self
@@ -596,14 +960,46 @@ impl State {
                     )
                 }
             }
+            Content::Strip(strip) => {
+                if let Some(strip) = strip {
+                    let settings_modal =
+                        || modal::pane::settings::strip_cfg_view(&strip.config, id);
+
+                    self.compose_stack_view(
+                        strip.view(),
+                        id,
+                        None,
+                        compact_controls,
+                        settings_modal,
+                        None,
+                        tickers_table,
+                    )
+                } else {
+                    let base = uninitialized_base(ContentKind::TimeframeStrip);
+                    self.compose_stack_view(
+                        base,
+                        id,
+                        None,
+                        compact_controls,
+                        || column![].into(),
+                        None,
+                        tickers_table,
+                    )
+                }
+            }
             Content::TimeAndSales(panel) => {
                 if let Some(panel) = panel {
                     let base = panel::view(panel, timezone).map(move |message| {
                         Message::PaneEvent(id, Event::PanelInteraction(message))
                     });
 
-                    let settings_modal =
-                        || modal::pane::settings::timesales_cfg_view(panel.config, id);
+                    let settings_modal = || {
+                        modal::pane::settings::timesales_cfg_view(
+                            panel.config,
+                            id,
+                            self.settings.size_unit_override,
+                        )
+                    };
 
                     self.compose_stack_view(
                         base,
@@ -655,8 +1051,13 @@ impl State {
                         Message::PaneEvent(id, Event::PanelInteraction(message))
                     });
 
-                    let settings_modal =
-                        || modal::pane::settings::ladder_cfg_view(panel.config, id);
+                    let settings_modal = || {
+                        modal::pane::settings::ladder_cfg_view(
+                            panel.config,
+                            id,
+                            self.settings.size_unit_override,
+                        )
+                    };
 
                     self.compose_stack_view(
                         base,
@@ -721,6 +1122,9 @@ impl State {
                             chart.study_configurator(),
                             &chart.studies,
                             basis,
+                            self.settings.size_unit_override,
+                            self.settings.data_window,
+                            self.spread_alert_cfg(id),
                         )
                     };
 
@@ -729,7 +1133,7 @@ impl State {
                             id,
                             self,
                             indicators,
-                            self.stream_pair().map(|i| i.ticker.market_type()),
+                            self.stream_pair(),
                         ))
                     } else {
                         None
@@ -807,9 +1211,18 @@ impl State {
                         }
                     }
 
+                    if let Some(spread) = chart.latest_spread() {
+                        stream_info_element =
+                            stream_info_element.push(text(format!("Spread {spread}")).size(12));
+                    }
+
                     let base = chart::view(chart, indicators, timezone).map(move |message| {
                         Message::PaneEvent(id, Event::ChartInteraction(message))
                     });
+                    let base = match replay_controls(id, chart, timezone) {
+                        Some(controls) => column![controls, base].into(),
+                        None => base,
+                    };
                     let settings_modal = || {
                         kline_cfg_view(
                             chart.study_configurator(),
@@ -817,6 +1230,21 @@ impl State {
                             chart_kind,
                             id,
                             chart.basis(),
+                            chart.price_scale(),
+                            self.settings.bar_close_alert,
+                            self.settings.stop_runs.as_ref(),
+                            self.settings.patterns,
+                            self.settings.volume_curve,
+                            self.settings.calendar,
+                            self.settings.data_window,
+                            self.settings.size_unit_override,
+                            self.settings.reference_lines,
+                            &self.settings.overlays,
+                            self.stream_pair()
+                                .map(|ti| ti.capabilities())
+                                .unwrap_or_default(),
+                            self.spread_alert_cfg(id),
+                            self.signals_cfg(id),
                         )
                     };
 
@@ -825,7 +1253,7 @@ impl State {
                             id,
                             self,
                             indicators,
-                            self.stream_pair().map(|i| i.ticker.market_type()),
+                            self.stream_pair(),
                         ))
                     } else {
                         None
@@ -872,14 +1300,28 @@ impl State {
             Status::Loading(exchange::fetcher::InfoKind::FetchingOI) => {
                 stream_info_element = stream_info_element.push(text("Fetching Open Interest..."));
             }
+            Status::Loading(exchange::fetcher::InfoKind::FetchingDepth) => {
+                stream_info_element = stream_info_element.push(text("Fetching Depth History..."));
+            }
             Status::Stale(msg) => {
                 stream_info_element = stream_info_element.push(text(msg));
             }
             Status::Ready => {}
         }
 
-        let content = pane_grid::Content::new(body)
-            .style(move |theme| style::pane_background(theme, is_focused));
+        let body = container(body)
+            .id(self.capture_id())
+            .width(Length::Fill)
+            .height(Length::Fill);
+
+        let flash = self.border_flash();
+        let content = pane_grid::Content::new(body).style(move |theme| {
+            if flash {
+                style::pane_alert(theme)
+            } else {
+                style::pane_background(theme, is_focused)
+            }
+        });
 
         let controls = {
             let compact_control = container(
@@ -935,6 +1377,7 @@ impl State {
             }
             Event::ContentSelected(kind) => {
                 self.content = Content::placeholder(kind);
+                self.paused = None;
 
                 if !matches!(kind, ContentKind::Starter) {
                     self.streams = ResolvedStream::Waiting(vec![]);
@@ -945,15 +1388,106 @@ impl State {
                     }
                 }
             }
-            Event::ChartInteraction(msg) => match &mut self.content {
-                Content::Heatmap { chart: Some(c), .. } => {
-                    super::chart::update(c, &msg);
-                }
-                Content::Kline { chart: Some(c), .. } => {
-                    super::chart::update(c, &msg);
+            Event::ChartInteraction(msg) => {
+                let ticker_info = self.stream_pair();
+                let ticker = ticker_info.map(|ticker_info| ticker_info.ticker);
+
+                match &mut self.content {
+                    Content::Heatmap { chart: Some(c), .. } => {
+                        super::chart::update(c, &msg);
+
+                        if let super::chart::Message::PriceCopied(price) = msg {
+                            return Some(Effect::CopyToClipboard(format_price(price, ticker_info)));
+                        }
+                    }
+                    Content::Kline { chart: Some(c), .. } => {
+                        super::chart::update(c, &msg);
+
+                        match msg {
+                            super::chart::Message::SplitDragged(..) => c.record_panel_heights(),
+                            super::chart::Message::PriceCopied(price) => {
+                                return Some(Effect::CopyToClipboard(format_price(
+                                    price,
+                                    ticker_info,
+                                )));
+                            }
+                            super::chart::Message::AlertAt(price) => {
+                                let exists = self
+                                    .settings
+                                    .price_alerts
+                                    .iter()
+                                    .any(|alert| alert.price == price);
+
+                                if !exists {
+                                    self.settings
+                                        .price_alerts
+                                        .push(PriceAlert { price, label: None });
+                                    self.notifications.push(Toast::new(Notification::Info(
+                                        format!(
+                                            "Alert set at {}",
+                                            format_price(price, ticker_info)
+                                        ),
+                                    )));
+                                }
+                            }
+                            super::chart::Message::DrawingAdded(_)
+                            | super::chart::Message::DrawingRemoved(_) => {
+                                if let Some(ticker) = ticker {
+                                    self.settings
+                                        .drawings
+                                        .set(ticker, c.state().drawings().to_vec());
+                                }
+                            }
+                            super::chart::Message::LevelAlertRequested(level) => {
+                                let exists = self
+                                    .settings
+                                    .price_alerts
+                                    .iter()
+                                    .any(|alert| alert.price == level.price);
+
+                                if !exists {
+                                    self.settings.price_alerts.push(PriceAlert {
+                                        price: level.price,
+                                        label: Some(level.kind.to_string()),
+                                    });
+                                    self.notifications.push(Toast::new(Notification::Info(
+                                        format!(
+                                            "Alert set at {} ({})",
+                                            level.price.to_f32(),
+                                            level.kind
+                                        ),
+                                    )));
+                                }
+                            }
+                            super::chart::Message::BracketPlaced { entry, stop } => {
+                                self.position_size.set_bracket(entry, stop, ticker_info);
+                                self.modal = Some(Modal::PositionSize);
+                            }
+                            super::chart::Message::ReplayStartPicked(time) => {
+                                c.start_replay(time);
+                            }
+                            super::chart::Message::CoverageBackfill(from, to) => {
+                                if let Some(chart::Action::RequestFetch(fetch)) =
+                                    c.backfill(from, to)
+                                {
+                                    return Some(Effect::RequestFetch(fetch));
+                                }
+                            }
+                            super::chart::Message::ReplayStepped(delta) => c.replay_step(delta),
+                            super::chart::Message::ReplayPlayToggled => {
+                                c.replay_toggle_play(Instant::now());
+                            }
+                            super::chart::Message::PriceAlertRemoved(index)
+                                if index < self.settings.price_alerts.len() =>
+                            {
+                                self.settings.price_alerts.remove(index);
+                            }
+                            _ => {}
+                        }
+                    }
+                    _ => {}
                 }
-                _ => {}
-            },
+            }
             Event::PanelInteraction(msg) => match &mut self.content {
                 Content::Ladder(Some(p)) => super::panel::update(p, msg),
                 Content::TimeAndSales(Some(p)) => super::panel::update(p, msg),
@@ -980,6 +1514,88 @@ impl State {
                     *cur = c.kind.clone();
                 }
             }
+            Event::BarCloseAlertChanged(alert) => {
+                self.settings.bar_close_alert = alert;
+                self.bar_close.reset();
+            }
+            Event::StopRunsChanged(config) => {
+                self.settings.stop_runs = config;
+            }
+            Event::PatternsChanged(config) => {
+                self.settings.patterns = config;
+            }
+            Event::VolumeCurveChanged(config) => {
+                self.settings.volume_curve = config;
+            }
+            Event::CalendarChanged(config) => {
+                self.settings.calendar = config;
+            }
+            Event::DataWindowChanged(config) => {
+                self.settings.data_window = config;
+            }
+            Event::SpreadAlertChanged(config) => {
+                self.settings.spread_alert = config;
+                self.spread_watch = None;
+            }
+            Event::SizeUnitChanged(unit) => {
+                self.settings.size_unit_override = unit;
+                self.sync_size_unit();
+            }
+            Event::PriceScaleChanged(price_scale) => {
+                if let Content::Kline { chart, layout, .. } = &mut self.content {
+                    layout.price_scale = price_scale;
+                    if let Some(chart) = chart {
+                        chart.set_price_scale(price_scale);
+                    }
+                }
+            }
+            Event::ReferenceLinesChanged(reference_lines) => {
+                self.settings.reference_lines = reference_lines;
+
+                if let Content::Kline {
+                    chart: Some(chart), ..
+                } = &mut self.content
+                {
+                    chart.set_reference_lines(reference_lines);
+                }
+            }
+            Event::OverlaysChanged(overlays) => {
+                self.settings.overlays = overlays;
+            }
+            Event::SignalsChanged(signals) => {
+                self.settings.signals = signals;
+            }
+            Event::RearmSignals => {
+                if let Content::Kline {
+                    chart: Some(chart), ..
+                } = &mut self.content
+                {
+                    chart.rearm_signals();
+                }
+            }
+            Event::TogglePause => {
+                self.toggle_pause(Instant::now());
+            }
+            Event::ReconnectNow => exchange::adapter::metatrader5::reconnect_now(),
+            Event::StopReconnecting => exchange::adapter::metatrader5::stop_reconnecting(),
+            Event::ReloadScripts => {
+                let report = indicators::script::reload();
+
+                let toast = if report.errors.is_empty() {
+                    Toast::new(Notification::Info(format!(
+                        "Reloaded {} scripts, {} changed",
+                        report.loaded, report.changed
+                    )))
+                } else {
+                    let errors = report
+                        .errors
+                        .iter()
+                        .map(|(file, error)| format!("{file}: {error}"))
+                        .collect::<Vec<_>>();
+                    Toast::error(format!("Failed to load scripts:\n{}", errors.join("\n")))
+                };
+                return Some(Effect::Notify(toast));
+            }
             Event::ClusterScalingSelected(scaling) => {
                 if let Content::Kline { chart, kind, .. } = &mut self.content
                     && let Some(c) = chart
@@ -1061,133 +1677,7 @@ impl State {
                             }
                             modal::stream::Action::BasisSelected(new_basis) => {
                                 modifier.update_kind_with_basis(new_basis);
-                                self.settings.selected_basis = Some(new_basis);
-
-                                let base_ticker = self.stream_pair();
-
-                                match &mut self.content {
-                                    Content::Heatmap { chart: Some(c), .. } => {
-                                        c.set_basis(new_basis);
-
-                                        if let Some(stream_type) =
-                                            self.streams.ready_iter_mut().and_then(|mut it| {
-                                                it.find(|s| {
-                                                    matches!(s, StreamKind::DepthAndTrades { .. })
-                                                })
-                                            })
-                                            && let StreamKind::DepthAndTrades {
-                                                push_freq,
-                                                ticker_info,
-                                                ..
-                                            } = stream_type
-                                            && ticker_info.exchange().is_custom_push_freq()
-                                        {
-                                            match new_basis {
-                                                Basis::Time(tf) => {
-                                                    *push_freq = exchange::PushFrequency::Custom(tf)
-                                                }
-                                                Basis::Tick(_) => {
-                                                    *push_freq =
-                                                        exchange::PushFrequency::ServerDefault
-                                                }
-                                            }
-                                        }
-
-                                        effect = Some(Effect::RefreshStreams);
-                                    }
-                                    Content::Kline { chart: Some(c), .. } => {
-                                        if let Some(base_ticker) = base_ticker {
-                                            match new_basis {
-                                                Basis::Time(tf) => {
-                                                    let kline_stream = StreamKind::Kline {
-                                                        ticker_info: base_ticker,
-                                                        timeframe: tf,
-                                                    };
-                                                    let mut streams = vec![kline_stream];
-
-                                                    if matches!(
-                                                        c.kind,
-                                                        data::chart::KlineChartKind::Footprint { .. }
-                                                    ) {
-                                                        let depth_aggr = if base_ticker
-                                                            .exchange()
-                                                            .is_depth_client_aggr()
-                                                        {
-                                                            StreamTicksize::Client
-                                                        } else {
-                                                            StreamTicksize::ServerSide(
-                                                                self.settings
-                                                                    .tick_multiply
-                                                                    .unwrap_or(TickMultiplier(1)),
-                                                            )
-                                                        };
-                                                        streams.push(StreamKind::DepthAndTrades {
-                                                            ticker_info: base_ticker,
-                                                            depth_aggr,
-                                                            push_freq: exchange::PushFrequency::ServerDefault,
-                                                        });
-                                                    }
-
-                                                    self.streams = ResolvedStream::Ready(streams);
-                                                    let action = c.set_basis(new_basis);
-
-                                                    if let Some(chart::Action::RequestFetch(
-                                                        fetch,
-                                                    )) = action
-                                                    {
-                                                        effect = Some(Effect::RequestFetch(fetch));
-                                                    }
-                                                }
-                                                Basis::Tick(_) => {
-                                                    let depth_aggr = if base_ticker
-                                                        .exchange()
-                                                        .is_depth_client_aggr()
-                                                    {
-                                                        StreamTicksize::Client
-                                                    } else {
-                                                        StreamTicksize::ServerSide(
-                                                            self.settings
-                                                                .tick_multiply
-                                                                .unwrap_or(TickMultiplier(1)),
-                                                        )
-                                                    };
-
-                                                    self.streams = ResolvedStream::Ready(vec![
-                                                        StreamKind::DepthAndTrades {
-                                                            ticker_info: base_ticker,
-                                                            depth_aggr,
-                                                            push_freq: exchange::PushFrequency::ServerDefault,
-                                                        },
-                                                    ]);
-                                                    c.set_basis(new_basis);
-                                                    effect = Some(Effect::RefreshStreams);
-                                                }
-                                            }
-                                        }
-                                    }
-                                    Content::Comparison(Some(c)) => {
-                                        if let Basis::Time(tf) = new_basis {
-                                            let streams: Vec<StreamKind> = c
-                                                .selected_tickers()
-                                                .iter()
-                                                .copied()
-                                                .map(|ti| StreamKind::Kline {
-                                                    ticker_info: ti,
-                                                    timeframe: tf,
-                                                })
-                                                .collect();
-
-                                            self.streams = ResolvedStream::Ready(streams);
-                                            let action = c.set_basis(new_basis);
-
-                                            if let Some(chart::Action::RequestFetch(fetch)) = action
-                                            {
-                                                effect = Some(Effect::RequestFetch(fetch));
-                                            }
-                                        }
-                                    }
-                                    _ => {}
-                                }
+                                effect = self.apply_basis(new_basis);
                             }
                         }
                     }
@@ -1223,6 +1713,33 @@ impl State {
                     }
                 }
             }
+            Event::StripConfigChanged(cfg) => {
+                if let Content::Strip(Some(strip)) = &mut self.content {
+                    strip.set_config(cfg.clone());
+                    self.settings.visual_config = Some(VisualConfig::TimeframeStrip(cfg));
+                    self.streams = ResolvedStream::Ready(strip.streams());
+                    return Some(Effect::RefreshStreams);
+                }
+            }
+            Event::PositionSizeChanged(message) => {
+                self.position_size.update(message);
+            }
+            Event::Replay(control) => {
+                if let Content::Kline { chart: Some(c), .. } = &mut self.content {
+                    match control {
+                        ReplayControl::Toggled => {
+                            if c.state().replay_input() == chart::ReplayInput::Off {
+                                c.arm_replay();
+                            } else {
+                                c.exit_replay();
+                            }
+                        }
+                        ReplayControl::Stepped(delta) => c.replay_step(delta),
+                        ReplayControl::PlayToggled => c.replay_toggle_play(Instant::now()),
+                        ReplayControl::SpeedSelected(speed) => c.set_replay_speed(speed),
+                    }
+                }
+            }
             Event::MiniTickersListInteraction(message) => {
                 if let Some(Modal::MiniTickersList(ref mut mini_panel)) = self.modal
                     && let Some(action) = mini_panel.update(message)
@@ -1255,6 +1772,20 @@ impl State {
                     }
                 }
             }
+            Event::SourceSelected(source) => {
+                self.modal = None;
+                if self.settings.source != source {
+                    self.settings.source = source;
+                    return self.stream_pair().map(Effect::ReloadTicker);
+                }
+            }
+            Event::PriceBasisSelected(price_basis) => {
+                self.modal = None;
+                if self.settings.price_basis != price_basis {
+                    self.settings.price_basis = price_basis;
+                    return self.stream_pair().map(Effect::ReloadTicker);
+                }
+            }
         }
         None
     }
@@ -1279,8 +1810,10 @@ impl State {
             }
         };
 
-        let treat_as_starter =
-            matches!(&self.content, Content::Starter) || !self.content.initialized();
+        let treat_as_starter = matches!(
+            &self.content,
+            Content::Starter | Content::Quarantined { .. }
+        ) || !self.content.initialized();
 
         let tooltip_pos = tooltip::Position::Bottom;
         let mut buttons = row![];
@@ -1310,38 +1843,122 @@ impl State {
                 modal_btn_style(Modal::Indicators),
             ));
         }
-
-        if is_popout {
+        if !treat_as_starter && matches!(&self.content, Content::Kline { .. }) {
             buttons = buttons.push(button_with_tooltip(
-                icon_text(Icon::Popout, 12),
-                Message::Merge,
-                Some("Merge"),
+                icon_text(Icon::Edit, 12),
+                show_modal(Modal::PositionSize),
+                Some("Position size"),
                 tooltip_pos,
-                control_btn_style(is_popout),
+                modal_btn_style(Modal::PositionSize),
             ));
-        } else if total_panes > 1 {
+        }
+        if let Content::Kline { chart: Some(c), .. } = &self.content
+            && c.supports_replay()
+        {
+            let is_active = c.state().replay_input() != chart::ReplayInput::Off;
             buttons = buttons.push(button_with_tooltip(
-                icon_text(Icon::Popout, 12),
-                Message::Popout,
-                Some("Pop out"),
+                icon_text(Icon::Return, 12),
+                Message::PaneEvent(pane, Event::Replay(ReplayControl::Toggled)),
+                Some(if is_active {
+                    "Exit bar replay"
+                } else {
+                    "Bar replay"
+                }),
                 tooltip_pos,
-                control_btn_style(is_popout),
+                control_btn_style(is_active),
             ));
         }
 
-        if total_panes > 1 {
-            let (resize_icon, message) = if is_maximized {
-                (Icon::ResizeSmall, Message::Restore)
+        if !treat_as_starter {
+            let on_clone = if self.settings.drawings.is_empty() {
+                Message::ClonePane(pane, false)
             } else {
-                (Icon::ResizeFull, Message::MaximizePane(pane))
+                show_modal(Modal::Clone)
             };
 
             buttons = buttons.push(button_with_tooltip(
-                icon_text(resize_icon, 12),
-                message,
-                None,
+                icon_text(Icon::Clone, 12),
+                on_clone,
+                Some("Clone pane"),
                 tooltip_pos,
-                control_btn_style(is_maximized),
+                modal_btn_style(Modal::Clone),
+            ));
+        }
+
+        if matches!(
+            self.content,
+            Content::Heatmap { chart: Some(_), .. } | Content::Ladder(Some(_))
+        ) {
+            buttons = buttons.push(button_with_tooltip(
+                icon_text(Icon::Folder, 12),
+                Message::DumpBook(pane),
+                Some("Dump order book to file"),
+                tooltip_pos,
+                control_btn_style(false),
+            ));
+        }
+
+        if !treat_as_starter {
+            let is_paused = self.is_paused();
+            buttons = buttons.push(button_with_tooltip(
+                icon_text(
+                    if is_paused {
+                        Icon::Locked
+                    } else {
+                        Icon::Unlocked
+                    },
+                    12,
+                ),
+                Message::PaneEvent(pane, Event::TogglePause),
+                Some(if is_paused {
+                    "Resume stream"
+                } else {
+                    "Pause stream"
+                }),
+                tooltip_pos,
+                control_btn_style(is_paused),
+            ));
+
+            buttons = buttons.push(button_with_tooltip(
+                icon_text(Icon::ExternalLink, 12),
+                Message::ExportImage(pane),
+                Some("Export image"),
+                tooltip_pos,
+                control_btn_style(false),
+            ));
+        }
+
+        if is_popout {
+            buttons = buttons.push(button_with_tooltip(
+                icon_text(Icon::Popout, 12),
+                Message::Merge,
+                Some("Merge"),
+                tooltip_pos,
+                control_btn_style(is_popout),
+            ));
+        } else if total_panes > 1 {
+            buttons = buttons.push(button_with_tooltip(
+                icon_text(Icon::Popout, 12),
+                Message::Popout,
+                Some("Pop out"),
+                tooltip_pos,
+                control_btn_style(is_popout),
+            ));
+        }
+
+        if total_panes > 1 {
+            let (resize_icon, message) = if is_maximized {
+                (Icon::ResizeSmall, Message::Restore)
+            } else {
+                (Icon::ResizeFull, Message::MaximizePane(pane))
+            };
+
+            buttons = buttons.push(button_with_tooltip(
+                icon_text(resize_icon, 12),
+                message,
+                None,
+                tooltip_pos,
+                control_btn_style(is_maximized),
             ));
 
             buttons = buttons.push(button_with_tooltip(
@@ -1360,6 +1977,31 @@ impl State {
             .into()
     }
 
+    /// Spread alert settings, for panes of MT5 tickers
+    fn signals_cfg<'a>(&self, pane: pane_grid::Pane) -> Element<'a, Message> {
+        let disabled = match &self.content {
+            Content::Kline {
+                chart: Some(chart), ..
+            } => chart.disabled_signals(),
+            _ => vec![],
+        };
+        signals_cfg(pane, &self.settings.signals, &disabled)
+    }
+
+    fn spread_alert_cfg<'a>(&self, pane: pane_grid::Pane) -> Option<Element<'a, Message>> {
+        self.stream_pair()
+            .filter(|ticker_info| {
+                ticker_info.exchange() == exchange::adapter::Exchange::MetaTrader5
+            })
+            .map(|_| {
+                spread_alert_cfg(
+                    pane,
+                    self.settings.spread_alert,
+                    self.connection_spread_alert(),
+                )
+            })
+    }
+
     fn compose_stack_view<'a, F>(
         &'a self,
         base: Element<'a, Message>,
@@ -1373,6 +2015,31 @@ impl State {
     where
         F: FnOnce() -> Element<'a, Message>,
     {
+        let banner = match (
+            self.pause_banner()
+                .or_else(|| self.slot_banner())
+                .or_else(|| self.retired_banner()),
+            self.pending_reconnect(),
+        ) {
+            (Some(banner), _) => Some(text(banner).size(11).into()),
+            (None, Some(retry)) => Some(reconnect_banner(pane, retry)),
+            (None, None) => self
+                .market_banner()
+                .map(|banner| text(banner).size(11).into()),
+        };
+
+        let base = match banner {
+            Some(banner) => column![
+                container(banner)
+                    .width(Length::Fill)
+                    .padding(padding::left(8).right(8).top(2).bottom(2))
+                    .style(style::market_state_banner),
+                base,
+            ]
+            .into(),
+            None => base,
+        };
+
         let base =
             widget::toast::Manager::new(base, &self.notifications, Alignment::End, move |msg| {
                 Message::PaneEvent(pane, Event::DeleteNotification(msg))
@@ -1382,8 +2049,22 @@ impl State {
         let on_blur = Message::PaneEvent(pane, Event::HideModal);
 
         match &self.modal {
-            Some(Modal::LinkGroup) => {
-                let content = link_group_modal(pane, self.link_group);
+            Some(Modal::DataSource) => stack_modal(
+                base,
+                source_modal(pane, self.settings.source.as_ref()),
+                on_blur,
+                padding::right(12).left(48),
+                Alignment::Start,
+            ),
+            Some(Modal::PriceBasis) => stack_modal(
+                base,
+                price_basis_modal(pane, self.settings.price_basis),
+                on_blur,
+                padding::right(12).left(48),
+                Alignment::Start,
+            ),
+            Some(Modal::LinkGroup { sync_timeframe }) => {
+                let content = link_group_modal(pane, self.link_group, *sync_timeframe);
 
                 stack_modal(
                     base,
@@ -1437,6 +2118,24 @@ impl State {
                 padding::right(12).left(12),
                 Alignment::End,
             ),
+            Some(Modal::PositionSize) => stack_modal(
+                base,
+                self.position_size
+                    .view(self.stream_pair())
+                    .map(move |message| {
+                        Message::PaneEvent(pane, Event::PositionSizeChanged(message))
+                    }),
+                on_blur,
+                padding::right(12).left(12),
+                Alignment::End,
+            ),
+            Some(Modal::Clone) => stack_modal(
+                base,
+                clone_modal(pane),
+                on_blur,
+                padding::right(12).left(12),
+                Alignment::End,
+            ),
             Some(Modal::Controls) => stack_modal(
                 base,
                 if let Some(controls) = compact_controls {
@@ -1493,16 +2192,19 @@ impl State {
             Content::Ladder(panel) => panel
                 .as_mut()
                 .and_then(|p| p.invalidate(Some(now)).map(Action::Panel)),
-            Content::Starter => None,
+            Content::Starter | Content::Quarantined { .. } => None,
             Content::Comparison(chart) => chart
                 .as_mut()
                 .and_then(|c| c.invalidate(Some(now)).map(Action::Chart)),
+            Content::Strip(strip) => strip
+                .as_mut()
+                .and_then(|s| s.invalidate(Some(now)).map(Action::Chart)),
         }
     }
 
     pub fn update_interval(&self) -> Option<u64> {
         match &self.content {
-            Content::Kline { .. } | Content::Comparison(_) => Some(1000),
+            Content::Kline { .. } | Content::Comparison(_) | Content::Strip(_) => Some(1000),
             Content::Heatmap { chart, .. } => {
                 if let Some(chart) = chart {
                     chart.basis_interval()
@@ -1511,7 +2213,7 @@ impl State {
                 }
             }
             Content::Ladder(_) | Content::TimeAndSales(_) => Some(100),
-            Content::Starter => None,
+            Content::Starter | Content::Quarantined { .. } => None,
         }
     }
 
@@ -1553,9 +2255,574 @@ impl State {
         None
     }
 
+    /// Switches the chart's basis and its streams, returns what the dashboard must do to backfill
+    fn apply_basis(&mut self, new_basis: Basis) -> Option<Effect> {
+        self.settings.selected_basis = Some(new_basis);
+
+        let base_ticker = self.stream_pair();
+
+        match &mut self.content {
+            Content::Heatmap { chart: Some(c), .. } => {
+                c.set_basis(new_basis);
+
+                if let Some(stream_type) = self
+                    .streams
+                    .ready_iter_mut()
+                    .and_then(|mut it| it.find(|s| matches!(s, StreamKind::DepthAndTrades { .. })))
+                    && let StreamKind::DepthAndTrades {
+                        push_freq,
+                        ticker_info,
+                        ..
+                    } = stream_type
+                    && ticker_info.exchange().is_custom_push_freq()
+                {
+                    match new_basis {
+                        Basis::Time(tf) => *push_freq = exchange::PushFrequency::Custom(tf),
+                        Basis::Tick(_) => *push_freq = exchange::PushFrequency::ServerDefault,
+                    }
+                }
+
+                return Some(Effect::RefreshStreams);
+            }
+            Content::Kline { chart: Some(c), .. } => {
+                if let Some(base_ticker) = base_ticker {
+                    match new_basis {
+                        Basis::Time(tf) => {
+                            let kline_stream = StreamKind::Kline {
+                                ticker_info: base_ticker,
+                                timeframe: tf,
+                            };
+                            let mut streams = vec![kline_stream];
+
+                            if matches!(c.kind, data::chart::KlineChartKind::Footprint { .. }) {
+                                let depth_aggr = if base_ticker.exchange().is_depth_client_aggr() {
+                                    StreamTicksize::Client
+                                } else {
+                                    StreamTicksize::ServerSide(
+                                        self.settings.tick_multiply.unwrap_or(TickMultiplier(1)),
+                                    )
+                                };
+                                streams.push(StreamKind::DepthAndTrades {
+                                    ticker_info: base_ticker,
+                                    depth_aggr,
+                                    push_freq: exchange::PushFrequency::ServerDefault,
+                                });
+                            }
+
+                            self.streams = ResolvedStream::Ready(streams);
+                            let action = c.set_basis(new_basis);
+
+                            if let Some(chart::Action::RequestFetch(fetch)) = action {
+                                return Some(Effect::RequestFetch(fetch));
+                            }
+                        }
+                        Basis::Tick(_) => {
+                            let depth_aggr = if base_ticker.exchange().is_depth_client_aggr() {
+                                StreamTicksize::Client
+                            } else {
+                                StreamTicksize::ServerSide(
+                                    self.settings.tick_multiply.unwrap_or(TickMultiplier(1)),
+                                )
+                            };
+
+                            self.streams =
+                                ResolvedStream::Ready(vec![StreamKind::DepthAndTrades {
+                                    ticker_info: base_ticker,
+                                    depth_aggr,
+                                    push_freq: exchange::PushFrequency::ServerDefault,
+                                }]);
+                            c.set_basis(new_basis);
+                            return Some(Effect::RefreshStreams);
+                        }
+                    }
+                }
+            }
+            Content::Comparison(Some(c)) => {
+                if let Basis::Time(tf) = new_basis {
+                    let streams: Vec<StreamKind> = c
+                        .selected_tickers()
+                        .iter()
+                        .copied()
+                        .map(|ti| StreamKind::Kline {
+                            ticker_info: ti,
+                            timeframe: tf,
+                        })
+                        .collect();
+
+                    self.streams = ResolvedStream::Ready(streams);
+                    let action = c.set_basis(new_basis);
+
+                    if let Some(chart::Action::RequestFetch(fetch)) = action {
+                        return Some(Effect::RequestFetch(fetch));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+
+    /// Active timeframe of a time based kline or comparison chart
+    pub fn timeframe(&self) -> Option<Timeframe> {
+        match &self.content {
+            Content::Kline { chart: Some(c), .. } => match c.basis() {
+                Basis::Time(tf) => Some(tf),
+                Basis::Tick(_) => None,
+            },
+            Content::Comparison(Some(c)) => Some(c.timeframe),
+            _ => None,
+        }
+    }
+
+    /// Applies a timeframe coming from a linked pane, no-op when it's already showing it
+    pub fn sync_timeframe(&mut self, timeframe: Timeframe) -> Option<Effect> {
+        match self.timeframe() {
+            Some(current) if current != timeframe => self.apply_basis(Basis::Time(timeframe)),
+            _ => None,
+        }
+    }
+
+    /// Collects the liquidity wall changes the heatmap picked up since the last poll
+    pub fn poll_wall_events(&mut self) -> Option<(TickerInfo, WallConfig, Vec<WallEvent>)> {
+        let ticker_info = self.stream_pair()?;
+
+        let Content::Heatmap {
+            chart: Some(chart), ..
+        } = &mut self.content
+        else {
+            return None;
+        };
+
+        let config = chart.visual_config().walls?;
+        let events = chart.take_wall_events();
+
+        (!events.is_empty()).then_some((ticker_info, config, events))
+    }
+
+    /// Polls the bar close clock against the exchange's server time, returns the alert to raise
+    /// if a bar of the pane's timeframe just closed
+    pub fn poll_bar_close(&mut self) -> Option<(TickerInfo, Timeframe, BarCloseAlert)> {
+        let alert = self.settings.bar_close_alert?;
+
+        let Content::Kline {
+            chart: Some(chart), ..
+        } = &self.content
+        else {
+            return None;
+        };
+        let Basis::Time(timeframe) = chart.basis() else {
+            return None;
+        };
+        let ticker_info = self.stream_pair()?;
+
+        let server_now = exchange::adapter::server_now_ms(ticker_info.exchange());
+
+        self.bar_close
+            .poll(timeframe.to_milliseconds(), server_now)
+            .map(|_| (ticker_info, timeframe, alert))
+    }
+
+    /// Keeps the chart's candle pattern markers in line with the pane settings, returns the
+    /// patterns to announce
+    pub fn poll_patterns(&mut self) -> Option<(TickerInfo, Vec<PatternMark>)> {
+        let ticker_info = self.stream_pair()?;
+
+        let Content::Kline {
+            chart: Some(chart), ..
+        } = &mut self.content
+        else {
+            return None;
+        };
+
+        let marks = chart.poll_patterns(self.settings.patterns);
+        if marks.is_empty() {
+            return None;
+        }
+
+        Some((ticker_info, marks))
+    }
+
+    /// Keeps the chart's stop run markers in line with the pane settings, returns the runs to
+    /// announce
+    /// Runs the chart's signal scripts, returns what they announced by script name
+    pub fn poll_signals(&mut self) -> Option<(TickerInfo, Vec<(String, signal::Output)>)> {
+        let ticker_info = self.stream_pair()?;
+
+        let Content::Kline {
+            chart: Some(chart), ..
+        } = &mut self.content
+        else {
+            return None;
+        };
+
+        let outputs = chart.poll_signals(&self.settings.signals, Instant::now());
+        (!outputs.is_empty()).then_some((ticker_info, outputs))
+    }
+
+    pub fn poll_stop_runs(&mut self) -> Option<(TickerInfo, StopRunConfig, Vec<StopRun>)> {
+        let ticker_info = self.stream_pair()?;
+
+        let Content::Kline {
+            chart: Some(chart), ..
+        } = &mut self.content
+        else {
+            return None;
+        };
+
+        let config = self.settings.stop_runs.clone();
+        let runs = chart.poll_stop_runs(
+            config.as_ref().map(|config| config.sensitivity.params()),
+            Instant::now(),
+        );
+        if runs.is_empty() {
+            return None;
+        }
+
+        Some((ticker_info, config?, runs))
+    }
+
+    /// Spread alert of the pane's MT5 connection, for panes that set none of their own
+    pub fn connection_spread_alert(&self) -> Option<SpreadAlert> {
+        let ticker_info = self.stream_pair()?;
+        if ticker_info.exchange() != exchange::adapter::Exchange::MetaTrader5 {
+            return None;
+        }
+
+        let connection = ticker_info
+            .ticker
+            .connection()
+            .and_then(exchange::adapter::metatrader5::connection_name)
+            .or_else(|| {
+                exchange::adapter::metatrader5::get_global_config()
+                    .map(|config| config.connection_name())
+            })?;
+        spread_alert::default_for(&connection)
+    }
+
+    /// Feeds the spread alert in effect from a quote of the pane's MT5 ticker
+    fn watch_spread(&mut self, ticker_info: TickerInfo, depth: &Depth) {
+        if ticker_info.exchange() != exchange::adapter::Exchange::MetaTrader5 {
+            return;
+        }
+        let Some(alert) = self
+            .settings
+            .spread_alert
+            .or_else(|| self.connection_spread_alert())
+            .filter(|alert| alert.enabled)
+        else {
+            self.spread_watch = None;
+            return;
+        };
+        let Some(spread) = spread::spread_points(depth, ticker_info.min_ticksize) else {
+            return;
+        };
+
+        let watch = match &mut self.spread_watch {
+            Some((ticker, watch)) if *ticker == ticker_info.ticker => watch,
+            watch => &mut watch.insert((ticker_info.ticker, SpreadWatch::default())).1,
+        };
+        if let Some(widening) = watch.update(spread, alert.limit) {
+            self.spread_flash = Some(Instant::now());
+            self.spread_widening = Some((ticker_info, alert, widening));
+        }
+    }
+
+    /// The spread widening to announce, if one started since the last call
+    pub fn poll_spread_widening(&mut self) -> Option<(TickerInfo, SpreadAlert, Widening)> {
+        self.spread_widening.take()
+    }
+
+    /// Whether the border is lit, it blinks for a few seconds after the spread widens
+    fn border_flash(&self) -> bool {
+        self.spread_flash.is_some_and(|at| {
+            let elapsed = at.elapsed();
+            elapsed < SPREAD_FLASH && (elapsed.as_millis() / 250) % 2 == 0
+        })
+    }
+
+    /// Errors of the chart's overlays since the last call, e.g. a failing indicator script
+    pub fn take_overlay_errors(&mut self) -> Vec<String> {
+        match &mut self.content {
+            Content::Kline {
+                chart: Some(chart), ..
+            } => chart.take_overlay_errors(),
+            _ => vec![],
+        }
+    }
+
+    /// Shows sizes in the pane's unit, charts and panels get rebuilt on ticker changes
+    fn sync_size_unit(&mut self) {
+        let unit = self.settings.size_unit_override;
+
+        match &mut self.content {
+            Content::Kline {
+                chart: Some(chart), ..
+            } => chart.set_size_unit(unit),
+            Content::Heatmap {
+                chart: Some(chart), ..
+            } => chart.set_size_unit(unit),
+            Content::TimeAndSales(Some(panel)) => panel.set_size_unit(unit),
+            Content::Ladder(Some(panel)) => panel.set_size_unit(unit),
+            _ => {}
+        }
+    }
+
+    fn sync_data_window(&mut self, timezone: UserTimezone) {
+        let config = self.settings.data_window;
+
+        match &mut self.content {
+            Content::Kline {
+                chart: Some(chart), ..
+            } => chart.set_data_window(config),
+            Content::Heatmap {
+                chart: Some(chart), ..
+            } => chart.set_data_window(config, timezone),
+            _ => {}
+        }
+    }
+
+    /// Keeps the chart's reference lines and drawings in sync with the pane settings, as charts
+    /// get rebuilt on basis and ticker changes, and rolls its session levels over against the
+    /// exchange's server time
+    pub fn poll_chart_overlays(&mut self, timezone: UserTimezone) -> Vec<(TickerInfo, PriceAlert)> {
+        self.sync_size_unit();
+        self.sync_data_window(timezone);
+
+        let reference_lines = self.settings.reference_lines;
+        let ticker_info = self.stream_pair();
+
+        let Content::Kline {
+            chart: Some(chart), ..
+        } = &mut self.content
+        else {
+            return vec![];
+        };
+
+        if chart.reference_lines() != reference_lines {
+            chart.set_reference_lines(reference_lines);
+        }
+        chart.sync_overlays(&self.settings.overlays, timezone);
+        chart.set_volume_curve(self.settings.volume_curve, timezone);
+        chart.set_calendar(self.settings.calendar);
+        chart.poll_replay(Instant::now());
+
+        if let Some(ticker_info) = ticker_info {
+            let drawings = self.settings.drawings.get(&ticker_info.ticker);
+            if chart.state().drawings() != drawings {
+                chart.mut_state().set_drawings(drawings.to_vec());
+            }
+
+            let server_now = exchange::adapter::server_now_ms(ticker_info.exchange());
+            chart.poll_session(timezone, server_now);
+        }
+
+        // Replayed prices are history, they must not fire alerts
+        let last_price = chart
+            .last_traded_price()
+            .filter(|_| chart.replay().is_none());
+        let prev_price = std::mem::replace(&mut self.last_alert_price, last_price);
+
+        let mut triggered = vec![];
+        if let (Some(ticker_info), Some(prev), Some(now)) = (ticker_info, prev_price, last_price) {
+            self.settings.price_alerts.retain(|alert| {
+                let crossed = alert.crossed(prev, now);
+                if crossed {
+                    triggered.push((ticker_info, alert.clone()));
+                }
+                !crossed
+            });
+        }
+
+        let bracket = if self.modal == Some(Modal::PositionSize) {
+            self.position_size.bracket_prices()
+        } else {
+            None
+        };
+        chart.mut_state().set_bracket(bracket);
+
+        chart.set_price_alerts(
+            self.settings
+                .price_alerts
+                .iter()
+                .map(|alert| alert.price)
+                .collect(),
+        );
+
+        triggered
+    }
+
     pub fn unique_id(&self) -> uuid::Uuid {
         self.id
     }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.is_some()
+    }
+
+    /// Holds back the pane's stream data, or applies what was held back in order
+    pub fn toggle_pause(&mut self, now: Instant) {
+        if self.paused.is_some() {
+            self.resume();
+        } else if self.content.initialized() {
+            self.paused = Some(PausedStream::new(now));
+        }
+    }
+
+    fn resume(&mut self) {
+        let Some(paused) = self.paused.take() else {
+            return;
+        };
+
+        for (stream, time, depth, trades) in paused.depth_updates() {
+            // Held for a stream the pane has since switched away from
+            if self.matches_stream(stream) {
+                self.apply_depth_and_trades(stream, time, depth, trades);
+            }
+        }
+        for (stream, kline, status) in paused.klines() {
+            if self.matches_stream(stream) {
+                self.apply_kline(stream, kline, *status);
+            }
+        }
+    }
+
+    pub fn insert_depth_and_trades(
+        &mut self,
+        stream: &StreamKind,
+        depth_update_t: u64,
+        depth: &Arc<Depth>,
+        trades_buffer: &[Trade],
+    ) {
+        let Some(paused) = &mut self.paused else {
+            self.apply_depth_and_trades(stream, depth_update_t, depth, trades_buffer);
+            return;
+        };
+
+        if paused.push_depth(*stream, depth_update_t, depth.clone(), trades_buffer)
+            == stream_pause::Held::Full
+        {
+            self.resume();
+            self.notifications.push(Toast::warn(format!(
+                "Resumed, {} trades were held back, the most a paused pane keeps",
+                stream_pause::MAX_TRADES
+            )));
+        }
+    }
+
+    fn apply_depth_and_trades(
+        &mut self,
+        stream: &StreamKind,
+        depth_update_t: u64,
+        depth: &Depth,
+        trades_buffer: &[Trade],
+    ) {
+        let market_closed = self.market_is_closed();
+        // Quotes of a closed market would plot stale spreads
+        if !market_closed {
+            self.watch_spread(stream.ticker_info(), depth);
+        }
+
+        match &mut self.content {
+            Content::Heatmap { chart, .. } => {
+                // No history accumulates for a closed market, its book only repeats
+                if let Some(c) = chart
+                    && !market_closed
+                {
+                    c.insert_datapoint(trades_buffer, depth_update_t, depth);
+                }
+            }
+            Content::Kline { chart, .. } => {
+                if let Some(c) = chart {
+                    c.insert_trades_buffer(trades_buffer);
+                    // Quotes of a closed market would plot stale spreads
+                    if !market_closed {
+                        c.insert_depth(depth_update_t, depth);
+                    }
+                }
+            }
+            Content::TimeAndSales(panel) => {
+                if let Some(p) = panel {
+                    p.insert_buffer(trades_buffer);
+                }
+            }
+            Content::Ladder(panel) => {
+                if let Some(panel) = panel {
+                    panel.insert_buffers(depth_update_t, depth, trades_buffer);
+                }
+            }
+            _ => {
+                log::error!("No chart found for the stream: {stream:?}");
+            }
+        }
+    }
+
+    pub fn insert_kline(&mut self, stream: &StreamKind, kline: &Kline, status: BarStatus) {
+        match &mut self.paused {
+            Some(paused) => paused.push_kline(*stream, *kline, status),
+            None => self.apply_kline(stream, kline, status),
+        }
+    }
+
+    /// Coverage of the history of `stream`, for a kline chart showing it
+    pub fn set_coverage(&mut self, stream: &StreamKind, coverage: &[data::kline_store::Stretch]) {
+        if let Content::Kline { chart: Some(c), .. } = &mut self.content
+            && self.streams.matches_stream(stream)
+        {
+            c.set_coverage(coverage.to_vec());
+        }
+    }
+
+    fn apply_kline(&mut self, stream: &StreamKind, kline: &Kline, status: BarStatus) {
+        match &mut self.content {
+            Content::Kline { chart: Some(c), .. } => {
+                c.update_latest_kline(kline, status);
+            }
+            Content::Comparison(Some(c)) => {
+                c.update_latest_kline(&stream.ticker_info(), kline);
+            }
+            Content::Strip(Some(strip)) => {
+                if let StreamKind::Kline { timeframe, .. } = stream {
+                    strip.update_latest_kline(*timeframe, kline);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// e.g. `Paused · 12s held, 340 trades`
+    fn pause_banner(&self) -> Option<String> {
+        let paused = self.paused.as_ref()?;
+        let held_for = paused.held_for(Instant::now()).as_secs();
+
+        let mut banner = format!("Paused · {held_for}s held, {} trades", paused.trade_count());
+        if paused.dropped_updates() > 0 {
+            banner.push_str(&format!(
+                ", {} book updates skipped",
+                paused.dropped_updates()
+            ));
+        }
+        Some(banner)
+    }
+
+    /// Id of the container holding the pane's body, to find it when exporting an image
+    pub fn capture_id(&self) -> iced::widget::Id {
+        iced::widget::Id::from(format!("pane-{}", self.id))
+    }
+
+    /// What the pane shows, e.g. `BTCUSDT · Candlestick Chart · 5m`
+    pub fn snapshot_label(&self) -> String {
+        let mut parts = vec![];
+        if let Some(ticker_info) = self.stream_pair() {
+            parts.push(ticker_info.ticker.to_string());
+        }
+        parts.push(self.content.kind().to_string());
+        if let Some(basis) = self.settings.selected_basis {
+            parts.push(basis.to_string());
+        }
+
+        parts.join(" · ")
+    }
 }
 
 impl Default for State {
@@ -1569,6 +2836,15 @@ impl Default for State {
             notifications: vec![],
             status: Status::Ready,
             link_group: None,
+            bar_close: BarCloseClock::default(),
+            last_alert_price: None,
+            position_size: modal::pane::position_size::Calculator::default(),
+            market_state: None,
+            paused: None,
+            spread_watch: None,
+            spread_widening: None,
+            spread_flash: None,
+            stream_slot: None,
         }
     }
 }
@@ -1592,6 +2868,12 @@ pub enum Content {
     TimeAndSales(Option<TimeAndSales>),
     Ladder(Option<Ladder>),
     Comparison(Option<ComparisonChart>),
+    Strip(Option<TimeframeStrip>),
+    /// A saved pane that failed to load, written back as it was until replaced
+    Quarantined {
+        error: String,
+        raw: serde_json::Value,
+    },
 }
 
 impl Content {
@@ -1610,9 +2892,13 @@ impl Content {
         {
             (
                 indicators.clone(),
+                // A rebuilt chart opens at the default framing, only a restored one keeps its own
                 chart
                     .as_ref()
-                    .map(|c| c.chart_layout())
+                    .map(|c| ViewConfig {
+                        viewport: None,
+                        ..c.chart_layout()
+                    })
                     .unwrap_or(layout.clone()),
                 chart
                     .as_ref()
@@ -1624,6 +2910,9 @@ impl Content {
                 ViewConfig {
                     splits: vec![],
                     autoscale: Some(data::chart::Autoscale::CenterLatest),
+                    viewport: None,
+                    price_scale: data::chart::PriceScale::Linear,
+                    panel_heights: None,
                 },
                 vec![],
             )
@@ -1668,7 +2957,10 @@ impl Content {
         {
             (
                 Some(indicators.clone()),
-                Some(chart.as_ref().map_or(layout.clone(), |c| c.chart_layout())),
+                Some(chart.as_ref().map_or(layout.clone(), |c| ViewConfig {
+                    viewport: None,
+                    ..c.chart_layout()
+                })),
                 Some(chart.as_ref().map_or(kind.clone(), |c| c.kind().clone())),
             )
         } else {
@@ -1727,11 +3019,20 @@ impl Content {
             splits_vec
         };
 
+        let price_scale = prev_layout
+            .as_ref()
+            .map(|l| l.price_scale)
+            .unwrap_or_default();
+        // Heights of the panels are kept even when the rest of the layout no longer fits
+        let panel_heights = prev_layout.as_ref().and_then(|l| l.panel_heights.clone());
         let layout = prev_layout
             .filter(|l| l.splits.len() == splits.len())
             .unwrap_or(ViewConfig {
                 splits,
                 autoscale: Some(data::chart::Autoscale::FitToVisible),
+                viewport: None,
+                price_scale,
+                panel_heights,
             });
 
         let chart = KlineChart::new(
@@ -1763,6 +3064,9 @@ impl Content {
                 layout: ViewConfig {
                     splits: vec![],
                     autoscale: Some(data::chart::Autoscale::FitToVisible),
+                    viewport: None,
+                    price_scale: data::chart::PriceScale::Linear,
+                    panel_heights: None,
                 },
             },
             ContentKind::FootprintChart => Content::Kline {
@@ -1776,6 +3080,9 @@ impl Content {
                 layout: ViewConfig {
                     splits: vec![],
                     autoscale: Some(data::chart::Autoscale::FitToVisible),
+                    viewport: None,
+                    price_scale: data::chart::PriceScale::Linear,
+                    panel_heights: None,
                 },
             },
             ContentKind::HeatmapChart => Content::Heatmap {
@@ -1785,11 +3092,15 @@ impl Content {
                 layout: ViewConfig {
                     splits: vec![],
                     autoscale: Some(data::chart::Autoscale::CenterLatest),
+                    viewport: None,
+                    price_scale: data::chart::PriceScale::Linear,
+                    panel_heights: None,
                 },
             },
             ContentKind::ComparisonChart => Content::Comparison(None),
             ContentKind::TimeAndSales => Content::TimeAndSales(None),
             ContentKind::Ladder => Content::Ladder(None),
+            ContentKind::TimeframeStrip => Content::Strip(None),
         }
     }
 
@@ -1800,7 +3111,8 @@ impl Content {
             Content::TimeAndSales(panel) => Some(panel.as_ref()?.last_update()),
             Content::Ladder(panel) => Some(panel.as_ref()?.last_update()),
             Content::Comparison(chart) => Some(chart.as_ref()?.last_update()),
-            Content::Starter => None,
+            Content::Strip(strip) => Some(strip.as_ref()?.last_update()),
+            Content::Starter | Content::Quarantined { .. } => None,
         }
     }
 
@@ -1854,11 +3166,20 @@ impl Content {
     pub fn reorder_indicators(&mut self, event: &column_drag::DragEvent) {
         match self {
             Content::Heatmap { indicators, .. } => column_drag::reorder_vec(indicators, event),
-            Content::Kline { indicators, .. } => column_drag::reorder_vec(indicators, event),
+            Content::Kline {
+                indicators, chart, ..
+            } => {
+                column_drag::reorder_vec(indicators, event);
+                if let Some(chart) = chart {
+                    chart.arrange_panels(indicators);
+                }
+            }
             Content::TimeAndSales(_)
             | Content::Ladder(_)
             | Content::Starter
-            | Content::Comparison(_) => {
+            | Content::Quarantined { .. }
+            | Content::Comparison(_)
+            | Content::Strip(_) => {
                 panic!("indicator reorder on {} pane", self)
             }
         }
@@ -1878,6 +3199,9 @@ impl Content {
             (Content::Comparison(Some(chart)), VisualConfig::Comparison(cfg)) => {
                 chart.config = cfg;
             }
+            (Content::Strip(Some(strip)), VisualConfig::TimeframeStrip(cfg)) => {
+                strip.set_config(cfg);
+            }
             _ => {}
         }
     }
@@ -1895,7 +3219,9 @@ impl Content {
             Content::TimeAndSales(_)
             | Content::Ladder(_)
             | Content::Starter
-            | Content::Comparison(_) => None,
+            | Content::Quarantined { .. }
+            | Content::Comparison(_)
+            | Content::Strip(_) => None,
         }
     }
 
@@ -1941,7 +3267,8 @@ impl Content {
             Content::TimeAndSales(_) => ContentKind::TimeAndSales,
             Content::Ladder(_) => ContentKind::Ladder,
             Content::Comparison(_) => ContentKind::ComparisonChart,
-            Content::Starter => ContentKind::Starter,
+            Content::Strip(_) => ContentKind::TimeframeStrip,
+            Content::Starter | Content::Quarantined { .. } => ContentKind::Starter,
         }
     }
 
@@ -1952,7 +3279,8 @@ impl Content {
             Content::TimeAndSales(panel) => panel.is_some(),
             Content::Ladder(panel) => panel.is_some(),
             Content::Comparison(chart) => chart.is_some(),
-            Content::Starter => true,
+            Content::Strip(strip) => strip.is_some(),
+            Content::Starter | Content::Quarantined { .. } => true,
         }
     }
 }
@@ -1968,6 +3296,7 @@ impl PartialEq for Content {
         matches!(
             (self, other),
             (Content::Starter, Content::Starter)
+                | (Content::Quarantined { .. }, Content::Quarantined { .. })
                 | (Content::Heatmap { .. }, Content::Heatmap { .. })
                 | (Content::Kline { .. }, Content::Kline { .. })
                 | (Content::TimeAndSales(_), Content::TimeAndSales(_))
@@ -1976,9 +3305,217 @@ impl PartialEq for Content {
     }
 }
 
+/// A price as the symbol quotes it
+fn format_price(price: Price, ticker_info: Option<TickerInfo>) -> String {
+    match ticker_info {
+        Some(ticker_info) => price.to_string(ticker_info.tick_rule().precision()),
+        None => price.to_f32().to_string(),
+    }
+}
+
+fn clone_modal<'a>(pane: pane_grid::Pane) -> Element<'a, Message> {
+    let option = |label: &'static str, with_drawings: bool| {
+        button(text(label))
+            .width(Length::Fill)
+            .on_press(Message::ClonePane(pane, with_drawings))
+            .style(move |theme, status| style::button::menu_body(theme, status, false))
+    };
+
+    container(column![option("Clone", false), option("Clone with drawings", true)].spacing(4))
+        .max_width(200)
+        .padding(16)
+        .style(style::chart_modal)
+        .into()
+}
+
+/// The connection a pane streams from, shown next to its ticker
+fn source_badge<'a>(
+    pane: pane_grid::Pane,
+    source: Option<&DataSource>,
+    is_open: bool,
+) -> Element<'a, Message> {
+    let label = source.map_or("Active", |source| source.connection.as_str());
+
+    button(text(label.to_string()).size(11))
+        .on_press(Message::PaneEvent(
+            pane,
+            Event::ShowModal(Modal::DataSource),
+        ))
+        .style(move |theme, status| style::button::modifier(theme, status, !is_open))
+        .padding([2, 6])
+        .into()
+}
+
+/// The price an MT5 chart is drawn from, shown next to its ticker
+fn price_basis_badge<'a>(
+    pane: pane_grid::Pane,
+    price_basis: Option<PriceBasis>,
+    is_open: bool,
+) -> Element<'a, Message> {
+    let label = price_basis.map_or("As sent".to_string(), |basis| basis.to_string());
+
+    button(text(label).size(11))
+        .on_press(Message::PaneEvent(
+            pane,
+            Event::ShowModal(Modal::PriceBasis),
+        ))
+        .style(move |theme, status| style::button::modifier(theme, status, !is_open))
+        .padding([2, 6])
+        .into()
+}
+
+fn price_basis_modal<'a>(
+    pane: pane_grid::Pane,
+    selected: Option<PriceBasis>,
+) -> Element<'a, Message> {
+    let option = |label: String, price_basis: Option<PriceBasis>| {
+        let is_selected = selected == price_basis;
+        button(text(label).size(13))
+            .width(Length::Fill)
+            .on_press(Message::PaneEvent(
+                pane,
+                Event::PriceBasisSelected(price_basis),
+            ))
+            .style(move |theme, status| style::button::menu_body(theme, status, is_selected))
+    };
+
+    let list = PriceBasis::ALL.into_iter().fold(
+        column![option("As the feed sends it".to_string(), None)].spacing(4),
+        |list, basis| list.push(option(basis.to_string(), Some(basis))),
+    );
+
+    container(column![text("Price basis").size(13), list].spacing(8))
+        .max_width(200)
+        .padding(16)
+        .style(style::chart_modal)
+        .into()
+}
+
+/// Reminds that the ticker is bucketed by a tick size the user set, not the broker's
+fn tick_override_badge<'a>(tick_size: f32) -> Element<'a, Message> {
+    container(text(format!("Tick {tick_size}")).size(11))
+        .style(|theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                text_color: Some(palette.warning.base.color),
+                border: iced::Border {
+                    width: 1.0,
+                    color: palette.warning.weak.color,
+                    radius: 4.0.into(),
+                },
+                ..Default::default()
+            }
+        })
+        .padding([2, 6])
+        .into()
+}
+
+/// Coarser timeframe a zoomed out chart shows in place of its own
+fn resolution_badge<'a>(shown: Timeframe) -> Element<'a, Message> {
+    container(text(format!("Showing {shown}")).size(11))
+        .style(|theme: &Theme| {
+            let palette = theme.extended_palette();
+            container::Style {
+                text_color: Some(palette.background.weak.text),
+                border: iced::Border {
+                    width: 1.0,
+                    color: palette.background.strong.color,
+                    radius: 4.0.into(),
+                },
+                ..Default::default()
+            }
+        })
+        .padding([2, 6])
+        .into()
+}
+
+/// Outage of the MT5 proxy a pane streams from, with controls over its reconnects
+fn reconnect_banner<'a>(pane: pane_grid::Pane, retry: Retry) -> Element<'a, Message> {
+    let control = |label: &'a str, event: Event| {
+        button(text(label).size(11))
+            .padding(padding::left(6).right(6))
+            .style(|theme, status| style::button::transparent(theme, status, false))
+            .on_press(Message::PaneEvent(pane, event))
+    };
+
+    let attempts = match retry.failures {
+        1 => "1 failed attempt".to_string(),
+        n => format!("{n} failed attempts"),
+    };
+
+    let (status, controls) = if retry.stopped {
+        (
+            format!(
+                "Disconnected ({}) — stopped retrying after {attempts}",
+                retry.cause
+            ),
+            row![control("Reconnect", Event::ReconnectNow)],
+        )
+    } else {
+        let remaining = retry.remaining().as_secs_f32().ceil();
+        let next = if remaining > 0.0 {
+            format!("retrying in {remaining:.0}s")
+        } else {
+            "reconnecting...".to_string()
+        };
+
+        (
+            format!("Disconnected ({}), {attempts} — {next}", retry.cause),
+            row![
+                control("Retry now", Event::ReconnectNow),
+                control("Stop retrying", Event::StopReconnecting),
+            ],
+        )
+    };
+
+    row![
+        text(status).size(11),
+        iced::widget::space::horizontal(),
+        controls.spacing(4),
+    ]
+    .align_y(Alignment::Center)
+    .into()
+}
+
+fn source_modal<'a>(pane: pane_grid::Pane, pinned: Option<&DataSource>) -> Element<'a, Message> {
+    let option = |label: String, source: Option<DataSource>| {
+        let is_selected = pinned == source.as_ref();
+        button(text(label).size(13))
+            .width(Length::Fill)
+            .on_press(Message::PaneEvent(pane, Event::SourceSelected(source)))
+            .style(move |theme, status| style::button::menu_body(theme, status, is_selected))
+    };
+
+    let mut names = exchange::adapter::metatrader5::connection_names();
+    // A pinned connection that isn't set up can still be picked again
+    if let Some(pinned) = pinned
+        && !names.contains(&pinned.connection)
+    {
+        names.push(pinned.connection.clone());
+    }
+
+    let list = names.into_iter().fold(
+        column![option("Active connection".to_string(), None)].spacing(4),
+        |list, name| {
+            let source = DataSource {
+                exchange: exchange::adapter::Exchange::MetaTrader5,
+                connection: name.clone(),
+            };
+            list.push(option(name, Some(source)))
+        },
+    );
+
+    container(column![text("Data source").size(13), list].spacing(8))
+        .max_width(240)
+        .padding(16)
+        .style(style::chart_modal)
+        .into()
+}
+
 fn link_group_modal<'a>(
     pane: pane_grid::Pane,
     selected_group: Option<LinkGroup>,
+    sync_timeframe: bool,
 ) -> Element<'a, Message> {
     let mut grid = column![].spacing(4);
     let rows = LinkGroup::ALL.chunks(3);
@@ -1988,7 +3525,9 @@ fn link_group_modal<'a>(
 
         for &group in row_groups {
             let is_selected = selected_group == Some(group);
-            let btn_content = text(group.to_string()).font(style::AZERET_MONO);
+            let btn_content = text(group.to_string())
+                .font(style::AZERET_MONO)
+                .color(style::link_group_color(group));
 
             let btn = if is_selected {
                 button_with_tooltip(
@@ -2011,6 +3550,14 @@ fn link_group_modal<'a>(
         grid = grid.push(button_row);
     }
 
+    if let Some(group) = selected_group {
+        grid = grid.push(
+            checkbox(sync_timeframe)
+                .label("Sync timeframe")
+                .on_toggle(move |enabled| Message::ToggleLinkedTimeframe(pane, group, enabled)),
+        );
+    }
+
     container(grid)
         .max_width(240)
         .padding(16)
@@ -2062,6 +3609,72 @@ fn basis_modifier<'a>(
         .into()
 }
 
+/// Bar above a kline chart while bar replay is armed or running
+fn replay_controls<'a>(
+    id: pane_grid::Pane,
+    chart: &'a KlineChart,
+    timezone: UserTimezone,
+) -> Option<Element<'a, Message>> {
+    let on_replay = move |control| Message::PaneEvent(id, Event::Replay(control));
+
+    let content: Element<_> = match (chart.state().replay_input(), chart.replay()) {
+        (chart::ReplayInput::Off, _) => return None,
+        (_, None) => text("Click the bar to replay from, the replay button cancels")
+            .size(12)
+            .into(),
+        (_, Some(cursor)) => {
+            let interval = match chart.basis() {
+                Basis::Time(timeframe) => timeframe.to_milliseconds(),
+                Basis::Tick(_) => 0,
+            };
+            let time = chart
+                .replay_time()
+                .map(|t| timezone.format_crosshair_timestamp(t as i64, interval))
+                .unwrap_or_default();
+
+            let step_btn = |label: &'static str, delta: isize| {
+                button(text(label).size(12))
+                    .on_press(on_replay(ReplayControl::Stepped(delta)))
+                    .style(|theme, status| style::button::transparent(theme, status, false))
+            };
+            let play_btn =
+                button(text(if cursor.is_playing() { "Pause" } else { "Play" }).size(12))
+                    .on_press_maybe(
+                        (!cursor.is_at_end() || cursor.is_playing())
+                            .then_some(on_replay(ReplayControl::PlayToggled)),
+                    )
+                    .style(move |theme, status| {
+                        style::button::transparent(theme, status, cursor.is_playing())
+                    });
+
+            row![
+                step_btn("<", -1),
+                play_btn,
+                step_btn(">", 1),
+                pick_list(ReplaySpeed::ALL, Some(cursor.speed()), move |speed| {
+                    on_replay(ReplayControl::SpeedSelected(speed))
+                })
+                .text_size(12),
+                text(format!("{}/{}", cursor.revealed(), cursor.len())).size(12),
+                text(time).size(12),
+                iced::widget::space::horizontal(),
+                text("\u{2190}/\u{2192} step, space play").size(11),
+            ]
+            .spacing(6)
+            .align_y(Alignment::Center)
+            .into()
+        }
+    };
+
+    Some(
+        container(content)
+            .width(Length::Fill)
+            .padding(padding::left(8).right(8).top(2).bottom(2))
+            .style(style::modal_container)
+            .into(),
+    )
+}
+
 fn by_basis_default<T>(
     basis: Option<Basis>,
     default_tf: Timeframe,
@@ -12,6 +12,8 @@ pub enum Message {
     Scrolled(f32),
     ResetScroll,
     Invalidate(Option<Instant>),
+    /// Lists a sweep of the tape print by print, or collapses it again
+    ToggleExpanded(u64),
 }
 
 pub enum Action {}
@@ -24,6 +26,8 @@ pub trait Panel: canvas::Program<Message> {
     fn invalidate(&mut self, now: Option<Instant>) -> Option<Action>;
 
     fn is_empty(&self) -> bool;
+
+    fn toggle_expanded(&mut self, _group: u64) {}
 }
 
 pub fn view<T: Panel>(panel: &'_ T, _timezone: data::UserTimezone) -> Element<'_, Message> {
@@ -51,5 +55,8 @@ pub fn update<T: Panel>(panel: &mut T, message: Message) {
         Message::Invalidate(now) => {
             panel.invalidate(now);
         }
+        Message::ToggleExpanded(group) => {
+            panel.toggle_expanded(group);
+        }
     }
 }
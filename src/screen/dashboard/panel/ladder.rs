@@ -1,9 +1,14 @@
 use super::Message;
 use crate::style;
-use data::panel::ladder::{ChaseTracker, Config, GroupedDepth, Side, TradeStore};
+use data::panel::ladder::{ChaseTracker, Config, Side, TradeStore};
 use exchange::Trade;
+use exchange::conversion::SizeDisplay;
 use exchange::util::{Price, PriceStep};
-use exchange::{TickerInfo, depth::Depth};
+use exchange::{
+    SizeUnit, TickerInfo,
+    depth::{Depth, GroupedDepthView},
+    volume_size_unit,
+};
 
 use iced::widget::canvas::{self, Path, Stroke, Text};
 use iced::{Alignment, Event, Point, Rectangle, Renderer, Size, Theme, mouse};
@@ -61,10 +66,12 @@ pub struct Ladder {
     tick_size: PriceStep,
     scroll_px: f32,
     last_exchange_ts_ms: Option<u64>,
-    orderbook: [GroupedDepth; 2],
+    book: GroupedDepthView,
+    chase: [ChaseTracker; 2],
     trades: TradeStore,
     pending_tick_size: Option<PriceStep>,
     raw_price_spread: Option<Price>,
+    sizes: SizeDisplay,
 }
 
 impl Ladder {
@@ -72,13 +79,15 @@ impl Ladder {
         Self {
             trades: TradeStore::new(),
             config: config.unwrap_or_default(),
+            sizes: SizeDisplay::new(&ticker_info, None, volume_size_unit()),
             ticker_info,
             cache: canvas::Cache::default(),
             last_tick: Instant::now(),
             tick_size: PriceStep::from_f32(tick_size),
             scroll_px: 0.0,
             last_exchange_ts_ms: None,
-            orderbook: [GroupedDepth::new(), GroupedDepth::new()],
+            book: GroupedDepthView::new(PriceStep::from_f32(tick_size)),
+            chase: [ChaseTracker::default(), ChaseTracker::default()],
             raw_price_spread: None,
             pending_tick_size: None,
         }
@@ -132,23 +141,26 @@ impl Ladder {
     }
 
     fn grouped_asks(&self) -> &BTreeMap<Price, f32> {
-        &self.orderbook[Side::Ask.idx()].orders
+        self.book.asks()
     }
 
     fn grouped_bids(&self) -> &BTreeMap<Price, f32> {
-        &self.orderbook[Side::Bid.idx()].orders
+        self.book.bids()
     }
 
     fn chase_tracker(&self, side: Side) -> &ChaseTracker {
-        &self.orderbook[side.idx()].chase
+        &self.chase[side.idx()]
     }
 
     fn chase_tracker_mut(&mut self, side: Side) -> &mut ChaseTracker {
-        &mut self.orderbook[side.idx()].chase
+        &mut self.chase[side.idx()]
     }
 
     fn best_price(&self, side: Side) -> Option<Price> {
-        self.orderbook[side.idx()].best_price(side)
+        match side {
+            Side::Bid => self.book.best_bid(),
+            Side::Ask => self.book.best_ask(),
+        }
     }
 
     pub fn min_tick_size(&self) -> f32 {
@@ -174,10 +186,9 @@ impl Ladder {
     }
 
     fn regroup_from_depth(&mut self, depth: &Depth) {
-        let step = self.tick_size;
-
-        self.orderbook[Side::Ask.idx()].regroup_from_raw(&depth.asks, Side::Ask, step);
-        self.orderbook[Side::Bid.idx()].regroup_from_raw(&depth.bids, Side::Bid, step);
+        self.book.set_step(self.tick_size);
+        self.book.set_tiers(self.ticker_info.tick_tiers);
+        self.book.update(depth);
     }
 
     pub fn invalidate(&mut self, now: Option<Instant>) -> Option<super::Action> {
@@ -197,8 +208,17 @@ impl Ladder {
         price.to_string(precision)
     }
 
-    fn format_quantity(&self, qty: f32) -> String {
-        data::util::abbr_large_numbers(qty)
+    /// Shows sizes in `unit`, the app wide one while `None`
+    pub fn set_size_unit(&mut self, unit: Option<SizeUnit>) {
+        let sizes = SizeDisplay::new(&self.ticker_info, unit, volume_size_unit());
+        if sizes != self.sizes {
+            self.sizes = sizes;
+            self.cache.clear();
+        }
+    }
+
+    fn format_quantity(&self, qty: f32, price: Price) -> String {
+        data::util::abbr_large_numbers(self.sizes.qty(qty, price.to_f32()))
     }
 }
 
@@ -500,7 +520,12 @@ impl Ladder {
         let price_width = price_px.min(usable_width);
 
         let rest = (usable_width - price_width).max(0.0);
-        let rest_ratio = ORDER_QTY_COLS_WIDTH + TRADE_QTY_COLS_WIDTH; // 0.80
+        let trade_cols_width = if self.config.show_trades {
+            TRADE_QTY_COLS_WIDTH
+        } else {
+            0.0
+        };
+        let rest_ratio = ORDER_QTY_COLS_WIDTH + trade_cols_width;
 
         let order_share = if rest_ratio > 0.0 {
             (ORDER_QTY_COLS_WIDTH / rest_ratio) * rest
@@ -508,7 +533,7 @@ impl Ladder {
             0.0
         };
         let trade_share = if rest_ratio > 0.0 {
-            (TRADE_QTY_COLS_WIDTH / rest_ratio) * rest
+            (trade_cols_width / rest_ratio) * rest
         } else {
             0.0
         };
@@ -577,7 +602,7 @@ impl Ladder {
                 true,
                 0.20,
             );
-            let qty_txt = self.format_quantity(order_qty);
+            let qty_txt = self.format_quantity(order_qty, price);
             let x_text = cols.bid_order.0 + 6.0;
             Self::draw_cell_text(frame, &qty_txt, x_text, y, text_color, Alignment::Start);
         } else {
@@ -592,11 +617,52 @@ impl Ladder {
                 false,
                 0.20,
             );
-            let qty_txt = self.format_quantity(order_qty);
+            let qty_txt = self.format_quantity(order_qty, price);
             let x_text = cols.ask_order.1 - 6.0;
             Self::draw_cell_text(frame, &qty_txt, x_text, y, text_color, Alignment::End);
         }
 
+        if self.config.show_trades {
+            self.draw_trade_cells(
+                frame,
+                y,
+                price,
+                trade_buy_qty,
+                trade_sell_qty,
+                max_trade_qty,
+                trade_buy_color,
+                trade_sell_color,
+                text_color,
+                cols,
+            );
+        }
+
+        // Price
+        let price_text = self.format_price(price);
+        let price_x_center = (cols.price.0 + cols.price.1) * 0.5;
+        Self::draw_cell_text(
+            frame,
+            &price_text,
+            price_x_center,
+            y,
+            side_color,
+            Alignment::Center,
+        );
+    }
+
+    fn draw_trade_cells(
+        &self,
+        frame: &mut iced::widget::canvas::Frame,
+        y: f32,
+        price: Price,
+        trade_buy_qty: f32,
+        trade_sell_qty: f32,
+        max_trade_qty: f32,
+        trade_buy_color: iced::Color,
+        trade_sell_color: iced::Color,
+        text_color: iced::Color,
+        cols: &ColumnRanges,
+    ) {
         // Sell trades (right-to-left)
         Self::fill_bar(
             frame,
@@ -610,7 +676,7 @@ impl Ladder {
             0.30,
         );
         let sell_txt = if trade_sell_qty > 0.0 {
-            self.format_quantity(trade_sell_qty)
+            self.format_quantity(trade_sell_qty, price)
         } else {
             "".into()
         };
@@ -636,7 +702,7 @@ impl Ladder {
             0.30,
         );
         let buy_txt = if trade_buy_qty > 0.0 {
-            self.format_quantity(trade_buy_qty)
+            self.format_quantity(trade_buy_qty, price)
         } else {
             "".into()
         };
@@ -648,18 +714,6 @@ impl Ladder {
             text_color,
             Alignment::Start,
         );
-
-        // Price
-        let price_text = self.format_price(price);
-        let price_x_center = (cols.price.0 + cols.price.1) * 0.5;
-        Self::draw_cell_text(
-            frame,
-            &price_text,
-            price_x_center,
-            y,
-            side_color,
-            Alignment::Center,
-        );
     }
 
     fn fill_bar(
@@ -794,6 +848,8 @@ impl Ladder {
         let rows_needed = (bounds.height / ROW_HEIGHT).ceil() as i32 + 1;
         let idx_bottom = idx_top + rows_needed;
 
+        let levels = i32::from(self.config.levels_per_side);
+
         for idx in idx_top..=idx_bottom {
             if idx == 0 {
                 let top_y_screen = mid_screen_y + PriceGrid::top_y(0) - scroll;
@@ -816,6 +872,10 @@ impl Ladder {
                 continue;
             }
 
+            if levels > 0 && idx.abs() > levels {
+                continue;
+            }
+
             let Some(price) = grid.index_to_price(idx) else {
                 continue;
             };
@@ -911,3 +971,99 @@ impl PriceGrid {
         (idx as f32) * ROW_HEIGHT - ROW_HEIGHT * 0.5
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use exchange::Ticker;
+    use exchange::adapter::Exchange;
+
+    /// A ladder around 100.0 with `depth` levels of 0.1 on each side
+    fn ladder(config: Config, depth: usize) -> Ladder {
+        let ticker_info = TickerInfo::new(
+            Ticker::new("BTCUSDT", Exchange::BinanceLinear),
+            0.1,
+            0.001,
+            None,
+        );
+        let mut ladder = Ladder::new(Some(config), ticker_info, 0.1);
+
+        let level = |steps: i64| Price::from_f32(100.0).add_steps(steps, ladder.tick_size);
+        let depth = depth as i64;
+        let depth = Depth {
+            bids: (0..depth).map(|i| (level(-i), 1.0)).collect(),
+            asks: (1..=depth).map(|i| (level(i), 1.0)).collect(),
+            ..Depth::default()
+        };
+        ladder.insert_buffers(0, &depth, &[]);
+        ladder
+    }
+
+    /// Bid and ask rows drawn in a pane `height` high
+    fn sides(ladder: &Ladder, height: f32) -> (usize, usize) {
+        let grid = ladder.build_price_grid().expect("the book has levels");
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(300.0, height));
+        let (rows, _) = ladder.visible_rows(bounds, &grid);
+
+        rows.iter().fold((0, 0), |(bids, asks), row| match row.row {
+            DomRow::Bid { .. } => (bids + 1, asks),
+            DomRow::Ask { .. } => (bids, asks + 1),
+            DomRow::Spread | DomRow::CenterDivider => (bids, asks),
+        })
+    }
+
+    #[test]
+    fn levels_per_side_cuts_off_the_book() {
+        let height = ROW_HEIGHT * 41.0;
+
+        let capped = ladder(
+            Config {
+                levels_per_side: 5,
+                ..Config::default()
+            },
+            50,
+        );
+        assert_eq!(sides(&capped, height), (5, 5));
+
+        // Unset, the rows fill the pane, about half of it on each side
+        let (bids, asks) = sides(&ladder(Config::default(), 50), height);
+        assert!(bids >= 20 && asks >= 20, "{bids} bids, {asks} asks");
+
+        // More levels than fit are cut by the pane instead
+        let roomy = ladder(
+            Config {
+                levels_per_side: 100,
+                ..Config::default()
+            },
+            50,
+        );
+        assert_eq!(sides(&roomy, height), (bids, asks));
+    }
+
+    #[test]
+    fn hidden_trades_hand_their_width_to_the_order_columns() {
+        let width = |range: (f32, f32)| range.1 - range.0;
+        let (total, price_px) = (400.0, 60.0);
+        let rest = total - COL_PADDING * Ladder::NUMBER_OF_COLUMN_GAPS - price_px;
+
+        let with_trades = ladder(Config::default(), 1).column_ranges(total, price_px);
+        assert_eq!(width(with_trades.price), price_px);
+        assert_eq!(width(with_trades.bid_order), width(with_trades.ask_order));
+        assert_eq!(width(with_trades.sell), width(with_trades.buy));
+        assert!((width(with_trades.bid_order) - rest * 0.375).abs() < 1e-3);
+        assert!((width(with_trades.sell) - rest * 0.125).abs() < 1e-3);
+
+        let without = ladder(
+            Config {
+                show_trades: false,
+                ..Config::default()
+            },
+            1,
+        )
+        .column_ranges(total, price_px);
+        assert_eq!(width(without.price), price_px);
+        assert_eq!((width(without.sell), width(without.buy)), (0.0, 0.0));
+        assert!((width(without.bid_order) - rest * 0.5).abs() < 1e-3);
+        assert!((width(without.ask_order) - rest * 0.5).abs() < 1e-3);
+    }
+}
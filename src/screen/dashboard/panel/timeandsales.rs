@@ -1,13 +1,17 @@
 use super::Message;
 use crate::style;
+use data::aggr::sweep::SweepAggregator;
 use data::config::theme::{darken, lighten};
 pub use data::panel::timeandsales::Config;
 use data::panel::timeandsales::{HistAgg, StackedBar, StackedBarRatio, TradeDisplay, TradeEntry};
-use exchange::{TickerInfo, Trade, volume_size_unit};
+use exchange::conversion::SizeDisplay;
+use exchange::{SizeUnit, TickerInfo, Trade, volume_size_unit};
 
 use iced::widget::canvas::{self, Text};
 use iced::{Alignment, Event, Point, Rectangle, Renderer, Size, Theme, mouse};
+use rustc_hash::FxHashSet;
 use std::collections::VecDeque;
+use std::ops::Range;
 use std::time::Instant;
 
 const TEXT_SIZE: iced::Pixels = iced::Pixels(11.0);
@@ -15,18 +19,22 @@ const METRICS_HEIGHT_COMPACT: f32 = 8.0;
 const METRICS_HEIGHT_FULL: f32 = 18.0;
 const TRADE_ROW_HEIGHT: f32 = 14.0;
 
+/// One line of the tape
+#[derive(Debug, Clone)]
+enum Row {
+    /// A print, `in_sweep` for the prints listed under an expanded sweep
+    Trade { index: usize, in_sweep: bool },
+    /// Prints `range` of `recent_trades` collapsed into one line
+    Sweep { range: Range<usize>, expanded: bool },
+}
+
 impl super::Panel for TimeAndSales {
     fn scroll(&mut self, delta: f32) {
         self.scroll_offset -= delta;
 
-        let stacked_bar_h = self.stacked_bar_height();
-        let total_content_height =
-            (self.recent_trades.len() as f32 * TRADE_ROW_HEIGHT) + stacked_bar_h;
-        let max_scroll_offset = (total_content_height - TRADE_ROW_HEIGHT).max(0.0);
-
-        self.scroll_offset = self.scroll_offset.clamp(0.0, max_scroll_offset);
+        self.clamp_scroll();
 
-        if self.scroll_offset > stacked_bar_h + TRADE_ROW_HEIGHT {
+        if self.scroll_offset > self.stacked_bar_height() + TRADE_ROW_HEIGHT {
             self.is_paused = true;
         } else if self.is_paused {
             self.is_paused = false;
@@ -67,6 +75,14 @@ impl super::Panel for TimeAndSales {
     fn is_empty(&self) -> bool {
         self.recent_trades.is_empty() && self.paused_trades_buffer.is_empty()
     }
+
+    fn toggle_expanded(&mut self, sweep: u64) {
+        if !self.expanded.remove(&sweep) {
+            self.expanded.insert(sweep);
+        }
+        self.clamp_scroll();
+        self.invalidate(Some(Instant::now()));
+    }
 }
 
 pub struct TimeAndSales {
@@ -80,27 +96,53 @@ pub struct TimeAndSales {
     cache: canvas::Cache,
     last_tick: Instant,
     scroll_offset: f32,
+    /// Groups incoming prints into sweeps while `config.sweep_window_ms` is set
+    sweeps: Option<SweepAggregator>,
+    /// Sweep of the latest print
+    sweep_id: u64,
+    /// Sweeps listed print by print
+    expanded: FxHashSet<u64>,
+    /// Unit the pane shows sizes in, `None` for the app wide one
+    size_unit: Option<SizeUnit>,
 }
 
 impl TimeAndSales {
     pub fn new(config: Option<Config>, ticker_info: TickerInfo) -> Self {
+        let config = config.unwrap_or_default();
+
         Self {
             recent_trades: VecDeque::new(),
             paused_trades_buffer: VecDeque::new(),
             hist_agg: HistAgg::default(),
             is_paused: false,
-            config: config.unwrap_or_default(),
+            sweeps: config.sweep_window_ms.map(SweepAggregator::new),
+            config,
             max_filtered_qty: 0.0,
             ticker_info,
             cache: canvas::Cache::default(),
             last_tick: Instant::now(),
             scroll_offset: 0.0,
+            sweep_id: 0,
+            expanded: FxHashSet::default(),
+            size_unit: None,
+        }
+    }
+
+    /// Shows sizes in `unit`, `None` for the app wide unit they're streamed in
+    pub fn set_size_unit(&mut self, unit: Option<SizeUnit>) {
+        if self.size_unit != unit {
+            self.size_unit = unit;
+            self.cache.clear();
         }
     }
 
     pub fn insert_buffer(&mut self, trades_buffer: &[Trade]) {
         let size_filter = self.config.trade_size_filter;
 
+        if self.sweeps.as_ref().map(SweepAggregator::window_ms) != self.config.sweep_window_ms {
+            self.sweeps = self.config.sweep_window_ms.map(SweepAggregator::new);
+        }
+
         let target_trades = if self.is_paused {
             &mut self.paused_trades_buffer
         } else {
@@ -134,9 +176,22 @@ impl TimeAndSales {
                     self.max_filtered_qty = self.max_filtered_qty.max(trade_display.qty);
                 }
 
+                let continues_sweep = self.sweeps.as_mut().is_some_and(|sweeps| {
+                    let window_ms = sweeps.window_ms();
+                    let continues = sweeps
+                        .open()
+                        .is_some_and(|open| open.extends(trade, window_ms));
+                    sweeps.push(trade);
+                    continues
+                });
+                if !continues_sweep {
+                    self.sweep_id += 1;
+                }
+
                 target_trades.push_back(TradeEntry {
                     ts_ms: trade_time_ms,
                     display: trade_display,
+                    sweep: self.sweep_id,
                 });
 
                 if !self.is_paused
@@ -153,6 +208,20 @@ impl TimeAndSales {
         self.prune_paused_by_time(None);
     }
 
+    /// Prints on the tape, oldest first, including those held back while scrolled
+    #[cfg(test)]
+    pub fn trades(&self) -> impl Iterator<Item = &TradeEntry> {
+        self.recent_trades
+            .iter()
+            .chain(self.paused_trades_buffer.iter())
+    }
+
+    /// Buy and sell totals of the stacked bar, with the buy ratio
+    #[cfg(test)]
+    pub fn stats(&self, ratio: StackedBarRatio) -> Option<(f64, f64, f32)> {
+        self.hist_agg.values_for(ratio)
+    }
+
     pub fn last_update(&self) -> Instant {
         self.last_tick
     }
@@ -182,6 +251,82 @@ impl TimeAndSales {
         self.stacked_bar_height().max(METRICS_HEIGHT_COMPACT) + TRADE_ROW_HEIGHT
     }
 
+    /// Lines of the tape, newest first. Prints of one sweep share a line unless it's expanded.
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::with_capacity(self.recent_trades.len());
+        let mut end = self.recent_trades.len();
+
+        while end > 0 {
+            let sweep = self.recent_trades[end - 1].sweep;
+            let mut start = end - 1;
+            if self.config.sweep_window_ms.is_some() {
+                while start > 0 && self.recent_trades[start - 1].sweep == sweep {
+                    start -= 1;
+                }
+            }
+
+            if end - start == 1 {
+                rows.push(Row::Trade {
+                    index: start,
+                    in_sweep: false,
+                });
+            } else {
+                let expanded = self.expanded.contains(&sweep);
+                rows.push(Row::Sweep {
+                    range: start..end,
+                    expanded,
+                });
+                if expanded {
+                    rows.extend((start..end).rev().map(|index| Row::Trade {
+                        index,
+                        in_sweep: true,
+                    }));
+                }
+            }
+            end = start;
+        }
+        rows
+    }
+
+    /// Rows that pass the size filter, a sweep counts with its total size
+    fn visible_rows(&self) -> impl Iterator<Item = Row> + '_ {
+        let market_type = self.ticker_info.market_type();
+        let size_in_quote_ccy = volume_size_unit() == exchange::SizeUnit::Quote;
+
+        self.rows().into_iter().filter(move |row| {
+            let (qty, price) = match row {
+                // Prints of an expanded sweep are listed whatever their size
+                Row::Trade { in_sweep: true, .. } => return true,
+                Row::Trade { index, .. } => {
+                    let trade = &self.recent_trades[*index].display;
+                    (trade.qty, trade.price)
+                }
+                Row::Sweep { range, .. } => self.sweep_totals(range.clone()),
+            };
+            market_type.qty_in_quote_value(qty, price, size_in_quote_ccy)
+                >= self.config.trade_size_filter
+        })
+    }
+
+    /// Total size and last price of the prints in `range`
+    fn sweep_totals(&self, range: Range<usize>) -> (f32, exchange::util::Price) {
+        let last_price = self.recent_trades[range.end - 1].display.price;
+        let qty = self
+            .recent_trades
+            .range(range)
+            .map(|entry| entry.display.qty)
+            .sum();
+        (qty, last_price)
+    }
+
+    fn clamp_scroll(&mut self) {
+        let stacked_bar_h = self.stacked_bar_height();
+        let total_content_height = (self.rows().len() as f32 * TRADE_ROW_HEIGHT) + stacked_bar_h;
+        let max_scroll_offset = (total_content_height - TRADE_ROW_HEIGHT).max(0.0);
+
+        self.scroll_offset = self.scroll_offset.clamp(0.0, max_scroll_offset);
+    }
+
     fn prune_by_time(&mut self, now_epoch_ms: Option<u64>) {
         if self.recent_trades.is_empty() {
             return;
@@ -236,11 +381,10 @@ impl TimeAndSales {
                 .map(|e| e.display.qty)
                 .fold(0.0, f32::max);
 
-            let stacked_bar_h = self.stacked_bar_height();
-            let total_content_height =
-                (self.recent_trades.len() as f32 * TRADE_ROW_HEIGHT) + stacked_bar_h;
-            let max_scroll_offset = (total_content_height - TRADE_ROW_HEIGHT).max(0.0);
-            self.scroll_offset = self.scroll_offset.clamp(0.0, max_scroll_offset);
+            if let Some(oldest) = self.recent_trades.front() {
+                self.expanded.retain(|sweep| *sweep >= oldest.sweep);
+            }
+            self.clamp_scroll();
         }
     }
 
@@ -305,9 +449,28 @@ impl canvas::Program<Message> for TimeAndSales {
                         };
 
                         if self.is_paused && paused_box.contains(cursor_position) {
-                            Some(canvas::Action::publish(Message::ResetScroll).and_capture())
-                        } else {
-                            None
+                            return Some(
+                                canvas::Action::publish(Message::ResetScroll).and_capture(),
+                            );
+                        }
+
+                        let content_y =
+                            cursor_position.y + self.scroll_offset - self.stacked_bar_height();
+                        if content_y < 0.0 {
+                            return None;
+                        }
+                        match self
+                            .visible_rows()
+                            .nth((content_y / TRADE_ROW_HEIGHT) as usize)
+                        {
+                            Some(Row::Sweep { range, .. }) => {
+                                let sweep = self.recent_trades[range.start].sweep;
+                                Some(
+                                    canvas::Action::publish(Message::ToggleExpanded(sweep))
+                                        .and_capture(),
+                                )
+                            }
+                            _ => None,
                         }
                     }
                     _ => None,
@@ -342,11 +505,15 @@ impl canvas::Program<Message> for TimeAndSales {
         bounds: Rectangle,
         cursor: mouse::Cursor,
     ) -> Vec<canvas::Geometry> {
-        let market_type = self.ticker_info.market_type();
-
         let palette = theme.extended_palette();
         let is_scroll_paused = self.is_paused;
         let stacked_bar_h = self.stacked_bar_height();
+        let sizes = SizeDisplay::new(&self.ticker_info, self.size_unit, volume_size_unit());
+        // Totals of the stacked bar are valued at the latest print
+        let last_price = self
+            .recent_trades
+            .back()
+            .map_or(0.0, |trade| trade.display.price.to_f32());
 
         let content = self.cache.draw(renderer, bounds.size(), |frame| {
             let content_top_y = -self.scroll_offset;
@@ -395,7 +562,9 @@ impl canvas::Program<Message> for TimeAndSales {
                         let buy_text_content = match ratio_kind {
                             StackedBarRatio::Count => format!("{}", buy_val as i64),
                             StackedBarRatio::AverageSize | StackedBarRatio::Volume => {
-                                data::util::abbr_large_numbers(buy_val as f32)
+                                data::util::abbr_large_numbers(
+                                    sizes.qty(buy_val as f32, last_price),
+                                )
                             }
                         };
                         let buy_text = Text {
@@ -416,7 +585,9 @@ impl canvas::Program<Message> for TimeAndSales {
                         let sell_text_content = match ratio_kind {
                             StackedBarRatio::Count => format!("{}", sell_val as i64),
                             StackedBarRatio::AverageSize | StackedBarRatio::Volume => {
-                                data::util::abbr_large_numbers(sell_val as f32)
+                                data::util::abbr_large_numbers(
+                                    sizes.qty(sell_val as f32, last_price),
+                                )
                             }
                         };
                         let sell_text = Text {
@@ -445,22 +616,7 @@ impl canvas::Program<Message> for TimeAndSales {
             let start_index = (row_scroll_offset / row_height).floor() as usize;
             let visible_rows = (bounds.height / row_height).ceil() as usize;
 
-            let size_in_quote_ccy = volume_size_unit() == exchange::SizeUnit::Quote;
-
-            let trades_to_draw = self
-                .recent_trades
-                .iter()
-                .filter(|t| {
-                    let trade_size = market_type.qty_in_quote_value(
-                        t.display.qty,
-                        t.display.price,
-                        size_in_quote_ccy,
-                    );
-                    trade_size >= self.config.trade_size_filter
-                })
-                .rev()
-                .skip(start_index)
-                .take(visible_rows + 2);
+            let rows_to_draw = self.visible_rows().skip(start_index).take(visible_rows + 2);
 
             let create_text =
                 |content: String, position: Point, align_x: Alignment, color: iced::Color| Text {
@@ -473,8 +629,7 @@ impl canvas::Program<Message> for TimeAndSales {
                     ..Default::default()
                 };
 
-            for (i, entry) in trades_to_draw.enumerate() {
-                let trade = &entry.display;
+            for (i, row) in rows_to_draw.enumerate() {
                 let y_position =
                     content_top_y + stacked_bar_h + ((start_index + i) as f32 * row_height);
 
@@ -482,17 +637,35 @@ impl canvas::Program<Message> for TimeAndSales {
                     continue;
                 }
 
+                // A sweep is drawn as its last print with the total size
+                let (trade, qty, in_sweep, sweep) = match &row {
+                    Row::Trade { index, in_sweep } => {
+                        let trade = &self.recent_trades[*index].display;
+                        (trade, trade.qty, *in_sweep, None)
+                    }
+                    Row::Sweep { range, expanded } => (
+                        &self.recent_trades[range.end - 1].display,
+                        self.sweep_totals(range.clone()).0,
+                        false,
+                        Some((range.len(), *expanded)),
+                    ),
+                };
+
                 let bg_color = if trade.is_sell {
                     palette.danger.weak.color
                 } else {
                     palette.success.weak.color
                 };
 
-                let bg_color_alpha = if self.max_filtered_qty > 0.0 {
-                    (trade.qty / self.max_filtered_qty).clamp(0.02, 1.0)
+                let mut bg_color_alpha = if self.max_filtered_qty > 0.0 {
+                    (qty / self.max_filtered_qty).clamp(0.02, 1.0)
                 } else {
                     0.02
                 };
+                // Prints under an expanded sweep stay in its shade
+                if in_sweep {
+                    bg_color_alpha *= 0.5;
+                }
 
                 let mut text_color = if palette.is_dark {
                     lighten(bg_color, bg_color_alpha.max(0.1))
@@ -542,7 +715,7 @@ impl canvas::Program<Message> for TimeAndSales {
                 frame.fill_text(trade_price);
 
                 let trade_qty = create_text(
-                    data::util::abbr_large_numbers(trade.qty),
+                    data::util::abbr_large_numbers(sizes.qty(qty, trade.price.to_f32())),
                     Point {
                         x: row_width * 0.9,
                         y: y_position,
@@ -551,6 +724,30 @@ impl canvas::Program<Message> for TimeAndSales {
                     text_color,
                 );
                 frame.fill_text(trade_qty);
+
+                if let Some((count, expanded)) = sweep {
+                    let marker = create_text(
+                        if expanded { "-" } else { "+" }.to_string(),
+                        Point {
+                            x: 2.0,
+                            y: y_position,
+                        },
+                        Alignment::Start,
+                        text_color,
+                    );
+                    frame.fill_text(marker);
+
+                    let count = create_text(
+                        count.to_string(),
+                        Point {
+                            x: row_width - 2.0,
+                            y: y_position,
+                        },
+                        Alignment::End,
+                        text_color,
+                    );
+                    frame.fill_text(count);
+                }
             }
 
             if is_scroll_paused {
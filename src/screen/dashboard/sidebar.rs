@@ -5,12 +5,12 @@ use crate::{
     style::{Icon, icon_text},
     widget::button_with_tooltip,
 };
-use data::sidebar;
+use data::{sidebar, t};
 
 use iced::{
-    Alignment, Element, Subscription, Task,
+    Alignment, Element, Subscription, Task, Theme,
     widget::responsive,
-    widget::{column, row, space},
+    widget::{column, row, space, text},
 };
 use rustc_hash::FxHashMap;
 
@@ -18,6 +18,7 @@ use rustc_hash::FxHashMap;
 pub enum Message {
     ToggleSidebarMenu(Option<sidebar::Menu>),
     SetSidebarPosition(sidebar::Position),
+    ToggleConnectionBar,
     TickersTable(super::tickers_table::Message),
 }
 
@@ -32,6 +33,7 @@ pub enum Action {
         Option<data::layout::pane::ContentKind>,
     ),
     ErrorOccurred(data::InternalError),
+    OverrideTickSize(exchange::TickerInfo),
 }
 
 impl Sidebar {
@@ -60,6 +62,9 @@ impl Sidebar {
             Message::SetSidebarPosition(position) => {
                 self.state.position = position;
             }
+            Message::ToggleConnectionBar => {
+                self.state.connection_bar = !self.state.connection_bar;
+            }
             Message::TickersTable(msg) => {
                 let action = self.tickers_table.update(msg);
 
@@ -79,6 +84,9 @@ impl Sidebar {
                     Some(tickers_table::Action::FocusWidget(id)) => {
                         return (iced::widget::operation::focus(id), None);
                     }
+                    Some(tickers_table::Action::OverrideTickSize(ticker_info)) => {
+                        return (Task::none(), Some(Action::OverrideTickSize(ticker_info)));
+                    }
                     None => {}
                 }
             }
@@ -87,7 +95,11 @@ impl Sidebar {
         (Task::none(), None)
     }
 
-    pub fn view(&self, audio_volume: Option<f32>) -> Element<'_, Message> {
+    pub fn view(
+        &self,
+        audio_volume: Option<f32>,
+        unread_notifications: usize,
+    ) -> Element<'_, Message> {
         let state = &self.state;
 
         let tooltip_position = if state.position == sidebar::Position::Left {
@@ -98,7 +110,12 @@ impl Sidebar {
 
         let is_table_open = self.tickers_table.is_shown;
 
-        let nav_buttons = self.nav_buttons(is_table_open, audio_volume, tooltip_position);
+        let nav_buttons = self.nav_buttons(
+            is_table_open,
+            audio_volume,
+            unread_notifications,
+            tooltip_position,
+        );
 
         let tickers_table = if is_table_open {
             column![responsive(move |size| self
@@ -126,11 +143,13 @@ impl Sidebar {
         &self,
         is_table_open: bool,
         audio_volume: Option<f32>,
+        unread_notifications: usize,
         tooltip_position: TooltipPosition,
     ) -> iced::widget::Column<'_, Message> {
         let settings_modal_button = {
             let is_active = self.is_menu_active(sidebar::Menu::Settings)
-                || self.is_menu_active(sidebar::Menu::ThemeEditor);
+                || self.is_menu_active(sidebar::Menu::ThemeEditor)
+                || self.is_menu_active(sidebar::Menu::Logs);
 
             button_with_tooltip(
                 icon_text(Icon::Cog, 14)
@@ -204,12 +223,75 @@ impl Sidebar {
             )
         };
 
+        let custom_ws_btn = {
+            let is_active = self.is_menu_active(sidebar::Menu::CustomWsConfig);
+
+            button_with_tooltip(
+                icon_text(Icon::ExternalLink, 14)
+                    .width(24)
+                    .align_x(Alignment::Center),
+                Message::ToggleSidebarMenu(Some(sidebar::Menu::CustomWsConfig)),
+                None,
+                tooltip_position,
+                move |theme, status| crate::style::button::transparent(theme, status, is_active),
+            )
+        };
+
+        let connection_bar_btn = {
+            let is_active = self.state.connection_bar;
+
+            button_with_tooltip(
+                icon_text(Icon::ChartOutline, 14)
+                    .width(24)
+                    .align_x(Alignment::Center),
+                Message::ToggleConnectionBar,
+                Some(t!("sidebar.active_streams")),
+                tooltip_position,
+                move |theme, status| crate::style::button::transparent(theme, status, is_active),
+            )
+        };
+
+        // The icon font has no bell, the unread count stands in for it
+        let notifications_btn = {
+            let is_active = self.is_menu_active(sidebar::Menu::Notifications);
+
+            let label = match unread_notifications {
+                0 => "0".to_string(),
+                count if count > 99 => "99+".to_string(),
+                count => count.to_string(),
+            };
+
+            button_with_tooltip(
+                text(label)
+                    .size(11)
+                    .width(24)
+                    .align_x(Alignment::Center)
+                    .style(move |theme: &Theme| {
+                        let palette = theme.extended_palette();
+                        text::Style {
+                            color: Some(if unread_notifications > 0 {
+                                palette.warning.base.color
+                            } else {
+                                palette.background.strongest.color
+                            }),
+                        }
+                    }),
+                Message::ToggleSidebarMenu(Some(sidebar::Menu::Notifications)),
+                None,
+                tooltip_position,
+                move |theme, status| crate::style::button::transparent(theme, status, is_active),
+            )
+        };
+
         column![
             ticker_search_button,
             layout_modal_button,
             mt5_btn,
+            custom_ws_btn,
             audio_btn,
             space::vertical(),
+            connection_bar_btn,
+            notifications_btn,
             settings_modal_button,
         ]
         .width(32)
@@ -230,6 +312,10 @@ impl Sidebar {
         false
     }
 
+    pub fn is_connection_bar_shown(&self) -> bool {
+        self.state.connection_bar
+    }
+
     pub fn is_menu_active(&self, menu: sidebar::Menu) -> bool {
         self.state.active_menu == Some(menu)
     }
@@ -13,6 +13,7 @@ use data::{
 use exchange::{
     Ticker, TickerInfo, TickerStats,
     adapter::{Exchange, ExchangeInclusive, MarketKind, fetch_ticker_info, fetch_ticker_prices},
+    synthetic,
 };
 use iced::{
     Alignment, Element, Length, Renderer, Size, Subscription, Task, Theme,
@@ -42,7 +43,8 @@ const SORT_AND_FILTER_HEIGHT: f32 = 200.0;
 
 const COMPACT_ROW_HEIGHT: f32 = 28.0;
 
-const EXCHANGE_FILTERS: [(ExchangeInclusive, Exchange, &str); 5] = [
+/// Exchanges the table filters by, with the exchange whose logo stands for each
+pub const EXCHANGE_FILTERS: [(ExchangeInclusive, Exchange, &str); 6] = [
     (ExchangeInclusive::Bybit, Exchange::BybitLinear, "Bybit"),
     (
         ExchangeInclusive::Binance,
@@ -56,6 +58,7 @@ const EXCHANGE_FILTERS: [(ExchangeInclusive, Exchange, &str); 5] = [
     ),
     (ExchangeInclusive::Okex, Exchange::OkexLinear, "OKX"),
     (ExchangeInclusive::MetaTrader5, Exchange::MetaTrader5, "MT5"),
+    (ExchangeInclusive::CustomWs, Exchange::CustomWs, "Custom"),
 ];
 
 pub fn fetch_tickers_info() -> Task<Message> {
@@ -77,6 +80,7 @@ pub enum Action {
     ErrorOccurred(data::InternalError),
     Fetch(Task<Message>),
     FocusWidget(iced::widget::Id),
+    OverrideTickSize(TickerInfo),
 }
 
 #[derive(Debug, Clone)]
@@ -87,9 +91,11 @@ pub enum Message {
     TickerSelected(Ticker, Option<ContentKind>),
     ExpandTickerCard(Option<Ticker>),
     FavoriteTicker(Ticker),
+    OverrideTickSize(Ticker),
     Scrolled(scrollable::Viewport),
     ToggleMarketFilter(MarketKind),
     ToggleExchangeFilter(ExchangeInclusive),
+    ToggleSyntheticFilter,
     ToggleTable,
     ToggleFavorites,
     FetchForTickerStats(Option<Exchange>),
@@ -101,6 +107,7 @@ pub enum Message {
 pub struct TickersTable {
     ticker_rows: Vec<TickerRowData>,
     pub favorited_tickers: FxHashSet<Ticker>,
+    pub recent_tickers: Vec<Ticker>,
     display_cache: FxHashMap<Ticker, TickerDisplayData>,
     search_query: String,
     show_sort_options: bool,
@@ -112,8 +119,12 @@ pub struct TickersTable {
     selected_exchanges: FxHashSet<ExchangeInclusive>,
     selected_markets: FxHashSet<MarketKind>,
     show_favorites: bool,
+    show_synthetic: bool,
+    synthetic_tickers: FxHashSet<Ticker>,
     row_index: FxHashMap<Ticker, usize>,
     pending_stats_batches: usize,
+    /// MT5 symbols listed by more than one connection, without their connection
+    shared_symbols: FxHashSet<Ticker>,
 }
 
 impl TickersTable {
@@ -122,26 +133,30 @@ impl TickersTable {
     }
 
     pub fn new_with_settings(settings: &Settings) -> (Self, Task<Message>) {
-        (
-            Self {
-                ticker_rows: Vec::new(),
-                display_cache: FxHashMap::default(),
-                favorited_tickers: settings.favorited_tickers.iter().cloned().collect(),
-                search_query: String::new(),
-                show_sort_options: false,
-                selected_sort_option: settings.selected_sort_option,
-                expand_ticker_card: None,
-                scroll_offset: AbsoluteOffset::default(),
-                is_shown: false,
-                tickers_info: FxHashMap::default(),
-                selected_exchanges: settings.selected_exchanges.iter().cloned().collect(),
-                selected_markets: settings.selected_markets.iter().cloned().collect(),
-                show_favorites: settings.show_favorites,
-                row_index: FxHashMap::default(),
-                pending_stats_batches: 0,
-            },
-            fetch_tickers_info(),
-        )
+        let mut table = Self {
+            ticker_rows: Vec::new(),
+            display_cache: FxHashMap::default(),
+            favorited_tickers: settings.favorited_tickers.iter().cloned().collect(),
+            recent_tickers: settings.recent_tickers.clone(),
+            search_query: String::new(),
+            show_sort_options: false,
+            selected_sort_option: settings.selected_sort_option,
+            expand_ticker_card: None,
+            scroll_offset: AbsoluteOffset::default(),
+            is_shown: false,
+            tickers_info: FxHashMap::default(),
+            selected_exchanges: settings.selected_exchanges.iter().cloned().collect(),
+            selected_markets: settings.selected_markets.iter().cloned().collect(),
+            show_favorites: settings.show_favorites,
+            show_synthetic: settings.show_synthetic,
+            synthetic_tickers: FxHashSet::default(),
+            row_index: FxHashMap::default(),
+            pending_stats_batches: 0,
+            shared_symbols: FxHashSet::default(),
+        };
+        table.insert_synthetic_rows();
+
+        (table, fetch_tickers_info())
     }
 
     pub fn settings(&self) -> Settings {
@@ -151,9 +166,37 @@ impl TickersTable {
             selected_sort_option: self.selected_sort_option,
             selected_exchanges: self.selected_exchanges.iter().cloned().collect(),
             selected_markets: self.selected_markets.iter().cloned().collect(),
+            show_synthetic: self.show_synthetic,
+            recent_tickers: self.recent_tickers.clone(),
         }
     }
 
+    /// Lists only the tickers of `exchanges`
+    pub fn set_exchange_filters(&mut self, exchanges: impl IntoIterator<Item = ExchangeInclusive>) {
+        self.selected_exchanges = exchanges.into_iter().collect();
+    }
+
+    pub fn exchange_filters(&self) -> impl Iterator<Item = ExchangeInclusive> + '_ {
+        self.selected_exchanges.iter().copied()
+    }
+
+    /// Moves `ticker` to the front of the recently opened list
+    pub fn push_recent(&mut self, ticker: Ticker) {
+        self.recent_tickers.retain(|t| *t != ticker);
+        self.recent_tickers.insert(0, ticker);
+        self.recent_tickers
+            .truncate(data::tickers_table::MAX_RECENT_TICKERS);
+    }
+
+    /// Daily stats of `ticker`, if its exchange reports any
+    pub fn stats(&self, ticker: &Ticker) -> Option<TickerStats> {
+        let row = &self.ticker_rows[*self.row_index.get(ticker)?];
+        row.exchange
+            .capabilities()
+            .ticker_stats
+            .then_some(row.stats)
+    }
+
     pub fn update(&mut self, message: Message) -> Option<Action> {
         match message {
             Message::UpdateSearchQuery(query) => {
@@ -171,6 +214,11 @@ impl TickersTable {
             Message::FavoriteTicker(ticker) => {
                 self.favorite_ticker(ticker);
             }
+            Message::OverrideTickSize(ticker) => {
+                if let Some(Some(ticker_info)) = self.tickers_info.get(&ticker) {
+                    return Some(Action::OverrideTickSize(*ticker_info));
+                }
+            }
             Message::Scrolled(viewport) => {
                 self.scroll_offset = viewport.absolute_offset();
             }
@@ -188,6 +236,9 @@ impl TickersTable {
                     self.selected_exchanges.insert(exch);
                 }
             }
+            Message::ToggleSyntheticFilter => {
+                self.show_synthetic = !self.show_synthetic;
+            }
             Message::ToggleFavorites => {
                 self.show_favorites = !self.show_favorites;
             }
@@ -195,6 +246,7 @@ impl TickersTable {
                 let ticker_info = self.tickers_info.get(&ticker).cloned().flatten();
 
                 if let Some(ticker_info) = ticker_info {
+                    self.push_recent(ticker);
                     return Some(Action::TickerSelected(ticker_info, content));
                 } else {
                     log::warn!(
@@ -212,7 +264,7 @@ impl TickersTable {
                         row.previous_stats = None;
                         self.display_cache.insert(
                             row.ticker,
-                            compute_display_data(&row.ticker, &row.stats, None),
+                            display_data(&row.ticker, &row.stats, None, &self.shared_symbols),
                         );
                     }
 
@@ -221,7 +273,7 @@ impl TickersTable {
             }
             Message::FetchForTickerStats(exchange) => {
                 let task = if let Some(exchange) = exchange {
-                    if exchange == Exchange::MetaTrader5 {
+                    if !exchange.capabilities().ticker_stats {
                         self.pending_stats_batches = 0;
                         Task::none()
                     } else {
@@ -242,7 +294,7 @@ impl TickersTable {
                     let fetch_tasks = exchanges
                         .into_iter()
                         .map(|exchange| {
-                            if exchange == Exchange::MetaTrader5 {
+                            if !exchange.capabilities().ticker_stats {
                                 Task::none()
                             } else {
                                 Task::perform(fetch_ticker_prices(exchange), move |result| {
@@ -277,7 +329,7 @@ impl TickersTable {
             Message::UpdateTickersInfo(exchange, info) => {
                 self.update_ticker_info(exchange, info);
 
-                let task = if exchange == Exchange::MetaTrader5 {
+                let task = if !exchange.capabilities().ticker_stats {
                     Task::none()
                 } else {
                     Task::perform(fetch_ticker_prices(exchange), move |result| match result {
@@ -530,9 +582,15 @@ impl TickersTable {
         if let Some(selected_ticker) = &self.expand_ticker_card {
             let selected_exchange = selected_ticker.exchange;
             if ticker == selected_ticker && exchange == selected_exchange {
-                container(expanded_ticker_card(ticker, display_data, is_fav))
-                    .style(style::ticker_card)
-                    .into()
+                let ticker_info = self.tickers_info.get(ticker).copied().flatten();
+                container(expanded_ticker_card(
+                    ticker,
+                    ticker_info,
+                    display_data,
+                    is_fav,
+                ))
+                .style(style::ticker_card)
+                .into()
             } else {
                 ticker_card(ticker, display_data)
             }
@@ -582,36 +640,116 @@ impl TickersTable {
             .into()
     }
 
+    fn synthetic_filter_btn(&self) -> Element<'_, Message> {
+        let selected = self.show_synthetic;
+
+        let content = if selected {
+            row![
+                text("Synthetic"),
+                space::horizontal(),
+                container(icon_text(Icon::Checkmark, 12)),
+            ]
+        } else {
+            row![text("Synthetic")]
+        };
+
+        let btn = button(content.spacing(4).width(Length::Fill))
+            .style(move |theme, status| style::button::modifier(theme, status, selected))
+            .on_press(Message::ToggleSyntheticFilter)
+            .width(Length::Fill);
+
+        container(btn)
+            .padding(2)
+            .style(style::dragger_row_container)
+            .into()
+    }
+
+    /// Synthetic tickers have no stats endpoint, so their rows are created up front
+    fn insert_synthetic_rows(&mut self) {
+        for spec in synthetic::all() {
+            let info = spec.ticker_info();
+
+            self.tickers_info.insert(info.ticker, Some(info));
+            self.synthetic_tickers.insert(info.ticker);
+            self.insert_placeholder_row(info.exchange(), info.ticker);
+        }
+    }
+
+    fn insert_placeholder_row(&mut self, exchange: Exchange, ticker: Ticker) {
+        if self.row_index.contains_key(&ticker) {
+            return;
+        }
+
+        let new_row = TickerRowData {
+            exchange,
+            ticker,
+            stats: TickerStats {
+                mark_price: 0.0,
+                daily_price_chg: 0.0,
+                daily_volume: 0.0,
+            },
+            previous_stats: None,
+            is_favorited: self.favorited_tickers.contains(&ticker),
+        };
+        self.ticker_rows.push(new_row);
+        let idx = self.ticker_rows.len() - 1;
+        self.row_index.insert(ticker, idx);
+
+        self.display_cache.insert(
+            ticker,
+            display_data(
+                &ticker,
+                &self.ticker_rows[idx].stats,
+                None,
+                &self.shared_symbols,
+            ),
+        );
+    }
+
     fn update_ticker_info(
         &mut self,
         exchange: Exchange,
         info: HashMap<Ticker, Option<TickerInfo>>,
     ) {
         for (ticker, ticker_info) in info.into_iter() {
+            // Favorites saved before tickers told their connections apart
+            if ticker.connection().is_some()
+                && self.favorited_tickers.remove(&ticker.on_connection(None))
+            {
+                self.favorited_tickers.insert(ticker);
+            }
             self.tickers_info.insert(ticker, ticker_info);
 
-            // For MT5, we need to create ticker_rows directly since there's no
-            // fetch_ticker_prices endpoint. Other exchanges will get their rows
-            // populated via update_ticker_rows when price stats are fetched.
-            if exchange == Exchange::MetaTrader5 && !self.row_index.contains_key(&ticker) {
-                let new_row = TickerRowData {
-                    exchange,
-                    ticker,
-                    stats: TickerStats {
-                        mark_price: 0.0,
-                        daily_price_chg: 0.0,
-                        daily_volume: 0.0,
-                    },
-                    previous_stats: None,
-                    is_favorited: self.favorited_tickers.contains(&ticker),
-                };
-                self.ticker_rows.push(new_row);
-                let idx = self.ticker_rows.len() - 1;
-                self.row_index.insert(ticker, idx);
+            // Without a fetch_ticker_prices endpoint (MT5, custom feeds) rows are created
+            // directly. Other exchanges will get their rows populated via
+            // update_ticker_rows when price stats are fetched.
+            if !exchange.capabilities().ticker_stats {
+                self.insert_placeholder_row(exchange, ticker);
+            }
+        }
 
+        if exchange == Exchange::MetaTrader5 {
+            self.label_shared_symbols();
+        }
+    }
+
+    /// Tells apart the rows of MT5 symbols more than one connection lists
+    fn label_shared_symbols(&mut self) {
+        let mut listed = FxHashSet::default();
+        self.shared_symbols = self
+            .tickers_info
+            .keys()
+            .filter(|ticker| ticker.connection().is_some())
+            .map(|ticker| ticker.on_connection(None))
+            .filter(|name| !listed.insert(*name))
+            .collect();
+
+        for row in &mut self.ticker_rows {
+            if row.exchange == Exchange::MetaTrader5 {
+                row.is_favorited = self.favorited_tickers.contains(&row.ticker);
                 self.display_cache.insert(
-                    ticker,
-                    compute_display_data(&ticker, &self.ticker_rows[idx].stats, None),
+                    row.ticker,
+                    display_data(&row.ticker, &row.stats, None, &self.shared_symbols),
                 );
             }
         }
@@ -631,7 +769,7 @@ impl TickersTable {
 
                 self.display_cache.insert(
                     ticker,
-                    compute_display_data(&ticker, &row.stats, previous_price),
+                    display_data(&ticker, &row.stats, previous_price, &self.shared_symbols),
                 );
             } else {
                 let new_row = TickerRowData {
@@ -647,7 +785,12 @@ impl TickersTable {
 
                 self.display_cache.insert(
                     ticker,
-                    compute_display_data(&ticker, &self.ticker_rows[idx].stats, None),
+                    display_data(
+                        &ticker,
+                        &self.ticker_rows[idx].stats,
+                        None,
+                        &self.shared_symbols,
+                    ),
                 );
             }
         }
@@ -770,6 +913,9 @@ impl TickersTable {
             for (exchange_inclusive, exchange_logo, label) in EXCHANGE_FILTERS {
                 col = col.push(self.exchange_filter_btn(exchange_inclusive, exchange_logo, label));
             }
+            if !self.synthetic_tickers.is_empty() {
+                col = col.push(self.synthetic_filter_btn());
+            }
             col.spacing(4)
         };
 
@@ -1071,6 +1217,14 @@ impl TickersTable {
             self.selected_exchanges
                 .contains(&ExchangeInclusive::of(row.exchange))
         };
+        // Synthetic rows form their own group, independent of the exchange/market filters
+        let matches_filters = |row: &TickerRowData| {
+            if self.synthetic_tickers.contains(&row.ticker) {
+                self.show_synthetic
+            } else {
+                matches_market(row) && matches_exchange(row)
+            }
+        };
 
         // Collect fav_rows with search ranks
         let mut fav_rows: Vec<_> = if self.show_favorites {
@@ -1079,8 +1233,7 @@ impl TickersTable {
                 .filter(|row| {
                     row.is_favorited
                         && !excluded.is_some_and(|ex| ex.contains(&row.ticker))
-                        && matches_market(row)
-                        && matches_exchange(row)
+                        && matches_filters(row)
                 })
                 .filter_map(|row| calc_search_rank(row, search_upper).map(|rank| (row, rank)))
                 .collect()
@@ -1115,8 +1268,7 @@ impl TickersTable {
             .filter(|row| {
                 (!self.show_favorites || !row.is_favorited)
                     && !excluded.is_some_and(|ex| ex.contains(&row.ticker))
-                    && matches_market(row)
-                    && matches_exchange(row)
+                    && matches_filters(row)
             })
             .filter_map(|row| calc_search_rank(row, search_upper).map(|rank| (row, rank)))
             .collect();
@@ -1310,12 +1462,30 @@ fn ticker_card<'a>(ticker: &Ticker, display_data: &'a TickerDisplayData) -> Elem
 
 fn expanded_ticker_card<'a>(
     ticker: &Ticker,
+    ticker_info: Option<TickerInfo>,
     display_data: &'a TickerDisplayData,
     is_fav: bool,
 ) -> Element<'a, Message> {
     let (ticker_str, market) = ticker.display_symbol_and_type();
     let exchange_icon = style::exchange_icon(ticker.exchange);
 
+    let tick_size = ticker_info.map(|info| {
+        let tick = info.min_ticksize.as_f32();
+        if exchange::adapter::metatrader5::tick_override::get(ticker).is_some() {
+            text(format!("{tick} (override)")).style(|theme: &Theme| text::Style {
+                color: Some(theme.extended_palette().warning.base.color),
+            })
+        } else {
+            text(tick.to_string())
+        }
+    });
+    let override_btn =
+        (ticker.exchange == Exchange::MetaTrader5 && ticker_info.is_some()).then(|| {
+            button(text("Override tick size…").size(11))
+                .on_press(Message::OverrideTickSize(*ticker))
+                .style(move |theme, status| style::button::transparent(theme, status, false))
+        });
+
     column![
         row![
             button(icon_text(Icon::Return, 11))
@@ -1335,7 +1505,7 @@ fn expanded_ticker_card<'a>(
             text(
                 ticker_str
                     + " "
-                    + &market.to_string()
+                    + market.to_string().as_str()
                     + match market {
                         MarketKind::Spot => "",
                         MarketKind::LinearPerps | MarketKind::InversePerps => " Perp",
@@ -1361,6 +1531,14 @@ fn expanded_ticker_card<'a>(
                     text(&display_data.volume_display),
                 ],
             ]
+            .push(tick_size.map(|tick_size| {
+                row![
+                    text("Tick Size: ").size(11),
+                    Space::new().width(Length::Fill).height(Length::Shrink),
+                    tick_size,
+                ]
+            }))
+            .push(override_btn)
             .spacing(2)
         )
         .style(|theme: &Theme| {
@@ -1377,6 +1555,7 @@ fn expanded_ticker_card<'a>(
             init_content_button(ContentKind::ComparisonChart, *ticker, 180.0),
             init_content_button(ContentKind::TimeAndSales, *ticker, 160.0),
             init_content_button(ContentKind::Ladder, *ticker, 160.0),
+            init_content_button(ContentKind::TimeframeStrip, *ticker, 160.0),
         ]
         .width(Length::Fill)
         .spacing(2)
@@ -1499,9 +1678,34 @@ fn init_content_button<'a>(
         .width(Length::Fixed(width))
 }
 
+/// [`compute_display_data`], naming the connection of a symbol more than one lists,
+/// e.g. `EURUSD @ ftmo:443`
+fn display_data(
+    ticker: &Ticker,
+    stats: &TickerStats,
+    previous_price: Option<f32>,
+    shared_symbols: &FxHashSet<Ticker>,
+) -> TickerDisplayData {
+    let mut data = compute_display_data(ticker, stats, previous_price);
+
+    if let Some(source) = ticker.connection()
+        && shared_symbols.contains(&ticker.on_connection(None))
+        && let Some(name) = exchange::adapter::metatrader5::connection_name(source)
+    {
+        let name = name.strip_prefix("MT5 ").unwrap_or(&name);
+        data.display_ticker = format!("{} @ {name}", data.display_ticker);
+    }
+    if exchange::adapter::metatrader5::retired::is_retired(ticker) {
+        data.display_ticker = format!("{} (retired)", data.display_ticker);
+    }
+    data
+}
+
 fn short_card_label(ticker: &Ticker, display_data: &TickerDisplayData) -> String {
-    if display_data.display_ticker.len() >= 11 {
-        format!("{}...", &display_data.display_ticker[..9])
+    if display_data.display_ticker.chars().count() >= 11 {
+        // Connection names of shared MT5 symbols needn't be ASCII
+        let head: String = display_data.display_ticker.chars().take(9).collect();
+        format!("{head}...")
     } else {
         format!(
             "{}{}",
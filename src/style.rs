@@ -1,3 +1,4 @@
+use data::layout::pane::LinkGroup;
 use exchange::adapter::Exchange;
 
 use iced::font::{Family, Stretch, Weight};
@@ -109,9 +110,39 @@ pub fn exchange_icon(exchange: Exchange) -> Icon {
         Exchange::HyperliquidLinear | Exchange::HyperliquidSpot => Icon::HyperliquidLogo,
         Exchange::OkexLinear | Exchange::OkexInverse | Exchange::OkexSpot => Icon::OkexLogo,
         Exchange::MetaTrader5 => Icon::Link,
+        Exchange::CustomWs => Icon::ExternalLink,
     }
 }
 
+/// Fixed per-group hue so a group reads the same across themes and windows
+pub fn link_group_color(group: LinkGroup) -> Color {
+    match group {
+        LinkGroup::A => Color::from_rgb8(0xE5, 0x48, 0x4D),
+        LinkGroup::B => Color::from_rgb8(0x3E, 0x8E, 0xF7),
+        LinkGroup::C => Color::from_rgb8(0x30, 0xA4, 0x6C),
+        LinkGroup::D => Color::from_rgb8(0xF5, 0xA5, 0x24),
+        LinkGroup::E => Color::from_rgb8(0x8E, 0x4E, 0xC6),
+        LinkGroup::F => Color::from_rgb8(0x12, 0xA5, 0x94),
+        LinkGroup::G => Color::from_rgb8(0xE9, 0x3D, 0x82),
+        LinkGroup::H => Color::from_rgb8(0xA1, 0x8A, 0x72),
+        LinkGroup::I => Color::from_rgb8(0x8D, 0x8D, 0x8D),
+    }
+}
+
+/// Line color of the nth overlay on a chart, cycling through fixed hues that read on both light
+/// and dark themes
+pub fn overlay_color(index: usize) -> Color {
+    const COLORS: [Color; 6] = [
+        Color::from_rgb8(0xF5, 0xA5, 0x24),
+        Color::from_rgb8(0x3E, 0x8E, 0xF7),
+        Color::from_rgb8(0xE9, 0x3D, 0x82),
+        Color::from_rgb8(0x12, 0xA5, 0x94),
+        Color::from_rgb8(0x8E, 0x4E, 0xC6),
+        Color::from_rgb8(0xA1, 0x8A, 0x72),
+    ];
+    COLORS[index % COLORS.len()]
+}
+
 #[cfg(target_os = "macos")]
 pub fn title_text(theme: &Theme) -> iced::widget::text::Style {
     let palette = theme.extended_palette();
@@ -121,6 +152,12 @@ pub fn title_text(theme: &Theme) -> iced::widget::text::Style {
     }
 }
 
+pub fn muted_text(theme: &Theme) -> iced::widget::text::Style {
+    iced::widget::text::Style {
+        color: Some(theme.extended_palette().background.strongest.color),
+    }
+}
+
 pub fn tooltip(theme: &Theme) -> Style {
     let palette = theme.extended_palette();
 
@@ -464,6 +501,31 @@ pub fn pane_background(theme: &Theme, is_focused: bool) -> Style {
     }
 }
 
+/// Pane whose border flashes for an alert
+pub fn pane_alert(theme: &Theme) -> Style {
+    let palette = theme.extended_palette();
+
+    Style {
+        border: Border {
+            width: 2.0,
+            color: palette.danger.base.color,
+            radius: 4.0.into(),
+        },
+        ..pane_background(theme, true)
+    }
+}
+
+/// Thin strip over a pane whose market isn't trading
+pub fn market_state_banner(theme: &Theme) -> Style {
+    let palette = theme.extended_palette();
+
+    Style {
+        text_color: Some(palette.background.base.text),
+        background: Some(palette.warning.weak.color.scale_alpha(0.25).into()),
+        ..Default::default()
+    }
+}
+
 // Modals
 pub fn chart_modal(theme: &Theme) -> Style {
     let palette = theme.extended_palette();
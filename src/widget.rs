@@ -245,6 +245,7 @@ where
     let icon = if let Some(group) = link_group {
         text(group.to_string())
             .font(style::AZERET_MONO)
+            .color(style::link_group_color(group))
             .align_x(Alignment::Start)
             .align_y(Alignment::Center)
     } else {
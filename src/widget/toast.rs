@@ -12,6 +12,7 @@ use iced::{
 use iced::{Border, mouse, padding, theme, window};
 
 use crate::style;
+use data::notifications::Severity;
 
 pub const DEFAULT_TIMEOUT: u64 = 8;
 
@@ -38,6 +39,8 @@ pub struct Toast {
     title: String,
     body: String,
     status: Status,
+    /// Stays until closed instead of timing out
+    sticky: bool,
 }
 
 impl Toast {
@@ -47,16 +50,19 @@ impl Toast {
                 title: "Error".to_string(),
                 body,
                 status: Status::Danger,
+                sticky: false,
             },
             Notification::Info(body) => Self {
                 title: "Info".to_string(),
                 body,
                 status: Status::Primary,
+                sticky: false,
             },
             Notification::Warn(body) => Self {
                 title: "Warning".to_string(),
                 body,
                 status: Status::Warning,
+                sticky: false,
             },
         }
     }
@@ -66,6 +72,7 @@ impl Toast {
             title: "Error".to_string(),
             body: body.into(),
             status: Status::Danger,
+            sticky: false,
         }
     }
 
@@ -74,6 +81,27 @@ impl Toast {
             title: "Warning".to_string(),
             body: body.into(),
             status: Status::Warning,
+            sticky: false,
+        }
+    }
+
+    /// Keeps the toast up until it's closed, for problems that need the user to act
+    pub fn sticky(self) -> Self {
+        Self {
+            sticky: true,
+            ..self
+        }
+    }
+
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self.status {
+            Status::Danger => Severity::Error,
+            Status::Warning => Severity::Warning,
+            Status::Primary | Status::Secondary | Status::Success => Severity::Info,
         }
     }
 }
@@ -81,6 +109,7 @@ impl Toast {
 pub struct Manager<'a, Message> {
     content: Element<'a, Message>,
     toasts: Vec<Element<'a, Message>>,
+    sticky: Vec<bool>,
     timeout_secs: u64,
     on_close: Box<dyn Fn(usize) -> Message + 'a>,
     alignment: Alignment,
@@ -96,6 +125,7 @@ where
         alignment: Alignment,
         on_close: impl Fn(usize) -> Message + 'a,
     ) -> Self {
+        let toasts_sticky = toasts.iter().map(|toast| toast.sticky).collect();
         let toasts = toasts
             .iter()
             .enumerate()
@@ -136,6 +166,7 @@ where
             content: content.into(),
             alignment,
             toasts,
+            sticky: toasts_sticky,
             timeout_secs: DEFAULT_TIMEOUT,
             on_close: Box::new(on_close),
         }
@@ -307,6 +338,7 @@ impl<Message> Widget<Message, Theme, Renderer> for Manager<'_, Message> {
                 bounds: layout.bounds(),
                 alignment: self.alignment,
                 toasts: &mut self.toasts,
+                sticky: &self.sticky,
                 state: toasts_state,
                 instants,
                 on_close: &self.on_close,
@@ -325,6 +357,7 @@ struct Overlay<'a, 'b, Message> {
     bounds: Rectangle,
     alignment: Alignment,
     toasts: &'b mut [Element<'a, Message>],
+    sticky: &'b [bool],
     state: &'b mut [Tree],
     instants: &'b mut [Option<Instant>],
     on_close: &'b dyn Fn(usize) -> Message,
@@ -364,6 +397,9 @@ impl<Message> overlay::Overlay<Message, Theme, Renderer> for Overlay<'_, '_, Mes
                 .iter_mut()
                 .enumerate()
                 .for_each(|(index, maybe_instant)| {
+                    if self.sticky.get(index).copied().unwrap_or(false) {
+                        return;
+                    }
                     if let Some(instant) = maybe_instant.as_mut() {
                         let remaining =
                             time::seconds(self.timeout_secs).saturating_sub(instant.elapsed());
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
 use data::layout::WindowSpec;
-use iced::{Point, Size, Subscription, Task, window};
+use iced::advanced::widget::operation::{Operation, Outcome};
+use iced::{Point, Rectangle, Size, Subscription, Task, window};
 
 pub use iced::window::{Id, Position, Settings, close, open};
 use iced_futures::MaybeSend;
@@ -89,6 +90,86 @@ where
         })
 }
 
+/// A screenshot of a window and where in it the widget being exported was laid out
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub screenshot: window::Screenshot,
+    /// Logical bounds of the widget, `None` exports the whole window
+    pub region: Option<Rectangle>,
+    /// What the image shows, for its watermark and file name
+    pub label: String,
+}
+
+impl Capture {
+    /// Pixels of the exported region, clamped to the window
+    pub fn cropped(&self) -> data::snapshot::Snapshot {
+        let shot = &self.screenshot;
+
+        let cropped = self.region.and_then(|region| {
+            let region = region * shot.scale_factor;
+            let x = (region.x.max(0.0).round() as u32).min(shot.size.width);
+            let y = (region.y.max(0.0).round() as u32).min(shot.size.height);
+
+            shot.crop(Rectangle {
+                x,
+                y,
+                width: (region.width.round() as u32).min(shot.size.width - x),
+                height: (region.height.round() as u32).min(shot.size.height - y),
+            })
+            .inspect_err(|e| log::warn!("Exporting the whole window, cropping failed: {e:?}"))
+            .ok()
+        });
+        let shot = cropped.as_ref().unwrap_or(shot);
+
+        data::snapshot::Snapshot::new(shot.size.width, shot.size.height, shot.rgba.to_vec())
+    }
+}
+
+/// Screenshots `window` along with the bounds of the container with the `target` id. The
+/// pixels are the ones the window last presented, so they match the screen exactly.
+pub fn capture(window: Id, target: Option<iced::widget::Id>, label: String) -> Task<Capture> {
+    let region = match target {
+        Some(target) => iced::advanced::widget::operate(FindBounds {
+            target,
+            found: None,
+        }),
+        None => Task::done(None),
+    };
+
+    region.then(move |region| {
+        let label = label.clone();
+        window::screenshot(window).map(move |screenshot| Capture {
+            screenshot,
+            region,
+            label: label.clone(),
+        })
+    })
+}
+
+/// Looks up the layout bounds of a container by its id, across all windows
+struct FindBounds {
+    target: iced::widget::Id,
+    found: Option<Rectangle>,
+}
+
+impl Operation<Option<Rectangle>> for FindBounds {
+    fn traverse(&mut self, operate: &mut dyn FnMut(&mut dyn Operation<Option<Rectangle>>)) {
+        if self.found.is_none() {
+            operate(self);
+        }
+    }
+
+    fn container(&mut self, id: Option<&iced::widget::Id>, bounds: Rectangle) {
+        if id == Some(&self.target) {
+            self.found = Some(bounds);
+        }
+    }
+
+    fn finish(&self) -> Outcome<Option<Rectangle>> {
+        Outcome::Some(self.found)
+    }
+}
+
 #[cfg(target_os = "linux")]
 pub fn settings() -> Settings {
     Settings {